@@ -310,6 +310,38 @@ pub mod tests {
 {"depth":1,"gas":"0xffeb","op":96,"opName":"PUSH1","pc":15,"stack":["0xaaaaaa","0xaa","0xaa","0xaa","0xaa"],"storage":{}}
 {"depth":1,"gas":"0xffe8","op":96,"opName":"PUSH1","pc":17,"stack":["0xaaaaaa","0xaa","0xaa","0xaa","0xaa","0xaa"],"storage":{}}
 {"depth":1,"gas":"0xffe5","op":96,"opName":"PUSH1","pc":19,"stack":["0xaaaaaa","0xaa","0xaa","0xaa","0xaa","0xaa","0xaa"],"storage":{}}
+"#
+        );
+    }
+
+    #[test]
+    fn should_run_auth_on_eip3074_spec() {
+        use display::{config::Config, std_json::tests::informant};
+
+        let (inf, res) = informant(Config::default());
+        let mut params = ActionParams::default();
+        // AUTH(0xaa, 0, 0) fails without a signature, so the following AUTHCALL is invalid
+        params.code = Some(Arc::new(
+            "6000600060aaf6808080808080f7".from_hex().unwrap(),
+        ));
+        params.gas = 0xffff.into();
+
+        let spec = ::ethcore::ethereum::new_eip3074_test();
+        let _result = run_action(&spec, params, inf, TrieSpec::Secure);
+
+        assert_eq!(
+            &String::from_utf8_lossy(&**res.lock().unwrap()),
+            r#"{"depth":1,"gas":"0xffff","op":96,"opName":"PUSH1","pc":0,"stack":[],"storage":{}}
+{"depth":1,"gas":"0xfffc","op":96,"opName":"PUSH1","pc":2,"stack":["0x0"],"storage":{}}
+{"depth":1,"gas":"0xfff9","op":96,"opName":"PUSH1","pc":4,"stack":["0x0","0x0"],"storage":{}}
+{"depth":1,"gas":"0xfff6","op":246,"opName":"AUTH","pc":6,"stack":["0x0","0x0","0xaa"],"storage":{}}
+{"depth":1,"gas":"0xe9b2","op":128,"opName":"DUP1","pc":7,"stack":["0x0"],"storage":{}}
+{"depth":1,"gas":"0xe9af","op":128,"opName":"DUP1","pc":8,"stack":["0x0","0x0"],"storage":{}}
+{"depth":1,"gas":"0xe9ac","op":128,"opName":"DUP1","pc":9,"stack":["0x0","0x0","0x0"],"storage":{}}
+{"depth":1,"gas":"0xe9a9","op":128,"opName":"DUP1","pc":10,"stack":["0x0","0x0","0x0","0x0"],"storage":{}}
+{"depth":1,"gas":"0xe9a6","op":128,"opName":"DUP1","pc":11,"stack":["0x0","0x0","0x0","0x0","0x0"],"storage":{}}
+{"depth":1,"gas":"0xe9a3","op":128,"opName":"DUP1","pc":12,"stack":["0x0","0x0","0x0","0x0","0x0","0x0"],"storage":{}}
+{"depth":1,"gas":"0xe9a0","op":247,"opName":"AUTHCALL","pc":13,"stack":["0x0","0x0","0x0","0x0","0x0","0x0","0x0"],"storage":{}}
 "#
         );
     }
@@ -86,7 +86,7 @@ State test options:
     --chain CHAIN      Run only from specific chain name (i.e. one of EIP150, EIP158,
                        Frontier, Homestead, Byzantium, Constantinople,
                        ConstantinopleFix, Istanbul, EIP158ToByzantiumAt5, FrontierToHomesteadAt5,
-                       HomesteadToDaoAt5, HomesteadToEIP150At5, Berlin, Yolo3).
+                       HomesteadToDaoAt5, HomesteadToEIP150At5, Berlin, London, EIP3074).
     --only NAME        Runs only a single test matching the name.
 
 General options:
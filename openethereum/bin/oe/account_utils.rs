@@ -98,7 +98,7 @@ mod accounts {
         );
         let account_settings = AccountProviderSettings {
             unlock_keep_secret: cfg.enable_fast_unlock,
-            blacklisted_accounts: match *spec {
+            blacklisted_accounts: match *spec.base() {
                 SpecType::Morden
                 | SpecType::Ropsten
                 | SpecType::Kovan
@@ -118,7 +118,7 @@ mod accounts {
         let account_provider = AccountProvider::new(Box::new(ethstore), account_settings);
 
         // Add development account if running dev chain:
-        if let SpecType::Dev = *spec {
+        if let SpecType::Dev = *spec.base() {
             insert_dev_account(&account_provider);
         }
 
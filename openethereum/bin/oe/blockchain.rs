@@ -14,13 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{fs, io, sync::Arc, time::Instant};
+use std::{
+    fs, io,
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    bytes::ToPretty,
     cache::CacheConfig,
     db,
-    hash::{keccak, KECCAK_NULL_RLP},
     helpers::{execute_upgrades, to_client_config},
     informant::{FullNodeInformantData, Informant, MillisecondDuration},
     params::{fatdb_switch_to_bool, tracing_switch_to_bool, Pruning, SpecType, Switch},
@@ -28,17 +32,18 @@ use crate::{
     user_defaults::UserDefaults,
 };
 use ansi_term::Colour;
+use bytes::ToPretty;
 use dir::Directories;
 use ethcore::{
     client::{
-        Balance, BlockChainClient, BlockChainReset, BlockId, DatabaseCompactionProfile,
-        ImportExportBlocks, Mode, Nonce, VMType,
+        BackupStatus, BadBlocks, BlockChainClient, BlockChainReset, BlockId,
+        DatabaseCompactionProfile, ExportStateOptions, ImportExportBlocks, Mode, VMType,
     },
     miner::Miner,
     verification::queue::VerifierSettings,
 };
 use ethcore_service::ClientService;
-use ethereum_types::{Address, H256, U256};
+use ethereum_types::U256;
 
 #[derive(Debug, PartialEq)]
 pub enum BlockchainCmd {
@@ -47,6 +52,10 @@ pub enum BlockchainCmd {
     Export(ExportBlockchain),
     ExportState(ExportState),
     Reset(ResetBlockchain),
+    RebuildBlooms(RebuildBlooms),
+    BadBlocks(ListBadBlocks),
+    Migrate(MigrateBlockchain),
+    Backup(BackupBlockchain),
 }
 
 #[derive(Debug, PartialEq)]
@@ -61,6 +70,56 @@ pub struct ResetBlockchain {
     pub compaction: DatabaseCompactionProfile,
     pub cache_config: CacheConfig,
     pub num: u32,
+    pub target: Option<BlockId>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RebuildBlooms {
+    pub dirs: Directories,
+    pub spec: SpecType,
+    pub pruning: Pruning,
+    pub pruning_history: u64,
+    pub pruning_memory: usize,
+    pub tracing: Switch,
+    pub fat_db: Switch,
+    pub compaction: DatabaseCompactionProfile,
+    pub cache_config: CacheConfig,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ListBadBlocks {
+    pub dirs: Directories,
+    pub spec: SpecType,
+    pub pruning: Pruning,
+    pub pruning_history: u64,
+    pub pruning_memory: usize,
+    pub tracing: Switch,
+    pub fat_db: Switch,
+    pub compaction: DatabaseCompactionProfile,
+    pub cache_config: CacheConfig,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BackupBlockchain {
+    pub dirs: Directories,
+    pub spec: SpecType,
+    pub pruning: Pruning,
+    pub pruning_history: u64,
+    pub pruning_memory: usize,
+    pub tracing: Switch,
+    pub fat_db: Switch,
+    pub compaction: DatabaseCompactionProfile,
+    pub cache_config: CacheConfig,
+    pub path: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MigrateBlockchain {
+    pub spec: SpecType,
+    pub dirs: Directories,
+    pub pruning: Pruning,
+    pub compaction: DatabaseCompactionProfile,
+    pub dry_run: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -77,6 +136,7 @@ pub struct ImportBlockchain {
     pub dirs: Directories,
     pub file_path: Option<String>,
     pub format: Option<DataFormat>,
+    pub resume_from: Option<u64>,
     pub pruning: Pruning,
     pub pruning_history: u64,
     pub pruning_memory: usize,
@@ -137,6 +197,10 @@ pub fn execute(cmd: BlockchainCmd) -> Result<(), String> {
         BlockchainCmd::Export(export_cmd) => execute_export(export_cmd),
         BlockchainCmd::ExportState(export_cmd) => execute_export_state(export_cmd),
         BlockchainCmd::Reset(reset_cmd) => execute_reset(reset_cmd),
+        BlockchainCmd::RebuildBlooms(rebuild_cmd) => execute_rebuild_blooms(rebuild_cmd),
+        BlockchainCmd::BadBlocks(list_cmd) => execute_bad_blocks(list_cmd),
+        BlockchainCmd::Migrate(migrate_cmd) => execute_migrate(migrate_cmd),
+        BlockchainCmd::Backup(backup_cmd) => execute_backup(backup_cmd),
     }
 }
 
@@ -242,7 +306,7 @@ fn execute_import(cmd: ImportBlockchain) -> Result<(), String> {
         .register_io_handler(informant)
         .map_err(|_| "Unable to register informant handler".to_owned())?;
 
-    client.import_blocks(instream, cmd.format)?;
+    client.import_blocks(instream, cmd.format, cmd.resume_from)?;
 
     // save user defaults
     user_defaults.pruning = algorithm;
@@ -400,108 +464,123 @@ fn execute_export_state(cmd: ExportState) -> Result<(), String> {
 
     let client = service.client();
 
-    let mut out: Box<dyn io::Write> = match cmd.file_path {
+    let out: Box<dyn io::Write> = match cmd.file_path {
         Some(f) => Box::new(
             fs::File::create(&f).map_err(|_| format!("Cannot write to file given: {}", f))?,
         ),
         None => Box::new(io::stdout()),
     };
 
-    let mut last: Option<Address> = None;
-    let at = cmd.at;
-    let mut i = 0usize;
+    let options = ExportStateOptions {
+        code: cmd.code,
+        storage: cmd.storage,
+        min_balance: cmd.min_balance,
+        max_balance: cmd.max_balance,
+    };
+    let exported = client.export_state(cmd.at, out, &options)?;
+    info!("Exported {} accounts.", exported);
+    Ok(())
+}
 
-    out.write_fmt(format_args!("{{ \"state\": {{",))
-        .expect("Couldn't write to stream.");
-    loop {
-        let accounts = client
-            .list_accounts(at, last.as_ref(), 1000)
-            .ok_or("Specified block not found")?;
-        if accounts.is_empty() {
-            break;
-        }
+fn execute_reset(cmd: ResetBlockchain) -> Result<(), String> {
+    let service = start_client(
+        cmd.dirs,
+        cmd.spec,
+        cmd.pruning,
+        cmd.pruning_history,
+        cmd.pruning_memory,
+        cmd.tracing,
+        cmd.fat_db,
+        cmd.compaction,
+        cmd.cache_config,
+        false,
+        0,
+    )?;
 
-        for account in accounts.into_iter() {
-            let balance = client
-                .balance(&account, at.into())
-                .unwrap_or_else(U256::zero);
-            if cmd.min_balance.map_or(false, |m| balance < m)
-                || cmd.max_balance.map_or(false, |m| balance > m)
-            {
-                last = Some(account);
-                continue; //filtered out
-            }
+    let client = service.client();
+    match cmd.target {
+        Some(target) => client.reset_to(target)?,
+        None => client.reset(cmd.num)?,
+    }
+    info!("{}", Colour::Green.bold().paint("Successfully reset db!"));
 
-            if i != 0 {
-                out.write(b",").expect("Write error");
-            }
-            out.write_fmt(format_args!(
-                "\n\"0x{:x}\": {{\"balance\": \"{:x}\", \"nonce\": \"{:x}\"",
-                account,
-                balance,
-                client.nonce(&account, at).unwrap_or_else(U256::zero)
-            ))
-            .expect("Write error");
-            let code = client
-                .code(&account, at.into())
-                .unwrap_or(None)
-                .unwrap_or_else(Vec::new);
-            if !code.is_empty() {
-                out.write_fmt(format_args!(", \"code_hash\": \"0x{:x}\"", keccak(&code)))
-                    .expect("Write error");
-                if cmd.code {
-                    out.write_fmt(format_args!(", \"code\": \"{}\"", code.to_hex()))
-                        .expect("Write error");
-                }
-            }
-            let storage_root = client.storage_root(&account, at).unwrap_or(KECCAK_NULL_RLP);
-            if storage_root != KECCAK_NULL_RLP {
-                out.write_fmt(format_args!(", \"storage_root\": \"0x{:x}\"", storage_root))
-                    .expect("Write error");
-                if cmd.storage {
-                    out.write_fmt(format_args!(", \"storage\": {{"))
-                        .expect("Write error");
-                    let mut last_storage: Option<H256> = None;
-                    loop {
-                        let keys = client
-                            .list_storage(at, &account, last_storage.as_ref(), 1000)
-                            .ok_or("Specified block not found")?;
-                        if keys.is_empty() {
-                            break;
-                        }
-
-                        for key in keys.into_iter() {
-                            if last_storage.is_some() {
-                                out.write(b",").expect("Write error");
-                            }
-                            out.write_fmt(format_args!(
-                                "\n\t\"0x{:x}\": \"0x{:x}\"",
-                                key,
-                                client
-                                    .storage_at(&account, &key, at.into())
-                                    .unwrap_or_else(Default::default)
-                            ))
-                            .expect("Write error");
-                            last_storage = Some(key);
-                        }
-                    }
-                    out.write(b"\n}").expect("Write error");
-                }
-            }
-            out.write(b"}").expect("Write error");
-            i += 1;
-            if i % 10000 == 0 {
-                info!("Account #{}", i);
-            }
-            last = Some(account);
-        }
+    Ok(())
+}
+
+fn execute_rebuild_blooms(cmd: RebuildBlooms) -> Result<(), String> {
+    const BLOOMS_BATCH: u64 = 16384;
+
+    let service = start_client(
+        cmd.dirs,
+        cmd.spec,
+        cmd.pruning,
+        cmd.pruning_history,
+        cmd.pruning_memory,
+        cmd.tracing,
+        cmd.fat_db,
+        cmd.compaction,
+        cmd.cache_config,
+        false,
+        0,
+    )?;
+
+    let timer = Instant::now();
+    let chain = service.client().chain();
+    let best = chain.best_block_number();
+    info!("Rebuilding blooms of {} blocks", best + 1);
+    chain
+        .rebuild_blooms(BLOOMS_BATCH, |number| {
+            info!(
+                "Rebuilt blooms up to #{} ({}%)",
+                number,
+                (number + 1) * 100 / (best + 1)
+            );
+        })
+        .map_err(|e| format!("Failed to write blooms: {}", e))?;
+
+    info!(
+        "{} Took {}ms",
+        Colour::Green.bold().paint("Successfully rebuilt blooms!"),
+        timer.elapsed().as_milliseconds()
+    );
+
+    Ok(())
+}
+
+fn execute_bad_blocks(cmd: ListBadBlocks) -> Result<(), String> {
+    let service = start_client(
+        cmd.dirs,
+        cmd.spec,
+        cmd.pruning,
+        cmd.pruning_history,
+        cmd.pruning_memory,
+        cmd.tracing,
+        cmd.fat_db,
+        cmd.compaction,
+        cmd.cache_config,
+        false,
+        0,
+    )?;
+
+    let bad_blocks = service.client().bad_blocks();
+    if bad_blocks.is_empty() {
+        println!("No bad blocks recorded.");
     }
-    out.write_fmt(format_args!("\n}}}}")).expect("Write error");
-    info!("Export completed.");
+    for bad_block in bad_blocks {
+        println!(
+            "#{} {:?} rejected at {}: {}\nRLP: {}",
+            bad_block.block.header.number(),
+            bad_block.block.header.hash(),
+            bad_block.reported_at,
+            bad_block.reason,
+            bad_block.block.bytes.to_hex(),
+        );
+    }
+
     Ok(())
 }
 
-fn execute_reset(cmd: ResetBlockchain) -> Result<(), String> {
+fn execute_backup(cmd: BackupBlockchain) -> Result<(), String> {
     let service = start_client(
         cmd.dirs,
         cmd.spec,
@@ -516,13 +595,42 @@ fn execute_reset(cmd: ResetBlockchain) -> Result<(), String> {
         0,
     )?;
 
+    let timer = Instant::now();
     let client = service.client();
-    client.reset(cmd.num)?;
-    info!("{}", Colour::Green.bold().paint("Successfully reset db!"));
+    client
+        .backup(Path::new(&cmd.path))
+        .map_err(|e| format!("Failed to write backup: {}", e))?;
+    loop {
+        match client.backup_status() {
+            BackupStatus::Running { .. } | BackupStatus::Idle => {
+                thread::sleep(Duration::from_secs(1))
+            }
+            BackupStatus::Done { .. } => break,
+            BackupStatus::Failed { error, .. } => {
+                return Err(format!("Failed to write backup: {}", error))
+            }
+        }
+    }
+
+    info!(
+        "{} Took {}ms",
+        Colour::Green.bold().paint("Successfully wrote backup!"),
+        timer.elapsed().as_milliseconds()
+    );
 
     Ok(())
 }
 
+fn execute_migrate(cmd: MigrateBlockchain) -> Result<(), String> {
+    let spec = cmd.spec.spec(&cmd.dirs.cache)?;
+    let genesis_hash = spec.genesis_header().hash();
+    let db_dirs = cmd.dirs.database(genesis_hash, None, spec.data_dir);
+    let user_defaults = UserDefaults::load(&db_dirs.user_defaults_path())?;
+    let algorithm = cmd.pruning.to_algorithm(&user_defaults);
+    db::migrate_schema(&db_dirs.db_path(algorithm), &cmd.compaction, cmd.dry_run)
+        .map_err(|e| format!("{}", e))
+}
+
 pub fn kill_db(cmd: KillBlockchain) -> Result<(), String> {
     let spec = cmd.spec.spec(&cmd.dirs.cache)?;
     let genesis_hash = spec.genesis_header().hash();
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
+use ethcore::client::CacheSizes;
 use std::cmp::max;
 
 const MIN_BC_CACHE_MB: u32 = 4;
@@ -104,6 +105,16 @@ impl CacheConfig {
     pub fn jump_tables(&self) -> u32 {
         self.state / 4
     }
+
+    /// Cache budgets in bytes which can be applied to a running client.
+    pub fn runtime_sizes(&self) -> CacheSizes {
+        let mb = 1024 * 1024;
+        CacheSizes {
+            state: Some(self.state() as usize * mb),
+            blockchain: Some(self.blockchain() as usize * mb),
+            traces: Some(self.traces() as usize * mb),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -118,6 +129,8 @@ mod tests {
         assert_eq!(config.queue(), 40);
         assert_eq!(config.state(), 30);
         assert_eq!(config.jump_tables(), 10);
+        assert_eq!(config.runtime_sizes().state, Some(30 * 1024 * 1024));
+        assert_eq!(config.runtime_sizes().blockchain, Some(20 * 1024 * 1024));
     }
 
     #[test]
@@ -82,6 +82,10 @@ usage! {
             "--format=[FORMAT]",
             "Import in a given format. FORMAT must be either 'hex' or 'binary'. (default: auto)",
 
+            ARG arg_import_resume_from: (Option<u64>) = None,
+            "--resume-from=[NUM]",
+            "Skip blocks numbered below NUM without fully decoding them, e.g. to continue an interrupted import.",
+
             ARG arg_import_file: (Option<String>) = None,
             "[FILE]",
             "Path to the file to import from",
@@ -114,7 +118,7 @@ usage! {
 
             CMD cmd_export_state
             {
-                "Export the blockchain state from the given --chain (default: mainnet) into a file. This command requires the chain to be synced with --fat-db on.",
+                "Export the blockchain state from the given --chain (default: mainnet) into a file. The output is a JSON object usable as the accounts section of a chain spec (builtins are not included). This command requires the chain to be synced with --fat-db on.",
 
                 FLAG flag_export_state_no_storage: (bool) = false,
                 "--no-storage",
@@ -227,6 +231,34 @@ usage! {
                 ARG arg_db_reset_num: (u32) = 10u32,
                 "<NUM>",
                 "Number of blocks to revert",
+
+                ARG arg_db_reset_to: (Option<String>) = None,
+                "--to=[BLOCK]",
+                "Reset the chain head to block BLOCK instead, which may be an index or hash. The block may be on a side chain, in which case its branch becomes the canon chain.",
+            }
+
+            CMD cmd_db_rebuild_blooms {
+                "Regenerate the blooms database used for log filtering from the block headers",
+            }
+
+            CMD cmd_db_bad_blocks {
+                "List the recently rejected blocks stored in the db, with the reason they were rejected",
+            }
+
+            CMD cmd_db_backup {
+                "Write a copy of the database of the given --chain into DIR, which can be used as the database of a fresh node. Use the parity_backup RPC to back up a running node",
+
+                ARG arg_db_backup_dir: (Option<String>) = None,
+                "<DIR>",
+                "Empty directory to write the backup to",
+            }
+
+            CMD cmd_db_migrate {
+                "Apply pending database schema migrations",
+
+                FLAG flag_db_migrate_dry_run: (bool) = false,
+                "--dry-run",
+                "Only list the pending migrations without applying them.",
             }
 
         }
@@ -250,6 +282,10 @@ usage! {
             "--chain=[CHAIN]",
             "Specify the blockchain type. CHAIN may be either a JSON chain specification file or ethereum, poacore, xdai, volta, ewc, musicoin, ellaism, mix, callisto, morden, ropsten, kovan, rinkeby, goerli, poasokol, testnet, yolo3 or dev.",
 
+            ARG arg_chain_overlay: (Option<String>) = None, or |c: &Config| c.parity.as_ref()?.chain_overlay.clone(),
+            "--chain-overlay=[FILE]",
+            "Apply the fork transitions of the JSON overlay FILE on top of the chain specification, e.g. {\"params\": {\"eip1559Transition\": \"0x10\", \"eip3198Transition\": null}}. A null transition is deactivated.",
+
             ARG arg_keys_path: (String) = "$BASE/keys", or |c: &Config| c.parity.as_ref()?.keys_path.clone(),
             "--keys-path=[PATH]",
             "Specify the path for JSON key files to be found",
@@ -326,6 +362,18 @@ usage! {
             "--warp-barrier=[NUM]",
             "When warp enabled never attempt regular sync before warping to block NUM.",
 
+            ARG arg_rpc_bridge_url: (Option<String>) = None, or |c: &Config| c.network.as_ref()?.rpc_bridge_url.clone(),
+            "--rpc-bridge-url=[URL]",
+            "Bootstrap the chain from the trusted JSON-RPC provider at URL, then hand over to p2p sync once caught up. The provider must serve debug_getRawBlock and debug_getRawReceipts.",
+
+            ARG arg_rpc_bridge_batch_size: (u64) = 32u64, or |c: &Config| c.network.as_ref()?.rpc_bridge_batch_size.clone(),
+            "--rpc-bridge-batch-size=[NUM]",
+            "Request NUM blocks from the RPC bridge provider in a single batch.",
+
+            ARG arg_rpc_bridge_handover_distance: (u64) = 64u64, or |c: &Config| c.network.as_ref()?.rpc_bridge_handover_distance.clone(),
+            "--rpc-bridge-handover-distance=[NUM]",
+            "Hand over to p2p sync once within NUM blocks of the RPC bridge provider's head.",
+
             ARG arg_port: (u16) = 30303u16, or |c: &Config| c.network.as_ref()?.port.clone(),
             "--port=[PORT]",
             "Override the port on which the node should listen.",
@@ -374,6 +422,14 @@ usage! {
             "--reserved-peers=[FILE]",
             "Provide a file containing enodes, one per line. These nodes will always have a reserved slot on top of the normal maximum peers.",
 
+            ARG arg_node_certificate: (Option<String>) = None, or |c: &Config| c.network.as_ref()?.node_certificate.clone(),
+            "--node-certificate=[FILE]",
+            "Provide a file containing the hex-encoded certificate of this node, signed by a network certificate authority. The certificate is presented to peers during the handshake.",
+
+            ARG arg_node_certificate_authorities: (Option<String>) = None, or |c: &Config| c.network.as_ref()?.node_certificate_authorities.as_ref().map(|vec| vec.join(",")),
+            "--node-certificate-authorities=[KEYS]",
+            "Only allow peers presenting a node certificate signed by one of these certificate authorities. KEYS should be comma-delimited public keys.",
+
             CHECK |args: &Args| {
                 if let (Some(max_peers), Some(min_peers)) = (args.arg_max_peers, args.arg_min_peers) {
                     if min_peers > max_peers {
@@ -411,7 +467,7 @@ usage! {
 
             ARG arg_jsonrpc_apis: (String) = "web3,eth,pubsub,net,parity,parity_pubsub,traces,rpc", or |c: &Config| c.rpc.as_ref()?.apis.as_ref().map(|vec| vec.join(",")),
             "--jsonrpc-apis=[APIS]",
-            "Specify the APIs available through the HTTP JSON-RPC interface using a comma-delimited list of API names. Possible names are: all, safe, debug, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, rpc, secretstore. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
+            "Specify the APIs available through the HTTP JSON-RPC interface using a comma-delimited list of API names. Possible names are: all, safe, debug, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, txpool, rpc, secretstore. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
 
             ARG arg_jsonrpc_hosts: (String) = "none", or |c: &Config| c.rpc.as_ref()?.hosts.as_ref().map(|vec| vec.join(",")),
             "--jsonrpc-hosts=[HOSTS]",
@@ -437,6 +493,42 @@ usage! {
             "--poll-lifetime=[S]",
             "Set the RPC filter lifetime to S seconds. The filter has to be polled at least every S seconds , otherwise it is removed.",
 
+            ARG arg_jsonrpc_call_timeout: (u64) = 5000u64, or |c: &Config| c.rpc.as_ref()?.call_timeout.clone(),
+            "--jsonrpc-call-timeout=[MS]",
+            "Interrupt eth_call and eth_estimateGas executions running longer than MS milliseconds. Requests may only ask for a shorter timeout. 0 disables the limit.",
+
+            ARG arg_jsonrpc_range_max_cost: (Option<u64>) = None, or |c: &Config| c.rpc.as_ref()?.range_max_cost.clone(),
+            "--jsonrpc-range-max-cost=[BLOCKS]",
+            "Maximum estimated number of blocks read by a single range query (eth_getLogs, trace_filter, or a single trace_filter page). Larger queries fail with the partial results and the block to continue from. Unlimited if not set.",
+
+            ARG arg_jsonrpc_range_chunk: (u64) = 1000u64, or |c: &Config| c.rpc.as_ref()?.range_chunk.clone(),
+            "--jsonrpc-range-chunk=[BLOCKS]",
+            "Split range queries into sub-ranges of at most BLOCKS blocks, executed one after another so that other requests are served in between.",
+
+            ARG arg_jsonrpc_record: (Option<String>) = None, or |c: &Config| c.rpc.as_ref()?.record.clone(),
+            "--jsonrpc-record=[FILE]",
+            "Append every handled read-only RPC request, its response and the best block at the time of the call to FILE, one JSON test vector per line. Signing, sending and other state changing requests are never recorded.",
+
+            ARG arg_jsonrpc_replay: (Option<String>) = None, or |c: &Config| c.rpc.as_ref()?.replay.clone(),
+            "--jsonrpc-replay=[FILE]",
+            "Replay the read-only RPC test vectors recorded in FILE at startup and report responses which are not byte-identical. Use with --mode=offline on a database at the recorded chain state.",
+
+            ARG arg_rpc_jwt_secret: (Option<String>) = None, or |c: &Config| c.rpc.as_ref()?.jwt_secret.clone(),
+            "--rpc-jwt-secret=[FILE]",
+            "Require HTTP and WebSockets JSON-RPC requests to carry an Engine API style HS256 JWT bearer token signed with the hex encoded 32-byte secret in FILE.",
+
+            ARG arg_jsonrpc_max_batch_size: (usize) = 1024usize, or |c: &Config| c.rpc.as_ref()?.max_batch_size,
+            "--jsonrpc-max-batch-size=[NUM]",
+            "Reject JSON-RPC batch requests (all transports) with more than NUM calls.",
+
+            ARG arg_jsonrpc_max_batch_cost: (usize) = 10000usize, or |c: &Config| c.rpc.as_ref()?.max_batch_cost,
+            "--jsonrpc-max-batch-cost=[COST]",
+            "Reject JSON-RPC batch requests (all transports) whose calls cost more than COST in total. Calls cost 1, except eth_call, eth_estimateGas and eth_createAccessList which cost 10 and eth_getLogs, trace and debug calls which cost 100.",
+
+            ARG arg_jsonrpc_batch_parallelism: (usize) = 4usize, or |c: &Config| c.rpc.as_ref()?.batch_parallelism,
+            "--jsonrpc-batch-parallelism=[NUM]",
+            "Execute the calls of JSON-RPC batch requests on a dedicated pool of NUM threads, shared by all batches.",
+
         ["API and Console Options – WebSockets"]
             FLAG flag_no_ws: (bool) = false, or |c: &Config| c.websockets.as_ref()?.disable.clone(),
             "--no-ws",
@@ -452,7 +544,7 @@ usage! {
 
             ARG arg_ws_apis: (String) = "web3,eth,pubsub,net,parity,parity_pubsub,traces,rpc", or |c: &Config| c.websockets.as_ref()?.apis.as_ref().map(|vec| vec.join(",")),
             "--ws-apis=[APIS]",
-            "Specify the JSON-RPC APIs available through the WebSockets interface using a comma-delimited list of API names. Possible names are: all, safe, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, rpc, secretstore. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
+            "Specify the JSON-RPC APIs available through the WebSockets interface using a comma-delimited list of API names. Possible names are: all, safe, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, txpool, rpc, secretstore. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
 
             ARG arg_ws_origins: (String) = "parity://*,chrome-extension://*,moz-extension://*", or |c: &Config| c.websockets.as_ref()?.origins.as_ref().map(|vec| vec.join(",")),
             "--ws-origins=[URL]",
@@ -470,6 +562,14 @@ usage! {
             "--ws-max-payload=[MB]",
             "Specify maximum size for WS JSON-RPC requests in megabytes.",
 
+            ARG arg_ws_max_queued_notifications: (usize) = 10000usize, or |c: &Config| c.websockets.as_ref()?.max_queued_notifications,
+            "--ws-max-queued-notifications=[NUM]",
+            "Maximum number of notifications of a single eth_subscribe or parity_subscribe subscription waiting to be sent to a slow subscriber.",
+
+            ARG arg_ws_queue_overflow: (String) = "drop-oldest", or |c: &Config| c.websockets.as_ref()?.queue_overflow.clone(),
+            "--ws-queue-overflow=[POLICY]",
+            "What to do when the notification queue of a subscription is full. POLICY may be: drop-oldest - discard the oldest queued notification; disconnect - send an error notification and drop the subscription.",
+
         ["Metrics"]
             FLAG flag_metrics: (bool) = false, or |c: &Config| c.metrics.as_ref()?.enable.clone(),
             "--metrics",
@@ -494,11 +594,11 @@ usage! {
 
             ARG arg_ipc_path: (String) = if cfg!(windows) { r"\\.\pipe\jsonrpc.ipc" } else { "$BASE/jsonrpc.ipc" }, or |c: &Config| c.ipc.as_ref()?.path.clone(),
             "--ipc-path=[PATH]",
-            "Specify custom path for JSON-RPC over IPC service.",
+            "Specify custom path for JSON-RPC over IPC service. On Windows the service is a named pipe and the path must be in the \\\\.\\pipe\\ namespace.",
 
             ARG arg_ipc_apis: (String) = "web3,eth,pubsub,net,parity,parity_pubsub,parity_accounts,traces,rpc", or |c: &Config| c.ipc.as_ref()?.apis.as_ref().map(|vec| vec.join(",")),
             "--ipc-apis=[APIS]",
-            "Specify custom API set available via JSON-RPC over IPC using a comma-delimited list of API names. Possible names are: all, safe, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, rpc, secretstore. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
+            "Specify custom API set available via JSON-RPC over IPC using a comma-delimited list of API names. Possible names are: all, safe, web3, net, eth, pubsub, personal, signer, parity, parity_pubsub, parity_accounts, parity_set, traces, txpool, rpc, secretstore. You can also disable a specific API by putting '-' in the front, example: all,-personal. 'safe' enables the following APIs: web3, net, eth, pubsub, parity, parity_pubsub, traces, rpc",
 
         ["Secret Store Options"]
             FLAG flag_no_secretstore: (bool) = false, or |c: &Config| c.secretstore.as_ref()?.disable.clone(),
@@ -590,6 +690,10 @@ usage! {
             "--tx-queue-no-unfamiliar-locals",
             "Local transactions sent through JSON-RPC (HTTP, WebSockets, etc) will be treated as 'external' if the sending account is unknown.",
 
+            FLAG flag_tx_queue_node_clock: (bool) = false, or |c: &Config| c.mining.as_ref()?.tx_queue_node_clock.clone(),
+            "--tx-queue-node-clock",
+            "Check timestamp conditions of queued transactions against the node clock instead of the timestamp of the latest block.",
+
             FLAG flag_tx_queue_no_early_reject: (bool) = false, or |c: &Config| c.mining.as_ref()?.tx_queue_no_early_reject.clone(),
             "--tx-queue-no-early-reject",
             "Disables transaction queue optimization to early reject transactions below minimal effective gas price. This allows local transactions to always enter the pool, despite it being full, but requires additional ecrecover on every transaction.",
@@ -602,10 +706,26 @@ usage! {
             "--infinite-pending-block",
             "Pending block will be created with maximal possible gas limit and will execute all transactions in the queue. Note that such block is invalid and should never be attempted to be mined.",
 
+            ARG arg_block_building_budget: (Option<u64>) = None, or |c: &Config| c.mining.as_ref()?.block_building_budget.clone(),
+            "--block-building-budget=[MS]",
+            "Maximal time in milliseconds spent adding transactions to a new block. Engines with a sealing deadline, like AuRa, stop at the deadline as well.",
+
             FLAG flag_no_persistent_txqueue: (bool) = false, or |c: &Config| c.parity.as_ref()?.no_persistent_txqueue,
             "--no-persistent-txqueue",
             "Don't save pending local transactions to disk to be restored whenever the node restarts.",
 
+            FLAG flag_tx_journal_encryption: (bool) = false, or |c: &Config| c.parity.as_ref()?.tx_journal_encryption,
+            "--tx-journal-encryption",
+            "Encrypt the journal of local transactions and the persistent transaction queue on disk with a key derived from the node key.",
+
+            ARG arg_tx_journal_segment_size: (usize) = 1024usize, or |c: &Config| c.parity.as_ref()?.tx_journal_segment_size.clone(),
+            "--tx-journal-segment-size=[KB]",
+            "Size in kilobytes after which the local transaction journal continues in a new segment.",
+
+            ARG arg_tx_journal_segments: (u64) = 8u64, or |c: &Config| c.parity.as_ref()?.tx_journal_segments.clone(),
+            "--tx-journal-segments=[NUM]",
+            "Number of local transaction journal segments kept on disk. The oldest one is dropped when a new segment is started.",
+
             FLAG flag_stratum: (bool) = false, or |c: &Config| Some(c.stratum.is_some()),
             "--stratum",
             "Run Stratum server for miner push notification.",
@@ -622,6 +742,10 @@ usage! {
             "--reseal-max-period=[MS]",
             "Specify the maximum time since last block to enable force-sealing. MS is time measured in milliseconds.",
 
+            ARG arg_reseal_throttle_max_period: (Option<u64>) = None, or |c: &Config| c.mining.as_ref()?.reseal_throttle_max_period.clone(),
+            "--reseal-throttle-max-period=[MS]",
+            "Let the minimal period between transaction-inspired reseals adapt to the load, widening from --reseal-min-period up to MS milliseconds while transactions and blocks keep invalidating the pending block. Fixed by default.",
+
             ARG arg_work_queue_size: (usize) = 20usize, or |c: &Config| c.mining.as_ref()?.work_queue_size.clone(),
             "--work-queue-size=[ITEMS]",
             "Specify the number of historical work packages which are kept cached lest a solution is found for them later. High values take more memory but result in fewer unusable solutions.",
@@ -658,14 +782,74 @@ usage! {
             "--tx-queue-size=[LIMIT]",
             "Maximum amount of transactions in the queue (waiting to be included in next block).",
 
+            ARG arg_tx_queue_future_size: (Option<usize>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_future_size.clone(),
+            "--tx-queue-future-size=[LIMIT]",
+            "Maximum amount of non-local transactions waiting behind a nonce gap. Such transactions never push out others from a full queue. Unlimited by default.",
+
+            ARG arg_tx_queue_future_mem_limit: (Option<u32>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_future_mem_limit.clone(),
+            "--tx-queue-future-mem-limit=[MB]",
+            "Maximum amount of memory that can be used by non-local transactions waiting behind a nonce gap. Unlimited by default.",
+
             ARG arg_tx_queue_per_sender: (Option<usize>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_per_sender.clone(),
             "--tx-queue-per-sender=[LIMIT]",
             "Maximum number of transactions per sender in the queue. By default it's 1% of the entire queue, but not less than 16.",
 
+            ARG arg_tx_queue_sender_gas_limit: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_sender_gas_limit.clone(),
+            "--tx-queue-sender-gas-limit=[GAS]",
+            "Maximum total gas of the transactions a single non-local sender can have in the queue.",
+
+            ARG arg_tx_queue_sender_window_limit: (Option<usize>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_sender_window_limit.clone(),
+            "--tx-queue-sender-window-limit=[NUM]",
+            "Number of transactions a non-local sender can queue within a block window before further ones have to pay an escalating fee premium to keep their place. Disabled by default.",
+
+            ARG arg_tx_queue_sender_window: (u64) = 1u64, or |c: &Config| c.mining.as_ref()?.tx_queue_sender_window.clone(),
+            "--tx-queue-sender-window=[BLOCKS]",
+            "Length of the block window for --tx-queue-sender-window-limit, in blocks.",
+
+            ARG arg_tx_queue_sender_premium: (u32) = 10u32, or |c: &Config| c.mining.as_ref()?.tx_queue_sender_premium.clone(),
+            "--tx-queue-sender-premium=[PERCENT]",
+            "Fee premium, in percent, required for every transaction of a sender beyond --tx-queue-sender-window-limit within a block window. The premium grows with every additional transaction.",
+
+            ARG arg_tx_queue_replace_bump_permille: (u32) = 125u32, or |c: &Config| c.mining.as_ref()?.tx_queue_replace_bump_permille.clone(),
+            "--tx-queue-replace-bump-permille=[PERMILLE]",
+            "Minimal gas price increase, in permille, required to replace a transaction with the same sender and nonce. The default of 125 is a 12.5% increase.",
+
+            ARG arg_tx_queue_replace_max_fee_bump_permille: (Option<u32>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_replace_max_fee_bump_permille.clone(),
+            "--tx-queue-replace-max-fee-bump-permille=[PERMILLE]",
+            "Minimal max fee per gas increase, in permille, required to replace an EIP-1559 transaction. By default the effective gas prices are compared using --tx-queue-replace-bump-permille.",
+
+            ARG arg_tx_queue_replace_priority_fee_bump_permille: (Option<u32>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_replace_priority_fee_bump_permille.clone(),
+            "--tx-queue-replace-priority-fee-bump-permille=[PERMILLE]",
+            "Minimal max priority fee per gas increase, in permille, required to replace an EIP-1559 transaction. By default the effective gas prices are compared using --tx-queue-replace-bump-permille.",
+
+            ARG arg_tx_queue_max_age: (Option<u64>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_max_age.clone(),
+            "--tx-queue-max-age=[S]",
+            "Maximal time in seconds a non-local transaction may stay in the queue before it is dropped. Unlimited by default.",
+
+            ARG arg_tx_queue_timestamp_tolerance: (u64) = 0u64, or |c: &Config| c.mining.as_ref()?.tx_queue_timestamp_tolerance.clone(),
+            "--tx-queue-timestamp-tolerance=[S]",
+            "Time in seconds the clock may step back without making transactions with a timestamp condition that are already ready wait again.",
+
             ARG arg_tx_queue_locals: (Option<String>) = None, or |c: &Config| helpers::join_set(c.mining.as_ref()?.tx_queue_locals.as_ref()),
             "--tx-queue-locals=[ACCOUNTS]",
             "Specify local accounts for which transactions are prioritized in the queue. ACCOUNTS is a comma-delimited list of addresses.",
 
+            ARG arg_tx_queue_prioritized_senders: (Option<String>) = None, or |c: &Config| helpers::join_set(c.mining.as_ref()?.tx_queue_prioritized_senders.as_ref()),
+            "--tx-queue-prioritized-senders=[ACCOUNTS]",
+            "Specify accounts whose transactions are ordered ahead of other transactions with the same gas price, without being treated as local. ACCOUNTS is a comma-delimited list of addresses.",
+
+            ARG arg_tx_queue_entry_points: (Option<String>) = None, or |c: &Config| helpers::join_set(c.mining.as_ref()?.tx_queue_entry_points.as_ref()),
+            "--tx-queue-entry-points=[ADDRESSES]",
+            "Specify EIP-4337 EntryPoint contracts. Transactions calling them are UserOperation bundles metered in a separate lane of the queue. ADDRESSES is a comma-delimited list of addresses.",
+
+            ARG arg_tx_queue_bundle_limit: (usize) = 256usize, or |c: &Config| c.mining.as_ref()?.tx_queue_bundle_limit.clone(),
+            "--tx-queue-bundle-limit=[LIMIT]",
+            "Maximum number of UserOperation bundle transactions in the queue, including slots reserved by bundlers.",
+
+            ARG arg_tx_queue_bundle_gas_limit: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.tx_queue_bundle_gas_limit.clone(),
+            "--tx-queue-bundle-gas-limit=[GAS]",
+            "Maximum total gas of the UserOperation bundle transactions in the queue.",
+
             ARG arg_tx_queue_strategy: (String) = "gas_price", or |c: &Config| c.mining.as_ref()?.tx_queue_strategy.clone(),
             "--tx-queue-strategy=[S]",
             "Prioritization strategy used to order transactions in the queue. S may be: gas_price - Prioritize txs with high gas price",
@@ -694,6 +878,26 @@ usage! {
             "--engine-signer=[ADDRESS]",
             "Specify the address which should be used to sign consensus messages and issue blocks. Relevant only to non-PoW chains.",
 
+            ARG arg_engine_signer_pkcs11_module: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.engine_signer_pkcs11_module.clone(),
+            "--engine-signer-pkcs11-module=[PATH]",
+            "Sign consensus messages with a secp256k1 key kept on a PKCS#11 token (HSM), using the vendor module at PATH. The token PIN is read from the first --password file. Requires the pkcs11 build feature.",
+
+            ARG arg_engine_signer_pkcs11_slot: (u64) = 0u64, or |c: &Config| c.mining.as_ref()?.engine_signer_pkcs11_slot.clone(),
+            "--engine-signer-pkcs11-slot=[SLOT]",
+            "PKCS#11 slot holding the engine signer token.",
+
+            ARG arg_engine_signer_pkcs11_key_label: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.engine_signer_pkcs11_key_label.clone(),
+            "--engine-signer-pkcs11-key-label=[LABEL]",
+            "Label of the engine signer key pair on the PKCS#11 token.",
+
+            ARG arg_engine_signer_pkcs11_timeout: (u64) = 5000u64, or |c: &Config| c.mining.as_ref()?.engine_signer_pkcs11_timeout.clone(),
+            "--engine-signer-pkcs11-timeout=[MS]",
+            "Give up on a signature which the PKCS#11 token did not produce within MS milliseconds.",
+
+            ARG arg_fee_recipient: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.fee_recipient.clone(),
+            "--fee-recipient=[ADDRESS]",
+            "Specify the address credited with block rewards and fees when it should differ from the block author. Only supported by Ethash and by BasicAuthority past its feeRecipientTransition, other engines like AuRa refuse to start with it.",
+
             ARG arg_tx_gas_limit: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.tx_gas_limit.clone(),
             "--tx-gas-limit=[GAS]",
             "Apply a limit of GAS as the maximum amount of gas a single transaction may have for it to be mined.",
@@ -710,6 +914,22 @@ usage! {
             "--notify-work=[URLS]",
             "URLs to which work package notifications are pushed. URLS should be a comma-delimited list of HTTP URLs.",
 
+            ARG arg_tx_webhooks: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.tx_webhooks.as_ref().map(|vec| vec.join(",")),
+            "--tx-webhooks=[URLS]",
+            "URLs to which batches of transaction lifecycle events (added, replaced, dropped, culled, mined) are pushed. URLS should be a comma-delimited list of HTTP URLs.",
+
+            ARG arg_tx_webhook_retries: (usize) = 3usize, or |c: &Config| c.mining.as_ref()?.tx_webhook_retries.clone(),
+            "--tx-webhook-retries=[NUM]",
+            "Number of times a failed delivery to a transaction webhook is retried, with exponential backoff.",
+
+            ARG arg_block_builder_url: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.block_builder_url.clone(),
+            "--block-builder-url=[URL]",
+            "HTTP URL of an external builder asked for the transactions of new blocks. The local transaction queue is used if it fails to answer.",
+
+            ARG arg_block_builder_timeout: (u64) = 500u64, or |c: &Config| c.mining.as_ref()?.block_builder_timeout.clone(),
+            "--block-builder-timeout=[MS]",
+            "Time to wait for the external block builder to answer before falling back to the local transaction queue. A later answer is still used for the next block built on the same parent.",
+
             ARG arg_stratum_secret: (Option<String>) = None, or |c: &Config| c.stratum.as_ref()?.secret.clone(),
             "--stratum-secret=[STRING]",
             "Secret for authorizing Stratum server for peers.",
@@ -718,6 +938,10 @@ usage! {
             "--max-round-blocks-to-import=[S]",
             "Maximal number of blocks to import for each import round.",
 
+            ARG arg_uncle_strategy: (String) = "first", or |c: &Config| c.mining.as_ref()?.uncle_strategy.clone(),
+            "--uncle-strategy=[S]",
+            "Which uncles to include in authored blocks. S may be: first - The first ones found, closest generations first; profitable - Uncles of our own author first, then the closest generations; newest - The most recently mined ones first; none - No uncles.",
+
             ARG arg_new_transactions_stats_period: (u64) = 0u64, or |c: &Config| c.mining.as_ref()?.new_transactions_stats_period.clone(),
             "--new-transactions-stats-period=[N]",
             "Specify number of blocks for which new transactions will be returned in a result of `parity_newTransactionsStats` RPC call. Setting this parameter to 0 will return only transactions imported during the current block. (default: 0)",
@@ -748,6 +972,48 @@ usage! {
             "--log-file=[FILENAME]",
             "Specify a filename into which logging should be appended.",
 
+        ["Clock Drift Options"]
+            FLAG flag_clock_drift_monitor: (bool) = false, or |c: &Config| c.misc.as_ref()?.clock_drift_monitor.clone(),
+            "--clock-drift-monitor",
+            "Periodically compare the local clock against NTP servers, log a warning when the drift exceeds --clock-drift-threshold and export it as a prometheus metric.",
+
+            ARG arg_ntp_servers: (String) = "0.pool.ntp.org:123,1.pool.ntp.org:123,2.pool.ntp.org:123,3.pool.ntp.org:123", or |c: &Config| c.misc.as_ref()?.ntp_servers.as_ref().map(|vec| vec.join(",")),
+            "--ntp-servers=[HOSTS]",
+            "Comma separated list of NTP servers used by the clock drift monitor.",
+
+            ARG arg_clock_drift_interval: (u64) = 300u64, or |c: &Config| c.misc.as_ref()?.clock_drift_interval.clone(),
+            "--clock-drift-interval=[SECS]",
+            "Number of seconds between two clock drift checks.",
+
+            ARG arg_clock_drift_threshold: (u64) = 1000u64, or |c: &Config| c.misc.as_ref()?.clock_drift_threshold.clone(),
+            "--clock-drift-threshold=[MS]",
+            "Log a warning when the local clock drifts from NTP by more than MS milliseconds.",
+
+            ARG arg_max_future_block_drift: (u64) = 15u64, or |c: &Config| c.misc.as_ref()?.max_future_block_drift.clone(),
+            "--max-future-block-drift=[SECS]",
+            "Maximum number of seconds a block timestamp may be ahead of the local clock. Blocks up to ten times further ahead are quarantined and re-imported once the local clock catches up.",
+
+            ARG arg_max_relay_future_drift: (Option<u64>) = None, or |c: &Config| c.misc.as_ref()?.max_relay_future_drift.clone(),
+            "--max-relay-future-drift=[SECS]",
+            "Do not relay imported blocks whose timestamp is more than SECS seconds ahead of the local clock until the local clock catches up.",
+
+            ARG arg_max_reorg_depth: (Option<u64>) = None, or |c: &Config| c.misc.as_ref()?.max_reorg_depth.clone(),
+            "--max-reorg-depth=[BLOCKS]",
+            "Refuse to switch to a fork whose common ancestor is more than BLOCKS blocks below the current best block. The fork is still stored but stays non-canonical.",
+
+        ["Spec Update Options"]
+            FLAG flag_spec_update_check: (bool) = false, or |c: &Config| c.misc.as_ref()?.spec_update_check.clone(),
+            "--spec-update-check",
+            "Compare the running chain spec with the latest spec announced by the spec_registry contract of the registrar and warn when it is outdated.",
+
+            ARG arg_spec_update_check_interval: (u64) = 100u64, or |c: &Config| c.misc.as_ref()?.spec_update_check_interval.clone(),
+            "--spec-update-check-interval=[BLOCKS]",
+            "Number of blocks between two spec update checks.",
+
+            FLAG flag_spec_update_refuse_sealing: (bool) = false, or |c: &Config| c.misc.as_ref()?.spec_update_refuse_sealing.clone(),
+            "--spec-update-refuse-sealing",
+            "Stop sealing blocks once the announced chain spec is active and the node still runs an outdated one.",
+
         ["Footprint Options"]
             FLAG flag_scale_verifiers: (bool) = false, or |c: &Config| c.footprint.as_ref()?.scale_verifiers.clone(),
             "--scale-verifiers",
@@ -759,7 +1025,7 @@ usage! {
 
             ARG arg_pruning: (String) = "auto", or |c: &Config| c.footprint.as_ref()?.pruning.clone(),
             "--pruning=[METHOD]",
-            "Configure pruning of the state/storage trie. METHOD may be one of auto, archive, fast: archive - keep all state trie data. No pruning. fast - maintain journal overlay. Fast but 50MB used. auto - use the method most recently synced or default to fast if none synced.",
+            "Configure pruning of the state/storage trie. METHOD may be one of auto, archive, fast, generational: archive - keep all state trie data. No pruning. fast - maintain journal overlay. Fast but 50MB used. generational - like fast, but canonical changes are flushed in batches to reduce disk writes; suited to short --pruning-history. auto - use the method most recently synced or default to fast if none synced.",
 
             ARG arg_pruning_history: (u64) = 64u64, or |c: &Config| c.footprint.as_ref()?.pruning_history.clone(),
             "--pruning-history=[NUM]",
@@ -769,13 +1035,25 @@ usage! {
             "--pruning-memory=[MB]",
             "The ideal amount of memory in megabytes to use to store recent states. As many states as possible will be kept within this limit, and at least --pruning-history states will always be kept.",
 
+            ARG arg_history_expiry: (Option<u64>) = None, or |c: &Config| c.footprint.as_ref()?.history_expiry.clone(),
+            "--history-expiry=[BLOCKS]",
+            "Keep block bodies and receipts only for the given number of most recent blocks. Older bodies and receipts are deleted while their headers are kept, so transactions, receipts and logs of expired blocks can no longer be served. Nodes with expired history cannot produce snapshots.",
+
+            ARG arg_tx_index_expiry: (Option<u64>) = None, or |c: &Config| c.footprint.as_ref()?.tx_index_expiry.clone(),
+            "--tx-index-expiry=[BLOCKS]",
+            "Index transactions by hash only for the given number of most recent blocks. Older index entries are replaced by short markers, so transactions and receipts of older blocks can no longer be looked up by hash and such lookups return an error.",
+
+            ARG arg_state_regeneration_limit: (Option<u64>) = None, or |c: &Config| c.footprint.as_ref()?.state_regeneration_limit.clone(),
+            "--state-regeneration-limit=[BLOCKS]",
+            "When pruned state is requested (e.g. by trace RPCs), rebuild it by re-executing up to the given number of blocks on top of the nearest available ancestor state. The rebuilt state is kept in memory only.",
+
             ARG arg_cache_size_db: (u32) = 128u32, or |c: &Config| c.footprint.as_ref()?.cache_size_db.clone(),
             "--cache-size-db=[MB]",
             "Override database cache size.",
 
             ARG arg_cache_size_blocks: (u32) = 8u32, or |c: &Config| c.footprint.as_ref()?.cache_size_blocks.clone(),
             "--cache-size-blocks=[MB]",
-            "Specify the preferred size of the blockchain cache in megabytes.",
+            "Specify the preferred size of the blockchain cache in megabytes. Reloaded on SIGHUP.",
 
             ARG arg_cache_size_queue: (u32) = 40u32, or |c: &Config| c.footprint.as_ref()?.cache_size_queue.clone(),
             "--cache-size-queue=[MB]",
@@ -783,7 +1061,7 @@ usage! {
 
             ARG arg_cache_size_state: (u32) = 25u32, or |c: &Config| c.footprint.as_ref()?.cache_size_state.clone(),
             "--cache-size-state=[MB]",
-            "Specify the maximum size of memory to use for the state cache.",
+            "Specify the maximum size of memory to use for the state cache. Reloaded on SIGHUP.",
 
             ARG arg_db_compaction: (String) = "auto", or |c: &Config| c.footprint.as_ref()?.db_compaction.clone(),
             "--db-compaction=[TYPE]",
@@ -801,6 +1079,18 @@ usage! {
             "--num-verifiers=[INT]",
             "Amount of verifier threads to use or to begin with, if verifier auto-scaling is enabled.",
 
+            FLAG flag_db_read_profiler: (bool) = false, or |c: &Config| c.footprint.as_ref()?.db_read_profiler.clone(),
+            "--db-read-profiler",
+            "Attribute database reads to the code path, column and key type that issued them and log the aggregated report on shutdown. Adds overhead to every database read.",
+
+            FLAG flag_read_only: (bool) = false, or |c: &Config| c.footprint.as_ref()?.read_only.clone(),
+            "--read-only",
+            "Serve RPC queries from an existing database without syncing, importing blocks or transactions, or sealing. The database may be in use by a running node: a snapshot of it is taken next to it when the client starts and does not follow the node afterwards.",
+
+            FLAG flag_compress_history: (bool) = false, or |c: &Config| c.footprint.as_ref()?.compress_history.clone(),
+            "--compress-history",
+            "Store block bodies and receipts snappy-compressed. Data written before the flag was enabled is recompressed in the background.",
+
         ["Import/export Options"]
             FLAG flag_no_seal_check: (bool) = false, or |_| None,
             "--no-seal-check",
@@ -843,11 +1133,15 @@ struct Operating {
     mode_timeout: Option<u64>,
     mode_alarm: Option<u64>,
     chain: Option<String>,
+    chain_overlay: Option<String>,
     base_path: Option<String>,
     db_path: Option<String>,
     keys_path: Option<String>,
     identity: Option<String>,
     no_persistent_txqueue: Option<bool>,
+    tx_journal_encryption: Option<bool>,
+    tx_journal_segment_size: Option<usize>,
+    tx_journal_segments: Option<u64>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -871,6 +1165,9 @@ struct Ui {
 struct Network {
     warp: Option<bool>,
     warp_barrier: Option<u64>,
+    rpc_bridge_url: Option<String>,
+    rpc_bridge_batch_size: Option<u64>,
+    rpc_bridge_handover_distance: Option<u64>,
     port: Option<u16>,
     interface: Option<String>,
     min_peers: Option<u16>,
@@ -885,6 +1182,8 @@ struct Network {
     node_key: Option<String>,
     reserved_peers: Option<String>,
     reserved_only: Option<bool>,
+    node_certificate: Option<String>,
+    node_certificate_authorities: Option<Vec<String>>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -902,7 +1201,16 @@ struct Rpc {
     keep_alive: Option<bool>,
     experimental_rpcs: Option<bool>,
     poll_lifetime: Option<u32>,
+    call_timeout: Option<u64>,
     allow_missing_blocks: Option<bool>,
+    range_max_cost: Option<u64>,
+    range_chunk: Option<u64>,
+    record: Option<String>,
+    replay: Option<String>,
+    jwt_secret: Option<String>,
+    max_batch_size: Option<usize>,
+    max_batch_cost: Option<usize>,
+    batch_parallelism: Option<usize>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -916,6 +1224,8 @@ struct Ws {
     hosts: Option<Vec<String>>,
     max_connections: Option<usize>,
     max_payload: Option<usize>,
+    max_queued_notifications: Option<usize>,
+    queue_overflow: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -963,11 +1273,17 @@ struct SecretStore {
 struct Mining {
     author: Option<String>,
     engine_signer: Option<String>,
+    engine_signer_pkcs11_module: Option<String>,
+    engine_signer_pkcs11_slot: Option<u64>,
+    engine_signer_pkcs11_key_label: Option<String>,
+    engine_signer_pkcs11_timeout: Option<u64>,
+    fee_recipient: Option<String>,
     force_sealing: Option<bool>,
     reseal_on_uncle: Option<bool>,
     reseal_on_txs: Option<String>,
     reseal_min_period: Option<u64>,
     reseal_max_period: Option<u64>,
+    reseal_throttle_max_period: Option<u64>,
     work_queue_size: Option<usize>,
     tx_gas_limit: Option<String>,
     tx_time_limit: Option<u64>,
@@ -981,19 +1297,41 @@ struct Mining {
     gas_cap: Option<String>,
     extra_data: Option<String>,
     tx_queue_size: Option<usize>,
+    tx_queue_future_size: Option<usize>,
+    tx_queue_future_mem_limit: Option<u32>,
     tx_queue_per_sender: Option<usize>,
+    tx_queue_sender_gas_limit: Option<String>,
+    tx_queue_sender_window_limit: Option<usize>,
+    tx_queue_sender_window: Option<u64>,
+    tx_queue_sender_premium: Option<u32>,
+    tx_queue_replace_bump_permille: Option<u32>,
+    tx_queue_replace_max_fee_bump_permille: Option<u32>,
+    tx_queue_replace_priority_fee_bump_permille: Option<u32>,
     tx_queue_mem_limit: Option<u32>,
+    tx_queue_max_age: Option<u64>,
+    tx_queue_timestamp_tolerance: Option<u64>,
     tx_queue_locals: Option<HashSet<String>>,
+    tx_queue_prioritized_senders: Option<HashSet<String>>,
+    tx_queue_entry_points: Option<HashSet<String>>,
+    tx_queue_bundle_limit: Option<usize>,
+    tx_queue_bundle_gas_limit: Option<String>,
     tx_queue_strategy: Option<String>,
     tx_queue_ban_count: Option<u16>,
     tx_queue_ban_time: Option<u16>,
     tx_queue_no_unfamiliar_locals: Option<bool>,
+    tx_queue_node_clock: Option<bool>,
     tx_queue_no_early_reject: Option<bool>,
     remove_solved: Option<bool>,
     notify_work: Option<Vec<String>>,
+    tx_webhooks: Option<Vec<String>>,
+    tx_webhook_retries: Option<usize>,
+    block_builder_url: Option<String>,
+    block_builder_timeout: Option<u64>,
     refuse_service_transactions: Option<bool>,
     infinite_pending_block: Option<bool>,
+    block_building_budget: Option<u64>,
     max_round_blocks_to_import: Option<usize>,
+    uncle_strategy: Option<String>,
     new_transactions_stats_period: Option<u64>,
 }
 
@@ -1012,6 +1350,9 @@ struct Footprint {
     pruning: Option<String>,
     pruning_history: Option<u64>,
     pruning_memory: Option<usize>,
+    history_expiry: Option<u64>,
+    tx_index_expiry: Option<u64>,
+    state_regeneration_limit: Option<u64>,
     fast_and_loose: Option<bool>,
     cache_size: Option<u32>,
     cache_size_db: Option<u32>,
@@ -1022,6 +1363,9 @@ struct Footprint {
     fat_db: Option<String>,
     scale_verifiers: Option<bool>,
     num_verifiers: Option<usize>,
+    db_read_profiler: Option<bool>,
+    read_only: Option<bool>,
+    compress_history: Option<bool>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -1039,6 +1383,16 @@ struct Misc {
     color: Option<bool>,
     ports_shift: Option<u16>,
     unsafe_expose: Option<bool>,
+    clock_drift_monitor: Option<bool>,
+    ntp_servers: Option<Vec<String>>,
+    clock_drift_interval: Option<u64>,
+    clock_drift_threshold: Option<u64>,
+    max_future_block_drift: Option<u64>,
+    max_relay_future_drift: Option<u64>,
+    max_reorg_depth: Option<u64>,
+    spec_update_check: Option<bool>,
+    spec_update_check_interval: Option<u64>,
+    spec_update_refuse_sealing: Option<bool>,
 }
 
 #[cfg(test)]
@@ -1088,6 +1442,10 @@ mod tests {
         let args =
             Args::parse(&["openethereum", "export", "state", "--min-balance", "123"]).unwrap();
         assert_eq!(args.arg_export_state_min_balance, Some("123".to_string()));
+
+        let args = Args::parse(&["openethereum", "db", "migrate", "--dry-run"]).unwrap();
+        assert!(args.cmd_db_migrate);
+        assert_eq!(args.flag_db_migrate_dry_run, true);
     }
 
     #[test]
@@ -1251,11 +1609,16 @@ mod tests {
                 cmd_db: false,
                 cmd_db_kill: false,
                 cmd_db_reset: false,
+                cmd_db_rebuild_blooms: false,
+                cmd_db_bad_blocks: false,
+                cmd_db_backup: false,
+                cmd_db_migrate: false,
 
                 // Arguments
                 arg_daemon_pid_file: None,
                 arg_import_file: None,
                 arg_import_format: None,
+                arg_import_resume_from: None,
                 arg_export_blocks_file: None,
                 arg_export_blocks_format: None,
                 arg_export_state_file: None,
@@ -1269,17 +1632,24 @@ mod tests {
                 arg_account_import_path: None,
                 arg_wallet_import_path: None,
                 arg_db_reset_num: 10,
+                arg_db_reset_to: None,
+                arg_db_backup_dir: None,
+                flag_db_migrate_dry_run: false,
 
                 // -- Operating Options
                 arg_mode: "last".into(),
                 arg_mode_timeout: 300u64,
                 arg_mode_alarm: 3600u64,
                 arg_chain: "xyz".into(),
+                arg_chain_overlay: None,
                 arg_base_path: Some("$HOME/.parity".into()),
                 arg_db_path: Some("$HOME/.parity/chains".into()),
                 arg_keys_path: "$HOME/.parity/keys".into(),
                 arg_identity: "".into(),
                 flag_no_persistent_txqueue: false,
+                flag_tx_journal_encryption: false,
+                arg_tx_journal_segment_size: 1024usize,
+                arg_tx_journal_segments: 8u64,
 
                 // -- Convenience Options
                 arg_config: "$BASE/config.toml".into(),
@@ -1310,9 +1680,14 @@ mod tests {
                 flag_no_discovery: false,
                 arg_node_key: None,
                 arg_reserved_peers: Some("./path_to_file".into()),
+                arg_node_certificate: None,
+                arg_node_certificate_authorities: None,
                 flag_reserved_only: false,
                 flag_no_ancient_blocks: false,
                 arg_warp_barrier: None,
+                arg_rpc_bridge_url: None,
+                arg_rpc_bridge_batch_size: 32u64,
+                arg_rpc_bridge_handover_distance: 64u64,
 
                 // -- API and Console Options
                 // RPC
@@ -1328,6 +1703,15 @@ mod tests {
                 arg_jsonrpc_threads: 4,
                 arg_jsonrpc_max_payload: None,
                 arg_poll_lifetime: 60u32,
+                arg_jsonrpc_call_timeout: 5000u64,
+                arg_jsonrpc_range_max_cost: None,
+                arg_jsonrpc_range_chunk: 1000u64,
+                arg_jsonrpc_record: None,
+                arg_jsonrpc_replay: None,
+                arg_rpc_jwt_secret: None,
+                arg_jsonrpc_max_batch_size: 1024usize,
+                arg_jsonrpc_max_batch_cost: 10000usize,
+                arg_jsonrpc_batch_parallelism: 4usize,
                 flag_jsonrpc_allow_missing_blocks: false,
 
                 // WS
@@ -1339,6 +1723,8 @@ mod tests {
                 arg_ws_hosts: "none".into(),
                 arg_ws_max_connections: 100,
                 arg_ws_max_payload: 5,
+                arg_ws_max_queued_notifications: 10000,
+                arg_ws_queue_overflow: "drop-oldest".into(),
 
                 // IPC
                 flag_no_ipc: false,
@@ -1375,10 +1761,16 @@ mod tests {
                 // -- Sealing/Mining Options
                 arg_author: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
                 arg_engine_signer: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
+                arg_engine_signer_pkcs11_module: None,
+                arg_engine_signer_pkcs11_slot: 0u64,
+                arg_engine_signer_pkcs11_key_label: None,
+                arg_engine_signer_pkcs11_timeout: 5000u64,
+                arg_fee_recipient: None,
                 flag_force_sealing: true,
                 arg_reseal_on_txs: "all".into(),
                 arg_reseal_min_period: 4000u64,
                 arg_reseal_max_period: 60000u64,
+                arg_reseal_throttle_max_period: None,
                 flag_reseal_on_uncle: false,
                 arg_work_queue_size: 20usize,
                 arg_tx_gas_limit: Some("10000000".into()),
@@ -1393,17 +1785,39 @@ mod tests {
                 arg_gas_cap: "10000000".into(),
                 arg_extra_data: Some("Parity".into()),
                 flag_tx_queue_no_unfamiliar_locals: false,
+                flag_tx_queue_node_clock: false,
                 flag_tx_queue_no_early_reject: false,
                 arg_tx_queue_size: 8192usize,
+                arg_tx_queue_future_size: None,
+                arg_tx_queue_future_mem_limit: None,
                 arg_tx_queue_per_sender: None,
+                arg_tx_queue_sender_gas_limit: None,
+                arg_tx_queue_sender_window_limit: None,
+                arg_tx_queue_sender_window: 1u64,
+                arg_tx_queue_sender_premium: 10u32,
+                arg_tx_queue_replace_bump_permille: 125u32,
+                arg_tx_queue_replace_max_fee_bump_permille: None,
+                arg_tx_queue_replace_priority_fee_bump_permille: None,
                 arg_tx_queue_mem_limit: 4u32,
+                arg_tx_queue_max_age: None,
+                arg_tx_queue_timestamp_tolerance: 0u64,
                 arg_tx_queue_locals: Some("0xdeadbeefcafe0000000000000000000000000000".into()),
+                arg_tx_queue_prioritized_senders: None,
+                arg_tx_queue_entry_points: None,
+                arg_tx_queue_bundle_limit: 256usize,
+                arg_tx_queue_bundle_gas_limit: None,
                 arg_tx_queue_strategy: "gas_factor".into(),
                 flag_remove_solved: false,
                 arg_notify_work: Some("http://localhost:3001".into()),
+                arg_tx_webhooks: None,
+                arg_tx_webhook_retries: 3usize,
+                arg_block_builder_url: None,
+                arg_block_builder_timeout: 500u64,
                 flag_refuse_service_transactions: false,
                 flag_infinite_pending_block: false,
+                arg_block_building_budget: None,
                 arg_max_round_blocks_to_import: 1usize,
+                arg_uncle_strategy: "first".into(),
                 arg_new_transactions_stats_period: 0u64,
 
                 flag_stratum: false,
@@ -1416,6 +1830,9 @@ mod tests {
                 arg_pruning: "auto".into(),
                 arg_pruning_history: 64u64,
                 arg_pruning_memory: 500usize,
+                arg_history_expiry: None,
+                arg_tx_index_expiry: None,
+                arg_state_regeneration_limit: None,
                 arg_cache_size_db: 64u32,
                 arg_cache_size_blocks: 8u32,
                 arg_cache_size_queue: 50u32,
@@ -1425,6 +1842,9 @@ mod tests {
                 arg_fat_db: "auto".into(),
                 flag_scale_verifiers: true,
                 arg_num_verifiers: Some(6),
+                flag_db_read_profiler: false,
+                flag_read_only: false,
+                flag_compress_history: false,
 
                 // -- Import/Export Options
                 arg_export_blocks_from: "1".into(),
@@ -1450,6 +1870,20 @@ mod tests {
                 arg_log_file: Some("/var/log/openethereum.log".into()),
                 flag_no_color: false,
                 flag_no_config: false,
+
+                // -- Clock Drift Options
+                flag_clock_drift_monitor: false,
+                arg_ntp_servers:
+                    "0.pool.ntp.org:123,1.pool.ntp.org:123,2.pool.ntp.org:123,3.pool.ntp.org:123"
+                        .into(),
+                arg_clock_drift_interval: 300u64,
+                arg_clock_drift_threshold: 1000u64,
+                arg_max_future_block_drift: 15u64,
+                arg_max_relay_future_drift: None,
+                arg_max_reorg_depth: None,
+                flag_spec_update_check: false,
+                arg_spec_update_check_interval: 100u64,
+                flag_spec_update_refuse_sealing: false,
             }
         );
     }
@@ -1490,11 +1924,15 @@ mod tests {
                     mode_timeout: Some(15u64),
                     mode_alarm: Some(10u64),
                     chain: Some("./chain.json".into()),
+                    chain_overlay: None,
                     base_path: None,
                     db_path: None,
                     keys_path: None,
                     identity: None,
                     no_persistent_txqueue: None,
+                    tx_journal_encryption: None,
+                    tx_journal_segment_size: None,
+                    tx_journal_segments: None,
                 }),
                 account: Some(Account {
                     unlock: Some(vec!["0x1".into(), "0x2".into(), "0x3".into()]),
@@ -1507,6 +1945,9 @@ mod tests {
                 network: Some(Network {
                     warp: Some(false),
                     warp_barrier: None,
+                    rpc_bridge_url: None,
+                    rpc_bridge_batch_size: None,
+                    rpc_bridge_handover_distance: None,
                     port: None,
                     interface: None,
                     min_peers: Some(10),
@@ -1521,6 +1962,8 @@ mod tests {
                     node_key: None,
                     reserved_peers: Some("./path/to/reserved_peers".into()),
                     reserved_only: Some(true),
+                    node_certificate: None,
+                    node_certificate_authorities: None,
                 }),
                 websockets: Some(Ws {
                     disable: Some(true),
@@ -1531,6 +1974,8 @@ mod tests {
                     hosts: None,
                     max_connections: None,
                     max_payload: None,
+                    max_queued_notifications: None,
+                    queue_overflow: None,
                 }),
                 rpc: Some(Rpc {
                     disable: Some(true),
@@ -1545,7 +1990,16 @@ mod tests {
                     keep_alive: None,
                     experimental_rpcs: None,
                     poll_lifetime: None,
-                    allow_missing_blocks: None
+                    call_timeout: None,
+                    allow_missing_blocks: None,
+                    range_max_cost: None,
+                    range_chunk: None,
+                    record: None,
+                    replay: None,
+                    jwt_secret: None,
+                    max_batch_size: None,
+                    max_batch_cost: None,
+                    batch_parallelism: None,
                 }),
                 ipc: Some(Ipc {
                     disable: None,
@@ -1581,11 +2035,17 @@ mod tests {
                 mining: Some(Mining {
                     author: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
                     engine_signer: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
+                    engine_signer_pkcs11_module: None,
+                    engine_signer_pkcs11_slot: None,
+                    engine_signer_pkcs11_key_label: None,
+                    engine_signer_pkcs11_timeout: None,
+                    fee_recipient: None,
                     force_sealing: Some(true),
                     reseal_on_txs: Some("all".into()),
                     reseal_on_uncle: None,
                     reseal_min_period: Some(4000),
                     reseal_max_period: Some(60000),
+                    reseal_throttle_max_period: None,
                     work_queue_size: None,
                     relay_set: None,
                     min_gas_price: None,
@@ -1596,22 +2056,44 @@ mod tests {
                     gas_floor_target: None,
                     gas_cap: None,
                     tx_queue_size: Some(8192),
+                    tx_queue_future_size: None,
+                    tx_queue_future_mem_limit: None,
                     tx_queue_per_sender: None,
+                    tx_queue_sender_gas_limit: None,
+                    tx_queue_sender_window_limit: None,
+                    tx_queue_sender_window: None,
+                    tx_queue_sender_premium: None,
+                    tx_queue_replace_bump_permille: None,
+                    tx_queue_replace_max_fee_bump_permille: None,
+                    tx_queue_replace_priority_fee_bump_permille: None,
                     tx_queue_mem_limit: None,
+                    tx_queue_max_age: None,
+                    tx_queue_timestamp_tolerance: None,
                     tx_queue_locals: None,
+                    tx_queue_prioritized_senders: None,
+                    tx_queue_entry_points: None,
+                    tx_queue_bundle_limit: None,
+                    tx_queue_bundle_gas_limit: None,
                     tx_queue_strategy: None,
                     tx_queue_ban_count: None,
                     tx_queue_ban_time: None,
                     tx_queue_no_unfamiliar_locals: None,
+                    tx_queue_node_clock: None,
                     tx_queue_no_early_reject: None,
                     tx_gas_limit: None,
                     tx_time_limit: None,
                     extra_data: None,
                     remove_solved: None,
                     notify_work: None,
+                    tx_webhooks: None,
+                    tx_webhook_retries: None,
+                    block_builder_url: None,
+                    block_builder_timeout: None,
                     refuse_service_transactions: None,
                     infinite_pending_block: None,
+                    block_building_budget: None,
                     max_round_blocks_to_import: None,
+                    uncle_strategy: None,
                     new_transactions_stats_period: None,
                 }),
                 footprint: Some(Footprint {
@@ -1619,6 +2101,9 @@ mod tests {
                     pruning: Some("fast".into()),
                     pruning_history: Some(64),
                     pruning_memory: None,
+                    history_expiry: None,
+                    tx_index_expiry: None,
+                    state_regeneration_limit: None,
                     fast_and_loose: None,
                     cache_size: None,
                     cache_size_db: Some(256),
@@ -1629,6 +2114,9 @@ mod tests {
                     fat_db: Some("off".into()),
                     scale_verifiers: Some(false),
                     num_verifiers: None,
+                    db_read_profiler: None,
+                    read_only: None,
+                    compress_history: None,
                 }),
                 snapshots: Some(Snapshots {
                     enable: Some(false),
@@ -1640,6 +2128,16 @@ mod tests {
                     color: Some(true),
                     ports_shift: Some(0),
                     unsafe_expose: Some(false),
+                    clock_drift_monitor: None,
+                    ntp_servers: None,
+                    clock_drift_interval: None,
+                    clock_drift_threshold: None,
+                    max_future_block_drift: None,
+                    max_relay_future_drift: None,
+                    max_reorg_depth: None,
+                    spec_update_check: None,
+                    spec_update_check_interval: None,
+                    spec_update_refuse_sealing: None,
                 }),
                 stratum: None,
             }
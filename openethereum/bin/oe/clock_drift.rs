@@ -0,0 +1,452 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Local clock drift monitor.
+//!
+//! Periodically queries a set of NTP servers (SNTPv3) and estimates the offset of the local
+//! wall-clock. Timestamp validity checks in the engines are relative to the local clock, so a
+//! skewed host is exposed to (and may unknowingly take part in) time-manipulation scenarios.
+//!
+//! Name resolution and the NTP exchanges block, so checks run on a dedicated thread and every
+//! step is bounded by a timeout.
+
+use std::{
+    io,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering as AtomicOrdering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ansi_term::Colour;
+use parity_rpc::ClockDrift;
+use parking_lot::Mutex;
+use stats::{PrometheusMetrics, PrometheusRegistry};
+
+/// Seconds between the NTP epoch (1900) and the UNIX epoch (1970).
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+/// Size of an SNTP packet without authentication.
+const NTP_PACKET_SIZE: usize = 48;
+/// LI = 0 (no warning), VN = 3, Mode = 3 (client).
+const NTP_CLIENT_REQUEST: u8 = 0x1b;
+/// Mode of a server reply.
+const NTP_MODE_SERVER: u8 = 4;
+/// Leap indicator of a server whose clock is not synchronized.
+const NTP_LEAP_ALARM: u8 = 3;
+/// Highest stratum of a synchronized server. Stratum 0 is a kiss-o'-death reply.
+const NTP_MAX_STRATUM: u8 = 15;
+/// Timeout for a single server round-trip.
+const NTP_QUERY_TIMEOUT: Duration = Duration::from_secs(1);
+/// Timeout for resolving the address of a single server.
+const NTP_RESOLVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Delay of the first check after startup, so the result doesn't wait for a full interval.
+const INITIAL_CHECK_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClockDriftConfiguration {
+    /// Is the monitor enabled (default is false)?
+    pub enabled: bool,
+    /// NTP servers to query, in `host:port` form.
+    pub servers: Vec<String>,
+    /// Time between two consecutive checks.
+    pub interval: Duration,
+    /// Absolute offset above which a warning is logged.
+    pub warn_threshold: Duration,
+}
+
+impl Default for ClockDriftConfiguration {
+    fn default() -> Self {
+        ClockDriftConfiguration {
+            enabled: false,
+            servers: vec![
+                "0.pool.ntp.org:123".into(),
+                "1.pool.ntp.org:123".into(),
+                "2.pool.ntp.org:123".into(),
+                "3.pool.ntp.org:123".into(),
+            ],
+            interval: Duration::from_secs(300),
+            warn_threshold: Duration::from_millis(1000),
+        }
+    }
+}
+
+/// Periodically estimates the local clock offset against NTP servers.
+pub struct ClockDriftMonitor {
+    config: ClockDriftConfiguration,
+    /// Last estimated offset in milliseconds (positive when the local clock is behind).
+    offset_ms: AtomicI64,
+    has_sample: AtomicBool,
+    /// Was the last offset above the warning threshold? Warnings are only logged on change.
+    drifting: AtomicBool,
+    /// Did the last check fail?
+    unreachable: AtomicBool,
+    checks: AtomicU64,
+    failures: AtomicU64,
+    in_shutdown: AtomicBool,
+    worker: Mutex<Option<(mpsc::Sender<()>, JoinHandle<()>)>>,
+}
+
+impl ClockDriftMonitor {
+    /// Create a new monitor. Nothing is queried until the monitor is started.
+    pub fn new(config: ClockDriftConfiguration) -> Self {
+        ClockDriftMonitor {
+            config,
+            offset_ms: AtomicI64::new(0),
+            has_sample: AtomicBool::new(false),
+            drifting: AtomicBool::new(false),
+            unreachable: AtomicBool::new(false),
+            checks: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            in_shutdown: AtomicBool::new(false),
+            worker: Mutex::new(None),
+        }
+    }
+
+    /// Start checking the clock drift periodically on a dedicated thread.
+    pub fn start(self: &Arc<Self>) -> io::Result<()> {
+        let (stop, stopped) = mpsc::channel();
+        let monitor = self.clone();
+        let handle = thread::Builder::new()
+            .name("clock-drift".into())
+            .spawn(move || {
+                let mut wait = INITIAL_CHECK_DELAY;
+                // Runs until `shutdown` sends a message or drops the sender.
+                while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(wait) {
+                    monitor.check();
+                    wait = monitor.config.interval;
+                }
+            })?;
+        *self.worker.lock() = Some((stop, handle));
+        Ok(())
+    }
+
+    /// Last estimated offset of the local clock, in milliseconds.
+    /// Positive values mean the local clock is behind the reference time.
+    pub fn offset_millis(&self) -> Option<i64> {
+        match self.has_sample.load(AtomicOrdering::SeqCst) {
+            true => Some(self.offset_ms.load(AtomicOrdering::SeqCst)),
+            false => None,
+        }
+    }
+
+    /// Signal that we're shutting down and wait for the checking thread to exit.
+    pub fn shutdown(&self) {
+        self.in_shutdown.store(true, AtomicOrdering::SeqCst);
+        if let Some((stop, handle)) = self.worker.lock().take() {
+            let _ = stop.send(());
+            if handle.join().is_err() {
+                warn!(target: "clock", "Clock drift monitor thread panicked");
+            }
+        }
+    }
+
+    /// Query all configured servers and update the estimated offset with the median result.
+    pub fn check(&self) {
+        self.checks.fetch_add(1, AtomicOrdering::Relaxed);
+
+        let mut offsets = self
+            .config
+            .servers
+            .iter()
+            .take_while(|_| !self.in_shutdown.load(AtomicOrdering::SeqCst))
+            .filter_map(|server| match query_offset(server) {
+                Ok(offset) => {
+                    trace!(target: "clock", "NTP server {} reports offset {} ms", server, offset);
+                    Some(offset)
+                }
+                Err(e) => {
+                    debug!(target: "clock", "Failed to query NTP server {}: {}", server, e);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if offsets.is_empty() {
+            self.failures.fetch_add(1, AtomicOrdering::Relaxed);
+            if !self.unreachable.swap(true, AtomicOrdering::SeqCst) {
+                warn!(target: "clock", "Unable to estimate local clock drift: no NTP server responded.");
+            } else {
+                debug!(target: "clock", "Unable to estimate local clock drift: no NTP server responded.");
+            }
+            return;
+        }
+        self.unreachable.store(false, AtomicOrdering::SeqCst);
+
+        offsets.sort();
+        self.record_offset(offsets[offsets.len() / 2]);
+    }
+
+    /// Store the estimated offset, logging when it crosses the warning threshold.
+    fn record_offset(&self, offset: i64) {
+        self.offset_ms.store(offset, AtomicOrdering::SeqCst);
+        self.has_sample.store(true, AtomicOrdering::SeqCst);
+
+        let drifting = offset.unsigned_abs() as u128 > self.config.warn_threshold.as_millis();
+        let was_drifting = self.drifting.swap(drifting, AtomicOrdering::SeqCst);
+        if drifting && !was_drifting {
+            warn!(
+                target: "clock",
+                "Local clock is {} by {} ms. Block timestamp checks depend on the local clock, please synchronize it.",
+                if offset > 0 { "behind" } else { "ahead" },
+                Colour::Red.bold().paint(format!("{}", offset.abs())),
+            );
+        } else if was_drifting && !drifting {
+            info!(target: "clock", "Local clock is synchronized again, offset is {} ms", offset);
+        } else {
+            debug!(target: "clock", "Local clock offset is {} ms", offset);
+        }
+    }
+}
+
+impl ClockDrift for ClockDriftMonitor {
+    fn offset_millis(&self) -> Option<i64> {
+        ClockDriftMonitor::offset_millis(self)
+    }
+}
+
+impl PrometheusMetrics for ClockDriftMonitor {
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        r.register_counter(
+            "clock_drift_checks",
+            "Number of clock drift checks performed",
+            self.checks.load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_counter(
+            "clock_drift_failures",
+            "Number of clock drift checks where no NTP server responded",
+            self.failures.load(AtomicOrdering::Relaxed) as i64,
+        );
+        if let Some(offset) = self.offset_millis() {
+            r.register_gauge(
+                "clock_drift_offset_ms",
+                "Estimated local clock offset against NTP, in milliseconds",
+                offset,
+            );
+        }
+    }
+}
+
+/// Performs a single SNTP exchange and returns the clock offset in milliseconds.
+fn query_offset(server: &str) -> io::Result<i64> {
+    let addr = resolve(server, NTP_RESOLVE_TIMEOUT)?;
+    let bind_addr = if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(bind_addr)?;
+    socket.set_read_timeout(Some(NTP_QUERY_TIMEOUT))?;
+    socket.set_write_timeout(Some(NTP_QUERY_TIMEOUT))?;
+
+    let originate = unix_millis_now();
+    let mut request = [0u8; NTP_PACKET_SIZE];
+    request[0] = NTP_CLIENT_REQUEST;
+    // the server echoes it as the originate timestamp of its reply
+    request[40..48].copy_from_slice(&unix_millis_to_ntp_timestamp(originate));
+    socket.send_to(&request, addr)?;
+
+    let mut response = [0u8; NTP_PACKET_SIZE];
+    let (len, _) = socket.recv_from(&mut response)?;
+    let destination = unix_millis_now();
+
+    if len < NTP_PACKET_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "short NTP response",
+        ));
+    }
+    validate_response(&request, &response)?;
+
+    let receive = ntp_timestamp_to_unix_millis(&response[32..40]);
+    let transmit = ntp_timestamp_to_unix_millis(&response[40..48]);
+    Ok(sntp_offset(originate, receive, transmit, destination))
+}
+
+/// Resolves the first address of `server`, giving up after `timeout`.
+///
+/// The system resolver has no timeout of its own, so the lookup runs on a short-lived thread.
+/// A lookup that times out is left to finish in the background.
+fn resolve(server: &str, timeout: Duration) -> io::Result<SocketAddr> {
+    let (tx, rx) = mpsc::channel();
+    let host = server.to_owned();
+    thread::Builder::new()
+        .name("clock-drift-dns".into())
+        .spawn(move || {
+            let _ = tx.send(host.to_socket_addrs().map(|mut addrs| addrs.next()));
+        })?;
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(Some(addr))) => Ok(addr),
+        Ok(Ok(None)) => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "server address did not resolve",
+        )),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "server address resolution timed out",
+        )),
+    }
+}
+
+/// Checks that `response` is a reply of a synchronized server to `request`.
+fn validate_response(
+    request: &[u8; NTP_PACKET_SIZE],
+    response: &[u8; NTP_PACKET_SIZE],
+) -> io::Result<()> {
+    let invalid = |reason: &str| Err(io::Error::new(io::ErrorKind::InvalidData, reason));
+    let leap = response[0] >> 6;
+    let mode = response[0] & 0x07;
+    let stratum = response[1];
+
+    if mode != NTP_MODE_SERVER {
+        return invalid("NTP response is not a server reply");
+    }
+    if leap == NTP_LEAP_ALARM {
+        return invalid("NTP server is not synchronized");
+    }
+    if stratum == 0 || stratum > NTP_MAX_STRATUM {
+        return invalid("NTP server is not synchronized or refused the request");
+    }
+    if response[24..32] != request[40..48] {
+        return invalid("NTP response does not answer our request");
+    }
+    if response[40..48].iter().all(|b| *b == 0) {
+        return invalid("NTP response has no transmit timestamp");
+    }
+    Ok(())
+}
+
+/// Standard SNTP offset: `((T2 - T1) + (T3 - T4)) / 2`.
+fn sntp_offset(originate: i64, receive: i64, transmit: i64, destination: i64) -> i64 {
+    ((receive - originate) + (transmit - destination)) / 2
+}
+
+/// Converts a 64-bit NTP timestamp (32.32 fixed point, NTP epoch) into UNIX milliseconds.
+fn ntp_timestamp_to_unix_millis(bytes: &[u8]) -> i64 {
+    let secs = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64;
+    let frac = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as u64;
+    let millis = (frac * 1000) >> 32;
+    (secs as i64 - NTP_UNIX_OFFSET_SECS as i64) * 1000 + millis as i64
+}
+
+/// Converts UNIX milliseconds into a 64-bit NTP timestamp.
+fn unix_millis_to_ntp_timestamp(millis: i64) -> [u8; 8] {
+    let secs = (millis.div_euclid(1000) + NTP_UNIX_OFFSET_SECS as i64) as u32;
+    let frac = ((millis.rem_euclid(1000) as u64) << 32) / 1000;
+    let mut bytes = [0u8; 8];
+    bytes[..4].copy_from_slice(&secs.to_be_bytes());
+    bytes[4..].copy_from_slice(&(frac as u32).to_be_bytes());
+    bytes
+}
+
+fn unix_millis_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ntp_timestamp_to_unix_millis, sntp_offset, unix_millis_to_ntp_timestamp, validate_response,
+        ClockDriftConfiguration, ClockDriftMonitor, NTP_CLIENT_REQUEST, NTP_PACKET_SIZE,
+        NTP_UNIX_OFFSET_SECS,
+    };
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn should_convert_ntp_timestamp() {
+        let secs = (NTP_UNIX_OFFSET_SECS + 1_600_000_000) as u32;
+        let mut bytes = [0u8; 8];
+        bytes[..4].copy_from_slice(&secs.to_be_bytes());
+        // half a second
+        bytes[4..].copy_from_slice(&0x8000_0000u32.to_be_bytes());
+
+        assert_eq!(ntp_timestamp_to_unix_millis(&bytes), 1_600_000_000_500);
+    }
+
+    #[test]
+    fn should_convert_unix_millis_to_ntp_timestamp() {
+        let bytes = unix_millis_to_ntp_timestamp(1_600_000_000_500);
+        assert_eq!(ntp_timestamp_to_unix_millis(&bytes), 1_600_000_000_500);
+    }
+
+    #[test]
+    fn should_validate_response() {
+        let mut request = [0u8; NTP_PACKET_SIZE];
+        request[0] = NTP_CLIENT_REQUEST;
+        request[40..48].copy_from_slice(&unix_millis_to_ntp_timestamp(1_600_000_000_000));
+
+        let mut response = [0u8; NTP_PACKET_SIZE];
+        // LI = 0, VN = 3, Mode = 4 (server), stratum 2
+        response[0] = 0x1c;
+        response[1] = 2;
+        response[24..32].copy_from_slice(&request[40..48]);
+        response[40..48].copy_from_slice(&unix_millis_to_ntp_timestamp(1_600_000_001_000));
+        assert!(validate_response(&request, &response).is_ok());
+
+        let invalid = |change: &dyn Fn(&mut [u8; NTP_PACKET_SIZE])| {
+            let mut response = response;
+            change(&mut response);
+            validate_response(&request, &response).is_err()
+        };
+        // client mode
+        assert!(invalid(&|r| r[0] = 0x1b));
+        // unsynchronized leap indicator
+        assert!(invalid(&|r| r[0] |= 0xc0));
+        // kiss-o'-death
+        assert!(invalid(&|r| r[1] = 0));
+        // unsynchronized stratum
+        assert!(invalid(&|r| r[1] = 16));
+        // reply to another request
+        assert!(invalid(&|r| r[31] ^= 1));
+        // no transmit timestamp
+        assert!(invalid(&|r| r[40..48].copy_from_slice(&[0; 8])));
+    }
+
+    #[test]
+    fn should_track_threshold_crossings() {
+        let monitor = ClockDriftMonitor::new(ClockDriftConfiguration {
+            servers: vec![],
+            ..Default::default()
+        });
+
+        monitor.record_offset(-2_000);
+        assert!(monitor.drifting.load(Ordering::SeqCst));
+        monitor.record_offset(3_000);
+        assert!(monitor.drifting.load(Ordering::SeqCst));
+        monitor.record_offset(200);
+        assert!(!monitor.drifting.load(Ordering::SeqCst));
+        assert_eq!(monitor.offset_millis(), Some(200));
+
+        monitor.check();
+        assert!(monitor.unreachable.load(Ordering::SeqCst));
+        assert_eq!(monitor.failures.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn should_compute_offset() {
+        // local clock is 2s behind, 100ms round trip
+        assert_eq!(sntp_offset(1_000, 3_050, 3_050, 1_100), 2_000);
+        // local clock is 2s ahead
+        assert_eq!(sntp_offset(5_000, 3_050, 3_050, 5_100), -2_000);
+    }
+}
@@ -17,6 +17,7 @@
 use crate::{
     bytes::Bytes,
     cli::{Args, ArgsError},
+    clock_drift::ClockDriftConfiguration,
     hash::keccak,
     metrics::MetricsConfiguration,
     miner::pool,
@@ -26,9 +27,10 @@ use ansi_term::Colour;
 
 use crypto::publickey::{Public, Secret};
 use ethcore::{
-    client::VMType,
+    client::{CacheSizes, UncleStrategy, VMType},
     miner::{stratum, MinerOptions},
     snapshot::SnapshotConfiguration,
+    spec::SpecUpdateConfig,
     verification::queue::VerifierSettings,
 };
 use ethereum_types::{Address, H256, U256};
@@ -49,16 +51,19 @@ use std::{
 use crate::{
     account::{AccountCmd, ImportAccounts, ListAccounts, NewAccount},
     blockchain::{
-        BlockchainCmd, ExportBlockchain, ExportState, ImportBlockchain, KillBlockchain,
-        ResetBlockchain,
+        BackupBlockchain, BlockchainCmd, ExportBlockchain, ExportState, ImportBlockchain,
+        KillBlockchain, ListBadBlocks, MigrateBlockchain, RebuildBlooms, ResetBlockchain,
     },
     cache::CacheConfig,
     helpers::{
         parity_ipc_path, to_address, to_addresses, to_block_id, to_bootnodes, to_duration, to_mode,
         to_pending_set, to_price, to_queue_penalization, to_queue_strategy, to_u256,
     },
+    hsm_signer::Pkcs11Config,
     network::IpFilter,
-    params::{AccountsConfig, GasPricerConfig, MinerExtras, ResealPolicy, SpecType},
+    params::{
+        load_spec_overlay, AccountsConfig, GasPricerConfig, MinerExtras, ResealPolicy, SpecType,
+    },
     presale::ImportWallet,
     rpc::{HttpConfiguration, IpcConfiguration, WsConfiguration},
     run::RunCmd,
@@ -75,7 +80,8 @@ use dir::{
     Directories,
 };
 use ethcore_logger::Config as LogConfig;
-use parity_rpc::NetworkSettings;
+use node_filter::NodeCertificate;
+use parity_rpc::{BatchLimits, NetworkSettings, RangeBudget, SubscriptionLimits};
 
 const DEFAULT_MAX_PEERS: u16 = 50;
 const DEFAULT_MIN_PEERS: u16 = 25;
@@ -137,6 +143,11 @@ impl Configuration {
         Ok(config)
     }
 
+    /// Cache budgets which can be applied to a running client.
+    pub fn runtime_cache_sizes(&self) -> CacheSizes {
+        self.cache_config().runtime_sizes()
+    }
+
     pub(crate) fn into_command(self) -> Result<Execute, String> {
         let dirs = self.directories();
         let pruning = self.args.arg_pruning.parse()?;
@@ -167,6 +178,9 @@ impl Configuration {
         let secretstore_conf = self.secretstore_config()?;
         let format = self.format()?;
         let metrics_conf = self.metrics_config()?;
+        let clock_drift_conf = self.clock_drift_config();
+        let spec_update_conf = self.spec_update_config();
+        let node_certificate_authorities = self.node_certificate_authorities()?;
         let keys_iterations = NonZeroU32::new(self.args.arg_keys_iterations)
             .ok_or_else(|| "--keys-iterations must be non-zero")?;
 
@@ -217,6 +231,61 @@ impl Configuration {
                 compaction,
                 cache_config,
                 num: self.args.arg_db_reset_num,
+                target: self
+                    .args
+                    .arg_db_reset_to
+                    .as_ref()
+                    .map(|target| to_block_id(target))
+                    .transpose()?,
+            }))
+        } else if self.args.cmd_db && self.args.cmd_db_rebuild_blooms {
+            Cmd::Blockchain(BlockchainCmd::RebuildBlooms(RebuildBlooms {
+                dirs,
+                spec,
+                pruning,
+                pruning_history,
+                pruning_memory: self.args.arg_pruning_memory,
+                tracing,
+                fat_db,
+                compaction,
+                cache_config,
+            }))
+        } else if self.args.cmd_db && self.args.cmd_db_bad_blocks {
+            Cmd::Blockchain(BlockchainCmd::BadBlocks(ListBadBlocks {
+                dirs,
+                spec,
+                pruning,
+                pruning_history,
+                pruning_memory: self.args.arg_pruning_memory,
+                tracing,
+                fat_db,
+                compaction,
+                cache_config,
+            }))
+        } else if self.args.cmd_db && self.args.cmd_db_backup {
+            Cmd::Blockchain(BlockchainCmd::Backup(BackupBlockchain {
+                dirs,
+                spec,
+                pruning,
+                pruning_history,
+                pruning_memory: self.args.arg_pruning_memory,
+                tracing,
+                fat_db,
+                compaction,
+                cache_config,
+                path: self
+                    .args
+                    .arg_db_backup_dir
+                    .clone()
+                    .ok_or_else(|| "Backup directory is required".to_owned())?,
+            }))
+        } else if self.args.cmd_db && self.args.cmd_db_migrate {
+            Cmd::Blockchain(BlockchainCmd::Migrate(MigrateBlockchain {
+                spec,
+                dirs,
+                pruning,
+                compaction,
+                dry_run: self.args.flag_db_migrate_dry_run,
             }))
         } else if self.args.cmd_db && self.args.cmd_db_kill {
             Cmd::Blockchain(BlockchainCmd::Kill(KillBlockchain {
@@ -278,6 +347,7 @@ impl Configuration {
                 dirs: dirs,
                 file_path: self.args.arg_import_file.clone(),
                 format: format,
+                resume_from: self.args.arg_import_resume_from,
                 pruning: pruning,
                 pruning_history: pruning_history,
                 pruning_memory: self.args.arg_pruning_memory,
@@ -403,6 +473,10 @@ impl Configuration {
                 miner_options: self.miner_options()?,
                 gas_price_percentile: self.args.arg_gas_price_percentile,
                 poll_lifetime: self.args.arg_poll_lifetime,
+                call_timeout: match self.args.arg_jsonrpc_call_timeout {
+                    0 => None,
+                    ms => Some(Duration::from_millis(ms)),
+                },
                 ws_conf: ws_conf,
                 snapshot_conf: snapshot_conf,
                 http_conf: http_conf,
@@ -421,6 +495,7 @@ impl Configuration {
                 vm_type: vm_type,
                 warp_sync: warp_sync,
                 warp_barrier: self.args.arg_warp_barrier,
+                rpc_bridge: self.rpc_bridge_config(),
                 experimental_rpcs,
                 net_settings: self.network_settings()?,
                 secretstore_conf: secretstore_conf,
@@ -431,8 +506,43 @@ impl Configuration {
                 new_transactions_stats_period: self.args.arg_new_transactions_stats_period,
                 verifier_settings: verifier_settings,
                 no_persistent_txqueue: self.args.flag_no_persistent_txqueue,
+                tx_journal_encryption: self.args.flag_tx_journal_encryption,
+                tx_journal_segment_size: self.args.arg_tx_journal_segment_size * 1024,
+                tx_journal_segments: self.args.arg_tx_journal_segments,
                 max_round_blocks_to_import: self.args.arg_max_round_blocks_to_import,
                 metrics_conf,
+                clock_drift_conf,
+                db_read_profiler: self.args.flag_db_read_profiler,
+                read_only: self.args.flag_read_only,
+                compress_history: self.args.flag_compress_history,
+                max_future_block_drift: Duration::from_secs(cmp::max(
+                    1,
+                    self.args.arg_max_future_block_drift,
+                )),
+                max_relay_future_drift: self
+                    .args
+                    .arg_max_relay_future_drift
+                    .map(Duration::from_secs),
+                max_reorg_depth: self.args.arg_max_reorg_depth,
+                history_expiry: self.args.arg_history_expiry,
+                tx_index_expiry: self.args.arg_tx_index_expiry,
+                state_regeneration_limit: self.args.arg_state_regeneration_limit,
+                uncle_strategy: self.args.arg_uncle_strategy.parse()?,
+                spec_update_conf,
+                range_budget: self.range_budget(),
+                batch_limits: self.batch_limits(),
+                subscription_limits: self.subscription_limits()?,
+                node_certificate_authorities,
+                rpc_record: self
+                    .args
+                    .arg_jsonrpc_record
+                    .as_ref()
+                    .map(|path| replace_home(&self.directories().base, path)),
+                rpc_replay: self
+                    .args
+                    .arg_jsonrpc_replay
+                    .as_ref()
+                    .map(|path| replace_home(&self.directories().base, path)),
             };
             Cmd::Run(run_cmd)
         };
@@ -455,7 +565,14 @@ impl Configuration {
             extra_data: self.extra_data()?,
             gas_range_target: (floor, ceil),
             engine_signer: self.engine_signer()?,
+            engine_signer_pkcs11: self.engine_signer_pkcs11()?,
+            fee_recipient: self.fee_recipient()?,
             work_notify: self.work_notify(),
+            tx_webhooks: self.tx_webhooks(),
+            tx_webhook_retries: self.args.arg_tx_webhook_retries,
+            tx_queue_node_clock: self.args.flag_tx_queue_node_clock,
+            block_builder_url: self.args.arg_block_builder_url.clone(),
+            block_builder_timeout: Duration::from_millis(self.args.arg_block_builder_timeout),
             local_accounts: HashSet::from_iter(
                 to_addresses(&self.args.arg_tx_queue_locals)?.into_iter(),
             ),
@@ -472,6 +589,35 @@ impl Configuration {
         to_address(self.args.arg_engine_signer.clone())
     }
 
+    fn engine_signer_pkcs11(&self) -> Result<Option<Pkcs11Config>, String> {
+        let module = match self.args.arg_engine_signer_pkcs11_module {
+            Some(ref module) => module,
+            None => return Ok(None),
+        };
+        let key_label = self
+            .args
+            .arg_engine_signer_pkcs11_key_label
+            .clone()
+            .ok_or_else(|| {
+                "--engine-signer-pkcs11-key-label is required with --engine-signer-pkcs11-module"
+                    .to_owned()
+            })?;
+
+        Ok(Some(Pkcs11Config {
+            module: replace_home(&self.directories().base, module).into(),
+            slot: self.args.arg_engine_signer_pkcs11_slot,
+            key_label,
+            timeout: Duration::from_millis(self.args.arg_engine_signer_pkcs11_timeout),
+        }))
+    }
+
+    fn fee_recipient(&self) -> Result<Option<Address>, String> {
+        match self.args.arg_fee_recipient {
+            Some(ref recipient) => to_address(Some(recipient.clone())).map(Some),
+            None => Ok(None),
+        }
+    }
+
     fn format(&self) -> Result<Option<DataFormat>, String> {
         match self
             .args
@@ -511,11 +657,19 @@ impl Configuration {
     }
 
     fn chain(&self) -> Result<SpecType, String> {
-        Ok(self.args.arg_chain.parse()?)
+        let spec = self.args.arg_chain.parse()?;
+        Ok(match self.args.arg_chain_overlay {
+            Some(ref overlay) => {
+                // an invalid overlay is a configuration error, not a failure to load the spec
+                load_spec_overlay(overlay)?;
+                SpecType::Overlay(Box::new(spec), overlay.clone())
+            }
+            None => spec,
+        })
     }
 
     fn is_dev_chain(&self) -> Result<bool, String> {
-        Ok(self.chain()? == SpecType::Dev)
+        Ok(*self.chain()?.base() == SpecType::Dev)
     }
 
     fn max_peers(&self) -> u32 {
@@ -552,6 +706,13 @@ impl Configuration {
             .map_or_else(Vec::new, |s| s.split(',').map(|s| s.to_owned()).collect())
     }
 
+    fn tx_webhooks(&self) -> Vec<String> {
+        self.args
+            .arg_tx_webhooks
+            .as_ref()
+            .map_or_else(Vec::new, |s| s.split(',').map(|s| s.to_owned()).collect())
+    }
+
     fn accounts_config(&self) -> Result<AccountsConfig, String> {
         let keys_iterations = NonZeroU32::new(self.args.arg_keys_iterations)
             .ok_or_else(|| "--keys-iterations must be non-zero")?;
@@ -604,15 +765,38 @@ impl Configuration {
             reseal_on_uncle: self.args.flag_reseal_on_uncle,
             reseal_min_period: Duration::from_millis(self.args.arg_reseal_min_period),
             reseal_max_period: Duration::from_millis(self.args.arg_reseal_max_period),
+            reseal_throttle_max_period: self
+                .args
+                .arg_reseal_throttle_max_period
+                .map(Duration::from_millis),
 
             pending_set: to_pending_set(&self.args.arg_relay_set)?,
             work_queue_size: self.args.arg_work_queue_size,
             enable_resubmission: !self.args.flag_remove_solved,
             infinite_pending_block: self.args.flag_infinite_pending_block,
+            block_building_budget: self
+                .args
+                .arg_block_building_budget
+                .map(Duration::from_millis),
 
             tx_queue_penalization: to_queue_penalization(self.args.arg_tx_time_limit)?,
             tx_queue_strategy: to_queue_strategy(&self.args.arg_tx_queue_strategy)?,
             tx_queue_no_unfamiliar_locals: self.args.flag_tx_queue_no_unfamiliar_locals,
+            tx_queue_max_age: self.args.arg_tx_queue_max_age.map(Duration::from_secs),
+            tx_queue_timestamp_tolerance: Duration::from_secs(
+                self.args.arg_tx_queue_timestamp_tolerance,
+            ),
+            tx_queue_alt_mempool: pool::alt_mempool::Options {
+                entry_points: HashSet::from_iter(
+                    to_addresses(&self.args.arg_tx_queue_entry_points)?.into_iter(),
+                ),
+                max_count: self.args.arg_tx_queue_bundle_limit,
+                max_gas: match self.args.arg_tx_queue_bundle_gas_limit {
+                    Some(ref d) => Some(to_u256(d)?),
+                    None => None,
+                },
+            },
+            tx_queue_future_limits: self.pool_future_limits(),
             refuse_service_transactions: self.args.flag_refuse_service_transactions,
 
             pool_limits: self.pool_limits()?,
@@ -622,6 +806,20 @@ impl Configuration {
         Ok(options)
     }
 
+    fn pool_future_limits(&self) -> Option<pool::FutureLimits> {
+        let max_count = self.args.arg_tx_queue_future_size;
+        let max_mem_usage = self.args.arg_tx_queue_future_mem_limit;
+        if max_count.is_none() && max_mem_usage.is_none() {
+            return None;
+        }
+
+        Some(pool::FutureLimits {
+            max_count: max_count.unwrap_or_else(usize::max_value),
+            max_mem_usage: max_mem_usage
+                .map_or_else(usize::max_value, |mb| mb as usize * 1024 * 1024),
+        })
+    }
+
     fn pool_limits(&self) -> Result<pool::Options, String> {
         let max_count = self.args.arg_tx_queue_size;
 
@@ -651,6 +849,26 @@ impl Configuration {
             },
             no_early_reject: self.args.flag_tx_queue_no_early_reject,
             allow_non_eoa_sender: false,
+            max_gas_per_sender: match self.args.arg_tx_queue_sender_gas_limit {
+                Some(ref d) => Some(to_u256(d)?),
+                None => None,
+            },
+            replacement_bump: pool::replace::ReplacementBump {
+                gas_price: self.args.arg_tx_queue_replace_bump_permille,
+                max_fee: self.args.arg_tx_queue_replace_max_fee_bump_permille,
+                priority_fee: self.args.arg_tx_queue_replace_priority_fee_bump_permille,
+            },
+            prioritized_senders: HashSet::from_iter(
+                to_addresses(&self.args.arg_tx_queue_prioritized_senders)?.into_iter(),
+            ),
+            sender_premium: self
+                .args
+                .arg_tx_queue_sender_window_limit
+                .map(|per_window| pool::scoring::SenderPremium {
+                    window: self.args.arg_tx_queue_sender_window,
+                    per_window,
+                    premium: self.args.arg_tx_queue_sender_premium,
+                }),
         })
     }
 
@@ -811,6 +1029,42 @@ impl Configuration {
         Ok((listen_address, public_address))
     }
 
+    fn node_certificate(&self) -> Result<Option<Vec<u8>>, String> {
+        use rustc_hex::FromHex;
+        use std::fs;
+
+        let path = match self.args.arg_node_certificate {
+            Some(ref path) => replace_home(&self.directories().base, path),
+            None => return Ok(None),
+        };
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Error reading node certificate file: {}", e))?;
+        let content = content.trim();
+        let certificate = content
+            .trim_start_matches("0x")
+            .from_hex::<Vec<u8>>()
+            .map_err(|e| format!("Invalid node certificate: {}", e))?;
+        NodeCertificate::decode(&certificate)
+            .map_err(|e| format!("Invalid node certificate: {}", e))?;
+        Ok(Some(certificate))
+    }
+
+    fn node_certificate_authorities(&self) -> Result<Vec<Public>, String> {
+        match self.args.arg_node_certificate_authorities {
+            Some(ref keys) => keys
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(|key| {
+                    key.trim_start_matches("0x")
+                        .parse()
+                        .map_err(|e| format!("Invalid node certificate authority {}: {}", key, e))
+                })
+                .collect(),
+            None => Ok(Vec::new()),
+        }
+    }
+
     fn net_config(&self) -> Result<NetworkConfiguration, String> {
         let mut ret = NetworkConfiguration::new();
         ret.nat_enabled = self.args.arg_nat == "any" || self.args.arg_nat == "upnp";
@@ -838,6 +1092,7 @@ impl Configuration {
         ret.config_path = Some(net_path.to_str().unwrap().to_owned());
         ret.reserved_nodes = self.init_reserved_nodes()?;
         ret.allow_non_reserved = !self.args.flag_reserved_only;
+        ret.node_certificate = self.node_certificate()?;
         ret.client_version = {
             let mut client_version = version();
             if !self.args.arg_identity.is_empty() {
@@ -899,6 +1154,13 @@ impl Configuration {
         self.hosts(&self.args.arg_jsonrpc_hosts, &self.rpc_interface())
     }
 
+    fn rpc_jwt_secret(&self) -> Option<PathBuf> {
+        self.args
+            .arg_rpc_jwt_secret
+            .as_ref()
+            .map(|path| replace_home(&self.directories().base, path).into())
+    }
+
     fn ws_hosts(&self) -> Option<Vec<String>> {
         self.hosts(&self.args.arg_ws_hosts, &self.ws_interface())
     }
@@ -939,6 +1201,7 @@ impl Configuration {
                 _ => 5usize,
             },
             keep_alive: !self.args.flag_jsonrpc_no_keep_alive,
+            jwt_secret: self.rpc_jwt_secret(),
         };
 
         Ok(conf)
@@ -960,6 +1223,7 @@ impl Configuration {
             support_token_api,
             max_connections: self.args.arg_ws_max_connections,
             max_payload: self.args.arg_ws_max_payload,
+            jwt_secret: self.rpc_jwt_secret(),
         };
 
         Ok(conf)
@@ -975,6 +1239,73 @@ impl Configuration {
         Ok(conf)
     }
 
+    fn range_budget(&self) -> RangeBudget {
+        let chunk = cmp::max(1, self.args.arg_jsonrpc_range_chunk);
+        RangeBudget {
+            max_chunk_blocks: chunk,
+            max_chunk_cost: chunk,
+            max_cost: self
+                .args
+                .arg_jsonrpc_range_max_cost
+                .unwrap_or(u64::max_value()),
+            ..Default::default()
+        }
+    }
+
+    fn batch_limits(&self) -> BatchLimits {
+        BatchLimits {
+            max_size: self.args.arg_jsonrpc_max_batch_size,
+            max_cost: self.args.arg_jsonrpc_max_batch_cost,
+            parallelism: cmp::max(1, self.args.arg_jsonrpc_batch_parallelism),
+        }
+    }
+
+    fn subscription_limits(&self) -> Result<SubscriptionLimits, String> {
+        Ok(SubscriptionLimits {
+            max_queued: self.args.arg_ws_max_queued_notifications,
+            policy: self.args.arg_ws_queue_overflow.parse()?,
+        })
+    }
+
+    fn spec_update_config(&self) -> Option<SpecUpdateConfig> {
+        match self.args.flag_spec_update_check {
+            true => Some(SpecUpdateConfig {
+                check_interval: cmp::max(1, self.args.arg_spec_update_check_interval),
+                refuse_sealing: self.args.flag_spec_update_refuse_sealing,
+            }),
+            false => None,
+        }
+    }
+
+    fn rpc_bridge_config(&self) -> Option<sync::RpcBridgeConfig> {
+        self.args
+            .arg_rpc_bridge_url
+            .clone()
+            .map(|url| sync::RpcBridgeConfig {
+                url,
+                batch_size: cmp::max(1, self.args.arg_rpc_bridge_batch_size),
+                handover_distance: self.args.arg_rpc_bridge_handover_distance,
+                download_old_blocks: !self.args.flag_no_ancient_blocks,
+                ..Default::default()
+            })
+    }
+
+    fn clock_drift_config(&self) -> ClockDriftConfiguration {
+        ClockDriftConfiguration {
+            enabled: self.args.flag_clock_drift_monitor,
+            servers: self
+                .args
+                .arg_ntp_servers
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            interval: Duration::from_secs(cmp::max(1, self.args.arg_clock_drift_interval)),
+            warn_threshold: Duration::from_millis(self.args.arg_clock_drift_threshold),
+        }
+    }
+
     fn snapshot_config(&self) -> Result<SnapshotConfiguration, String> {
         let conf = SnapshotConfiguration {
             enable: self.args.flag_enable_snapshotting,
@@ -1268,7 +1599,7 @@ mod tests {
     };
     use dir::Directories;
     use ethcore::{client::VMType, miner::MinerOptions};
-    use parity_rpc::NetworkSettings;
+    use parity_rpc::{BatchLimits, NetworkSettings, RangeBudget, SubscriptionLimits};
     use tempdir::TempDir;
 
     use crate::network::{AllowIP, IpFilter};
@@ -1375,6 +1706,7 @@ mod tests {
                 dirs: Default::default(),
                 file_path: Some("blockchain.json".into()),
                 format: Default::default(),
+                resume_from: None,
                 pruning: Default::default(),
                 pruning_history: 64,
                 pruning_memory: 32,
@@ -1500,6 +1832,7 @@ mod tests {
                     support_token_api: true,
                     max_connections: 100,
                     max_payload: 5,
+                    jwt_secret: None,
                 },
                 LogConfig {
                     color: !cfg!(windows),
@@ -1541,6 +1874,7 @@ mod tests {
             miner_options: Default::default(),
             gas_price_percentile: 50,
             poll_lifetime: 60,
+            call_timeout: Some(Duration::from_millis(5000)),
             ws_conf: Default::default(),
             http_conf: Default::default(),
             ipc_conf: Default::default(),
@@ -1548,6 +1882,7 @@ mod tests {
             network_id: None,
             warp_sync: true,
             warp_barrier: None,
+            rpc_bridge: None,
             acc_conf: Default::default(),
             gas_pricer_conf: Default::default(),
             miner_extras: Default::default(),
@@ -1568,8 +1903,29 @@ mod tests {
             new_transactions_stats_period: 0,
             verifier_settings: Default::default(),
             no_persistent_txqueue: false,
+            tx_journal_encryption: false,
+            tx_journal_segment_size: 1024 * 1024,
+            tx_journal_segments: 8,
             max_round_blocks_to_import: 1,
             metrics_conf: MetricsConfiguration::default(),
+            clock_drift_conf: ClockDriftConfiguration::default(),
+            db_read_profiler: false,
+            read_only: false,
+            compress_history: false,
+            max_future_block_drift: Duration::from_secs(15),
+            max_relay_future_drift: None,
+            max_reorg_depth: None,
+            history_expiry: None,
+            tx_index_expiry: None,
+            state_regeneration_limit: None,
+            uncle_strategy: UncleStrategy::First,
+            spec_update_conf: None,
+            range_budget: RangeBudget::default(),
+            batch_limits: BatchLimits::default(),
+            subscription_limits: SubscriptionLimits::default(),
+            node_certificate_authorities: Vec::new(),
+            rpc_record: None,
+            rpc_replay: None,
         };
         expected.secretstore_conf.enabled = cfg!(feature = "secretstore");
         expected.secretstore_conf.http_enabled = cfg!(feature = "secretstore");
@@ -1664,6 +2020,33 @@ mod tests {
         assert_eq!(conf0.directories().signer, "signer".to_owned());
     }
 
+    #[test]
+    fn should_check_chain_overlay_at_configuration() {
+        let tempdir = TempDir::new("").unwrap();
+        let valid = tempdir.path().join("valid.json");
+        File::create(&valid)
+            .unwrap()
+            .write_all(br#"{ "params": { "eip1559Transition": "0x10" } }"#)
+            .unwrap();
+        let invalid = tempdir.path().join("invalid.json");
+        File::create(&invalid)
+            .unwrap()
+            .write_all(br#"{ "params": { "eip9999Transition": "0x10" } }"#)
+            .unwrap();
+
+        let conf = parse(&[
+            "openethereum",
+            "--chain",
+            "dev",
+            "--chain-overlay",
+            valid.to_str().unwrap(),
+        ]);
+        assert!(conf.is_dev_chain().unwrap());
+
+        let conf = parse(&["openethereum", "--chain-overlay", invalid.to_str().unwrap()]);
+        assert!(conf.chain().is_err());
+    }
+
     #[test]
     fn should_not_bail_on_empty_line_in_reserved_peers() {
         let tempdir = TempDir::new("").unwrap();
@@ -1697,6 +2080,21 @@ mod tests {
         assert_eq!(reserved_nodes.unwrap().len(), 1);
     }
 
+    #[test]
+    fn should_parse_node_certificate_authorities() {
+        let key = "6f8a80d14311c39f35f516fa664deaaaa13e85b2f7493f37f6144d86991ec012937307647bd3b9a82abe2974e1407241d54947bbb39763a4cac9f77166ad92a0";
+        let keys = format!("0x{}, {}", key, key);
+        let args = vec!["openethereum", "--node-certificate-authorities", &keys];
+        let conf = Configuration::parse_cli(&args).unwrap();
+        let authorities = conf.node_certificate_authorities().unwrap();
+        assert_eq!(authorities.len(), 2);
+        assert_eq!(authorities[0], key.parse().unwrap());
+
+        let args = vec!["openethereum", "--node-certificate-authorities", "0x12"];
+        let conf = Configuration::parse_cli(&args).unwrap();
+        assert!(conf.node_certificate_authorities().is_err());
+    }
+
     #[test]
     fn test_dev_preset() {
         let args = vec!["openethereum", "--config", "dev"];
@@ -1993,6 +2391,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_parse_batch_limits() {
+        let conf = parse(&["openethereum"]);
+        assert_eq!(conf.batch_limits(), BatchLimits::default());
+
+        let conf = parse(&[
+            "openethereum",
+            "--jsonrpc-max-batch-size=10",
+            "--jsonrpc-max-batch-cost=200",
+            "--jsonrpc-batch-parallelism=0",
+        ]);
+        assert_eq!(
+            conf.batch_limits(),
+            BatchLimits {
+                max_size: 10,
+                max_cost: 200,
+                parallelism: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn should_parse_rpc_bridge_config() {
+        let conf = parse(&["openethereum"]);
+        assert_eq!(conf.rpc_bridge_config(), None);
+
+        let conf = parse(&[
+            "openethereum",
+            "--rpc-bridge-url=https://rpc.example.org",
+            "--rpc-bridge-batch-size=0",
+            "--no-ancient-blocks",
+        ]);
+        assert_eq!(
+            conf.rpc_bridge_config(),
+            Some(sync::RpcBridgeConfig {
+                url: "https://rpc.example.org".into(),
+                batch_size: 1,
+                handover_distance: 64,
+                download_old_blocks: false,
+                ..Default::default()
+            })
+        );
+    }
+
     #[test]
     fn should_respect_only_max_peers_and_default() {
         let args = vec!["openethereum", "--max-peers=50"];
@@ -15,10 +15,11 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::{
-    kvdb_rocksdb::{CompactionProfile, DatabaseConfig},
+    kvdb_rocksdb::{CompactionProfile, Database, DatabaseConfig},
     migration_rocksdb::{ChangeColumns, Config as MigrationConfig, Manager as MigrationManager},
 };
 use ethcore::{self, client::DatabaseCompactionProfile};
+use ethcore_db::migration::{self as schema, Migrations};
 use std::{
     fmt::{Display, Error as FmtError, Formatter},
     fs,
@@ -70,6 +71,8 @@ pub enum Error {
     UseMigrationTool,
     /// Blooms-db migration error.
     BloomsDB(ethcore::error::Error),
+    /// Schema migration error.
+    Schema(schema::Error),
     /// Migration was completed succesfully,
     /// but there was a problem with io.
     Io(IoError),
@@ -82,6 +85,7 @@ impl Display for Error {
 			Error::FutureDBVersion => "Database was created with newer client version. Upgrade your client or delete DB and resync.".into(),
 			Error::MigrationImpossible => format!("Database migration to version {} is not possible.", CURRENT_VERSION),
             Error::BloomsDB(ref err) => format!("blooms-db migration error: {}", err),
+            Error::Schema(ref err) => format!("Schema migration error: {}", err),
             Error::UseMigrationTool => "For db versions 15 and lower (v2.5.13=>13, 2.7.2=>14, v3.0.1=>15) please use upgrade db tool to manually upgrade db: https://github.com/openethereum/3.1-db-upgrade-tool".into(),
 			Error::Io(ref err) => format!("Unexpected io error on DB migration: {}.", err),
 		};
@@ -96,6 +100,12 @@ impl From<IoError> for Error {
     }
 }
 
+impl From<schema::Error> for Error {
+    fn from(err: schema::Error) -> Self {
+        Error::Schema(err)
+    }
+}
+
 /// Returns the version file path.
 fn version_file_path(path: &Path) -> PathBuf {
     let mut file_path = path.to_owned();
@@ -120,7 +130,7 @@ fn current_version(path: &Path) -> Result<u32, Error> {
 
 /// Writes current database version to the file.
 /// Creates a new file if the version file does not exist yet.
-fn update_version(path: &Path) -> Result<(), Error> {
+pub(super) fn update_version(path: &Path) -> Result<(), Error> {
     fs::create_dir_all(path)?;
     let mut file = fs::File::create(version_file_path(path))?;
     file.write_all(format!("{}", CURRENT_VERSION).as_bytes())?;
@@ -202,6 +212,80 @@ fn migrate_database(
     fs::remove_dir_all(&backup_path).map_err(Into::into)
 }
 
+/// Schema migrations applied in place to the consolidated database.
+/// Every column change registers its forward migration here.
+pub(super) fn schema_migrations() -> Migrations {
+    Migrations::new()
+}
+
+/// Applies pending schema migrations to the consolidated database, or only
+/// lists them when `dry_run` is set.
+pub fn migrate_schema(
+    path: &Path,
+    compaction_profile: &DatabaseCompactionProfile,
+    dry_run: bool,
+) -> Result<(), Error> {
+    let compaction_profile = helpers::compaction_profile(&compaction_profile, path);
+    run_schema_migrations(path, compaction_profile, dry_run)
+}
+
+fn run_schema_migrations(
+    path: &Path,
+    compaction_profile: CompactionProfile,
+    dry_run: bool,
+) -> Result<(), Error> {
+    let db_path = consolidated_database_path(path);
+    if !exists(&db_path) {
+        return Ok(());
+    }
+
+    let db_config = DatabaseConfig {
+        max_open_files: 64,
+        memory_budget: None,
+        compaction: compaction_profile,
+        columns: ethcore_db::NUM_COLUMNS,
+    };
+    let db = Database::open(&db_config, &db_path.to_string_lossy())?;
+
+    let migrations = schema_migrations();
+    let version = schema::schema_version(&db)?;
+    let pending = migrations.pending(version)?;
+    if pending.is_empty() {
+        if dry_run {
+            println!("Database schema is up to date (version {})", version);
+        }
+        return Ok(());
+    }
+
+    println!(
+        "Database schema version {}, {} pending migrations:",
+        version,
+        pending.len()
+    );
+    for migration in &pending {
+        println!("  {}: {}", migration.version(), migration.description());
+    }
+    if dry_run {
+        return Ok(());
+    }
+
+    let version = migrations.run(&db, &mut |progress| match progress.total {
+        Some(total) if total > 0 => println!(
+            "Migrating to schema version {}: {}/{} ({}%)",
+            progress.version,
+            progress.done,
+            total,
+            progress.done * 100 / total
+        ),
+        _ => println!(
+            "Migrating to schema version {}: {}",
+            progress.version, progress.done
+        ),
+    })?;
+    println!("Database schema migrated to version {}", version);
+    Ok(())
+}
+
 fn exists(path: &Path) -> bool {
     fs::metadata(path).is_ok()
 }
@@ -219,9 +303,9 @@ pub fn migrate(path: &Path, compaction_profile: &DatabaseCompactionProfile) -> R
         return Err(Error::FutureDBVersion);
     }
 
-    // We are in the latest version, yay!
+    // We are in the latest version, yay! Only schema migrations may be left.
     if version == CURRENT_VERSION {
-        return Ok(());
+        return run_schema_migrations(path, compaction_profile, false);
     }
 
     if version != DEFAULT_VERSION && version <= USE_MIGRATION_TOOL {
@@ -258,5 +342,7 @@ pub fn migrate(path: &Path, compaction_profile: &DatabaseCompactionProfile) -> R
     }
 
     // update version file.
-    update_version(path)
+    update_version(path)?;
+
+    run_schema_migrations(path, compaction_profile, false)
 }
@@ -19,25 +19,77 @@ extern crate kvdb_rocksdb;
 extern crate migration_rocksdb;
 
 use self::{
-    ethcore_blockchain::{BlockChainDB, BlockChainDBHandler},
+    ethcore_blockchain::{BlockChainDB, BlockChainDBHandler, DatabaseBackup},
     kvdb_rocksdb::{Database, DatabaseConfig},
 };
 use blooms_db;
 use ethcore::client::ClientConfig;
-use ethcore_db::KeyValueDB;
+use ethcore_db::{DBTransaction, KeyValueDB, NUM_COLUMNS};
 use stats::PrometheusMetrics;
-use std::{fs, io, path::Path, sync::Arc};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 mod blooms;
 mod helpers;
 mod migration;
 
-pub use self::migration::migrate;
+pub use self::migration::{migrate, migrate_schema};
+
+/// Defines how many items are copied into a backup at once.
+const BACKUP_BATCH_SIZE: usize = 1024;
+/// Prefix of the directories next to the client database holding the copy a read-only
+/// client opens, suffixed with the process id and a counter.
+const READ_ONLY_SNAPSHOT_DIR: &str = "read_only_snapshot";
+/// Prefix of the directories next to the client database holding the copy a backup is
+/// written from, suffixed with the process id and a counter.
+const BACKUP_SNAPSHOT_DIR: &str = "backup_snapshot";
+/// Number of database snapshots taken by this process.
+static SNAPSHOTS: AtomicUsize = AtomicUsize::new(0);
+/// How many times taking a snapshot of a database in use is attempted.
+const SNAPSHOT_ATTEMPTS: usize = 3;
 
 struct AppDB {
     key_value: Arc<dyn KeyValueDB>,
     blooms: blooms_db::Database,
     trace_blooms: blooms_db::Database,
+    config: DatabaseConfig,
+    path: PathBuf,
+    // declared last, so that the database is closed before its directory is removed
+    _snapshot_dir: Option<SnapshotDir>,
+}
+
+/// Directory of a database snapshot, removed on drop.
+struct SnapshotDir(PathBuf);
+
+impl SnapshotDir {
+    /// A fresh directory named after `prefix` next to the database at `path`.
+    fn next_to(path: &Path, prefix: &str) -> Self {
+        SnapshotDir(path.with_file_name(format!(
+            "{}-{}-{}",
+            prefix,
+            process::id(),
+            SNAPSHOTS.fetch_add(1, Ordering::Relaxed)
+        )))
+    }
+}
+
+impl Drop for SnapshotDir {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.0) {
+            warn!(
+                "Failed to remove database snapshot {}: {}",
+                self.0.display(),
+                e
+            );
+        }
+    }
 }
 
 impl BlockChainDB for AppDB {
@@ -52,6 +104,68 @@ impl BlockChainDB for AppDB {
     fn trace_blooms(&self) -> &blooms_db::Database {
         &self.trace_blooms
     }
+
+    /// Snapshots the database next to it, which is cheap as the table files are hard linked.
+    /// The blooms are rewritten in place, so they're copied into the backup right away.
+    fn backup(&self, path: &Path) -> io::Result<Box<dyn DatabaseBackup>> {
+        if fs::read_dir(path).map_or(false, |mut entries| entries.next().is_some()) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Backup directory {} is not empty", path.display()),
+            ));
+        }
+
+        let snapshot_dir = SnapshotDir::next_to(&self.path, BACKUP_SNAPSHOT_DIR);
+        snapshot_database(&self.path, &snapshot_dir.0)?;
+        let client_path = path.join("db");
+        self.blooms.copy_to(client_path.join("blooms"))?;
+        self.trace_blooms
+            .copy_to(client_path.join("trace_blooms"))?;
+
+        Ok(Box::new(AppDBBackup {
+            snapshot_dir,
+            path: path.to_owned(),
+            config: self.config.clone(),
+        }))
+    }
+}
+
+/// Snapshot of an `AppDB` to be copied into a backup.
+struct AppDBBackup {
+    snapshot_dir: SnapshotDir,
+    path: PathBuf,
+    config: DatabaseConfig,
+}
+
+impl DatabaseBackup for AppDBBackup {
+    /// Copies every column into a fresh database laid out like a pruning directory,
+    /// i.e. `path/db` next to a `path/db_version` file.
+    fn write(self: Box<Self>, progress: &dyn Fn(u64)) -> io::Result<u64> {
+        let snapshot = Database::open(&self.config, &self.snapshot_dir.0.to_string_lossy())?;
+        let client_path = self.path.join("db");
+        fs::create_dir_all(&client_path)?;
+        let backup = Database::open(&self.config, &client_path.to_string_lossy())?;
+        let mut copied = 0u64;
+        for col in 0..NUM_COLUMNS.unwrap_or(0) {
+            let mut batch = DBTransaction::new();
+            for (key, value) in snapshot.iter(Some(col)) {
+                batch.put(Some(col), &key, &value);
+                copied += 1;
+                if copied % BACKUP_BATCH_SIZE as u64 == 0 {
+                    backup.write(batch)?;
+                    batch = DBTransaction::new();
+                    progress(copied);
+                }
+            }
+            backup.write(batch)?;
+        }
+        backup.flush()?;
+        progress(copied);
+
+        migration::update_version(&self.path)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        Ok(copied)
+    }
 }
 
 impl PrometheusMetrics for AppDB {
@@ -95,11 +209,128 @@ pub fn restoration_db_handler(
     })
 }
 
+/// Open the database at `client_path` without writing to it, even while a running node holds it.
+///
+/// RocksDB only lets one process open a database, so the client opens a snapshot of it
+/// taken next to it. The snapshot shares the table files with the original and reflects
+/// the data at the time it is opened. Blooms are read from the original files.
+pub fn open_read_only_database(
+    client_path: &Path,
+    client_config: &ClientConfig,
+) -> io::Result<Arc<dyn BlockChainDB>> {
+    let config = helpers::client_db_config(client_path, client_config);
+    let blooms_path = client_path.join("blooms");
+    let trace_blooms_path = client_path.join("trace_blooms");
+    if !blooms_path.is_dir() || !trace_blooms_path.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "A read-only client requires an existing database at {}",
+                client_path.display()
+            ),
+        ));
+    }
+
+    // every read-only client takes its own snapshot, several may run at once
+    let snapshot_dir = SnapshotDir::next_to(client_path, READ_ONLY_SNAPSHOT_DIR);
+    snapshot_database(client_path, &snapshot_dir.0)?;
+    let db = Database::open(&config, &snapshot_dir.0.to_string_lossy())?;
+
+    let version = ethcore_db::migration::schema_version(&db)?;
+    let pending = migration::schema_migrations()
+        .pending(version)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    if !pending.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Database schema version {} needs migrating, which a read-only client can't do",
+                version
+            ),
+        ));
+    }
+
+    let db_with_metrics = ethcore_db::DatabaseWithMetrics::new(db);
+    let db = AppDB {
+        key_value: Arc::new(ethcore_db::ReadOnlyDatabase::new(Arc::new(db_with_metrics))),
+        blooms: blooms_db::Database::open(blooms_path)?,
+        trace_blooms: blooms_db::Database::open(trace_blooms_path)?,
+        config,
+        path: snapshot_dir.0.clone(),
+        _snapshot_dir: Some(snapshot_dir),
+    };
+
+    Ok(Arc::new(db))
+}
+
+/// Lay out a copy of the RocksDB directory `source` in `target` that can be opened while
+/// another process holds `source`. Table files are immutable and hard linked, everything
+/// else RocksDB needs is copied.
+fn snapshot_database(source: &Path, target: &Path) -> io::Result<()> {
+    let mut attempt = 1;
+    loop {
+        match try_snapshot_database(source, target) {
+            // compaction or a flush removed a file while it was being copied
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound && attempt < SNAPSHOT_ATTEMPTS => {
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn try_snapshot_database(source: &Path, target: &Path) -> io::Result<()> {
+    if target.exists() {
+        fs::remove_dir_all(target)?;
+    }
+    fs::create_dir_all(target)?;
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        if let Some(rank) = entry.file_name().to_str().and_then(snapshot_rank) {
+            files.push((rank, entry.file_name()));
+        }
+    }
+    // the manifest goes first so that it can't reference a table file the listing missed
+    files.sort();
+
+    for (rank, name) in files {
+        let (from, to) = (source.join(&name), target.join(&name));
+        if rank == SnapshotRank::Table && fs::hard_link(&from, &to).is_ok() {
+            continue;
+        }
+        fs::copy(&from, &to)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SnapshotRank {
+    Manifest,
+    Table,
+    Log,
+}
+
+/// Whether and in which order a file of a RocksDB directory is snapshot.
+fn snapshot_rank(name: &str) -> Option<SnapshotRank> {
+    if name == "LOCK" || name.starts_with("LOG") || name == "blooms" || name == "trace_blooms" {
+        None
+    } else if name.ends_with(".sst") {
+        Some(SnapshotRank::Table)
+    } else if name.ends_with(".log") {
+        Some(SnapshotRank::Log)
+    } else {
+        Some(SnapshotRank::Manifest)
+    }
+}
+
 pub fn open_database(
     client_path: &str,
     config: &DatabaseConfig,
 ) -> io::Result<Arc<dyn BlockChainDB>> {
     let path = Path::new(client_path);
+    let is_new = fs::metadata(path).is_err();
 
     let blooms_path = path.join("blooms");
     let trace_blooms_path = path.join("trace_blooms");
@@ -107,13 +338,154 @@ pub fn open_database(
     fs::create_dir_all(&trace_blooms_path)?;
 
     let db = Database::open(&config, client_path)?;
+    if is_new {
+        let version = migration::schema_migrations().latest_version();
+        ethcore_db::migration::init_schema_version(&db, version)?;
+    }
     let db_with_metrics = ethcore_db::DatabaseWithMetrics::new(db);
 
     let db = AppDB {
         key_value: Arc::new(db_with_metrics),
         blooms: blooms_db::Database::open(blooms_path)?,
         trace_blooms: blooms_db::Database::open(trace_blooms_path)?,
+        config: config.clone(),
+        path: path.to_owned(),
+        _snapshot_dir: None,
     };
 
     Ok(Arc::new(db))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn opens_read_only_database_held_by_writer() {
+        let tempdir = TempDir::new("").unwrap();
+        let client_path = tempdir.path().join("db");
+        let client_config = ClientConfig::default();
+        let config = helpers::client_db_config(&client_path, &client_config);
+        let writer = open_database(&client_path.to_string_lossy(), &config).unwrap();
+        let mut batch = DBTransaction::new();
+        batch.put(ethcore_db::COL_EXTRA, b"key", b"value");
+        writer.key_value().write(batch).unwrap();
+
+        // the writer holds the database
+        assert!(Database::open(&config, &client_path.to_string_lossy()).is_err());
+
+        let reader = open_read_only_database(&client_path, &client_config).unwrap();
+        assert_eq!(
+            reader
+                .key_value()
+                .get(ethcore_db::COL_EXTRA, b"key")
+                .unwrap()
+                .as_deref(),
+            Some(&b"value"[..])
+        );
+        let mut batch = DBTransaction::new();
+        batch.put(ethcore_db::COL_EXTRA, b"key", b"other");
+        assert!(reader.key_value().write(batch).is_err());
+        let mut batch = DBTransaction::new();
+        batch.delete(ethcore_db::COL_EXTRA, b"key");
+        reader.key_value().write_buffered(batch);
+        assert!(reader.key_value().flush().is_err());
+
+        let mut batch = DBTransaction::new();
+        batch.put(ethcore_db::COL_EXTRA, b"key", b"other");
+        writer.key_value().write(batch).unwrap();
+        assert_eq!(
+            writer
+                .key_value()
+                .get(ethcore_db::COL_EXTRA, b"key")
+                .unwrap()
+                .as_deref(),
+            Some(&b"other"[..])
+        );
+    }
+
+    #[test]
+    fn opens_several_read_only_databases_at_once() {
+        let tempdir = TempDir::new("").unwrap();
+        let client_path = tempdir.path().join("db");
+        let client_config = ClientConfig::default();
+        let config = helpers::client_db_config(&client_path, &client_config);
+        let writer = open_database(&client_path.to_string_lossy(), &config).unwrap();
+        let mut batch = DBTransaction::new();
+        batch.put(ethcore_db::COL_EXTRA, b"key", b"value");
+        writer.key_value().write(batch).unwrap();
+
+        let first = open_read_only_database(&client_path, &client_config).unwrap();
+        let second = open_read_only_database(&client_path, &client_config).unwrap();
+        drop(first);
+        assert_eq!(
+            second
+                .key_value()
+                .get(ethcore_db::COL_EXTRA, b"key")
+                .unwrap()
+                .as_deref(),
+            Some(&b"value"[..])
+        );
+
+        // the snapshots are removed once closed
+        drop(second);
+        let snapshots = fs::read_dir(tempdir.path())
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(READ_ONLY_SNAPSHOT_DIR)
+            })
+            .count();
+        assert_eq!(snapshots, 0);
+    }
+
+    #[test]
+    fn writes_backup_of_the_snapshot() {
+        let tempdir = TempDir::new("").unwrap();
+        let client_path = tempdir.path().join("db");
+        let backup_path = tempdir.path().join("backup");
+        let config = helpers::client_db_config(&client_path, &ClientConfig::default());
+        let db = open_database(&client_path.to_string_lossy(), &config).unwrap();
+        let mut batch = DBTransaction::new();
+        batch.put(ethcore_db::COL_EXTRA, b"key", b"value");
+        db.key_value().write(batch).unwrap();
+        db.key_value().flush().unwrap();
+
+        let backup = db.backup(&backup_path).unwrap();
+        // writes after the snapshot don't make it into the backup
+        let mut batch = DBTransaction::new();
+        batch.put(ethcore_db::COL_EXTRA, b"key", b"other");
+        batch.put(ethcore_db::COL_EXTRA, b"later", b"value");
+        db.key_value().write(batch).unwrap();
+        assert!(backup.write(&|_| {}).unwrap() > 0);
+
+        let restored = Database::open(&config, &backup_path.join("db").to_string_lossy()).unwrap();
+        assert_eq!(
+            restored
+                .get(ethcore_db::COL_EXTRA, b"key")
+                .unwrap()
+                .as_deref(),
+            Some(&b"value"[..])
+        );
+        assert_eq!(restored.get(ethcore_db::COL_EXTRA, b"later").unwrap(), None);
+        assert!(backup_path.join("db").join("blooms").is_dir());
+        assert!(backup_path.join("db_version").is_file());
+
+        // the snapshot is removed once written
+        let snapshots = fs::read_dir(tempdir.path())
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(BACKUP_SNAPSHOT_DIR)
+            })
+            .count();
+        assert_eq!(snapshots, 0);
+    }
+}
@@ -19,7 +19,7 @@ use crate::{
     db::migrate,
     miner::pool::PrioritizationStrategy,
     sync::{self, validate_node_url},
-    upgrade::{upgrade, upgrade_data_paths},
+    upgrade::upgrade_data_paths,
 };
 use dir::{helpers::replace_home, DatabaseDirectories};
 use ethcore::{
@@ -246,6 +246,7 @@ pub fn default_network_config() -> crate::sync::NetworkConfiguration {
         reserved_nodes: Vec::new(),
         allow_non_reserved: true,
         client_version: ::parity_version::version(),
+        node_certificate: None,
     }
 }
 
@@ -312,16 +313,6 @@ pub fn execute_upgrades(
 ) -> Result<(), String> {
     upgrade_data_paths(base_path, dirs, pruning);
 
-    match upgrade(&dirs.path) {
-        Ok(upgrades_applied) if upgrades_applied > 0 => {
-            debug!("Executed {} upgrade scripts - ok", upgrades_applied);
-        }
-        Err(e) => {
-            return Err(format!("Error upgrading OpenEthereum data: {:?}", e));
-        }
-        _ => {}
-    }
-
     let client_path = dirs.db_path(pruning);
     migrate(&client_path, compaction_profile).map_err(|e| format!("{}", e))
 }
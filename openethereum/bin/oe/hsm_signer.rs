@@ -0,0 +1,504 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Engine signer keeping the validator key on a PKCS#11 token (HSM).
+//!
+//! Tokens are slow and their sessions must not be shared between threads, so every
+//! signing request is handed to a dedicated thread owning the session.
+
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
+use crypto::publickey::{self, public_to_address, Public, Signature};
+use ethcore::engines::EngineSigner;
+use ethereum_types::{Address, BigEndianHash, H256, U256};
+use std::{
+    cmp,
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Order of the secp256k1 group.
+const SECP256K1_N: U256 = U256([
+    0xBFD25E8CD0364141,
+    0xBAAEDCE6AF48A03B,
+    0xFFFFFFFFFFFFFFFE,
+    0xFFFFFFFFFFFFFFFF,
+]);
+
+/// Location of the engine signing key on a PKCS#11 token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pkcs11Config {
+    /// Path to the PKCS#11 module of the token vendor.
+    pub module: PathBuf,
+    /// Slot holding the token.
+    pub slot: u64,
+    /// `CKA_LABEL` of the secp256k1 key pair.
+    pub key_label: String,
+    /// How long to wait for the token to produce a signature.
+    pub timeout: Duration,
+}
+
+/// A secp256k1 key pair stored on a hardware token.
+pub trait SigningToken {
+    /// Public key of the signing key.
+    fn public(&self) -> Public;
+
+    /// Plain ECDSA signature (`r || s`) over a 32 byte digest.
+    fn sign_digest(&mut self, digest: &H256) -> Result<Vec<u8>, String>;
+}
+
+struct SignRequest {
+    hash: H256,
+    /// The requester stops waiting at this point, so the token must not sign afterwards.
+    deadline: Instant,
+    reply: Sender<Result<Signature, publickey::Error>>,
+}
+
+/// `EngineSigner` forwarding requests to a token through a signing queue.
+pub struct HsmSigner {
+    public: Public,
+    address: Address,
+    requests: Sender<SignRequest>,
+    timeout: Duration,
+}
+
+impl HsmSigner {
+    /// Open the token on a new signing thread. Requests are served in order until the
+    /// signer is dropped.
+    pub fn spawn<T, F>(open: F, timeout: Duration) -> Result<Self, String>
+    where
+        T: SigningToken,
+        F: FnOnce() -> Result<T, String> + Send + 'static,
+    {
+        let (requests, queue) = unbounded();
+        let (opened_tx, opened) = bounded(1);
+
+        thread::Builder::new()
+            .name("hsm-signer".into())
+            .spawn(move || match open() {
+                Ok(token) => {
+                    let _ = opened_tx.send(Ok(token.public()));
+                    serve_requests(token, queue);
+                }
+                Err(e) => {
+                    let _ = opened_tx.send(Err(e));
+                }
+            })
+            .map_err(|e| format!("Unable to start HSM signing thread: {}", e))?;
+
+        let public = opened
+            .recv()
+            .map_err(|_| "HSM signing thread exited unexpectedly".to_owned())??;
+
+        Ok(HsmSigner {
+            public,
+            address: public_to_address(&public),
+            requests,
+            timeout,
+        })
+    }
+}
+
+fn serve_requests<T: SigningToken>(mut token: T, queue: Receiver<SignRequest>) {
+    let public = token.public();
+    for request in queue.iter() {
+        if Instant::now() >= request.deadline {
+            debug!(target: "engine", "Dropping expired HSM request for {}", request.hash);
+            continue;
+        }
+        let result = token
+            .sign_digest(&request.hash)
+            .map_err(|e| {
+                warn!(target: "engine", "HSM failed to sign {}: {}", request.hash, e);
+                publickey::Error::Custom(e)
+            })
+            .and_then(|raw| to_recoverable(&raw, &request.hash, &public));
+        // the requester may have given up waiting
+        let _ = request.reply.send(result);
+    }
+}
+
+/// Turn a plain ECDSA signature into a low-s recoverable one.
+fn to_recoverable(raw: &[u8], hash: &H256, public: &Public) -> Result<Signature, publickey::Error> {
+    if raw.len() != 64 {
+        return Err(publickey::Error::InvalidSignature);
+    }
+
+    let r = H256::from_slice(&raw[..32]);
+    let mut s = U256::from_big_endian(&raw[32..]);
+    // tokens are free to return either of the two valid `s` values
+    if s > SECP256K1_N >> 1 {
+        s = SECP256K1_N - s;
+    }
+    let s = H256::from_uint(&s);
+
+    (0..2)
+        .map(|v| Signature::from_rsv(&r, &s, v))
+        .find(|signature| publickey::recover(signature, hash).ok().as_ref() == Some(public))
+        .ok_or(publickey::Error::InvalidSignature)
+}
+
+impl HsmSigner {
+    fn request(&self, hash: H256, deadline: Instant) -> Result<Signature, publickey::Error> {
+        let (reply, response) = bounded(1);
+        self.requests
+            .send(SignRequest {
+                hash,
+                deadline,
+                reply,
+            })
+            .map_err(|_| publickey::Error::Custom("HSM signing thread has stopped".into()))?;
+
+        response.recv_deadline(deadline).unwrap_or_else(|_| {
+            warn!(target: "engine", "HSM did not sign {} in time", hash);
+            Err(publickey::Error::Custom("HSM signing timed out".into()))
+        })
+    }
+}
+
+impl EngineSigner for HsmSigner {
+    fn sign(&self, hash: H256) -> Result<Signature, publickey::Error> {
+        self.request(hash, Instant::now() + self.timeout)
+    }
+
+    // the token skips the request if it is still queued at the deadline
+    fn sign_before(&self, hash: H256, deadline: Instant) -> Result<Signature, publickey::Error> {
+        self.request(hash, cmp::min(deadline, Instant::now() + self.timeout))
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn decrypt(&self, _auth_data: &[u8], _cipher: &[u8]) -> Result<Vec<u8>, publickey::Error> {
+        Err(publickey::Error::Custom(
+            "Decryption is not supported for HSM keys".into(),
+        ))
+    }
+
+    fn public(&self) -> Option<Public> {
+        Some(self.public)
+    }
+
+    fn is_slow(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(not(feature = "pkcs11"))]
+mod token {
+    use super::{HsmSigner, Pkcs11Config};
+
+    /// Open the configured PKCS#11 token.
+    pub fn open_pkcs11(_config: Pkcs11Config, _pin: String) -> Result<HsmSigner, String> {
+        Err("PKCS#11 support is not compiled in. Rebuild with the `pkcs11` feature.".into())
+    }
+}
+
+#[cfg(feature = "pkcs11")]
+mod token {
+    use super::{HsmSigner, Pkcs11Config, SigningToken};
+    use crypto::publickey::Public;
+    use ethereum_types::H256;
+    use pkcs11::{types::*, Ctx};
+    use std::ptr;
+
+    struct Pkcs11Token {
+        ctx: Ctx,
+        session: CK_SESSION_HANDLE,
+        key: CK_OBJECT_HANDLE,
+        public: Public,
+    }
+
+    impl Pkcs11Token {
+        fn open(config: &Pkcs11Config, pin: &str) -> Result<Self, String> {
+            let ctx = Ctx::new_and_initialize(&config.module).map_err(|e| {
+                format!(
+                    "Unable to load PKCS#11 module {}: {}",
+                    config.module.display(),
+                    e
+                )
+            })?;
+            let session = ctx
+                .open_session(config.slot, CKF_SERIAL_SESSION, None, None)
+                .map_err(|e| format!("Unable to open PKCS#11 slot {}: {}", config.slot, e))?;
+            ctx.login(session, CKU_USER, Some(pin))
+                .map_err(|e| format!("PKCS#11 login failed: {}", e))?;
+
+            let key = find_object(&ctx, session, CKO_PRIVATE_KEY, &config.key_label)?;
+            let public_key = find_object(&ctx, session, CKO_PUBLIC_KEY, &config.key_label)?;
+            let ec_point = attribute(&ctx, session, public_key, CKA_EC_POINT)?;
+
+            Ok(Pkcs11Token {
+                ctx,
+                session,
+                key,
+                public: parse_ec_point(&ec_point)?,
+            })
+        }
+    }
+
+    impl SigningToken for Pkcs11Token {
+        fn public(&self) -> Public {
+            self.public
+        }
+
+        fn sign_digest(&mut self, digest: &H256) -> Result<Vec<u8>, String> {
+            let mechanism = CK_MECHANISM {
+                mechanism: CKM_ECDSA,
+                pParameter: ptr::null_mut(),
+                ulParameterLen: 0,
+            };
+            self.ctx
+                .sign_init(self.session, &mechanism, self.key)
+                .and_then(|_| self.ctx.sign(self.session, digest.as_bytes()))
+                .map_err(|e| e.to_string())
+        }
+    }
+
+    impl Drop for Pkcs11Token {
+        fn drop(&mut self) {
+            let _ = self.ctx.logout(self.session);
+            let _ = self.ctx.close_session(self.session);
+        }
+    }
+
+    fn find_object(
+        ctx: &Ctx,
+        session: CK_SESSION_HANDLE,
+        class: CK_OBJECT_CLASS,
+        label: &String,
+    ) -> Result<CK_OBJECT_HANDLE, String> {
+        let template = vec![
+            CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&class),
+            CK_ATTRIBUTE::new(CKA_LABEL).with_string(label),
+        ];
+        ctx.find_objects_init(session, &template)
+            .map_err(|e| e.to_string())?;
+        let found = ctx.find_objects(session, 1);
+        let _ = ctx.find_objects_final(session);
+
+        found
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("No PKCS#11 key labelled {:?} found", label))
+    }
+
+    fn attribute(
+        ctx: &Ctx,
+        session: CK_SESSION_HANDLE,
+        object: CK_OBJECT_HANDLE,
+        attribute: CK_ATTRIBUTE_TYPE,
+    ) -> Result<Vec<u8>, String> {
+        // the first call reports the length, the second one fills the buffer
+        let mut template = vec![CK_ATTRIBUTE::new(attribute)];
+        ctx.get_attribute_value(session, object, &mut template)
+            .map_err(|e| e.to_string())?;
+        let value = vec![0u8; template[0].ulValueLen as usize];
+        let mut template = vec![CK_ATTRIBUTE::new(attribute).with_bytes(&value)];
+        ctx.get_attribute_value(session, object, &mut template)
+            .map_err(|e| e.to_string())?;
+        Ok(value)
+    }
+
+    /// Extract the public key from a `CKA_EC_POINT`, which is an uncompressed point
+    /// optionally wrapped in a DER octet string.
+    fn parse_ec_point(ec_point: &[u8]) -> Result<Public, String> {
+        let point = if ec_point.len() == 67 && ec_point[0] == 0x04 && ec_point[1] == 0x41 {
+            &ec_point[2..]
+        } else {
+            ec_point
+        };
+
+        if point.len() != 65 || point[0] != 0x04 {
+            return Err("PKCS#11 key is not an uncompressed secp256k1 point".into());
+        }
+        Ok(Public::from_slice(&point[1..]))
+    }
+
+    /// Open the configured PKCS#11 token.
+    pub fn open_pkcs11(config: Pkcs11Config, pin: String) -> Result<HsmSigner, String> {
+        let timeout = config.timeout;
+        HsmSigner::spawn(move || Pkcs11Token::open(&config, &pin), timeout)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::parse_ec_point;
+
+        #[test]
+        fn parses_raw_and_der_wrapped_points() {
+            let mut raw = vec![0x04];
+            raw.extend((1..=64).collect::<Vec<u8>>());
+            let mut wrapped = vec![0x04, 0x41];
+            wrapped.extend(&raw);
+
+            let public = parse_ec_point(&raw).unwrap();
+            assert_eq!(public.as_bytes(), &raw[1..]);
+            assert_eq!(parse_ec_point(&wrapped).unwrap(), public);
+            assert!(parse_ec_point(&raw[1..]).is_err());
+        }
+    }
+}
+
+pub use self::token::open_pkcs11;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::publickey::{Generator, KeyPair, Random};
+    use std::sync::Arc;
+
+    struct SoftwareToken(KeyPair);
+
+    impl SigningToken for SoftwareToken {
+        fn public(&self) -> Public {
+            *self.0.public()
+        }
+
+        fn sign_digest(&mut self, digest: &H256) -> Result<Vec<u8>, String> {
+            let signature = publickey::sign(self.0.secret(), digest).map_err(|e| e.to_string())?;
+            let mut raw = signature.r().to_vec();
+            raw.extend_from_slice(signature.s());
+            Ok(raw)
+        }
+    }
+
+    #[test]
+    fn signs_through_queue() {
+        let keypair = Random.generate();
+        let expected = keypair.clone();
+        let signer =
+            HsmSigner::spawn(move || Ok(SoftwareToken(keypair)), Duration::from_secs(5)).unwrap();
+        assert_eq!(signer.address(), expected.address());
+
+        for i in 0..3 {
+            let hash = H256::from_low_u64_be(i);
+            let signature = signer.sign(hash).unwrap();
+            assert_eq!(
+                publickey::recover(&signature, &hash).unwrap(),
+                *expected.public()
+            );
+        }
+    }
+
+    #[test]
+    fn normalizes_high_s_signatures() {
+        let keypair = Random.generate();
+        let hash = H256::from_low_u64_be(42);
+        let signature = publickey::sign(keypair.secret(), &hash).unwrap();
+
+        let high_s = SECP256K1_N - U256::from_big_endian(signature.s());
+        let mut raw = signature.r().to_vec();
+        raw.extend_from_slice(H256::from_uint(&high_s).as_bytes());
+
+        let normalized = to_recoverable(&raw, &hash, keypair.public()).unwrap();
+        assert!(normalized.is_low_s());
+        assert_eq!(normalized, signature);
+        assert!(to_recoverable(&raw[1..], &hash, keypair.public()).is_err());
+    }
+
+    struct SlowToken {
+        inner: SoftwareToken,
+        delay: Duration,
+        signed: Sender<H256>,
+    }
+
+    impl SigningToken for SlowToken {
+        fn public(&self) -> Public {
+            self.inner.public()
+        }
+
+        fn sign_digest(&mut self, digest: &H256) -> Result<Vec<u8>, String> {
+            thread::sleep(self.delay);
+            let _ = self.signed.send(*digest);
+            self.inner.sign_digest(digest)
+        }
+    }
+
+    #[test]
+    fn drops_expired_requests() {
+        let (signed_tx, signed) = unbounded();
+        let token = SlowToken {
+            inner: SoftwareToken(Random.generate()),
+            delay: Duration::from_millis(200),
+            signed: signed_tx,
+        };
+        let signer =
+            Arc::new(HsmSigner::spawn(move || Ok(token), Duration::from_millis(100)).unwrap());
+
+        // the first request occupies the token past its own timeout, the second one
+        // expires while still queued
+        let first = {
+            let signer = signer.clone();
+            thread::spawn(move || signer.sign(H256::from_low_u64_be(1)))
+        };
+        thread::sleep(Duration::from_millis(20));
+        assert!(signer.sign(H256::from_low_u64_be(2)).is_err());
+        assert!(first.join().unwrap().is_err());
+
+        // a fresh request is served once the token is free again
+        let mut signer = Arc::try_unwrap(signer).ok().unwrap();
+        signer.timeout = Duration::from_secs(5);
+        assert!(signer.sign(H256::from_low_u64_be(3)).is_ok());
+
+        let signed: Vec<_> = signed.try_iter().collect();
+        assert_eq!(
+            signed,
+            vec![H256::from_low_u64_be(1), H256::from_low_u64_be(3)]
+        );
+    }
+
+    #[test]
+    fn drops_requests_past_their_deadline() {
+        let (signed_tx, signed) = unbounded();
+        let token = SlowToken {
+            inner: SoftwareToken(Random.generate()),
+            delay: Duration::from_millis(100),
+            signed: signed_tx,
+        };
+        let signer = Arc::new(HsmSigner::spawn(move || Ok(token), Duration::from_secs(5)).unwrap());
+
+        // the first request keeps the token busy past the deadline of the second one
+        let first = {
+            let signer = signer.clone();
+            thread::spawn(move || signer.sign(H256::from_low_u64_be(1)))
+        };
+        thread::sleep(Duration::from_millis(20));
+        let deadline = Instant::now() + Duration::from_millis(30);
+        assert!(signer
+            .sign_before(H256::from_low_u64_be(2), deadline)
+            .is_err());
+        assert!(first.join().unwrap().is_ok());
+        assert!(signer.sign(H256::from_low_u64_be(3)).is_ok());
+
+        let signed: Vec<_> = signed.try_iter().collect();
+        assert_eq!(
+            signed,
+            vec![H256::from_low_u64_be(1), H256::from_low_u64_be(3)]
+        );
+    }
+
+    #[test]
+    fn reports_token_open_failure() {
+        let result = HsmSigner::spawn(
+            || Err::<SoftwareToken, _>("no token".into()),
+            Duration::from_secs(1),
+        );
+        assert_eq!(result.err(), Some("no token".into()));
+    }
+}
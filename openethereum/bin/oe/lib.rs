@@ -22,6 +22,7 @@ extern crate docopt;
 #[macro_use]
 extern crate clap;
 extern crate atty;
+extern crate crossbeam_channel;
 extern crate dir;
 extern crate futures;
 extern crate jsonrpc_core;
@@ -32,7 +33,6 @@ extern crate regex;
 extern crate rlp;
 extern crate rpassword;
 extern crate rustc_hex;
-extern crate semver;
 extern crate serde;
 extern crate serde_json;
 #[macro_use]
@@ -81,6 +81,9 @@ extern crate ethcore_accounts as accounts;
 #[cfg(feature = "secretstore")]
 extern crate ethcore_secretstore;
 
+#[cfg(feature = "pkcs11")]
+extern crate pkcs11;
+
 #[cfg(test)]
 #[macro_use]
 extern crate pretty_assertions;
@@ -97,9 +100,11 @@ mod account_utils;
 mod blockchain;
 mod cache;
 mod cli;
+mod clock_drift;
 mod configuration;
 mod db;
 mod helpers;
+mod hsm_signer;
 mod informant;
 mod metrics;
 mod modules;
@@ -125,7 +130,10 @@ use crate::{
 #[cfg(feature = "memory_profiling")]
 use std::alloc::System;
 
-pub use self::{configuration::Configuration, run::RunningClient};
+pub use self::{
+    configuration::Configuration,
+    run::{ConfigReloader, RunningClient},
+};
 pub use ethcore_logger::{setup_log, Config as LoggerConfig, RotatingLogger};
 pub use parity_rpc::PubSubSession;
 
@@ -30,6 +30,8 @@ extern crate parity_daemonize;
 extern crate parking_lot;
 
 extern crate ethcore_logger;
+#[cfg(unix)]
+extern crate signal_hook;
 #[cfg(windows)]
 extern crate winapi;
 
@@ -46,7 +48,7 @@ use ansi_term::Colour;
 use ctrlc::CtrlC;
 use ethcore_logger::setup_log;
 use fdlimit::raise_fd_limit;
-use openethereum::{start, ExecutionAction};
+use openethereum::{start, ConfigReloader, ExecutionAction};
 use parity_daemonize::AsHandle;
 use parking_lot::{Condvar, Mutex};
 
@@ -59,6 +61,41 @@ struct ExitStatus {
     should_exit: bool,
 }
 
+/// Re-read the command line and config file whenever the process receives SIGHUP.
+#[cfg(unix)]
+fn reload_on_sighup(reloader: ConfigReloader) {
+    use signal_hook::{consts::SIGHUP, iterator::Signals};
+
+    let mut signals = match Signals::new(&[SIGHUP]) {
+        Ok(signals) => signals,
+        Err(e) => {
+            warn!(
+                "Unable to listen for SIGHUP, configuration reloading is disabled: {}",
+                e
+            );
+            return;
+        }
+    };
+    let spawned = std::thread::Builder::new()
+        .name("sighup".into())
+        .spawn(move || {
+            for _ in signals.forever() {
+                info!("SIGHUP received, reloading the configuration");
+                let args = std::env::args().collect::<Vec<_>>();
+                match openethereum::Configuration::parse_cli(&args) {
+                    Ok(conf) => reloader.apply(&conf),
+                    Err(e) => warn!("Unable to reload the configuration: {:?}", e),
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        warn!("Unable to start the SIGHUP handler thread: {}", e);
+    }
+}
+
+#[cfg(not(unix))]
+fn reload_on_sighup(_reloader: ConfigReloader) {}
+
 fn main() -> Result<(), i32> {
     let conf = {
         let args = std::env::args().collect::<Vec<_>>();
@@ -146,6 +183,8 @@ fn main() -> Result<(), i32> {
                     }
                 });
 
+                reload_on_sighup(client.config_reloader());
+
                 // so the client has started successfully
                 // if this is a daemon, detach from the parent process
                 if let Some(mut handle) = handle {
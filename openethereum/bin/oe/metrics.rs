@@ -52,6 +52,13 @@ fn handle_request(
             let state = state.lock();
             state.rpc_apis.client.prometheus_metrics(&mut reg);
             state.rpc_apis.sync.prometheus_metrics(&mut reg);
+            state
+                .rpc_apis
+                .subscription_metrics
+                .prometheus_metrics(&mut reg);
+            if let Some(ref clock_drift) = state.rpc_apis.clock_drift {
+                clock_drift.prometheus_metrics(&mut reg);
+            }
             let elapsed = start.elapsed();
             reg.register_gauge(
                 "metrics_time",
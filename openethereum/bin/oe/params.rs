@@ -17,6 +17,7 @@
 use std::{collections::HashSet, fmt, fs, num::NonZeroU32, str, time::Duration};
 
 use crate::{
+    hsm_signer::Pkcs11Config,
     miner::{
         gas_price_calibrator::{GasPriceCalibrator, GasPriceCalibratorOptions},
         gas_pricer::GasPricer,
@@ -26,7 +27,7 @@ use crate::{
 use ethcore::{
     client::Mode,
     ethereum,
-    spec::{Spec, SpecParams},
+    spec::{Spec, SpecOverlay, SpecParams},
 };
 use ethereum_types::{Address, U256};
 use fetch::Client as FetchClient;
@@ -56,6 +57,8 @@ pub enum SpecType {
     Yolo3,
     Dev,
     Custom(String),
+    /// A chain spec with the fork transitions of the given overlay file applied.
+    Overlay(Box<SpecType>, String),
 }
 
 impl Default for SpecType {
@@ -95,6 +98,7 @@ impl str::FromStr for SpecType {
 impl fmt::Display for SpecType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(match *self {
+            SpecType::Overlay(ref base, _) => return base.fmt(f),
             SpecType::Foundation => "foundation",
             SpecType::Poanet => "poanet",
             SpecType::Xdai => "xdai",
@@ -137,24 +141,61 @@ impl SpecType {
             SpecType::Goerli => Ok(ethereum::new_goerli(params)),
             SpecType::Sokol => Ok(ethereum::new_sokol(params)),
             SpecType::Yolo3 => Ok(ethereum::new_yolo3(params)),
-            SpecType::Dev => Ok(Spec::new_instant()),
+            SpecType::Dev => Spec::new_instant_with_params(params),
             SpecType::Custom(ref filename) => {
                 let file = fs::File::open(filename).map_err(|e| {
                     format!("Could not load specification file at {}: {}", filename, e)
                 })?;
                 Spec::load(params, file)
             }
+            SpecType::Overlay(ref base, ref filename) => {
+                let overlay = load_spec_overlay(filename)?;
+                for (name, block) in &overlay.params {
+                    match block {
+                        Some(block) => info!(
+                            "Chain spec overlay: {} at block #{}",
+                            name,
+                            block.0.low_u64()
+                        ),
+                        None => info!("Chain spec overlay: {} deactivated", name),
+                    }
+                }
+                let spec = base.spec(params.with_overlay(Some(&overlay)))?;
+                info!("Chain spec hash with overlay applied: {:?}", spec.hash);
+                Ok(spec)
+            }
+        }
+    }
+
+    /// The chain spec without any overlay.
+    pub fn base(&self) -> &SpecType {
+        match *self {
+            SpecType::Overlay(ref base, _) => base.base(),
+            ref spec => spec,
         }
     }
 
     pub fn legacy_fork_name(&self) -> Option<String> {
         match *self {
+            SpecType::Overlay(ref base, _) => base.legacy_fork_name(),
             SpecType::Musicoin => Some("musicoin".to_owned()),
             _ => None,
         }
     }
 }
 
+/// Load the chain spec overlay at `filename` and check it only moves known fork transitions.
+pub fn load_spec_overlay(filename: &str) -> Result<SpecOverlay, String> {
+    let file = fs::File::open(filename)
+        .map_err(|e| format!("Could not load chain spec overlay at {}: {}", filename, e))?;
+    let overlay = SpecOverlay::load(file)
+        .map_err(|e| format!("Chain spec overlay at {} is invalid: {}", filename, e))?;
+    overlay
+        .validate()
+        .map_err(|e| format!("Chain spec overlay at {} is invalid: {}", filename, e))?;
+    Ok(overlay)
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Pruning {
     Specific(Algorithm),
@@ -291,9 +332,16 @@ impl GasPricerConfig {
 pub struct MinerExtras {
     pub author: Address,
     pub engine_signer: Address,
+    pub engine_signer_pkcs11: Option<Pkcs11Config>,
+    pub fee_recipient: Option<Address>,
     pub extra_data: Vec<u8>,
     pub gas_range_target: (U256, U256),
     pub work_notify: Vec<String>,
+    pub tx_webhooks: Vec<String>,
+    pub tx_webhook_retries: usize,
+    pub tx_queue_node_clock: bool,
+    pub block_builder_url: Option<String>,
+    pub block_builder_timeout: Duration,
     pub local_accounts: HashSet<Address>,
 }
 
@@ -302,9 +350,16 @@ impl Default for MinerExtras {
         MinerExtras {
             author: Default::default(),
             engine_signer: Default::default(),
+            engine_signer_pkcs11: None,
+            fee_recipient: None,
             extra_data: version_data(),
             gas_range_target: (8_000_000.into(), 10_000_000.into()),
             work_notify: Default::default(),
+            tx_webhooks: Default::default(),
+            tx_webhook_retries: 3,
+            tx_queue_node_clock: false,
+            block_builder_url: None,
+            block_builder_timeout: Duration::from_millis(500),
             local_accounts: Default::default(),
         }
     }
@@ -411,6 +466,13 @@ mod tests {
         assert_eq!(SpecType::Foundation, SpecType::default());
     }
 
+    #[test]
+    fn test_spec_type_overlay_display() {
+        let spec = SpecType::Overlay(Box::new(SpecType::Goerli), "fork.json".into());
+        assert_eq!(format!("{}", spec), "goerli");
+        assert_eq!(spec.legacy_fork_name(), None);
+    }
+
     #[test]
     fn test_spec_type_display() {
         assert_eq!(format!("{}", SpecType::Foundation), "foundation");
@@ -451,6 +513,10 @@ mod tests {
             Pruning::Specific(Algorithm::RefCounted),
             "basic".parse().unwrap()
         );
+        assert_eq!(
+            Pruning::Specific(Algorithm::Generational),
+            "generational".parse().unwrap()
+        );
     }
 
     #[test]
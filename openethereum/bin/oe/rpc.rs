@@ -14,7 +14,13 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{collections::HashSet, io, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufReader},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use crate::{
     helpers::parity_ipc_path,
@@ -25,6 +31,7 @@ use jsonrpc_core::MetaIoHandler;
 use parity_rpc::{
     self as rpc,
     informant::{Middleware, RpcStats},
+    recorder::{replay, ReplayReport, RpcRecorder},
     DomainsValidation, Metadata,
 };
 use parity_runtime::Executor;
@@ -34,6 +41,10 @@ pub use parity_rpc::{HttpServer, IpcServer, RequestMiddleware};
 pub use parity_rpc::ws::{ws, Server as WsServer};
 
 pub const DAPPS_DOMAIN: &'static str = "web3.site";
+/// Path answering `GET` with the result of `parity_nodeStatus`, without authentication.
+const HEALTH_API_PATH: &'static str = "/api/health";
+/// Alias of `HEALTH_API_PATH` for load balancers.
+const LOAD_BALANCER_HEALTH_PATH: &'static str = "/health";
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct HttpConfiguration {
@@ -47,6 +58,7 @@ pub struct HttpConfiguration {
     pub processing_threads: usize,
     pub max_payload: usize,
     pub keep_alive: bool,
+    pub jwt_secret: Option<PathBuf>,
 }
 
 impl Default for HttpConfiguration {
@@ -62,6 +74,7 @@ impl Default for HttpConfiguration {
             processing_threads: 4,
             max_payload: 5,
             keep_alive: true,
+            jwt_secret: None,
         }
     }
 }
@@ -100,6 +113,7 @@ pub struct WsConfiguration {
     pub signer_path: PathBuf,
     pub support_token_api: bool,
     pub max_payload: usize,
+    pub jwt_secret: Option<PathBuf>,
 }
 
 impl Default for WsConfiguration {
@@ -120,6 +134,7 @@ impl Default for WsConfiguration {
             signer_path: replace_home(&data_dir, "$BASE/signer").into(),
             support_token_api: true,
             max_payload: 5,
+            jwt_secret: None,
         }
     }
 }
@@ -150,6 +165,9 @@ pub struct Dependencies<D: rpc_apis::Dependencies> {
     pub apis: Arc<D>,
     pub executor: Executor,
     pub stats: Arc<RpcStats>,
+    pub recorder: Option<Arc<RpcRecorder>>,
+    pub batch_limits: rpc::BatchLimits,
+    pub batch_executor: Executor,
 }
 
 pub fn new_ws<D: rpc_apis::Dependencies>(
@@ -170,12 +188,13 @@ pub fn new_ws<D: rpc_apis::Dependencies>(
     let handler = {
         let mut handler = MetaIoHandler::with_middleware((
             rpc::WsDispatcher::new(full_handler),
-            Middleware::new(deps.stats.clone(), deps.apis.activity_notifier()),
+            Middleware::new(deps.stats.clone(), deps.apis.activity_notifier())
+                .with_recorder(deps.recorder.clone()),
         ));
         let apis = conf.apis.list_apis();
         deps.apis.extend_with_set(&mut handler, &apis);
 
-        handler
+        with_batch_limits(handler, deps)
     };
 
     let allowed_origins = into_domains(with_domain(conf.origins, domain, &None));
@@ -189,6 +208,7 @@ pub fn new_ws<D: rpc_apis::Dependencies>(
         }
         false => None,
     };
+    let jwt_secret = load_jwt_secret(&conf.jwt_secret)?;
     let start_result = rpc_servers::start_ws(
         &addr,
         handler,
@@ -196,7 +216,7 @@ pub fn new_ws<D: rpc_apis::Dependencies>(
         allowed_hosts,
         conf.max_connections,
         rpc::WsExtractor::new(path.clone()),
-        rpc::WsExtractor::new(path.clone()),
+        rpc::WsExtractor::new(path.clone()).with_jwt_secret(jwt_secret),
         rpc::WsStats::new(deps.stats.clone()),
         conf.max_payload,
     );
@@ -234,19 +254,26 @@ pub fn new_http<D: rpc_apis::Dependencies>(
     let addr = url
         .parse()
         .map_err(|_| format!("Invalid {} listen host/port given: {}", id, url))?;
-    let handler = setup_apis(conf.apis, deps);
+    let handler = with_batch_limits(setup_apis(conf.apis, deps), deps);
 
     let cors_domains = into_domains(conf.cors);
     let allowed_hosts = into_domains(with_domain(conf.hosts, domain, &Some(url.clone().into())));
-    let health_api = Some(("/api/health", "parity_nodeStatus"));
+    let health_api = Some((HEALTH_API_PATH, "parity_nodeStatus"));
+    // load balancers probe the health endpoints without credentials
+    let middleware = rpc::HealthMiddleware::new(
+        HEALTH_API_PATH,
+        vec![LOAD_BALANCER_HEALTH_PATH],
+        load_jwt_secret(&conf.jwt_secret)?.map(rpc::JwtMiddleware::new),
+    );
 
-    let start_result = rpc_servers::start_http(
+    let start_result = rpc_servers::start_http_with_middleware(
         &addr,
         cors_domains,
         allowed_hosts,
         health_api,
         handler,
         rpc::RpcExtractor,
+        middleware,
         conf.server_threads,
         conf.max_payload,
         conf.keep_alive,
@@ -269,7 +296,7 @@ pub fn new_ipc<D: rpc_apis::Dependencies>(
         return Ok(None);
     }
 
-    let handler = setup_apis(conf.apis, dependencies);
+    let handler = with_batch_limits(setup_apis(conf.apis, dependencies), dependencies);
     let path = PathBuf::from(&conf.socket_addr);
     // Make sure socket file can be created on unix-like OS.
     // Windows pipe paths are not on the FS.
@@ -291,6 +318,12 @@ pub fn new_ipc<D: rpc_apis::Dependencies>(
     }
 }
 
+fn load_jwt_secret(path: &Option<PathBuf>) -> Result<Option<rpc::JwtSecret>, String> {
+    path.as_ref()
+        .map(|path| rpc::JwtSecret::from_file(path))
+        .transpose()
+}
+
 fn into_domains<T: From<String>>(items: Option<Vec<String>>) -> DomainsValidation<T> {
     items
         .map(|vals| vals.into_iter().map(T::from).collect())
@@ -333,14 +366,52 @@ pub fn setup_apis<D>(
 where
     D: rpc_apis::Dependencies,
 {
+    let mut handler = MetaIoHandler::with_middleware(
+        Middleware::new(deps.stats.clone(), deps.apis.activity_notifier())
+            .with_recorder(deps.recorder.clone()),
+    );
+    let apis = apis.list_apis();
+    deps.apis.extend_with_set(&mut handler, &apis);
+
+    handler
+}
+
+/// Wraps `handler` to enforce the batch limits and execute batch calls on the batch thread pool.
+fn with_batch_limits<D, S>(
+    handler: MetaIoHandler<Metadata, S>,
+    deps: &Dependencies<D>,
+) -> MetaIoHandler<Metadata, rpc::BatchDispatcher<S>>
+where
+    D: rpc_apis::Dependencies,
+    S: jsonrpc_core::Middleware<Metadata>,
+{
+    MetaIoHandler::with_middleware(rpc::BatchDispatcher::new(
+        handler,
+        deps.batch_executor.clone(),
+        deps.batch_limits.clone(),
+    ))
+}
+
+/// Replay recorded RPC test vectors against all APIs. Replayed requests are not recorded.
+pub fn replay_fixture<D>(path: &str, deps: &Dependencies<D>) -> Result<ReplayReport, String>
+where
+    D: rpc_apis::Dependencies,
+{
+    let fixture =
+        File::open(path).map_err(|e| format!("Unable to open RPC replay file {}: {}", path, e))?;
     let mut handler = MetaIoHandler::with_middleware(Middleware::new(
         deps.stats.clone(),
         deps.apis.activity_notifier(),
     ));
-    let apis = apis.list_apis();
+    let apis = ApiSet::All.list_apis();
     deps.apis.extend_with_set(&mut handler, &apis);
 
-    handler
+    replay(
+        BufReader::new(fixture),
+        &handler,
+        Metadata::default(),
+        &deps.apis.activity_notifier(),
+    )
 }
 
 #[cfg(test)]
@@ -25,17 +25,24 @@ pub use parity_rpc::signer::SignerService;
 
 use crate::{
     account_utils::{self, AccountProvider},
+    clock_drift::ClockDriftMonitor,
+    local_store::TransactionJournal,
     miner::external::ExternalMiner,
     sync::{ManageNetwork, SyncProvider},
 };
-use ethcore::{client::Client, miner::Miner, snapshot::SnapshotService};
+use ethcore::{
+    client::Client,
+    miner::{Miner, MinerService},
+    snapshot::SnapshotService,
+};
 use ethcore_logger::RotatingLogger;
 use fetch::Client as FetchClient;
 use jsonrpc_core::{self as core, MetaIoHandler};
 use parity_rpc::{
     dispatch::FullDispatcher,
     informant::{ActivityNotifier, ClientNotifier},
-    Host, Metadata, NetworkSettings,
+    ChainHealth, Host, Metadata, NetworkSettings, RangeBudget, SubscriptionLimits,
+    SubscriptionMetrics,
 };
 use parity_runtime::Executor;
 use parking_lot::Mutex;
@@ -58,6 +65,8 @@ pub enum Api {
     Parity,
     /// Traces (Safe)
     Traces,
+    /// Geth-compatible txpool inspection (Safe)
+    TxPool,
     /// Rpc (Safe)
     Rpc,
     /// Parity PubSub - Generic Publish-Subscriber (Safety depends on other APIs exposed).
@@ -93,6 +102,7 @@ impl FromStr for Api {
             "secretstore" => Ok(SecretStore),
             "signer" => Ok(Signer),
             "traces" => Ok(Traces),
+            "txpool" => Ok(TxPool),
             "web3" => Ok(Web3),
             api => Err(format!("Unknown api: {}", api)),
         }
@@ -173,6 +183,7 @@ fn to_modules(apis: &HashSet<Api>) -> BTreeMap<String, String> {
             Api::SecretStore => ("secretstore", "1.0"),
             Api::Signer => ("signer", "1.0"),
             Api::Traces => ("traces", "1.0"),
+            Api::TxPool => ("txpool", "1.0"),
             Api::Web3 => ("web3", "1.0"),
         };
         modules.insert(name.into(), version.into());
@@ -234,6 +245,12 @@ pub struct FullDependencies {
     pub poll_lifetime: u32,
     pub allow_missing_blocks: bool,
     pub no_ancient_blocks: bool,
+    pub clock_drift: Option<Arc<ClockDriftMonitor>>,
+    pub chain_health: Arc<ChainHealth>,
+    pub range_budget: RangeBudget,
+    pub subscription_limits: SubscriptionLimits,
+    pub subscription_metrics: Arc<SubscriptionMetrics>,
+    pub transaction_journal: Arc<dyn TransactionJournal>,
 }
 
 impl FullDependencies {
@@ -283,6 +300,7 @@ impl FullDependencies {
                             allow_missing_blocks: self.allow_missing_blocks,
                             allow_experimental_rpcs: self.experimental_rpcs,
                             no_ancient_blocks: self.no_ancient_blocks,
+                            range_budget: self.range_budget.clone(),
                         },
                     );
                     handler.extend_with(client.to_delegate());
@@ -305,13 +323,17 @@ impl FullDependencies {
                 }
                 Api::EthPubSub => {
                     if !for_generic_pubsub {
-                        let client =
-                            EthPubSubClient::new(self.client.clone(), self.executor.clone());
+                        let client = EthPubSubClient::with_limits(
+                            self.client.clone(),
+                            self.executor.clone(),
+                            self.subscription_limits.clone(),
+                            self.subscription_metrics.clone(),
+                        );
                         let h = client.handler();
                         self.miner
-                            .add_transactions_listener(Box::new(move |hashes| {
+                            .add_full_transactions_listener(Box::new(move |transactions| {
                                 if let Some(h) = h.upgrade() {
-                                    h.notify_new_transactions(hashes);
+                                    h.notify_new_transactions(transactions);
                                 }
                             }));
 
@@ -360,6 +382,7 @@ impl FullDependencies {
                             self.ws_address.clone(),
                             self.snapshot.clone().into(),
                         )
+                        .with_health(self.chain_health.clone())
                         .to_delegate(),
                     );
                     #[cfg(feature = "accounts")]
@@ -384,7 +407,13 @@ impl FullDependencies {
                             .list_apis();
                         self.extend_api(&mut rpc, &apis, true);
                         handler.extend_with(
-                            PubSubClient::new(rpc, self.executor.clone()).to_delegate(),
+                            PubSubClient::with_limits(
+                                rpc,
+                                self.executor.clone(),
+                                self.subscription_limits.clone(),
+                                self.subscription_metrics.clone(),
+                            )
+                            .to_delegate(),
                         );
                     }
                 }
@@ -402,6 +431,7 @@ impl FullDependencies {
                             &self.net_service,
                             self.fetch.clone(),
                         )
+                        .with_transaction_journal(self.transaction_journal.clone())
                         .to_delegate(),
                     );
                     #[cfg(feature = "accounts")]
@@ -409,7 +439,13 @@ impl FullDependencies {
                         ParitySetAccountsClient::new(&self.accounts, &self.miner).to_delegate(),
                     );
                 }
-                Api::Traces => handler.extend_with(TracesClient::new(&self.client).to_delegate()),
+                Api::Traces => handler.extend_with(
+                    TracesClient::with_range_budget(&self.client, self.range_budget.clone())
+                        .to_delegate(),
+                ),
+                Api::TxPool => {
+                    handler.extend_with(TxPoolClient::new(&self.client, &self.miner).to_delegate());
+                }
                 Api::Rpc => {
                     let modules = to_modules(&apis);
                     handler.extend_with(RpcClient::new(modules).to_delegate());
@@ -475,6 +511,7 @@ impl ApiSet {
             ApiSet::All => {
                 public_list.insert(Api::Debug);
                 public_list.insert(Api::Traces);
+                public_list.insert(Api::TxPool);
                 public_list.insert(Api::ParityPubSub);
                 public_list.insert(Api::ParityAccounts);
                 public_list.insert(Api::ParitySet);
@@ -514,6 +551,7 @@ mod test {
         assert_eq!(Api::ParityAccounts, "parity_accounts".parse().unwrap());
         assert_eq!(Api::ParitySet, "parity_set".parse().unwrap());
         assert_eq!(Api::Traces, "traces".parse().unwrap());
+        assert_eq!(Api::TxPool, "txpool".parse().unwrap());
         assert_eq!(Api::Rpc, "rpc".parse().unwrap());
         assert_eq!(Api::SecretStore, "secretstore".parse().unwrap());
         assert!("rp".parse::<Api>().is_err());
@@ -590,6 +628,7 @@ mod test {
                     Api::Signer,
                     Api::Personal,
                     Api::Debug,
+                    Api::TxPool,
                 ]
                 .into_iter()
                 .collect()
@@ -616,6 +655,7 @@ mod test {
                     Api::ParitySet,
                     Api::Signer,
                     Api::Debug,
+                    Api::TxPool,
                 ]
                 .into_iter()
                 .collect()
@@ -25,11 +25,20 @@ use std::{
 use crate::{
     account_utils,
     cache::CacheConfig,
+    clock_drift::{ClockDriftConfiguration, ClockDriftMonitor},
+    configuration::Configuration,
     db,
+    hash::keccak,
     helpers::{execute_upgrades, passwords_from_files, to_client_config},
+    hsm_signer,
     informant::{FullNodeInformantData, Informant},
     metrics::{start_prometheus_metrics, MetricsConfiguration},
-    miner::{external::ExternalMiner, work_notify::WorkPoster},
+    miner::{
+        block_builder::HttpBlockBuilder,
+        external::ExternalMiner,
+        pool::{self, HttpWebhook, ScoredTransaction},
+        work_notify::WorkPoster,
+    },
     modules,
     params::{
         fatdb_switch_to_bool, mode_switch_to_bool, tracing_switch_to_bool, AccountsConfig,
@@ -40,19 +49,26 @@ use crate::{
     user_defaults::UserDefaults,
 };
 use ansi_term::Colour;
+use crypto::publickey::{KeyPair, Public, Secret};
 use dir::{DatabaseDirectories, Directories};
 use ethcore::{
-    client::{BlockChainClient, BlockInfo, Client, DatabaseCompactionProfile, Mode, VMType},
+    client::{
+        BlockChainClient, BlockInfo, Client, DatabaseCompactionProfile, Mode, UncleStrategy, VMType,
+    },
     miner::{self, stratum, Miner, MinerOptions, MinerService},
     snapshot::{self, SnapshotConfiguration},
+    spec::{SpecUpdateChecker, SpecUpdateConfig},
     verification::queue::VerifierSettings,
 };
 use ethcore_logger::{Config as LogConfig, RotatingLogger};
 use ethcore_service::ClientService;
 use ethereum_types::{H256, U64};
 use journaldb::Algorithm;
-use node_filter::NodeFilter;
-use parity_rpc::{informant, is_major_importing, NetworkSettings};
+use node_filter::{CertificateFilter, NodeFilter};
+use parity_rpc::{
+    informant, is_major_importing, recorder::RpcRecorder, BatchLimits, ChainHealth, ClockDrift,
+    NetworkSettings, RangeBudget, SubscriptionLimits,
+};
 use parity_runtime::Runtime;
 use parity_version::version;
 
@@ -79,6 +95,7 @@ pub struct RunCmd {
     pub miner_options: MinerOptions,
     pub gas_price_percentile: usize,
     pub poll_lifetime: u32,
+    pub call_timeout: Option<Duration>,
     pub ws_conf: rpc::WsConfiguration,
     pub http_conf: rpc::HttpConfiguration,
     pub ipc_conf: rpc::IpcConfiguration,
@@ -86,6 +103,7 @@ pub struct RunCmd {
     pub network_id: Option<u64>,
     pub warp_sync: bool,
     pub warp_barrier: Option<u64>,
+    pub rpc_bridge: Option<sync::RpcBridgeConfig>,
     pub acc_conf: AccountsConfig,
     pub gas_pricer_conf: GasPricerConfig,
     pub miner_extras: MinerExtras,
@@ -107,8 +125,29 @@ pub struct RunCmd {
     pub new_transactions_stats_period: u64,
     pub verifier_settings: VerifierSettings,
     pub no_persistent_txqueue: bool,
+    pub tx_journal_encryption: bool,
+    pub tx_journal_segment_size: usize,
+    pub tx_journal_segments: u64,
     pub max_round_blocks_to_import: usize,
     pub metrics_conf: MetricsConfiguration,
+    pub clock_drift_conf: ClockDriftConfiguration,
+    pub db_read_profiler: bool,
+    pub read_only: bool,
+    pub compress_history: bool,
+    pub max_future_block_drift: Duration,
+    pub max_relay_future_drift: Option<Duration>,
+    pub max_reorg_depth: Option<u64>,
+    pub history_expiry: Option<u64>,
+    pub tx_index_expiry: Option<u64>,
+    pub state_regeneration_limit: Option<u64>,
+    pub uncle_strategy: UncleStrategy,
+    pub spec_update_conf: Option<SpecUpdateConfig>,
+    pub range_budget: RangeBudget,
+    pub batch_limits: BatchLimits,
+    pub subscription_limits: SubscriptionLimits,
+    pub node_certificate_authorities: Vec<Public>,
+    pub rpc_record: Option<String>,
+    pub rpc_replay: Option<String>,
 }
 
 // node info fetcher for the local store.
@@ -173,15 +212,17 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
     trace!(target: "mode", "mode is {:?}", mode);
     let network_enabled = match mode {
         Mode::Dark(_) | Mode::Off => false,
-        _ => true,
+        _ => !cmd.read_only,
     };
 
     // prepare client and snapshot paths.
     let client_path = db_dirs.client_path(algorithm);
     let snapshot_path = db_dirs.snapshot_path();
 
-    // execute upgrades
-    execute_upgrades(&cmd.dirs.base, &db_dirs, algorithm, &cmd.compaction)?;
+    // execute upgrades, which a read-only client leaves to the node owning the database
+    if !cmd.read_only {
+        execute_upgrades(&cmd.dirs.base, &db_dirs, algorithm, &cmd.compaction)?;
+    }
 
     // create dirs used by parity
     cmd.dirs.create_dirs(
@@ -209,6 +250,11 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         "Operating mode: {}",
         Colour::White.bold().paint(format!("{}", mode))
     );
+    info!(
+        "Chain spec: {} {}",
+        Colour::White.bold().paint(spec.name.clone()),
+        Colour::White.bold().paint(format!("{:?}", spec.hash))
+    );
 
     // display warning about using experimental journaldb algorithm
     if !algorithm.is_stable() {
@@ -252,7 +298,9 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
     };
     sync_config.download_old_blocks = cmd.download_old_blocks;
     sync_config.eip1559_transition = spec.params().eip1559_transition;
+    sync_config.eip4895_transition_timestamp = spec.params().eip4895_transition_timestamp;
     sync_config.new_transactions_stats_period = cmd.new_transactions_stats_period;
+    sync_config.max_relay_future_drift = cmd.max_relay_future_drift;
 
     let passwords = passwords_from_files(&cmd.acc_conf.password_files)?;
 
@@ -272,6 +320,16 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
     let fetch = fetch::Client::new(FETCH_FULL_NUM_DNS_THREADS)
         .map_err(|e| format!("Error starting fetch client: {:?}", e))?;
 
+    // engines like AuRa bind the block author to the sealing identity
+    if cmd.miner_extras.fee_recipient.is_some()
+        && !spec.engine.supports_fee_recipient(u64::max_value())
+    {
+        return Err(format!(
+            "The {} engine does not support a separate fee recipient, remove --fee-recipient.",
+            spec.engine.name()
+        ));
+    }
+
     let txpool_size = cmd.miner_options.pool_limits.max_count;
     // create miner
     let miner = Arc::new(Miner::new(
@@ -285,6 +343,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         ),
     ));
     miner.set_author(miner::Author::External(cmd.miner_extras.author));
+    miner.set_fee_recipient(cmd.miner_extras.fee_recipient);
     miner.set_gas_range_target(cmd.miner_extras.gas_range_target);
     miner.set_extra_data(cmd.miner_extras.extra_data);
 
@@ -296,8 +355,47 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         )));
     }
 
+    for url in &cmd.miner_extras.tx_webhooks {
+        miner.add_transaction_webhook(Box::new(HttpWebhook::new(
+            url,
+            fetch.clone(),
+            runtime.executor(),
+            cmd.miner_extras.tx_webhook_retries,
+        )?));
+    }
+
+    if let Some(ref url) = cmd.miner_extras.block_builder_url {
+        miner.set_block_builder(Box::new(HttpBlockBuilder::new(
+            url,
+            fetch.clone(),
+            runtime.executor(),
+            cmd.miner_extras.block_builder_timeout,
+        )?));
+    }
+
     let engine_signer = cmd.miner_extras.engine_signer;
-    if engine_signer != Default::default() {
+    if let Some(ref pkcs11) = cmd.miner_extras.engine_signer_pkcs11 {
+        use ethcore::engines::EngineSigner;
+
+        let pin = passwords.first().ok_or_else(|| {
+            "No PIN found for the PKCS#11 engine signer. Provide it with --password.".to_owned()
+        })?;
+        let signer = hsm_signer::open_pkcs11(pkcs11.clone(), pin.as_str().to_owned())?;
+        if engine_signer != Default::default() && signer.address() != engine_signer {
+            return Err(format!(
+                "PKCS#11 key {:?} belongs to {:?}, not to the configured engine signer {:?}",
+                pkcs11.key_label,
+                signer.address(),
+                engine_signer
+            ));
+        }
+        info!(
+            "Signing consensus messages with {:?} on PKCS#11 slot {}",
+            signer.address(),
+            pkcs11.slot
+        );
+        miner.set_author(miner::Author::Sealer(Box::new(signer)));
+    } else if engine_signer != Default::default() {
         if let Some(author) = account_utils::miner_author(
             &cmd.spec,
             &cmd.dirs,
@@ -328,8 +426,21 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
 
     client_config.queue.verifier_settings = cmd.verifier_settings;
     client_config.queue.verifier_settings.bad_hashes = verification_bad_blocks(&cmd.spec);
-    client_config.transaction_verification_queue_size = ::std::cmp::max(2048, txpool_size / 4);
+    client_config.io_queue.transactions.limit = ::std::cmp::max(2048, txpool_size / 4);
     client_config.snapshot = cmd.snapshot_conf.clone();
+    client_config.max_future_block_drift = cmd.max_future_block_drift;
+    client_config.call_timeout = cmd.call_timeout;
+    client_config.max_reorg_depth = cmd.max_reorg_depth;
+    client_config.history_expiry = cmd.history_expiry;
+    client_config.tx_index_expiry = cmd.tx_index_expiry;
+    client_config.state_regeneration_limit = cmd.state_regeneration_limit;
+    client_config.uncle_strategy = cmd.uncle_strategy;
+    client_config.read_only = cmd.read_only;
+    client_config.blockchain.compress_history = cmd.compress_history;
+
+    if cmd.miner_extras.tx_queue_node_clock {
+        miner.set_transaction_clock(Arc::new(client_config.clock.clone()));
+    }
 
     // set up bootnodes
     let mut net_conf = cmd.net_conf;
@@ -340,10 +451,18 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
     // set network path.
     net_conf.net_config_path = Some(db_dirs.network_path().to_string_lossy().into_owned());
 
+    if cmd.db_read_profiler {
+        info!("Database read profiler enabled");
+        ethcore_db::read_profiler::enable();
+    }
+
     let restoration_db_handler = db::restoration_db_handler(&client_path, &client_config);
-    let client_db = restoration_db_handler
-        .open(&client_path)
-        .map_err(|e| format!("Failed to open database {:?}", e))?;
+    let client_db = if cmd.read_only {
+        db::open_read_only_database(&client_path, &client_config)
+    } else {
+        restoration_db_handler.open(&client_path)
+    }
+    .map_err(|e| format!("Failed to open database {:?}", e))?;
 
     // create client service.
     let service = ClientService::start(
@@ -360,6 +479,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
     let connection_filter_address = spec.params().node_permission_contract;
     // drop the spec to free up genesis state.
     let forks = spec.hard_forks.clone();
+    let spec_hash = spec.hash;
     drop(spec);
 
     // take handle to client
@@ -381,8 +501,22 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         Arc::new(NodeFilter::new(
             Arc::downgrade(&client) as Weak<dyn BlockChainClient>,
             a,
-        ))
+        )) as Arc<dyn crate::sync::ConnectionFilter + 'static>
     });
+    let connection_filter = if cmd.node_certificate_authorities.is_empty() {
+        connection_filter
+    } else {
+        info!(
+            "Only peers with a node certificate of {} authorities are allowed",
+            cmd.node_certificate_authorities.len()
+        );
+        Some(Arc::new(CertificateFilter::new(
+            cmd.node_certificate_authorities.clone(),
+            client.clock(),
+            connection_filter,
+        ))
+            as Arc<dyn crate::sync::ConnectionFilter + 'static>)
+    };
     let snapshot_service = service.snapshot_service();
 
     // initialize the local node information store.
@@ -395,11 +529,34 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
             },
         };
 
+        // the key is loaded even without encryption, so data encrypted before is still read.
+        let encryption_key = match net_conf
+            .node_key()
+            .map_err(|e| e.to_string())
+            .and_then(|key| journal_key(&key))
+        {
+            Ok(key) => Some(key),
+            Err(e) if cmd.tx_journal_encryption => {
+                return Err(format!("Error loading transaction journal key: {}", e))
+            }
+            Err(e) => {
+                warn!("Unable to load the transaction journal key: {}", e);
+                None
+            }
+        };
+        let journal_options = crate::local_store::JournalOptions {
+            max_segment_size: cmd.tx_journal_segment_size,
+            max_segments: cmd.tx_journal_segments,
+            encryption_key,
+            encrypt: cmd.tx_journal_encryption,
+        };
         let store = crate::local_store::create(
             db.key_value().clone(),
             ::ethcore_db::COL_NODE_INFO,
             node_info,
-        );
+            journal_options,
+        )
+        .map_err(|e| format!("Error opening local transaction journal: {}", e))?;
 
         if cmd.no_persistent_txqueue {
             info!("Running without a persistent transaction queue.");
@@ -426,9 +583,29 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
 
     // register it as an IO service to update periodically.
     service
-        .register_io_handler(store)
+        .register_io_handler(store.clone())
         .map_err(|_| "Unable to register local store handler".to_owned())?;
 
+    // journal local transactions as soon as they enter the queue.
+    if !cmd.no_persistent_txqueue {
+        let journal = Arc::downgrade(&store);
+        miner.add_full_transactions_listener(Box::new(move |txs| {
+            let local: Vec<_> = txs
+                .iter()
+                .filter(|tx| tx.priority() == pool::Priority::Local)
+                .map(|tx| tx.pending().clone())
+                .collect();
+            if local.is_empty() {
+                return;
+            }
+            if let Some(journal) = journal.upgrade() {
+                if let Err(e) = journal.journal_transactions(local) {
+                    warn!("Error journaling local transactions: {}", e);
+                }
+            }
+        }));
+    }
+
     // create external miner
     let external_miner = Arc::new(ExternalMiner::default());
 
@@ -447,9 +624,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
             forks,
             snapshot_service.clone(),
             &cmd.logger_config,
-            connection_filter
-                .clone()
-                .map(|f| f as Arc<dyn crate::sync::ConnectionFilter + 'static>),
+            connection_filter,
         )
         .map_err(|e| format!("Sync error: {}", e))?;
 
@@ -486,6 +661,40 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         chain_notify.start();
     }
 
+    // bootstrap from a trusted rpc provider, handing over to p2p sync once caught up
+    let rpc_bridge = match cmd.rpc_bridge {
+        Some(mut conf) if !cmd.read_only => {
+            conf.eip1559_transition = sync_config.eip1559_transition;
+            conf.eip4895_transition_timestamp = sync_config.eip4895_transition_timestamp;
+            Some(sync::RpcBridge::start(
+                conf,
+                &(client.clone() as Arc<dyn BlockChainClient>),
+                fetch.clone(),
+            )?)
+        }
+        _ => None,
+    };
+
+    // the clock drift monitor
+    let clock_drift = match cmd.clock_drift_conf.enabled {
+        false => None,
+        true => {
+            let monitor = Arc::new(ClockDriftMonitor::new(cmd.clock_drift_conf.clone()));
+            monitor
+                .start()
+                .map_err(|e| format!("Unable to start clock drift monitor: {}", e))?;
+            Some(monitor)
+        }
+    };
+
+    // block import and reorganization tracking for parity_nodeHealth
+    let chain_health = Arc::new(ChainHealth::new(
+        clock_drift
+            .clone()
+            .map(|monitor| monitor as Arc<dyn ClockDrift>),
+    ));
+    service.add_notify(chain_health.clone());
+
     // set up dependencies for rpc servers
     let rpc_stats = Arc::new(informant::RpcStats::default());
     let secret_store = account_provider.clone();
@@ -511,14 +720,72 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         poll_lifetime: cmd.poll_lifetime,
         allow_missing_blocks: cmd.allow_missing_blocks,
         no_ancient_blocks: !cmd.download_old_blocks,
+        clock_drift: clock_drift.clone(),
+        chain_health: chain_health.clone(),
+        range_budget: cmd.range_budget.clone(),
+        subscription_limits: cmd.subscription_limits.clone(),
+        subscription_metrics: Default::default(),
+        transaction_journal: store,
     });
 
+    let rpc_recorder = match cmd.rpc_record {
+        Some(ref path) => {
+            info!("Recording RPC test vectors to {}", path);
+            Some(Arc::new(RpcRecorder::new(path).map_err(|e| {
+                format!("Unable to open RPC recording file {}: {}", path, e)
+            })?))
+        }
+        None => None,
+    };
+
+    // calls of batch requests are handled synchronously, so they get threads of their own
+    // instead of blocking the shared runtime.
+    let batch_runtime = Runtime::with_thread_count(cmd.batch_limits.parallelism);
     let dependencies = rpc::Dependencies {
         apis: deps_for_rpc_apis.clone(),
         executor: runtime.executor(),
         stats: rpc_stats.clone(),
+        recorder: rpc_recorder,
+        batch_limits: cmd.batch_limits.clone(),
+        batch_executor: batch_runtime.executor(),
     };
 
+    if let Some(ref path) = cmd.rpc_replay {
+        let report = rpc::replay_fixture(path, &dependencies)?;
+        if report.is_ok() {
+            info!(
+                "Replayed {} RPC test vectors from {} ({} skipped), all responses match",
+                report.replayed, path, report.skipped
+            );
+        } else {
+            for mismatch in &report.mismatches {
+                if mismatch.replayed_block != mismatch.block {
+                    warn!(
+                        "RPC test vector at line {} not replayed, recorded block {:?} differs from the imported one {:?}",
+                        mismatch.line, mismatch.block, mismatch.replayed_block
+                    );
+                    continue;
+                }
+                warn!(
+                    "RPC test vector at line {} (recorded at block {:?}) differs: {}\n  expected: {:?}\n  actual: {:?}",
+                    mismatch.line,
+                    mismatch.block.as_ref().map(|b| b.number),
+                    mismatch.request,
+                    mismatch.expected,
+                    mismatch.actual
+                );
+            }
+            warn!(
+                "Replayed {} RPC test vectors from {}, {} responses differ",
+                report.replayed,
+                path,
+                Colour::Red
+                    .bold()
+                    .paint(format!("{}", report.mismatches.len()))
+            );
+        }
+    }
+
     // start rpc servers
     let ws_server = rpc::new_ws(cmd.ws_conf.clone(), &dependencies)?;
     let ipc_server = rpc::new_ipc(cmd.ipc_conf, &dependencies)?;
@@ -598,23 +865,44 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         }
     };
 
+    // the spec update checker must be kept alive.
+    let spec_update_checker = cmd.spec_update_conf.map(|conf| {
+        let checker = Arc::new(SpecUpdateChecker::new(&client, &miner, spec_hash, conf));
+        service.add_notify(checker.clone());
+        checker
+    });
+
     Ok(RunningClient {
         inner: RunningClientInner::Full {
             informant,
             client,
             client_service: Arc::new(service),
+            clock_drift,
             keep_alive: Box::new((
                 watcher,
+                spec_update_checker,
+                rpc_bridge,
                 ws_server,
                 http_server,
                 ipc_server,
                 secretstore_key_server,
                 runtime,
+                batch_runtime,
             )),
         },
     })
 }
 
+/// Derives the key local transactions are encrypted with at rest from the node key, so the
+/// network identity key is not used for anything else.
+fn journal_key(node_key: &KeyPair) -> Result<KeyPair, String> {
+    let mut seed = b"local transaction journal".to_vec();
+    seed.extend_from_slice(node_key.secret().as_bytes());
+    Secret::import_key(keccak(&seed).as_bytes())
+        .and_then(KeyPair::from_secret)
+        .map_err(|e| format!("Invalid transaction journal key: {}", e))
+}
+
 /// Set bad blocks in VerificationQeueu. By omiting header we can omit particular fork of chain.
 fn verification_bad_blocks(spec: &SpecType) -> Vec<H256> {
     match *spec {
@@ -641,11 +929,43 @@ enum RunningClientInner {
         informant: Arc<Informant<FullNodeInformantData>>,
         client: Arc<Client>,
         client_service: Arc<ClientService>,
+        clock_drift: Option<Arc<ClockDriftMonitor>>,
         keep_alive: Box<dyn Any>,
     },
 }
 
+/// Applies re-read configuration to a running client.
+#[derive(Clone)]
+pub struct ConfigReloader {
+    client: Weak<Client>,
+}
+
+impl ConfigReloader {
+    /// Apply the settings which can change without a restart. Currently these are the cache
+    /// sizes.
+    pub fn apply(&self, conf: &Configuration) {
+        match self.client.upgrade() {
+            Some(client) => {
+                let sizes = conf.runtime_cache_sizes();
+                info!("Applying reloaded cache sizes: {:?}", sizes);
+                client.set_cache_sizes(sizes);
+            }
+            None => debug!("Client is shut down, ignoring the reloaded configuration"),
+        }
+    }
+}
+
 impl RunningClient {
+    /// Returns a handle applying re-read configuration to the client. The handle does not keep
+    /// the client alive.
+    pub fn config_reloader(&self) -> ConfigReloader {
+        match self.inner {
+            RunningClientInner::Full { ref client, .. } => ConfigReloader {
+                client: Arc::downgrade(client),
+            },
+        }
+    }
+
     /// Shuts down the client.
     pub fn shutdown(self) {
         match self.inner {
@@ -653,6 +973,7 @@ impl RunningClient {
                 informant,
                 client,
                 client_service,
+                clock_drift,
                 keep_alive,
             } => {
                 info!("Finishing work, please wait...");
@@ -672,12 +993,18 @@ impl RunningClient {
                 // just Arc is dropping here, to allow other reference release in its default time
                 drop(informant);
                 trace!(target: "shutdown", "Informant dropped");
+                if let Some(clock_drift) = clock_drift {
+                    clock_drift.shutdown();
+                }
                 drop(client);
                 trace!(target: "shutdown", "Client dropped");
                 // This may help when debugging ref cycles. Requires nightly-only  `#![feature(weak_counts)]`
                 // trace!(target: "shutdown", "Waiting for refs to Client to shutdown, strong_count={:?}, weak_count={:?}", weak_client.strong_count(), weak_client.weak_count());
                 trace!(target: "shutdown", "Waiting for refs to Client to shutdown");
                 wait_for_drop(weak_client);
+                if ethcore_db::read_profiler::is_enabled() {
+                    info!(target: "db", "Database read profile:\n{}", ethcore_db::read_profiler::report());
+                }
             }
         }
     }
@@ -14,128 +14,16 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Parity upgrade logic
+//! Relocation of data directories and keys left behind by older versions.
+//! Database schema changes are handled by `ethcore_db::migration`.
 
 use dir::{default_data_path, helpers::replace_home, home_dir, DatabaseDirectories};
 use journaldb::Algorithm;
-use semver::{SemVerError, Version};
 use std::{
-    collections::*,
-    fs::{self, create_dir_all, File},
-    io,
-    io::{Read, Write},
+    fs, io,
     path::{Path, PathBuf},
 };
 
-#[derive(Debug)]
-pub enum Error {
-    CannotCreateConfigPath(io::Error),
-    CannotWriteVersionFile(io::Error),
-    CannotUpdateVersionFile(io::Error),
-    SemVer(SemVerError),
-}
-
-impl From<SemVerError> for Error {
-    fn from(err: SemVerError) -> Self {
-        Error::SemVer(err)
-    }
-}
-
-const CURRENT_VERSION: &'static str = env!("CARGO_PKG_VERSION");
-
-#[derive(Hash, PartialEq, Eq)]
-struct UpgradeKey {
-    pub old_version: Version,
-    pub new_version: Version,
-}
-
-type UpgradeList = HashMap<UpgradeKey, fn() -> Result<(), Error>>;
-
-impl UpgradeKey {
-    // given the following config exist
-    // ver.lock 1.1 (`previous_version`)
-    //
-    //  current_version 1.4 (`current_version`)
-    //
-    //
-    //upgrades (set of `UpgradeKey`)
-    //	1.0 -> 1.1 (u1)
-    //	1.1 -> 1.2 (u2)
-    //	1.2 -> 1.3 (u3)
-    //	1.3 -> 1.4 (u4)
-    //	1.4 -> 1.5 (u5)
-    //
-    // then the following upgrades should be applied:
-    // u2, u3, u4
-    fn is_applicable(&self, previous_version: &Version, current_version: &Version) -> bool {
-        self.old_version >= *previous_version && self.new_version <= *current_version
-    }
-}
-
-// dummy upgrade (remove when the first one is in)
-fn dummy_upgrade() -> Result<(), Error> {
-    Ok(())
-}
-
-fn push_upgrades(upgrades: &mut UpgradeList) {
-    // dummy upgrade (remove when the first one is in)
-    upgrades.insert(
-        UpgradeKey {
-            old_version: Version::new(0, 9, 0),
-            new_version: Version::new(1, 0, 0),
-        },
-        dummy_upgrade,
-    );
-}
-
-fn upgrade_from_version(previous_version: &Version) -> Result<usize, Error> {
-    let mut upgrades = HashMap::new();
-    push_upgrades(&mut upgrades);
-
-    let current_version = Version::parse(CURRENT_VERSION)?;
-
-    let mut count = 0;
-    for upgrade_key in upgrades.keys() {
-        if upgrade_key.is_applicable(previous_version, &current_version) {
-            let upgrade_script = upgrades[upgrade_key];
-            upgrade_script()?;
-            count += 1;
-        }
-    }
-    Ok(count)
-}
-
-fn with_locked_version<F>(db_path: &str, script: F) -> Result<usize, Error>
-where
-    F: Fn(&Version) -> Result<usize, Error>,
-{
-    let mut path = PathBuf::from(db_path);
-    create_dir_all(&path).map_err(Error::CannotCreateConfigPath)?;
-    path.push("ver.lock");
-
-    let version = File::open(&path)
-        .ok()
-        .and_then(|ref mut file| {
-            let mut version_string = String::new();
-            file.read_to_string(&mut version_string)
-                .ok()
-                .and_then(|_| Version::parse(&version_string).ok())
-        })
-        .unwrap_or(Version::new(0, 9, 0));
-
-    let mut lock = File::create(&path).map_err(Error::CannotWriteVersionFile)?;
-    let result = script(&version);
-
-    let written_version = Version::parse(CURRENT_VERSION)?;
-    lock.write_all(written_version.to_string().as_bytes())
-        .map_err(Error::CannotUpdateVersionFile)?;
-    result
-}
-
-pub fn upgrade(db_path: &str) -> Result<usize, Error> {
-    with_locked_version(db_path, |ver| upgrade_from_version(ver))
-}
-
 fn file_exists(path: &Path) -> bool {
     match fs::metadata(&path) {
         Err(ref e) if e.kind() == io::ErrorKind::NotFound => false,
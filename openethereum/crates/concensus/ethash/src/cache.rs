@@ -25,11 +25,15 @@ use shared::{epoch, get_cache_size, to_hex, Node, ETHASH_CACHE_ROUNDS, NODE_BYTE
 
 use std::{
     borrow::Cow,
+    ffi::OsStr,
     fs,
     io::{self, Read, Write},
     path::{Path, PathBuf},
-    slice,
-    sync::Arc,
+    process, slice,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 type Cache = Either<Vec<Node>, MmapMut>;
@@ -186,21 +190,56 @@ impl NodeCache {
     }
 }
 
+/// Path of a temporary file next to `path`, unique to this writer. Caches are written there
+/// first and renamed into place once complete, so that concurrent writers and readers never
+/// see a partial cache.
+fn temp_path(path: &Path) -> PathBuf {
+    static WRITERS: AtomicUsize = AtomicUsize::new(0);
+
+    let mut name = path
+        .file_name()
+        .map(OsStr::to_os_string)
+        .unwrap_or_default();
+    name.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        WRITERS.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+/// Runs `write` on a temporary file and renames it to `path` if it succeeds.
+fn write_atomically<T, F>(path: &Path, write: F) -> io::Result<T>
+where
+    F: FnOnce(&Path) -> io::Result<T>,
+{
+    let temp = temp_path(path);
+    let result = write(&temp).and_then(|value| fs::rename(&temp, path).map(|_| value));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
 fn make_memmapped_cache(path: &Path, num_nodes: usize, ident: &H256) -> io::Result<MmapMut> {
     use std::fs::OpenOptions;
 
-    let file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(&path)?;
-    file.set_len((num_nodes * NODE_BYTES) as _)?;
+    // the mapping stays valid once the file is renamed
+    write_atomically(path, |temp| {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(temp)?;
+        file.set_len((num_nodes * NODE_BYTES) as _)?;
 
-    let mut memmap = unsafe { MmapMut::map_mut(&file)? };
+        let mut memmap = unsafe { MmapMut::map_mut(&file)? };
 
-    unsafe { initialize_memory(memmap.as_mut_ptr() as *mut Node, num_nodes, ident) };
+        unsafe { initialize_memory(memmap.as_mut_ptr() as *mut Node, num_nodes, ident) };
 
-    Ok(memmap)
+        memmap.flush()?;
+        Ok(memmap)
+    })
 }
 
 fn make_memory_cache(num_nodes: usize, ident: &H256) -> Vec<Node> {
@@ -225,17 +264,15 @@ fn consume_cache(cache: &mut Cache, path: &Path) -> io::Result<()> {
 
     match *cache {
         Either::Left(ref mut vec) => {
-            let mut file = OpenOptions::new()
-                .read(true)
-                .write(true)
-                .create(true)
-                .open(&path)?;
-
             let buf = unsafe {
                 slice::from_raw_parts_mut(vec.as_mut_ptr() as *mut u8, vec.len() * NODE_BYTES)
             };
 
-            file.write_all(buf).map(|_| ())
+            write_atomically(path, |temp| {
+                let mut file = OpenOptions::new().write(true).create_new(true).open(temp)?;
+                file.write_all(buf)?;
+                file.sync_all()
+            })
         }
         Either::Right(ref mmap) => mmap.flush(),
     }
@@ -245,11 +282,7 @@ fn cache_from_path(path: &Path, optimize_for: OptimizeFor) -> io::Result<Cache>
     let memmap = match optimize_for {
         OptimizeFor::Cpu => None,
         OptimizeFor::Memory => {
-            let file = fs::OpenOptions::new()
-                .read(true)
-                .write(true)
-                .create(true)
-                .open(path)?;
+            let file = fs::OpenOptions::new().read(true).write(true).open(path)?;
             unsafe { MmapMut::map_mut(&file).ok() }
         }
     };
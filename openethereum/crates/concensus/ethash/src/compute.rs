@@ -115,6 +115,14 @@ impl Light {
         })
     }
 
+    /// Whether this cache verifies ProgPoW rather than Hashimoto seals.
+    pub fn is_progpow(&self) -> bool {
+        match self.algorithm {
+            Algorithm::Progpow(_) => true,
+            Algorithm::Hashimoto => false,
+        }
+    }
+
     pub fn to_file(&mut self) -> io::Result<&Path> {
         self.cache.flush()?;
         Ok(self.cache.cache_path())
@@ -59,26 +59,89 @@ use parking_lot::Mutex;
 pub use seed_compute::SeedHashCompute;
 pub use shared::ETHASH_EPOCH_LENGTH;
 use std::{
+    cmp,
+    collections::VecDeque,
     convert::TryFrom,
-    mem,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering as AtomicOrdering},
+    thread,
 };
 
 use std::sync::Arc;
 
+/// Number of light caches kept in memory unless configured otherwise.
+pub const DEFAULT_CACHE_COUNT: usize = 2;
+
 struct LightCache {
-    recent_epoch: Option<u64>,
-    recent: Option<Arc<Light>>,
-    prev_epoch: Option<u64>,
-    prev: Option<Arc<Light>>,
+    /// Cached epochs, most recently used first. An epoch spanning the ProgPoW transition
+    /// may be present twice, once per algorithm.
+    entries: VecDeque<(u64, Arc<Light>)>,
+    /// Epoch whose cache is being generated in the background.
+    prebuilding: Option<u64>,
+}
+
+impl LightCache {
+    fn get(&mut self, epoch: u64, progpow: bool) -> Option<Arc<Light>> {
+        let position = self
+            .entries
+            .iter()
+            .position(|&(e, ref light)| e == epoch && light.is_progpow() == progpow)?;
+        let entry = self.entries.remove(position)?;
+        let light = entry.1.clone();
+        self.entries.push_front(entry);
+        Some(light)
+    }
+
+    fn contains(&self, epoch: u64, progpow: bool) -> bool {
+        self.entries
+            .iter()
+            .any(|&(e, ref light)| e == epoch && light.is_progpow() == progpow)
+    }
+
+    fn insert(&mut self, epoch: u64, light: Arc<Light>, capacity: usize) {
+        let progpow = light.is_progpow();
+        self.entries
+            .retain(|&(e, ref cached)| e != epoch || cached.is_progpow() != progpow);
+        self.entries.push_front((epoch, light));
+        self.entries.truncate(cmp::max(capacity, 1));
+    }
+
+    /// Insert a cache which is not in use yet. It goes right behind the most recently used
+    /// one so the cache currently needed for verification is never evicted by it.
+    fn insert_prebuilt(&mut self, epoch: u64, light: Arc<Light>, capacity: usize) {
+        if self.contains(epoch, light.is_progpow()) {
+            return;
+        }
+        let position = cmp::min(1, self.entries.len());
+        self.entries.insert(position, (epoch, light));
+        self.entries.truncate(cmp::max(capacity, 1));
+    }
+}
+
+/// Load the light cache for the epoch of `block_number` from disk, generating
+/// and persisting it if there is no usable file.
+fn load_light(builder: &NodeCacheBuilder, cache_dir: &Path, block_number: u64) -> Arc<Light> {
+    match builder.light_from_file(cache_dir, block_number) {
+        Ok(light) => Arc::new(light),
+        Err(e) => {
+            debug!("Light cache file not found for {}:{}", block_number, e);
+            let mut light = builder.light(cache_dir, block_number);
+            if let Err(e) = light.to_file() {
+                warn!("Light cache file write error: {}", e);
+            }
+            Arc::new(light)
+        }
+    }
 }
 
 /// Light/Full cache manager.
 pub struct EthashManager {
     nodecache_builder: NodeCacheBuilder,
-    cache: Mutex<LightCache>,
+    cache: Arc<Mutex<LightCache>>,
     cache_dir: PathBuf,
     progpow_transition: u64,
+    cache_count: AtomicUsize,
+    prebuild_distance: AtomicU64,
 }
 
 impl EthashManager {
@@ -95,15 +158,28 @@ impl EthashManager {
                 progpow_transition,
             ),
             progpow_transition: progpow_transition,
-            cache: Mutex::new(LightCache {
-                recent_epoch: None,
-                recent: None,
-                prev_epoch: None,
-                prev: None,
-            }),
+            cache: Arc::new(Mutex::new(LightCache {
+                entries: VecDeque::new(),
+                prebuilding: None,
+            })),
+            cache_count: AtomicUsize::new(DEFAULT_CACHE_COUNT),
+            prebuild_distance: AtomicU64::new(0),
         }
     }
 
+    /// Set the number of light caches kept in memory. At least one is always kept.
+    pub fn set_cache_count(&self, count: usize) {
+        self.cache_count.store(count, AtomicOrdering::Relaxed);
+        self.cache.lock().entries.truncate(cmp::max(count, 1));
+    }
+
+    /// Start generating the cache of the next epoch on a background thread once a
+    /// block within `blocks` of the epoch boundary is verified. Zero disables prebuilding.
+    pub fn set_prebuild_distance(&self, blocks: u64) {
+        self.prebuild_distance
+            .store(blocks, AtomicOrdering::Relaxed);
+    }
+
     /// Calculate the light client data
     /// `block_number` - Block number to check
     /// `light` - The light client handler
@@ -113,59 +189,61 @@ impl EthashManager {
         let epoch = block_number / ETHASH_EPOCH_LENGTH;
         let light = {
             let mut lights = self.cache.lock();
-            let light = if block_number == self.progpow_transition {
-                // we need to regenerate the cache to trigger algorithm change to progpow inside `Light`
-                None
-            } else {
-                match lights.recent_epoch.clone() {
-                    Some(ref e) if *e == epoch => lights.recent.clone(),
-                    _ => match lights.prev_epoch.clone() {
-                        Some(e) if e == epoch => {
-                            // don't swap if recent is newer.
-                            if lights.recent_epoch > lights.prev_epoch {
-                                None
-                            } else {
-                                // swap
-                                let t = lights.prev_epoch;
-                                lights.prev_epoch = lights.recent_epoch;
-                                lights.recent_epoch = t;
-                                let t = lights.prev.clone();
-                                lights.prev = lights.recent.clone();
-                                lights.recent = t;
-                                lights.recent.clone()
-                            }
-                        }
-                        _ => None,
-                    },
-                }
-            };
+            let light = lights.get(epoch, block_number >= self.progpow_transition);
 
             match light {
                 None => {
-                    let light = match self
-                        .nodecache_builder
-                        .light_from_file(&self.cache_dir, block_number)
-                    {
-                        Ok(light) => Arc::new(light),
-                        Err(e) => {
-                            debug!("Light cache file not found for {}:{}", block_number, e);
-                            let mut light =
-                                self.nodecache_builder.light(&self.cache_dir, block_number);
-                            if let Err(e) = light.to_file() {
-                                warn!("Light cache file write error: {}", e);
-                            }
-                            Arc::new(light)
-                        }
-                    };
-                    lights.prev_epoch = mem::replace(&mut lights.recent_epoch, Some(epoch));
-                    lights.prev = mem::replace(&mut lights.recent, Some(light.clone()));
+                    let light = load_light(&self.nodecache_builder, &self.cache_dir, block_number);
+                    lights.insert(
+                        epoch,
+                        light.clone(),
+                        self.cache_count.load(AtomicOrdering::Relaxed),
+                    );
                     light
                 }
                 Some(light) => light,
             }
         };
+        self.prebuild_next_epoch(block_number);
         light.compute(header_hash, nonce, block_number)
     }
+
+    fn prebuild_next_epoch(&self, block_number: u64) {
+        let distance = self.prebuild_distance.load(AtomicOrdering::Relaxed);
+        let next_epoch = block_number / ETHASH_EPOCH_LENGTH + 1;
+        let next_epoch_start = next_epoch * ETHASH_EPOCH_LENGTH;
+        if distance == 0 || next_epoch_start - block_number > distance {
+            return;
+        }
+
+        {
+            let mut lights = self.cache.lock();
+            let progpow = next_epoch_start >= self.progpow_transition;
+            if lights.prebuilding.is_some() || lights.contains(next_epoch, progpow) {
+                return;
+            }
+            lights.prebuilding = Some(next_epoch);
+        }
+
+        let cache = self.cache.clone();
+        let builder = self.nodecache_builder.clone();
+        let cache_dir = self.cache_dir.clone();
+        let cache_count = self.cache_count.load(AtomicOrdering::Relaxed);
+        let spawned = thread::Builder::new()
+            .name("ethash-prebuild".into())
+            .spawn(move || {
+                debug!("Prebuilding light cache for epoch {}", next_epoch);
+                let light = load_light(&builder, &cache_dir, next_epoch_start);
+                let mut lights = cache.lock();
+                lights.prebuilding = None;
+                lights.insert_prebuilt(next_epoch, light, cache_count);
+            });
+
+        if let Err(e) = spawned {
+            warn!("Failed to spawn light cache prebuild thread: {}", e);
+            self.cache.lock().prebuilding = None;
+        }
+    }
 }
 
 /// Convert an Ethash boundary to its original difficulty. Basically just `f(x) = 2^256 / x`.
@@ -191,6 +269,17 @@ fn difficulty_to_boundary_aux<T: Into<U512>>(difficulty: T) -> ethereum_types::U
     }
 }
 
+#[cfg(test)]
+fn cached_epochs(ethash: &EthashManager) -> Vec<u64> {
+    ethash
+        .cache
+        .lock()
+        .entries
+        .iter()
+        .map(|&(epoch, _)| epoch)
+        .collect()
+}
+
 #[test]
 fn test_lru() {
     use tempdir::TempDir;
@@ -200,14 +289,68 @@ fn test_lru() {
     let hash = [0u8; 32];
     ethash.compute_light(1, &hash, 1);
     ethash.compute_light(50000, &hash, 1);
-    assert_eq!(ethash.cache.lock().recent_epoch.unwrap(), 1);
-    assert_eq!(ethash.cache.lock().prev_epoch.unwrap(), 0);
+    assert_eq!(cached_epochs(&ethash), vec![1, 0]);
     ethash.compute_light(1, &hash, 1);
-    assert_eq!(ethash.cache.lock().recent_epoch.unwrap(), 0);
-    assert_eq!(ethash.cache.lock().prev_epoch.unwrap(), 1);
+    assert_eq!(cached_epochs(&ethash), vec![0, 1]);
     ethash.compute_light(70000, &hash, 1);
-    assert_eq!(ethash.cache.lock().recent_epoch.unwrap(), 2);
-    assert_eq!(ethash.cache.lock().prev_epoch.unwrap(), 0);
+    assert_eq!(cached_epochs(&ethash), vec![2, 0]);
+
+    ethash.set_cache_count(3);
+    ethash.compute_light(90000, &hash, 1);
+    assert_eq!(cached_epochs(&ethash), vec![3, 2, 0]);
+}
+
+#[test]
+fn test_progpow_transition_within_epoch() {
+    use tempdir::TempDir;
+
+    let tempdir = TempDir::new("").unwrap();
+    let ethash = EthashManager::new(tempdir.path(), None, 10);
+    let hash = [0u8; 32];
+
+    // both algorithms share the epoch's cache file but keep separate lights
+    for &block_number in &[5, 11, 5, 11] {
+        let progpow = block_number >= 10;
+        let pow = ethash.compute_light(block_number, &hash, 1);
+        assert_eq!(
+            quick_get_difficulty(&hash, 1, &pow.mix_hash, progpow),
+            pow.value
+        );
+        assert!(ethash.cache.lock().contains(0, progpow));
+    }
+    assert_eq!(cached_epochs(&ethash), vec![0, 0]);
+}
+
+#[test]
+fn test_prebuild_next_epoch() {
+    use std::time::{Duration, Instant};
+    use tempdir::TempDir;
+
+    let tempdir = TempDir::new("").unwrap();
+    let ethash = EthashManager::new(tempdir.path(), None, u64::max_value());
+    ethash.set_prebuild_distance(100);
+    let hash = [0u8; 32];
+
+    // too far away from the boundary
+    ethash.compute_light(ETHASH_EPOCH_LENGTH - 101, &hash, 1);
+    assert!(ethash.cache.lock().prebuilding.is_none());
+    assert_eq!(cached_epochs(&ethash), vec![0]);
+
+    ethash.compute_light(ETHASH_EPOCH_LENGTH - 100, &hash, 1);
+
+    let deadline = Instant::now() + Duration::from_secs(60);
+    while ethash.cache.lock().prebuilding.is_some() {
+        assert!(Instant::now() < deadline, "prebuild did not finish in time");
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(cached_epochs(&ethash), vec![0, 1]);
+
+    let reference_dir = TempDir::new("").unwrap();
+    let reference = EthashManager::new(reference_dir.path(), None, u64::max_value());
+    let expected = reference.compute_light(ETHASH_EPOCH_LENGTH, &hash, 1);
+    let pow = ethash.compute_light(ETHASH_EPOCH_LENGTH, &hash, 1);
+    assert_eq!(pow.value, expected.value);
+    assert_eq!(pow.mix_hash, expected.mix_hash);
 }
 
 #[test]
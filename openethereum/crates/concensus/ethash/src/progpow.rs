@@ -395,6 +395,7 @@ mod test {
 
     use super::*;
     use cache::{NodeCacheBuilder, OptimizeFor};
+    use compute::quick_get_difficulty;
     use keccak::H256;
     use rustc_hex::FromHex;
     use serde_json::{self, Value};
@@ -577,6 +578,12 @@ mod test {
 
             assert_eq!(digest, test.final_hash);
             assert_eq!(result, test.mix_hash);
+
+            // the quick pre-verification must agree with the full light computation
+            assert_eq!(
+                quick_get_difficulty(&test.header_hash, test.nonce, &test.mix_hash, true),
+                test.final_hash
+            );
         }
     }
 }
@@ -16,10 +16,13 @@
 
 //! Manages local node data: pending local transactions, sync security level
 
-use std::{fmt, sync::Arc, time::Duration};
+use std::{collections::HashSet, fmt, sync::Arc, time::Duration};
 
+use crypto::publickey::{ecies, KeyPair};
 use ethcore_db::KeyValueDB;
+use ethereum_types::H256;
 use io::IoHandler;
+use parking_lot::Mutex;
 use types::transaction::{
     Condition as TransactionCondition, PendingTransaction, SignedTransaction, TypedTransaction,
     UnverifiedTransaction,
@@ -28,8 +31,10 @@ use types::transaction::{
 extern crate common_types as types;
 extern crate ethcore_db;
 extern crate ethcore_io as io;
+extern crate ethereum_types;
 extern crate kvdb;
 extern crate parity_crypto as crypto;
+extern crate parking_lot;
 extern crate rlp;
 extern crate serde;
 extern crate serde_json;
@@ -46,6 +51,11 @@ extern crate ethkey;
 extern crate kvdb_memorydb;
 
 const LOCAL_TRANSACTIONS_KEY: &'static [u8] = &*b"LOCAL_TXS";
+const JOURNAL_HEAD_KEY: &'static [u8] = &*b"LOCAL_TXS_JOURNAL";
+const JOURNAL_SEGMENT_PREFIX: &'static [u8] = &*b"LOCAL_TXS_JOURNAL_";
+
+/// First byte of ECIES ciphertexts: the uncompressed ephemeral public key.
+const ENCRYPTED_PREFIX: u8 = 0x04;
 
 const UPDATE_TIMER: ::io::TimerToken = 0;
 const UPDATE_TIMEOUT: Duration = Duration::from_secs(15 * 60); // once every 15 minutes.
@@ -57,6 +67,10 @@ pub enum Error {
     Io(::std::io::Error),
     /// JSON errors.
     Json(::serde_json::Error),
+    /// Encryption errors.
+    Crypto(crypto::publickey::Error),
+    /// Stored data is encrypted but no key was given.
+    MissingKey,
 }
 
 impl fmt::Display for Error {
@@ -64,10 +78,57 @@ impl fmt::Display for Error {
         match *self {
             Error::Io(ref val) => write!(f, "{}", val),
             Error::Json(ref err) => write!(f, "{}", err),
+            Error::Crypto(ref err) => write!(f, "{}", err),
+            Error::MissingKey => write!(f, "Stored data is encrypted but no key was given"),
+        }
+    }
+}
+
+/// Local transaction journal options.
+#[derive(Debug, Clone)]
+pub struct JournalOptions {
+    /// Size in bytes after which the journal continues in a new segment.
+    pub max_segment_size: usize,
+    /// Number of segments kept, the oldest one is dropped when a new one is started.
+    pub max_segments: u64,
+    /// Key stored data is decrypted with, and encrypted with if `encrypt` is set.
+    pub encryption_key: Option<KeyPair>,
+    /// Encrypt newly written journal entries and pending transactions at rest.
+    pub encrypt: bool,
+}
+
+impl Default for JournalOptions {
+    fn default() -> Self {
+        JournalOptions {
+            max_segment_size: 1024 * 1024,
+            max_segments: 8,
+            encryption_key: None,
+            encrypt: false,
         }
     }
 }
 
+/// Read access to the journal of locally submitted transactions.
+pub trait TransactionJournal: Send + Sync {
+    /// All journaled transactions, oldest first.
+    fn journaled_transactions(&self) -> Result<Vec<PendingTransaction>, Error>;
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+struct JournalHead {
+    first: u64,
+    last: u64,
+    /// Number of entries in the last segment.
+    last_entries: u64,
+    /// Size in bytes of the entries in the last segment.
+    last_size: usize,
+}
+
+struct JournalState {
+    head: JournalHead,
+    hashes: HashSet<H256>,
+}
+
 #[derive(Serialize, Deserialize)]
 enum Condition {
     Number(types::BlockNumber),
@@ -140,12 +201,46 @@ pub fn create<T: NodeInfo>(
     db: Arc<dyn KeyValueDB>,
     col: Option<u32>,
     node: T,
-) -> LocalDataStore<T> {
-    LocalDataStore {
+    options: JournalOptions,
+) -> Result<LocalDataStore<T>, Error> {
+    let store = LocalDataStore {
         db: db,
         col: col,
         node: node,
+        options: options,
+        journal: Mutex::new(JournalState {
+            head: JournalHead::default(),
+            hashes: HashSet::new(),
+        }),
+    };
+
+    let head = match store
+        .db
+        .get(store.col, JOURNAL_HEAD_KEY)
+        .map_err(Error::Io)?
+    {
+        Some(val) => ::serde_json::from_slice(&val).map_err(Error::Json)?,
+        None => JournalHead::default(),
+    };
+    let mut hashes = HashSet::new();
+    for index in head.first..=head.last {
+        hashes.extend(store.read_segment(index).into_iter().map(|tx| tx.hash()));
     }
+    *store.journal.lock() = JournalState { head, hashes };
+
+    Ok(store)
+}
+
+fn segment_key(index: u64) -> Vec<u8> {
+    let mut key = JOURNAL_SEGMENT_PREFIX.to_vec();
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+fn entry_key(segment: u64, entry: u64) -> Vec<u8> {
+    let mut key = segment_key(segment);
+    key.extend_from_slice(&entry.to_be_bytes());
+    key
 }
 
 /// Manages local node data.
@@ -156,26 +251,87 @@ pub struct LocalDataStore<T: NodeInfo> {
     db: Arc<dyn KeyValueDB>,
     col: Option<u32>,
     node: T,
+    options: JournalOptions,
+    journal: Mutex<JournalState>,
 }
 
 impl<T: NodeInfo> LocalDataStore<T> {
     /// Attempt to read pending transactions out of the local store.
     pub fn pending_transactions(&self) -> Result<Vec<PendingTransaction>, Error> {
-        if let Some(val) = self
-            .db
-            .get(self.col, LOCAL_TRANSACTIONS_KEY)
-            .map_err(Error::Io)?
-        {
-            let local_txs: Vec<_> = ::serde_json::from_slice::<Vec<TransactionEntry>>(&val)
-                .map_err(Error::Json)?
-                .into_iter()
-                .filter_map(TransactionEntry::into_pending)
-                .collect();
+        self.read_txs(LOCAL_TRANSACTIONS_KEY)
+    }
 
-            Ok(local_txs)
-        } else {
-            Ok(Vec::new())
+    /// Append local pending transactions which are not journaled yet to the journal.
+    pub fn journal(&self) -> Result<(), Error> {
+        self.journal_transactions(self.node.pending_transactions())
+    }
+
+    /// Append the given transactions which are not journaled yet to the journal.
+    ///
+    /// Every transaction is stored as a separate entry, so earlier entries are never rewritten.
+    /// The journal continues in a new segment once the current one exceeds `max_segment_size`,
+    /// dropping the oldest segment beyond `max_segments`.
+    pub fn journal_transactions(&self, txs: Vec<PendingTransaction>) -> Result<(), Error> {
+        let mut journal = self.journal.lock();
+        let new_txs: Vec<PendingTransaction> = txs
+            .into_iter()
+            .filter(|tx| !journal.hashes.contains(&tx.hash()))
+            .collect();
+        if new_txs.is_empty() {
+            return Ok(());
+        }
+
+        trace!(target: "local_store", "Journaling {} local transactions.", new_txs.len());
+
+        let mut head = journal.head;
+        let mut entries = Vec::with_capacity(new_txs.len());
+        for tx in new_txs {
+            let hash = tx.hash();
+            let entry = TransactionEntry::from(tx);
+            let value = self.seal(::serde_json::to_vec(&entry).map_err(Error::Json)?)?;
+            if head.last_entries > 0 && head.last_size + value.len() > self.options.max_segment_size
+            {
+                head.last += 1;
+                head.last_entries = 0;
+                head.last_size = 0;
+            }
+            head.last_size += value.len();
+            entries.push((
+                head.last,
+                entry_key(head.last, head.last_entries),
+                value,
+                hash,
+            ));
+            head.last_entries += 1;
         }
+
+        let mut batch = self.db.transaction();
+        let mut dropped = Vec::new();
+        while head.last - head.first >= self.options.max_segments.max(1) {
+            for (key, value) in self.segment_entries(head.first) {
+                dropped.extend(self.read_entry(&value).map(|tx| tx.hash()));
+                batch.delete(self.col, &key);
+            }
+            head.first += 1;
+        }
+        let mut journaled = Vec::with_capacity(entries.len());
+        // entries of segments dropped right away are not written at all.
+        for (segment, key, value, hash) in entries {
+            if segment >= head.first {
+                batch.put_vec(self.col, &key, value);
+                journaled.push(hash);
+            }
+        }
+        let head_json = ::serde_json::to_vec(&head).map_err(Error::Json)?;
+        batch.put_vec(self.col, JOURNAL_HEAD_KEY, head_json);
+        self.db.write(batch).map_err(Error::Io)?;
+
+        journal.head = head;
+        for hash in dropped {
+            journal.hashes.remove(&hash);
+        }
+        journal.hashes.extend(journaled);
+        Ok(())
     }
 
     /// Update the entries in the database.
@@ -203,12 +359,98 @@ impl<T: NodeInfo> LocalDataStore<T> {
     fn write_txs(&self, txs: &[TransactionEntry]) -> Result<(), Error> {
         let mut batch = self.db.transaction();
 
-        let local_json = ::serde_json::to_value(txs).map_err(Error::Json)?;
-        let json_str = format!("{}", local_json);
+        let local_json = self.seal(entries_json(txs)?)?;
 
-        batch.put_vec(self.col, LOCAL_TRANSACTIONS_KEY, json_str.into_bytes());
+        batch.put_vec(self.col, LOCAL_TRANSACTIONS_KEY, local_json);
         self.db.write(batch).map_err(Error::Io)
     }
+
+    // helper for reading a vector of transaction entries from disk.
+    fn read_txs(&self, key: &[u8]) -> Result<Vec<PendingTransaction>, Error> {
+        if let Some(val) = self.db.get(self.col, key).map_err(Error::Io)? {
+            let local_txs: Vec<_> =
+                ::serde_json::from_slice::<Vec<TransactionEntry>>(&self.open(val.to_vec())?)
+                    .map_err(Error::Json)?
+                    .into_iter()
+                    .filter_map(TransactionEntry::into_pending)
+                    .collect();
+
+            Ok(local_txs)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    // stored entries of a journal segment, in order.
+    fn segment_entries(&self, index: u64) -> Vec<(Box<[u8]>, Box<[u8]>)> {
+        let prefix = segment_key(index);
+        self.db
+            .iter_from_prefix(self.col, &prefix)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .collect()
+    }
+
+    // readable transactions of a journal segment. Entries which can't be read, e.g. because
+    // they were encrypted with a key which isn't available anymore, are skipped.
+    fn read_segment(&self, index: u64) -> Vec<PendingTransaction> {
+        self.segment_entries(index)
+            .into_iter()
+            .filter_map(|(_, value)| self.read_entry(&value))
+            .collect()
+    }
+
+    fn read_entry(&self, value: &[u8]) -> Option<PendingTransaction> {
+        let entry = self.open(value.to_vec()).and_then(|json| {
+            ::serde_json::from_slice::<TransactionEntry>(&json).map_err(Error::Json)
+        });
+        match entry {
+            Ok(entry) => entry.into_pending(),
+            Err(e) => {
+                warn!(target: "local_store", "Skipping unreadable journal entry: {}", e);
+                None
+            }
+        }
+    }
+
+    // encrypts data with the journal key, if encryption is enabled.
+    fn seal(&self, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        match self.options.encryption_key {
+            Some(ref key) if self.options.encrypt => {
+                ecies::encrypt(key.public(), &crypto::DEFAULT_MAC, &data).map_err(Error::Crypto)
+            }
+            _ => Ok(data),
+        }
+    }
+
+    // decrypts data written with a journal key. Plain JSON written while
+    // encryption was disabled is returned as is.
+    fn open(&self, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        if data.first() != Some(&ENCRYPTED_PREFIX) {
+            return Ok(data);
+        }
+        match self.options.encryption_key {
+            Some(ref key) => {
+                ecies::decrypt(key.secret(), &crypto::DEFAULT_MAC, &data).map_err(Error::Crypto)
+            }
+            None => Err(Error::MissingKey),
+        }
+    }
+}
+
+fn entries_json(txs: &[TransactionEntry]) -> Result<Vec<u8>, Error> {
+    let local_json = ::serde_json::to_value(txs).map_err(Error::Json)?;
+    Ok(format!("{}", local_json).into_bytes())
+}
+
+impl<T: NodeInfo> TransactionJournal for LocalDataStore<T> {
+    fn journaled_transactions(&self) -> Result<Vec<PendingTransaction>, Error> {
+        let head = self.journal.lock().head;
+        let mut txs = Vec::new();
+        for index in head.first..=head.last {
+            txs.extend(self.read_segment(index));
+        }
+        Ok(txs)
+    }
 }
 
 impl<T: NodeInfo, M: Send + Sync + 'static> IoHandler<M> for LocalDataStore<T> {
@@ -219,10 +461,13 @@ impl<T: NodeInfo, M: Send + Sync + 'static> IoHandler<M> for LocalDataStore<T> {
     }
 
     fn timeout(&self, _io: &::io::IoContext<M>, timer: ::io::TimerToken) {
-        if let UPDATE_TIMER = timer {
-            if let Err(e) = self.update() {
-                debug!(target: "local_store", "Error updating local store: {}", e);
+        match timer {
+            UPDATE_TIMER => {
+                if let Err(e) = self.update() {
+                    debug!(target: "local_store", "Error updating local store: {}", e);
+                }
             }
+            _ => {}
         }
     }
 }
@@ -231,15 +476,18 @@ impl<T: NodeInfo> Drop for LocalDataStore<T> {
     fn drop(&mut self) {
         debug!(target: "local_store", "Updating node data store on shutdown.");
 
+        let _ = self.journal();
         let _ = self.update();
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::NodeInfo;
+    use super::{JournalOptions, NodeInfo, TransactionJournal};
 
+    use ethcore_db::KeyValueDB;
     use ethkey::Brain;
+    use parking_lot::Mutex;
     use std::sync::Arc;
     use types::transaction::{Condition, PendingTransaction, Transaction, TypedTransaction};
 
@@ -258,12 +506,12 @@ mod tests {
         let db = Arc::new(ethcore_db::InMemoryWithMetrics::create(0));
 
         {
-            let store = super::create(db.clone(), None, Dummy(vec![]));
+            let store = super::create(db.clone(), None, Dummy(vec![]), Default::default()).unwrap();
             assert_eq!(store.pending_transactions().unwrap(), vec![])
         }
 
         {
-            let store = super::create(db.clone(), None, Dummy(vec![]));
+            let store = super::create(db.clone(), None, Dummy(vec![]), Default::default()).unwrap();
             assert_eq!(store.pending_transactions().unwrap(), vec![])
         }
     }
@@ -290,17 +538,23 @@ mod tests {
 
         {
             // nothing written yet, will write pending.
-            let store = super::create(db.clone(), None, Dummy(transactions.clone()));
+            let store = super::create(
+                db.clone(),
+                None,
+                Dummy(transactions.clone()),
+                Default::default(),
+            )
+            .unwrap();
             assert_eq!(store.pending_transactions().unwrap(), vec![])
         }
         {
             // pending written, will write nothing.
-            let store = super::create(db.clone(), None, Dummy(vec![]));
+            let store = super::create(db.clone(), None, Dummy(vec![]), Default::default()).unwrap();
             assert_eq!(store.pending_transactions().unwrap(), transactions)
         }
         {
             // pending removed, will write nothing.
-            let store = super::create(db.clone(), None, Dummy(vec![]));
+            let store = super::create(db.clone(), None, Dummy(vec![]), Default::default()).unwrap();
             assert_eq!(store.pending_transactions().unwrap(), vec![])
         }
     }
@@ -330,15 +584,129 @@ mod tests {
         let db = Arc::new(ethcore_db::InMemoryWithMetrics::create(0));
         {
             // nothing written, will write bad.
-            let store = super::create(db.clone(), None, Dummy(transactions.clone()));
+            let store = super::create(
+                db.clone(),
+                None,
+                Dummy(transactions.clone()),
+                Default::default(),
+            )
+            .unwrap();
             assert_eq!(store.pending_transactions().unwrap(), vec![])
         }
         {
             // try to load transactions. The last transaction, which is invalid, will be skipped.
-            let store = super::create(db.clone(), None, Dummy(vec![]));
+            let store = super::create(db.clone(), None, Dummy(vec![]), Default::default()).unwrap();
             let loaded = store.pending_transactions().unwrap();
             transactions.pop();
             assert_eq!(loaded, transactions);
         }
     }
+
+    struct Shared(Arc<Mutex<Vec<PendingTransaction>>>);
+    impl NodeInfo for Shared {
+        fn pending_transactions(&self) -> Vec<PendingTransaction> {
+            self.0.lock().clone()
+        }
+    }
+
+    fn signed(nonce: u64) -> PendingTransaction {
+        let keypair = Brain::new("abcd".into()).generate();
+        let mut tx = TypedTransaction::Legacy(Transaction::default());
+        tx.tx_mut().nonce = nonce.into();
+        PendingTransaction::new(tx.sign(keypair.secret(), None), None)
+    }
+
+    #[test]
+    fn journal_rotates_segments() {
+        let db = Arc::new(ethcore_db::InMemoryWithMetrics::create(0));
+        let options = JournalOptions {
+            max_segment_size: 1,
+            max_segments: 2,
+            ..Default::default()
+        };
+        let pending = Arc::new(Mutex::new(Vec::new()));
+        let transactions: Vec<_> = (0..4).map(signed).collect();
+
+        {
+            let store =
+                super::create(db.clone(), None, Shared(pending.clone()), options.clone()).unwrap();
+            for tx in &transactions {
+                pending.lock().push(tx.clone());
+                store.journal().unwrap();
+            }
+            // journaled transactions are not appended again.
+            pending.lock().drain(..2);
+            store.journal().unwrap();
+
+            // the first two segments were dropped.
+            assert_eq!(
+                store.journaled_transactions().unwrap(),
+                transactions[2..].to_vec()
+            );
+            pending.lock().clear();
+        }
+
+        let store = super::create(db.clone(), None, Dummy(vec![]), options).unwrap();
+        assert_eq!(
+            store.journaled_transactions().unwrap(),
+            transactions[2..].to_vec()
+        );
+    }
+
+    #[test]
+    fn journal_encrypted_at_rest() {
+        let db = Arc::new(ethcore_db::InMemoryWithMetrics::create(0));
+        let options = JournalOptions {
+            encryption_key: Some(Brain::new("node key".into()).generate()),
+            encrypt: true,
+            ..Default::default()
+        };
+        let transactions = vec![signed(0)];
+
+        {
+            let store = super::create(
+                db.clone(),
+                None,
+                Dummy(transactions.clone()),
+                options.clone(),
+            )
+            .unwrap();
+            store.journal().unwrap();
+            assert_eq!(store.journaled_transactions().unwrap(), transactions);
+        }
+
+        let entry = db.get(None, &super::entry_key(0, 0)).unwrap().unwrap();
+        let pending = db
+            .get(None, super::LOCAL_TRANSACTIONS_KEY)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry[0], super::ENCRYPTED_PREFIX);
+        assert_eq!(pending[0], super::ENCRYPTED_PREFIX);
+
+        {
+            let store = super::create(db.clone(), None, Dummy(vec![]), options.clone()).unwrap();
+            assert_eq!(store.pending_transactions().unwrap(), transactions);
+            assert_eq!(store.journaled_transactions().unwrap(), transactions);
+        }
+
+        {
+            // with encryption disabled earlier entries are still read, new ones are plain.
+            let options = JournalOptions {
+                encrypt: false,
+                ..options
+            };
+            let store = super::create(db.clone(), None, Dummy(vec![signed(1)]), options).unwrap();
+            store.journal().unwrap();
+            assert_eq!(
+                store.journaled_transactions().unwrap(),
+                vec![signed(0), signed(1)]
+            );
+        }
+        let entry = db.get(None, &super::entry_key(0, 1)).unwrap().unwrap();
+        assert_ne!(entry[0], super::ENCRYPTED_PREFIX);
+
+        // without the key encrypted entries are skipped.
+        let store = super::create(db.clone(), None, Dummy(vec![]), Default::default()).unwrap();
+        assert_eq!(store.journaled_transactions().unwrap(), vec![signed(1)]);
+    }
 }
@@ -0,0 +1,319 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Delegates selection of block contents to an external builder.
+
+use ethereum_types::{Address, H256, U256};
+use types::transaction::SignedTransaction;
+
+/// Parameters of the block a payload is requested for.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadAttributes {
+    /// Hash of the parent block.
+    pub parent_hash: H256,
+    /// Number of the block.
+    pub number: u64,
+    /// Timestamp of the block.
+    pub timestamp: u64,
+    /// Gas limit of the block.
+    pub gas_limit: U256,
+    /// Author receiving the block rewards and fees.
+    pub author: Address,
+}
+
+/// Trait for selecting the transactions of a new block.
+///
+/// It is consulted while the miner prepares a block, so it must answer within a bounded time.
+pub trait BlockBuilder: Send + Sync {
+    /// Returns transactions to include in the block, in order.
+    /// `None` makes the miner fall back to its local transaction pool.
+    fn payload(&self, attributes: &PayloadAttributes) -> Option<Vec<SignedTransaction>>;
+
+    /// Whether a payload for a child of `parent_hash` became available since the last call
+    /// to `payload`. The miner then authors a fresh block instead of extending the current one.
+    fn has_payload(&self, _parent_hash: &H256) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "block-builder")]
+pub use self::http::HttpBlockBuilder;
+
+#[cfg(feature = "block-builder")]
+mod http {
+    extern crate fetch;
+    extern crate hyper;
+    extern crate rustc_hex;
+    extern crate url;
+
+    use std::{
+        str,
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use self::{
+        fetch::{Abort, Client as FetchClient, Fetch, Method, Request},
+        hyper::header::{self, HeaderValue},
+        rustc_hex::FromHex,
+        url::Url,
+    };
+    use ethereum_types::H256;
+    use futures::{
+        future::{self, Either},
+        Future, Stream,
+    };
+    use parity_runtime::Executor;
+    use parking_lot::{Condvar, Mutex};
+    use serde_json;
+    use types::transaction::{SignedTransaction, TypedTransaction};
+
+    use super::{BlockBuilder, PayloadAttributes};
+
+    #[derive(Serialize)]
+    struct PayloadRequest<'a> {
+        jsonrpc: &'static str,
+        id: u64,
+        method: &'static str,
+        params: [&'a PayloadAttributes; 1],
+    }
+
+    #[derive(Deserialize)]
+    struct PayloadResponse {
+        result: Option<Payload>,
+        error: Option<serde_json::Value>,
+    }
+
+    #[derive(Deserialize)]
+    struct Payload {
+        transactions: Vec<String>,
+    }
+
+    /// Parent hash and number of the block a payload is for.
+    type PayloadKey = (H256, u64);
+
+    #[derive(Default)]
+    struct State {
+        /// Block of the request in flight or answered last.
+        requested: Option<PayloadKey>,
+        /// Whether the request for `requested` is still in flight.
+        in_flight: bool,
+        /// Answer which hasn't been handed to the miner yet.
+        ready: Option<(PayloadKey, Vec<SignedTransaction>)>,
+    }
+
+    /// Requests block payloads from an external builder with `builder_getPayload`.
+    ///
+    /// The request is sent the first time a block is asked for, and its answer is awaited
+    /// for at most the timeout. Any failure, including no answer within the timeout, falls
+    /// back to the local pool. An answer arriving later is still used for the next block
+    /// prepared on the same parent.
+    pub struct HttpBlockBuilder<F: Fetch = FetchClient> {
+        url: Url,
+        client: F,
+        executor: Executor,
+        timeout: Duration,
+        state: Arc<(Mutex<State>, Condvar)>,
+    }
+
+    impl<F: Fetch> HttpBlockBuilder<F> {
+        /// Create new `HttpBlockBuilder`.
+        pub fn new(
+            url: &str,
+            fetch: F,
+            executor: Executor,
+            timeout: Duration,
+        ) -> Result<Self, String> {
+            let url =
+                Url::parse(url).map_err(|e| format!("Invalid block builder URL {}: {}", url, e))?;
+            Ok(HttpBlockBuilder {
+                url,
+                client: fetch,
+                executor,
+                timeout,
+                state: Default::default(),
+            })
+        }
+
+        fn request(&self, attributes: &PayloadAttributes) {
+            let key = (attributes.parent_hash, attributes.number);
+            let body = match serde_json::to_string(&PayloadRequest {
+                jsonrpc: "2.0",
+                id: attributes.number,
+                method: "builder_getPayload",
+                params: [attributes],
+            }) {
+                Ok(body) => body,
+                Err(e) => {
+                    warn!(target: "miner", "Failed to encode block builder request: {}", e);
+                    self.state.0.lock().in_flight = false;
+                    return;
+                }
+            };
+
+            let url = self.url.clone();
+            let state = self.state.clone();
+            let request = self
+                .client
+                .fetch(
+                    Request::new(self.url.clone(), Method::POST)
+                        .with_header(
+                            header::CONTENT_TYPE,
+                            HeaderValue::from_static("application/json"),
+                        )
+                        .with_body(body),
+                    // the timeout bounds the wait for the answer, not the request itself
+                    Abort::default(),
+                )
+                .map_err(|e| e.to_string())
+                .and_then(|response| {
+                    if !response.is_success() {
+                        return Either::A(future::err(format!(
+                            "Unexpected status {}",
+                            response.status()
+                        )));
+                    }
+                    Either::B(response.concat2().map_err(|e| e.to_string()))
+                })
+                .and_then(|body| decode_payload(&body))
+                .then(move |result| {
+                    let (ref lock, ref answered) = *state;
+                    let mut state = lock.lock();
+                    if state.requested == Some(key) {
+                        state.in_flight = false;
+                    }
+                    match result {
+                        // answers to requests for an older block are dropped
+                        Ok(transactions) if state.requested == Some(key) => {
+                            state.ready = Some((key, transactions));
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            warn!(target: "miner", "Block builder {} failed for block #{}: {}. Falling back to the local pool.", url, key.1, e);
+                        }
+                    }
+                    answered.notify_all();
+                    Ok::<(), ()>(())
+                });
+            self.executor.spawn(request);
+        }
+    }
+
+    /// Decodes RLP encoded transactions returned by the builder.
+    fn decode_payload(body: &[u8]) -> Result<Vec<SignedTransaction>, String> {
+        let body = str::from_utf8(body).map_err(|e| e.to_string())?;
+        let response: PayloadResponse = serde_json::from_str(body).map_err(|e| e.to_string())?;
+        let payload = match (response.result, response.error) {
+            (_, Some(error)) => return Err(format!("Builder returned an error: {}", error)),
+            (Some(payload), None) => payload,
+            (None, None) => return Err("Builder returned an empty response".into()),
+        };
+
+        payload
+            .transactions
+            .iter()
+            .map(|tx| {
+                let bytes: Vec<u8> = tx
+                    .trim_start_matches("0x")
+                    .from_hex()
+                    .map_err(|e| format!("Invalid transaction hex: {}", e))?;
+                let tx = TypedTransaction::decode(&bytes)
+                    .map_err(|e| format!("Invalid transaction RLP: {}", e))?;
+                SignedTransaction::new(tx).map_err(|e| format!("Invalid signature: {}", e))
+            })
+            .collect()
+    }
+
+    impl<F: Fetch> BlockBuilder for HttpBlockBuilder<F> {
+        fn payload(&self, attributes: &PayloadAttributes) -> Option<Vec<SignedTransaction>> {
+            let key = (attributes.parent_hash, attributes.number);
+            let (ref lock, ref answered) = *self.state;
+            {
+                let mut state = lock.lock();
+                match state.ready.take() {
+                    Some((ready, transactions)) if ready == key => return Some(transactions),
+                    _ => {}
+                }
+                // the answer was already waited for once
+                if state.requested == Some(key) {
+                    return None;
+                }
+                state.requested = Some(key);
+                state.in_flight = true;
+            }
+            self.request(attributes);
+
+            let deadline = Instant::now() + self.timeout;
+            let mut state = lock.lock();
+            while state.in_flight && state.requested == Some(key) {
+                if answered.wait_until(&mut state, deadline).timed_out() {
+                    break;
+                }
+            }
+            match state.ready.take() {
+                Some((ready, transactions)) if ready == key => Some(transactions),
+                other => {
+                    state.ready = other;
+                    None
+                }
+            }
+        }
+
+        fn has_payload(&self, parent_hash: &H256) -> bool {
+            self.state
+                .0
+                .lock()
+                .ready
+                .as_ref()
+                .map_or(false, |((parent, _), _)| parent == parent_hash)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crypto::publickey::{Generator, Random};
+        use rustc_hex::ToHex;
+        use types::transaction::{Action, Transaction};
+
+        #[test]
+        fn should_decode_builder_payload() {
+            let keypair = Random.generate();
+            let signed = TypedTransaction::Legacy(Transaction {
+                action: Action::Create,
+                value: 0.into(),
+                data: vec![],
+                gas: 21_000.into(),
+                gas_price: 1.into(),
+                nonce: 0.into(),
+            })
+            .sign(keypair.secret(), None);
+            let raw: String = signed.encode().to_hex();
+
+            let body = format!(
+                r#"{{"jsonrpc":"2.0","id":1,"result":{{"transactions":["0x{}"]}}}}"#,
+                raw
+            );
+            assert_eq!(decode_payload(body.as_bytes()), Ok(vec![signed]));
+
+            let error =
+                r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"no payload"}}"#;
+            assert!(decode_payload(error.as_bytes()).is_err());
+            assert!(decode_payload(b"not json").is_err());
+        }
+    }
+}
@@ -35,6 +35,8 @@ extern crate parking_lot;
 #[cfg(feature = "price-info")]
 extern crate price_info;
 extern crate rlp;
+extern crate serde_json;
+extern crate time_utils;
 extern crate txpool;
 
 #[macro_use]
@@ -55,6 +57,7 @@ extern crate ethkey;
 #[cfg(test)]
 extern crate rustc_hex;
 
+pub mod block_builder;
 pub mod external;
 #[cfg(feature = "price-info")]
 pub mod gas_price_calibrator;
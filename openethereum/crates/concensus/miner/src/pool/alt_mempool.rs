@@ -0,0 +1,285 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Alt-mempool lane for EIP-4337 UserOperation bundles.
+//!
+//! Bundlers submit UserOperations in `handleOps` calls to an EntryPoint contract.
+//! Transactions calling one of the configured entry points are metered against
+//! the limits of the lane instead of competing with regular traffic, and external
+//! bundlers may reserve part of its capacity ahead of submitting their bundles.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ethereum_types::{Address, U256};
+use types::transaction::{self, Action};
+
+/// Validates bundle transactions before they enter the lane.
+pub trait BundleValidator: fmt::Debug + Send + Sync {
+    /// Returns the reason for rejecting the bundle transaction, if any.
+    fn validate(&self, transaction: &transaction::SignedTransaction) -> Result<(), String>;
+}
+
+/// Alt-mempool lane limits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// EntryPoint contracts, the lane is disabled if empty.
+    pub entry_points: HashSet<Address>,
+    /// Maximal number of bundle transactions in the queue.
+    pub max_count: usize,
+    /// Maximal total gas of the bundle transactions in the queue.
+    pub max_gas: Option<U256>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            entry_points: HashSet::new(),
+            max_count: 256,
+            max_gas: None,
+        }
+    }
+}
+
+/// Current usage of the lane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capacity {
+    /// Maximal number of bundle transactions.
+    pub max_count: usize,
+    /// Number of bundle transactions in the queue.
+    pub count: usize,
+    /// Number of slots reserved by bundlers.
+    pub reserved: usize,
+    /// Maximal total gas of bundle transactions.
+    pub max_gas: Option<U256>,
+    /// Total gas of bundle transactions in the queue.
+    pub gas: U256,
+}
+
+impl Capacity {
+    /// Number of slots neither used nor reserved.
+    pub fn available(&self) -> usize {
+        self.max_count
+            .saturating_sub(self.count)
+            .saturating_sub(self.reserved)
+    }
+}
+
+/// Slots of the lane held for a bundler.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reservation {
+    /// Sender of the bundle transactions.
+    pub bundler: Address,
+    /// Number of reserved slots.
+    pub slots: usize,
+    /// Instant after which the remaining slots are released.
+    pub expires_at: Instant,
+}
+
+/// Errors concerning lane reservations.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReservationError {
+    /// No entry points are configured.
+    Disabled,
+    /// Not enough free slots in the lane.
+    InsufficientCapacity {
+        /// Slots which could still be reserved
+        available: usize,
+        /// Slots requested
+        requested: usize,
+    },
+}
+
+impl fmt::Display for ReservationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReservationError::Disabled => write!(f, "Alt-mempool lane is disabled"),
+            ReservationError::InsufficientCapacity {
+                available,
+                requested,
+            } => write!(
+                f,
+                "Requested {} slots but only {} are available",
+                requested, available
+            ),
+        }
+    }
+}
+
+/// Alt-mempool lane state.
+#[derive(Debug, Default)]
+pub struct AltMempool {
+    options: Options,
+    validator: Option<Arc<dyn BundleValidator>>,
+    reservations: HashMap<Address, Reservation>,
+}
+
+impl AltMempool {
+    /// Create new lane with given limits.
+    pub fn new(options: Options) -> Self {
+        AltMempool {
+            options,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the validator run on every bundle transaction.
+    pub fn validator(&self) -> Option<Arc<dyn BundleValidator>> {
+        self.validator.clone()
+    }
+
+    /// Sets the validator run on every bundle transaction.
+    pub fn set_validator(&mut self, validator: Arc<dyn BundleValidator>) {
+        self.validator = Some(validator);
+    }
+
+    /// Returns the entry points of the lane.
+    pub fn entry_points(&self) -> &HashSet<Address> {
+        &self.options.entry_points
+    }
+
+    /// Returns `true` if the transaction calls one of the entry points.
+    pub fn is_bundle(&self, transaction: &transaction::SignedTransaction) -> bool {
+        match transaction.tx().action {
+            Action::Call(ref to) => self.options.entry_points.contains(to),
+            Action::Create => false,
+        }
+    }
+
+    /// Runs the validator on a bundle transaction.
+    pub fn validate(
+        &self,
+        transaction: &transaction::SignedTransaction,
+    ) -> Result<(), transaction::Error> {
+        match self.validator {
+            Some(ref validator) => validator
+                .validate(transaction)
+                .map_err(transaction::Error::InvalidUserOperations),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks whether a bundle transaction fits given the current `count` and `gas` of the
+    /// lane. Slots reserved by other bundlers are not available.
+    ///
+    /// Once the transaction is imported, `commit` must be called to consume the sender's
+    /// reserved slot.
+    pub fn admit(
+        &mut self,
+        transaction: &transaction::SignedTransaction,
+        count: usize,
+        gas: U256,
+    ) -> Result<(), transaction::Error> {
+        self.prune();
+        let sender = transaction.sender();
+        let reserved_for_others: usize = self
+            .reservations
+            .values()
+            .filter(|reservation| reservation.bundler != sender)
+            .map(|reservation| reservation.slots)
+            .sum();
+        let too_many = count + reserved_for_others >= self.options.max_count;
+        let too_much_gas = self
+            .options
+            .max_gas
+            .map_or(false, |max| gas.saturating_add(transaction.tx().gas) > max);
+        if too_many || too_much_gas {
+            return Err(transaction::Error::AltMempoolFull);
+        }
+
+        Ok(())
+    }
+
+    /// Consumes a slot reserved by `sender`, if any, for a bundle transaction that was imported.
+    pub fn commit(&mut self, sender: &Address) {
+        let exhausted = match self.reservations.get_mut(sender) {
+            Some(reservation) => {
+                reservation.slots -= 1;
+                reservation.slots == 0
+            }
+            None => false,
+        };
+        if exhausted {
+            self.reservations.remove(sender);
+        }
+    }
+
+    /// Returns the capacity of the lane given its current `count` and `gas`.
+    pub fn capacity(&mut self, count: usize, gas: U256) -> Capacity {
+        self.prune();
+        Capacity {
+            max_count: self.options.max_count,
+            count,
+            reserved: self.reservations.values().map(|r| r.slots).sum(),
+            max_gas: self.options.max_gas,
+            gas,
+        }
+    }
+
+    /// Reserves `slots` for `bundler` for the next `ttl`, replacing its previous reservation.
+    pub fn reserve(
+        &mut self,
+        bundler: Address,
+        slots: usize,
+        ttl: Duration,
+        count: usize,
+    ) -> Result<Reservation, ReservationError> {
+        if self.options.entry_points.is_empty() {
+            return Err(ReservationError::Disabled);
+        }
+
+        self.prune();
+        let reserved_for_others: usize = self
+            .reservations
+            .values()
+            .filter(|reservation| reservation.bundler != bundler)
+            .map(|reservation| reservation.slots)
+            .sum();
+        let available = self
+            .options
+            .max_count
+            .saturating_sub(count)
+            .saturating_sub(reserved_for_others);
+        if slots > available {
+            return Err(ReservationError::InsufficientCapacity {
+                available,
+                requested: slots,
+            });
+        }
+
+        let reservation = Reservation {
+            bundler,
+            slots,
+            expires_at: Instant::now() + ttl,
+        };
+        if slots == 0 {
+            self.reservations.remove(&bundler);
+        } else {
+            self.reservations.insert(bundler, reservation.clone());
+        }
+        Ok(reservation)
+    }
+
+    fn prune(&mut self) {
+        let now = Instant::now();
+        self.reservations
+            .retain(|_, reservation| reservation.expires_at > now);
+    }
+}
@@ -45,6 +45,8 @@ pub enum TransactionType {
     Regular,
     /// Service transaction (allowed by a contract to have gas_price=0)
     Service,
+    /// System transaction (sent by the address listed in the chain spec, exempt from fees)
+    System,
 }
 
 /// Verification client.
@@ -14,29 +14,41 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Notifier for new transaction hashes.
+//! Notifier for new transactions.
 
-use std::{fmt, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, mem,
+    sync::Arc,
+};
 
-use ethereum_types::H256;
+use ethereum_types::{Address, H256, U256};
+use parking_lot::Mutex;
 use txpool::{self, VerifiedTransaction};
+use types::transaction::Action;
 
-use pool::VerifiedTransaction as Transaction;
+use pool::{
+    local_transactions::LocalTransactionsList, ScoredTransaction,
+    VerifiedTransaction as Transaction,
+};
 
 type Listener = Box<dyn Fn(&[H256]) + Send + Sync>;
+type FullListener = Box<dyn Fn(&[Arc<Transaction>]) + Send + Sync>;
 
 /// Manages notifications to pending transaction listeners.
 #[derive(Default)]
 pub struct Notifier {
     listeners: Vec<Listener>,
-    pending: Vec<H256>,
+    full_listeners: Vec<FullListener>,
+    pending: Vec<Arc<Transaction>>,
 }
 
 impl fmt::Debug for Notifier {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_struct("Notifier")
             .field("listeners", &self.listeners.len())
-            .field("pending", &self.pending)
+            .field("full_listeners", &self.full_listeners.len())
+            .field("pending", &self.pending.len())
             .finish()
     }
 }
@@ -47,13 +59,24 @@ impl Notifier {
         self.listeners.push(f)
     }
 
+    /// Add new listener to receive notifications with the full transactions.
+    pub fn add_full(&mut self, f: FullListener) {
+        self.full_listeners.push(f)
+    }
+
     /// Notify listeners about all currently pending transactions.
     pub fn notify(&mut self) {
         if self.pending.is_empty() {
             return;
         }
 
-        for l in &self.listeners {
+        if !self.listeners.is_empty() {
+            let hashes: Vec<H256> = self.pending.iter().map(|tx| *tx.hash()).collect();
+            for l in &self.listeners {
+                (l)(&hashes);
+            }
+        }
+        for l in &self.full_listeners {
             (l)(&self.pending);
         }
 
@@ -63,7 +86,720 @@ impl Notifier {
 
 impl txpool::Listener<Transaction> for Notifier {
     fn added(&mut self, tx: &Arc<Transaction>, _old: Option<&Arc<Transaction>>) {
-        self.pending.push(*tx.hash());
+        if !self.listeners.is_empty() || !self.full_listeners.is_empty() {
+            self.pending.push(tx.clone());
+        }
+    }
+}
+
+/// Maximal number of events delivered to a webhook in a single request.
+pub const MAX_WEBHOOK_BATCH: usize = 256;
+
+/// Kind of a transaction lifecycle event.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionEventKind {
+    /// Transaction entered the pool.
+    Added,
+    /// Transaction was replaced by another one with the same sender and nonce.
+    Replaced,
+    /// Transaction was removed from the pool without being mined.
+    Dropped,
+    /// Transaction was culled because its nonce is stale.
+    Culled,
+    /// Transaction was culled after being included in a block.
+    Mined,
+}
+
+/// Transaction lifecycle event delivered to webhooks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionEvent {
+    /// Hash of the transaction.
+    pub hash: H256,
+    /// What happened to the transaction.
+    #[serde(rename = "event")]
+    pub kind: TransactionEventKind,
+    /// Why the transaction left the pool.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Hash of the transaction taking its place.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<H256>,
+}
+
+impl TransactionEvent {
+    fn new(tx: &Transaction, kind: TransactionEventKind, reason: Option<&str>) -> Self {
+        TransactionEvent {
+            hash: *tx.hash(),
+            kind,
+            reason: reason.map(Into::into),
+            replaced_by: None,
+        }
+    }
+}
+
+/// Receiver of batched transaction lifecycle events.
+pub trait WebhookSink: Send + Sync {
+    /// Delivers the batch, must not block the pool.
+    fn deliver(&self, events: Vec<TransactionEvent>);
+}
+
+/// Collects lifecycle events of all transactions for the registered webhooks.
+#[derive(Default)]
+pub struct Webhooks {
+    sinks: Vec<Box<dyn WebhookSink>>,
+    pending: Vec<TransactionEvent>,
+    in_chain: Option<Box<dyn Fn(&H256) -> bool + Send + Sync>>,
+}
+
+impl fmt::Debug for Webhooks {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Webhooks")
+            .field("sinks", &self.sinks.len())
+            .field("pending", &self.pending)
+            .field("in_chain", &self.in_chain.is_some())
+            .finish()
+    }
+}
+
+impl Webhooks {
+    /// Add new webhook to receive events.
+    pub fn add(&mut self, sink: Box<dyn WebhookSink>) {
+        self.sinks.push(sink)
+    }
+
+    /// Set blockchain checker telling mined transactions apart from stale ones.
+    pub fn set_in_chain_checker<F>(&mut self, checker: F)
+    where
+        F: Fn(&H256) -> bool + Send + Sync + 'static,
+    {
+        self.in_chain = Some(Box::new(checker));
+    }
+
+    /// Deliver all collected events to the webhooks.
+    pub fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let events = mem::replace(&mut self.pending, Vec::new());
+        for batch in events.chunks(MAX_WEBHOOK_BATCH) {
+            for sink in &self.sinks {
+                sink.deliver(batch.to_vec());
+            }
+        }
+    }
+
+    fn push(&mut self, event: TransactionEvent) {
+        if self.sinks.is_empty() {
+            return;
+        }
+
+        self.pending.push(event);
+        if self.pending.len() >= MAX_WEBHOOK_BATCH {
+            self.flush();
+        }
+    }
+}
+
+impl txpool::Listener<Transaction> for Webhooks {
+    fn added(&mut self, tx: &Arc<Transaction>, old: Option<&Arc<Transaction>>) {
+        if let Some(old) = old {
+            self.push(TransactionEvent {
+                replaced_by: Some(*tx.hash()),
+                ..TransactionEvent::new(old, TransactionEventKind::Replaced, None)
+            });
+        }
+        self.push(TransactionEvent::new(tx, TransactionEventKind::Added, None));
+    }
+
+    fn dropped(&mut self, tx: &Arc<Transaction>, new: Option<&Transaction>) {
+        let reason = match new {
+            Some(_) => "pool limit reached",
+            None => "pool cleared",
+        };
+        self.push(TransactionEvent {
+            replaced_by: new.map(|new| *new.hash()),
+            ..TransactionEvent::new(tx, TransactionEventKind::Dropped, Some(reason))
+        });
+    }
+
+    fn invalid(&mut self, tx: &Arc<Transaction>) {
+        self.push(TransactionEvent::new(
+            tx,
+            TransactionEventKind::Dropped,
+            Some("invalid"),
+        ));
+    }
+
+    fn canceled(&mut self, tx: &Arc<Transaction>) {
+        self.push(TransactionEvent::new(
+            tx,
+            TransactionEventKind::Dropped,
+            Some("canceled"),
+        ));
+    }
+
+    fn expired(&mut self, tx: &Arc<Transaction>) {
+        self.push(TransactionEvent::new(
+            tx,
+            TransactionEventKind::Dropped,
+            Some("expired"),
+        ));
+    }
+
+    fn culled(&mut self, tx: &Arc<Transaction>) {
+        if self.sinks.is_empty() {
+            return;
+        }
+
+        let is_in_chain = self
+            .in_chain
+            .as_ref()
+            .map(|checker| checker(tx.hash()))
+            .unwrap_or(false);
+        let event = if is_in_chain {
+            TransactionEvent::new(tx, TransactionEventKind::Mined, None)
+        } else {
+            TransactionEvent::new(tx, TransactionEventKind::Culled, Some("stale"))
+        };
+        self.push(event);
+    }
+}
+
+/// Remembers whether the transaction asked about last is in the chain.
+///
+/// Listeners are told about a culled transaction one after another, so sharing
+/// the cache between their in-chain checkers looks each transaction up once.
+/// It has to be reset once the pool is culled.
+#[derive(Debug, Default)]
+pub struct InChainCache {
+    last: Mutex<Option<(H256, bool)>>,
+}
+
+impl InChainCache {
+    /// Returns whether `hash` is in the chain, asking `checker` unless `hash` was asked about last.
+    pub fn check<F: Fn(&H256) -> bool>(&self, hash: &H256, checker: F) -> bool {
+        let mut last = self.last.lock();
+        match *last {
+            Some((ref last_hash, is_in_chain)) if last_hash == hash => is_in_chain,
+            _ => {
+                let is_in_chain = checker(hash);
+                *last = Some((*hash, is_in_chain));
+                is_in_chain
+            }
+        }
+    }
+
+    /// Forgets the last answer.
+    pub fn reset(&self) {
+        *self.last.lock() = None;
+    }
+}
+
+/// Numbers of transactions entering and leaving the pool, by reason.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChurnCounters {
+    /// Transactions inserted into the pool.
+    pub inserted: u64,
+    /// Transactions replaced by another one with the same sender and nonce.
+    pub replaced: u64,
+    /// Transactions rejected for a too low gas price.
+    pub dropped_low_fee: u64,
+    /// Transactions pushed out for exceeding the pool limits.
+    pub dropped_limit: u64,
+    /// Transactions marked as invalid.
+    pub dropped_invalid: u64,
+    /// Transactions culled without being included in the chain.
+    pub dropped_stale: u64,
+    /// Transactions removed for exceeding the maximal age.
+    pub dropped_expired: u64,
+    /// Transactions culled, mined or stale.
+    pub culled: u64,
+}
+
+/// Counts transactions entering and leaving the pool.
+#[derive(Default)]
+pub struct Churn {
+    counters: ChurnCounters,
+    in_chain: Option<Box<dyn Fn(&H256) -> bool + Send + Sync>>,
+}
+
+impl fmt::Debug for Churn {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Churn")
+            .field("counters", &self.counters)
+            .field("in_chain", &self.in_chain.is_some())
+            .finish()
+    }
+}
+
+impl Churn {
+    /// Set blockchain checker telling mined transactions apart from stale ones.
+    pub fn set_in_chain_checker<F>(&mut self, checker: F)
+    where
+        F: Fn(&H256) -> bool + Send + Sync + 'static,
+    {
+        self.in_chain = Some(Box::new(checker));
+    }
+
+    /// Returns the counters.
+    pub fn counters(&self) -> &ChurnCounters {
+        &self.counters
+    }
+}
+
+impl txpool::Listener<Transaction> for Churn {
+    fn added(&mut self, tx: &Arc<Transaction>, old: Option<&Arc<Transaction>>) {
+        self.counters.inserted += 1;
+        if let Some(old) = old {
+            if old.sender() == tx.sender() && old.signed().tx().nonce == tx.signed().tx().nonce {
+                self.counters.replaced += 1;
+            } else {
+                self.counters.dropped_limit += 1;
+            }
+        }
+    }
+
+    fn rejected<H: fmt::Debug + fmt::LowerHex>(
+        &mut self,
+        _tx: &Arc<Transaction>,
+        reason: &txpool::Error<H>,
+    ) {
+        match *reason {
+            txpool::Error::TooCheapToEnter(..) | txpool::Error::TooCheapToReplace(..) => {
+                self.counters.dropped_low_fee += 1
+            }
+            txpool::Error::AlreadyImported(_) => {}
+        }
+    }
+
+    fn dropped(&mut self, _tx: &Arc<Transaction>, new: Option<&Transaction>) {
+        if new.is_some() {
+            self.counters.dropped_limit += 1;
+        }
+    }
+
+    fn invalid(&mut self, _tx: &Arc<Transaction>) {
+        self.counters.dropped_invalid += 1;
+    }
+
+    fn culled(&mut self, tx: &Arc<Transaction>) {
+        self.counters.culled += 1;
+        let is_in_chain = self
+            .in_chain
+            .as_ref()
+            .map(|checker| checker(tx.hash()))
+            .unwrap_or(false);
+        if !is_in_chain {
+            self.counters.dropped_stale += 1;
+        }
+    }
+
+    fn expired(&mut self, _tx: &Arc<Transaction>) {
+        self.counters.dropped_expired += 1;
+    }
+}
+
+/// Queued transactions of a sender, for telling future ones apart.
+#[derive(Debug, Default, Clone)]
+struct SenderQueue {
+    /// Nonce of the sender in the latest state, as last seen by the queue.
+    chain_nonce: Option<U256>,
+    /// Memory usage of the queued transactions by nonce, `None` for local ones.
+    transactions: BTreeMap<U256, Option<usize>>,
+    /// Number and memory usage of the non-local future transactions.
+    future: (usize, usize),
+}
+
+impl SenderQueue {
+    fn compute_future(&mut self) {
+        let mut next = match self
+            .chain_nonce
+            .or_else(|| self.transactions.keys().next().cloned())
+        {
+            Some(nonce) => nonce,
+            None => {
+                self.future = (0, 0);
+                return;
+            }
+        };
+        let mut future = (0, 0);
+        let mut gap = false;
+        for (nonce, mem_usage) in self.transactions.range(next..) {
+            if !gap && *nonce == next {
+                next = next.saturating_add(U256::one());
+                continue;
+            }
+            gap = true;
+            if let Some(mem_usage) = *mem_usage {
+                future = (future.0 + 1, future.1 + mem_usage);
+            }
+        }
+        self.future = future;
+    }
+
+    fn is_future(&self, nonce: &U256) -> bool {
+        let mut next = match self.chain_nonce {
+            Some(nonce) => nonce,
+            None => return false,
+        };
+        while self.transactions.contains_key(&next) && next < *nonce {
+            next = next.saturating_add(U256::one());
+        }
+        *nonce > next
+    }
+}
+
+/// Keeps count of the non-local transactions queued behind a nonce gap of their sender.
+///
+/// The nonce a sender's queue starts at is the one last given by `set_chain_nonce`, or the
+/// lowest queued nonce until then.
+#[derive(Debug, Default)]
+pub struct FutureTransactions {
+    senders: HashMap<Address, SenderQueue>,
+    count: usize,
+    mem_usage: usize,
+}
+
+impl FutureTransactions {
+    /// Number of future transactions.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Memory usage of future transactions.
+    pub fn mem_usage(&self) -> usize {
+        self.mem_usage
+    }
+
+    /// Record the nonce of `sender` in the latest state.
+    pub fn set_chain_nonce(&mut self, sender: &Address, nonce: U256) {
+        if self.senders.contains_key(sender) {
+            self.update(sender, |queue| queue.chain_nonce = Some(nonce));
+        }
+    }
+
+    /// Returns the number and memory usage of the future transactions once `tx` is added, or
+    /// `None` if `tx` itself would not be a future transaction.
+    pub fn with_transaction(&self, tx: &Transaction, chain_nonce: U256) -> Option<(usize, usize)> {
+        let nonce = tx.nonce();
+        let mut queue = self.senders.get(tx.sender()).cloned().unwrap_or_default();
+        let before = queue.future;
+        queue.chain_nonce = Some(chain_nonce);
+        if !queue.is_future(&nonce) {
+            return None;
+        }
+        queue.transactions.insert(nonce, Self::mem_usage_of(tx));
+        queue.compute_future();
+        Some((
+            self.count - before.0 + queue.future.0,
+            self.mem_usage - before.1 + queue.future.1,
+        ))
+    }
+
+    fn mem_usage_of(tx: &Transaction) -> Option<usize> {
+        if tx.priority().is_local() {
+            None
+        } else {
+            Some(tx.mem_usage())
+        }
+    }
+
+    fn update<F: FnOnce(&mut SenderQueue)>(&mut self, sender: &Address, f: F) {
+        let queue = self.senders.entry(*sender).or_insert_with(Default::default);
+        self.count -= queue.future.0;
+        self.mem_usage -= queue.future.1;
+        f(queue);
+        queue.compute_future();
+        self.count += queue.future.0;
+        self.mem_usage += queue.future.1;
+        if queue.transactions.is_empty() {
+            self.senders.remove(sender);
+        }
+    }
+
+    fn remove(&mut self, tx: &Transaction) {
+        if self.senders.contains_key(tx.sender()) {
+            self.update(tx.sender(), |queue| {
+                queue.transactions.remove(&tx.nonce());
+            });
+        }
+    }
+}
+
+impl txpool::Listener<Transaction> for FutureTransactions {
+    fn added(&mut self, tx: &Arc<Transaction>, old: Option<&Arc<Transaction>>) {
+        if let Some(old) = old {
+            if old.sender() != tx.sender() {
+                self.remove(old);
+            }
+        }
+        let mem_usage = Self::mem_usage_of(tx);
+        self.update(tx.sender(), |queue| {
+            queue.transactions.insert(tx.nonce(), mem_usage);
+        });
+    }
+
+    fn dropped(&mut self, tx: &Arc<Transaction>, _new: Option<&Transaction>) {
+        self.remove(tx);
+    }
+
+    fn invalid(&mut self, tx: &Arc<Transaction>) {
+        self.remove(tx);
+    }
+
+    fn canceled(&mut self, tx: &Arc<Transaction>) {
+        self.remove(tx);
+    }
+
+    fn culled(&mut self, tx: &Arc<Transaction>) {
+        self.remove(tx);
+    }
+
+    fn expired(&mut self, tx: &Arc<Transaction>) {
+        self.remove(tx);
+    }
+}
+
+/// Keeps count of the bundle transactions in the alt-mempool lane, i.e. those calling one of
+/// the EIP-4337 entry points.
+#[derive(Debug, Default)]
+pub struct BundleTransactions {
+    entry_points: HashSet<Address>,
+    /// Gas limit of the queued bundle transactions by sender and nonce.
+    transactions: HashMap<(Address, U256), U256>,
+    gas: U256,
+}
+
+impl BundleTransactions {
+    /// Number of bundle transactions.
+    pub fn count(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Total gas limit of bundle transactions.
+    pub fn gas(&self) -> U256 {
+        self.gas
+    }
+
+    /// Returns `true` if the transaction calls one of the entry points.
+    pub fn is_bundle(&self, tx: &Transaction) -> bool {
+        match tx.signed().tx().action {
+            Action::Call(ref to) => self.entry_points.contains(to),
+            Action::Create => false,
+        }
+    }
+
+    /// Replace the entry points and recount the given queued transactions.
+    pub fn set_entry_points<'a, I>(&mut self, entry_points: HashSet<Address>, queued: I)
+    where
+        I: IntoIterator<Item = &'a Arc<Transaction>>,
+    {
+        self.entry_points = entry_points;
+        self.transactions.clear();
+        self.gas = U256::zero();
+        for tx in queued {
+            self.insert(tx);
+        }
+    }
+
+    /// Returns the number and total gas of the bundle transactions other than the one `tx`
+    /// would replace, or `None` if `tx` is not a bundle transaction.
+    pub fn without_replaced(&self, tx: &Transaction) -> Option<(usize, U256)> {
+        if !self.is_bundle(tx) {
+            return None;
+        }
+        Some(match self.transactions.get(&(*tx.sender(), tx.nonce())) {
+            Some(gas) => (self.count() - 1, self.gas - *gas),
+            None => (self.count(), self.gas),
+        })
+    }
+
+    fn insert(&mut self, tx: &Transaction) {
+        if self.is_bundle(tx) {
+            let gas = tx.signed().tx().gas;
+            self.gas = self.gas.saturating_add(gas);
+            if let Some(old) = self.transactions.insert((*tx.sender(), tx.nonce()), gas) {
+                self.gas -= old;
+            }
+        }
+    }
+
+    fn remove(&mut self, tx: &Transaction) {
+        if let Some(gas) = self.transactions.remove(&(*tx.sender(), tx.nonce())) {
+            self.gas -= gas;
+        }
+    }
+}
+
+impl txpool::Listener<Transaction> for BundleTransactions {
+    fn added(&mut self, tx: &Arc<Transaction>, old: Option<&Arc<Transaction>>) {
+        if let Some(old) = old {
+            self.remove(old);
+        }
+        self.insert(tx);
+    }
+
+    fn dropped(&mut self, tx: &Arc<Transaction>, _new: Option<&Transaction>) {
+        self.remove(tx);
+    }
+
+    fn invalid(&mut self, tx: &Arc<Transaction>) {
+        self.remove(tx);
+    }
+
+    fn canceled(&mut self, tx: &Arc<Transaction>) {
+        self.remove(tx);
+    }
+
+    fn culled(&mut self, tx: &Arc<Transaction>) {
+        self.remove(tx);
+    }
+
+    fn expired(&mut self, tx: &Arc<Transaction>) {
+        self.remove(tx);
+    }
+}
+
+#[cfg(feature = "webhooks")]
+pub use self::http::HttpWebhook;
+
+#[cfg(feature = "webhooks")]
+mod http {
+    extern crate fetch;
+    extern crate hyper;
+    extern crate url;
+
+    use std::{
+        cmp,
+        time::{Duration, Instant},
+    };
+
+    use self::{
+        fetch::{Client as FetchClient, Fetch, Method, Request},
+        hyper::header::{self, HeaderValue},
+        url::Url,
+    };
+    use futures::{
+        future::{self, Either, Loop},
+        Future,
+    };
+    use parity_runtime::{Delay, Executor};
+    use serde_json;
+
+    use super::{TransactionEvent, WebhookSink};
+
+    /// Delay before the first retry, doubled on every further attempt.
+    const RETRY_DELAY: Duration = Duration::from_millis(500);
+    /// Longest delay between two attempts.
+    const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+    /// Delay before the given retry attempt.
+    fn retry_delay(attempt: usize) -> Duration {
+        2u32.checked_pow(cmp::min(attempt, 32) as u32)
+            .and_then(|factor| RETRY_DELAY.checked_mul(factor))
+            .map_or(MAX_RETRY_DELAY, |delay| cmp::min(delay, MAX_RETRY_DELAY))
+    }
+
+    #[derive(Serialize)]
+    struct Batch<'a> {
+        events: &'a [TransactionEvent],
+    }
+
+    /// POSTs batches of transaction events as JSON to given url.
+    pub struct HttpWebhook {
+        url: Url,
+        client: FetchClient,
+        executor: Executor,
+        retries: usize,
+    }
+
+    impl HttpWebhook {
+        /// Create new `HttpWebhook` retrying failed deliveries up to `retries` times.
+        pub fn new(
+            url: &str,
+            fetch: FetchClient,
+            executor: Executor,
+            retries: usize,
+        ) -> Result<Self, String> {
+            let url = Url::parse(url).map_err(|e| format!("Invalid webhook URL {}: {}", url, e))?;
+            Ok(HttpWebhook {
+                url,
+                client: fetch,
+                executor,
+                retries,
+            })
+        }
+    }
+
+    impl WebhookSink for HttpWebhook {
+        fn deliver(&self, events: Vec<TransactionEvent>) {
+            let body = match serde_json::to_string(&Batch { events: &events }) {
+                Ok(body) => body,
+                Err(e) => {
+                    warn!(target: "txqueue", "Unable to serialize webhook events: {}", e);
+                    return;
+                }
+            };
+
+            let client = self.client.clone();
+            let url = self.url.clone();
+            let retries = self.retries;
+            let count = events.len();
+            self.executor.spawn(future::loop_fn(0, move |attempt| {
+                let url = url.clone();
+                client
+                    .fetch(
+                        Request::new(url.clone(), Method::POST)
+                            .with_header(
+                                header::CONTENT_TYPE,
+                                HeaderValue::from_static("application/json"),
+                            )
+                            .with_body(body.clone()),
+                        Default::default(),
+                    )
+                    .map_err(|e| e.to_string())
+                    .and_then(|response| {
+                        if response.is_success() {
+                            Ok(())
+                        } else {
+                            Err(format!("Unexpected status {}", response.status()))
+                        }
+                    })
+                    .then(move |result| match result {
+                        Ok(()) => Either::A(future::ok::<_, ()>(Loop::Break(()))),
+                        Err(e) if attempt < retries => {
+                            debug!(target: "txqueue", "Webhook {} failed: {}, retrying", url, e);
+                            let delay = retry_delay(attempt);
+                            Either::B(
+                                Delay::new(Instant::now() + delay)
+                                    .then(move |_| Ok::<_, ()>(Loop::Continue(attempt + 1))),
+                            )
+                        }
+                        Err(e) => {
+                            warn!(target: "txqueue", "Dropping {} events for webhook {}: {}", count, url, e);
+                            Either::A(future::ok(Loop::Break(())))
+                        }
+                    })
+            }));
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{retry_delay, MAX_RETRY_DELAY};
+        use std::time::Duration;
+
+        #[test]
+        fn should_cap_retry_delay() {
+            assert_eq!(retry_delay(0), Duration::from_millis(500));
+            assert_eq!(retry_delay(3), Duration::from_secs(4));
+            assert_eq!(retry_delay(7), MAX_RETRY_DELAY);
+            assert_eq!(retry_delay(32), MAX_RETRY_DELAY);
+            assert_eq!(retry_delay(usize::max_value()), MAX_RETRY_DELAY);
+        }
     }
 }
 
@@ -119,13 +855,111 @@ impl txpool::Listener<Transaction> for Logger {
     fn culled(&mut self, tx: &Arc<Transaction>) {
         debug!(target: "txqueue", "[{:?}] Culled or mined.", tx.hash());
     }
+
+    fn expired(&mut self, tx: &Arc<Transaction>) {
+        debug!(target: "txqueue", "[{:?}] Expired.", tx.hash());
+    }
+}
+
+/// Listeners of the transaction queue, notified in the order of the fields.
+#[derive(Debug, Default)]
+pub struct Listeners {
+    /// Tracks the local transactions.
+    pub local: LocalTransactionsList,
+    /// Notifies the pending transaction listeners.
+    pub notifier: Notifier,
+    /// Logs the transaction events.
+    pub logger: Logger,
+    /// Delivers the transaction events to the webhooks.
+    pub webhooks: Webhooks,
+    /// Counts the transaction events.
+    pub churn: Churn,
+    /// Tracks the transactions behind a nonce gap.
+    pub future: FutureTransactions,
+    /// Tracks the bundle transactions of the alt-mempool lane.
+    pub bundles: BundleTransactions,
+}
+
+impl txpool::Listener<Transaction> for Listeners {
+    fn added(&mut self, tx: &Arc<Transaction>, old: Option<&Arc<Transaction>>) {
+        self.local.added(tx, old);
+        self.notifier.added(tx, old);
+        self.logger.added(tx, old);
+        self.webhooks.added(tx, old);
+        self.churn.added(tx, old);
+        self.future.added(tx, old);
+        self.bundles.added(tx, old);
+    }
+
+    fn rejected<H: fmt::Debug + fmt::LowerHex>(
+        &mut self,
+        tx: &Arc<Transaction>,
+        reason: &txpool::Error<H>,
+    ) {
+        self.local.rejected(tx, reason);
+        self.notifier.rejected(tx, reason);
+        self.logger.rejected(tx, reason);
+        self.webhooks.rejected(tx, reason);
+        self.churn.rejected(tx, reason);
+        self.future.rejected(tx, reason);
+        self.bundles.rejected(tx, reason);
+    }
+
+    fn dropped(&mut self, tx: &Arc<Transaction>, new: Option<&Transaction>) {
+        self.local.dropped(tx, new);
+        self.notifier.dropped(tx, new);
+        self.logger.dropped(tx, new);
+        self.webhooks.dropped(tx, new);
+        self.churn.dropped(tx, new);
+        self.future.dropped(tx, new);
+        self.bundles.dropped(tx, new);
+    }
+
+    fn invalid(&mut self, tx: &Arc<Transaction>) {
+        self.local.invalid(tx);
+        self.notifier.invalid(tx);
+        self.logger.invalid(tx);
+        self.webhooks.invalid(tx);
+        self.churn.invalid(tx);
+        self.future.invalid(tx);
+        self.bundles.invalid(tx);
+    }
+
+    fn canceled(&mut self, tx: &Arc<Transaction>) {
+        self.local.canceled(tx);
+        self.notifier.canceled(tx);
+        self.logger.canceled(tx);
+        self.webhooks.canceled(tx);
+        self.churn.canceled(tx);
+        self.future.canceled(tx);
+        self.bundles.canceled(tx);
+    }
+
+    fn culled(&mut self, tx: &Arc<Transaction>) {
+        self.local.culled(tx);
+        self.notifier.culled(tx);
+        self.logger.culled(tx);
+        self.webhooks.culled(tx);
+        self.churn.culled(tx);
+        self.future.culled(tx);
+        self.bundles.culled(tx);
+    }
+
+    fn expired(&mut self, tx: &Arc<Transaction>) {
+        self.local.expired(tx);
+        self.notifier.expired(tx);
+        self.logger.expired(tx);
+        self.webhooks.expired(tx);
+        self.churn.expired(tx);
+        self.future.expired(tx);
+        self.bundles.expired(tx);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use ethereum_types::H160;
-    use parking_lot::Mutex;
     use txpool::Listener;
     use types::transaction;
 
@@ -158,13 +992,244 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_notify_full_listeners() {
+        // given
+        let received = Arc::new(Mutex::new(vec![]));
+        let r = received.clone();
+        let listener = Box::new(move |txs: &[Arc<Transaction>]| {
+            *r.lock() = txs.to_vec();
+        });
+
+        let mut tx_listener = Notifier::default();
+        tx_listener.add_full(listener);
+
+        // when
+        let tx = new_tx();
+        tx_listener.added(&tx, None);
+        tx_listener.notify();
+
+        // then
+        assert_eq!(*received.lock(), vec![tx]);
+    }
+
+    #[test]
+    fn should_deliver_batched_webhook_events() {
+        // given
+        struct Collect(Arc<Mutex<Vec<Vec<TransactionEvent>>>>);
+        impl WebhookSink for Collect {
+            fn deliver(&self, events: Vec<TransactionEvent>) {
+                self.0.lock().push(events);
+            }
+        }
+
+        let received = Arc::new(Mutex::new(vec![]));
+        let mut webhooks = Webhooks::default();
+        webhooks.add(Box::new(Collect(received.clone())));
+        let mined = new_tx();
+        let mined_hash = *mined.hash();
+        webhooks.set_in_chain_checker(move |hash| *hash == mined_hash);
+
+        // when
+        let old = new_tx_with_gas_price(1);
+        webhooks.added(&old, None);
+        webhooks.added(&mined, Some(&old));
+        webhooks.culled(&mined);
+        let stale = new_tx_with_gas_price(2);
+        webhooks.culled(&stale);
+        assert!(received.lock().is_empty());
+
+        // then
+        webhooks.flush();
+        webhooks.flush();
+        let received = received.lock();
+        assert_eq!(received.len(), 1);
+        let kinds: Vec<_> = received[0].iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TransactionEventKind::Added,
+                TransactionEventKind::Replaced,
+                TransactionEventKind::Added,
+                TransactionEventKind::Mined,
+                TransactionEventKind::Culled,
+            ]
+        );
+        assert_eq!(received[0][1].replaced_by, Some(*mined.hash()));
+        assert_eq!(received[0][4].reason, Some("stale".into()));
+    }
+
+    #[test]
+    fn should_count_pool_churn() {
+        // given
+        let mut churn = Churn::default();
+        let mined = new_tx();
+        let mined_hash = *mined.hash();
+        churn.set_in_chain_checker(move |hash| *hash == mined_hash);
+        let old = new_tx_with_gas_price(1);
+
+        // when
+        churn.added(&old, None);
+        churn.added(&mined, Some(&old));
+        churn.rejected(
+            &old,
+            &txpool::Error::TooCheapToEnter(*old.hash(), "0x1".into()),
+        );
+        churn.rejected(&old, &txpool::Error::AlreadyImported(*old.hash()));
+        churn.dropped(&old, Some(&mined));
+        churn.invalid(&old);
+        churn.culled(&mined);
+        churn.culled(&new_tx_with_gas_price(2));
+        churn.expired(&new_tx_with_gas_price(3));
+
+        // then
+        assert_eq!(
+            churn.counters(),
+            &ChurnCounters {
+                inserted: 2,
+                replaced: 1,
+                dropped_low_fee: 1,
+                dropped_limit: 1,
+                dropped_invalid: 1,
+                dropped_stale: 1,
+                dropped_expired: 1,
+                culled: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn should_count_future_transactions() {
+        // given
+        let mut future = FutureTransactions::default();
+        let sender = H160::from_low_u64_be(5);
+        let tx3 = new_tx_with_nonce(3);
+        let tx5 = new_tx_with_nonce(5);
+        let mem_usage = tx5.mem_usage();
+
+        // when
+        future.added(&tx3, None);
+        future.added(&tx5, None);
+        future.set_chain_nonce(&sender, 3.into());
+
+        // then
+        assert_eq!((future.count(), future.mem_usage()), (1, mem_usage));
+        assert_eq!(
+            future.with_transaction(&new_tx_with_nonce(4), 3.into()),
+            None
+        );
+        assert_eq!(
+            future.with_transaction(&new_tx_with_nonce(7), 3.into()),
+            Some((2, 2 * mem_usage))
+        );
+        assert_eq!(
+            future.with_transaction(&new_tx_with_nonce(5), 3.into()),
+            Some((1, mem_usage))
+        );
+
+        // filling the gap makes the transaction ready
+        let tx4 = new_tx_with_nonce(4);
+        future.added(&tx4, None);
+        assert_eq!((future.count(), future.mem_usage()), (0, 0));
+
+        // mining the first one doesn't change anything, removing the middle one does
+        future.culled(&tx3);
+        future.set_chain_nonce(&sender, 4.into());
+        assert_eq!(future.count(), 0);
+        future.dropped(&tx4, None);
+        assert_eq!(future.count(), 1);
+        future.invalid(&tx5);
+        assert_eq!((future.count(), future.mem_usage()), (0, 0));
+    }
+
+    #[test]
+    fn should_count_bundle_transactions() {
+        // given
+        let entry_point = H160::from_low_u64_be(0x4337);
+        let bundle = |nonce: u64, gas: u64| {
+            let signed = transaction::TypedTransaction::Legacy(transaction::Transaction {
+                action: transaction::Action::Call(entry_point),
+                data: vec![1, 2, 3],
+                nonce: nonce.into(),
+                gas: gas.into(),
+                gas_price: 5.into(),
+                value: 0.into(),
+            })
+            .fake_sign(H160::from_low_u64_be(5));
+            Arc::new(Transaction::from_pending_block_transaction(signed))
+        };
+        let mut bundles = BundleTransactions::default();
+        let (tx0, tx1) = (bundle(0, 100_000), bundle(1, 200_000));
+        bundles.added(&tx0, None);
+        assert_eq!(bundles.count(), 0);
+
+        // when
+        bundles.set_entry_points(vec![entry_point].into_iter().collect(), vec![&tx0]);
+        bundles.added(&tx1, None);
+        bundles.added(&new_tx_with_nonce(2), None);
+
+        // then
+        assert_eq!((bundles.count(), bundles.gas()), (2, 300_000.into()));
+        assert_eq!(
+            bundles.without_replaced(&bundle(1, 50_000)),
+            Some((1, 100_000.into()))
+        );
+        assert_eq!(
+            bundles.without_replaced(&bundle(2, 50_000)),
+            Some((2, 300_000.into()))
+        );
+        assert_eq!(bundles.without_replaced(&new_tx_with_nonce(3)), None);
+
+        // replacing and removing bundle transactions updates the totals
+        let replacement = bundle(1, 50_000);
+        bundles.added(&replacement, Some(&tx1));
+        assert_eq!((bundles.count(), bundles.gas()), (2, 150_000.into()));
+        bundles.culled(&tx0);
+        bundles.dropped(&replacement, None);
+        assert_eq!((bundles.count(), bundles.gas()), (0, 0.into()));
+    }
+
+    #[test]
+    fn should_check_chain_once_per_culled_transaction() {
+        // given
+        let cache = InChainCache::default();
+        let lookups = ::std::cell::Cell::new(0);
+        let checker = |_: &H256| {
+            lookups.set(lookups.get() + 1);
+            true
+        };
+        let (a, b) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+
+        // when
+        assert!(cache.check(&a, &checker));
+        assert!(cache.check(&a, &checker));
+        assert!(cache.check(&b, &checker));
+        cache.reset();
+        assert!(cache.check(&b, &checker));
+
+        // then
+        assert_eq!(lookups.get(), 3);
+    }
+
     fn new_tx() -> Arc<Transaction> {
+        new_tx_with_gas_price(5)
+    }
+
+    fn new_tx_with_gas_price(gas_price: u64) -> Arc<Transaction> {
+        new_tx_with(5, gas_price)
+    }
+
+    fn new_tx_with_nonce(nonce: u64) -> Arc<Transaction> {
+        new_tx_with(nonce, 5)
+    }
+
+    fn new_tx_with(nonce: u64, gas_price: u64) -> Arc<Transaction> {
         let signed = transaction::TypedTransaction::Legacy(transaction::Transaction {
             action: transaction::Action::Create,
             data: vec![1, 2, 3],
-            nonce: 5.into(),
+            nonce: nonce.into(),
             gas: 21_000.into(),
-            gas_price: 5.into(),
+            gas_price: gas_price.into(),
             value: 0.into(),
         })
         .fake_sign(H160::from_low_u64_be(5));
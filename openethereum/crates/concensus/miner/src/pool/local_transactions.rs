@@ -16,7 +16,14 @@
 
 //! Local Transactions List.
 
-use std::{fmt, sync::Arc};
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        Arc,
+    },
+};
 
 use ethereum_types::H256;
 use linked_hash_map::LinkedHashMap;
@@ -61,11 +68,16 @@ impl Status {
     }
 }
 
+/// Maximal number of removals kept until they are taken with `take_removed()`.
+const MAX_REMOVED: usize = 1024;
+
 /// Keeps track of local transactions that are in the queue or were mined/dropped recently.
 pub struct LocalTransactionsList {
     max_old: usize,
     transactions: LinkedHashMap<H256, Status>,
     pending: usize,
+    removed: VecDeque<(H256, String)>,
+    has_removed: Arc<AtomicBool>,
     in_chain: Option<Box<dyn Fn(&H256) -> bool + Send + Sync>>,
 }
 
@@ -75,6 +87,7 @@ impl fmt::Debug for LocalTransactionsList {
             .field("max_old", &self.max_old)
             .field("transactions", &self.transactions)
             .field("pending", &self.pending)
+            .field("removed", &self.removed)
             .field("in_chain", &self.in_chain.is_some())
             .finish()
     }
@@ -93,6 +106,8 @@ impl LocalTransactionsList {
             max_old,
             transactions: Default::default(),
             pending: 0,
+            removed: VecDeque::new(),
+            has_removed: Default::default(),
             in_chain: None,
         }
     }
@@ -123,6 +138,26 @@ impl LocalTransactionsList {
         self.pending > 0
     }
 
+    /// Returns and forgets local transactions removed from the pool, other than by being mined,
+    /// together with the reason of their removal.
+    pub fn take_removed(&mut self) -> Vec<(H256, String)> {
+        self.removed.drain(..).collect()
+    }
+
+    /// Returns a flag raised whenever a removal is recorded, so that callers can check for
+    /// removals without locking the list. It is up to them to lower it.
+    pub fn removed_flag(&self) -> Arc<AtomicBool> {
+        self.has_removed.clone()
+    }
+
+    fn note_removed(&mut self, hash: H256, reason: String) {
+        if self.removed.len() == MAX_REMOVED {
+            self.removed.pop_front();
+        }
+        self.removed.push_back((hash, reason));
+        self.has_removed.store(true, AtomicOrdering::SeqCst);
+    }
+
     fn clear_old(&mut self) {
         let number_of_old = self.transactions.len() - self.pending;
         if self.max_old >= number_of_old {
@@ -165,6 +200,7 @@ impl txpool::Listener<Transaction> for LocalTransactionsList {
 
         if let Some(old) = old {
             if self.transactions.contains_key(old.hash()) {
+                self.note_removed(*old.hash(), format!("replaced by {:?}", tx.hash()));
                 self.insert(
                     *old.hash(),
                     Status::Replaced {
@@ -186,6 +222,7 @@ impl txpool::Listener<Transaction> for LocalTransactionsList {
         }
 
         debug!(target: "own_tx", "Transaction rejected (hash {:?}). {}", tx.hash(), reason);
+        self.note_removed(*tx.hash(), format!("rejected: {}", reason));
         self.insert(
             *tx.hash(),
             Status::Rejected(tx.clone(), format!("{}", reason)),
@@ -206,6 +243,7 @@ impl txpool::Listener<Transaction> for LocalTransactionsList {
                 warn!(target: "own_tx", "Transaction dropped because of limit (hash: {:?})", tx.hash())
             }
         }
+        self.note_removed(*tx.hash(), "dropped because of limit".into());
         self.insert(*tx.hash(), Status::Dropped(tx.clone()));
         self.clear_old();
     }
@@ -216,6 +254,7 @@ impl txpool::Listener<Transaction> for LocalTransactionsList {
         }
 
         warn!(target: "own_tx", "Transaction marked invalid (hash {:?})", tx.hash());
+        self.note_removed(*tx.hash(), "invalid".into());
         self.insert(*tx.hash(), Status::Invalid(tx.clone()));
         self.clear_old();
     }
@@ -226,10 +265,22 @@ impl txpool::Listener<Transaction> for LocalTransactionsList {
         }
 
         warn!(target: "own_tx", "Transaction canceled (hash {:?})", tx.hash());
+        self.note_removed(*tx.hash(), "canceled".into());
         self.insert(*tx.hash(), Status::Canceled(tx.clone()));
         self.clear_old();
     }
 
+    fn expired(&mut self, tx: &Arc<Transaction>) {
+        if !tx.priority().is_local() {
+            return;
+        }
+
+        warn!(target: "own_tx", "Transaction expired (hash {:?})", tx.hash());
+        self.note_removed(*tx.hash(), "expired".into());
+        self.insert(*tx.hash(), Status::Dropped(tx.clone()));
+        self.clear_old();
+    }
+
     fn culled(&mut self, tx: &Arc<Transaction>) {
         if !tx.priority().is_local() {
             return;
@@ -247,6 +298,10 @@ impl txpool::Listener<Transaction> for LocalTransactionsList {
         }
 
         info!(target: "own_tx", "Transaction culled (hash {:?})", tx.hash());
+        self.note_removed(
+            *tx.hash(),
+            "culled, another transaction with the same nonce was mined".into(),
+        );
         self.insert(*tx.hash(), Status::Culled(tx.clone()));
     }
 }
@@ -338,6 +393,36 @@ mod tests {
         assert!(list.contains(tx3.hash()));
     }
 
+    #[test]
+    fn should_take_removed_transactions() {
+        // given
+        let mut list = LocalTransactionsList::default();
+        let removed = list.removed_flag();
+        let tx1 = new_tx(10);
+        let tx2 = new_tx(20);
+        let tx3 = new_tx(30);
+        list.added(&tx1, None);
+        list.added(&tx2, None);
+        list.set_in_chain_checker(|_: &_| true);
+        assert!(!removed.load(AtomicOrdering::SeqCst));
+
+        // when
+        list.added(&tx3, Some(&tx1));
+        list.invalid(&tx2);
+        list.culled(&tx3);
+
+        // then
+        assert!(removed.load(AtomicOrdering::SeqCst));
+        assert_eq!(
+            list.take_removed(),
+            vec![
+                (*tx1.hash(), format!("replaced by {:?}", tx3.hash())),
+                (*tx2.hash(), "invalid".into()),
+            ]
+        );
+        assert_eq!(list.take_removed(), vec![]);
+    }
+
     fn new_tx<T: Into<U256>>(nonce: T) -> Arc<Transaction> {
         let keypair = Random.generate();
         let signed = transaction::TypedTransaction::Legacy(transaction::Transaction {
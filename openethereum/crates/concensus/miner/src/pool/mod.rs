@@ -16,15 +16,18 @@
 
 //! Transaction Pool
 
+use std::time::Instant;
+
 use ethereum_types::{Address, H256, U256};
 use parity_util_mem::MallocSizeOfExt;
 use txpool;
-use types::transaction;
+use types::{timestamp::BlockTimestamp, transaction};
 
 mod listener;
 mod queue;
 mod ready;
 
+pub mod alt_mempool;
 pub mod client;
 pub mod local_transactions;
 pub mod replace;
@@ -35,9 +38,17 @@ pub mod verifier;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "webhooks")]
+pub use self::listener::HttpWebhook;
 pub use self::{
-    queue::{Status as QueueStatus, TransactionQueue},
-    txpool::{Options, VerifiedTransaction as PoolVerifiedTransaction},
+    listener::{
+        ChurnCounters, TransactionEvent, TransactionEventKind, WebhookSink, MAX_WEBHOOK_BATCH,
+    },
+    queue::{
+        FutureLimits, NonceGap, SenderNonceState, SenderTransactions, Status as QueueStatus,
+        TransactionQueue,
+    },
+    txpool::{Options, Status as ReadinessStatus, VerifiedTransaction as PoolVerifiedTransaction},
 };
 
 /// How to prioritize transactions in the pool
@@ -64,7 +75,7 @@ pub struct PendingSettings {
     /// Current block number (affects readiness of some transactions).
     pub block_number: u64,
     /// Current timestamp (affects readiness of some transactions).
-    pub current_timestamp: u64,
+    pub current_timestamp: BlockTimestamp,
     /// Nonce cap (for dust protection; EIP-168)
     pub nonce_cap: Option<U256>,
     /// Maximal number of transactions in pending the set.
@@ -81,7 +92,7 @@ pub struct PendingSettings {
 
 impl PendingSettings {
     /// Get all transactions (no cap or len limit) prioritized.
-    pub fn all_prioritized(block_number: u64, current_timestamp: u64) -> Self {
+    pub fn all_prioritized(block_number: u64, current_timestamp: BlockTimestamp) -> Self {
         PendingSettings {
             block_number,
             current_timestamp,
@@ -104,6 +115,11 @@ pub enum Priority {
     /// When block becomes non-canonical we re-import the transactions it contains
     /// to the queue and boost their priority.
     Retracted,
+    /// Transactions from senders on the prioritized list (medium-high priority)
+    ///
+    /// Operators list accounts whose operational transactions have to be
+    /// included ahead of regular traffic during congestion.
+    Prioritized,
     /// Local transactions (high priority)
     ///
     /// Transactions either from a local account or
@@ -134,6 +150,12 @@ pub trait ScoredTransaction {
     /// Gets transaction nonce.
     fn nonce(&self) -> U256;
 
+    /// Gets the number of the best block when the transaction was verified.
+    fn inserted_block(&self) -> u64;
+
+    /// Gets max fee and max priority fee per gas of EIP-1559 style transactions.
+    fn eip1559_fees(&self) -> Option<(U256, U256)>;
+
     /// Calculates maximal transaction cost
     /// (`gas_price` * `gas_limit` + `value`).
     fn cost(&self) -> U256;
@@ -148,6 +170,8 @@ pub struct VerifiedTransaction {
     sender: Address,
     priority: Priority,
     insertion_id: usize,
+    inserted_at: Instant,
+    inserted_block: u64,
 }
 
 impl VerifiedTransaction {
@@ -165,6 +189,8 @@ impl VerifiedTransaction {
             sender,
             priority: Priority::Retracted,
             insertion_id: 0,
+            inserted_at: Instant::now(),
+            inserted_block: 0,
         }
     }
 
@@ -173,6 +199,11 @@ impl VerifiedTransaction {
         self.insertion_id
     }
 
+    /// Gets the time the transaction was verified for insertion.
+    pub fn inserted_at(&self) -> Instant {
+        self.inserted_at
+    }
+
     /// Gets wrapped `SignedTransaction`
     pub fn signed(&self) -> &transaction::SignedTransaction {
         &self.transaction
@@ -223,6 +254,20 @@ impl ScoredTransaction for VerifiedTransaction {
         self.transaction.tx().nonce
     }
 
+    fn inserted_block(&self) -> u64 {
+        self.inserted_block
+    }
+
+    fn eip1559_fees(&self) -> Option<(U256, U256)> {
+        match self.transaction.tx_type() {
+            transaction::TypedTxId::EIP1559Transaction => Some((
+                self.transaction.tx().gas_price,
+                self.transaction.max_priority_fee_per_gas(),
+            )),
+            _ => None,
+        }
+    }
+
     /// Gets maximum potential cost of the transaction.
     fn cost(&self) -> U256 {
         let tx = self.transaction.tx();
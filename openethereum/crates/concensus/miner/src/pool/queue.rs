@@ -21,38 +21,34 @@ use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     fmt,
     sync::{
-        atomic::{self, AtomicUsize},
+        atomic::{self, AtomicBool, AtomicU64, AtomicUsize},
         Arc,
     },
+    time::Duration,
 };
 
 use self::scoring::ScoringEvent;
 use ethereum_types::{Address, H256, U256};
 use parking_lot::RwLock;
-use txpool::{self, Verifier};
-use types::transaction;
+use time_utils::Clock;
+use txpool::{self, VerifiedTransaction, Verifier};
+use types::{timestamp::BlockTimestamp, transaction};
 
 use pool::{
-    self, client, listener,
-    local_transactions::LocalTransactionsList,
-    ready, replace, scoring,
+    self, alt_mempool, client, listener, ready, replace, scoring,
     transaction_filter::{match_filter, TransactionFilter},
     verifier, PendingOrdering, PendingSettings, PrioritizationStrategy,
 };
 
-type Listener = (
-    LocalTransactionsList,
-    (listener::Notifier, listener::Logger),
-);
-type Pool = txpool::Pool<pool::VerifiedTransaction, scoring::NonceAndGasPrice, Listener>;
+type Pool = txpool::Pool<pool::VerifiedTransaction, scoring::NonceAndGasPrice, listener::Listeners>;
 
-/// Max cache time in milliseconds for pending transactions.
+/// Max cache time for pending transactions.
 ///
 /// Pending transactions are cached and will only be computed again
-/// if last cache has been created earler than `TIMESTAMP_CACHE` ms ago.
+/// if last cache has been created earler than `TIMESTAMP_CACHE` ago, measured in block time.
 /// This timeout applies only if there are local pending transactions
 /// since it only affects transaction Condition.
-const TIMESTAMP_CACHE: u64 = 1000;
+const TIMESTAMP_CACHE: Duration = Duration::from_secs(1000);
 
 /// How many senders at once do we attempt to process while culling.
 ///
@@ -71,6 +67,12 @@ pub struct Status {
     pub status: txpool::LightStatus,
     /// Current limits of the transaction pool.
     pub limits: txpool::Options,
+    /// Number of transactions dropped for exceeding the maximal age.
+    pub expired_transactions: usize,
+    /// Number of times the time checked by timestamp conditions moved backwards.
+    pub backwards_clock_jumps: usize,
+    /// Transactions which entered and left the pool, by reason.
+    pub churn: listener::ChurnCounters,
 }
 
 impl fmt::Display for Status {
@@ -89,10 +91,119 @@ impl fmt::Display for Status {
     }
 }
 
+/// Limits of the transactions waiting behind a nonce gap.
+///
+/// Future transactions are also never allowed to push other transactions out of a full pool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FutureLimits {
+    /// Maximal number of future transactions.
+    pub max_count: usize,
+    /// Maximal memory usage of future transactions.
+    pub max_mem_usage: usize,
+}
+
+/// A range of missing nonces in the transactions queued by a sender.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NonceGap {
+    /// First missing nonce.
+    pub start: U256,
+    /// First nonce after the gap, i.e. the nonce of the first blocked transaction.
+    pub end: U256,
+    /// Transactions waiting behind this gap, up to the next one.
+    pub blocked: Vec<H256>,
+}
+
+/// Nonce state of a sender's pooled transactions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SenderNonceState {
+    /// Nonce of the sender in the latest state.
+    pub chain_nonce: U256,
+    /// Nonce following the consecutive pooled transactions starting at `chain_nonce`.
+    pub ready_nonce: U256,
+    /// Gaps keeping the remaining transactions from becoming ready, in nonce order.
+    pub gaps: Vec<NonceGap>,
+}
+
+impl SenderNonceState {
+    /// Computes the state from the sender's `(nonce, hash)` pairs, ordered by nonce.
+    /// Transactions with a nonce below `chain_nonce` are ignored.
+    pub fn new<I>(chain_nonce: U256, transactions: I) -> Self
+    where
+        I: IntoIterator<Item = (U256, H256)>,
+    {
+        let mut ready_nonce = chain_nonce;
+        let mut gaps: Vec<NonceGap> = Vec::new();
+        let mut expected = chain_nonce;
+        for (nonce, hash) in transactions {
+            if nonce < expected {
+                continue;
+            }
+            if nonce > expected {
+                gaps.push(NonceGap {
+                    start: expected,
+                    end: nonce,
+                    blocked: Vec::new(),
+                });
+            }
+            expected = nonce.saturating_add(U256::from(1));
+            match gaps.last_mut() {
+                Some(gap) => gap.blocked.push(hash),
+                None => ready_nonce = expected,
+            }
+        }
+
+        SenderNonceState {
+            chain_nonce,
+            ready_nonce,
+            gaps,
+        }
+    }
+}
+
+/// Pooled transactions of a sender, split at the first nonce gap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SenderTransactions {
+    /// Transactions with consecutive nonces starting at the nonce in the latest state.
+    pub pending: Vec<Arc<pool::VerifiedTransaction>>,
+    /// Transactions waiting behind a nonce gap.
+    pub queued: Vec<Arc<pool::VerifiedTransaction>>,
+}
+
+impl SenderTransactions {
+    /// Splits the sender's transactions, ordered by nonce, at the first gap after `chain_nonce`.
+    /// Transactions with a nonce below `chain_nonce` are ignored.
+    pub fn new<I>(chain_nonce: U256, transactions: I) -> Self
+    where
+        I: IntoIterator<Item = Arc<pool::VerifiedTransaction>>,
+    {
+        let mut pending = Vec::new();
+        let mut queued = Vec::new();
+        let mut expected = Some(chain_nonce);
+        for tx in transactions {
+            let nonce = tx.signed().tx().nonce;
+            if nonce < chain_nonce {
+                continue;
+            }
+            match expected {
+                Some(next) if next == nonce => {
+                    expected = Some(nonce.saturating_add(U256::from(1)));
+                    pending.push(tx);
+                }
+                _ => {
+                    expected = None;
+                    queued.push(tx);
+                }
+            }
+        }
+
+        SenderTransactions { pending, queued }
+    }
+}
+
 #[derive(Debug)]
 struct CachedPending {
     block_number: u64,
-    current_timestamp: u64,
+    current_timestamp: BlockTimestamp,
     nonce_cap: Option<U256>,
     has_local_pending: bool,
     pending: Option<Vec<Arc<pool::VerifiedTransaction>>>,
@@ -104,7 +215,7 @@ impl CachedPending {
     pub fn none() -> Self {
         CachedPending {
             block_number: 0,
-            current_timestamp: 0,
+            current_timestamp: Default::default(),
             has_local_pending: false,
             pending: None,
             nonce_cap: None,
@@ -121,7 +232,7 @@ impl CachedPending {
     pub fn pending(
         &self,
         block_number: u64,
-        current_timestamp: u64,
+        current_timestamp: BlockTimestamp,
         nonce_cap: Option<&U256>,
         max_len: usize,
     ) -> Option<Vec<Arc<pool::VerifiedTransaction>>> {
@@ -131,7 +242,7 @@ impl CachedPending {
     pub fn pending_filtered(
         &self,
         block_number: u64,
-        current_timestamp: u64,
+        current_timestamp: BlockTimestamp,
         nonce_cap: Option<&U256>,
         max_len: usize,
         filter: Option<TransactionFilter>,
@@ -146,7 +257,9 @@ impl CachedPending {
         // In case we don't have any local pending transactions
         // there is no need to invalidate the cache because of timestamp.
         // Timestamp only affects local `PendingTransactions` with `Condition::Timestamp`.
-        if self.has_local_pending && current_timestamp > self.current_timestamp + TIMESTAMP_CACHE {
+        if self.has_local_pending
+            && current_timestamp > self.current_timestamp.saturating_add(TIMESTAMP_CACHE)
+        {
             return None;
         }
 
@@ -228,6 +341,8 @@ const MIN_REJECTED_CACHE_SIZE: usize = 2048;
 #[derive(Debug)]
 pub struct TransactionQueue {
     insertion_id: Arc<AtomicUsize>,
+    /// Number of the best block, imported transactions are tagged with it.
+    best_block: AtomicU64,
     pool: RwLock<Pool>,
     options: RwLock<verifier::Options>,
     /// Cached pending transactions got *with* priority fee enforcement.
@@ -235,6 +350,18 @@ pub struct TransactionQueue {
     /// Cached pending transactions got *without* priority fee enforcement.
     cached_non_enforced_pending: RwLock<CachedPending>,
     recently_rejected: RecentlyRejected,
+    /// Maximal age of non-local transactions.
+    max_age: RwLock<Option<Duration>>,
+    /// Lane metering EIP-4337 bundle transactions.
+    alt_mempool: RwLock<alt_mempool::AltMempool>,
+    /// Separate budget of non-local transactions behind a nonce gap.
+    future_limits: RwLock<Option<FutureLimits>>,
+    /// Time against which timestamp conditions are checked.
+    readiness_clock: ready::ReadinessClock,
+    /// Raised by the local transactions list when it records a removal.
+    local_removed: Arc<AtomicBool>,
+    /// In-chain answers shared by the listeners while culling.
+    in_chain_cache: Arc<listener::InChainCache>,
 }
 
 impl TransactionQueue {
@@ -245,16 +372,21 @@ impl TransactionQueue {
         strategy: PrioritizationStrategy,
     ) -> Self {
         let max_count = limits.max_count;
+        let pool = txpool::Pool::new(
+            Default::default(),
+            scoring::NonceAndGasPrice {
+                strategy,
+                block_base_fee: verification_options.block_base_fee,
+                replacement_bump: verification_options.replacement_bump,
+                sender_premium: verification_options.sender_premium,
+            },
+            limits,
+        );
+        let local_removed = pool.listener().local.removed_flag();
         TransactionQueue {
             insertion_id: Default::default(),
-            pool: RwLock::new(txpool::Pool::new(
-                Default::default(),
-                scoring::NonceAndGasPrice {
-                    strategy,
-                    block_base_fee: verification_options.block_base_fee,
-                },
-                limits,
-            )),
+            best_block: AtomicU64::new(0),
+            pool: RwLock::new(pool),
             options: RwLock::new(verification_options),
             cached_enforced_pending: RwLock::new(CachedPending::none()),
             cached_non_enforced_pending: RwLock::new(CachedPending::none()),
@@ -262,9 +394,69 @@ impl TransactionQueue {
                 MIN_REJECTED_CACHE_SIZE,
                 max_count / 4,
             )),
+            max_age: RwLock::new(None),
+            alt_mempool: RwLock::new(Default::default()),
+            future_limits: RwLock::new(None),
+            readiness_clock: Default::default(),
+            local_removed,
+            in_chain_cache: Default::default(),
         }
     }
 
+    /// Set the limits of the alt-mempool lane for EIP-4337 bundle transactions.
+    ///
+    /// Existing reservations are dropped.
+    pub fn set_alt_mempool_options(&self, options: alt_mempool::Options) {
+        let mut pool = self.pool.write();
+        let queued = {
+            let all = |_tx: &pool::VerifiedTransaction| txpool::Readiness::Ready;
+            pool.unordered_pending(all, Default::default())
+                .collect::<Vec<_>>()
+        };
+        pool.listener_mut()
+            .bundles
+            .set_entry_points(options.entry_points.clone(), &queued);
+
+        let mut alt_mempool = alt_mempool::AltMempool::new(options);
+        if let Some(validator) = self.alt_mempool.read().validator() {
+            alt_mempool.set_validator(validator);
+        }
+        *self.alt_mempool.write() = alt_mempool;
+    }
+
+    /// Sets the validator run on every bundle transaction entering the alt-mempool lane.
+    pub fn set_bundle_validator(&self, validator: Arc<dyn alt_mempool::BundleValidator>) {
+        self.alt_mempool.write().set_validator(validator);
+    }
+
+    /// Set the limits of non-local transactions behind a nonce gap,
+    /// `None` lets them take any part of the pool.
+    pub fn set_future_limits(&self, limits: Option<FutureLimits>) {
+        *self.future_limits.write() = limits;
+    }
+
+    /// Set the clock against which timestamp conditions are checked,
+    /// `None` uses the timestamp of the latest block.
+    pub fn set_readiness_clock(&self, clock: Option<Arc<dyn Clock>>) {
+        self.readiness_clock.set_source(clock);
+    }
+
+    /// Set how far back the time checked by timestamp conditions may step
+    /// before transactions which became ready are considered future again.
+    pub fn set_timestamp_tolerance(&self, tolerance: Duration) {
+        self.readiness_clock.set_tolerance(tolerance);
+    }
+
+    /// Set the maximal age of non-local transactions, after which they are dropped on cull.
+    pub fn set_max_age(&self, max_age: Option<Duration>) {
+        *self.max_age.write() = max_age;
+    }
+
+    /// Set the number of the best block, the sender premium is metered per window of blocks.
+    pub fn set_best_block(&self, number: u64) {
+        self.best_block.store(number, atomic::Ordering::Relaxed);
+    }
+
     /// If latest block has different base fee than it's parent, then transaction pool scoring needs to be updated.
     pub fn update_scoring(&self, block_base_fee: U256) {
         let update_needed = match self.pool.read().scoring().block_base_fee {
@@ -273,10 +465,16 @@ impl TransactionQueue {
         };
 
         if update_needed {
+            let (replacement_bump, sender_premium) = {
+                let options = self.options.read();
+                (options.replacement_bump, options.sender_premium)
+            };
             self.pool.write().set_scoring(
                 scoring::NonceAndGasPrice {
                     strategy: PrioritizationStrategy::GasPriceOnly,
                     block_base_fee: Some(block_base_fee),
+                    replacement_bump,
+                    sender_premium,
                 },
                 ScoringEvent::BlockBaseFeeChanged,
             );
@@ -290,7 +488,29 @@ impl TransactionQueue {
     ///
     /// Some parameters of verification may vary in time (like block gas limit or minimal gas price).
     pub fn set_verifier_options(&self, options: verifier::Options) {
+        let replacement_bump = options.replacement_bump;
+        let sender_premium = options.sender_premium;
         *self.options.write() = options;
+
+        let mut pool = self.pool.write();
+        if pool.scoring().replacement_bump != replacement_bump {
+            let scoring = scoring::NonceAndGasPrice {
+                replacement_bump,
+                ..pool.scoring().clone()
+            };
+            pool.set_scoring(scoring, ScoringEvent::ReplacementBumpChanged);
+        }
+        if pool.scoring().sender_premium != sender_premium {
+            let scoring = scoring::NonceAndGasPrice {
+                sender_premium,
+                ..pool.scoring().clone()
+            };
+            pool.set_scoring(scoring, ScoringEvent::SenderPremiumChanged);
+            drop(pool);
+
+            self.cached_enforced_pending.write().clear();
+            self.cached_non_enforced_pending.write().clear();
+        }
     }
 
     /// Sets the in-chain transaction checker for pool listener.
@@ -298,7 +518,21 @@ impl TransactionQueue {
     where
         F: Fn(&H256) -> bool + Send + Sync + 'static,
     {
-        self.pool.write().listener_mut().0.set_in_chain_checker(f)
+        let cache = self.in_chain_cache.clone();
+        let f = Arc::new(move |hash: &H256| cache.check(hash, &f));
+        let webhooks_f = f.clone();
+        let churn_f = f.clone();
+        let mut pool = self.pool.write();
+        let listeners = pool.listener_mut();
+        listeners
+            .local
+            .set_in_chain_checker(move |hash: &H256| f(hash));
+        listeners
+            .webhooks
+            .set_in_chain_checker(move |hash| webhooks_f(hash));
+        listeners
+            .churn
+            .set_in_chain_checker(move |hash| churn_f(hash));
     }
 
     // t_nb 10.2
@@ -314,6 +548,7 @@ impl TransactionQueue {
         // Run verification
         trace_time!("pool::verify_and_import");
         let options = self.options.read().clone();
+        let max_gas_per_sender = options.max_gas_per_sender;
 
         let transaction_to_replace = {
             if options.no_early_reject {
@@ -332,13 +567,14 @@ impl TransactionQueue {
         let verifier = verifier::Verifier::new(
             client.clone(),
             options,
+            self.best_block.load(atomic::Ordering::Relaxed),
             self.insertion_id.clone(),
             transaction_to_replace,
         );
 
         let mut replace = replace::ReplaceByScoreReadinessAndValidity::new(
             self.pool.read().scoring().clone(),
-            client,
+            client.clone(),
             self.options.read().block_base_fee,
         );
 
@@ -359,7 +595,44 @@ impl TransactionQueue {
                 let imported = verifier
                     .verify_transaction(transaction)
                     .and_then(|verified| {
-                        self.pool.write().import(verified, &mut replace).map_err(convert_error)
+                        let is_bundle = {
+                            let alt_mempool = self.alt_mempool.read();
+                            let is_bundle = alt_mempool.is_bundle(verified.signed());
+                            if is_bundle {
+                                alt_mempool.validate(verified.signed())?;
+                            }
+                            is_bundle
+                        };
+                        let future_limits = self.future_limits.read().clone();
+                        let chain_nonce = match future_limits {
+                            Some(_) if !verified.priority.is_local() => {
+                                Some(client.account_nonce(&verified.sender))
+                            }
+                            _ => None,
+                        };
+                        let sender = verified.sender;
+                        let mut pool = self.pool.write();
+                        if let Some(max_gas) = max_gas_per_sender {
+                            Self::check_sender_gas(&pool, &verified, max_gas)?;
+                        }
+                        if let (Some(limits), Some(chain_nonce)) = (future_limits, chain_nonce) {
+                            Self::check_future_limits(&pool, &verified, chain_nonce, &limits)?;
+                        }
+                        if is_bundle {
+                            self.check_alt_mempool(&pool, &verified)?;
+                        }
+                        pool.import(verified, &mut replace)
+                            .map(|_| {
+                                if is_bundle {
+                                    self.alt_mempool.write().commit(&sender);
+                                }
+                                if let Some(chain_nonce) = chain_nonce {
+                                    pool.listener_mut()
+                                        .future
+                                        .set_chain_nonce(&sender, chain_nonce);
+                                }
+                            })
+                            .map_err(convert_error)
                     });
 
                 match imported {
@@ -373,7 +646,11 @@ impl TransactionQueue {
             .collect::<Vec<_>>();
 
         // Notify about imported transactions.
-        (self.pool.write().listener_mut().1).0.notify();
+        {
+            let mut pool = self.pool.write();
+            pool.listener_mut().notifier.notify();
+            pool.listener_mut().webhooks.flush();
+        }
 
         if results.iter().any(|r| r.is_ok()) {
             self.cached_enforced_pending.write().clear();
@@ -383,6 +660,144 @@ impl TransactionQueue {
         results
     }
 
+    /// Rejects transactions of non-local senders whose transactions already use too much gas.
+    ///
+    /// Must be called with the pool locked for the import, so that the transactions of the
+    /// sender can't change in between. A transaction replacing one with the same nonce doesn't
+    /// count against the limit. The number of transactions per sender is limited by the pool.
+    fn check_sender_gas(
+        pool: &Pool,
+        transaction: &pool::VerifiedTransaction,
+        max_gas: U256,
+    ) -> Result<(), transaction::Error> {
+        if transaction.priority.is_local() {
+            return Ok(());
+        }
+
+        let nonce = transaction.signed().tx().nonce;
+        let all = |_tx: &pool::VerifiedTransaction| txpool::Readiness::Ready;
+        let gas = pool
+            .pending_from_sender(all, &transaction.sender, Default::default())
+            .filter(|tx| tx.signed().tx().nonce != nonce)
+            .fold(U256::zero(), |gas, tx| {
+                gas.saturating_add(tx.signed().tx().gas)
+            });
+
+        if gas.saturating_add(transaction.signed().tx().gas) > max_gas {
+            trace!(
+                target: "txqueue",
+                "[{:?}] Rejected tx, sender {:?} has transactions using {} gas in the queue",
+                transaction.hash,
+                transaction.sender,
+                gas,
+            );
+            return Err(transaction::Error::SenderLimitReached);
+        }
+
+        Ok(())
+    }
+
+    /// Rejects non-local transactions behind a nonce gap once the future budget is used up
+    /// or the pool is full.
+    ///
+    /// Must be called with the pool locked for the import, so that the counts kept by the
+    /// listener can't change in between. A transaction replacing one with the same sender and
+    /// nonce doesn't count against the limits.
+    fn check_future_limits(
+        pool: &Pool,
+        transaction: &pool::VerifiedTransaction,
+        chain_nonce: U256,
+        limits: &FutureLimits,
+    ) -> Result<(), transaction::Error> {
+        let future = &pool.listener().future;
+        let (count, mem_usage) = match future.with_transaction(transaction, chain_nonce) {
+            Some(usage) => usage,
+            None => return Ok(()),
+        };
+
+        if pool.is_full() || count > limits.max_count || mem_usage > limits.max_mem_usage {
+            trace!(
+                target: "txqueue",
+                "[{:?}] Rejected future tx, queue has {} future transactions using {} bytes",
+                transaction.hash,
+                future.count(),
+                future.mem_usage(),
+            );
+            return Err(transaction::Error::LimitReached);
+        }
+
+        Ok(())
+    }
+
+    /// Meters bundle transactions against the alt-mempool lane.
+    ///
+    /// Must be called with the pool locked for the import, so that the usage kept by the
+    /// listener can't change in between. A transaction replacing one with the same sender and
+    /// nonce doesn't count against the limits.
+    fn check_alt_mempool(
+        &self,
+        pool: &Pool,
+        transaction: &pool::VerifiedTransaction,
+    ) -> Result<(), transaction::Error> {
+        let bundles = &pool.listener().bundles;
+        let (count, gas) = match bundles.without_replaced(transaction) {
+            Some(usage) => usage,
+            None => return Ok(()),
+        };
+        let result = self
+            .alt_mempool
+            .write()
+            .admit(transaction.signed(), count, gas);
+        if let Err(ref err) = result {
+            trace!(
+                target: "txqueue",
+                "[{:?}] Rejected bundle tx, lane has {} transactions using {} gas: {}",
+                transaction.hash,
+                count,
+                gas,
+                err,
+            );
+        }
+        result
+    }
+
+    /// Returns the number and total gas of the bundle transactions in the queue.
+    fn alt_mempool_usage(pool: &Pool) -> (usize, U256) {
+        let bundles = &pool.listener().bundles;
+        (bundles.count(), bundles.gas())
+    }
+
+    /// Returns all bundle transactions in the alt-mempool lane.
+    pub fn alt_mempool_transactions(&self) -> Vec<Arc<pool::VerifiedTransaction>> {
+        let all = |_tx: &pool::VerifiedTransaction| txpool::Readiness::Ready;
+        let pool = self.pool.read();
+        let bundles = &pool.listener().bundles;
+        pool.unordered_pending(all, Default::default())
+            .filter(|tx| bundles.is_bundle(tx))
+            .collect()
+    }
+
+    /// Returns the current usage of the alt-mempool lane.
+    pub fn alt_mempool_capacity(&self) -> alt_mempool::Capacity {
+        let pool = self.pool.read();
+        let (count, gas) = Self::alt_mempool_usage(&pool);
+        self.alt_mempool.write().capacity(count, gas)
+    }
+
+    /// Reserves `slots` of the alt-mempool lane for bundle transactions of `bundler`.
+    ///
+    /// The reservation replaces any previous one of the bundler and expires after `ttl`.
+    pub fn reserve_alt_mempool(
+        &self,
+        bundler: Address,
+        slots: usize,
+        ttl: Duration,
+    ) -> Result<alt_mempool::Reservation, alt_mempool::ReservationError> {
+        let pool = self.pool.read();
+        let (count, _) = Self::alt_mempool_usage(&pool);
+        self.alt_mempool.write().reserve(bundler, slots, ttl, count)
+    }
+
     /// Returns all transactions in the queue without explicit ordering.
     pub fn all_transactions(&self) -> Vec<Arc<pool::VerifiedTransaction>> {
         let ready = |_tx: &pool::VerifiedTransaction| txpool::Readiness::Ready;
@@ -439,6 +854,7 @@ impl TransactionQueue {
             includable_boundary,
             enforce_priority_fees,
         } = settings;
+        let current_timestamp = self.readiness_clock.now(current_timestamp);
 
         let cached = if enforce_priority_fees {
             &self.cached_enforced_pending
@@ -534,6 +950,47 @@ impl TransactionQueue {
         )
     }
 
+    /// Returns current pending transactions of given senders, in nonce order per sender.
+    ///
+    /// Only the transactions of `senders` are visited, so unlike `pending` this is cheap
+    /// regardless of the size of the pool.
+    pub fn pending_from_senders<C>(
+        &self,
+        client: C,
+        senders: &BTreeSet<Address>,
+        settings: PendingSettings,
+    ) -> Vec<Arc<pool::VerifiedTransaction>>
+    where
+        C: client::NonceClient,
+    {
+        let effective_priority_fee_filter = self.build_effective_priority_fee_filter(
+            settings.enforce_priority_fees,
+            settings.includable_boundary,
+        );
+        let current_timestamp = self.readiness_clock.now(settings.current_timestamp);
+        let mut ready = Self::ready(
+            client,
+            settings.block_number,
+            current_timestamp,
+            settings.nonce_cap,
+        );
+
+        let pool = self.pool.read();
+        let mut pending = Vec::new();
+        for sender in senders {
+            let is_ready = |tx: &pool::VerifiedTransaction| txpool::Ready::is_ready(&mut ready, tx);
+            pending.extend(
+                pool.pending_from_sender(is_ready, sender, settings.includable_boundary)
+                    .filter(|tx| effective_priority_fee_filter(tx)),
+            );
+            if pending.len() >= settings.max_len {
+                break;
+            }
+        }
+        pending.truncate(settings.max_len);
+        pending
+    }
+
     /// Collect pending transactions.
     ///
     /// NOTE This is re-computing the pending set and it might be expensive to do so.
@@ -543,7 +1000,7 @@ impl TransactionQueue {
         client: C,
         includable_boundary: U256,
         block_number: u64,
-        current_timestamp: u64,
+        current_timestamp: BlockTimestamp,
         nonce_cap: Option<U256>,
         collect: F,
     ) -> T
@@ -554,12 +1011,13 @@ impl TransactionQueue {
                 pool::VerifiedTransaction,
                 (ready::Condition, ready::State<C>),
                 scoring::NonceAndGasPrice,
-                Listener,
+                listener::Listeners,
             >,
         ) -> T,
     {
         debug!(target: "txqueue", "Re-computing pending set for block: {}", block_number);
         trace_time!("pool::collect_pending");
+        let current_timestamp = self.readiness_clock.now(current_timestamp);
         let ready = Self::ready(client, block_number, current_timestamp, nonce_cap);
         collect(self.pool.read().pending(ready, includable_boundary))
     }
@@ -589,7 +1047,7 @@ impl TransactionQueue {
     fn ready<C>(
         client: C,
         block_number: u64,
-        current_timestamp: u64,
+        current_timestamp: BlockTimestamp,
         nonce_cap: Option<U256>,
     ) -> (ready::Condition, ready::State<C>)
     where
@@ -622,7 +1080,9 @@ impl TransactionQueue {
 
         self.recently_rejected.clear();
 
+        let max_age = *self.max_age.read();
         let mut removed = 0;
+        let mut expired = 0;
         let senders: Vec<_> = {
             let pool = self.pool.read();
             let senders = pool.senders().cloned().collect();
@@ -631,9 +1091,42 @@ impl TransactionQueue {
         for chunk in senders.chunks(CULL_SENDERS_CHUNK) {
             trace_time!("pool::cull::chunk");
             let state_readiness = ready::State::new(client.clone(), stale_id, nonce_cap);
-            removed += self.pool.write().cull(Some(chunk), state_readiness);
+            let mut pool = self.pool.write();
+            removed += pool.cull(Some(chunk), state_readiness);
+            // Non-local transactions older than `max_age` are stale regardless of their nonce,
+            // also when they are queued behind younger ones.
+            if let Some(max_age) = max_age {
+                let all = |_tx: &pool::VerifiedTransaction| txpool::Readiness::Ready;
+                let stale = chunk
+                    .iter()
+                    .flat_map(|sender| pool.pending_from_sender(all, sender, Default::default()))
+                    .filter(|tx| !tx.priority.is_local() && tx.inserted_at.elapsed() >= max_age)
+                    .map(|tx| tx.hash)
+                    .collect::<Vec<_>>();
+                for hash in stale {
+                    if pool.expire(&hash).is_some() {
+                        expired += 1;
+                        removed += 1;
+                    }
+                }
+            }
+            if self.future_limits.read().is_some() {
+                let future = &mut pool.listener_mut().future;
+                for sender in chunk {
+                    future.set_chain_nonce(sender, client.account_nonce(sender));
+                }
+            }
         }
-        debug!(target: "txqueue", "Removed {} stalled transactions. {}", removed, self.status());
+        self.in_chain_cache.reset();
+        self.pool.write().listener_mut().webhooks.flush();
+        debug!(target: "txqueue", "Removed {} stalled transactions ({} expired). {}", removed, expired, self.status());
+    }
+
+    /// Splits the queued transactions into stalled, ready and future ones, with nonces of `client`.
+    pub fn readiness_status<C: client::NonceClient>(&self, client: C) -> txpool::Status {
+        self.pool
+            .read()
+            .status(ready::State::new(client, None, None))
     }
 
     /// Returns next valid nonce for given sender
@@ -653,6 +1146,52 @@ impl TransactionQueue {
             .map(|tx| tx.signed().tx().nonce.saturating_add(U256::from(1)))
     }
 
+    /// Returns the nonce state of given sender, including gaps between queued transactions.
+    pub fn sender_nonce_state<C: client::NonceClient>(
+        &self,
+        client: C,
+        address: &Address,
+    ) -> SenderNonceState {
+        let chain_nonce = client.account_nonce(address);
+        let all = |_tx: &pool::VerifiedTransaction| txpool::Readiness::Ready;
+        let transactions = self
+            .pool
+            .read()
+            .pending_from_sender(all, address, Default::default())
+            .map(|tx| (tx.signed().tx().nonce, tx.hash))
+            .collect::<Vec<_>>();
+
+        SenderNonceState::new(chain_nonce, transactions)
+    }
+
+    /// Returns all pooled transactions grouped by sender, split into pending and queued ones.
+    pub fn content<C: client::NonceClient>(
+        &self,
+        client: C,
+    ) -> BTreeMap<Address, SenderTransactions> {
+        let all = |_tx: &pool::VerifiedTransaction| txpool::Readiness::Ready;
+        // nonce lookups can hit the state, so they run after the pool lock is released
+        let by_sender = {
+            let pool = self.pool.read();
+            pool.senders()
+                .map(|sender| {
+                    let transactions = pool
+                        .pending_from_sender(all, sender, Default::default())
+                        .collect::<Vec<_>>();
+                    (*sender, transactions)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        by_sender
+            .into_iter()
+            .map(|(sender, transactions)| {
+                let chain_nonce = client.account_nonce(&sender);
+                (sender, SenderTransactions::new(chain_nonce, transactions))
+            })
+            .collect()
+    }
+
     /// Retrieve a transaction from the pool.
     ///
     /// Given transaction hash looks up that transaction in the pool
@@ -675,10 +1214,12 @@ impl TransactionQueue {
         let results = {
             let mut pool = self.pool.write();
 
-            hashes
+            let results = hashes
                 .into_iter()
                 .map(|hash| pool.remove(hash, is_invalid))
-                .collect::<Vec<_>>()
+                .collect::<Vec<_>>();
+            pool.listener_mut().webhooks.flush();
+            results
         };
 
         if results.iter().any(Option::is_some) {
@@ -691,7 +1232,9 @@ impl TransactionQueue {
 
     /// Clear the entire pool.
     pub fn clear(&self) {
-        self.pool.write().clear();
+        let mut pool = self.pool.write();
+        pool.clear();
+        pool.listener_mut().webhooks.flush();
     }
 
     /// Penalize given senders.
@@ -740,6 +1283,9 @@ impl TransactionQueue {
             options,
             status,
             limits,
+            expired_transactions: pool.listener().churn.counters().dropped_expired as usize,
+            backwards_clock_jumps: self.readiness_clock.backwards_jumps(),
+            churn: pool.listener().churn.counters().clone(),
         }
     }
 
@@ -751,7 +1297,7 @@ impl TransactionQueue {
     /// Local transactions are the ones from accounts managed by this node
     /// and transactions submitted via local RPC (`eth_sendRawTransaction`)
     pub fn has_local_pending_transactions(&self) -> bool {
-        self.pool.read().listener().0.has_pending()
+        self.pool.read().listener().local.has_pending()
     }
 
     /// Returns status of recently seen local transactions.
@@ -759,17 +1305,42 @@ impl TransactionQueue {
         self.pool
             .read()
             .listener()
-            .0
+            .local
             .all_transactions()
             .iter()
             .map(|(a, b)| (*a, b.clone()))
             .collect()
     }
 
+    /// Returns local transactions removed from the pool since the last call, other than
+    /// by being mined, together with the reason of their removal.
+    pub fn take_removed_local_transactions(&self) -> Vec<(H256, String)> {
+        if !self.local_removed.swap(false, atomic::Ordering::SeqCst) {
+            return Vec::new();
+        }
+        self.pool.write().listener_mut().local.take_removed()
+    }
+
     /// Add a callback to be notified about all transactions entering the pool.
     pub fn add_listener(&self, f: Box<dyn Fn(&[H256]) + Send + Sync>) {
         let mut pool = self.pool.write();
-        (pool.listener_mut().1).0.add(f);
+        pool.listener_mut().notifier.add(f);
+    }
+
+    /// Add a callback to be notified about all transactions entering the pool,
+    /// receiving the transactions themselves.
+    pub fn add_full_listener(
+        &self,
+        f: Box<dyn Fn(&[Arc<pool::VerifiedTransaction>]) + Send + Sync>,
+    ) {
+        let mut pool = self.pool.write();
+        pool.listener_mut().notifier.add_full(f);
+    }
+
+    /// Add a webhook to receive lifecycle events of all transactions in the pool.
+    pub fn add_webhook(&self, sink: Box<dyn listener::WebhookSink>) {
+        let mut pool = self.pool.write();
+        pool.listener_mut().webhooks.add(sink);
     }
 
     /// Check if pending set is cached.
@@ -812,7 +1383,7 @@ mod tests {
 
         let pending: Vec<_> = queue.pending(
             TestClient::default(),
-            PendingSettings::all_prioritized(0, 0),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
         );
 
         for tx in pending {
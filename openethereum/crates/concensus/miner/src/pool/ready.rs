@@ -38,11 +38,22 @@
 //! First `Readiness::Future` response also causes all subsequent transactions from the same sender
 //! to be marked as `Future`.
 
-use std::{cmp, collections::HashMap};
+use std::{
+    cmp,
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use ethereum_types::{H160 as Address, U256};
+use parking_lot::Mutex;
+use time_utils::Clock;
 use txpool::{self, VerifiedTransaction as PoolVerifiedTransaction};
-use types::transaction;
+use types::{timestamp::BlockTimestamp, transaction};
 
 use super::{client::NonceClient, VerifiedTransaction};
 
@@ -100,12 +111,12 @@ impl<C: NonceClient> txpool::Ready<VerifiedTransaction> for State<C> {
 #[derive(Debug)]
 pub struct Condition {
     block_number: u64,
-    now: u64,
+    now: BlockTimestamp,
 }
 
 impl Condition {
     /// Create a new condition checker given current block number and UTC timestamp.
-    pub fn new(block_number: u64, now: u64) -> Self {
+    pub fn new(block_number: u64, now: BlockTimestamp) -> Self {
         Condition { block_number, now }
     }
 }
@@ -116,7 +127,7 @@ impl txpool::Ready<VerifiedTransaction> for Condition {
             Some(transaction::Condition::Number(block)) if block > self.block_number => {
                 txpool::Readiness::Future
             }
-            Some(transaction::Condition::Timestamp(time)) if time > self.now => {
+            Some(transaction::Condition::Timestamp(time)) if time > self.now.as_u64() => {
                 txpool::Readiness::Future
             }
             _ => txpool::Readiness::Ready,
@@ -124,6 +135,86 @@ impl txpool::Ready<VerifiedTransaction> for Condition {
     }
 }
 
+struct ClockState {
+    source: Option<Arc<dyn Clock>>,
+    tolerance: Duration,
+    latest: BlockTimestamp,
+}
+
+/// Time against which timestamp conditions are checked.
+///
+/// Uses the block timestamp given by the caller unless a clock is set. The time never moves
+/// backwards by up to `tolerance`, so transactions which became ready don't go back to future
+/// when the host clock steps back (NTP adjustment, VM resume). Larger steps are accepted.
+pub struct ReadinessClock {
+    state: Mutex<ClockState>,
+    backwards_jumps: AtomicUsize,
+}
+
+impl Default for ReadinessClock {
+    fn default() -> Self {
+        ReadinessClock {
+            state: Mutex::new(ClockState {
+                source: None,
+                tolerance: Duration::from_secs(0),
+                latest: Default::default(),
+            }),
+            backwards_jumps: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl fmt::Debug for ReadinessClock {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.state.lock();
+        fmt.debug_struct("ReadinessClock")
+            .field("clock", &state.source.is_some())
+            .field("tolerance", &state.tolerance)
+            .field("latest", &state.latest)
+            .field("backwards_jumps", &self.backwards_jumps)
+            .finish()
+    }
+}
+
+impl ReadinessClock {
+    /// Sets the clock to check against, `None` uses the block timestamps.
+    pub fn set_source(&self, source: Option<Arc<dyn Clock>>) {
+        self.state.lock().source = source;
+    }
+
+    /// Sets how far back the time may step without being observed.
+    pub fn set_tolerance(&self, tolerance: Duration) {
+        self.state.lock().tolerance = tolerance;
+    }
+
+    /// Returns the current time given the latest `block_timestamp`.
+    pub fn now(&self, block_timestamp: BlockTimestamp) -> BlockTimestamp {
+        let mut state = self.state.lock();
+        let observed = match state.source {
+            Some(ref clock) => BlockTimestamp::from_unix_time(clock.unix_now()),
+            None => block_timestamp,
+        };
+
+        if let Some(step) = state.latest.duration_since(observed) {
+            if step > Duration::from_secs(0) {
+                self.backwards_jumps.fetch_add(1, Ordering::Relaxed);
+                if step <= state.tolerance {
+                    return state.latest;
+                }
+                debug!(target: "txqueue", "Readiness time moved back by {:?}, resyncing.", step);
+            }
+        }
+
+        state.latest = observed;
+        observed
+    }
+
+    /// Number of times the time was observed moving backwards.
+    pub fn backwards_jumps(&self) -> usize {
+        self.backwards_jumps.load(Ordering::Relaxed)
+    }
+}
+
 /// Readiness checker that only relies on nonce cache (does actually go to state).
 ///
 /// Checks readiness of transactions by comparing the nonce to state nonce. If nonce
@@ -169,6 +260,7 @@ mod tests {
         client::TestClient,
         tx::{Tx, TxExt},
     };
+    use time_utils::MockClock;
     use txpool::Ready;
 
     #[test]
@@ -250,24 +342,48 @@ mod tests {
 
         // when/then
         assert_eq!(
-            Condition::new(0, 0).is_ready(&tx1),
+            Condition::new(0, BlockTimestamp::new(0)).is_ready(&tx1),
             txpool::Readiness::Future
         );
         assert_eq!(
-            Condition::new(0, 0).is_ready(&tx2),
+            Condition::new(0, BlockTimestamp::new(0)).is_ready(&tx2),
             txpool::Readiness::Future
         );
         assert_eq!(
-            Condition::new(0, 0).is_ready(&tx3),
+            Condition::new(0, BlockTimestamp::new(0)).is_ready(&tx3),
             txpool::Readiness::Ready
         );
         assert_eq!(
-            Condition::new(5, 0).is_ready(&tx1),
+            Condition::new(5, BlockTimestamp::new(0)).is_ready(&tx1),
             txpool::Readiness::Ready
         );
         assert_eq!(
-            Condition::new(0, 3).is_ready(&tx2),
+            Condition::new(0, BlockTimestamp::new(3)).is_ready(&tx2),
             txpool::Readiness::Ready
         );
     }
+
+    #[test]
+    fn should_hold_readiness_time_within_tolerance() {
+        // given
+        let clock = Arc::new(MockClock::with_unix_secs(1_000));
+        let readiness = ReadinessClock::default();
+        readiness.set_source(Some(clock.clone()));
+        readiness.set_tolerance(Duration::from_secs(30));
+        let block_timestamp = BlockTimestamp::new(10);
+
+        // when/then
+        assert_eq!(readiness.now(block_timestamp), BlockTimestamp::new(1_000));
+        clock.rewind(Duration::from_secs(20));
+        assert_eq!(readiness.now(block_timestamp), BlockTimestamp::new(1_000));
+        assert_eq!(readiness.backwards_jumps(), 1);
+        clock.advance(Duration::from_secs(25));
+        assert_eq!(readiness.now(block_timestamp), BlockTimestamp::new(1_005));
+        clock.rewind(Duration::from_secs(100));
+        assert_eq!(readiness.now(block_timestamp), BlockTimestamp::new(905));
+        assert_eq!(readiness.backwards_jumps(), 2);
+
+        readiness.set_source(None);
+        assert_eq!(readiness.now(block_timestamp), block_timestamp);
+    }
 }
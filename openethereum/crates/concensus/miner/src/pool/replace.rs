@@ -33,6 +33,60 @@ use txpool::{
     ReplaceTransaction, VerifiedTransaction,
 };
 
+/// Minimal price increase, in permille, a transaction has to offer to replace
+/// a pooled transaction with the same sender and nonce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplacementBump {
+    /// Bump of the effective gas price.
+    pub gas_price: u32,
+    /// Bump of the max fee per gas when both transactions are EIP-1559 ones.
+    /// Falls back to `gas_price` if only `priority_fee` is set.
+    pub max_fee: Option<u32>,
+    /// Bump of the max priority fee per gas when both transactions are EIP-1559 ones.
+    /// Falls back to `gas_price` if only `max_fee` is set.
+    pub priority_fee: Option<u32>,
+}
+
+impl Default for ReplacementBump {
+    fn default() -> Self {
+        ReplacementBump {
+            gas_price: 125,
+            max_fee: None,
+            priority_fee: None,
+        }
+    }
+}
+
+impl ReplacementBump {
+    /// Checks whether `new` pays enough to replace `old`.
+    ///
+    /// Unless separate EIP-1559 thresholds are configured the effective gas prices are compared.
+    pub fn allows<T: ScoredTransaction>(
+        &self,
+        old: &T,
+        new: &T,
+        block_base_fee: Option<U256>,
+    ) -> bool {
+        if self.max_fee.is_some() || self.priority_fee.is_some() {
+            if let (Some((old_max_fee, old_priority_fee)), Some((new_max_fee, new_priority_fee))) =
+                (old.eip1559_fees(), new.eip1559_fees())
+            {
+                let max_fee = self.max_fee.unwrap_or(self.gas_price);
+                let priority_fee = self.priority_fee.unwrap_or(self.gas_price);
+                return new_max_fee >= bump(old_max_fee, max_fee)
+                    && new_priority_fee >= bump(old_priority_fee, priority_fee);
+            }
+        }
+
+        new.effective_gas_price(block_base_fee)
+            >= bump(old.effective_gas_price(block_base_fee), self.gas_price)
+    }
+}
+
+fn bump(price: U256, permille: u32) -> U256 {
+    price.saturating_add(price.saturating_mul(permille.into()) / 1000)
+}
+
 /// Choose whether to replace based on the sender, the score, the `Readiness`,
 /// and finally the `Validity` of the transactions being compared.
 #[derive(Debug)]
@@ -287,6 +341,8 @@ mod tests {
         let scoring = NonceAndGasPrice {
             strategy: PrioritizationStrategy::GasPriceOnly,
             block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: None,
         };
         let client = TestClient::new().with_nonce(1);
         let replace = ReplaceByScoreReadinessAndValidity::new(scoring, client, None);
@@ -384,6 +440,8 @@ mod tests {
         let scoring = NonceAndGasPrice {
             strategy: PrioritizationStrategy::GasPriceOnly,
             block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: None,
         };
         let client = TestClient::new().with_nonce(1);
         let replace = ReplaceByScoreReadinessAndValidity::new(scoring, client, None);
@@ -449,6 +507,8 @@ mod tests {
         let scoring = NonceAndGasPrice {
             strategy: PrioritizationStrategy::GasPriceOnly,
             block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: None,
         };
         let client = TestClient::new().with_nonce(0).with_balance(1_000_000);
         let replace = ReplaceByScoreReadinessAndValidity::new(scoring, client, None);
@@ -547,6 +607,8 @@ mod tests {
         let scoring = NonceAndGasPrice {
             strategy: PrioritizationStrategy::GasPriceOnly,
             block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: None,
         };
         let client = TestClient::new().with_nonce(1);
         let replace = ReplaceByScoreReadinessAndValidity::new(scoring, client, None);
@@ -579,6 +641,8 @@ mod tests {
         let scoring = NonceAndGasPrice {
             strategy: PrioritizationStrategy::GasPriceOnly,
             block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: None,
         };
         let client = TestClient::new().with_balance(64000);
         let replace = ReplaceByScoreReadinessAndValidity::new(scoring, client, None);
@@ -607,6 +671,8 @@ mod tests {
         let scoring = NonceAndGasPrice {
             strategy: PrioritizationStrategy::GasPriceOnly,
             block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: None,
         };
         let client = TestClient::new().with_nonce(1);
         let replace = ReplaceByScoreReadinessAndValidity::new(scoring, client, None);
@@ -678,6 +744,8 @@ mod tests {
         let scoring = NonceAndGasPrice {
             strategy: PrioritizationStrategy::GasPriceOnly,
             block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: None,
         };
         let client = TestClient::new().with_nonce(1).with_balance(1_000_000);
         let replace = ReplaceByScoreReadinessAndValidity::new(scoring, client, None);
@@ -749,6 +817,8 @@ mod tests {
         let scoring = NonceAndGasPrice {
             strategy: PrioritizationStrategy::GasPriceOnly,
             block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: None,
         };
         let client = TestClient::new().with_nonce(1);
         let replace = ReplaceByScoreReadinessAndValidity::new(scoring, client, None);
@@ -807,6 +877,8 @@ mod tests {
         let scoring = NonceAndGasPrice {
             strategy: PrioritizationStrategy::GasPriceOnly,
             block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: None,
         };
         let client = TestClient::new().with_nonce(1);
         let replace = ReplaceByScoreReadinessAndValidity::new(scoring, client, None);
@@ -859,4 +931,40 @@ mod tests {
 
         assert_eq!(replace.should_replace(&old, &new), RejectNew);
     }
+
+    #[test]
+    fn should_require_twelve_and_a_half_percent_bump_by_default() {
+        let bump = ReplacementBump::default();
+        let old = Tx::gas_price(1000).signed().verified();
+        let too_cheap = Tx::gas_price(1124).signed().verified();
+        let enough = Tx::gas_price(1125).signed().verified();
+        assert!(!bump.allows(&old, &too_cheap, None));
+        assert!(bump.allows(&old, &enough, None));
+    }
+
+    #[test]
+    fn should_require_configured_bump_to_replace() {
+        let bump = ReplacementBump {
+            gas_price: 100,
+            max_fee: None,
+            priority_fee: None,
+        };
+        let old = Tx::gas_price(100).signed().verified();
+        let too_cheap = Tx::gas_price(109).signed().verified();
+        let enough = Tx::gas_price(110).signed().verified();
+        assert!(!bump.allows(&old, &too_cheap, None));
+        assert!(bump.allows(&old, &enough, None));
+
+        // EIP-1559 fees are checked separately once configured
+        let bump = ReplacementBump {
+            gas_price: 100,
+            max_fee: Some(100),
+            priority_fee: Some(500),
+        };
+        let old = Tx::gas_price(100).eip1559_one(10);
+        let max_fee_only = Tx::gas_price(150).eip1559_one(14);
+        let both = Tx::gas_price(110).eip1559_one(15);
+        assert!(!bump.allows(&old.clone().verified(), &max_fee_only.verified(), None));
+        assert!(bump.allows(&old.verified(), &both.verified(), None));
+    }
 }
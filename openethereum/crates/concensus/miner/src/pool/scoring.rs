@@ -26,22 +26,19 @@
 //! Transactions between senders are prioritized using `gas price`. Higher `gas price`
 //! yields more profits for miners. Additionally we prioritize transactions that originate
 //! from our local node (own transactions).
+//!
+//! Optionally senders queueing more than a few transactions within a window of blocks have
+//! to pay a premium for every further one, see `SenderPremium`.
 
-use std::cmp;
+use std::{cmp, ops::Range};
 
-use super::{verifier, PrioritizationStrategy, ScoredTransaction, VerifiedTransaction};
+use super::{
+    replace::ReplacementBump, verifier, PrioritizationStrategy, ScoredTransaction,
+    VerifiedTransaction,
+};
 use ethereum_types::U256;
 use txpool::{self, scoring};
 
-/// Transaction with the same (sender, nonce) can be replaced only if
-/// `new_gas_price > old_gas_price + old_gas_price >> SHIFT`
-const GAS_PRICE_BUMP_SHIFT: usize = 3; // 2 = 25%, 3 = 12.5%, 4 = 6.25%
-
-/// Calculate minimal gas price requirement.
-#[inline]
-fn bump_gas_price(old_gp: U256) -> U256 {
-    old_gp.saturating_add(old_gp >> GAS_PRICE_BUMP_SHIFT)
-}
 /// List of events that trigger updating of scores
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum ScoringEvent {
@@ -49,6 +46,70 @@ pub enum ScoringEvent {
     Penalize,
     /// Every time new block is added to blockchain, block base fee is changed and triggers score change.
     BlockBaseFeeChanged,
+    /// Replacement rules were changed, scores stay the same.
+    ReplacementBumpChanged,
+    /// Sender premium was changed, all scores are recomputed.
+    SenderPremiumChanged,
+}
+
+/// Escalating fee premium for senders queueing many transactions within a block window.
+///
+/// Blocks are grouped into windows of `window` blocks. Of the transactions a non-local sender
+/// has queued during one window, the first `per_window` are scored as usual. The score of each
+/// further one is divided by `1 + premium% * n`, `n` being its position past the allowance,
+/// so that it has to pay a growing premium to keep its place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SenderPremium {
+    /// Length of a window in blocks.
+    pub window: u64,
+    /// Number of transactions of a sender per window without a premium.
+    pub per_window: usize,
+    /// Premium in percent, growing with every additional transaction.
+    pub premium: u32,
+}
+
+impl SenderPremium {
+    /// Applies the premium to the `score` of the sender's `n`-th transaction within a window.
+    pub fn apply(&self, score: U256, n: usize) -> U256 {
+        match (n + 1).saturating_sub(self.per_window) {
+            0 => score,
+            extra => {
+                score.saturating_mul(100.into())
+                    / (U256::from(100) + U256::from(self.premium) * U256::from(extra))
+            }
+        }
+    }
+
+    /// Returns the window of a transaction verified at `block`.
+    fn window_of(&self, block: u64) -> u64 {
+        block / cmp::max(self.window, 1)
+    }
+
+    /// Returns the position of each non-local transaction of a sender among the ones
+    /// queued during the same window, in order of arrival.
+    fn positions<P: ScoredTransaction>(&self, txs: &[txpool::Transaction<P>]) -> Vec<usize> {
+        let mut arrivals = (0..txs.len())
+            .filter(|&i| !txs[i].priority().is_local())
+            .map(|i| {
+                (
+                    self.window_of(txs[i].inserted_block()),
+                    txs[i].insertion_id,
+                    i,
+                )
+            })
+            .collect::<Vec<_>>();
+        arrivals.sort();
+
+        let mut positions = vec![0; txs.len()];
+        let mut previous = None;
+        let mut n = 0;
+        for (window, _, i) in arrivals {
+            n = if previous == Some(window) { n + 1 } else { 0 };
+            previous = Some(window);
+            positions[i] = n;
+        }
+        positions
+    }
 }
 /// Simple, gas-price based scoring for transactions.
 ///
@@ -60,6 +121,10 @@ pub struct NonceAndGasPrice {
     pub strategy: PrioritizationStrategy,
     /// Block base fee. Exists if the EIP 1559 is activated.
     pub block_base_fee: Option<U256>,
+    /// Price bump required to replace a transaction with the same (sender, nonce).
+    pub replacement_bump: ReplacementBump,
+    /// Premium for senders with many transactions in the pool.
+    pub sender_premium: Option<SenderPremium>,
 }
 
 impl NonceAndGasPrice {
@@ -81,6 +146,46 @@ impl NonceAndGasPrice {
 
         old.effective_gas_price(self.block_base_fee) > new.effective_gas_price(self.block_base_fee)
     }
+
+    /// Computes the score of a transaction before the sender premium.
+    fn base_score<P: ScoredTransaction>(&self, tx: &P) -> U256 {
+        let score = tx.effective_gas_price(self.block_base_fee);
+        let boost = match tx.priority() {
+            super::Priority::Local => 15,
+            super::Priority::Prioritized => 12,
+            super::Priority::Retracted => 10,
+            super::Priority::Regular => 0,
+        };
+
+        //boost local and retracted only if they are currently includable (base fee criteria)
+        if self.block_base_fee.is_none() || score >= self.block_base_fee.unwrap() {
+            score << boost
+        } else {
+            score
+        }
+    }
+
+    /// Recomputes the scores of the sender's transactions at `indices` from their base.
+    fn rescore<P: ScoredTransaction>(
+        &self,
+        txs: &[txpool::Transaction<P>],
+        scores: &mut [U256],
+        indices: Range<usize>,
+    ) {
+        let premium = self
+            .sender_premium
+            .map(|premium| (premium, premium.positions(txs)));
+
+        for i in indices {
+            let score = self.base_score(&*txs[i].transaction);
+            scores[i] = match premium {
+                Some((ref premium, ref positions)) if !txs[i].priority().is_local() => {
+                    premium.apply(score, positions[i])
+                }
+                _ => score,
+            };
+        }
+    }
 }
 
 impl<P> txpool::Scoring<P> for NonceAndGasPrice
@@ -99,14 +204,10 @@ where
             return scoring::Choice::InsertNew;
         }
 
-        let old_gp = old.effective_gas_price(self.block_base_fee);
-        let new_gp = new.effective_gas_price(self.block_base_fee);
-
-        let min_required_gp = bump_gas_price(old_gp);
-
-        match min_required_gp.cmp(&new_gp) {
-            cmp::Ordering::Greater => scoring::Choice::RejectNew,
-            _ => scoring::Choice::ReplaceOld,
+        if self.replacement_bump.allows(old, new, self.block_base_fee) {
+            scoring::Choice::ReplaceOld
+        } else {
+            scoring::Choice::RejectNew
         }
     }
 
@@ -119,22 +220,22 @@ where
         use self::scoring::Change;
 
         match change {
-            Change::Culled(_) => {}
-            Change::RemovedAt(_) => {}
+            // Later arrivals within the window of a removed transaction pay less.
+            Change::Culled(_) | Change::RemovedAt(_) => {
+                if self.sender_premium.is_some() {
+                    self.rescore(txs, scores, 0..txs.len());
+                }
+            }
             Change::InsertedAt(i) | Change::ReplacedAt(i) => {
                 assert!(i < txs.len());
                 assert!(i < scores.len());
 
-                scores[i] = txs[i].effective_gas_price(self.block_base_fee);
-                let boost = match txs[i].priority() {
-                    super::Priority::Local => 15,
-                    super::Priority::Retracted => 10,
-                    super::Priority::Regular => 0,
-                };
-
-                //boost local and retracted only if they are currently includable (base fee criteria)
-                if self.block_base_fee.is_none() || scores[i] >= self.block_base_fee.unwrap() {
-                    scores[i] = scores[i] << boost;
+                // An inserted transaction is the latest arrival and doesn't affect the others.
+                match change {
+                    Change::ReplacedAt(_) if self.sender_premium.is_some() => {
+                        self.rescore(txs, scores, 0..txs.len())
+                    }
+                    _ => self.rescore(txs, scores, i..i + 1),
                 }
             }
             // We are only sending an event in case of penalization.
@@ -149,23 +250,10 @@ where
                             }
                         }
                     }
-                    ScoringEvent::BlockBaseFeeChanged => {
-                        for i in 0..txs.len() {
-                            scores[i] = txs[i].transaction.effective_gas_price(self.block_base_fee);
-                            let boost = match txs[i].priority() {
-                                super::Priority::Local => 15,
-                                super::Priority::Retracted => 10,
-                                super::Priority::Regular => 0,
-                            };
-
-                            //boost local and retracted only if they are currently includable (base fee criteria)
-                            if self.block_base_fee.is_none()
-                                || scores[i] >= self.block_base_fee.unwrap()
-                            {
-                                scores[i] = scores[i] << boost;
-                            }
-                        }
+                    ScoringEvent::BlockBaseFeeChanged | ScoringEvent::SenderPremiumChanged => {
+                        self.rescore(txs, scores, 0..txs.len());
                     }
+                    ScoringEvent::ReplacementBumpChanged => {}
                 }
             }
         }
@@ -190,6 +278,8 @@ mod tests {
         let scoring = NonceAndGasPrice {
             strategy: PrioritizationStrategy::GasPriceOnly,
             block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: None,
         };
         let (tx1, tx2, tx3) = Tx::default().signed_triple();
         let transactions = vec![tx1, tx2, tx3]
@@ -247,4 +337,80 @@ mod tests {
         );
         assert_eq!(scores, vec![32768.into(), 128.into(), 0.into()]);
     }
+
+    #[test]
+    fn should_apply_growing_sender_premium() {
+        // given
+        let scoring = NonceAndGasPrice {
+            strategy: PrioritizationStrategy::GasPriceOnly,
+            block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: Some(SenderPremium {
+                window: 10,
+                per_window: 1,
+                premium: 100,
+            }),
+        };
+        let (tx1, tx2, tx3) = Tx::gas_price(600).signed_triple();
+        let mut transactions = vec![tx1, tx2, tx3]
+            .into_iter()
+            .enumerate()
+            .map(|(i, tx)| txpool::Transaction {
+                insertion_id: i as u64,
+                transaction: Arc::new(tx.verified()),
+            })
+            .collect::<Vec<_>>();
+        let mut scores = vec![U256::from(0), 0.into(), 0.into()];
+
+        // when
+        for i in 0..3 {
+            scoring.update_scores(&transactions, &mut *scores, scoring::Change::InsertedAt(i));
+        }
+
+        // then
+        assert_eq!(scores, vec![600.into(), 300.into(), 200.into()]);
+
+        // when the first one is removed
+        transactions.remove(0);
+        scores.remove(0);
+        scoring.update_scores(&transactions, &mut *scores, scoring::Change::RemovedAt(0));
+
+        // then the others move up and pay less
+        assert_eq!(scores, vec![600.into(), 300.into()]);
+    }
+
+    #[test]
+    fn should_not_apply_sender_premium_across_windows() {
+        // given
+        let scoring = NonceAndGasPrice {
+            strategy: PrioritizationStrategy::GasPriceOnly,
+            block_base_fee: None,
+            replacement_bump: Default::default(),
+            sender_premium: Some(SenderPremium {
+                window: 10,
+                per_window: 1,
+                premium: 100,
+            }),
+        };
+        let (tx1, tx2) = Tx::gas_price(600).signed_pair();
+        let mut tx2 = tx2.verified();
+        tx2.inserted_block = 10;
+        let transactions = vec![tx1.verified(), tx2]
+            .into_iter()
+            .enumerate()
+            .map(|(i, tx)| txpool::Transaction {
+                insertion_id: i as u64,
+                transaction: Arc::new(tx),
+            })
+            .collect::<Vec<_>>();
+        let mut scores = vec![U256::from(0), 0.into()];
+
+        // when
+        for i in 0..2 {
+            scoring.update_scores(&transactions, &mut *scores, scoring::Change::InsertedAt(i));
+        }
+
+        // then
+        assert_eq!(scores, vec![600.into(), 600.into()]);
+    }
 }
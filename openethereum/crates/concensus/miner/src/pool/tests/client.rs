@@ -14,7 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::sync::{atomic, Arc};
+use std::{
+    sync::{atomic, Arc},
+    time::Instant,
+};
 
 use ethereum_types::{Address, H256, U256};
 use rlp::Rlp;
@@ -100,6 +103,8 @@ impl TestClient {
             priority: pool::Priority::Regular,
             transaction: tx,
             insertion_id: 1,
+            inserted_at: Instant::now(),
+            inserted_block: 0,
         }
     }
 
@@ -14,14 +14,24 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethereum_types::U256;
+use std::{sync::Arc, thread, time::Duration};
+
+use crypto::publickey::{Generator, KeyPair, Random};
+use ethereum_types::{Address, U256};
 use hash::KECCAK_EMPTY;
+use time_utils::MockClock;
 use txpool;
-use types::transaction::{self, PendingTransaction};
+use types::{
+    timestamp::BlockTimestamp,
+    transaction::{self, PendingTransaction},
+};
 
 use pool::{
-    transaction_filter::TransactionFilter, verifier, PendingOrdering, PendingSettings,
-    PrioritizationStrategy, TransactionQueue,
+    alt_mempool::{self, ReservationError},
+    scoring::SenderPremium,
+    transaction_filter::TransactionFilter,
+    verifier, FutureLimits, NonceGap, PendingOrdering, PendingSettings, PrioritizationStrategy,
+    SenderNonceState, TransactionQueue,
 };
 
 pub mod client;
@@ -52,6 +62,10 @@ fn new_queue() -> TransactionQueue {
             no_early_reject: false,
             block_base_fee: None,
             allow_non_eoa_sender: false,
+            max_gas_per_sender: None,
+            replacement_bump: Default::default(),
+            prioritized_senders: Default::default(),
+            sender_premium: None,
         },
         PrioritizationStrategy::GasPriceOnly,
     )
@@ -72,6 +86,10 @@ fn should_return_correct_nonces_when_dropped_because_of_limit() {
             no_early_reject: false,
             block_base_fee: None,
             allow_non_eoa_sender: false,
+            max_gas_per_sender: None,
+            replacement_bump: Default::default(),
+            prioritized_senders: Default::default(),
+            sender_premium: None,
         },
         PrioritizationStrategy::GasPriceOnly,
     );
@@ -134,6 +152,10 @@ fn should_never_drop_local_transactions_from_different_senders() {
             no_early_reject: false,
             block_base_fee: None,
             allow_non_eoa_sender: false,
+            max_gas_per_sender: None,
+            replacement_bump: Default::default(),
+            prioritized_senders: Default::default(),
+            sender_premium: None,
         },
         PrioritizationStrategy::GasPriceOnly,
     );
@@ -186,7 +208,10 @@ fn should_handle_same_transaction_imported_twice_with_different_state_nonces() {
     // and then there should be only one transaction in current (the one with higher gas_price)
     assert_eq!(res, vec![Ok(())]);
     assert_eq!(txq.status().status.transaction_count, 1);
-    let top = txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0));
+    let top = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
     assert_eq!(top[0].hash, hash);
 }
 
@@ -211,7 +236,10 @@ fn should_move_all_transactions_from_future() {
     // then
     assert_eq!(res, vec![Ok(())]);
     assert_eq!(txq.status().status.transaction_count, 2);
-    let top = txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0));
+    let top = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
     assert_eq!(top[0].hash, hash);
     assert_eq!(top[1].hash, hash2);
 }
@@ -385,7 +413,10 @@ fn should_import_txs_from_same_sender() {
     txq.import(TestClient::new(), txs.local().into_vec());
 
     // then
-    let top = txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0));
+    let top = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
     assert_eq!(top[0].hash, hash);
     assert_eq!(top[1].hash, hash2);
     assert_eq!(top.len(), 2);
@@ -407,7 +438,10 @@ fn should_prioritize_local_transactions_within_same_nonce_height() {
     assert_eq!(res, vec![Ok(()), Ok(())]);
 
     // then
-    let top = txq.pending(client, PendingSettings::all_prioritized(0, 0));
+    let top = txq.pending(
+        client,
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
     assert_eq!(top[0].hash, hash); // local should be first
     assert_eq!(top[1].hash, hash2);
     assert_eq!(top.len(), 2);
@@ -429,7 +463,10 @@ fn should_prioritize_reimported_transactions_within_same_nonce_height() {
     assert_eq!(res, vec![Ok(()), Ok(())]);
 
     // then
-    let top = txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0));
+    let top = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
     assert_eq!(top[0].hash, hash); // retracted should be first
     assert_eq!(top[1].hash, hash2);
     assert_eq!(top.len(), 2);
@@ -448,7 +485,10 @@ fn should_not_prioritize_local_transactions_with_different_nonce_height() {
     assert_eq!(res, vec![Ok(()), Ok(())]);
 
     // then
-    let top = txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0));
+    let top = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
     assert_eq!(top[0].hash, hash);
     assert_eq!(top[1].hash, hash2);
     assert_eq!(top.len(), 2);
@@ -466,7 +506,10 @@ fn should_put_transaction_to_futures_if_gap_detected() {
 
     // then
     assert_eq!(res, vec![Ok(()), Ok(())]);
-    let top = txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0));
+    let top = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
     assert_eq!(top.len(), 1);
     assert_eq!(top[0].hash, hash);
 }
@@ -492,9 +535,15 @@ fn should_handle_min_block() {
     assert_eq!(res, vec![Ok(()), Ok(())]);
 
     // then
-    let top = txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0));
+    let top = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
     assert_eq!(top.len(), 0);
-    let top = txq.pending(TestClient::new(), PendingSettings::all_prioritized(1, 0));
+    let top = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(1, BlockTimestamp::new(0)),
+    );
     assert_eq!(top.len(), 2);
 }
 
@@ -526,8 +575,11 @@ fn should_move_transactions_if_gap_filled() {
     assert_eq!(res, vec![Ok(()), Ok(())]);
     assert_eq!(txq.status().status.transaction_count, 2);
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         1
     );
 
@@ -538,8 +590,11 @@ fn should_move_transactions_if_gap_filled() {
     // then
     assert_eq!(txq.status().status.transaction_count, 3);
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         3
     );
 }
@@ -554,8 +609,11 @@ fn should_remove_transaction() {
     assert_eq!(res, vec![Ok(()), Ok(())]);
     assert_eq!(txq.status().status.transaction_count, 2);
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         1
     );
 
@@ -565,7 +623,7 @@ fn should_remove_transaction() {
     assert_eq!(
         txq.pending(
             TestClient::new().with_nonce(125),
-            PendingSettings::all_prioritized(0, 0)
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
         )
         .len(),
         1
@@ -588,8 +646,11 @@ fn should_move_transactions_to_future_if_gap_introduced() {
     assert_eq!(res, vec![Ok(()), Ok(())]);
     assert_eq!(txq.status().status.transaction_count, 2);
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         1
     );
 
@@ -597,8 +658,11 @@ fn should_move_transactions_to_future_if_gap_introduced() {
     assert_eq!(res, vec![Ok(())]);
     assert_eq!(txq.status().status.transaction_count, 3);
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         3
     );
 
@@ -608,8 +672,11 @@ fn should_move_transactions_to_future_if_gap_introduced() {
     // then
     assert_eq!(txq.status().status.transaction_count, 2);
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         1
     );
 }
@@ -647,6 +714,10 @@ fn should_prefer_current_transactions_when_hitting_the_limit() {
             no_early_reject: false,
             block_base_fee: None,
             allow_non_eoa_sender: false,
+            max_gas_per_sender: None,
+            replacement_bump: Default::default(),
+            prioritized_senders: Default::default(),
+            sender_premium: None,
         },
         PrioritizationStrategy::GasPriceOnly,
     );
@@ -665,7 +736,10 @@ fn should_prefer_current_transactions_when_hitting_the_limit() {
     assert_eq!(res, vec![Ok(())]);
     assert_eq!(txq.status().status.transaction_count, 1);
 
-    let top = txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0));
+    let top = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
     assert_eq!(top.len(), 1);
     assert_eq!(top[0].hash, hash);
     assert_eq!(txq.next_nonce(TestClient::new(), &sender), Some(124.into()));
@@ -713,8 +787,11 @@ fn should_accept_same_transaction_twice_if_removed() {
     assert_eq!(res, vec![Ok(()), Ok(())]);
     assert_eq!(txq.status().status.transaction_count, 2);
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         2
     );
 
@@ -722,8 +799,11 @@ fn should_accept_same_transaction_twice_if_removed() {
     txq.remove(vec![&hash], true);
     assert_eq!(txq.status().status.transaction_count, 1);
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         0
     );
 
@@ -733,8 +813,11 @@ fn should_accept_same_transaction_twice_if_removed() {
     // then
     assert_eq!(txq.status().status.transaction_count, 2);
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         2
     );
 }
@@ -766,17 +849,23 @@ fn should_not_replace_same_transaction_if_the_fee_is_less_than_minimal_bump() {
     );
     assert_eq!(txq.status().status.transaction_count, 2);
     assert_eq!(
-        txq.pending(client.clone(), PendingSettings::all_prioritized(0, 0))[0]
-            .signed()
-            .tx()
-            .gas_price,
+        txq.pending(
+            client.clone(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )[0]
+        .signed()
+        .tx()
+        .gas_price,
         U256::from(20)
     );
     assert_eq!(
-        txq.pending(client.clone(), PendingSettings::all_prioritized(0, 0))[1]
-            .signed()
-            .tx()
-            .gas_price,
+        txq.pending(
+            client.clone(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )[1]
+        .signed()
+        .tx()
+        .gas_price,
         U256::from(2)
     );
 }
@@ -805,6 +894,32 @@ fn should_return_correct_nonce_when_transactions_from_given_address_exist() {
     assert_eq!(txq.next_nonce(TestClient::new(), &from), Some(nonce + 1));
 }
 
+#[test]
+fn should_return_pending_transactions_of_given_senders_only() {
+    // given
+    let txq = new_queue();
+    let (tx1, tx2) = Tx::default().signed_pair();
+    let other = Tx::default().signed();
+    let sender = tx1.sender();
+    let (hash1, hash2) = (tx1.hash(), tx2.hash());
+    let res = txq.import(TestClient::new(), vec![tx1, tx2, other].local());
+    assert_eq!(res, vec![Ok(()), Ok(()), Ok(())]);
+
+    // when
+    let senders = vec![sender].into_iter().collect();
+    let pending = txq.pending_from_senders(
+        TestClient::new(),
+        &senders,
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
+
+    // then
+    assert_eq!(
+        pending.iter().map(|tx| tx.hash).collect::<Vec<_>>(),
+        vec![hash1, hash2]
+    );
+}
+
 #[test]
 fn should_return_valid_last_nonce_after_cull() {
     // given
@@ -821,8 +936,11 @@ fn should_return_valid_last_nonce_after_cull() {
     txq.cull(client.clone());
     // tx2 should be not be promoted to current
     assert_eq!(
-        txq.pending(client.clone(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            client.clone(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         0
     );
 
@@ -834,6 +952,88 @@ fn should_return_valid_last_nonce_after_cull() {
     );
 }
 
+#[test]
+fn should_report_nonce_gaps_of_sender() {
+    // given
+    let txq = new_queue();
+    let keypair = Random.generate();
+    let sign = |nonce| {
+        Tx {
+            nonce,
+            ..Default::default()
+        }
+        .unsigned()
+        .sign(keypair.secret(), None)
+    };
+    let (tx1, tx2, tx3) = (sign(123), sign(125), sign(126));
+    let sender = tx1.sender();
+    let (hash2, hash3) = (tx2.hash(), tx3.hash());
+    let res = txq.import(TestClient::new(), vec![tx1, tx2, tx3].local());
+    assert_eq!(res, vec![Ok(()), Ok(()), Ok(())]);
+
+    // when
+    let state = txq.sender_nonce_state(TestClient::new(), &sender);
+
+    // then
+    assert_eq!(
+        state,
+        SenderNonceState {
+            chain_nonce: 123.into(),
+            ready_nonce: 124.into(),
+            gaps: vec![NonceGap {
+                start: 124.into(),
+                end: 125.into(),
+                blocked: vec![hash2, hash3],
+            }],
+        }
+    );
+
+    // once the first transaction is mined it is no longer reported.
+    let state = txq.sender_nonce_state(TestClient::new().with_nonce(124), &sender);
+    assert_eq!(state.ready_nonce, 124.into());
+    assert_eq!(state.gaps.len(), 1);
+}
+
+#[test]
+fn should_group_content_by_sender_and_split_at_nonce_gap() {
+    // given
+    let txq = new_queue();
+    let keypair = Random.generate();
+    let sign = |nonce| {
+        Tx {
+            nonce,
+            ..Default::default()
+        }
+        .unsigned()
+        .sign(keypair.secret(), None)
+    };
+    let (tx1, tx2, tx3) = (sign(123), sign(124), sign(126));
+    let sender = tx1.sender();
+    let hashes = (tx1.hash(), tx2.hash(), tx3.hash());
+    let other = Tx::default().signed();
+    let other_sender = other.sender();
+    let res = txq.import(TestClient::new(), vec![tx1, tx2, tx3, other].local());
+    assert_eq!(res, vec![Ok(()), Ok(()), Ok(()), Ok(())]);
+
+    // when
+    let content = txq.content(TestClient::new());
+
+    // then
+    assert_eq!(content.len(), 2);
+    let hash = |tx: &Arc<::pool::VerifiedTransaction>| tx.hash;
+    let of_sender = &content[&sender];
+    assert_eq!(
+        of_sender.pending.iter().map(hash).collect::<Vec<_>>(),
+        vec![hashes.0, hashes.1]
+    );
+    assert_eq!(
+        of_sender.queued.iter().map(hash).collect::<Vec<_>>(),
+        vec![hashes.2]
+    );
+    assert_eq!(content[&other_sender].pending.len(), 1);
+    assert!(content[&other_sender].queued.is_empty());
+}
+
 #[test]
 fn should_return_true_if_there_is_local_transaction_pending() {
     // given
@@ -872,6 +1072,145 @@ fn should_reject_transactions_below_base_gas() {
     );
 }
 
+#[test]
+fn should_not_limit_gas_of_local_senders() {
+    // given
+    let txq = new_queue();
+    txq.set_verifier_options(verifier::Options {
+        max_gas_per_sender: Some(21_000.into()),
+        ..Default::default()
+    });
+    let (tx1, tx2) = Tx::default().signed_pair();
+    let (tx3, tx4) = Tx::default().signed_pair();
+
+    // when
+    let external = txq.import(TestClient::new(), vec![tx1, tx2].unverified());
+    let local = txq.import(TestClient::new(), vec![tx3, tx4].local());
+
+    // then
+    assert_eq!(
+        external,
+        vec![Ok(()), Err(transaction::Error::SenderLimitReached)]
+    );
+    assert_eq!(local, vec![Ok(()), Ok(())]);
+    assert_eq!(txq.status().status.transaction_count, 3);
+}
+
+#[test]
+fn should_limit_gas_per_non_local_sender() {
+    // given
+    let txq = new_queue();
+    txq.set_verifier_options(verifier::Options {
+        max_gas_per_sender: Some(21_000.into()),
+        ..Default::default()
+    });
+    let (tx1, tx2) = Tx::default().signed_pair();
+    let (tx3, tx4) = Tx::default().signed_replacement();
+
+    // when
+    let res = txq.import(TestClient::new(), vec![tx1, tx2].unverified());
+    let replaced = txq.import(TestClient::new(), vec![tx3, tx4].unverified());
+
+    // then
+    assert_eq!(
+        res,
+        vec![Ok(()), Err(transaction::Error::SenderLimitReached)]
+    );
+    // a replacement doesn't add to the gas of the sender
+    assert_eq!(replaced, vec![Ok(()), Ok(())]);
+    assert_eq!(txq.status().status.transaction_count, 2);
+}
+
+#[test]
+fn should_order_prioritized_senders_ahead_of_equal_gas_price() {
+    // given
+    let txq = new_queue();
+    let regular = Tx::gas_price(2).signed();
+    let prioritized = Tx::gas_price(2).signed();
+    txq.set_verifier_options(verifier::Options {
+        prioritized_senders: vec![prioritized.sender()].into_iter().collect(),
+        ..Default::default()
+    });
+
+    // when
+    let res = txq.import(
+        TestClient::new(),
+        vec![regular.clone(), prioritized.clone()].unverified(),
+    );
+
+    // then
+    assert_eq!(res, vec![Ok(()), Ok(())]);
+    let top = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
+    assert_eq!(top[0].hash, prioritized.hash());
+    assert_eq!(top[1].hash, regular.hash());
+}
+
+#[test]
+fn should_require_premium_from_senders_with_many_transactions() {
+    // given
+    let txq = new_queue();
+    txq.set_verifier_options(verifier::Options {
+        sender_premium: Some(SenderPremium {
+            window: 1,
+            per_window: 1,
+            premium: 100,
+        }),
+        ..Default::default()
+    });
+    let (tx1, tx2) = Tx::gas_price(4).signed_pair();
+    let other = Tx::gas_price(3).signed();
+
+    // when
+    let res = txq.import(
+        TestClient::new(),
+        vec![tx1.clone(), tx2.clone(), other.clone()].unverified(),
+    );
+
+    // then
+    assert_eq!(res, vec![Ok(()), Ok(()), Ok(())]);
+    let top = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
+    assert_eq!(top[0].hash, tx1.hash());
+    assert_eq!(top[1].hash, other.hash());
+    assert_eq!(top[2].hash, tx2.hash());
+}
+
+#[test]
+fn should_keep_future_transactions_within_their_own_limits() {
+    // given
+    let txq = new_queue();
+    txq.set_future_limits(Some(FutureLimits {
+        max_count: 1,
+        max_mem_usage: usize::max_value(),
+    }));
+    let future = |nonce| {
+        Tx {
+            nonce,
+            ..Default::default()
+        }
+        .signed()
+    };
+
+    // when
+    let res = txq.import(
+        TestClient::new(),
+        vec![future(125), future(126), Tx::default().signed()].unverified(),
+    );
+    let local = txq.import(TestClient::new(), vec![future(127)].local());
+
+    // then
+    assert_eq!(
+        res,
+        vec![Ok(()), Err(transaction::Error::LimitReached), Ok(())]
+    );
+    assert_eq!(local, vec![Ok(())]);
+}
+
 #[test]
 fn should_remove_out_of_date_transactions_occupying_queue() {
     // given
@@ -909,6 +1248,56 @@ fn should_remove_out_of_date_transactions_occupying_queue() {
     assert_eq!(txq.status().status.transaction_count, 102);
 }
 
+#[test]
+fn should_drop_non_local_transactions_exceeding_max_age() {
+    // given
+    let txq = new_queue();
+    let (tx1, tx2) = Tx::default().signed_pair();
+    let local = Tx::default().signed();
+    let res = txq.import(TestClient::new(), vec![tx1, tx2].unverified());
+    assert_eq!(res, vec![Ok(()), Ok(())]);
+    let res = txq.import(TestClient::new(), vec![local].local());
+    assert_eq!(res, vec![Ok(())]);
+
+    // when
+    txq.set_max_age(Some(Duration::from_secs(3600)));
+    txq.cull(TestClient::new());
+
+    // then
+    assert_eq!(txq.status().status.transaction_count, 3);
+
+    // when
+    txq.set_max_age(Some(Duration::from_secs(0)));
+    txq.cull(TestClient::new());
+
+    // then
+    let status = txq.status();
+    assert_eq!(status.status.transaction_count, 1);
+    assert_eq!(status.expired_transactions, 2);
+}
+
+#[test]
+fn should_drop_expired_transactions_queued_behind_young_ones() {
+    // given
+    let txq = new_queue();
+    let (tx1, tx2) = Tx::default().signed_pair();
+    let res = txq.import(TestClient::new(), vec![tx2.clone()].unverified());
+    assert_eq!(res, vec![Ok(())]);
+    thread::sleep(Duration::from_millis(200));
+    let res = txq.import(TestClient::new(), vec![tx1.clone()].unverified());
+    assert_eq!(res, vec![Ok(())]);
+
+    // when
+    txq.set_max_age(Some(Duration::from_millis(100)));
+    txq.cull(TestClient::new());
+
+    // then
+    let status = txq.status();
+    assert_eq!(status.status.transaction_count, 1);
+    assert_eq!(status.expired_transactions, 1);
+    assert!(txq.find(&tx1.hash()).is_some());
+}
+
 #[test]
 fn should_accept_local_transactions_below_min_gas_price() {
     // given
@@ -932,8 +1321,11 @@ fn should_accept_local_transactions_below_min_gas_price() {
 
     // then
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         1
     );
 }
@@ -950,8 +1342,11 @@ fn should_accept_local_service_transaction() {
 
     // then
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(0))
+        )
+        .len(),
         1
     );
 }
@@ -1003,13 +1398,16 @@ fn should_not_return_transactions_over_nonce_cap(enforce_priority_fees: bool) {
     assert_eq!(res, vec![Ok(()), Ok(()), Ok(())]);
 
     // when
-    let all = txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 0));
+    let all = txq.pending(
+        TestClient::new(),
+        PendingSettings::all_prioritized(0, BlockTimestamp::new(0)),
+    );
     // This should invalidate the cache!
     let limited = txq.pending(
         TestClient::new(),
         PendingSettings {
             block_number: 0,
-            current_timestamp: 0,
+            current_timestamp: BlockTimestamp::new(0),
             nonce_cap: Some(123.into()),
             max_len: usize::max_value(),
             ordering: PendingOrdering::Priority,
@@ -1049,7 +1447,7 @@ fn should_return_cached_pending_even_if_unordered_is_requested(enforce_priority_
         TestClient::new(),
         PendingSettings {
             block_number: 0,
-            current_timestamp: 0,
+            current_timestamp: BlockTimestamp::new(0),
             nonce_cap: None,
             max_len: 3,
             ordering: PendingOrdering::Priority,
@@ -1065,7 +1463,7 @@ fn should_return_cached_pending_even_if_unordered_is_requested(enforce_priority_
         TestClient::new(),
         PendingSettings {
             block_number: 0,
-            current_timestamp: 0,
+            current_timestamp: BlockTimestamp::new(0),
             nonce_cap: None,
             max_len: 3,
             ordering: PendingOrdering::Unordered,
@@ -1104,7 +1502,7 @@ fn should_return_unordered_and_not_populate_the_cache(enforce_priority_fees: boo
         TestClient::new(),
         PendingSettings {
             block_number: 0,
-            current_timestamp: 0,
+            current_timestamp: BlockTimestamp::new(0),
             nonce_cap: None,
             max_len: usize::max_value(),
             ordering: PendingOrdering::Unordered,
@@ -1142,25 +1540,77 @@ fn should_clear_cache_after_timeout_for_local() {
     // This should populate cache and set timestamp to 1
     // when
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 1))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(1))
+        )
+        .len(),
         0
     );
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 1000))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(1000))
+        )
+        .len(),
         0
     );
 
     // This should invalidate the cache and trigger transaction ready.
     // then
     assert_eq!(
-        txq.pending(TestClient::new(), PendingSettings::all_prioritized(0, 1002))
-            .len(),
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(0, BlockTimestamp::new(1002))
+        )
+        .len(),
         2
     );
 }
 
+#[test]
+fn should_keep_timestamp_condition_ready_when_clock_steps_back() {
+    // given
+    let txq = new_queue();
+    let clock = Arc::new(MockClock::with_unix_secs(1000));
+    txq.set_readiness_clock(Some(clock.clone()));
+    txq.set_timestamp_tolerance(Duration::from_secs(30));
+    let (tx, tx2) = Tx::default().signed_pair();
+    let res = txq.import(
+        TestClient::new(),
+        vec![
+            verifier::Transaction::Local(PendingTransaction::new(
+                tx,
+                transaction::Condition::Timestamp(1000).into(),
+            )),
+            tx2.local(),
+        ],
+    );
+    assert_eq!(res, vec![Ok(()), Ok(())]);
+    let pending = |block_number| {
+        txq.pending(
+            TestClient::new(),
+            PendingSettings::all_prioritized(block_number, BlockTimestamp::new(1)),
+        )
+        .len()
+    };
+    assert_eq!(pending(0), 2);
+
+    // when
+    clock.rewind(Duration::from_secs(10));
+
+    // then
+    assert_eq!(pending(1), 2);
+    assert_eq!(txq.status().backwards_clock_jumps, 1);
+
+    // when
+    clock.rewind(Duration::from_secs(100));
+
+    // then
+    assert_eq!(pending(2), 0);
+    assert_eq!(txq.status().backwards_clock_jumps, 2);
+}
+
 #[test]
 fn should_reject_big_transaction() {
     let txq = new_queue();
@@ -1191,6 +1641,10 @@ fn should_include_local_transaction_to_a_full_pool() {
             no_early_reject: false,
             block_base_fee: None,
             allow_non_eoa_sender: false,
+            max_gas_per_sender: None,
+            replacement_bump: Default::default(),
+            prioritized_senders: Default::default(),
+            sender_premium: None,
         },
         PrioritizationStrategy::GasPriceOnly,
     );
@@ -1225,6 +1679,10 @@ fn should_avoid_verifying_transaction_already_in_pool() {
             no_early_reject: false,
             block_base_fee: None,
             allow_non_eoa_sender: false,
+            max_gas_per_sender: None,
+            replacement_bump: Default::default(),
+            prioritized_senders: Default::default(),
+            sender_premium: None,
         },
         PrioritizationStrategy::GasPriceOnly,
     );
@@ -1262,6 +1720,10 @@ fn should_avoid_reverifying_recently_rejected_transactions() {
             no_early_reject: false,
             block_base_fee: None,
             allow_non_eoa_sender: false,
+            max_gas_per_sender: None,
+            replacement_bump: Default::default(),
+            prioritized_senders: Default::default(),
+            sender_premium: None,
         },
         PrioritizationStrategy::GasPriceOnly,
     );
@@ -1312,6 +1774,10 @@ fn should_reject_early_in_case_gas_price_is_less_than_min_effective() {
             no_early_reject: false,
             block_base_fee: None,
             allow_non_eoa_sender: false,
+            max_gas_per_sender: None,
+            replacement_bump: Default::default(),
+            prioritized_senders: Default::default(),
+            sender_premium: None,
         },
         PrioritizationStrategy::GasPriceOnly,
     );
@@ -1356,6 +1822,10 @@ fn should_not_reject_early_in_case_gas_price_is_less_than_min_effective() {
             no_early_reject: true,
             block_base_fee: None,
             allow_non_eoa_sender: false,
+            max_gas_per_sender: None,
+            replacement_bump: Default::default(),
+            prioritized_senders: Default::default(),
+            sender_premium: None,
         },
         PrioritizationStrategy::GasPriceOnly,
     );
@@ -1419,7 +1889,7 @@ fn should_not_return_pending_external_transactions_with_too_low_priority_fee_if_
     // when (priority fees are enforced)
     let pending_settings = PendingSettings {
         block_number: 0,
-        current_timestamp: 0,
+        current_timestamp: BlockTimestamp::new(0),
         nonce_cap: None,
         max_len: 4,
         ordering: PendingOrdering::Priority,
@@ -1445,7 +1915,7 @@ fn should_not_return_pending_external_transactions_with_too_low_priority_fee_if_
     // when (priority fees are not enforced)
     let pending_settings = PendingSettings {
         block_number: 0,
-        current_timestamp: 0,
+        current_timestamp: BlockTimestamp::new(0),
         nonce_cap: None,
         max_len: 4,
         ordering: PendingOrdering::Priority,
@@ -1497,7 +1967,7 @@ fn should_return_correct_cached_pending_depending_on_fees_enforcement(enforce_pr
     // when (priority fees are enforced)
     let pending_settings = PendingSettings {
         block_number: 0,
-        current_timestamp: 0,
+        current_timestamp: BlockTimestamp::new(0),
         nonce_cap: None,
         max_len: 4,
         ordering: PendingOrdering::Priority,
@@ -1511,7 +1981,7 @@ fn should_return_correct_cached_pending_depending_on_fees_enforcement(enforce_pr
         TestClient::new(),
         PendingSettings {
             block_number: 0,
-            current_timestamp: 0,
+            current_timestamp: BlockTimestamp::new(0),
             nonce_cap: None,
             max_len: 3,
             ordering: PendingOrdering::Unordered,
@@ -1523,3 +1993,121 @@ fn should_return_correct_cached_pending_depending_on_fees_enforcement(enforce_pr
     // then
     assert_eq!(all, limited);
 }
+
+fn bundle(entry_point: Address, keypair: &KeyPair) -> transaction::SignedTransaction {
+    transaction::TypedTransaction::Legacy(transaction::Transaction {
+        action: transaction::Action::Call(entry_point),
+        value: 0.into(),
+        data: vec![],
+        gas: 21_000.into(),
+        gas_price: 1.into(),
+        nonce: 123.into(),
+    })
+    .sign(keypair.secret(), None)
+}
+
+fn alt_mempool_queue(entry_point: Address, max_count: usize) -> TransactionQueue {
+    let txq = new_queue();
+    txq.set_alt_mempool_options(alt_mempool::Options {
+        entry_points: vec![entry_point].into_iter().collect(),
+        max_count,
+        max_gas: None,
+    });
+    txq
+}
+
+#[test]
+fn should_limit_bundle_transactions_separately() {
+    // given
+    let entry_point = Address::from_low_u64_be(0x4337);
+    let txq = alt_mempool_queue(entry_point, 1);
+    let bundle1 = bundle(entry_point, &Random.generate());
+    let bundle2 = bundle(entry_point, &Random.generate());
+
+    // when
+    let res = txq.import(
+        TestClient::new(),
+        vec![bundle1, bundle2, Tx::default().signed()].unverified(),
+    );
+
+    // then
+    assert_eq!(
+        res,
+        vec![Ok(()), Err(transaction::Error::AltMempoolFull), Ok(())]
+    );
+    assert_eq!(txq.status().status.transaction_count, 2);
+    assert_eq!(txq.alt_mempool_transactions().len(), 1);
+    let capacity = txq.alt_mempool_capacity();
+    assert_eq!(capacity.count, 1);
+    assert_eq!(capacity.gas, 21_000.into());
+    assert_eq!(capacity.available(), 0);
+}
+
+#[test]
+fn should_keep_reserved_alt_mempool_slots_for_bundler() {
+    // given
+    let entry_point = Address::from_low_u64_be(0x4337);
+    let txq = alt_mempool_queue(entry_point, 2);
+    let bundler = Random.generate();
+    let reservation = txq
+        .reserve_alt_mempool(bundler.address(), 1, Duration::from_secs(60))
+        .unwrap();
+    assert_eq!(reservation.slots, 1);
+    assert_eq!(
+        txq.reserve_alt_mempool(Address::from_low_u64_be(1), 2, Duration::from_secs(60)),
+        Err(ReservationError::InsufficientCapacity {
+            available: 1,
+            requested: 2,
+        })
+    );
+
+    // when
+    let others = txq.import(
+        TestClient::new(),
+        vec![
+            bundle(entry_point, &Random.generate()),
+            bundle(entry_point, &Random.generate()),
+        ]
+        .unverified(),
+    );
+    let own = txq.import(
+        TestClient::new(),
+        vec![bundle(entry_point, &bundler)].unverified(),
+    );
+
+    // then
+    assert_eq!(
+        others,
+        vec![Ok(()), Err(transaction::Error::AltMempoolFull)]
+    );
+    assert_eq!(own, vec![Ok(())]);
+    let capacity = txq.alt_mempool_capacity();
+    assert_eq!(capacity.count, 2);
+    assert_eq!(capacity.reserved, 0);
+}
+
+#[test]
+fn should_keep_alt_mempool_reservation_if_import_fails() {
+    // given
+    let entry_point = Address::from_low_u64_be(0x4337);
+    let txq = alt_mempool_queue(entry_point, 4);
+    let bundler = Random.generate();
+    txq.reserve_alt_mempool(bundler.address(), 2, Duration::from_secs(60))
+        .unwrap();
+    let first = bundle(entry_point, &bundler);
+    let not_replacing = {
+        let mut tx = first.tx().clone();
+        tx.gas = 22_000.into();
+        transaction::TypedTransaction::Legacy(tx).sign(bundler.secret(), None)
+    };
+
+    // when
+    let res = txq.import(TestClient::new(), vec![first, not_replacing].unverified());
+
+    // then
+    assert_eq!(res[0], Ok(()));
+    assert!(res[1].is_err());
+    let capacity = txq.alt_mempool_capacity();
+    assert_eq!(capacity.count, 1);
+    assert_eq!(capacity.reserved, 1);
+}
@@ -18,13 +18,13 @@
 
 #![allow(missing_docs)]
 
-use ethereum_types::{Address, U256};
+use ethereum_types::{Address, H32, U256};
 
 use pool::VerifiedTransaction;
 use types::transaction::Action;
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[serde()]
 pub enum SenderArgument {
     eq(Address),
@@ -47,11 +47,13 @@ impl SenderArgument {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[serde()]
 pub enum ActionArgument {
     eq(Address),
     action(String),
+    /// Calls to an address within the inclusive range.
+    range(Address, Address),
     None,
 }
 
@@ -66,13 +68,17 @@ impl ActionArgument {
         match self {
             Self::eq(expected) => *value == Action::Call(*expected),
             Self::action(name) => *value == Action::Create && name == "contract_creation",
+            Self::range(start, end) => match value {
+                Action::Call(to) => start <= to && to <= end,
+                Action::Create => false,
+            },
             Self::None => true,
         }
     }
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[serde()]
 pub enum ValueFilterArgument {
     eq(U256),
@@ -98,8 +104,38 @@ impl ValueFilterArgument {
     }
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
-#[serde(default, rename_all = "camelCase")]
+/// Matches the 4-byte function selector at the start of the calldata.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde()]
+pub enum SelectorArgument {
+    eq(H32),
+    any(Vec<H32>),
+    None,
+}
+
+impl Default for SelectorArgument {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl SelectorArgument {
+    fn matches(&self, action: &Action, data: &[u8]) -> bool {
+        let selector = match (action, data.len() >= 4) {
+            (Action::Call(_), true) => H32::from_slice(&data[..4]),
+            _ => return *self == Self::None,
+        };
+        match self {
+            Self::eq(expected) => selector == *expected,
+            Self::any(expected) => expected.contains(&selector),
+            Self::None => true,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
 pub struct TransactionFilter {
     from: SenderArgument,
     to: ActionArgument,
@@ -107,6 +143,7 @@ pub struct TransactionFilter {
     gas_price: ValueFilterArgument,
     value: ValueFilterArgument,
     nonce: ValueFilterArgument,
+    selector: SelectorArgument,
 }
 
 impl TransactionFilter {
@@ -118,6 +155,7 @@ impl TransactionFilter {
             && self.gas_price.matches(&tx.gas_price)
             && self.nonce.matches(&tx.nonce)
             && self.value.matches(&tx.value)
+            && self.selector.matches(&tx.action, &tx.data)
     }
 }
 
@@ -127,3 +165,50 @@ pub fn match_filter(filter: &Option<TransactionFilter>, transaction: &VerifiedTr
         None => true,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::H160;
+    use serde_json;
+    use types::transaction::{Transaction, TypedTransaction};
+
+    fn call(to: Address, data: Vec<u8>) -> VerifiedTransaction {
+        let signed = TypedTransaction::Legacy(Transaction {
+            action: Action::Call(to),
+            data,
+            nonce: 0.into(),
+            gas: 21_000.into(),
+            gas_price: 1.into(),
+            value: 0.into(),
+        })
+        .fake_sign(H160::from_low_u64_be(1));
+        VerifiedTransaction::from_pending_block_transaction(signed)
+    }
+
+    #[test]
+    fn should_match_selector_and_target_range() {
+        let token = H160::from_low_u64_be(0x20);
+        let transfer = call(token, vec![0xa9, 0x05, 0x9c, 0xbb, 0, 0]);
+        let approve = call(token, vec![0x09, 0x5e, 0xa7, 0xb3]);
+        let no_data = call(H160::from_low_u64_be(0x40), vec![]);
+
+        let filter: TransactionFilter = serde_json::from_str(
+            r#"{
+                "to": {"range": ["0x0000000000000000000000000000000000000010", "0x0000000000000000000000000000000000000030"]},
+                "selector": {"eq": "0xa9059cbb"}
+            }"#,
+        )
+        .unwrap();
+        assert!(filter.matches(&transfer));
+        assert!(!filter.matches(&approve));
+        assert!(!filter.matches(&no_data));
+
+        let filter: TransactionFilter =
+            serde_json::from_str(r#"{"selector": {"any": ["0xa9059cbb", "0x095ea7b3"]}}"#).unwrap();
+        assert!(filter.matches(&transfer));
+        assert!(filter.matches(&approve));
+        assert!(!filter.matches(&no_data));
+        assert!(TransactionFilter::default().matches(&no_data));
+    }
+}
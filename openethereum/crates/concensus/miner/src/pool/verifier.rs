@@ -24,19 +24,23 @@
 
 use std::{
     cmp,
+    collections::HashSet,
     sync::{
         atomic::{self, AtomicUsize},
         Arc,
     },
+    time::Instant,
 };
 
-use ethereum_types::{H256, U256};
+use ethereum_types::{Address, H256, U256};
 use hash::KECCAK_EMPTY;
 use txpool;
 use types::transaction;
 
 use super::{
     client::{Client, TransactionType},
+    replace::ReplacementBump,
+    scoring::SenderPremium,
     VerifiedTransaction,
 };
 
@@ -55,6 +59,14 @@ pub struct Options {
     pub no_early_reject: bool,
     /// Accept transactions from non EOAs (see EIP-3607)
     pub allow_non_eoa_sender: bool,
+    /// Maximal total gas of the transactions a single non-local sender can have in the queue.
+    pub max_gas_per_sender: Option<U256>,
+    /// Price bump required to replace a transaction with the same sender and nonce.
+    pub replacement_bump: ReplacementBump,
+    /// Senders whose transactions are ordered ahead of regular ones with the same gas price.
+    pub prioritized_senders: HashSet<Address>,
+    /// Premium for senders with many transactions in the queue.
+    pub sender_premium: Option<SenderPremium>,
 }
 
 #[cfg(test)]
@@ -67,6 +79,10 @@ impl Default for Options {
             tx_gas_limit: U256::max_value(),
             no_early_reject: false,
             allow_non_eoa_sender: false,
+            max_gas_per_sender: None,
+            replacement_bump: Default::default(),
+            prioritized_senders: Default::default(),
+            sender_premium: None,
         }
     }
 }
@@ -184,21 +200,26 @@ impl Transaction {
 pub struct Verifier<C, S, V> {
     client: C,
     options: Options,
+    best_block: u64,
     id: Arc<AtomicUsize>,
     transaction_to_replace: Option<(S, Arc<V>)>,
 }
 
 impl<C, S, V> Verifier<C, S, V> {
     /// Creates new transaction verfier with specified options.
+    ///
+    /// Verified transactions are tagged with `best_block`.
     pub fn new(
         client: C,
         options: Options,
+        best_block: u64,
         id: Arc<AtomicUsize>,
         transaction_to_replace: Option<(S, Arc<V>)>,
     ) -> Self {
         Verifier {
             client,
             options,
+            best_block,
             id,
             transaction_to_replace,
         }
@@ -351,6 +372,8 @@ impl<C: Client> txpool::Verifier<Transaction>
             let transaction_type = self.client.transaction_type(&transaction);
             if let TransactionType::Service = transaction_type {
                 debug!(target: "txqueue", "Service tx {:?} below minimal gas price accepted", hash);
+            } else if let TransactionType::System = transaction_type {
+                debug!(target: "txqueue", "System tx {:?} below minimal gas price accepted", hash);
             } else if is_own || account_details.is_local {
                 info!(target: "own_tx", "Local tx {:?} below minimal gas price accepted", hash);
             } else {
@@ -415,10 +438,16 @@ impl<C: Client> txpool::Verifier<Transaction>
             bail!(transaction::Error::Old);
         }
 
-        let priority = match (is_own || account_details.is_local, is_retracted) {
-            (true, _) => super::Priority::Local,
-            (false, false) => super::Priority::Regular,
-            (false, true) => super::Priority::Retracted,
+        let is_prioritized = self.options.prioritized_senders.contains(&sender);
+        let priority = match (
+            is_own || account_details.is_local,
+            is_prioritized,
+            is_retracted,
+        ) {
+            (true, _, _) => super::Priority::Local,
+            (false, true, _) => super::Priority::Prioritized,
+            (false, false, false) => super::Priority::Regular,
+            (false, false, true) => super::Priority::Retracted,
         };
         Ok(VerifiedTransaction {
             transaction,
@@ -426,6 +455,8 @@ impl<C: Client> txpool::Verifier<Transaction>
             hash,
             sender,
             insertion_id: self.id.fetch_add(1, atomic::Ordering::AcqRel),
+            inserted_at: Instant::now(),
+            inserted_block: self.best_block,
         })
     }
 }
@@ -18,7 +18,7 @@
 
 use call_contract::{CallContract, RegistryInfo};
 use ethabi::FunctionOutputDecoder;
-use ethereum_types::Address;
+use ethereum_types::{Address, H256};
 use parking_lot::RwLock;
 use std::{collections::HashMap, mem, sync::Arc};
 use types::{ids::BlockId, transaction::SignedTransaction};
@@ -30,13 +30,43 @@ use_contract!(
 
 const SERVICE_TRANSACTION_CONTRACT_REGISTRY_NAME: &'static str = "service_transaction_checker";
 
+/// Storage root and code hash of a contract at the latest block. Any write to the
+/// contract changes one of them.
+pub type ContractState = (Option<H256>, Option<H256>);
+
 /// Service transactions checker.
+///
+/// Certified addresses are cached until the state of the registrar or of the certifier
+/// contract differs from the one the cache was refreshed at, see `is_stale`.
 #[derive(Default, Clone)]
 pub struct ServiceTransactionChecker {
     certified_addresses_cache: Arc<RwLock<HashMap<Address, bool>>>,
+    registrar: Arc<RwLock<Option<Address>>>,
+    /// Registrar and certifier with their state at the last refresh, if any.
+    refreshed_at: Arc<RwLock<Option<Vec<(Address, ContractState)>>>>,
 }
 
 impl ServiceTransactionChecker {
+    /// Sets the registrar whose state is watched along with the certifier.
+    pub fn set_registrar(&self, registrar: Option<Address>) {
+        *self.registrar.write() = registrar;
+        *self.refreshed_at.write() = None;
+    }
+
+    /// Returns `true` if the cache should be refreshed with `refresh_cache`, which is when
+    /// the registrar or the certifier changed since the last refresh.
+    pub fn is_stale<F>(&self, contract_state: F) -> bool
+    where
+        F: Fn(&Address) -> ContractState,
+    {
+        match *self.refreshed_at.read() {
+            Some(ref contracts) => contracts
+                .iter()
+                .any(|(address, state)| contract_state(address) != *state),
+            None => true,
+        }
+    }
+
     /// Checks if given address in tx is whitelisted to send service transactions.
     pub fn check<C: CallContract + RegistryInfo>(
         &self,
@@ -83,11 +113,13 @@ impl ServiceTransactionChecker {
     }
 
     /// Refresh certified addresses cache
-    pub fn refresh_cache<C: CallContract + RegistryInfo>(
-        &self,
-        client: &C,
-    ) -> Result<bool, String> {
+    pub fn refresh_cache<C, F>(&self, client: &C, contract_state: F) -> Result<bool, String>
+    where
+        C: CallContract + RegistryInfo,
+        F: Fn(&Address) -> ContractState,
+    {
         trace!(target: "txqueue", "Refreshing certified addresses cache");
+        *self.refreshed_at.write() = None;
         // replace the cache with an empty list,
         // since it's not recent it won't be used anyway.
         let cache = mem::replace(
@@ -95,10 +127,19 @@ impl ServiceTransactionChecker {
             HashMap::default(),
         );
 
-        if let Some(contract_address) = client.registry_address(
+        let contract_address = client.registry_address(
             SERVICE_TRANSACTION_CONTRACT_REGISTRY_NAME.to_owned(),
             BlockId::Latest,
-        ) {
+        );
+        let watched: Vec<_> = self
+            .registrar
+            .read()
+            .iter()
+            .chain(contract_address.iter())
+            .map(|address| (*address, contract_state(address)))
+            .collect();
+
+        let refreshed = if let Some(contract_address) = contract_address {
             let addresses: Vec<_> = cache.keys().collect();
             let mut cache: HashMap<Address, bool> = HashMap::default();
             for address in addresses {
@@ -106,10 +147,13 @@ impl ServiceTransactionChecker {
                 cache.insert(*address, allowed);
             }
             *self.certified_addresses_cache.write() = cache;
-            Ok(true)
+            true
         } else {
-            Ok(false)
-        }
+            false
+        };
+        // only a complete refresh is recorded, a failed one is retried on the next block
+        *self.refreshed_at.write() = Some(watched);
+        Ok(refreshed)
     }
 
     fn call_contract<C: CallContract + RegistryInfo>(
@@ -123,3 +167,69 @@ impl ServiceTransactionChecker {
         decoder.decode(&value).map_err(|e| e.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Registry(Option<Address>);
+
+    impl CallContract for Registry {
+        fn call_contract(&self, _: BlockId, _: Address, _: Vec<u8>) -> Result<Vec<u8>, String> {
+            Err("unexpected contract call".into())
+        }
+    }
+
+    impl RegistryInfo for Registry {
+        fn registry_address(&self, _: String, _: BlockId) -> Option<Address> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn should_become_stale_when_watched_contracts_change() {
+        use std::cell::RefCell;
+
+        let registrar = Address::from_low_u64_be(1);
+        let certifier = Address::from_low_u64_be(2);
+        let states = RefCell::new(HashMap::new());
+        let contract_state = |address: &Address| {
+            (
+                states.borrow().get(address).cloned(),
+                Some(H256::from_low_u64_be(0xc0de)),
+            )
+        };
+        let set_storage_root = |address: Address, root: u64| {
+            states
+                .borrow_mut()
+                .insert(address, H256::from_low_u64_be(root));
+        };
+
+        let checker = ServiceTransactionChecker::default();
+        checker.set_registrar(Some(registrar));
+        assert!(checker.is_stale(&contract_state));
+
+        assert_eq!(
+            checker.refresh_cache(&Registry(Some(certifier)), &contract_state),
+            Ok(true)
+        );
+        assert!(!checker.is_stale(&contract_state));
+
+        // writes to other contracts don't matter
+        set_storage_root(Address::from_low_u64_be(3), 1);
+        assert!(!checker.is_stale(&contract_state));
+
+        set_storage_root(certifier, 1);
+        assert!(checker.is_stale(&contract_state));
+
+        // without a certifier only the registrar is watched
+        assert_eq!(
+            checker.refresh_cache(&Registry(None), &contract_state),
+            Ok(false)
+        );
+        set_storage_root(certifier, 2);
+        assert!(!checker.is_stale(&contract_state));
+        set_storage_root(registrar, 1);
+        assert!(checker.is_stale(&contract_state));
+    }
+}
@@ -15,7 +15,7 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{
-    error, fmt, io,
+    cmp, error, fmt, fs, io,
     path::{Path, PathBuf},
 };
 
@@ -90,6 +90,26 @@ impl DatabaseFiles {
         Ok(())
     }
 
+    /// Recompute the top and mid level blooms covering the bot level blooms in `from..=to`.
+    pub fn rebuild_upper_levels(&mut self, from: u64, to: u64) -> io::Result<()> {
+        Self::rebuild_level(&mut self.mid, &self.bot, from >> 4, to >> 4)?;
+        Self::rebuild_level(&mut self.top, &self.mid, from >> 8, to >> 8)
+    }
+
+    // every bloom of `upper` in `from..=to` becomes the union of the 16 blooms of `lower`
+    // it represents.
+    fn rebuild_level(upper: &mut File, lower: &File, from: u64, to: u64) -> io::Result<()> {
+        for pos in from..=to {
+            let end = cmp::min((pos + 1) * 16, lower.bloom_count());
+            let mut bloom = ethbloom::Bloom::default();
+            for lower_pos in pos * 16..end {
+                bloom.accrue_bloom(&lower.read_bloom(lower_pos)?);
+            }
+            upper.replace_bloom(pos, &bloom)?;
+        }
+        Ok(())
+    }
+
     pub fn iterator_from(&mut self, pos: Positions) -> io::Result<DatabaseFilesIterator> {
         Ok(DatabaseFilesIterator {
             top: self.top.iterator_from(pos.top)?,
@@ -148,6 +168,21 @@ impl Database {
         Ok(())
     }
 
+    /// Copies the database files into `path`, creating it if necessary.
+    pub fn copy_to(&mut self, path: &Path) -> io::Result<()> {
+        match self.db_files {
+            Some(ref mut db_files) => {
+                db_files.flush()?;
+                fs::create_dir_all(path)?;
+                for name in &["top.bdb", "mid.bdb", "bot.bdb"] {
+                    fs::copy(self.path.join(name), path.join(name))?;
+                }
+                Ok(())
+            }
+            None => Err(other_io_err("Database is closed")),
+        }
+    }
+
     /// Insert consecutive blooms into database starting at the given positon.
     pub fn insert_blooms<'a, I, B>(&mut self, from: u64, blooms: I) -> io::Result<()>
     where
@@ -171,6 +206,32 @@ impl Database {
         }
     }
 
+    /// Replace consecutive blooms starting at the given position. Unlike `insert_blooms`, the top
+    /// and mid level blooms covering them are rebuilt, dropping what the old blooms left there.
+    pub fn replace_blooms<'a, I, B>(&mut self, from: u64, blooms: I) -> io::Result<()>
+    where
+        ethbloom::BloomRef<'a>: From<B>,
+        I: Iterator<Item = B>,
+    {
+        match self.db_files {
+            Some(ref mut db_files) => {
+                let mut end = from;
+                for (index, bloom) in (from..).into_iter().zip(blooms.map(Into::into)) {
+                    db_files
+                        .bot
+                        .replace_bloom::<ethbloom::BloomRef>(index, bloom)?;
+                    end = index + 1;
+                }
+                if end > from {
+                    db_files.rebuild_upper_levels(from, end - 1)?;
+                }
+                db_files.flush()?;
+                Ok(())
+            }
+            None => Err(other_io_err("Database is closed")),
+        }
+    }
+
     /// Returns an iterator yielding all indexes containing given bloom.
     pub fn iterate_matching<'a, 'b, B, I, II>(
         &'a mut self,
@@ -405,6 +466,58 @@ mod tests {
         assert_eq!(matches, vec![2]);
     }
 
+    #[test]
+    fn test_replace_blooms() {
+        let tempdir = TempDir::new("").unwrap();
+        let mut database = Database::open(tempdir.path()).unwrap();
+        let mut blooms = vec![Bloom::zero(); 18];
+        blooms[0] = Bloom::from_low_u64_be(0x01);
+        blooms[17] = Bloom::from_low_u64_be(0x10);
+        database.insert_blooms(0, blooms.iter()).unwrap();
+
+        blooms[0] = Bloom::zero();
+        blooms[1] = Bloom::from_low_u64_be(0x10);
+        blooms[17] = Bloom::zero();
+        database.replace_blooms(0, blooms.iter()).unwrap();
+
+        {
+            let files = database.db_files.as_ref().unwrap();
+            assert_eq!(
+                files.mid.read_bloom(0).unwrap(),
+                Bloom::from_low_u64_be(0x10)
+            );
+            assert_eq!(files.mid.read_bloom(1).unwrap(), Bloom::zero());
+            assert_eq!(
+                files.top.read_bloom(0).unwrap(),
+                Bloom::from_low_u64_be(0x10)
+            );
+        }
+        let matches = database
+            .iterate_matching(0, 17, Some(&Bloom::from_low_u64_be(0x01)))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn test_copy_to() {
+        let tempdir = TempDir::new("").unwrap();
+        let mut database = Database::open(tempdir.path()).unwrap();
+        database
+            .insert_blooms(0, vec![Bloom::from_low_u64_be(0x01), Bloom::zero()].iter())
+            .unwrap();
+        database.copy_to(&tempdir.path().join("copy")).unwrap();
+
+        let mut copy = Database::open(tempdir.path().join("copy")).unwrap();
+        let matches = copy
+            .iterate_matching(0, 1, Some(&Bloom::from_low_u64_be(0x01)))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(matches, vec![0]);
+    }
+
     #[test]
     fn test_database2() {
         let tempdir = TempDir::new("").unwrap();
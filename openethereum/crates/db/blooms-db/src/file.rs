@@ -61,6 +61,11 @@ impl File {
         Ok(())
     }
 
+    /// Number of blooms the file has space for.
+    pub fn bloom_count(&self) -> u64 {
+        self.len / 256
+    }
+
     /// Read bloom at given position.
     pub fn read_bloom(&self, pos: u64) -> io::Result<ethbloom::Bloom> {
         let mut file_ref = &self.file;
@@ -66,6 +66,11 @@ impl Database {
         self.database.lock().reopen()
     }
 
+    /// Copies the database files into `path`.
+    pub fn copy_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.database.lock().copy_to(path.as_ref())
+    }
+
     /// Inserts one or more blooms into database.
     ///
     /// # Arguments
@@ -80,6 +85,20 @@ impl Database {
         self.database.lock().insert_blooms(from, blooms)
     }
 
+    /// Replaces one or more blooms, rebuilding the upper levels of the index over them.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - index of the first bloom that needs to be replaced
+    /// * `blooms` - iterator over blooms
+    pub fn replace_blooms<'a, I, B>(&self, from: u64, blooms: I) -> io::Result<()>
+    where
+        ethbloom::BloomRef<'a>: From<B>,
+        I: Iterator<Item = B>,
+    {
+        self.database.lock().replace_blooms(from, blooms)
+    }
+
     /// Returns indexes of all headers matching given bloom in a specified range.
     ///
     /// # Arguments
@@ -52,6 +52,13 @@ where
         }
     }
 
+    /// Change the preferred and maximum cache sizes. Takes effect on the next
+    /// `collect_garbage`.
+    pub fn set_sizes(&mut self, pref_cache_size: usize, max_cache_size: usize) {
+        self.pref_cache_size = pref_cache_size;
+        self.max_cache_size = max_cache_size;
+    }
+
     /// Mark element as used.
     pub fn note_used(&mut self, id: T) {
         if !self.cache_usage[0].contains(&id) {
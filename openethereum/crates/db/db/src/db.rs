@@ -16,6 +16,7 @@
 
 //! Database utilities and definitions.
 
+use crate::read_profiler;
 use kvdb::DBTransaction;
 use kvdb_rocksdb::Database;
 use parking_lot::RwLock;
@@ -209,6 +210,7 @@ pub trait Readable {
         {
             let read = cache.read();
             if let Some(v) = read.get(key) {
+                read_profiler::record_cache_hit(col, read_profiler::short_type_name::<T>());
                 return Some(v.clone());
             }
         }
@@ -236,6 +238,7 @@ pub trait Readable {
         {
             let read = l1_cache.read();
             if let Some(v) = read.get(key) {
+                read_profiler::record_cache_hit(col, read_profiler::short_type_name::<T>());
                 return Some(v.clone());
             }
         }
@@ -258,6 +261,7 @@ pub trait Readable {
         {
             let read = cache.read();
             if read.get(key).is_some() {
+                read_profiler::record_cache_hit(col, read_profiler::short_type_name::<T>());
                 return true;
             }
         }
@@ -290,16 +294,20 @@ impl<KVDB: kvdb::KeyValueDB + ?Sized> Readable for KVDB {
         T: rlp::Decodable,
         R: AsRef<[u8]>,
     {
-        self.get(col, key.key().as_ref())
-            .expect(&format!("db get failed, key: {:?}", key.key().as_ref()))
-            .map(|v| rlp::decode(&v).expect("decode db value failed"))
+        read_profiler::with_key_class(read_profiler::short_type_name::<T>(), || {
+            self.get(col, key.key().as_ref())
+        })
+        .expect(&format!("db get failed, key: {:?}", key.key().as_ref()))
+        .map(|v| rlp::decode(&v).expect("decode db value failed"))
     }
 
     fn exists<T, R>(&self, col: Option<u32>, key: &dyn Key<T, Target = R>) -> bool
     where
         R: AsRef<[u8]>,
     {
-        let result = self.get(col, key.key().as_ref());
+        let result = read_profiler::with_key_class(read_profiler::short_type_name::<T>(), || {
+            self.get(col, key.key().as_ref())
+        });
 
         match result {
             Ok(v) => v.is_some(),
@@ -345,6 +353,9 @@ impl kvdb::KeyValueDB for DatabaseWithMetrics {
         let count = res
             .as_ref()
             .map_or(0, |y| y.as_ref().map_or(0, |x| x.bytes().count()));
+        if let Ok(ref value) = res {
+            read_profiler::record_db_read(col, key, value.as_ref().map(|v| v.len()));
+        }
 
         self.reads
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
@@ -356,6 +367,7 @@ impl kvdb::KeyValueDB for DatabaseWithMetrics {
     fn get_by_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<Box<[u8]>> {
         let res = self.db.get_by_prefix(col, prefix);
         let count = res.as_ref().map_or(0, |x| x.bytes().count());
+        read_profiler::record_db_read(col, prefix, res.as_ref().map(|v| v.len()));
 
         self.reads
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
@@ -458,10 +470,16 @@ pub struct InMemoryWithMetrics {
 
 impl kvdb::KeyValueDB for InMemoryWithMetrics {
     fn get(&self, col: Option<u32>, key: &[u8]) -> std::io::Result<Option<kvdb::DBValue>> {
-        self.db.get(col, key)
+        let res = self.db.get(col, key);
+        if let Ok(ref value) = res {
+            read_profiler::record_db_read(col, key, value.as_ref().map(|v| v.len()));
+        }
+        res
     }
     fn get_by_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<Box<[u8]>> {
-        self.db.get_by_prefix(col, prefix)
+        let res = self.db.get_by_prefix(col, prefix);
+        read_profiler::record_db_read(col, prefix, res.as_ref().map(|v| v.len()));
+        res
     }
     fn write_buffered(&self, transaction: DBTransaction) {
         self.db.write_buffered(transaction)
@@ -507,3 +525,81 @@ impl InMemoryWithMetrics {
         }
     }
 }
+
+/// Database that serves reads from the wrapped one and rejects every write.
+///
+/// `write` fails right away. Buffered writes cannot fail, so they are dropped and the next
+/// `flush` reports them.
+pub struct ReadOnlyDatabase {
+    db: std::sync::Arc<dyn KeyValueDB>,
+    rejected_writes: std::sync::atomic::AtomicUsize,
+}
+
+impl ReadOnlyDatabase {
+    /// Create a new instance
+    pub fn new(db: std::sync::Arc<dyn KeyValueDB>) -> Self {
+        Self {
+            db,
+            rejected_writes: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    fn read_only_error() -> std::io::Error {
+        std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "database is opened read-only",
+        )
+    }
+}
+
+impl kvdb::KeyValueDB for ReadOnlyDatabase {
+    fn get(&self, col: Option<u32>, key: &[u8]) -> std::io::Result<Option<kvdb::DBValue>> {
+        self.db.get(col, key)
+    }
+    fn get_by_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<Box<[u8]>> {
+        self.db.get_by_prefix(col, prefix)
+    }
+    fn write_buffered(&self, _transaction: DBTransaction) {
+        self.rejected_writes
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+    fn write(&self, _transaction: DBTransaction) -> std::io::Result<()> {
+        Err(Self::read_only_error())
+    }
+    fn flush(&self) -> std::io::Result<()> {
+        match self
+            .rejected_writes
+            .swap(0, std::sync::atomic::Ordering::Relaxed)
+        {
+            0 => Ok(()),
+            _ => Err(Self::read_only_error()),
+        }
+    }
+
+    fn iter<'a>(
+        &'a self,
+        col: Option<u32>,
+    ) -> Box<(dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a)> {
+        self.db.iter(col)
+    }
+
+    fn iter_from_prefix<'a>(
+        &'a self,
+        col: Option<u32>,
+        prefix: &'a [u8],
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+        self.db.iter_from_prefix(col, prefix)
+    }
+
+    fn restore(&self, _new_db: &str) -> std::io::Result<()> {
+        Err(Self::read_only_error())
+    }
+}
+
+impl PrometheusMetrics for ReadOnlyDatabase {
+    fn prometheus_metrics(&self, p: &mut PrometheusRegistry) {
+        self.db.prometheus_metrics(p)
+    }
+}
+
+impl KeyValueDB for ReadOnlyDatabase {}
@@ -44,6 +44,8 @@ pub enum ExtrasIndex {
     EpochTransitions = 5,
     /// Pending epoch transition data index.
     PendingEpochTransition = 6,
+    /// Index of transactions whose address is no longer stored.
+    ExpiredTransaction = 7,
 }
 
 fn with_index(hash: &H256, i: ExtrasIndex) -> H264 {
@@ -76,6 +78,30 @@ impl Key<H256> for BlockNumber {
     }
 }
 
+/// Number of bytes of a transaction hash kept in `ExpiredTransactionKey`.
+pub const EXPIRED_TRANSACTION_HASH_LEN: usize = 8;
+
+/// Key marking a transaction whose address expired from the index. Only a prefix of
+/// the hash is stored, which is enough to tell pruned transactions from unknown ones
+/// at a fraction of the size of the address.
+pub struct ExpiredTransactionKey([u8; 1 + EXPIRED_TRANSACTION_HASH_LEN]);
+
+impl ExpiredTransactionKey {
+    /// Marker key of the transaction with given hash.
+    pub fn new(hash: &H256) -> Self {
+        let mut result = [0u8; 1 + EXPIRED_TRANSACTION_HASH_LEN];
+        result[0] = ExtrasIndex::ExpiredTransaction as u8;
+        result[1..].copy_from_slice(&hash.as_bytes()[..EXPIRED_TRANSACTION_HASH_LEN]);
+        ExpiredTransactionKey(result)
+    }
+}
+
+impl AsRef<[u8]> for ExpiredTransactionKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0[..]
+    }
+}
+
 impl Key<BlockDetails> for H256 {
     type Target = H264;
 
@@ -22,6 +22,8 @@ mod db;
 
 pub mod cache_manager;
 pub mod keys;
+pub mod migration;
+pub mod read_profiler;
 pub use kvdb::{DBTransaction, DBValue};
 
 pub use self::db::*;
@@ -0,0 +1,319 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Versioned, in-place schema migrations.
+//!
+//! Every change to the layout of a column ships as a `Migration` with a
+//! strictly increasing version number. The version of the schema a database
+//! was written with is stored in `COL_EXTRA`; the final batch of every
+//! migration is committed together with the version bump, so an interrupted
+//! migration is simply run again on the next start. New databases are stamped
+//! with the latest version when they are created.
+
+use std::{fmt, io};
+
+use kvdb::{DBTransaction, KeyValueDB};
+
+use crate::db::COL_EXTRA;
+
+/// Key under which the schema version is stored in `COL_EXTRA`.
+pub const SCHEMA_VERSION_KEY: &'static [u8] = b"schema_version";
+
+/// Reads the schema version of the database. Databases created before schema
+/// versions were introduced are at version 0.
+pub fn schema_version(db: &dyn KeyValueDB) -> io::Result<u32> {
+    match db.get(COL_EXTRA, SCHEMA_VERSION_KEY)? {
+        Some(value) => {
+            rlp::decode(&value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+        None => Ok(0),
+    }
+}
+
+fn set_schema_version(batch: &mut DBTransaction, version: u32) {
+    batch.put(COL_EXTRA, SCHEMA_VERSION_KEY, &rlp::encode(&version));
+}
+
+/// Stamps a newly created database with `version`, usually `Migrations::latest_version`,
+/// so that no migrations are run over data which is already in the latest layout.
+pub fn init_schema_version(db: &dyn KeyValueDB, version: u32) -> io::Result<()> {
+    let mut batch = DBTransaction::new();
+    set_schema_version(&mut batch, version);
+    db.write(batch)
+}
+
+/// Migration related errors.
+#[derive(Debug)]
+pub enum Error {
+    /// Database was written by a newer client.
+    FutureVersion(u32),
+    /// Migrations were registered out of order.
+    OutOfOrder {
+        /// Version of the last registered migration.
+        last: u32,
+        /// Version of the rejected migration.
+        version: u32,
+    },
+    /// Underlying database error.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::FutureVersion(version) => write!(
+                f,
+                "Database schema version {} is newer than supported. Upgrade your client or delete DB and resync.",
+                version
+            ),
+            Error::OutOfOrder { last, version } => write!(
+                f,
+                "Migration to version {} registered after version {}",
+                version, last
+            ),
+            Error::Io(ref err) => write!(f, "Unexpected io error on DB migration: {}", err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// Progress of a running migration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// Version the database is being migrated to.
+    pub version: u32,
+    /// Number of items migrated so far.
+    pub done: u64,
+    /// Expected number of items, if known.
+    pub total: Option<u64>,
+}
+
+/// Handed to a migration to commit its batches and report progress.
+pub struct MigrationContext<'a> {
+    db: &'a dyn KeyValueDB,
+    version: u32,
+    done: u64,
+    total: Option<u64>,
+    pending: Option<DBTransaction>,
+    progress: &'a mut dyn FnMut(Progress),
+}
+
+impl<'a> MigrationContext<'a> {
+    /// Sets the expected number of items, used for progress reporting.
+    pub fn set_total(&mut self, total: u64) {
+        self.total = Some(total);
+    }
+
+    /// Queues a batch covering `items` migrated items. The previously queued
+    /// batch is written out; the last one is held back to be committed
+    /// together with the version bump.
+    pub fn write(&mut self, batch: DBTransaction, items: u64) -> io::Result<()> {
+        if let Some(previous) = self.pending.replace(batch) {
+            self.db.write(previous)?;
+        }
+        self.done += items;
+        (self.progress)(Progress {
+            version: self.version,
+            done: self.done,
+            total: self.total,
+        });
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        let mut batch = self.pending.take().unwrap_or_else(DBTransaction::new);
+        set_schema_version(&mut batch, self.version);
+        self.db.write(batch)
+    }
+}
+
+/// A forward migration of the database schema.
+pub trait Migration: Send + Sync {
+    /// Schema version the database is at after this migration.
+    fn version(&self) -> u32;
+    /// Short human readable description.
+    fn description(&self) -> &str;
+    /// Migrates the database, committing batches through `ctx`. Migrations
+    /// must tolerate being re-run over partially migrated data.
+    fn migrate(&self, db: &dyn KeyValueDB, ctx: &mut MigrationContext) -> io::Result<()>;
+}
+
+/// Ordered set of migrations.
+#[derive(Default)]
+pub struct Migrations {
+    migrations: Vec<Box<dyn Migration>>,
+}
+
+impl Migrations {
+    /// Creates an empty set of migrations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a migration. Versions must be strictly increasing.
+    pub fn add<M: Migration + 'static>(&mut self, migration: M) -> Result<(), Error> {
+        let last = self.latest_version();
+        if migration.version() <= last {
+            return Err(Error::OutOfOrder {
+                last,
+                version: migration.version(),
+            });
+        }
+        self.migrations.push(Box::new(migration));
+        Ok(())
+    }
+
+    /// Schema version after all migrations were applied.
+    pub fn latest_version(&self) -> u32 {
+        self.migrations.last().map_or(0, |m| m.version())
+    }
+
+    /// Migrations which still have to be applied to a database at `current`.
+    pub fn pending(&self, current: u32) -> Result<Vec<&dyn Migration>, Error> {
+        if current > self.latest_version() {
+            return Err(Error::FutureVersion(current));
+        }
+        Ok(self
+            .migrations
+            .iter()
+            .filter(|m| m.version() > current)
+            .map(|m| &**m)
+            .collect())
+    }
+
+    /// Applies all pending migrations, reporting progress through `progress`.
+    /// Returns the resulting schema version.
+    pub fn run(
+        &self,
+        db: &dyn KeyValueDB,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<u32, Error> {
+        let current = schema_version(db)?;
+        for migration in self.pending(current)? {
+            let mut ctx = MigrationContext {
+                db,
+                version: migration.version(),
+                done: 0,
+                total: None,
+                pending: None,
+                progress: &mut *progress,
+            };
+            migration.migrate(db, &mut ctx)?;
+            ctx.finish()?;
+        }
+        Ok(self.latest_version().max(current))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::COL_STATE;
+
+    struct Rename(u32, &'static [u8], &'static [u8]);
+
+    impl Migration for Rename {
+        fn version(&self) -> u32 {
+            self.0
+        }
+
+        fn description(&self) -> &str {
+            "rename key"
+        }
+
+        fn migrate(&self, db: &dyn KeyValueDB, ctx: &mut MigrationContext) -> io::Result<()> {
+            ctx.set_total(1);
+            if let Some(value) = db.get(COL_STATE, self.1)? {
+                let mut batch = DBTransaction::new();
+                batch.delete(COL_STATE, self.1);
+                batch.put(COL_STATE, self.2, &value);
+                ctx.write(batch, 1)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn runs_pending_migrations_and_bumps_version() {
+        let db = kvdb_memorydb::create(crate::NUM_COLUMNS.unwrap());
+        let mut batch = DBTransaction::new();
+        batch.put(COL_STATE, b"a", b"value");
+        db.write(batch).unwrap();
+
+        let mut migrations = Migrations::new();
+        migrations.add(Rename(1, b"a", b"b")).unwrap();
+        migrations.add(Rename(2, b"b", b"c")).unwrap();
+
+        let mut reported = Vec::new();
+        assert_eq!(migrations.run(&db, &mut |p| reported.push(p)).unwrap(), 2);
+        assert_eq!(schema_version(&db).unwrap(), 2);
+        assert_eq!(&*db.get(COL_STATE, b"c").unwrap().unwrap(), b"value");
+        assert_eq!(
+            reported,
+            vec![
+                Progress {
+                    version: 1,
+                    done: 1,
+                    total: Some(1)
+                },
+                Progress {
+                    version: 2,
+                    done: 1,
+                    total: Some(1)
+                },
+            ]
+        );
+
+        // nothing left to do.
+        assert!(migrations.pending(2).unwrap().is_empty());
+        assert_eq!(migrations.run(&db, &mut |_| panic!()).unwrap(), 2);
+    }
+
+    #[test]
+    fn skips_migrations_on_stamped_databases() {
+        let db = kvdb_memorydb::create(crate::NUM_COLUMNS.unwrap());
+        let mut migrations = Migrations::new();
+        migrations.add(Rename(1, b"a", b"b")).unwrap();
+        assert_eq!(schema_version(&db).unwrap(), 0);
+
+        init_schema_version(&db, migrations.latest_version()).unwrap();
+
+        assert_eq!(schema_version(&db).unwrap(), 1);
+        assert_eq!(migrations.run(&db, &mut |_| panic!()).unwrap(), 1);
+    }
+
+    #[test]
+    fn rejects_out_of_order_and_future_versions() {
+        let mut migrations = Migrations::new();
+        migrations.add(Rename(2, b"a", b"b")).unwrap();
+        assert!(migrations.add(Rename(2, b"b", b"c")).is_err());
+        assert!(migrations.add(Rename(1, b"b", b"c")).is_err());
+
+        let db = kvdb_memorydb::create(crate::NUM_COLUMNS.unwrap());
+        let mut batch = DBTransaction::new();
+        set_schema_version(&mut batch, 3);
+        db.write(batch).unwrap();
+        match migrations.run(&db, &mut |_| {}) {
+            Err(Error::FutureVersion(3)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Database read-amplification profiler.
+//!
+//! When enabled, every database read is attributed to the code path that issued it (as declared
+//! with [`scope`]), the column, a key class and whether it was served by a cache, found in the
+//! database or missing. The aggregated [`report`] points at the worst read-amplification
+//! offenders. The profiler is disabled by default, costing a single atomic load per read.
+
+use parking_lot::{const_mutex, Mutex};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STATS: Mutex<Option<HashMap<ReadKey, ReadCounters>>> = const_mutex(None);
+
+thread_local! {
+    static SCOPES: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    static KEY_CLASS: Cell<Option<&'static str>> = Cell::new(None);
+}
+
+/// Path recorded for reads issued outside of any scope.
+const UNSCOPED: &str = "unscoped";
+
+/// Outcome of a profiled read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ReadOutcome {
+    /// Served from an in-memory cache, the database wasn't touched.
+    CacheHit,
+    /// Read from the database, value found.
+    Hit,
+    /// Read from the database, value missing.
+    Miss,
+}
+
+impl fmt::Display for ReadOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadOutcome::CacheHit => write!(f, "cache-hit"),
+            ReadOutcome::Hit => write!(f, "hit"),
+            ReadOutcome::Miss => write!(f, "miss"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ReadKey {
+    path: String,
+    column: Option<u32>,
+    key_class: Cow<'static, str>,
+    outcome: ReadOutcome,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct ReadCounters {
+    count: u64,
+    bytes: u64,
+}
+
+/// Aggregated reads of a single code path, column, key class and outcome.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadStat {
+    /// Code path, nested scopes joined with `/`.
+    pub path: String,
+    /// Database column.
+    pub column: Option<u32>,
+    /// Type of the value read, or the key length for untyped reads.
+    pub key_class: String,
+    /// Outcome of the reads.
+    pub outcome: ReadOutcome,
+    /// Number of reads.
+    pub count: u64,
+    /// Number of value bytes read from the database.
+    pub bytes: u64,
+}
+
+/// Read statistics ordered by number of reads, most frequent first.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ReadReport(pub Vec<ReadStat>);
+
+impl fmt::Display for ReadReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<32} {:>6} {:<32} {:<9} {:>12} {:>14}",
+            "path", "column", "key class", "outcome", "reads", "bytes"
+        )?;
+        for stat in &self.0 {
+            let column = stat
+                .column
+                .map_or_else(|| "-".to_owned(), |c| c.to_string());
+            writeln!(
+                f,
+                "{:<32} {:>6} {:<32} {:<9} {:>12} {:>14}",
+                stat.path, column, stat.key_class, stat.outcome, stat.count, stat.bytes
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Start recording database reads.
+pub fn enable() {
+    STATS.lock().get_or_insert_with(HashMap::new);
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Stop recording database reads. Collected statistics are kept.
+pub fn disable() {
+    ENABLED.store(false, Ordering::SeqCst);
+}
+
+/// Whether reads are currently being recorded.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Drop all collected statistics.
+pub fn reset() {
+    if let Some(stats) = STATS.lock().as_mut() {
+        stats.clear();
+    }
+}
+
+/// Aggregated statistics collected so far.
+pub fn report() -> ReadReport {
+    let mut stats = STATS
+        .lock()
+        .as_ref()
+        .map(|stats| {
+            stats
+                .iter()
+                .map(|(key, counters)| ReadStat {
+                    path: key.path.clone(),
+                    column: key.column,
+                    key_class: key.key_class.to_string(),
+                    outcome: key.outcome,
+                    count: counters.count,
+                    bytes: counters.bytes,
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    stats.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.path.cmp(&b.path)));
+    ReadReport(stats)
+}
+
+/// Guard attributing reads issued by the current thread to a code path until dropped.
+#[must_use]
+pub struct Scope {
+    active: bool,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if self.active {
+            SCOPES.with(|scopes| scopes.borrow_mut().pop());
+        }
+    }
+}
+
+/// Attribute all reads of the current thread to the given code path until the returned guard
+/// is dropped. Scopes nest.
+pub fn scope(name: &str) -> Scope {
+    if !is_enabled() {
+        return Scope { active: false };
+    }
+    SCOPES.with(|scopes| scopes.borrow_mut().push(name.to_owned()));
+    Scope { active: true }
+}
+
+/// Classify reads issued by `f` on the current thread as reads of the given value type.
+pub fn with_key_class<F, R>(key_class: &'static str, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    if !is_enabled() {
+        return f();
+    }
+    let previous = KEY_CLASS.with(|class| class.replace(Some(key_class)));
+    let result = f();
+    KEY_CLASS.with(|class| class.set(previous));
+    result
+}
+
+/// Record a read served from a cache.
+pub fn record_cache_hit(column: Option<u32>, key_class: &'static str) {
+    if !is_enabled() {
+        return;
+    }
+    record(column, Cow::Borrowed(key_class), ReadOutcome::CacheHit, 0);
+}
+
+/// Record a read that reached the database. `value_len` is `None` if the key was missing.
+pub fn record_db_read(column: Option<u32>, key: &[u8], value_len: Option<usize>) {
+    if !is_enabled() {
+        return;
+    }
+    let key_class = match KEY_CLASS.with(Cell::get) {
+        Some(class) => Cow::Borrowed(class),
+        None => Cow::Owned(format!("{}-byte key", key.len())),
+    };
+    let outcome = match value_len {
+        Some(_) => ReadOutcome::Hit,
+        None => ReadOutcome::Miss,
+    };
+    record(column, key_class, outcome, value_len.unwrap_or(0) as u64);
+}
+
+/// Last path segment of a type name, e.g. `BlockDetails` for `common_types::BlockDetails`.
+pub fn short_type_name<T: ?Sized>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let base = name.split('<').next().unwrap_or(name);
+    match base.rfind("::") {
+        Some(pos) => &name[pos + 2..],
+        None => name,
+    }
+}
+
+fn record(column: Option<u32>, key_class: Cow<'static, str>, outcome: ReadOutcome, bytes: u64) {
+    let path = SCOPES.with(|scopes| {
+        let scopes = scopes.borrow();
+        if scopes.is_empty() {
+            UNSCOPED.to_owned()
+        } else {
+            scopes.join("/")
+        }
+    });
+    let key = ReadKey {
+        path,
+        column,
+        key_class,
+        outcome,
+    };
+
+    let mut stats = STATS.lock();
+    let counters = stats
+        .get_or_insert_with(HashMap::new)
+        .entry(key)
+        .or_default();
+    counters.count += 1;
+    counters.bytes += bytes;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_attribute_reads_to_scopes() {
+        enable();
+        reset();
+        {
+            let _import = scope("import");
+            record_db_read(Some(3), &[0u8; 33], Some(10));
+            {
+                let _inner = scope("uncles");
+                with_key_class("BlockDetails", || record_db_read(Some(3), &[0u8; 33], None));
+            }
+            record_cache_hit(Some(3), "BlockDetails");
+            record_db_read(Some(3), &[1u8; 33], Some(5));
+        }
+        disable();
+        // not recorded while disabled
+        record_db_read(Some(3), &[0u8; 33], Some(10));
+
+        let report = report();
+        let find = |path: &str, outcome| {
+            report
+                .0
+                .iter()
+                .find(|s| s.path == path && s.outcome == outcome)
+                .cloned()
+        };
+
+        let raw = find("import", ReadOutcome::Hit).unwrap();
+        assert_eq!((raw.count, raw.bytes), (2, 15));
+        assert_eq!(raw.key_class, "33-byte key");
+
+        let miss = find("import/uncles", ReadOutcome::Miss).unwrap();
+        assert_eq!(miss.key_class, "BlockDetails");
+        assert_eq!(find("import", ReadOutcome::CacheHit).unwrap().count, 1);
+        assert_eq!(
+            report
+                .0
+                .iter()
+                .filter(|s| s.path.starts_with("import"))
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn should_shorten_type_names() {
+        assert_eq!(
+            short_type_name::<std::collections::HashMap<u8, u8>>(),
+            "HashMap<u8, u8>"
+        );
+        assert_eq!(short_type_name::<u64>(), "u64");
+    }
+}
@@ -18,11 +18,12 @@
 use crate::{AsKeyedHashDB, KeyedHashDB};
 use archivedb::ArchiveDB;
 use earlymergedb::EarlyMergeDB;
+use generationaldb::GenerationalDB;
 use hash_db::{AsHashDB, HashDB};
 use keccak_hasher::KeccakHasher;
 use kvdb::DBValue;
 use overlaydb::OverlayDB;
-use overlayrecentdb::OverlayRecentDB;
+use overlayrecentdb::{OverlayRecentDB, PinnedOverlayRecentDB};
 use refcounteddb::RefCountedDB;
 
 impl AsHashDB<KeccakHasher, DBValue> for ArchiveDB {
@@ -43,6 +44,15 @@ impl AsHashDB<KeccakHasher, DBValue> for EarlyMergeDB {
     }
 }
 
+impl AsHashDB<KeccakHasher, DBValue> for GenerationalDB {
+    fn as_hash_db(&self) -> &dyn HashDB<KeccakHasher, DBValue> {
+        self
+    }
+    fn as_hash_db_mut(&mut self) -> &mut dyn HashDB<KeccakHasher, DBValue> {
+        self
+    }
+}
+
 impl AsHashDB<KeccakHasher, DBValue> for OverlayRecentDB {
     fn as_hash_db(&self) -> &dyn HashDB<KeccakHasher, DBValue> {
         self
@@ -52,6 +62,15 @@ impl AsHashDB<KeccakHasher, DBValue> for OverlayRecentDB {
     }
 }
 
+impl AsHashDB<KeccakHasher, DBValue> for PinnedOverlayRecentDB {
+    fn as_hash_db(&self) -> &dyn HashDB<KeccakHasher, DBValue> {
+        self
+    }
+    fn as_hash_db_mut(&mut self) -> &mut dyn HashDB<KeccakHasher, DBValue> {
+        self
+    }
+}
+
 impl AsHashDB<KeccakHasher, DBValue> for RefCountedDB {
     fn as_hash_db(&self) -> &dyn HashDB<KeccakHasher, DBValue> {
         self
@@ -82,6 +101,12 @@ impl AsKeyedHashDB for EarlyMergeDB {
     }
 }
 
+impl AsKeyedHashDB for GenerationalDB {
+    fn as_keyed_hash_db(&self) -> &dyn KeyedHashDB {
+        self
+    }
+}
+
 impl AsKeyedHashDB for OverlayRecentDB {
     fn as_keyed_hash_db(&self) -> &dyn KeyedHashDB {
         self
@@ -0,0 +1,758 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `JournalDB` keeping recent eras in memory and flushing canonical changes in generations.
+
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    io, mem,
+    sync::Arc,
+};
+
+use super::{error_negatively_reference_hash, JournalDB, DB_PREFIX_LEN, LATEST_ERA_KEY};
+use bytes::Bytes;
+use ethcore_db::{DBTransaction, DBValue, KeyValueDB};
+use ethereum_types::H256;
+use fastmap::H256FastMap;
+use hash_db::HashDB;
+use keccak_hasher::KeccakHasher;
+use memory_db::*;
+use overlayrecentdb::{to_short_key, DatabaseValue, DatabaseValueRef};
+use parking_lot::RwLock;
+use rlp::{decode, encode};
+use util::DatabaseKey;
+
+/// Number of canonical eras collected before their changes are written to the backing database.
+const GENERATION_ERAS: usize = 16;
+
+/// Implementation of the `JournalDB` trait for a disk-backed database with a memory overlay,
+/// tuned for short pruning histories.
+///
+/// Journalling works like in `OverlayRecentDB`: every commit is journalled to disk and its
+/// insertions are kept in the history overlay. Unlike `OverlayRecentDB`, marking an era as
+/// canonical does not touch the state in the backing database. Canonical eras are collected
+/// into a generation which stays in memory, and once `GENERATION_ERAS` eras were collected
+/// their net changes are written out in a single batch. Nodes which are inserted and removed
+/// again within one generation never reach the disk, which avoids the write amplification of
+/// flushing every era separately.
+///
+/// The journal records of a generation are deleted together with the flush. Should the node
+/// stop before that, the eras are recovered as not yet canonical and marked again.
+pub struct GenerationalDB {
+    transaction_overlay: MemoryDB<KeccakHasher, DBValue>,
+    backing: Arc<dyn KeyValueDB>,
+    journal_overlay: Arc<RwLock<JournalOverlay>>,
+    generation_eras: usize,
+    column: Option<u32>,
+}
+
+#[derive(PartialEq)]
+struct JournalOverlay {
+    backing_overlay: MemoryDB<KeccakHasher, DBValue>, // Nodes added in the history period
+    pending_overlay: H256FastMap<DBValue>, // Nodes being transfered from backing_overlay to backing db
+    journal: HashMap<u64, Vec<JournalEntry>>,
+    generation: Vec<CanonicalEra>, // Canonical eras waiting to be flushed, oldest first
+    latest_era: Option<u64>,
+    earliest_era: Option<u64>,
+    cumulative_size: usize, // cumulative size of all entries.
+}
+
+#[derive(PartialEq)]
+struct JournalEntry {
+    id: H256,
+    insertions: Vec<H256>,
+    deletions: Vec<H256>,
+}
+
+#[derive(PartialEq)]
+struct CanonicalEra {
+    era: u64,
+    records: usize,
+    insertions: Vec<H256>,
+    deletions: Vec<H256>,
+}
+
+impl Clone for GenerationalDB {
+    fn clone(&self) -> GenerationalDB {
+        GenerationalDB {
+            transaction_overlay: self.transaction_overlay.clone(),
+            backing: self.backing.clone(),
+            journal_overlay: self.journal_overlay.clone(),
+            generation_eras: self.generation_eras,
+            column: self.column.clone(),
+        }
+    }
+}
+
+impl GenerationalDB {
+    /// Create a new instance.
+    pub fn new(backing: Arc<dyn KeyValueDB>, col: Option<u32>) -> GenerationalDB {
+        Self::with_generation_eras(backing, col, GENERATION_ERAS)
+    }
+
+    fn with_generation_eras(
+        backing: Arc<dyn KeyValueDB>,
+        col: Option<u32>,
+        generation_eras: usize,
+    ) -> GenerationalDB {
+        let journal_overlay = Arc::new(RwLock::new(Self::read_overlay(&*backing, col)));
+        GenerationalDB {
+            transaction_overlay: ::new_memory_db(),
+            backing,
+            journal_overlay,
+            generation_eras,
+            column: col,
+        }
+    }
+
+    #[cfg(test)]
+    fn can_reconstruct_refs(&self) -> bool {
+        let reconstructed = Self::read_overlay(&*self.backing, self.column);
+        let journal_overlay = self.journal_overlay.read();
+        journal_overlay.generation.is_empty()
+            && journal_overlay.backing_overlay == reconstructed.backing_overlay
+            && journal_overlay.pending_overlay == reconstructed.pending_overlay
+            && journal_overlay.journal == reconstructed.journal
+            && journal_overlay.latest_era == reconstructed.latest_era
+            && journal_overlay.cumulative_size == reconstructed.cumulative_size
+    }
+
+    fn payload(&self, key: &H256) -> Option<DBValue> {
+        self.backing
+            .get(self.column, key.as_bytes())
+            .expect("Low-level database error. Some issue with your hard disk?")
+    }
+
+    fn read_overlay(db: &dyn KeyValueDB, col: Option<u32>) -> JournalOverlay {
+        let mut journal = HashMap::new();
+        let mut overlay = ::new_memory_db();
+        let mut latest_era = None;
+        let mut earliest_era = None;
+        let mut cumulative_size = 0;
+        if let Some(val) = db
+            .get(col, &LATEST_ERA_KEY)
+            .expect("Low-level database error.")
+        {
+            let mut era = decode::<u64>(&val).expect("decoding db value failed");
+            latest_era = Some(era);
+            loop {
+                let mut db_key = DatabaseKey { era, index: 0usize };
+                while let Some(rlp_data) = db
+                    .get(col, &encode(&db_key))
+                    .expect("Low-level database error.")
+                {
+                    let value = decode::<DatabaseValue>(&rlp_data).expect(&format!(
+                        "read_overlay: Error decoding DatabaseValue era={}, index{}",
+                        era, db_key.index
+                    ));
+                    let mut inserted_keys = Vec::new();
+                    for (k, v) in value.inserts {
+                        let short_key = to_short_key(&k);
+
+                        if !overlay.contains(&short_key) {
+                            cumulative_size += v.len();
+                        }
+
+                        overlay.emplace(short_key, v);
+                        inserted_keys.push(k);
+                    }
+                    journal
+                        .entry(era)
+                        .or_insert_with(Vec::new)
+                        .push(JournalEntry {
+                            id: value.id,
+                            insertions: inserted_keys,
+                            deletions: value.deletes,
+                        });
+                    db_key.index += 1;
+                    earliest_era = Some(era);
+                }
+                if db_key.index == 0 || era == 0 {
+                    break;
+                }
+                era -= 1;
+            }
+        }
+        trace!(target: "journaldb", "Recovered {} journal entries", journal.len());
+        JournalOverlay {
+            backing_overlay: overlay,
+            pending_overlay: HashMap::default(),
+            journal,
+            generation: Vec::new(),
+            latest_era,
+            earliest_era,
+            cumulative_size,
+        }
+    }
+
+    /// Writes the net changes of all collected canonical eras and drops their journal records.
+    fn flush_generation(
+        &self,
+        batch: &mut DBTransaction,
+        journal_overlay: &mut JournalOverlay,
+    ) -> u32 {
+        let generation = mem::replace(&mut journal_overlay.generation, Vec::new());
+        trace!(target: "journaldb", "Flushing {} canonical eras", generation.len());
+
+        // later eras override earlier ones: `None` marks a removal. The flag tells whether
+        // the key was inserted within this generation.
+        let mut changes: H256FastMap<(Option<DBValue>, bool)> = H256FastMap::default();
+        for era in &generation {
+            for k in &era.insertions {
+                if let Some((d, rc)) = journal_overlay.backing_overlay.raw(&to_short_key(k)) {
+                    if rc > 0 {
+                        changes.insert(*k, (Some(d.clone()), true));
+                    }
+                }
+            }
+            for k in &era.deletions {
+                let change = changes.entry(*k).or_insert((None, false));
+                change.0 = None;
+            }
+        }
+
+        for era in &generation {
+            for k in &era.insertions {
+                if let Some(val) = journal_overlay
+                    .backing_overlay
+                    .remove_and_purge(&to_short_key(k))
+                {
+                    journal_overlay.cumulative_size -= val.len();
+                }
+            }
+            for index in 0..era.records {
+                let db_key = DatabaseKey {
+                    era: era.era,
+                    index,
+                };
+                batch.delete(self.column, &encode(&db_key));
+            }
+        }
+        journal_overlay.backing_overlay.shrink_to_fit();
+
+        let mut ops = 0;
+        for (k, (change, inserted)) in changes {
+            match change {
+                Some(v) => {
+                    batch.put(self.column, k.as_bytes(), &v);
+                    journal_overlay.pending_overlay.insert(to_short_key(&k), v);
+                    ops += 1;
+                }
+                // still referenced by a more recent era.
+                None if journal_overlay.backing_overlay.contains(&to_short_key(&k)) => {}
+                // inserted and removed within the generation, never reached the disk.
+                None if inserted && self.payload(&k).is_none() => {}
+                None => {
+                    batch.delete(self.column, k.as_bytes());
+                    ops += 1;
+                }
+            }
+        }
+        ops
+    }
+}
+
+impl ::traits::KeyedHashDB for GenerationalDB {
+    fn keys(&self) -> HashMap<H256, i32> {
+        let mut ret: HashMap<H256, i32> = self
+            .backing
+            .iter(self.column)
+            .map(|(key, _)| (H256::from_slice(&*key), 1))
+            .collect();
+
+        for (key, refs) in self.transaction_overlay.keys() {
+            match ret.entry(key) {
+                Entry::Occupied(mut entry) => {
+                    *entry.get_mut() += refs;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(refs);
+                }
+            }
+        }
+        ret
+    }
+}
+
+impl JournalDB for GenerationalDB {
+    fn boxed_clone(&self) -> Box<dyn JournalDB> {
+        Box::new(self.clone())
+    }
+
+    fn get_sizes(&self, sizes: &mut BTreeMap<String, usize>) {
+        sizes.insert(
+            String::from("db_generational_transactions_size"),
+            self.transaction_overlay.len(),
+        );
+
+        let overlay = self.journal_overlay.read();
+        sizes.insert(
+            String::from("db_generational_backing_size"),
+            overlay.backing_overlay.len(),
+        );
+        sizes.insert(
+            String::from("db_generational_pending_size"),
+            overlay.pending_overlay.len(),
+        );
+        sizes.insert(
+            String::from("db_generational_journal_size"),
+            overlay.journal.len(),
+        );
+        sizes.insert(
+            String::from("db_generational_generation_size"),
+            overlay.generation.len(),
+        );
+    }
+
+    fn journal_size(&self) -> usize {
+        self.journal_overlay.read().cumulative_size
+    }
+
+    fn is_empty(&self) -> bool {
+        self.backing
+            .get(self.column, &LATEST_ERA_KEY)
+            .expect("Low level database error")
+            .is_none()
+    }
+
+    fn backing(&self) -> &Arc<dyn KeyValueDB> {
+        &self.backing
+    }
+
+    fn latest_era(&self) -> Option<u64> {
+        self.journal_overlay.read().latest_era
+    }
+
+    fn earliest_era(&self) -> Option<u64> {
+        self.journal_overlay.read().earliest_era
+    }
+
+    fn journal_under(&mut self, batch: &mut DBTransaction, now: u64, id: &H256) -> io::Result<u32> {
+        trace!(target: "journaldb", "entry: #{} ({})", now, id);
+
+        let mut journal_overlay = self.journal_overlay.write();
+
+        // flush previous changes
+        journal_overlay.pending_overlay.clear();
+
+        let mut tx = self.transaction_overlay.drain();
+        let inserted_keys: Vec<_> = tx
+            .iter()
+            .filter_map(|(k, &(_, c))| if c > 0 { Some(k.clone()) } else { None })
+            .collect();
+        let removed_keys: Vec<_> = tx
+            .iter()
+            .filter_map(|(k, &(_, c))| if c < 0 { Some(k.clone()) } else { None })
+            .collect();
+        let ops = inserted_keys.len() + removed_keys.len();
+
+        let insertions: Vec<_> = tx
+            .drain()
+            .filter_map(|(k, (v, c))| if c > 0 { Some((k, v)) } else { None })
+            .collect();
+
+        let encoded_value = encode(&DatabaseValueRef {
+            id,
+            inserts: &insertions,
+            deletes: &removed_keys,
+        });
+
+        for (k, v) in insertions {
+            let short_key = to_short_key(&k);
+            if !journal_overlay.backing_overlay.contains(&short_key) {
+                journal_overlay.cumulative_size += v.len();
+            }
+
+            journal_overlay.backing_overlay.emplace(short_key, v);
+        }
+
+        let index = journal_overlay.journal.get(&now).map_or(0, |j| j.len());
+        let db_key = DatabaseKey { era: now, index };
+
+        batch.put_vec(self.column, &encode(&db_key), encoded_value.to_vec());
+        if journal_overlay.latest_era.map_or(true, |e| now > e) {
+            batch.put_vec(self.column, &LATEST_ERA_KEY, encode(&now).to_vec());
+            journal_overlay.latest_era = Some(now);
+        }
+
+        if journal_overlay.earliest_era.map_or(true, |e| e > now) {
+            journal_overlay.earliest_era = Some(now);
+        }
+
+        journal_overlay
+            .journal
+            .entry(now)
+            .or_insert_with(Vec::new)
+            .push(JournalEntry {
+                id: id.clone(),
+                insertions: inserted_keys,
+                deletions: removed_keys,
+            });
+        Ok(ops as u32)
+    }
+
+    fn mark_canonical(
+        &mut self,
+        batch: &mut DBTransaction,
+        end_era: u64,
+        canon_id: &H256,
+    ) -> io::Result<u32> {
+        trace!(target: "journaldb", "canonical: #{} ({})", end_era, canon_id);
+
+        let mut journal_overlay = self.journal_overlay.write();
+        let journal_overlay = &mut *journal_overlay;
+
+        if let Some(records) = journal_overlay.journal.remove(&end_era) {
+            let mut canonical = CanonicalEra {
+                era: end_era,
+                records: records.len(),
+                insertions: Vec::new(),
+                deletions: Vec::new(),
+            };
+            for journal in records {
+                if *canon_id == journal.id {
+                    canonical.insertions = journal.insertions;
+                    canonical.deletions = journal.deletions;
+                    continue;
+                }
+                // non-canonical insertions can go right away.
+                for k in &journal.insertions {
+                    if let Some(val) = journal_overlay
+                        .backing_overlay
+                        .remove_and_purge(&to_short_key(k))
+                    {
+                        journal_overlay.cumulative_size -= val.len();
+                    }
+                }
+            }
+            journal_overlay.generation.push(canonical);
+        }
+
+        if !journal_overlay.journal.is_empty() {
+            trace!(target: "journaldb", "Set earliest_era to {}", end_era + 1);
+            journal_overlay.earliest_era = Some(end_era + 1);
+        }
+
+        if journal_overlay.generation.len() >= self.generation_eras {
+            return Ok(self.flush_generation(batch, journal_overlay));
+        }
+
+        Ok(0)
+    }
+
+    fn flush(&self) {
+        self.journal_overlay.write().pending_overlay.clear();
+    }
+
+    fn inject(&mut self, batch: &mut DBTransaction) -> io::Result<u32> {
+        let mut ops = 0;
+        for (key, (value, rc)) in self.transaction_overlay.drain() {
+            if rc != 0 {
+                ops += 1
+            }
+
+            match rc {
+                0 => {}
+                _ if rc > 0 => batch.put(self.column, key.as_bytes(), &value),
+                -1 => {
+                    if cfg!(debug_assertions)
+                        && self.backing.get(self.column, key.as_bytes())?.is_none()
+                    {
+                        return Err(error_negatively_reference_hash(&key));
+                    }
+                    batch.delete(self.column, key.as_bytes())
+                }
+                _ => panic!("Attempted to inject invalid state ({})", rc),
+            }
+        }
+
+        Ok(ops)
+    }
+
+    fn consolidate(&mut self, with: MemoryDB<KeccakHasher, DBValue>) {
+        self.transaction_overlay.consolidate(with);
+    }
+
+    fn state(&self, key: &H256) -> Option<Bytes> {
+        let journal_overlay = self.journal_overlay.read();
+        let key = to_short_key(key);
+        journal_overlay
+            .backing_overlay
+            .get(&key)
+            .map(|v| v.into_vec())
+            .or_else(|| {
+                journal_overlay
+                    .pending_overlay
+                    .get(&key)
+                    .map(|d| d.clone().into_vec())
+            })
+            .or_else(|| {
+                self.backing
+                    .get_by_prefix(self.column, &key[0..DB_PREFIX_LEN])
+                    .map(|b| b.into_vec())
+            })
+    }
+}
+
+impl HashDB<KeccakHasher, DBValue> for GenerationalDB {
+    fn get(&self, key: &H256) -> Option<DBValue> {
+        if let Some((d, rc)) = self.transaction_overlay.raw(key) {
+            if rc > 0 {
+                return Some(d.clone());
+            }
+        }
+        let v = {
+            let journal_overlay = self.journal_overlay.read();
+            let key = to_short_key(key);
+            journal_overlay
+                .backing_overlay
+                .get(&key)
+                .or_else(|| journal_overlay.pending_overlay.get(&key).cloned())
+        };
+        v.or_else(|| self.payload(key))
+    }
+
+    fn contains(&self, key: &H256) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn insert(&mut self, value: &[u8]) -> H256 {
+        self.transaction_overlay.insert(value)
+    }
+    fn emplace(&mut self, key: H256, value: DBValue) {
+        self.transaction_overlay.emplace(key, value);
+    }
+    fn remove(&mut self, key: &H256) {
+        self.transaction_overlay.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use hash_db::HashDB;
+    use keccak::keccak;
+    use overlayrecentdb::OverlayRecentDB;
+    use JournalDB;
+
+    /// A database flushing every canonical era, which behaves like `OverlayRecentDB`.
+    fn new_db() -> GenerationalDB {
+        let backing = Arc::new(ethcore_db::InMemoryWithMetrics::create(0));
+        GenerationalDB::with_generation_eras(backing, None, 1)
+    }
+
+    #[test]
+    fn long_history() {
+        // history is 3
+        let mut jdb = new_db();
+        let h = jdb.insert(b"foo");
+        jdb.commit_batch(0, &keccak(b"0"), None).unwrap();
+        assert!(jdb.can_reconstruct_refs());
+        assert!(jdb.contains(&h));
+        jdb.remove(&h);
+        jdb.commit_batch(1, &keccak(b"1"), None).unwrap();
+        assert!(jdb.can_reconstruct_refs());
+        assert!(jdb.contains(&h));
+        jdb.commit_batch(2, &keccak(b"2"), None).unwrap();
+        assert!(jdb.can_reconstruct_refs());
+        assert!(jdb.contains(&h));
+        jdb.commit_batch(3, &keccak(b"3"), Some((0, keccak(b"0"))))
+            .unwrap();
+        assert!(jdb.can_reconstruct_refs());
+        assert!(jdb.contains(&h));
+        jdb.commit_batch(4, &keccak(b"4"), Some((1, keccak(b"1"))))
+            .unwrap();
+        assert!(jdb.can_reconstruct_refs());
+        assert!(!jdb.contains(&h));
+    }
+
+    #[test]
+    fn fork() {
+        // history is 1
+        let mut jdb = new_db();
+
+        let foo = jdb.insert(b"foo");
+        let bar = jdb.insert(b"bar");
+        jdb.commit_batch(0, &keccak(b"0"), None).unwrap();
+        assert!(jdb.can_reconstruct_refs());
+
+        jdb.remove(&foo);
+        let baz = jdb.insert(b"baz");
+        jdb.commit_batch(1, &keccak(b"1a"), Some((0, keccak(b"0"))))
+            .unwrap();
+        assert!(jdb.can_reconstruct_refs());
+
+        jdb.remove(&bar);
+        jdb.commit_batch(1, &keccak(b"1b"), Some((0, keccak(b"0"))))
+            .unwrap();
+        assert!(jdb.can_reconstruct_refs());
+        assert!(jdb.contains(&foo));
+        assert!(jdb.contains(&bar));
+        assert!(jdb.contains(&baz));
+
+        jdb.commit_batch(2, &keccak(b"2b"), Some((1, keccak(b"1b"))))
+            .unwrap();
+        assert!(jdb.can_reconstruct_refs());
+        assert!(jdb.contains(&foo));
+        assert!(!jdb.contains(&baz));
+        assert!(!jdb.contains(&bar));
+    }
+
+    #[test]
+    fn canonical_changes_wait_for_the_generation() {
+        let backing = Arc::new(ethcore_db::InMemoryWithMetrics::create(0));
+        let mut jdb = GenerationalDB::with_generation_eras(backing, None, 3);
+
+        // history is 1
+        let foo = jdb.insert(b"foo");
+        jdb.commit_batch(0, &keccak(b"0"), None).unwrap();
+        jdb.remove(&foo);
+        let bar = jdb.insert(b"bar");
+        jdb.commit_batch(1, &keccak(b"1"), Some((0, keccak(b"0"))))
+            .unwrap();
+        jdb.remove(&bar);
+        jdb.commit_batch(2, &keccak(b"2"), Some((1, keccak(b"1"))))
+            .unwrap();
+
+        // two eras are canonical, but nothing was written yet.
+        assert!(jdb.payload(&foo).is_none());
+        assert!(jdb.payload(&bar).is_none());
+        assert!(jdb.contains(&foo));
+        assert!(jdb.contains(&bar));
+        assert_eq!(jdb.earliest_era(), Some(2));
+
+        jdb.commit_batch(3, &keccak(b"3"), Some((2, keccak(b"2"))))
+            .unwrap();
+        assert!(jdb.can_reconstruct_refs());
+        // `foo` and `bar` lived and died within the generation and never hit the disk.
+        assert!(!jdb.contains(&foo));
+        assert!(!jdb.contains(&bar));
+    }
+
+    #[test]
+    fn reopen_before_flush() {
+        let shared_db = Arc::new(ethcore_db::InMemoryWithMetrics::create(0));
+
+        let foo = {
+            let mut jdb = GenerationalDB::with_generation_eras(shared_db.clone(), None, 4);
+            // history is 1
+            let foo = jdb.insert(b"foo");
+            jdb.commit_batch(0, &keccak(b"0"), None).unwrap();
+            let bar = jdb.insert(b"bar");
+            jdb.commit_batch(1, &keccak(b"1a"), Some((0, keccak(b"0"))))
+                .unwrap();
+            jdb.remove(&foo);
+            jdb.commit_batch(1, &keccak(b"1b"), None).unwrap();
+            jdb.commit_batch(2, &keccak(b"2"), Some((1, keccak(b"1b"))))
+                .unwrap();
+            assert!(!jdb.contains(&bar));
+            foo
+        };
+
+        // canonical eras are recovered as journalled ones and marked again.
+        let mut jdb = GenerationalDB::with_generation_eras(shared_db, None, 4);
+        assert_eq!(jdb.earliest_era(), Some(0));
+        assert!(jdb.contains(&foo));
+        jdb.commit_batch(3, &keccak(b"3"), Some((0, keccak(b"0"))))
+            .unwrap();
+        jdb.commit_batch(4, &keccak(b"4"), Some((1, keccak(b"1b"))))
+            .unwrap();
+        jdb.commit_batch(5, &keccak(b"5"), Some((2, keccak(b"2"))))
+            .unwrap();
+        assert!(jdb.contains(&foo));
+        jdb.commit_batch(6, &keccak(b"6"), Some((3, keccak(b"3"))))
+            .unwrap();
+        assert!(!jdb.contains(&foo));
+        assert_eq!(jdb.earliest_era(), Some(4));
+    }
+
+    #[test]
+    fn inject() {
+        let mut jdb = new_db();
+        let key = jdb.insert(b"dog");
+        jdb.inject_batch().unwrap();
+
+        assert_eq!(jdb.get(&key).unwrap(), DBValue::from_slice(b"dog"));
+        jdb.remove(&key);
+        jdb.inject_batch().unwrap();
+
+        assert!(jdb.get(&key).is_none());
+    }
+
+    /// Replays the same chain into `jdb` and returns the number of state writes and
+    /// the number of bytes of state written to the backing database.
+    fn state_writes(jdb: &mut dyn JournalDB) -> (usize, usize) {
+        // history is 4, with a competing block every eighth era.
+        let history = 4;
+        let mut writes = 0;
+        let mut bytes = 0;
+        let mut live = Vec::new();
+        for era in 0..256u64 {
+            // every block rewrites a few nodes, keeping the state size constant.
+            if live.len() >= 4 {
+                let old: H256 = live.remove(0);
+                jdb.remove(&old);
+            }
+            live.push(jdb.insert(format!("node {}", era).as_bytes()));
+
+            let id = keccak(era.to_be_bytes());
+            let mut batch = jdb.backing().transaction();
+            jdb.journal_under(&mut batch, era, &id).unwrap();
+            if era % 8 == 0 {
+                jdb.insert(format!("uncle {}", era).as_bytes());
+                jdb.journal_under(&mut batch, era, &keccak(format!("uncle {}", era)))
+                    .unwrap();
+            }
+            if era >= history {
+                let canon = keccak((era - history).to_be_bytes());
+                jdb.mark_canonical(&mut batch, era - history, &canon)
+                    .unwrap();
+            }
+            for op in &batch.ops {
+                match *op {
+                    ::kvdb::DBOp::Insert {
+                        ref key, ref value, ..
+                    } if key.len() == 32 => {
+                        writes += 1;
+                        bytes += value.len();
+                    }
+                    ::kvdb::DBOp::Delete { ref key, .. } if key.len() == 32 => writes += 1,
+                    _ => {}
+                }
+            }
+            jdb.backing().write(batch).unwrap();
+            jdb.flush();
+        }
+        assert!(live.iter().all(|k| jdb.contains(k)));
+        (writes, bytes)
+    }
+
+    #[test]
+    fn writes_less_state_than_overlay_recent() {
+        let (overlay_writes, overlay_bytes) = state_writes(&mut OverlayRecentDB::new(
+            Arc::new(ethcore_db::InMemoryWithMetrics::create(0)),
+            None,
+        ));
+        let (generational_writes, generational_bytes) = state_writes(&mut GenerationalDB::new(
+            Arc::new(ethcore_db::InMemoryWithMetrics::create(0)),
+            None,
+        ));
+
+        assert!(generational_writes * 2 < overlay_writes);
+        assert!(generational_bytes * 2 < overlay_bytes);
+    }
+}
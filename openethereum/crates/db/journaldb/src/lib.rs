@@ -43,6 +43,7 @@ use std::{fmt, io, str, sync::Arc};
 mod archivedb;
 mod as_hash_db_impls;
 mod earlymergedb;
+mod generationaldb;
 mod overlayrecentdb;
 mod refcounteddb;
 /// Export the journaldb module.
@@ -84,6 +85,13 @@ pub enum Algorithm {
     ///
     /// References are counted in disk-backed DB.
     RefCounted,
+
+    /// Ancient and recent history maintained separately; recent history lasts for particular
+    /// number of blocks.
+    ///
+    /// Like `OverlayRecent`, but canonical eras are collected in memory and flushed to the
+    /// backing database in batches, so short-lived nodes never get written.
+    Generational,
 }
 
 impl str::FromStr for Algorithm {
@@ -95,6 +103,7 @@ impl str::FromStr for Algorithm {
             "light" => Ok(Algorithm::EarlyMerge),
             "fast" => Ok(Algorithm::OverlayRecent),
             "basic" => Ok(Algorithm::RefCounted),
+            "generational" => Ok(Algorithm::Generational),
             e => Err(format!("Invalid algorithm: {}", e)),
         }
     }
@@ -108,6 +117,7 @@ impl Algorithm {
             Algorithm::EarlyMerge => "light",
             Algorithm::OverlayRecent => "fast",
             Algorithm::RefCounted => "basic",
+            Algorithm::Generational => "generational",
         }
     }
 
@@ -118,6 +128,7 @@ impl Algorithm {
             Algorithm::EarlyMerge => "earlymerge",
             Algorithm::OverlayRecent => "overlayrecent",
             Algorithm::RefCounted => "refcounted",
+            Algorithm::Generational => "generational",
         }
     }
 
@@ -136,6 +147,7 @@ impl Algorithm {
             Algorithm::EarlyMerge,
             Algorithm::OverlayRecent,
             Algorithm::RefCounted,
+            Algorithm::Generational,
         ]
     }
 }
@@ -157,6 +169,7 @@ pub fn new(
         Algorithm::EarlyMerge => Box::new(earlymergedb::EarlyMergeDB::new(backing, col)),
         Algorithm::OverlayRecent => Box::new(overlayrecentdb::OverlayRecentDB::new(backing, col)),
         Algorithm::RefCounted => Box::new(refcounteddb::RefCountedDB::new(backing, col)),
+        Algorithm::Generational => Box::new(generationaldb::GenerationalDB::new(backing, col)),
     }
 }
 
@@ -192,6 +205,7 @@ mod tests {
         assert_eq!(Algorithm::EarlyMerge, "light".parse().unwrap());
         assert_eq!(Algorithm::OverlayRecent, "fast".parse().unwrap());
         assert_eq!(Algorithm::RefCounted, "basic".parse().unwrap());
+        assert_eq!(Algorithm::Generational, "generational".parse().unwrap());
     }
 
     #[test]
@@ -200,6 +214,10 @@ mod tests {
         assert_eq!(Algorithm::EarlyMerge.to_string(), "light".to_owned());
         assert_eq!(Algorithm::OverlayRecent.to_string(), "fast".to_owned());
         assert_eq!(Algorithm::RefCounted.to_string(), "basic".to_owned());
+        assert_eq!(
+            Algorithm::Generational.to_string(),
+            "generational".to_owned()
+        );
     }
 
     #[test]
@@ -208,6 +226,7 @@ mod tests {
         assert!(Algorithm::OverlayRecent.is_stable());
         assert!(!Algorithm::EarlyMerge.is_stable());
         assert!(!Algorithm::RefCounted.is_stable());
+        assert!(!Algorithm::Generational.is_stable());
     }
 
     #[test]
@@ -217,6 +236,7 @@ mod tests {
         let mut earlymerge = 0;
         let mut overlayrecent = 0;
         let mut refcounted = 0;
+        let mut generational = 0;
 
         for a in &Algorithm::all_types() {
             match *a {
@@ -224,6 +244,7 @@ mod tests {
                 Algorithm::EarlyMerge => earlymerge += 1,
                 Algorithm::OverlayRecent => overlayrecent += 1,
                 Algorithm::RefCounted => refcounted += 1,
+                Algorithm::Generational => generational += 1,
             }
         }
 
@@ -231,5 +252,6 @@ mod tests {
         assert_eq!(earlymerge, 1);
         assert_eq!(overlayrecent, 1);
         assert_eq!(refcounted, 1);
+        assert_eq!(generational, 1);
     }
 }
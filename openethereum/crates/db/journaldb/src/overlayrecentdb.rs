@@ -19,7 +19,10 @@
 use std::{
     collections::{hash_map::Entry, BTreeMap, HashMap},
     io,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc, Weak,
+    },
 };
 
 use super::{error_negatively_reference_hash, JournalDB, DB_PREFIX_LEN, LATEST_ERA_KEY};
@@ -70,13 +73,48 @@ pub struct OverlayRecentDB {
     transaction_overlay: MemoryDB<KeccakHasher, DBValue>,
     backing: Arc<dyn KeyValueDB>,
     journal_overlay: Arc<RwLock<JournalOverlay>>,
+    pins: Arc<RwLock<Vec<Weak<Preserved>>>>,
     column: Option<u32>,
 }
 
-struct DatabaseValue {
-    id: H256,
-    inserts: Vec<(H256, DBValue)>,
-    deletes: Vec<H256>,
+/// Values removed from the backing database while a pinned view is alive.
+#[derive(Default)]
+struct Preserved {
+    values: RwLock<H256FastMap<DBValue>>,
+    size: AtomicUsize,
+}
+
+impl Preserved {
+    fn insert(&self, key: H256, value: DBValue) {
+        let len = value.len();
+        if self.values.write().insert(key, value).is_none() {
+            self.size.fetch_add(len, AtomicOrdering::Relaxed);
+        }
+    }
+
+    fn get(&self, key: &H256) -> Option<DBValue> {
+        self.values.read().get(key).cloned()
+    }
+
+    fn size(&self) -> usize {
+        self.size.load(AtomicOrdering::Relaxed)
+    }
+}
+
+/// Read-only view of an `OverlayRecentDB` which keeps resolving the state it was
+/// created at while ancient eras are pruned. Any value deleted from the backing
+/// database during the lifetime of the view is kept in memory until it is dropped.
+/// The client holds back pruning once these values outgrow its memory budget, see
+/// `JournalDB::pinned_size`.
+pub struct PinnedOverlayRecentDB {
+    db: OverlayRecentDB,
+    preserved: Arc<Preserved>,
+}
+
+pub struct DatabaseValue {
+    pub id: H256,
+    pub inserts: Vec<(H256, DBValue)>,
+    pub deletes: Vec<H256>,
 }
 
 impl Decodable for DatabaseValue {
@@ -103,10 +141,10 @@ impl Decodable for DatabaseValue {
     }
 }
 
-struct DatabaseValueRef<'a> {
-    id: &'a H256,
-    inserts: &'a [(H256, DBValue)],
-    deletes: &'a [H256],
+pub struct DatabaseValueRef<'a> {
+    pub id: &'a H256,
+    pub inserts: &'a [(H256, DBValue)],
+    pub deletes: &'a [H256],
 }
 
 impl<'a> Encodable for DatabaseValueRef<'a> {
@@ -146,6 +184,7 @@ impl Clone for OverlayRecentDB {
             transaction_overlay: self.transaction_overlay.clone(),
             backing: self.backing.clone(),
             journal_overlay: self.journal_overlay.clone(),
+            pins: self.pins.clone(),
             column: self.column.clone(),
         }
     }
@@ -159,10 +198,18 @@ impl OverlayRecentDB {
             transaction_overlay: ::new_memory_db(),
             backing: backing,
             journal_overlay: journal_overlay,
+            pins: Arc::new(RwLock::new(Vec::new())),
             column: col,
         }
     }
 
+    /// Returns the preserved sets of all views which are still alive, forgetting the dropped ones.
+    fn live_pins(&self) -> Vec<Arc<Preserved>> {
+        let mut pins = self.pins.write();
+        pins.retain(|pin| pin.strong_count() > 0);
+        pins.iter().filter_map(Weak::upgrade).collect()
+    }
+
     #[cfg(test)]
     fn can_reconstruct_refs(&self) -> bool {
         let reconstructed = Self::read_overlay(&*self.backing, self.column);
@@ -250,7 +297,7 @@ impl OverlayRecentDB {
 }
 
 #[inline]
-fn to_short_key(key: &H256) -> H256 {
+pub fn to_short_key(key: &H256) -> H256 {
     let mut k = H256::zero();
     k[0..DB_PREFIX_LEN].copy_from_slice(&key[0..DB_PREFIX_LEN]);
     k
@@ -283,6 +330,21 @@ impl JournalDB for OverlayRecentDB {
         Box::new(self.clone())
     }
 
+    fn pinned_view(&self) -> Option<Box<dyn HashDB<KeccakHasher, DBValue>>> {
+        let preserved = Arc::new(Preserved::default());
+        self.pins.write().push(Arc::downgrade(&preserved));
+        Some(Box::new(PinnedOverlayRecentDB {
+            db: OverlayRecentDB {
+                transaction_overlay: ::new_memory_db(),
+                backing: self.backing.clone(),
+                journal_overlay: self.journal_overlay.clone(),
+                pins: self.pins.clone(),
+                column: self.column,
+            },
+            preserved,
+        }))
+    }
+
     fn get_sizes(&self, sizes: &mut BTreeMap<String, usize>) {
         sizes.insert(
             String::from("db_overlay_recent_transactions_size"),
@@ -302,12 +364,21 @@ impl JournalDB for OverlayRecentDB {
             String::from("db_overlay_recent_journal_size"),
             overlay.journal.len(),
         );
+        drop(overlay);
+        sizes.insert(
+            String::from("db_overlay_recent_pinned_size"),
+            self.pinned_size(),
+        );
     }
 
     fn journal_size(&self) -> usize {
         self.journal_overlay.read().cumulative_size
     }
 
+    fn pinned_size(&self) -> usize {
+        self.live_pins().iter().map(|pin| pin.size()).sum()
+    }
+
     fn is_empty(&self) -> bool {
         self.backing
             .get(self.column, &LATEST_ERA_KEY)
@@ -409,6 +480,7 @@ impl JournalDB for OverlayRecentDB {
         let mut journal_overlay = self.journal_overlay.write();
         let journal_overlay = &mut *journal_overlay;
 
+        let pins = self.live_pins();
         let mut ops = 0;
         // apply old commits' details
         if let Some(ref mut records) = journal_overlay.journal.get_mut(&end_era) {
@@ -463,6 +535,19 @@ impl JournalDB for OverlayRecentDB {
             // apply canon deletions
             for k in canon_deletions {
                 if !journal_overlay.backing_overlay.contains(&to_short_key(&k)) {
+                    if !pins.is_empty() {
+                        // keep the value around for views which may still reach it
+                        let value = journal_overlay
+                            .pending_overlay
+                            .get(&to_short_key(&k))
+                            .cloned()
+                            .or_else(|| self.payload(&k));
+                        if let Some(value) = value {
+                            for pin in &pins {
+                                pin.insert(k, value.clone());
+                            }
+                        }
+                    }
                     batch.delete(self.column, k.as_bytes());
                 }
             }
@@ -565,6 +650,28 @@ impl HashDB<KeccakHasher, DBValue> for OverlayRecentDB {
     }
 }
 
+impl HashDB<KeccakHasher, DBValue> for PinnedOverlayRecentDB {
+    fn get(&self, key: &H256) -> Option<DBValue> {
+        // the backing database must be consulted first: a value is preserved
+        // before it gets deleted, so a miss there is guaranteed to be caught here.
+        self.db.get(key).or_else(|| self.preserved.get(key))
+    }
+
+    fn contains(&self, key: &H256) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn insert(&mut self, value: &[u8]) -> H256 {
+        self.db.insert(value)
+    }
+    fn emplace(&mut self, key: H256, value: DBValue) {
+        self.db.emplace(key, value)
+    }
+    fn remove(&mut self, key: &H256) {
+        self.db.remove(key)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -639,6 +746,31 @@ mod tests {
         assert!(!jdb.contains(&h));
     }
 
+    #[test]
+    fn pinned_view_survives_pruning() {
+        // history is 3
+        let mut jdb = new_db();
+        let h = jdb.insert(b"foo");
+        jdb.commit_batch(0, &keccak(b"0"), None).unwrap();
+        jdb.remove(&h);
+        jdb.commit_batch(1, &keccak(b"1"), None).unwrap();
+
+        let view = jdb.pinned_view().unwrap();
+        jdb.commit_batch(2, &keccak(b"2"), None).unwrap();
+        jdb.commit_batch(3, &keccak(b"3"), Some((0, keccak(b"0"))))
+            .unwrap();
+        jdb.commit_batch(4, &keccak(b"4"), Some((1, keccak(b"1"))))
+            .unwrap();
+        assert!(jdb.can_reconstruct_refs());
+        assert!(!jdb.contains(&h));
+        assert_eq!(view.get(&h).unwrap().to_vec(), b"foo".to_vec());
+        assert_eq!(jdb.pinned_size(), 3);
+
+        drop(view);
+        assert!(jdb.live_pins().is_empty());
+        assert_eq!(jdb.pinned_size(), 0);
+    }
+
     #[test]
     fn complex() {
         // history is 1
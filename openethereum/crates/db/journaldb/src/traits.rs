@@ -43,6 +43,13 @@ pub trait JournalDB: KeyedHashDB {
     /// Return a copy of ourself, in a box.
     fn boxed_clone(&self) -> Box<dyn JournalDB>;
 
+    /// Return a read-only view of the state as it is now, which stays valid while the
+    /// database keeps pruning ancient eras. `None` if the algorithm cannot provide one,
+    /// in which case pruning has to be held back for as long as the state is being read.
+    fn pinned_view(&self) -> Option<Box<dyn HashDB<KeccakHasher, DBValue>>> {
+        None
+    }
+
     /// Returns heap memory size used
     fn get_sizes(&self, sizes: &mut BTreeMap<String, usize>);
 
@@ -53,6 +60,12 @@ pub trait JournalDB: KeyedHashDB {
         0
     }
 
+    /// Returns the size of the values kept in memory for the views returned by `pinned_view`.
+    /// Pruning grows it for as long as a view is alive.
+    fn pinned_size(&self) -> usize {
+        0
+    }
+
     /// Check if this database has any commits
     fn is_empty(&self) -> bool;
 
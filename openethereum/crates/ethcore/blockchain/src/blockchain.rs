@@ -17,6 +17,8 @@
 //! Blockchain database.
 
 use std::{
+    borrow::Cow,
+    cmp,
     collections::{HashMap, HashSet},
     io, mem,
     path::Path,
@@ -32,7 +34,7 @@ use common_types::{
         epoch::{PendingTransition as PendingEpochTransition, Transition as EpochTransition},
         ForkChoice,
     },
-    header::{ExtendedHeader, Header},
+    header::{ExtendedHeader, Header, HeaderDecodeParams},
     log_entry::{LocalizedLogEntry, LogEntry},
     receipt::TypedReceipt,
     transaction::LocalizedTransaction,
@@ -45,10 +47,13 @@ use db::{DBTransaction, KeyValueDB};
 use ethcore_db::{
     self as db,
     cache_manager::CacheManager,
-    keys::{BlockDetails, BlockReceipts, EpochTransitions, TransactionAddress, EPOCH_KEY_PREFIX},
-    CacheUpdatePolicy, Readable, Writable,
+    keys::{
+        BlockDetails, BlockReceipts, EpochTransitions, ExpiredTransactionKey, TransactionAddress,
+        EPOCH_KEY_PREFIX,
+    },
+    CacheUpdatePolicy, Key, Readable, Writable,
 };
-use ethereum_types::{Bloom, BloomRef, H256, U256};
+use ethereum_types::{Bloom, BloomRef, H256, H264, U256};
 use itertools::Itertools;
 use log::{info, trace, warn};
 use parity_bytes::Bytes;
@@ -91,6 +96,23 @@ pub trait BlockChainDB: Send + Sync + PrometheusMetrics {
         self.trace_blooms().reopen()?;
         Ok(())
     }
+
+    /// Take a consistent snapshot of the DB for a backup to the given path, which can be opened
+    /// by a fresh node once the snapshot is written. The caller is responsible for holding off
+    /// writes while the snapshot is taken, but not while it is written.
+    fn backup(&self, _path: &Path) -> Result<Box<dyn DatabaseBackup>, io::Error> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Backups are not supported by this database",
+        ))
+    }
+}
+
+/// Snapshot of a `BlockChainDB` taken for a backup.
+pub trait DatabaseBackup: Send {
+    /// Write the backup. `progress` is called with the number of entries copied so far,
+    /// the total is returned.
+    fn write(self: Box<Self>, progress: &dyn Fn(u64)) -> Result<u64, io::Error>;
 }
 
 /// Generic database handler. This trait contains one function `open`. When called, it opens database with a
@@ -253,6 +275,26 @@ pub struct BlockChain {
     // Stores the last block of the last sequence of blocks. `None` if there are no gaps.
     // This is calculated on start and does not get updated.
     first_block: Option<H256>,
+    // Number of the first block whose body and receipts are still stored.
+    // Everything below it (except the genesis) was removed by `expire_history`.
+    earliest_history: RwLock<BlockNumber>,
+    // Number of the first block which ancient sync may have imported below `earliest_history`
+    // and `expire_history` has not removed again yet.
+    ancient_history: RwLock<BlockNumber>,
+    // Number of the first block whose transactions are still indexed by hash.
+    // Addresses of transactions below it were removed by `expire_transaction_index`
+    // and are not written for blocks imported there afterwards.
+    earliest_transaction_index: RwLock<BlockNumber>,
+    // Whether bodies and receipts are written snappy-compressed.
+    compress_history: bool,
+    // Number of the first canonical block which `recompress_history` has not visited yet.
+    recompressed_up_to: RwLock<BlockNumber>,
+    // Held while stored history is rewritten, so that recompression never writes back a body
+    // or receipts `expire_history` deleted in the meantime.
+    history_lock: Mutex<()>,
+    // Hashes of the best block and its 255 ancestors, newest first. `None` until it is
+    // first requested or after the best block moved in a way it could not follow.
+    last_hashes: RwLock<Option<Arc<Vec<H256>>>>,
 
     // block cache
     block_headers: RwLock<HashMap<H256, encoded::Header>>,
@@ -270,12 +312,15 @@ pub struct BlockChain {
 
     pending_best_ancient_block: RwLock<Option<Option<BestAncientBlock>>>,
     pending_best_block: RwLock<Option<BestBlock>>,
+    pending_last_hashes: RwLock<Option<Option<Arc<Vec<H256>>>>>,
     pending_block_hashes: RwLock<HashMap<BlockNumber, H256>>,
     pending_block_details: RwLock<HashMap<H256, BlockDetails>>,
     pending_transaction_addresses: RwLock<HashMap<H256, Option<TransactionAddress>>>,
 
     /// Number of first block where EIP-1559 rules begin. New encoding/decoding block format.
     pub eip1559_transition: BlockNumber,
+    /// Parameters used to decode stored headers.
+    header_decode_params: HeaderDecodeParams,
 }
 
 impl BlockProvider for BlockChain {
@@ -373,7 +418,7 @@ impl BlockProvider for BlockChain {
                 "Low level database error when fetching block body data. Some issue with disk?",
             )?;
 
-        let body = encoded::Body::new(decompress(&b, blocks_swapper()).into_vec());
+        let body = encoded::Body::new(decompress(&unpack(&b), blocks_swapper()).into_vec());
         let mut write = self.block_bodies.write();
         write.insert(*hash, body.clone());
 
@@ -418,10 +463,21 @@ impl BlockProvider for BlockChain {
 
     /// Get receipts of block with given hash.
     fn block_receipts(&self, hash: &H256) -> Option<BlockReceipts> {
+        let cached = self.block_receipts.read().get(hash).cloned();
         let result =
-            self.db
-                .key_value()
-                .read_with_cache(db::COL_EXTRA, &self.block_receipts, hash)?;
+            match cached {
+                Some(receipts) => receipts,
+                None => {
+                    let key: H264 = Key::<BlockReceipts>::key(hash);
+                    let raw = self.db.key_value().get(db::COL_EXTRA, key.as_ref()).expect(
+                    "Low level database error when fetching block receipts. Some issue with disk?",
+                )?;
+                    let receipts: BlockReceipts = rlp::decode(&unpack(&raw))
+                        .expect("block receipts are stored in valid RLP, possibly compressed; qed");
+                    self.block_receipts.write().insert(*hash, receipts.clone());
+                    receipts
+                }
+            };
         self.cache_man
             .lock()
             .note_used(CacheId::BlockReceipts(*hash));
@@ -563,7 +619,7 @@ impl<'a> Iterator for AncestryWithMetadataIter<'a> {
         } else {
             let details = self.chain.block_details(&self.current);
             let header = self.chain.block_header_data(&self.current).map(|h| {
-                h.decode(self.chain.eip1559_transition)
+                h.decode(self.chain.header_decode_params)
                     .expect("Stored block header data is valid RLP; qed")
             });
 
@@ -634,17 +690,25 @@ impl<'a> Iterator for EpochTransitionIter<'a> {
 
 impl BlockChain {
     /// Create new instance of blockchain from given Genesis.
-    pub fn new(
+    pub fn new<P: Into<HeaderDecodeParams>>(
         config: Config,
         genesis: &[u8],
         db: Arc<dyn BlockChainDB>,
-        eip1559_transition: BlockNumber,
+        header_decode_params: P,
     ) -> BlockChain {
+        let header_decode_params = header_decode_params.into();
         // 400 is the average size of the key
         let cache_man = CacheManager::new(config.pref_cache_size, config.max_cache_size, 400);
 
         let mut bc = BlockChain {
             first_block: None,
+            earliest_history: RwLock::new(0),
+            ancient_history: RwLock::new(0),
+            earliest_transaction_index: RwLock::new(0),
+            compress_history: config.compress_history,
+            recompressed_up_to: RwLock::new(0),
+            history_lock: Mutex::new(()),
+            last_hashes: RwLock::new(None),
             best_block: RwLock::new(BestBlock {
                 // BestBlock will be overwritten anyway.
                 header: Default::default(),
@@ -662,10 +726,12 @@ impl BlockChain {
             cache_man: Mutex::new(cache_man),
             pending_best_ancient_block: RwLock::new(None),
             pending_best_block: RwLock::new(None),
+            pending_last_hashes: RwLock::new(None),
             pending_block_hashes: RwLock::new(HashMap::new()),
             pending_block_details: RwLock::new(HashMap::new()),
             pending_transaction_addresses: RwLock::new(HashMap::new()),
-            eip1559_transition,
+            eip1559_transition: header_decode_params.eip1559_transition,
+            header_decode_params,
         };
 
         // load best block
@@ -725,7 +791,7 @@ impl BlockChain {
             let mut best_block = bc.best_block.write();
             *best_block = BestBlock {
                 total_difficulty: best_block_total_difficulty,
-                header: best_block_rlp.decode_header(eip1559_transition),
+                header: best_block_rlp.decode_header(header_decode_params),
                 block: best_block_rlp,
             };
         }
@@ -788,6 +854,46 @@ impl BlockChain {
                 }
             }
 
+            let earliest_history = bc
+                .db
+                .key_value()
+                .get(db::COL_EXTRA, b"history")
+                .expect("Low level database error when fetching 'history' marker. Some issue with disk?")
+                .map(|v| rlp::decode::<BlockNumber>(&v).expect("'history' marker is always a valid block number; qed"));
+            if let Some(number) = earliest_history {
+                *bc.earliest_history.write() = number;
+            }
+
+            let ancient_history = bc
+                .db
+                .key_value()
+                .get(db::COL_EXTRA, b"ancient_history")
+                .expect("Low level database error when fetching 'ancient_history' marker. Some issue with disk?")
+                .map(|v| rlp::decode::<BlockNumber>(&v).expect("'ancient_history' marker is always a valid block number; qed"));
+            if let Some(number) = ancient_history {
+                *bc.ancient_history.write() = number;
+            }
+
+            let earliest_transaction_index = bc
+                .db
+                .key_value()
+                .get(db::COL_EXTRA, b"tx_index")
+                .expect("Low level database error when fetching 'tx_index' marker. Some issue with disk?")
+                .map(|v| rlp::decode::<BlockNumber>(&v).expect("'tx_index' marker is always a valid block number; qed"));
+            if let Some(number) = earliest_transaction_index {
+                *bc.earliest_transaction_index.write() = number;
+            }
+
+            let recompressed_up_to = bc
+                .db
+                .key_value()
+                .get(db::COL_EXTRA, b"recompressed")
+                .expect("Low level database error when fetching 'recompressed' marker. Some issue with disk?")
+                .map(|v| rlp::decode::<BlockNumber>(&v).expect("'recompressed' marker is always a valid block number; qed"));
+            if let Some(number) = recompressed_up_to {
+                *bc.recompressed_up_to.write() = number;
+            }
+
             // and write them
             if let (Some(hash), Some(number)) = (best_ancient, best_ancient_number) {
                 let mut best_ancient_block = bc.best_ancient_block.write();
@@ -943,7 +1049,11 @@ impl BlockChain {
 
         // store block in db
         batch.put(db::COL_HEADERS, hash.as_bytes(), &compressed_header);
-        batch.put(db::COL_BODIES, hash.as_bytes(), &compressed_body);
+        batch.put(
+            db::COL_BODIES,
+            hash.as_bytes(),
+            &self.pack(&compressed_body),
+        );
 
         let maybe_parent = self.uncommitted_block_details(&block_parent_hash);
 
@@ -1028,12 +1138,346 @@ impl BlockChain {
         }
     }
 
+    /// Number of the first block whose body and receipts are still stored.
+    /// Returns `0` if history expiry never removed anything.
+    pub fn earliest_history(&self) -> BlockNumber {
+        *self.earliest_history.read()
+    }
+
+    /// Remove bodies, receipts and transaction addresses of canonical blocks below `up_to`,
+    /// keeping their headers and details. The genesis block is never expired.
+    ///
+    /// Ancient sync fills the gap below the first block of a restored snapshot afterwards,
+    /// so blocks it imported below the expired range are removed by later calls as well.
+    ///
+    /// At most `limit` blocks are processed per call so that large ranges can be expired
+    /// incrementally. Returns the number of the first block whose history is still stored.
+    pub fn expire_history(&self, up_to: BlockNumber, limit: u64) -> BlockNumber {
+        let _history = self.history_lock.lock();
+        let from = cmp::max(self.earliest_history(), 1);
+        let to = cmp::max(
+            cmp::min(
+                cmp::min(up_to, from.saturating_add(limit)),
+                self.best_block_number(),
+            ),
+            from,
+        );
+
+        // Ancient blocks exist up to the best ancient block while the gap is being filled
+        // and up to the first block once it is closed.
+        let ancient_from = cmp::max(*self.ancient_history.read(), 1);
+        let ancient_end = match (self.best_ancient_number(), self.first_block_number()) {
+            (Some(best_ancient), _) => best_ancient + 1,
+            (None, Some(first)) => first,
+            (None, None) => 0,
+        };
+        let ancient_to = cmp::max(
+            cmp::min(
+                cmp::min(ancient_end, from),
+                ancient_from.saturating_add(limit.saturating_sub(to - from)),
+            ),
+            ancient_from,
+        );
+        if from == to && ancient_from == ancient_to {
+            return from;
+        }
+
+        let mut batch = DBTransaction::new();
+        let mut expired = Vec::new();
+        let mut expired_transactions = Vec::new();
+        for number in (ancient_from..ancient_to).chain(from..to) {
+            let hash = match self.block_hash(number) {
+                Some(hash) => hash,
+                None => continue,
+            };
+            if let Some(body) = self.block_body(&hash) {
+                for tx_hash in body.transaction_hashes() {
+                    Writable::delete::<TransactionAddress, H264>(
+                        &mut batch,
+                        db::COL_EXTRA,
+                        &tx_hash,
+                    );
+                    batch.put(
+                        db::COL_EXTRA,
+                        ExpiredTransactionKey::new(&tx_hash).as_ref(),
+                        &[],
+                    );
+                    expired_transactions.push(tx_hash);
+                }
+            }
+            batch.delete(db::COL_BODIES, hash.as_bytes());
+            Writable::delete::<BlockReceipts, H264>(&mut batch, db::COL_EXTRA, &hash);
+            expired.push(hash);
+        }
+        batch.put(db::COL_EXTRA, b"history", &rlp::encode(&to));
+        batch.put(db::COL_EXTRA, b"ancient_history", &rlp::encode(&ancient_to));
+        self.db
+            .key_value()
+            .write(batch)
+            .expect("Low level database error when expiring block history. Some issue with disk?");
+
+        {
+            let mut block_bodies = self.block_bodies.write();
+            let mut block_receipts = self.block_receipts.write();
+            let mut transaction_addresses = self.transaction_addresses.write();
+            for hash in &expired {
+                block_bodies.remove(hash);
+                block_receipts.remove(hash);
+            }
+            for hash in &expired_transactions {
+                transaction_addresses.remove(hash);
+            }
+        }
+        *self.earliest_history.write() = to;
+        *self.ancient_history.write() = ancient_to;
+
+        trace!(target: "blockchain", "Expired history of blocks {}..{} and ancient blocks {}..{}", from, to, ancient_from, ancient_to);
+        to
+    }
+
+    /// Number of the first block whose transactions can be looked up by hash.
+    /// Returns `0` if the transaction index was never pruned.
+    pub fn earliest_transaction_index(&self) -> BlockNumber {
+        cmp::max(
+            *self.earliest_transaction_index.read(),
+            self.earliest_history(),
+        )
+    }
+
+    /// Whether the transaction with given hash was in a block whose transactions are no
+    /// longer indexed. May rarely report an unknown transaction as such.
+    pub fn is_transaction_unindexed(&self, hash: &H256) -> bool {
+        self.earliest_transaction_index() > 0
+            && self
+                .db
+                .key_value()
+                .get(db::COL_EXTRA, ExpiredTransactionKey::new(hash).as_ref())
+                .expect("Low level database error when fetching a transaction marker. Some issue with disk?")
+                .is_some()
+    }
+
+    /// Remove the transaction addresses of canonical blocks below `up_to`, leaving their
+    /// bodies and receipts in place. Blocks imported below that point later on are not indexed.
+    ///
+    /// At most `limit` blocks are processed per call. Returns the number of the first block
+    /// whose transactions are still indexed.
+    pub fn expire_transaction_index(&self, up_to: BlockNumber, limit: u64) -> BlockNumber {
+        let from = cmp::max(self.earliest_transaction_index(), 1);
+        let to = cmp::min(
+            cmp::min(up_to, from.saturating_add(limit)),
+            self.best_block_number(),
+        );
+        if from >= to {
+            return from;
+        }
+
+        let mut batch = DBTransaction::new();
+        let mut expired_transactions = Vec::new();
+        for number in from..to {
+            let body = match self
+                .block_hash(number)
+                .and_then(|hash| self.block_body(&hash))
+            {
+                Some(body) => body,
+                None => continue,
+            };
+            for tx_hash in body.transaction_hashes() {
+                Writable::delete::<TransactionAddress, H264>(&mut batch, db::COL_EXTRA, &tx_hash);
+                batch.put(
+                    db::COL_EXTRA,
+                    ExpiredTransactionKey::new(&tx_hash).as_ref(),
+                    &[],
+                );
+                expired_transactions.push(tx_hash);
+            }
+        }
+        batch.put(db::COL_EXTRA, b"tx_index", &rlp::encode(&to));
+        self.db.key_value().write(batch).expect(
+            "Low level database error when expiring the transaction index. Some issue with disk?",
+        );
+
+        {
+            let mut transaction_addresses = self.transaction_addresses.write();
+            for hash in &expired_transactions {
+                transaction_addresses.remove(hash);
+            }
+        }
+        *self.earliest_transaction_index.write() = to;
+
+        trace!(target: "blockchain", "Expired transaction index of blocks {}..{}", from, to);
+        to
+    }
+
+    /// Rewrite the bodies and receipts of canonical blocks which were stored uncompressed,
+    /// continuing where the previous call stopped. Does nothing unless `compress_history`
+    /// is enabled.
+    ///
+    /// At most `limit` blocks are visited per call. Returns the number of the first
+    /// block that has not been visited yet.
+    pub fn recompress_history(&self, limit: u64) -> BlockNumber {
+        let _history = self.history_lock.lock();
+        let from = cmp::max(
+            *self.recompressed_up_to.read(),
+            *self.earliest_history.read(),
+        );
+        if !self.compress_history {
+            return from;
+        }
+        let to = cmp::min(from.saturating_add(limit), self.best_block_number() + 1);
+        if from >= to {
+            return from;
+        }
+
+        let key_value = self.db.key_value();
+        let mut batch = DBTransaction::new();
+        let mut recompressed = 0;
+        for number in from..to {
+            let hash = match self.block_hash(number) {
+                Some(hash) => hash,
+                None => continue,
+            };
+            let body = key_value.get(db::COL_BODIES, hash.as_bytes()).expect(
+                "Low level database error when fetching block body data. Some issue with disk?",
+            );
+            if let Some(body) = body.filter(|b| !is_packed(b)) {
+                batch.put(db::COL_BODIES, hash.as_bytes(), &pack(&body));
+                recompressed += 1;
+            }
+            let key: H264 = Key::<BlockReceipts>::key(&hash);
+            let receipts = key_value.get(db::COL_EXTRA, key.as_ref()).expect(
+                "Low level database error when fetching block receipts. Some issue with disk?",
+            );
+            if let Some(receipts) = receipts.filter(|r| !is_packed(r)) {
+                batch.put(db::COL_EXTRA, key.as_ref(), &pack(&receipts));
+            }
+        }
+        batch.put(db::COL_EXTRA, b"recompressed", &rlp::encode(&to));
+        key_value
+            .write(batch)
+            .expect("Low level database error when recompressing history. Some issue with disk?");
+        *self.recompressed_up_to.write() = to;
+
+        trace!(target: "blockchain", "Recompressed {} bodies of blocks {}..{}", recompressed, from, to);
+        to
+    }
+
+    /// Get the hashes of the given block and its 255 ancestors, newest first, padded with
+    /// zero hashes past the genesis. This is the window a child of `parent_hash` executes with.
+    ///
+    /// The window of the best block is maintained across imports and reorgs, so asking for
+    /// it, or for one of its ancestors, avoids walking the chain.
+    pub fn last_hashes(&self, parent_hash: &H256) -> Arc<Vec<H256>> {
+        let cached = self.last_hashes.read().clone();
+        let best = match cached {
+            Some(best) => best,
+            None => {
+                let best = Arc::new(self.build_last_hashes(self.best_block_hash()));
+                *self.last_hashes.write() = Some(best.clone());
+                best
+            }
+        };
+
+        match best.iter().position(|hash| hash == parent_hash) {
+            Some(0) => best,
+            Some(index) => {
+                let mut hashes = best[index..].to_vec();
+                self.complete_last_hashes(&mut hashes);
+                Arc::new(hashes)
+            }
+            None => Arc::new(self.build_last_hashes(*parent_hash)),
+        }
+    }
+
+    /// Work out the window of a block becoming the new best block, starting from the
+    /// window of the current best one. Returns `None` if it has to be rebuilt from scratch.
+    fn next_last_hashes(&self, info: &BlockInfo, parent_hash: &H256) -> Option<Arc<Vec<H256>>> {
+        let current = match self.pending_last_hashes.read().clone() {
+            Some(pending) => pending,
+            None => self.last_hashes.read().clone(),
+        }?;
+
+        let (enacted, retracted): (&[H256], usize) = match info.location {
+            BlockLocation::BranchBecomingCanonChain(ref data) => {
+                if current.get(data.retracted.len()) != Some(&data.ancestor) {
+                    return None;
+                }
+                (&data.enacted, data.retracted.len())
+            }
+            _ if current.first() == Some(parent_hash) => (&[], 0),
+            _ => return None,
+        };
+
+        let mut hashes = Vec::with_capacity(LAST_HASHES_LEN);
+        hashes.push(info.hash);
+        hashes.extend(enacted.iter().rev().cloned());
+        hashes.extend_from_slice(&current[retracted..]);
+        hashes.truncate(LAST_HASHES_LEN);
+        self.complete_last_hashes(&mut hashes);
+        Some(Arc::new(hashes))
+    }
+
+    fn build_last_hashes(&self, hash: H256) -> Vec<H256> {
+        let mut hashes = Vec::with_capacity(LAST_HASHES_LEN);
+        hashes.push(hash);
+        self.complete_last_hashes(&mut hashes);
+        hashes
+    }
+
+    /// Follow parent links from the oldest hash until the window is full.
+    fn complete_last_hashes(&self, hashes: &mut Vec<H256>) {
+        while hashes.len() < LAST_HASHES_LEN {
+            match hashes.last().and_then(|hash| self.block_details(hash)) {
+                Some(details) => hashes.push(details.parent),
+                None => break,
+            }
+        }
+        hashes.resize(LAST_HASHES_LEN, H256::default());
+    }
+
+    /// Snappy-compress a stored value if `compress_history` is enabled.
+    fn pack<'a>(&self, raw: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.compress_history {
+            Cow::Owned(pack(raw))
+        } else {
+            Cow::Borrowed(raw)
+        }
+    }
+
+    /// Regenerate the blooms database of the canon chain from the block headers, overwriting
+    /// whatever is stored at every level. Blocks whose header is not available get an empty
+    /// bloom.
+    ///
+    /// `progress` is called with the number of the last rewritten block after every batch.
+    pub fn rebuild_blooms<F>(&self, batch_size: u64, mut progress: F) -> io::Result<()>
+    where
+        F: FnMut(BlockNumber),
+    {
+        let batch_size = cmp::max(batch_size, 1);
+        let best = self.best_block_number();
+        let mut from = 0;
+        while from <= best {
+            let to = cmp::min(from + batch_size - 1, best);
+            let blooms = (from..=to)
+                .map(|number| {
+                    self.block_hash(number)
+                        .and_then(|hash| self.block_header_data(&hash))
+                        .map_or_else(Bloom::default, |header| header.log_bloom())
+                })
+                .collect::<Vec<_>>();
+            self.db.blooms().replace_blooms(from, blooms.iter())?;
+            progress(to);
+            from = to + 1;
+        }
+        Ok(())
+    }
+
     /// clears all caches, re-loads best block from disk for testing purposes
     pub fn clear_cache(&self) {
         self.block_bodies.write().clear();
         self.block_details.write().clear();
         self.block_hashes.write().clear();
         self.block_headers.write().clear();
+        *self.last_hashes.write() = None;
         // Fetch best block details from disk
         let best_block_hash = self
             .db
@@ -1054,7 +1498,7 @@ impl BlockChain {
         let mut best_block = self.best_block.write();
         *best_block = BestBlock {
             total_difficulty: best_block_total_difficulty,
-            header: best_block_rlp.decode_header(self.eip1559_transition),
+            header: best_block_rlp.decode_header(self.header_decode_params),
             block: best_block_rlp,
         };
     }
@@ -1328,7 +1772,11 @@ impl BlockChain {
 
         // store block in db
         batch.put(db::COL_HEADERS, hash.as_bytes(), &compressed_header);
-        batch.put(db::COL_BODIES, hash.as_bytes(), &compressed_body);
+        batch.put(
+            db::COL_BODIES,
+            hash.as_bytes(),
+            &self.pack(&compressed_body),
+        );
 
         let info = self.block_info(&block.header_view(), route, &extras);
 
@@ -1450,12 +1898,33 @@ impl BlockChain {
     fn prepare_update(&self, batch: &mut DBTransaction, update: ExtrasUpdate, is_best: bool) {
         {
             let mut write_receipts = self.block_receipts.write();
-            batch.extend_with_cache(
-                db::COL_EXTRA,
-                &mut *write_receipts,
-                update.block_receipts,
-                CacheUpdatePolicy::Remove,
-            );
+            if self.compress_history {
+                for (hash, receipts) in update.block_receipts {
+                    let key: H264 = Key::<BlockReceipts>::key(&hash);
+                    batch.put(db::COL_EXTRA, key.as_ref(), &pack(&rlp::encode(&receipts)));
+                    write_receipts.remove(&hash);
+                }
+            } else {
+                batch.extend_with_cache(
+                    db::COL_EXTRA,
+                    &mut *write_receipts,
+                    update.block_receipts,
+                    CacheUpdatePolicy::Remove,
+                );
+            }
+        }
+
+        // Transactions of canonical blocks imported below the indexed range are only marked.
+        if update.info.location == BlockLocation::CanonChain
+            && update.info.number < *self.earliest_transaction_index.read()
+        {
+            for tx_hash in update.block.view().transaction_hashes() {
+                batch.put(
+                    db::COL_EXTRA,
+                    ExpiredTransactionKey::new(&tx_hash).as_ref(),
+                    &[],
+                );
+            }
         }
 
         if let Some((block, blooms)) = update.blocks_blooms {
@@ -1465,6 +1934,12 @@ impl BlockChain {
                 .expect("Low level database error when updating blooms. Some issue with disk?");
         }
 
+        if is_best && update.info.location != BlockLocation::Branch {
+            let parent_hash = update.block.header_view().parent_hash();
+            let last_hashes = self.next_last_hashes(&update.info, &parent_hash);
+            *self.pending_last_hashes.write() = Some(last_hashes);
+        }
+
         // These cached values must be updated last with all four locks taken to avoid
         // cache decoherence
         {
@@ -1473,7 +1948,7 @@ impl BlockChain {
                 batch.put(db::COL_EXTRA, b"best", update.info.hash.as_bytes());
                 *best_block = Some(BestBlock {
                     total_difficulty: update.info.total_difficulty,
-                    header: update.block.decode_header(self.eip1559_transition),
+                    header: update.block.decode_header(self.header_decode_params),
                     block: update.block,
                 });
             }
@@ -1507,6 +1982,7 @@ impl BlockChain {
     pub fn commit(&self) {
         let mut pending_best_ancient_block = self.pending_best_ancient_block.write();
         let mut pending_best_block = self.pending_best_block.write();
+        let mut pending_last_hashes = self.pending_last_hashes.write();
         let mut pending_write_hashes = self.pending_block_hashes.write();
         let mut pending_block_details = self.pending_block_details.write();
         let mut pending_write_txs = self.pending_transaction_addresses.write();
@@ -1524,6 +2000,9 @@ impl BlockChain {
         if let Some(block) = pending_best_block.take() {
             *best_block = block;
         }
+        if let Some(last_hashes) = pending_last_hashes.take() {
+            *self.last_hashes.write() = last_hashes;
+        }
 
         let pending_txs = mem::replace(&mut *pending_write_txs, HashMap::new());
         let (retracted_txs, enacted_txs) = pending_txs
@@ -1700,6 +2179,9 @@ impl BlockChain {
         transaction_hashes: Vec<H256>,
         info: &BlockInfo,
     ) -> HashMap<H256, Option<TransactionAddress>> {
+        if info.number < *self.earliest_transaction_index.read() {
+            return HashMap::new();
+        }
         match info.location {
             BlockLocation::CanonChain => transaction_hashes
                 .into_iter()
@@ -1854,6 +2336,14 @@ impl BlockChain {
         }
     }
 
+    /// Change the preferred and maximum cache sizes in bytes. Takes effect on the next
+    /// `collect_garbage`.
+    pub fn set_cache_sizes(&self, pref_cache_size: usize, max_cache_size: usize) {
+        self.cache_man
+            .lock()
+            .set_sizes(pref_cache_size, max_cache_size);
+    }
+
     /// Ticks our cache system and throws out any old data.
     pub fn collect_garbage(&self) {
         let current_size = self.cache_size().total();
@@ -1907,12 +2397,21 @@ impl BlockChain {
         });
     }
 
+    /// Parameters used to decode stored headers.
+    pub fn header_decode_params(&self) -> HeaderDecodeParams {
+        self.header_decode_params
+    }
+
     /// Create a block body from a block.
     pub fn block_to_body(block: &[u8]) -> Bytes {
-        let mut body = RlpStream::new_list(2);
         let block_view = view!(BlockView, block);
+        let withdrawals = block_view.withdrawals_rlp();
+        let mut body = RlpStream::new_list(2 + withdrawals.is_some() as usize);
         body.append_raw(block_view.transactions_rlp().as_raw(), 1);
         body.append_raw(block_view.uncles_rlp().as_raw(), 1);
+        if let Some(withdrawals) = withdrawals {
+            body.append_raw(withdrawals.as_raw(), 1);
+        }
         body.out()
     }
 
@@ -1942,6 +2441,36 @@ impl BlockChain {
     }
 }
 
+/// Number of block hashes available to the `BLOCKHASH` opcode.
+const LAST_HASHES_LEN: usize = 256;
+
+/// Leading byte of snappy-compressed bodies and receipts. Both are stored as RLP lists,
+/// which never start with it, so compressed and plain values can live side by side.
+const PACKED_PREFIX: u8 = 0x00;
+
+fn is_packed(stored: &[u8]) -> bool {
+    stored.first() == Some(&PACKED_PREFIX)
+}
+
+fn pack(raw: &[u8]) -> Vec<u8> {
+    let compressed = snappy::compress(raw);
+    let mut packed = Vec::with_capacity(1 + compressed.len());
+    packed.push(PACKED_PREFIX);
+    packed.extend_from_slice(&compressed);
+    packed
+}
+
+fn unpack(stored: &[u8]) -> Cow<[u8]> {
+    if is_packed(stored) {
+        Cow::Owned(
+            snappy::decompress(&stored[1..])
+                .expect("packed values are always valid snappy data; qed"),
+        )
+    } else {
+        Cow::Borrowed(stored)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2263,6 +2792,52 @@ mod tests {
         assert_eq!(bc.transaction_address(&t1_hash), None);
     }
 
+    #[test]
+    fn test_last_hashes_follow_reorgs() {
+        let genesis = BlockBuilder::genesis();
+        let b1 = genesis.add_block();
+        let b2a = b1.add_block();
+        let b2b = b1.add_block_with_difficulty(9);
+        let b3b = b2b.add_block();
+
+        let genesis_hash = genesis.last().hash();
+        let b1_hash = b1.last().hash();
+        let b2a_hash = b2a.last().hash();
+        let b2b_hash = b2b.last().hash();
+        let b3b_hash = b3b.last().hash();
+
+        let db = new_db();
+        let bc = new_chain(
+            genesis.last().encoded(),
+            db.clone(),
+            BlockNumber::max_value(),
+        );
+        let window = bc.last_hashes(&genesis_hash);
+        assert_eq!(window.len(), 256);
+        assert_eq!(window[..2], [genesis_hash, H256::default()]);
+
+        for block in vec![b1.last(), b2a.last()] {
+            insert_block(&db, &bc, block.encoded(), vec![]);
+        }
+        let window = bc.last_hashes(&b2a_hash);
+        assert_eq!(window[..3], [b2a_hash, b1_hash, genesis_hash]);
+        assert!(Arc::ptr_eq(&window, &bc.last_hashes(&b2a_hash)));
+        assert_eq!(bc.last_hashes(&b1_hash)[..2], [b1_hash, genesis_hash]);
+
+        insert_block(&db, &bc, b2b.last().encoded(), vec![]);
+        assert_eq!(bc.best_block_hash(), b2a_hash);
+        insert_block(&db, &bc, b3b.last().encoded(), vec![]);
+        assert_eq!(bc.best_block_hash(), b3b_hash);
+
+        let window = bc.last_hashes(&b3b_hash);
+        assert_eq!(window.len(), 256);
+        assert_eq!(
+            window[..5],
+            [b3b_hash, b2b_hash, b1_hash, genesis_hash, H256::default()]
+        );
+        assert!(Arc::ptr_eq(&window, &bc.last_hashes(&b3b_hash)));
+    }
+
     #[test]
     fn test_overwriting_transaction_addresses() {
         let t1 = TypedTransaction::Legacy(Transaction {
@@ -2552,6 +3127,244 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_expire_history() {
+        let t1 = TypedTransaction::Legacy(Transaction {
+            nonce: 0.into(),
+            gas_price: 0.into(),
+            gas: 100_000.into(),
+            action: Action::Create,
+            value: 100.into(),
+            data: vec![],
+        })
+        .sign(&secret(), None);
+        let t1_hash = t1.hash();
+
+        let genesis = BlockBuilder::genesis();
+        let b1 = genesis.add_block_with_transactions(iter::once(t1));
+        let b2 = b1.add_block();
+        let b3 = b2.add_block();
+        let b1_hash = b1.last().hash();
+        let b2_hash = b2.last().hash();
+        let b3_hash = b3.last().hash();
+
+        let db = new_db();
+        {
+            let bc = new_chain(
+                genesis.last().encoded(),
+                db.clone(),
+                BlockNumber::max_value(),
+            );
+            for block in vec![b1.last(), b2.last(), b3.last()] {
+                insert_block(&db, &bc, block.encoded(), vec![]);
+            }
+            assert!(bc.transaction_address(&t1_hash).is_some());
+            assert_eq!(bc.earliest_history(), 0);
+
+            // expiry is bounded by the limit and never touches the best block
+            assert_eq!(bc.expire_history(3, 1), 2);
+            assert!(bc.block_body(&b1_hash).is_none());
+            assert!(bc.block_receipts(&b1_hash).is_none());
+            assert!(bc.transaction_address(&t1_hash).is_none());
+            assert!(bc.block_header_data(&b1_hash).is_some());
+            assert!(bc.block_body(&b2_hash).is_some());
+
+            assert_eq!(bc.expire_history(10, 10), 3);
+            assert!(bc.block_body(&b2_hash).is_none());
+            assert!(bc.block_body(&b3_hash).is_some());
+            assert!(bc.block_body(&bc.genesis_hash()).is_some());
+        }
+
+        let bc = new_chain(
+            genesis.last().encoded(),
+            db.clone(),
+            BlockNumber::max_value(),
+        );
+        assert_eq!(bc.earliest_history(), 3);
+        assert_eq!(bc.block_hash(1), Some(b1_hash));
+        assert!(bc.block_body(&b1_hash).is_none());
+    }
+
+    #[test]
+    fn test_expire_history_of_ancient_blocks() {
+        let genesis = BlockBuilder::genesis();
+        let b1 = genesis.add_block();
+        let b2 = b1.add_block();
+        let b3 = b2.add_block();
+        let b4 = b3.add_block();
+        let b5 = b4.add_block();
+        let b2_total_difficulty =
+            genesis.last().difficulty() + b1.last().difficulty() + b2.last().difficulty();
+
+        // restored from a snapshot at block 3.
+        let db = new_db();
+        {
+            let bc = new_chain(
+                genesis.last().encoded(),
+                db.clone(),
+                BlockNumber::max_value(),
+            );
+            let mut batch = db.key_value().transaction();
+            bc.insert_unordered_block(
+                &mut batch,
+                b3.last().encoded(),
+                vec![],
+                Some(b2_total_difficulty),
+                true,
+                false,
+            );
+            bc.commit();
+            for block in vec![b4.last(), b5.last()] {
+                bc.insert_unordered_block(&mut batch, block.encoded(), vec![], None, true, false);
+                bc.commit();
+            }
+            db.key_value().write(batch).unwrap();
+        }
+
+        let bc = new_chain(
+            genesis.last().encoded(),
+            db.clone(),
+            BlockNumber::max_value(),
+        );
+        assert_eq!(bc.expire_history(5, 10), 5);
+        assert!(bc.block_body(&b3.last().hash()).is_none());
+
+        // ancient sync fills the gap below the expired range afterwards
+        for block in vec![b1.last(), b2.last()] {
+            let mut batch = db.key_value().transaction();
+            bc.insert_unordered_block(&mut batch, block.encoded(), vec![], None, false, true);
+            bc.commit();
+            db.key_value().write(batch).unwrap();
+            assert!(bc.block_body(&block.hash()).is_some());
+
+            assert_eq!(bc.expire_history(5, 10), 5);
+            assert!(bc.block_body(&block.hash()).is_none());
+            assert!(bc.block_header_data(&block.hash()).is_some());
+        }
+        assert_eq!(bc.best_ancient_number(), None);
+    }
+
+    #[test]
+    fn test_expire_transaction_index() {
+        let t1 = TypedTransaction::Legacy(Transaction {
+            nonce: 0.into(),
+            gas_price: 0.into(),
+            gas: 100_000.into(),
+            action: Action::Create,
+            value: 100.into(),
+            data: vec![],
+        })
+        .sign(&secret(), None);
+        let t1_hash = t1.hash();
+
+        let genesis = BlockBuilder::genesis();
+        let b1 = genesis.add_block_with_transactions(iter::once(t1));
+        let b2 = b1.add_block();
+        let b1_hash = b1.last().hash();
+
+        let db = new_db();
+        {
+            let bc = new_chain(
+                genesis.last().encoded(),
+                db.clone(),
+                BlockNumber::max_value(),
+            );
+            for block in vec![b1.last(), b2.last()] {
+                insert_block(&db, &bc, block.encoded(), vec![]);
+            }
+            assert_eq!(bc.earliest_transaction_index(), 0);
+            assert!(!bc.is_transaction_unindexed(&t1_hash));
+
+            assert_eq!(bc.expire_transaction_index(2, 10), 2);
+            assert!(bc.transaction_address(&t1_hash).is_none());
+            assert!(bc.is_transaction_unindexed(&t1_hash));
+            assert!(bc.block_body(&b1_hash).is_some());
+        }
+
+        let bc = new_chain(
+            genesis.last().encoded(),
+            db.clone(),
+            BlockNumber::max_value(),
+        );
+        assert_eq!(bc.earliest_transaction_index(), 2);
+        assert!(bc.transaction_address(&t1_hash).is_none());
+        assert!(bc.is_transaction_unindexed(&t1_hash));
+        assert!(!bc.is_transaction_unindexed(&H256::from_low_u64_be(1)));
+    }
+
+    #[test]
+    fn test_recompress_history() {
+        let receipt = TypedReceipt::Legacy(LegacyReceipt {
+            outcome: TransactionOutcome::StateRoot(H256::default()),
+            gas_used: 10_000.into(),
+            log_bloom: Default::default(),
+            logs: vec![LogEntry {
+                address: Default::default(),
+                topics: vec![],
+                data: vec![1],
+            }],
+        });
+
+        let genesis = BlockBuilder::genesis();
+        let b1 = genesis.add_block();
+        let b2 = b1.add_block();
+        let b1_hash = b1.last().hash();
+        let b2_hash = b2.last().hash();
+        let stored_body = |chain_db: &Arc<dyn BlockChainDB>, hash: &H256| {
+            chain_db
+                .key_value()
+                .get(db::COL_BODIES, hash.as_bytes())
+                .unwrap()
+                .unwrap()
+        };
+
+        let db = new_db();
+        {
+            let bc = new_chain(
+                genesis.last().encoded(),
+                db.clone(),
+                BlockNumber::max_value(),
+            );
+            insert_block(&db, &bc, b1.last().encoded(), vec![receipt.clone()]);
+            assert!(!is_packed(&stored_body(&db, &b1_hash)));
+        }
+
+        let config = Config {
+            compress_history: true,
+            ..Config::default()
+        };
+        let bc = BlockChain::new(
+            config.clone(),
+            genesis.last().encoded().raw(),
+            db.clone(),
+            BlockNumber::max_value(),
+        );
+        insert_block(&db, &bc, b2.last().encoded(), vec![receipt.clone()]);
+        assert!(is_packed(&stored_body(&db, &b2_hash)));
+        assert!(!is_packed(&stored_body(&db, &b1_hash)));
+
+        assert_eq!(bc.recompress_history(2), 2);
+        assert!(is_packed(&stored_body(&db, &b1_hash)));
+
+        let bc = BlockChain::new(
+            config,
+            genesis.last().encoded().raw(),
+            db.clone(),
+            BlockNumber::max_value(),
+        );
+        assert_eq!(bc.recompress_history(10), 3);
+        assert_eq!(bc.recompress_history(10), 3);
+        assert_eq!(
+            bc.block_body(&b1_hash).unwrap().rlp().as_raw(),
+            &BlockChain::block_to_body(b1.last().encoded().raw())[..]
+        );
+        assert_eq!(
+            bc.block_receipts(&b1_hash).unwrap().receipts,
+            vec![receipt.clone()]
+        );
+        assert_eq!(bc.block_receipts(&b2_hash).unwrap().receipts, vec![receipt]);
+    }
+
     #[test]
     fn find_transaction_by_hash() {
         let genesis = "f901fcf901f7a00000000000000000000000000000000000000000000000000000000000000000a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347948888f1f195afa192cfee860698584c030f4c9db1a0af81e09f8c46ca322193edfda764fa7e88e81923f802f1d325ec0b0308ac2cd0a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421b9010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000830200008083023e38808454c98c8142a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421880102030405060708c0c0".from_hex().unwrap();
@@ -2816,6 +3629,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rebuild_blooms() {
+        let bloom = Bloom::from_low_u64_be(0x1234);
+        let genesis = BlockBuilder::genesis();
+        let b1 = genesis.add_block_with_bloom(bloom);
+        let b2 = b1.add_block();
+        let b3 = b2.add_block_with_bloom(bloom);
+
+        let db = new_db();
+        let bc = new_chain(
+            genesis.last().encoded(),
+            db.clone(),
+            BlockNumber::max_value(),
+        );
+        insert_block(&db, &bc, b1.last().encoded(), vec![]);
+        insert_block(&db, &bc, b2.last().encoded(), vec![]);
+        insert_block(&db, &bc, b3.last().encoded(), vec![]);
+        assert_eq!(bc.blocks_with_bloom(Some(&bloom), 0, 3), vec![1, 3]);
+
+        // wipe the stored blooms
+        let empty = vec![Bloom::default(); 4];
+        db.blooms().insert_blooms(0, empty.iter()).unwrap();
+        assert!(bc.blocks_with_bloom(Some(&bloom), 0, 3).is_empty());
+
+        let mut reported = Vec::new();
+        bc.rebuild_blooms(2, |number| reported.push(number))
+            .unwrap();
+        assert_eq!(reported, vec![1, 3]);
+        assert_eq!(bc.blocks_with_bloom(Some(&bloom), 0, 3), vec![1, 3]);
+    }
+
     #[test]
     fn test_bloom_filter_simple() {
         let bloom_b1 = Bloom::from_str("00000020000000000000000000000000000000000000000002000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000040000000000000010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000008000400000000000000000000002000").unwrap();
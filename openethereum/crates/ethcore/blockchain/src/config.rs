@@ -23,6 +23,8 @@ pub struct Config {
     pub pref_cache_size: usize,
     /// Maximum cache size in bytes.
     pub max_cache_size: usize,
+    /// Store block bodies and receipts snappy-compressed.
+    pub compress_history: bool,
 }
 
 impl Default for Config {
@@ -30,6 +32,7 @@ impl Default for Config {
         Config {
             pref_cache_size: 1 << 14,
             max_cache_size: 1 << 20,
+            compress_history: false,
         }
     }
 }
@@ -19,6 +19,7 @@
 #![warn(missing_docs)]
 
 extern crate parity_crypto as crypto;
+extern crate parity_snappy as snappy;
 
 mod best_block;
 mod block_info;
@@ -31,7 +32,7 @@ mod update;
 pub mod generator;
 
 pub use self::{
-    blockchain::{BlockChain, BlockChainDB, BlockChainDBHandler, BlockProvider},
+    blockchain::{BlockChain, BlockChainDB, BlockChainDBHandler, BlockProvider, DatabaseBackup},
     cache::CacheSize,
     config::Config,
     import_route::ImportRoute,
@@ -48,11 +48,12 @@ use verification::PreverifiedBlock;
 use vm::{EnvInfo, LastHashes};
 
 use hash::keccak;
-use rlp::{encode_list, RlpStream};
+use rlp::{encode, encode_list, RlpStream};
 use types::{
     header::{ExtendedHeader, Header},
     receipt::{TransactionOutcome, TypedReceipt},
     transaction::{Error as TransactionError, SignedTransaction},
+    withdrawal::Withdrawal,
 };
 
 /// Block that is ready for transactions to be added.
@@ -98,6 +99,8 @@ pub struct ExecutedBlock {
     pub transactions: Vec<SignedTransaction>,
     /// Uncles.
     pub uncles: Vec<Header>,
+    /// EIP-4895 withdrawals, credited when the block is closed.
+    pub withdrawals: Option<Vec<Withdrawal>>,
     /// Transaction receipts.
     pub receipts: Vec<TypedReceipt>,
     /// Hashes of already executed transactions.
@@ -108,6 +111,9 @@ pub struct ExecutedBlock {
     pub traces: Tracing,
     /// Hashes of last 256 blocks.
     pub last_hashes: Arc<LastHashes>,
+    /// Author the block is executed and rewarded for. Clique keeps the vote beneficiary in the
+    /// header author and recovers the signer from the seal instead.
+    pub executive_author: Address,
 }
 
 impl ExecutedBlock {
@@ -117,6 +123,7 @@ impl ExecutedBlock {
             header: Default::default(),
             transactions: Default::default(),
             uncles: Default::default(),
+            withdrawals: None,
             receipts: Default::default(),
             transactions_set: Default::default(),
             state: state,
@@ -126,6 +133,7 @@ impl ExecutedBlock {
                 Tracing::Disabled
             },
             last_hashes: last_hashes,
+            executive_author: Address::zero(),
         }
     }
 
@@ -141,6 +149,7 @@ impl ExecutedBlock {
             gas_used: self.receipts.last().map_or(U256::zero(), |r| r.gas_used),
             gas_limit: *self.header.gas_limit(),
             base_fee: self.header.base_fee(),
+            random: self.header.mix_hash(),
         }
     }
 
@@ -193,6 +202,7 @@ impl<'x> OpenBlock<'x> {
         r.block.header.set_parent_hash(parent.hash());
         r.block.header.set_number(number);
         r.block.header.set_author(author);
+        r.block.executive_author = author;
         r.block
             .header
             .set_timestamp(engine.open_block_header_timestamp(parent.timestamp()));
@@ -255,6 +265,11 @@ impl<'x> OpenBlock<'x> {
         Ok(())
     }
 
+    /// Set the EIP-4895 withdrawals to be credited when the block is closed.
+    pub fn set_withdrawals(&mut self, withdrawals: Vec<Withdrawal>) {
+        self.block.withdrawals = Some(withdrawals);
+    }
+
     /// Push a transaction into the block.
     ///
     /// If valid, it will be executed, and archived together with the receipt.
@@ -263,6 +278,22 @@ impl<'x> OpenBlock<'x> {
         t: SignedTransaction,
         h: Option<H256>,
     ) -> Result<&TypedReceipt, Error> {
+        self.execute_transaction(t, h)?;
+        Ok(self
+            .block
+            .receipts
+            .last()
+            .expect("receipt just pushed; qed"))
+    }
+
+    /// Execute a transaction and archive it together with the receipt.
+    ///
+    /// Returns the exception the execution ended with, if any.
+    fn execute_transaction(
+        &mut self,
+        t: SignedTransaction,
+        h: Option<H256>,
+    ) -> Result<Option<vm::Error>, Error> {
         if self.block.transactions_set.contains(&t.hash()) {
             return Err(TransactionError::AlreadyImported.into());
         }
@@ -283,11 +314,56 @@ impl<'x> OpenBlock<'x> {
             traces.push(outcome.trace.into());
         }
         self.block.receipts.push(outcome.receipt);
-        Ok(self
-            .block
-            .receipts
-            .last()
-            .expect("receipt just pushed; qed"))
+        Ok(outcome.exception)
+    }
+
+    /// Push a bundle of transactions which either all execute successfully or are all left out.
+    ///
+    /// If any of the transactions is invalid or its execution fails, the state is reverted to a
+    /// checkpoint taken before the bundle and the bundle's transactions are removed again.
+    /// Bundles need EIP-658, since intermediate state commits would discard the checkpoint.
+    pub fn push_bundle(&mut self, transactions: Vec<SignedTransaction>) -> Result<(), Error> {
+        if self.block.header.number() < self.engine.params().eip658_transition {
+            return Err("Bundles are not supported before EIP-658".into());
+        }
+
+        let transactions_before = self.block.transactions.len();
+        let traces_before = match self.block.traces {
+            Tracing::Enabled(ref traces) => traces.len(),
+            Tracing::Disabled => 0,
+        };
+        self.block.state.checkpoint();
+
+        let mut result = Ok(());
+        for t in transactions {
+            let hash = t.hash();
+            match self.execute_transaction(t, None) {
+                Ok(None) => {}
+                Ok(Some(exception)) => {
+                    result =
+                        Err(format!("Bundle transaction {:?} failed: {}", hash, exception).into());
+                    break;
+                }
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
+
+        if result.is_ok() {
+            self.block.state.discard_checkpoint();
+            return result;
+        }
+        self.block.state.revert_to_checkpoint();
+        for t in self.block.transactions.drain(transactions_before..) {
+            self.block.transactions_set.remove(&t.hash());
+        }
+        self.block.receipts.truncate(transactions_before);
+        if let Tracing::Enabled(ref mut traces) = self.block.traces {
+            traces.truncate(traces_before);
+        }
+        result
     }
 
     /// Push transactions onto the block.
@@ -361,8 +437,19 @@ impl<'x> OpenBlock<'x> {
     pub fn close_and_lock(self) -> Result<LockedBlock, Error> {
         let mut s = self;
 
+        // locally authored blocks have no consensus layer feeding them withdrawals
+        if s.block.withdrawals.is_none()
+            && s.engine
+                .params()
+                .withdrawals_enabled(s.block.header.timestamp())
+        {
+            s.block.withdrawals = Some(Vec::new());
+        }
+
         // t_nb 8.5.1 engine applies block rewards (Ethash and AuRa do.Clique is empty)
         s.engine.on_close_block(&mut s.block)?;
+        // apply the spec reward schedule and credit EIP-4895 withdrawals carried by the block
+        s.engine.machine().on_close_block(&mut s.block)?;
 
         // t_nb 8.5.2 commit account changes from cache to tree
         s.block.state.commit()?;
@@ -373,6 +460,12 @@ impl<'x> OpenBlock<'x> {
         ));
         let uncle_bytes = encode_list(&s.block.uncles);
         s.block.header.set_uncles_hash(keccak(&uncle_bytes));
+        let withdrawals_root = s
+            .block
+            .withdrawals
+            .as_ref()
+            .map(|w| ordered_trie_root(w.iter().map(encode)));
+        s.block.header.set_withdrawals_root(withdrawals_root);
         s.block.header.set_state_root(s.block.state.root().clone());
         s.block.header.set_receipts_root(ordered_trie_root(
             s.block.receipts.iter().map(|r| r.encode()),
@@ -510,10 +603,13 @@ impl Drain for LockedBlock {
 impl SealedBlock {
     /// Get the RLP-encoding of the block.
     pub fn rlp_bytes(&self) -> Bytes {
-        let mut block_rlp = RlpStream::new_list(3);
+        let mut block_rlp = RlpStream::new_list(3 + self.block.withdrawals.is_some() as usize);
         block_rlp.append(&self.block.header);
         SignedTransaction::rlp_append_list(&mut block_rlp, &self.block.transactions);
         block_rlp.append_list(&self.block.uncles);
+        if let Some(ref withdrawals) = self.block.withdrawals {
+            block_rlp.append_list(withdrawals);
+        }
         block_rlp.out()
     }
 }
@@ -529,6 +625,7 @@ pub(crate) fn enact(
     header: Header,
     transactions: Vec<SignedTransaction>,
     uncles: Vec<Header>,
+    withdrawals: Option<Vec<Withdrawal>>,
     engine: &dyn EthEngine,
     tracing: bool,
     db: StateDB,
@@ -584,6 +681,10 @@ pub(crate) fn enact(
         b.push_uncle(u)?;
     }
 
+    if let Some(withdrawals) = withdrawals {
+        b.set_withdrawals(withdrawals);
+    }
+
     // t_nb 8.5 close block
     b.close_and_lock()
 }
@@ -604,6 +705,7 @@ pub fn enact_verified(
         block.header,
         block.transactions,
         block.uncles,
+        block.withdrawals,
         engine,
         tracing,
         db,
@@ -737,6 +839,233 @@ mod tests {
         let _ = b.seal(&*spec.engine, vec![]);
     }
 
+    #[test]
+    fn withdrawals_are_credited_on_close() {
+        use spec::*;
+        use types::{header::HeaderDecodeParams, withdrawal::Withdrawal};
+        let spec = Spec::new_test();
+        let engine = &*spec.engine;
+        let genesis_header = spec.genesis_header();
+        let db = spec
+            .ensure_db_good(get_temp_state_db(), &Default::default())
+            .unwrap();
+        let last_hashes = Arc::new(vec![genesis_header.hash()]);
+        let mut b = OpenBlock::new(
+            engine,
+            Default::default(),
+            false,
+            db,
+            &genesis_header,
+            last_hashes,
+            Address::zero(),
+            (3141562.into(), 31415620.into()),
+            vec![],
+            false,
+            None,
+        )
+        .unwrap();
+        let withdrawal = Withdrawal {
+            index: 0,
+            validator_index: 1,
+            address: Address::from_low_u64_be(0x4895),
+            amount: 2,
+        };
+        b.set_withdrawals(vec![withdrawal.clone()]);
+        let b = b.close_and_lock().unwrap();
+
+        assert_eq!(
+            b.header.withdrawals_root(),
+            Some(&ordered_trie_root(vec![encode(&withdrawal)]))
+        );
+        assert_eq!(
+            b.state.balance(&withdrawal.address).unwrap(),
+            U256::from(2_000_000_000u64)
+        );
+
+        let sealed = b.seal(engine, vec![]).unwrap();
+        let params = HeaderDecodeParams {
+            eip1559_transition: engine.params().eip1559_transition,
+            eip4895_transition_timestamp: 0,
+        };
+        let unverified = Unverified::from_rlp(sealed.rlp_bytes(), params).unwrap();
+        assert_eq!(unverified.withdrawals, Some(vec![withdrawal]));
+        assert_eq!(unverified.header.hash(), sealed.header.hash());
+    }
+
+    #[test]
+    fn authored_blocks_carry_empty_withdrawals() {
+        use hash::KECCAK_NULL_RLP;
+        use spec::*;
+        use tempdir::TempDir;
+        let json = include_str!("../res/chainspec/test/null_morden.json").replace(
+            "\"networkID\" : \"0x2\"",
+            "\"networkID\" : \"0x2\", \"eip4895TransitionTimestamp\": \"0x0\"",
+        );
+        let tempdir = TempDir::new("").unwrap();
+        let spec = Spec::load(&tempdir.path(), json.as_bytes()).unwrap();
+        let engine = &*spec.engine;
+        let genesis_header = spec.genesis_header();
+        let db = spec
+            .ensure_db_good(get_temp_state_db(), &Default::default())
+            .unwrap();
+        let last_hashes = Arc::new(vec![genesis_header.hash()]);
+        let b = OpenBlock::new(
+            engine,
+            Default::default(),
+            false,
+            db,
+            &genesis_header,
+            last_hashes,
+            Address::zero(),
+            (3141562.into(), 31415620.into()),
+            vec![],
+            false,
+            None,
+        )
+        .unwrap();
+        let b = b.close_and_lock().unwrap();
+        assert_eq!(b.header.withdrawals_root(), Some(&KECCAK_NULL_RLP));
+
+        let sealed = b.seal(engine, vec![]).unwrap();
+        let unverified =
+            Unverified::from_rlp(sealed.rlp_bytes(), engine.params().header_decode_params())
+                .unwrap();
+        assert_eq!(unverified.withdrawals, Some(Vec::new()));
+        assert_eq!(unverified.header.hash(), sealed.header.hash());
+    }
+
+    #[test]
+    fn reward_schedule_replaces_engine_reward() {
+        use spec::*;
+        use tempdir::TempDir;
+        let json = include_str!("../res/chainspec/test/null_morden_with_reward.json").replace(
+            "\"networkID\" : \"0x2\"",
+            "\"networkID\" : \"0x2\", \"blockRewardSchedule\": [{ \"block\": \"0x0\", \"reward\": \"0x64\" }]",
+        );
+        let tempdir = TempDir::new("").unwrap();
+        let spec = Spec::load(&tempdir.path(), json.as_bytes()).unwrap();
+        let engine = &*spec.engine;
+        let genesis_header = spec.genesis_header();
+        let db = spec
+            .ensure_db_good(get_temp_state_db(), &Default::default())
+            .unwrap();
+        let last_hashes = Arc::new(vec![genesis_header.hash()]);
+        let author = Address::from_low_u64_be(0x797);
+        let b = OpenBlock::new(
+            engine,
+            Default::default(),
+            false,
+            db,
+            &genesis_header,
+            last_hashes,
+            author,
+            (3141562.into(), 31415620.into()),
+            vec![],
+            false,
+            None,
+        )
+        .unwrap();
+        let b = b.close().unwrap();
+        // Only the scheduled reward is paid, not the engine's `blockReward` on top of it.
+        assert_eq!(b.state.balance(&author).unwrap(), U256::from(0x64));
+    }
+
+    #[test]
+    fn reward_schedule_pays_executive_author() {
+        use spec::*;
+        use tempdir::TempDir;
+        let json = include_str!("../res/chainspec/test/null_morden.json").replace(
+            "\"networkID\" : \"0x2\"",
+            "\"networkID\" : \"0x2\", \"blockRewardSchedule\": [{ \"block\": \"0x0\", \"reward\": \"0x64\" }]",
+        );
+        let tempdir = TempDir::new("").unwrap();
+        let spec = Spec::load(&tempdir.path(), json.as_bytes()).unwrap();
+        let engine = &*spec.engine;
+        let genesis_header = spec.genesis_header();
+        let db = spec
+            .ensure_db_good(get_temp_state_db(), &Default::default())
+            .unwrap();
+        let last_hashes = Arc::new(vec![genesis_header.hash()]);
+        let signer = Address::from_low_u64_be(0x797);
+        let mut b = OpenBlock::new(
+            engine,
+            Default::default(),
+            false,
+            db,
+            &genesis_header,
+            last_hashes,
+            signer,
+            (3141562.into(), 31415620.into()),
+            vec![],
+            false,
+            None,
+        )
+        .unwrap();
+        // like a Clique header carrying the beneficiary of a vote
+        let beneficiary = Address::from_low_u64_be(0xbe);
+        b.block.header.set_author(beneficiary);
+        let b = b.close().unwrap();
+
+        assert_eq!(b.state.balance(&signer).unwrap(), U256::from(0x64));
+        assert_eq!(b.state.balance(&beneficiary).unwrap(), U256::zero());
+    }
+
+    #[test]
+    fn failed_bundle_leaves_block_unchanged() {
+        use crypto::publickey::{Generator, Random};
+        use spec::*;
+        use tempdir::TempDir;
+        use types::transaction::{Action, Transaction, TypedTransaction};
+        let json = include_str!("../res/chainspec/test/null_morden.json").replace(
+            "\"networkID\" : \"0x2\"",
+            "\"networkID\" : \"0x2\", \"eip658Transition\": \"0x0\"",
+        );
+        let tempdir = TempDir::new("").unwrap();
+        let spec = Spec::load(&tempdir.path(), json.as_bytes()).unwrap();
+        let engine = &*spec.engine;
+        let genesis_header = spec.genesis_header();
+        let db = spec
+            .ensure_db_good(get_temp_state_db(), &Default::default())
+            .unwrap();
+        let last_hashes = Arc::new(vec![genesis_header.hash()]);
+        let mut b = OpenBlock::new(
+            engine,
+            Default::default(),
+            false,
+            db,
+            &genesis_header,
+            last_hashes,
+            Address::zero(),
+            (3141562.into(), 31415620.into()),
+            vec![],
+            false,
+            None,
+        )
+        .unwrap();
+        let keypair = Random.generate();
+        let tx = |nonce: u64| {
+            TypedTransaction::Legacy(Transaction {
+                action: Action::Call(Address::from_low_u64_be(0x839)),
+                value: 0.into(),
+                data: vec![],
+                gas: 21_000.into(),
+                gas_price: 0.into(),
+                nonce: nonce.into(),
+            })
+            .sign(keypair.secret(), None)
+        };
+
+        // The second transaction has an invalid nonce, so the first one is rolled back too.
+        assert!(b.push_bundle(vec![tx(0), tx(2)]).is_err());
+        assert!(b.block.transactions.is_empty());
+        assert!(b.block.receipts.is_empty());
+        assert_eq!(b.block.state.nonce(&keypair.address()).unwrap(), 0.into());
+
+        assert!(b.push_bundle(vec![tx(0), tx(1)]).is_ok());
+        assert_eq!(b.block.transactions.len(), 2);
+        assert_eq!(b.block.state.nonce(&keypair.address()).unwrap(), 2.into());
+    }
+
     #[test]
     fn enact_block() {
         use spec::*;
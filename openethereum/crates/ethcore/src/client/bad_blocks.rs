@@ -16,31 +16,69 @@
 
 //! Stores recently seen bad blocks.
 
+use std::collections::VecDeque;
+
 use bytes::{Bytes, ToPretty};
+use db::{DBTransaction, KeyValueDB};
 use ethereum_types::H256;
 use itertools::Itertools;
-use memory_cache::MemoryLruCache;
-use parking_lot::RwLock;
-use types::BlockNumber;
+use parking_lot::Mutex;
+use rlp::{Rlp, RlpStream};
+use types::header::HeaderDecodeParams;
 use verification::queue::kind::blocks::Unverified;
 
-/// Recently seen bad blocks.
-pub struct BadBlocks {
-    last_blocks: RwLock<MemoryLruCache<H256, (Unverified, String)>>,
+/// Prefix of the bad block keys in the node info column.
+const BAD_BLOCK_KEY_PREFIX: &[u8] = b"BAD_BLOCK";
+
+/// Number of bad blocks kept in the database.
+const MAX_BAD_BLOCKS: usize = 64;
+
+/// A block rejected during import.
+pub struct BadBlock {
+    /// The rejected block.
+    pub block: Unverified,
+    /// Why the block was rejected.
+    pub reason: String,
+    /// UNIX timestamp of the rejection.
+    pub reported_at: u64,
 }
 
-impl Default for BadBlocks {
-    fn default() -> Self {
-        BadBlocks {
-            last_blocks: RwLock::new(MemoryLruCache::new(8 * 1024 * 1024)),
-        }
-    }
+/// A bad block as it is stored.
+struct Record {
+    hash: H256,
+    bytes: Bytes,
+    reason: String,
+    reported_at: u64,
+}
+
+#[derive(Default)]
+struct State {
+    /// Reports not written to the database yet, oldest first.
+    pending: VecDeque<Record>,
+    /// Hashes of the stored bad blocks, oldest first. Read from the database on the first flush.
+    persisted: Option<VecDeque<H256>>,
+}
+
+/// Recently seen bad blocks, persisted in the node info column so that they
+/// can still be inspected after a restart.
+///
+/// Reports are kept in memory and written in a single batch by `flush`, so that reporting
+/// a block doesn't touch the database on the import path.
+#[derive(Default)]
+pub struct BadBlocks {
+    state: Mutex<State>,
 }
 
 impl BadBlocks {
-    /// Reports given RLP as invalid block.
-    pub fn report(&self, raw: Bytes, message: String, eip1559_transition: BlockNumber) {
-        match Unverified::from_rlp(raw, eip1559_transition) {
+    /// Reports given RLP as invalid block, rejected at the UNIX timestamp `reported_at`.
+    pub fn report(
+        &self,
+        raw: Bytes,
+        message: String,
+        decode_params: HeaderDecodeParams,
+        reported_at: u64,
+    ) {
+        match Unverified::from_rlp(raw, decode_params) {
             Ok(unverified) => {
                 error!(
                     target: "client",
@@ -59,9 +97,18 @@ impl BadBlocks {
                         .map(|(index, tx)| format!("[Tx {}] {:?}", index, tx))
                         .join("\n"),
                 );
-                self.last_blocks
-                    .write()
-                    .insert(unverified.header.hash(), (unverified, message));
+                let hash = unverified.header.hash();
+                let mut state = self.state.lock();
+                state.pending.retain(|record| record.hash != hash);
+                if state.pending.len() >= MAX_BAD_BLOCKS {
+                    state.pending.pop_front();
+                }
+                state.pending.push_back(Record {
+                    hash,
+                    bytes: unverified.bytes,
+                    reason: message,
+                    reported_at,
+                });
             }
             Err(err) => {
                 error!(target: "client", "Bad undecodable block detected: {}\n{:?}", message, err);
@@ -69,19 +116,196 @@ impl BadBlocks {
         }
     }
 
-    /// Returns a list of recently detected bad blocks with error descriptions.
-    pub fn bad_blocks(&self, eip1559_transition: BlockNumber) -> Vec<(Unverified, String)> {
-        self.last_blocks
-            .read()
-            .backstore()
-            .iter()
-            .map(|(_k, (unverified, message))| {
-                (
-                    Unverified::from_rlp(unverified.bytes.clone(), eip1559_transition)
-                        .expect("Bytes coming from UnverifiedBlock so decodable; qed"),
-                    message.clone(),
-                )
+    /// Returns a list of recently detected bad blocks, newest first.
+    pub fn bad_blocks(
+        &self,
+        db: &dyn KeyValueDB,
+        decode_params: HeaderDecodeParams,
+    ) -> Vec<BadBlock> {
+        let state = self.state.lock();
+        let mut records = records(db);
+        records.retain(|stored| {
+            state
+                .pending
+                .iter()
+                .all(|record| record.hash != stored.hash)
+        });
+        records.extend(state.pending.iter().map(|record| Record {
+            hash: record.hash,
+            bytes: record.bytes.clone(),
+            reason: record.reason.clone(),
+            reported_at: record.reported_at,
+        }));
+        drop(state);
+
+        // pending reports are newer than the stored ones
+        records
+            .into_iter()
+            .rev()
+            .take(MAX_BAD_BLOCKS)
+            .filter_map(|record| {
+                Unverified::from_rlp(record.bytes, decode_params)
+                    .ok()
+                    .map(|block| BadBlock {
+                        block,
+                        reason: record.reason,
+                        reported_at: record.reported_at,
+                    })
             })
             .collect()
     }
+
+    /// Writes the pending reports and drops the oldest stored ones beyond the limit.
+    pub fn flush(&self, db: &dyn KeyValueDB) {
+        let mut state = self.state.lock();
+        if state.pending.is_empty() {
+            return;
+        }
+
+        let pending = ::std::mem::replace(&mut state.pending, VecDeque::new());
+        let mut persisted = match state.persisted.take() {
+            Some(persisted) => persisted,
+            None => records(db).into_iter().map(|record| record.hash).collect(),
+        };
+        let mut batch = DBTransaction::new();
+        for record in pending {
+            let mut stream = RlpStream::new_list(3);
+            stream
+                .append(&record.bytes)
+                .append(&record.reason)
+                .append(&record.reported_at);
+            batch.put(
+                ::db::COL_NODE_INFO,
+                &bad_block_key(&record.hash),
+                &stream.out(),
+            );
+            persisted.retain(|hash| *hash != record.hash);
+            persisted.push_back(record.hash);
+        }
+        while persisted.len() > MAX_BAD_BLOCKS {
+            if let Some(hash) = persisted.pop_front() {
+                batch.delete(::db::COL_NODE_INFO, &bad_block_key(&hash));
+            }
+        }
+
+        match db.write(batch) {
+            Ok(()) => state.persisted = Some(persisted),
+            // the stored hashes are read again on the next flush
+            Err(e) => warn!(target: "client", "Failed to persist bad blocks: {}", e),
+        }
+    }
+}
+
+fn bad_block_key(hash: &H256) -> Vec<u8> {
+    let mut key = BAD_BLOCK_KEY_PREFIX.to_vec();
+    key.extend_from_slice(hash.as_bytes());
+    key
+}
+
+/// Decodable stored bad block records, oldest first.
+fn records(db: &dyn KeyValueDB) -> Vec<Record> {
+    let mut records = db
+        .iter_from_prefix(::db::COL_NODE_INFO, BAD_BLOCK_KEY_PREFIX)
+        .take_while(|(key, _)| key.starts_with(BAD_BLOCK_KEY_PREFIX))
+        .filter(|(key, _)| key.len() == BAD_BLOCK_KEY_PREFIX.len() + H256::len_bytes())
+        .filter_map(|(key, value)| {
+            let rlp = Rlp::new(&value);
+            match (rlp.val_at(0), rlp.val_at(1), rlp.val_at(2)) {
+                (Ok(bytes), Ok(reason), Ok(reported_at)) => Some(Record {
+                    hash: H256::from_slice(&key[BAD_BLOCK_KEY_PREFIX.len()..]),
+                    bytes,
+                    reason,
+                    reported_at,
+                }),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    records.sort_by_key(|record| record.reported_at);
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_helpers::{get_good_dummy_block, new_db};
+
+    fn decode_params() -> HeaderDecodeParams {
+        u64::max_value().into()
+    }
+
+    #[test]
+    fn persists_bad_blocks() {
+        let db = new_db();
+        let bad_blocks = BadBlocks::default();
+        bad_blocks.report(
+            get_good_dummy_block(),
+            "Invalid seal".into(),
+            decode_params(),
+            10,
+        );
+        bad_blocks.report(vec![0xff], "Undecodable".into(), decode_params(), 11);
+
+        // reports are served before they are written
+        assert_eq!(
+            bad_blocks
+                .bad_blocks(&**db.key_value(), decode_params())
+                .len(),
+            1
+        );
+        assert!(BadBlocks::default()
+            .bad_blocks(&**db.key_value(), decode_params())
+            .is_empty());
+
+        // a fresh instance reads what was stored
+        bad_blocks.flush(&**db.key_value());
+        let reported = BadBlocks::default().bad_blocks(&**db.key_value(), decode_params());
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].reason, "Invalid seal");
+        assert_eq!(reported[0].block.bytes, get_good_dummy_block());
+        assert_eq!(reported[0].reported_at, 10);
+    }
+
+    #[test]
+    fn prunes_oldest_bad_blocks() {
+        let db = new_db();
+        let stored = MAX_BAD_BLOCKS as u64 + 5;
+        let mut batch = DBTransaction::new();
+        for reported_at in 0..stored {
+            let mut stream = RlpStream::new_list(3);
+            stream
+                .append(&get_good_dummy_block())
+                .append(&"Invalid seal".to_owned())
+                .append(&reported_at);
+            let key = bad_block_key(&H256::from_low_u64_be(reported_at));
+            batch.put(::db::COL_NODE_INFO, &key, &stream.out());
+        }
+        db.key_value().write(batch).unwrap();
+
+        let bad_blocks = BadBlocks::default();
+        bad_blocks.report(
+            get_good_dummy_block(),
+            "Invalid seal".into(),
+            decode_params(),
+            stored,
+        );
+        bad_blocks.flush(&**db.key_value());
+        let kept = records(&**db.key_value());
+        assert_eq!(kept.len(), MAX_BAD_BLOCKS);
+        assert_eq!(kept[0].reported_at, 6);
+        assert_eq!(kept[MAX_BAD_BLOCKS - 1].reported_at, stored);
+
+        // reporting a stored block again replaces it
+        bad_blocks.report(
+            get_good_dummy_block(),
+            "Invalid seal".into(),
+            decode_params(),
+            stored + 1,
+        );
+        bad_blocks.flush(&**db.key_value());
+        let kept = records(&**db.key_value());
+        assert_eq!(kept.len(), MAX_BAD_BLOCKS);
+        assert_eq!(kept[0].reported_at, 6);
+        assert_eq!(kept[MAX_BAD_BLOCKS - 1].reported_at, stored + 1);
+    }
 }
@@ -18,7 +18,7 @@ use blockchain::ImportRoute;
 use bytes::Bytes;
 use ethereum_types::{H256, U256};
 use std::{collections::HashMap, time::Duration};
-use types::transaction::UnverifiedTransaction;
+use types::{transaction::UnverifiedTransaction, BlockNumber};
 
 /// Messages to broadcast via chain
 pub enum ChainMessageType {
@@ -162,6 +162,69 @@ impl NewBlocks {
     }
 }
 
+/// Used by `ChainNotify` `reorg_refused()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefusedReorg {
+    /// Best block which was kept as the head of the chain.
+    pub best: H256,
+    /// Block which would have become the new head.
+    pub refused: H256,
+    /// Common ancestor of both branches.
+    pub ancestor: H256,
+    /// Number of canonical blocks the reorganization would have retracted.
+    pub depth: usize,
+}
+
+/// Block taking part in a reorganization.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReorgBlock {
+    /// Block number.
+    pub number: BlockNumber,
+    /// Block hash.
+    pub hash: H256,
+}
+
+/// Used by `ChainNotify` `chain_reorganized()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reorg {
+    /// Common ancestor of the retracted and the enacted branch.
+    pub ancestor: ReorgBlock,
+    /// Blocks no longer canonical, from the old head down to the ancestor.
+    pub retracted: Vec<ReorgBlock>,
+    /// Blocks which became canonical, from the ancestor up to the new head.
+    pub enacted: Vec<ReorgBlock>,
+    /// Transactions included in the retracted blocks.
+    pub retracted_transactions: Vec<H256>,
+    /// Transactions included in the enacted blocks.
+    pub enacted_transactions: Vec<H256>,
+}
+
+/// Used by `ChainNotify` `local_transactions()`
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocalTransactionEvent {
+    /// Local transaction was included in a canonical block.
+    Mined {
+        /// Transaction hash.
+        hash: H256,
+        /// Hash of the block including the transaction.
+        block_hash: H256,
+    },
+    /// Local transaction was removed from the pool without being mined.
+    Dropped {
+        /// Transaction hash.
+        hash: H256,
+        /// Reason of the removal.
+        reason: String,
+    },
+    /// Local transaction went back to the pool because its block was retracted.
+    Requeued {
+        /// Transaction hash.
+        hash: H256,
+        /// Hash of the retracted block.
+        block_hash: H256,
+    },
+}
+
 /// Represents what has to be handled by actor listening to chain events
 pub trait ChainNotify: Send + Sync {
     /// fires when chain has new blocks.
@@ -194,4 +257,24 @@ pub trait ChainNotify: Send + Sync {
     fn transactions_received(&self, _txs: &[UnverifiedTransaction], _peer_id: usize) {
         // does nothing by default
     }
+
+    /// fires when a heavier fork is kept off the canonical chain because it would
+    /// reorganize deeper than the configured maximum depth
+    /// implementations should be light
+    fn reorg_refused(&self, _reorg: &RefusedReorg) {
+        // does nothing by default
+    }
+
+    /// fires when a block retracting part of the canonical chain has been committed,
+    /// before the corresponding `new_blocks()` notification
+    /// implementations should be light
+    fn chain_reorganized(&self, _reorg: &Reorg) {
+        // does nothing by default
+    }
+
+    /// fires when locally submitted transactions are mined, dropped from the pool
+    /// or requeued after a reorganization
+    fn local_transactions(&self, _events: &[LocalTransactionEvent]) {
+        // does nothing by default
+    }
 }
@@ -17,13 +17,14 @@
 use std::{
     cmp,
     collections::{BTreeMap, HashSet, VecDeque},
-    convert::TryFrom,
-    io::{BufRead, BufReader},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
     str::{from_utf8, FromStr},
     sync::{
-        atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering as AtomicOrdering},
+        atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering},
         Arc, Weak,
     },
+    thread,
     time::{Duration, Instant},
 };
 
@@ -36,12 +37,12 @@ use call_contract::CallContract;
 use db::{DBTransaction, DBValue, KeyValueDB};
 use ethcore_miner::pool::VerifiedTransaction;
 use ethereum_types::{Address, H256, H264, U256};
-use hash::keccak;
+use hash::{keccak, KECCAK_EMPTY, KECCAK_NULL_RLP};
 use itertools::Itertools;
 use parking_lot::{Mutex, RwLock};
 use rand::rngs::OsRng;
 use rlp::{PayloadInfo, Rlp};
-use rustc_hex::FromHex;
+use rustc_hex::{FromHex, ToHex};
 use trie::{Trie, TrieFactory, TrieSpec};
 use types::{
     ancestry_action::AncestryAction,
@@ -62,17 +63,21 @@ use vm::{EnvInfo, LastHashes};
 use ansi_term::Colour;
 use block::{enact_verified, ClosedBlock, Drain, LockedBlock, OpenBlock, SealedBlock};
 use call_contract::RegistryInfo;
+use client::overlay::OverlayChain;
+use client::prefetch::{self, StatePrefetch};
+use client::timestamp_quarantine::TimestampQuarantine;
 use client::{
     ancient_import::AncientVerifier,
-    bad_blocks,
+    bad_blocks::{self, BadBlock},
     traits::{ForceUpdateSealing, TransactionRequest},
-    AccountData, BadBlocks, Balance, BlockChain as BlockChainTrait, BlockChainClient,
-    BlockChainReset, BlockId, BlockInfo, BlockProducer, BroadcastProposalBlock, Call,
+    AccountData, BackupStatus, BadBlocks, Balance, BlockChain as BlockChainTrait, BlockChainClient,
+    BlockChainReset, BlockId, BlockInfo, BlockProducer, BroadcastProposalBlock, CacheSizes, Call,
     CallAnalytics, ChainInfo, ChainMessageType, ChainNotify, ChainRoute, ClientConfig,
     ClientIoMessage, EngineInfo, ImportBlock, ImportExportBlocks, ImportSealedBlock, IoClient,
-    Mode, NewBlocks, Nonce, PrepareOpenBlock, ProvingBlockChainClient, PruningInfo, ReopenBlock,
-    ScheduleInfo, SealedBlockImporter, StateClient, StateInfo, StateOrBlock, TraceFilter, TraceId,
-    TransactionId, TransactionInfo, UncleId,
+    IoQueueClassConfig, IoQueueConfig, LocalTransactionEvent, Mode, NewBlocks, Nonce,
+    PrepareOpenBlock, ProvingBlockChainClient, PruningInfo, RefusedReorg, ReopenBlock, Reorg,
+    ReorgBlock, ReplayOptions, ScheduleInfo, SealedBlockImporter, StateClient, StateInfo,
+    StateOrBlock, StateOverride, TraceFilter, TraceId, TransactionId, TransactionInfo, UncleId,
 };
 use engines::{
     epoch::PendingTransition, EngineError, EpochTransition, EthEngine, ForkChoice, SealingState,
@@ -85,12 +90,16 @@ use error::{
 use executive::{contract_address, Executed, Executive, TransactOptions};
 use factory::{Factories, VmFactory};
 use io::IoChannel;
-use miner::{Miner, MinerService};
+use lru_cache::LruCache;
+use miner::{BlockFees, Miner, MinerService};
+use pod_state::PodState;
+use rayon::prelude::*;
 use snapshot::{self, io as snapshot_io, SnapshotClient};
 use spec::Spec;
 use state::{self, State};
 use state_db::StateDB;
-use stats::{PrometheusMetrics, PrometheusRegistry};
+use stats::{latency_histogram, prometheus::Histogram, PrometheusMetrics, PrometheusRegistry};
+use time_utils::SharedClock;
 use trace::{
     self, Database as TraceDatabase, ImportRequest as TraceImportRequest, LocalizedTrace, TraceDB,
 };
@@ -104,7 +113,6 @@ use vm::Schedule;
 // re-export
 pub use blockchain::CacheSize as BlockChainCacheSize;
 use db::{keys::BlockDetails, Readable, Writable};
-pub use reth_util::queue::ExecutionQueue;
 pub use types::{block_status::BlockStatus, blockchain_info::BlockChainInfo};
 pub use verification::QueueInfo as BlockQueueInfo;
 use_contract!(registry, "res/contracts/registrar.json");
@@ -112,8 +120,43 @@ use_contract!(registry, "res/contracts/registrar.json");
 const ANCIENT_BLOCKS_QUEUE_SIZE: usize = 4096;
 // Max number of blocks imported at once.
 const ANCIENT_BLOCKS_BATCH_SIZE: usize = 4;
+// How long `queue_ancient_block` holds the caller back on a full queue before rejecting the block.
+// Kept short as the caller may hold the sync lock.
+const ANCIENT_BLOCKS_QUEUE_TIMEOUT: Duration = Duration::from_millis(100);
 const MAX_QUEUE_SIZE_TO_SLEEP_ON: usize = 2;
 const MIN_HISTORY_SIZE: u64 = 8;
+const HISTORY_EXPIRY_BATCH: u64 = 4096;
+// Number of blocks recompressed at once by the recompression thread.
+const RECOMPRESS_BATCH: u64 = 64;
+// Pause of the recompression thread between batches, and once it has caught up.
+const RECOMPRESS_PAUSE: Duration = Duration::from_millis(100);
+const RECOMPRESS_IDLE: Duration = Duration::from_secs(30);
+// Number of blocks whose fees are kept for `block_fees`.
+const BLOCK_FEES_CACHE_SIZE: usize = 512;
+
+/// Filters applied by `Client::export_state`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportStateOptions {
+    /// Include contract code.
+    pub code: bool,
+    /// Include contract storage.
+    pub storage: bool,
+    /// Skip accounts with a lower balance.
+    pub min_balance: Option<U256>,
+    /// Skip accounts with a higher balance.
+    pub max_balance: Option<U256>,
+}
+
+impl Default for ExportStateOptions {
+    fn default() -> Self {
+        ExportStateOptions {
+            code: true,
+            storage: true,
+            min_balance: None,
+            max_balance: None,
+        }
+    }
+}
 
 /// Report on the status of a client.
 #[derive(Default, Clone, Debug, Eq, PartialEq)]
@@ -124,6 +167,8 @@ pub struct ClientReport {
     pub transactions_applied: usize,
     /// How much gas has been processed so far.
     pub gas_processed: U256,
+    /// How many reorganizations were refused for exceeding the maximum depth.
+    pub reorgs_refused: usize,
     /// Internal structure item sizes
     pub item_sizes: BTreeMap<String, usize>,
 }
@@ -144,6 +189,7 @@ impl<'a> ::std::ops::Sub<&'a ClientReport> for ClientReport {
         self.blocks_imported -= other.blocks_imported;
         self.transactions_applied -= other.transactions_applied;
         self.gas_processed = self.gas_processed - other.gas_processed;
+        self.reorgs_refused -= other.reorgs_refused;
 
         self
     }
@@ -190,6 +236,18 @@ struct Importer {
 
     /// A lru cache of recently detected bad blocks
     pub bad_blocks: bad_blocks::BadBlocks,
+
+    /// Blocks rejected for a future timestamp, re-imported once the local clock catches up
+    pub timestamp_quarantine: TimestampQuarantine,
+
+    /// Time spent executing and locking each imported block
+    pub execution_histogram: Histogram,
+
+    /// Time spent committing each imported block to the database
+    pub commit_histogram: Histogram,
+
+    /// Time spent flushing the database at the end of an import round
+    pub flush_histogram: Histogram,
 }
 
 /// Blockchain database client backed by a persistent database. Owns and manages a blockchain and a block queue.
@@ -231,26 +289,47 @@ pub struct Client {
     /// List of actors to be notified on certain chain events
     notify: RwLock<Vec<Weak<dyn ChainNotify>>>,
 
-    /// Queued transactions from IO
-    queue_transactions: IoChannelQueue,
+    /// Prioritized queue of work (transactions, consensus messages) handed over to IO
+    io_queue: IoChannelQueue,
     /// Ancient blocks import queue
     /// Queued ancient blocks, make sure they are imported in order.
     queued_ancient_blocks: Arc<RwLock<HashSet<H256>>>,
-    queued_ancient_blocks_executer: Mutex<Option<ExecutionQueue<(Unverified, Bytes)>>>,
-    /// Consensus messages import queue
-    queue_consensus_message: IoChannelQueue,
+    /// Bounded channel feeding the ancient block import thread. Blocks that don't fit
+    /// within `ANCIENT_BLOCKS_QUEUE_TIMEOUT` are rejected and downloaded again by the sync layer.
+    ancient_blocks_sender: RwLock<Option<crossbeam_channel::Sender<(Unverified, Bytes)>>>,
+    ancient_blocks_importer: Mutex<Option<thread::JoinHandle<()>>>,
+    /// Number of times queueing an ancient block had to wait for a full queue.
+    ancient_blocks_queue_waits: AtomicU64,
+    /// Number of ancient blocks rejected by a full queue.
+    ancient_blocks_queue_rejected: AtomicU64,
+
+    /// Held for reading by database writers running outside of block import,
+    /// and for writing while a backup copies the database.
+    db_writers: RwLock<()>,
+    /// Channel feeding the backup thread with target directories.
+    backup_sender: Mutex<Option<crossbeam_channel::Sender<PathBuf>>>,
+    backup_worker: Mutex<Option<thread::JoinHandle<()>>>,
+    backup_status: RwLock<BackupStatus>,
+    /// Dropped to stop the history recompression thread.
+    recompress_stop: Mutex<Option<crossbeam_channel::Sender<()>>>,
+    recompress_worker: Mutex<Option<thread::JoinHandle<()>>>,
 
-    last_hashes: RwLock<VecDeque<H256>>,
     factories: Factories,
 
     /// Number of eras kept in a journal before they are pruned
     history: u64,
 
+    /// Serializes on-demand regeneration of pruned states.
+    regeneration_lock: Mutex<()>,
+
     /// An action to be done if a mode/spec_name change happens
     on_user_defaults_change: Mutex<Option<Box<dyn FnMut(Option<Mode>) + 'static + Send>>>,
 
     registrar_address: Option<Address>,
 
+    /// Fees of recently requested blocks, keyed by block hash.
+    block_fees: Mutex<LruCache<H256, BlockFees>>,
+
     /// A closure to call when we want to restart the client
     exit_handler: Mutex<Option<Box<dyn Fn(String) + 'static + Send>>>,
 
@@ -279,13 +358,44 @@ impl Importer {
             ancient_verifier: AncientVerifier::new(engine.clone()),
             engine,
             bad_blocks: Default::default(),
+            timestamp_quarantine: Default::default(),
+            execution_histogram: latency_histogram(
+                "import_execution_seconds",
+                "Time spent executing a block during import",
+            ),
+            commit_histogram: latency_histogram(
+                "import_commit_seconds",
+                "Time spent committing an imported block to the database",
+            ),
+            flush_histogram: latency_histogram(
+                "import_flush_seconds",
+                "Time spent flushing the database after an import round",
+            ),
         })
     }
 
+    /// Keep `raw` as a bad block rejected now. It is written to the database on the next tick.
+    fn report_bad_block(&self, raw: Bytes, message: String) {
+        let now = ::machine::Machine::clock(self.engine.machine())
+            .current()
+            .unix_now();
+        self.bad_blocks.report(
+            raw,
+            message,
+            self.engine.params().header_decode_params(),
+            now,
+        );
+    }
+
     // t_nb 6.0 This is triggered by a message coming from a block queue when the block is ready for insertion
+    //
+    // Of the import work, only sender recovery (on the queue's verifier threads) and state reads
+    // (the prefetch below) run ahead of the block being enacted. Family verification, execution
+    // and commit of a block need its parent committed, and stay sequential under the import lock.
     pub fn import_verified_blocks(&self, client: &Client) -> usize {
         // Shortcut out if we know we're incapable of syncing the chain.
         trace!(target: "block_import", "fn import_verified_blocks");
+        let _profile = db::read_profiler::scope("import");
         if !client.enabled.load(AtomicOrdering::SeqCst) {
             self.block_queue.reset_verification_ready_signal();
             return 0;
@@ -303,7 +413,9 @@ impl Importer {
         ) = {
             let mut imported_blocks = Vec::with_capacity(max_blocks_to_import);
             let mut invalid_blocks = HashSet::new();
-            let proposed_blocks = Vec::with_capacity(max_blocks_to_import);
+            // blocks which can't be checked for now, released from the queue without being bad
+            let mut deferred_blocks = HashSet::new();
+            let mut proposed_blocks = Vec::with_capacity(max_blocks_to_import);
             let mut import_results = Vec::with_capacity(max_blocks_to_import);
 
             let _import_lock = self.import_lock.lock();
@@ -316,6 +428,21 @@ impl Importer {
             trace_time!("import_verified_blocks");
             let start = Instant::now();
 
+            // t_nb 6.1 warm the caches with the state touched by the later blocks of this round
+            // while the earlier ones are being enacted. The blocks are still imported in order.
+            let _prefetch = if blocks.len() > 1 {
+                let best = client.chain.read().best_block_header();
+                StatePrefetch::spawn(
+                    client.state_db.read().boxed_clone_canon(&best.hash()),
+                    *best.state_root(),
+                    self.engine.account_start_nonce(best.number()),
+                    client.factories.clone(),
+                    prefetch::touched_accounts(&blocks[1..]),
+                )
+            } else {
+                None
+            };
+
             for block in blocks {
                 let header = block.header.clone();
                 let bytes = block.bytes.clone();
@@ -333,13 +460,28 @@ impl Importer {
                     invalid_blocks.insert(hash);
                     continue;
                 }
+                if deferred_blocks.contains(header.parent_hash()) {
+                    deferred_blocks.insert(hash);
+                    continue;
+                }
                 // t_nb 7.0 check and lock block
-                match self.check_and_lock_block(&bytes, block, client) {
+                let execution_start = Instant::now();
+                let checked = self.check_and_lock_block(&bytes, block, client);
+                self.execution_histogram
+                    .observe(execution_start.elapsed().as_secs_f64());
+                match checked {
                     Ok((closed_block, pending)) => {
+                        // Proposals are only broadcast, they are committed with their final seal.
+                        if self.engine.is_proposal(&header) {
+                            self.block_queue.mark_as_good(&[hash]);
+                            proposed_blocks.push(bytes);
+                            continue;
+                        }
                         imported_blocks.push(hash);
                         let transactions_len = closed_block.transactions.len();
                         trace!(target:"block_import","Block #{}({}) check pass",header.number(),header.hash());
                         // t_nb 8.0 commit block to db
+                        let commit_start = Instant::now();
                         let route = self.commit_block(
                             closed_block,
                             &header,
@@ -347,6 +489,8 @@ impl Importer {
                             pending,
                             client,
                         );
+                        self.commit_histogram
+                            .observe(commit_start.elapsed().as_secs_f64());
                         trace!(target:"block_import","Block #{}({}) commited",header.number(),header.hash());
                         import_results.push(route);
                         client
@@ -354,12 +498,12 @@ impl Importer {
                             .write()
                             .accrue_block(&header, transactions_len);
                     }
+                    Err(EthcoreError(EthcoreErrorKind::Engine(EngineError::Unavailable(e)), _)) => {
+                        warn!(target: "client", "Block import deferred for #{} ({}): {}", header.number(), hash, e);
+                        deferred_blocks.insert(hash);
+                    }
                     Err(err) => {
-                        self.bad_blocks.report(
-                            bytes,
-                            format!("{:?}", err),
-                            self.engine.params().eip1559_transition,
-                        );
+                        self.report_bad_block(bytes, format!("{:?}", err));
                         invalid_blocks.insert(hash);
                     }
                 }
@@ -371,6 +515,10 @@ impl Importer {
             if !invalid_blocks.is_empty() {
                 self.block_queue.mark_as_bad(&invalid_blocks);
             }
+            if !deferred_blocks.is_empty() {
+                let deferred_blocks = deferred_blocks.into_iter().collect::<Vec<H256>>();
+                self.block_queue.mark_as_good(&deferred_blocks);
+            }
             let has_more_blocks_to_import = !self.block_queue.mark_as_good(&imported_blocks);
             (
                 imported_blocks,
@@ -412,11 +560,17 @@ impl Importer {
                         has_more_blocks_to_import,
                     ));
                 });
+
+                // t_nb 11.1 notify about mined, dropped and requeued local transactions
+                client.notify_local_transactions(route.enacted(), route.retracted());
             }
         }
         trace!(target:"block_import","Flush block to db");
         let db = client.db.read();
+        let flush_start = Instant::now();
         db.key_value().flush().expect("DB flush failed.");
+        self.flush_histogram
+            .observe(flush_start.elapsed().as_secs_f64());
 
         self.block_queue.resignal_verification();
         trace!(target:"block_import","Resignal verifier");
@@ -491,9 +645,22 @@ impl Importer {
         if header.number() >= engine.params().validate_service_transactions_transition {
             // Check if zero gas price transactions are certified to be service transactions
             // using the Certifier contract. If they are not certified, the block is treated as invalid.
-            let service_transaction_checker = self.miner.service_transaction_checker();
+            // The cache is only refreshed once the state of the registrar or the certifier changed.
+            let contract_state = |address: &Address| {
+                (
+                    client.storage_root(address, BlockId::Latest),
+                    client.code_hash(address, BlockId::Latest),
+                )
+            };
+            let service_transaction_checker = self
+                .miner
+                .service_transaction_checker()
+                .filter(|checker| checker.is_stale(&contract_state));
             if service_transaction_checker.is_some() {
-                match service_transaction_checker.unwrap().refresh_cache(client) {
+                match service_transaction_checker
+                    .unwrap()
+                    .refresh_cache(client, &contract_state)
+                {
                     Ok(true) => {
                         trace!(target: "client", "Service transaction cache was refreshed successfully");
                     }
@@ -506,7 +673,7 @@ impl Importer {
                 };
             };
             for t in &block.transactions {
-                if t.has_zero_gas_price() {
+                if t.has_zero_gas_price() && !engine.machine().is_system_transaction(t) {
                     match self.miner.service_transaction_checker() {
                         None => {
                             let e = "Service transactions are not allowed. You need to enable Certifier contract.";
@@ -679,7 +846,7 @@ impl Importer {
             let header = chain
                 .block_header_data(&hash)
                 .expect("Best block is in the database; qed")
-                .decode(self.engine.params().eip1559_transition)
+                .decode(self.engine.params().header_decode_params())
                 .expect("Stored block header is valid RLP; qed");
             let details = chain
                 .block_details(&hash)
@@ -696,12 +863,26 @@ impl Importer {
         let route = chain.tree_route(best_hash, *parent).expect("forks are only kept when it has common ancestors; tree route from best to prospective's parent always exists; qed");
 
         // t_nb 9.3 Check block total difficulty
-        let fork_choice = if route.is_from_route_finalized {
+        let mut fork_choice = if route.is_from_route_finalized {
             ForkChoice::Old
         } else {
             self.engine.fork_choice(&new, &best)
         };
 
+        let refused_reorg = match client.config.max_reorg_depth {
+            Some(max_depth) if fork_choice == ForkChoice::New && route.index as u64 > max_depth => {
+                fork_choice = ForkChoice::Old;
+                Some(RefusedReorg {
+                    best: best_hash,
+                    refused: *hash,
+                    ancestor: route.ancestor,
+                    depth: route.index,
+                })
+            }
+            _ => None,
+        };
+        let ancestor = route.ancestor;
+
         // t_nb 9.4 CHECK! I *think* this is fine, even if the state_root is equal to another
         // already-imported block of the same number.
         // TODO: Prove it with a test.
@@ -770,20 +951,67 @@ impl Importer {
         // t_nb 9.12 commit changed to become current greatest by applying pending insertion updates (Sync point)
         chain.commit();
 
+        let reorg = if route.retracted.is_empty() {
+            None
+        } else {
+            Some(Self::reorg(&chain, ancestor, &route))
+        };
+
         // t_nb 9.13 check epoch end. Related only to AuRa and it seems light engine
         self.check_epoch_end(&header, &finalized, &chain, client);
 
-        // t_nb 9.14 update last hashes. They are build in step 7.5
-        client.update_last_hashes(&parent, hash);
-
         // t_nb 9.15 prune ancient states
         if let Err(e) = client.prune_ancient(state, &chain) {
             warn!("Failed to prune ancient state data: {}", e);
         }
+        drop(chain);
+
+        if let Some(reorg) = refused_reorg {
+            warn!(
+                "Refusing to reorganize {} blocks deep to #{} ({}); common ancestor {} is beyond the maximum reorg depth",
+                reorg.depth, number, reorg.refused, reorg.ancestor
+            );
+            client.report.write().reorgs_refused += 1;
+            client.notify(|notify| notify.reorg_refused(&reorg));
+        }
+
+        if let Some(reorg) = reorg {
+            debug!(target: "client", "Reorganized {} blocks to #{} ({}); common ancestor #{} ({})",
+                   reorg.retracted.len(), number, hash, reorg.ancestor.number, reorg.ancestor.hash);
+            client.notify(|notify| notify.chain_reorganized(&reorg));
+        }
 
         route
     }
 
+    // describe a committed reorganization, with the blocks and transactions it touched.
+    fn reorg(chain: &BlockChain, ancestor: H256, route: &ImportRoute) -> Reorg {
+        const KNOWN_BLOCK_PROOF: &str = "blocks on the import route are in the database; qed";
+        let block = |hash: &H256| ReorgBlock {
+            number: chain.block_number(hash).expect(KNOWN_BLOCK_PROOF),
+            hash: *hash,
+        };
+        let transactions = |hashes: &[H256]| {
+            hashes
+                .iter()
+                .flat_map(|hash| {
+                    chain
+                        .block_body(hash)
+                        .expect(KNOWN_BLOCK_PROOF)
+                        .transaction_hashes()
+                })
+                .collect()
+        };
+
+        Reorg {
+            ancestor: block(&ancestor),
+            retracted: route.retracted.iter().map(&block).collect(),
+            enacted: route.enacted.iter().map(&block).collect(),
+            retracted_transactions: transactions(&route.retracted),
+            enacted_transactions: transactions(&route.enacted),
+        }
+    }
+
     // check for epoch end signal and write pending transition if it occurs.
     // state for the given block must be available.
     fn check_epoch_end_signal(
@@ -818,6 +1046,7 @@ impl Importer {
                             gas_used: U256::default(),
                             gas_limit: u64::max_value().into(),
                             base_fee: header.base_fee(),
+                            random: header.mix_hash(),
                         };
 
                         let call = move |addr, data| {
@@ -840,7 +1069,7 @@ impl Importer {
 
                             let options = TransactOptions::with_no_tracing().dont_check_nonce();
                             let machine = self.engine.machine();
-                            let schedule = machine.schedule(env_info.number);
+                            let schedule = machine.schedule(env_info.number, env_info.timestamp);
                             let res = Executive::new(&mut state, &env_info, &machine, &schedule)
                                 .transact(&transaction, options);
 
@@ -933,6 +1162,13 @@ impl Client {
         miner: Arc<Miner>,
         message_channel: IoChannel<ClientIoMessage>,
     ) -> Result<Arc<Client>, ::error::Error> {
+        spec.engine.machine().set_clock(config.clock.current());
+        spec.engine
+            .machine()
+            .set_max_future_drift(config.max_future_block_drift);
+        spec.engine
+            .set_pow_cache_config(config.ethash_cache_count, config.ethash_prebuild_distance);
+
         let trie_spec = match config.fat_db {
             true => TrieSpec::Fat,
             false => TrieSpec::Secure,
@@ -947,6 +1183,9 @@ impl Client {
 
         let journal_db = journaldb::new(db.key_value().clone(), config.pruning, ::db::COL_STATE);
         let mut state_db = StateDB::new(journal_db, config.state_cache_size);
+        if config.read_only && state_db.journal_db().is_empty() {
+            return Err("A read-only client requires an existing database".into());
+        }
         if state_db.journal_db().is_empty() {
             // Sets the correct state root.
             state_db = spec.ensure_db_good(state_db, &factories)?;
@@ -960,7 +1199,7 @@ impl Client {
             config.blockchain.clone(),
             &gb,
             db.clone(),
-            spec.params().eip1559_transition,
+            spec.params().header_decode_params(),
         ));
         let tracedb = RwLock::new(TraceDB::new(
             config.tracing.clone(),
@@ -1010,6 +1249,9 @@ impl Client {
         if let Some(ref addr) = registrar_address {
             trace!(target: "client", "Found registrar at {}", addr);
         }
+        if let Some(checker) = importer.miner.service_transaction_checker() {
+            checker.set_registrar(registrar_address);
+        }
 
         let client = Arc::new(Client {
             enabled: AtomicBool::new(true),
@@ -1026,57 +1268,58 @@ impl Client {
             report: RwLock::new(Default::default()),
             io_channel: RwLock::new(message_channel),
             notify: RwLock::new(Vec::new()),
-            queue_transactions: IoChannelQueue::new(config.transaction_verification_queue_size),
+            io_queue: IoChannelQueue::new(config.io_queue.clone()),
             queued_ancient_blocks: Default::default(),
-            queued_ancient_blocks_executer: Default::default(),
-            queue_consensus_message: IoChannelQueue::new(usize::max_value()),
-            last_hashes: RwLock::new(VecDeque::new()),
+            ancient_blocks_sender: RwLock::new(None),
+            ancient_blocks_importer: Mutex::new(None),
+            ancient_blocks_queue_waits: AtomicU64::new(0),
+            ancient_blocks_queue_rejected: AtomicU64::new(0),
+            db_writers: RwLock::new(()),
+            backup_sender: Mutex::new(None),
+            backup_worker: Mutex::new(None),
+            recompress_stop: Mutex::new(None),
+            recompress_worker: Mutex::new(None),
+            backup_status: RwLock::new(BackupStatus::Idle),
             factories,
             history,
+            regeneration_lock: Mutex::new(()),
             on_user_defaults_change: Mutex::new(None),
             registrar_address,
+            block_fees: Mutex::new(LruCache::new(BLOCK_FEES_CACHE_SIZE)),
             exit_handler: Mutex::new(None),
             importer,
             config,
         });
 
+        let (sender, receiver) = crossbeam_channel::bounded(ANCIENT_BLOCKS_QUEUE_SIZE);
         let exec_client = client.clone();
-
-        let queued = client.queued_ancient_blocks.clone();
-        let queued_ancient_blocks_executer = ExecutionQueue::new(
-            ANCIENT_BLOCKS_QUEUE_SIZE,
-            ANCIENT_BLOCKS_BATCH_SIZE,
-            move |ancient_block: Vec<(Unverified, Bytes)>| {
-                trace_time!("import_ancient_block");
-                for (unverified, receipts_bytes) in ancient_block {
-                    let hash = unverified.hash();
-                    if !exec_client.chain.read().is_known(&unverified.parent_hash()) {
-                        queued.write().remove(&hash);
-                        continue;
-                    }
-                    let result = exec_client.importer.import_old_block(
-                        unverified,
-                        &receipts_bytes,
-                        &**exec_client.db.read().key_value(),
-                        &*exec_client.chain.read(),
-                    );
-                    if let Err(e) = result {
-                        error!(target: "client", "Error importing ancient block: {}", e);
-
-                        let mut queued = queued.write();
-                        queued.clear();
-                    }
-                    // remove from pending
-                    queued.write().remove(&hash);
+        let importer = thread::Builder::new()
+            .name("ancient_block_exec".into())
+            .spawn(move || exec_client.import_ancient_blocks(receiver))?;
+        *client.ancient_blocks_sender.write() = Some(sender);
+        *client.ancient_blocks_importer.lock() = Some(importer);
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let backup_client = client.clone();
+        let worker = thread::Builder::new()
+            .name("db_backup".into())
+            .spawn(move || {
+                while let Ok(path) = receiver.recv() {
+                    backup_client.write_backup(path);
                 }
-            },
-            "ancient_block_exec",
-        );
-
-        client
-            .queued_ancient_blocks_executer
-            .lock()
-            .replace(queued_ancient_blocks_executer);
+            })?;
+        *client.backup_sender.lock() = Some(sender);
+        *client.backup_worker.lock() = Some(worker);
+
+        if client.config.blockchain.compress_history && !client.config.read_only {
+            let (sender, receiver) = crossbeam_channel::bounded(0);
+            let recompress_client = client.clone();
+            let worker = thread::Builder::new()
+                .name("db_recompress".into())
+                .spawn(move || recompress_client.recompress_history(receiver))?;
+            *client.recompress_stop.lock() = Some(sender);
+            *client.recompress_worker.lock() = Some(worker);
+        }
 
         // prune old states.
         {
@@ -1089,7 +1332,7 @@ impl Client {
         {
             let chain = client.chain.read();
             let gh = spec.genesis_header();
-            if chain.epoch_transition(0, gh.hash()).is_none() {
+            if !client.config.read_only && chain.epoch_transition(0, gh.hash()).is_none() {
                 trace!(target: "client", "No genesis transition found.");
 
                 let proof = client.with_proving_caller(BlockId::Number(0), |call| {
@@ -1127,11 +1370,98 @@ impl Client {
 
     /// signals shutdown of application. We do cleanup here.
     pub fn shutdown(&self) {
-        let mut abe = self.queued_ancient_blocks_executer.lock();
-        if abe.is_some() {
-            abe.as_mut().unwrap().end()
+        // dropping the sender lets the import thread drain the queue and exit.
+        self.ancient_blocks_sender.write().take();
+        if let Some(importer) = self.ancient_blocks_importer.lock().take() {
+            if importer.join().is_err() {
+                error!(target: "client", "Ancient block import thread panicked");
+            }
+        }
+        // a running backup is finished first.
+        self.backup_sender.lock().take();
+        if let Some(worker) = self.backup_worker.lock().take() {
+            if worker.join().is_err() {
+                error!(target: "client", "Database backup thread panicked");
+            }
+        }
+        self.recompress_stop.lock().take();
+        if let Some(worker) = self.recompress_worker.lock().take() {
+            if worker.join().is_err() {
+                error!(target: "client", "History recompression thread panicked");
+            }
+        }
+        self.importer
+            .bad_blocks
+            .flush(&**self.db.read().key_value());
+    }
+
+    /// Copies the database into `path` with block import and other writers paused.
+    fn write_backup(&self, path: PathBuf) {
+        info!(target: "client", "Writing database backup to {}", path.display());
+        let backup = {
+            let _import_lock = self.importer.import_lock.lock();
+            let _writers = self.db_writers.write();
+            let db = self.db.read();
+            db.key_value().flush().and_then(|_| db.backup(&path))
+        };
+        // imports carry on while the snapshot is copied
+        let result = backup.and_then(|backup| {
+            backup.write(&|copied: u64| {
+                *self.backup_status.write() = BackupStatus::Running {
+                    path: path.clone(),
+                    copied,
+                };
+            })
+        });
+
+        let status = match result {
+            Ok(copied) => {
+                info!(target: "client", "Wrote database backup to {}", path.display());
+                BackupStatus::Done { path, copied }
+            }
+            Err(e) => {
+                warn!(target: "client", "Database backup to {} failed: {}", path.display(), e);
+                BackupStatus::Failed {
+                    path,
+                    error: e.to_string(),
+                }
+            }
+        };
+        *self.backup_status.write() = status;
+    }
+
+    /// Imports queued ancient blocks in batches until every sender is dropped.
+    fn import_ancient_blocks(&self, receiver: crossbeam_channel::Receiver<(Unverified, Bytes)>) {
+        while let Ok(first) = receiver.recv() {
+            trace_time!("import_ancient_block");
+            let batch = ::std::iter::once(first)
+                .chain(receiver.try_iter().take(ANCIENT_BLOCKS_BATCH_SIZE - 1))
+                .collect::<Vec<_>>();
+            for (unverified, receipts_bytes) in batch {
+                let hash = unverified.hash();
+                if !self.chain.read().is_known(&unverified.parent_hash()) {
+                    self.queued_ancient_blocks.write().remove(&hash);
+                    continue;
+                }
+                let result = {
+                    let _writers = self.db_writers.read();
+                    self.importer.import_old_block(
+                        unverified,
+                        &receipts_bytes,
+                        &**self.db.read().key_value(),
+                        &*self.chain.read(),
+                    )
+                };
+                if let Err(e) = result {
+                    error!(target: "client", "Error importing ancient block: {}", e);
+
+                    let mut queued = self.queued_ancient_blocks.write();
+                    queued.clear();
+                }
+                // remove from pending
+                self.queued_ancient_blocks.write().remove(&hash);
+            }
         }
-        *abe = None;
     }
 
     /// Wakes up client if it's a sleep.
@@ -1167,6 +1497,58 @@ impl Client {
         }
     }
 
+    // notify about local transactions included in `enacted` or `retracted` blocks
+    // and about the ones removed from the queue since the last notification.
+    fn notify_local_transactions(&self, enacted: &[H256], retracted: &[H256]) {
+        let mut events = Vec::new();
+        let local = if enacted.is_empty() && retracted.is_empty() {
+            Default::default()
+        } else {
+            self.importer.miner.local_transactions()
+        };
+        if !local.is_empty() {
+            let chain = self.chain.read();
+            let local_in = |hashes: &[H256]| {
+                hashes
+                    .iter()
+                    .filter_map(|block_hash| {
+                        chain
+                            .block_body(block_hash)
+                            .map(|body| (*block_hash, body.transaction_hashes()))
+                    })
+                    .flat_map(|(block_hash, txs)| {
+                        txs.into_iter()
+                            .filter(|hash| local.contains_key(hash))
+                            .map(move |hash| (hash, block_hash))
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let mined = local_in(enacted);
+            events.extend(
+                local_in(retracted)
+                    .into_iter()
+                    .filter(|(hash, _)| !mined.iter().any(|(mined, _)| mined == hash))
+                    .map(|(hash, block_hash)| LocalTransactionEvent::Requeued { hash, block_hash }),
+            );
+            events.extend(
+                mined
+                    .into_iter()
+                    .map(|(hash, block_hash)| LocalTransactionEvent::Mined { hash, block_hash }),
+            );
+        }
+        events.extend(
+            self.importer
+                .miner
+                .take_removed_local_transactions()
+                .into_iter()
+                .map(|(hash, reason)| LocalTransactionEvent::Dropped { hash, reason }),
+        );
+
+        if !events.is_empty() {
+            self.notify(|notify| notify.local_transactions(&events));
+        }
+    }
+
     /// Register an action to be done if a mode/spec_name change happens.
     pub fn on_user_defaults_change<F>(&self, f: F)
     where
@@ -1205,35 +1587,38 @@ impl Client {
             } else {
                 None
             },
+            random: header.mix_hash(),
         })
     }
 
     fn build_last_hashes(&self, parent_hash: &H256) -> Arc<LastHashes> {
-        {
-            let hashes = self.last_hashes.read();
-            if hashes.front().map_or(false, |h| h == parent_hash) {
-                let mut res = Vec::from(hashes.clone());
-                res.resize(256, H256::default());
-                return Arc::new(res);
-            }
-        }
-        let mut last_hashes = LastHashes::new();
-        last_hashes.resize(256, H256::default());
-        last_hashes[0] = parent_hash.clone();
+        self.chain.read().last_hashes(parent_hash)
+    }
+
+    /// Hashes of the canon blocks preceding block `number`, as `BLOCKHASH` sees them in a
+    /// block of that number. Blocks past the best block are not known and get a zero hash.
+    fn canon_last_hashes(&self, number: BlockNumber) -> Arc<LastHashes> {
         let chain = self.chain.read();
-        for i in 0..255 {
-            match chain.block_details(&last_hashes[i]) {
-                Some(details) => {
-                    last_hashes[i + 1] = details.parent.clone();
-                }
-                None => break,
-            }
+        let next = chain.best_block_number() + 1;
+        let mut last_hashes =
+            vec![H256::zero(); cmp::min(number.saturating_sub(next), 256) as usize];
+        let parent = cmp::min(number, next).checked_sub(1);
+        if let Some(hash) = parent.and_then(|parent| chain.block_hash(parent)) {
+            last_hashes.extend(chain.last_hashes(&hash).iter().cloned());
         }
-        let mut cached_hashes = self.last_hashes.write();
-        *cached_hashes = VecDeque::from(last_hashes.clone());
+        last_hashes.resize(256, H256::zero());
         Arc::new(last_hashes)
     }
 
+    /// Replace the fields of `env_info` overridden by `options`. An overridden number also
+    /// moves the window of hashes `BLOCKHASH` looks up.
+    fn apply_replay_options(&self, options: &ReplayOptions, env_info: &mut EnvInfo) {
+        options.apply(env_info);
+        if let Some(number) = options.number {
+            env_info.last_hashes = self.canon_last_hashes(number);
+        }
+    }
+
     /// This is triggered by a message coming from a block queue when the block is ready for insertion
     pub fn import_verified_blocks(&self) -> usize {
         self.importer.import_verified_blocks(self)
@@ -1288,6 +1673,13 @@ impl Client {
 						       freeze_at, earliest_era, latest_era, state_db.journal_db().journal_size());
                         break;
                     }
+                    // values pruned from under a pinned state view are kept in memory.
+                    let pinned_size = state_db.journal_db().pinned_size();
+                    if pinned_size >= self.config.history_mem {
+                        trace!(target: "pruning", "Pruning is paused at era {} (pinned state views hold {} bytes) – Not pruning.",
+						       earliest_era, pinned_size);
+                        break;
+                    }
                     trace!(target: "client", "Pruning state for ancient era {}", earliest_era);
                     match chain.block_hash(earliest_era) {
                         Some(ancient_hash) => {
@@ -1308,17 +1700,6 @@ impl Client {
         Ok(())
     }
 
-    // t_nb 9.14 update last hashes. They are build in step 7.5
-    fn update_last_hashes(&self, parent: &H256, hash: &H256) {
-        let mut hashes = self.last_hashes.write();
-        if hashes.front().map_or(false, |h| h == parent) {
-            if hashes.len() > 255 {
-                hashes.pop_back();
-            }
-            hashes.push_front(hash.clone());
-        }
-    }
-
     /// Get shared miner reference.
     #[cfg(test)]
     pub fn miner(&self) -> Arc<Miner> {
@@ -1365,6 +1746,20 @@ impl Client {
         panic!("Couldn't get latest state in 5 tries");
     }
 
+    /// Fork the best block into an in-memory overlay for speculative execution.
+    pub fn overlay(&self) -> OverlayChain {
+        // A plain clone does not read through the canonical account cache, which the
+        // overlay would otherwise shadow with stale entries.
+        let state_db = self.state_db.read().boxed_clone();
+        OverlayChain::new(
+            self.engine.clone(),
+            self.factories.clone(),
+            self.chain.read().clone(),
+            self.best_block_header(),
+            state_db,
+        )
+    }
+
     /// Attempt to get a copy of a specific block's final state.
     ///
     /// This will not fail if given BlockId::Latest.
@@ -1384,7 +1779,7 @@ impl Client {
 
             // early exit for pruned blocks
             if db.is_pruned() && self.pruning_info().earliest_state > block_number {
-                return None;
+                return self.regenerate_state(header.hash());
             }
 
             let root = header.state_root();
@@ -1395,9 +1790,109 @@ impl Client {
                 self.factories.clone(),
             )
             .ok()
+            .or_else(|| self.regenerate_state(header.hash()))
         })
     }
 
+    /// Rebuild the state after the given block by re-executing blocks on top of the
+    /// nearest ancestor whose state is still available.
+    ///
+    /// The regenerated state only lives in the returned `State`'s overlay and is never
+    /// written to the database. Returns `None` if regeneration is disabled, no ancestor
+    /// state is available within `state_regeneration_limit` blocks, or replay fails.
+    fn regenerate_state(&self, hash: H256) -> Option<State<StateDB>> {
+        let limit = self
+            .config
+            .state_regeneration_limit
+            .filter(|limit| *limit > 0)?;
+        let _lock = self.regeneration_lock.lock();
+        let nonce = |number| self.engine.account_start_nonce(number);
+
+        // Walk back by parent hash so that side-chain blocks replay along their own branch.
+        let mut route = vec![self.block_header_decoded(BlockId::Hash(hash))?];
+        let base = loop {
+            let parent_hash = *route
+                .last()
+                .expect("route is never empty; qed")
+                .parent_hash();
+            let parent = self.block_header_decoded(BlockId::Hash(parent_hash))?;
+            let db = self.state_db.read().boxed_clone();
+            if let Ok(state) = State::from_existing(
+                db,
+                *parent.state_root(),
+                nonce(parent.number()),
+                self.factories.clone(),
+            ) {
+                break (parent, state);
+            }
+            if route.len() as u64 >= limit || parent.number() == 0 {
+                debug!(target: "client", "Unable to regenerate state of {}: no ancestor state within {} blocks", hash, limit);
+                return None;
+            }
+            route.push(parent);
+        };
+
+        debug!(target: "client", "Regenerating state of {} by replaying {} blocks from #{}", hash, route.len(), base.0.number());
+        let (mut parent, state) = base;
+        let mut db = state.drop().1;
+        for header in route.into_iter().rev() {
+            let bytes = self.block(BlockId::Hash(header.hash()))?.into_inner();
+            let block =
+                Unverified::from_rlp(bytes, self.engine.params().header_decode_params()).ok()?;
+            let transactions = block
+                .transactions
+                .into_iter()
+                .map(SignedTransaction::new)
+                .collect::<Result<Vec<_>, _>>()
+                .ok()?;
+            let is_epoch_begin = self
+                .chain
+                .read()
+                .epoch_transition(parent.number(), parent.hash())
+                .is_some();
+            let preverified = PreverifiedBlock {
+                header: block.header,
+                transactions,
+                uncles: block.uncles,
+                withdrawals: block.withdrawals,
+                bytes: block.bytes,
+            };
+
+            let enacted = enact_verified(
+                preverified,
+                &*self.engine,
+                false,
+                db,
+                &parent,
+                self.build_last_hashes(&parent.hash()),
+                self.factories.clone(),
+                is_epoch_begin,
+                &mut self.chain.read().ancestry_with_metadata_iter(parent.hash()),
+            );
+            let executed = match enacted {
+                Ok(locked) => locked.drain(),
+                Err(e) => {
+                    debug!(target: "client", "Unable to regenerate state of {}: replaying #{} failed: {}", hash, header.number(), e);
+                    return None;
+                }
+            };
+            if executed.state.root() != header.state_root() {
+                warn!(target: "client", "Regenerated state of #{} ({}) does not match its state root", header.number(), header.hash());
+                return None;
+            }
+            db = executed.state.drop().1;
+            parent = header;
+        }
+
+        State::from_existing(
+            db,
+            *parent.state_root(),
+            nonce(parent.number()),
+            self.factories.clone(),
+        )
+        .ok()
+    }
+
     /// Attempt to get a copy of a specific block's beginning state.
     ///
     /// This will not fail if given BlockId::Latest.
@@ -1410,6 +1905,102 @@ impl Client {
         }
     }
 
+    /// Stream every account of the state at `id` to `out` as a JSON object usable as the
+    /// `accounts` section of a chain spec. Returns the number of exported accounts.
+    ///
+    /// Addresses and storage keys are only recoverable from trie preimages, so this
+    /// requires a fat DB.
+    pub fn export_state<'a>(
+        &self,
+        id: BlockId,
+        mut out: Box<dyn Write + 'a>,
+        options: &ExportStateOptions,
+    ) -> Result<usize, String> {
+        if !self.factories.trie.is_fat() {
+            return Err("Exporting state requires a fat DB (--fat-db=on).".into());
+        }
+        let state = self
+            .state_at(id)
+            .ok_or("State of the specified block is not available")?;
+        let (root, state_db) = state.drop();
+        let db = &state_db.as_hash_db();
+        let trie = self
+            .factories
+            .trie
+            .readonly(db, &root)
+            .map_err(|e| format!("Couldn't open the state trie: {}", e))?;
+        let write_err = |e: ::std::io::Error| format!("Couldn't write to stream. Cause: {}", e);
+        let trie_err = |e| format!("State trie error: {}", e);
+
+        out.write_all(b"{").map_err(write_err)?;
+        let mut exported = 0usize;
+        for item in trie.iter().map_err(trie_err)? {
+            let (key, value) = item.map_err(trie_err)?;
+            let address = Address::from_slice(&key);
+            let account: ::types::basic_account::BasicAccount = ::rlp::decode(&value)
+                .map_err(|e| format!("Invalid account {:?}: {}", address, e))?;
+            if options.min_balance.map_or(false, |m| account.balance < m)
+                || options.max_balance.map_or(false, |m| account.balance > m)
+            {
+                continue;
+            }
+
+            if exported != 0 {
+                out.write_all(b",").map_err(write_err)?;
+            }
+            write!(
+                out,
+                "\n\"0x{:x}\": {{\"balance\": \"0x{:x}\", \"nonce\": \"0x{:x}\"",
+                address, account.balance, account.nonce
+            )
+            .map_err(write_err)?;
+
+            let account_db = self
+                .factories
+                .accountdb
+                .readonly(state_db.as_hash_db(), keccak(&address));
+            let account_db = &account_db.as_hash_db();
+            if options.code && account.code_hash != KECCAK_EMPTY {
+                let code = account_db
+                    .get(&account.code_hash)
+                    .ok_or_else(|| format!("Missing code of account {:?}", address))?;
+                write!(out, ", \"code\": \"0x{}\"", code.to_hex()).map_err(write_err)?;
+            }
+            if options.storage && account.storage_root != KECCAK_NULL_RLP {
+                let storage = self
+                    .factories
+                    .trie
+                    .readonly(account_db, &account.storage_root)
+                    .map_err(trie_err)?;
+                out.write_all(b", \"storage\": {").map_err(write_err)?;
+                for (i, item) in storage.iter().map_err(trie_err)?.enumerate() {
+                    let (key, value) = item.map_err(trie_err)?;
+                    let value: U256 = ::rlp::decode(&value)
+                        .map_err(|e| format!("Invalid storage of account {:?}: {}", address, e))?;
+                    if i != 0 {
+                        out.write_all(b",").map_err(write_err)?;
+                    }
+                    write!(
+                        out,
+                        "\n\t\"0x{:x}\": \"0x{:x}\"",
+                        H256::from_slice(&key),
+                        value
+                    )
+                    .map_err(write_err)?;
+                }
+                out.write_all(b"\n}").map_err(write_err)?;
+            }
+            out.write_all(b"}").map_err(write_err)?;
+
+            exported += 1;
+            if exported % 10000 == 0 {
+                info!("Exported {} accounts", exported);
+            }
+        }
+        out.write_all(b"\n}\n").map_err(write_err)?;
+        Ok(exported)
+    }
+
     /// Get a copy of the best block's state.
     pub fn state(&self) -> impl StateInfo {
         let (state, _) = self.latest_state_and_header();
@@ -1432,11 +2023,123 @@ impl Client {
     // TODO: manage by real events.
     pub fn tick(&self, prevent_sleep: bool) {
         self.check_garbage();
+        if !self.config.read_only {
+            self.expire_history();
+            self.expire_transaction_index();
+            self.release_quarantined_blocks();
+            self.flush_bad_blocks();
+        }
         if !prevent_sleep {
             self.check_snooze();
         }
     }
 
+    /// Check the seal and author of a block with a future timestamp, which stage 1
+    /// verification stopped at. Only blocks passing this are worth keeping in quarantine.
+    /// Returns `Ok(false)` if the parent isn't in the chain yet, so the author can't be checked.
+    fn verify_quarantine_candidate(&self, block: &Unverified) -> EthcoreResult<bool> {
+        let parent = match self.block_header_decoded(BlockId::Hash(*block.header.parent_hash())) {
+            Some(parent) => parent,
+            None => return Ok(false),
+        };
+        self.engine.verify_block_unordered(&block.header)?;
+        self.engine.verify_block_family(&block.header, &parent)?;
+        self.engine.verify_block_external(&block.header)?;
+        Ok(true)
+    }
+
+    /// Re-import quarantined blocks whose timestamp the local clock has caught up with.
+    fn release_quarantined_blocks(&self) {
+        let quarantine = &self.importer.timestamp_quarantine;
+        if quarantine.is_empty() {
+            return;
+        }
+
+        let machine = self.engine.machine();
+        let now = ::machine::Machine::clock(machine).current().unix_now();
+        for block in quarantine.release(now, machine.max_future_drift()) {
+            let hash = block.hash();
+            match self.import_block(block) {
+                Ok(_) => {
+                    debug!(target: "client", "Re-imported block {} from timestamp quarantine", hash)
+                }
+                Err(e) => {
+                    debug!(target: "client", "Block {} released from timestamp quarantine rejected: {}", hash, e)
+                }
+            }
+        }
+    }
+
+    /// Delete bodies and receipts of blocks which fell out of the configured history window.
+    /// Never expires blocks whose state may still be needed for a reorganization.
+    fn expire_history(&self) {
+        let keep = match self.config.history_expiry {
+            Some(keep) => cmp::max(keep, self.history),
+            None => return,
+        };
+        // skipped while a backup is running.
+        let _writers = match self.db_writers.try_read() {
+            Some(writers) => writers,
+            None => return,
+        };
+        let chain = self.chain.read();
+        let up_to = chain.best_block_number().saturating_sub(keep);
+        // also called once the window is reached, to expire blocks added by ancient sync.
+        let earliest = chain.expire_history(up_to, HISTORY_EXPIRY_BATCH);
+        trace!(target: "client", "Expired history up to block {}", earliest);
+    }
+
+    /// Delete transaction index entries of blocks which fell out of the configured window.
+    fn expire_transaction_index(&self) {
+        let keep = match self.config.tx_index_expiry {
+            Some(keep) => cmp::max(keep, self.history),
+            None => return,
+        };
+        let _writers = match self.db_writers.try_read() {
+            Some(writers) => writers,
+            None => return,
+        };
+        let chain = self.chain.read();
+        let up_to = chain.best_block_number().saturating_sub(keep);
+        if up_to > chain.earliest_transaction_index() {
+            let earliest = chain.expire_transaction_index(up_to, HISTORY_EXPIRY_BATCH);
+            debug!(target: "client", "Expired transaction index up to block {}", earliest);
+        }
+    }
+
+    /// Compress the bodies and receipts that were stored before compression was enabled, a
+    /// small batch at a time, until `stop` is dropped. Runs on its own thread.
+    fn recompress_history(&self, stop: crossbeam_channel::Receiver<()>) {
+        let mut next = None;
+        loop {
+            let recompressed = {
+                let _writers = self.db_writers.read();
+                self.chain.read().recompress_history(RECOMPRESS_BATCH)
+            };
+            let pause = if next == Some(recompressed) {
+                RECOMPRESS_IDLE
+            } else {
+                trace!(target: "client", "Recompressed history up to block {}", recompressed);
+                RECOMPRESS_PAUSE
+            };
+            next = Some(recompressed);
+            match stop.recv_timeout(pause) {
+                Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
+                _ => return,
+            }
+        }
+    }
+
+    fn flush_bad_blocks(&self) {
+        let _writers = match self.db_writers.try_read() {
+            Some(writers) => writers,
+            None => return,
+        };
+        self.importer
+            .bad_blocks
+            .flush(&**self.db.read().key_value());
+    }
+
     fn check_garbage(&self) {
         self.chain.read().collect_garbage();
         self.importer.block_queue.collect_garbage();
@@ -1486,6 +2189,9 @@ impl Client {
         p: &snapshot::Progress,
     ) -> Result<(), EthcoreError> {
         let db = self.state_db.read().journal_db().boxed_clone();
+        // Pin the state before checking what's available, so that pruning can't
+        // race past the starting block in between.
+        let pinned = db.pinned_view();
         let block_number = self
             .block_number(at)
             .ok_or_else(|| snapshot::Error::InvalidStartingBlock(at))?;
@@ -1520,6 +2226,23 @@ impl Client {
             .engine
             .snapshot_components()
             .ok_or(snapshot::Error::SnapshotsUnsupported)?;
+
+        // Prefer a view which survives pruning; only hold pruning back if the
+        // journal can't provide one.
+        if let Some(view) = pinned {
+            info!(target: "snapshot", "Taking snapshot from a pinned state view, pruning continues.");
+            snapshot::take_snapshot(
+                chunker,
+                &self.chain.read(),
+                start_hash,
+                &*view,
+                writer,
+                p,
+                processing_threads,
+            )?;
+            return Ok(());
+        }
+
         self.snapshotting_at
             .store(snapshot_block_number, AtomicOrdering::SeqCst);
         {
@@ -1611,12 +2334,170 @@ impl Client {
         .fake_sign(from)
     }
 
-    fn do_virtual_call(
+    /// Deadline of a virtual call started now. The requested timeout can only shorten the
+    /// configured one.
+    fn call_deadline(&self, requested: Option<Duration>) -> Option<Instant> {
+        let timeout = match (self.config.call_timeout, requested) {
+            (Some(limit), Some(requested)) => Some(cmp::min(limit, requested)),
+            (limit, requested) => limit.or(requested),
+        };
+        timeout.map(|timeout| Instant::now() + timeout)
+    }
+
+    /// Deadline of a replay started now. Unlike calls, replays only time out if the caller
+    /// asks for it.
+    fn replay_deadline(&self, requested: Option<Duration>) -> Option<Instant> {
+        requested.and_then(|_| self.call_deadline(requested))
+    }
+
+    /// Replay the first `count` transactions of a block, or all of them, under a modified
+    /// block environment. Unlike a plain replay, transactions may fail to execute there,
+    /// so they are run eagerly and the first failure is returned.
+    fn replay_with_options(
+        &self,
+        block: BlockId,
+        analytics: CallAnalytics,
+        options: ReplayOptions,
+        count: Option<usize>,
+    ) -> Result<Vec<(H256, Executed)>, CallError> {
+        let mut env_info = self.env_info(block).ok_or(CallError::StatePruned)?;
+        self.apply_replay_options(&options, &mut env_info);
+        let body = self.block_body(block).ok_or(CallError::StatePruned)?;
+        let mut state = self
+            .state_at_beginning(block)
+            .ok_or(CallError::StatePruned)?;
+        let machine = self.engine.machine();
+
+        const PROOF: &'static str =
+            "Transactions fetched from blockchain; blockchain transactions are valid; qed";
+
+        let deadline = self.replay_deadline(analytics.timeout);
+        let txs = body.transactions();
+        let count = count.unwrap_or(txs.len());
+        let mut results = Vec::with_capacity(count);
+        for t in txs.into_iter().take(count) {
+            let transaction_hash = t.hash();
+            let t = SignedTransaction::new(t).expect(PROOF);
+            let x = Self::do_virtual_call(machine, &env_info, &mut state, &t, analytics, deadline)?;
+            env_info.gas_used = env_info.gas_used + x.gas_used;
+            results.push((transaction_hash, x));
+        }
+        Ok(results)
+    }
+
+    /// Copy `state` and apply the call overrides to it.
+    fn overridden_state(
+        state: &State<StateDB>,
+        overrides: &StateOverride,
+    ) -> Result<State<StateDB>, CallError> {
+        let mut state = state.clone();
+        Self::apply_overrides(&mut state, overrides)?;
+        Ok(state)
+    }
+
+    fn apply_overrides(
+        state: &mut State<StateDB>,
+        overrides: &StateOverride,
+    ) -> Result<(), CallError> {
+        state.apply_overrides(overrides).map_err(|e| {
+            warn!(target: "client", "Failed to apply call state overrides: {}", e);
+            CallError::StateCorrupt
+        })
+    }
+
+    /// Makes dependent calls in the environment of `header` replaced by `options`,
+    /// applying the overrides given with a call right before it.
+    fn do_call_many<'a, I>(
+        &self,
+        transactions: I,
+        state: &mut State<StateDB>,
+        header: &Header,
+        options: ReplayOptions,
+    ) -> Result<Vec<Executed>, CallError>
+    where
+        I: ExactSizeIterator<
+            Item = (
+                &'a SignedTransaction,
+                CallAnalytics,
+                Option<&'a StateOverride>,
+            ),
+        >,
+    {
+        let mut env_info = EnvInfo {
+            number: header.number(),
+            author: header.author().clone(),
+            timestamp: header.timestamp(),
+            difficulty: header.difficulty().clone(),
+            last_hashes: self.build_last_hashes(header.parent_hash()),
+            gas_used: U256::default(),
+            gas_limit: U256::max_value(),
+            base_fee: header.base_fee(),
+            random: header.mix_hash(),
+        };
+        self.apply_replay_options(&options, &mut env_info);
+        let base_fee = env_info.base_fee;
+
+        let mut results = Vec::with_capacity(transactions.len());
+        let machine = self.engine.machine();
+
+        for (t, analytics, overrides) in transactions {
+            if let Some(overrides) = overrides {
+                Self::apply_overrides(state, overrides)?;
+            }
+
+            //if gas pricing is not defined, force base_fee to zero
+            if t.effective_gas_price(base_fee).is_zero() {
+                env_info.base_fee = Some(0.into());
+            } else {
+                env_info.base_fee = base_fee
+            }
+
+            let deadline = self.call_deadline(analytics.timeout);
+            let ret = Self::do_virtual_call(machine, &env_info, state, t, analytics, deadline)?;
+            env_info.gas_used = ret.cumulative_gas_used;
+            results.push(ret);
+        }
+
+        Ok(results)
+    }
+
+    /// Makes a non-persistent transaction call interrupted at `deadline`.
+    fn call_with_deadline(
+        &self,
+        transaction: &SignedTransaction,
+        analytics: CallAnalytics,
+        state: &mut State<StateDB>,
+        header: &Header,
+        deadline: Option<Instant>,
+    ) -> Result<Executed, CallError> {
+        let env_info = EnvInfo {
+            number: header.number(),
+            author: header.author().clone(),
+            timestamp: header.timestamp(),
+            difficulty: header.difficulty().clone(),
+            last_hashes: self.build_last_hashes(header.parent_hash()),
+            gas_used: U256::default(),
+            gas_limit: U256::max_value(),
+            //if gas pricing is not defined, force base_fee to zero
+            base_fee: if transaction.effective_gas_price(header.base_fee()).is_zero() {
+                Some(0.into())
+            } else {
+                header.base_fee()
+            },
+            random: header.mix_hash(),
+        };
+        let machine = self.engine.machine();
+
+        Self::do_virtual_call(&machine, &env_info, state, transaction, analytics, deadline)
+    }
+
+    pub(crate) fn do_virtual_call(
         machine: &::machine::EthereumMachine,
         env_info: &EnvInfo,
         state: &mut State<StateDB>,
         t: &SignedTransaction,
         analytics: CallAnalytics,
+        deadline: Option<Instant>,
     ) -> Result<Executed, CallError> {
         fn call<V, T>(
             state: &mut State<StateDB>,
@@ -1625,21 +2506,28 @@ impl Client {
             state_diff: bool,
             transaction: &SignedTransaction,
             options: TransactOptions<T, V>,
+            deadline: Option<Instant>,
         ) -> Result<Executed<T::Output, V::Output>, CallError>
         where
             T: trace::Tracer,
             V: trace::VMTracer,
         {
-            let options = options.dont_check_nonce().save_output_from_contract();
+            let options = options
+                .dont_check_nonce()
+                .save_output_from_contract()
+                .with_deadline(deadline);
             let original_state = if state_diff {
                 Some(state.clone())
             } else {
                 None
             };
-            let schedule = machine.schedule(env_info.number);
+            let schedule = machine.schedule(env_info.number, env_info.timestamp);
 
             let mut ret = Executive::new(state, env_info, &machine, &schedule)
                 .transact_virtual(transaction, options)?;
+            if ret.exception == Some(vm::Error::ExecutionTimeout) {
+                return Err(CallError::Timeout);
+            }
 
             if let Some(original) = original_state {
                 ret.state_diff = Some(state.diff_from(original).map_err(ExecutionError::from)?);
@@ -1657,6 +2545,7 @@ impl Client {
                 state_diff,
                 t,
                 TransactOptions::with_tracing_and_vm_tracing(),
+                deadline,
             ),
             (true, false) => call(
                 state,
@@ -1665,6 +2554,7 @@ impl Client {
                 state_diff,
                 t,
                 TransactOptions::with_tracing(),
+                deadline,
             ),
             (false, true) => call(
                 state,
@@ -1673,6 +2563,7 @@ impl Client {
                 state_diff,
                 t,
                 TransactOptions::with_vm_tracing(),
+                deadline,
             ),
             (false, false) => call(
                 state,
@@ -1681,6 +2572,7 @@ impl Client {
                 state_diff,
                 t,
                 TransactOptions::with_no_tracing(),
+                deadline,
             ),
         }
     }
@@ -1709,7 +2601,7 @@ impl Client {
             }
             _ => self
                 .block_header(id)
-                .and_then(|h| h.decode(self.engine.params().eip1559_transition).ok()),
+                .and_then(|h| h.decode(self.engine.params().header_decode_params()).ok()),
         }
     }
 }
@@ -1736,7 +2628,7 @@ impl snapshot::DatabaseRestore for Client {
             self.config.blockchain.clone(),
             &[],
             db.clone(),
-            self.engine.params().eip1559_transition,
+            self.engine.params().header_decode_params(),
         ));
         *tracedb = TraceDB::new(self.config.tracing.clone(), db.clone(), chain.clone());
         Ok(())
@@ -1751,64 +2643,142 @@ impl BlockChainReset for Client {
             return Err("invalid number of blocks to reset".into());
         }
 
-        let mut blocks_to_delete = Vec::with_capacity(num as usize);
-        let mut best_block_hash = self.chain.read().best_block_hash();
-        let mut batch = DBTransaction::with_capacity(blocks_to_delete.len());
+        let best_block_number = self.chain.read().best_block_number();
+        let target = best_block_number
+            .checked_sub(num as u64)
+            .ok_or_else(|| "Attempting to reset past the genesis block".to_owned())?;
 
-        for _ in 0..num {
-            let current_header = self
-                .chain
-                .read()
-                .block_header_data(&best_block_hash)
-                .expect(
-                "best_block_hash was fetched from db; block_header_data should exist in db; qed",
-            );
-            best_block_hash = current_header.parent_hash();
+        self.reset_to(BlockId::Number(target))
+    }
 
-            let (number, hash) = (current_header.number(), current_header.hash());
+    fn reset_to(&self, target: BlockId) -> Result<(), String> {
+        if self.config.read_only {
+            return Err("Cannot reset a read-only client".into());
+        }
+        let chain = self.chain.read();
+        let target_hash = Self::block_hash(&chain, target)
+            .ok_or_else(|| format!("Unknown reset target {:?}", target))?;
+        let target_header = chain
+            .block_header_data(&target_hash)
+            .ok_or_else(|| format!("Unknown reset target {:?}", target))?;
+        let best_block_hash = chain.best_block_hash();
+        if target_hash == best_block_hash {
+            return Err("Reset target is already the best block".into());
+        }
+
+        let state_db = self.state_db.read().boxed_clone();
+        if State::from_existing(
+            state_db,
+            target_header.state_root(),
+            self.engine.account_start_nonce(target_header.number()),
+            self.factories.clone(),
+        )
+        .is_err()
+        {
+            return Err("Attempting to reset to block with pruned state".into());
+        }
+
+        let route = chain
+            .tree_route(best_block_hash, target_hash)
+            .ok_or_else(|| {
+                "Reset target does not share an ancestor with the best block".to_owned()
+            })?;
+        let (retracted, enacted) = route.blocks.split_at(route.index);
+
+        let mut batch = DBTransaction::with_capacity(route.blocks.len() * 4);
+
+        // drop the retracted part of the canon chain entirely so it can be re-imported
+        for hash in retracted {
+            let number = chain
+                .block_number(hash)
+                .expect("retracted block is in the tree route; block details should exist; qed");
+            if let Some(body) = chain.block_body(hash) {
+                for tx_hash in body.transaction_hashes() {
+                    Writable::delete::<TransactionAddress, H264>(
+                        &mut batch,
+                        ::db::COL_EXTRA,
+                        &tx_hash,
+                    );
+                }
+            }
             batch.delete(::db::COL_HEADERS, hash.as_bytes());
             batch.delete(::db::COL_BODIES, hash.as_bytes());
-            Writable::delete::<BlockDetails, H264>(&mut batch, ::db::COL_EXTRA, &hash);
+            Writable::delete::<BlockDetails, H264>(&mut batch, ::db::COL_EXTRA, hash);
             Writable::delete::<H256, BlockNumberKey>(&mut batch, ::db::COL_EXTRA, &number);
+        }
 
-            blocks_to_delete.push((number, hash));
+        if !retracted.is_empty() {
+            info!(
+                "Deleting block hashes {}",
+                Colour::Red.bold().paint(format!("{:#?}", retracted))
+            );
         }
 
-        let hashes = blocks_to_delete
-            .iter()
-            .map(|(_, hash)| hash)
-            .collect::<Vec<_>>();
-        info!(
-            "Deleting block hashes {}",
-            Colour::Red.bold().paint(format!("{:#?}", hashes))
-        );
+        // remove the lowest retracted block as a child so that it can be re-imported
+        // ethcore/blockchain/src/blockchain.rs/Blockchain::is_known_child()
+        if let Some(lowest_retracted) = retracted.last() {
+            let mut ancestor_details = Readable::read::<BlockDetails, H264>(
+                &**self.db.read().key_value(),
+                ::db::COL_EXTRA,
+                &route.ancestor,
+            )
+            .expect("ancestor is in the tree route; block details should exist; qed");
+            ancestor_details
+                .children
+                .retain(|h| *h != *lowest_retracted);
+            batch.write(::db::COL_EXTRA, &route.ancestor, &ancestor_details);
+        }
 
-        let mut best_block_details = Readable::read::<BlockDetails, H264>(
-            &**self.db.read().key_value(),
-            ::db::COL_EXTRA,
-            &best_block_hash,
-        )
-        .expect("block was previously imported; best_block_details should exist; qed");
+        // a side chain target becomes canon: rebuild the number and transaction markers
+        let mut blooms = Vec::with_capacity(enacted.len());
+        for hash in enacted {
+            let header = chain
+                .block_header_data(hash)
+                .expect("enacted block is in the tree route; header should exist; qed");
+            let body = chain
+                .block_body(hash)
+                .expect("enacted block is in the tree route; body should exist; qed");
+            batch.write(::db::COL_EXTRA, &header.number(), hash);
+            for (index, tx_hash) in body.transaction_hashes().into_iter().enumerate() {
+                batch.write(
+                    ::db::COL_EXTRA,
+                    &tx_hash,
+                    &TransactionAddress {
+                        block_hash: *hash,
+                        index,
+                    },
+                );
+            }
+            blooms.push(header.log_bloom());
+        }
+
+        if !enacted.is_empty() {
+            info!(
+                "Moving side chain block hashes to canon chain {}",
+                Colour::Yellow.bold().paint(format!("{:#?}", enacted))
+            );
+        }
 
-        let (_, last_hash) = blocks_to_delete
-            .last()
-            .expect("num is > 0; blocks_to_delete can't be empty; qed");
-        // remove the last block as a child so that it can be re-imported
-        // ethcore/blockchain/src/blockchain.rs/Blockchain::is_known_child()
-        best_block_details.children.retain(|h| *h != *last_hash);
-        batch.write(::db::COL_EXTRA, &best_block_hash, &best_block_details);
         // update the new best block hash
-        batch.put(::db::COL_EXTRA, b"best", best_block_hash.as_bytes());
+        batch.put(::db::COL_EXTRA, b"best", target_hash.as_bytes());
 
-        self.db
-            .read()
-            .key_value()
+        let db = self.db.read();
+        db.key_value()
             .write(batch)
-            .map_err(|err| format!("could not delete blocks; io error occurred: {}", err))?;
+            .map_err(|err| format!("could not reset blocks; io error occurred: {}", err))?;
+        if !blooms.is_empty() {
+            let first_enacted = chain
+                .block_number(&route.ancestor)
+                .expect("ancestor is in the tree route; block details should exist; qed")
+                + 1;
+            db.blooms()
+                .insert_blooms(first_enacted, blooms.iter())
+                .map_err(|err| format!("could not update blooms; io error occurred: {}", err))?;
+        }
 
         info!(
             "New best block hash {}",
-            Colour::Green.bold().paint(format!("{:?}", best_block_hash))
+            Colour::Green.bold().paint(format!("{:?}", target_hash))
         );
 
         Ok(())
@@ -1905,7 +2875,8 @@ impl CallContract for Client {
 
         let transaction = self.contract_call_tx(block_id, address, data);
 
-        self.call(&transaction, Default::default(), state, &header)
+        // Contract calls made on behalf of the engine must not depend on wall-clock time.
+        self.call_with_deadline(&transaction, Default::default(), state, &header, None)
             .map_err(|e| format!("{:?}", e))
             .map(|executed| executed.output)
     }
@@ -1914,6 +2885,10 @@ impl CallContract for Client {
 impl ImportBlock for Client {
     // t_nb 2.0 import block to client
     fn import_block(&self, unverified: Unverified) -> EthcoreResult<H256> {
+        if self.config.read_only {
+            bail!(EthcoreErrorKind::Import(ImportErrorKind::ReadOnly));
+        }
+
         // t_nb 2.1 check if header hash is known to us.
         if self.chain.read().is_known(&unverified.hash()) {
             bail!(EthcoreErrorKind::Import(ImportErrorKind::AlreadyInChain));
@@ -1946,13 +2921,33 @@ impl ImportBlock for Client {
                 }
                 Ok(hash)
             }
+            // Blocks slightly ahead of the local clock are held back instead of being reported
+            // as bad, they are re-evaluated once the clock catches up. Only blocks sealed by a
+            // valid author are kept, anyone else could fill the quarantine for free.
+            Err((
+                Some(block),
+                EthcoreError(EthcoreErrorKind::Block(BlockError::TemporarilyInvalid(oob)), _),
+            )) => {
+                let hash = block.hash();
+                match self.verify_quarantine_candidate(&block) {
+                    Ok(true) => {
+                        if self.importer.timestamp_quarantine.insert(block) {
+                            debug!(target: "client", "Quarantined block {} with a future timestamp: {:?}", hash, oob);
+                        }
+                    }
+                    Ok(false) => {
+                        debug!(target: "client", "Not quarantining block {} with a future timestamp, its parent is not imported yet", hash);
+                    }
+                    Err(e) => {
+                        self.importer.report_bad_block(block.bytes, e.to_string());
+                        return Err(e);
+                    }
+                }
+                bail!(EthcoreErrorKind::Block(BlockError::TemporarilyInvalid(oob)))
+            }
             // t_nb 2.5 if block is not okay print error. we only care about block errors (not import errors)
             Err((Some(block), EthcoreError(EthcoreErrorKind::Block(err), _))) => {
-                self.importer.bad_blocks.report(
-                    block.bytes,
-                    err.to_string(),
-                    self.engine.params().eip1559_transition,
-                );
+                self.importer.report_bad_block(block.bytes, err.to_string());
                 bail!(EthcoreErrorKind::Block(err))
             }
             Err((None, EthcoreError(EthcoreErrorKind::Block(err), _))) => {
@@ -1991,25 +2986,17 @@ impl Call for Client {
         analytics: CallAnalytics,
         state: &mut Self::State,
         header: &Header,
+        overrides: &StateOverride,
     ) -> Result<Executed, CallError> {
-        let env_info = EnvInfo {
-            number: header.number(),
-            author: header.author().clone(),
-            timestamp: header.timestamp(),
-            difficulty: header.difficulty().clone(),
-            last_hashes: self.build_last_hashes(header.parent_hash()),
-            gas_used: U256::default(),
-            gas_limit: U256::max_value(),
-            //if gas pricing is not defined, force base_fee to zero
-            base_fee: if transaction.effective_gas_price(header.base_fee()).is_zero() {
-                Some(0.into())
-            } else {
-                header.base_fee()
-            },
+        let mut overridden;
+        let state = if overrides.is_empty() {
+            state
+        } else {
+            overridden = Self::overridden_state(state, overrides)?;
+            &mut overridden
         };
-        let machine = self.engine.machine();
-
-        Self::do_virtual_call(&machine, &env_info, state, transaction, analytics)
+        let deadline = self.call_deadline(analytics.timeout);
+        self.call_with_deadline(transaction, analytics, state, header, deadline)
     }
 
     fn call_many(
@@ -2017,35 +3004,40 @@ impl Call for Client {
         transactions: &[(SignedTransaction, CallAnalytics)],
         state: &mut Self::State,
         header: &Header,
+        overrides: &StateOverride,
     ) -> Result<Vec<Executed>, CallError> {
-        let mut env_info = EnvInfo {
-            number: header.number(),
-            author: header.author().clone(),
-            timestamp: header.timestamp(),
-            difficulty: header.difficulty().clone(),
-            last_hashes: self.build_last_hashes(header.parent_hash()),
-            gas_used: U256::default(),
-            gas_limit: U256::max_value(),
-            base_fee: header.base_fee(),
+        let mut overridden;
+        let state = if overrides.is_empty() {
+            state
+        } else {
+            overridden = Self::overridden_state(state, overrides)?;
+            &mut overridden
         };
+        self.do_call_many(
+            transactions
+                .iter()
+                .map(|&(ref t, analytics)| (t, analytics, None)),
+            state,
+            header,
+            ReplayOptions::default(),
+        )
+    }
 
-        let mut results = Vec::with_capacity(transactions.len());
-        let machine = self.engine.machine();
-
-        for &(ref t, analytics) in transactions {
-            //if gas pricing is not defined, force base_fee to zero
-            if t.effective_gas_price(header.base_fee()).is_zero() {
-                env_info.base_fee = Some(0.into());
-            } else {
-                env_info.base_fee = header.base_fee()
-            }
-
-            let ret = Self::do_virtual_call(machine, &env_info, state, t, analytics)?;
-            env_info.gas_used = ret.cumulative_gas_used;
-            results.push(ret);
-        }
-
-        Ok(results)
+    fn call_many_with_overrides(
+        &self,
+        transactions: &[(SignedTransaction, CallAnalytics, StateOverride)],
+        state: &mut Self::State,
+        header: &Header,
+        options: ReplayOptions,
+    ) -> Result<Vec<Executed>, CallError> {
+        self.do_call_many(
+            transactions
+                .iter()
+                .map(|&(ref t, analytics, ref overrides)| (t, analytics, Some(overrides))),
+            state,
+            header,
+            options,
+        )
     }
 
     fn estimate_gas(
@@ -2053,6 +3045,7 @@ impl Call for Client {
         t: &SignedTransaction,
         state: &Self::State,
         header: &Header,
+        timeout: Option<Duration>,
     ) -> Result<U256, CallError> {
         let (mut upper, max_upper, env_info) = {
             let init = *header.gas_limit();
@@ -2071,13 +3064,20 @@ impl Call for Client {
                 } else {
                     header.base_fee()
                 },
+                random: header.mix_hash(),
             };
 
             (init, max, env_info)
         };
 
         let sender = t.sender();
-        let options = || TransactOptions::with_tracing().dont_check_nonce();
+        // A single deadline covers every execution of the search.
+        let deadline = self.call_deadline(timeout);
+        let options = || {
+            TransactOptions::with_tracing()
+                .dont_check_nonce()
+                .with_deadline(deadline)
+        };
 
         let exec = |gas| {
             let mut tx = t.as_unsigned().clone();
@@ -2086,17 +3086,26 @@ impl Call for Client {
 
             let mut clone = state.clone();
             let machine = self.engine.machine();
-            let schedule = machine.schedule(env_info.number);
+            let schedule = machine.schedule(env_info.number, env_info.timestamp);
             Executive::new(&mut clone, &env_info, &machine, &schedule)
                 .transact_virtual(&tx, options())
         };
 
-        let cond = |gas| exec(gas).ok().map_or(false, |r| r.exception.is_none());
+        let cond = |gas| match exec(gas) {
+            Ok(ref r) if r.exception == Some(vm::Error::ExecutionTimeout) => {
+                Err(CallError::Timeout)
+            }
+            Ok(r) => Ok(r.exception.is_none()),
+            Err(_) => Ok(false),
+        };
 
-        if !cond(upper) {
+        if !cond(upper)? {
             upper = max_upper;
             match exec(upper) {
                 Ok(v) => {
+                    if v.exception == Some(vm::Error::ExecutionTimeout) {
+                        return Err(CallError::Timeout);
+                    }
                     if let Some(exception) = v.exception {
                         return Err(CallError::Exceptional(exception));
                     }
@@ -2113,9 +3122,9 @@ impl Call for Client {
         }
         let lower = t
             .tx()
-            .gas_required(&self.engine.schedule(env_info.number))
+            .gas_required(&self.engine.schedule(env_info.number, env_info.timestamp))
             .into();
-        if cond(lower) {
+        if cond(lower)? {
             trace!(target: "estimate_gas", "estimate_gas succeeded with {}", lower);
             return Ok(lower);
         }
@@ -2125,12 +3134,12 @@ impl Call for Client {
         /// We assert: `cond(lower) = false`, `cond(upper) = true`
         fn binary_chop<F, E>(mut lower: U256, mut upper: U256, mut cond: F) -> Result<U256, E>
         where
-            F: FnMut(U256) -> bool,
+            F: FnMut(U256) -> Result<bool, E>,
         {
             while upper - lower > 1.into() {
                 let mid = (lower + upper) / 2;
                 trace!(target: "estimate_gas", "{} .. {} .. {}", lower, mid, upper);
-                let c = cond(mid);
+                let c = cond(mid)?;
                 match c {
                     true => upper = mid,
                     false => lower = mid,
@@ -2153,15 +3162,21 @@ impl EngineInfo for Client {
 }
 
 impl BadBlocks for Client {
-    fn bad_blocks(&self) -> Vec<(Unverified, String)> {
-        self.importer
-            .bad_blocks
-            .bad_blocks(self.engine.params().eip1559_transition)
+    fn bad_blocks(&self) -> Vec<BadBlock> {
+        self.importer.bad_blocks.bad_blocks(
+            &**self.db.read().key_value(),
+            self.engine.params().header_decode_params(),
+        )
     }
 }
 
 impl BlockChainClient for Client {
-    fn replay(&self, id: TransactionId, analytics: CallAnalytics) -> Result<Executed, CallError> {
+    fn replay(
+        &self,
+        id: TransactionId,
+        analytics: CallAnalytics,
+        options: ReplayOptions,
+    ) -> Result<Executed, CallError> {
         let address = self
             .transaction_address(id)
             .ok_or(CallError::TransactionNotFound)?;
@@ -2169,9 +3184,17 @@ impl BlockChainClient for Client {
 
         const PROOF: &'static str =
             "The transaction address contains a valid index within block; qed";
+        // a replay which may time out has to run eagerly to return the error.
+        if options == ReplayOptions::default() && analytics.timeout.is_none() {
+            return Ok(self
+                .replay_block_transactions(block, analytics, options)?
+                .nth(address.index)
+                .expect(PROOF)
+                .1);
+        }
         Ok(self
-            .replay_block_transactions(block, analytics)?
-            .nth(address.index)
+            .replay_with_options(block, analytics, options, Some(address.index + 1))?
+            .pop()
             .expect(PROOF)
             .1)
     }
@@ -2180,7 +3203,13 @@ impl BlockChainClient for Client {
         &self,
         block: BlockId,
         analytics: CallAnalytics,
+        options: ReplayOptions,
     ) -> Result<Box<dyn Iterator<Item = (H256, Executed)>>, CallError> {
+        if options != ReplayOptions::default() {
+            let results = self.replay_with_options(block, analytics, options, None)?;
+            return Ok(Box::new(results.into_iter()));
+        }
+
         let mut env_info = self.env_info(block).ok_or(CallError::StatePruned)?;
         let body = self.block_body(block).ok_or(CallError::StatePruned)?;
         let mut state = self
@@ -2197,19 +3226,95 @@ impl BlockChainClient for Client {
             let transaction_hash = t.hash();
             let t = SignedTransaction::new(t).expect(PROOF);
             let machine = engine.machine();
-            let x = Self::do_virtual_call(machine, &env_info, &mut state, &t, analytics)
+            let x = Self::do_virtual_call(machine, &env_info, &mut state, &t, analytics, None)
                 .expect(EXECUTE_PROOF);
             env_info.gas_used = env_info.gas_used + x.gas_used;
             (transaction_hash, x)
         })))
     }
 
+    fn replay_prestate(
+        &self,
+        id: TransactionId,
+        timeout: Option<Duration>,
+    ) -> Result<PodState, CallError> {
+        let address = self
+            .transaction_address(id)
+            .ok_or(CallError::TransactionNotFound)?;
+        let block = BlockId::Hash(address.block_hash);
+        let deadline = self.replay_deadline(timeout);
+        let mut env_info = self.env_info(block).ok_or(CallError::StatePruned)?;
+        let body = self.block_body(block).ok_or(CallError::StatePruned)?;
+        let mut state = self
+            .state_at_beginning(block)
+            .ok_or(CallError::StatePruned)?;
+        let machine = self.engine.machine();
+
+        const PROOF: &'static str =
+            "Transactions fetched from blockchain; blockchain transactions are valid; qed";
+
+        for (index, t) in body.transactions().into_iter().enumerate() {
+            let t = SignedTransaction::new(t).expect(PROOF);
+            if index == address.index {
+                let original = state.clone();
+                state.record_accesses();
+                Self::do_virtual_call(
+                    machine,
+                    &env_info,
+                    &mut state,
+                    &t,
+                    Default::default(),
+                    deadline,
+                )?;
+                return Ok(state
+                    .prestate_from(original)
+                    .map_err(ExecutionError::from)?);
+            }
+            let x = Self::do_virtual_call(
+                machine,
+                &env_info,
+                &mut state,
+                &t,
+                Default::default(),
+                deadline,
+            )?;
+            env_info.gas_used = env_info.gas_used + x.gas_used;
+        }
+        Err(CallError::TransactionNotFound)
+    }
+
     fn mode(&self) -> Mode {
         let r = self.mode.lock().clone().into();
         trace!(target: "mode", "Asked for mode = {:?}. returning {:?}", &*self.mode.lock(), r);
         r
     }
 
+    fn backup(&self, path: &Path) -> std::io::Result<()> {
+        let mut status = self.backup_status.write();
+        if let BackupStatus::Running { ref path, .. } = *status {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("A backup to {} is already running", path.display()),
+            ));
+        }
+        self.backup_sender
+            .lock()
+            .as_ref()
+            .and_then(|sender| sender.send(path.to_owned()).ok())
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::Other, "Client is shutting down")
+            })?;
+        *status = BackupStatus::Running {
+            path: path.to_owned(),
+            copied: 0,
+        };
+        Ok(())
+    }
+
+    fn backup_status(&self) -> BackupStatus {
+        self.backup_status.read().clone()
+    }
+
     fn disable(&self) {
         self.set_mode(Mode::Off);
         self.enabled.store(false, AtomicOrdering::SeqCst);
@@ -2239,6 +3344,20 @@ impl BlockChainClient for Client {
         }
     }
 
+    fn set_cache_sizes(&self, sizes: CacheSizes) {
+        debug!(target: "client", "Client::set_cache_sizes({:?})", sizes);
+        // mirror the preferred to maximum ratio used for the configured sizes.
+        if let Some(size) = sizes.state {
+            self.state_db.write().set_cache_size(size);
+        }
+        if let Some(size) = sizes.blockchain {
+            self.chain.read().set_cache_sizes(size * 3 / 4, size);
+        }
+        if let Some(size) = sizes.traces {
+            self.tracedb.read().set_cache_sizes(size * 3 / 4, size);
+        }
+    }
+
     fn spec_name(&self) -> String {
         self.config.spec_name.clone()
     }
@@ -2287,6 +3406,10 @@ impl BlockChainClient for Client {
             .is_processing_fork(&chain.best_block_hash(), &chain)
     }
 
+    fn clock(&self) -> SharedClock {
+        self.config.clock.clone()
+    }
+
     fn block_total_difficulty(&self, id: BlockId) -> Option<U256> {
         let chain = self.chain.read();
 
@@ -2540,6 +3663,23 @@ impl BlockChainClient for Client {
         self.chain.read().block_receipts(hash)
     }
 
+    fn block_fees(&self, id: BlockId) -> Option<BlockFees> {
+        let hash = self.block_hash(id)?;
+        if let Some(fees) = self.block_fees.lock().get_mut(&hash) {
+            return Some(fees.clone());
+        }
+
+        let header = self.block_header_decoded(BlockId::Hash(hash))?;
+        let body = self.chain.read().block_body(&hash)?;
+        let receipts = self
+            .block_receipts(&hash)
+            .map(|r| r.receipts)
+            .unwrap_or_default();
+        let fees = BlockFees::new(&header, &body.transactions(), &receipts);
+        self.block_fees.lock().insert(hash, fees.clone());
+        Some(fees)
+    }
+
     fn queue_info(&self) -> BlockQueueInfo {
         self.importer.block_queue.queue_info()
     }
@@ -2649,6 +3789,14 @@ impl BlockChainClient for Client {
         Ok(chain.logs(blocks, |entry| filter.matches(entry), filter.limit))
     }
 
+    fn logs_cost_estimate(&self, filter: &Filter, from: BlockNumber, to: BlockNumber) -> u64 {
+        // Only blocks matching the bloom index have their receipts read.
+        self.chain
+            .read()
+            .blocks_with_bloom(&filter.bloom_possibilities(), from, to)
+            .len() as u64
+    }
+
     fn filter_traces(&self, filter: TraceFilter) -> Option<Vec<LocalizedTrace>> {
         if !self.tracedb.read().tracing_enabled() {
             return None;
@@ -2760,15 +3908,19 @@ impl BlockChainClient for Client {
 
     fn uncle_extra_info(&self, id: UncleId) -> Option<BTreeMap<String, String>> {
         self.uncle(id).and_then(|h| {
-            h.decode(self.engine.params().eip1559_transition)
+            h.decode(self.engine.params().header_decode_params())
                 .map(|dh| self.engine.extra_info(&dh))
                 .ok()
         })
     }
 
     fn pruning_info(&self) -> PruningInfo {
+        let chain = self.chain.read();
+        let earliest_history = chain.earliest_history();
         PruningInfo {
-            earliest_chain: self.chain.read().first_block_number().unwrap_or(1),
+            earliest_chain: cmp::max(chain.first_block_number().unwrap_or(1), earliest_history),
+            earliest_history,
+            earliest_transaction_index: chain.earliest_transaction_index(),
             earliest_state: self
                 .state_db
                 .read()
@@ -2778,6 +3930,10 @@ impl BlockChainClient for Client {
         }
     }
 
+    fn is_transaction_unindexed(&self, hash: &H256) -> bool {
+        self.chain.read().is_transaction_unindexed(hash)
+    }
+
     fn create_transaction(
         &self,
         TransactionRequest {
@@ -2817,6 +3973,9 @@ impl BlockChainClient for Client {
     }
 
     fn transact(&self, tx_request: TransactionRequest) -> Result<(), transaction::Error> {
+        if self.config.read_only {
+            return Err(transaction::Error::NotAllowed);
+        }
         let signed = self.create_transaction(tx_request)?;
         self.importer
             .miner
@@ -2836,29 +3995,38 @@ impl IoClient for Client {
     fn queue_transactions(&self, transactions: Vec<Bytes>, peer_id: usize) {
         trace_time!("queue_transactions");
         let len = transactions.len();
-        self.queue_transactions
-            .queue(&self.io_channel.read(), len, move |client| {
-                trace_time!("import_queued_transactions");
-                let best_block_number = client.best_block_header().number();
-                let txs: Vec<UnverifiedTransaction> = transactions
-                    .iter()
-                    .filter_map(|bytes| {
-                        client
-                            .engine
-                            .decode_transaction(bytes, best_block_number)
-                            .ok()
-                    })
-                    .collect();
-
-                client.notify(|notify| {
-                    notify.transactions_received(&txs, peer_id);
-                });
-
-                client
-                    .importer
-                    .miner
-                    .import_external_transactions(client, txs);
-            })
+        if self.config.read_only {
+            debug!(target: "client", "Ignoring {} transactions: client is read-only", len);
+            return;
+        }
+        self.io_queue
+            .queue(
+                &self.io_channel.read(),
+                IoQueuePriority::Transactions,
+                len,
+                move |client| {
+                    trace_time!("import_queued_transactions");
+                    let best_block_header = client.best_block_header();
+                    let txs: Vec<UnverifiedTransaction> = transactions
+                        .iter()
+                        .filter_map(|bytes| {
+                            client
+                                .engine
+                                .decode_transaction(bytes, &best_block_header)
+                                .ok()
+                        })
+                        .collect();
+
+                    client.notify(|notify| {
+                        notify.transactions_received(&txs, peer_id);
+                    });
+
+                    client
+                        .importer
+                        .miner
+                        .import_external_transactions(client, txs);
+                },
+            )
             .unwrap_or_else(|e| {
                 debug!(target: "client", "Ignoring {} transactions: {}", len, e);
             });
@@ -2870,6 +4038,9 @@ impl IoClient for Client {
         receipts_bytes: Bytes,
     ) -> EthcoreResult<H256> {
         trace_time!("queue_ancient_block");
+        if self.config.read_only {
+            bail!(EthcoreErrorKind::Import(ImportErrorKind::ReadOnly));
+        }
 
         let hash = unverified.hash();
         {
@@ -2888,45 +4059,78 @@ impl IoClient for Client {
             }
         }
 
-        // we queue blocks here and trigger an Executer.
-        {
-            let mut queued = self.queued_ancient_blocks.write();
-            queued.insert(hash);
-        }
+        // the sender is cloned so that a caller waiting on a full queue does not block shutdown.
+        let sender = match self.ancient_blocks_sender.read().as_ref() {
+            Some(sender) => sender.clone(),
+            None => bail!(EthcoreErrorKind::Queue(QueueErrorKind::Closed)),
+        };
 
-        // see content of executer in Client::new()
-        match self.queued_ancient_blocks_executer.lock().as_ref() {
-            Some(queue) => {
-                if !queue.enqueue((unverified, receipts_bytes)) {
-                    bail!(EthcoreErrorKind::Queue(QueueErrorKind::Full(
-                        ANCIENT_BLOCKS_QUEUE_SIZE
-                    )));
-                }
+        // we queue blocks here and hand them over to the import thread, see `import_ancient_blocks`.
+        self.queued_ancient_blocks.write().insert(hash);
+
+        let item = match sender.try_send((unverified, receipts_bytes)) {
+            Ok(()) => return Ok(hash),
+            Err(crossbeam_channel::TrySendError::Full(item)) => item,
+            Err(crossbeam_channel::TrySendError::Disconnected(_)) => {
+                self.queued_ancient_blocks.write().remove(&hash);
+                bail!(EthcoreErrorKind::Queue(QueueErrorKind::Closed))
+            }
+        };
+
+        // queue is full: hold the caller back briefly so that sync slows down to the importer.
+        self.ancient_blocks_queue_waits
+            .fetch_add(1, AtomicOrdering::Relaxed);
+        match sender.send_timeout(item, ANCIENT_BLOCKS_QUEUE_TIMEOUT) {
+            Ok(()) => Ok(hash),
+            Err(crossbeam_channel::SendTimeoutError::Timeout(_)) => {
+                self.queued_ancient_blocks.write().remove(&hash);
+                self.ancient_blocks_queue_rejected
+                    .fetch_add(1, AtomicOrdering::Relaxed);
+                bail!(EthcoreErrorKind::Queue(QueueErrorKind::Full(
+                    ANCIENT_BLOCKS_QUEUE_SIZE
+                )))
+            }
+            Err(crossbeam_channel::SendTimeoutError::Disconnected(_)) => {
+                self.queued_ancient_blocks.write().remove(&hash);
+                bail!(EthcoreErrorKind::Queue(QueueErrorKind::Closed))
             }
-            None => (),
         }
-        Ok(hash)
     }
 
     fn ancient_block_queue_fullness(&self) -> f32 {
-        match self.queued_ancient_blocks_executer.lock().as_ref() {
-            Some(queue) => queue.len() as f32 / ANCIENT_BLOCKS_QUEUE_SIZE as f32,
+        match self.ancient_blocks_sender.read().as_ref() {
+            Some(sender) => sender.len() as f32 / ANCIENT_BLOCKS_QUEUE_SIZE as f32,
             None => 1.0, //return 1.0 if queue is not set
         }
     }
 
     fn queue_consensus_message(&self, message: Bytes) {
-        match self
-            .queue_consensus_message
-            .queue(&self.io_channel.read(), 1, move |client| {
+        if self.config.read_only {
+            debug!(target: "poa", "Ignoring the message: client is read-only");
+            return;
+        }
+        match self.io_queue.queue(
+            &self.io_channel.read(),
+            IoQueuePriority::Consensus,
+            1,
+            move |client| {
                 if let Err(e) = client.engine().handle_message(&message) {
                     debug!(target: "poa", "Invalid message received: {}", e);
                 }
-            }) {
+            },
+        ) {
             Ok(_) => (),
-            Err(e) => {
-                debug!(target: "poa", "Ignoring the message, error queueing: {}", e);
-            }
+            Err(e) => match self
+                .io_queue
+                .rejected_since_warning(IoQueuePriority::Consensus)
+            {
+                Some(rejected) => {
+                    warn!(target: "poa", "Ignoring the message, error queueing: {} ({} messages rejected since the last warning)", e, rejected);
+                }
+                None => {
+                    debug!(target: "poa", "Ignoring the message, error queueing: {}", e);
+                }
+            },
         }
     }
 }
@@ -2942,26 +4146,30 @@ impl ReopenBlock for Client {
             // Add new uncles
             let uncles = chain
                 .find_uncle_hashes(&h, MAX_UNCLE_AGE)
-                .unwrap_or_else(Vec::new);
-
-            for h in uncles {
-                if !block.uncles.iter().any(|header| header.hash() == h) {
-                    let uncle = chain
+                .unwrap_or_else(Vec::new)
+                .into_iter()
+                .filter(|h| !block.uncles.iter().any(|header| header.hash() == *h))
+                .map(|h| {
+                    chain
                         .block_header_data(&h)
-                        .expect("find_uncle_hashes only returns hashes for existing headers; qed");
-                    let uncle = uncle
-                        .decode(self.engine.params().eip1559_transition)
-                        .expect("decoding failure");
-                    block.push_uncle(uncle).expect(
-                        "pushing up to maximum_uncle_count;
-												push_uncle is not ok only if more than maximum_uncle_count is pushed;
-												so all push_uncle are Ok;
-												qed",
-                    );
-                    if block.uncles.len() >= max_uncles {
-                        break;
-                    }
-                }
+                        .expect("find_uncle_hashes only returns hashes for existing headers; qed")
+                        .decode(self.engine.params().header_decode_params())
+                        .expect("decoding failure")
+                })
+                .collect();
+            let uncles = self.config.uncle_strategy.select(
+                uncles,
+                block.header.author(),
+                max_uncles - block.uncles.len(),
+            );
+
+            for uncle in uncles {
+                block.push_uncle(uncle).expect(
+                    "pushing up to maximum_uncle_count;
+											push_uncle is not ok only if more than maximum_uncle_count is pushed;
+											so all push_uncle are Ok;
+											qed",
+                );
             }
         }
         block
@@ -2996,24 +4204,31 @@ impl PrepareOpenBlock for Client {
         )?;
 
         // Add uncles
-        chain
+        let uncles = chain
             .find_uncle_headers(&h, MAX_UNCLE_AGE)
             .unwrap_or_else(Vec::new)
             .into_iter()
-            .take(engine.maximum_uncle_count(open_block.header.number()))
-            .foreach(|h| {
-                open_block
-                    .push_uncle(
-                        h.decode(engine.params().eip1559_transition)
-                            .expect("decoding failure"),
-                    )
-                    .expect(
-                        "pushing maximum_uncle_count;
+            .map(|h| {
+                h.decode(engine.params().header_decode_params())
+                    .expect("decoding failure")
+            })
+            .collect();
+        self.config
+            .uncle_strategy
+            .select(
+                uncles,
+                &author,
+                engine.maximum_uncle_count(open_block.header.number()),
+            )
+            .into_iter()
+            .foreach(|uncle| {
+                open_block.push_uncle(uncle).expect(
+                    "pushing maximum_uncle_count;
 												open_block was just created;
 												push_uncle is not ok only if more than maximum_uncle_count is pushed;
 												so all push_uncle are Ok;
 												qed",
-                    );
+                );
             });
 
         Ok(open_block)
@@ -3024,12 +4239,16 @@ impl BlockProducer for Client {}
 
 impl ScheduleInfo for Client {
     fn latest_schedule(&self) -> Schedule {
-        self.engine.schedule(self.latest_env_info().number)
+        let env_info = self.latest_env_info();
+        self.engine.schedule(env_info.number, env_info.timestamp)
     }
 }
 
 impl ImportSealedBlock for Client {
     fn import_sealed_block(&self, block: SealedBlock) -> EthcoreResult<H256> {
+        if self.config.read_only {
+            bail!(EthcoreErrorKind::Import(ImportErrorKind::ReadOnly));
+        }
         let start = Instant::now();
         let raw = block.rlp_bytes();
         let header = block.header.clone();
@@ -3039,10 +4258,9 @@ impl ImportSealedBlock for Client {
         let route = {
             // Do a super duper basic verification to detect potential bugs
             if let Err(e) = self.engine.verify_block_basic(&header) {
-                self.importer.bad_blocks.report(
+                self.importer.report_bad_block(
                     block.rlp_bytes(),
                     format!("Detected an issue with locally sealed block: {}", e),
-                    self.engine.params().eip1559_transition,
                 );
                 return Err(e.into());
             }
@@ -3122,7 +4340,11 @@ impl BroadcastProposalBlock for Client {
 impl SealedBlockImporter for Client {}
 
 impl ::miner::TransactionVerifierClient for Client {}
-impl ::miner::BlockChainClient for Client {}
+impl ::miner::BlockChainClient for Client {
+    fn local_transactions_updated(&self) {
+        self.notify_local_transactions(&[], &[]);
+    }
+}
 
 impl super::traits::EngineClient for Client {
     fn update_sealing(&self, force: ForceUpdateSealing) {
@@ -3253,8 +4475,11 @@ impl ImportExportBlocks for Client {
         &self,
         mut source: Box<dyn std::io::Read + 'a>,
         format: Option<DataFormat>,
+        resume_from: Option<BlockNumber>,
     ) -> Result<(), String> {
         const READAHEAD_BYTES: usize = 8;
+        // number of blocks read ahead and decoded in parallel.
+        const DECODE_BATCH: usize = 1024;
 
         let mut first_bytes: Vec<u8> = vec![0; READAHEAD_BYTES];
         let mut first_read = 0;
@@ -3272,21 +4497,52 @@ impl ImportExportBlocks for Client {
             }
         };
 
-        let do_import = |bytes: Vec<u8>| {
-            let block = Unverified::from_rlp(bytes, self.engine.params().eip1559_transition)
-                .map_err(|_| "Invalid block rlp")?;
-            let number = block.header.number();
-            while self.queue_info().is_full() {
-                std::thread::sleep(Duration::from_secs(1));
-            }
-            match self.import_block(block) {
-                Err(Error(EthcoreErrorKind::Import(ImportErrorKind::AlreadyInChain), _)) => {
-                    trace!("Skipping block #{}: already in chain.", number);
+        let decode_params = self.engine.params().header_decode_params();
+        let import_batch = |batch: &mut Vec<Vec<u8>>| -> Result<(), String> {
+            let blocks = batch
+                .drain(..)
+                .collect::<Vec<_>>()
+                .into_par_iter()
+                .map(|bytes| Unverified::from_rlp(bytes, decode_params))
+                .collect::<Vec<_>>();
+            for block in blocks {
+                let block = block.map_err(|_| "Invalid block rlp")?;
+                let number = block.header.number();
+                while self.queue_info().is_full() {
+                    std::thread::sleep(Duration::from_secs(1));
                 }
-                Err(e) => {
-                    return Err(format!("Cannot import block #{}: {:?}", number, e));
+                match self.import_block(block) {
+                    Err(Error(EthcoreErrorKind::Import(ImportErrorKind::AlreadyInChain), _)) => {
+                        trace!("Skipping block #{}: already in chain.", number);
+                    }
+                    Err(e) => {
+                        return Err(format!("Cannot import block #{}: {:?}", number, e));
+                    }
+                    Ok(_) => {}
+                }
+            }
+            Ok(())
+        };
+
+        if let Some(number) = resume_from {
+            info!("Skipping blocks below #{}", number);
+        }
+        let resume_from = resume_from.unwrap_or(0);
+        let mut batch = Vec::with_capacity(DECODE_BATCH);
+        let mut do_import = |bytes: Vec<u8>| -> Result<(), String> {
+            if resume_from > 0 {
+                // only the header's number field is read for blocks being skipped.
+                let number: BlockNumber = Rlp::new(&bytes)
+                    .at(0)
+                    .and_then(|header| header.val_at(8))
+                    .map_err(|_| "Invalid block rlp")?;
+                if number < resume_from {
+                    return Ok(());
                 }
-                Ok(_) => {}
+            }
+            batch.push(bytes);
+            if batch.len() == DECODE_BATCH {
+                import_batch(&mut batch)?;
             }
             Ok(())
         };
@@ -3335,6 +4591,7 @@ impl ImportExportBlocks for Client {
                 }
             }
         };
+        import_batch(&mut batch)?;
         self.flush_queue();
         Ok(())
     }
@@ -3404,51 +4661,182 @@ fn transaction_receipt(
     }
 }
 
+/// Priority classes of work queued for the IO client, highest priority first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IoQueuePriority {
+    Consensus,
+    Transactions,
+}
+
+impl IoQueuePriority {
+    const ALL: [IoQueuePriority; 2] = [IoQueuePriority::Consensus, IoQueuePriority::Transactions];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            IoQueuePriority::Consensus => "consensus",
+            IoQueuePriority::Transactions => "transactions",
+        }
+    }
+
+    fn config(self, config: &IoQueueConfig) -> IoQueueClassConfig {
+        match self {
+            IoQueuePriority::Consensus => config.consensus,
+            IoQueuePriority::Transactions => config.transactions,
+        }
+    }
+}
+
+/// Single piece of queued work, accounting for `count` items.
+struct QueuedWork {
+    count: usize,
+    fun: Box<dyn FnOnce(&Client) + Send>,
+}
+
+/// Minimal time between two warnings about work rejected by a full class.
+const IO_QUEUE_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Queued work of all priority classes together with the class statistics.
+#[derive(Default)]
+struct PrioritizedWork {
+    queues: [VecDeque<QueuedWork>; 2],
+    queued: [usize; 2],
+    rejected: [u64; 2],
+    /// Time of the last warning about rejected work and the rejections counted then.
+    warned: [Option<(Instant, u64)>; 2],
+}
+
+impl PrioritizedWork {
+    /// Adds work to its class, rejecting it if the class is full.
+    fn push(
+        &mut self,
+        priority: IoQueuePriority,
+        config: IoQueueClassConfig,
+        work: QueuedWork,
+    ) -> EthcoreResult<()> {
+        let idx = priority.index();
+        if self.queued[idx] >= config.limit {
+            self.rejected[idx] += work.count as u64;
+            bail!("The {} queue is full ({})", priority.name(), config.limit);
+        }
+
+        self.queued[idx] += work.count;
+        self.queues[idx].push_back(work);
+        Ok(())
+    }
+
+    /// Removes the most recently added work of the given class.
+    fn pop_back(&mut self, priority: IoQueuePriority) {
+        let idx = priority.index();
+        if let Some(work) = self.queues[idx].pop_back() {
+            self.queued[idx] -= work.count;
+        }
+    }
+
+    /// Returns the number of items of the class rejected since the last warning,
+    /// or `None` if the last warning was less than `IO_QUEUE_WARNING_INTERVAL` ago.
+    fn rejected_since_warning(&mut self, priority: IoQueuePriority, now: Instant) -> Option<u64> {
+        let idx = priority.index();
+        let since = match self.warned[idx] {
+            Some((at, _)) if now.saturating_duration_since(at) < IO_QUEUE_WARNING_INTERVAL => {
+                return None
+            }
+            Some((_, rejected)) => rejected,
+            None => 0,
+        };
+        self.warned[idx] = Some((now, self.rejected[idx]));
+        Some(self.rejected[idx] - since)
+    }
+
+    /// Takes the oldest work of the highest non-empty priority class.
+    fn pop(&mut self) -> Option<QueuedWork> {
+        for idx in 0..self.queues.len() {
+            if let Some(work) = self.queues[idx].pop_front() {
+                self.queued[idx] -= work.count;
+                return Some(work);
+            }
+        }
+        None
+    }
+}
+
 /// Queue some items to be processed by IO client.
+///
+/// Every queued item sends one message to the IO channel; whichever message is handled
+/// first processes the highest priority work available at that time.
 struct IoChannelQueue {
-    /// Using a *signed* integer for counting currently queued messages since the
-    /// order in which the counter is incremented and decremented is not defined.
-    /// Using an unsigned integer can (and will) result in integer underflow,
-    /// incorrectly rejecting messages and returning a FullQueue error.
-    currently_queued: Arc<AtomicI64>,
-    limit: i64,
+    work: Arc<Mutex<PrioritizedWork>>,
+    config: IoQueueConfig,
 }
 
 impl IoChannelQueue {
-    pub fn new(limit: usize) -> Self {
-        let limit = i64::try_from(limit).unwrap_or(i64::max_value());
+    pub fn new(config: IoQueueConfig) -> Self {
         IoChannelQueue {
-            currently_queued: Default::default(),
-            limit,
+            work: Default::default(),
+            config,
         }
     }
 
     pub fn queue<F>(
         &self,
         channel: &IoChannel<ClientIoMessage>,
+        priority: IoQueuePriority,
         count: usize,
         fun: F,
     ) -> EthcoreResult<()>
     where
-        F: Fn(&Client) + Send + Sync + 'static,
+        F: FnOnce(&Client) + Send + 'static,
     {
-        let queue_size = self.currently_queued.load(AtomicOrdering::SeqCst);
-        if queue_size >= self.limit {
-            let err_limit = usize::try_from(self.limit).unwrap_or(usize::max_value());
-            bail!("The queue is full ({})", err_limit);
+        let work = QueuedWork {
+            count,
+            fun: Box::new(fun),
         };
+        self.work
+            .lock()
+            .push(priority, priority.config(&self.config), work)?;
 
-        let count = i64::try_from(count).unwrap_or(i64::max_value());
+        let queued = self.work.clone();
+        let sent = channel.send(ClientIoMessage::execute(move |client| {
+            let next = queued.lock().pop();
+            if let Some(work) = next {
+                (work.fun)(client);
+            }
+        }));
+        if let Err(e) = sent {
+            self.work.lock().pop_back(priority);
+            return Err(e.into());
+        }
+        Ok(())
+    }
 
-        let currently_queued = self.currently_queued.clone();
-        let _ok = channel.send(ClientIoMessage::execute(move |client| {
-            currently_queued.fetch_sub(count, AtomicOrdering::SeqCst);
-            fun(client);
-        }))?;
+    /// See `PrioritizedWork::rejected_since_warning`.
+    fn rejected_since_warning(&self, priority: IoQueuePriority) -> Option<u64> {
+        self.work
+            .lock()
+            .rejected_since_warning(priority, Instant::now())
+    }
 
-        self.currently_queued
-            .fetch_add(count, AtomicOrdering::SeqCst);
-        Ok(())
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        let work = self.work.lock();
+        for priority in IoQueuePriority::ALL.iter() {
+            let idx = priority.index();
+            r.register_gauge(
+                &format!("io_queue_{}_depth", priority.name()),
+                &format!("Number of queued {} items", priority.name()),
+                work.queued[idx] as i64,
+            );
+            r.register_counter(
+                &format!("io_queue_{}_rejected", priority.name()),
+                &format!(
+                    "Number of {} items rejected by a full queue",
+                    priority.name()
+                ),
+                work.rejected[idx] as i64,
+            );
+        }
     }
 }
 
@@ -3480,6 +4868,105 @@ impl PrometheusMetrics for Client {
             "Transactions applied",
             report.transactions_applied as i64,
         );
+        r.register_counter(
+            "import_reorgs_refused",
+            "Reorganizations refused for exceeding the maximum depth",
+            report.reorgs_refused as i64,
+        );
+
+        // transaction queue
+        let queue_status = self.importer.miner.queue_status();
+        r.register_gauge(
+            "txqueue_transactions",
+            "Number of transactions in the queue",
+            queue_status.status.transaction_count as i64,
+        );
+        r.register_counter(
+            "txqueue_expired",
+            "Transactions dropped from the queue for exceeding the maximal age",
+            queue_status.expired_transactions as i64,
+        );
+        r.register_counter(
+            "txqueue_clock_backwards_jumps",
+            "Times the time checked by timestamp conditions of queued transactions moved backwards",
+            queue_status.backwards_clock_jumps as i64,
+        );
+        r.register_gauge(
+            "txqueue_mem_usage",
+            "Memory used by the transactions in the queue, in bytes",
+            queue_status.status.mem_usage as i64,
+        );
+        let readiness = self.importer.miner.queue_readiness(self);
+        r.register_gauge(
+            "txqueue_ready",
+            "Transactions in the queue which can be included in the next block",
+            readiness.pending as i64,
+        );
+        r.register_gauge(
+            "txqueue_future",
+            "Transactions in the queue waiting for a nonce gap to be filled",
+            readiness.future as i64,
+        );
+        let churn = &queue_status.churn;
+        for &(name, help, value) in &[
+            (
+                "inserted",
+                "Transactions inserted into the queue",
+                churn.inserted,
+            ),
+            (
+                "replaced",
+                "Transactions replaced by one with the same sender and nonce",
+                churn.replaced,
+            ),
+            (
+                "culled",
+                "Transactions culled from the queue, mined or stale",
+                churn.culled,
+            ),
+        ] {
+            r.register_counter(&format!("txqueue_{}", name), help, value as i64);
+        }
+        for &(reason, value) in &[
+            ("low_fee", churn.dropped_low_fee),
+            ("limit", churn.dropped_limit),
+            ("invalid", churn.dropped_invalid),
+            ("stale", churn.dropped_stale),
+            ("expired", churn.dropped_expired),
+        ] {
+            r.register_counter(
+                &format!("txqueue_dropped_{}", reason),
+                &format!("Transactions dropped from the queue, reason: {}", reason),
+                value as i64,
+            );
+        }
+        self.importer.miner.prometheus_metrics(r);
+
+        // import latency, per stage
+        self.importer.block_queue.prometheus_metrics(r);
+        r.register_gauge(
+            "ancient_block_queue_depth",
+            "Number of ancient blocks waiting for import",
+            self.ancient_blocks_sender
+                .read()
+                .as_ref()
+                .map_or(0, |sender| sender.len()) as i64,
+        );
+        r.register_counter(
+            "ancient_block_queue_waits",
+            "Number of times queueing an ancient block waited for a full queue",
+            self.ancient_blocks_queue_waits
+                .load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_counter(
+            "ancient_block_queue_rejected",
+            "Number of ancient blocks rejected by a full queue",
+            self.ancient_blocks_queue_rejected
+                .load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_histogram(&self.importer.execution_histogram);
+        r.register_histogram(&self.importer.commit_histogram);
+        r.register_histogram(&self.importer.flush_histogram);
 
         let state_db = self.state_db.read();
         r.register_gauge(
@@ -3587,6 +5074,15 @@ impl PrometheusMetrics for Client {
             queue.verifying_queue_size as i64,
         );
 
+        // io queue info
+        self.io_queue.prometheus_metrics(r);
+
+        // timestamp quarantine info
+        self.importer.timestamp_quarantine.prometheus_metrics(r);
+
+        // consensus engine info
+        self.engine.prometheus_metrics(r);
+
         // database info
         self.db.read().key_value().prometheus_metrics(r);
     }
@@ -3798,4 +5294,116 @@ mod tests {
         assert_eq!(block2_details.children.len(), 0);
         assert!(!block2_details.is_finalized);
     }
+
+    #[test]
+    fn should_process_io_work_by_priority() {
+        use super::{Client, IoQueuePriority, PrioritizedWork, QueuedWork};
+        use client::IoQueueConfig;
+
+        let work = |count| QueuedWork {
+            count,
+            fun: Box::new(|_: &Client| {}),
+        };
+        let config = IoQueueConfig::default();
+        let mut queue = PrioritizedWork::default();
+
+        for (priority, count) in vec![
+            (IoQueuePriority::Transactions, 2),
+            (IoQueuePriority::Consensus, 3),
+            (IoQueuePriority::Transactions, 4),
+        ] {
+            queue
+                .push(priority, priority.config(&config), work(count))
+                .unwrap();
+        }
+
+        let order: Vec<_> = ::std::iter::from_fn(|| queue.pop())
+            .map(|work| work.count)
+            .collect();
+        assert_eq!(order, vec![3, 2, 4]);
+        assert_eq!(queue.queued, [0, 0]);
+    }
+
+    #[test]
+    fn should_reject_work_of_full_io_queue_class() {
+        use super::{Client, IoQueuePriority, PrioritizedWork, QueuedWork};
+        use client::IoQueueClassConfig;
+
+        let work = |count| QueuedWork {
+            count,
+            fun: Box::new(|_: &Client| {}),
+        };
+        let config = IoQueueClassConfig { limit: 2 };
+        let mut queue = PrioritizedWork::default();
+
+        let txs = IoQueuePriority::Transactions;
+        assert!(queue.push(txs, config, work(2)).is_ok());
+        assert!(queue.push(txs, config, work(1)).is_err());
+        assert_eq!(queue.rejected[txs.index()], 1);
+
+        // a full class doesn't affect the others
+        let consensus = IoQueuePriority::Consensus;
+        assert!(queue.push(consensus, config, work(1)).is_ok());
+        assert_eq!(queue.queued, [1, 2]);
+    }
+
+    #[test]
+    fn should_look_up_block_hashes_relative_to_overridden_number() {
+        use client::{BlockChainClient, BlockId};
+        use test_helpers::generate_dummy_client;
+
+        let client = generate_dummy_client(3);
+        let hash = |number| client.block_hash(BlockId::Number(number)).unwrap();
+
+        let last_hashes = client.canon_last_hashes(2);
+        assert_eq!(&last_hashes[..2], &[hash(1), hash(0)]);
+
+        // blocks 4 and 5 don't exist yet
+        let last_hashes = client.canon_last_hashes(6);
+        assert_eq!(
+            &last_hashes[..6],
+            &[
+                H256::zero(),
+                H256::zero(),
+                hash(3),
+                hash(2),
+                hash(1),
+                hash(0)
+            ]
+        );
+        assert_eq!(last_hashes.len(), 256);
+
+        assert!(client
+            .canon_last_hashes(0)
+            .iter()
+            .all(|hash| hash.is_zero()));
+    }
+
+    #[test]
+    fn should_rate_limit_io_queue_rejection_warnings() {
+        use super::{
+            Client, IoQueuePriority, PrioritizedWork, QueuedWork, IO_QUEUE_WARNING_INTERVAL,
+        };
+        use client::IoQueueClassConfig;
+        use std::time::Instant;
+
+        let work = |count| QueuedWork {
+            count,
+            fun: Box::new(|_: &Client| {}),
+        };
+        let config = IoQueueClassConfig { limit: 0 };
+        let consensus = IoQueuePriority::Consensus;
+        let mut queue = PrioritizedWork::default();
+        let now = Instant::now();
+
+        assert!(queue.push(consensus, config, work(1)).is_err());
+        assert_eq!(queue.rejected_since_warning(consensus, now), Some(1));
+        assert!(queue.push(consensus, config, work(1)).is_err());
+        assert!(queue.push(consensus, config, work(1)).is_err());
+        assert_eq!(queue.rejected_since_warning(consensus, now), None);
+        assert_eq!(
+            queue.rejected_since_warning(consensus, now + IO_QUEUE_WARNING_INTERVAL),
+            Some(2)
+        );
+    }
 }
@@ -19,8 +19,13 @@ use std::{
     str::FromStr,
 };
 
+use ethash;
+use ethereum_types::Address;
 use journaldb;
+use machine::DEFAULT_MAX_FUTURE_DRIFT;
 use snapshot::SnapshotConfiguration;
+use time_utils::SharedClock;
+use types::header::Header;
 use verification::{QueueConfig, VerifierType};
 
 pub use blockchain::Config as BlockChainConfig;
@@ -28,6 +33,18 @@ pub use evm::VMType;
 pub use std::time::Duration;
 pub use trace::Config as TraceConfig;
 
+/// Cache budgets in bytes which can be changed while the client is running.
+/// Sizes left unset are kept as they are.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CacheSizes {
+    /// Size of the state cache.
+    pub state: Option<usize>,
+    /// Maximum size of the blockchain cache.
+    pub blockchain: Option<usize>,
+    /// Maximum size of the traces cache.
+    pub traces: Option<usize>,
+}
+
 /// Client state db compaction profile
 #[derive(Debug, PartialEq, Clone)]
 pub enum DatabaseCompactionProfile {
@@ -58,6 +75,59 @@ impl FromStr for DatabaseCompactionProfile {
     }
 }
 
+/// Which of the available uncles are included in newly authored blocks.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UncleStrategy {
+    /// The first ones found, closest generations first.
+    First,
+    /// Uncles authored by the block author first, so that it gets the uncle rewards as well,
+    /// then the closest generations as their rewards are the highest.
+    MostProfitable,
+    /// The most recently mined uncles first.
+    Newest,
+    /// No uncles, for smaller blocks.
+    Disabled,
+}
+
+impl Default for UncleStrategy {
+    fn default() -> Self {
+        UncleStrategy::First
+    }
+}
+
+impl FromStr for UncleStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(UncleStrategy::First),
+            "profitable" => Ok(UncleStrategy::MostProfitable),
+            "newest" => Ok(UncleStrategy::Newest),
+            "none" => Ok(UncleStrategy::Disabled),
+            _ => Err("Invalid uncle strategy given. Expected first/profitable/newest/none.".into()),
+        }
+    }
+}
+
+impl UncleStrategy {
+    /// Picks at most `max` of the `uncles`, found in order of their generation,
+    /// for a block authored by `author`.
+    pub fn select(&self, mut uncles: Vec<Header>, author: &Address, max: usize) -> Vec<Header> {
+        match *self {
+            UncleStrategy::First => {}
+            UncleStrategy::MostProfitable => uncles.sort_by(|a, b| {
+                (b.author() == author)
+                    .cmp(&(a.author() == author))
+                    .then(b.number().cmp(&a.number()))
+            }),
+            UncleStrategy::Newest => uncles.sort_by(|a, b| b.timestamp().cmp(&a.timestamp())),
+            UncleStrategy::Disabled => return Vec::new(),
+        }
+        uncles.truncate(max);
+        uncles
+    }
+}
+
 /// Operating mode for the client.
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum Mode {
@@ -84,6 +154,35 @@ impl Display for Mode {
     }
 }
 
+/// Limit of a single priority class of the IO queue.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct IoQueueClassConfig {
+    /// Maximal number of queued items, new work is rejected once it is reached.
+    pub limit: usize,
+}
+
+/// Configuration of the queue of work handed over to the IO client.
+///
+/// Work is processed by priority class: consensus messages first, then transactions.
+/// Each class has its own limit so that a flood of transactions can't crowd out
+/// consensus messages.
+#[derive(Debug, PartialEq, Clone)]
+pub struct IoQueueConfig {
+    /// Consensus engine messages.
+    pub consensus: IoQueueClassConfig,
+    /// Transactions received from the network, limit counts transactions.
+    pub transactions: IoQueueClassConfig,
+}
+
+impl Default for IoQueueConfig {
+    fn default() -> Self {
+        IoQueueConfig {
+            consensus: IoQueueClassConfig { limit: 4096 },
+            transactions: IoQueueClassConfig { limit: 8192 },
+        }
+    }
+}
+
 /// Client configuration. Includes configs for all sub-systems.
 #[derive(Debug, PartialEq, Clone)]
 pub struct ClientConfig {
@@ -121,12 +220,43 @@ pub struct ClientConfig {
     pub history_mem: usize,
     /// Check seal valididity on block import
     pub check_seal: bool,
-    /// Maximal number of transactions queued for verification in a separate thread.
-    pub transaction_verification_queue_size: usize,
+    /// Limits of work queued for the IO client, including transactions queued for
+    /// verification in a separate thread.
+    pub io_queue: IoQueueConfig,
     /// Maximal number of blocks to import at each round.
     pub max_round_blocks_to_import: usize,
     /// Snapshot configuration
     pub snapshot: SnapshotConfiguration,
+    /// Clock used for timestamp dependent logic. Defaults to the system clock.
+    pub clock: SharedClock,
+    /// Maximum amount of time a block timestamp may be ahead of the local clock.
+    /// Blocks up to ten times further ahead are quarantined until the clock catches up.
+    pub max_future_block_drift: Duration,
+    /// Wall-clock execution limit of virtual calls such as `eth_call` and `eth_estimateGas`.
+    /// Requests may only ask for a shorter limit. `None` means unlimited.
+    pub call_timeout: Option<Duration>,
+    /// Number of Ethash light caches kept in memory.
+    pub ethash_cache_count: usize,
+    /// Distance in blocks to the next Ethash epoch at which its cache starts being
+    /// generated in the background. Zero disables prebuilding.
+    pub ethash_prebuild_distance: u64,
+    /// Refuse to switch to a fork whose common ancestor with the current best block
+    /// is more than this many blocks deep. `None` allows reorganizations of any depth.
+    pub max_reorg_depth: Option<u64>,
+    /// Keep bodies and receipts only for this many most recent blocks, deleting older ones
+    /// while retaining their headers. `None` keeps the whole history.
+    pub history_expiry: Option<u64>,
+    /// Index transactions by hash only for this many most recent blocks, deleting older
+    /// index entries. `None` indexes the whole chain.
+    pub tx_index_expiry: Option<u64>,
+    /// Maximum number of blocks replayed to regenerate a pruned state on demand.
+    /// `None` disables state regeneration.
+    pub state_regeneration_limit: Option<u64>,
+    /// Only serve queries from an existing database: block, transaction and consensus
+    /// message import, sealing and background database maintenance are disabled.
+    pub read_only: bool,
+    /// Selection of uncles for newly authored blocks.
+    pub uncle_strategy: UncleStrategy,
 }
 
 impl Default for ClientConfig {
@@ -150,15 +280,28 @@ impl Default for ClientConfig {
             history: 64,
             history_mem: 32 * mb,
             check_seal: true,
-            transaction_verification_queue_size: 8192,
+            io_queue: Default::default(),
             max_round_blocks_to_import: 1,
             snapshot: Default::default(),
+            clock: Default::default(),
+            max_future_block_drift: DEFAULT_MAX_FUTURE_DRIFT,
+            call_timeout: None,
+            ethash_cache_count: ethash::DEFAULT_CACHE_COUNT,
+            ethash_prebuild_distance: 1024,
+            max_reorg_depth: None,
+            history_expiry: None,
+            tx_index_expiry: None,
+            state_regeneration_limit: None,
+            read_only: false,
+            uncle_strategy: Default::default(),
         }
     }
 }
 #[cfg(test)]
 mod test {
-    use super::DatabaseCompactionProfile;
+    use super::{DatabaseCompactionProfile, UncleStrategy};
+    use ethereum_types::Address;
+    use types::header::Header;
 
     #[test]
     fn test_default_compaction_profile() {
@@ -174,4 +317,35 @@ mod test {
         assert_eq!(DatabaseCompactionProfile::SSD, "ssd".parse().unwrap());
         assert_eq!(DatabaseCompactionProfile::HDD, "hdd".parse().unwrap());
     }
+
+    #[test]
+    fn test_select_uncles() {
+        let author = Address::from_low_u64_be(1);
+        let uncle = |number: u64, timestamp: u64, author: Address| {
+            let mut header = Header::new();
+            header.set_number(number);
+            header.set_timestamp(timestamp);
+            header.set_author(author);
+            header
+        };
+        let uncles = vec![
+            uncle(9, 10, Address::zero()),
+            uncle(8, 30, author),
+            uncle(7, 20, Address::zero()),
+        ];
+        let numbers = |strategy: UncleStrategy| {
+            strategy
+                .select(uncles.clone(), &author, 2)
+                .iter()
+                .map(|header| header.number())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(numbers(UncleStrategy::First), vec![9, 8]);
+        assert_eq!(numbers(UncleStrategy::MostProfitable), vec![8, 9]);
+        assert_eq!(numbers(UncleStrategy::Newest), vec![8, 7]);
+        assert_eq!(numbers(UncleStrategy::Disabled), Vec::<u64>::new());
+        assert_eq!(UncleStrategy::Newest, "newest".parse().unwrap());
+        assert!("oldest".parse::<UncleStrategy>().is_err());
+    }
 }
@@ -109,6 +109,8 @@ impl<'a> EvmTestClient<'a> {
             ForkSpec::Berlin => Some(ethereum::new_berlin_test()),
             ForkSpec::London => Some(ethereum::new_london_test()),
             ForkSpec::BerlinToLondonAt5 => Some(ethereum::new_berlin_to_london_test()),
+            ForkSpec::Merge => Some(ethereum::new_merge_test()),
+            ForkSpec::EIP3074 => Some(ethereum::new_eip3074_test()),
             ForkSpec::FrontierToHomesteadAt5
             | ForkSpec::HomesteadToDaoAt5
             | ForkSpec::HomesteadToEIP150At5
@@ -251,6 +253,7 @@ impl<'a> EvmTestClient<'a> {
             gas_used: 0.into(),
             gas_limit: *genesis.gas_limit(),
             base_fee: genesis.base_fee(),
+            random: genesis.mix_hash(),
         };
         self.call_envinfo(params, tracer, vm_tracer, info)
     }
@@ -266,7 +269,7 @@ impl<'a> EvmTestClient<'a> {
     ) -> Result<FinalizationResult, EvmTestError> {
         let mut substate = state::Substate::new();
         let machine = self.spec.engine.machine();
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut executive = executive::Executive::new(&mut self.state, &info, &machine, &schedule);
         executive
             .call(params, &mut substate, tracer, vm_tracer)
@@ -310,7 +313,11 @@ impl<'a> EvmTestClient<'a> {
         // Touch the coinbase at the end of the test to simulate
         // miner reward.
         // Details: https://github.com/openethereum/openethereum/issues/9431
-        let schedule = self.spec.engine.machine().schedule(env_info.number);
+        let schedule = self
+            .spec
+            .engine
+            .machine()
+            .schedule(env_info.number, env_info.timestamp);
         self.state
             .add_balance(
                 &env_info.author,
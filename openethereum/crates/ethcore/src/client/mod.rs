@@ -23,8 +23,11 @@ mod config;
 #[cfg(any(test, feature = "test-helpers"))]
 mod evm_test_client;
 mod io_message;
+mod overlay;
+mod prefetch;
 #[cfg(any(test, feature = "test-helpers"))]
 pub mod test_client;
+mod timestamp_quarantine;
 mod trace;
 
 #[cfg(any(test, feature = "test-helpers"))]
@@ -32,23 +35,31 @@ pub use self::evm_test_client::{EvmTestClient, EvmTestError, TransactErr, Transa
 #[cfg(any(test, feature = "test-helpers"))]
 pub use self::test_client::{EachBlockWith, TestBlockChainClient};
 pub use self::{
-    chain_notify::{ChainMessageType, ChainNotify, ChainRoute, ChainRouteType, NewBlocks},
+    bad_blocks::BadBlock,
+    chain_notify::{
+        ChainMessageType, ChainNotify, ChainRoute, ChainRouteType, LocalTransactionEvent,
+        NewBlocks, RefusedReorg, Reorg, ReorgBlock,
+    },
     client::*,
-    config::{BlockChainConfig, ClientConfig, DatabaseCompactionProfile, Mode, VMType},
+    config::{
+        BlockChainConfig, CacheSizes, ClientConfig, DatabaseCompactionProfile, IoQueueClassConfig,
+        IoQueueConfig, Mode, UncleStrategy, VMType,
+    },
     io_message::ClientIoMessage,
+    overlay::OverlayChain,
     traits::{
-        AccountData, BadBlocks, Balance, BlockChain, BlockChainClient, BlockChainReset, BlockInfo,
-        BlockProducer, BroadcastProposalBlock, Call, ChainInfo, EngineClient, EngineInfo,
-        ImportBlock, ImportExportBlocks, ImportSealedBlock, IoClient, Nonce, PrepareOpenBlock,
-        ProvingBlockChainClient, ReopenBlock, ScheduleInfo, SealedBlockImporter, StateClient,
-        StateOrBlock, TransactionInfo,
+        AccountData, BackupStatus, BadBlocks, Balance, BlockChain, BlockChainClient,
+        BlockChainReset, BlockInfo, BlockProducer, BroadcastProposalBlock, Call, ChainInfo,
+        EngineClient, EngineInfo, ImportBlock, ImportExportBlocks, ImportSealedBlock, IoClient,
+        Nonce, PrepareOpenBlock, ProvingBlockChainClient, ReopenBlock, ReplayOptions, ScheduleInfo,
+        SealedBlockImporter, StateClient, StateOrBlock, TransactionInfo,
     },
 };
 pub use state::StateInfo;
 
 pub use types::{
     call_analytics::CallAnalytics, ids::*, pruning_info::PruningInfo,
-    trace_filter::Filter as TraceFilter,
+    state_override::StateOverride, trace_filter::Filter as TraceFilter,
 };
 
 pub use executive::{Executed, Executive, TransactOptions};
@@ -0,0 +1,410 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! In-memory overlay of the chain head for speculative execution.
+//!
+//! An `OverlayChain` forks the best block of a client: blocks built on top of it are executed
+//! against an in-memory copy of the state database and are never written to disk or announced.
+//! Dropping the overlay discards everything that was applied to it.
+
+use std::sync::Arc;
+
+use blockchain::{BlockChain, BlockProvider};
+use bytes::Bytes;
+use call_contract::CallContract;
+use ethereum_types::{Address, H256, U256};
+use rlp::RlpStream;
+use types::{
+    encoded,
+    header::Header,
+    receipt::TypedReceipt,
+    transaction::{Action, SignedTransaction, Transaction, TypedTransaction},
+};
+use vm::LastHashes;
+
+use block::{Drain, OpenBlock};
+use client::{Balance, BlockId, BlockInfo, CallAnalytics, Client, Nonce, StateOrBlock};
+use engines::EthEngine;
+use error::{CallError, Error};
+use executive::Executed;
+use factory::Factories;
+use state::State;
+use state_db::StateDB;
+
+/// A block applied on top of the overlay's fork point.
+struct OverlayBlock {
+    block: encoded::Block,
+    header: Header,
+    receipts: Vec<TypedReceipt>,
+    state_db: StateDB,
+}
+
+/// A lightweight, throw-away fork of the chain head kept entirely in memory.
+///
+/// Transactions are applied in synthetic, unsealed blocks; the resulting chain can be queried
+/// through the regular client traits, states through `latest_state_and_header` and `state_at`.
+/// States of blocks older than the fork point are not available.
+pub struct OverlayChain {
+    engine: Arc<dyn EthEngine>,
+    factories: Factories,
+    chain: Arc<BlockChain>,
+    base: Header,
+    base_state_db: StateDB,
+    blocks: Vec<OverlayBlock>,
+}
+
+impl OverlayChain {
+    /// Create an overlay on top of `base`, whose state is read from `state_db`.
+    ///
+    /// `state_db` must not share a canonical account cache with the client, or reads could
+    /// observe the client's view instead of the overlay's.
+    pub(crate) fn new(
+        engine: Arc<dyn EthEngine>,
+        factories: Factories,
+        chain: Arc<BlockChain>,
+        base: Header,
+        base_state_db: StateDB,
+    ) -> Self {
+        OverlayChain {
+            engine,
+            factories,
+            chain,
+            base,
+            base_state_db,
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Header of the block the overlay was forked from.
+    pub fn fork_header(&self) -> &Header {
+        &self.base
+    }
+
+    /// Number of blocks applied on top of the fork point.
+    pub fn depth(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Apply `transactions` in a new block on top of the overlay head and return their receipts.
+    ///
+    /// The block is authored by `author` and stamped `timestamp` seconds, defaulting to one
+    /// second after its parent. A failing transaction rejects the whole block.
+    pub fn push_block(
+        &mut self,
+        author: Address,
+        timestamp: Option<u64>,
+        transactions: Vec<SignedTransaction>,
+    ) -> Result<&[TypedReceipt], Error> {
+        let parent = self.head().clone();
+        let mut open_block = OpenBlock::new(
+            &*self.engine,
+            self.factories.clone(),
+            false,
+            self.head_state_db().boxed_clone(),
+            &parent,
+            self.last_hashes(&parent),
+            author,
+            (*parent.gas_limit(), *parent.gas_limit()),
+            Vec::new(),
+            false,
+            None,
+        )?;
+        open_block.set_timestamp(timestamp.unwrap_or_else(|| parent.timestamp() + 1));
+        for transaction in transactions {
+            open_block.push_transaction(transaction, None)?;
+        }
+
+        let executed = open_block.close_and_lock()?.drain();
+        let mut header = executed.header;
+        let hash = header.compute_hash();
+        let mut block = RlpStream::new_list(3 + executed.withdrawals.is_some() as usize);
+        block.append(&header);
+        SignedTransaction::rlp_append_list(&mut block, &executed.transactions);
+        block.append_list(&executed.uncles);
+        if let Some(ref withdrawals) = executed.withdrawals {
+            block.append_list(withdrawals);
+        }
+        trace!(target: "client", "Applied overlay block #{} ({})", header.number(), hash);
+
+        self.blocks.push(OverlayBlock {
+            block: encoded::Block::new(block.out()),
+            header,
+            receipts: executed.receipts,
+            state_db: executed.state.drop().1,
+        });
+        Ok(&self
+            .blocks
+            .last()
+            .expect("overlay block was just pushed; qed")
+            .receipts)
+    }
+
+    /// Receipts of an overlay block, if `id` refers to one.
+    pub fn block_receipts(&self, id: BlockId) -> Option<&[TypedReceipt]> {
+        self.overlay_block(id).map(|block| &block.receipts[..])
+    }
+
+    /// Make a non-persistent call against the overlay head.
+    pub fn call(
+        &self,
+        transaction: &SignedTransaction,
+        analytics: CallAnalytics,
+    ) -> Result<Executed, CallError> {
+        let (mut state, header) = self.latest_state_and_header()?;
+        let env_info = ::vm::EnvInfo {
+            number: header.number(),
+            author: *header.author(),
+            timestamp: header.timestamp(),
+            difficulty: *header.difficulty(),
+            last_hashes: self.last_hashes(&header),
+            gas_used: U256::zero(),
+            gas_limit: U256::max_value(),
+            base_fee: if transaction.effective_gas_price(header.base_fee()).is_zero() {
+                Some(0.into())
+            } else {
+                header.base_fee()
+            },
+            random: header.mix_hash(),
+        };
+        Client::do_virtual_call(
+            self.engine.machine(),
+            &env_info,
+            &mut state,
+            transaction,
+            analytics,
+            None,
+        )
+    }
+
+    /// Get a copy of the overlay head's state and its header. Fails with `StatePruned` if the
+    /// state of the fork point has been pruned from the client database since.
+    pub fn latest_state_and_header(&self) -> Result<(State<StateDB>, Header), CallError> {
+        let header = self.head().clone();
+        let state = self
+            .state_from(self.head_state_db(), &header)
+            .ok_or(CallError::StatePruned)?;
+        Ok((state, header))
+    }
+
+    /// Attempt to get a copy of the state of the fork point or of an overlay block.
+    pub fn state_at(&self, id: BlockId) -> Option<State<StateDB>> {
+        self.state_db_at(id)
+            .and_then(|(db, header)| self.state_from(db, header))
+    }
+
+    fn head(&self) -> &Header {
+        self.blocks.last().map_or(&self.base, |block| &block.header)
+    }
+
+    fn head_state_db(&self) -> &StateDB {
+        self.blocks
+            .last()
+            .map_or(&self.base_state_db, |block| &block.state_db)
+    }
+
+    /// Hashes of the 256 ancestors of the block after `parent`, `parent` first.
+    fn last_hashes(&self, parent: &Header) -> Arc<LastHashes> {
+        let mut last_hashes = vec![parent.hash()];
+        let mut number = parent.number();
+        while last_hashes.len() < 256 && number > self.base.number() {
+            number -= 1;
+            match self.overlay_block(BlockId::Number(number)) {
+                Some(block) => last_hashes.push(block.header.hash()),
+                None => last_hashes.push(self.base.hash()),
+            }
+        }
+        let mut hash = *last_hashes.last().expect("parent hash was pushed; qed");
+        while last_hashes.len() < 256 {
+            match self.chain.block_details(&hash) {
+                Some(details) => {
+                    hash = details.parent;
+                    last_hashes.push(hash);
+                }
+                None => break,
+            }
+        }
+        last_hashes.resize(256, H256::zero());
+        Arc::new(last_hashes)
+    }
+
+    fn overlay_block(&self, id: BlockId) -> Option<&OverlayBlock> {
+        match id {
+            BlockId::Hash(hash) => self.blocks.iter().find(|b| b.header.hash() == hash),
+            BlockId::Number(number) if number > self.base.number() => {
+                self.blocks.get((number - self.base.number() - 1) as usize)
+            }
+            BlockId::Latest => self.blocks.last(),
+            _ => None,
+        }
+    }
+
+    fn base_hash(&self, id: BlockId) -> Option<H256> {
+        match id {
+            BlockId::Hash(hash) => Some(hash),
+            BlockId::Number(number) => self.chain.block_hash(number),
+            BlockId::Earliest => self.chain.block_hash(0),
+            BlockId::Latest => Some(self.base.hash()),
+        }
+    }
+
+    fn state_db_at(&self, id: BlockId) -> Option<(&StateDB, &Header)> {
+        if let Some(block) = self.overlay_block(id) {
+            return Some((&block.state_db, &block.header));
+        }
+        match self.base_hash(id) {
+            Some(hash) if hash == self.base.hash() => Some((&self.base_state_db, &self.base)),
+            _ => None,
+        }
+    }
+
+    fn state_from(&self, db: &StateDB, header: &Header) -> Option<State<StateDB>> {
+        State::from_existing(
+            db.boxed_clone(),
+            *header.state_root(),
+            self.engine.account_start_nonce(header.number()),
+            self.factories.clone(),
+        )
+        .ok()
+    }
+}
+
+impl Nonce for OverlayChain {
+    fn nonce(&self, address: &Address, id: BlockId) -> Option<U256> {
+        self.state_at(id).and_then(|s| s.nonce(address).ok())
+    }
+}
+
+impl Balance for OverlayChain {
+    fn balance(&self, address: &Address, state: StateOrBlock) -> Option<U256> {
+        match state {
+            StateOrBlock::State(s) => s.balance(address).ok(),
+            StateOrBlock::Block(id) => self.state_at(id).and_then(|s| s.balance(address).ok()),
+        }
+    }
+}
+
+impl BlockInfo for OverlayChain {
+    fn block_header(&self, id: BlockId) -> Option<encoded::Header> {
+        if let Some(block) = self.overlay_block(id) {
+            return Some(block.header.encoded());
+        }
+        self.base_hash(id)
+            .and_then(|hash| self.chain.block_header_data(&hash))
+    }
+
+    fn best_block_header(&self) -> Header {
+        self.head().clone()
+    }
+
+    fn block(&self, id: BlockId) -> Option<encoded::Block> {
+        if let Some(block) = self.overlay_block(id) {
+            return Some(block.block.clone());
+        }
+        self.base_hash(id).and_then(|hash| self.chain.block(&hash))
+    }
+
+    fn code_hash(&self, address: &Address, id: BlockId) -> Option<H256> {
+        self.state_at(id)
+            .and_then(|s| s.code_hash(address).unwrap_or(None))
+    }
+}
+
+impl CallContract for OverlayChain {
+    fn call_contract(&self, id: BlockId, address: Address, data: Bytes) -> Result<Bytes, String> {
+        match id {
+            BlockId::Latest => (),
+            BlockId::Hash(hash) if hash == self.head().hash() => (),
+            _ => return Err(CallError::StatePruned.to_string()),
+        }
+        let from = Address::default();
+        let transaction = TypedTransaction::Legacy(Transaction {
+            nonce: self.latest_nonce(&from),
+            action: Action::Call(address),
+            gas: U256::from(50_000_000),
+            gas_price: U256::default(),
+            value: U256::default(),
+            data,
+        })
+        .fake_sign(from);
+
+        self.call(&transaction, Default::default())
+            .map_err(|e| format!("{:?}", e))
+            .map(|executed| executed.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client::{BlockChainClient, ChainInfo};
+    use crypto::publickey::{Generator, Random};
+    use test_helpers::generate_dummy_client;
+
+    #[test]
+    fn overlay_blocks_do_not_touch_the_client() {
+        let client = generate_dummy_client(2);
+        let best = client.chain_info().best_block_number;
+        let keypair = Random.generate();
+        let sender = keypair.address();
+        let start_nonce = client.latest_nonce(&sender);
+
+        let mut overlay = client.overlay();
+        assert_eq!(overlay.fork_header().number(), best);
+
+        let transaction = TypedTransaction::Legacy(Transaction {
+            action: Action::Create,
+            value: U256::zero(),
+            data: Vec::new(),
+            gas: U256::from(100_000),
+            gas_price: U256::zero(),
+            nonce: start_nonce,
+        })
+        .sign(keypair.secret(), client.signing_chain_id());
+        let receipts = overlay
+            .push_block(Address::zero(), None, vec![transaction.clone()])
+            .unwrap()
+            .len();
+        assert_eq!(receipts, 1);
+        assert_eq!(overlay.depth(), 1);
+        assert_eq!(overlay.best_block_header().number(), best + 1);
+        assert_eq!(overlay.latest_nonce(&sender), start_nonce + 1);
+        let (state, header) = overlay.latest_state_and_header().unwrap();
+        assert_eq!(header.number(), best + 1);
+        assert_eq!(state.nonce(&sender).unwrap(), start_nonce + 1);
+        assert_eq!(
+            overlay.nonce(&sender, BlockId::Number(best)),
+            Some(start_nonce)
+        );
+        assert_eq!(
+            overlay.block_header(BlockId::Number(best)).unwrap().hash(),
+            client.best_block_header().hash()
+        );
+        assert_eq!(
+            overlay.block(BlockId::Latest).unwrap().transactions_count(),
+            1
+        );
+
+        // replaying the same nonce is rejected and leaves the overlay untouched
+        assert!(overlay
+            .push_block(Address::zero(), None, vec![transaction])
+            .is_err());
+        assert_eq!(overlay.depth(), 1);
+
+        assert_eq!(client.chain_info().best_block_number, best);
+        assert_eq!(client.latest_nonce(&sender), start_nonce);
+        assert!(client.block_header(BlockId::Number(best + 1)).is_none());
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prefetching of the state read by the blocks of an import round.
+//!
+//! This is not a pipeline: family verification and execution of a block need the committed
+//! state of its parent, so the blocks of a round are still verified and enacted one after
+//! another under the import lock. Overlapping them would mean executing on uncommitted state
+//! and undoing it when the parent turns out invalid, which the import path doesn't support. Only the disk access overlaps: while a block executes, the
+//! accounts touched by the blocks queued behind it are read on a separate thread. This puts
+//! their trie nodes in the database cache and their code in the shared code cache by the time
+//! those blocks are enacted.
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        Arc,
+    },
+    thread,
+};
+
+use ethereum_types::{Address, H256, U256};
+use types::transaction::Action;
+
+use factory::Factories;
+use state::State;
+use state_db::StateDB;
+use verification::PreverifiedBlock;
+
+/// Accounts touched by the given blocks, in the order they are first used.
+pub fn touched_accounts(blocks: &[PreverifiedBlock]) -> Vec<Address> {
+    let mut seen = HashSet::new();
+    let mut accounts = Vec::new();
+    {
+        let mut touch = |address: Address| {
+            if seen.insert(address) {
+                accounts.push(address);
+            }
+        };
+
+        for block in blocks {
+            touch(*block.header.author());
+            for transaction in &block.transactions {
+                touch(transaction.sender());
+                if let Action::Call(to) = transaction.tx().action {
+                    touch(to);
+                }
+            }
+            for withdrawal in block.withdrawals.iter().flatten() {
+                touch(withdrawal.address);
+            }
+        }
+    }
+    accounts
+}
+
+/// Background thread reading accounts from the state. Dropping it stops the thread.
+pub struct StatePrefetch {
+    stop: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl StatePrefetch {
+    /// Start loading `accounts` and their code from the state with the given root.
+    pub fn spawn(
+        db: StateDB,
+        root: H256,
+        account_start_nonce: U256,
+        factories: Factories,
+        accounts: Vec<Address>,
+    ) -> Option<Self> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let handle = thread::Builder::new()
+            .name("state-prefetch".into())
+            .spawn(move || {
+                let state = match State::from_existing(db, root, account_start_nonce, factories) {
+                    Ok(state) => state,
+                    Err(e) => {
+                        debug!(target: "client", "State prefetch unavailable for {}: {}", root, e);
+                        return;
+                    }
+                };
+                for address in accounts {
+                    if thread_stop.load(AtomicOrdering::Relaxed) {
+                        break;
+                    }
+                    if let Err(e) = state.code(&address) {
+                        debug!(target: "client", "State prefetch of {} failed: {}", address, e);
+                        break;
+                    }
+                }
+            })
+            .map_err(|e| warn!(target: "client", "Failed to spawn state prefetch thread: {}", e))
+            .ok()?;
+
+        Some(StatePrefetch {
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Wait until all accounts are read.
+    #[cfg(test)]
+    fn wait(mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("prefetch thread doesn't panic; qed");
+        }
+    }
+}
+
+impl Drop for StatePrefetch {
+    fn drop(&mut self) {
+        self.stop.store(true, AtomicOrdering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::publickey::{Generator, Random};
+    use hash::keccak;
+    use state::Backend;
+    use test_helpers::{get_temp_state, get_temp_state_db};
+    use types::{
+        header::Header,
+        transaction::{Transaction, TypedTransaction},
+    };
+
+    fn block_with(author: Address, to: Address) -> PreverifiedBlock {
+        let mut header = Header::new();
+        header.set_author(author);
+        let transaction = TypedTransaction::Legacy(Transaction {
+            action: Action::Call(to),
+            ..Default::default()
+        })
+        .sign(Random.generate().secret(), None);
+
+        PreverifiedBlock {
+            header,
+            transactions: vec![transaction],
+            uncles: Vec::new(),
+            withdrawals: None,
+            bytes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn touched_accounts_are_deduplicated_in_order() {
+        let author = Address::from_low_u64_be(1);
+        let to = Address::from_low_u64_be(2);
+        let blocks = vec![block_with(author, to), block_with(to, author)];
+
+        let accounts = touched_accounts(&blocks);
+        assert_eq!(accounts.len(), 4);
+        assert_eq!(accounts[0], author);
+        assert_eq!(accounts[1], blocks[0].transactions[0].sender());
+        assert_eq!(accounts[2], to);
+        assert_eq!(accounts[3], blocks[1].transactions[0].sender());
+    }
+
+    #[test]
+    fn prefetch_fills_shared_code_cache() {
+        let address = Address::from_low_u64_be(7);
+        let code = vec![0x60, 0x00, 0x60, 0x00, 0xf3];
+        let code_hash = keccak(&code);
+        let mut state = get_temp_state();
+        state.init_code(&address, code.clone()).unwrap();
+        state.commit().unwrap();
+        let (root, db) = state.drop();
+        // Shares the code cache with the database the prefetch reads from.
+        let importer_db = db.boxed_clone();
+        assert!(importer_db.get_cached_code(&code_hash).is_none());
+
+        StatePrefetch::spawn(
+            db,
+            root,
+            U256::zero(),
+            Default::default(),
+            vec![Address::from_low_u64_be(8), address],
+        )
+        .expect("prefetch thread is spawned")
+        .wait();
+
+        assert_eq!(
+            importer_db.get_cached_code(&code_hash),
+            Some(Arc::new(code))
+        );
+
+        // an unknown root is skipped quietly
+        let prefetch = StatePrefetch::spawn(
+            get_temp_state_db(),
+            H256::from_low_u64_be(1),
+            U256::zero(),
+            Default::default(),
+            vec![address],
+        );
+        drop(prefetch);
+    }
+}
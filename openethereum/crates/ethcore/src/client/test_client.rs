@@ -17,12 +17,14 @@
 //! Test client.
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
     str::FromStr,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrder},
         Arc,
     },
+    time::Duration,
 };
 
 use blockchain::{BlockReceipts, TreeRoute};
@@ -60,18 +62,21 @@ use block::{ClosedBlock, OpenBlock, SealedBlock};
 use call_contract::{CallContract, RegistryInfo};
 use client::{
     traits::{ForceUpdateSealing, TransactionRequest},
-    AccountData, BadBlocks, Balance, BlockChain, BlockChainClient, BlockChainInfo, BlockId,
-    BlockInfo, BlockProducer, BlockStatus, BroadcastProposalBlock, Call, CallAnalytics, ChainInfo,
-    EngineInfo, ImportBlock, ImportSealedBlock, IoClient, LastHashes, Mode, Nonce,
-    PrepareOpenBlock, ProvingBlockChainClient, ReopenBlock, ScheduleInfo, SealedBlockImporter,
-    StateClient, StateOrBlock, TraceFilter, TraceId, TransactionId, TransactionInfo, UncleId,
+    AccountData, BackupStatus, BadBlock, BadBlocks, Balance, BlockChain, BlockChainClient,
+    BlockChainInfo, BlockId, BlockInfo, BlockProducer, BlockStatus, BroadcastProposalBlock,
+    CacheSizes, Call, CallAnalytics, ChainInfo, EngineInfo, ImportBlock, ImportSealedBlock,
+    IoClient, LastHashes, Mode, Nonce, PrepareOpenBlock, ProvingBlockChainClient, ReopenBlock,
+    ReplayOptions, ScheduleInfo, SealedBlockImporter, StateClient, StateOrBlock, StateOverride,
+    TraceFilter, TraceId, TransactionId, TransactionInfo, UncleId,
 };
 use engines::EthEngine;
 use error::{Error, EthcoreResult};
 use executed::CallError;
 use executive::Executed;
 use journaldb;
-use miner::{self, Miner, MinerService};
+use miner::{self, BlockFees, Miner, MinerService};
+use pod_account::PodAccount;
+use pod_state::PodState;
 use spec::Spec;
 use state::StateInfo;
 use state_db::StateDB;
@@ -125,10 +130,24 @@ pub struct TestBlockChainClient {
     pub traces: RwLock<Option<Vec<LocalizedTrace>>>,
     /// Pruning history size to report.
     pub history: RwLock<Option<u64>>,
+    /// First block whose body and receipts are reported as stored.
+    pub earliest_history: RwLock<u64>,
+    /// First block whose transactions are reported as indexed.
+    pub earliest_transaction_index: RwLock<u64>,
+    /// Transactions reported as no longer indexed.
+    pub unindexed_transactions: RwLock<HashSet<H256>>,
     /// Is disabled
     pub disabled: AtomicBool,
     /// Transaction hashes producer
     pub new_transaction_hashes: RwLock<Option<crossbeam_channel::Sender<H256>>>,
+    /// Last cache budgets set.
+    pub cache_sizes: RwLock<CacheSizes>,
+    /// State overrides passed to the last call.
+    pub call_overrides: RwLock<StateOverride>,
+    /// Options passed to the last replay.
+    pub replay_options: RwLock<ReplayOptions>,
+    /// Paths passed to backup requests.
+    pub backups: RwLock<Vec<PathBuf>>,
 }
 
 /// Used for generating test client blocks.
@@ -196,9 +215,16 @@ impl TestBlockChainClient {
             first_block: RwLock::new(None),
             traces: RwLock::new(None),
             history: RwLock::new(None),
+            earliest_history: RwLock::new(0),
+            earliest_transaction_index: RwLock::new(0),
+            unindexed_transactions: RwLock::new(HashSet::new()),
             disabled: AtomicBool::new(false),
             error_on_logs: RwLock::new(None),
             new_transaction_hashes: RwLock::new(None),
+            cache_sizes: RwLock::new(CacheSizes::default()),
+            call_overrides: RwLock::new(StateOverride::new()),
+            replay_options: RwLock::new(ReplayOptions::default()),
+            backups: RwLock::new(Vec::new()),
         };
 
         // insert genesis hash.
@@ -412,6 +438,21 @@ impl TestBlockChainClient {
         *self.history.write() = h;
     }
 
+    /// Set reported first block with unexpired history.
+    pub fn set_earliest_history(&self, n: u64) {
+        *self.earliest_history.write() = n;
+    }
+
+    /// Set reported first block whose transactions are indexed.
+    pub fn set_earliest_transaction_index(&self, n: u64) {
+        *self.earliest_transaction_index.write() = n;
+    }
+
+    /// Report the transaction with given hash as no longer indexed.
+    pub fn add_unindexed_transaction(&self, hash: H256) {
+        self.unindexed_transactions.write().insert(hash);
+    }
+
     /// Returns true if the client has been disabled.
     pub fn is_disabled(&self) -> bool {
         self.disabled.load(AtomicOrder::SeqCst)
@@ -678,7 +719,9 @@ impl Call for TestBlockChainClient {
         _analytics: CallAnalytics,
         _state: &mut Self::State,
         _header: &Header,
+        overrides: &StateOverride,
     ) -> Result<Executed, CallError> {
+        *self.call_overrides.write() = overrides.clone();
         self.execution_result.read().clone().unwrap()
     }
 
@@ -687,10 +730,26 @@ impl Call for TestBlockChainClient {
         txs: &[(SignedTransaction, CallAnalytics)],
         state: &mut Self::State,
         header: &Header,
+        overrides: &StateOverride,
     ) -> Result<Vec<Executed>, CallError> {
         let mut res = Vec::with_capacity(txs.len());
         for &(ref tx, analytics) in txs {
-            res.push(self.call(tx, analytics, state, header)?);
+            res.push(self.call(tx, analytics, state, header, overrides)?);
+        }
+        Ok(res)
+    }
+
+    fn call_many_with_overrides(
+        &self,
+        txs: &[(SignedTransaction, CallAnalytics, StateOverride)],
+        state: &mut Self::State,
+        header: &Header,
+        options: ReplayOptions,
+    ) -> Result<Vec<Executed>, CallError> {
+        *self.replay_options.write() = options;
+        let mut res = Vec::with_capacity(txs.len());
+        for &(ref tx, analytics, ref overrides) in txs {
+            res.push(self.call(tx, analytics, state, header, overrides)?);
         }
         Ok(res)
     }
@@ -700,6 +759,7 @@ impl Call for TestBlockChainClient {
         _t: &SignedTransaction,
         _state: &Self::State,
         _header: &Header,
+        _timeout: Option<Duration>,
     ) -> Result<U256, CallError> {
         Ok(21000.into())
     }
@@ -743,29 +803,68 @@ impl EngineInfo for TestBlockChainClient {
 }
 
 impl BadBlocks for TestBlockChainClient {
-    fn bad_blocks(&self) -> Vec<(Unverified, String)> {
-        vec![(
-            Unverified {
+    fn bad_blocks(&self) -> Vec<BadBlock> {
+        vec![BadBlock {
+            block: Unverified {
                 header: Default::default(),
                 transactions: vec![],
                 uncles: vec![],
+                withdrawals: None,
                 bytes: vec![1, 2, 3],
             },
-            "Invalid block".into(),
-        )]
+            reason: "Invalid block".into(),
+            reported_at: 0,
+        }]
     }
 }
 
 impl BlockChainClient for TestBlockChainClient {
-    fn replay(&self, _id: TransactionId, _analytics: CallAnalytics) -> Result<Executed, CallError> {
+    fn replay(
+        &self,
+        _id: TransactionId,
+        _analytics: CallAnalytics,
+        options: ReplayOptions,
+    ) -> Result<Executed, CallError> {
+        *self.replay_options.write() = options;
         self.execution_result.read().clone().unwrap()
     }
 
+    fn replay_prestate(
+        &self,
+        _id: TransactionId,
+        _timeout: Option<Duration>,
+    ) -> Result<PodState, CallError> {
+        let nonces = self.nonces.read();
+        let code = self.code.read();
+        let storage = self.storage.read();
+        Ok(PodState::from(
+            self.balances
+                .read()
+                .iter()
+                .map(|(address, balance)| {
+                    let account = PodAccount {
+                        balance: *balance,
+                        nonce: nonces.get(address).cloned().unwrap_or_default(),
+                        code: code.get(address).cloned(),
+                        storage: storage
+                            .iter()
+                            .filter(|&(&(a, _), _)| a == *address)
+                            .map(|(&(_, key), value)| (key, *value))
+                            .collect(),
+                    };
+                    (*address, account)
+                })
+                .collect(),
+        ))
+    }
+
     fn replay_block_transactions(
         &self,
         _block: BlockId,
         _analytics: CallAnalytics,
+        options: ReplayOptions,
     ) -> Result<Box<dyn Iterator<Item = (H256, Executed)>>, CallError> {
+        *self.replay_options.write() = options;
         Ok(Box::new(
             self.traces
                 .read()
@@ -792,6 +891,9 @@ impl BlockChainClient for TestBlockChainClient {
     fn code(&self, address: &Address, state: StateOrBlock) -> Option<Option<Bytes>> {
         match state {
             StateOrBlock::Block(BlockId::Latest) => Some(self.code.read().get(address).cloned()),
+            StateOrBlock::Block(BlockId::Hash(hash)) if hash == *self.last_hash.read() => {
+                Some(self.code.read().get(address).cloned())
+            }
             _ => None,
         }
     }
@@ -963,6 +1065,19 @@ impl BlockChainClient for TestBlockChainClient {
         None
     }
 
+    fn block_fees(&self, id: BlockId) -> Option<BlockFees> {
+        let header = self
+            .block_header(id)?
+            .decode(BlockNumber::max_value())
+            .ok()?;
+        let transactions = self.block_body(id)?.transactions();
+        let receipts = self
+            .block_receipts(&header.hash())
+            .map(|r| r.receipts)
+            .unwrap_or_default();
+        Some(BlockFees::new(&header, &transactions, &receipts))
+    }
+
     fn block_receipts(&self, hash: &H256) -> Option<BlockReceipts> {
         // starts with 'f' ?
         if *hash
@@ -999,8 +1114,23 @@ impl BlockChainClient for TestBlockChainClient {
         Default::default()
     }
 
-    fn filter_traces(&self, _filter: TraceFilter) -> Option<Vec<LocalizedTrace>> {
-        self.traces.read().clone()
+    fn filter_traces(&self, filter: TraceFilter) -> Option<Vec<LocalizedTrace>> {
+        let range = match (filter.range.start, filter.range.end) {
+            (BlockId::Number(start), BlockId::Number(end)) => Some(start..=end),
+            _ => None,
+        };
+        self.traces.read().clone().map(|traces| {
+            traces
+                .into_iter()
+                .filter(|trace| {
+                    range
+                        .as_ref()
+                        .map_or(true, |r| r.contains(&trace.block_number))
+                })
+                .skip(filter.after.unwrap_or(0))
+                .take(filter.count.unwrap_or(usize::max_value()))
+                .collect()
+        })
     }
 
     fn trace(&self, _trace: TraceId) -> Option<LocalizedTrace> {
@@ -1035,6 +1165,10 @@ impl BlockChainClient for TestBlockChainClient {
         unimplemented!();
     }
 
+    fn set_cache_sizes(&self, sizes: CacheSizes) {
+        *self.cache_sizes.write() = sizes;
+    }
+
     fn spec_name(&self) -> String {
         "foundation".into()
     }
@@ -1043,6 +1177,21 @@ impl BlockChainClient for TestBlockChainClient {
         unimplemented!();
     }
 
+    fn backup(&self, path: &Path) -> std::io::Result<()> {
+        self.backups.write().push(path.to_owned());
+        Ok(())
+    }
+
+    fn backup_status(&self) -> BackupStatus {
+        match self.backups.read().last() {
+            Some(path) => BackupStatus::Done {
+                path: path.clone(),
+                copied: 0,
+            },
+            None => BackupStatus::Idle,
+        }
+    }
+
     fn disable(&self) {
         self.disabled.store(true, AtomicOrder::SeqCst);
     }
@@ -1050,7 +1199,9 @@ impl BlockChainClient for TestBlockChainClient {
     fn pruning_info(&self) -> PruningInfo {
         let best_num = self.chain_info().best_block_number;
         PruningInfo {
-            earliest_chain: 1,
+            earliest_chain: ::std::cmp::max(1, *self.earliest_history.read()),
+            earliest_history: *self.earliest_history.read(),
+            earliest_transaction_index: *self.earliest_transaction_index.read(),
             earliest_state: self
                 .history
                 .read()
@@ -1060,6 +1211,10 @@ impl BlockChainClient for TestBlockChainClient {
         }
     }
 
+    fn is_transaction_unindexed(&self, hash: &H256) -> bool {
+        self.unindexed_transactions.read().contains(hash)
+    }
+
     fn create_transaction(
         &self,
         TransactionRequest {
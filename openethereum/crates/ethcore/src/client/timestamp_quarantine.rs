@@ -0,0 +1,219 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Holds blocks rejected for having a timestamp too far in the future until
+//! the local clock catches up with them.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::atomic::{AtomicU64, Ordering as AtomicOrdering},
+    time::Duration,
+};
+
+use ethereum_types::H256;
+use parking_lot::Mutex;
+use stats::{PrometheusMetrics, PrometheusRegistry};
+use verification::queue::kind::blocks::Unverified;
+
+/// Default number of blocks kept in quarantine.
+pub const DEFAULT_QUARANTINE_SIZE: usize = 1024;
+
+#[derive(Default)]
+struct Quarantined {
+    /// Quarantined blocks by timestamp.
+    by_timestamp: BTreeMap<u64, Vec<Unverified>>,
+    hashes: HashSet<H256>,
+}
+
+/// Blocks rejected on timestamp grounds, waiting to be re-evaluated.
+pub struct TimestampQuarantine {
+    limit: usize,
+    blocks: Mutex<Quarantined>,
+    quarantined: AtomicU64,
+    released: AtomicU64,
+    evicted: AtomicU64,
+}
+
+impl Default for TimestampQuarantine {
+    fn default() -> Self {
+        TimestampQuarantine::new(DEFAULT_QUARANTINE_SIZE)
+    }
+}
+
+impl TimestampQuarantine {
+    /// Create a quarantine holding at most `limit` blocks.
+    pub fn new(limit: usize) -> Self {
+        TimestampQuarantine {
+            limit,
+            blocks: Mutex::new(Quarantined::default()),
+            quarantined: AtomicU64::new(0),
+            released: AtomicU64::new(0),
+            evicted: AtomicU64::new(0),
+        }
+    }
+
+    /// Put a block in quarantine. Returns `false` if it was already quarantined.
+    ///
+    /// When full, the block with the timestamp furthest in the future is evicted, since it is
+    /// the least likely to ever become valid.
+    pub fn insert(&self, block: Unverified) -> bool {
+        let mut blocks = self.blocks.lock();
+        if self.limit == 0 || !blocks.hashes.insert(block.hash()) {
+            return false;
+        }
+        blocks
+            .by_timestamp
+            .entry(block.header.timestamp())
+            .or_insert_with(Vec::new)
+            .push(block);
+        self.quarantined.fetch_add(1, AtomicOrdering::Relaxed);
+
+        while blocks.hashes.len() > self.limit {
+            let latest = *blocks
+                .by_timestamp
+                .keys()
+                .next_back()
+                .expect("more blocks than the limit; qed");
+            let evicted = {
+                let entry = blocks
+                    .by_timestamp
+                    .get_mut(&latest)
+                    .expect("key just read from the map; qed");
+                let evicted = entry.pop().expect("empty entries are never kept; qed");
+                if entry.is_empty() {
+                    blocks.by_timestamp.remove(&latest);
+                }
+                evicted
+            };
+            blocks.hashes.remove(&evicted.hash());
+            self.evicted.fetch_add(1, AtomicOrdering::Relaxed);
+            debug!(target: "client", "Evicted block {} from timestamp quarantine", evicted.hash());
+        }
+        true
+    }
+
+    /// Remove and return all blocks whose timestamp is no longer ahead of `now` by more than
+    /// `max_drift`, oldest first.
+    pub fn release(&self, now: Duration, max_drift: Duration) -> Vec<Unverified> {
+        let limit = (now + max_drift).as_secs();
+        let mut blocks = self.blocks.lock();
+        let later = blocks.by_timestamp.split_off(&limit.saturating_add(1));
+        let ready = ::std::mem::replace(&mut blocks.by_timestamp, later);
+        let ready = ready.into_iter().flat_map(|(_, b)| b).collect::<Vec<_>>();
+        for block in &ready {
+            blocks.hashes.remove(&block.hash());
+        }
+        self.released
+            .fetch_add(ready.len() as u64, AtomicOrdering::Relaxed);
+        ready
+    }
+
+    /// Whether the given block is currently quarantined.
+    pub fn contains(&self, hash: &H256) -> bool {
+        self.blocks.lock().hashes.contains(hash)
+    }
+
+    /// Number of quarantined blocks.
+    pub fn len(&self) -> usize {
+        self.blocks.lock().hashes.len()
+    }
+
+    /// Whether the quarantine is empty.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.lock().hashes.is_empty()
+    }
+}
+
+impl PrometheusMetrics for TimestampQuarantine {
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        r.register_gauge(
+            "timestamp_quarantine_blocks",
+            "Blocks quarantined for a future timestamp",
+            self.len() as i64,
+        );
+        r.register_counter(
+            "timestamp_quarantine_inserted",
+            "Blocks put in timestamp quarantine",
+            self.quarantined.load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_counter(
+            "timestamp_quarantine_released",
+            "Blocks released from timestamp quarantine for re-import",
+            self.released.load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_counter(
+            "timestamp_quarantine_evicted",
+            "Blocks evicted from a full timestamp quarantine",
+            self.evicted.load(AtomicOrdering::Relaxed) as i64,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimestampQuarantine;
+    use std::time::Duration;
+    use types::header::Header;
+    use verification::queue::kind::blocks::Unverified;
+
+    fn block(number: u64, timestamp: u64) -> Unverified {
+        let mut header = Header::new();
+        header.set_number(number);
+        header.set_timestamp(timestamp);
+        Unverified {
+            header,
+            transactions: Vec::new(),
+            uncles: Vec::new(),
+            withdrawals: None,
+            bytes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn releases_blocks_once_clock_catches_up() {
+        let quarantine = TimestampQuarantine::new(8);
+        assert!(quarantine.insert(block(1, 100)));
+        assert!(quarantine.insert(block(2, 130)));
+        assert!(!quarantine.insert(block(1, 100)));
+        assert_eq!(quarantine.len(), 2);
+
+        let drift = Duration::from_secs(15);
+        assert!(quarantine
+            .release(Duration::from_secs(80), drift)
+            .is_empty());
+
+        let released = quarantine.release(Duration::from_secs(85), drift);
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].header.number(), 1);
+        assert_eq!(quarantine.len(), 1);
+
+        assert_eq!(quarantine.release(Duration::from_secs(200), drift).len(), 1);
+        assert_eq!(quarantine.len(), 0);
+    }
+
+    #[test]
+    fn evicts_furthest_future_block_when_full() {
+        let quarantine = TimestampQuarantine::new(2);
+        let far = block(3, 500);
+        let far_hash = far.hash();
+        quarantine.insert(block(1, 100));
+        quarantine.insert(far);
+        quarantine.insert(block(2, 200));
+
+        assert_eq!(quarantine.len(), 2);
+        assert!(!quarantine.contains(&far_hash));
+    }
+}
@@ -16,7 +16,12 @@
 
 //! Traits implemented by client.
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use blockchain::{BlockReceipts, TreeRoute};
 use bytes::Bytes;
@@ -26,6 +31,7 @@ use ethereum_types::{Address, H256, U256};
 use evm::Schedule;
 use itertools::Itertools;
 use kvdb::DBValue;
+use time_utils::SharedClock;
 use types::{
     basic_account::BasicAccount,
     block_status::BlockStatus,
@@ -39,22 +45,86 @@ use types::{
     log_entry::LocalizedLogEntry,
     pruning_info::PruningInfo,
     receipt::LocalizedReceipt,
+    state_override::StateOverride,
     trace_filter::Filter as TraceFilter,
     transaction::{self, Action, LocalizedTransaction, SignedTransaction, TypedTxId},
     BlockNumber,
 };
-use vm::LastHashes;
+use vm::{EnvInfo, LastHashes};
 
 use block::{ClosedBlock, OpenBlock, SealedBlock};
-use client::Mode;
+use client::{BadBlock, CacheSizes, Mode};
 use engines::EthEngine;
 use error::{Error, EthcoreResult};
 use executed::CallError;
 use executive::Executed;
+use miner::BlockFees;
+use pod_state::PodState;
 use state::StateInfo;
 use trace::LocalizedTrace;
 use verification::queue::{kind::blocks::Unverified, QueueInfo as BlockQueueInfo};
 
+/// Block environment fields replaced when replaying transactions. Fields left as `None`
+/// keep the values of the replayed block.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ReplayOptions {
+    /// Block timestamp.
+    pub timestamp: Option<u64>,
+    /// Block number. Together with the timestamp it selects the fork rules used.
+    pub number: Option<BlockNumber>,
+    /// Block base fee.
+    pub base_fee: Option<U256>,
+    /// Block difficulty.
+    pub difficulty: Option<U256>,
+    /// Block randomness returned by `PREVRANDAO`.
+    pub random: Option<H256>,
+    /// Block gas limit.
+    pub gas_limit: Option<U256>,
+}
+
+impl ReplayOptions {
+    /// Replace the overridden fields of `env_info`.
+    pub fn apply(&self, env_info: &mut EnvInfo) {
+        if let Some(timestamp) = self.timestamp {
+            env_info.timestamp = timestamp;
+        }
+        if let Some(number) = self.number {
+            env_info.number = number;
+        }
+        if let Some(base_fee) = self.base_fee {
+            env_info.base_fee = Some(base_fee);
+        }
+        if let Some(difficulty) = self.difficulty {
+            env_info.difficulty = difficulty;
+        }
+        if let Some(random) = self.random {
+            env_info.random = Some(random);
+        }
+        if let Some(gas_limit) = self.gas_limit {
+            env_info.gas_limit = gas_limit;
+        }
+    }
+}
+
+/// Progress of the database backup started by `BlockChainClient::backup`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackupStatus {
+    /// No backup was started since the client was started.
+    Idle,
+    /// The backup into `path` is running, `copied` database entries were written so far.
+    Running { path: PathBuf, copied: u64 },
+    /// The backup into `path` is complete.
+    Done { path: PathBuf, copied: u64 },
+    /// The backup into `path` failed.
+    Failed { path: PathBuf, error: String },
+}
+
+impl Default for BackupStatus {
+    fn default() -> Self {
+        BackupStatus::Idle
+    }
+}
+
 /// State information to be used during client query
 pub enum StateOrBlock {
     /// State to be used, may be pending
@@ -176,29 +246,46 @@ pub trait Call {
     type State: StateInfo;
 
     /// Makes a non-persistent transaction call.
+    /// Non-empty `overrides` are applied to a copy of `state`, which is then left untouched.
     fn call(
         &self,
         tx: &SignedTransaction,
         analytics: CallAnalytics,
         state: &mut Self::State,
         header: &Header,
+        overrides: &StateOverride,
     ) -> Result<Executed, CallError>;
 
     /// Makes multiple non-persistent but dependent transaction calls.
     /// Returns a vector of successes or a failure if any of the transaction fails.
+    /// Non-empty `overrides` are applied once, before the first call, to a copy of `state`.
     fn call_many(
         &self,
         txs: &[(SignedTransaction, CallAnalytics)],
         state: &mut Self::State,
         header: &Header,
+        overrides: &StateOverride,
+    ) -> Result<Vec<Executed>, CallError>;
+
+    /// Makes multiple non-persistent but dependent transaction calls in the block environment
+    /// replaced by `options`. The overrides of each call are applied to `state` right before
+    /// it, on top of the changes made by the previous calls.
+    fn call_many_with_overrides(
+        &self,
+        txs: &[(SignedTransaction, CallAnalytics, StateOverride)],
+        state: &mut Self::State,
+        header: &Header,
+        options: ReplayOptions,
     ) -> Result<Vec<Executed>, CallError>;
 
     /// Estimates how much gas will be necessary for a call.
+    /// `timeout` caps the whole estimation, bounded by the client configuration.
     fn estimate_gas(
         &self,
         t: &SignedTransaction,
         state: &Self::State,
         header: &Header,
+        timeout: Option<Duration>,
     ) -> Result<U256, CallError>;
 }
 
@@ -229,8 +316,9 @@ pub trait IoClient: Sync + Send {
 
 /// Provides recently seen bad blocks.
 pub trait BadBlocks {
-    /// Returns a list of blocks that were recently not imported because they were invalid.
-    fn bad_blocks(&self) -> Vec<(Unverified, String)>;
+    /// Returns a list of blocks that were recently not imported because they were invalid,
+    /// newest first.
+    fn bad_blocks(&self) -> Vec<BadBlock>;
 }
 
 /// Blockchain database client. Owns and manages a blockchain and a block queue.
@@ -339,6 +427,9 @@ pub trait BlockChainClient:
     /// Get block receipts data by block header hash.
     fn block_receipts(&self, hash: &H256) -> Option<BlockReceipts>;
 
+    /// Get base fee, gas usage and priority fees paid in the given block.
+    fn block_fees(&self, id: BlockId) -> Option<BlockFees>;
+
     /// Get block queue information.
     fn queue_info(&self) -> BlockQueueInfo;
 
@@ -356,16 +447,38 @@ pub trait BlockChainClient:
     /// Returns logs matching given filter. If one of the filtering block cannot be found, returns the block id that caused the error.
     fn logs(&self, filter: Filter) -> Result<Vec<LocalizedLogEntry>, BlockId>;
 
-    /// Replays a given transaction for inspection.
-    fn replay(&self, t: TransactionId, analytics: CallAnalytics) -> Result<Executed, CallError>;
+    /// Estimated cost of a `logs` query over the canonical blocks `from..=to`, as the number of
+    /// blocks whose receipts have to be read.
+    fn logs_cost_estimate(&self, _filter: &Filter, from: BlockNumber, to: BlockNumber) -> u64 {
+        to.saturating_sub(from).saturating_add(1)
+    }
+
+    /// Replays a given transaction for inspection. The replay is interrupted after the
+    /// timeout of `analytics`, if any, bounded by the client configuration.
+    fn replay(
+        &self,
+        t: TransactionId,
+        analytics: CallAnalytics,
+        options: ReplayOptions,
+    ) -> Result<Executed, CallError>;
 
     /// Replays all the transactions in a given block for inspection.
     fn replay_block_transactions(
         &self,
         block: BlockId,
         analytics: CallAnalytics,
+        options: ReplayOptions,
     ) -> Result<Box<dyn Iterator<Item = (H256, Executed)>>, CallError>;
 
+    /// Replays a given transaction and returns the accounts and storage slots it accessed,
+    /// as they were before the transaction. The replay is interrupted after `timeout`, if any,
+    /// bounded by the client configuration.
+    fn replay_prestate(
+        &self,
+        t: TransactionId,
+        timeout: Option<Duration>,
+    ) -> Result<PodState, CallError>;
+
     /// Returns traces matching given filter.
     fn filter_traces(&self, filter: TraceFilter) -> Option<Vec<LocalizedTrace>>;
 
@@ -459,12 +572,23 @@ pub trait BlockChainClient:
     /// Set the mode.
     fn set_mode(&self, mode: Mode);
 
+    /// Change the in-memory cache budgets without restarting.
+    fn set_cache_sizes(&self, sizes: CacheSizes);
+
     /// Get the chain spec name.
     fn spec_name(&self) -> String;
 
     /// Set the chain via a spec name.
     fn set_spec_name(&self, spec_name: String) -> Result<(), ()>;
 
+    /// Start writing a consistent copy of the database into `path` on a background thread,
+    /// while the client keeps running. Fails if a backup is already running.
+    /// Block import and other database writes are paused until the copy is complete.
+    fn backup(&self, path: &Path) -> std::io::Result<()>;
+
+    /// Progress of the last backup started with `backup`.
+    fn backup_status(&self) -> BackupStatus;
+
     /// Disable the client from importing blocks. This cannot be undone in this session and indicates
     /// that a subsystem has reason to believe this executable incapable of syncing the chain.
     fn disable(&self);
@@ -478,6 +602,10 @@ pub trait BlockChainClient:
     /// Returns information about pruning/data availability.
     fn pruning_info(&self) -> PruningInfo;
 
+    /// Returns true if the transaction with given hash is in a block whose
+    /// transactions are no longer indexed.
+    fn is_transaction_unindexed(&self, hash: &H256) -> bool;
+
     /// Returns a transaction signed with the key configured in the engine signer.
     fn create_transaction(
         &self,
@@ -493,6 +621,11 @@ pub trait BlockChainClient:
 
     /// Returns true, if underlying import queue is processing possible fork at the moment
     fn is_processing_fork(&self) -> bool;
+
+    /// Wall-clock block timestamps are checked against.
+    fn clock(&self) -> SharedClock {
+        SharedClock::default()
+    }
 }
 
 /// The data required for a `Client` to create a transaction.
@@ -652,6 +785,11 @@ pub trait ProvingBlockChainClient: BlockChainClient {
 pub trait BlockChainReset {
     /// reset to best_block - n
     fn reset(&self, num: u32) -> Result<(), String>;
+
+    /// reset the chain head to the given block, which may be on a side chain.
+    /// Canon chain blocks above the common ancestor are removed and the target's
+    /// branch becomes the canon chain.
+    fn reset_to(&self, target: BlockId) -> Result<(), String>;
 }
 
 /// Provides a method for importing/exporting blocks
@@ -673,9 +811,11 @@ pub trait ImportExportBlocks {
     /// For hex format imports, it attempts to read the blocks on a line by line basis.
     /// For binary format imports, reads the 8 byte RLP header in order to decode the block
     /// length to be read.
+    /// Blocks numbered below `resume_from` are skipped without being fully decoded.
     fn import_blocks<'a>(
         &self,
         source: Box<dyn std::io::Read + 'a>,
         format: Option<DataFormat>,
+        resume_from: Option<BlockNumber>,
     ) -> Result<(), String>;
 }
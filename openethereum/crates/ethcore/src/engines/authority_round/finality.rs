@@ -369,4 +369,29 @@ mod tests {
         assert_eq!(finality.unfinalized_hashes().next(), Some(&hashes[11].0));
         assert_eq!(finality.subchain_head(), Some(hashes[11].0));
     }
+
+    #[test]
+    fn finalize_across_2_3_transition() {
+        let signers: Vec<_> = (0..6).map(|_| Address::random()).collect();
+        let mut finality = RollingFinality::blank(signers.clone(), 2);
+        let hashes: Vec<_> = (0..3).map(|_| H256::random()).collect();
+
+        // before the transition 4 / 6 signers are enough.
+        assert_eq!(
+            finality
+                .push_hash(hashes[0], 1, signers[0..4].to_vec())
+                .unwrap(),
+            vec![hashes[0]]
+        );
+
+        // from the transition on more than 2/3 of the signers are required.
+        assert!(finality
+            .push_hash(hashes[1], 2, signers[0..4].to_vec())
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            finality.push_hash(hashes[2], 3, vec![signers[4]]).unwrap(),
+            vec![hashes[1]]
+        );
+    }
 }
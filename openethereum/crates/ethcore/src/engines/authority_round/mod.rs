@@ -41,7 +41,7 @@ use std::{
         atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering},
         Arc, Weak,
     },
-    time::{Duration, UNIX_EPOCH},
+    time::{Duration, Instant, UNIX_EPOCH},
     u64,
 };
 
@@ -61,6 +61,8 @@ use crypto::publickey::{self, Signature};
 use engines::{
     block_reward,
     block_reward::{BlockRewardContract, RewardKind},
+    message_filter::MessageFilter,
+    metrics::SealingMetrics,
     ConstructedVerifier, Engine, EngineError, Seal, SealingState,
 };
 use error::{BlockError, Error, ErrorKind};
@@ -71,15 +73,17 @@ use hash::keccak;
 use io::{IoContext, IoHandler, IoService, TimerToken};
 use itertools::{self, Itertools};
 use lru_cache::LruCache;
-use machine::{AuxiliaryData, Call, EthereumMachine};
+use machine::{AuxiliaryData, Call, EthereumMachine, Machine};
 use parking_lot::{Mutex, RwLock};
 use rand::rngs::OsRng;
 use rlp::{encode, Decodable, DecoderError, Encodable, Rlp, RlpStream};
-use time_utils::CheckedSystemTime;
+use stats::PrometheusRegistry;
+use time_utils::{CheckedSystemTime, Clock, SharedClock};
 use types::{
     ancestry_action::AncestryAction,
     header::{ExtendedHeader, Header},
     ids::BlockId,
+    timestamp::{BlockTimestamp, StepDuration},
     transaction::SignedTransaction,
     BlockNumber,
 };
@@ -93,12 +97,12 @@ pub(crate) mod util;
 /// `AuthorityRound` params.
 pub struct AuthorityRoundParams {
     /// A map defining intervals of blocks with the given times (in seconds) to wait before next
-    /// block or authority switching. The keys in the map are steps of starting blocks of those
-    /// periods. The entry at `0` should be defined.
+    /// block or authority switching. The keys in the map are timestamps of starting blocks of
+    /// those periods. The entry at `0` should be defined.
     ///
     /// Wait times (durations) are additionally required to be less than 65535 since larger values
     /// lead to slow block issuance.
-    pub step_durations: BTreeMap<u64, u64>,
+    pub step_durations: BTreeMap<BlockTimestamp, StepDuration>,
     /// Starting step,
     pub start_step: Option<u64>,
     /// Valid validators.
@@ -136,8 +140,16 @@ pub struct AuthorityRoundParams {
     /// The block numbers at which the bytecodes should be rewritten for
     /// the specified contracts (can be more than one per block)
     rewrite_bytecode_transitions: BTreeMap<BlockNumber, BTreeMap<Address, Bytes>>,
+    /// Block from which header timestamps are validated against the median of the recent
+    /// ancestor timestamps instead of the parent timestamp. Blocks stay ordered by their step.
+    pub median_time_past_transition: BlockNumber,
+    /// Number of ancestors the median timestamp is taken over.
+    pub median_time_past_window: usize,
 }
 
+/// Default number of ancestors for median-time-past timestamp validation.
+const DEFAULT_MEDIAN_TIME_PAST_WINDOW: usize = 11;
+
 const U16_MAX: usize = ::std::u16::MAX as usize;
 
 /// The number of recent block hashes for which the gas limit override is memoized.
@@ -154,18 +166,20 @@ impl From<ethjson::spec::AuthorityRoundParams> for AuthorityRoundParams {
                 warn!(target: "engine", "step duration is too high ({}), setting it to {}", step_duration_usize, U16_MAX);
                 step_duration_usize = U16_MAX;
             }
-            step_duration_usize as u64
+            StepDuration::from_secs(step_duration_usize as u64)
         };
         let step_durations: BTreeMap<_, _> = match p.step_duration {
             ethjson::spec::StepDuration::Single(u) => {
-                iter::once((0, map_step_duration(u))).collect()
+                iter::once((BlockTimestamp::new(0), map_step_duration(u))).collect()
             }
             ethjson::spec::StepDuration::Transitions(tr) => {
                 if tr.is_empty() {
                     panic!("AuthorityRoundParams: step duration transitions cannot be empty");
                 }
                 tr.into_iter()
-                    .map(|(timestamp, u)| (timestamp.into(), map_step_duration(u)))
+                    .map(|(timestamp, u)| {
+                        (BlockTimestamp::new(timestamp.into()), map_step_duration(u))
+                    })
                     .collect()
             }
         };
@@ -278,6 +292,14 @@ impl From<ethjson::spec::AuthorityRoundParams> for AuthorityRoundParams {
             block_gas_limit_contract_transitions,
             posdao_transition: p.posdao_transition.map(Into::into),
             rewrite_bytecode_transitions,
+            median_time_past_transition: p
+                .median_time_past_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            median_time_past_window: p
+                .median_time_past_window
+                .map_or(DEFAULT_MEDIAN_TIME_PAST_WINDOW, |w| {
+                    ::std::cmp::max(w.into(), 1)
+                }),
         }
     }
 }
@@ -297,6 +319,8 @@ struct Step {
     inner: AtomicU64,
     /// Planned durations of steps.
     durations: Vec<StepDurationInfo>,
+    /// Clock the step is calibrated against.
+    clock: SharedClock,
 }
 
 impl Step {
@@ -312,6 +336,27 @@ impl Step {
         })
     }
 
+    /// Finds the unix time in seconds at which the given step starts, and its duration. Returns
+    /// `None` if there was an overflow.
+    fn opt_step_time(&self, step: u64) -> Option<(u64, u64)> {
+        let StepDurationInfo {
+            transition_step,
+            transition_timestamp,
+            step_duration,
+        } = self
+            .durations
+            .iter()
+            .take_while(|info| info.transition_step <= step)
+            .last()
+            .expect("durations cannot be empty")
+            .clone();
+        let start = transition_timestamp.checked_add(
+            step.checked_sub(transition_step)?
+                .checked_mul(step_duration)?,
+        )?;
+        Some((start, step_duration))
+    }
+
     /// Finds the remaining duration of the current step. Returns `None` if there was a counter
     /// under- or overflow.
     fn opt_duration_remaining(&self) -> Option<Duration> {
@@ -333,7 +378,7 @@ impl Step {
                 .checked_mul(step_duration)?,
         )?;
         Some(Duration::from_secs(
-            next_time.saturating_sub(unix_now().as_secs()),
+            next_time.saturating_sub(self.clock.unix_now().as_secs()),
         ))
     }
 
@@ -362,7 +407,7 @@ impl Step {
 
     /// Calibrates the AuRa step number according to the current time.
     fn opt_calibrate(&self) -> Option<()> {
-        let now = unix_now().as_secs();
+        let now = self.clock.unix_now().as_secs();
         let StepDurationInfo {
             transition_step,
             transition_timestamp,
@@ -667,6 +712,99 @@ impl Decodable for SealedEmptyStep {
 struct PermissionedStep {
     inner: Step,
     can_propose: AtomicBool,
+    metrics: StepMetrics,
+}
+
+impl PermissionedStep {
+    /// Move to the next step and allow proposing again.
+    fn advance(&self) {
+        self.metrics.note_step_end(self.inner.load());
+        self.inner.increment();
+        self.can_propose.store(true, AtomicOrdering::SeqCst);
+    }
+}
+
+/// Tracks the steps we are the proposer of and how many of them are missed, and the steps
+/// skipped by the chain.
+struct StepMetrics {
+    /// When the current step started.
+    started: Mutex<Instant>,
+    /// Last step we were the proposer of.
+    proposer_step: AtomicU64,
+    /// Last step we sealed a block or broadcast an empty step message in.
+    proposed_step: AtomicU64,
+    proposer_steps: AtomicU64,
+    missed_proposer_steps: AtomicU64,
+    /// Steps without a block between verified blocks and their parents.
+    missed_steps: AtomicU64,
+}
+
+impl Default for StepMetrics {
+    fn default() -> Self {
+        StepMetrics {
+            started: Mutex::new(Instant::now()),
+            proposer_step: AtomicU64::new(u64::MAX),
+            proposed_step: AtomicU64::new(u64::MAX),
+            proposer_steps: AtomicU64::new(0),
+            missed_proposer_steps: AtomicU64::new(0),
+            missed_steps: AtomicU64::new(0),
+        }
+    }
+}
+
+impl StepMetrics {
+    /// We are the proposer of the given step.
+    fn note_proposer(&self, step: u64) {
+        if self.proposer_step.swap(step, AtomicOrdering::SeqCst) != step {
+            self.proposer_steps.fetch_add(1, AtomicOrdering::Relaxed);
+        }
+    }
+
+    /// We proposed in the given step.
+    fn note_proposed(&self, step: u64) {
+        self.proposed_step.store(step, AtomicOrdering::SeqCst);
+    }
+
+    /// The given step is over.
+    fn note_step_end(&self, step: u64) {
+        if self.proposer_step.load(AtomicOrdering::SeqCst) == step
+            && self.proposed_step.load(AtomicOrdering::SeqCst) != step
+        {
+            self.missed_proposer_steps
+                .fetch_add(1, AtomicOrdering::Relaxed);
+            debug!(target: "engine", "Missed our proposer step {}", step);
+        }
+        *self.started.lock() = Instant::now();
+    }
+
+    /// A block of `step` was verified on top of a parent of `parent_step`.
+    fn note_block(&self, parent_step: u64, step: u64) {
+        let missed = step.saturating_sub(parent_step).saturating_sub(1);
+        self.missed_steps.fetch_add(missed, AtomicOrdering::Relaxed);
+    }
+
+    /// Time elapsed since the start of the current step.
+    fn since_step_start(&self) -> Duration {
+        self.started.lock().elapsed()
+    }
+
+    fn register(&self, r: &mut PrometheusRegistry) {
+        r.register_counter(
+            "aura_proposer_steps",
+            "Steps this node was the proposer of",
+            self.proposer_steps.load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_counter(
+            "aura_missed_proposer_steps",
+            "Steps this node was the proposer of but didn't propose in",
+            self.missed_proposer_steps.load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_counter(
+            "aura_missed_steps",
+            "Steps without a block between verified blocks and their parents",
+            self.missed_steps.load(AtomicOrdering::Relaxed) as i64,
+        );
+    }
 }
 
 /// Engine using `AuthorityRound` proof-of-authority BFT consensus.
@@ -705,6 +843,14 @@ pub struct AuthorityRound {
     /// The block numbers at which the bytecodes should be rewritten for
     /// the specified contracts (can be more than one per block)
     rewrite_bytecode_transitions: BTreeMap<BlockNumber, BTreeMap<Address, Bytes>>,
+    /// Block from which median-time-past timestamp validation applies.
+    median_time_past_transition: BlockNumber,
+    /// Number of ancestors the median timestamp is taken over.
+    median_time_past_window: usize,
+    /// Sealing latency and rejected seals.
+    sealing_metrics: SealingMetrics,
+    /// Drops replayed empty step messages.
+    message_filter: MessageFilter,
 }
 
 // header-chain validator.
@@ -1000,13 +1146,16 @@ impl AuthorityRound {
         our_params: AuthorityRoundParams,
         machine: EthereumMachine,
     ) -> Result<Arc<Self>, Error> {
-        if !our_params.step_durations.contains_key(&0) {
+        if !our_params
+            .step_durations
+            .contains_key(&BlockTimestamp::new(0))
+        {
             error!(target: "engine", "Authority Round step 0 duration is undefined, aborting");
             return Err(Error::from_kind(ErrorKind::Engine(EngineError::Custom(
                 String::from("step 0 duration is undefined"),
             ))));
         }
-        if our_params.step_durations.values().any(|v| *v == 0) {
+        if our_params.step_durations.values().any(|v| v.as_secs() == 0) {
             error!(target: "engine", "Authority Round step duration cannot be 0");
             return Err(Error::from_kind(ErrorKind::Engine(EngineError::Custom(
                 String::from("step duration cannot be 0"),
@@ -1022,15 +1171,15 @@ impl AuthorityRound {
             let mut dur_info = StepDurationInfo {
                 transition_step: 0u64,
                 transition_timestamp: 0u64,
-                step_duration: our_params.step_durations[&0],
+                step_duration: our_params.step_durations[&BlockTimestamp::new(0)].as_secs(),
             };
             durations.push(dur_info);
             for (time, dur) in our_params.step_durations.iter().skip(1) {
-                let (step, time) = next_step_time_duration(dur_info, *time)
+                let (step, time) = next_step_time_duration(dur_info, time.as_u64())
                     .ok_or(BlockError::TimestampOverflow)?;
                 dur_info.transition_step = step;
                 dur_info.transition_timestamp = time;
-                dur_info.step_duration = *dur;
+                dur_info.step_duration = dur.as_secs();
                 durations.push(dur_info);
             }
         }
@@ -1039,6 +1188,7 @@ impl AuthorityRound {
             inner: AtomicU64::new(initial_step),
             calibrate: our_params.start_step.is_none(),
             durations,
+            clock: machine.clock().clone(),
         };
         step.calibrate();
 
@@ -1047,6 +1197,7 @@ impl AuthorityRound {
             step: Arc::new(PermissionedStep {
                 inner: step,
                 can_propose: AtomicBool::new(true),
+                metrics: StepMetrics::default(),
             }),
             client: Arc::new(RwLock::new(None)),
             signer: RwLock::new(None),
@@ -1073,6 +1224,10 @@ impl AuthorityRound {
             gas_limit_override_cache: Mutex::new(LruCache::new(GAS_LIMIT_OVERRIDE_CACHE_CAPACITY)),
             posdao_transition: our_params.posdao_transition,
             rewrite_bytecode_transitions: our_params.rewrite_bytecode_transitions,
+            median_time_past_transition: our_params.median_time_past_transition,
+            median_time_past_window: our_params.median_time_past_window,
+            sealing_metrics: SealingMetrics::default(),
+            message_filter: MessageFilter::default(),
         });
 
         // Do not initialize timeouts for tests.
@@ -1155,6 +1310,26 @@ impl AuthorityRound {
         self.empty_steps.lock().insert(empty_step);
     }
 
+    /// Instant at which `step` ends, `None` if steps don't follow the clock.
+    fn step_end(&self, step: u64) -> Option<Instant> {
+        if !self.step.inner.calibrate {
+            return None;
+        }
+        let (start, duration) = self.step.inner.opt_step_time(step)?;
+        let end = Duration::from_secs(start.checked_add(duration)?);
+        let now = self.step.inner.clock.unix_now();
+        Some(Instant::now() + end.checked_sub(now).unwrap_or_default())
+    }
+
+    fn sign_before(&self, hash: H256, deadline: Instant) -> Result<Signature, Error> {
+        Ok(self
+            .signer
+            .read()
+            .as_ref()
+            .ok_or(publickey::Error::InvalidAddress)?
+            .sign_before(hash, deadline)?)
+    }
+
     fn generate_empty_step(&self, parent_hash: &H256) {
         let step = self.step.inner.load();
         let empty_step_rlp = empty_step_rlp(step, parent_hash);
@@ -1433,10 +1608,14 @@ impl AuthorityRound {
     }
 }
 
-fn unix_now() -> Duration {
-    UNIX_EPOCH
-        .elapsed()
-        .expect("Valid time has to be set in your system.")
+/// RLP list of the given encoded headers, proving that their author signed all of them in the
+/// same step.
+fn double_vote_proof(headers: &[Bytes]) -> Bytes {
+    let mut stream = RlpStream::new_list(headers.len());
+    for header in headers {
+        stream.append_raw(header, 1);
+    }
+    stream.out().to_vec()
 }
 
 struct TransitionHandler {
@@ -1461,8 +1640,7 @@ impl IoHandler<()> for TransitionHandler {
             // has not been called fast enough.
             // Make sure to advance up to the actual step.
             while AsMillis::as_millis(&self.step.inner.duration_remaining()) == 0 {
-                self.step.inner.increment();
-                self.step.can_propose.store(true, AtomicOrdering::SeqCst);
+                self.step.advance();
                 if let Some(ref weak) = *self.client.read() {
                     if let Some(c) = weak.upgrade() {
                         c.update_sealing(ForceUpdateSealing::No);
@@ -1497,8 +1675,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
     }
 
     fn step(&self) {
-        self.step.inner.increment();
-        self.step.can_propose.store(true, AtomicOrdering::SeqCst);
+        self.step.advance();
         if let Ok(c) = self.upgrade_client_or(None) {
             c.update_sealing(ForceUpdateSealing::No);
         }
@@ -1555,6 +1732,14 @@ impl Engine<EthereumMachine> for AuthorityRound {
         }
     }
 
+    fn median_time_past_window(&self, block: BlockNumber) -> Option<usize> {
+        if block >= self.median_time_past_transition {
+            Some(self.median_time_past_window)
+        } else {
+            None
+        }
+    }
+
     fn populate_from_parent(&self, header: &mut Header, parent: &Header) {
         let parent_step = header_step(parent, self.empty_steps_transition)
             .expect("Header has been verified; qed");
@@ -1587,6 +1772,10 @@ impl Engine<EthereumMachine> for AuthorityRound {
             None => return false,
         };
 
+        self.is_allowed_to_seal_as(&our_addr)
+    }
+
+    fn is_allowed_to_seal_as(&self, address: &Address) -> bool {
         let client = match self.upgrade_client_or("Not preparing block") {
             Ok(client) => client,
             Err(_) => return false,
@@ -1613,7 +1802,26 @@ impl Engine<EthereumMachine> for AuthorityRound {
             }
             CowLike::Owned(epoch_manager.validators().clone())
         };
-        validators.contains(&parent.hash(), &our_addr)
+        validators.contains(&parent.hash(), address)
+    }
+
+    // Building stops halfway through the next step we propose in, the rest is left for
+    // sealing and propagating the block within that step.
+    fn sealing_deadline(&self, header: &Header) -> Option<Instant> {
+        // steps which don't follow the clock have no deadline
+        if !self.step.inner.calibrate {
+            return None;
+        }
+        let our_addr = self.signer.read().as_ref()?.address();
+        let (validators, _) = self.epoch_set(header).ok()?;
+        let current_step = self.step.inner.load();
+        let validator_count = validators.count(header.parent_hash()) as u64;
+        let our_step = (current_step..current_step.checked_add(validator_count)?)
+            .find(|step| is_step_proposer(&*validators, header.parent_hash(), *step, &our_addr))?;
+        let (start, duration) = self.step.inner.opt_step_time(our_step)?;
+        let deadline = Duration::from_secs(start) + Duration::from_secs(duration) / 2;
+        let now = self.step.inner.clock.unix_now();
+        Some(Instant::now() + deadline.checked_sub(now).unwrap_or_default())
     }
 
     fn sealing_state(&self) -> SealingState {
@@ -1662,9 +1870,21 @@ impl Engine<EthereumMachine> for AuthorityRound {
             return SealingState::NotReady;
         }
 
+        self.step.metrics.note_proposer(step);
         SealingState::Ready
     }
 
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        self.sealing_metrics.register(r);
+        self.step.metrics.register(r);
+        self.message_filter.register(r, "aura");
+        r.register_gauge(
+            "aura_step",
+            "Current AuRa step",
+            self.step.inner.load() as i64,
+        );
+    }
+
     fn handle_message(&self, rlp: &[u8]) -> Result<(), EngineError> {
         fn fmt_err<T: fmt::Debug>(x: T) -> EngineError {
             EngineError::MalformedMessage(format!("{:?}", x))
@@ -1673,16 +1893,31 @@ impl Engine<EthereumMachine> for AuthorityRound {
         let rlp = Rlp::new(rlp);
         let empty_step: EmptyStep = rlp.as_val().map_err(fmt_err)?;
 
-        if empty_step.verify(&*self.validators).unwrap_or(false) {
-            if self.step.inner.check_future(empty_step.step).is_ok() {
+        if self.step.inner.check_future(empty_step.step).is_err() {
+            trace!(target: "engine", "handle_message: empty step message from the future {:?}", empty_step);
+            return Ok(());
+        }
+
+        let verified =
+            self.message_filter
+                .check(empty_step.step, rlp.as_raw(), || {
+                    match empty_step.verify(&*self.validators) {
+                        Ok(true) => Ok(()),
+                        _ => Err(()),
+                    }
+                });
+        match verified {
+            Ok(Some(())) => {
                 trace!(target: "engine", "handle_message: received empty step message {:?}", empty_step);
                 self.handle_empty_step_message(empty_step);
-            } else {
-                trace!(target: "engine", "handle_message: empty step message from the future {:?}", empty_step);
             }
-        } else {
-            trace!(target: "engine", "handle_message: received invalid step message {:?}", empty_step);
-        };
+            Ok(None) => {
+                trace!(target: "engine", "handle_message: dropped duplicate empty step message {:?}", empty_step);
+            }
+            Err(()) => {
+                trace!(target: "engine", "handle_message: received invalid step message {:?}", empty_step);
+            }
+        }
 
         Ok(())
     }
@@ -1754,6 +1989,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
                     .compare_exchange(true, false, AtomicOrdering::SeqCst, AtomicOrdering::SeqCst)
                     .is_ok()
                 {
+                    self.step.metrics.note_proposed(step);
                     self.generate_empty_step(header.parent_hash());
                 }
 
@@ -1767,10 +2003,14 @@ impl Engine<EthereumMachine> for AuthorityRound {
                 None
             };
 
-            if let Ok(signature) = self.sign(header_seal_hash(
-                header,
-                empty_steps_rlp.as_ref().map(|e| &**e),
-            )) {
+            // the seal is of no use once the step is over, the next proposer is building
+            // on the parent by then.
+            let hash = header_seal_hash(header, empty_steps_rlp.as_ref().map(|e| &**e));
+            let signature = match self.step_end(step) {
+                Some(deadline) => self.sign_before(hash, deadline),
+                None => self.sign(hash),
+            };
+            if let Ok(signature) = signature {
                 trace!(target: "engine", "generate_seal: Issuing a block for step {}.", step);
 
                 // only issue the seal if we were the first to reach the compare_exchange.
@@ -1780,6 +2020,10 @@ impl Engine<EthereumMachine> for AuthorityRound {
                     .compare_exchange(true, false, AtomicOrdering::SeqCst, AtomicOrdering::SeqCst)
                     .is_ok()
                 {
+                    self.step.metrics.note_proposed(step);
+                    self.sealing_metrics
+                        .note_sealed(self.step.metrics.since_step_start());
+
                     // we can drop all accumulated empty step messages that are
                     // older than the parent step since we're including them in
                     // the seal
@@ -1881,7 +2125,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
                 let parent = client
                     .block_header(::client::BlockId::Hash(*block.header.parent_hash()))
                     .expect("hash is from parent; parent header must exist; qed")
-                    .decode(self.params().eip1559_transition)?;
+                    .decode(self.params().header_decode_params())?;
 
                 let parent_step = header_step(&parent, self.empty_steps_transition)?;
                 let current_step = self.step.inner.load();
@@ -1905,7 +2149,10 @@ impl Engine<EthereumMachine> for AuthorityRound {
             .block_reward_contract_transitions
             .range(..=block.header.number())
             .last();
-        let rewards: Vec<_> = if let Some((_, contract)) = block_reward_contract_transition {
+        let rewards: Vec<_> = if self.machine.has_reward_schedule(number) {
+            // The spec's reward schedule replaces the engine's rewards.
+            Vec::new()
+        } else if let Some((_, contract)) = block_reward_contract_transition {
             let mut call = crate::engines::default_system_or_code_call(&self.machine, block);
             let rewards = contract.reward(&beneficiaries, &mut call)?;
             rewards
@@ -2002,7 +2249,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
                 header.author(),
                 set_number,
                 header.number(),
-                Default::default(),
+                double_vote_proof(&[header.encoded().into_inner(), parent.encoded().into_inner()]),
             );
             Err(EngineError::DoubleVote(*header.author()))?;
         }
@@ -2010,19 +2257,28 @@ impl Engine<EthereumMachine> for AuthorityRound {
         // Report malice if the validator produced other sibling blocks in the same step.
         let received_step_key = (step, *header.author());
         let new_hash = header.hash();
-        if self
+        let sibling_hash = self
             .received_step_hashes
             .read()
             .get(&received_step_key)
-            .map_or(false, |h| *h != new_hash)
-        {
+            .cloned()
+            .filter(|h| *h != new_hash);
+        if let Some(sibling_hash) = sibling_hash {
             trace!(target: "engine", "Validator {} produced sibling blocks in the same step", header.author());
-            self.validators.report_malicious(
-                header.author(),
-                set_number,
-                header.number(),
-                Default::default(),
-            );
+            let sibling = self
+                .client
+                .read()
+                .as_ref()
+                .and_then(Weak::upgrade)
+                .and_then(|c| {
+                    c.as_full_client()
+                        .and_then(|c| c.block_header(BlockId::Hash(sibling_hash)))
+                });
+            let mut headers = vec![header.encoded().into_inner()];
+            headers.extend(sibling.map(|sibling| sibling.into_inner()));
+            let proof = double_vote_proof(&headers);
+            self.validators
+                .report_malicious(header.author(), set_number, header.number(), proof);
         } else {
             self.received_step_hashes
                 .write()
@@ -2121,6 +2377,7 @@ impl Engine<EthereumMachine> for AuthorityRound {
             }
         }
 
+        self.step.metrics.note_block(parent_step, step);
         Ok(())
     }
 
@@ -2148,6 +2405,9 @@ impl Engine<EthereumMachine> for AuthorityRound {
             }
             _ => {}
         }
+        if let Err(ref e) = res {
+            self.sealing_metrics.note_verification_error(e);
+        }
         res
     }
 
@@ -2383,7 +2643,7 @@ fn next_step_time_duration(info: StepDurationInfo, time: u64) -> Option<(u64, u6
 mod tests {
     use super::{
         calculate_score, next_step_time_duration, util::BoundContract, AuthorityRound,
-        AuthorityRoundParams, EmptyStep, SealedEmptyStep, StepDurationInfo,
+        AuthorityRoundParams, EmptyStep, SealedEmptyStep, StepDurationInfo, StepMetrics,
     };
     use accounts::AccountProvider;
     use block::*;
@@ -2414,9 +2674,11 @@ mod tests {
         generate_dummy_client_with_spec, generate_dummy_client_with_spec_and_data,
         get_temp_state_db, push_block_with_transactions_and_author, TestNotify,
     };
+    use time_utils::{Clock, MockClock, SharedClock};
     use types::{
         header::Header,
         ids::BlockId,
+        timestamp::{BlockTimestamp, StepDuration},
         transaction::{Action, Transaction, TypedTransaction},
     };
 
@@ -2425,7 +2687,10 @@ mod tests {
         F: FnOnce(&mut AuthorityRoundParams),
     {
         let mut params = AuthorityRoundParams {
-            step_durations: [(0, 1)].to_vec().into_iter().collect(),
+            step_durations: [(BlockTimestamp::new(0), StepDuration::from_secs(1))]
+                .to_vec()
+                .into_iter()
+                .collect(),
             start_step: Some(1),
             validators: Box::new(TestSet::default()),
             validate_score_transition: 0,
@@ -2443,6 +2708,8 @@ mod tests {
             block_gas_limit_contract_transitions: BTreeMap::new(),
             posdao_transition: Some(0),
             rewrite_bytecode_transitions: BTreeMap::new(),
+            median_time_past_transition: u64::max_value(),
+            median_time_past_window: 11,
         };
 
         // mutate aura params
@@ -2480,7 +2747,7 @@ mod tests {
     #[test]
     fn can_return_schedule() {
         let engine = Spec::new_test_round().engine;
-        let schedule = engine.schedule(10000000);
+        let schedule = engine.schedule(10000000, 0);
 
         assert!(schedule.stack_limit > 0);
     }
@@ -2663,13 +2930,13 @@ mod tests {
 
         engine.set_signer(Some(Box::new((tap.clone(), addr1, "1".into()))));
         match engine.generate_seal(&b1, &genesis_header) {
-            Seal::None | Seal::Proposal(_) => panic!("wrong seal"),
+            Seal::None | Seal::Proposal(_) | Seal::Pending(_) => panic!("wrong seal"),
             Seal::Regular(_) => {
                 engine.step();
 
                 engine.set_signer(Some(Box::new((tap.clone(), addr2, "0".into()))));
                 match engine.generate_seal(&b2, &genesis_header) {
-                    Seal::Regular(_) | Seal::Proposal(_) => {
+                    Seal::Regular(_) | Seal::Proposal(_) | Seal::Pending(_) => {
                         panic!("sealed despite wrong difficulty")
                     }
                     Seal::None => {}
@@ -2802,6 +3069,40 @@ mod tests {
         assert_eq!(last_benign.load(AtomicOrdering::SeqCst), 2);
     }
 
+    #[test]
+    fn counts_missed_proposer_steps() {
+        let metrics = StepMetrics::default();
+
+        // proposed in step 1
+        metrics.note_proposer(1);
+        metrics.note_proposer(1);
+        metrics.note_proposed(1);
+        metrics.note_step_end(1);
+        // not a proposer in step 2
+        metrics.note_step_end(2);
+        // missed step 3
+        metrics.note_proposer(3);
+        metrics.note_step_end(3);
+
+        assert_eq!(metrics.proposer_steps.load(AtomicOrdering::SeqCst), 2);
+        assert_eq!(
+            metrics.missed_proposer_steps.load(AtomicOrdering::SeqCst),
+            1
+        );
+    }
+
+    #[test]
+    fn counts_all_steps_skipped_between_blocks() {
+        let metrics = StepMetrics::default();
+
+        metrics.note_block(1, 2);
+        metrics.note_block(2, 6);
+        // an older block on a side chain skips nothing
+        metrics.note_block(6, 5);
+
+        assert_eq!(metrics.missed_steps.load(AtomicOrdering::SeqCst), 3);
+    }
+
     #[test]
     fn reports_multiple_blocks_per_step() {
         let tap = AccountProvider::transient_provider();
@@ -2860,6 +3161,7 @@ mod tests {
             .to_vec()
             .into_iter()
             .collect(),
+            clock: Default::default(),
         };
         step.increment();
     }
@@ -2879,6 +3181,7 @@ mod tests {
             .to_vec()
             .into_iter()
             .collect(),
+            clock: Default::default(),
         };
         step.duration_remaining();
     }
@@ -2886,9 +3189,9 @@ mod tests {
     #[test]
     fn test_change_step_duration() {
         use super::Step;
-        use std::thread;
 
-        let now = super::unix_now().as_secs();
+        let clock = Arc::new(MockClock::with_unix_secs(1_600_000_000));
+        let now = clock.unix_now().as_secs();
         let step = Step {
             calibrate: true,
             inner: AtomicU64::new(::std::u64::MAX),
@@ -2912,13 +3215,14 @@ mod tests {
             .to_vec()
             .into_iter()
             .collect(),
+            clock: SharedClock::new(clock.clone()),
         };
         // calibrated step `now`
         step.calibrate();
         let duration_remaining = step.duration_remaining();
         assert_eq!(step.inner.load(AtomicOrdering::SeqCst), now);
         assert!(duration_remaining <= Duration::from_secs(2));
-        thread::sleep(duration_remaining);
+        clock.advance(duration_remaining);
         step.increment();
         // calibrated step `now + 1`
         step.calibrate();
@@ -2928,11 +3232,43 @@ mod tests {
         assert!(duration_remaining <= Duration::from_secs(4));
     }
 
+    #[test]
+    fn test_step_time_follows_duration_transitions() {
+        use super::Step;
+        let step = Step {
+            calibrate: false,
+            inner: AtomicU64::new(0),
+            durations: [
+                StepDurationInfo {
+                    transition_step: 0,
+                    transition_timestamp: 0,
+                    step_duration: 5,
+                },
+                StepDurationInfo {
+                    transition_step: 10,
+                    transition_timestamp: 50,
+                    step_duration: 2,
+                },
+            ]
+            .to_vec()
+            .into_iter()
+            .collect(),
+            clock: Default::default(),
+        };
+        assert_eq!(step.opt_step_time(3), Some((15, 5)));
+        assert_eq!(step.opt_step_time(10), Some((50, 2)));
+        assert_eq!(step.opt_step_time(12), Some((54, 2)));
+        assert_eq!(step.opt_step_time(u64::max_value()), None);
+    }
+
     #[test]
     #[should_panic]
     fn test_step_duration_zero() {
         aura(|params| {
-            params.step_durations = [(0, 0)].to_vec().into_iter().collect();
+            params.step_durations = [(BlockTimestamp::new(0), StepDuration::from_secs(0))]
+                .to_vec()
+                .into_iter()
+                .collect();
         });
     }
 
@@ -3541,7 +3877,10 @@ mod tests {
     #[test]
     fn test_empty_steps() {
         let engine = aura(|p| {
-            p.step_durations = [(0, 4)].to_vec().into_iter().collect();
+            p.step_durations = [(BlockTimestamp::new(0), StepDuration::from_secs(4))]
+                .to_vec()
+                .into_iter()
+                .collect();
             p.empty_steps_transition = 0;
             p.maximum_empty_steps = 0;
         });
@@ -3578,7 +3917,10 @@ mod tests {
         let (_spec, tap, accounts) = setup_empty_steps();
         let engine = aura(|p| {
             p.validators = Box::new(SimpleList::new(accounts.clone()));
-            p.step_durations = [(0, 4)].to_vec().into_iter().collect();
+            p.step_durations = [(BlockTimestamp::new(0), StepDuration::from_secs(4))]
+                .to_vec()
+                .into_iter()
+                .collect();
             p.empty_steps_transition = 0;
             p.maximum_empty_steps = 0;
         });
@@ -3617,7 +3959,10 @@ mod tests {
         let (_spec, tap, accounts) = setup_empty_steps();
         let engine = aura(|p| {
             p.validators = Box::new(SimpleList::new(accounts.clone()));
-            p.step_durations = [(0, 4)].to_vec().into_iter().collect();
+            p.step_durations = [(BlockTimestamp::new(0), StepDuration::from_secs(4))]
+                .to_vec()
+                .into_iter()
+                .collect();
             p.empty_steps_transition = 0;
             p.maximum_empty_steps = 0;
         });
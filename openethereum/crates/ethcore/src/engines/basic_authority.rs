@@ -15,54 +15,97 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 //! A blockchain engine that supports a basic, non-BFT proof-of-authority.
+//!
+//! From `round_robin_transition` on, validators take turns: the validator scheduled for a
+//! height seals with difficulty `DIFF_INTURN`, while any other validator may seal out of turn
+//! with difficulty `DIFF_NOTURN`. No validator may seal more than one of `validators / 2 + 1`
+//! consecutive blocks, so the chain keeps going as long as that many validators are online.
+//! Block timestamps play no part in these rules; out-of-turn validators only delay sealing on
+//! their local clock, the longer the further they are behind the scheduled one. The total
+//! difficulty fork choice thereby prefers chains sealed in turn.
 
 use super::validator_set::{new_validator_set, SimpleList, ValidatorSet};
 use block::*;
-use client::EngineClient;
+use client::{traits::ForceUpdateSealing, EngineClient};
 use crypto::publickey::{self, Signature};
-use engines::{signer::EngineSigner, ConstructedVerifier, Engine, EngineError, Seal, SealingState};
+use engines::{
+    signer::EngineSigner, ConstructedVerifier, Engine, EngineError, PendingSeal, Seal, SealingState,
+};
 use error::{BlockError, Error};
-use ethereum_types::{H256, H520};
+use ethereum_types::{Address, H256, H520, U256};
 use ethjson;
 use machine::{AuxiliaryData, Call, EthereumMachine};
-use parking_lot::RwLock;
-use std::sync::Weak;
-use types::header::{ExtendedHeader, Header};
+use parking_lot::{Mutex, RwLock};
+use std::{
+    sync::{Arc, Weak},
+    thread,
+    time::{Duration, Instant},
+};
+use types::{
+    header::{ExtendedHeader, Header},
+    ids::BlockId,
+    BlockNumber,
+};
+use unexpected::Mismatch;
+
+/// Difficulty of a block sealed by the scheduled validator.
+pub const DIFF_INTURN: U256 = U256([2, 0, 0, 0]);
+/// Difficulty of a block sealed out of turn.
+pub const DIFF_NOTURN: U256 = U256([1, 0, 0, 0]);
 
 /// `BasicAuthority` params.
 #[derive(Debug, PartialEq)]
 pub struct BasicAuthorityParams {
     /// Valid signatories.
     pub validators: ethjson::spec::ValidatorSet,
+    /// Block from which the block author may differ from the signer, so that rewards and fees
+    /// can be credited to a separate recipient.
+    pub fee_recipient_transition: BlockNumber,
+    /// Block from which validators take turns to seal.
+    pub round_robin_transition: BlockNumber,
+    /// Seconds an out-of-turn validator waits per position behind the scheduled one before sealing,
+    /// measured on the local clock. Defaults to the block duration.
+    pub round_robin_grace_period: u64,
 }
 
 impl From<ethjson::spec::BasicAuthorityParams> for BasicAuthorityParams {
     fn from(p: ethjson::spec::BasicAuthorityParams) -> Self {
         BasicAuthorityParams {
             validators: p.validators,
+            fee_recipient_transition: p
+                .fee_recipient_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            round_robin_transition: p
+                .round_robin_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            round_robin_grace_period: p
+                .round_robin_grace_period
+                .unwrap_or(p.duration_limit)
+                .into(),
         }
     }
 }
 
 struct EpochVerifier {
     list: SimpleList,
+    fee_recipient_transition: BlockNumber,
 }
 
 impl super::EpochVerifier<EthereumMachine> for EpochVerifier {
     fn verify_light(&self, header: &Header) -> Result<(), Error> {
-        verify_external(header, &self.list)
+        verify_external(header, &self.list, self.fee_recipient_transition)
     }
 }
 
-fn verify_external(header: &Header, validators: &dyn ValidatorSet) -> Result<(), Error> {
-    use rlp::Rlp;
-
+fn verify_external(
+    header: &Header,
+    validators: &dyn ValidatorSet,
+    fee_recipient_transition: BlockNumber,
+) -> Result<(), Error> {
     // Check if the signature belongs to a validator, can depend on parent state.
-    let sig = Rlp::new(&header.seal()[0]).as_val::<H520>()?;
-    let signer =
-        publickey::public_to_address(&publickey::recover(&sig.into(), &header.bare_hash())?);
+    let signer = recover_signer(header)?;
 
-    if *header.author() != signer {
+    if header.number() < fee_recipient_transition && *header.author() != signer {
         return Err(EngineError::NotAuthorized(*header.author()).into());
     }
 
@@ -72,11 +115,75 @@ fn verify_external(header: &Header, validators: &dyn ValidatorSet) -> Result<(),
     }
 }
 
+fn recover_signer(header: &Header) -> Result<Address, Error> {
+    use rlp::Rlp;
+
+    let sig = Rlp::new(&header.seal()[0]).as_val::<H520>()?;
+    Ok(publickey::public_to_address(&publickey::recover(
+        &sig.into(),
+        &header.bare_hash(),
+    )?))
+}
+
+/// Number of consecutive blocks of which a validator may seal at most one.
+fn recent_signer_limit(validators: usize) -> usize {
+    validators / 2 + 1
+}
+
+/// Check that `signer` sealed none of the `recent` blocks preceding a header, most recent first,
+/// within the recent signer limit.
+fn verify_not_recent(signer: &Address, recent: &[Address], validators: usize) -> Result<(), Error> {
+    let limit = recent_signer_limit(validators);
+    match recent.iter().take(limit - 1).any(|recent| recent == signer) {
+        true => Err(EngineError::TooRecentlySigned(*signer).into()),
+        false => Ok(()),
+    }
+}
+
+/// Check that `signer` sealed `header` with the difficulty for its turn under round-robin
+/// rotation and return its position behind the scheduled validator.
+fn verify_turn(
+    header: &Header,
+    signer: &Address,
+    validators: &dyn ValidatorSet,
+) -> Result<u64, Error> {
+    let parent_hash = header.parent_hash();
+    let count = validators.count(parent_hash) as u64;
+    let position = (0..count)
+        .find(|offset| validators.get(parent_hash, (header.number() + offset) as usize) == *signer)
+        .ok_or_else(|| {
+            EngineError::NotProposer(Mismatch {
+                expected: validators.get(parent_hash, header.number() as usize),
+                found: *signer,
+            })
+        })?;
+    let expected_difficulty = if position == 0 {
+        DIFF_INTURN
+    } else {
+        DIFF_NOTURN
+    };
+
+    if *header.difficulty() != expected_difficulty {
+        return Err(BlockError::InvalidDifficulty(Mismatch {
+            expected: expected_difficulty,
+            found: *header.difficulty(),
+        })
+        .into());
+    }
+    Ok(position)
+}
+
 /// Engine using `BasicAuthority`, trivial proof-of-authority consensus.
 pub struct BasicAuthority {
     machine: EthereumMachine,
-    signer: RwLock<Option<Box<dyn EngineSigner>>>,
+    signer: RwLock<Option<Arc<dyn EngineSigner>>>,
     validators: Box<dyn ValidatorSet>,
+    fee_recipient_transition: BlockNumber,
+    round_robin_transition: BlockNumber,
+    round_robin_grace_period: u64,
+    client: RwLock<Option<Weak<dyn EngineClient>>>,
+    /// Parent on which sealing was first attempted, and when.
+    first_seal_attempt: Mutex<Option<(H256, Instant)>>,
 }
 
 impl BasicAuthority {
@@ -86,6 +193,106 @@ impl BasicAuthority {
             machine: machine,
             signer: RwLock::new(None),
             validators: new_validator_set(our_params.validators),
+            fee_recipient_transition: our_params.fee_recipient_transition,
+            round_robin_transition: our_params.round_robin_transition,
+            round_robin_grace_period: our_params.round_robin_grace_period,
+            client: RwLock::new(None),
+            first_seal_attempt: Mutex::new(None),
+        }
+    }
+
+    fn is_round_robin(&self, block_number: BlockNumber) -> bool {
+        block_number >= self.round_robin_transition
+    }
+
+    /// Check that `signer` sealed none of the blocks preceding `header` within the recent signer
+    /// limit.
+    fn verify_recent_signers(
+        &self,
+        header: &Header,
+        parent: &Header,
+        signer: &Address,
+    ) -> Result<(), Error> {
+        let count = self.validators.count(header.parent_hash());
+        let recent = self.recent_signers(parent, recent_signer_limit(count) - 1)?;
+        verify_not_recent(signer, &recent, count)
+    }
+
+    /// Signers of up to `limit` round-robin blocks ending with `parent`, most recent first.
+    fn recent_signers(&self, parent: &Header, limit: usize) -> Result<Vec<Address>, Error> {
+        let mut signers = Vec::with_capacity(limit);
+        let mut ancestor = parent.clone();
+        while signers.len() < limit {
+            if ancestor.number() == 0 || !self.is_round_robin(ancestor.number()) {
+                break;
+            }
+            signers.push(recover_signer(&ancestor)?);
+            if signers.len() == limit {
+                break;
+            }
+            let client = match self.client.read().as_ref().and_then(|w| w.upgrade()) {
+                Some(client) => client,
+                None => return Err(EngineError::RequiresClient.into()),
+            };
+            let parent_hash = *ancestor.parent_hash();
+            ancestor = match client.block_header(BlockId::Hash(parent_hash)) {
+                Some(header) => header.decode(self.machine.params().header_decode_params())?,
+                None => return Err(BlockError::UnknownParent(parent_hash).into()),
+            };
+        }
+        Ok(signers)
+    }
+
+    /// Whether an out-of-turn validator at the given position may seal on top of `parent` yet. The
+    /// wait starts when sealing on `parent` is first attempted and uses the local monotonic clock.
+    fn out_of_turn_wait_elapsed(&self, parent: H256, position: u64) -> bool {
+        let mut first_seal_attempt = self.first_seal_attempt.lock();
+        let started = match *first_seal_attempt {
+            Some((hash, started)) if hash == parent => started,
+            _ => {
+                let now = Instant::now();
+                *first_seal_attempt = Some((parent, now));
+                now
+            }
+        };
+        started.elapsed()
+            >= Duration::from_secs(self.round_robin_grace_period.saturating_mul(position))
+    }
+
+    /// Sign the seal on a separate thread, so that a slow signer doesn't hold up the miner. The
+    /// client is asked to update sealing once the seal is ready.
+    fn sign_in_background(&self, signer: Arc<dyn EngineSigner>, hash: H256) -> Seal {
+        let pending = PendingSeal::new();
+        let handle = pending.clone();
+        let client = self.client.read().clone();
+        let spawned = thread::Builder::new()
+            .name("BasicAuthoritySeal".into())
+            .spawn(move || {
+                if handle.is_cancelled() {
+                    return;
+                }
+                let seal = match signer.sign(hash) {
+                    Ok(signature) => {
+                        Seal::Regular(vec![::rlp::encode(&(H520::from(signature).as_bytes()))])
+                    }
+                    Err(e) => {
+                        warn!(target: "basicauthority", "Failed to sign block {}: {}", hash, e);
+                        Seal::None
+                    }
+                };
+                let sealed = seal != Seal::None;
+                if handle.complete(seal) && sealed {
+                    if let Some(client) = client.and_then(|client| client.upgrade()) {
+                        client.update_sealing(ForceUpdateSealing::Yes);
+                    }
+                }
+            });
+        match spawned {
+            Ok(_) => Seal::Pending(pending),
+            Err(e) => {
+                warn!(target: "basicauthority", "Failed to start signing block {}: {}", hash, e);
+                Seal::None
+            }
         }
     }
 }
@@ -113,10 +320,37 @@ impl Engine<EthereumMachine> for BasicAuthority {
     }
 
     /// Attempt to seal the block internally.
-    fn generate_seal(&self, block: &ExecutedBlock, _parent: &Header) -> Seal {
+    fn generate_seal(&self, block: &ExecutedBlock, parent: &Header) -> Seal {
         let header = &block.header;
-        let author = header.author();
-        if self.validators.contains(header.parent_hash(), author) {
+        let (signer, slow_signer) = match *self.signer.read() {
+            Some(ref signer) if signer.is_slow() => (signer.address(), Some(signer.clone())),
+            Some(ref signer) => (signer.address(), None),
+            None => return Seal::None,
+        };
+        let author_allowed =
+            *header.author() == signer || self.supports_fee_recipient(header.number());
+        if self.is_round_robin(header.number()) {
+            let position = match verify_turn(header, &signer, &*self.validators).and_then(
+                |position| {
+                    self.verify_recent_signers(header, parent, &signer)
+                        .map(|_| position)
+                },
+            ) {
+                Ok(position) => position,
+                Err(e) => {
+                    trace!(target: "basicauthority", "generate_seal: not our turn to seal #{}: {}", header.number(), e);
+                    return Seal::None;
+                }
+            };
+            if position > 0 && !self.out_of_turn_wait_elapsed(parent.hash(), position) {
+                trace!(target: "basicauthority", "generate_seal: waiting to seal #{} out of turn", header.number());
+                return Seal::None;
+            }
+        }
+        if author_allowed && self.validators.contains(header.parent_hash(), &signer) {
+            if let Some(signer) = slow_signer {
+                return self.sign_in_background(signer, header.bare_hash());
+            }
             // account should be pernamently unlocked, otherwise sealing will fail
             if let Ok(signature) = self.sign(header.bare_hash()) {
                 return Seal::Regular(vec![::rlp::encode(&(H520::from(signature).as_bytes()))]);
@@ -131,8 +365,37 @@ impl Engine<EthereumMachine> for BasicAuthority {
         Ok(())
     }
 
+    fn verify_block_family(&self, header: &Header, parent: &Header) -> Result<(), Error> {
+        if !self.is_round_robin(header.number()) {
+            return Ok(());
+        }
+        let signer = recover_signer(header)?;
+        verify_turn(header, &signer, &*self.validators)?;
+        self.verify_recent_signers(header, parent, &signer)
+    }
+
     fn verify_block_external(&self, header: &Header) -> Result<(), Error> {
-        verify_external(header, &*self.validators)
+        verify_external(header, &*self.validators, self.fee_recipient_transition)
+    }
+
+    fn supports_fee_recipient(&self, block_number: BlockNumber) -> bool {
+        block_number >= self.fee_recipient_transition
+    }
+
+    fn populate_from_parent(&self, header: &mut Header, _parent: &Header) {
+        if !self.is_round_robin(header.number()) {
+            return;
+        }
+        // Whether an out-of-turn block may be sealed yet is checked in `generate_seal`.
+        let scheduled = self
+            .validators
+            .get(header.parent_hash(), header.number() as usize);
+        let in_turn = self
+            .signer
+            .read()
+            .as_ref()
+            .map_or(false, |signer| signer.address() == scheduled);
+        header.set_difficulty(if in_turn { DIFF_INTURN } else { DIFF_NOTURN });
     }
 
     fn genesis_epoch_data(&self, header: &Header, call: &Call) -> Result<Vec<u8>, String> {
@@ -185,7 +448,10 @@ impl Engine<EthereumMachine> for BasicAuthority {
             .epoch_set(first, &self.machine, header.number(), proof)
         {
             Ok((list, finalize)) => {
-                let verifier = Box::new(EpochVerifier { list: list });
+                let verifier = Box::new(EpochVerifier {
+                    list: list,
+                    fee_recipient_transition: self.fee_recipient_transition,
+                });
 
                 // our epoch verifier will ensure no unverified verifier is ever verified.
                 match finalize {
@@ -198,11 +464,12 @@ impl Engine<EthereumMachine> for BasicAuthority {
     }
 
     fn register_client(&self, client: Weak<dyn EngineClient>) {
+        *self.client.write() = Some(client.clone());
         self.validators.register_client(client);
     }
 
     fn set_signer(&self, signer: Option<Box<dyn EngineSigner>>) {
-        *self.signer.write() = signer;
+        *self.signer.write() = signer.map(Arc::from);
     }
 
     fn sign(&self, hash: H256) -> Result<Signature, Error> {
@@ -219,19 +486,33 @@ impl Engine<EthereumMachine> for BasicAuthority {
     }
 
     fn fork_choice(&self, new: &ExtendedHeader, current: &ExtendedHeader) -> super::ForkChoice {
+        // Under round-robin rotation in-turn blocks weigh more, so the heaviest chain is the one
+        // sealed by the scheduled validators.
         super::total_difficulty_fork_choice(new, current)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{
+        recent_signer_limit, verify_external, verify_not_recent, verify_turn, SimpleList,
+        DIFF_INTURN, DIFF_NOTURN,
+    };
     use accounts::AccountProvider;
     use block::*;
-    use engines::{Seal, SealingState};
-    use ethereum_types::H520;
+    use crypto::publickey::{self, Generator, Random};
+    use engines::{
+        signer::{self, EngineSigner},
+        Seal, SealingState,
+    };
+    use ethereum_types::{Address, H256, H520};
     use hash::keccak;
     use spec::Spec;
-    use std::sync::Arc;
+    use std::{
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
     use tempdir::TempDir;
     use test_helpers::get_temp_state_db;
     use types::header::Header;
@@ -252,7 +533,7 @@ mod tests {
     #[test]
     fn can_return_schedule() {
         let engine = new_test_authority().engine;
-        let schedule = engine.schedule(10000000);
+        let schedule = engine.schedule(10000000, 0);
         assert!(schedule.stack_limit > 0);
     }
 
@@ -299,6 +580,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn slow_signer_seals_in_background() {
+        struct SlowSigner(Box<dyn EngineSigner>);
+
+        impl EngineSigner for SlowSigner {
+            fn sign(&self, hash: H256) -> Result<publickey::Signature, publickey::Error> {
+                thread::sleep(Duration::from_millis(50));
+                self.0.sign(hash)
+            }
+
+            fn address(&self) -> Address {
+                self.0.address()
+            }
+
+            fn decrypt(
+                &self,
+                auth_data: &[u8],
+                cipher: &[u8],
+            ) -> Result<Vec<u8>, publickey::Error> {
+                self.0.decrypt(auth_data, cipher)
+            }
+
+            fn public(&self) -> Option<publickey::Public> {
+                self.0.public()
+            }
+
+            fn is_slow(&self) -> bool {
+                true
+            }
+        }
+
+        let keypair = publickey::KeyPair::from_secret(keccak("").into()).unwrap();
+        let addr = keypair.address();
+        let spec = new_test_authority();
+        let engine = &*spec.engine;
+        engine.set_signer(Some(Box::new(SlowSigner(signer::from_keypair(keypair)))));
+        let genesis_header = spec.genesis_header();
+        let db = spec
+            .ensure_db_good(get_temp_state_db(), &Default::default())
+            .unwrap();
+        let last_hashes = Arc::new(vec![genesis_header.hash()]);
+        let b = OpenBlock::new(
+            engine,
+            Default::default(),
+            false,
+            db,
+            &genesis_header,
+            last_hashes,
+            addr,
+            (3141562.into(), 31415620.into()),
+            vec![],
+            false,
+            None,
+        )
+        .unwrap();
+        let b = b.close_and_lock().unwrap();
+
+        let handle = match engine.generate_seal(&b, &genesis_header) {
+            Seal::Pending(handle) => handle,
+            seal => panic!("expected a pending seal, got {:?}", seal),
+        };
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let seal = loop {
+            match handle.take() {
+                Some(seal) => break seal,
+                None if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+                None => panic!("seal was not generated in time"),
+            }
+        };
+        match seal {
+            Seal::Regular(seal) => assert!(b.try_seal(engine, seal).is_ok()),
+            seal => panic!("expected a regular seal, got {:?}", seal),
+        }
+    }
+
     #[test]
     fn sealing_state() {
         let tap = AccountProvider::transient_provider();
@@ -311,4 +667,97 @@ mod tests {
         engine.set_signer(None);
         assert_eq!(SealingState::NotReady, engine.sealing_state());
     }
+
+    #[test]
+    fn fee_recipient_authored_block_verifies_after_transition() {
+        let keypair = Random.generate();
+        let validators = SimpleList::new(vec![keypair.address()]);
+
+        let mut header = Header::default();
+        header.set_number(10);
+        header.set_author(Address::from_low_u64_be(0xfee));
+        let signature = publickey::sign(keypair.secret(), &header.bare_hash()).unwrap();
+        header.set_seal(vec![::rlp::encode(&H520::from(signature).as_bytes())]);
+
+        assert!(verify_external(&header, &validators, 10).is_ok());
+        assert!(verify_external(&header, &validators, 11).is_err());
+    }
+
+    #[test]
+    fn round_robin_checks_difficulty_of_turn() {
+        let first = Address::from_low_u64_be(1);
+        let second = Address::from_low_u64_be(2);
+        let third = Address::from_low_u64_be(3);
+        let outsider = Address::from_low_u64_be(4);
+        let validators = SimpleList::new(vec![first, second, third]);
+
+        let mut header = Header::default();
+        header.set_number(3);
+
+        // height 3 is scheduled for the first validator, followed by the second and third
+        header.set_difficulty(DIFF_INTURN);
+        assert_eq!(verify_turn(&header, &first, &validators).unwrap(), 0);
+        assert!(verify_turn(&header, &second, &validators).is_err());
+
+        header.set_difficulty(DIFF_NOTURN);
+        assert!(verify_turn(&header, &first, &validators).is_err());
+        assert_eq!(verify_turn(&header, &second, &validators).unwrap(), 1);
+        assert_eq!(verify_turn(&header, &third, &validators).unwrap(), 2);
+        assert!(verify_turn(&header, &outsider, &validators).is_err());
+    }
+
+    #[test]
+    fn round_robin_keeps_sealing_with_one_validator_offline() {
+        let online = vec![Address::from_low_u64_be(2), Address::from_low_u64_be(3)];
+        let offline = Address::from_low_u64_be(1);
+        let validators = SimpleList::new(vec![offline, online[0], online[1]]);
+
+        let mut signers: Vec<Address> = Vec::new();
+        for number in 1..30 {
+            let recent = signers.iter().rev().cloned().collect::<Vec<_>>();
+            let mut header = Header::default();
+            header.set_number(number);
+            // the online validator closest behind the scheduled one which may seal
+            let sealer = online
+                .iter()
+                .filter_map(|signer| {
+                    [DIFF_INTURN, DIFF_NOTURN].iter().find_map(|difficulty| {
+                        header.set_difficulty(*difficulty);
+                        verify_turn(&header, signer, &validators)
+                            .ok()
+                            .filter(|_| verify_not_recent(signer, &recent, 3).is_ok())
+                            .map(|position| (position, *signer))
+                    })
+                })
+                .min()
+                .map(|(_, signer)| signer);
+            match sealer {
+                Some(signer) => signers.push(signer),
+                None => panic!("no validator may seal #{}", number),
+            }
+        }
+
+        // the online validators seal in turn whenever they can
+        assert_eq!(signers[0], online[0]);
+        assert!(signers.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn recent_signers_may_not_seal() {
+        let first = Address::from_low_u64_be(1);
+        let second = Address::from_low_u64_be(2);
+
+        assert!(verify_not_recent(&first, &[first], 3).is_err());
+        assert!(verify_not_recent(&first, &[second, first], 3).is_ok());
+        assert!(verify_not_recent(&first, &[second, first], 4).is_err());
+        assert!(verify_not_recent(&first, &[], 1).is_ok());
+    }
+
+    #[test]
+    fn recent_signer_limit_covers_half_of_the_validators() {
+        assert_eq!(recent_signer_limit(1), 1);
+        assert_eq!(recent_signer_limit(2), 2);
+        assert_eq!(recent_signer_limit(3), 2);
+        assert_eq!(recent_signer_limit(4), 3);
+    }
 }
@@ -25,9 +25,9 @@ use block::ExecutedBlock;
 use error::Error;
 use hash::keccak;
 use machine::Machine;
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 use trace::{self, ExecutiveTracer, Tracer, Tracing};
-use types::BlockNumber;
+use types::{header::Header, BlockNumber};
 
 use_contract!(block_reward_contract, "res/contracts/block_reward.json");
 
@@ -82,6 +82,100 @@ impl Into<trace::RewardType> for RewardKind {
     }
 }
 
+/// How the uncles of a block are rewarded during a reward era.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum UncleReward {
+    /// `(8 - depth) / 8` of the block reward, as in Ethash.
+    Depth,
+    /// The block reward divided by the given divisor, regardless of depth.
+    Divisor(U256),
+}
+
+/// Rewards paid from a given block on.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct RewardEra {
+    /// Reward of the block author, before uncle inclusion rewards.
+    pub reward: U256,
+    /// Reward of the uncles.
+    pub uncle_reward: UncleReward,
+}
+
+/// Engine-independent table of block reward eras, keyed by the first block of each era.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct RewardSchedule {
+    eras: BTreeMap<BlockNumber, RewardEra>,
+}
+
+impl RewardSchedule {
+    /// Create a schedule from eras keyed by their first block.
+    pub fn new(eras: BTreeMap<BlockNumber, RewardEra>) -> Self {
+        RewardSchedule { eras }
+    }
+
+    /// The era `number` belongs to, if the schedule has started by then.
+    pub fn era(&self, number: BlockNumber) -> Option<&RewardEra> {
+        self.eras.range(..=number).next_back().map(|(_, era)| era)
+    }
+
+    /// Rewards for a block `number` authored by `author` and including `uncles`.
+    ///
+    /// The author additionally receives 1/32 of the reward for every included uncle.
+    pub fn rewards(
+        &self,
+        author: Address,
+        number: BlockNumber,
+        uncles: &[Header],
+    ) -> Vec<(Address, RewardKind, U256)> {
+        let era = match self.era(number) {
+            Some(era) => era,
+            None => return Vec::new(),
+        };
+
+        let inclusion_reward = era.reward >> 5;
+        let mut rewards = vec![(
+            author,
+            RewardKind::Author,
+            era.reward + inclusion_reward * U256::from(uncles.len()),
+        )];
+        for uncle in uncles {
+            let uncle_reward = match era.uncle_reward {
+                UncleReward::Depth => {
+                    let depth = number.saturating_sub(uncle.number());
+                    (era.reward * U256::from(8u64.saturating_sub(depth))) >> 3
+                }
+                UncleReward::Divisor(divisor) => era.reward / divisor,
+            };
+            rewards.push((
+                *uncle.author(),
+                RewardKind::uncle(number, uncle.number()),
+                uncle_reward,
+            ));
+        }
+        rewards
+    }
+}
+
+impl From<Vec<::ethjson::spec::BlockRewardEra>> for RewardSchedule {
+    fn from(eras: Vec<::ethjson::spec::BlockRewardEra>) -> Self {
+        RewardSchedule::new(
+            eras.into_iter()
+                .map(|era| {
+                    let uncle_reward = era
+                        .uncle_reward_divisor
+                        .map_or(UncleReward::Depth, |d| UncleReward::Divisor(d.into()));
+                    (
+                        era.block.into(),
+                        RewardEra {
+                            reward: era.reward.into(),
+                            uncle_reward,
+                        },
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
 /// A client for the block reward contract.
 #[derive(PartialEq, Debug)]
 pub struct BlockRewardContract {
@@ -200,9 +294,58 @@ mod test {
     use spec::Spec;
     use test_helpers::generate_dummy_client_with_spec;
 
-    use super::{BlockRewardContract, RewardKind};
+    use super::{BlockRewardContract, RewardEra, RewardKind, RewardSchedule, UncleReward};
     use engines::SystemOrCodeCallKind;
     use std::str::FromStr;
+    use types::header::Header;
+
+    #[test]
+    fn reward_schedule_applies_eras_and_uncle_scaling() {
+        let author = H160::from_low_u64_be(1);
+        let uncle_author = H160::from_low_u64_be(2);
+        let schedule = RewardSchedule::new(
+            vec![
+                (
+                    10,
+                    RewardEra {
+                        reward: 800.into(),
+                        uncle_reward: UncleReward::Depth,
+                    },
+                ),
+                (
+                    20,
+                    RewardEra {
+                        reward: 320.into(),
+                        uncle_reward: UncleReward::Divisor(32.into()),
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let mut uncle = Header::new();
+        uncle.set_author(uncle_author);
+
+        assert!(schedule.rewards(author, 9, &[]).is_empty());
+
+        uncle.set_number(13);
+        assert_eq!(
+            schedule.rewards(author, 15, &[uncle.clone()]),
+            vec![
+                (author, RewardKind::Author, 825.into()),
+                (uncle_author, RewardKind::Uncle(2), 600.into()),
+            ]
+        );
+
+        uncle.set_number(23);
+        assert_eq!(
+            schedule.rewards(author, 25, &[uncle]),
+            vec![
+                (author, RewardKind::Author, 330.into()),
+                (uncle_author, RewardKind::Uncle(2), 10.into()),
+            ]
+        );
+    }
 
     #[test]
     fn block_reward_contract() {
@@ -22,6 +22,7 @@ use std::{
 
 use engines::{
     clique::{
+        params::VotingRules,
         util::{extract_signers, recover_creator},
         VoteType, DIFF_INTURN, DIFF_NOTURN, NULL_AUTHOR, SIGNING_DELAY_NOTURN_MS,
     },
@@ -75,6 +76,8 @@ pub struct CliqueBlockState {
     pub next_timestamp_inturn: Option<SystemTime>,
     /// noturn signing should wait until this time
     pub next_timestamp_noturn: Option<SystemTime>,
+    /// how votes are encoded and when they pass
+    voting: VotingRules,
 }
 
 #[cfg(test)]
@@ -92,6 +95,8 @@ pub struct CliqueBlockState {
     pub next_timestamp_inturn: Option<SystemTime>,
     /// noturn signing should wait until this time
     pub next_timestamp_noturn: Option<SystemTime>,
+    /// how votes are encoded and when they pass
+    pub voting: VotingRules,
 }
 
 impl fmt::Display for CliqueBlockState {
@@ -141,9 +146,10 @@ impl fmt::Display for CliqueBlockState {
 
 impl CliqueBlockState {
     /// Create new state with given information, this is used creating new state from Checkpoint block.
-    pub fn new(signers: BTreeSet<Address>) -> Self {
+    pub fn new(signers: BTreeSet<Address>, voting: VotingRules) -> Self {
         CliqueBlockState {
             signers,
+            voting,
             ..Default::default()
         }
     }
@@ -223,7 +229,7 @@ impl CliqueBlockState {
 
             let nonce = H64::from_slice(decoded_seal[1]);
             self.update_signers_on_vote(
-                VoteType::from_nonce(nonce)?,
+                self.voting.vote_type(nonce)?,
                 creator,
                 *header.author(),
                 header.number(),
@@ -269,12 +275,12 @@ impl CliqueBlockState {
             Some((v, k)) => (v, k),
             None => return Ok(()),
         };
-        let threshold = self.signers.len() / 2;
+        let required = self.voting.required_votes(self.signers.len());
 
-        debug!(target: "engine", "{}/{} votes to have consensus", votes, threshold + 1);
+        debug!(target: "engine", "{}/{} votes to have consensus", votes, required);
         trace!(target: "engine", "votes: {:?}", votes);
 
-        if votes > threshold {
+        if votes >= required {
             match vote_kind {
                 VoteType::Add => {
                     if self.signers.insert(beneficiary) {
@@ -60,9 +60,12 @@
 use std::cmp;
 use std::{
     collections::{HashMap, VecDeque},
-    sync::{Arc, Weak},
+    sync::{
+        atomic::{AtomicU64, Ordering as AtomicOrdering},
+        Arc, Weak,
+    },
     thread, time,
-    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, UNIX_EPOCH},
 };
 
 use super::signer::EngineSigner;
@@ -71,6 +74,7 @@ use client::{traits::ForceUpdateSealing, BlockId, EngineClient};
 use crypto::publickey::Signature;
 use engines::{
     clique::util::{extract_signers, recover_creator},
+    metrics::SealingMetrics,
     Engine, EngineError, Seal, SealingState,
 };
 use error::{BlockError, Error};
@@ -78,17 +82,21 @@ use ethereum_types::{Address, H160, H256, H64, U256};
 use hash::KECCAK_EMPTY_LIST_RLP;
 use itertools::Itertools;
 use lru_cache::LruCache;
-use machine::{Call, EthereumMachine};
+use machine::{Call, EthereumMachine, Machine};
 use parking_lot::RwLock;
 use rand::Rng;
-use time_utils::CheckedSystemTime;
+use stats::PrometheusRegistry;
+use time_utils::{CheckedSystemTime, Clock};
 use types::{
     header::{ExtendedHeader, Header},
     BlockNumber,
 };
 use unexpected::{Mismatch, OutOfBounds};
 
-use self::{block_state::CliqueBlockState, params::CliqueParams};
+use self::{
+    block_state::CliqueBlockState,
+    params::{CliqueParams, VotingRules},
+};
 
 mod block_state;
 mod params;
@@ -115,8 +123,6 @@ pub const DIFF_INTURN: U256 = U256([2, 0, 0, 0]);
 pub const DIFF_NOTURN: U256 = U256([1, 0, 0, 0]);
 /// Default empty author field value
 pub const NULL_AUTHOR: Address = H160([0x00; 20]);
-/// Default empty nonce value
-pub const NULL_NONCE: H64 = NONCE_DROP_VOTE;
 /// Default value for mixhash
 pub const NULL_MIXHASH: H256 = H256([0; 32]);
 /// Default value for uncles hash
@@ -134,38 +140,21 @@ pub enum VoteType {
     Remove,
 }
 
-impl VoteType {
-    /// Try to construct a `Vote` from a nonce
-    pub fn from_nonce(nonce: H64) -> Result<Self, Error> {
-        if nonce == NONCE_AUTH_VOTE {
-            Ok(VoteType::Add)
-        } else if nonce == NONCE_DROP_VOTE {
-            Ok(VoteType::Remove)
-        } else {
-            Err(EngineError::CliqueInvalidNonce(nonce))?
-        }
-    }
-
-    /// Get the rlp encoding of the vote
-    pub fn as_rlp(&self) -> Vec<Vec<u8>> {
-        match self {
-            VoteType::Add => vec![rlp::encode(&NULL_MIXHASH), rlp::encode(&NONCE_AUTH_VOTE)],
-            VoteType::Remove => vec![rlp::encode(&NULL_MIXHASH), rlp::encode(&NONCE_DROP_VOTE)],
-        }
-    }
-}
-
 /// Clique Engine implementation
 // block_state_by_hash -> block state indexed by header hash.
 #[cfg(not(test))]
 pub struct Clique {
     epoch_length: u64,
     period: u64,
+    voting: VotingRules,
     machine: EthereumMachine,
     client: RwLock<Option<Weak<dyn EngineClient>>>,
     block_state_by_hash: RwLock<LruCache<H256, CliqueBlockState>>,
     proposals: RwLock<HashMap<Address, VoteType>>,
     signer: RwLock<Option<Box<dyn EngineSigner>>>,
+    sealing_metrics: SealingMetrics,
+    sealed_inturn: AtomicU64,
+    sealed_noturn: AtomicU64,
 }
 
 #[cfg(test)]
@@ -173,11 +162,15 @@ pub struct Clique {
 pub struct Clique {
     pub epoch_length: u64,
     pub period: u64,
+    pub voting: VotingRules,
     pub machine: EthereumMachine,
     pub client: RwLock<Option<Weak<dyn EngineClient>>>,
     pub block_state_by_hash: RwLock<LruCache<H256, CliqueBlockState>>,
     pub proposals: RwLock<HashMap<Address, VoteType>>,
     pub signer: RwLock<Option<Box<dyn EngineSigner>>>,
+    pub sealing_metrics: SealingMetrics,
+    pub sealed_inturn: AtomicU64,
+    pub sealed_noturn: AtomicU64,
 }
 
 impl Clique {
@@ -189,10 +182,14 @@ impl Clique {
         let engine = Clique {
             epoch_length: params.epoch,
             period: params.period,
+            voting: params.voting,
             client: Default::default(),
             block_state_by_hash: RwLock::new(LruCache::new(STATE_CACHE_NUM)),
             proposals: Default::default(),
             signer: Default::default(),
+            sealing_metrics: Default::default(),
+            sealed_inturn: Default::default(),
+            sealed_noturn: Default::default(),
             machine,
         };
         let engine = Arc::new(engine);
@@ -222,16 +219,20 @@ impl Clique {
     /// Initialize test variant of `CliqueEngine`,
     /// Note we need to `mock` the miner and it is introduced to test block verification to trigger new blocks
     /// to mainly test consensus edge cases
-    pub fn with_test(epoch_length: u64, period: u64) -> Self {
+    pub fn with_test(epoch_length: u64, period: u64, voting: VotingRules) -> Self {
         use spec::Spec;
 
         Self {
             epoch_length,
             period,
+            voting,
             client: Default::default(),
             block_state_by_hash: RwLock::new(LruCache::new(STATE_CACHE_NUM)),
             proposals: Default::default(),
             signer: Default::default(),
+            sealing_metrics: Default::default(),
+            sealed_inturn: Default::default(),
+            sealed_noturn: Default::default(),
             machine: Spec::new_test_machine(),
         }
     }
@@ -253,7 +254,7 @@ impl Clique {
     fn new_checkpoint_state(&self, header: &Header) -> Result<CliqueBlockState, Error> {
         debug_assert_eq!(header.number() % self.epoch_length, 0);
 
-        let mut state = CliqueBlockState::new(extract_signers(header)?);
+        let mut state = CliqueBlockState::new(extract_signers(header)?, self.voting);
 
         // TODO(niklasad1): refactor to perform this check in the `CliqueBlockState` constructor instead
         state.calc_next_timestamp(header.timestamp(), self.period)?;
@@ -316,8 +317,9 @@ impl Clique {
                             return Err(BlockError::UnknownParent(last_parent_hash))?;
                         }
                         Some(next) => {
-                            chain
-                                .push_front(next.decode(self.machine.params().eip1559_transition)?);
+                            chain.push_front(
+                                next.decode(self.machine.params().header_decode_params())?,
+                            );
                         }
                     }
                 }
@@ -328,13 +330,14 @@ impl Clique {
                     .expect("chain has at least one element; qed")
                     .parent_hash();
 
-                let last_checkpoint_header =
-                    match c.block_header(BlockId::Hash(last_checkpoint_hash)) {
-                        None => {
-                            return Err(EngineError::CliqueMissingCheckpoint(last_checkpoint_hash))?
-                        }
-                        Some(header) => header.decode(self.machine.params().eip1559_transition)?,
-                    };
+                let last_checkpoint_header = match c
+                    .block_header(BlockId::Hash(last_checkpoint_hash))
+                {
+                    None => {
+                        return Err(EngineError::CliqueMissingCheckpoint(last_checkpoint_hash))?
+                    }
+                    Some(header) => header.decode(self.machine.params().header_decode_params())?,
+                };
 
                 let last_checkpoint_state = match block_state_by_hash.get_mut(&last_checkpoint_hash)
                 {
@@ -425,7 +428,7 @@ impl Engine<EthereumMachine> for Clique {
                 trace!(target: "engine", "Casting vote: beneficiary {}, type {:?} ", beneficiary, vote_type);
 
                 header.set_author(beneficiary);
-                header.set_seal(vote_type.as_rlp());
+                header.set_seal(self.voting.seal(vote_type));
             }
         }
 
@@ -481,10 +484,24 @@ impl Engine<EthereumMachine> for Clique {
         SealingState::Ready
     }
 
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        self.sealing_metrics.register(r);
+        r.register_counter(
+            "clique_sealed_inturn",
+            "Blocks sealed by this node in turn",
+            self.sealed_inturn.load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_counter(
+            "clique_sealed_noturn",
+            "Blocks sealed by this node out of turn",
+            self.sealed_noturn.load(AtomicOrdering::Relaxed) as i64,
+        );
+    }
+
     /// Returns if we are ready to seal, the real sealing (signing extra_data) is actually done in `on_seal_block()`.
     fn generate_seal(&self, block: &ExecutedBlock, parent: &Header) -> Seal {
         trace!(target: "engine", "tried to generate_seal");
-        let null_seal = util::null_seal();
+        let null_seal = self.voting.null_seal();
 
         if block.header.number() == 0 {
             trace!(target: "engine", "attempted to seal genesis block");
@@ -519,7 +536,7 @@ impl Engine<EthereumMachine> for Clique {
 
                     let inturn = state.is_inturn(block.header.number(), &author);
 
-                    let now = SystemTime::now();
+                    let now = self.machine.clock().now();
 
                     let limit = match inturn {
                         true => state.next_timestamp_inturn.unwrap_or(now),
@@ -531,7 +548,7 @@ impl Engine<EthereumMachine> for Clique {
                         trace!(target: "engine",
 								"generate_seal: sleeping to sign: inturn: {}, now: {:?}, to: {:?}.",
 								inturn, now, limit);
-                        match limit.duration_since(SystemTime::now()) {
+                        match limit.duration_since(self.machine.clock().now()) {
                             Ok(duration) => {
                                 thread::sleep(duration);
                             }
@@ -544,6 +561,17 @@ impl Engine<EthereumMachine> for Clique {
 
                     trace!(target: "engine", "generate_seal: seal ready for block {}, txs: {}.",
 							block.header.number(), block.transactions.len());
+                    let latency = self
+                        .machine
+                        .clock()
+                        .now()
+                        .duration_since(limit)
+                        .unwrap_or_default();
+                    self.sealing_metrics.note_sealed(latency);
+                    match inturn {
+                        true => self.sealed_inturn.fetch_add(1, AtomicOrdering::Relaxed),
+                        false => self.sealed_noturn.fetch_add(1, AtomicOrdering::Relaxed),
+                    };
                     return Seal::Regular(null_seal);
                 }
             }
@@ -565,9 +593,11 @@ impl Engine<EthereumMachine> for Clique {
 
         // Don't waste time checking blocks from the future
         {
-            let limit =
-                CheckedSystemTime::checked_add(SystemTime::now(), Duration::from_secs(self.period))
-                    .ok_or(BlockError::TimestampOverflow)?;
+            let limit = CheckedSystemTime::checked_add(
+                self.machine.clock().now(),
+                Duration::from_secs(self.period),
+            )
+            .ok_or(BlockError::TimestampOverflow)?;
 
             // This should succeed under the contraints that the system clock works
             let limit_as_dur = limit.duration_since(UNIX_EPOCH).map_err(|e| {
@@ -607,12 +637,10 @@ impl Engine<EthereumMachine> for Clique {
         let mixhash = H256::from_slice(seal_fields[0]);
         let nonce = H64::from_slice(seal_fields[1]);
 
-        // Nonce must be 0x00..0 or 0xff..f
-        if nonce != NONCE_DROP_VOTE && nonce != NONCE_AUTH_VOTE {
-            Err(EngineError::CliqueInvalidNonce(nonce))?;
-        }
+        // Nonce must be the drop or auth vote nonce, 0x00..0 or 0xff..f by default
+        self.voting.vote_type(nonce)?;
 
-        if is_checkpoint && nonce != NULL_NONCE {
+        if is_checkpoint && nonce != self.voting.checkpoint_nonce() {
             Err(EngineError::CliqueInvalidNonce(nonce))?;
         }
 
@@ -704,7 +732,10 @@ impl Engine<EthereumMachine> for Clique {
         let parent_state = self.state(&parent)?;
         // Try to apply current state, apply() will further check signer and recent signer.
         let mut new_state = parent_state.clone();
-        new_state.apply(header, header.number() % self.epoch_length == 0)?;
+        if let Err(e) = new_state.apply(header, header.number() % self.epoch_length == 0) {
+            self.sealing_metrics.note_verification_error(&e);
+            return Err(e);
+        }
         new_state.calc_next_timestamp(header.timestamp(), self.period)?;
         self.block_state_by_hash
             .write()
@@ -793,13 +824,8 @@ impl Engine<EthereumMachine> for Clique {
 
     /// Clique timestamp is set to parent + period , or current time which ever is higher.
     fn open_block_header_timestamp(&self, parent_timestamp: u64) -> u64 {
-        let now = time::SystemTime::now()
-            .duration_since(time::UNIX_EPOCH)
-            .unwrap_or_default();
-        cmp::max(
-            now.as_secs() as u64,
-            parent_timestamp.saturating_add(self.period),
-        )
+        let now = self.machine.clock().unix_now();
+        cmp::max(now.as_secs(), parent_timestamp.saturating_add(self.period))
     }
 
     fn is_timestamp_valid(&self, header_timestamp: u64, parent_timestamp: u64) -> bool {
@@ -16,23 +16,159 @@
 
 //! Clique specific parameters.
 
+use engines::clique::{VoteType, NONCE_AUTH_VOTE, NONCE_DROP_VOTE, NULL_MIXHASH};
+use engines::EngineError;
+use error::Error;
+use ethereum_types::H64;
 use ethjson;
 
+/// Default block period, in seconds.
+///
+/// Together with `DEFAULT_EPOCH` these are the defaults specs without the fields have always
+/// been loaded with, so they are kept even though they look swapped.
+pub const DEFAULT_PERIOD: u64 = 30000;
+/// Default epoch length, in blocks.
+pub const DEFAULT_EPOCH: u64 = 15;
+/// Default percentage of the signers a proposal has to exceed to pass.
+pub const DEFAULT_VOTE_THRESHOLD: u64 = 50;
+
 /// `Clique` params.
 pub struct CliqueParams {
     /// Period as defined in EIP
     pub period: u64,
     /// Epoch length as defined in EIP
     pub epoch: u64,
+    /// Voting rules.
+    pub voting: VotingRules,
 }
 
 impl From<ethjson::spec::CliqueParams> for CliqueParams {
     fn from(p: ethjson::spec::CliqueParams) -> Self {
-        let period = p.period.map_or_else(|| 30000 as u64, Into::into);
-        let epoch = p.epoch.map_or_else(|| 15 as u64, Into::into);
+        let period = p.period.unwrap_or(DEFAULT_PERIOD);
+        let epoch = p.epoch.map_or(DEFAULT_EPOCH, Into::into);
+        let voting = VotingRules {
+            auth_nonce: p.auth_nonce.map_or(NONCE_AUTH_VOTE, Into::into),
+            drop_nonce: p.drop_nonce.map_or(NONCE_DROP_VOTE, Into::into),
+            vote_threshold: p.vote_threshold.unwrap_or(DEFAULT_VOTE_THRESHOLD),
+        };
 
         assert!(epoch > 0);
+        assert!(
+            voting.vote_threshold < 100,
+            "Clique vote threshold must be below 100%"
+        );
+        assert_ne!(
+            voting.auth_nonce, voting.drop_nonce,
+            "Clique auth and drop nonces must differ"
+        );
+
+        CliqueParams {
+            period,
+            epoch,
+            voting,
+        }
+    }
+}
+
+/// How votes are encoded in the header nonce and when they pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VotingRules {
+    /// Nonce of a vote to add the beneficiary to the signers.
+    pub auth_nonce: H64,
+    /// Nonce of a vote to remove the beneficiary, also required on checkpoint blocks and
+    /// blocks without a vote.
+    pub drop_nonce: H64,
+    /// Percentage of the signers a proposal has to exceed to pass.
+    pub vote_threshold: u64,
+}
+
+impl Default for VotingRules {
+    fn default() -> Self {
+        VotingRules {
+            auth_nonce: NONCE_AUTH_VOTE,
+            drop_nonce: NONCE_DROP_VOTE,
+            vote_threshold: DEFAULT_VOTE_THRESHOLD,
+        }
+    }
+}
+
+impl VotingRules {
+    /// Try to construct a `Vote` from a nonce
+    pub fn vote_type(&self, nonce: H64) -> Result<VoteType, Error> {
+        if nonce == self.auth_nonce {
+            Ok(VoteType::Add)
+        } else if nonce == self.drop_nonce {
+            Ok(VoteType::Remove)
+        } else {
+            Err(EngineError::CliqueInvalidNonce(nonce))?
+        }
+    }
+
+    /// Nonce required on checkpoint blocks.
+    pub fn checkpoint_nonce(&self) -> H64 {
+        self.drop_nonce
+    }
+
+    /// Get the rlp encoding of the vote
+    pub fn seal(&self, vote: VoteType) -> Vec<Vec<u8>> {
+        let nonce = match vote {
+            VoteType::Add => self.auth_nonce,
+            VoteType::Remove => self.drop_nonce,
+        };
+        vec![rlp::encode(&NULL_MIXHASH), rlp::encode(&nonce)]
+    }
+
+    /// Seal of a block without a vote.
+    pub fn null_seal(&self) -> Vec<Vec<u8>> {
+        vec![
+            rlp::encode(&NULL_MIXHASH.as_bytes().to_vec()),
+            rlp::encode(&self.drop_nonce.as_bytes().to_vec()),
+        ]
+    }
+
+    /// Number of votes a proposal needs with the given number of signers.
+    pub fn required_votes(&self, signers: usize) -> usize {
+        (signers as u64 * self.vote_threshold / 100) as usize + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CliqueParams, VotingRules, DEFAULT_EPOCH, DEFAULT_PERIOD};
+    use ethjson;
+
+    #[test]
+    fn defaults_period_and_epoch() {
+        let params: CliqueParams = ethjson::spec::CliqueParams {
+            period: None,
+            epoch: None,
+            vote_threshold: None,
+            auth_nonce: None,
+            drop_nonce: None,
+        }
+        .into();
+        assert_eq!(params.period, 30000);
+        assert_eq!(params.epoch, 15);
+        assert_eq!((DEFAULT_PERIOD, DEFAULT_EPOCH), (30000, 15));
+        assert_eq!(params.voting, VotingRules::default());
+    }
+
+    #[test]
+    fn default_threshold_is_simple_majority() {
+        let rules = VotingRules::default();
+        for signers in 1..20 {
+            assert_eq!(rules.required_votes(signers), signers / 2 + 1);
+        }
+    }
 
-        CliqueParams { period, epoch }
+    #[test]
+    fn custom_threshold() {
+        let rules = VotingRules {
+            vote_threshold: 66,
+            ..Default::default()
+        };
+        assert_eq!(rules.required_votes(3), 2);
+        assert_eq!(rules.required_votes(4), 3);
+        assert_eq!(rules.required_votes(10), 7);
     }
 }
@@ -55,13 +55,23 @@ pub struct CliqueTester {
 impl CliqueTester {
     /// Create a `Clique` tester with settings
     pub fn with(epoch: u64, period: u64, initial_signers: Vec<char>) -> Self {
+        Self::with_voting(epoch, period, initial_signers, VotingRules::default())
+    }
+
+    /// Create a `Clique` tester with settings and custom voting rules
+    pub fn with_voting(
+        epoch: u64,
+        period: u64,
+        initial_signers: Vec<char>,
+        voting: VotingRules,
+    ) -> Self {
         assert_eq!(
             initial_signers.iter().all(|s| SIGNER_TAGS.contains(s)),
             true,
             "Not all the initial signers is in SIGNER_TAGS, possible keys are 'A' ..= 'F'"
         );
 
-        let clique = Clique::with_test(epoch, period);
+        let clique = Clique::with_test(epoch, period, voting);
         let mut genesis = Header::default();
         let mut signers = HashMap::new();
 
@@ -86,7 +96,7 @@ impl CliqueTester {
         genesis.set_extra_data(extra_data);
         genesis.set_gas_limit(U256::from(0xa00000));
         genesis.set_difficulty(U256::from(1));
-        genesis.set_seal(util::null_seal());
+        genesis.set_seal(clique.voting.null_seal());
 
         clique
             .genesis_epoch_data(&genesis, &call)
@@ -160,7 +170,7 @@ impl CliqueTester {
         signer: char,
     ) -> Result<Header, Error> {
         let mut extra_data = vec![0; VANITY_LENGTH];
-        let mut seal = util::null_seal();
+        let mut seal = self.clique.voting.null_seal();
         let last_hash = last_header.hash();
 
         match block_type {
@@ -170,7 +180,7 @@ impl CliqueTester {
                     extra_data.extend(signer.as_bytes());
                 }
             }
-            CliqueBlockType::Vote(v) => seal = v.as_rlp(),
+            CliqueBlockType::Vote(v) => seal = self.clique.voting.seal(v),
             CliqueBlockType::Empty => (),
         };
 
@@ -266,6 +276,51 @@ fn one_signer_two_votes() {
     assert_eq!(&tags, &['A', 'B']);
 }
 
+#[test]
+fn custom_vote_threshold() {
+    let voting = VotingRules {
+        vote_threshold: 0,
+        ..Default::default()
+    };
+    let tester = CliqueTester::with_voting(10, 1, vec!['A', 'B'], voting);
+
+    // A single vote is enough to add `C`
+    let vote = tester
+        .new_block_and_import(
+            CliqueBlockType::Vote(VoteType::Add),
+            &tester.genesis,
+            Some(tester.signers[&'C'].address()),
+            'A',
+        )
+        .unwrap();
+    let tags = tester.into_tags(tester.clique_signers(&vote.hash()));
+    assert_eq!(&tags, &['A', 'B', 'C']);
+}
+
+#[test]
+fn custom_vote_nonces() {
+    let voting = VotingRules {
+        auth_nonce: H64::from_low_u64_be(1),
+        drop_nonce: H64::from_low_u64_be(2),
+        ..Default::default()
+    };
+    let tester = CliqueTester::with_voting(10, 1, vec!['A'], voting);
+
+    let vote = tester
+        .new_block_and_import(
+            CliqueBlockType::Vote(VoteType::Add),
+            &tester.genesis,
+            Some(tester.signers[&'B'].address()),
+            'A',
+        )
+        .unwrap();
+    let tags = tester.into_tags(tester.clique_signers(&vote.hash()));
+    assert_eq!(&tags, &['A', 'B']);
+
+    // The standard nonces are not valid votes any more
+    assert!(tester.clique.voting.vote_type(NONCE_AUTH_VOTE).is_err());
+}
+
 #[test]
 fn two_signers_six_votes_deny_last() {
     let tester = CliqueTester::with(10, 1, vec!['A', 'B']);
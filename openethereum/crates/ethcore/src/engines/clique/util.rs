@@ -18,14 +18,13 @@ use std::collections::BTreeSet;
 
 use crypto::publickey::{public_to_address, recover as ec_recover, Signature};
 use engines::{
-    clique::{ADDRESS_LENGTH, NULL_MIXHASH, NULL_NONCE, SIGNATURE_LENGTH, VANITY_LENGTH},
+    clique::{ADDRESS_LENGTH, SIGNATURE_LENGTH, VANITY_LENGTH},
     EngineError,
 };
 use error::Error;
 use ethereum_types::{Address, H160, H256};
 use lru_cache::LruCache;
 use parking_lot::RwLock;
-use rlp::encode;
 use types::header::Header;
 
 /// How many recovered signature to cache in the memory.
@@ -112,11 +111,3 @@ pub fn extract_signers(header: &Header) -> Result<BTreeSet<Address>, Error> {
 
     Ok(signers)
 }
-
-/// Retrieve `null_seal`
-pub fn null_seal() -> Vec<Vec<u8>> {
-    vec![
-        encode(&NULL_MIXHASH.as_bytes().to_vec()),
-        encode(&NULL_NONCE.as_bytes().to_vec()),
-    ]
-}
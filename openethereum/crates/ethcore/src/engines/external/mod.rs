@@ -0,0 +1,573 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An engine delegating consensus decisions to an out-of-process plugin.
+//!
+//! Block verification, sealing and fork choice are forwarded to the plugin using the versioned
+//! protobuf messages of `consensus_plugin.proto`, so that consensus rules can be prototyped
+//! without changing this crate. The node stays in charge of executing blocks.
+//!
+//! Blocks which can't be verified because the plugin is unreachable are not invalid, their
+//! import fails with `EngineError::Unavailable` and is retried later. If the plugin can't be
+//! asked for a fork choice, the built-in total difficulty rule is used instead.
+
+mod protocol;
+
+use self::protocol::{Request, Response, Stage};
+
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::Weak,
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+use block::ExecutedBlock;
+use client::EngineClient;
+use engines::{total_difficulty_fork_choice, Engine, EngineError, ForkChoice, Seal, SealingState};
+use error::Error;
+use ethereum_types::{H256, U256};
+use ethjson;
+use machine::EthereumMachine;
+use parking_lot::{Mutex, RwLock};
+use types::{
+    header::{ExtendedHeader, Header},
+    ids::BlockId,
+};
+
+/// Time to wait for a reply of the plugin if the spec does not say otherwise.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time to wait for a connection to the plugin if the spec does not say otherwise.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// `ExternalEngine` params.
+#[derive(Debug, PartialEq)]
+pub struct ExternalEngineParams {
+    /// Plugin endpoint: `tcp://host:port`, or the path of a Unix socket.
+    pub endpoint: String,
+    /// Number of seal fields in a header.
+    pub seal_fields: usize,
+    /// Time to wait for a reply of the plugin.
+    pub timeout: Duration,
+    /// Time to wait for a connection to the plugin.
+    pub connect_timeout: Duration,
+}
+
+impl From<ethjson::spec::ExternalEngineParams> for ExternalEngineParams {
+    fn from(p: ethjson::spec::ExternalEngineParams) -> Self {
+        ExternalEngineParams {
+            endpoint: p.endpoint,
+            seal_fields: p.seal_fields.map_or(1, Into::into),
+            timeout: p
+                .timeout
+                .map_or(DEFAULT_TIMEOUT, |ms| Duration::from_millis(ms.into())),
+            connect_timeout: p.connect_timeout.map_or(DEFAULT_CONNECT_TIMEOUT, |ms| {
+                Duration::from_millis(ms.into())
+            }),
+        }
+    }
+}
+
+/// Channel to a consensus plugin.
+pub trait PluginTransport: Send + Sync {
+    /// Send an encoded request and wait for the encoded response.
+    fn call(&self, request: &[u8]) -> io::Result<Vec<u8>>;
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Stream {
+    fn connect(endpoint: &str, timeout: Duration) -> io::Result<Stream> {
+        if endpoint.starts_with("tcp://") {
+            let mut last_err = io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "plugin endpoint does not resolve to any address",
+            );
+            for addr in endpoint["tcp://".len()..].to_socket_addrs()? {
+                match TcpStream::connect_timeout(&addr, timeout) {
+                    Ok(stream) => {
+                        stream.set_nodelay(true)?;
+                        return Ok(Stream::Tcp(stream));
+                    }
+                    Err(e) => last_err = e,
+                }
+            }
+            return Err(last_err);
+        }
+        Self::connect_unix(endpoint)
+    }
+
+    // connecting to a local socket does not wait for the other side to accept.
+    #[cfg(unix)]
+    fn connect_unix(path: &str) -> io::Result<Stream> {
+        Ok(Stream::Unix(UnixStream::connect(path)?))
+    }
+
+    #[cfg(not(unix))]
+    fn connect_unix(_path: &str) -> io::Result<Stream> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "only tcp:// plugin endpoints are supported on this platform",
+        ))
+    }
+
+    fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        match *self {
+            Stream::Tcp(ref s) => {
+                s.set_read_timeout(Some(timeout))?;
+                s.set_write_timeout(Some(timeout))
+            }
+            #[cfg(unix)]
+            Stream::Unix(ref s) => {
+                s.set_read_timeout(Some(timeout))?;
+                s.set_write_timeout(Some(timeout))
+            }
+        }
+    }
+
+    /// Send the request and read the reply, all within `timeout`.
+    fn call(&mut self, request: &[u8], timeout: Duration) -> io::Result<Vec<u8>> {
+        let mut stream = Deadline {
+            stream: self,
+            deadline: Instant::now() + timeout,
+        };
+        protocol::write_message(&mut stream, request)?;
+        protocol::read_message(&mut stream)
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            Stream::Tcp(ref mut s) => s.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(ref mut s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            Stream::Tcp(ref mut s) => s.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(ref mut s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            Stream::Tcp(ref mut s) => s.flush(),
+            #[cfg(unix)]
+            Stream::Unix(ref mut s) => s.flush(),
+        }
+    }
+}
+
+/// Limits the socket timeouts of every read and write to the time left until `deadline`, so
+/// that a plugin trickling its reply can't hold up the caller.
+struct Deadline<'a> {
+    stream: &'a mut Stream,
+    deadline: Instant,
+}
+
+impl<'a> Deadline<'a> {
+    fn arm(&mut self) -> io::Result<()> {
+        let now = Instant::now();
+        if now >= self.deadline {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "consensus plugin call timed out",
+            ));
+        }
+        self.stream.set_timeout(self.deadline - now)
+    }
+}
+
+impl<'a> Read for Deadline<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.arm()?;
+        self.stream.read(buf)
+    }
+}
+
+impl<'a> Write for Deadline<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.arm()?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.arm()?;
+        self.stream.flush()
+    }
+}
+
+/// Transport over a TCP or Unix socket. The connection is (re-)established on demand.
+pub struct SocketTransport {
+    endpoint: String,
+    timeout: Duration,
+    connect_timeout: Duration,
+    stream: Mutex<Option<Stream>>,
+}
+
+impl SocketTransport {
+    /// Create a transport to the plugin listening at `endpoint`.
+    pub fn new(endpoint: String, timeout: Duration, connect_timeout: Duration) -> Self {
+        SocketTransport {
+            endpoint,
+            timeout,
+            connect_timeout,
+            stream: Mutex::new(None),
+        }
+    }
+}
+
+impl PluginTransport for SocketTransport {
+    fn call(&self, request: &[u8]) -> io::Result<Vec<u8>> {
+        let mut stream = self.stream.lock();
+        if stream.is_none() {
+            *stream = Some(Stream::connect(&self.endpoint, self.connect_timeout)?);
+        }
+        let result = stream
+            .as_mut()
+            .expect("connection was just established; qed")
+            .call(request, self.timeout);
+        if result.is_err() {
+            // the reply may still arrive later, never reuse a connection out of sync
+            *stream = None;
+        }
+        result
+    }
+}
+
+/// Engine forwarding consensus rules to an external plugin.
+pub struct ExternalEngine {
+    machine: EthereumMachine,
+    seal_fields: usize,
+    transport: Box<dyn PluginTransport>,
+    client: RwLock<Option<Weak<dyn EngineClient>>>,
+    /// Fork choice obtained while the block was verified, for new and current best hash.
+    fork_choice: Mutex<Option<(H256, H256, ForkChoice)>>,
+}
+
+impl ExternalEngine {
+    /// Create an engine talking to the plugin at the endpoint given in `params`.
+    pub fn new(params: ExternalEngineParams, machine: EthereumMachine) -> Self {
+        let transport = SocketTransport::new(
+            params.endpoint.clone(),
+            params.timeout,
+            params.connect_timeout,
+        );
+        Self::with_transport(params, machine, Box::new(transport))
+    }
+
+    /// Create an engine talking to a plugin over the given transport.
+    pub fn with_transport(
+        params: ExternalEngineParams,
+        machine: EthereumMachine,
+        transport: Box<dyn PluginTransport>,
+    ) -> Self {
+        ExternalEngine {
+            machine,
+            seal_fields: params.seal_fields,
+            transport,
+            client: RwLock::new(None),
+            fork_choice: Mutex::new(None),
+        }
+    }
+
+    fn request(&self, request: &Request) -> Result<Response, EngineError> {
+        let reply = self
+            .transport
+            .call(&request.encode())
+            .map_err(|e| EngineError::Unavailable(format!("consensus plugin: {}", e)))?;
+        Response::decode(&reply)
+            .map_err(|e| EngineError::MalformedMessage(format!("Consensus plugin reply: {}", e)))
+    }
+
+    fn verify(&self, stage: Stage, header: &Header, parent: Option<&Header>) -> Result<(), Error> {
+        match self.request(&Request::Verify {
+            stage,
+            header,
+            parent,
+        })? {
+            Response::Valid => Ok(()),
+            Response::Invalid(reason) => Err(EngineError::Custom(reason).into()),
+            other => Err(unexpected_response(other).into()),
+        }
+    }
+
+    fn request_fork_choice(
+        &self,
+        new: &Header,
+        new_total_score: U256,
+        current: &Header,
+        current_total_score: U256,
+    ) -> Result<ForkChoice, EngineError> {
+        match self.request(&Request::ForkChoice {
+            new,
+            new_total_score,
+            current,
+            current_total_score,
+        })? {
+            Response::ForkChoice(choice) => Ok(choice),
+            other => Err(unexpected_response(other)),
+        }
+    }
+
+    /// Ask the plugin for the fork choice against the current best block while a failure can
+    /// still fail the import of `header`.
+    fn prepare_fork_choice(&self, header: &Header) -> Result<(), Error> {
+        let client = match self.client.read().as_ref().and_then(Weak::upgrade) {
+            Some(client) => client,
+            None => return Ok(()),
+        };
+        let client = match client.as_full_client() {
+            Some(client) => client,
+            None => return Ok(()),
+        };
+        let current = client.best_block_header();
+        let scores = (
+            client.block_total_difficulty(BlockId::Hash(*header.parent_hash())),
+            client.block_total_difficulty(BlockId::Hash(current.hash())),
+        );
+        let (parent_score, current_score) = match scores {
+            (Some(parent), Some(current)) => (parent, current),
+            _ => return Ok(()),
+        };
+        let choice = self.request_fork_choice(
+            header,
+            parent_score + *header.difficulty(),
+            &current,
+            current_score,
+        )?;
+        *self.fork_choice.lock() = Some((header.hash(), current.hash(), choice));
+        Ok(())
+    }
+}
+
+fn unexpected_response(response: Response) -> EngineError {
+    EngineError::MalformedMessage(format!("Unexpected consensus plugin reply: {:?}", response))
+}
+
+impl Engine<EthereumMachine> for ExternalEngine {
+    fn name(&self) -> &str {
+        "External"
+    }
+
+    fn machine(&self) -> &EthereumMachine {
+        &self.machine
+    }
+
+    fn seal_fields(&self, _header: &Header) -> usize {
+        self.seal_fields
+    }
+
+    fn sealing_state(&self) -> SealingState {
+        // whether to seal is up to the plugin, see `generate_seal`.
+        SealingState::Ready
+    }
+
+    fn generate_seal(&self, block: &ExecutedBlock, parent: &Header) -> Seal {
+        let request = Request::GenerateSeal {
+            header: &block.header,
+            parent,
+        };
+        match self.request(&request) {
+            Ok(Response::Seal(seal)) => seal,
+            Ok(other) => {
+                warn!(target: "engine", "generate_seal: {}", unexpected_response(other));
+                Seal::None
+            }
+            Err(e) => {
+                warn!(target: "engine", "generate_seal: {}", e);
+                Seal::None
+            }
+        }
+    }
+
+    fn verify_local_seal(&self, header: &Header) -> Result<(), Error> {
+        self.verify(Stage::Local, header, None)
+    }
+
+    fn verify_block_basic(&self, header: &Header) -> Result<(), Error> {
+        self.verify(Stage::Basic, header, None)
+    }
+
+    fn verify_block_unordered(&self, header: &Header) -> Result<(), Error> {
+        self.verify(Stage::Unordered, header, None)
+    }
+
+    fn verify_block_family(&self, header: &Header, parent: &Header) -> Result<(), Error> {
+        self.verify(Stage::Family, header, Some(parent))
+    }
+
+    fn verify_block_external(&self, header: &Header) -> Result<(), Error> {
+        self.verify(Stage::External, header, None)?;
+        self.prepare_fork_choice(header)
+    }
+
+    fn register_client(&self, client: Weak<dyn EngineClient>) {
+        *self.client.write() = Some(client);
+    }
+
+    fn fork_choice(&self, new: &ExtendedHeader, current: &ExtendedHeader) -> ForkChoice {
+        if let Some((new_hash, current_hash, choice)) = self.fork_choice.lock().take() {
+            if new_hash == new.header.hash() && current_hash == current.header.hash() {
+                return choice;
+            }
+        }
+        match self.request_fork_choice(
+            &new.header,
+            new.total_score(),
+            &current.header,
+            current.total_score(),
+        ) {
+            Ok(choice) => choice,
+            Err(e) => {
+                warn!(
+                    target: "engine",
+                    "Consensus plugin failed to choose between #{} ({}) and best block #{} ({}), using the total difficulty: {}",
+                    new.header.number(),
+                    new.header.hash(),
+                    current.header.number(),
+                    current.header.hash(),
+                    e
+                );
+                total_difficulty_fork_choice(new, current)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use error::ErrorKind;
+    use spec::Spec;
+    use std::net::TcpListener;
+
+    /// Answers every request with the same reply.
+    struct Fixed(Bytes);
+
+    impl PluginTransport for Fixed {
+        fn call(&self, _request: &[u8]) -> io::Result<Vec<u8>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    struct Unreachable;
+
+    impl PluginTransport for Unreachable {
+        fn call(&self, _request: &[u8]) -> io::Result<Vec<u8>> {
+            Err(io::ErrorKind::ConnectionRefused.into())
+        }
+    }
+
+    fn engine(transport: Box<dyn PluginTransport>) -> ExternalEngine {
+        let params = ExternalEngineParams {
+            endpoint: String::new(),
+            seal_fields: 1,
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+        };
+        ExternalEngine::with_transport(params, Spec::new_test_machine(), transport)
+    }
+
+    fn extended(difficulty: u64) -> ExtendedHeader {
+        let mut header = Header::default();
+        header.set_difficulty(difficulty.into());
+        ExtendedHeader {
+            header,
+            is_finalized: false,
+            parent_total_difficulty: U256::zero(),
+        }
+    }
+
+    #[test]
+    fn forwards_verification_verdicts() {
+        let header = Header::default();
+
+        let accepting = engine(Box::new(Fixed(Response::Valid.encode())));
+        assert!(accepting.verify_block_basic(&header).is_ok());
+        assert!(accepting.verify_block_family(&header, &header).is_ok());
+
+        let rejecting = engine(Box::new(Fixed(
+            Response::Invalid("bad seal".into()).encode(),
+        )));
+        assert!(rejecting.verify_block_external(&header).is_err());
+    }
+
+    #[test]
+    fn unreachable_plugin_fails_import_temporarily() {
+        let engine = engine(Box::new(Unreachable));
+        match engine.verify_block_basic(&Header::default()) {
+            Err(Error(ErrorKind::Engine(EngineError::Unavailable(_)), _)) => {}
+            other => panic!("Expected the plugin to be unavailable, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unreachable_plugin_falls_back_to_total_difficulty() {
+        let engine = engine(Box::new(Unreachable));
+        assert_eq!(
+            engine.fork_choice(&extended(2), &extended(1)),
+            ForkChoice::New
+        );
+        assert_eq!(
+            engine.fork_choice(&extended(1), &extended(2)),
+            ForkChoice::Old
+        );
+    }
+
+    #[test]
+    fn uses_fork_choice_of_the_plugin() {
+        // keep the current best block, even though the new one has more difficulty
+        let engine = engine(Box::new(Fixed(
+            Response::ForkChoice(ForkChoice::Old).encode(),
+        )));
+        assert_eq!(
+            engine.fork_choice(&extended(2), &extended(1)),
+            ForkChoice::Old
+        );
+    }
+
+    #[test]
+    fn times_out_waiting_for_the_plugin() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("tcp://{}", listener.local_addr().unwrap());
+        let transport = SocketTransport::new(
+            endpoint,
+            Duration::from_millis(100),
+            DEFAULT_CONNECT_TIMEOUT,
+        );
+
+        // the connection is accepted by the backlog, but nobody ever replies
+        let start = Instant::now();
+        assert!(transport.call(&[1, 2, 3]).is_err());
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
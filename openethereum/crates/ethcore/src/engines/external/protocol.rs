@@ -0,0 +1,483 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Wire protocol spoken with external consensus plugins.
+//!
+//! Messages are the protobuf messages of `consensus_plugin.proto`, framed by their length as a
+//! 4-byte big-endian integer. The few messages are encoded by hand to avoid a code generator.
+//! Both sides must reject messages of a version they do not speak, so the schema can evolve
+//! incompatibly by bumping `PROTOCOL_VERSION`.
+
+use std::io::{self, Read, Write};
+
+use bytes::Bytes;
+use engines::{ForkChoice, Seal};
+use ethereum_types::U256;
+use types::header::Header;
+
+/// Version of the plugin protocol implemented here.
+pub const PROTOCOL_VERSION: u64 = 1;
+
+/// Upper bound on the size of a single message.
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+const WIRE_VARINT: u64 = 0;
+const WIRE_FIXED64: u64 = 1;
+const WIRE_BYTES: u64 = 2;
+const WIRE_FIXED32: u64 = 5;
+
+const FIELD_VERSION: u64 = 1;
+
+const REQUEST_VERIFY: u64 = 2;
+const REQUEST_GENERATE_SEAL: u64 = 3;
+const REQUEST_FORK_CHOICE: u64 = 4;
+
+const RESPONSE_VALID: u64 = 2;
+const RESPONSE_INVALID: u64 = 3;
+const RESPONSE_SEAL: u64 = 4;
+const RESPONSE_FORK_CHOICE: u64 = 5;
+
+const SEAL_NONE: u64 = 0;
+const SEAL_REGULAR: u64 = 1;
+const SEAL_PROPOSAL: u64 = 2;
+
+const FORK_CHOICE_OLD: u64 = 0;
+const FORK_CHOICE_NEW: u64 = 1;
+
+/// Verification step a header is checked at, see the `verify_*` methods of `Engine`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Seal of a locally produced block.
+    Local = 0,
+    /// Checks not depending on anything but the header.
+    Basic = 1,
+    /// Expensive checks not depending on other blocks.
+    Unordered = 2,
+    /// Checks against the parent header.
+    Family = 3,
+    /// Checks against the state of the parent.
+    External = 4,
+}
+
+/// Request sent to the plugin.
+#[derive(Debug, PartialEq)]
+pub enum Request<'a> {
+    /// Verify `header` at the given stage; `parent` is only given at the family stage.
+    Verify {
+        /// Verification step.
+        stage: Stage,
+        /// Header to verify.
+        header: &'a Header,
+        /// Parent of the header.
+        parent: Option<&'a Header>,
+    },
+    /// Seal the unsealed `header`, built on top of `parent`.
+    GenerateSeal {
+        /// Header to seal.
+        header: &'a Header,
+        /// Parent of the header.
+        parent: &'a Header,
+    },
+    /// Choose between a newly imported block and the current best block.
+    ForkChoice {
+        /// Newly imported header.
+        new: &'a Header,
+        /// Total score of the new header.
+        new_total_score: U256,
+        /// Current best header.
+        current: &'a Header,
+        /// Total score of the current best header.
+        current_total_score: U256,
+    },
+}
+
+impl<'a> Request<'a> {
+    /// Encode the request as a `Request` protobuf message.
+    pub fn encode(&self) -> Bytes {
+        let mut kind = Vec::new();
+        let field = match *self {
+            Request::Verify {
+                stage,
+                header,
+                parent,
+            } => {
+                put_varint_field(&mut kind, 1, stage as u64);
+                put_bytes_field(&mut kind, 2, &::rlp::encode(header));
+                if let Some(parent) = parent {
+                    put_bytes_field(&mut kind, 3, &::rlp::encode(parent));
+                }
+                REQUEST_VERIFY
+            }
+            Request::GenerateSeal { header, parent } => {
+                put_bytes_field(&mut kind, 1, &::rlp::encode(header));
+                put_bytes_field(&mut kind, 2, &::rlp::encode(parent));
+                REQUEST_GENERATE_SEAL
+            }
+            Request::ForkChoice {
+                new,
+                ref new_total_score,
+                current,
+                ref current_total_score,
+            } => {
+                put_bytes_field(&mut kind, 1, &::rlp::encode(new));
+                put_bytes_field(&mut kind, 2, &score_bytes(new_total_score));
+                put_bytes_field(&mut kind, 3, &::rlp::encode(current));
+                put_bytes_field(&mut kind, 4, &score_bytes(current_total_score));
+                REQUEST_FORK_CHOICE
+            }
+        };
+        let mut out = Vec::with_capacity(kind.len() + 16);
+        put_varint_field(&mut out, FIELD_VERSION, PROTOCOL_VERSION);
+        put_bytes_field(&mut out, field, &kind);
+        out
+    }
+}
+
+/// Reply of the plugin.
+#[derive(Debug, PartialEq)]
+pub enum Response {
+    /// The header passed verification.
+    Valid,
+    /// The header failed verification for the given reason.
+    Invalid(String),
+    /// Seal of the requested header.
+    Seal(Seal),
+    /// Block to follow.
+    ForkChoice(ForkChoice),
+}
+
+impl Response {
+    /// Decode a `Response` protobuf message.
+    pub fn decode(message: &[u8]) -> Result<Response, &'static str> {
+        let mut version = 0;
+        let mut response = None;
+        for field in Fields(message) {
+            match field? {
+                (FIELD_VERSION, Value::Varint(v)) => version = v,
+                (RESPONSE_VALID, Value::Bytes(_)) => response = Some(Response::Valid),
+                (RESPONSE_INVALID, Value::Bytes(invalid)) => {
+                    let mut reason = String::new();
+                    for field in Fields(invalid) {
+                        if let (1, Value::Bytes(r)) = field? {
+                            reason = String::from_utf8(r.to_vec())
+                                .map_err(|_| "Invalid reason is not UTF-8")?;
+                        }
+                    }
+                    response = Some(Response::Invalid(reason));
+                }
+                (RESPONSE_SEAL, Value::Bytes(seal)) => {
+                    let mut kind = SEAL_NONE;
+                    let mut fields = Vec::new();
+                    for field in Fields(seal) {
+                        match field? {
+                            (1, Value::Varint(k)) => kind = k,
+                            (2, Value::Bytes(f)) => fields.push(f.to_vec()),
+                            _ => {}
+                        }
+                    }
+                    response = Some(Response::Seal(match kind {
+                        SEAL_NONE => Seal::None,
+                        SEAL_REGULAR => Seal::Regular(fields),
+                        SEAL_PROPOSAL => Seal::Proposal(fields),
+                        _ => return Err("Unknown seal kind"),
+                    }));
+                }
+                (RESPONSE_FORK_CHOICE, Value::Bytes(choice)) => {
+                    let mut choice_value = FORK_CHOICE_OLD;
+                    for field in Fields(choice) {
+                        if let (1, Value::Varint(c)) = field? {
+                            choice_value = c;
+                        }
+                    }
+                    response = Some(Response::ForkChoice(match choice_value {
+                        FORK_CHOICE_OLD => ForkChoice::Old,
+                        FORK_CHOICE_NEW => ForkChoice::New,
+                        _ => return Err("Unknown fork choice"),
+                    }));
+                }
+                // unknown fields are skipped, so the plugin may add some
+                _ => {}
+            }
+        }
+        if version != PROTOCOL_VERSION {
+            return Err("Unsupported plugin protocol version");
+        }
+        response.ok_or("Unknown response kind")
+    }
+
+    /// Encode the response as a `Response` protobuf message, as a plugin would.
+    #[cfg(test)]
+    pub fn encode(&self) -> Bytes {
+        let mut kind = Vec::new();
+        let field = match *self {
+            Response::Valid => RESPONSE_VALID,
+            Response::Invalid(ref reason) => {
+                put_bytes_field(&mut kind, 1, reason.as_bytes());
+                RESPONSE_INVALID
+            }
+            Response::Seal(ref seal) => {
+                let (seal_kind, fields) = match *seal {
+                    Seal::Regular(ref fields) => (SEAL_REGULAR, &fields[..]),
+                    Seal::Proposal(ref fields) => (SEAL_PROPOSAL, &fields[..]),
+                    _ => (SEAL_NONE, &[][..]),
+                };
+                put_varint_field(&mut kind, 1, seal_kind);
+                for f in fields {
+                    put_bytes_field(&mut kind, 2, f);
+                }
+                RESPONSE_SEAL
+            }
+            Response::ForkChoice(ref choice) => {
+                let choice = match *choice {
+                    ForkChoice::Old => FORK_CHOICE_OLD,
+                    ForkChoice::New => FORK_CHOICE_NEW,
+                };
+                put_varint_field(&mut kind, 1, choice);
+                RESPONSE_FORK_CHOICE
+            }
+        };
+        let mut out = Vec::new();
+        put_varint_field(&mut out, FIELD_VERSION, PROTOCOL_VERSION);
+        put_bytes_field(&mut out, field, &kind);
+        out
+    }
+}
+
+fn score_bytes(score: &U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    score.to_big_endian(&mut bytes);
+    bytes
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(out, field << 3 | WIRE_VARINT);
+    put_varint(out, value);
+}
+
+fn put_bytes_field(out: &mut Vec<u8>, field: u64, value: &[u8]) {
+    put_varint(out, field << 3 | WIRE_BYTES);
+    put_varint(out, value.len() as u64);
+    out.extend_from_slice(value);
+}
+
+/// Value of a protobuf field. Fixed-size values are skipped, no message here uses them.
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Iterates over the `(field number, value)` pairs of an encoded protobuf message.
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn varint(&mut self) -> Result<u64, &'static str> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self.0.split_first().ok_or("Truncated varint")?;
+            self.0 = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Varint too long")
+    }
+
+    fn take(&mut self, len: u64) -> Result<&'a [u8], &'static str> {
+        if len > self.0.len() as u64 {
+            return Err("Truncated field");
+        }
+        let (value, rest) = self.0.split_at(len as usize);
+        self.0 = rest;
+        Ok(value)
+    }
+
+    fn field(&mut self) -> Result<(u64, Value<'a>), &'static str> {
+        let key = self.varint()?;
+        let value = match key & 0x7 {
+            WIRE_VARINT => Value::Varint(self.varint()?),
+            WIRE_FIXED64 => self.take(8).map(|_| Value::Fixed)?,
+            WIRE_BYTES => {
+                let len = self.varint()?;
+                Value::Bytes(self.take(len)?)
+            }
+            WIRE_FIXED32 => self.take(4).map(|_| Value::Fixed)?,
+            _ => return Err("Unsupported protobuf wire type"),
+        };
+        Ok((key >> 3, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u64, Value<'a>), &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_err() {
+            // stop after the first error
+            self.0 = &[];
+        }
+        Some(field)
+    }
+}
+
+/// Write a length-prefixed message.
+pub fn write_message<W: Write>(writer: &mut W, message: &[u8]) -> io::Result<()> {
+    if message.len() > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "plugin message too large",
+        ));
+    }
+    writer.write_all(&(message.len() as u32).to_be_bytes())?;
+    writer.write_all(message)?;
+    writer.flush()
+}
+
+/// Read a length-prefixed message.
+pub fn read_message<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "plugin message too large",
+        ));
+    }
+    let mut message = vec![0u8; len];
+    reader.read_exact(&mut message)?;
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_responses() {
+        let responses = vec![
+            Response::Valid,
+            Response::Invalid("bad seal".into()),
+            Response::Seal(Seal::Regular(vec![vec![1, 2], vec![]])),
+            Response::Seal(Seal::None),
+            Response::ForkChoice(ForkChoice::New),
+            Response::ForkChoice(ForkChoice::Old),
+        ];
+        for response in responses {
+            assert_eq!(Response::decode(&response.encode()), Ok(response));
+        }
+    }
+
+    #[test]
+    fn decodes_hand_encoded_response() {
+        // version 1, invalid { reason: "no" }
+        let message = [0x08, 0x01, 0x1a, 0x04, 0x0a, 0x02, b'n', b'o'];
+        assert_eq!(
+            Response::decode(&message),
+            Ok(Response::Invalid("no".into()))
+        );
+    }
+
+    #[test]
+    fn skips_unknown_fields() {
+        let mut message = Response::Valid.encode();
+        put_varint_field(&mut message, 100, 7);
+        put_bytes_field(&mut message, 101, b"future");
+        assert_eq!(Response::decode(&message), Ok(Response::Valid));
+    }
+
+    #[test]
+    fn rejects_other_protocol_versions() {
+        let mut message = Vec::new();
+        put_varint_field(&mut message, FIELD_VERSION, PROTOCOL_VERSION + 1);
+        put_bytes_field(&mut message, RESPONSE_VALID, &[]);
+        assert!(Response::decode(&message).is_err());
+
+        let mut unversioned = Vec::new();
+        put_bytes_field(&mut unversioned, RESPONSE_VALID, &[]);
+        assert!(Response::decode(&unversioned).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_messages() {
+        let message = Response::Invalid("bad seal".into()).encode();
+        assert!(Response::decode(&message[..message.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn encodes_requests() {
+        let header = Header::default();
+        let request = Request::Verify {
+            stage: Stage::Family,
+            header: &header,
+            parent: Some(&header),
+        }
+        .encode();
+
+        let fields = Fields(&request).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(fields.len(), 2);
+        match fields[0] {
+            (FIELD_VERSION, Value::Varint(PROTOCOL_VERSION)) => {}
+            _ => panic!("Expected the protocol version first"),
+        }
+        let verify = match fields[1] {
+            (REQUEST_VERIFY, Value::Bytes(verify)) => verify,
+            _ => panic!("Expected a verify request"),
+        };
+        let verify = Fields(verify).collect::<Result<Vec<_>, _>>().unwrap();
+        match verify[0] {
+            (1, Value::Varint(stage)) => assert_eq!(stage, Stage::Family as u64),
+            _ => panic!("Expected the stage first"),
+        }
+        match verify[1] {
+            (2, Value::Bytes(h)) => assert_eq!(h, &::rlp::encode(&header)[..]),
+            _ => panic!("Expected the header"),
+        }
+        match verify[2] {
+            (3, Value::Bytes(p)) => assert_eq!(p, &::rlp::encode(&header)[..]),
+            _ => panic!("Expected the parent"),
+        }
+    }
+
+    #[test]
+    fn frames_messages() {
+        let header = Header::default();
+        let request = Request::Verify {
+            stage: Stage::Basic,
+            header: &header,
+            parent: None,
+        }
+        .encode();
+
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &request).unwrap();
+        assert_eq!(buffer.len(), request.len() + 4);
+        assert_eq!(read_message(&mut &buffer[..]).unwrap(), request);
+    }
+}
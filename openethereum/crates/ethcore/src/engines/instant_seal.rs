@@ -15,9 +15,18 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 use block::ExecutedBlock;
+use client::{traits::ForceUpdateSealing, EngineClient};
 use engines::{Engine, Seal, SealingState};
+use io::{IoContext, IoHandler, IoService, TimerToken};
 use machine::Machine;
-use std::sync::atomic::{AtomicU64, Ordering};
+use parking_lot::RwLock;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Weak,
+    },
+    time::Duration,
+};
 use types::header::{ExtendedHeader, Header};
 
 /// `InstantSeal` params.
@@ -25,36 +34,121 @@ use types::header::{ExtendedHeader, Header};
 pub struct InstantSealParams {
     /// Whether to use millisecond timestamp
     pub millisecond_timestamp: bool,
+    /// Seal a block every given number of seconds, even if empty, instead of on every
+    /// transaction.
+    pub period_seconds: Option<u64>,
 }
 
 impl From<::ethjson::spec::InstantSealParams> for InstantSealParams {
     fn from(p: ::ethjson::spec::InstantSealParams) -> Self {
         InstantSealParams {
             millisecond_timestamp: p.millisecond_timestamp,
+            period_seconds: p.period_seconds.filter(|period| *period > 0),
+        }
+    }
+}
+
+const PERIOD_TIMER_TOKEN: TimerToken = 0;
+
+/// Sealing state shared between the engine and the period timer.
+struct PeriodicSealing<C: ?Sized> {
+    /// Whether a period elapsed since the last sealed block.
+    due: AtomicBool,
+    client: RwLock<Option<Weak<C>>>,
+}
+
+impl<C: ?Sized + EngineClient> PeriodicSealing<C> {
+    fn trigger(&self) {
+        self.due.store(true, Ordering::SeqCst);
+        if let Some(ref weak) = *self.client.read() {
+            if let Some(c) = weak.upgrade() {
+                // the block is due even if nothing changed since the last one, which
+                // the reseal checks would skip
+                c.update_sealing(ForceUpdateSealing::Yes);
+            }
+        }
+    }
+}
+
+struct PeriodTimer<C: ?Sized> {
+    sealing: Arc<PeriodicSealing<C>>,
+    period: Duration,
+}
+
+impl<C: ?Sized + EngineClient + 'static> IoHandler<()> for PeriodTimer<C> {
+    fn initialize(&self, io: &IoContext<()>) {
+        io.register_timer(PERIOD_TIMER_TOKEN, self.period)
+            .unwrap_or_else(
+                |e| warn!(target: "engine", "Failed to start sealing period timer: {}.", e),
+            )
+    }
+
+    fn timeout(&self, _io: &IoContext<()>, timer: TimerToken) {
+        if timer == PERIOD_TIMER_TOKEN {
+            self.sealing.trigger();
         }
     }
 }
 
 /// An engine which does not provide any consensus mechanism, just seals blocks internally.
-/// Only seals blocks which have transactions.
-pub struct InstantSeal<M> {
+/// Only seals blocks which have transactions, unless a sealing period is configured, in which
+/// case a block is sealed once per period.
+pub struct InstantSeal<M: Machine> {
     params: InstantSealParams,
     machine: M,
     last_sealed_block: AtomicU64,
+    sealing: Arc<PeriodicSealing<M::EngineClient>>,
+    /// Drives the period timer, kept alive for as long as the engine.
+    _period_service: Option<IoService<()>>,
 }
 
-impl<M> InstantSeal<M> {
+impl<M: Machine> InstantSeal<M>
+where
+    M::EngineClient: EngineClient + 'static,
+{
     /// Returns new instance of InstantSeal over the given state machine.
     pub fn new(params: InstantSealParams, machine: M) -> Self {
+        let sealing = Arc::new(PeriodicSealing {
+            due: AtomicBool::new(false),
+            client: RwLock::new(None),
+        });
+        let period_service = params
+            .period_seconds
+            .and_then(|period| Self::start_period_timer(sealing.clone(), period));
+
         InstantSeal {
             params,
             machine,
             last_sealed_block: AtomicU64::new(0),
+            sealing,
+            _period_service: period_service,
+        }
+    }
+
+    fn start_period_timer(
+        sealing: Arc<PeriodicSealing<M::EngineClient>>,
+        period: u64,
+    ) -> Option<IoService<()>> {
+        let handler = PeriodTimer {
+            sealing,
+            period: Duration::from_secs(period),
+        };
+        let service = IoService::<()>::start("InstantSeal")
+            .and_then(|service| service.register_handler(Arc::new(handler)).map(|_| service));
+        match service {
+            Ok(service) => Some(service),
+            Err(e) => {
+                warn!(target: "engine", "Failed to start sealing period service: {}.", e);
+                None
+            }
         }
     }
 }
 
-impl<M: Machine> Engine<M> for InstantSeal<M> {
+impl<M: Machine> Engine<M> for InstantSeal<M>
+where
+    M::EngineClient: EngineClient + 'static,
+{
     fn name(&self) -> &str {
         "InstantSeal"
     }
@@ -71,11 +165,26 @@ impl<M: Machine> Engine<M> for InstantSeal<M> {
         // We would like for the miner to `update_sealing` if there are local_pending_transactions
         // in the pool to prevent transactions sent in parallel from stalling in the transaction
         // pool. (see #9660)
-        true
+        // With a sealing period, blocks are only sealed when the period elapses.
+        self.params.period_seconds.is_none()
+    }
+
+    fn register_client(&self, client: Weak<M::EngineClient>) {
+        *self.sealing.client.write() = Some(client);
+    }
+
+    fn step(&self) {
+        if self.params.period_seconds.is_some() {
+            self.sealing.trigger();
+        }
     }
 
     fn generate_seal(&self, block: &ExecutedBlock, _parent: &Header) -> Seal {
-        if !block.transactions.is_empty() {
+        let should_seal = match self.params.period_seconds {
+            Some(_) => self.sealing.due.load(Ordering::SeqCst),
+            None => !block.transactions.is_empty(),
+        };
+        if should_seal {
             let block_number = block.header.number();
             let last_sealed_block = self.last_sealed_block.load(Ordering::SeqCst);
             // Return a regular seal if the given block is _higher_ than
@@ -91,6 +200,7 @@ impl<M: Machine> Engine<M> for InstantSeal<M> {
                     )
                     .is_ok()
                 {
+                    self.sealing.due.store(false, Ordering::SeqCst);
                     return Seal::Regular(Vec::new());
                 }
             }
@@ -103,14 +213,15 @@ impl<M: Machine> Engine<M> for InstantSeal<M> {
     }
 
     fn open_block_header_timestamp(&self, parent_timestamp: u64) -> u64 {
-        use std::{cmp, time};
+        use std::cmp;
+        use time_utils::Clock;
 
-        let dur = time::SystemTime::now()
-            .duration_since(time::UNIX_EPOCH)
-            .unwrap_or_default();
+        let dur = self.machine.clock().unix_now();
         let mut now = dur.as_secs();
         if self.params.millisecond_timestamp {
-            now = now * 1000 + dur.subsec_millis() as u64;
+            now = now
+                .saturating_mul(1000)
+                .saturating_add(dur.subsec_millis() as u64);
         }
         cmp::max(now, parent_timestamp)
     }
@@ -126,8 +237,9 @@ impl<M: Machine> Engine<M> for InstantSeal<M> {
 
 #[cfg(test)]
 mod tests {
+    use super::{InstantSeal, InstantSealParams};
     use block::*;
-    use engines::Seal;
+    use engines::{Engine, Seal};
     use ethereum_types::{Address, H520};
     use spec::Spec;
     use std::sync::Arc;
@@ -163,6 +275,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn instant_seals_once_per_period() {
+        let spec = Spec::new_instant();
+        let engine = InstantSeal::new(
+            InstantSealParams {
+                millisecond_timestamp: false,
+                period_seconds: Some(3600),
+            },
+            Spec::new_test_machine(),
+        );
+        assert!(!engine.should_reseal_on_update());
+
+        let db = spec
+            .ensure_db_good(get_temp_state_db(), &Default::default())
+            .unwrap();
+        let genesis_header = spec.genesis_header();
+        let last_hashes = Arc::new(vec![genesis_header.hash()]);
+        let b = OpenBlock::new(
+            &engine,
+            Default::default(),
+            false,
+            db,
+            &genesis_header,
+            last_hashes,
+            Address::default(),
+            (3141562.into(), 31415620.into()),
+            vec![],
+            false,
+            None,
+        )
+        .unwrap();
+        let b = b.close_and_lock().unwrap();
+
+        // empty block, but the period did not elapse yet
+        assert_eq!(engine.generate_seal(&b, &genesis_header), Seal::None);
+
+        engine.step();
+        match engine.generate_seal(&b, &genesis_header) {
+            Seal::Regular(seal) => assert!(b.try_seal(&engine, seal).is_ok()),
+            seal => panic!("expected a regular seal, got {:?}", seal),
+        }
+    }
+
+    #[test]
+    fn instant_seals_empty_block_into_chain_once_period_elapsed() {
+        use client::ChainInfo;
+        use tempdir::TempDir;
+        use test_helpers::generate_dummy_client_with_spec;
+
+        let client = generate_dummy_client_with_spec(|| {
+            let json = include_str!("../../res/chainspec/instant_seal.json")
+                .replace("\"params\": {}", "\"params\": { \"periodSeconds\": 3600 }");
+            let tempdir = TempDir::new("").unwrap();
+            Spec::load(&tempdir.path(), json.as_bytes()).unwrap()
+        });
+        client
+            .engine()
+            .register_client(Arc::downgrade(&client) as _);
+        assert_eq!(client.chain_info().best_block_number, 0);
+
+        // nothing requested a reseal, the elapsed period alone seals the block
+        client.engine().step();
+        assert_eq!(client.chain_info().best_block_number, 1);
+    }
+
     #[test]
     fn instant_cant_verify() {
         let engine = Spec::new_instant().engine;
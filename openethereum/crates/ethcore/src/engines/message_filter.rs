@@ -0,0 +1,152 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Replay protection for gossiped consensus messages.
+//!
+//! Consensus messages are rebroadcast by every validator receiving them, so the same bytes
+//! usually arrive many times. The filter remembers the hashes of recently accepted messages per
+//! round (AuRa step or BFT height), drops duplicates before any signature recovery takes place
+//! and only remembers messages whose signature checked out.
+
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+
+use ethereum_types::H256;
+use hash::keccak;
+use lru_cache::LruCache;
+use parking_lot::Mutex;
+use stats::PrometheusRegistry;
+
+/// Default number of remembered messages.
+pub const DEFAULT_MESSAGE_CACHE_SIZE: usize = 4096;
+
+/// Drops consensus messages already handled and counts the dropped ones.
+pub struct MessageFilter {
+    seen: Mutex<LruCache<(u64, H256), ()>>,
+    accepted: AtomicU64,
+    duplicates: AtomicU64,
+    invalid: AtomicU64,
+}
+
+impl Default for MessageFilter {
+    fn default() -> Self {
+        MessageFilter::new(DEFAULT_MESSAGE_CACHE_SIZE)
+    }
+}
+
+impl MessageFilter {
+    /// Create a filter remembering at most `capacity` messages.
+    pub fn new(capacity: usize) -> Self {
+        MessageFilter {
+            seen: Mutex::new(LruCache::new(capacity)),
+            accepted: AtomicU64::new(0),
+            duplicates: AtomicU64::new(0),
+            invalid: AtomicU64::new(0),
+        }
+    }
+
+    /// Check a raw message of the given round.
+    ///
+    /// Returns `Ok(None)` if the message was already accepted. Otherwise `verify` checks the
+    /// signature; the message is only remembered if it succeeds, so invalid messages can't evict
+    /// valid ones.
+    pub fn check<T, E, F>(&self, round: u64, message: &[u8], verify: F) -> Result<Option<T>, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        let key = (round, keccak(message));
+        if self.seen.lock().contains_key(&key) {
+            self.duplicates.fetch_add(1, AtomicOrdering::Relaxed);
+            return Ok(None);
+        }
+
+        let verified = match verify() {
+            Ok(verified) => verified,
+            Err(e) => {
+                self.invalid.fetch_add(1, AtomicOrdering::Relaxed);
+                return Err(e);
+            }
+        };
+
+        // the same message may have been accepted by another thread in the meantime
+        if self.seen.lock().insert(key, ()).is_some() {
+            self.duplicates.fetch_add(1, AtomicOrdering::Relaxed);
+            return Ok(None);
+        }
+        self.accepted.fetch_add(1, AtomicOrdering::Relaxed);
+        Ok(Some(verified))
+    }
+
+    /// Number of duplicate messages dropped.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Number of messages rejected for an invalid signature.
+    pub fn invalid(&self) -> u64 {
+        self.invalid.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Register the metrics, prefixed with the engine name.
+    pub fn register(&self, r: &mut PrometheusRegistry, engine: &str) {
+        r.register_counter(
+            &format!("{}_messages_accepted", engine),
+            "Consensus messages accepted for handling",
+            self.accepted.load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_counter(
+            &format!("{}_messages_duplicate", engine),
+            "Duplicate consensus messages dropped",
+            self.duplicates() as i64,
+        );
+        r.register_counter(
+            &format!("{}_messages_invalid", engine),
+            "Consensus messages dropped for an invalid signature",
+            self.invalid() as i64,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageFilter;
+
+    #[test]
+    fn drops_duplicates_and_forgets_invalid_messages() {
+        let filter = MessageFilter::new(8);
+        let valid = || Ok::<_, ()>(());
+
+        assert_eq!(filter.check(1, b"vote", valid), Ok(Some(())));
+        assert_eq!(filter.check(1, b"vote", valid), Ok(None));
+        // same bytes in another round are a different message
+        assert_eq!(filter.check(2, b"vote", valid), Ok(Some(())));
+
+        assert_eq!(filter.check(1, b"forged", || Err::<(), _>(())), Err(()));
+        assert_eq!(filter.check(1, b"forged", valid), Ok(Some(())));
+
+        assert_eq!(filter.duplicates(), 1);
+        assert_eq!(filter.invalid(), 1);
+    }
+
+    #[test]
+    fn skips_verification_of_duplicates() {
+        let filter = MessageFilter::new(8);
+        filter.check(1, b"vote", || Ok::<_, ()>(())).unwrap();
+        let result = filter.check(1, b"vote", || -> Result<(), ()> {
+            panic!("duplicate must not be verified")
+        });
+        assert_eq!(result, Ok(None));
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Sealing metrics shared by the consensus engines.
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering as AtomicOrdering},
+    time::Duration,
+};
+
+use engines::EngineError;
+use error::{BlockError, Error, ErrorKind};
+use stats::PrometheusRegistry;
+
+/// Counters for the blocks sealed by this node and the seals rejected on import.
+#[derive(Default)]
+pub struct SealingMetrics {
+    sealed: AtomicU64,
+    latency_total_ms: AtomicU64,
+    latency_last_ms: AtomicU64,
+    latency_max_ms: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl SealingMetrics {
+    /// Record a block sealed `latency` after the start of its slot.
+    pub fn note_sealed(&self, latency: Duration) {
+        let latency = latency.as_millis() as u64;
+        self.sealed.fetch_add(1, AtomicOrdering::Relaxed);
+        self.latency_total_ms
+            .fetch_add(latency, AtomicOrdering::Relaxed);
+        self.latency_last_ms.store(latency, AtomicOrdering::Relaxed);
+        self.latency_max_ms
+            .fetch_max(latency, AtomicOrdering::Relaxed);
+    }
+
+    /// Record an imported block whose seal was rejected.
+    pub fn note_rejected(&self) {
+        self.rejected.fetch_add(1, AtomicOrdering::Relaxed);
+    }
+
+    /// Record an error verifying an imported block, if it rejects the seal.
+    pub fn note_verification_error(&self, error: &Error) {
+        if is_seal_error(error) {
+            self.note_rejected();
+        }
+    }
+
+    /// Number of blocks sealed.
+    pub fn sealed(&self) -> u64 {
+        self.sealed.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Number of seals rejected.
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Register the metrics.
+    pub fn register(&self, r: &mut PrometheusRegistry) {
+        r.register_counter(
+            "engine_sealed_blocks",
+            "Blocks sealed by this node",
+            self.sealed() as i64,
+        );
+        r.register_counter(
+            "engine_sealing_latency_total_ms",
+            "Sum of the delays between the start of a slot and sealing our block, in milliseconds",
+            self.latency_total_ms.load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_gauge(
+            "engine_sealing_latency_last_ms",
+            "Delay between the start of the slot and sealing our last block, in milliseconds",
+            self.latency_last_ms.load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_gauge(
+            "engine_sealing_latency_max_ms",
+            "Longest delay between the start of a slot and sealing our block, in milliseconds",
+            self.latency_max_ms.load(AtomicOrdering::Relaxed) as i64,
+        );
+        r.register_counter(
+            "engine_rejected_seals",
+            "Imported blocks rejected for an invalid seal",
+            self.rejected() as i64,
+        );
+    }
+}
+
+/// Whether the error rejects the seal of a block: its signature, signer or seal fields.
+fn is_seal_error(error: &Error) -> bool {
+    match *error.kind() {
+        ErrorKind::Engine(EngineError::NotAuthorized(_))
+        | ErrorKind::Engine(EngineError::NotProposer(_))
+        | ErrorKind::Engine(EngineError::BadSealFieldSize(_))
+        | ErrorKind::Engine(EngineError::CliqueMissingVanity)
+        | ErrorKind::Engine(EngineError::CliqueMissingSignature)
+        | ErrorKind::Engine(EngineError::CliqueTooRecentlySigned(_))
+        | ErrorKind::Engine(EngineError::TooRecentlySigned(_))
+        | ErrorKind::Block(BlockError::InvalidSeal)
+        | ErrorKind::Block(BlockError::InvalidSealArity(_))
+        | ErrorKind::Ethkey(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SealingMetrics;
+    use engines::EngineError;
+    use error::{BlockError, Error, ErrorKind};
+    use ethereum_types::Address;
+    use std::{sync::atomic::Ordering as AtomicOrdering, time::Duration};
+    use unexpected::Mismatch;
+
+    #[test]
+    fn records_sealing_latency() {
+        let metrics = SealingMetrics::default();
+        metrics.note_sealed(Duration::from_millis(120));
+        metrics.note_sealed(Duration::from_millis(30));
+        metrics.note_rejected();
+
+        assert_eq!(metrics.sealed(), 2);
+        assert_eq!(metrics.rejected(), 1);
+        assert_eq!(metrics.latency_total_ms.load(AtomicOrdering::Relaxed), 150);
+    }
+
+    #[test]
+    fn counts_only_seal_errors_as_rejected() {
+        let metrics = SealingMetrics::default();
+        let seal: Error = ErrorKind::Engine(EngineError::NotAuthorized(Address::zero())).into();
+        let difficulty: Error = ErrorKind::Block(BlockError::InvalidDifficulty(Mismatch {
+            expected: 1.into(),
+            found: 2.into(),
+        }))
+        .into();
+
+        metrics.note_verification_error(&seal);
+        metrics.note_verification_error(&difficulty);
+        metrics.note_verification_error(&ErrorKind::Block(BlockError::InvalidSeal).into());
+
+        assert_eq!(metrics.rejected(), 2);
+    }
+}
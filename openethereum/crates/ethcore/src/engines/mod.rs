@@ -19,8 +19,12 @@
 mod authority_round;
 mod basic_authority;
 mod clique;
+mod external;
 mod instant_seal;
+mod message_filter;
+mod metrics;
 mod null_engine;
+mod tendermint;
 mod validator_set;
 
 pub mod block_reward;
@@ -30,9 +34,11 @@ pub use self::{
     authority_round::AuthorityRound,
     basic_authority::BasicAuthority,
     clique::Clique,
+    external::{ExternalEngine, ExternalEngineParams, PluginTransport, SocketTransport},
     instant_seal::{InstantSeal, InstantSealParams},
     null_engine::NullEngine,
     signer::EngineSigner,
+    tendermint::Tendermint,
 };
 
 // TODO [ToDr] Remove re-export (#10130)
@@ -44,13 +50,18 @@ pub use types::engines::{
 use std::{
     collections::{BTreeMap, HashMap},
     error, fmt,
-    sync::{Arc, Weak},
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        Arc, Weak,
+    },
+    time::Instant,
 };
 
 use builtin::Builtin;
 use error::Error;
 use snapshot::SnapshotComponents;
 use spec::CommonParams;
+use stats::PrometheusRegistry;
 use types::{
     header::{ExtendedHeader, Header},
     transaction::{self, SignedTransaction, UnverifiedTransaction},
@@ -63,6 +74,7 @@ use bytes::Bytes;
 use crypto::publickey::Signature;
 use ethereum_types::{Address, H256, H64, U256};
 use machine::{self, AuxiliaryData, AuxiliaryRequest, Machine};
+use parking_lot::Mutex;
 use types::ancestry_action::AncestryAction;
 use unexpected::{Mismatch, OutOfBounds};
 
@@ -101,6 +113,9 @@ pub enum EngineError {
     InvalidEngine,
     /// Requires signer ref, but none registered.
     RequiresSigner,
+    /// A service the engine depends on can't be reached. The block is not invalid and may be
+    /// imported again later.
+    Unavailable(String),
     /// Checkpoint is missing
     CliqueMissingCheckpoint(H256),
     /// Missing vanity data
@@ -119,6 +134,8 @@ pub enum EngineError {
     CliqueInvalidNonce(H64),
     /// The signer signed a block to recently
     CliqueTooRecentlySigned(Address),
+    /// The signer sealed one of the most recent blocks
+    TooRecentlySigned(Address),
     /// Custom
     Custom(String),
 }
@@ -149,6 +166,12 @@ impl fmt::Display for EngineError {
             CliqueTooRecentlySigned(ref address) => {
                 format!("The signer: {} has signed a block too recently", address)
             }
+            TooRecentlySigned(ref address) => {
+                format!(
+                    "The signer: {} has sealed one of the most recent blocks",
+                    address
+                )
+            }
             Custom(ref s) => s.clone(),
             DoubleVote(ref address) => format!("Author {} issued too many blocks.", address),
             NotProposer(ref mis) => format!("Author is not a current proposer: {}", mis),
@@ -166,6 +189,7 @@ impl fmt::Display for EngineError {
             MalformedMessage(ref msg) => format!("Received malformed consensus message: {}", msg),
             RequiresClient => format!("Call requires client but none registered"),
             RequiresSigner => format!("Call requires signer but none registered"),
+            Unavailable(ref msg) => format!("Consensus service unavailable: {}", msg),
             InvalidEngine => format!("Invalid engine specification or implementation"),
         };
 
@@ -186,10 +210,79 @@ pub enum Seal {
     Proposal(Vec<Bytes>),
     /// Regular block seal; should be part of the blockchain.
     Regular(Vec<Bytes>),
+    /// The seal is being generated in the background, e.g. by a remote signer.
+    /// The engine completes the handle and calls `EngineClient::update_sealing` once done.
+    Pending(PendingSeal),
     /// Engine does not generate seal for this block right now.
     None,
 }
 
+/// Handle to a seal generated asynchronously.
+///
+/// The engine keeps a clone and completes it with the final seal. The miner cancels it when
+/// the block being sealed is superseded by a new best block, after which the result is dropped.
+#[derive(Clone, Default)]
+pub struct PendingSeal {
+    inner: Arc<PendingSealInner>,
+}
+
+#[derive(Default)]
+struct PendingSealInner {
+    result: Mutex<Option<Seal>>,
+    cancelled: AtomicBool,
+}
+
+impl PendingSeal {
+    /// Create a new, not yet completed, handle.
+    pub fn new() -> Self {
+        PendingSeal::default()
+    }
+
+    /// Provide the generated seal. Returns `false` if the seal was cancelled in the meantime.
+    pub fn complete(&self, seal: Seal) -> bool {
+        let mut result = self.inner.result.lock();
+        if self.is_cancelled() {
+            return false;
+        }
+        *result = Some(seal);
+        true
+    }
+
+    /// Cancel seal generation. Engines should check `is_cancelled` before doing expensive work.
+    pub fn cancel(&self) {
+        let mut result = self.inner.result.lock();
+        self.inner.cancelled.store(true, AtomicOrdering::SeqCst);
+        *result = None;
+    }
+
+    /// Whether the seal was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(AtomicOrdering::SeqCst)
+    }
+
+    /// Take the generated seal, if it is ready.
+    pub fn take(&self) -> Option<Seal> {
+        self.inner.result.lock().take()
+    }
+}
+
+impl fmt::Debug for PendingSeal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PendingSeal")
+            .field("ready", &self.inner.result.lock().is_some())
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+impl PartialEq for PendingSeal {
+    fn eq(&self, other: &PendingSeal) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for PendingSeal {}
+
 /// The type of sealing the engine is currently able to perform.
 #[derive(Debug, PartialEq, Eq)]
 pub enum SealingState {
@@ -355,6 +448,15 @@ pub trait Engine<M: Machine>: Sync + Send {
         SealingState::External
     }
 
+    /// Returns the instant by which the given block has to be built to be sealed in our next
+    /// slot, leaving time to seal and propagate it. `None` if the engine has no such deadline.
+    fn sealing_deadline(&self, _header: &Header) -> Option<Instant> {
+        None
+    }
+
+    /// Register engine metrics, such as sealing latency and missed slots.
+    fn prometheus_metrics(&self, _r: &mut PrometheusRegistry) {}
+
     /// Called in `miner.chain_new_blocks` if the engine wishes to `update_sealing`
     /// after a block was recently sealed.
     ///
@@ -367,8 +469,8 @@ pub trait Engine<M: Machine>: Sync + Send {
     ///
     /// If `Some` is returned, then you get a valid seal.
     ///
-    /// This operation is synchronous and may (quite reasonably) not be available, in which None will
-    /// be returned.
+    /// This operation may (quite reasonably) not be available, in which None will be returned.
+    /// Engines relying on slow signers may return `Seal::Pending` and complete the seal later.
     ///
     /// It is fine to require access to state or a full client for this function, since
     /// light clients do not generate seals.
@@ -467,6 +569,12 @@ pub trait Engine<M: Machine>: Sync + Send {
         Err(EngineError::UnexpectedMessage)
     }
 
+    /// Whether the verified header carries a proposal seal. Proposals are broadcast but not
+    /// committed to the chain; the block is imported once it is sealed by the consensus round.
+    fn is_proposal(&self, _verified_header: &Header) -> bool {
+        false
+    }
+
     /// Register a component which signs consensus messages.
     fn set_signer(&self, _signer: Option<Box<dyn EngineSigner>>) {}
 
@@ -478,6 +586,14 @@ pub trait Engine<M: Machine>: Sync + Send {
         true
     }
 
+    /// Returns whether the given address would be allowed to seal on top of the current
+    /// best block if it was registered as the signer.
+    ///
+    /// Used to check that a new signing key is registered before handing over to it.
+    fn is_allowed_to_seal_as(&self, _address: &Address) -> bool {
+        true
+    }
+
     /// Sign using the EngineSigner, to be used for consensus tx signing.
     fn sign(&self, _hash: H256) -> Result<Signature, M::Error> {
         unimplemented!()
@@ -489,6 +605,10 @@ pub trait Engine<M: Machine>: Sync + Send {
     /// Trigger next step of the consensus engine.
     fn step(&self) {}
 
+    /// Configure how many proof-of-work caches are kept in memory and how many blocks
+    /// ahead of an epoch boundary the next one is prebuilt. Only relevant to Ethash.
+    fn set_pow_cache_config(&self, _cache_count: usize, _prebuild_distance: u64) {}
+
     /// Create a factory for building snapshot chunks and restoring from them.
     /// Returning `None` indicates that this engine doesn't support snapshot creation.
     fn snapshot_components(&self) -> Option<Box<dyn SnapshotComponents>> {
@@ -502,12 +622,18 @@ pub trait Engine<M: Machine>: Sync + Send {
 
     /// Return a new open block header timestamp based on the parent timestamp.
     fn open_block_header_timestamp(&self, parent_timestamp: u64) -> u64 {
-        use std::{cmp, time};
+        use std::cmp;
+        use time_utils::Clock;
 
-        let now = time::SystemTime::now()
-            .duration_since(time::UNIX_EPOCH)
-            .unwrap_or_default();
-        cmp::max(now.as_secs() as u64, parent_timestamp + 1)
+        let now = self.machine().clock().unix_now();
+        cmp::max(now.as_secs(), parent_timestamp.saturating_add(1))
+    }
+
+    /// Number of ancestors whose median timestamp a header at the given block number has to
+    /// exceed. If set, this check replaces `is_timestamp_valid` for blocks verified with access
+    /// to their ancestry, so that a single block running ahead doesn't drag the chain clock along.
+    fn median_time_past_window(&self, _block: BlockNumber) -> Option<usize> {
+        None
     }
 
     /// Check whether the parent timestamp is valid.
@@ -529,10 +655,17 @@ pub trait Engine<M: Machine>: Sync + Send {
     fn fork_choice(&self, new: &ExtendedHeader, best: &ExtendedHeader) -> ForkChoice;
 
     /// Returns author should used when executing tx's for this block.
+    /// Transaction fees are credited to this address.
     fn executive_author(&self, header: &Header) -> Result<Address, Error> {
         Ok(*header.author())
     }
 
+    /// Whether the block at the given height may name a block author other than the sealing
+    /// identity, so that block rewards and fees are credited to a separate fee recipient.
+    fn supports_fee_recipient(&self, _block_number: BlockNumber) -> bool {
+        false
+    }
+
     /// Returns a list of transactions for a new block if we are the author.
     ///
     /// This is called when the miner prepares a new block that this node will author and seal. It returns a list of
@@ -571,9 +704,9 @@ pub trait EthEngine: Engine<::machine::EthereumMachine> {
         self.machine().params()
     }
 
-    /// Get the EVM schedule for the given block number.
-    fn schedule(&self, block_number: BlockNumber) -> Schedule {
-        self.machine().schedule(block_number)
+    /// Get the EVM schedule for the block with given number and timestamp.
+    fn schedule(&self, block_number: BlockNumber, timestamp: u64) -> Schedule {
+        self.machine().schedule(block_number, timestamp)
     }
 
     /// Builtin-contracts for the chain..
@@ -650,9 +783,9 @@ pub trait EthEngine: Engine<::machine::EthereumMachine> {
     fn decode_transaction(
         &self,
         transaction: &[u8],
-        best_block_number: BlockNumber,
+        best_block_header: &Header,
     ) -> Result<UnverifiedTransaction, transaction::Error> {
-        let schedule = self.schedule(best_block_number);
+        let schedule = self.schedule(best_block_header.number(), best_block_header.timestamp());
         self.machine().decode_transaction(transaction, &schedule)
     }
 
@@ -16,6 +16,8 @@
 
 //! A signer used by Engines which need to sign messages.
 
+use std::time::Instant;
+
 use crypto::publickey::{self, ecies, Error, Public, Signature};
 use ethereum_types::{Address, H256};
 //TODO dr
@@ -25,6 +27,16 @@ pub trait EngineSigner: Send + Sync {
     /// Sign a consensus message hash.
     fn sign(&self, hash: H256) -> Result<Signature, publickey::Error>;
 
+    /// Sign a consensus message hash, failing instead if `deadline` passes first.
+    fn sign_before(&self, hash: H256, deadline: Instant) -> Result<Signature, publickey::Error> {
+        if Instant::now() >= deadline {
+            return Err(publickey::Error::Custom(
+                "Signing deadline has passed".into(),
+            ));
+        }
+        self.sign(hash)
+    }
+
     /// Signing address
     fn address(&self) -> Address;
 
@@ -33,6 +45,12 @@ pub trait EngineSigner: Send + Sync {
 
     /// The signer's public key, if available.
     fn public(&self) -> Option<Public>;
+
+    /// Whether signing may take long, e.g. on a hardware token. Engines then generate their
+    /// seals in the background instead of holding up the miner.
+    fn is_slow(&self) -> bool {
+        false
+    }
 }
 
 /// Creates a new `EngineSigner` from given key pair.
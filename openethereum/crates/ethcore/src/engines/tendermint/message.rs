@@ -0,0 +1,247 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tendermint message handling.
+
+use super::{BlockHash, Height, Step, View};
+use bytes::Bytes;
+use crypto::publickey::{public_to_address, recover, Error as CryptoError};
+use error::Error;
+use ethereum_types::{Address, H256, H520};
+use hash::keccak;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use std::cmp;
+use types::header::Header;
+
+/// Height, view and step of a vote.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct VoteStep {
+    pub height: Height,
+    pub view: View,
+    pub step: Step,
+}
+
+impl VoteStep {
+    pub fn new(height: Height, view: View, step: Step) -> Self {
+        VoteStep { height, view, step }
+    }
+
+    pub fn is_height(&self, height: Height) -> bool {
+        self.height == height
+    }
+
+    pub fn is_view(&self, height: Height, view: View) -> bool {
+        self.height == height && self.view == view
+    }
+}
+
+impl PartialOrd for VoteStep {
+    fn partial_cmp(&self, m: &VoteStep) -> Option<cmp::Ordering> {
+        Some(self.cmp(m))
+    }
+}
+
+impl Ord for VoteStep {
+    fn cmp(&self, m: &VoteStep) -> cmp::Ordering {
+        if self.height != m.height {
+            self.height.cmp(&m.height)
+        } else if self.view != m.view {
+            self.view.cmp(&m.view)
+        } else {
+            self.step.number().cmp(&m.step.number())
+        }
+    }
+}
+
+/// A signed vote for a block (or for nothing) at a given vote step.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ConsensusMessage {
+    pub vote_step: VoteStep,
+    pub block_hash: BlockHash,
+    pub signature: H520,
+}
+
+impl ConsensusMessage {
+    pub fn new(
+        signature: H520,
+        height: Height,
+        view: View,
+        step: Step,
+        block_hash: BlockHash,
+    ) -> Self {
+        ConsensusMessage {
+            signature,
+            block_hash,
+            vote_step: VoteStep::new(height, view, step),
+        }
+    }
+
+    /// Consensus message implied by the seal of a proposal header.
+    pub fn new_proposal(header: &Header) -> Result<Self, ::rlp::DecoderError> {
+        Ok(ConsensusMessage {
+            signature: Rlp::new(header.seal().get(1).ok_or(DecoderError::RlpIsTooShort)?)
+                .as_val()?,
+            block_hash: Some(header.bare_hash()),
+            vote_step: VoteStep::new(header.number(), consensus_view(header)?, Step::Propose),
+        })
+    }
+
+    /// Recover the address of the message author.
+    pub fn verify(&self) -> Result<Address, Error> {
+        let full_rlp = ::rlp::encode(self);
+        let block_info = Rlp::new(&full_rlp).at(1)?;
+        let public_key = recover(&self.signature.into(), &keccak(block_info.as_raw()))?;
+        Ok(public_to_address(&public_key))
+    }
+}
+
+impl Decodable for ConsensusMessage {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let m = rlp.at(1)?;
+        let block_message: H256 = m.val_at(3)?;
+        Ok(ConsensusMessage {
+            vote_step: VoteStep::new(m.val_at(0)?, m.val_at(1)?, Step::decode(&m.at(2)?)?),
+            block_hash: match block_message.is_zero() {
+                true => None,
+                false => Some(block_message),
+            },
+            signature: rlp.val_at(0)?,
+        })
+    }
+}
+
+impl Encodable for ConsensusMessage {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        let info = message_info_rlp(&self.vote_step, self.block_hash);
+        s.begin_list(2).append(&self.signature).append_raw(&info, 1);
+    }
+}
+
+/// View of a sealed header.
+pub fn consensus_view(header: &Header) -> Result<View, ::rlp::DecoderError> {
+    let view_rlp = header.seal().get(0).ok_or(DecoderError::RlpIsTooShort)?;
+    Rlp::new(view_rlp.as_slice()).as_val()
+}
+
+/// RLP of the signed part of a message.
+pub fn message_info_rlp(vote_step: &VoteStep, block_hash: BlockHash) -> Bytes {
+    let mut s = RlpStream::new_list(4);
+    s.append(&vote_step.height)
+        .append(&vote_step.view)
+        .append(&vote_step.step)
+        .append(&block_hash.unwrap_or_else(H256::zero));
+    s.out()
+}
+
+/// RLP of a complete message.
+pub fn message_full_rlp(signature: &H520, vote_info: &Bytes) -> Bytes {
+    let mut s = RlpStream::new_list(2);
+    s.append(signature).append_raw(vote_info, 1);
+    s.out()
+}
+
+/// Hash signed by the author of a message.
+pub fn message_hash(vote_step: VoteStep, block_hash: H256) -> H256 {
+    keccak(message_info_rlp(&vote_step, Some(block_hash)))
+}
+
+/// Recover the author of a message from its hash and signature.
+pub fn recover_author(signature: &H520, message_hash: &H256) -> Result<Address, CryptoError> {
+    recover(&(*signature).into(), message_hash).map(|public| public_to_address(&public))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::Step, *};
+    use accounts::AccountProvider;
+    use rlp::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn encode_step() {
+        let step = Step::Precommit;
+
+        let mut s = RlpStream::new_list(2);
+        s.append(&step);
+        assert!(!s.is_finished(), "List shouldn't finished yet");
+        s.append(&step);
+        assert!(s.is_finished(), "List should be finished now");
+        s.out();
+    }
+
+    #[test]
+    fn encode_decode() {
+        let message = ConsensusMessage {
+            signature: H520::default(),
+            vote_step: VoteStep {
+                height: 10,
+                view: 123,
+                step: Step::Precommit,
+            },
+            block_hash: Some(keccak("1")),
+        };
+        let raw_rlp = ::rlp::encode(&message);
+        let rlp = Rlp::new(&raw_rlp);
+        assert_eq!(Ok(message), rlp.as_val());
+
+        let message = ConsensusMessage {
+            signature: H520::default(),
+            vote_step: VoteStep {
+                height: 1314,
+                view: 0,
+                step: Step::Prevote,
+            },
+            block_hash: None,
+        };
+        let raw_rlp = ::rlp::encode(&message);
+        let rlp = Rlp::new(&raw_rlp);
+        assert_eq!(Ok(message), rlp.as_val());
+    }
+
+    #[test]
+    fn generate_and_verify() {
+        let tap = Arc::new(AccountProvider::transient_provider());
+        let addr = tap.insert_account(keccak("0").into(), &"0".into()).unwrap();
+
+        let vote_step = VoteStep::new(123, 2, Step::Precommit);
+        let block_hash = Some(H256::from_low_u64_be(1));
+        let vote_info = message_info_rlp(&vote_step, block_hash);
+        let signature = tap
+            .sign(addr, Some("0".into()), keccak(&vote_info))
+            .unwrap();
+
+        let message = ConsensusMessage {
+            signature: signature.into(),
+            vote_step,
+            block_hash,
+        };
+        assert_eq!(addr, message.verify().unwrap());
+        assert_eq!(
+            message,
+            Rlp::new(&message_full_rlp(&signature.into(), &vote_info))
+                .as_val()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn step_ordering() {
+        assert!(VoteStep::new(10, 123, Step::Precommit) < VoteStep::new(11, 123, Step::Precommit));
+        assert!(VoteStep::new(10, 123, Step::Propose) < VoteStep::new(11, 123, Step::Precommit));
+        assert!(VoteStep::new(10, 122, Step::Propose) < VoteStep::new(11, 123, Step::Propose));
+        assert!(VoteStep::new(10, 123, Step::Prevote) < VoteStep::new(10, 123, Step::Precommit));
+    }
+}
@@ -0,0 +1,1129 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tendermint BFT consensus engine with round robin proof-of-authority.
+//!
+//! At each blockchain `Height` there can be multiple `View`s of voting.
+//! Signatures always sign `Height`, `View`, `Step` and `BlockHash` which is a block hash without
+//! seal.
+//!
+//! First a block with `Seal::Proposal` is issued by the designated proposer. Next the `View`
+//! proceeds through `Prevote` and `Precommit` `Step`s. A block is committed once more than two
+//! thirds of the validators precommitted it, at which point it is final. The committed block
+//! carries the `Precommit` signatures in its seal.
+
+mod message;
+mod params;
+mod vote_collector;
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering as AtomicOrdering},
+        Arc, Weak,
+    },
+    time::Duration,
+};
+
+use self::{
+    message::*,
+    params::{TendermintParams, TendermintTimeouts},
+    vote_collector::VoteCollector,
+};
+use super::{
+    signer::EngineSigner,
+    validator_set::{SimpleList, ValidatorSet},
+};
+use block::*;
+use bytes::Bytes;
+use client::{traits::ForceUpdateSealing, ChainInfo, EngineClient};
+use crypto::publickey::{self, Signature};
+use engines::{
+    block_reward::{self, RewardKind},
+    message_filter::MessageFilter,
+    ConstructedVerifier, Engine, EngineError, Seal, SealingState,
+};
+use error::{BlockError, Error};
+use ethereum_types::{Address, H256, H520, U128, U256};
+use hash::keccak;
+use io::{IoContext, IoHandler, IoService, TimerToken};
+use machine::{AuxiliaryData, Call, EthereumMachine, Machine};
+use parking_lot::RwLock;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use stats::PrometheusRegistry;
+use types::{
+    header::{ExtendedHeader, Header},
+    BlockNumber,
+};
+use unexpected::{Mismatch, OutOfBounds};
+
+/// Height of the block being decided.
+pub type Height = BlockNumber;
+/// Voting round at a given height.
+pub type View = u64;
+/// Block hash a vote is cast for, `None` for a vote for no block.
+pub type BlockHash = Option<H256>;
+
+/// Step of a view.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Step {
+    Propose,
+    Prevote,
+    Precommit,
+    Commit,
+}
+
+impl Step {
+    fn number(&self) -> u8 {
+        match *self {
+            Step::Propose => 0,
+            Step::Prevote => 1,
+            Step::Precommit => 2,
+            Step::Commit => 3,
+        }
+    }
+}
+
+impl Decodable for Step {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        match rlp.as_val()? {
+            0u8 => Ok(Step::Propose),
+            1 => Ok(Step::Prevote),
+            2 => Ok(Step::Precommit),
+            _ => Err(DecoderError::Custom("Invalid step.")),
+        }
+    }
+}
+
+impl Encodable for Step {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append_internal(&self.number());
+    }
+}
+
+/// Engine using `Tendermint` consensus algorithm.
+pub struct Tendermint {
+    step_service: IoService<Duration>,
+    client: RwLock<Option<Weak<dyn EngineClient>>>,
+    /// Blockchain height.
+    height: AtomicU64,
+    /// Consensus view.
+    view: AtomicU64,
+    /// Consensus step.
+    step: RwLock<Step>,
+    /// Vote accumulator.
+    votes: VoteCollector,
+    /// Used to sign messages and proposals.
+    signer: RwLock<Option<Box<dyn EngineSigner>>>,
+    /// Message for the last PoLC.
+    lock_change: RwLock<Option<ConsensusMessage>>,
+    /// Last lock view.
+    last_lock: AtomicU64,
+    /// Bare hash of the proposed block, used for seal submission.
+    proposal: RwLock<Option<H256>>,
+    /// Hash of the proposal parent block.
+    proposal_parent: RwLock<H256>,
+    /// Last block proposed by this validator.
+    last_proposed: RwLock<H256>,
+    /// Set used to determine the current validators.
+    validators: Box<dyn ValidatorSet>,
+    /// Step timeouts.
+    timeouts: TendermintTimeouts,
+    /// Reward per block, in base units.
+    block_reward: U256,
+    /// Drops replayed consensus messages.
+    message_filter: MessageFilter,
+    machine: EthereumMachine,
+}
+
+struct EpochVerifier {
+    subchain_validators: SimpleList,
+}
+
+impl super::EpochVerifier<EthereumMachine> for EpochVerifier {
+    fn verify_light(&self, header: &Header) -> Result<(), Error> {
+        let view = consensus_view(header)?;
+        let vote_step = VoteStep::new(header.number(), view, Step::Precommit);
+        let precommit_hash = message_hash(vote_step, header.bare_hash());
+        let signatures = precommit_signatures(header)?;
+
+        let mut origins = HashSet::new();
+        for signature in signatures {
+            let address = recover_author(&signature, &precommit_hash)?;
+            if !self.subchain_validators.contains(&address) {
+                return Err(EngineError::NotAuthorized(address).into());
+            }
+            if !origins.insert(address) {
+                return Err(BlockError::InvalidSeal.into());
+            }
+        }
+        check_above_threshold(self.subchain_validators.len(), origins.len()).map_err(Into::into)
+    }
+
+    fn check_finality_proof(&self, proof: &[u8]) -> Option<Vec<H256>> {
+        let header: Header = ::rlp::decode(proof).ok()?;
+        self.verify_light(&header).ok().map(|_| vec![header.hash()])
+    }
+}
+
+fn combine_proofs(signal_number: BlockNumber, set_proof: &[u8], finality_proof: &[u8]) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(3);
+    stream
+        .append(&signal_number)
+        .append(&set_proof)
+        .append(&finality_proof);
+    stream.out()
+}
+
+fn destructure_proofs(combined: &[u8]) -> Result<(BlockNumber, &[u8], &[u8]), Error> {
+    let rlp = Rlp::new(combined);
+    Ok((rlp.at(0)?.as_val()?, rlp.at(1)?.data()?, rlp.at(2)?.data()?))
+}
+
+/// Precommit signatures of a committed header.
+fn precommit_signatures(header: &Header) -> Result<Vec<H520>, Error> {
+    let field = header.seal().get(2).ok_or(BlockError::InvalidSeal)?;
+    Ok(Rlp::new(field).as_list()?)
+}
+
+/// Whether the header carries a proposal seal rather than a commit seal.
+fn is_proposal_seal(header: &Header) -> bool {
+    header.seal().get(2).map_or(false, |precommits| {
+        precommits[..] == ::rlp::EMPTY_LIST_RLP[..]
+    })
+}
+
+/// More than two thirds of `validators` need to agree.
+fn check_above_threshold(validators: usize, votes: usize) -> Result<(), EngineError> {
+    let threshold = validators * 2 / 3;
+    if votes > threshold {
+        Ok(())
+    } else {
+        Err(EngineError::BadSealFieldSize(OutOfBounds {
+            min: Some(threshold + 1),
+            max: None,
+            found: votes,
+        }))
+    }
+}
+
+const ENGINE_TIMEOUT_TOKEN: TimerToken = 23;
+
+/// Calls `Tendermint::step` whenever the timeout of the current step expires.
+struct TransitionHandler {
+    engine: Weak<Tendermint>,
+    initial_timeout: Duration,
+}
+
+impl IoHandler<Duration> for TransitionHandler {
+    fn initialize(&self, io: &IoContext<Duration>) {
+        io.register_timer_once(ENGINE_TIMEOUT_TOKEN, self.initial_timeout)
+            .unwrap_or_else(
+                |e| warn!(target: "engine", "Failed to start consensus step timer: {}.", e),
+            )
+    }
+
+    fn timeout(&self, _io: &IoContext<Duration>, timer: TimerToken) {
+        if timer == ENGINE_TIMEOUT_TOKEN {
+            if let Some(engine) = self.engine.upgrade() {
+                engine.step();
+            }
+        }
+    }
+
+    fn message(&self, io: &IoContext<Duration>, next_timeout: &Duration) {
+        if let Err(e) = io.clear_timer(ENGINE_TIMEOUT_TOKEN) {
+            warn!(target: "engine", "Failed to clear consensus step timer: {}.", e);
+        }
+        io.register_timer_once(ENGINE_TIMEOUT_TOKEN, *next_timeout)
+            .unwrap_or_else(
+                |e| warn!(target: "engine", "Failed to restart consensus step timer: {}.", e),
+            )
+    }
+}
+
+impl Tendermint {
+    /// Create a new instance of Tendermint engine
+    pub fn new(our_params: TendermintParams, machine: EthereumMachine) -> Result<Arc<Self>, Error> {
+        let engine = Arc::new(Tendermint {
+            step_service: IoService::<Duration>::start("Tendermint")?,
+            client: RwLock::new(None),
+            height: AtomicU64::new(1),
+            view: AtomicU64::new(0),
+            step: RwLock::new(Step::Propose),
+            votes: Default::default(),
+            signer: RwLock::new(None),
+            lock_change: RwLock::new(None),
+            last_lock: AtomicU64::new(0),
+            proposal: RwLock::new(None),
+            proposal_parent: Default::default(),
+            last_proposed: Default::default(),
+            validators: our_params.validators,
+            timeouts: our_params.timeouts,
+            block_reward: our_params.block_reward,
+            message_filter: MessageFilter::default(),
+            machine: machine,
+        });
+
+        let handler = TransitionHandler {
+            engine: Arc::downgrade(&engine),
+            initial_timeout: engine.timeouts.propose,
+        };
+        engine.step_service.register_handler(Arc::new(handler))?;
+        Ok(engine)
+    }
+
+    fn update_sealing(&self) {
+        if let Some(ref weak) = *self.client.read() {
+            if let Some(c) = weak.upgrade() {
+                c.update_sealing(ForceUpdateSealing::Yes);
+            }
+        }
+    }
+
+    fn submit_seal(&self, block_hash: H256, seal: Vec<Bytes>) {
+        if let Some(ref weak) = *self.client.read() {
+            if let Some(c) = weak.upgrade() {
+                c.submit_seal(block_hash, seal);
+            }
+        }
+    }
+
+    fn broadcast_message(&self, message: Bytes) {
+        if let Some(ref weak) = *self.client.read() {
+            if let Some(c) = weak.upgrade() {
+                c.broadcast_consensus_message(message);
+            }
+        }
+    }
+
+    fn signer_address(&self) -> Option<Address> {
+        self.signer.read().as_ref().map(|s| s.address())
+    }
+
+    fn generate_message(&self, block_hash: BlockHash) -> Option<Bytes> {
+        let h = self.height.load(AtomicOrdering::SeqCst);
+        let r = self.view.load(AtomicOrdering::SeqCst);
+        let s = *self.step.read();
+        let vote_info = message_info_rlp(&VoteStep::new(h, r, s), block_hash);
+        match (self.signer_address(), self.sign(keccak(&vote_info))) {
+            (Some(validator), Ok(signature)) if self.is_authority(&validator) => {
+                let signature = H520::from(signature);
+                let message_rlp = message_full_rlp(&signature, &vote_info);
+                let message = ConsensusMessage::new(signature, h, r, s, block_hash);
+                self.votes.vote(message.clone(), validator);
+                debug!(target: "engine", "Generated {:?} as {}.", message, validator);
+                self.handle_valid_message(&message);
+
+                Some(message_rlp)
+            }
+            (None, _) => {
+                trace!(target: "engine", "No message, since there is no engine signer.");
+                None
+            }
+            (Some(v), Ok(_)) => {
+                trace!(target: "engine", "No message, since {} is not a validator.", v);
+                None
+            }
+            (Some(v), Err(e)) => {
+                trace!(target: "engine", "{} could not sign the message {}", v, e);
+                None
+            }
+        }
+    }
+
+    fn generate_and_broadcast_message(&self, block_hash: BlockHash) {
+        if let Some(message) = self.generate_message(block_hash) {
+            self.broadcast_message(message);
+        }
+    }
+
+    fn to_next_height(&self, height: Height) {
+        let new_height = height + 1;
+        debug!(target: "engine", "Received a Commit, transitioning to height {}.", new_height);
+        self.last_lock.store(0, AtomicOrdering::SeqCst);
+        self.height.store(new_height, AtomicOrdering::SeqCst);
+        self.view.store(0, AtomicOrdering::SeqCst);
+        *self.lock_change.write() = None;
+        *self.proposal.write() = None;
+        self.votes
+            .throw_out_old(&VoteStep::new(new_height, 0, Step::Propose));
+    }
+
+    /// Use via step_service to transition steps.
+    fn to_step(&self, step: Step) {
+        if let Err(io_err) = self.step_service.send_message(self.timeouts.for_step(step)) {
+            warn!(target: "engine", "Could not proceed to step {:?}: {}", step, io_err);
+        }
+        *self.step.write() = step;
+        match step {
+            Step::Propose => self.update_sealing(),
+            Step::Prevote => {
+                let block_hash = match *self.lock_change.read() {
+                    Some(ref m) if !self.should_unlock(m.vote_step.view) => m.block_hash,
+                    _ => *self.proposal.read(),
+                };
+                self.generate_and_broadcast_message(block_hash);
+            }
+            Step::Precommit => {
+                trace!(target: "engine", "to_step: Precommit.");
+                let block_hash = match *self.lock_change.read() {
+                    Some(ref m) if self.is_view(m) && m.block_hash.is_some() => {
+                        trace!(target: "engine", "Setting last lock: {}", m.vote_step.view);
+                        self.last_lock
+                            .store(m.vote_step.view, AtomicOrdering::SeqCst);
+                        m.block_hash
+                    }
+                    _ => None,
+                };
+                self.generate_and_broadcast_message(block_hash);
+            }
+            Step::Commit => {
+                trace!(target: "engine", "to_step: Commit.");
+                let height = self.height.load(AtomicOrdering::SeqCst);
+                let view = self.view.load(AtomicOrdering::SeqCst);
+                let block_hash = match *self.proposal.read() {
+                    Some(block_hash) => block_hash,
+                    None => return,
+                };
+                // Only the proposer has the block ready for seal submission, other validators
+                // import the committed block once it is broadcast.
+                if *self.last_proposed.read() != block_hash {
+                    return;
+                }
+                let precommit = VoteStep::new(height, view, Step::Precommit);
+                let precommits = self.votes.round_signatures(&precommit, &Some(block_hash));
+                if check_above_threshold(self.validator_count(), precommits.len()).is_ok() {
+                    let seal = vec![
+                        ::rlp::encode(&view),
+                        ::rlp::NULL_RLP.to_vec(),
+                        ::rlp::encode_list(&precommits),
+                    ];
+                    self.to_next_height(height);
+                    self.submit_seal(block_hash, seal);
+                }
+            }
+        }
+    }
+
+    /// Fire the step timeout after the given delay instead of the step's own timeout.
+    fn schedule_step(&self, delay: Duration) {
+        if let Err(io_err) = self.step_service.send_message(delay) {
+            warn!(target: "engine", "Could not schedule step timeout: {}", io_err);
+        }
+    }
+
+    fn validator_count(&self) -> usize {
+        self.validators.count(&*self.proposal_parent.read())
+    }
+
+    fn is_authority(&self, address: &Address) -> bool {
+        self.validators
+            .contains(&*self.proposal_parent.read(), address)
+    }
+
+    fn check_above_threshold(&self, n: usize) -> Result<(), EngineError> {
+        check_above_threshold(self.validator_count(), n)
+    }
+
+    /// Find the designated for the given view.
+    fn view_proposer(&self, bh: &H256, height: Height, view: View) -> Address {
+        let proposer_nonce = height + view;
+        trace!(target: "engine", "Proposer nonce: {}", proposer_nonce);
+        self.validators.get(bh, proposer_nonce as usize)
+    }
+
+    /// Check if address is a proposer for given view.
+    fn check_view_proposer(
+        &self,
+        bh: &H256,
+        height: Height,
+        view: View,
+        address: &Address,
+    ) -> Result<(), EngineError> {
+        let proposer = self.view_proposer(bh, height, view);
+        if proposer == *address {
+            Ok(())
+        } else {
+            Err(EngineError::NotProposer(Mismatch {
+                expected: proposer,
+                found: *address,
+            }))
+        }
+    }
+
+    /// Check if current signer is the current proposer.
+    fn is_signer_proposer(&self, bh: &H256) -> bool {
+        let proposer = self.view_proposer(
+            bh,
+            self.height.load(AtomicOrdering::SeqCst),
+            self.view.load(AtomicOrdering::SeqCst),
+        );
+        self.signer_address() == Some(proposer)
+    }
+
+    fn is_height(&self, message: &ConsensusMessage) -> bool {
+        message
+            .vote_step
+            .is_height(self.height.load(AtomicOrdering::SeqCst))
+    }
+
+    fn is_view(&self, message: &ConsensusMessage) -> bool {
+        message.vote_step.is_view(
+            self.height.load(AtomicOrdering::SeqCst),
+            self.view.load(AtomicOrdering::SeqCst),
+        )
+    }
+
+    fn increment_view(&self, n: View) {
+        trace!(target: "engine", "increment_view: New view.");
+        self.view.fetch_add(n, AtomicOrdering::SeqCst);
+        *self.proposal.write() = None;
+    }
+
+    fn should_unlock(&self, lock_change_view: View) -> bool {
+        self.last_lock.load(AtomicOrdering::SeqCst) < lock_change_view
+            && lock_change_view < self.view.load(AtomicOrdering::SeqCst)
+    }
+
+    fn has_enough_any_votes(&self) -> bool {
+        let step_votes = self.votes.count_round_votes(&VoteStep::new(
+            self.height.load(AtomicOrdering::SeqCst),
+            self.view.load(AtomicOrdering::SeqCst),
+            *self.step.read(),
+        ));
+        self.check_above_threshold(step_votes).is_ok()
+    }
+
+    fn has_enough_future_step_votes(&self, vote_step: &VoteStep) -> bool {
+        if vote_step.view > self.view.load(AtomicOrdering::SeqCst) {
+            let step_votes = self.votes.count_round_votes(vote_step);
+            self.check_above_threshold(step_votes).is_ok()
+        } else {
+            false
+        }
+    }
+
+    fn has_enough_aligned_votes(&self, message: &ConsensusMessage) -> bool {
+        let aligned_count = self.votes.count_aligned_votes(message);
+        self.check_above_threshold(aligned_count).is_ok()
+    }
+
+    fn handle_valid_message(&self, message: &ConsensusMessage) {
+        let vote_step = &message.vote_step;
+        let is_newer_than_lock = match *self.lock_change.read() {
+            Some(ref lock) => vote_step > &lock.vote_step,
+            None => true,
+        };
+        let lock_change = is_newer_than_lock
+            && vote_step.step == Step::Prevote
+            && message.block_hash.is_some()
+            && self.has_enough_aligned_votes(message);
+        if lock_change {
+            trace!(target: "engine", "handle_valid_message: Lock change.");
+            *self.lock_change.write() = Some(message.clone());
+        }
+        // Check if it can affect the step transition.
+        if self.is_height(message) {
+            let view = self.view.load(AtomicOrdering::SeqCst);
+            let step = *self.step.read();
+            let next_step = match step {
+                Step::Precommit
+                    if message.block_hash.is_none() && self.has_enough_aligned_votes(message) =>
+                {
+                    self.increment_view(1);
+                    Some(Step::Propose)
+                }
+                Step::Precommit if self.has_enough_aligned_votes(message) => {
+                    self.votes.throw_out_old(vote_step);
+                    Some(Step::Commit)
+                }
+                Step::Precommit if self.has_enough_future_step_votes(vote_step) => {
+                    self.increment_view(vote_step.view - view);
+                    Some(Step::Precommit)
+                }
+                // Avoid counting votes twice.
+                Step::Prevote if lock_change => Some(Step::Precommit),
+                Step::Prevote if self.has_enough_aligned_votes(message) => Some(Step::Precommit),
+                Step::Prevote if self.has_enough_future_step_votes(vote_step) => {
+                    self.increment_view(vote_step.view - view);
+                    Some(Step::Prevote)
+                }
+                _ => None,
+            };
+
+            if let Some(step) = next_step {
+                trace!(target: "engine", "Transition to {:?} triggered.", step);
+                self.to_step(step);
+            }
+        }
+    }
+}
+
+impl Engine<EthereumMachine> for Tendermint {
+    fn name(&self) -> &str {
+        "Tendermint"
+    }
+
+    fn machine(&self) -> &EthereumMachine {
+        &self.machine
+    }
+
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        self.message_filter.register(r, "tendermint");
+    }
+
+    /// (consensus view, proposal signature, authority signatures)
+    fn seal_fields(&self, _header: &Header) -> usize {
+        3
+    }
+
+    /// Additional engine-specific information for the user/developer concerning `header`.
+    fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
+        let view = consensus_view(header)
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        let precommits = precommit_signatures(header)
+            .map(|s| s.len().to_string())
+            .unwrap_or_default();
+        map![
+            "view".into() => view,
+            "precommits".into() => precommits
+        ]
+    }
+
+    fn populate_from_parent(&self, header: &mut Header, parent: &Header) {
+        // Chain scoring: total weight is sqrt(U256::max_value())*height - view
+        let new_difficulty = U256::from(U128::max_value()) + consensus_view(parent).unwrap_or(0)
+            - self.view.load(AtomicOrdering::SeqCst);
+        header.set_difficulty(new_difficulty);
+    }
+
+    fn sealing_state(&self) -> SealingState {
+        if self.signer.read().is_none() {
+            return SealingState::NotReady;
+        }
+        if *self.step.read() != Step::Propose || self.proposal.read().is_some() {
+            return SealingState::NotReady;
+        }
+        if !self.is_signer_proposer(&*self.proposal_parent.read()) {
+            return SealingState::NotReady;
+        }
+        SealingState::Ready
+    }
+
+    fn should_reseal_on_update(&self) -> bool {
+        // Sealing is driven by the consensus steps.
+        false
+    }
+
+    /// Attempt to seal generate a proposal seal.
+    ///
+    /// This operation is synchronous and may (quite reasonably) not be available, in which case
+    /// `Seal::None` will be returned.
+    fn generate_seal(&self, block: &ExecutedBlock, _parent: &Header) -> Seal {
+        let header = &block.header;
+        let author = header.author();
+        let height = header.number();
+        // Only proposer can generate seal if None was generated.
+        if *self.step.read() != Step::Propose
+            || height != self.height.load(AtomicOrdering::SeqCst)
+            || self.proposal.read().is_some()
+            || !self.is_signer_proposer(header.parent_hash())
+            || self.signer_address() != Some(*author)
+        {
+            return Seal::None;
+        }
+
+        let view = self.view.load(AtomicOrdering::SeqCst);
+        let bh = Some(header.bare_hash());
+        let vote_info = message_info_rlp(&VoteStep::new(height, view, Step::Propose), bh);
+        if let Ok(signature) = self.sign(keccak(&vote_info)).map(H520::from) {
+            // Insert Propose vote.
+            debug!(target: "engine", "Submitting proposal {} at height {} view {}.", header.bare_hash(), height, view);
+            self.votes.vote(
+                ConsensusMessage::new(signature, height, view, Step::Propose, bh),
+                *author,
+            );
+            // Remember the owned block.
+            *self.last_proposed.write() = header.bare_hash();
+            // Remember proposal for later seal submission.
+            *self.proposal.write() = bh;
+            *self.proposal_parent.write() = *header.parent_hash();
+            // Prevote on our own proposal without waiting for the propose timeout.
+            self.schedule_step(Duration::from_millis(0));
+            Seal::Proposal(vec![
+                ::rlp::encode(&view),
+                ::rlp::encode(&signature),
+                ::rlp::EMPTY_LIST_RLP.to_vec(),
+            ])
+        } else {
+            warn!(target: "engine", "generate_seal: FAIL: accounts secret key unavailable");
+            Seal::None
+        }
+    }
+
+    fn handle_message(&self, rlp: &[u8]) -> Result<(), EngineError> {
+        fn fmt_err<T: ::std::fmt::Debug>(x: T) -> EngineError {
+            EngineError::MalformedMessage(format!("{:?}", x))
+        }
+
+        let rlp = Rlp::new(rlp);
+        let message: ConsensusMessage = rlp.as_val().map_err(fmt_err)?;
+        if !self.votes.is_old_or_known(&message) {
+            let height = message.vote_step.height;
+            let verified = self.message_filter.check(height, rlp.as_raw(), || {
+                let msg_hash = keccak(rlp.at(1).map_err(fmt_err)?.as_raw());
+                let sender = recover_author(&message.signature, &msg_hash).map_err(fmt_err)?;
+
+                if !self.is_authority(&sender) {
+                    return Err(EngineError::NotAuthorized(sender));
+                }
+                Ok(sender)
+            })?;
+            let sender = match verified {
+                Some(sender) => sender,
+                None => {
+                    trace!(target: "engine", "Dropped duplicate {:?}.", message);
+                    return Ok(());
+                }
+            };
+            self.broadcast_message(rlp.as_raw().to_vec());
+            if let Some(double) = self.votes.vote(message.clone(), sender) {
+                self.validators
+                    .report_malicious(&sender, height, height, ::rlp::encode(&double));
+                return Err(EngineError::DoubleVote(sender));
+            }
+            trace!(target: "engine", "Handling a valid {:?} from {}.", message, sender);
+            self.handle_valid_message(&message);
+        }
+        Ok(())
+    }
+
+    /// Apply the block reward on finalisation of the block.
+    fn on_close_block(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
+        if self.block_reward.is_zero() || self.machine.has_reward_schedule(block.header.number()) {
+            return Ok(());
+        }
+        let author = *block.header.author();
+        block_reward::apply_block_rewards(
+            &[(author, RewardKind::Author, self.block_reward)],
+            block,
+            &self.machine,
+        )
+    }
+
+    fn verify_local_seal(&self, _header: &Header) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn verify_block_basic(&self, header: &Header) -> Result<(), Error> {
+        let seal_length = header.seal().len();
+        let expected_seal_fields = self.seal_fields(header);
+        if seal_length == expected_seal_fields {
+            // Either proposal or commit.
+            if (header.seal()[1] == ::rlp::NULL_RLP) != is_proposal_seal(header) {
+                Ok(())
+            } else {
+                warn!(target: "engine", "verify_block_basic: Block is neither a Commit nor Proposal.");
+                Err(BlockError::InvalidSeal.into())
+            }
+        } else {
+            Err(BlockError::InvalidSealArity(Mismatch {
+                expected: expected_seal_fields,
+                found: seal_length,
+            })
+            .into())
+        }
+    }
+
+    fn verify_block_external(&self, header: &Header) -> Result<(), Error> {
+        if is_proposal_seal(header) {
+            let proposal = ConsensusMessage::new_proposal(header)?;
+            let proposer = proposal.verify()?;
+            if !self.validators.contains(header.parent_hash(), &proposer) {
+                return Err(EngineError::NotAuthorized(proposer).into());
+            }
+            self.check_view_proposer(
+                header.parent_hash(),
+                proposal.vote_step.height,
+                proposal.vote_step.view,
+                &proposer,
+            )
+            .map_err(Into::into)
+        } else {
+            let vote_step =
+                VoteStep::new(header.number(), consensus_view(header)?, Step::Precommit);
+            let precommit_hash = message_hash(vote_step.clone(), header.bare_hash());
+            let mut origins = HashSet::new();
+            for signature in precommit_signatures(header)? {
+                let precommit = ConsensusMessage {
+                    signature,
+                    block_hash: Some(header.bare_hash()),
+                    vote_step: vote_step.clone(),
+                };
+                let address = match self.votes.get(&precommit) {
+                    Some(a) => a,
+                    None => recover_author(&signature, &precommit_hash)?,
+                };
+                if !self.validators.contains(header.parent_hash(), &address) {
+                    return Err(EngineError::NotAuthorized(address).into());
+                }
+                if !origins.insert(address) {
+                    warn!(target: "engine", "verify_block_unordered: Duplicate signature from {} on the seal.", address);
+                    return Err(BlockError::InvalidSeal.into());
+                }
+            }
+            check_above_threshold(self.validators.count(header.parent_hash()), origins.len())
+                .map_err(Into::into)
+        }
+    }
+
+    fn is_proposal(&self, header: &Header) -> bool {
+        let height = self.height.load(AtomicOrdering::SeqCst);
+        if !is_proposal_seal(header) {
+            // New Commit received, skip to next height.
+            if header.number() >= height {
+                trace!(target: "engine", "Received a commit: {:?}.", header.number());
+                self.to_next_height(header.number());
+                *self.proposal_parent.write() = header.hash();
+                self.to_step(Step::Commit);
+            }
+            return false;
+        }
+
+        let proposal = match ConsensusMessage::new_proposal(header) {
+            Ok(proposal) => proposal,
+            Err(_) => return true,
+        };
+        let proposer = match proposal.verify() {
+            Ok(proposer) => proposer,
+            Err(_) => return true,
+        };
+        if !self.votes.is_old_or_known(&proposal) {
+            self.votes.vote(proposal.clone(), proposer);
+        }
+        if self.is_view(&proposal) && self.proposal.read().is_none() {
+            debug!(target: "engine", "Received proposal {} at height {} view {}.", header.bare_hash(), height, proposal.vote_step.view);
+            *self.proposal.write() = proposal.block_hash;
+            *self.proposal_parent.write() = *header.parent_hash();
+            if *self.step.read() == Step::Propose {
+                self.to_step(Step::Prevote);
+            }
+        }
+        true
+    }
+
+    fn genesis_epoch_data(&self, header: &Header, call: &Call) -> Result<Vec<u8>, String> {
+        self.validators
+            .genesis_epoch_data(header, call)
+            .map(|set_proof| combine_proofs(0, &set_proof, &[]))
+    }
+
+    fn signals_epoch_end(
+        &self,
+        header: &Header,
+        aux: AuxiliaryData,
+    ) -> super::EpochChange<EthereumMachine> {
+        let first = header.number() == 0;
+        self.validators.signals_epoch_end(first, header, aux)
+    }
+
+    fn is_epoch_end(
+        &self,
+        chain_head: &Header,
+        _finalized: &[H256],
+        _chain: &super::Headers<Header>,
+        transition_store: &super::PendingTransitionStore,
+    ) -> Option<Vec<u8>> {
+        let first = chain_head.number() == 0;
+
+        if let Some(change) = self.validators.is_epoch_end(first, chain_head) {
+            let change = combine_proofs(chain_head.number(), &change, &[]);
+            return Some(change);
+        } else if let Some(pending) = transition_store(chain_head.hash()) {
+            // Every committed block is final, so the signalling block is its own finality proof.
+            let signal_number = chain_head.number();
+            let finality_proof = ::rlp::encode(chain_head);
+            return Some(combine_proofs(
+                signal_number,
+                &pending.proof,
+                &finality_proof,
+            ));
+        }
+
+        None
+    }
+
+    fn epoch_verifier<'a>(
+        &self,
+        _header: &Header,
+        proof: &'a [u8],
+    ) -> ConstructedVerifier<'a, EthereumMachine> {
+        let (signal_number, set_proof, finality_proof) = match destructure_proofs(proof) {
+            Ok(x) => x,
+            Err(e) => return ConstructedVerifier::Err(e),
+        };
+        let first = signal_number == 0;
+
+        match self
+            .validators
+            .epoch_set(first, &self.machine, signal_number, set_proof)
+        {
+            Ok((list, finalize)) => {
+                let verifier = Box::new(EpochVerifier {
+                    subchain_validators: list,
+                });
+
+                match finalize {
+                    Some(finalize) => {
+                        ConstructedVerifier::Unconfirmed(verifier, finality_proof, finalize)
+                    }
+                    None => ConstructedVerifier::Trusted(verifier),
+                }
+            }
+            Err(e) => ConstructedVerifier::Err(e),
+        }
+    }
+
+    fn set_signer(&self, signer: Option<Box<dyn EngineSigner>>) {
+        *self.signer.write() = signer;
+    }
+
+    fn sign(&self, hash: H256) -> Result<Signature, Error> {
+        Ok(self
+            .signer
+            .read()
+            .as_ref()
+            .ok_or(publickey::Error::InvalidAddress)?
+            .sign(hash)?)
+    }
+
+    fn snapshot_components(&self) -> Option<Box<dyn crate::snapshot::SnapshotComponents>> {
+        Some(Box::new(::snapshot::PoaSnapshot))
+    }
+
+    fn fork_choice(&self, new: &ExtendedHeader, current: &ExtendedHeader) -> super::ForkChoice {
+        super::total_difficulty_fork_choice(new, current)
+    }
+
+    fn step(&self) {
+        let step = *self.step.read();
+        let next_step = match step {
+            Step::Propose => {
+                trace!(target: "engine", "Propose timeout.");
+                if self.proposal.read().is_none() {
+                    // Report the proposer if no proposal was received.
+                    let height = self.height.load(AtomicOrdering::SeqCst);
+                    let current_proposer = self.view_proposer(
+                        &*self.proposal_parent.read(),
+                        height,
+                        self.view.load(AtomicOrdering::SeqCst),
+                    );
+                    self.validators
+                        .report_benign(&current_proposer, height, height);
+                }
+                Step::Prevote
+            }
+            Step::Prevote if self.has_enough_any_votes() => {
+                trace!(target: "engine", "Prevote timeout.");
+                Step::Precommit
+            }
+            Step::Prevote => {
+                trace!(target: "engine", "Prevote timeout without enough votes.");
+                Step::Prevote
+            }
+            Step::Precommit if self.has_enough_any_votes() => {
+                trace!(target: "engine", "Precommit timeout.");
+                self.increment_view(1);
+                Step::Propose
+            }
+            Step::Precommit => {
+                trace!(target: "engine", "Precommit timeout without enough votes.");
+                Step::Precommit
+            }
+            Step::Commit => {
+                trace!(target: "engine", "Commit timeout.");
+                Step::Propose
+            }
+        };
+        self.to_step(next_step);
+    }
+
+    fn register_client(&self, client: Weak<dyn EngineClient>) {
+        if let Some(c) = client.upgrade() {
+            let chain_info = c.chain_info();
+            self.height
+                .store(chain_info.best_block_number + 1, AtomicOrdering::SeqCst);
+            *self.proposal_parent.write() = chain_info.best_block_hash;
+        }
+        *self.client.write() = Some(client.clone());
+        self.validators.register_client(client);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{message::*, Step};
+    use accounts::AccountProvider;
+    use block::*;
+    use engines::{EngineError, Seal};
+    use error::{Error, ErrorKind};
+    use ethereum_types::{Address, H520};
+    use hash::keccak;
+    use spec::Spec;
+    use std::sync::Arc;
+    use test_helpers::get_temp_state_db;
+    use types::header::Header;
+
+    fn sign_precommit(
+        tap: &AccountProvider,
+        signer: Address,
+        header: &Header,
+        password: &str,
+    ) -> H520 {
+        let vote_step = VoteStep::new(header.number(), 0, Step::Precommit);
+        let hash = message_hash(vote_step, header.bare_hash());
+        tap.sign(signer, Some(password.into()), hash)
+            .unwrap()
+            .into()
+    }
+
+    fn commit_header(parent: &Header, precommits: &[H520]) -> Header {
+        let mut header = Header::default();
+        header.set_number(1);
+        header.set_parent_hash(parent.hash());
+        header.set_seal(vec![
+            ::rlp::encode(&0u64),
+            ::rlp::NULL_RLP.to_vec(),
+            ::rlp::encode_list(precommits),
+        ]);
+        header
+    }
+
+    #[test]
+    fn has_valid_metadata() {
+        let engine = Spec::new_test_tendermint().engine;
+        assert!(!engine.name().is_empty());
+    }
+
+    #[test]
+    fn verification_fails_on_short_seal() {
+        let engine = Spec::new_test_tendermint().engine;
+        let header = Header::default();
+
+        let verify_result = engine.verify_block_basic(&header);
+
+        match verify_result {
+            Err(Error(ErrorKind::Block(_), _)) => {}
+            Err(_) => panic!(
+                "should be block seal-arity mismatch error (got {:?})",
+                verify_result
+            ),
+            _ => panic!("Should be error, got Ok"),
+        }
+    }
+
+    #[test]
+    fn generates_proposal_only_as_proposer() {
+        let tap = Arc::new(AccountProvider::transient_provider());
+        let addr0 = tap.insert_account(keccak("0").into(), &"0".into()).unwrap();
+        let addr1 = tap.insert_account(keccak("1").into(), &"1".into()).unwrap();
+
+        let spec = Spec::new_test_tendermint();
+        let engine = &*spec.engine;
+        let genesis_header = spec.genesis_header();
+        let open_block = |author| {
+            let db = spec
+                .ensure_db_good(get_temp_state_db(), &Default::default())
+                .unwrap();
+            OpenBlock::new(
+                engine,
+                Default::default(),
+                false,
+                db,
+                &genesis_header,
+                Arc::new(vec![genesis_header.hash()]),
+                author,
+                (3141562.into(), 31415620.into()),
+                vec![],
+                false,
+                None,
+            )
+            .unwrap()
+            .close_and_lock()
+            .unwrap()
+        };
+
+        // keccak("1") proposes at height 1, view 0.
+        let b0 = open_block(addr0);
+        engine.set_signer(Some(Box::new((tap.clone(), addr0, "0".into()))));
+        assert!(engine.generate_seal(&b0, &genesis_header) == Seal::None);
+
+        let b1 = open_block(addr1);
+        engine.set_signer(Some(Box::new((tap.clone(), addr1, "1".into()))));
+        match engine.generate_seal(&b1, &genesis_header) {
+            Seal::Proposal(seal) => {
+                let sealed = b1.clone().try_seal(engine, seal).unwrap();
+                assert!(engine.verify_block_basic(&sealed.header).is_ok());
+                assert!(engine.verify_block_external(&sealed.header).is_ok());
+            }
+            _ => panic!("block 1 not proposed"),
+        }
+        // Only a single proposal per view.
+        assert!(engine.generate_seal(&b1, &genesis_header) == Seal::None);
+    }
+
+    #[test]
+    fn commit_requires_enough_precommits() {
+        let tap = AccountProvider::transient_provider();
+        let addr0 = tap.insert_account(keccak("0").into(), &"0".into()).unwrap();
+        let addr1 = tap.insert_account(keccak("1").into(), &"1".into()).unwrap();
+        let outsider = tap.insert_account(keccak("2").into(), &"2".into()).unwrap();
+
+        let spec = Spec::new_test_tendermint();
+        let engine = &*spec.engine;
+        let genesis_header = spec.genesis_header();
+        let unsealed = commit_header(&genesis_header, &[]);
+
+        let precommit0 = sign_precommit(&tap, addr0, &unsealed, "0");
+        let precommit1 = sign_precommit(&tap, addr1, &unsealed, "1");
+        let header = commit_header(&genesis_header, &[precommit0, precommit1]);
+        assert!(engine.verify_block_basic(&header).is_ok());
+        assert!(engine.verify_block_external(&header).is_ok());
+
+        // One out of two validators is not above the two thirds threshold.
+        let header = commit_header(&genesis_header, &[precommit0]);
+        match engine.verify_block_external(&header) {
+            Err(Error(ErrorKind::Engine(EngineError::BadSealFieldSize(_)), _)) => {}
+            other => panic!("expected threshold error, got {:?}", other),
+        }
+
+        // Duplicated precommits are rejected.
+        let header = commit_header(&genesis_header, &[precommit0, precommit0]);
+        assert!(engine.verify_block_external(&header).is_err());
+
+        let precommit2 = sign_precommit(&tap, outsider, &unsealed, "2");
+        let header = commit_header(&genesis_header, &[precommit0, precommit2]);
+        match engine.verify_block_external(&header) {
+            Err(Error(ErrorKind::Engine(EngineError::NotAuthorized(a)), _)) => {
+                assert_eq!(a, outsider)
+            }
+            other => panic!("expected unauthorized error, got {:?}", other),
+        }
+    }
+}
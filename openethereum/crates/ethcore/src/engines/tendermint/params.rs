@@ -0,0 +1,86 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tendermint specific parameters.
+
+use super::Step;
+use engines::validator_set::{new_validator_set, ValidatorSet};
+use ethereum_types::U256;
+use ethjson;
+use std::time::Duration;
+
+/// `Tendermint` params.
+pub struct TendermintParams {
+    /// List of validators.
+    pub validators: Box<dyn ValidatorSet>,
+    /// Timeout durations for different steps.
+    pub timeouts: TendermintTimeouts,
+    /// Reward per block in base units.
+    pub block_reward: U256,
+}
+
+/// Base timeout of each step.
+#[derive(Debug, Clone)]
+pub struct TendermintTimeouts {
+    pub propose: Duration,
+    pub prevote: Duration,
+    pub precommit: Duration,
+    pub commit: Duration,
+}
+
+impl TendermintTimeouts {
+    /// Timeout of the given step.
+    pub fn for_step(&self, step: Step) -> Duration {
+        match step {
+            Step::Propose => self.propose,
+            Step::Prevote => self.prevote,
+            Step::Precommit => self.precommit,
+            Step::Commit => self.commit,
+        }
+    }
+}
+
+impl Default for TendermintTimeouts {
+    fn default() -> Self {
+        TendermintTimeouts {
+            propose: Duration::from_millis(1000),
+            prevote: Duration::from_millis(1000),
+            precommit: Duration::from_millis(1000),
+            commit: Duration::from_millis(10000),
+        }
+    }
+}
+
+fn to_duration(ms: ethjson::uint::Uint) -> Duration {
+    let ms: u64 = ms.into();
+    Duration::from_millis(ms)
+}
+
+impl From<ethjson::spec::TendermintParams> for TendermintParams {
+    fn from(p: ethjson::spec::TendermintParams) -> Self {
+        let dt = TendermintTimeouts::default();
+        TendermintParams {
+            validators: new_validator_set(p.validators),
+            timeouts: TendermintTimeouts {
+                propose: p.timeout_propose.map_or(dt.propose, to_duration),
+                prevote: p.timeout_prevote.map_or(dt.prevote, to_duration),
+                precommit: p.timeout_precommit.map_or(dt.precommit, to_duration),
+                commit: p.timeout_commit.map_or(dt.commit, to_duration),
+            },
+            block_reward: p.block_reward.map_or_else(U256::zero, Into::into),
+        }
+    }
+}
@@ -0,0 +1,284 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Collects votes on hashes at each vote step.
+
+use super::{
+    message::{ConsensusMessage, VoteStep},
+    BlockHash,
+};
+use ethereum_types::{Address, H520};
+use parking_lot::RwLock;
+use rlp::{Encodable, RlpStream};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Storing all Proposals, Prevotes and Precommits.
+#[derive(Debug, Default)]
+pub struct VoteCollector {
+    votes: RwLock<BTreeMap<VoteStep, StepCollector>>,
+}
+
+#[derive(Debug, Default)]
+struct StepCollector {
+    voted: HashMap<Address, ConsensusMessage>,
+    block_votes: HashMap<BlockHash, HashMap<H520, Address>>,
+    messages: HashSet<ConsensusMessage>,
+}
+
+/// Two different votes of the same author at the same vote step.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DoubleVote {
+    pub author: Address,
+    vote_one: ConsensusMessage,
+    vote_two: ConsensusMessage,
+}
+
+impl Encodable for DoubleVote {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2)
+            .append(&self.vote_one)
+            .append(&self.vote_two);
+    }
+}
+
+impl StepCollector {
+    /// Returns the previous vote of the author if it is double voting.
+    fn insert(&mut self, message: ConsensusMessage, address: Address) -> Option<&ConsensusMessage> {
+        // Do nothing when message was seen.
+        if self.messages.insert(message.clone()) {
+            if let Some(previous) = self.voted.insert(address, message.clone()) {
+                // Bad validator sent a different message.
+                return self.messages.get(&previous);
+            } else {
+                self.block_votes
+                    .entry(message.block_hash)
+                    .or_insert_with(HashMap::new)
+                    .insert(message.signature, address);
+            }
+        }
+        None
+    }
+
+    /// Count all votes for the given block hash at this step.
+    fn count_block(&self, block_hash: &BlockHash) -> usize {
+        self.block_votes.get(block_hash).map_or(0, HashMap::len)
+    }
+
+    /// Count all votes collected for the given step.
+    fn count(&self) -> usize {
+        self.voted.len()
+    }
+}
+
+impl VoteCollector {
+    /// Insert vote if it is newer than the oldest one.
+    pub fn vote(&self, message: ConsensusMessage, voter: Address) -> Option<DoubleVote> {
+        let mut guard = self.votes.write();
+        guard
+            .entry(message.vote_step.clone())
+            .or_insert_with(Default::default)
+            .insert(message.clone(), voter)
+            .map(|previous| DoubleVote {
+                author: voter,
+                vote_one: previous.clone(),
+                vote_two: message,
+            })
+    }
+
+    /// Checks if the message should be ignored.
+    pub fn is_old_or_known(&self, message: &ConsensusMessage) -> bool {
+        let guard = self.votes.read();
+        guard
+            .get(&message.vote_step)
+            .map_or(false, |c| c.messages.contains(message))
+            || guard
+                .keys()
+                .next()
+                .map_or(false, |oldest| &message.vote_step < oldest)
+    }
+
+    /// Throws out messages older than the given vote step.
+    pub fn throw_out_old(&self, vote_step: &VoteStep) {
+        let mut guard = self.votes.write();
+        let new_collector = guard.split_off(vote_step);
+        *guard = new_collector;
+    }
+
+    /// Collects the signatures of the votes for the given block hash at the given step.
+    pub fn round_signatures(&self, vote_step: &VoteStep, block_hash: &BlockHash) -> Vec<H520> {
+        self.votes
+            .read()
+            .get(vote_step)
+            .and_then(|c| c.block_votes.get(block_hash))
+            .map(|votes| votes.keys().cloned().collect())
+            .unwrap_or_else(Vec::new)
+    }
+
+    /// Count votes which agree with the given message.
+    pub fn count_aligned_votes(&self, message: &ConsensusMessage) -> usize {
+        self.votes
+            .read()
+            .get(&message.vote_step)
+            .map_or(0, |m| m.count_block(&message.block_hash))
+    }
+
+    /// Count all votes collected for a given step.
+    pub fn count_round_votes(&self, vote_step: &VoteStep) -> usize {
+        self.votes
+            .read()
+            .get(vote_step)
+            .map_or(0, StepCollector::count)
+    }
+
+    /// Get the author of an already collected message.
+    pub fn get(&self, message: &ConsensusMessage) -> Option<Address> {
+        self.votes
+            .read()
+            .get(&message.vote_step)
+            .and_then(|c| c.block_votes.get(&message.block_hash))
+            .and_then(|origins| origins.get(&message.signature).cloned())
+    }
+
+    /// Number of vote steps currently tracked.
+    pub fn len(&self) -> usize {
+        self.votes.read().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{message::*, Step},
+        *,
+    };
+    use ethereum_types::H256;
+    use hash::keccak;
+
+    fn random_vote(
+        collector: &VoteCollector,
+        signature: H520,
+        vote_step: VoteStep,
+        block_hash: BlockHash,
+    ) -> bool {
+        full_vote(
+            collector,
+            signature,
+            vote_step,
+            block_hash,
+            &Address::random(),
+        )
+        .is_none()
+    }
+
+    fn full_vote(
+        collector: &VoteCollector,
+        signature: H520,
+        vote_step: VoteStep,
+        block_hash: BlockHash,
+        address: &Address,
+    ) -> Option<DoubleVote> {
+        collector.vote(
+            ConsensusMessage {
+                signature,
+                vote_step,
+                block_hash,
+            },
+            *address,
+        )
+    }
+
+    #[test]
+    fn counts_votes() {
+        let collector = VoteCollector::default();
+        let round1 = VoteStep::new(1, 1, Step::Prevote);
+        let round3 = VoteStep::new(1, 3, Step::Prevote);
+        let bh = Some(keccak("1"));
+        let bh2 = Some(keccak("2"));
+
+        random_vote(&collector, H520::random(), round1.clone(), bh);
+        random_vote(&collector, H520::random(), round1.clone(), bh);
+        random_vote(&collector, H520::random(), round1.clone(), bh2);
+        random_vote(&collector, H520::random(), round3.clone(), None);
+
+        assert_eq!(
+            collector.count_aligned_votes(&ConsensusMessage {
+                signature: H520::default(),
+                vote_step: round1.clone(),
+                block_hash: bh,
+            }),
+            2
+        );
+        assert_eq!(collector.count_round_votes(&round1), 3);
+        assert_eq!(collector.count_round_votes(&round3), 1);
+        assert_eq!(collector.round_signatures(&round1, &bh).len(), 2);
+    }
+
+    #[test]
+    fn remove_old() {
+        let collector = VoteCollector::default();
+        let vote = |height, view, hash: Option<H256>| {
+            random_vote(
+                &collector,
+                H520::random(),
+                VoteStep::new(height, view, Step::Precommit),
+                hash,
+            );
+        };
+        vote(3, 2, Some(keccak("0")));
+        vote(4, 1, Some(keccak("1")));
+        vote(5, 2, Some(keccak("2")));
+        vote(6, 3, None);
+
+        collector.throw_out_old(&VoteStep::new(5, 0, Step::Propose));
+        assert_eq!(collector.len(), 2);
+        assert!(collector.is_old_or_known(&ConsensusMessage {
+            signature: H520::random(),
+            vote_step: VoteStep::new(4, 1, Step::Precommit),
+            block_hash: Some(keccak("1")),
+        }));
+    }
+
+    #[test]
+    fn detects_double_vote() {
+        let collector = VoteCollector::default();
+        let round = VoteStep::new(3, 7, Step::Prevote);
+        let address = Address::random();
+        assert!(full_vote(
+            &collector,
+            H520::random(),
+            round.clone(),
+            Some(keccak("0")),
+            &address
+        )
+        .is_none());
+        let double = full_vote(
+            &collector,
+            H520::random(),
+            round.clone(),
+            Some(keccak("1")),
+            &address,
+        );
+        assert_eq!(double.map(|d| d.author), Some(address));
+        assert_eq!(
+            collector.count_aligned_votes(&ConsensusMessage {
+                signature: H520::default(),
+                vote_step: round,
+                block_hash: Some(keccak("0")),
+            }),
+            1
+        );
+    }
+}
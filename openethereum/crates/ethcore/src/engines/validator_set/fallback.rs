@@ -0,0 +1,314 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+/// Validator set falling back to a secondary set when the primary one is unavailable.
+use std::sync::Weak;
+
+use bytes::Bytes;
+use ethereum_types::{Address, H256};
+use lru_cache::LruCache;
+use parking_lot::{Mutex, RwLock};
+use types::{header::Header, ids::BlockId, BlockNumber};
+
+use super::{SimpleList, SystemCall, ValidatorSet};
+use client::EngineClient;
+use error::Error as EthcoreError;
+use machine::{AuxiliaryData, Call, EthereumMachine};
+
+/// Number of blocks for which the use of the primary set is remembered.
+const SOURCE_CACHE_SIZE: usize = 500;
+
+/// Set which answers validator queries at a given block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Primary,
+    Fallback,
+}
+
+/// Reads validators from the primary set (usually a contract) and switches to the fallback set
+/// for blocks at which the primary set is empty or its contract has no code.
+///
+/// Only these conditions are decided by the chain itself. Any other failure to read the primary
+/// set, e.g. missing state, is local to this node and reported as such by the primary set
+/// instead of silently answering with other validators.
+///
+/// Block production, reporting and epoch signalling stay with the primary set; only the
+/// validator lists are taken from the fallback.
+pub struct Fallback {
+    primary: Box<dyn ValidatorSet>,
+    fallback: Box<dyn ValidatorSet>,
+    /// Address of the primary set contract, if it is one.
+    contract: Option<Address>,
+    client: RwLock<Option<Weak<dyn EngineClient>>>,
+    /// Blocks at which the primary set is known to be used. Fallback decisions are not cached.
+    sources: Mutex<LruCache<H256, Source>>,
+}
+
+impl Fallback {
+    pub fn new(
+        primary: Box<dyn ValidatorSet>,
+        contract: Option<Address>,
+        fallback: Box<dyn ValidatorSet>,
+    ) -> Self {
+        Fallback {
+            primary,
+            fallback,
+            contract,
+            client: RwLock::new(None),
+            sources: Mutex::new(LruCache::new(SOURCE_CACHE_SIZE)),
+        }
+    }
+
+    /// Whether the state at the given block has no code at the primary set contract. `None` if
+    /// that can't be told, e.g. because the state is not available.
+    fn contract_missing(&self, bh: &H256) -> Option<bool> {
+        let contract = self.contract?;
+        let client = self.client.read().as_ref()?.upgrade()?;
+        let code = client
+            .as_full_client()?
+            .code(&contract, BlockId::Hash(*bh).into())?;
+        Some(code.map_or(true, |code| code.is_empty()))
+    }
+
+    fn source(&self, bh: &H256, caller: &Call) -> Source {
+        if let Some(source) = self.sources.lock().get_mut(bh) {
+            return *source;
+        }
+        // Contract sets report `usize::max_value()` when the call fails.
+        match self.primary.count_with_caller(bh, caller) {
+            0 => {
+                warn!(target: "engine", "Primary validator set is empty at block {}, using the fallback set.", bh);
+                Source::Fallback
+            }
+            count if count == usize::max_value() => match self.contract_missing(bh) {
+                Some(true) => {
+                    warn!(target: "engine", "Primary validator set contract has no code at block {}, using the fallback set.", bh);
+                    Source::Fallback
+                }
+                _ => {
+                    warn!(target: "engine", "Primary validator set could not be read at block {}.", bh);
+                    Source::Primary
+                }
+            },
+            _ => {
+                self.sources.lock().insert(*bh, Source::Primary);
+                Source::Primary
+            }
+        }
+    }
+
+    fn correct_set(&self, bh: &H256, caller: &Call) -> &dyn ValidatorSet {
+        match self.source(bh, caller) {
+            Source::Primary => &*self.primary,
+            Source::Fallback => &*self.fallback,
+        }
+    }
+}
+
+impl ValidatorSet for Fallback {
+    fn default_caller(&self, block_id: BlockId) -> Box<Call> {
+        self.primary.default_caller(block_id)
+    }
+
+    fn generate_engine_transactions(
+        &self,
+        first: bool,
+        header: &Header,
+        call: &mut SystemCall,
+    ) -> Result<Vec<(Address, Bytes)>, EthcoreError> {
+        self.primary
+            .generate_engine_transactions(first, header, call)
+    }
+
+    fn on_close_block(&self, header: &Header, address: &Address) -> Result<(), EthcoreError> {
+        self.primary.on_close_block(header, address)
+    }
+
+    fn on_epoch_begin(
+        &self,
+        first: bool,
+        header: &Header,
+        call: &mut SystemCall,
+    ) -> Result<(), EthcoreError> {
+        self.primary.on_epoch_begin(first, header, call)
+    }
+
+    fn genesis_epoch_data(&self, header: &Header, call: &Call) -> Result<Vec<u8>, String> {
+        self.primary
+            .genesis_epoch_data(header, call)
+            .or_else(|e| match self.contract_missing(&header.hash()) {
+                Some(true) => {
+                    warn!(target: "engine", "Primary validator set contract has no code at genesis, using the fallback set.");
+                    self.fallback.genesis_epoch_data(header, call)
+                }
+                _ => Err(e),
+            })
+    }
+
+    fn is_epoch_end(&self, first: bool, chain_head: &Header) -> Option<Vec<u8>> {
+        self.primary.is_epoch_end(first, chain_head)
+    }
+
+    fn signals_epoch_end(
+        &self,
+        first: bool,
+        header: &Header,
+        aux: AuxiliaryData,
+    ) -> ::engines::EpochChange<EthereumMachine> {
+        self.primary.signals_epoch_end(first, header, aux)
+    }
+
+    fn epoch_set(
+        &self,
+        first: bool,
+        machine: &EthereumMachine,
+        number: BlockNumber,
+        proof: &[u8],
+    ) -> Result<(SimpleList, Option<H256>), ::error::Error> {
+        // A proof which fails to verify is never skipped, only a proven empty set falls back.
+        match self.primary.epoch_set(first, machine, number, proof)? {
+            (ref list, _) if list.is_empty() => {
+                warn!(target: "engine", "Primary validator set is empty at epoch #{}, using the fallback set.", number);
+                self.fallback.epoch_set(first, machine, number, proof)
+            }
+            set => Ok(set),
+        }
+    }
+
+    fn contains_with_caller(&self, bh: &H256, address: &Address, caller: &Call) -> bool {
+        self.correct_set(bh, caller)
+            .contains_with_caller(bh, address, caller)
+    }
+
+    fn get_with_caller(&self, bh: &H256, nonce: usize, caller: &Call) -> Address {
+        self.correct_set(bh, caller)
+            .get_with_caller(bh, nonce, caller)
+    }
+
+    fn count_with_caller(&self, bh: &H256, caller: &Call) -> usize {
+        self.correct_set(bh, caller).count_with_caller(bh, caller)
+    }
+
+    fn report_malicious(
+        &self,
+        validator: &Address,
+        set_block: BlockNumber,
+        block: BlockNumber,
+        proof: Bytes,
+    ) {
+        self.primary
+            .report_malicious(validator, set_block, block, proof);
+    }
+
+    fn report_benign(&self, validator: &Address, set_block: BlockNumber, block: BlockNumber) {
+        self.primary.report_benign(validator, set_block, block);
+    }
+
+    fn register_client(&self, client: Weak<dyn EngineClient>) {
+        self.primary.register_client(client.clone());
+        self.fallback.register_client(client.clone());
+        *self.client.write() = Some(client);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{super::ValidatorSafeContract, Fallback};
+    use client::{BlockChainClient, TestBlockChainClient};
+    use engines::validator_set::{SimpleList, ValidatorSet};
+    use ethereum_types::{Address, H256};
+    use spec::Spec;
+
+    fn contract_set(client: &Arc<TestBlockChainClient>) -> Fallback {
+        let contract = Address::from_low_u64_be(100);
+        let set = Fallback::new(
+            Box::new(ValidatorSafeContract::new(contract, None)),
+            Some(contract),
+            Box::new(SimpleList::new(vec![Address::from_low_u64_be(2)])),
+        );
+        set.register_client(Arc::downgrade(client) as _);
+        set
+    }
+
+    #[test]
+    fn uses_primary_set_when_available() {
+        let primary = Address::from_low_u64_be(1);
+        let fallback = Address::from_low_u64_be(2);
+        let set = Fallback::new(
+            Box::new(SimpleList::new(vec![primary])),
+            None,
+            Box::new(SimpleList::new(vec![fallback])),
+        );
+        let bh = H256::from_low_u64_be(10);
+
+        assert!(set.contains(&bh, &primary));
+        assert!(!set.contains(&bh, &fallback));
+        assert_eq!(set.get(&bh, 0), primary);
+        assert_eq!(set.sources.lock().len(), 1);
+    }
+
+    #[test]
+    fn falls_back_on_empty_primary_set() {
+        let fallback = Address::from_low_u64_be(2);
+        let set = Fallback::new(
+            Box::new(SimpleList::new(vec![])),
+            None,
+            Box::new(SimpleList::new(vec![fallback])),
+        );
+        let bh = H256::from_low_u64_be(10);
+
+        assert!(set.contains(&bh, &fallback));
+        assert_eq!(set.count(&bh), 1);
+        assert_eq!(set.sources.lock().len(), 0);
+    }
+
+    #[test]
+    fn falls_back_when_contract_has_no_code() {
+        let client = Arc::new(TestBlockChainClient::new());
+        let set = contract_set(&client);
+        let bh = client.chain_info().best_block_hash;
+
+        assert_eq!(set.count(&bh), 1);
+        assert_eq!(set.get(&bh, 5), Address::from_low_u64_be(2));
+        assert_eq!(set.sources.lock().len(), 0);
+    }
+
+    #[test]
+    fn does_not_fall_back_on_failed_contract_call() {
+        let client = Arc::new(TestBlockChainClient::new());
+        client.set_code(Address::from_low_u64_be(100), vec![0x60, 0x00]);
+        let set = contract_set(&client);
+        let bh = client.chain_info().best_block_hash;
+
+        // the code is there, so the call failing is a local error reported by the primary set
+        assert_eq!(set.count(&bh), usize::max_value());
+        assert_eq!(set.sources.lock().len(), 0);
+
+        // the state of an unknown block can't be read either
+        assert_eq!(set.count(&H256::from_low_u64_be(10)), usize::max_value());
+    }
+
+    #[test]
+    fn does_not_skip_failed_epoch_proof() {
+        let client = Arc::new(TestBlockChainClient::new());
+        let set = contract_set(&client);
+        let machine = Spec::new_test_machine();
+
+        assert!(set.epoch_set(true, &machine, 1, &[0x80]).is_err());
+    }
+}
@@ -15,6 +15,7 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 mod contract;
+mod fallback;
 mod multi;
 mod safe_contract;
 mod simple_list;
@@ -38,7 +39,10 @@ use error::Error as EthcoreError;
 pub use self::simple_list::SimpleList;
 #[cfg(test)]
 pub use self::test::TestSet;
-use self::{contract::ValidatorContract, multi::Multi, safe_contract::ValidatorSafeContract};
+use self::{
+    contract::ValidatorContract, fallback::Fallback, multi::Multi,
+    safe_contract::ValidatorSafeContract,
+};
 use super::SystemCall;
 
 /// Creates a validator set from the given spec and initializes a transition to POSDAO AuRa consensus.
@@ -68,6 +72,19 @@ pub fn new_validator_set_posdao(
                 })
                 .collect(),
         )),
+        ValidatorSpec::Fallback { primary, fallback } => {
+            let contract = match *primary {
+                ValidatorSpec::SafeContract(ref address) | ValidatorSpec::Contract(ref address) => {
+                    Some(address.clone().into())
+                }
+                _ => None,
+            };
+            Box::new(Fallback::new(
+                new_validator_set_posdao(*primary, posdao_transition),
+                contract,
+                new_validator_set_posdao(*fallback, posdao_transition),
+            ))
+        }
     }
 }
 
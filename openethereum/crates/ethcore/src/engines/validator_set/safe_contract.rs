@@ -131,6 +131,7 @@ fn check_first_proof(
         },
         gas_used: 0.into(),
         base_fee: old_header.base_fee(),
+        random: old_header.mix_hash(),
     };
 
     // check state proof using given machine.
@@ -654,8 +655,24 @@ impl ValidatorSet for ValidatorSafeContract {
 struct ReportQueue(VecDeque<(Address, BlockNumber, Vec<u8>)>);
 
 impl ReportQueue {
-    /// Pushes a report to the end of the queue.
+    /// Pushes a report to the end of the queue, unless the same misbehavior is already queued.
+    ///
+    /// The queue is only filtered and truncated when closing our own blocks, so to keep it
+    /// bounded on nodes that don't seal, the oldest report is dropped if it is full.
     fn push(&mut self, addr: Address, block: BlockNumber, data: Vec<u8>) {
+        if self
+            .0
+            .iter()
+            .any(|&(queued_addr, queued_block, _)| queued_addr == addr && queued_block == block)
+        {
+            return;
+        }
+        if self.0.len() >= MAX_QUEUED_REPORTS {
+            if let Some((dropped_addr, dropped_block, _)) = self.0.pop_front() {
+                warn!(target: "engine", "Report queue is full, dropping report of validator {} at block {}",
+                    dropped_addr, dropped_block);
+            }
+        }
         self.0.push_back((addr, block, data));
     }
 
@@ -713,7 +730,10 @@ impl ReportQueue {
 
 #[cfg(test)]
 mod tests {
-    use super::{super::ValidatorSet, ValidatorSafeContract, EVENT_NAME_HASH};
+    use super::{
+        super::ValidatorSet, ReportQueue, ValidatorSafeContract, EVENT_NAME_HASH,
+        MAX_QUEUED_REPORTS,
+    };
     use accounts::AccountProvider;
     use client::{
         traits::{EngineClient, ForceUpdateSealing},
@@ -910,4 +930,20 @@ mod tests {
             _ => panic!("Expected state to be required to prove initial signal"),
         };
     }
+
+    #[test]
+    fn report_queue_is_bounded_and_deduplicated() {
+        let mut queue = ReportQueue::default();
+        let validator = Address::from_low_u64_be(1);
+        queue.push(validator, 1, vec![1]);
+        queue.push(validator, 1, vec![2]);
+        assert_eq!(queue.iter().count(), 1);
+
+        for block in 2..(MAX_QUEUED_REPORTS as u64 + 2) {
+            queue.push(validator, block, Vec::new());
+        }
+        assert_eq!(queue.iter().count(), MAX_QUEUED_REPORTS);
+        // The oldest report was dropped.
+        assert_eq!(queue.iter().next().map(|r| r.1), Some(2));
+    }
 }
@@ -68,6 +68,8 @@ pub enum BlockError {
     InvalidGasUsed(Mismatch<U256>),
     /// Transactions root header field is invalid.
     InvalidTransactionsRoot(Mismatch<H256>),
+    /// Withdrawals root header field does not match the block's withdrawals.
+    InvalidWithdrawalsRoot(Mismatch<H256>),
     /// Difficulty is out of range; this can be used as an looser error prior to getting a definitive
     /// value for difficulty. This error needs only provide bounds of which it is out.
     DifficultyOutOfBounds(OutOfBounds<U256>),
@@ -101,6 +103,8 @@ pub enum BlockError {
     TimestampOverflow,
     /// Too many transactions from a particular address.
     TooManyTransactions(Address),
+    /// Block has too many system transactions.
+    TooManySystemTransactions(OutOfBounds<usize>),
     /// Parent given is unknown.
     UnknownParent(H256),
     /// Uncle parent given is unknown.
@@ -133,6 +137,9 @@ impl fmt::Display for BlockError {
             InvalidTransactionsRoot(ref mis) => {
                 format!("Invalid transactions root in header: {}", mis)
             }
+            InvalidWithdrawalsRoot(ref mis) => {
+                format!("Invalid withdrawals root in header: {}", mis)
+            }
             DifficultyOutOfBounds(ref oob) => format!("Invalid block difficulty: {}", oob),
             InvalidDifficulty(ref mis) => format!("Invalid block difficulty: {}", mis),
             MismatchedH256SealElement(ref mis) => format!("Seal element out of bounds: {}", mis),
@@ -161,6 +168,9 @@ impl fmt::Display for BlockError {
             }
             TimestampOverflow => format!("Timestamp overflow"),
             TooManyTransactions(ref address) => format!("Too many transactions from: {}", address),
+            TooManySystemTransactions(ref oob) => {
+                format!("Block has too many system transactions. {}", oob)
+            }
         };
 
         f.write_fmt(format_args!("Block error ({})", msg))
@@ -184,6 +194,12 @@ error_chain! {
             description("Queue is full")
             display("The queue is full ({})", limit)
         }
+
+        #[doc = "Queue is closed"]
+        Closed {
+            description("Queue is closed")
+            display("The queue is closed")
+        }
     }
 
     foreign_links {
@@ -214,6 +230,12 @@ error_chain! {
             description("block known to be bad")
             display("block known to be bad")
         }
+
+        #[doc = "The client was started in read-only mode."]
+        ReadOnly {
+            description("client is read-only")
+            display("client is read-only")
+        }
     }
 }
 
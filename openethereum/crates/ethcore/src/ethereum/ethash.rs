@@ -39,7 +39,7 @@ use engines::{
 };
 use error::{BlockError, Error};
 use ethash::{self, quick_get_difficulty, slow_hash_block_number, EthashManager, OptimizeFor};
-use machine::EthereumMachine;
+use machine::{EthereumMachine, Machine};
 
 /// Number of blocks in an ethash snapshot.
 // make dependent on difficulty incrment divisor?
@@ -245,6 +245,11 @@ impl Engine<EthereumMachine> for Arc<Ethash> {
         2
     }
 
+    fn set_pow_cache_config(&self, cache_count: usize, prebuild_distance: u64) {
+        self.pow.set_cache_count(cache_count);
+        self.pow.set_prebuild_distance(prebuild_distance);
+    }
+
     /// Additional engine-specific information for the user/developer concerning `header`.
     fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
         match Seal::parse_seal(header.seal()) {
@@ -276,6 +281,9 @@ impl Engine<EthereumMachine> for Arc<Ethash> {
 
         let author = *block.header.author();
         let number = block.header.number();
+        if self.machine.has_reward_schedule(number) {
+            return Ok(());
+        }
 
         let rewards = match self.ethash_params.block_reward_contract {
             Some(ref c) if number >= self.ethash_params.block_reward_contract_transition => {
@@ -458,6 +466,11 @@ impl Engine<EthereumMachine> for Arc<Ethash> {
     fn fork_choice(&self, new: &ExtendedHeader, current: &ExtendedHeader) -> engines::ForkChoice {
         engines::total_difficulty_fork_choice(new, current)
     }
+
+    // The author is only the reward beneficiary, sealing is proof of work.
+    fn supports_fee_recipient(&self, _block_number: BlockNumber) -> bool {
+        true
+    }
 }
 
 impl Ethash {
@@ -560,7 +573,7 @@ fn ecip1017_eras_block_reward(era_rounds: u64, mut reward: U256, block_number: u
 #[cfg(test)]
 mod tests {
     use super::{
-        super::{new_homestead_test_machine, new_mcip3_test, new_morden},
+        super::{new_foundation, new_homestead_test_machine, new_mcip3_test, new_morden},
         ecip1017_eras_block_reward, Ethash, EthashParams,
     };
     use block::*;
@@ -762,10 +775,10 @@ mod tests {
     #[test]
     fn can_return_schedule() {
         let engine = test_spec().engine;
-        let schedule = engine.schedule(10000000);
+        let schedule = engine.schedule(10000000, 0);
         assert!(schedule.stack_limit > 0);
 
-        let schedule = engine.schedule(100);
+        let schedule = engine.schedule(100, 0);
         assert!(!schedule.have_delegate_call);
     }
 
@@ -1004,6 +1017,42 @@ mod tests {
         assert_eq!(U256::from_str("1fc50f118efe").unwrap(), difficulty);
     }
 
+    #[test]
+    fn difficulty_bomb_delays_follow_foundation_spec() {
+        let tempdir = TempDir::new("").unwrap();
+        let spec = new_foundation(&tempdir.path());
+        let parent_difficulty = U256::from(12_000_000_000_000_000u64);
+
+        // 13 seconds after the parent leaves the difficulty unadjusted, only the bomb is added.
+        let difficulty_at = |number: u64| {
+            let mut parent_header = Header::default();
+            parent_header.set_number(number - 1);
+            parent_header.set_difficulty(parent_difficulty);
+            parent_header.set_timestamp(1_650_000_000);
+            let mut header = Header::default();
+            header.set_number(number);
+            header.set_timestamp(1_650_000_013);
+            spec.engine
+                .populate_from_parent(&mut header, &parent_header);
+            *header.difficulty()
+        };
+
+        // Arrow Glacier (EIP-4345): delayed by 10.7M blocks in total.
+        assert_eq!(
+            difficulty_at(13_773_000),
+            parent_difficulty + (U256::one() << 28)
+        );
+        assert_eq!(
+            difficulty_at(15_049_999),
+            parent_difficulty + (U256::one() << 41)
+        );
+        // Gray Glacier (EIP-5133): delayed by 11.4M blocks in total.
+        assert_eq!(
+            difficulty_at(15_050_000),
+            parent_difficulty + (U256::one() << 34)
+        );
+    }
+
     #[test]
     fn difficulty_max_timestamp() {
         let machine = new_homestead_test_machine();
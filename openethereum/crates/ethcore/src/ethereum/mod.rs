@@ -290,6 +290,22 @@ pub fn new_berlin_to_london_test() -> Spec {
     )
 }
 
+/// Create a new Merge era spec.
+pub fn new_merge_test() -> Spec {
+    load(
+        None,
+        include_bytes!("../../res/chainspec/test/merge_test.json"),
+    )
+}
+
+/// Create a new London era spec with EIP-3074 enabled.
+pub fn new_eip3074_test() -> Spec {
+    load(
+        None,
+        include_bytes!("../../res/chainspec/test/eip3074_test.json"),
+    )
+}
+
 /// Create a new Musicoin-MCIP3-era spec.
 pub fn new_mcip3_test() -> Spec {
     load(
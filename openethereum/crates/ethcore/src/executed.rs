@@ -206,6 +206,8 @@ pub enum CallError {
     StateCorrupt,
     /// Error executing.
     Execution(ExecutionError),
+    /// Execution ran past the call timeout and was interrupted.
+    Timeout,
 }
 
 impl From<ExecutionError> for CallError {
@@ -224,6 +226,7 @@ impl fmt::Display for CallError {
             Exceptional(ref e) => format!("An exception ({}) happened in the execution", e),
             StateCorrupt => "Stored state found to be corrupted.".into(),
             Execution(ref e) => format!("{}", e),
+            Timeout => "Execution timed out".into(),
         };
 
         f.write_fmt(format_args!("Transaction execution error ({}).", msg))
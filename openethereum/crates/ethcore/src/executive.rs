@@ -25,7 +25,7 @@ use factory::VmFactory;
 use hash::keccak;
 use machine::EthereumMachine as Machine;
 use state::{Backend as StateBackend, CleanupMode, State, Substate};
-use std::{cmp, convert::TryFrom, sync::Arc};
+use std::{cmp, convert::TryFrom, sync::Arc, time::Instant};
 use trace::{self, Tracer, VMTracer};
 use transaction_ext::Transaction;
 use types::transaction::{Action, SignedTransaction, TypedTransaction};
@@ -131,6 +131,8 @@ pub struct TransactOptions<T, V> {
     pub check_nonce: bool,
     /// Records the output from init contract calls.
     pub output_from_init_contract: bool,
+    /// Interrupts the execution once this instant passes.
+    pub deadline: Option<Instant>,
 }
 
 impl<T, V> TransactOptions<T, V> {
@@ -141,6 +143,7 @@ impl<T, V> TransactOptions<T, V> {
             vm_tracer,
             check_nonce: true,
             output_from_init_contract: false,
+            deadline: None,
         }
     }
 
@@ -155,6 +158,12 @@ impl<T, V> TransactOptions<T, V> {
         self.output_from_init_contract = true;
         self
     }
+
+    /// Aborts the execution with `vm::Error::ExecutionTimeout` once `deadline` passes.
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
 }
 
 impl TransactOptions<trace::ExecutiveTracer, trace::ExecutiveVMTracer> {
@@ -165,6 +174,7 @@ impl TransactOptions<trace::ExecutiveTracer, trace::ExecutiveVMTracer> {
             vm_tracer: trace::ExecutiveVMTracer::toplevel(),
             check_nonce: true,
             output_from_init_contract: false,
+            deadline: None,
         }
     }
 }
@@ -177,6 +187,7 @@ impl TransactOptions<trace::ExecutiveTracer, trace::NoopVMTracer> {
             vm_tracer: trace::NoopVMTracer,
             check_nonce: true,
             output_from_init_contract: false,
+            deadline: None,
         }
     }
 }
@@ -189,6 +200,7 @@ impl TransactOptions<trace::NoopTracer, trace::ExecutiveVMTracer> {
             vm_tracer: trace::ExecutiveVMTracer::toplevel(),
             check_nonce: true,
             output_from_init_contract: false,
+            deadline: None,
         }
     }
 }
@@ -201,6 +213,7 @@ impl TransactOptions<trace::NoopTracer, trace::NoopVMTracer> {
             vm_tracer: trace::NoopVMTracer,
             check_nonce: true,
             output_from_init_contract: false,
+            deadline: None,
         }
     }
 }
@@ -229,6 +242,7 @@ pub struct CallCreateExecutive<'a> {
     depth: usize,
     stack_depth: usize,
     static_flag: bool,
+    deadline: Option<Instant>,
     is_create: bool,
     gas: U256,
     kind: CallCreateExecutiveKind,
@@ -256,6 +270,7 @@ impl<'a> CallCreateExecutive<'a> {
         depth: usize,
         stack_depth: usize,
         parent_static_flag: bool,
+        deadline: Option<Instant>,
     ) -> Self {
         trace!(
             "Executive::call(params={:?}) self.env_info={:?}, parent_static={}",
@@ -296,6 +311,7 @@ impl<'a> CallCreateExecutive<'a> {
             depth,
             stack_depth,
             static_flag,
+            deadline,
             kind,
             gas,
             is_create: false,
@@ -312,6 +328,7 @@ impl<'a> CallCreateExecutive<'a> {
         depth: usize,
         stack_depth: usize,
         static_flag: bool,
+        deadline: Option<Instant>,
     ) -> Self {
         trace!(
             "Executive::create(params={:?}) self.env_info={:?}, static={}",
@@ -333,6 +350,7 @@ impl<'a> CallCreateExecutive<'a> {
             depth,
             stack_depth,
             static_flag,
+            deadline,
             kind,
             gas,
             is_create: true,
@@ -445,6 +463,8 @@ impl<'a> CallCreateExecutive<'a> {
             | Err(vm::Error::OutOfSubStack { .. })
             | Err(vm::Error::InvalidSubEntry)
             | Err(vm::Error::InvalidCode)
+            | Err(vm::Error::Unauthorized)
+            | Err(vm::Error::ExecutionTimeout)
             | Ok(FinalizationResult {
                 apply_state: false, ..
             }) => {
@@ -472,6 +492,7 @@ impl<'a> CallCreateExecutive<'a> {
         depth: usize,
         stack_depth: usize,
         static_flag: bool,
+        deadline: Option<Instant>,
         origin_info: &'any OriginInfo,
         substate: &'any mut Substate,
         output: OutputPolicy,
@@ -496,6 +517,7 @@ impl<'a> CallCreateExecutive<'a> {
             vm_tracer,
             static_flag,
         )
+        .with_deadline(deadline)
     }
 
     /// Execute the executive. If a sub-call/create action is required, a resume trap error is returned. The caller is
@@ -617,6 +639,7 @@ impl<'a> CallCreateExecutive<'a> {
                         self.depth,
                         self.stack_depth,
                         self.static_flag,
+                        self.deadline,
                         &origin_info,
                         &mut unconfirmed_substate,
                         OutputPolicy::Return,
@@ -685,6 +708,7 @@ impl<'a> CallCreateExecutive<'a> {
                         self.depth,
                         self.stack_depth,
                         self.static_flag,
+                        self.deadline,
                         &origin_info,
                         &mut unconfirmed_substate,
                         OutputPolicy::InitContract,
@@ -747,6 +771,7 @@ impl<'a> CallCreateExecutive<'a> {
                         self.depth,
                         self.stack_depth,
                         self.static_flag,
+                        self.deadline,
                         &origin_info,
                         &mut unconfirmed_substate,
                         if self.is_create {
@@ -821,6 +846,7 @@ impl<'a> CallCreateExecutive<'a> {
                         self.depth,
                         self.stack_depth,
                         self.static_flag,
+                        self.deadline,
                         &origin_info,
                         &mut unconfirmed_substate,
                         if self.is_create {
@@ -987,6 +1013,7 @@ impl<'a> CallCreateExecutive<'a> {
 						resume.depth + 1,
 						resume.stack_depth,
 						resume.static_flag,
+						resume.deadline,
 					);
 
 					callstack.push((None, resume));
@@ -1005,7 +1032,8 @@ impl<'a> CallCreateExecutive<'a> {
 						resume.factory,
 						resume.depth + 1,
 						resume.stack_depth,
-						resume.static_flag
+						resume.static_flag,
+						resume.deadline,
 					);
 
 					callstack.push((Some(address), resume));
@@ -1025,6 +1053,7 @@ pub struct Executive<'a, B: 'a> {
     schedule: &'a Schedule,
     depth: usize,
     static_flag: bool,
+    deadline: Option<Instant>,
 }
 
 impl<'a, B: 'a + StateBackend> Executive<'a, B> {
@@ -1042,6 +1071,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             schedule: schedule,
             depth: 0,
             static_flag: false,
+            deadline: None,
         }
     }
 
@@ -1061,6 +1091,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             schedule: schedule,
             depth: parent_depth + 1,
             static_flag: static_flag,
+            deadline: None,
         }
     }
 
@@ -1074,6 +1105,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         T: Tracer,
         V: VMTracer,
     {
+        self.deadline = options.deadline;
         self.transact_with_tracer(
             t,
             options.check_nonce,
@@ -1361,6 +1393,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             self.depth,
             stack_depth,
             self.static_flag,
+            self.deadline,
         )
         .consume(self.state, substate, tracer, vm_tracer);
 
@@ -1431,6 +1464,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             self.depth,
             stack_depth,
             self.static_flag,
+            self.deadline,
         )
         .consume(self.state, substate, tracer, vm_tracer);
 
@@ -1703,7 +1737,7 @@ mod tests {
             .unwrap();
         let info = EnvInfo::default();
         let machine = make_frontier_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
 
         let FinalizationResult { gas_left, .. } = {
@@ -1773,7 +1807,7 @@ mod tests {
             .unwrap();
         let info = EnvInfo::default();
         let machine = make_frontier_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
 
         let FinalizationResult { gas_left, .. } = {
@@ -1819,7 +1853,7 @@ mod tests {
             .unwrap();
         let info = EnvInfo::default();
         let machine = make_byzantium_machine(5);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
         let mut tracer = ExecutiveTracer::default();
         let mut vm_tracer = ExecutiveVMTracer::toplevel();
@@ -1922,7 +1956,7 @@ mod tests {
             .unwrap();
         let info = EnvInfo::default();
         let machine = make_frontier_machine(5);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
         let mut tracer = ExecutiveTracer::default();
         let mut vm_tracer = ExecutiveVMTracer::toplevel();
@@ -2053,7 +2087,7 @@ mod tests {
             .unwrap();
         let info = EnvInfo::default();
         let machine = ::ethereum::new_byzantium_test_machine();
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
         let mut tracer = ExecutiveTracer::default();
         let mut vm_tracer = ExecutiveVMTracer::toplevel();
@@ -2139,7 +2173,7 @@ mod tests {
             .unwrap();
         let info = EnvInfo::default();
         let machine = make_frontier_machine(5);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
         let mut tracer = ExecutiveTracer::default();
         let mut vm_tracer = ExecutiveVMTracer::toplevel();
@@ -2315,7 +2349,7 @@ mod tests {
             .unwrap();
         let info = EnvInfo::default();
         let machine = make_frontier_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
 
         let FinalizationResult { gas_left, .. } = {
@@ -2385,7 +2419,7 @@ mod tests {
             .unwrap();
         let info = EnvInfo::default();
         let machine = make_frontier_machine(1024);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
 
         {
@@ -2454,7 +2488,7 @@ mod tests {
 
         let info = EnvInfo::default();
         let machine = make_frontier_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
 
         let FinalizationResult { gas_left, .. } = {
@@ -2513,7 +2547,7 @@ mod tests {
         state.init_code(&address, code).unwrap();
         let info = EnvInfo::default();
         let machine = make_frontier_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
 
         let FinalizationResult { gas_left, .. } = {
@@ -2567,7 +2601,7 @@ mod tests {
         let mut info = EnvInfo::default();
         info.gas_limit = U256::from(100_000);
         let machine = make_frontier_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
 
         let executed = {
             let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
@@ -2611,7 +2645,7 @@ mod tests {
         let mut info = EnvInfo::default();
         info.gas_limit = U256::from(100_000);
         let machine = make_frontier_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
 
         let res = {
             let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
@@ -2651,7 +2685,7 @@ mod tests {
         info.gas_used = U256::from(20_000);
         info.gas_limit = U256::from(100_000);
         let machine = make_frontier_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
 
         let res = {
             let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
@@ -2709,7 +2743,7 @@ mod tests {
         info.gas_limit = U256::from(100_000);
         info.base_fee = Some(U256::from(100));
         let machine = make_london_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
 
         let res = {
             let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
@@ -2742,7 +2776,7 @@ mod tests {
         let mut info = EnvInfo::default();
         info.gas_limit = U256::from(100_000);
         let machine = make_frontier_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
 
         let res = {
             let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
@@ -2799,7 +2833,7 @@ mod tests {
         info.gas_limit = U256::from(100_000);
         info.base_fee = Some(U256::from(100));
         let machine = make_london_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
 
         let res = {
             let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
@@ -2859,7 +2893,7 @@ mod tests {
         info.gas_limit = U256::from(100_000);
         info.base_fee = Some(U256::from(100));
         let machine = make_london_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
 
         let res = {
             let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
@@ -2912,7 +2946,7 @@ mod tests {
             .unwrap();
         let info = EnvInfo::default();
         let machine = make_frontier_machine(0);
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
 
         let result = {
@@ -2953,7 +2987,7 @@ mod tests {
         params.value = ActionValue::Transfer(U256::zero());
         let info = EnvInfo::default();
         let machine = ::ethereum::new_byzantium_test_machine();
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
         let mut substate = Substate::new();
 
         let mut output = [0u8; 14];
@@ -2978,6 +3012,44 @@ mod tests {
         );
     }
 
+    evm_test! {test_transact_deadline: test_transact_deadline_int}
+    fn test_transact_deadline(factory: Factory) {
+        let looping = Address::from_low_u64_be(0x1000);
+        let mut state = get_temp_state_with_factory(factory);
+        state
+            .new_contract(&looping, U256::zero(), U256::from(1))
+            .unwrap();
+        // JUMPDEST PUSH1 0 JUMP
+        state
+            .init_code(&looping, "5b600056".from_hex().unwrap())
+            .unwrap();
+
+        let keypair = Random.generate();
+        let t = TypedTransaction::Legacy(Transaction {
+            action: Action::Call(looping),
+            value: U256::zero(),
+            data: vec![],
+            gas: U256::from(100_000),
+            gas_price: U256::zero(),
+            nonce: U256::zero(),
+        })
+        .sign(keypair.secret(), None);
+
+        let mut info = EnvInfo::default();
+        info.gas_limit = U256::from(100_000);
+        let machine = make_frontier_machine(0);
+        let schedule = machine.schedule(info.number, info.timestamp);
+
+        let executed = {
+            let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
+            let opts = TransactOptions::with_no_tracing().with_deadline(Some(Instant::now()));
+            ex.transact_virtual(&t, opts).unwrap()
+        };
+
+        assert_eq!(executed.exception, Some(vm::Error::ExecutionTimeout));
+        assert_eq!(executed.gas_used, U256::from(100_000));
+    }
+
     evm_test! {test_eip1283: test_eip1283_int}
     fn test_eip1283(factory: Factory) {
         let x1 = Address::from_low_u64_be(0x1000);
@@ -3015,7 +3087,7 @@ mod tests {
 
         let info = EnvInfo::default();
         let machine = ::ethereum::new_constantinople_test_machine();
-        let schedule = machine.schedule(info.number);
+        let schedule = machine.schedule(info.number, info.timestamp);
 
         assert_eq!(
             state.storage_at(&operating_address, &k).unwrap(),
@@ -3113,7 +3185,7 @@ mod tests {
             return_data,
             ..
         } = {
-            let schedule = machine.schedule(info.number);
+            let schedule = machine.schedule(info.number, info.timestamp);
             let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
             ex.call(
                 params.clone(),
@@ -3138,7 +3210,7 @@ mod tests {
             return_data,
             ..
         } = {
-            let schedule = machine.schedule(info.number);
+            let schedule = machine.schedule(info.number, info.timestamp);
             let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
             ex.call(
                 params.clone(),
@@ -3164,7 +3236,7 @@ mod tests {
             return_data,
             ..
         } = {
-            let schedule = machine.schedule(info.number);
+            let schedule = machine.schedule(info.number, info.timestamp);
             let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
             ex.call(
                 params,
@@ -16,11 +16,13 @@
 
 //! Transaction Execution environment.
 use bytes::Bytes;
+use crypto::publickey::{self, Signature};
 use ethereum_types::{Address, BigEndianHash, H256, U256};
 use executive::*;
+use hash::{keccak, KECCAK_EMPTY};
 use machine::EthereumMachine as Machine;
 use state::{Backend as StateBackend, CleanupMode, State, Substate};
-use std::{cmp, sync::Arc};
+use std::{cell::Cell, cmp, sync::Arc, time::Instant};
 use trace::{Tracer, VMTracer};
 use types::transaction::UNSIGNED_SENDER;
 use vm::{
@@ -37,12 +39,17 @@ pub enum OutputPolicy {
     InitContract,
 }
 
+/// Prefix of the EIP-3074 `AUTH` message, preventing signature collisions with other schemes.
+const EIP3074_MAGIC: u8 = 0x04;
+
 /// Transaction properties that externalities need to know about.
 pub struct OriginInfo {
     address: Address,
     origin: Address,
     gas_price: U256,
     value: U256,
+    /// Account authorized by `AUTH`, kept for the whole call frame.
+    authorized: Cell<Option<Address>>,
 }
 
 impl OriginInfo {
@@ -55,6 +62,7 @@ impl OriginInfo {
             value: match params.value {
                 ActionValue::Transfer(val) | ActionValue::Apparent(val) => val,
             },
+            authorized: Cell::new(None),
         }
     }
 }
@@ -73,6 +81,7 @@ pub struct Externalities<'a, T: 'a, V: 'a, B: 'a> {
     tracer: &'a mut T,
     vm_tracer: &'a mut V,
     static_flag: bool,
+    deadline: Option<Instant>,
 }
 
 impl<'a, T: 'a, V: 'a, B: 'a> Externalities<'a, T, V, B>
@@ -109,8 +118,15 @@ where
             tracer: tracer,
             vm_tracer: vm_tracer,
             static_flag: static_flag,
+            deadline: None,
         }
     }
+
+    /// Interrupt the execution once `deadline` passes.
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
 }
 
 impl<'a, T: 'a, V: 'a, B: 'a> Ext for Externalities<'a, T, V, B>
@@ -514,6 +530,11 @@ where
         self.substate.sstore_clears_refund -= value as i128;
     }
 
+    fn deadline_exceeded(&self) -> bool {
+        self.deadline
+            .map_or(false, |deadline| Instant::now() >= deadline)
+    }
+
     fn trace_next_instruction(&mut self, pc: usize, instruction: u8, current_gas: U256) -> bool {
         self.vm_tracer
             .trace_next_instruction(pc, instruction, current_gas)
@@ -558,6 +579,66 @@ where
     fn al_insert_address(&mut self, address: Address) {
         self.substate.access_list.insert_address(address)
     }
+
+    fn auth(
+        &mut self,
+        authority: &Address,
+        signature: &[u8; 65],
+        commit: &H256,
+    ) -> vm::Result<bool> {
+        self.origin_info.authorized.set(None);
+
+        // only externally owned accounts may authorize
+        if self
+            .state
+            .code_hash(authority)?
+            .map_or(false, |hash| hash != KECCAK_EMPTY)
+        {
+            return Ok(false);
+        }
+        let y_parity = signature[0];
+        if y_parity > 1 {
+            return Ok(false);
+        }
+        let signature = Signature::from_rsv(
+            &H256::from_slice(&signature[1..33]),
+            &H256::from_slice(&signature[33..65]),
+            y_parity,
+        );
+        if !signature.is_valid() || !signature.is_low_s() {
+            return Ok(false);
+        }
+
+        let message = eip3074_message(
+            self.chain_id(),
+            self.state.nonce(authority)?,
+            &self.origin_info.address,
+            commit,
+        );
+        match publickey::recover(&signature, &message) {
+            Ok(public) if publickey::public_to_address(&public) == *authority => {
+                self.origin_info.authorized.set(Some(*authority));
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn authorized(&self) -> Option<Address> {
+        self.origin_info.authorized.get()
+    }
+}
+
+/// Message signed by an EIP-3074 authority:
+/// `keccak256(MAGIC || chain_id || nonce || invoker || commit)`, all but `MAGIC` as 32-byte words.
+fn eip3074_message(chain_id: u64, nonce: U256, invoker: &Address, commit: &H256) -> H256 {
+    let mut message = [0u8; 129];
+    message[0] = EIP3074_MAGIC;
+    U256::from(chain_id).to_big_endian(&mut message[1..33]);
+    nonce.to_big_endian(&mut message[33..65]);
+    message[77..97].copy_from_slice(invoker.as_bytes());
+    message[97..].copy_from_slice(commit.as_bytes());
+    keccak(&message[..])
 }
 
 #[cfg(test)]
@@ -576,6 +657,7 @@ mod tests {
             origin: Address::zero(),
             gas_price: U256::zero(),
             value: U256::zero(),
+            authorized: Cell::new(None),
         }
     }
 
@@ -589,6 +671,7 @@ mod tests {
             gas_used: 0.into(),
             gas_limit: 0.into(),
             base_fee: None,
+            random: None,
         }
     }
 
@@ -610,7 +693,7 @@ mod tests {
         fn new() -> Self {
             let machine = ::spec::Spec::new_test_machine();
             let env_info = get_test_env_info();
-            let schedule = machine.schedule(env_info.number);
+            let schedule = machine.schedule(env_info.number, env_info.timestamp);
             TestSetup {
                 state: get_temp_state(),
                 schedule: schedule,
@@ -962,4 +1045,53 @@ mod tests {
         let result = call_ret(Schedule::new_london(), &data);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn eip_3074_auth() {
+        use crypto::publickey::{sign, Generator, Random};
+
+        let mut setup = TestSetup::new();
+        let mut tracer = NoopTracer;
+        let mut vm_tracer = NoopVMTracer;
+        let origin_info = get_test_origin();
+        let authority = Random.generate();
+        let commit = H256::from_low_u64_be(0x3074);
+
+        let mut ext = Externalities::new(
+            &mut setup.state,
+            &setup.env_info,
+            &setup.machine,
+            &setup.schedule,
+            0,
+            0,
+            &origin_info,
+            &mut setup.sub_state,
+            OutputPolicy::InitContract,
+            &mut tracer,
+            &mut vm_tracer,
+            false,
+        );
+
+        let message = eip3074_message(ext.chain_id(), U256::zero(), &Address::zero(), &commit);
+        let signed = sign(authority.secret(), &message).unwrap();
+        let mut signature = [0u8; 65];
+        signature[0] = signed.v();
+        signature[1..33].copy_from_slice(signed.r());
+        signature[33..].copy_from_slice(signed.s());
+
+        assert!(ext.auth(&authority.address(), &signature, &commit).unwrap());
+        assert_eq!(ext.authorized(), Some(authority.address()));
+
+        // a signature over another commit clears the authorization
+        assert!(!ext
+            .auth(&authority.address(), &signature, &H256::zero())
+            .unwrap());
+        assert_eq!(ext.authorized(), None);
+
+        // signed by someone else
+        assert!(!ext
+            .auth(&Address::from_low_u64_be(1), &signature, &commit)
+            .unwrap());
+        assert_eq!(ext.authorized(), None);
+    }
 }
@@ -149,6 +149,15 @@ pub fn json_chain_test<H: FnMut(&str, HookType)>(
             continue;
         }
 
+        // Post-merge blocks are not sealed by proof of work, which the test engines expect.
+        if blockchain.network == ethjson::spec::ForkSpec::Merge {
+            info!(
+                "   SKIPPED {:?} {:?} - No proof-of-stake engine",
+                name, blockchain.network
+            );
+            continue;
+        }
+
         let mut fail = false;
         {
             let mut fail_unless = |cond: bool| {
@@ -204,7 +213,7 @@ pub fn json_chain_test<H: FnMut(&str, HookType)>(
 
                 for b in blockchain.blocks_rlp() {
                     let bytes_len = b.len();
-                    let block = Unverified::from_rlp(b, spec.params().eip1559_transition);
+                    let block = Unverified::from_rlp(b, spec.params().header_decode_params());
                     match block {
                         Ok(block) => {
                             let num = block.header.number();
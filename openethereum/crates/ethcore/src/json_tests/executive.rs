@@ -263,6 +263,19 @@ where
     fn al_insert_address(&mut self, address: Address) {
         self.ext.al_insert_address(address)
     }
+
+    fn auth(
+        &mut self,
+        authority: &Address,
+        signature: &[u8; 65],
+        commit: &H256,
+    ) -> vm::Result<bool> {
+        self.ext.auth(authority, signature, commit)
+    }
+
+    fn authorized(&self) -> Option<Address> {
+        self.ext.authorized()
+    }
 }
 
 /// run an json executive test
@@ -326,7 +339,7 @@ pub fn json_executive_test<H: FnMut(&str, HookType)>(
 
         // execute
         let (res, callcreates) = {
-            let schedule = machine.schedule(info.number);
+            let schedule = machine.schedule(info.number, info.timestamp);
             let mut ex = try_fail!(TestExt::new(
                 &mut state,
                 &info,
@@ -76,7 +76,7 @@ pub fn json_transaction_test<H: FnMut(&str, HookType)>(
 
                     let minimal = t
                         .tx()
-                        .gas_required(&spec.engine.schedule(header.number()))
+                        .gas_required(&spec.engine.schedule(header.number(), header.timestamp()))
                         .into();
                     if t.tx().gas < minimal {
                         return Err(::types::transaction::Error::InsufficientGas {
@@ -19,14 +19,18 @@
 use std::{
     cmp::{self, max},
     collections::{BTreeMap, HashMap},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering as AtomicOrdering},
+        Arc,
+    },
+    time::Duration,
 };
 
 use ethereum_types::{Address, H256, U256};
 use types::{
     header::Header,
     transaction::{
-        self, SignedTransaction, TypedTransaction, UnverifiedTransaction, SYSTEM_ADDRESS,
+        self, Action, SignedTransaction, TypedTransaction, UnverifiedTransaction, SYSTEM_ADDRESS,
         UNSIGNED_SENDER,
     },
     BlockNumber,
@@ -40,10 +44,12 @@ use block::ExecutedBlock;
 use builtin::Builtin;
 use call_contract::CallContract;
 use client::BlockInfo;
+use engines::block_reward;
 use error::Error;
 use executive::Executive;
 use spec::CommonParams;
 use state::{CleanupMode, Substate};
+use time_utils::{Clock, SharedClock};
 use trace::{NoopTracer, NoopVMTracer};
 use tx_filter::TransactionFilter;
 
@@ -80,6 +86,9 @@ impl From<::ethjson::spec::EthashParams> for EthashExtensions {
     }
 }
 
+/// Default maximum amount of time a block timestamp may be ahead of the local clock.
+pub const DEFAULT_MAX_FUTURE_DRIFT: Duration = Duration::from_secs(15);
+
 /// Special rules to be applied to the schedule.
 pub type ScheduleCreationRules = dyn Fn(&mut Schedule, BlockNumber) + Sync + Send;
 
@@ -90,6 +99,8 @@ pub struct EthereumMachine {
     tx_filter: Option<Arc<TransactionFilter>>,
     ethash_extensions: Option<EthashExtensions>,
     schedule_rules: Option<Box<ScheduleCreationRules>>,
+    clock: SharedClock,
+    max_future_drift_ms: AtomicU64,
 }
 
 impl EthereumMachine {
@@ -102,6 +113,8 @@ impl EthereumMachine {
             tx_filter: tx_filter,
             ethash_extensions: None,
             schedule_rules: None,
+            clock: SharedClock::default(),
+            max_future_drift_ms: AtomicU64::new(DEFAULT_MAX_FUTURE_DRIFT.as_millis() as u64),
         }
     }
 
@@ -126,6 +139,22 @@ impl EthereumMachine {
     pub fn ethash_extensions(&self) -> Option<&EthashExtensions> {
         self.ethash_extensions.as_ref()
     }
+
+    /// Point the machine (and every engine component sharing its clock) at the given clock.
+    pub fn set_clock(&self, clock: Arc<dyn Clock>) {
+        self.clock.set(clock);
+    }
+
+    /// Maximum amount of time a block timestamp may be ahead of the local clock.
+    pub fn max_future_drift(&self) -> Duration {
+        Duration::from_millis(self.max_future_drift_ms.load(AtomicOrdering::Relaxed))
+    }
+
+    /// Set the maximum amount of time a block timestamp may be ahead of the local clock.
+    pub fn set_max_future_drift(&self, drift: Duration) {
+        self.max_future_drift_ms
+            .store(drift.as_millis() as u64, AtomicOrdering::Relaxed);
+    }
 }
 
 impl EthereumMachine {
@@ -201,7 +230,7 @@ impl EthereumMachine {
             params_type: ParamsType::Separate,
             access_list: AccessList::default(),
         };
-        let schedule = self.schedule(env_info.number);
+        let schedule = self.schedule(env_info.number, env_info.timestamp);
         let mut ex = Executive::new(&mut state, &env_info, self, &schedule);
         let mut substate = Substate::new();
 
@@ -255,6 +284,27 @@ impl EthereumMachine {
         Ok(())
     }
 
+    /// t_nb 8.5.1 Logic to perform on closing a block: applying the spec's block reward schedule,
+    /// which replaces the engine's rewards and pays the executive author, and crediting EIP-4895 withdrawals to their
+    /// recipients. Withdrawals are plain balance increases and do not consume gas.
+    pub fn on_close_block(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
+        if let Some(ref schedule) = self.params.block_reward_schedule {
+            let rewards =
+                schedule.rewards(block.executive_author, block.header.number(), &block.uncles);
+            block_reward::apply_block_rewards(&rewards, block, self)?;
+        }
+        if let Some(ref withdrawals) = block.withdrawals {
+            for withdrawal in withdrawals {
+                block.state.add_balance(
+                    &withdrawal.address,
+                    &withdrawal.amount_wei(),
+                    CleanupMode::NoEmpty,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     /// Populate a header's fields based on its parent's header.
     /// Usually implements the chain scoring rule based on weight.
     /// The gas floor target must not be lower than the engine's minimum gas limit.
@@ -266,10 +316,11 @@ impl EthereumMachine {
         gas_ceil_target: U256,
     ) {
         header.set_difficulty(parent.difficulty().clone());
-        let gas_limit = parent.gas_limit() * self.schedule(header.number()).eip1559_gas_limit_bump;
+        let schedule = self.schedule(header.number(), header.timestamp());
+        let gas_limit = parent.gas_limit() * schedule.eip1559_gas_limit_bump;
         assert!(!gas_limit.is_zero(), "Gas limit should be > 0");
 
-        let gas_limit_target = if self.schedule(header.number()).eip1559 {
+        let gas_limit_target = if schedule.eip1559 {
             gas_ceil_target
         } else {
             gas_floor_target
@@ -298,15 +349,15 @@ impl EthereumMachine {
         &self.params
     }
 
-    /// Get the EVM schedule for the given block number.
-    pub fn schedule(&self, block_number: BlockNumber) -> Schedule {
+    /// Get the EVM schedule for the block with given number and timestamp.
+    pub fn schedule(&self, block_number: BlockNumber, timestamp: u64) -> Schedule {
         let mut schedule = match self.ethash_extensions {
-            None => self.params.schedule(block_number),
+            None => self.params.schedule(block_number, timestamp),
             Some(ref ext) => {
                 if block_number < ext.homestead_transition {
                     Schedule::new_frontier()
                 } else {
-                    self.params.schedule(block_number)
+                    self.params.schedule(block_number, timestamp)
                 }
             }
         };
@@ -409,6 +460,32 @@ impl EthereumMachine {
         Ok(())
     }
 
+    /// Whether the transaction is a system transaction, i.e. sent by the configured system
+    /// transaction sender.
+    pub fn is_system_transaction(&self, t: &SignedTransaction) -> bool {
+        self.params()
+            .system_transaction_sender
+            .map_or(false, |sender| t.sender() == sender)
+    }
+
+    /// Verify that a system transaction calls one of the allowed target contracts.
+    /// Other transactions are always valid.
+    pub fn verify_system_transaction(
+        &self,
+        t: &SignedTransaction,
+    ) -> Result<(), transaction::Error> {
+        if !self.is_system_transaction(t) {
+            return Ok(());
+        }
+        match t.tx().action {
+            Action::Call(ref to) if self.params().system_transaction_targets.contains(to) => Ok(()),
+            _ => {
+                debug!(target: "machine", "Rejected system transaction {:?} to a contract not in the allowlist", t.hash());
+                Err(transaction::Error::NotAllowed)
+            }
+        }
+    }
+
     /// Does verification of the transaction against the parent state.
     pub fn verify_transaction<C: BlockInfo + CallContract>(
         &self,
@@ -416,6 +493,8 @@ impl EthereumMachine {
         parent: &Header,
         client: &C,
     ) -> Result<(), transaction::Error> {
+        self.verify_system_transaction(t)?;
+
         if let Some(ref filter) = self.tx_filter.as_ref() {
             if !filter.transaction_allowed(&parent.hash(), parent.number() + 1, t, client) {
                 return Err(transaction::Error::NotAllowed.into());
@@ -559,6 +638,10 @@ impl super::Machine for EthereumMachine {
 
     type Error = Error;
 
+    fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
     fn balance(&self, live: &ExecutedBlock, address: &Address) -> Result<U256, Error> {
         live.state.balance(address).map_err(Into::into)
     }
@@ -573,6 +656,13 @@ impl super::Machine for EthereumMachine {
             .add_balance(address, amount, CleanupMode::NoEmpty)
             .map_err(Into::into)
     }
+
+    fn has_reward_schedule(&self, number: BlockNumber) -> bool {
+        self.params
+            .block_reward_schedule
+            .as_ref()
+            .map_or(false, |schedule| schedule.era(number).is_some())
+    }
 }
 
 #[cfg(test)]
@@ -617,6 +707,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_verify_system_transactions() {
+        let sender = Address::from_low_u64_be(1);
+        let target = Address::from_low_u64_be(2);
+        let mut params = ::ethereum::new_ropsten_test().params().clone();
+        params.system_transaction_sender = Some(sender);
+        params.system_transaction_targets = vec![target];
+        let machine = EthereumMachine::regular(params, Default::default());
+
+        let call = |from: Address, to: Address| {
+            TypedTransaction::Legacy(transaction::Transaction {
+                action: Action::Call(to),
+                ..Default::default()
+            })
+            .fake_sign(from)
+        };
+
+        assert!(machine.is_system_transaction(&call(sender, target)));
+        assert!(!machine.is_system_transaction(&call(target, target)));
+        assert_eq!(
+            machine.verify_system_transaction(&call(sender, target)),
+            Ok(())
+        );
+        assert_eq!(
+            machine.verify_system_transaction(&call(sender, sender)),
+            Err(transaction::Error::NotAllowed)
+        );
+        // Regular transactions are not restricted.
+        assert_eq!(
+            machine.verify_system_transaction(&call(target, sender)),
+            Ok(())
+        );
+    }
+
     #[test]
     fn calculate_base_fee_success() {
         let machine = new_london_test_machine();
@@ -19,6 +19,8 @@
 
 use block::ExecutedBlock;
 use ethereum_types::{Address, U256};
+use time_utils::SharedClock;
+use types::BlockNumber;
 
 /// Generalization of types surrounding blockchain-suitable state machines.
 pub trait Machine: Send + Sync {
@@ -28,6 +30,9 @@ pub trait Machine: Send + Sync {
     /// Errors which can occur when querying or interacting with the machine.
     type Error;
 
+    /// Wall-clock used for all timestamp dependent decisions.
+    fn clock(&self) -> &SharedClock;
+
     /// Get the balance, in base units, associated with an account.
     /// Extracts data from the live block.
     fn balance(&self, live: &ExecutedBlock, address: &Address) -> Result<U256, Self::Error>;
@@ -39,4 +44,10 @@ pub trait Machine: Send + Sync {
         address: &Address,
         amount: &U256,
     ) -> Result<(), Self::Error>;
+
+    /// Whether the rewards of the given block are paid by the machine's reward schedule, in
+    /// which case the engine must not apply its own block rewards.
+    fn has_reward_schedule(&self, _number: BlockNumber) -> bool {
+        false
+    }
 }
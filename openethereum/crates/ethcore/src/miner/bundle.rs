@@ -0,0 +1,205 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction bundles.
+//!
+//! A bundle is an ordered group of transactions which is included contiguously
+//! at the top of a given block, or not at all.
+
+use std::fmt;
+
+use ethereum_types::H256;
+use hash::keccak;
+use rlp::RlpStream;
+use types::{transaction::SignedTransaction, BlockNumber};
+
+/// Maximal number of transactions in a single bundle.
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 64;
+
+/// Maximal number of bundles kept by the miner.
+pub const MAX_BUNDLES: usize = 256;
+
+/// Maximal distance of the target block from the best block.
+pub const MAX_BUNDLE_BLOCK_DISTANCE: BlockNumber = 25;
+
+/// An ordered group of transactions targeting a single block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bundle {
+    /// Transactions, in the order they are included.
+    pub transactions: Vec<SignedTransaction>,
+    /// Number of the block the bundle has to be included in.
+    pub block_number: BlockNumber,
+}
+
+impl Bundle {
+    /// Hash identifying the bundle: keccak of the target block and the transaction hashes.
+    pub fn hash(&self) -> H256 {
+        let mut stream = RlpStream::new_list(self.transactions.len() + 1);
+        stream.append(&self.block_number);
+        for tx in &self.transactions {
+            stream.append(&tx.hash());
+        }
+        keccak(stream.out())
+    }
+
+    /// Checks that the bundle can still be included on top of `best_block`.
+    pub fn verify(&self, best_block: BlockNumber) -> Result<(), BundleError> {
+        if self.transactions.is_empty() {
+            return Err(BundleError::Empty);
+        }
+        if self.transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+            return Err(BundleError::TooManyTransactions(self.transactions.len()));
+        }
+        if self.block_number <= best_block {
+            return Err(BundleError::StaleBlock {
+                best: best_block,
+                target: self.block_number,
+            });
+        }
+        if self.block_number - best_block > MAX_BUNDLE_BLOCK_DISTANCE {
+            return Err(BundleError::FutureBlock {
+                best: best_block,
+                target: self.block_number,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Errors concerning bundle submission.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleError {
+    /// Bundle has no transactions.
+    Empty,
+    /// Bundle has more than `MAX_BUNDLE_TRANSACTIONS` transactions.
+    TooManyTransactions(usize),
+    /// Target block is already part of the chain.
+    StaleBlock {
+        /// Current best block
+        best: BlockNumber,
+        /// Target block of the bundle
+        target: BlockNumber,
+    },
+    /// Target block is more than `MAX_BUNDLE_BLOCK_DISTANCE` blocks ahead of the chain.
+    FutureBlock {
+        /// Current best block
+        best: BlockNumber,
+        /// Target block of the bundle
+        target: BlockNumber,
+    },
+    /// Miner already holds `MAX_BUNDLES` bundles.
+    TooManyBundles,
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BundleError::Empty => write!(f, "Bundle has no transactions"),
+            BundleError::TooManyTransactions(count) => write!(
+                f,
+                "Bundle has {} transactions, at most {} are allowed",
+                count, MAX_BUNDLE_TRANSACTIONS
+            ),
+            BundleError::StaleBlock { best, target } => write!(
+                f,
+                "Bundle targets block {} but the best block is already {}",
+                target, best
+            ),
+            BundleError::FutureBlock { best, target } => write!(
+                f,
+                "Bundle targets block {} but the best block is only {}, at most {} blocks ahead are allowed",
+                target, best, MAX_BUNDLE_BLOCK_DISTANCE
+            ),
+            BundleError::TooManyBundles => write!(f, "Too many bundles are pending"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::publickey::{Generator, Random};
+    use types::transaction::{Action, Transaction, TypedTransaction};
+
+    fn signed(nonce: u64) -> SignedTransaction {
+        let keypair = Random.generate();
+        TypedTransaction::Legacy(Transaction {
+            action: Action::Create,
+            value: 0.into(),
+            data: vec![],
+            gas: 21_000.into(),
+            gas_price: 1.into(),
+            nonce: nonce.into(),
+        })
+        .sign(keypair.secret(), None)
+    }
+
+    #[test]
+    fn should_verify_bundle() {
+        let bundle = Bundle {
+            transactions: vec![signed(0), signed(1)],
+            block_number: 10,
+        };
+        assert_eq!(bundle.verify(9), Ok(()));
+        assert_eq!(
+            bundle.verify(10),
+            Err(BundleError::StaleBlock {
+                best: 10,
+                target: 10
+            })
+        );
+
+        let far = Bundle {
+            transactions: vec![signed(0)],
+            block_number: MAX_BUNDLE_BLOCK_DISTANCE + 10,
+        };
+        assert_eq!(far.verify(10), Ok(()));
+        assert_eq!(
+            far.verify(9),
+            Err(BundleError::FutureBlock {
+                best: 9,
+                target: MAX_BUNDLE_BLOCK_DISTANCE + 10
+            })
+        );
+
+        let empty = Bundle {
+            transactions: vec![],
+            block_number: 10,
+        };
+        assert_eq!(empty.verify(9), Err(BundleError::Empty));
+    }
+
+    #[test]
+    fn should_hash_target_block_and_transactions() {
+        let transactions = vec![signed(0), signed(1)];
+        let bundle = Bundle {
+            transactions: transactions.clone(),
+            block_number: 10,
+        };
+        let other_block = Bundle {
+            transactions: transactions.clone(),
+            block_number: 11,
+        };
+        let reordered = Bundle {
+            transactions: transactions.into_iter().rev().collect(),
+            block_number: 10,
+        };
+
+        assert_eq!(bundle.hash(), bundle.clone().hash());
+        assert!(bundle.hash() != other_block.hash());
+        assert!(bundle.hash() != reordered.hash());
+    }
+}
@@ -0,0 +1,300 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fee history oracle.
+//!
+//! Records base fee, gas usage and priority fees of recently imported blocks,
+//! which are used for `eth_feeHistory` and for gas price suggestions.
+
+use std::collections::VecDeque;
+
+use ethereum_types::{H256, U256};
+use parking_lot::RwLock;
+use types::{
+    header::Header, receipt::TypedReceipt, transaction::UnverifiedTransaction, BlockNumber,
+};
+
+/// Number of recent blocks kept by the oracle.
+pub const FEE_HISTORY_BLOCKS: usize = 1024;
+
+/// Number of recent blocks considered for suggestions.
+const SUGGESTION_BLOCKS: usize = 20;
+
+/// Percentile of the priority fees of each block considered for suggestions.
+const SUGGESTION_PERCENTILE: f64 = 60.0;
+
+/// Fees paid in a single block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockFees {
+    /// Number of the block.
+    pub number: BlockNumber,
+    /// Hash of the block.
+    pub hash: H256,
+    /// Base fee of the block, zero before EIP-1559.
+    pub base_fee: U256,
+    /// Gas used by the block.
+    pub gas_used: U256,
+    /// Gas limit of the block.
+    pub gas_limit: U256,
+    /// Gas used and priority fee of each transaction, sorted by priority fee.
+    rewards: Vec<(U256, U256)>,
+}
+
+impl BlockFees {
+    /// Collects fees of a block from its transactions and receipts.
+    ///
+    /// Priority fees are skipped if transactions and receipts don't match.
+    pub fn new(
+        header: &Header,
+        transactions: &[UnverifiedTransaction],
+        receipts: &[TypedReceipt],
+    ) -> Self {
+        let base_fee = header.base_fee();
+        let mut rewards = Vec::with_capacity(transactions.len());
+        if transactions.len() == receipts.len() {
+            let mut cumulative_gas_used = U256::zero();
+            for (tx, receipt) in transactions.iter().zip(receipts) {
+                let gas_used = receipt.gas_used.saturating_sub(cumulative_gas_used);
+                cumulative_gas_used = receipt.gas_used;
+                rewards.push((
+                    gas_used,
+                    tx.effective_gas_price(base_fee)
+                        .saturating_sub(base_fee.unwrap_or_default()),
+                ));
+            }
+        }
+        rewards.sort_by(|a, b| a.1.cmp(&b.1));
+
+        BlockFees {
+            number: header.number(),
+            hash: header.hash(),
+            base_fee: base_fee.unwrap_or_default(),
+            gas_used: *header.gas_used(),
+            gas_limit: *header.gas_limit(),
+            rewards,
+        }
+    }
+
+    /// Ratio of gas used to the gas limit.
+    pub fn gas_used_ratio(&self) -> f64 {
+        if self.gas_limit.is_zero() {
+            return 0.0;
+        }
+        (self.gas_used.as_u64() as f64) / (self.gas_limit.as_u64() as f64)
+    }
+
+    /// Priority fee paid at the given percentile of gas used in the block.
+    pub fn reward(&self, percentile: f64) -> U256 {
+        let target_gas = U256::from(((self.gas_used.as_u64() as f64) * percentile / 100.0) as u64);
+        let mut sum_gas = U256::zero();
+        for &(gas_used, reward) in &self.rewards {
+            sum_gas += gas_used;
+            if target_gas <= sum_gas {
+                return reward;
+            }
+        }
+        U256::zero()
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    blocks: VecDeque<BlockFees>,
+    next_base_fee: U256,
+}
+
+/// Fees of the most recent canonical blocks.
+pub struct FeeHistory {
+    inner: RwLock<Inner>,
+    capacity: usize,
+}
+
+impl Default for FeeHistory {
+    fn default() -> Self {
+        FeeHistory::new(FEE_HISTORY_BLOCKS)
+    }
+}
+
+impl FeeHistory {
+    /// Creates an oracle keeping at most `capacity` blocks.
+    pub fn new(capacity: usize) -> Self {
+        FeeHistory {
+            inner: RwLock::new(Inner::default()),
+            capacity,
+        }
+    }
+
+    /// Records fees of a new canonical block.
+    ///
+    /// Blocks at or above its number are replaced, and the history is reset
+    /// if the block doesn't follow the most recent one.
+    pub fn record(&self, fees: BlockFees) {
+        let mut inner = self.inner.write();
+        while inner
+            .blocks
+            .back()
+            .map_or(false, |last| last.number >= fees.number)
+        {
+            inner.blocks.pop_back();
+        }
+        if inner
+            .blocks
+            .back()
+            .map_or(false, |last| last.number + 1 != fees.number)
+        {
+            inner.blocks.clear();
+        }
+        inner.blocks.push_back(fees);
+        while inner.blocks.len() > self.capacity {
+            inner.blocks.pop_front();
+        }
+    }
+
+    /// Sets the base fee of the next block.
+    pub fn set_next_base_fee(&self, base_fee: U256) {
+        self.inner.write().next_base_fee = base_fee;
+    }
+
+    /// Returns recorded fees of the given block.
+    pub fn block(&self, number: BlockNumber) -> Option<BlockFees> {
+        let inner = self.inner.read();
+        let first = inner.blocks.front()?.number;
+        if number < first {
+            return None;
+        }
+        inner.blocks.get((number - first) as usize).cloned()
+    }
+
+    /// Suggested priority fee: median of the priority fees recent blocks paid
+    /// at `SUGGESTION_PERCENTILE`. Empty blocks are skipped.
+    pub fn suggested_priority_fee(&self) -> Option<U256> {
+        let inner = self.inner.read();
+        let mut rewards: Vec<U256> = inner
+            .blocks
+            .iter()
+            .rev()
+            .filter(|fees| !fees.rewards.is_empty())
+            .take(SUGGESTION_BLOCKS)
+            .map(|fees| fees.reward(SUGGESTION_PERCENTILE))
+            .collect();
+        if rewards.is_empty() {
+            return None;
+        }
+        rewards.sort();
+        Some(rewards[rewards.len() / 2])
+    }
+
+    /// Suggested gas price: base fee of the next block and the suggested priority fee.
+    pub fn suggested_gas_price(&self) -> Option<U256> {
+        let priority_fee = self.suggested_priority_fee()?;
+        Some(self.inner.read().next_base_fee.saturating_add(priority_fee))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::Address;
+    use types::{
+        receipt::{LegacyReceipt, TransactionOutcome},
+        transaction::{Action, Transaction, TypedTransaction},
+    };
+
+    fn block(number: BlockNumber, gas_prices: &[u64]) -> BlockFees {
+        let mut header = Header::new();
+        header.set_number(number);
+        header.set_gas_limit((21_000 * 10).into());
+        header.set_gas_used((21_000 * gas_prices.len() as u64).into());
+
+        let transactions: Vec<_> = gas_prices
+            .iter()
+            .map(|gas_price| {
+                TypedTransaction::Legacy(Transaction {
+                    action: Action::Create,
+                    value: 0.into(),
+                    data: vec![],
+                    gas: 21_000.into(),
+                    gas_price: (*gas_price).into(),
+                    nonce: 0.into(),
+                })
+                .fake_sign(Address::from_low_u64_be(1))
+                .into()
+            })
+            .collect();
+        let receipts: Vec<_> = (1..=gas_prices.len())
+            .map(|i| {
+                TypedReceipt::Legacy(LegacyReceipt::new(
+                    TransactionOutcome::Unknown,
+                    (21_000 * i as u64).into(),
+                    vec![],
+                ))
+            })
+            .collect();
+
+        BlockFees::new(&header, &transactions, &receipts)
+    }
+
+    #[test]
+    fn should_compute_block_rewards_at_percentiles() {
+        let fees = block(1, &[30, 10, 20, 40]);
+
+        assert_eq!(fees.gas_used_ratio(), 0.4);
+        assert_eq!(fees.reward(0.0), 10.into());
+        assert_eq!(fees.reward(50.0), 20.into());
+        assert_eq!(fees.reward(100.0), 40.into());
+    }
+
+    #[test]
+    fn should_keep_recent_canonical_blocks() {
+        let history = FeeHistory::new(2);
+        history.record(block(1, &[10]));
+        history.record(block(2, &[20]));
+        history.record(block(3, &[30]));
+
+        assert_eq!(history.block(1), None);
+        assert_eq!(
+            history.block(3).map(|fees| fees.reward(50.0)),
+            Some(30.into())
+        );
+
+        // reorg replaces the block at the same height
+        history.record(block(3, &[50]));
+        assert_eq!(
+            history.block(3).map(|fees| fees.reward(50.0)),
+            Some(50.into())
+        );
+        assert!(history.block(2).is_some());
+
+        // gap resets the history
+        history.record(block(5, &[10]));
+        assert_eq!(history.block(3), None);
+    }
+
+    #[test]
+    fn should_suggest_median_of_recent_blocks() {
+        let history = FeeHistory::default();
+        assert_eq!(history.suggested_gas_price(), None);
+
+        history.record(block(1, &[10]));
+        history.record(block(2, &[]));
+        history.record(block(3, &[30]));
+        history.record(block(4, &[20]));
+        history.set_next_base_fee(7.into());
+
+        assert_eq!(history.suggested_priority_fee(), Some(20.into()));
+        assert_eq!(history.suggested_gas_price(), Some(27.into()));
+    }
+}
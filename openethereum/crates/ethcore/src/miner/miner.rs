@@ -17,6 +17,7 @@
 use std::{
     cmp,
     collections::{BTreeMap, BTreeSet, HashSet},
+    mem,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -27,12 +28,13 @@ use call_contract::CallContract;
 #[cfg(feature = "work-notify")]
 use ethcore_miner::work_notify::NotifyWork;
 use ethcore_miner::{
+    block_builder::{BlockBuilder, PayloadAttributes},
     gas_pricer::GasPricer,
     local_accounts::LocalAccounts,
     pool::{
-        self,
+        self, alt_mempool,
         transaction_filter::{match_filter, TransactionFilter},
-        PrioritizationStrategy, QueueStatus, TransactionQueue, VerifiedTransaction,
+        PrioritizationStrategy, QueueStatus, TransactionQueue, VerifiedTransaction, WebhookSink,
     },
     service_transaction_checker::ServiceTransactionChecker,
 };
@@ -40,33 +42,38 @@ use ethereum_types::{Address, H256, U256};
 use io::IoChannel;
 use miner::{
     self,
+    bundle::{self, Bundle, BundleError},
     cache::Cache,
+    fee_history::{BlockFees, FeeHistory},
     pool_client::{CachedNonceClient, PoolClient},
+    reseal_throttle::ResealThrottle,
     MinerService,
 };
 use parking_lot::{Mutex, RwLock};
 use rayon::prelude::*;
+use time_utils::Clock;
 use types::{
     block::Block,
     header::Header,
-    receipt::RichReceipt,
+    receipt::{RichReceipt, TypedReceipt},
     transaction::{self, Action, PendingTransaction, SignedTransaction, UnverifiedTransaction},
     BlockNumber,
 };
 use using_queue::{GetAction, UsingQueue};
 
-use block::{ClosedBlock, SealedBlock};
+use block::{ClosedBlock, Drain, SealedBlock};
 use client::{
     traits::{EngineClient, ForceUpdateSealing},
     BlockChain, BlockId, BlockProducer, ChainInfo, ClientIoMessage, Nonce, SealedBlockImporter,
     TransactionId, TransactionInfo,
 };
-use engines::{EngineSigner, EthEngine, Seal, SealingState};
+use engines::{EngineSigner, EthEngine, PendingSeal, Seal, SealingState};
 use error::{Error, ErrorKind};
 use executed::ExecutionError;
 use executive::contract_address;
 use spec::Spec;
 use state::State;
+use stats::{PrometheusMetrics, PrometheusRegistry};
 
 /// Different possible definitions for pending transaction set.
 #[derive(Debug, PartialEq)]
@@ -123,6 +130,10 @@ const DEFAULT_MINIMAL_GAS_PRICE: u64 = 20_000_000_000;
 /// in case we have only a fraction of available block gas limit left.
 const MAX_SKIPPED_TRANSACTIONS: usize = 128;
 
+/// Maximal number of transactions taken from the queue or the block builder for a single block.
+/// Bounds the work done before the building deadline is checked, even for huge gas limits.
+const MAX_BLOCK_TRANSACTIONS: usize = 16_384;
+
 /// Configures the behaviour of the miner.
 #[derive(Debug, PartialEq)]
 pub struct MinerOptions {
@@ -138,6 +149,9 @@ pub struct MinerOptions {
     pub reseal_min_period: Duration,
     /// Maximum period between blocks (enables force sealing after that).
     pub reseal_max_period: Duration,
+    /// Upper bound of the period between transaction-inspired reseals, which is widened
+    /// from `reseal_min_period` under heavy churn. `None` keeps it at `reseal_min_period`.
+    pub reseal_throttle_max_period: Option<Duration>,
     /// Whether we should fallback to providing all the queue's transactions or just pending.
     pub pending_set: PendingSet,
     /// How many historical work packages can we store before running out?
@@ -148,6 +162,9 @@ pub struct MinerOptions {
     /// NOTE: Such block will contain all pending transactions but
     /// will be invalid if mined.
     pub infinite_pending_block: bool,
+    /// Maximal time spent adding transactions to a new block. Building also stops
+    /// at the sealing deadline of the engine.
+    pub block_building_budget: Option<Duration>,
 
     /// Strategy to use for prioritizing transactions in the queue.
     pub tx_queue_strategy: PrioritizationStrategy,
@@ -155,6 +172,15 @@ pub struct MinerOptions {
     pub tx_queue_penalization: Penalization,
     /// Do we want to mark transactions recieved locally (e.g. RPC) as local if we don't have the sending account?
     pub tx_queue_no_unfamiliar_locals: bool,
+    /// Maximal age of non-local transactions in the queue, older ones are dropped on cull.
+    pub tx_queue_max_age: Option<Duration>,
+    /// Limits of the alt-mempool lane for EIP-4337 bundle transactions.
+    pub tx_queue_alt_mempool: alt_mempool::Options,
+    /// Separate limits of non-local transactions behind a nonce gap.
+    pub tx_queue_future_limits: Option<pool::FutureLimits>,
+    /// How far back the time checked by timestamp conditions may step
+    /// without making ready transactions future again.
+    pub tx_queue_timestamp_tolerance: Duration,
     /// Do we refuse to accept service transactions even if sender is certified.
     pub refuse_service_transactions: bool,
     /// Transaction pool limits.
@@ -172,13 +198,19 @@ impl Default for MinerOptions {
             reseal_on_uncle: false,
             reseal_min_period: Duration::from_secs(2),
             reseal_max_period: Duration::from_secs(120),
+            reseal_throttle_max_period: None,
             pending_set: PendingSet::AlwaysQueue,
             work_queue_size: 20,
             enable_resubmission: true,
             infinite_pending_block: false,
+            block_building_budget: None,
             tx_queue_strategy: PrioritizationStrategy::GasPriceOnly,
             tx_queue_penalization: Penalization::Disabled,
             tx_queue_no_unfamiliar_locals: false,
+            tx_queue_max_age: None,
+            tx_queue_alt_mempool: Default::default(),
+            tx_queue_future_limits: None,
+            tx_queue_timestamp_tolerance: Duration::from_secs(0),
             refuse_service_transactions: false,
             pool_limits: pool::Options {
                 max_count: 8_192,
@@ -192,22 +224,43 @@ impl Default for MinerOptions {
                 tx_gas_limit: U256::max_value(),
                 no_early_reject: false,
                 allow_non_eoa_sender: false,
+                max_gas_per_sender: None,
+                replacement_bump: Default::default(),
+                prioritized_senders: Default::default(),
+                sender_premium: None,
             },
         }
     }
 }
 
 /// Configurable parameters of block authoring.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct AuthoringParams {
     /// Lower and upper bound of block gas limit that we are targeting
     pub gas_range_target: (U256, U256),
     /// Block author
     pub author: Address,
+    /// Recipient of block rewards and fees, if different from the block author.
+    pub fee_recipient: Option<Address>,
     /// Block extra data
     pub extra_data: Bytes,
 }
 
+/// Outcome of executing a candidate block that is neither sealed nor imported.
+#[derive(Debug, Clone)]
+pub struct ProposalPreview {
+    /// Header of the candidate block, without seal.
+    pub header: Header,
+    /// Gas used by the candidate block.
+    pub gas_used: U256,
+    /// State root after executing the candidate block.
+    pub state_root: H256,
+    /// Receipts of the transactions included in the candidate block.
+    pub receipts: Vec<TypedReceipt>,
+    /// Transactions that could not be included, with the reason.
+    pub rejected: Vec<(H256, String)>,
+}
+
 /// Block sealing mechanism
 pub enum Author {
     /// Sealing block is external and we only need a reward beneficiary (i.e. PoW)
@@ -226,13 +279,31 @@ impl Author {
     }
 }
 
+/// Engine signer waiting to take over block authoring.
+struct SignerRotation {
+    signer: Box<dyn EngineSigner>,
+    /// First block to be sealed by the new signer.
+    handover_block: BlockNumber,
+    /// Whether the engine already accepts the new signer as a block author.
+    ready: bool,
+}
+
 struct SealingWork {
     queue: UsingQueue<ClosedBlock>,
     enabled: bool,
     next_allowed_reseal: Instant,
     next_mandatory_reseal: Instant,
+    throttle: ResealThrottle,
     // block number when sealing work was last requested
     last_request: Option<u64>,
+    // authoring params the pending blocks in the queue were built with
+    block_params: Option<AuthoringParams>,
+    // block waiting for an asynchronously generated seal
+    pending_seal: Option<(ClosedBlock, PendingSeal)>,
+    // pending blocks authored from scratch
+    rebuilt_blocks: u64,
+    // pending blocks extended with new transactions
+    extended_blocks: u64,
 }
 
 impl SealingWork {
@@ -248,6 +319,8 @@ pub struct Miner {
     // NOTE [ToDr]  When locking always lock in this order!
     sealing: Mutex<SealingWork>,
     params: RwLock<AuthoringParams>,
+    signer_rotation: Mutex<Option<SignerRotation>>,
+    sealing_blocked: RwLock<Option<String>>,
     #[cfg(feature = "work-notify")]
     listeners: RwLock<Vec<Box<dyn NotifyWork>>>,
     nonce_cache: Cache<Address, U256>,
@@ -256,10 +329,15 @@ pub struct Miner {
     options: MinerOptions,
     // TODO [ToDr] Arc is only required because of price updater
     transaction_queue: Arc<TransactionQueue>,
+    // senders of the transactions that entered the queue since the pending block was prepared
+    new_senders: Arc<Mutex<BTreeSet<Address>>>,
     engine: Arc<dyn EthEngine>,
     accounts: Arc<dyn LocalAccounts>,
     io_channel: RwLock<Option<IoChannel<ClientIoMessage>>>,
     service_transaction_checker: Option<ServiceTransactionChecker>,
+    bundles: RwLock<Vec<Bundle>>,
+    block_builder: RwLock<Option<Box<dyn BlockBuilder>>>,
+    fee_history: FeeHistory,
 }
 
 impl Miner {
@@ -270,11 +348,48 @@ impl Miner {
         self.sealing.lock().enabled = true;
     }
 
+    /// Delegate selection of transactions for new blocks to an external builder.
+    /// The local pool is still used whenever the builder returns no payload.
+    pub fn set_block_builder(&self, builder: Box<dyn BlockBuilder>) {
+        *self.block_builder.write() = Some(builder);
+    }
+
+    /// Refuse to seal new blocks for the given reason, or allow sealing again with `None`.
+    pub fn set_sealing_blocked(&self, reason: Option<String>) {
+        let mut blocked = self.sealing_blocked.write();
+        if *blocked != reason {
+            match reason {
+                Some(ref reason) => warn!(target: "miner", "Sealing is blocked: {}", reason),
+                None => info!(target: "miner", "Sealing is no longer blocked"),
+            }
+        }
+        *blocked = reason;
+    }
+
     /// Set a callback to be notified about imported transactions' hashes.
     pub fn add_transactions_listener(&self, f: Box<dyn Fn(&[H256]) + Send + Sync>) {
         self.transaction_queue.add_listener(f);
     }
 
+    /// Set a callback to be notified about imported transactions.
+    pub fn add_full_transactions_listener(
+        &self,
+        f: Box<dyn Fn(&[Arc<VerifiedTransaction>]) + Send + Sync>,
+    ) {
+        self.transaction_queue.add_full_listener(f);
+    }
+
+    /// Add a webhook to receive lifecycle events of the queued transactions.
+    pub fn add_transaction_webhook(&self, sink: Box<dyn WebhookSink>) {
+        self.transaction_queue.add_webhook(sink);
+    }
+
+    /// Check timestamp conditions of queued transactions against `clock`
+    /// instead of the timestamp of the latest block.
+    pub fn set_transaction_clock(&self, clock: Arc<dyn Clock>) {
+        self.transaction_queue.set_readiness_clock(Some(clock));
+    }
+
     /// Creates new instance of miner Arc.
     pub fn new<A: LocalAccounts + 'static>(
         options: MinerOptions,
@@ -289,6 +404,20 @@ impl Miner {
         let balance_cache_size = cmp::max(4096, limits.max_count / 4);
         let refuse_service_transactions = options.refuse_service_transactions;
         let engine = spec.engine.clone();
+        let transaction_queue = TransactionQueue::new(limits, verifier_options, tx_queue_strategy);
+        transaction_queue.set_max_age(options.tx_queue_max_age);
+        transaction_queue.set_alt_mempool_options(options.tx_queue_alt_mempool.clone());
+        transaction_queue.set_future_limits(options.tx_queue_future_limits.clone());
+        transaction_queue.set_timestamp_tolerance(options.tx_queue_timestamp_tolerance);
+        let new_senders = Arc::new(Mutex::new(BTreeSet::new()));
+        {
+            let new_senders = new_senders.clone();
+            transaction_queue.add_full_listener(Box::new(move |transactions| {
+                new_senders
+                    .lock()
+                    .extend(transactions.iter().map(|tx| tx.signed().sender()));
+            }));
+        }
 
         Miner {
             sealing: Mutex::new(SealingWork {
@@ -297,20 +426,29 @@ impl Miner {
                     || spec.engine.sealing_state() != SealingState::External,
                 next_allowed_reseal: Instant::now(),
                 next_mandatory_reseal: Instant::now() + options.reseal_max_period,
+                throttle: ResealThrottle::new(
+                    options.reseal_min_period,
+                    options
+                        .reseal_throttle_max_period
+                        .unwrap_or(options.reseal_min_period),
+                ),
                 last_request: None,
+                block_params: None,
+                pending_seal: None,
+                rebuilt_blocks: 0,
+                extended_blocks: 0,
             }),
             params: RwLock::new(AuthoringParams::default()),
+            signer_rotation: Mutex::new(None),
+            sealing_blocked: RwLock::new(None),
             #[cfg(feature = "work-notify")]
             listeners: RwLock::new(vec![]),
             gas_pricer: Mutex::new(gas_pricer),
             nonce_cache: Cache::<Address, U256>::new("Nonce", nonce_cache_size),
             balance_cache: Cache::<Address, U256>::new("Balance", balance_cache_size),
             options,
-            transaction_queue: Arc::new(TransactionQueue::new(
-                limits,
-                verifier_options,
-                tx_queue_strategy,
-            )),
+            transaction_queue: Arc::new(transaction_queue),
+            new_senders,
             accounts: Arc::new(accounts),
             engine,
             io_channel: RwLock::new(None),
@@ -319,9 +457,23 @@ impl Miner {
             } else {
                 Some(ServiceTransactionChecker::default())
             },
+            bundles: RwLock::new(Vec::new()),
+            block_builder: RwLock::new(None),
+            fee_history: FeeHistory::default(),
         }
     }
 
+    /// Returns the bundles targeting `block_number` in submission order and drops stale ones.
+    fn bundles_for_block(&self, block_number: BlockNumber) -> Vec<Bundle> {
+        let mut bundles = self.bundles.write();
+        bundles.retain(|bundle| bundle.block_number >= block_number);
+        bundles
+            .iter()
+            .filter(|bundle| bundle.block_number == block_number)
+            .cloned()
+            .collect()
+    }
+
     /// Creates new instance of miner with given spec and accounts.
     ///
     /// NOTE This should be only used for tests.
@@ -347,6 +499,10 @@ impl Miner {
                     tx_gas_limit: U256::max_value(),
                     no_early_reject: false,
                     allow_non_eoa_sender: false,
+                    max_gas_per_sender: None,
+                    replacement_bump: Default::default(),
+                    prioritized_senders: Default::default(),
+                    sender_premium: None,
                 },
                 reseal_min_period: Duration::from_secs(0),
                 force_sealing,
@@ -381,6 +537,44 @@ impl Miner {
         self.sealing.lock().queue.reset();
     }
 
+    /// Hands block authoring over to a scheduled signer once the handover block is next.
+    ///
+    /// The switch only happens if the engine accepts the new signer as a block author,
+    /// otherwise the current signer is kept and the check is repeated with the next block.
+    fn rotate_signer_if_due(&self, best_block: BlockNumber) {
+        let mut rotation = self.signer_rotation.lock();
+        let signer = match rotation.as_mut() {
+            Some(pending) => {
+                let address = pending.signer.address();
+                if !pending.ready && self.engine.is_allowed_to_seal_as(&address) {
+                    info!(target: "miner", "Engine signer {} is ready to take over at block #{}", address, pending.handover_block);
+                    pending.ready = true;
+                }
+                if best_block + 1 < pending.handover_block {
+                    return;
+                }
+                if !pending.ready {
+                    warn!(target: "miner", "Handover block #{} reached but {} is not allowed to seal yet. Keeping the current engine signer.", pending.handover_block, address);
+                    return;
+                }
+                rotation
+                    .take()
+                    .expect("rotation is pending in this branch; qed")
+                    .signer
+            }
+            None => return,
+        };
+        drop(rotation);
+
+        info!(target: "miner", "Switching engine signer to {}", Colour::White.bold().paint(format!("{:#x}", signer.address())));
+        // --------------------------------------------------------------------------
+        // | NOTE Code below may require author and sealing locks                   |
+        // | (some `Engine`s call `EngineClient.update_sealing()`)                  |
+        // | Make sure to release the locks before calling that method.             |
+        // --------------------------------------------------------------------------
+        miner::MinerService::set_author(self, Author::Sealer(signer));
+    }
+
     /// Updates transaction queue verification limits.
     ///
     /// Limits consist of current block gas limit and minimal gas price.
@@ -413,6 +607,18 @@ impl Miner {
         self.service_transaction_checker.clone()
     }
 
+    /// Returns local transactions removed from the queue since the last call, other than
+    /// by being mined, together with the reason of their removal.
+    pub fn take_removed_local_transactions(&self) -> Vec<(H256, String)> {
+        self.transaction_queue.take_removed_local_transactions()
+    }
+
+    /// Splits the queued transactions into stalled, ready and future ones, with nonces of `chain`.
+    pub fn queue_readiness<C: Nonce + Sync>(&self, chain: &C) -> pool::ReadinessStatus {
+        self.transaction_queue
+            .readiness_status(CachedNonceClient::new(chain, &self.nonce_cache))
+    }
+
     /// Retrieves an existing pending block iff it's not older than given block number.
     ///
     /// NOTE: This will not prepare a new pending block if it's not existing.
@@ -454,33 +660,58 @@ impl Miner {
         let chain_info = chain.chain_info();
 
         // Some engines add transactions to the block for their own purposes, e.g. AuthorityRound RANDAO.
-        let (mut open_block, original_work_hash, engine_txs) = {
+        let (mut open_block, original_work_hash, engine_txs, is_new_block) = {
             let mut sealing = self.sealing.lock();
             let last_work_hash = sealing.queue.peek_last_ref().map(|pb| pb.header.hash());
             let best_hash = chain_info.best_block_hash;
 
-            // check to see if last ClosedBlock in would_seals is actually same parent block.
+            let params = self.params.read().clone();
+            let same_params = sealing.block_params.as_ref() == Some(&params);
+            // a builder payload that arrived since the last block was authored replaces it.
+            let builder_ready = self
+                .block_builder
+                .read()
+                .as_ref()
+                .map_or(false, |builder| builder.has_payload(&best_hash));
+
+            // check to see if last ClosedBlock in would_seals is actually same parent block
+            // and was built with the current authoring params.
             // if so
-            //   duplicate, re-open and push any new transactions.
-            //   if at least one was pushed successfully, close and enqueue new ClosedBlock;
-            //   otherwise, leave everything alone.
-            // otherwise, author a fresh block.
-            match sealing
-                .queue
-                .get_pending_if(|b| b.header.parent_hash() == &best_hash)
-            {
+            //   duplicate, re-open and push the pending transactions of the senders
+            //   that sent new transactions since, then close and enqueue new ClosedBlock.
+            // otherwise, author a fresh block from the whole queue.
+            match sealing.queue.get_pending_if(|b| {
+                same_params && !builder_ready && b.header.parent_hash() == &best_hash
+            }) {
                 Some(old_block) => {
                     trace!(target: "miner", "prepare_block: Already have previous work; updating and returning");
+                    sealing.extended_blocks += 1;
                     // add transactions to old_block
-                    (chain.reopen_block(old_block), last_work_hash, Vec::new())
+                    (
+                        chain.reopen_block(old_block),
+                        last_work_hash,
+                        Vec::new(),
+                        false,
+                    )
                 }
                 None => {
                     // block not found - create it.
                     trace!(target: "miner", "prepare_block: No existing work - making new block");
-                    let params = self.params.read().clone();
+                    sealing.rebuilt_blocks += 1;
+                    sealing.block_params = Some(params.clone());
+                    let author = match params.fee_recipient {
+                        Some(recipient)
+                            if self
+                                .engine
+                                .supports_fee_recipient(chain_info.best_block_number + 1) =>
+                        {
+                            recipient
+                        }
+                        _ => params.author,
+                    };
 
                     let block = match chain.prepare_open_block(
-                        params.author,
+                        author,
                         params.gas_range_target,
                         params.extra_data,
                     ) {
@@ -493,7 +724,7 @@ impl Miner {
                     };
                     // Before adding from the queue to the new block, give the engine a chance to add transactions.
                     match self.engine.generate_engine_transactions(&block) {
-                        Ok(transactions) => (block, last_work_hash, transactions),
+                        Ok(transactions) => (block, last_work_hash, transactions, true),
                         Err(err) => {
                             error!(target: "miner", "Failed to prepare engine transactions for new block: {:?}. \
 								   This is likely an error in chain specification or on-chain consensus smart \
@@ -516,10 +747,13 @@ impl Miner {
 
         let mut tx_count = 0usize;
         let mut skipped_transactions = 0usize;
+        let mut system_tx_count = 0usize;
 
         let client = self.pool_client(chain);
         let engine_params = self.engine.params();
-        let schedule = self.engine.schedule(block_number);
+        let schedule = self
+            .engine
+            .schedule(block_number, open_block.header.timestamp());
         let min_tx_gas: U256 = schedule.tx_gas.into();
         let gas_limit = open_block.header.gas_limit();
         let nonce_cap: Option<U256> = if chain_info.best_block_number + 1
@@ -531,45 +765,146 @@ impl Miner {
         };
         // we will never need more transactions than limit divided by min gas
         let max_transactions = if min_tx_gas.is_zero() {
-            usize::max_value()
+            MAX_BLOCK_TRANSACTIONS
         } else {
-            MAX_SKIPPED_TRANSACTIONS.saturating_add(
-                cmp::min(gas_limit / min_tx_gas, u64::max_value().into()).as_u64() as usize,
+            cmp::min(
+                MAX_SKIPPED_TRANSACTIONS.saturating_add(
+                    cmp::min(gas_limit / min_tx_gas, u64::max_value().into()).as_u64() as usize,
+                ),
+                MAX_BLOCK_TRANSACTIONS,
             )
         };
 
-        let queue_txs: Vec<Arc<_>> = self.transaction_queue.pending(
-            client.clone(),
-            pool::PendingSettings {
-                block_number: chain_info.best_block_number,
-                current_timestamp: chain_info.best_block_timestamp,
-                nonce_cap,
-                max_len: max_transactions.saturating_sub(engine_txs.len()),
-                ordering: miner::PendingOrdering::Priority,
-                includable_boundary: self
-                    .engine
-                    .calculate_base_fee(&chain.best_block_header())
-                    .unwrap_or_default(),
-                enforce_priority_fees: true,
-            },
-        );
+        // Senders of the transactions imported since the last block was prepared.
+        // A fresh block is built from the whole queue and includes them anyway.
+        let new_senders = mem::replace(&mut *self.new_senders.lock(), BTreeSet::new());
+
+        // A reopened block is extended from the local pool only.
+        let builder_txs = if is_new_block {
+            self.block_builder.read().as_ref().and_then(|builder| {
+                builder.payload(&PayloadAttributes {
+                    parent_hash: *open_block.header.parent_hash(),
+                    number: block_number,
+                    timestamp: open_block.header.timestamp(),
+                    gas_limit: *gas_limit,
+                    author: *open_block.header.author(),
+                })
+            })
+        } else {
+            None
+        };
+
+        let pending_settings = pool::PendingSettings {
+            block_number: chain_info.best_block_number,
+            current_timestamp: chain_info.best_block_timestamp.into(),
+            nonce_cap,
+            max_len: max_transactions.saturating_sub(engine_txs.len()),
+            ordering: miner::PendingOrdering::Priority,
+            includable_boundary: self
+                .engine
+                .calculate_base_fee(&chain.best_block_header())
+                .unwrap_or_default(),
+            enforce_priority_fees: true,
+        };
+        let queue_txs: Vec<SignedTransaction> = match builder_txs {
+            Some(mut transactions) => {
+                debug!(target: "miner", "Using {} transactions from the block builder.", transactions.len());
+                transactions.truncate(max_transactions.saturating_sub(engine_txs.len()));
+                transactions
+            }
+            None if is_new_block => self
+                .transaction_queue
+                .pending(client.clone(), pending_settings)
+                .into_iter()
+                .map(|tx| tx.signed().clone())
+                .collect(),
+            // Only the new senders can have pending transactions the reopened block is missing.
+            None => self
+                .transaction_queue
+                .pending_from_senders(client.clone(), &new_senders, pending_settings)
+                .into_iter()
+                .filter(|tx| !open_block.transactions_set.contains(&tx.signed().hash()))
+                .map(|tx| tx.signed().clone())
+                .collect(),
+        };
 
         let took_ms = |elapsed: &Duration| {
             elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000
         };
 
         let block_start = Instant::now();
+
+        // Bundles go to the top of a fresh block, a reopened one already has transactions.
+        if is_new_block {
+            for bundle in self.bundles_for_block(block_number) {
+                let hash = bundle.hash();
+                let len = bundle.transactions.len();
+                let result = bundle
+                    .transactions
+                    .iter()
+                    .map(|tx| client.verify_for_pending_block(tx, &open_block.header))
+                    .collect::<Result<(), _>>()
+                    .map_err(Error::from)
+                    .and_then(|_| open_block.push_bundle(bundle.transactions));
+                match result {
+                    Ok(()) => {
+                        debug!(target: "miner", "Included bundle {:?} with {} transactions", hash, len);
+                        tx_count += len;
+                    }
+                    Err(e) => {
+                        debug!(target: "miner", "Skipping bundle {:?}: {:?}", hash, e);
+                    }
+                }
+            }
+        }
+
         debug!(target: "miner", "Attempting to push {} transactions.", engine_txs.len() + queue_txs.len());
 
-        for transaction in engine_txs
-            .into_iter()
-            .chain(queue_txs.into_iter().map(|tx| tx.signed().clone()))
-        {
+        // Queue transactions are pushed until the deadline. Halfway there the block is closed
+        // into a valid candidate, which is kept in case the final block can't be closed.
+        let deadline = self.block_building_deadline(&open_block.header, block_start);
+        let candidate_at = deadline
+            .map(|deadline| block_start + deadline.saturating_duration_since(block_start) / 2);
+        let mut candidate: Option<ClosedBlock> = None;
+        let engine_tx_count = engine_txs.len();
+
+        for (index, transaction) in engine_txs.into_iter().chain(queue_txs).enumerate() {
             let start = Instant::now();
 
+            if index >= engine_tx_count {
+                if deadline.map_or(false, |deadline| start >= deadline) {
+                    debug!(target: "miner", "Reached block building deadline after {} transactions.", tx_count);
+                    break;
+                }
+                if candidate.is_none() && candidate_at.map_or(false, |at| start >= at) {
+                    let closed = match open_block.close() {
+                        Ok(block) => block,
+                        Err(err) => {
+                            warn!(target: "miner", "Closing the block failed with error {:?}. This is likely an error in chain specificiations or on-chain consensus smart contracts.", err);
+                            return None;
+                        }
+                    };
+                    trace!(target: "miner", "Keeping candidate block with {} transactions.", tx_count);
+                    open_block = closed.clone().reopen(&*self.engine);
+                    // External sealers can already work on the candidate; the final block
+                    // replaces it once ready.
+                    if self.engine.sealing_state() == SealingState::External {
+                        self.sealing.lock().queue.set_pending(closed.clone());
+                    }
+                    candidate = Some(closed);
+                }
+            }
+
             let hash = transaction.hash();
             let sender = transaction.sender();
 
+            // System transactions are bounded per block, the rest stay in the queue for later blocks.
+            let is_system = self.engine.machine().is_system_transaction(&transaction);
+            if is_system && system_tx_count >= engine_params.max_system_transactions_per_block {
+                debug!(target: "miner", "Skipping system transaction {:?}, block limit reached", hash);
+                continue;
+            }
+
             // Re-verify transaction again vs current state.
             let result = client
                 .verify_for_pending_block(&transaction, &open_block.header)
@@ -643,15 +978,24 @@ impl Miner {
                     invalid_transactions.insert(hash);
                 }
                 // imported ok
-                _ => tx_count += 1,
+                _ => {
+                    tx_count += 1;
+                    if is_system {
+                        system_tx_count += 1;
+                    }
+                }
             }
         }
         let elapsed = block_start.elapsed();
         debug!(target: "miner", "Pushed {} transactions in {} ms", tx_count, took_ms(&elapsed));
 
-        let block = match open_block.close() {
-            Ok(block) => block,
-            Err(err) => {
+        let block = match (open_block.close(), candidate) {
+            (Ok(block), _) => block,
+            (Err(err), Some(candidate)) => {
+                warn!(target: "miner", "Closing the block failed with error {:?}. Using the candidate block instead.", err);
+                candidate
+            }
+            (Err(err), None) => {
                 warn!(target: "miner", "Closing the block failed with error {:?}. This is likely an error in chain specificiations or on-chain consensus smart contracts.", err);
                 return None;
             }
@@ -668,6 +1012,19 @@ impl Miner {
         Some((block, original_work_hash))
     }
 
+    /// Instant at which adding transactions to a block started at `start` stops:
+    /// the earlier of the configured budget and the sealing deadline of the engine.
+    fn block_building_deadline(&self, header: &Header, start: Instant) -> Option<Instant> {
+        let budget = self
+            .options
+            .block_building_budget
+            .map(|budget| start + budget);
+        match (budget, self.engine.sealing_deadline(header)) {
+            (Some(budget), Some(sealing)) => Some(cmp::min(budget, sealing)),
+            (budget, sealing) => budget.or(sealing),
+        }
+    }
+
     /// Returns `true` if we should create pending block even if some other conditions are not met.
     ///
     /// In general we always seal iff:
@@ -731,10 +1088,14 @@ impl Miner {
             trace!(target: "miner", "Miner sleeping (current {}, last {})", best_block, sealing.last_request.unwrap_or(0));
             sealing.enabled = false;
             sealing.queue.reset();
+            if let Some((_, handle)) = sealing.pending_seal.take() {
+                handle.cancel();
+            }
             false
         } else {
             // sealing enabled and we don't want to sleep.
-            sealing.next_allowed_reseal = Instant::now() + self.options.reseal_min_period;
+            let now = Instant::now();
+            sealing.next_allowed_reseal = now + sealing.throttle.reseal(now);
             true
         }
     }
@@ -760,14 +1121,23 @@ impl Miner {
         trace!(target: "miner", "seal_block_internally: attempting internal seal.");
 
         let parent_header = match chain.block_header(BlockId::Hash(*block.header.parent_hash())) {
-            Some(h) => match h.decode(self.engine.params().eip1559_transition) {
+            Some(h) => match h.decode(self.engine.params().header_decode_params()) {
                 Ok(decoded_hdr) => decoded_hdr,
                 Err(_) => return false,
             },
             None => return false,
         };
 
-        match self.engine.generate_seal(&block, &parent_header) {
+        let seal = self.engine.generate_seal(&block, &parent_header);
+        self.import_internal_seal(chain, block, seal)
+    }
+
+    // Handles an internally generated seal depending on its type.
+    fn import_internal_seal<C>(&self, chain: &C, block: ClosedBlock, seal: Seal) -> bool
+    where
+        C: BlockChain + SealedBlockImporter,
+    {
+        match seal {
             // Save proposal for later seal submission and broadcast it.
             Seal::Proposal(seal) => {
                 trace!(target: "miner", "Received a Proposal seal.");
@@ -813,10 +1183,60 @@ impl Miner {
                         false
                     })
             }
+            // Keep the block until the engine completes the seal.
+            Seal::Pending(handle) => {
+                trace!(target: "miner", "Received a Pending seal.");
+                let mut sealing = self.sealing.lock();
+                if let Some((_, previous)) = sealing.pending_seal.replace((block, handle)) {
+                    previous.cancel();
+                }
+                false
+            }
             Seal::None => false,
         }
     }
 
+    // Imports the block of a completed asynchronous seal, cancelling it if the block is no longer
+    // on top of the best block. Returns `true` if no new block should be prepared.
+    fn poll_pending_seal<C>(&self, chain: &C) -> bool
+    where
+        C: BlockChain + SealedBlockImporter,
+    {
+        let (block, handle) = match self.sealing.lock().pending_seal.take() {
+            Some(pending) => pending,
+            None => return false,
+        };
+
+        if *block.header.parent_hash() != chain.chain_info().best_block_hash {
+            trace!(target: "miner", "poll_pending_seal: block #{} is stale, cancelling its seal", block.header.number());
+            handle.cancel();
+            return false;
+        }
+
+        match handle.take() {
+            // signing failed, start over with a new block
+            Some(Seal::None) => false,
+            Some(seal) => {
+                trace!(target: "miner", "poll_pending_seal: seal for block #{} is ready", block.header.number());
+                self.import_internal_seal(chain, block, seal);
+                true
+            }
+            None if handle.is_cancelled() => false,
+            None => {
+                self.sealing.lock().pending_seal = Some((block, handle));
+                true
+            }
+        }
+    }
+
+    /// Cancel the asynchronous seal in progress, if any.
+    fn cancel_pending_seal(&self) {
+        if let Some((block, handle)) = self.sealing.lock().pending_seal.take() {
+            trace!(target: "miner", "Cancelling pending seal of block #{}", block.header.number());
+            handle.cancel();
+        }
+    }
+
     /// Prepares work which has to be done to seal.
     fn prepare_work(&self, block: ClosedBlock, original_work_hash: Option<H256>) {
         let (work, is_new) = {
@@ -975,6 +1395,13 @@ impl miner::MinerService for Miner {
         self.params.write().extra_data = extra_data;
     }
 
+    fn set_fee_recipient(&self, recipient: Option<Address>) {
+        if recipient.is_some() && !self.engine.supports_fee_recipient(BlockNumber::max_value()) {
+            warn!(target: "miner", "Engine does not support a separate fee recipient, rewards go to the block author.");
+        }
+        self.params.write().fee_recipient = recipient;
+    }
+
     fn set_author<T: Into<Option<Author>>>(&self, author: T) {
         let author_opt = author.into();
         self.params.write().author = author_opt.as_ref().map(Author::address).unwrap_or_default();
@@ -1011,17 +1438,59 @@ impl miner::MinerService for Miner {
         }
     }
 
+    fn schedule_signer_rotation(
+        &self,
+        signer: Box<dyn EngineSigner>,
+        handover_block: BlockNumber,
+    ) -> Result<(), &str> {
+        if self.engine.sealing_state() == SealingState::External {
+            return Err("Engine does not use an engine signer.");
+        }
+
+        info!(target: "miner", "Scheduled engine signer rotation to {} at block #{}", signer.address(), handover_block);
+        *self.signer_rotation.lock() = Some(SignerRotation {
+            signer,
+            handover_block,
+            ready: false,
+        });
+        Ok(())
+    }
+
+    fn cancel_signer_rotation(&self) -> bool {
+        self.signer_rotation.lock().take().is_some()
+    }
+
+    fn signer_rotation(&self) -> Option<(Address, BlockNumber)> {
+        self.signer_rotation
+            .lock()
+            .as_ref()
+            .map(|rotation| (rotation.signer.address(), rotation.handover_block))
+    }
+
     fn sensible_gas_price(&self) -> U256 {
         // 10% above our minimum.
-        self.transaction_queue.current_worst_gas_price() * 110u32 / 100
+        let minimum = self.transaction_queue.current_worst_gas_price() * 110u32 / 100;
+        match self.fee_history.suggested_gas_price() {
+            Some(suggested) => cmp::max(suggested, minimum),
+            None => minimum,
+        }
     }
 
     fn sensible_max_priority_fee(&self) -> U256 {
         // 10% above our minimum.
-        self.transaction_queue
+        let minimum = self
+            .transaction_queue
             .current_worst_effective_priority_fee()
             * 110u32
-            / 100
+            / 100;
+        match self.fee_history.suggested_priority_fee() {
+            Some(suggested) => cmp::max(suggested, minimum),
+            None => minimum,
+        }
+    }
+
+    fn block_fees(&self, number: BlockNumber) -> Option<BlockFees> {
+        self.fee_history.block(number)
     }
 
     fn sensible_gas_limit(&self) -> U256 {
@@ -1074,16 +1543,38 @@ impl miner::MinerService for Miner {
         // | NOTE Code below requires sealing locks.                                |
         // | Make sure to release the locks before calling that method.             |
         // --------------------------------------------------------------------------
-        if !results.is_empty()
-            && self.options.reseal_on_external_tx
-            && self.sealing.lock().reseal_allowed()
-        {
+        let imported = results.iter().filter(|r| r.is_ok()).count();
+        let reseal_allowed = {
+            let mut sealing = self.sealing.lock();
+            sealing.throttle.record_imports(imported);
+            sealing.reseal_allowed()
+        };
+        if !results.is_empty() && self.options.reseal_on_external_tx && reseal_allowed {
             self.prepare_and_update_sealing(chain);
         }
 
         results
     }
 
+    fn submit_bundle<C: ChainInfo>(&self, chain: &C, bundle: Bundle) -> Result<H256, BundleError> {
+        let best_block = chain.chain_info().best_block_number;
+        bundle.verify(best_block)?;
+
+        let hash = bundle.hash();
+        let mut bundles = self.bundles.write();
+        bundles.retain(|b| b.block_number > best_block);
+        if bundles.iter().any(|b| b.hash() == hash) {
+            return Ok(hash);
+        }
+        if bundles.len() >= bundle::MAX_BUNDLES {
+            return Err(BundleError::TooManyBundles);
+        }
+
+        debug!(target: "miner", "Accepted bundle {:?} with {} transactions for block {}", hash, bundle.transactions.len(), bundle.block_number);
+        bundles.push(bundle);
+        Ok(hash)
+    }
+
     fn import_own_transaction<C: miner::BlockChainClient>(
         &self,
         chain: &C,
@@ -1104,10 +1595,15 @@ impl miner::MinerService for Miner {
         // | NOTE Code below requires sealing locks.                                |
         // | Make sure to release the locks before calling that method.             |
         // --------------------------------------------------------------------------
-        if imported.is_ok() && self.options.reseal_on_own_tx && self.sealing.lock().reseal_allowed()
-        {
+        let reseal_allowed = {
+            let mut sealing = self.sealing.lock();
+            sealing.throttle.record_imports(imported.is_ok() as usize);
+            sealing.reseal_allowed()
+        };
+        if imported.is_ok() && self.options.reseal_on_own_tx && reseal_allowed {
             self.prepare_and_update_sealing(chain);
         }
+        chain.local_transactions_updated();
 
         imported
     }
@@ -1200,7 +1696,7 @@ impl miner::MinerService for Miner {
             let client = CachedNonceClient::new(chain, &self.nonce_cache);
             let settings = pool::PendingSettings {
                 block_number: chain_info.best_block_number,
-                current_timestamp: chain_info.best_block_timestamp,
+                current_timestamp: chain_info.best_block_timestamp.into(),
                 nonce_cap,
                 max_len,
                 ordering,
@@ -1254,6 +1750,25 @@ impl miner::MinerService for Miner {
             .unwrap_or_else(|| chain.latest_nonce(address))
     }
 
+    fn sender_nonce_state<C>(&self, chain: &C, address: &Address) -> pool::SenderNonceState
+    where
+        C: Nonce + Sync,
+    {
+        self.transaction_queue
+            .sender_nonce_state(CachedNonceClient::new(chain, &self.nonce_cache), address)
+    }
+
+    fn queued_transactions_by_sender<C>(
+        &self,
+        chain: &C,
+    ) -> BTreeMap<Address, pool::SenderTransactions>
+    where
+        C: Nonce + Sync,
+    {
+        self.transaction_queue
+            .content(CachedNonceClient::new(chain, &self.nonce_cache))
+    }
+
     fn transaction(&self, hash: &H256) -> Option<Arc<VerifiedTransaction>> {
         self.transaction_queue.find(hash)
     }
@@ -1269,6 +1784,20 @@ impl miner::MinerService for Miner {
         self.transaction_queue.status()
     }
 
+    fn alt_mempool_capacity(&self) -> alt_mempool::Capacity {
+        self.transaction_queue.alt_mempool_capacity()
+    }
+
+    fn reserve_alt_mempool(
+        &self,
+        bundler: Address,
+        slots: usize,
+        ttl: Duration,
+    ) -> Result<alt_mempool::Reservation, alt_mempool::ReservationError> {
+        self.transaction_queue
+            .reserve_alt_mempool(bundler, slots, ttl)
+    }
+
     fn pending_receipts(&self, best_block: BlockNumber) -> Option<Vec<RichReceipt>> {
         self.map_existing_pending_block(
             |pending| {
@@ -1344,6 +1873,17 @@ impl miner::MinerService for Miner {
             return;
         }
 
+        if let Some(ref reason) = *self.sealing_blocked.read() {
+            trace!(target: "miner", "update_sealing: sealing is blocked: {}", reason);
+            return;
+        }
+
+        // Don't start sealing a new block while a seal for the current one is being generated.
+        if sealing_state == SealingState::Ready && self.poll_pending_seal(chain) {
+            trace!(target: "miner", "update_sealing: pending seal handled or still in progress");
+            return;
+        }
+
         // --------------------------------------------------------------------------
         // | NOTE Code below requires sealing locks.                                |
         // | Make sure to release the locks before calling that method.             |
@@ -1467,21 +2007,25 @@ impl miner::MinerService for Miner {
         }
 
         // t_nb 10.1 First update gas limit in transaction queue and minimal gas price.
-        let base_fee = self.engine.calculate_base_fee(&chain.best_block_header());
-        let gas_limit = chain.best_block_header().gas_limit()
+        let best_block_header = chain.best_block_header();
+        let base_fee = self.engine.calculate_base_fee(&best_block_header);
+        let gas_limit = best_block_header.gas_limit()
             // multiplication neccesary only if OE nodes are the only miners in network, not really essential but wont hurt
-            *  if self.engine.gas_limit_override(&chain.best_block_header()).is_none() {
+            *  if self.engine.gas_limit_override(&best_block_header).is_none() {
+            // the next block is at least one second younger than its parent
             self
                 .engine
-                .schedule(chain.best_block_header().number() + 1)
+                .schedule(best_block_header.number() + 1, best_block_header.timestamp() + 1)
                 .eip1559_gas_limit_bump
         } else {
             1
         };
         let allow_non_eoa_sender = self
             .engine
-            .allow_non_eoa_sender(chain.best_block_header().number() + 1);
+            .allow_non_eoa_sender(best_block_header.number() + 1);
         self.update_transaction_queue_limits(gas_limit, base_fee, allow_non_eoa_sender);
+        self.transaction_queue
+            .set_best_block(best_block_header.number());
 
         // t_nb 10.2 Then import all transactions from retracted blocks (retracted means from side chain).
         let client = self.pool_client(chain);
@@ -1503,11 +2047,42 @@ impl miner::MinerService for Miner {
                 });
         }
 
+        if has_new_best_block {
+            // Record fees paid in the new canonical blocks.
+            let eip1559_transition = self.engine.params().eip1559_transition;
+            for hash in enacted {
+                if let (Some(block), Some(receipts)) = (
+                    chain.block(BlockId::Hash(*hash)),
+                    chain.block_receipts(hash),
+                ) {
+                    self.fee_history.record(BlockFees::new(
+                        &block.decode_header(eip1559_transition),
+                        &block.transactions(),
+                        &receipts.receipts,
+                    ));
+                }
+            }
+            self.fee_history
+                .set_next_base_fee(base_fee.unwrap_or_default());
+
+            // A pending seal can only be for a block on top of the previous best block.
+            self.cancel_pending_seal();
+            // Switch to a scheduled signer before any work for the handover block is prepared.
+            self.rotate_signer_if_due(chain.best_block_header().number());
+        }
+
         if has_new_best_block || (imported.len() > 0 && self.options.reseal_on_uncle) {
             // t_nb 10.3 Reset `next_allowed_reseal` in case a block is imported.
             // Even if min_period is high, we will always attempt to create
             // new pending block.
-            self.sealing.lock().next_allowed_reseal = Instant::now();
+            {
+                let mut sealing = self.sealing.lock();
+                let now = Instant::now();
+                if has_new_best_block {
+                    sealing.throttle.record_new_block(now);
+                }
+                sealing.next_allowed_reseal = now;
+            }
 
             if !is_internal_import {
                 // t_nb 10.4 if it is internal import update sealing
@@ -1567,8 +2142,19 @@ impl miner::MinerService for Miner {
             }
         }
         // t_nb 10.6 For service transaction checker update addresses to latest block
-        if let Some(ref service_transaction_checker) = self.service_transaction_checker {
-            match service_transaction_checker.refresh_cache(chain) {
+        // if the state of the registrar or the certifier contract changed.
+        let contract_state = |address: &Address| {
+            (
+                chain.storage_root(address, BlockId::Latest),
+                chain.code_hash(address, BlockId::Latest),
+            )
+        };
+        if let Some(service_transaction_checker) = self
+            .service_transaction_checker
+            .as_ref()
+            .filter(|checker| checker.is_stale(&contract_state))
+        {
+            match service_transaction_checker.refresh_cache(chain, &contract_state) {
                 Ok(true) => {
                     trace!(target: "client", "Service transaction cache was refreshed successfully");
                 }
@@ -1596,6 +2182,7 @@ impl miner::MinerService for Miner {
                 header: b.header.clone(),
                 transactions: b.transactions.iter().cloned().map(Into::into).collect(),
                 uncles: b.uncles.to_vec(),
+                withdrawals: b.withdrawals.clone(),
             },
             latest_block_number,
         )
@@ -1610,6 +2197,84 @@ impl miner::MinerService for Miner {
             latest_block_number,
         )
     }
+
+    fn prepare_proposal<C>(
+        &self,
+        chain: &C,
+        author: Address,
+        transactions: Vec<SignedTransaction>,
+    ) -> Result<ProposalPreview, Error>
+    where
+        C: BlockChain + CallContract + BlockProducer + Nonce + Sync,
+    {
+        let params = self.params.read().clone();
+        let mut open_block =
+            chain.prepare_open_block(author, params.gas_range_target, params.extra_data)?;
+        for transaction in self.engine.generate_engine_transactions(&open_block)? {
+            open_block.push_transaction(transaction, None)?;
+        }
+
+        let mut rejected = Vec::new();
+        for transaction in transactions {
+            let hash = transaction.hash();
+            if let Err(e) = open_block.push_transaction(transaction, None) {
+                trace!(target: "miner", "prepare_proposal: rejected {:?}: {:?}", hash, e);
+                rejected.push((hash, e.to_string()));
+            }
+        }
+
+        let block = open_block.close_and_lock()?.drain();
+        Ok(ProposalPreview {
+            gas_used: *block.header.gas_used(),
+            state_root: *block.header.state_root(),
+            header: block.header,
+            receipts: block.receipts,
+            rejected,
+        })
+    }
+}
+
+impl PrometheusMetrics for Miner {
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        let (throttle, rebuilt_blocks, extended_blocks) = {
+            let sealing = self.sealing.lock();
+            (
+                sealing.throttle.clone(),
+                sealing.rebuilt_blocks,
+                sealing.extended_blocks,
+            )
+        };
+        r.register_gauge(
+            "miner_reseal_period_ms",
+            "Current minimal period between transaction-inspired reseals",
+            throttle.period().as_millis() as i64,
+        );
+        r.register_gauge(
+            "miner_reseal_churn_rate",
+            "Transactions imported per second, measured at the last reseal",
+            throttle.churn_rate() as i64,
+        );
+        r.register_counter(
+            "miner_reseals",
+            "Reseals of the pending block",
+            throttle.reseals() as i64,
+        );
+        r.register_counter(
+            "miner_reseal_invalidated",
+            "Pending blocks invalidated by a new chain block within the reseal period",
+            throttle.invalidated_blocks() as i64,
+        );
+        r.register_counter(
+            "miner_pending_block_rebuilds",
+            "Pending blocks authored from scratch",
+            rebuilt_blocks as i64,
+        );
+        r.register_counter(
+            "miner_pending_block_extensions",
+            "Pending blocks extended with new transactions",
+            extended_blocks as i64,
+        );
+    }
 }
 
 #[cfg(test)]
@@ -1641,6 +2306,26 @@ mod tests {
         assert!(sealing_work.is_some(), "Expected closed block");
     }
 
+    #[test]
+    fn should_author_blocks_as_fee_recipient_if_engine_supports_it() {
+        let client = TestBlockChainClient::default();
+        let author = Address::from_low_u64_be(1);
+        let recipient = Address::from_low_u64_be(2);
+
+        let miner = Miner::new_for_tests(&::ethereum::new_byzantium_test(), None);
+        miner.set_author(Author::External(author));
+        miner.set_fee_recipient(Some(recipient));
+        let (block, _) = miner.prepare_block(&client).unwrap();
+        assert_eq!(*block.header.author(), recipient);
+
+        // the null engine does not support a separate recipient
+        let miner = Miner::new_for_tests(&Spec::new_test(), None);
+        miner.set_author(Author::External(author));
+        miner.set_fee_recipient(Some(recipient));
+        let (block, _) = miner.prepare_block(&client).unwrap();
+        assert_eq!(*block.header.author(), author);
+    }
+
     #[test]
     fn should_still_work_after_a_couple_of_blocks() {
         // given
@@ -1672,13 +2357,19 @@ mod tests {
                 reseal_on_uncle: false,
                 reseal_min_period: Duration::from_secs(5),
                 reseal_max_period: Duration::from_secs(120),
+                reseal_throttle_max_period: None,
                 pending_set: PendingSet::AlwaysSealing,
                 work_queue_size: 5,
                 enable_resubmission: true,
                 infinite_pending_block: false,
+                block_building_budget: None,
                 tx_queue_penalization: Penalization::Disabled,
                 tx_queue_strategy: PrioritizationStrategy::GasPriceOnly,
                 tx_queue_no_unfamiliar_locals: false,
+                tx_queue_max_age: None,
+                tx_queue_alt_mempool: Default::default(),
+                tx_queue_future_limits: None,
+                tx_queue_timestamp_tolerance: Duration::from_secs(0),
                 refuse_service_transactions: false,
                 pool_limits: Default::default(),
                 pool_verification_options: pool::verifier::Options {
@@ -1688,6 +2379,10 @@ mod tests {
                     tx_gas_limit: U256::max_value(),
                     no_early_reject: false,
                     allow_non_eoa_sender: false,
+                    max_gas_per_sender: None,
+                    replacement_bump: Default::default(),
+                    prioritized_senders: Default::default(),
+                    sender_premium: None,
                 },
             },
             GasPricer::new_fixed(0u64.into()),
@@ -1742,6 +2437,181 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_extend_pending_block_with_new_transactions() {
+        // given
+        let client = TestBlockChainClient::default();
+        let miner = miner();
+        let best_block = 0;
+        let res =
+            miner.import_own_transaction(&client, PendingTransaction::new(transaction(), None));
+        assert_eq!(res.unwrap(), ());
+        assert_eq!(miner.pending_transactions(best_block).unwrap().len(), 1);
+
+        // when
+        let res =
+            miner.import_own_transaction(&client, PendingTransaction::new(transaction(), None));
+
+        // then
+        assert_eq!(res.unwrap(), ());
+        assert_eq!(miner.pending_transactions(best_block).unwrap().len(), 2);
+        let sealing = miner.sealing.lock();
+        assert_eq!(sealing.rebuilt_blocks, 1);
+        assert_eq!(sealing.extended_blocks, 1);
+    }
+
+    #[test]
+    fn should_extend_pending_block_with_transactions_of_new_senders() {
+        // given
+        let client = TestBlockChainClient::default();
+        let miner = miner();
+        let best_block = 0;
+        let tx1 = transaction();
+        let tx2 = transaction();
+        let tx3 = transaction();
+        miner
+            .import_own_transaction(&client, PendingTransaction::new(tx1.clone(), None))
+            .unwrap();
+        // external transactions don't trigger a reseal with this miner.
+        miner.import_external_transactions(&client, vec![tx2.clone().into()]);
+        assert_eq!(miner.new_senders.lock().len(), 1);
+
+        // when
+        miner
+            .import_own_transaction(&client, PendingTransaction::new(tx3.clone(), None))
+            .unwrap();
+
+        // then
+        let block = miner.pending_block(best_block).unwrap();
+        assert_eq!(block.transactions[0].hash(), tx1.hash());
+        let mut added = block.transactions[1..]
+            .iter()
+            .map(|tx| tx.hash())
+            .collect::<Vec<_>>();
+        added.sort();
+        let mut expected = vec![tx2.hash(), tx3.hash()];
+        expected.sort();
+        assert_eq!(added, expected);
+        assert!(miner.new_senders.lock().is_empty());
+        let sealing = miner.sealing.lock();
+        assert_eq!(sealing.rebuilt_blocks, 1);
+        assert_eq!(sealing.extended_blocks, 1);
+    }
+
+    #[test]
+    fn should_rebuild_pending_block_handed_out_or_with_changed_params() {
+        // given
+        let client = TestBlockChainClient::default();
+        let miner = miner();
+        let best_block = 0;
+        let tx1 = transaction();
+        let tx2 = transaction();
+        let hashes = |block: Block| {
+            block
+                .transactions
+                .iter()
+                .map(|tx| tx.hash())
+                .collect::<Vec<_>>()
+        };
+        let counts = || {
+            let sealing = miner.sealing.lock();
+            (sealing.rebuilt_blocks, sealing.extended_blocks)
+        };
+
+        // when
+        miner
+            .import_own_transaction(&client, PendingTransaction::new(tx1.clone(), None))
+            .unwrap();
+        miner
+            .import_own_transaction(&client, PendingTransaction::new(tx2.clone(), None))
+            .unwrap();
+
+        // then
+        let block = miner.pending_block(best_block).unwrap();
+        assert_eq!(hashes(block), vec![tx1.hash(), tx2.hash()]);
+        assert_eq!(counts(), (1, 1));
+
+        // when the authoring params change
+        miner.set_extra_data(vec![1, 2, 3]);
+        miner
+            .import_own_transaction(&client, PendingTransaction::new(transaction(), None))
+            .unwrap();
+
+        // then
+        let block = miner.pending_block(best_block).unwrap();
+        assert_eq!(block.header.extra_data(), &vec![1, 2, 3]);
+        assert_eq!(hashes(block).len(), 3);
+        assert_eq!(counts(), (2, 1));
+
+        // when the block is handed out as work
+        miner.sealing.lock().queue.use_last_ref();
+        miner
+            .import_own_transaction(&client, PendingTransaction::new(transaction(), None))
+            .unwrap();
+
+        // then
+        assert_eq!(hashes(miner.pending_block(best_block).unwrap()).len(), 4);
+        assert_eq!(counts(), (3, 1));
+    }
+
+    #[test]
+    fn should_stop_adding_transactions_at_block_building_deadline() {
+        // given
+        let client = TestBlockChainClient::default();
+        let miner = Miner::new(
+            MinerOptions {
+                block_building_budget: Some(Duration::from_millis(0)),
+                ..miner().options
+            },
+            GasPricer::new_fixed(0u64.into()),
+            &Spec::new_test(),
+            HashSet::new(),
+        );
+        let best_block = 0;
+
+        // when
+        let res =
+            miner.import_own_transaction(&client, PendingTransaction::new(transaction(), None));
+
+        // then
+        assert_eq!(res.unwrap(), ());
+        assert_eq!(miner.pending_transactions(best_block).unwrap().len(), 0);
+        assert_eq!(
+            miner
+                .ready_transactions(&client, 10, PendingOrdering::Priority)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn should_preview_proposal_without_touching_pending_block() {
+        // given
+        let client = TestBlockChainClient::default();
+        let miner = miner();
+        let transaction = transaction();
+        let hash = transaction.hash();
+
+        // when
+        let preview = miner
+            .prepare_proposal(
+                &client,
+                Address::zero(),
+                vec![transaction.clone(), transaction],
+            )
+            .unwrap();
+
+        // then
+        assert_eq!(preview.receipts.len(), 1);
+        assert_eq!(preview.gas_used, *preview.header.gas_used());
+        assert!(!preview.gas_used.is_zero());
+        assert_eq!(preview.state_root, *preview.header.state_root());
+        assert_eq!(preview.rejected.len(), 1);
+        assert_eq!(preview.rejected[0].0, hash);
+        assert!(miner.pending_block(0).is_none());
+        assert_eq!(client.chain_info().best_block_number, 0);
+    }
+
     #[test]
     fn should_not_return_stale_work_packages() {
         // given
@@ -1867,6 +2737,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_skip_system_transactions_over_block_limit() {
+        use engines::NullEngine;
+        use machine::EthereumMachine;
+
+        // given
+        let keypair = Random.generate();
+        let target = Address::from_low_u64_be(0x10);
+        let mut spec = Spec::new_test();
+        let mut params = spec.params().clone();
+        params.system_transaction_sender = Some(keypair.address());
+        params.system_transaction_targets = vec![target];
+        params.max_system_transactions_per_block = 2;
+        spec.engine = Arc::new(NullEngine::new(
+            Default::default(),
+            EthereumMachine::regular(params, Default::default()),
+        ));
+        let client = TestBlockChainClient::default();
+        let miner = Miner::new_for_tests(&spec, None);
+
+        // when
+        for nonce in 0..3 {
+            let transaction = TypedTransaction::Legacy(Transaction {
+                action: Action::Call(target),
+                value: U256::zero(),
+                data: vec![],
+                gas: U256::from(100_000),
+                gas_price: U256::zero(),
+                nonce: nonce.into(),
+            })
+            .sign(keypair.secret(), Some(TEST_CHAIN_ID));
+            miner
+                .import_own_transaction(&client, PendingTransaction::new(transaction, None))
+                .unwrap();
+        }
+        let (block, _) = miner.prepare_block(&client).unwrap();
+
+        // then
+        assert_eq!(block.transactions.len(), 2);
+        assert_eq!(
+            miner
+                .ready_transactions(&client, 10, PendingOrdering::Priority)
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn should_cancel_pending_seal_on_new_best_block() {
+        // given
+        let client = TestBlockChainClient::default();
+        let miner = Miner::new_for_tests(&Spec::new_test(), None);
+        let (block, _) = miner.prepare_block(&client).unwrap();
+        let handle = PendingSeal::new();
+
+        // when
+        assert!(!miner.import_internal_seal(&client, block, Seal::Pending(handle.clone())));
+
+        // then
+        assert!(
+            miner.poll_pending_seal(&client),
+            "seal is still in progress"
+        );
+        assert!(!handle.is_cancelled());
+
+        client.add_block(EachBlockWith::Nothing, |header| header);
+        let imported = [client.chain_info().best_block_hash];
+        miner.chain_new_blocks(&client, &imported, &[], &imported, &[], false);
+        assert!(handle.is_cancelled());
+        assert!(!handle.complete(Seal::Regular(vec![])));
+        assert!(!miner.poll_pending_seal(&client));
+    }
+
     #[test]
     fn should_treat_unfamiliar_locals_selectively() {
         // given
@@ -2064,6 +3007,31 @@ mod tests {
         assert_eq!(client.chain_info().best_block_number, 4 as BlockNumber);
     }
 
+    #[test]
+    fn should_not_seal_while_sealing_is_blocked() {
+        let spec = Spec::new_instant();
+        let miner = Miner::new_for_tests(&spec, None);
+        let client = generate_dummy_client(2);
+
+        miner.set_sealing_blocked(Some("chain spec is outdated".into()));
+        miner
+            .import_external_transactions(
+                &*client,
+                vec![transaction_with_chain_id(spec.chain_id()).into()],
+            )
+            .pop()
+            .unwrap()
+            .unwrap();
+        miner.update_sealing(&*client, ForceUpdateSealing::Yes);
+        client.flush_queue();
+        assert_eq!(client.chain_info().best_block_number, 2 as BlockNumber);
+
+        miner.set_sealing_blocked(None);
+        miner.update_sealing(&*client, ForceUpdateSealing::Yes);
+        client.flush_queue();
+        assert_eq!(client.chain_info().best_block_number, 3 as BlockNumber);
+    }
+
     #[test]
     fn should_not_fail_setting_engine_signer_without_account_provider() {
         let spec = Spec::new_test_round;
@@ -2080,6 +3048,52 @@ mod tests {
         assert!(client.engine().sign(msg).is_ok());
     }
 
+    #[test]
+    fn should_rotate_engine_signer_at_handover_block() {
+        let spec = Spec::new_test_round;
+        let tap = Arc::new(AccountProvider::transient_provider());
+        let addr1 = tap.insert_account(keccak("1").into(), &"1".into()).unwrap();
+        let addr2 = tap.insert_account(keccak("0").into(), &"0".into()).unwrap();
+        let client = generate_dummy_client_with_spec(spec);
+        let miner = client.miner();
+
+        miner.set_author(Author::Sealer(Box::new((tap.clone(), addr1, "1".into()))));
+        miner
+            .schedule_signer_rotation(Box::new((tap.clone(), addr2, "0".into())), 3)
+            .unwrap();
+        assert_eq!(miner.signer_rotation(), Some((addr2, 3)));
+
+        // not yet at the handover block
+        miner.rotate_signer_if_due(1);
+        assert_eq!(miner.authoring_params().author, addr1);
+
+        miner.rotate_signer_if_due(2);
+        assert_eq!(miner.authoring_params().author, addr2);
+        assert_eq!(miner.signer_rotation(), None);
+    }
+
+    #[test]
+    fn should_not_rotate_to_signer_that_is_not_a_validator() {
+        let spec = Spec::new_test_round;
+        let tap = Arc::new(AccountProvider::transient_provider());
+        let addr1 = tap.insert_account(keccak("1").into(), &"1".into()).unwrap();
+        let outsider = tap.insert_account(keccak("2").into(), &"2".into()).unwrap();
+        let client = generate_dummy_client_with_spec(spec);
+        let miner = client.miner();
+
+        miner.set_author(Author::Sealer(Box::new((tap.clone(), addr1, "1".into()))));
+        miner
+            .schedule_signer_rotation(Box::new((tap.clone(), outsider, "2".into())), 1)
+            .unwrap();
+
+        miner.rotate_signer_if_due(5);
+        assert_eq!(miner.authoring_params().author, addr1);
+        assert_eq!(miner.signer_rotation(), Some((outsider, 1)));
+
+        assert!(miner.cancel_signer_rotation());
+        assert_eq!(miner.signer_rotation(), None);
+    }
+
     #[test]
     fn should_mine_if_internal_sealing_is_enabled() {
         let spec = Spec::new_instant();
@@ -2132,6 +3146,79 @@ mod tests {
         assert!(miner.is_currently_sealing());
     }
 
+    #[test]
+    fn should_take_new_block_transactions_from_block_builder() {
+        struct FixedBuilder(Option<Vec<SignedTransaction>>);
+
+        impl BlockBuilder for FixedBuilder {
+            fn payload(&self, _attributes: &PayloadAttributes) -> Option<Vec<SignedTransaction>> {
+                self.0.clone()
+            }
+        }
+
+        // given
+        let client = TestBlockChainClient::default();
+        let fallback = miner();
+        let miner = miner();
+        let built = transaction();
+        miner.set_block_builder(Box::new(FixedBuilder(Some(vec![built.clone()]))));
+
+        // when
+        assert_eq!(
+            miner.prepare_pending_block(&client),
+            BlockPreparationStatus::Succeeded
+        );
+
+        // then
+        assert_eq!(miner.pending_transactions(0), Some(vec![built]));
+
+        // given
+        let pooled = transaction();
+        fallback.set_block_builder(Box::new(FixedBuilder(None)));
+
+        // when
+        let res =
+            fallback.import_own_transaction(&client, PendingTransaction::new(pooled.clone(), None));
+
+        // then
+        assert_eq!(res.unwrap(), ());
+        assert_eq!(fallback.pending_transactions(0), Some(vec![pooled]));
+    }
+
+    #[test]
+    fn should_rebuild_pending_block_once_builder_payload_is_ready() {
+        struct LateBuilder(Arc<Mutex<Option<Vec<SignedTransaction>>>>);
+
+        impl BlockBuilder for LateBuilder {
+            fn payload(&self, _attributes: &PayloadAttributes) -> Option<Vec<SignedTransaction>> {
+                self.0.lock().take()
+            }
+
+            fn has_payload(&self, _parent_hash: &H256) -> bool {
+                self.0.lock().is_some()
+            }
+        }
+
+        // given
+        let client = TestBlockChainClient::default();
+        let miner = miner();
+        let payload = Arc::new(Mutex::new(None));
+        miner.set_block_builder(Box::new(LateBuilder(payload.clone())));
+        let pooled = transaction();
+        miner
+            .import_own_transaction(&client, PendingTransaction::new(pooled.clone(), None))
+            .unwrap();
+        assert_eq!(miner.pending_transactions(0), Some(vec![pooled]));
+
+        // when
+        let built = transaction();
+        *payload.lock() = Some(vec![built.clone()]);
+        miner.update_sealing(&client, ForceUpdateSealing::Yes);
+
+        // then
+        assert_eq!(miner.pending_transactions(0), Some(vec![built]));
+    }
+
     #[test]
     fn should_not_mine_if_is_not_allowed_to_seal() {
         let spec = Spec::new_test_round();
@@ -19,14 +19,23 @@
 //! Miner module
 //! Keeps track of transactions and currently sealed pending block.
 
+mod bundle;
 mod cache;
+mod fee_history;
 mod miner;
+mod reseal_throttle;
 
 pub mod pool_client;
 #[cfg(feature = "stratum")]
 pub mod stratum;
 
-pub use self::miner::{Author, AuthoringParams, Miner, MinerOptions, Penalization, PendingSet};
+pub use self::miner::{
+    Author, AuthoringParams, Miner, MinerOptions, Penalization, PendingSet, ProposalPreview,
+};
+pub use self::{
+    bundle::{Bundle, BundleError},
+    fee_history::BlockFees,
+};
 pub use ethcore_miner::{
     local_accounts::LocalAccounts,
     pool::{transaction_filter::TransactionFilter, PendingOrdering},
@@ -35,10 +44,14 @@ pub use ethcore_miner::{
 use std::{
     collections::{BTreeMap, BTreeSet},
     sync::Arc,
+    time::Duration,
 };
 
 use bytes::Bytes;
-use ethcore_miner::pool::{local_transactions, QueueStatus, VerifiedTransaction};
+use ethcore_miner::pool::{
+    alt_mempool::{Capacity, Reservation, ReservationError},
+    local_transactions, QueueStatus, SenderNonceState, SenderTransactions, VerifiedTransaction,
+};
 use ethereum_types::{Address, H256, U256};
 use types::{
     block::Block,
@@ -54,6 +67,7 @@ use client::{
     traits::ForceUpdateSealing, AccountData, BlockChain, BlockProducer, ChainInfo, Nonce,
     ScheduleInfo, SealedBlockImporter,
 };
+use engines::EngineSigner;
 use error::Error;
 use state::StateInfo;
 
@@ -68,7 +82,12 @@ pub trait TransactionVerifierClient: Send + Sync
 /// Extended client interface used for mining
 pub trait BlockChainClient:
     TransactionVerifierClient + BlockProducer + SealedBlockImporter
+	// Required for the fee history
+	+ ::client::BlockChainClient
 {
+    /// Called once a local transaction went through the queue, which may have removed
+    /// other local transactions from it.
+    fn local_transactions_updated(&self) {}
 }
 
 /// Miner client API
@@ -115,6 +134,17 @@ pub trait MinerService: Send + Sync {
 
     // Pending block
 
+    /// Build and execute a block of `transactions` on top of the best block without sealing,
+    /// importing or broadcasting it. Transactions that fail are reported instead of included.
+    fn prepare_proposal<C>(
+        &self,
+        chain: &C,
+        author: Address,
+        transactions: Vec<SignedTransaction>,
+    ) -> Result<ProposalPreview, Error>
+    where
+        C: BlockChain + CallContract + BlockProducer + Nonce + Sync;
+
     /// Get a list of all pending receipts from pending block.
     fn pending_receipts(&self, best_block: BlockNumber) -> Option<Vec<RichReceipt>>;
 
@@ -155,6 +185,26 @@ pub trait MinerService: Send + Sync {
     /// On chains where sealing is done externally (e.g. PoW) we provide only reward beneficiary.
     fn set_author<T: Into<Option<Author>>>(&self, author: T);
 
+    /// Set the address credited with block rewards and fees instead of the block author,
+    /// where the engine supports it.
+    fn set_fee_recipient(&self, recipient: Option<Address>);
+
+    /// Schedule a switch of the engine signer to `signer`.
+    ///
+    /// The new signer is used for blocks starting at `handover_block`, provided the engine
+    /// accepts it as a block author by then. Replaces any previously scheduled rotation.
+    fn schedule_signer_rotation(
+        &self,
+        signer: Box<dyn EngineSigner>,
+        handover_block: BlockNumber,
+    ) -> Result<(), &str>;
+
+    /// Cancel a scheduled signer rotation. Returns `false` if none was scheduled.
+    fn cancel_signer_rotation(&self) -> bool;
+
+    /// Address of the scheduled next signer and its handover block, if any.
+    fn signer_rotation(&self) -> Option<(Address, BlockNumber)>;
+
     // Transaction Pool
 
     /// Imports transactions to transaction queue.
@@ -166,6 +216,10 @@ pub trait MinerService: Send + Sync {
     where
         C: BlockChainClient;
 
+    /// Submits a bundle of transactions to be included contiguously at the top of
+    /// `bundle.block_number`, or not at all. Returns the hash of the bundle.
+    fn submit_bundle<C: ChainInfo>(&self, chain: &C, bundle: Bundle) -> Result<H256, BundleError>;
+
     /// Imports own (node owner) transaction to queue.
     fn import_own_transaction<C>(
         &self,
@@ -206,6 +260,18 @@ pub trait MinerService: Send + Sync {
     where
         C: Nonce + Sync;
 
+    /// Returns the nonce of given address in the latest state together with the
+    /// gaps which keep its queued transactions from becoming ready.
+    fn sender_nonce_state<C>(&self, chain: &C, address: &Address) -> SenderNonceState
+    where
+        C: Nonce + Sync;
+
+    /// Returns all queued transactions grouped by sender and split into the ones
+    /// which are ready and the ones waiting behind a nonce gap.
+    fn queued_transactions_by_sender<C>(&self, chain: &C) -> BTreeMap<Address, SenderTransactions>
+    where
+        C: Nonce + Sync;
+
     /// Get a set of all pending transaction hashes.
     ///
     /// Depending on the settings may look in transaction pool or only in pending block.
@@ -256,6 +322,19 @@ pub trait MinerService: Send + Sync {
     /// Status includes verification thresholds and current pool utilization and limits.
     fn queue_status(&self) -> QueueStatus;
 
+    /// Get current usage of the alt-mempool lane for EIP-4337 bundle transactions.
+    fn alt_mempool_capacity(&self) -> Capacity;
+
+    /// Reserves `slots` of the alt-mempool lane for bundle transactions sent by `bundler`.
+    ///
+    /// The reservation replaces any previous one of the bundler and expires after `ttl`.
+    fn reserve_alt_mempool(
+        &self,
+        bundler: Address,
+        slots: usize,
+        ttl: Duration,
+    ) -> Result<Reservation, ReservationError>;
+
     // Misc
 
     /// Suggested gas price.
@@ -264,6 +343,9 @@ pub trait MinerService: Send + Sync {
     /// Suggested max priority fee gas price
     fn sensible_max_priority_fee(&self) -> U256;
 
+    /// Fees paid in a recent canonical block, if known.
+    fn block_fees(&self, number: BlockNumber) -> Option<BlockFees>;
+
     /// Suggested gas limit.
     fn sensible_gas_limit(&self) -> U256;
 
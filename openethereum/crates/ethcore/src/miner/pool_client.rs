@@ -236,6 +236,9 @@ where
     }
 
     fn transaction_type(&self, tx: &SignedTransaction) -> pool::client::TransactionType {
+        if self.engine.machine().is_system_transaction(tx) {
+            return pool::client::TransactionType::System;
+        }
         match self.service_transaction_checker {
             None => pool::client::TransactionType::Regular,
             Some(ref checker) => match checker.check(self.chain, &tx) {
@@ -253,8 +256,8 @@ where
         &self,
         transaction: &[u8],
     ) -> Result<UnverifiedTransaction, transaction::Error> {
-        let number = self.chain.best_block_header().number();
-        self.engine.decode_transaction(transaction, number)
+        self.engine
+            .decode_transaction(transaction, &self.best_block_header)
     }
 }
 
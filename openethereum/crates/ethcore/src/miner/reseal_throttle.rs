@@ -0,0 +1,165 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Adaptive throttling of transaction-inspired reseals.
+//!
+//! The minimal period between reseals is widened while transactions keep pouring in
+//! or pending blocks are invalidated by new chain blocks shortly after being built,
+//! and tightened again once the node is idle.
+
+use std::{
+    cmp,
+    time::{Duration, Instant},
+};
+
+/// Rate of imported transactions, per second, above which the node is considered busy.
+pub const BUSY_TRANSACTIONS_PER_SECOND: u64 = 50;
+
+/// Smallest widened period, used when the minimal period is zero.
+const MIN_STEP: Duration = Duration::from_millis(100);
+
+/// Controller of the period between transaction-inspired reseals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResealThrottle {
+    min_period: Duration,
+    max_period: Duration,
+    period: Duration,
+    last_reseal: Option<Instant>,
+    imported: u64,
+    invalidated: bool,
+    churn_rate: u64,
+    reseals: u64,
+    invalidated_blocks: u64,
+}
+
+impl ResealThrottle {
+    /// Create new throttle keeping the period within `min_period` and `max_period`.
+    /// The period is fixed if `max_period` does not exceed `min_period`.
+    pub fn new(min_period: Duration, max_period: Duration) -> Self {
+        ResealThrottle {
+            min_period,
+            max_period: cmp::max(min_period, max_period),
+            period: min_period,
+            last_reseal: None,
+            imported: 0,
+            invalidated: false,
+            churn_rate: 0,
+            reseals: 0,
+            invalidated_blocks: 0,
+        }
+    }
+
+    /// Records transactions imported to the queue.
+    pub fn record_imports(&mut self, count: usize) {
+        self.imported += count as u64;
+    }
+
+    /// Records a new chain block, which invalidates the pending block.
+    pub fn record_new_block(&mut self, now: Instant) {
+        let recent = self.last_reseal.map_or(false, |last| {
+            now.saturating_duration_since(last) < self.period
+        });
+        if recent {
+            self.invalidated = true;
+            self.invalidated_blocks += 1;
+        }
+    }
+
+    /// Records a reseal and returns the period until the next one is allowed.
+    pub fn reseal(&mut self, now: Instant) -> Duration {
+        if let Some(last) = self.last_reseal {
+            let elapsed = now.saturating_duration_since(last).as_millis() as u64;
+            self.churn_rate = self.imported * 1000 / cmp::max(elapsed, 1);
+        }
+
+        if self.invalidated || self.churn_rate >= BUSY_TRANSACTIONS_PER_SECOND {
+            self.period = cmp::min(cmp::max(self.period * 2, MIN_STEP), self.max_period);
+        } else if self.churn_rate < BUSY_TRANSACTIONS_PER_SECOND / 4 {
+            self.period = cmp::max(self.period / 2, self.min_period);
+        }
+
+        self.last_reseal = Some(now);
+        self.imported = 0;
+        self.invalidated = false;
+        self.reseals += 1;
+        self.period
+    }
+
+    /// Current period between reseals.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Imported transactions per second measured at the last reseal.
+    pub fn churn_rate(&self) -> u64 {
+        self.churn_rate
+    }
+
+    /// Number of reseals so far.
+    pub fn reseals(&self) -> u64 {
+        self.reseals
+    }
+
+    /// Number of pending blocks invalidated by a new chain block within the period.
+    pub fn invalidated_blocks(&self) -> u64 {
+        self.invalidated_blocks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_widen_period_under_churn_and_tighten_when_idle() {
+        let min = Duration::from_secs(2);
+        let max = Duration::from_secs(16);
+        let mut throttle = ResealThrottle::new(min, max);
+        let start = Instant::now();
+
+        assert_eq!(throttle.reseal(start), min);
+
+        // 200 transactions in 2 seconds
+        throttle.record_imports(200);
+        assert_eq!(throttle.reseal(start + min), min * 2);
+        assert_eq!(throttle.churn_rate(), 100);
+
+        // new block right after the reseal
+        throttle.record_new_block(start + min + Duration::from_secs(1));
+        assert_eq!(throttle.reseal(start + min * 3), min * 4);
+        assert_eq!(throttle.invalidated_blocks(), 1);
+
+        throttle.record_imports(10_000);
+        assert_eq!(throttle.reseal(start + min * 4), max);
+
+        // idle
+        assert_eq!(throttle.reseal(start + min * 20), max / 2);
+        assert_eq!(throttle.reseal(start + min * 30), max / 4);
+        assert_eq!(throttle.reseals(), 6);
+    }
+
+    #[test]
+    fn should_keep_fixed_period_without_headroom() {
+        let min = Duration::from_secs(2);
+        let mut throttle = ResealThrottle::new(min, min);
+        let start = Instant::now();
+
+        throttle.reseal(start);
+        throttle.record_imports(1_000);
+        assert_eq!(throttle.reseal(start + min), min);
+        assert_eq!(throttle.period(), min);
+    }
+}
@@ -19,10 +19,14 @@
 use bytes::Bytes;
 use ethereum_types::{H256, U256};
 use hash::keccak;
-use rlp::{DecoderError, Rlp, RlpStream};
+use rlp::{encode, DecoderError, Rlp, RlpStream};
 use triehash::ordered_trie_root;
 use types::{
-    block::Block, header::Header, transaction::TypedTransaction, views::BlockView, BlockNumber,
+    block::Block,
+    header::{Header, HeaderDecodeParams},
+    transaction::TypedTransaction,
+    views::BlockView,
+    withdrawal::Withdrawal,
 };
 
 const HEADER_FIELDS: usize = 8;
@@ -45,18 +49,20 @@ impl AbridgedBlock {
 
     /// Given a full block view, trim out the parent hash and block number,
     /// producing new rlp.
-    pub fn from_block_view(block_view: &BlockView, eip1559_transition: BlockNumber) -> Self {
-        let header = block_view.header_view();
-        let eip1559 = header.number() >= eip1559_transition;
-        let seal_fields = header.seal(eip1559);
-
-        let nmb_of_elements = if eip1559 {
-            HEADER_FIELDS + seal_fields.len() + BLOCK_FIELDS + 1
-        } else {
-            HEADER_FIELDS + seal_fields.len() + BLOCK_FIELDS
-        };
-
-        // 10 header fields, unknown number of seal fields, and 2 block fields.
+    pub fn from_block_view<P: Into<HeaderDecodeParams>>(block_view: &BlockView, params: P) -> Self {
+        let params = params.into();
+        let header = block_view.header(params);
+        let seal_fields = header.seal();
+        let withdrawals = block_view.withdrawals_rlp();
+
+        let nmb_of_elements = HEADER_FIELDS
+            + seal_fields.len()
+            + BLOCK_FIELDS
+            + header.base_fee().is_some() as usize
+            + withdrawals.is_some() as usize;
+
+        // 10 header fields, unknown number of seal fields, 2 block fields, then the
+        // optional base fee and withdrawals.
         let mut stream = RlpStream::new_list(nmb_of_elements);
 
         // write header values.
@@ -73,15 +79,20 @@ impl AbridgedBlock {
         // write block values.
 
         TypedTransaction::rlp_append_list(&mut stream, &block_view.transactions());
-        stream.append_list(&block_view.uncles(eip1559_transition));
+        stream.append_list(&block_view.uncles(params.eip1559_transition));
 
         // write seal fields.
         for field in seal_fields {
-            stream.append_raw(&field, 1);
+            stream.append_raw(field, 1);
         }
 
-        if eip1559 {
-            stream.append(&header.base_fee());
+        if let Some(base_fee) = header.base_fee() {
+            stream.append(&base_fee);
+        }
+
+        // the withdrawals root is recomputed from the withdrawals themselves.
+        if let Some(withdrawals) = withdrawals {
+            stream.append_raw(withdrawals.as_raw(), 1);
         }
 
         AbridgedBlock { rlp: stream.out() }
@@ -90,13 +101,14 @@ impl AbridgedBlock {
     /// Flesh out an abridged block view with the provided parent hash and block number.
     ///
     /// Will fail if contains invalid rlp.
-    pub fn to_block(
+    pub fn to_block<P: Into<HeaderDecodeParams>>(
         &self,
         parent_hash: H256,
         number: u64,
         receipts_root: H256,
-        eip1559_transition: BlockNumber,
+        params: P,
     ) -> Result<Block, DecoderError> {
+        let params = params.into();
         let rlp = Rlp::new(&self.rlp);
 
         let mut header: Header = Default::default();
@@ -112,7 +124,7 @@ impl AbridgedBlock {
         header.set_extra_data(rlp.val_at(7)?);
 
         let transactions = TypedTransaction::decode_rlp_list(&rlp.at(8)?)?;
-        let uncles = Header::decode_rlp_list(&rlp.at(9)?, eip1559_transition)?;
+        let uncles = Header::decode_rlp_list(&rlp.at(9)?, params)?;
 
         header.set_transactions_root(ordered_trie_root(rlp.at(8)?.iter().map(|r| {
             if r.is_list() {
@@ -128,26 +140,32 @@ impl AbridgedBlock {
         uncles_rlp.append_list(&uncles);
         header.set_uncles_hash(keccak(uncles_rlp.as_raw()));
 
-        let mut seal_fields = Vec::new();
-        let last_seal_index = if number >= eip1559_transition {
-            rlp.item_count()? - 1
+        let mut last_seal_index = rlp.item_count()?;
+        let withdrawals = if header.timestamp() >= params.eip4895_transition_timestamp {
+            last_seal_index -= 1;
+            let withdrawals: Vec<Withdrawal> = rlp.list_at(last_seal_index)?;
+            header.set_withdrawals_root(Some(ordered_trie_root(withdrawals.iter().map(encode))));
+            Some(withdrawals)
         } else {
-            rlp.item_count()?
+            None
         };
+        if number >= params.eip1559_transition {
+            last_seal_index -= 1;
+            header.set_base_fee(Some(rlp.val_at::<U256>(last_seal_index)?));
+        }
+
+        let mut seal_fields = Vec::new();
         for i in (HEADER_FIELDS + BLOCK_FIELDS)..last_seal_index {
             let seal_rlp = rlp.at(i)?;
             seal_fields.push(seal_rlp.as_raw().to_owned());
         }
         header.set_seal(seal_fields);
 
-        if number >= eip1559_transition {
-            header.set_base_fee(Some(rlp.val_at::<U256>(rlp.item_count()? - 1)?));
-        }
-
         Ok(Block {
             header: header,
             transactions: transactions,
             uncles: uncles,
+            withdrawals,
         })
     }
 }
@@ -160,9 +178,11 @@ mod tests {
     use ethereum_types::{Address, H256, U256};
     use types::{
         block::Block,
+        header::HeaderDecodeParams,
         transaction::{Action, Transaction, TypedTransaction},
         view,
         views::BlockView,
+        withdrawal::Withdrawal,
         BlockNumber,
     };
 
@@ -204,6 +224,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn withdrawals_block_abridging() {
+        let withdrawal = Withdrawal {
+            index: 1,
+            validator_index: 2,
+            address: Address::from_low_u64_be(3),
+            amount: 4,
+        };
+        let mut b = Block::default();
+        b.header.set_timestamp(1000);
+        b.header.set_base_fee(Some(U256::from(100)));
+        b.header.set_seal(vec![vec![50u8], vec![60u8]]);
+        b.header
+            .set_withdrawals_root(Some(::triehash::ordered_trie_root(vec![::rlp::encode(
+                &withdrawal,
+            )])));
+        b.withdrawals = Some(vec![withdrawal]);
+        let receipts_root = b.header.receipts_root().clone();
+        let encoded = encode_block(&b);
+
+        let params = HeaderDecodeParams {
+            eip1559_transition: 0,
+            eip4895_transition_timestamp: 1000,
+        };
+        let abridged = AbridgedBlock::from_block_view(&view!(BlockView, &encoded), params);
+        assert_eq!(
+            abridged
+                .to_block(H256::default(), 0, receipts_root, params)
+                .unwrap(),
+            b
+        );
+    }
+
     #[test]
     #[should_panic]
     fn wrong_number() {
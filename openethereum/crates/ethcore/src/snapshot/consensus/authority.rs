@@ -35,10 +35,14 @@ use bytes::Bytes;
 use db::KeyValueDB;
 use ethereum_types::{H256, U256};
 use itertools::{Itertools, Position};
-use rlp::{Rlp, RlpStream};
+use rlp::{DecoderError, Rlp, RlpStream};
 use types::{
-    encoded, header::Header, ids::BlockId, receipt::TypedReceipt, transaction::TypedTransaction,
-    BlockNumber,
+    block::Block,
+    encoded,
+    header::{Header, HeaderDecodeParams},
+    ids::BlockId,
+    receipt::TypedReceipt,
+    transaction::TypedTransaction,
 };
 
 /// Snapshot creation and restoration for PoA chains.
@@ -51,7 +55,8 @@ use types::{
 /// FLAG is a bool: true for last chunk, false otherwise.
 ///
 /// The last item of the last chunk will be a list containing data for the warp target block:
-/// [header, transactions, uncles, receipts, parent_td].
+/// [header, transactions, uncles, receipts, parent_td], followed by the withdrawals of
+/// blocks after EIP-4895.
 pub struct PoaSnapshot;
 
 impl SnapshotComponents for PoaSnapshot {
@@ -62,7 +67,7 @@ impl SnapshotComponents for PoaSnapshot {
         sink: &mut ChunkSink,
         _progress: &Progress,
         preferred_size: usize,
-        eip1559_transition: BlockNumber,
+        header_decode_params: HeaderDecodeParams,
     ) -> Result<(), Error> {
         let number = chain
             .block_number(&block_at)
@@ -109,7 +114,7 @@ impl SnapshotComponents for PoaSnapshot {
             .block(&block_at)
             .and_then(|b| chain.block_receipts(&block_at).map(|r| (b, r)))
             .ok_or_else(|| Error::BlockNotFound(block_at))?;
-        let block = block.decode(eip1559_transition)?;
+        let block = block.decode(header_decode_params)?;
 
         let parent_td = chain
             .block_details(block.header.parent_hash())
@@ -117,13 +122,16 @@ impl SnapshotComponents for PoaSnapshot {
             .ok_or_else(|| Error::BlockNotFound(block_at))?;
 
         rlps.push({
-            let mut stream = RlpStream::new_list(5);
+            let mut stream = RlpStream::new_list(5 + block.withdrawals.is_some() as usize);
             stream.append(&block.header);
             TypedTransaction::rlp_append_list(&mut stream, &block.transactions);
             stream
                 .append_list(&block.uncles)
                 .append(&receipts)
                 .append(&parent_td);
+            if let Some(ref withdrawals) = block.withdrawals {
+                stream.append_list(withdrawals);
+            }
             stream.out()
         });
 
@@ -186,6 +194,20 @@ struct ChunkRebuilder {
     last_epochs: Vec<(Header, Box<dyn EpochVerifier<EthereumMachine>>)>,
 }
 
+/// Decode the warp target block from the last item of the last chunk.
+fn decode_last_block(last_rlp: &Rlp, params: HeaderDecodeParams) -> Result<Block, DecoderError> {
+    Ok(Block {
+        header: Header::decode_rlp(&last_rlp.at(0)?, params)?,
+        transactions: TypedTransaction::decode_rlp_list(&last_rlp.at(1)?)?,
+        uncles: Header::decode_rlp_list(&last_rlp.at(2)?, params)?,
+        // the withdrawals follow the parent total difficulty, if the block has any
+        withdrawals: match last_rlp.item_count()? {
+            5 => None,
+            _ => Some(last_rlp.list_at(5)?),
+        },
+    })
+}
+
 // verified data.
 struct Verified {
     epoch_transition: EpochTransition,
@@ -202,8 +224,10 @@ impl ChunkRebuilder {
         use engines::ConstructedVerifier;
 
         // decode.
-        let header =
-            Header::decode_rlp(&transition_rlp.at(0)?, engine.params().eip1559_transition)?;
+        let header = Header::decode_rlp(
+            &transition_rlp.at(0)?,
+            engine.params().header_decode_params(),
+        )?;
         let epoch_data: Bytes = transition_rlp.val_at(1)?;
 
         trace!(target: "snapshot", "verifying transition to epoch at block {}", header.number());
@@ -349,17 +373,8 @@ impl Rebuilder for ChunkRebuilder {
         }
 
         if is_last_chunk {
-            use types::block::Block;
-
             let last_rlp = rlp.at(num_items - 1)?;
-            let block = Block {
-                header: Header::decode_rlp(&last_rlp.at(0)?, engine.params().eip1559_transition)?,
-                transactions: TypedTransaction::decode_rlp_list(&last_rlp.at(1)?)?,
-                uncles: Header::decode_rlp_list(
-                    &last_rlp.at(2)?,
-                    engine.params().eip1559_transition,
-                )?,
-            };
+            let block = decode_last_block(&last_rlp, engine.params().header_decode_params())?;
             let block_data = block.rlp_bytes();
             let receipts = TypedReceipt::decode_rlp_list(&last_rlp.at(3)?)?;
 
@@ -445,3 +460,42 @@ impl Rebuilder for ChunkRebuilder {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::decode_last_block;
+    use ethereum_types::{Address, H256, U256};
+    use rlp::{Rlp, RlpStream};
+    use types::{block::Block, header::HeaderDecodeParams, withdrawal::Withdrawal};
+
+    #[test]
+    fn last_block_keeps_withdrawals() {
+        let params = HeaderDecodeParams {
+            eip1559_transition: 0,
+            eip4895_transition_timestamp: 0,
+        };
+        let mut block = Block::default();
+        block.header.set_base_fee(Some(U256::from(7)));
+        block
+            .header
+            .set_withdrawals_root(Some(H256::from_low_u64_be(0x4895)));
+        block.withdrawals = Some(vec![Withdrawal {
+            index: 0,
+            validator_index: 1,
+            address: Address::from_low_u64_be(2),
+            amount: 3,
+        }]);
+
+        let mut stream = RlpStream::new_list(6);
+        stream
+            .append(&block.header)
+            .begin_list(0)
+            .append_list(&block.uncles)
+            .begin_list(0)
+            .append(&U256::from(1))
+            .append_list(block.withdrawals.as_ref().unwrap());
+        let bytes = stream.out();
+
+        assert_eq!(decode_last_block(&Rlp::new(&bytes), params).unwrap(), block);
+    }
+}
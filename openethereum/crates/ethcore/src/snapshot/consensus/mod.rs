@@ -22,7 +22,7 @@ use std::sync::{atomic::AtomicBool, Arc};
 use blockchain::{BlockChain, BlockChainDB};
 use engines::EthEngine;
 use snapshot::{Error, ManifestData, Progress};
-use types::BlockNumber;
+use types::header::HeaderDecodeParams;
 
 use ethereum_types::H256;
 
@@ -50,7 +50,7 @@ pub trait SnapshotComponents: Send {
         chunk_sink: &mut ChunkSink,
         progress: &Progress,
         preferred_size: usize,
-        eip1559_transition: BlockNumber,
+        header_decode_params: HeaderDecodeParams,
     ) -> Result<(), Error>;
 
     /// Create a rebuilder, which will have chunks fed into it in aribtrary
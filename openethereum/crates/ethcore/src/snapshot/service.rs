@@ -121,7 +121,7 @@ impl Restoration {
             Default::default(),
             params.genesis,
             raw_db.clone(),
-            params.engine.params().eip1559_transition,
+            params.engine.params().header_decode_params(),
         );
         let components = params
             .engine
@@ -395,7 +395,7 @@ impl Service {
             Default::default(),
             &[],
             next_db.clone(),
-            self.engine.params().eip1559_transition,
+            self.engine.params().header_decode_params(),
         );
         let next_chain_info = next_chain.chain_info();
 
@@ -19,8 +19,11 @@
 mod genesis;
 mod seal;
 mod spec;
+mod update_check;
 
 pub use self::{
     genesis::Genesis,
     spec::{CommonParams, OptimizeFor, Spec, SpecParams},
+    update_check::{SpecUpdateChecker, SpecUpdateConfig, SpecUpdateStatus, SPEC_REGISTRY_NAME},
 };
+pub use ethjson::spec::SpecOverlay;
@@ -31,12 +31,16 @@ use hash::{keccak, KECCAK_NULL_RLP};
 use parking_lot::RwLock;
 use rlp::{Rlp, RlpStream};
 use rustc_hex::FromHex;
-use types::{header::Header, BlockNumber};
+use types::{
+    header::{Header, HeaderDecodeParams},
+    BlockNumber,
+};
 use vm::{AccessList, ActionParams, ActionValue, CallType, EnvInfo, ParamsType};
 
 use builtin::Builtin;
 use engines::{
-    AuthorityRound, BasicAuthority, Clique, EthEngine, InstantSeal, InstantSealParams, NullEngine,
+    block_reward::RewardSchedule, AuthorityRound, BasicAuthority, Clique, EthEngine,
+    ExternalEngine, InstantSeal, InstantSealParams, NullEngine, Tendermint,
     DEFAULT_BLOCKHASH_CONTRACT,
 };
 use error::Error;
@@ -52,6 +56,7 @@ use trace::{NoopTracer, NoopVMTracer};
 pub use ethash::OptimizeFor;
 
 const MAX_TRANSACTION_SIZE: usize = 300 * 1024;
+const DEFAULT_MAX_SYSTEM_TRANSACTIONS_PER_BLOCK: usize = 16;
 
 // helper for formatting errors.
 fn fmt_err<F: ::std::fmt::Display>(f: F) -> String {
@@ -147,8 +152,22 @@ pub struct CommonParams {
     pub eip3529_transition: BlockNumber,
     /// Number of first block where EIP-3541 rule begins.
     pub eip3541_transition: BlockNumber,
+    /// Number of first block where EIP-3074 rules begin. AUTH and AUTHCALL opcodes.
+    pub eip3074_transition: BlockNumber,
     /// Number of first block where EIP-3607 rule begins.
     pub eip3607_transition: BlockNumber,
+    /// Number of first block where EIP-4399 rules begin. DIFFICULTY returns PREVRANDAO.
+    pub eip4399_transition: BlockNumber,
+    /// Timestamp from which EIP-3198 rules begin, if activated by time.
+    pub eip3198_transition_timestamp: u64,
+    /// Timestamp from which EIP-3529 rules begin, if activated by time.
+    pub eip3529_transition_timestamp: u64,
+    /// Timestamp from which EIP-3541 rule begins, if activated by time.
+    pub eip3541_transition_timestamp: u64,
+    /// Timestamp from which EIP-3074 rules begin, if activated by time.
+    pub eip3074_transition_timestamp: u64,
+    /// Timestamp from which EIP-4895 beacon chain withdrawals are processed.
+    pub eip4895_transition_timestamp: u64,
     /// Number of first block where dust cleanup rules (EIP-168 and EIP169) begin.
     pub dust_protection_transition: BlockNumber,
     /// Nonce cap increase per block. Nonce cap is only checked if dust protection is enabled.
@@ -195,11 +214,22 @@ pub struct CommonParams {
     pub eip1559_fee_collector_transition: BlockNumber,
     /// Block at which zero gas price transactions start being checked with Certifier contract.
     pub validate_service_transactions_transition: BlockNumber,
+    /// Sender of system transactions. They are exempt from fee checks, but may only call the
+    /// `system_transaction_targets` and are bounded per block.
+    pub system_transaction_sender: Option<Address>,
+    /// Contracts system transactions are allowed to call.
+    pub system_transaction_targets: Vec<Address>,
+    /// Maximum number of system transactions in a block.
+    pub max_system_transactions_per_block: usize,
+    /// Block rewards replacing the engine's own from the first scheduled era on.
+    pub block_reward_schedule: Option<RewardSchedule>,
 }
 
 impl CommonParams {
-    /// Schedule for an EVM in the post-EIP-150-era of the Ethereum main net.
-    pub fn schedule(&self, block_number: u64) -> ::vm::Schedule {
+    /// Schedule for an EVM in the post-EIP-150-era of the Ethereum main net, for the block
+    /// with given number and timestamp. A transition applies once either its block number
+    /// or its timestamp has been reached.
+    pub fn schedule(&self, block_number: u64, timestamp: u64) -> ::vm::Schedule {
         if block_number < self.eip150_transition {
             ::vm::Schedule::new_homestead()
         } else {
@@ -211,11 +241,24 @@ impl CommonParams {
                 block_number >= self.eip161d_transition,
             );
 
-            self.update_schedule(block_number, &mut schedule);
+            self.update_schedule(block_number, timestamp, &mut schedule);
             schedule
         }
     }
 
+    /// Whether the block with given timestamp carries EIP-4895 withdrawals.
+    pub fn withdrawals_enabled(&self, timestamp: u64) -> bool {
+        timestamp >= self.eip4895_transition_timestamp
+    }
+
+    /// Parameters needed to decode headers of this chain.
+    pub fn header_decode_params(&self) -> HeaderDecodeParams {
+        HeaderDecodeParams {
+            eip1559_transition: self.eip1559_transition,
+            eip4895_transition_timestamp: self.eip4895_transition_timestamp,
+        }
+    }
+
     /// Returns max code size at given block.
     pub fn max_code_size(&self, block_number: u64) -> u64 {
         if block_number >= self.max_code_size_transition {
@@ -226,7 +269,12 @@ impl CommonParams {
     }
 
     /// Apply common spec config parameters to the schedule.
-    pub fn update_schedule(&self, block_number: u64, schedule: &mut ::vm::Schedule) {
+    pub fn update_schedule(
+        &self,
+        block_number: u64,
+        timestamp: u64,
+        schedule: &mut ::vm::Schedule,
+    ) {
         schedule.have_create2 = block_number >= self.eip1014_transition;
         schedule.have_revert = block_number >= self.eip140_transition;
         schedule.have_static_call = block_number >= self.eip214_transition;
@@ -241,9 +289,14 @@ impl CommonParams {
         schedule.have_subs = block_number >= self.eip2315_transition;
         schedule.eip2929 = block_number >= self.eip2929_transition;
         schedule.eip2930 = block_number >= self.eip2930_transition;
-        schedule.eip3541 = block_number >= self.eip3541_transition;
+        schedule.eip3541 = block_number >= self.eip3541_transition
+            || timestamp >= self.eip3541_transition_timestamp;
         schedule.eip1559 = block_number >= self.eip1559_transition;
-        schedule.eip3198 = block_number >= self.eip3198_transition;
+        schedule.eip3198 = block_number >= self.eip3198_transition
+            || timestamp >= self.eip3198_transition_timestamp;
+        schedule.eip4399 = block_number >= self.eip4399_transition;
+        schedule.eip3074 = block_number >= self.eip3074_transition
+            || timestamp >= self.eip3074_transition_timestamp;
         if schedule.eip1559 {
             schedule.eip1559_elasticity_multiplier = self.eip1559_elasticity_multiplier.as_usize();
 
@@ -283,7 +336,8 @@ impl CommonParams {
             schedule.sload_gas = ::vm::schedule::EIP2929_WARM_STORAGE_READ_COST;
             schedule.sstore_reset_gas = ::vm::schedule::EIP2929_SSTORE_RESET_GAS;
         }
-        if block_number >= self.eip3529_transition {
+        if block_number >= self.eip3529_transition || timestamp >= self.eip3529_transition_timestamp
+        {
             schedule.suicide_refund_gas = 0;
             schedule.sstore_refund_gas = ::vm::schedule::EIP3529_SSTORE_CLEARS_SCHEDULE;
             schedule.max_refund_quotient = ::vm::schedule::EIP3529_MAX_REFUND_QUOTIENT;
@@ -433,10 +487,31 @@ impl From<ethjson::spec::Params> for CommonParams {
             eip3541_transition: p
                 .eip3541_transition
                 .map_or_else(BlockNumber::max_value, Into::into),
+            eip3074_transition: p
+                .eip3074_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
             dust_protection_transition: p
                 .dust_protection_transition
                 .map_or_else(BlockNumber::max_value, Into::into),
             eip3607_transition: p.eip3607_transition.map_or(0, Into::into),
+            eip4399_transition: p
+                .eip4399_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip3198_transition_timestamp: p
+                .eip3198_transition_timestamp
+                .map_or_else(u64::max_value, Into::into),
+            eip3529_transition_timestamp: p
+                .eip3529_transition_timestamp
+                .map_or_else(u64::max_value, Into::into),
+            eip3541_transition_timestamp: p
+                .eip3541_transition_timestamp
+                .map_or_else(u64::max_value, Into::into),
+            eip3074_transition_timestamp: p
+                .eip3074_transition_timestamp
+                .map_or_else(u64::max_value, Into::into),
+            eip4895_transition_timestamp: p
+                .eip4895_transition_timestamp
+                .map_or_else(u64::max_value, Into::into),
             nonce_cap_increment: p.nonce_cap_increment.map_or(64, Into::into),
             remove_dust_contracts: p.remove_dust_contracts.unwrap_or(false),
             gas_limit_bound_divisor: p.gas_limit_bound_divisor.into(),
@@ -483,6 +558,17 @@ impl From<ethjson::spec::Params> for CommonParams {
             validate_service_transactions_transition: p
                 .validate_service_transactions_transition
                 .map_or_else(BlockNumber::max_value, Into::into),
+            system_transaction_sender: p.system_transaction_sender.map(Into::into),
+            system_transaction_targets: p
+                .system_transaction_targets
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            max_system_transactions_per_block: p
+                .max_system_transactions_per_block
+                .map_or(DEFAULT_MAX_SYSTEM_TRANSACTIONS_PER_BLOCK, Into::into),
+            block_reward_schedule: p.block_reward_schedule.map(Into::into),
         }
     }
 }
@@ -498,6 +584,8 @@ pub struct SpecParams<'a> {
     /// memory. This may get more fine-grained in the future but for now is simply a binary
     /// option.
     pub optimization_setting: Option<OptimizeFor>,
+    /// Fork transitions to apply on top of the loaded spec.
+    pub overlay: Option<&'a ethjson::spec::SpecOverlay>,
 }
 
 impl<'a> SpecParams<'a> {
@@ -506,6 +594,7 @@ impl<'a> SpecParams<'a> {
         SpecParams {
             cache_dir: path,
             optimization_setting: None,
+            overlay: None,
         }
     }
 
@@ -514,8 +603,14 @@ impl<'a> SpecParams<'a> {
         SpecParams {
             cache_dir: path,
             optimization_setting: Some(optimization),
+            overlay: None,
         }
     }
+
+    /// Apply the given overlay to the loaded spec.
+    pub fn with_overlay(self, overlay: Option<&'a ethjson::spec::SpecOverlay>) -> Self {
+        SpecParams { overlay, ..self }
+    }
 }
 
 impl<'a, T: AsRef<Path>> From<&'a T> for SpecParams<'a> {
@@ -563,6 +658,9 @@ pub struct Spec {
     /// List of hard forks in the network.
     pub hard_forks: BTreeSet<BlockNumber>,
 
+    /// Keccak hash of the JSON the spec was loaded from.
+    pub hash: H256,
+
     /// Contract constructors to be executed on genesis.
     constructors: Vec<(Address, Bytes)>,
 
@@ -592,6 +690,7 @@ impl Clone for Spec {
             extra_data: self.extra_data.clone(),
             seal_rlp: self.seal_rlp.clone(),
             hard_forks: self.hard_forks.clone(),
+            hash: self.hash,
             constructors: self.constructors.clone(),
             state_root_memo: RwLock::new(*self.state_root_memo.read()),
             genesis_state: self.genesis_state.clone(),
@@ -624,8 +723,43 @@ fn convert_json_to_spec(
     Ok((address.into(), builtin))
 }
 
+/// Serialize a JSON value without whitespace and with object keys in sorted order.
+fn canonical_json(value: &serde_json::Value, out: &mut Vec<u8>) {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            out.push(b'{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                canonical_json(&Value::String(key.clone()), out);
+                out.push(b':');
+                canonical_json(value, out);
+            }
+            out.push(b'}');
+        }
+        Value::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                canonical_json(item, out);
+            }
+            out.push(b']');
+        }
+        scalar => {
+            out.extend(serde_json::to_vec(scalar).expect("JSON scalars always serialize; qed"))
+        }
+    }
+}
+
 /// Load from JSON object.
-fn load_from(spec_params: SpecParams, s: ethjson::spec::Spec) -> Result<Spec, Error> {
+fn load_from(spec_params: SpecParams, s: ethjson::spec::Spec, hash: H256) -> Result<Spec, Error> {
     let builtins: Result<BTreeMap<Address, Builtin>, _> = s
         .accounts
         .builtins()
@@ -656,6 +790,7 @@ fn load_from(spec_params: SpecParams, s: ethjson::spec::Spec) -> Result<Spec, Er
         seal_rlp: seal_rlp,
         base_fee: g.base_fee,
         hard_forks,
+        hash,
         constructors: s
             .accounts
             .constructors()
@@ -751,6 +886,8 @@ impl Spec {
             params.eip3198_transition,
             params.eip3529_transition,
             params.eip3541_transition,
+            params.eip3074_transition,
+            params.eip4399_transition,
             params.dust_protection_transition,
             params.wasm_activation_transition,
             params.wasm_disable_transition,
@@ -813,6 +950,13 @@ impl Spec {
                 AuthorityRound::new(authority_round.params.into(), machine)
                     .expect("Failed to start AuthorityRound consensus engine.")
             }
+            ethjson::spec::Engine::Tendermint(tendermint) => {
+                Tendermint::new(tendermint.params.into(), machine)
+                    .expect("Failed to start the Tendermint consensus engine.")
+            }
+            ethjson::spec::Engine::External(external) => {
+                Arc::new(ExternalEngine::new(external.params.into(), machine))
+            }
         };
 
         // Dummy value is a filler for non-existent transitions
@@ -859,6 +1003,7 @@ impl Spec {
                 gas_used: U256::zero(),
                 gas_limit: U256::max_value(),
                 base_fee: None,
+                random: None,
             };
 
             if !self.constructors.is_empty() {
@@ -886,7 +1031,7 @@ impl Spec {
 
                     {
                         let machine = self.engine.machine();
-                        let schedule = machine.schedule(env_info.number);
+                        let schedule = machine.schedule(env_info.number, env_info.timestamp);
                         let mut exec = Executive::new(&mut state, &env_info, &machine, &schedule);
                         if let Err(e) =
                             exec.create(params, &mut substate, &mut NoopTracer, &mut NoopVMTracer)
@@ -968,6 +1113,9 @@ impl Spec {
             r.iter().map(|f| f.as_raw().to_vec()).collect()
         });
         header.set_base_fee(self.base_fee.clone());
+        if self.engine.params().withdrawals_enabled(self.timestamp) {
+            header.set_withdrawals_root(Some(KECCAK_NULL_RLP));
+        }
         trace!(target: "spec", "Header hash is {}", header.hash());
         header
     }
@@ -976,10 +1124,14 @@ impl Spec {
     pub fn genesis_block(&self) -> Bytes {
         let empty_list = RlpStream::new_list(0).out();
         let header = self.genesis_header();
-        let mut ret = RlpStream::new_list(3);
+        let with_withdrawals = header.withdrawals_root().is_some();
+        let mut ret = RlpStream::new_list(3 + with_withdrawals as usize);
         ret.append(&header);
         ret.append_raw(&empty_list, 1);
         ret.append_raw(&empty_list, 1);
+        if with_withdrawals {
+            ret.append_raw(&empty_list, 1);
+        }
         ret.out()
     }
 
@@ -1044,13 +1196,30 @@ impl Spec {
 
     /// Loads spec from json file. Provide factories for executing contracts and ensuring
     /// storage goes to the right place.
-    pub fn load<'a, T: Into<SpecParams<'a>>, R>(params: T, reader: R) -> Result<Self, String>
+    pub fn load<'a, T: Into<SpecParams<'a>>, R>(params: T, mut reader: R) -> Result<Self, String>
     where
         R: Read,
     {
-        ethjson::spec::Spec::load(reader)
-            .map_err(fmt_err)
-            .and_then(|x| load_from(params.into(), x).map_err(fmt_err))
+        let mut json = Vec::new();
+        reader
+            .read_to_end(&mut json)
+            .map_err(|e| format!("Unable to read spec json: {}", e))?;
+        let params = params.into();
+        let mut value: serde_json::Value = serde_json::from_slice(&json).map_err(fmt_err)?;
+        if let Some(overlay) = params.overlay {
+            overlay.apply(&mut value)?;
+        }
+        // the spec is identified by its content after the overlay, independent of formatting
+        // and key order
+        let mut identity = Vec::new();
+        canonical_json(&value, &mut identity);
+        let hash = keccak(&identity);
+        let spec = match params.overlay {
+            Some(_) => serde_json::from_value(value),
+            None => ethjson::spec::Spec::load(&json[..]),
+        };
+        spec.map_err(fmt_err)
+            .and_then(|x| load_from(params, x, hash).map_err(fmt_err))
     }
 
     /// initialize genesis epoch data, using in-memory database for
@@ -1081,6 +1250,7 @@ impl Spec {
                 last_hashes: Arc::new(Vec::new()),
                 gas_used: 0.into(),
                 base_fee: genesis.base_fee(),
+                random: genesis.mix_hash(),
             };
 
             let from = Address::default();
@@ -1116,6 +1286,14 @@ impl Spec {
         load_bundled!("instant_seal")
     }
 
+    /// Create a new InstantSeal Spec loaded with the given params, e.g. with an overlay.
+    pub fn new_instant_with_params<'a, T: Into<SpecParams<'a>>>(params: T) -> Result<Spec, String> {
+        Spec::load(
+            params,
+            include_bytes!("../../res/chainspec/instant_seal.json") as &[u8],
+        )
+    }
+
     /// Create a new Spec which conforms to the Frontier-era Morden chain except that it's a
     /// NullEngine consensus.
     #[cfg(any(test, feature = "test-helpers"))]
@@ -1175,6 +1353,14 @@ impl Spec {
         load_bundled!("test/authority_round")
     }
 
+    /// Create a new Spec with Tendermint consensus which does internal sealing (not requiring
+    /// work).
+    /// Accounts with secrets keccak("0") and keccak("1") are the validators.
+    #[cfg(any(test, feature = "test-helpers"))]
+    pub fn new_test_tendermint() -> Self {
+        load_bundled!("test/tendermint")
+    }
+
     /// Create a new Spec with AuthorityRound consensus which does internal sealing (not
     /// requiring work) with empty step messages enabled.
     /// Accounts with secrets keccak("0") and keccak("1") are the validators.
@@ -1230,6 +1416,7 @@ impl Spec {
 mod tests {
     use super::*;
     use ethereum_types::{H160, H256};
+    use spec::SpecOverlay;
     use state::State;
     use std::str::FromStr;
     use tempdir::TempDir;
@@ -1242,6 +1429,53 @@ mod tests {
         assert!(Spec::load(&tempdir.path(), &[] as &[u8]).is_err());
     }
 
+    #[test]
+    fn test_hash_ignores_formatting() {
+        let tempdir = TempDir::new("").unwrap();
+        let json = include_bytes!("../../res/chainspec/test/null_morden.json");
+        let value: serde_json::Value = serde_json::from_slice(&json[..]).unwrap();
+        let compact = serde_json::to_vec(&value).unwrap();
+        let pretty = serde_json::to_vec_pretty(&value).unwrap();
+
+        let original = Spec::load(&tempdir.path(), &json[..]).unwrap();
+        assert_eq!(
+            Spec::load(&tempdir.path(), &compact[..]).unwrap().hash,
+            original.hash
+        );
+        assert_eq!(
+            Spec::load(&tempdir.path(), &pretty[..]).unwrap().hash,
+            original.hash
+        );
+    }
+
+    #[test]
+    fn test_load_with_overlay() {
+        let tempdir = TempDir::new("").unwrap();
+        let json = include_bytes!("../../res/chainspec/test/null_morden.json");
+        let overlay: SpecOverlay = serde_json::from_str(
+            r#"{ "params": { "eip140Transition": "0x2a", "eip155Transition": null } }"#,
+        )
+        .unwrap();
+
+        let plain = Spec::load(&tempdir.path(), &json[..]).unwrap();
+        let spec = Spec::load(
+            SpecParams::from_path(tempdir.path()).with_overlay(Some(&overlay)),
+            &json[..],
+        )
+        .unwrap();
+        assert_eq!(spec.params().eip140_transition, 42);
+        assert_eq!(spec.params().eip155_transition, BlockNumber::max_value());
+        assert_ne!(spec.hash, plain.hash);
+
+        let invalid: SpecOverlay =
+            serde_json::from_str(r#"{ "params": { "eip9999Transition": "0x1" } }"#).unwrap();
+        assert!(Spec::load(
+            SpecParams::from_path(tempdir.path()).with_overlay(Some(&invalid)),
+            &json[..],
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_chain() {
         let test_spec = Spec::new_test();
@@ -1281,4 +1515,23 @@ mod tests {
         assert_eq!(state.storage_at(&address, &H256::zero()).unwrap(), expected);
         assert_eq!(state.balance(&address).unwrap(), 1.into());
     }
+
+    #[test]
+    fn timestamp_transition_activates_schedule() {
+        let params = CommonParams {
+            eip3198_transition: BlockNumber::max_value(),
+            eip3198_transition_timestamp: 1_000,
+            eip3541_transition: 10,
+            eip3541_transition_timestamp: u64::max_value(),
+            ..Default::default()
+        };
+
+        assert!(!params.schedule(20, 999).eip3198);
+        assert!(params.schedule(20, 1_000).eip3198);
+        assert!(!params.schedule(20, 0).eip3198);
+
+        // block-activated transitions are unaffected by the timestamp
+        assert!(!params.schedule(9, 1_000).eip3541);
+        assert!(params.schedule(10, 0).eip3541);
+    }
 }
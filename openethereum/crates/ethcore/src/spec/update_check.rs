@@ -0,0 +1,295 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checks the running chain spec against the latest one announced on chain.
+//!
+//! The registrar entry `spec_registry` points at a contract exposing
+//! `latestSpec() returns (bytes32 specHash, uint256 activationBlock)`, where `specHash` is the
+//! keccak hash of the chain spec JSON nodes are expected to run from `activationBlock` on. The
+//! JSON is hashed in canonical form: without whitespace and with object keys in sorted order.
+
+use std::sync::{Arc, Weak};
+
+use call_contract::{CallContract, RegistryInfo};
+use client::{ChainInfo, ChainNotify, Client, NewBlocks};
+use ethabi::FunctionOutputDecoder;
+use ethereum_types::{H256, U256};
+use miner::Miner;
+use parking_lot::{Mutex, RwLock};
+use types::{ids::BlockId, BlockNumber};
+
+use_contract!(spec_registry, "res/contracts/spec_registry.json");
+
+/// Registrar name of the spec registry contract.
+pub const SPEC_REGISTRY_NAME: &str = "spec_registry";
+
+/// Spec update check configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpecUpdateConfig {
+    /// Number of blocks between two checks.
+    pub check_interval: BlockNumber,
+    /// Stop sealing once the announced spec is active and the node still runs an older one.
+    pub refuse_sealing: bool,
+}
+
+impl Default for SpecUpdateConfig {
+    fn default() -> Self {
+        SpecUpdateConfig {
+            check_interval: 100,
+            refuse_sealing: false,
+        }
+    }
+}
+
+/// Outcome of a spec update check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecUpdateStatus {
+    /// No check was performed yet.
+    Unknown,
+    /// The registry is not deployed or not registered.
+    NotConfigured,
+    /// The running spec is the announced one.
+    UpToDate,
+    /// A different spec is announced and becomes active at the given block.
+    Scheduled {
+        /// Hash of the announced spec.
+        spec_hash: H256,
+        /// Block from which the announced spec is active.
+        activation_block: BlockNumber,
+    },
+    /// A different spec is already active.
+    Outdated {
+        /// Hash of the announced spec.
+        spec_hash: H256,
+        /// Block from which the announced spec is active.
+        activation_block: BlockNumber,
+    },
+}
+
+impl SpecUpdateStatus {
+    /// Compare the running spec with the announced record at the given best block.
+    pub fn evaluate(
+        running: &H256,
+        announced: &H256,
+        activation_block: BlockNumber,
+        best_block: BlockNumber,
+    ) -> Self {
+        if announced.is_zero() || announced == running {
+            SpecUpdateStatus::UpToDate
+        } else if best_block < activation_block {
+            SpecUpdateStatus::Scheduled {
+                spec_hash: *announced,
+                activation_block,
+            }
+        } else {
+            SpecUpdateStatus::Outdated {
+                spec_hash: *announced,
+                activation_block,
+            }
+        }
+    }
+}
+
+/// A `ChainNotify` implementation reading the announced spec from the registry every
+/// `check_interval` blocks.
+pub struct SpecUpdateChecker {
+    client: Weak<Client>,
+    miner: Weak<Miner>,
+    spec_hash: H256,
+    config: SpecUpdateConfig,
+    status: RwLock<SpecUpdateStatus>,
+    last_check: Mutex<Option<BlockNumber>>,
+}
+
+/// Whether a check is due at `best_block` given the block of the last check.
+fn check_due(
+    last_check: Option<BlockNumber>,
+    best_block: BlockNumber,
+    interval: BlockNumber,
+) -> bool {
+    match last_check {
+        None => true,
+        // the chain was reorganized below the last check
+        Some(last) if best_block < last => true,
+        Some(last) => best_block - last >= interval,
+    }
+}
+
+impl SpecUpdateChecker {
+    /// Create a new checker for a node running the spec with the given hash.
+    pub fn new(
+        client: &Arc<Client>,
+        miner: &Arc<Miner>,
+        spec_hash: H256,
+        config: SpecUpdateConfig,
+    ) -> Self {
+        SpecUpdateChecker {
+            client: Arc::downgrade(client),
+            miner: Arc::downgrade(miner),
+            spec_hash,
+            config,
+            status: RwLock::new(SpecUpdateStatus::Unknown),
+            last_check: Mutex::new(None),
+        }
+    }
+
+    /// Result of the last check.
+    pub fn status(&self) -> SpecUpdateStatus {
+        self.status.read().clone()
+    }
+
+    /// Read the registry record and update the status.
+    pub fn check(&self) {
+        let client = match self.client.upgrade() {
+            Some(client) => client,
+            None => return,
+        };
+        let best_block = client.chain_info().best_block_number;
+
+        let status = match self.latest_spec(&*client) {
+            Ok(Some((announced, activation_block))) => SpecUpdateStatus::evaluate(
+                &self.spec_hash,
+                &announced,
+                activation_block,
+                best_block,
+            ),
+            Ok(None) => SpecUpdateStatus::NotConfigured,
+            Err(e) => {
+                warn!(target: "spec", "Unable to read the spec registry: {}", e);
+                return;
+            }
+        };
+
+        let previous = ::std::mem::replace(&mut *self.status.write(), status.clone());
+        match status {
+            SpecUpdateStatus::Scheduled {
+                spec_hash,
+                activation_block,
+            } => {
+                warn!(target: "spec", "A new chain spec {:?} becomes active at block #{}, this node runs {:?}. Please update the chain spec before that block.", spec_hash, activation_block, self.spec_hash);
+            }
+            SpecUpdateStatus::Outdated {
+                spec_hash,
+                activation_block,
+            } => {
+                error!(target: "spec", "Chain spec {:?} is active since block #{}, this node runs the outdated spec {:?}. Please update the chain spec.", spec_hash, activation_block, self.spec_hash);
+            }
+            SpecUpdateStatus::UpToDate if previous != SpecUpdateStatus::UpToDate => {
+                info!(target: "spec", "Running chain spec matches the spec registry");
+            }
+            SpecUpdateStatus::NotConfigured if previous != SpecUpdateStatus::NotConfigured => {
+                debug!(target: "spec", "No spec registry is registered, skipping spec update check");
+            }
+            _ => {}
+        }
+
+        if self.config.refuse_sealing {
+            if let Some(miner) = self.miner.upgrade() {
+                let outdated = match self.status() {
+                    SpecUpdateStatus::Outdated { .. } => Some("chain spec is outdated".to_owned()),
+                    _ => None,
+                };
+                miner.set_sealing_blocked(outdated);
+            }
+        }
+    }
+
+    fn latest_spec<C: CallContract + RegistryInfo>(
+        &self,
+        client: &C,
+    ) -> Result<Option<(H256, BlockNumber)>, String> {
+        let address = match client.registry_address(SPEC_REGISTRY_NAME.to_owned(), BlockId::Latest)
+        {
+            Some(address) => address,
+            None => return Ok(None),
+        };
+        let (data, decoder) = spec_registry::functions::latest_spec::call();
+        let value = client.call_contract(BlockId::Latest, address, data)?;
+        let (spec_hash, activation_block) = decoder.decode(&value).map_err(|e| e.to_string())?;
+        if activation_block > U256::from(BlockNumber::max_value()) {
+            return Err(format!(
+                "activation block {} is out of range",
+                activation_block
+            ));
+        }
+        Ok(Some((spec_hash, activation_block.as_u64())))
+    }
+}
+
+impl ChainNotify for SpecUpdateChecker {
+    fn new_blocks(&self, new_blocks: NewBlocks) {
+        if new_blocks.has_more_blocks_to_import || new_blocks.route.enacted().is_empty() {
+            return;
+        }
+
+        let best_block = match self.client.upgrade() {
+            Some(client) => client.chain_info().best_block_number,
+            None => return,
+        };
+        {
+            let mut last_check = self.last_check.lock();
+            if !check_due(*last_check, best_block, self.config.check_interval) {
+                return;
+            }
+            *last_check = Some(best_block);
+        }
+        self.check();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_due, SpecUpdateStatus};
+    use ethereum_types::H256;
+
+    #[test]
+    fn checks_after_skipped_blocks() {
+        assert!(check_due(None, 5, 100));
+        assert!(!check_due(Some(5), 104, 100));
+        // a batch import may skip the block at the exact interval
+        assert!(check_due(Some(5), 107, 100));
+        assert!(check_due(Some(107), 50, 100));
+    }
+
+    #[test]
+    fn evaluates_announced_spec() {
+        let running = H256::from_low_u64_be(1);
+        let announced = H256::from_low_u64_be(2);
+
+        assert_eq!(
+            SpecUpdateStatus::evaluate(&running, &running, 100, 50),
+            SpecUpdateStatus::UpToDate
+        );
+        assert_eq!(
+            SpecUpdateStatus::evaluate(&running, &H256::zero(), 100, 150),
+            SpecUpdateStatus::UpToDate
+        );
+        assert_eq!(
+            SpecUpdateStatus::evaluate(&running, &announced, 100, 99),
+            SpecUpdateStatus::Scheduled {
+                spec_hash: announced,
+                activation_block: 100,
+            }
+        );
+        assert_eq!(
+            SpecUpdateStatus::evaluate(&running, &announced, 100, 100),
+            SpecUpdateStatus::Outdated {
+                spec_hash: announced,
+                activation_block: 100,
+            }
+        );
+    }
+}
@@ -218,6 +218,16 @@ impl Account {
         self.storage_root = KECCAK_NULL_RLP;
     }
 
+    /// Replace this account's storage with given values.
+    pub fn reset_storage(&mut self, storage: HashMap<H256, H256>) {
+        self.storage_cache = Self::empty_storage_cache();
+        self.storage_changes = storage;
+        if self.storage_root != KECCAK_NULL_RLP {
+            self.original_storage_cache = Some((self.storage_root, Self::empty_storage_cache()));
+        }
+        self.storage_root = KECCAK_NULL_RLP;
+    }
+
     /// Set (and cache) the contents of the trie's storage at `key` to `value`.
     pub fn set_storage(&mut self, key: H256, value: H256) {
         self.storage_changes.insert(key, value);
@@ -515,6 +525,16 @@ impl Account {
         self.nonce = self.nonce.saturating_add(U256::from(1u8));
     }
 
+    /// Set the nonce of the account.
+    pub fn set_nonce(&mut self, nonce: U256) {
+        self.nonce = nonce;
+    }
+
+    /// Set the balance of the account.
+    pub fn set_balance(&mut self, balance: U256) {
+        self.balance = balance;
+    }
+
     /// Increase account balance.
     pub fn add_balance(&mut self, x: &U256) {
         self.balance = self.balance.saturating_add(*x);
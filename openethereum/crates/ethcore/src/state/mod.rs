@@ -40,6 +40,7 @@ use types::{
     basic_account::BasicAccount,
     receipt::{LegacyReceipt, TransactionOutcome, TypedReceipt},
     state_diff::StateDiff,
+    state_override::StateOverride,
     transaction::SignedTransaction,
 };
 
@@ -71,6 +72,8 @@ pub struct ApplyOutcome<T, V> {
     pub trace: Vec<T>,
     /// The VM trace for the applied transaction, None if tracing was not produced.
     pub vm_trace: Option<V>,
+    /// The exception the execution of the transaction ended with, if any.
+    pub exception: Option<vm::Error>,
 }
 
 /// Result type for the execution ("application") of a transaction.
@@ -314,6 +317,8 @@ pub struct State<B> {
     checkpoints: RefCell<Vec<HashMap<Address, Option<AccountEntry>>>>,
     account_start_nonce: U256,
     factories: Factories,
+    // Accounts and storage slots accessed since `record_accesses` was called.
+    accessed: RefCell<Option<BTreeMap<Address, BTreeSet<H256>>>>,
 }
 
 #[derive(Copy, Clone)]
@@ -384,6 +389,7 @@ impl<B: Backend> State<B> {
             checkpoints: RefCell::new(Vec::new()),
             account_start_nonce: account_start_nonce,
             factories: factories,
+            accessed: RefCell::new(None),
         }
     }
 
@@ -405,6 +411,7 @@ impl<B: Backend> State<B> {
             checkpoints: RefCell::new(Vec::new()),
             account_start_nonce: account_start_nonce,
             factories: factories,
+            accessed: RefCell::new(None),
         };
 
         Ok(state)
@@ -702,6 +709,8 @@ impl<B: Backend> State<B> {
         FCachedStorageAt: Fn(&Account, &H256) -> Option<H256>,
         FStorageAt: Fn(&Account, &dyn HashDB<KeccakHasher, DBValue>, &H256) -> TrieResult<H256>,
     {
+        self.note_access(address, Some(key));
+
         // Storage key search and update works like this:
         // 1. If there's an entry for the account in the local cache check for the key and return it if found.
         // 2. If there's an entry for the account in the global cache check for the key or load it into that account.
@@ -877,6 +886,34 @@ impl<B: Backend> State<B> {
         Ok(())
     }
 
+    /// Replace the balance, nonce, code or storage of the given accounts, creating
+    /// accounts which do not exist. `state_diff` is applied on top of `state`.
+    pub fn apply_overrides(&mut self, overrides: &StateOverride) -> TrieResult<()> {
+        for (address, account) in overrides {
+            if let Some(ref code) = account.code {
+                self.reset_code(address, code.clone())?;
+            }
+            {
+                let mut entry = self.require(address, false)?;
+                if let Some(balance) = account.balance {
+                    entry.set_balance(balance);
+                }
+                if let Some(nonce) = account.nonce {
+                    entry.set_nonce(nonce);
+                }
+                if let Some(ref storage) = account.state {
+                    entry.reset_storage(storage.iter().map(|(k, v)| (*k, *v)).collect());
+                }
+            }
+            if let Some(ref diff) = account.state_diff {
+                for (key, value) in diff {
+                    self.set_storage(address, *key, *value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Initialise the code of account `a` so that it is `code`.
     /// NOTE: Account should have been created with `new_contract`.
     pub fn init_code(&mut self, a: &Address, code: Bytes) -> TrieResult<()> {
@@ -966,6 +1003,7 @@ impl<B: Backend> State<B> {
             output,
             trace: e.trace,
             vm_trace: e.vm_trace,
+            exception: e.exception,
         })
     }
 
@@ -985,7 +1023,7 @@ impl<B: Backend> State<B> {
         T: trace::Tracer,
         V: trace::VMTracer,
     {
-        let schedule = machine.schedule(env_info.number);
+        let schedule = machine.schedule(env_info.number, env_info.timestamp);
         let mut e = Executive::new(self, env_info, machine, &schedule);
 
         match virt {
@@ -1266,6 +1304,48 @@ impl<B: Backend> State<B> {
         )?))
     }
 
+    /// Starts recording the accounts and storage slots accessed through this state.
+    pub fn record_accesses(&mut self) {
+        *self.accessed.get_mut() = Some(BTreeMap::new());
+    }
+
+    fn note_access(&self, address: &Address, key: Option<&H256>) {
+        if let Some(ref mut accessed) = *self.accessed.borrow_mut() {
+            let keys = accessed.entry(*address).or_insert_with(BTreeSet::new);
+            if let Some(key) = key {
+                keys.insert(*key);
+            }
+        }
+    }
+
+    /// Returns the accounts and storage slots accessed through `self` since `record_accesses`
+    /// was called, as they are in `orig`. Accounts missing from `orig` are left out.
+    pub fn prestate_from<X: Backend>(&self, orig: State<X>) -> TrieResult<PodState> {
+        let accessed = self.accessed.borrow().clone().unwrap_or_default();
+        let mut pod = BTreeMap::new();
+        for (address, keys) in accessed {
+            let account = orig.ensure_cached(&address, RequireCache::Code, |acc| {
+                acc.map(|acc| (*acc.balance(), *acc.nonce(), acc.code().map(|x| x.to_vec())))
+            })?;
+            if let Some((balance, nonce, code)) = account {
+                let mut storage = BTreeMap::new();
+                for key in keys {
+                    storage.insert(key, orig.storage_at(&address, &key)?);
+                }
+                pod.insert(
+                    address,
+                    PodAccount {
+                        balance,
+                        nonce,
+                        storage,
+                        code,
+                    },
+                );
+            }
+        }
+        Ok(PodState::from(pod))
+    }
+
     /// Returns a `StateDiff` describing the difference from `orig` to `self`.
     /// Consumes self.
     pub fn diff_from<X: Backend>(&self, mut orig: State<X>) -> TrieResult<StateDiff> {
@@ -1321,6 +1401,8 @@ impl<B: Backend> State<B> {
     where
         F: Fn(Option<&Account>) -> U,
     {
+        self.note_access(a, None);
+
         // check local cache first
         if let Some(ref mut maybe_acc) = self.cache.borrow_mut().get_mut(a) {
             if let Some(ref mut account) = maybe_acc.account {
@@ -1401,6 +1483,8 @@ impl<B: Backend> State<B> {
         F: FnOnce() -> Account,
         G: FnOnce(&mut Account),
     {
+        self.note_access(a, None);
+
         let contains_key = self.cache.borrow().contains_key(a);
         if !contains_key {
             match self.db.get_cached_account(a) {
@@ -1567,6 +1651,7 @@ impl Clone for State<StateDB> {
             checkpoints: RefCell::new(Vec::new()),
             account_start_nonce: self.account_start_nonce.clone(),
             factories: self.factories.clone(),
+            accessed: RefCell::new(None),
         }
     }
 }
@@ -2621,6 +2706,48 @@ mod tests {
         assert_eq!(state.code(&a).unwrap(), Some(Arc::new(vec![1u8, 2, 3])));
     }
 
+    #[test]
+    fn should_apply_overrides() {
+        use types::state_override::AccountOverride;
+
+        let a = Address::from_low_u64_be(1);
+        let b = Address::from_low_u64_be(2);
+        let key = |n: u64| -> H256 { BigEndianHash::from_uint(&U256::from(n)) };
+        let mut state = get_temp_state();
+        state.set_storage(&a, key(1), key(10)).unwrap();
+        state.set_storage(&a, key(2), key(20)).unwrap();
+        state.set_storage(&b, key(1), key(10)).unwrap();
+        state.commit().unwrap();
+
+        let mut overrides = StateOverride::new();
+        overrides.insert(
+            a,
+            AccountOverride {
+                balance: Some(69.into()),
+                nonce: Some(7.into()),
+                code: Some(vec![1, 2, 3]),
+                state: Some(vec![(key(2), key(22))].into_iter().collect()),
+                ..Default::default()
+            },
+        );
+        overrides.insert(
+            b,
+            AccountOverride {
+                state_diff: Some(vec![(key(2), key(22))].into_iter().collect()),
+                ..Default::default()
+            },
+        );
+        state.apply_overrides(&overrides).unwrap();
+
+        assert_eq!(state.balance(&a).unwrap(), 69.into());
+        assert_eq!(state.nonce(&a).unwrap(), 7.into());
+        assert_eq!(state.code(&a).unwrap(), Some(Arc::new(vec![1u8, 2, 3])));
+        assert_eq!(state.storage_at(&a, &key(1)).unwrap(), H256::zero());
+        assert_eq!(state.storage_at(&a, &key(2)).unwrap(), key(22));
+        assert_eq!(state.storage_at(&b, &key(1)).unwrap(), key(10));
+        assert_eq!(state.storage_at(&b, &key(2)).unwrap(), key(22));
+    }
+
     #[test]
     fn storage_at_from_database() {
         let a = Address::zero();
@@ -3459,6 +3586,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_report_prestate_of_accessed_storage() {
+        let a = Address::from_low_u64_be(10);
+        let b = Address::from_low_u64_be(11);
+        let slot = |n: u64| -> H256 { BigEndianHash::from_uint(&U256::from(n)) };
+        let db = get_temp_state_db();
+
+        let (root, db) = {
+            let mut state = State::new(db, U256::from(0), Default::default());
+            state
+                .add_balance(&a, &100.into(), CleanupMode::ForceCreate)
+                .unwrap();
+            state.set_storage(&a, slot(1), slot(20)).unwrap();
+            state.set_storage(&a, slot(2), slot(30)).unwrap();
+            state.commit().unwrap();
+            state.drop()
+        };
+
+        let mut state =
+            State::from_existing(db, root, U256::from(0u8), Default::default()).unwrap();
+        let original = state.clone();
+        state.record_accesses();
+        assert_eq!(state.balance(&b).unwrap(), U256::zero());
+        assert_eq!(state.storage_at(&a, &slot(2)).unwrap(), slot(30));
+        state.set_storage(&a, slot(1), slot(100)).unwrap();
+
+        let prestate = state.prestate_from(original).unwrap();
+        assert_eq!(prestate.get().len(), 1);
+        assert_eq!(
+            prestate.get().get(&a),
+            Some(&PodAccount {
+                balance: U256::from(100),
+                nonce: U256::zero(),
+                code: Some(Default::default()),
+                storage: vec![(slot(1), slot(20)), (slot(2), slot(30))]
+                    .into_iter()
+                    .collect(),
+            })
+        );
+    }
+
     #[cfg(feature = "to-pod-full")]
     #[test]
     fn should_get_full_pod_storage_values() {
@@ -429,6 +429,36 @@ pub fn new_db() -> Arc<dyn BlockChainDB> {
     Arc::new(db)
 }
 
+/// `BlockChainDB` serving `db` through an `ethcore_db::ReadOnlyDatabase`.
+struct ReadOnlyBlockChainDB {
+    db: Arc<dyn BlockChainDB>,
+    key_value: Arc<dyn KeyValueDB>,
+}
+
+impl BlockChainDB for ReadOnlyBlockChainDB {
+    fn key_value(&self) -> &Arc<dyn KeyValueDB> {
+        &self.key_value
+    }
+
+    fn blooms(&self) -> &blooms_db::Database {
+        self.db.blooms()
+    }
+
+    fn trace_blooms(&self) -> &blooms_db::Database {
+        self.db.trace_blooms()
+    }
+}
+
+impl stats::PrometheusMetrics for ReadOnlyBlockChainDB {
+    fn prometheus_metrics(&self, _: &mut stats::PrometheusRegistry) {}
+}
+
+/// Creates a read-only view of `db`, rejecting every write.
+pub fn read_only_db(db: Arc<dyn BlockChainDB>) -> Arc<dyn BlockChainDB> {
+    let key_value = Arc::new(ethcore_db::ReadOnlyDatabase::new(db.key_value().clone()));
+    Arc::new(ReadOnlyBlockChainDB { db, key_value })
+}
+
 /// Creates a new temporary `BlockChainDB` on FS
 pub fn new_temp_db(tempdir: &Path) -> Arc<dyn BlockChainDB> {
     let blooms_dir = TempDir::new("").unwrap();
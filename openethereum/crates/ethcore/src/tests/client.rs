@@ -17,40 +17,126 @@
 use std::{
     str::{from_utf8, FromStr},
     sync::Arc,
+    thread,
+    time::Duration,
 };
 
 use client::{
     traits::{
         BlockChainClient, BlockChainReset, BlockInfo, ChainInfo, ImportBlock, ImportExportBlocks,
     },
-    Client, ClientConfig, ImportSealedBlock, PrepareOpenBlock,
+    BackupStatus, CallAnalytics, ChainNotify, Client, ClientConfig, ExportStateOptions,
+    ImportSealedBlock, PrepareOpenBlock, RefusedReorg, Reorg, ReorgBlock, ReplayOptions,
 };
 use crypto::publickey::KeyPair;
 use ethereum;
-use ethereum_types::{Address, U256};
+use ethereum_types::{Address, H256, U256};
 use executive::{Executive, TransactOptions};
 use hash::keccak;
 use io::IoChannel;
 use miner::{Miner, MinerService, PendingOrdering};
-use rustc_hex::ToHex;
+use parking_lot::Mutex;
+use rustc_hex::{FromHex, ToHex};
 use spec::Spec;
 use state::{self, CleanupMode, State, StateInfo};
+use stats::{PrometheusMetrics, PrometheusRegistry};
 use tempdir::TempDir;
 use test_helpers::{
-    self, generate_dummy_client, generate_dummy_client_with_data, get_bad_state_dummy_block,
-    get_good_dummy_block, get_good_dummy_block_seq, get_test_client_with_blocks,
-    push_blocks_to_client,
+    self, create_test_block, generate_dummy_client, generate_dummy_client_with_data,
+    get_bad_state_dummy_block, get_good_dummy_block, get_good_dummy_block_seq,
+    get_test_client_with_blocks, push_block_with_transactions, push_blocks_to_client,
 };
 use types::{
     data_format::DataFormat,
     filter::Filter,
-    ids::BlockId,
+    header::Header,
+    ids::{BlockId, TransactionId},
     transaction::{Action, Condition, PendingTransaction, Transaction, TypedTransaction},
     view,
     views::BlockView,
 };
 use verification::queue::kind::blocks::Unverified;
 
+#[test]
+fn replays_with_overridden_block_environment() {
+    let client = generate_dummy_client_with_data(2, 1, &[0.into()]);
+    let block = BlockId::Number(2);
+    let analytics = CallAnalytics::default();
+
+    let plain: Vec<_> = client
+        .replay_block_transactions(block, analytics, ReplayOptions::default())
+        .unwrap()
+        .collect();
+    assert_eq!(plain.len(), 1);
+
+    let shifted = ReplayOptions {
+        timestamp: Some(1_000_000),
+        number: Some(100),
+        ..Default::default()
+    };
+    let replayed: Vec<_> = client
+        .replay_block_transactions(block, analytics, shifted)
+        .unwrap()
+        .collect();
+    assert_eq!(replayed.len(), 1);
+    assert_eq!(replayed[0].0, plain[0].0);
+    assert_eq!(replayed[0].1.gas_used, plain[0].1.gas_used);
+
+    let starved = ReplayOptions {
+        gas_limit: Some(1.into()),
+        ..Default::default()
+    };
+    assert!(client
+        .replay_block_transactions(block, analytics, starved)
+        .is_err());
+    assert!(client
+        .replay(TransactionId::Location(block, 0), analytics, starved)
+        .is_err());
+}
+
+#[test]
+fn read_only_client_refuses_imports() {
+    let db = test_helpers::new_db();
+    let spec = Spec::new_test();
+    let mut config = ClientConfig::default();
+    config.read_only = true;
+
+    // a read-only client only attaches to an initialized database
+    assert!(Client::new(
+        config.clone(),
+        &spec,
+        db.clone(),
+        Arc::new(Miner::new_for_tests(&spec, None)),
+        IoChannel::disconnected(),
+    )
+    .is_err());
+
+    Client::new(
+        ClientConfig::default(),
+        &spec,
+        db.clone(),
+        Arc::new(Miner::new_for_tests(&spec, None)),
+        IoChannel::disconnected(),
+    )
+    .unwrap();
+    let client = Client::new(
+        config,
+        &spec,
+        db,
+        Arc::new(Miner::new_for_tests(&spec, None)),
+        IoChannel::disconnected(),
+    )
+    .unwrap();
+
+    let block =
+        Unverified::from_rlp(get_good_dummy_block(), spec.params().eip1559_transition).unwrap();
+    assert!(client.import_block(block).is_err());
+    client.flush_queue();
+    client.import_verified_blocks();
+    assert_eq!(client.chain_info().best_block_number, 0);
+    assert!(client.reset_to(BlockId::Number(0)).is_err());
+}
+
 #[test]
 fn imports_from_empty() {
     let db = test_helpers::new_db();
@@ -235,6 +321,17 @@ fn can_generate_gas_price_median() {
     assert_eq!(Some(&U256::from(3)), client.gas_price_corpus(3).median());
 }
 
+#[test]
+fn returns_block_fees() {
+    let client = generate_dummy_client_with_data(3, 1, slice_into![1, 2, 3]);
+
+    let fees = client.block_fees(BlockId::Number(2)).unwrap();
+    assert_eq!(fees.number, 2);
+    assert_eq!(Some(fees.hash), client.block_hash(BlockId::Number(2)));
+    assert_eq!(client.block_fees(BlockId::Number(2)), Some(fees));
+    assert_eq!(client.block_fees(BlockId::Number(10)), None);
+}
+
 #[test]
 fn can_generate_gas_price_histogram() {
     let client = generate_dummy_client_with_data(
@@ -293,6 +390,307 @@ fn can_handle_long_fork() {
     assert_eq!(2000, client.chain_info().best_block_number);
 }
 
+#[test]
+fn refuses_reorgs_deeper_than_max_depth() {
+    #[derive(Default)]
+    struct RefusedReorgs(Mutex<Vec<RefusedReorg>>);
+
+    impl ChainNotify for RefusedReorgs {
+        fn reorg_refused(&self, reorg: &RefusedReorg) {
+            self.0.lock().push(reorg.clone());
+        }
+    }
+
+    let spec = Spec::new_test();
+    let client = Client::new(
+        ClientConfig {
+            max_reorg_depth: Some(2),
+            ..Default::default()
+        },
+        &spec,
+        test_helpers::new_db(),
+        Arc::new(Miner::new_for_tests(&spec, None)),
+        IoChannel::disconnected(),
+    )
+    .unwrap();
+    let notify = Arc::new(RefusedReorgs::default());
+    client.add_notify(notify.clone());
+
+    let import_branch = |parent: H256, parent_number: u64, salt: u64, count: u64| {
+        let mut hashes = vec![parent];
+        for number in parent_number + 1..parent_number + 1 + count {
+            let mut header = Header::new();
+            header.set_gas_limit(*spec.genesis_header().gas_limit());
+            header.set_difficulty(U256::from(0x20000));
+            header.set_timestamp(salt + number * 10);
+            header.set_number(number);
+            header.set_parent_hash(*hashes.last().unwrap());
+            header.set_state_root(*spec.genesis_header().state_root());
+            hashes.push(header.hash());
+
+            let block = test_helpers::create_test_block(&header);
+            client
+                .import_block(
+                    Unverified::from_rlp(block, spec.params().eip1559_transition).unwrap(),
+                )
+                .unwrap();
+        }
+        client.flush_queue();
+        client.import_verified_blocks();
+        hashes
+    };
+
+    let genesis = spec.genesis_header().hash();
+    let main = import_branch(genesis, 0, 0, 4);
+    assert_eq!(client.chain_info().best_block_hash, main[4]);
+
+    // retracts blocks #3 and #4, which is within the limit
+    let shallow = import_branch(main[2], 2, 1, 3);
+    assert_eq!(client.chain_info().best_block_hash, shallow[3]);
+
+    // would retract five blocks
+    let deep = import_branch(genesis, 0, 2, 6);
+    assert_eq!(client.chain_info().best_block_hash, shallow[3]);
+    assert!(client.block_header(BlockId::Hash(deep[6])).is_some());
+    assert_eq!(client.report().reorgs_refused, 1);
+    assert_eq!(
+        *notify.0.lock(),
+        vec![RefusedReorg {
+            best: shallow[3],
+            refused: deep[6],
+            ancestor: genesis,
+            depth: 5,
+        }]
+    );
+}
+
+#[test]
+fn notifies_committed_reorgs() {
+    #[derive(Default)]
+    struct Reorgs(Mutex<Vec<Reorg>>);
+
+    impl ChainNotify for Reorgs {
+        fn chain_reorganized(&self, reorg: &Reorg) {
+            self.0.lock().push(reorg.clone());
+        }
+    }
+
+    let spec = Spec::new_test();
+    let client = Client::new(
+        ClientConfig::default(),
+        &spec,
+        test_helpers::new_db(),
+        Arc::new(Miner::new_for_tests(&spec, None)),
+        IoChannel::disconnected(),
+    )
+    .unwrap();
+    let notify = Arc::new(Reorgs::default());
+    client.add_notify(notify.clone());
+
+    let import_branch = |parent: H256, parent_number: u64, salt: u64, count: u64| {
+        let mut hashes = vec![parent];
+        for number in parent_number + 1..parent_number + 1 + count {
+            let mut header = Header::new();
+            header.set_gas_limit(*spec.genesis_header().gas_limit());
+            header.set_difficulty(U256::from(0x20000));
+            header.set_timestamp(salt + number * 10);
+            header.set_number(number);
+            header.set_parent_hash(*hashes.last().unwrap());
+            header.set_state_root(*spec.genesis_header().state_root());
+            hashes.push(header.hash());
+
+            let block = test_helpers::create_test_block(&header);
+            client
+                .import_block(
+                    Unverified::from_rlp(block, spec.params().eip1559_transition).unwrap(),
+                )
+                .unwrap();
+        }
+        client.flush_queue();
+        client.import_verified_blocks();
+        hashes
+    };
+
+    let block = |number, hash| ReorgBlock { number, hash };
+    let main = import_branch(spec.genesis_header().hash(), 0, 0, 4);
+    assert!(notify.0.lock().is_empty());
+
+    let side = import_branch(main[2], 2, 1, 3);
+    assert_eq!(client.chain_info().best_block_hash, side[3]);
+    assert_eq!(
+        *notify.0.lock(),
+        vec![Reorg {
+            ancestor: block(2, main[2]),
+            retracted: vec![block(4, main[4]), block(3, main[3])],
+            enacted: vec![block(3, side[1]), block(4, side[2]), block(5, side[3])],
+            retracted_transactions: vec![],
+            enacted_transactions: vec![],
+        }]
+    );
+}
+
+#[test]
+fn expires_history_outside_retention_window() {
+    let spec = Spec::new_test();
+    let client = Client::new(
+        ClientConfig {
+            history: 8,
+            history_expiry: Some(2),
+            ..Default::default()
+        },
+        &spec,
+        test_helpers::new_db(),
+        Arc::new(Miner::new_for_tests(&spec, None)),
+        IoChannel::disconnected(),
+    )
+    .unwrap();
+
+    for block in get_good_dummy_block_seq(12) {
+        client
+            .import_block(Unverified::from_rlp(block, spec.params().eip1559_transition).unwrap())
+            .unwrap();
+    }
+    client.flush_queue();
+    client.import_verified_blocks();
+    assert_eq!(client.chain_info().best_block_number, 12);
+    assert_eq!(client.pruning_info().earliest_history, 0);
+
+    client.tick(true);
+
+    // the retention window never drops below the state history size
+    let pruning = client.pruning_info();
+    assert_eq!(pruning.earliest_history, 4);
+    assert_eq!(pruning.earliest_chain, 4);
+    assert!(client.block(BlockId::Number(3)).is_none());
+    assert!(client.block_header(BlockId::Number(3)).is_some());
+    assert!(client.block(BlockId::Number(4)).is_some());
+    assert!(client.block(BlockId::Number(0)).is_some());
+}
+
+#[test]
+fn regenerates_missing_state_from_ancestor() {
+    let spec = Spec::new_test();
+    let db = test_helpers::new_db();
+    let client = Client::new(
+        ClientConfig {
+            pruning: ::journaldb::Algorithm::Archive,
+            state_regeneration_limit: Some(1),
+            ..Default::default()
+        },
+        &spec,
+        db.clone(),
+        Arc::new(Miner::new_for_tests(&spec, None)),
+        IoChannel::disconnected(),
+    )
+    .unwrap();
+
+    let key = KeyPair::from_secret_slice(keccak("").as_bytes()).unwrap();
+    for nonce in 0..3u64 {
+        let tx = TypedTransaction::Legacy(Transaction {
+            nonce: nonce.into(),
+            gas_price: 0.into(),
+            gas: 100_000.into(),
+            action: Action::Create,
+            data: vec![],
+            value: 0.into(),
+        })
+        .sign(key.secret(), Some(spec.chain_id()));
+        push_block_with_transactions(&client, &[tx]);
+    }
+    let root_of = |number| {
+        client
+            .block_header(BlockId::Number(number))
+            .unwrap()
+            .state_root()
+    };
+    let forget_state = |number| {
+        let mut batch = db.key_value().transaction();
+        batch.delete(::db::COL_STATE, root_of(number).as_bytes());
+        db.key_value().write(batch).unwrap();
+    };
+
+    forget_state(2);
+    let state = client
+        .state_at(BlockId::Number(2))
+        .expect("state is regenerated from block #1");
+    assert_eq!(*state.root(), root_of(2));
+    assert_eq!(state.nonce(&key.address()).unwrap(), 2.into());
+
+    // regenerating from block #0 would replay more blocks than allowed
+    forget_state(1);
+    assert!(client.state_at(BlockId::Number(2)).is_none());
+}
+
+#[test]
+fn exports_state_as_spec_accounts() {
+    let spec = Spec::new_test();
+    let client = Client::new(
+        ClientConfig {
+            fat_db: true,
+            ..Default::default()
+        },
+        &spec,
+        test_helpers::new_db(),
+        Arc::new(Miner::new_for_tests(&spec, None)),
+        IoChannel::disconnected(),
+    )
+    .unwrap();
+
+    // stores 0x2a at slot 0 and deploys the single byte 0xff
+    let key = KeyPair::from_secret_slice(keccak("").as_bytes()).unwrap();
+    let tx = TypedTransaction::Legacy(Transaction {
+        nonce: 0.into(),
+        gas_price: 0.into(),
+        gas: 100_000.into(),
+        action: Action::Create,
+        data: "602a60005560ff60005360016000f3".from_hex().unwrap(),
+        value: 0.into(),
+    })
+    .sign(key.secret(), Some(spec.chain_id()));
+    push_block_with_transactions(&client, &[tx]);
+
+    let mut out = Vec::new();
+    let exported = client
+        .export_state(
+            BlockId::Latest,
+            Box::new(&mut out),
+            &ExportStateOptions::default(),
+        )
+        .unwrap();
+
+    let accounts: ::std::collections::BTreeMap<::ethjson::hash::Address, ::ethjson::spec::Account> =
+        ::serde_json::from_slice(&out).unwrap();
+    assert_eq!(accounts.len(), exported);
+    assert!(::serde_json::from_slice::<::ethjson::spec::State>(&out).is_ok());
+
+    let sender = &accounts[&::ethjson::hash::Address(key.address())];
+    assert_eq!(sender.nonce.map(Into::<U256>::into), Some(1.into()));
+    let contract = accounts
+        .values()
+        .find(|account| account.code.is_some())
+        .expect("the deployed contract is exported");
+    assert_eq!(contract.code.as_ref().unwrap().0, vec![0xff]);
+    let storage: Vec<(U256, U256)> = contract
+        .storage
+        .clone()
+        .unwrap()
+        .into_iter()
+        .map(|(k, v)| (k.into(), v.into()))
+        .collect();
+    assert_eq!(storage, vec![(0.into(), 0x2a.into())]);
+
+    let without_code = ExportStateOptions {
+        code: false,
+        storage: false,
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    client
+        .export_state(BlockId::Latest, Box::new(&mut out), &without_code)
+        .unwrap();
+    assert!(!String::from_utf8(out).unwrap().contains("\"code\""));
+}
+
 #[test]
 fn can_mine() {
     let dummy_blocks = get_good_dummy_block_seq(2);
@@ -474,7 +872,7 @@ fn transaction_proof() {
 
     let machine = test_spec.engine.machine();
     let env_info = client.latest_env_info();
-    let schedule = machine.schedule(env_info.number);
+    let schedule = machine.schedule(env_info.number, env_info.timestamp);
     let mut state = State::from_existing(backend, root, 0.into(), factories.clone()).unwrap();
     Executive::new(&mut state, &env_info, &machine, &schedule)
         .transact(
@@ -510,6 +908,88 @@ fn reset_blockchain() {
     assert!(client.block_header(BlockId::Number(15)).is_some());
 }
 
+#[test]
+fn reset_blockchain_to_side_chain_block() {
+    let client = get_test_client_with_blocks(get_good_dummy_block_seq(19));
+    let test_spec = Spec::new_test();
+
+    // fork off block 10 with a lighter branch, so it stays a side chain
+    let mut parent = client.block_hash(BlockId::Number(10)).unwrap();
+    let mut side_chain = Vec::new();
+    for number in 11..14u64 {
+        let mut header = Header::new();
+        header.set_gas_limit(*test_spec.genesis_header().gas_limit());
+        header.set_difficulty(U256::from(number) * U256([0, 1, 0, 0]));
+        header.set_timestamp(number * 10 + 5);
+        header.set_number(number);
+        header.set_parent_hash(parent);
+        header.set_state_root(*test_spec.genesis_header().state_root());
+        parent = header.hash();
+        side_chain.push(parent);
+
+        client
+            .import_block(
+                Unverified::from_rlp(
+                    create_test_block(&header),
+                    test_spec.params().eip1559_transition,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+    }
+    client.flush_queue();
+    client.import_verified_blocks();
+
+    let target = *side_chain.last().unwrap();
+    assert!(client.block_header(BlockId::Hash(target)).is_some());
+    assert_ne!(client.block_hash(BlockId::Number(13)), Some(target));
+    assert_eq!(client.chain_info().best_block_number, 20);
+
+    assert!(client.reset_to(BlockId::Hash(target)).is_ok());
+
+    client.chain().clear_cache();
+
+    assert_eq!(client.best_block_header().hash(), target);
+    for (number, hash) in (11..14).zip(side_chain) {
+        assert_eq!(client.block_hash(BlockId::Number(number)), Some(hash));
+    }
+    assert!(client.block_header(BlockId::Number(14)).is_none());
+    assert!(client.block_header(BlockId::Number(20)).is_none());
+    assert!(client.block_header(BlockId::Number(10)).is_some());
+}
+
+#[test]
+fn reset_blockchain_to_unknown_block_fails() {
+    let client = get_test_client_with_blocks(get_good_dummy_block_seq(5));
+
+    assert!(client
+        .reset_to(BlockId::Hash(H256::repeat_byte(1)))
+        .is_err());
+    assert!(client.reset_to(BlockId::Latest).is_err());
+    assert_eq!(client.chain_info().best_block_number, 6);
+}
+
+#[test]
+fn reports_import_stage_latency() {
+    // 3 + genesis block
+    let client = get_test_client_with_blocks(get_good_dummy_block_seq(3));
+
+    let mut registry = PrometheusRegistry::new(String::new());
+    client.prometheus_metrics(&mut registry);
+    let families = registry.registry().gather();
+    let samples = |name: &str| {
+        families
+            .iter()
+            .find(|family| family.get_name() == name)
+            .map(|family| family.get_metric()[0].get_histogram().get_sample_count())
+    };
+
+    for stage in &["queue_wait", "verification", "execution", "commit"] {
+        assert_eq!(samples(&format!("import_{}_seconds", stage)), Some(4));
+    }
+    assert!(samples("import_flush_seconds").unwrap() >= 1);
+}
+
 #[test]
 fn import_export_hex() {
     let client = get_test_client_with_blocks(get_good_dummy_block_seq(19));
@@ -547,7 +1027,7 @@ fn import_export_hex() {
     assert!(client.block_header(BlockId::Number(16)).is_none());
 
     client
-        .import_blocks(Box::new(&*out), Some(DataFormat::Hex))
+        .import_blocks(Box::new(&*out), Some(DataFormat::Hex), None)
         .unwrap();
 
     assert!(client.block_header(BlockId::Number(20)).is_some());
@@ -582,7 +1062,7 @@ fn import_export_binary() {
     assert!(client.block_header(BlockId::Number(16)).is_none());
 
     client
-        .import_blocks(Box::new(&*out), Some(DataFormat::Binary))
+        .import_blocks(Box::new(&*out), Some(DataFormat::Binary), None)
         .unwrap();
 
     assert!(client.block_header(BlockId::Number(19)).is_some());
@@ -592,6 +1072,33 @@ fn import_export_binary() {
     assert!(client.block_header(BlockId::Number(16)).is_some());
 }
 
+#[test]
+fn import_resumes_from_block() {
+    let client = get_test_client_with_blocks(get_good_dummy_block_seq(19));
+
+    let mut out = Vec::new();
+
+    client
+        .export_blocks(
+            Box::new(&mut out),
+            BlockId::Number(1),
+            BlockId::Number(20),
+            Some(DataFormat::Binary),
+        )
+        .unwrap();
+
+    assert!(client.reset(5).is_ok());
+    client.chain().clear_cache();
+    assert!(client.block_header(BlockId::Number(16)).is_none());
+
+    client
+        .import_blocks(Box::new(&*out), Some(DataFormat::Binary), Some(16))
+        .unwrap();
+
+    assert_eq!(client.chain_info().best_block_number, 20);
+    assert!(client.block_header(BlockId::Number(16)).is_some());
+}
+
 #[test]
 fn returns_state_root_basic() {
     let client = generate_dummy_client(6);
@@ -600,3 +1107,27 @@ fn returns_state_root_basic() {
 
     assert!(client.state_data(genesis_header.state_root()).is_some());
 }
+
+#[test]
+fn reports_backup_progress() {
+    let client = generate_dummy_client(1);
+    let dir = TempDir::new("backup").unwrap();
+    assert_eq!(client.backup_status(), BackupStatus::Idle);
+
+    // the test database doesn't support backups, but the backup runs in the background.
+    client.backup(dir.path()).unwrap();
+    let status = loop {
+        match client.backup_status() {
+            BackupStatus::Running { .. } => thread::sleep(Duration::from_millis(10)),
+            status => break status,
+        }
+    };
+
+    assert_eq!(
+        status,
+        BackupStatus::Failed {
+            path: dir.path().to_owned(),
+            error: "Backups are not supported by this database".into(),
+        }
+    );
+}
@@ -64,7 +64,7 @@ fn test_blockhash_eip210(factory: Factory) {
             params_type: ParamsType::Separate,
             access_list: AccessList::default(),
         };
-        let schedule = machine.schedule(env_info.number);
+        let schedule = machine.schedule(env_info.number, env_info.timestamp);
         let mut ex = Executive::new(&mut state, &env_info, &machine, &schedule);
         let mut substate = Substate::new();
         if let Err(e) = ex.call(params, &mut substate, &mut NoopTracer, &mut NoopVMTracer) {
@@ -88,7 +88,7 @@ fn test_blockhash_eip210(factory: Factory) {
         params_type: ParamsType::Separate,
         access_list: AccessList::default(),
     };
-    let schedule = machine.schedule(env_info.number);
+    let schedule = machine.schedule(env_info.number, env_info.timestamp);
     let mut ex = Executive::new(&mut state, &env_info, &machine, &schedule);
     let mut substate = Substate::new();
     let res = ex.call(params, &mut substate, &mut NoopTracer, &mut NoopVMTracer);
@@ -80,15 +80,26 @@ where
 {
     /// Creates new instance of `TraceDB`.
     pub fn new(config: Config, db: Arc<dyn BlockChainDB>, extras: Arc<T>) -> Self {
-        let mut batch = DBTransaction::new();
         let genesis = extras
             .block_hash(0)
             .expect("Genesis block is always inserted upon extras db creation qed");
-        batch.write(db::COL_TRACE, &genesis, &FlatBlockTraces::default());
-        batch.put(db::COL_TRACE, b"version", TRACE_DB_VER);
-        db.key_value()
-            .write(batch)
-            .expect("failed to update version");
+        let version = db
+            .key_value()
+            .get(db::COL_TRACE, b"version")
+            .expect("failed to read version");
+        let has_genesis = db
+            .key_value()
+            .read::<FlatBlockTraces, _>(db::COL_TRACE, &genesis)
+            .is_some();
+        // a database already set up is not written to, so that it can be opened read-only
+        if version.as_deref() != Some(TRACE_DB_VER) || !has_genesis {
+            let mut batch = DBTransaction::new();
+            batch.write(db::COL_TRACE, &genesis, &FlatBlockTraces::default());
+            batch.put(db::COL_TRACE, b"version", TRACE_DB_VER);
+            db.key_value()
+                .write(batch)
+                .expect("failed to update version");
+        }
 
         TraceDB {
             traces: RwLock::new(HashMap::new()),
@@ -113,6 +124,14 @@ where
         cache_manager.note_used(trace_id);
     }
 
+    /// Change the preferred and maximum cache sizes in bytes. Takes effect on the next
+    /// `collect_garbage`.
+    pub fn set_cache_sizes(&self, pref_cache_size: usize, max_cache_size: usize) {
+        self.cache_manager
+            .write()
+            .set_sizes(pref_cache_size, max_cache_size);
+    }
+
     /// Ticks our cache system and throws out any old data.
     pub fn collect_garbage(&self) {
         let current_size = self.cache_size();
@@ -410,7 +429,7 @@ mod tests {
     use evm::CallType;
     use kvdb::DBTransaction;
     use std::{collections::HashMap, sync::Arc};
-    use test_helpers::new_db;
+    use test_helpers::{new_db, read_only_db};
     use trace::{
         flat::{FlatBlockTraces, FlatTrace, FlatTransactionTraces},
         trace::{Action, Call, Res},
@@ -466,6 +485,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_reopening_db_read_only() {
+        let db = new_db();
+        let config = Config::default();
+        TraceDB::new(config.clone(), db.clone(), Arc::new(NoopExtras));
+
+        // an existing database is not written to on open
+        let tracedb = TraceDB::new(config, read_only_db(db), Arc::new(NoopExtras));
+        assert_eq!(tracedb.tracing_enabled(), false);
+    }
+
     #[test]
     fn test_reopening_db_with_tracing_off() {
         let db = new_db();
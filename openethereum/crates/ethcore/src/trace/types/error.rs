@@ -56,6 +56,10 @@ pub enum Error {
     OutOfBounds,
     /// Execution has been reverted with REVERT instruction.
     Reverted,
+    /// `AUTHCALL` without a successful `AUTH`.
+    Unauthorized,
+    /// Execution of a virtual call ran past its deadline.
+    ExecutionTimeout,
 }
 
 impl<'a> From<&'a VmError> for Error {
@@ -76,6 +80,8 @@ impl<'a> From<&'a VmError> for Error {
             VmError::MutableCallInStaticContext => Error::MutableCallInStaticContext,
             VmError::OutOfBounds => Error::OutOfBounds,
             VmError::Reverted => Error::Reverted,
+            VmError::Unauthorized => Error::Unauthorized,
+            VmError::ExecutionTimeout => Error::ExecutionTimeout,
         }
     }
 }
@@ -105,6 +111,8 @@ impl fmt::Display for Error {
             MutableCallInStaticContext => "Mutable Call In Static Context",
             OutOfBounds => "Out of bounds",
             Reverted => "Reverted",
+            Unauthorized => "Unauthorized",
+            ExecutionTimeout => "Execution timeout",
         };
         message.fmt(f)
     }
@@ -129,6 +137,8 @@ impl Encodable for Error {
             OutOfSubStack => 12,
             InvalidSubEntry => 13,
             InvalidCode => 14,
+            Unauthorized => 15,
+            ExecutionTimeout => 16,
         };
 
         s.append_internal(&value);
@@ -155,6 +165,8 @@ impl Decodable for Error {
             12 => Ok(OutOfSubStack),
             13 => Ok(InvalidSubEntry),
             14 => Ok(InvalidCode),
+            15 => Ok(Unauthorized),
+            16 => Ok(ExecutionTimeout),
             _ => Err(DecoderError::Custom("Invalid error type")),
         }
     }
@@ -81,9 +81,9 @@ pub mod blocks {
     use engines::EthEngine;
     use error::{BlockError, Error, ErrorKind};
     use types::{
-        header::Header,
+        header::{Header, HeaderDecodeParams},
         transaction::{TypedTransaction, UnverifiedTransaction},
-        BlockNumber,
+        withdrawal::Withdrawal,
     };
     use verification::{verify_block_basic, verify_block_unordered, PreverifiedBlock};
 
@@ -144,29 +144,38 @@ pub mod blocks {
         pub transactions: Vec<UnverifiedTransaction>,
         /// Unverified block uncles.
         pub uncles: Vec<Header>,
+        /// Unverified block withdrawals.
+        pub withdrawals: Option<Vec<Withdrawal>>,
         /// Raw block bytes.
         pub bytes: Bytes,
     }
 
     impl Unverified {
         /// Create an `Unverified` from raw bytes.
-        pub fn from_rlp(
+        pub fn from_rlp<P: Into<HeaderDecodeParams>>(
             bytes: Bytes,
-            eip1559_transition: BlockNumber,
+            params: P,
         ) -> Result<Self, ::rlp::DecoderError> {
             use rlp::Rlp;
-            let (header, transactions, uncles) = {
+            let params = params.into();
+            let (header, transactions, uncles, withdrawals) = {
                 let rlp = Rlp::new(&bytes);
-                let header = Header::decode_rlp(&rlp.at(0)?, eip1559_transition)?;
+                let header = Header::decode_rlp(&rlp.at(0)?, params)?;
                 let transactions = TypedTransaction::decode_rlp_list(&rlp.at(1)?)?;
-                let uncles = Header::decode_rlp_list(&rlp.at(2)?, eip1559_transition)?;
-                (header, transactions, uncles)
+                let uncles = Header::decode_rlp_list(&rlp.at(2)?, params)?;
+                let withdrawals = if rlp.item_count()? > 3 {
+                    Some(rlp.list_at(3)?)
+                } else {
+                    None
+                };
+                (header, transactions, uncles, withdrawals)
             };
 
             Ok(Unverified {
                 header,
                 transactions,
                 uncles,
+                withdrawals,
                 bytes,
             })
         }
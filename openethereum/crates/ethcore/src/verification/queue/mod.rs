@@ -19,22 +19,25 @@
 
 use blockchain::BlockChain;
 use client::ClientIoMessage;
-use engines::EthEngine;
+use engines::{EngineError, EthEngine};
 use error::{BlockError, Error, ErrorKind, ImportErrorKind};
 use ethereum_types::{H256, U256};
 use io::*;
 use len_caching_lock::LenCachingMutex;
 use parity_util_mem::{MallocSizeOf, MallocSizeOfExt};
 use parking_lot::{Condvar, Mutex, RwLock};
+use stats::{latency_histogram, prometheus::Histogram, PrometheusMetrics, PrometheusRegistry};
 use std::{
     cmp,
     collections::{HashMap, HashSet, VecDeque},
     iter::FromIterator,
+    mem,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering},
         Arc,
     },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use self::kind::{BlockLike, Kind};
@@ -135,6 +138,12 @@ impl Into<::types::block_status::BlockStatus> for Status {
     }
 }
 
+// when an item entered the queue and how long its verification took.
+struct Timing {
+    queued: Instant,
+    verification: Duration,
+}
+
 // the internal queue sizes.
 struct Sizes {
     unverified: AtomicUsize,
@@ -215,8 +224,14 @@ struct Verification<K: Kind> {
     verifying: LenCachingMutex<VecDeque<Verifying<K>>>,
     verified: LenCachingMutex<VecDeque<K::Verified>>,
     bad: Mutex<HashSet<H256>>,
+    /// Items which failed verification for a temporary reason. They leave the queue without
+    /// being marked as bad, so that they can be imported again.
+    deferred: Mutex<HashSet<H256>>,
+    timings: Mutex<HashMap<H256, Timing>>,
     sizes: Sizes,
     check_seal: bool,
+    queue_wait_histogram: Histogram,
+    verification_histogram: Histogram,
 }
 
 impl<K: Kind> VerificationQueue<K> {
@@ -232,12 +247,22 @@ impl<K: Kind> VerificationQueue<K> {
             verifying: LenCachingMutex::new(VecDeque::new()),
             verified: LenCachingMutex::new(VecDeque::new()),
             bad: Mutex::new(HashSet::from_iter(config.verifier_settings.bad_hashes)),
+            deferred: Mutex::new(HashSet::new()),
+            timings: Mutex::new(HashMap::new()),
             sizes: Sizes {
                 unverified: AtomicUsize::new(0),
                 verifying: AtomicUsize::new(0),
                 verified: AtomicUsize::new(0),
             },
             check_seal: check_seal,
+            queue_wait_histogram: latency_histogram(
+                "import_queue_wait_seconds",
+                "Time blocks spend waiting in the verification queue, excluding verification",
+            ),
+            verification_histogram: latency_histogram(
+                "import_verification_seconds",
+                "Time spent verifying a block in the verification queue",
+            ),
         });
         let more_to_verify = Arc::new(Condvar::new());
         let deleting = Arc::new(AtomicBool::new(false));
@@ -383,7 +408,17 @@ impl<K: Kind> VerificationQueue<K> {
 
             let hash = item.hash();
             // t_nb 5.0 verify standalone block (this verification is done in VerificationQueue thread pool)
-            let is_ready = match K::verify(item, &*engine, verification.check_seal) {
+            let verification_start = Instant::now();
+            let result = K::verify(item, &*engine, verification.check_seal);
+            let elapsed = verification_start.elapsed();
+            verification
+                .verification_histogram
+                .observe(elapsed.as_secs_f64());
+            if let Some(timing) = verification.timings.lock().get_mut(&hash) {
+                timing.verification = elapsed;
+            }
+
+            let is_ready = match result {
                 Ok(verified) => {
                     let mut verifying = verification.verifying.lock();
                     let mut idx = None;
@@ -405,10 +440,12 @@ impl<K: Kind> VerificationQueue<K> {
                         // we're next!
                         let mut verified = verification.verified.lock();
                         let mut bad = verification.bad.lock();
+                        let mut deferred = verification.deferred.lock();
                         VerificationQueue::drain_verifying(
                             &mut verifying,
                             &mut verified,
                             &mut bad,
+                            &mut deferred,
                             &verification.sizes,
                         );
                         true
@@ -416,12 +453,20 @@ impl<K: Kind> VerificationQueue<K> {
                         false
                     }
                 }
-                Err(_) => {
+                Err(err) => {
                     let mut verifying = verification.verifying.lock();
                     let mut verified = verification.verified.lock();
                     let mut bad = verification.bad.lock();
+                    let mut deferred = verification.deferred.lock();
 
-                    bad.insert(hash.clone());
+                    match err {
+                        Error(ErrorKind::Engine(EngineError::Unavailable(_)), _) => {
+                            deferred.insert(hash.clone());
+                        }
+                        _ => {
+                            bad.insert(hash.clone());
+                        }
+                    }
                     verifying.retain(|e| e.hash != hash);
 
                     if verifying.front().map_or(false, |x| x.output.is_some()) {
@@ -429,6 +474,7 @@ impl<K: Kind> VerificationQueue<K> {
                             &mut verifying,
                             &mut verified,
                             &mut bad,
+                            &mut deferred,
                             &verification.sizes,
                         );
                         true
@@ -448,6 +494,7 @@ impl<K: Kind> VerificationQueue<K> {
         verifying: &mut VecDeque<Verifying<K>>,
         verified: &mut VecDeque<K::Verified>,
         bad: &mut HashSet<H256>,
+        deferred: &mut HashSet<H256>,
         sizes: &Sizes,
     ) {
         let mut removed_size = 0;
@@ -460,6 +507,8 @@ impl<K: Kind> VerificationQueue<K> {
 
             if bad.contains(&output.parent_hash()) {
                 bad.insert(output.hash());
+            } else if deferred.contains(&output.parent_hash()) {
+                deferred.insert(output.hash());
             } else {
                 inserted_size += size;
                 verified.push_back(output);
@@ -490,6 +539,7 @@ impl<K: Kind> VerificationQueue<K> {
         *self.total_difficulty.write() = 0.into();
 
         self.processing.write().clear();
+        self.verification.timings.lock().clear();
     }
 
     /// Wait for unverified queue to be empty
@@ -566,14 +616,24 @@ impl<K: Kind> VerificationQueue<K> {
                     let mut td = self.total_difficulty.write();
                     *td = *td + item.difficulty();
                 }
+                self.verification.timings.lock().insert(
+                    hash,
+                    Timing {
+                        queued: Instant::now(),
+                        verification: Duration::from_secs(0),
+                    },
+                );
                 self.verification.unverified.lock().push_back(item);
                 self.more_to_verify.notify_all();
                 Ok(hash)
             }
             Err((input, err)) => {
                 match err {
-                    // Don't mark future blocks as bad.
+                    // Don't mark future blocks as bad. The local clock may be the one that is off,
+                    // so the same block must be importable again later.
                     Error(ErrorKind::Block(BlockError::TemporarilyInvalid(_)), _) => {}
+                    // Nor blocks which couldn't be checked by the consensus engine for now.
+                    Error(ErrorKind::Engine(EngineError::Unavailable(_)), _) => {}
                     // If the transaction root or uncles hash is invalid, it doesn't necessarily mean
                     // that the header is invalid. We might have just received a malformed block body,
                     // so we shouldn't put the header hash to `bad`.
@@ -652,6 +712,12 @@ impl<K: Kind> VerificationQueue<K> {
 
     /// Removes up to `max` verified items from the queue
     pub fn drain(&self, max: usize) -> Vec<K::Verified> {
+        let deferred = mem::replace(&mut *self.verification.deferred.lock(), HashSet::new());
+        if !deferred.is_empty() {
+            debug!(target: "verification", "Releasing {} items which could not be verified for now", deferred.len());
+            self.mark_as_good(&deferred.into_iter().collect::<Vec<_>>());
+        }
+
         let mut verified = self.verification.verified.lock();
         let count = cmp::min(max, verified.len());
         let result = verified.drain(..count).collect::<Vec<_>>();
@@ -665,6 +731,20 @@ impl<K: Kind> VerificationQueue<K> {
             .verified
             .fetch_sub(drained_size, AtomicOrdering::SeqCst);
 
+        let mut timings = self.verification.timings.lock();
+        for item in result.iter() {
+            if let Some(timing) = timings.remove(&item.hash()) {
+                let waited = timing
+                    .queued
+                    .elapsed()
+                    .checked_sub(timing.verification)
+                    .unwrap_or_default();
+                self.verification
+                    .queue_wait_histogram
+                    .observe(waited.as_secs_f64());
+            }
+        }
+
         result
     }
 
@@ -792,6 +872,12 @@ impl<K: Kind> VerificationQueue<K> {
             (u_len as isize, v_len as isize)
         };
 
+        {
+            let processing = self.processing.read();
+            let mut timings = self.verification.timings.lock();
+            timings.retain(|hash, _| processing.contains_key(hash));
+            timings.shrink_to_fit();
+        }
         self.processing.write().shrink_to_fit();
 
         if !self.scale_verifiers {
@@ -840,6 +926,13 @@ impl<K: Kind> VerificationQueue<K> {
     }
 }
 
+impl<K: Kind> PrometheusMetrics for VerificationQueue<K> {
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        r.register_histogram(&self.verification.queue_wait_histogram);
+        r.register_histogram(&self.verification.verification_histogram);
+    }
+}
+
 impl<K: Kind> Drop for VerificationQueue<K> {
     fn drop(&mut self) {
         trace!(target: "shutdown", "[VerificationQueue] Closing...");
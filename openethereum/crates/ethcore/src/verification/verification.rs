@@ -23,7 +23,7 @@
 
 use std::{
     collections::HashSet,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, UNIX_EPOCH},
 };
 
 use bytes::Bytes;
@@ -38,10 +38,11 @@ use call_contract::CallContract;
 use client::BlockInfo;
 use engines::{EthEngine, MAX_UNCLE_AGE};
 use error::{BlockError, Error};
-use types::{header::Header, transaction::SignedTransaction, BlockNumber};
+use machine::Machine;
+use types::{header::Header, transaction::SignedTransaction, withdrawal::Withdrawal, BlockNumber};
 use verification::queue::kind::blocks::Unverified;
 
-use time_utils::CheckedSystemTime;
+use time_utils::{CheckedSystemTime, Clock};
 
 /// Preprocessed block data gathered in `verify_block_unordered` call
 #[derive(MallocSizeOf)]
@@ -52,6 +53,8 @@ pub struct PreverifiedBlock {
     pub transactions: Vec<SignedTransaction>,
     /// Populated block uncles
     pub uncles: Vec<Header>,
+    /// Populated block withdrawals
+    pub withdrawals: Option<Vec<Withdrawal>>,
     /// Block bytes
     pub bytes: Bytes,
 }
@@ -143,10 +146,26 @@ pub fn verify_block_unordered(
         })
         .collect::<Result<Vec<_>, Error>>()?;
 
+    // t_nb 5.4 check the number of system transactions
+    let max_system_transactions = engine.params().max_system_transactions_per_block;
+    let system_transactions = transactions
+        .iter()
+        .filter(|t| engine.machine().is_system_transaction(t))
+        .count();
+    if system_transactions > max_system_transactions {
+        return Err(BlockError::TooManySystemTransactions(OutOfBounds {
+            min: None,
+            max: Some(max_system_transactions),
+            found: system_transactions,
+        })
+        .into());
+    }
+
     Ok(PreverifiedBlock {
         header,
         transactions,
         uncles: block.uncles,
+        withdrawals: block.withdrawals,
         bytes: block.bytes,
     })
 }
@@ -170,9 +189,17 @@ pub fn verify_block_family<C: BlockInfo + CallContract>(
     engine: &dyn EthEngine,
     do_full: Option<FullFamilyParams<C>>,
 ) -> Result<(), Error> {
-    // TODO: verify timestamp
+    // Median-time-past validation needs the ancestry, otherwise fall back to the parent timestamp.
+    let median_time_past = match (engine.median_time_past_window(header.number()), &do_full) {
+        (Some(window), Some(params)) => Some((window, params.block_provider)),
+        _ => None,
+    };
+
     // t_nb 6.3.1 verify parent
-    verify_parent(&header, &parent, engine)?;
+    verify_parent(&header, &parent, engine, median_time_past.is_none())?;
+    if let Some((window, block_provider)) = median_time_past {
+        verify_median_time_past(&header, &parent, block_provider, window)?;
+    }
     engine.verify_block_family(&header, &parent)?;
 
     let params = match do_full {
@@ -295,8 +322,8 @@ fn verify_uncles(
                 )));
             }
 
-            let uncle_parent = uncle_parent.decode(engine.params().eip1559_transition)?;
-            verify_parent(&uncle, &uncle_parent, engine)?;
+            let uncle_parent = uncle_parent.decode(engine.params().header_decode_params())?;
+            verify_parent(&uncle, &uncle_parent, engine, true)?;
             engine.verify_block_family(&uncle, &uncle_parent)?;
             verified.insert(uncle.hash());
         }
@@ -412,10 +439,10 @@ pub fn verify_header_params(
     }
 
     if is_full {
-        const ACCEPTABLE_DRIFT: Duration = Duration::from_secs(15);
+        let acceptable_drift = engine.machine().max_future_drift();
         // this will resist overflow until `year 2037`
-        let max_time = SystemTime::now() + ACCEPTABLE_DRIFT;
-        let invalid_threshold = max_time + ACCEPTABLE_DRIFT * 9;
+        let max_time = engine.machine().clock().now() + acceptable_drift;
+        let invalid_threshold = max_time + acceptable_drift * 9;
         let timestamp =
             CheckedSystemTime::checked_add(UNIX_EPOCH, Duration::from_secs(header.timestamp()))
                 .ok_or(BlockError::TimestampOverflow)?;
@@ -440,15 +467,62 @@ pub fn verify_header_params(
     Ok(())
 }
 
+/// Check that the header timestamp is above the median timestamp of the last `window` blocks,
+/// ending with the parent.
+fn verify_median_time_past(
+    header: &Header,
+    parent: &Header,
+    bc: &dyn BlockProvider,
+    window: usize,
+) -> Result<(), Error> {
+    let mut timestamps = Vec::with_capacity(window);
+    timestamps.push(parent.timestamp());
+    let mut hash = *parent.parent_hash();
+    while timestamps.len() < window {
+        match bc.block_header_data(&hash) {
+            Some(ancestor) => {
+                timestamps.push(ancestor.timestamp());
+                hash = ancestor.parent_hash();
+            }
+            None => break,
+        }
+    }
+    timestamps.sort_unstable();
+    let median = timestamps[timestamps.len() / 2];
+
+    if header.timestamp() <= median {
+        let min = CheckedSystemTime::checked_add(
+            UNIX_EPOCH,
+            Duration::from_secs(median.saturating_add(1)),
+        )
+        .ok_or(BlockError::TimestampOverflow)?;
+        let found =
+            CheckedSystemTime::checked_add(UNIX_EPOCH, Duration::from_secs(header.timestamp()))
+                .ok_or(BlockError::TimestampOverflow)?;
+        return Err(From::from(BlockError::InvalidTimestamp(OutOfBounds {
+            max: None,
+            min: Some(min),
+            found,
+        })));
+    }
+
+    Ok(())
+}
+
 /// Check header parameters agains parent header.
-fn verify_parent(header: &Header, parent: &Header, engine: &dyn EthEngine) -> Result<(), Error> {
+fn verify_parent(
+    header: &Header,
+    parent: &Header,
+    engine: &dyn EthEngine,
+    check_timestamp: bool,
+) -> Result<(), Error> {
     assert!(
         header.parent_hash().is_zero() || &parent.hash() == header.parent_hash(),
         "Parent hash should already have been verified; qed"
     );
 
-    if !engine.is_timestamp_valid(header.timestamp(), parent.timestamp()) {
-        let now = SystemTime::now();
+    if check_timestamp && !engine.is_timestamp_valid(header.timestamp(), parent.timestamp()) {
+        let now = engine.machine().clock().now();
         let min = CheckedSystemTime::checked_add(
             now,
             Duration::from_secs(parent.timestamp().saturating_add(1)),
@@ -481,8 +555,10 @@ fn verify_parent(header: &Header, parent: &Header, engine: &dyn EthEngine) -> Re
     // check if the block changed the gas limit too much
     if engine.gas_limit_override(header).is_none() {
         let gas_limit_divisor = engine.params().gas_limit_bound_divisor;
-        let parent_gas_limit =
-            parent.gas_limit() * engine.schedule(header.number()).eip1559_gas_limit_bump;
+        let parent_gas_limit = parent.gas_limit()
+            * engine
+                .schedule(header.number(), header.timestamp())
+                .eip1559_gas_limit_bump;
         let min_gas = parent_gas_limit - parent_gas_limit / gas_limit_divisor;
         let max_gas = parent_gas_limit + parent_gas_limit / gas_limit_divisor;
         if header.gas_limit() <= &min_gas || header.gas_limit() >= &max_gas {
@@ -533,6 +609,16 @@ fn verify_block_integrity(block: &Unverified) -> Result<(), Error> {
             found: *block.header.uncles_hash(),
         }));
     }
+    let expected_withdrawals = block
+        .withdrawals
+        .as_ref()
+        .map(|w| ordered_trie_root(w.iter().map(::rlp::encode)));
+    if expected_withdrawals.as_ref() != block.header.withdrawals_root() {
+        bail!(BlockError::InvalidWithdrawalsRoot(Mismatch {
+            expected: expected_withdrawals.unwrap_or_default(),
+            found: block.header.withdrawals_root().cloned().unwrap_or_default(),
+        }));
+    }
     Ok(())
 }
 
@@ -556,6 +642,7 @@ mod tests {
     use triehash::ordered_trie_root;
     use types::{
         encoded,
+        header::HeaderDecodeParams,
         log_entry::{LocalizedLogEntry, LogEntry},
         transaction::{Action, SignedTransaction, Transaction, TypedTransaction},
     };
@@ -709,6 +796,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn withdrawals_root_must_match_body() {
+        use hash::KECCAK_NULL_RLP;
+
+        let mut header = Header::default();
+        header.set_withdrawals_root(Some(H256::from_low_u64_be(1)));
+        let params = HeaderDecodeParams {
+            eip1559_transition: BlockNumber::max_value(),
+            eip4895_transition_timestamp: 0,
+        };
+        let mut block = Unverified::from_rlp(create_test_block(&header), params).unwrap();
+        assert_eq!(block.withdrawals, None);
+        check_fail(
+            verify_block_integrity(&block),
+            InvalidWithdrawalsRoot(Mismatch {
+                expected: H256::zero(),
+                found: H256::from_low_u64_be(1),
+            }),
+        );
+
+        block.withdrawals = Some(Vec::new());
+        check_fail(
+            verify_block_integrity(&block),
+            InvalidWithdrawalsRoot(Mismatch {
+                expected: KECCAK_NULL_RLP,
+                found: H256::from_low_u64_be(1),
+            }),
+        );
+
+        block.header.set_withdrawals_root(Some(KECCAK_NULL_RLP));
+        check_ok(verify_block_integrity(&block));
+    }
+
     fn basic_test(bytes: &[u8], engine: &dyn EthEngine) -> Result<(), Error> {
         let unverified = Unverified::from_rlp(bytes.to_vec(), engine.params().eip1559_transition)?;
         verify_block_basic(&unverified, engine, true)
@@ -741,6 +861,7 @@ mod tests {
             header,
             transactions,
             uncles: block.uncles,
+            withdrawals: block.withdrawals,
             bytes: bytes.to_vec(),
         };
 
@@ -779,6 +900,79 @@ mod tests {
         assert!(basic_test(&block, engine).is_err());
     }
 
+    #[test]
+    fn test_verify_header_timestamp_against_engine_clock() {
+        use std::{sync::Arc, time::Duration};
+        use time_utils::MockClock;
+
+        let spec = Spec::new_test();
+        let engine = &*spec.engine;
+        let clock = Arc::new(MockClock::with_unix_secs(1_000_000));
+        engine.machine().clock().set(clock.clone());
+
+        let mut header = Header::new();
+        header.set_gas_limit(engine.min_gas_limit());
+        header.set_number(10);
+
+        header.set_timestamp(1_000_010);
+        check_ok(verify_header_params(&header, engine, true, false));
+
+        header.set_timestamp(1_000_020);
+        check_fail_timestamp(verify_header_params(&header, engine, true, false), true);
+
+        header.set_timestamp(1_000_200);
+        check_fail_timestamp(verify_header_params(&header, engine, true, false), false);
+
+        // once the local clock catches up the block is acceptable
+        clock.advance(Duration::from_secs(60));
+        header.set_timestamp(1_000_020);
+        check_ok(verify_header_params(&header, engine, true, false));
+
+        // the acceptable drift is configurable
+        engine
+            .machine()
+            .set_max_future_drift(Duration::from_secs(2));
+        header.set_timestamp(1_000_065);
+        check_fail_timestamp(verify_header_params(&header, engine, true, false), true);
+        header.set_timestamp(1_000_090);
+        check_fail_timestamp(verify_header_params(&header, engine, true, false), false);
+    }
+
+    #[test]
+    fn test_verify_median_time_past() {
+        let mut bc = TestBlockChain::new();
+        let mut parent = Header::new();
+        // the third block jumps far into the future
+        for (number, timestamp) in vec![(0, 10), (1, 20), (2, 1_000)] {
+            let mut header = Header::new();
+            header.set_number(number);
+            header.set_timestamp(timestamp);
+            header.set_gas_limit(100_000.into());
+            header.set_parent_hash(parent.hash());
+            bc.insert(create_test_block(&header));
+            parent = header;
+        }
+
+        let mut header = Header::new();
+        header.set_number(3);
+        header.set_gas_limit(100_000.into());
+        header.set_parent_hash(parent.hash());
+
+        // only has to exceed the median of the window, not the parent that jumped ahead
+        header.set_timestamp(21);
+        check_ok(verify_median_time_past(&header, &parent, &bc, 3));
+        let spec = Spec::new_test();
+        check_fail_timestamp(verify_parent(&header, &parent, &*spec.engine, true), false);
+        check_ok(verify_parent(&header, &parent, &*spec.engine, false));
+
+        header.set_timestamp(20);
+        check_fail_timestamp(verify_median_time_past(&header, &parent, &bc, 3), false);
+
+        // with a window of one the parent timestamp is the median
+        header.set_timestamp(1_000);
+        check_fail_timestamp(verify_median_time_past(&header, &parent, &bc, 1), false);
+    }
+
     #[test]
     fn test_verify_block() {
         use rlp::RlpStream;
@@ -1168,4 +1362,68 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[test]
+    fn system_transactions_bound() {
+        use engines::NullEngine;
+        use machine::EthereumMachine;
+
+        let keypair = Random.generate();
+        let target = Address::from_low_u64_be(0x10);
+        let mut params = CommonParams::default();
+        params.system_transaction_sender = Some(keypair.address());
+        params.system_transaction_targets = vec![target];
+        params.max_system_transactions_per_block = 2;
+
+        let mut header = Header::default();
+        header.set_number(1);
+
+        let system_transactions: Vec<_> = (0..3)
+            .map(|i| {
+                TypedTransaction::Legacy(Transaction {
+                    action: Action::Call(target),
+                    value: U256::zero(),
+                    data: Vec::new(),
+                    gas: 0.into(),
+                    gas_price: U256::zero(),
+                    nonce: i.into(),
+                })
+                .sign(keypair.secret(), None)
+            })
+            .collect();
+        let regular_transaction = TypedTransaction::Legacy(Transaction {
+            action: Action::Call(target),
+            value: U256::zero(),
+            data: Vec::new(),
+            gas: 0.into(),
+            gas_price: U256::zero(),
+            nonce: 0.into(),
+        })
+        .sign(Random.generate().secret(), None);
+
+        let machine = EthereumMachine::regular(params, BTreeMap::new());
+        let engine = NullEngine::new(Default::default(), machine);
+        check_fail(
+            unordered_test(
+                &create_test_block_with_data(&header, &system_transactions, &[]),
+                &engine,
+            ),
+            TooManySystemTransactions(OutOfBounds {
+                min: None,
+                max: Some(2),
+                found: 3,
+            }),
+        );
+        // Regular transactions do not count towards the bound.
+        let transactions = [
+            system_transactions[0].clone(),
+            system_transactions[1].clone(),
+            regular_transaction,
+        ];
+        unordered_test(
+            &create_test_block_with_data(&header, &transactions, &[]),
+            &engine,
+        )
+        .unwrap();
+    }
 }
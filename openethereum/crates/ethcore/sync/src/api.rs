@@ -15,7 +15,7 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 use bytes::Bytes;
-use crypto::publickey::Secret;
+use crypto::publickey::{KeyPair, Secret};
 use devp2p::NetworkService;
 use network::{
     client_version::ClientVersion, ConnectionFilter, Error, ErrorKind,
@@ -112,8 +112,12 @@ pub struct SyncConfig {
     pub warp_sync: WarpSync,
     /// Number of first block where EIP-1559 rules begin. New encoding/decoding block format.
     pub eip1559_transition: BlockNumber,
+    /// Timestamp from which blocks carry EIP-4895 withdrawals.
+    pub eip4895_transition_timestamp: u64,
     /// Number of blocks for which new transactions will be returned in a result of `parity_newTransactionsStats` RPC call
     pub new_transactions_stats_period: u64,
+    /// Withhold relaying of blocks with a timestamp further ahead of the local clock than this.
+    pub max_relay_future_drift: Option<Duration>,
 }
 
 impl Default for SyncConfig {
@@ -126,7 +130,9 @@ impl Default for SyncConfig {
             fork_block: None,
             warp_sync: WarpSync::Disabled,
             eip1559_transition: BlockNumber::max_value(),
+            eip4895_transition_timestamp: u64::max_value(),
             new_transactions_stats_period: 0,
+            max_relay_future_drift: None,
         }
     }
 }
@@ -399,6 +405,65 @@ impl PrometheusMetrics for EthSync {
             sync_status.highest_block_number.unwrap_or(0) as i64,
         );
 
+        let relay_stats = self.eth_handler.sync.relay_stats();
+        r.register_counter(
+            "sync_relay_withheld",
+            "Number of best blocks withheld from relaying for a future timestamp",
+            relay_stats.withheld as i64,
+        );
+        r.register_counter(
+            "sync_relay_released",
+            "Number of withheld blocks relayed once their timestamp was reached",
+            relay_stats.released as i64,
+        );
+        r.register_counter(
+            "sync_relay_announced_beyond_bound",
+            "Number of announced blocks with a timestamp beyond the relay bound",
+            relay_stats.announced_beyond_bound as i64,
+        );
+        r.register_gauge(
+            "sync_relay_peers_beyond_bound",
+            "Number of connected peers which announced a block with a timestamp beyond the relay bound",
+            relay_stats.peers_beyond_bound as i64,
+        );
+
+        let download_stats = self.eth_handler.sync.download_stats();
+        r.register_counter(
+            "sync_head_requests",
+            "Number of block requests for the newest 128 blocks",
+            download_stats.head_requests as i64,
+        );
+        r.register_counter(
+            "sync_backfill_requests",
+            "Number of backfill block requests",
+            download_stats.backfill_requests as i64,
+        );
+        r.register_counter(
+            "sync_backfill_deferred",
+            "Number of times backfill was deferred in favour of blocks near the head",
+            download_stats.backfill_deferred as i64,
+        );
+        r.register_counter(
+            "sync_head_served",
+            "Number of blocks near the head imported after their body was requested",
+            download_stats.head_served as i64,
+        );
+        r.register_counter(
+            "sync_head_time_to_serve_ms_total",
+            "Total time between body request and import of blocks near the head, in milliseconds",
+            download_stats.head_time_to_serve_total_ms as i64,
+        );
+        r.register_gauge(
+            "sync_head_time_to_serve_max_ms",
+            "Longest time between body request and import of a block near the head, in milliseconds",
+            download_stats.head_time_to_serve_max_ms as i64,
+        );
+        r.register_gauge(
+            "sync_head_time_to_serve_last_ms",
+            "Time between body request and import of the last block near the head, in milliseconds",
+            download_stats.head_time_to_serve_last_ms as i64,
+        );
+
         r.register_gauge(
             "snapshot_download_active",
             "1 if downloading snapshots",
@@ -741,6 +806,8 @@ pub struct NetworkConfiguration {
     pub ip_filter: IpFilter,
     /// Client version string
     pub client_version: String,
+    /// Node certificate presented to peers
+    pub node_certificate: Option<Vec<u8>>,
 }
 
 impl NetworkConfiguration {
@@ -754,6 +821,25 @@ impl NetworkConfiguration {
         From::from(BasicNetworkConfiguration::new_local())
     }
 
+    /// Returns the key the network service identifies the node with, generating and storing
+    /// one if the node was not started before.
+    ///
+    /// Fails without a secret or a config path to store a generated key in, as the key would
+    /// change on every restart.
+    pub fn node_key(&self) -> Result<KeyPair, Error> {
+        if self.use_secret.is_none() && self.config_path.is_none() {
+            return Err(ErrorKind::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no network config path to store the node key in",
+            ))
+            .into());
+        }
+        ::devp2p::load_node_key(
+            self.use_secret.clone(),
+            self.config_path.as_ref().map(String::as_str),
+        )
+    }
+
     /// Attempt to convert this config into a BasicNetworkConfiguration.
     pub fn into_basic(self) -> Result<BasicNetworkConfiguration, AddrParseError> {
         Ok(BasicNetworkConfiguration {
@@ -784,6 +870,7 @@ impl NetworkConfiguration {
                 NonReservedPeerMode::Deny
             },
             client_version: self.client_version,
+            node_certificate: self.node_certificate,
         })
     }
 }
@@ -815,6 +902,7 @@ impl From<BasicNetworkConfiguration> for NetworkConfiguration {
                 _ => false,
             },
             client_version: other.client_version,
+            node_certificate: other.node_certificate,
         }
     }
 }
@@ -18,6 +18,7 @@ use blocks::{BlockCollection, SyncBody, SyncHeader};
 use chain::BlockSet;
 use ethcore::{
     client::{BlockId, BlockStatus},
+    engines::EngineError,
     error::{
         BlockError, Error as EthcoreError, ErrorKind as EthcoreErrorKind, ImportErrorKind,
         QueueErrorKind,
@@ -32,7 +33,7 @@ use std::cmp;
 ///
 use std::collections::{BTreeMap, HashSet, VecDeque};
 use sync_io::SyncIo;
-use types::BlockNumber;
+use types::{header::HeaderDecodeParams, BlockNumber};
 
 const MAX_HEADERS_TO_REQUEST: usize = 128;
 const MAX_BODIES_TO_REQUEST_LARGE: usize = 128;
@@ -186,6 +187,15 @@ impl BlockDownloader {
         self.state == State::Complete
     }
 
+    /// Check if there are headers or blocks left to download this round
+    pub fn has_pending_downloads(&self) -> bool {
+        match self.state {
+            State::ChainHead => true,
+            State::Blocks => !self.blocks.is_empty(),
+            State::Idle | State::Complete => false,
+        }
+    }
+
     /// Check if particular block hash is being downloaded
     pub fn is_downloading(&self, hash: &H256) -> bool {
         self.blocks.is_downloading(hash)
@@ -245,13 +255,14 @@ impl BlockDownloader {
     }
 
     /// Add new block headers.
-    pub fn import_headers(
+    pub fn import_headers<P: Into<HeaderDecodeParams>>(
         &mut self,
         io: &mut dyn SyncIo,
         r: &Rlp,
         expected_hash: H256,
-        eip1559_transition: BlockNumber,
+        params: P,
     ) -> Result<DownloadAction, BlockDownloaderImportError> {
+        let params = params.into();
         let item_count = r.item_count().unwrap_or(0);
         if self.state == State::Idle {
             trace_sync!(self, "Ignored unexpected block headers");
@@ -277,7 +288,7 @@ impl BlockDownloader {
         let mut hashes = Vec::new();
         let mut last_header = None;
         for i in 0..item_count {
-            let info = SyncHeader::from_rlp(r.at(i)?.as_raw().to_vec(), eip1559_transition)?;
+            let info = SyncHeader::from_rlp(r.at(i)?.as_raw().to_vec(), params)?;
             let number = BlockNumber::from(info.header.number());
             let hash = info.header.hash();
 
@@ -418,12 +429,13 @@ impl BlockDownloader {
     }
 
     /// Called by peer once it has new block bodies
-    pub fn import_bodies(
+    pub fn import_bodies<P: Into<HeaderDecodeParams>>(
         &mut self,
         r: &Rlp,
         expected_hashes: &[H256],
-        eip1559_transition: BlockNumber,
+        params: P,
     ) -> Result<(), BlockDownloaderImportError> {
+        let params = params.into();
         let item_count = r.item_count().unwrap_or(0);
         if item_count == 0 {
             return Err(BlockDownloaderImportError::Useless);
@@ -432,7 +444,7 @@ impl BlockDownloader {
         } else {
             let mut bodies = Vec::with_capacity(item_count);
             for i in 0..item_count {
-                let body = SyncBody::from_rlp(r.at(i)?.as_raw(), eip1559_transition)?;
+                let body = SyncBody::from_rlp(r.at(i)?.as_raw(), params)?;
                 bodies.push(body);
             }
 
@@ -714,11 +726,26 @@ impl BlockDownloader {
                     debug_sync!(self, "Block temporarily invalid: {:?}, restarting sync", h);
                     break;
                 }
+                Err(EthcoreError(EthcoreErrorKind::Engine(EngineError::Unavailable(e)), _)) => {
+                    debug_sync!(
+                        self,
+                        "Block {:?} can't be verified for now ({}), restarting sync",
+                        h,
+                        e
+                    );
+                    download_action = DownloadAction::Reset;
+                    break;
+                }
                 Err(EthcoreError(EthcoreErrorKind::Queue(QueueErrorKind::Full(limit)), _)) => {
                     debug_sync!(self, "Block import queue full ({}), restarting sync", limit);
                     download_action = DownloadAction::Reset;
                     break;
                 }
+                Err(EthcoreError(EthcoreErrorKind::Queue(QueueErrorKind::Closed), _)) => {
+                    debug_sync!(self, "Block import queue closed, restarting sync");
+                    download_action = DownloadAction::Reset;
+                    break;
+                }
                 Err(e) => {
                     debug_sync!(self, "Bad block {:?} : {:?}", h, e);
                     download_action = DownloadAction::Reset;
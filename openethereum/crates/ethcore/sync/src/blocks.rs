@@ -24,9 +24,9 @@ use rlp::{DecoderError, Rlp, RlpStream};
 use std::collections::{hash_map, BTreeMap, HashMap, HashSet};
 use triehash_ethereum::ordered_trie_root;
 use types::{
-    header::Header as BlockHeader,
+    header::{Header as BlockHeader, HeaderDecodeParams},
     transaction::{TypedTransaction, UnverifiedTransaction},
-    BlockNumber,
+    withdrawal::Withdrawal,
 };
 
 malloc_size_of_is_0!(HeaderId);
@@ -38,10 +38,13 @@ pub struct SyncHeader {
 }
 
 impl SyncHeader {
-    pub fn from_rlp(bytes: Bytes, eip1559_transition: BlockNumber) -> Result<Self, DecoderError> {
+    pub fn from_rlp<P: Into<HeaderDecodeParams>>(
+        bytes: Bytes,
+        params: P,
+    ) -> Result<Self, DecoderError> {
         let rlp = Rlp::new(&bytes);
         let result = SyncHeader {
-            header: BlockHeader::decode_rlp(&rlp, eip1559_transition)?,
+            header: BlockHeader::decode_rlp(&rlp, params)?,
             bytes,
         };
 
@@ -55,32 +58,59 @@ pub struct SyncBody {
     pub transactions: Vec<UnverifiedTransaction>,
     pub uncles_bytes: Bytes,
     pub uncles: Vec<BlockHeader>,
+    pub withdrawals_bytes: Option<Bytes>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
 }
 
 impl SyncBody {
-    pub fn from_rlp(bytes: &[u8], eip1559_transition: BlockNumber) -> Result<Self, DecoderError> {
+    pub fn from_rlp<P: Into<HeaderDecodeParams>>(
+        bytes: &[u8],
+        params: P,
+    ) -> Result<Self, DecoderError> {
         let rlp = Rlp::new(bytes);
         let transactions_rlp = rlp.at(0)?;
         let uncles_rlp = rlp.at(1)?;
+        // bodies of blocks after EIP-4895 carry the withdrawals as a third item
+        let withdrawals_rlp = match rlp.item_count()? {
+            2 => None,
+            3 => Some(rlp.at(2)?),
+            _ => return Err(DecoderError::RlpIncorrectListLen),
+        };
 
         let result = SyncBody {
             transactions_bytes: transactions_rlp.as_raw().to_vec(),
             transactions: TypedTransaction::decode_rlp_list(&transactions_rlp)?,
             uncles_bytes: uncles_rlp.as_raw().to_vec(),
-            uncles: BlockHeader::decode_rlp_list(&uncles_rlp, eip1559_transition)?,
+            uncles: BlockHeader::decode_rlp_list(&uncles_rlp, params)?,
+            withdrawals_bytes: withdrawals_rlp.as_ref().map(|r| r.as_raw().to_vec()),
+            withdrawals: withdrawals_rlp.map(|r| r.as_list()).transpose()?,
         };
 
         Ok(result)
     }
 
-    fn empty_body() -> Self {
+    fn empty_body(with_withdrawals: bool) -> Self {
         SyncBody {
             transactions_bytes: ::rlp::EMPTY_LIST_RLP.to_vec(),
             transactions: Vec::with_capacity(0),
             uncles_bytes: ::rlp::EMPTY_LIST_RLP.to_vec(),
             uncles: Vec::with_capacity(0),
+            withdrawals_bytes: match with_withdrawals {
+                true => Some(::rlp::EMPTY_LIST_RLP.to_vec()),
+                false => None,
+            },
+            withdrawals: match with_withdrawals {
+                true => Some(Vec::with_capacity(0)),
+                false => None,
+            },
         }
     }
+
+    fn withdrawals_root(&self) -> Option<H256> {
+        self.withdrawals_bytes
+            .as_ref()
+            .map(|bytes| ordered_trie_root(Rlp::new(bytes).iter().map(|r| r.as_raw())))
+    }
 }
 
 /// Block data with optional body.
@@ -93,16 +123,21 @@ struct SyncBlock {
 }
 
 fn unverified_from_sync(header: SyncHeader, body: Option<SyncBody>) -> Unverified {
-    let mut stream = RlpStream::new_list(3);
+    let body =
+        body.unwrap_or_else(|| SyncBody::empty_body(header.header.withdrawals_root().is_some()));
+    let mut stream = RlpStream::new_list(3 + body.withdrawals_bytes.is_some() as usize);
     stream.append_raw(&header.bytes, 1);
-    let body = body.unwrap_or_else(SyncBody::empty_body);
     stream.append_raw(&body.transactions_bytes, 1);
     stream.append_raw(&body.uncles_bytes, 1);
+    if let Some(ref withdrawals) = body.withdrawals_bytes {
+        stream.append_raw(withdrawals, 1);
+    }
 
     Unverified {
         header: header.header,
         transactions: body.transactions,
         uncles: body.uncles,
+        withdrawals: body.withdrawals,
         bytes: stream.out().to_vec(),
     }
 }
@@ -115,11 +150,12 @@ pub struct BlockAndReceipts {
     pub receipts: Option<Bytes>,
 }
 
-/// Used to identify header by transactions and uncles hashes
+/// Used to identify header by transactions, uncles and withdrawals hashes
 #[derive(Eq, PartialEq, Hash)]
 struct HeaderId {
     transactions_root: H256,
     uncles: H256,
+    withdrawals_root: Option<H256>,
 }
 
 /// A collection of blocks and subchain pointers being downloaded. This keeps track of
@@ -452,6 +488,7 @@ impl BlockCollection {
             HeaderId {
                 transactions_root: tx_root,
                 uncles: uncles,
+                withdrawals_root: body.withdrawals_root(),
             }
         };
 
@@ -539,13 +576,17 @@ impl BlockCollection {
         let header_id = HeaderId {
             transactions_root: *info.header.transactions_root(),
             uncles: *info.header.uncles_hash(),
+            withdrawals_root: info.header.withdrawals_root().cloned(),
         };
 
         let body = if header_id.transactions_root == KECCAK_NULL_RLP
             && header_id.uncles == KECCAK_EMPTY_LIST_RLP
+            && header_id
+                .withdrawals_root
+                .map_or(true, |root| root == KECCAK_NULL_RLP)
         {
             // empty body, just mark as downloaded
-            Some(SyncBody::empty_body())
+            Some(SyncBody::empty_body(header_id.withdrawals_root.is_some()))
         } else {
             trace!(
                 "Queueing body tx_root = {:?}, uncles = {:?}, block = {:?}, number = {}",
@@ -620,13 +661,19 @@ impl BlockCollection {
 
 #[cfg(test)]
 mod test {
-    use super::{BlockCollection, SyncHeader};
+    use super::{BlockCollection, SyncBody, SyncHeader};
     use ethcore::{
         client::{BlockChainClient, BlockId, EachBlockWith, TestBlockChainClient},
         verification::queue::kind::blocks::Unverified,
     };
+    use ethereum_types::{Address, H256};
     use rlp::*;
-    use types::BlockNumber;
+    use triehash_ethereum::ordered_trie_root;
+    use types::{
+        header::{Header, HeaderDecodeParams},
+        withdrawal::Withdrawal,
+        BlockNumber,
+    };
 
     fn is_empty(bc: &BlockCollection) -> bool {
         bc.heads.is_empty()
@@ -829,4 +876,45 @@ mod test {
         bc.insert_headers(headers[0..1].into_iter().map(Clone::clone).collect());
         assert_eq!(bc.drain().len(), 2);
     }
+
+    #[test]
+    fn carries_withdrawals_from_body_to_block() {
+        let params = HeaderDecodeParams {
+            eip1559_transition: BlockNumber::max_value(),
+            eip4895_transition_timestamp: 1000,
+        };
+        let withdrawal = Withdrawal {
+            index: 3,
+            validator_index: 9,
+            address: Address::from_low_u64_be(0x4895),
+            amount: 5,
+        };
+
+        let mut header = Header::new();
+        header.set_parent_hash(H256::from_low_u64_be(1));
+        header.set_number(1);
+        header.set_timestamp(1000);
+        header.set_withdrawals_root(Some(ordered_trie_root(vec![encode(&withdrawal)])));
+        let mut body = RlpStream::new_list(3);
+        body.begin_list(0);
+        body.begin_list(0);
+        body.append_list(&[withdrawal.clone()]);
+        let body = SyncBody::from_rlp(&body.out(), params).unwrap();
+        assert_eq!(body.withdrawals, Some(vec![withdrawal.clone()]));
+
+        let mut bc = BlockCollection::new(false);
+        bc.reset_to(vec![header.hash()]);
+        bc.insert_headers(vec![SyncHeader::from_rlp(encode(&header), params).unwrap()]);
+        // the withdrawals make the body non-empty, so it has to be downloaded
+        assert_eq!(bc.needed_bodies(1, false), vec![header.hash()]);
+        assert_eq!(bc.insert_bodies(vec![body]), vec![header.hash()]);
+
+        let block = bc.drain().pop().unwrap().block;
+        assert_eq!(block.withdrawals, Some(vec![withdrawal]));
+        assert_eq!(block.header.hash(), header.hash());
+        assert_eq!(
+            Unverified::from_rlp(block.bytes.clone(), params).unwrap(),
+            block
+        );
+    }
 }
@@ -86,7 +86,7 @@ mod tests {
             || ethereum::new_foundation(&String::new()),
             vec![
                 1_150_000, 1_920_000, 2_463_000, 2_675_000, 4_370_000, 7_280_000, 9_069_000,
-                9_200_000, 12_244_000, 12_965_000, 13_773_000,
+                9_200_000, 12_244_000, 12_965_000, 13_773_000, 15_050_000,
             ],
         )
     }
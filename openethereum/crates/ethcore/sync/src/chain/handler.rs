@@ -123,6 +123,7 @@ impl SyncHandler {
             sync.delayed_requests
                 .retain(|(request_peer_id, _, _)| *request_peer_id != peer_id);
             sync.active_peers.remove(&peer_id);
+            sync.relay_policy.remove_peer(peer_id);
 
             if sync.state == SyncState::SnapshotManifest {
                 // Check if we are asking other peers for
@@ -168,10 +169,17 @@ impl SyncHandler {
             return Ok(());
         }
         // t_nb 1.0 decode RLP
-        let block = Unverified::from_rlp(r.at(0)?.as_raw().to_vec(), sync.eip1559_transition)?;
+        let block = Unverified::from_rlp(r.at(0)?.as_raw().to_vec(), sync.header_decode_params())?;
         let hash = block.header.hash();
         let number = block.header.number();
         trace!(target: "sync", "{} -> NewBlock ({})", peer_id, hash);
+        let now = sync.relay_policy.unix_now();
+        if !sync
+            .relay_policy
+            .note_announced(peer_id, block.header.timestamp(), now)
+        {
+            debug!(target: "sync", "{} -> NewBlock {} with timestamp {} beyond the relay bound", peer_id, hash, block.header.timestamp());
+        }
         if number > sync.highest_block.unwrap_or(0) {
             sync.highest_block = Some(number);
         }
@@ -369,7 +377,11 @@ impl SyncHandler {
                         Some(ref mut blocks) => blocks,
                     },
                 };
-                downloader.import_bodies(r, expected_blocks.as_slice(), sync.eip1559_transition)?;
+                downloader.import_bodies(
+                    r,
+                    expected_blocks.as_slice(),
+                    sync.header_decode_params(),
+                )?;
             }
             sync.collect_blocks(io, block_set);
             Ok(())
@@ -488,7 +500,7 @@ impl SyncHandler {
                     Some(ref mut blocks) => blocks,
                 },
             };
-            downloader.import_headers(io, r, expected_hash, sync.eip1559_transition)?
+            downloader.import_headers(io, r, expected_hash, sync.header_decode_params())?
         };
 
         if result == DownloadAction::Reset {
@@ -101,6 +101,7 @@ use api::{EthProtocolInfo as PeerInfoDigest, PriorityTask, ETH_PROTOCOL, PAR_PRO
 use block_sync::{BlockDownloader, DownloadAction};
 use bytes::Bytes;
 use derive_more::Display;
+use download_scheduler::{DownloadScheduler, DownloadStats, DownloadTier};
 use ethcore::{
     client::{BlockChainClient, BlockChainInfo, BlockId, BlockQueueInfo, BlockStatus},
     snapshot::RestorationStatus,
@@ -111,6 +112,7 @@ use hash::keccak;
 use network::{self, client_version::ClientVersion, PeerId};
 use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
 use rand::{seq::SliceRandom, Rng};
+use relay_policy::{RelayPolicy, RelayStats};
 use rlp::{DecoderError, RlpStream};
 use snapshot::Snapshot;
 use std::{
@@ -121,7 +123,7 @@ use std::{
 };
 use sync_io::SyncIo;
 use transactions_stats::{Stats as TransactionStats, TransactionsStats};
-use types::{transaction::UnverifiedTransaction, BlockNumber};
+use types::{header::HeaderDecodeParams, transaction::UnverifiedTransaction, BlockNumber};
 
 use self::{
     handler::SyncHandler,
@@ -455,6 +457,16 @@ impl ChainSyncApi {
         self.sync.read().status()
     }
 
+    /// Returns block download scheduling statistics
+    pub fn download_stats(&self) -> DownloadStats {
+        self.sync.read().download_scheduler.stats()
+    }
+
+    /// Returns future block relay statistics
+    pub fn relay_stats(&self) -> RelayStats {
+        self.sync.read().relay_policy.stats()
+    }
+
     /// Returns pending transactions propagation statistics
     pub fn pending_transactions_stats(&self) -> BTreeMap<H256, ::TransactionStats> {
         self.sync
@@ -689,6 +701,10 @@ pub struct ChainSync {
     new_blocks: BlockDownloader,
     /// Block download process for ancient blocks
     old_blocks: Option<BlockDownloader>,
+    /// Prioritizes downloads near the head over backfill
+    download_scheduler: DownloadScheduler,
+    /// Withholds relaying of blocks with future timestamps
+    relay_policy: RelayPolicy,
     /// Last propagated block number
     last_sent_block_number: BlockNumber,
     /// Network ID
@@ -718,6 +734,8 @@ pub struct ChainSync {
     warp_sync: WarpSync,
     /// New block encoding/decoding format is introduced by the EIP1559
     eip1559_transition: BlockNumber,
+    /// Blocks from this timestamp on carry EIP-4895 withdrawals
+    eip4895_transition_timestamp: u64,
     /// Number of blocks for which new transactions will be returned in a result of `parity_newTransactionsStats` RPC call
     new_transactions_stats_period: BlockNumber,
 }
@@ -797,6 +815,8 @@ impl ChainSync {
                 chain_info.best_block_number,
             ),
             old_blocks: None,
+            download_scheduler: DownloadScheduler::default(),
+            relay_policy: RelayPolicy::new(config.max_relay_future_drift, chain.clock()),
             last_sent_block_number: 0,
             network_id: config.network_id,
             fork_block: config.fork_block,
@@ -808,12 +828,21 @@ impl ChainSync {
             transactions_stats: TransactionsStats::default(),
             warp_sync: config.warp_sync,
             eip1559_transition: config.eip1559_transition,
+            eip4895_transition_timestamp: config.eip4895_transition_timestamp,
             new_transactions_stats_period: config.new_transactions_stats_period,
         };
         sync.update_targets(chain);
         sync
     }
 
+    /// Parameters to decode headers and bodies received from peers.
+    fn header_decode_params(&self) -> HeaderDecodeParams {
+        HeaderDecodeParams {
+            eip1559_transition: self.eip1559_transition,
+            eip4895_transition_timestamp: self.eip4895_transition_timestamp,
+        }
+    }
+
     /// Returns synchonization status
     pub fn status(&self) -> SyncStatus {
         let last_imported_number = self.new_blocks.last_imported_block_number();
@@ -1102,6 +1131,7 @@ impl ChainSync {
 
     /// Restart sync disregarding the block queue status. May end up re-downloading up to QUEUE_SIZE blocks
     pub fn restart(&mut self, io: &mut dyn SyncIo) {
+        self.download_scheduler.clear();
         self.update_targets(io.chain());
         self.reset_and_continue(io);
     }
@@ -1256,6 +1286,8 @@ impl ChainSync {
 						// check if got new blocks to download
 						trace!(target: "sync", "Syncing with peer {}, force={}, td={:?}, our td={}, state={:?}", peer_id, force, peer_difficulty, syncing_difficulty, self.state);
 						if let Some(request) = self.new_blocks.request_blocks(peer_id, io, num_active_peers) {
+							let tier = DownloadTier::classify(self.new_blocks.last_imported_block_number(), self.highest_block);
+							self.download_scheduler.note_request(tier, &request, Instant::now());
 							SyncRequester::request_blocks(self, io, peer_id, request, BlockSet::NewBlocks);
 							if self.state == SyncState::Idle {
 								self.state = SyncState::Blocks;
@@ -1269,8 +1301,9 @@ impl ChainSync {
                     // check queue fullness
                     let ancient_block_fullness = io.chain().ancient_block_queue_fullness();
 					if force || equal_or_higher_difficulty {
-						if ancient_block_fullness < 0.8 {
+						if ancient_block_fullness < 0.8 && self.allow_backfill(num_active_peers) {
                             if let Some(request) = self.old_blocks.as_mut().and_then(|d| d.request_blocks(peer_id, io, num_active_peers)) {
+                                self.download_scheduler.note_request(DownloadTier::Backfill, &request, Instant::now());
                                 SyncRequester::request_blocks(self, io, peer_id, request, BlockSet::OldBlocks);
                                 return;
                             }
@@ -1405,6 +1438,23 @@ impl ChainSync {
         };
     }
 
+    /// Whether an idle peer may be given ancient block requests. Limited to a share of the
+    /// active peers while blocks near the head are still being downloaded.
+    fn allow_backfill(&mut self, num_active_peers: usize) -> bool {
+        let head_pending = DownloadTier::classify(
+            self.new_blocks.last_imported_block_number(),
+            self.highest_block,
+        ) == DownloadTier::Head
+            && self.new_blocks.has_pending_downloads();
+        let backfill_peers = self
+            .peers
+            .values()
+            .filter(|p| p.asking != PeerAsking::Nothing && p.block_set == Some(BlockSet::OldBlocks))
+            .count();
+        self.download_scheduler
+            .allow_backfill(head_pending, backfill_peers, num_active_peers)
+    }
+
     /// Mark all outstanding requests as expired
     fn reset_downloads(&mut self, block_set: BlockSet) {
         trace!(target: "sync", "Resetting downloads for {:?}", block_set);
@@ -1592,19 +1642,33 @@ impl ChainSync {
     pub fn maintain_sync(&mut self, io: &mut dyn SyncIo) {
         self.maybe_start_snapshot_sync(io);
         self.check_resume(io);
+        self.relay_withheld_block(io);
+    }
+
+    /// Relay the withheld best block once its timestamp is reached.
+    fn relay_withheld_block(&mut self, io: &mut dyn SyncIo) {
+        if let Some(hash) = self.relay_policy.release(Instant::now()) {
+            if io.chain().chain_info().best_block_hash == hash {
+                trace!(target: "sync", "Relaying withheld block {}", hash);
+                SyncPropagator::propagate_latest_blocks(self, io, &[]);
+            }
+        }
     }
 
     // t_nb 11.4 called when block is imported to chain - propagates the blocks and updates transactions sent to peers
     pub fn chain_new_blocks(
         &mut self,
         io: &mut dyn SyncIo,
-        _imported: &[H256],
+        imported: &[H256],
         invalid: &[H256],
         enacted: &[H256],
         _retracted: &[H256],
         sealed: &[H256],
         proposed: &[Bytes],
     ) {
+        self.download_scheduler
+            .note_imported(imported, Instant::now());
+
         let queue_info = io.chain().queue_info();
         let is_syncing = self.status().is_syncing(queue_info);
 
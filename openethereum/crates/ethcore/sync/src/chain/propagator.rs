@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{cmp, collections::HashSet};
+use std::{cmp, collections::HashSet, time::Instant};
 
 use bytes::Bytes;
 use ethereum_types::H256;
@@ -276,6 +276,21 @@ impl SyncPropagator {
     // t_nb 11.4.1 propagate latest blocks to peers
     pub fn propagate_latest_blocks(sync: &mut ChainSync, io: &mut dyn SyncIo, sealed: &[H256]) {
         let chain_info = io.chain().chain_info();
+        if sealed.is_empty() {
+            let timestamp = io.chain().best_block_header().timestamp();
+            let now = sync.relay_policy.unix_now();
+            if !sync.relay_policy.allows(timestamp, now) {
+                debug!(target: "sync", "Withholding relay of block {} with future timestamp {}", chain_info.best_block_hash, timestamp);
+                sync.relay_policy.withhold(
+                    chain_info.best_block_hash,
+                    timestamp,
+                    now,
+                    Instant::now(),
+                );
+                return;
+            }
+        }
+        sync.relay_policy.clear_withheld();
         if (((chain_info.best_block_number as i64) - (sync.last_sent_block_number as i64)).abs()
             as BlockNumber)
             < MAX_PEER_LAG_PROPAGATION
@@ -0,0 +1,217 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Two-tier block download scheduling.
+//!
+//! Downloads of the newest `HEAD_TIER_SIZE` blocks form the head tier, everything else
+//! (ancient blocks and new blocks far behind the network head) is backfill. While the head tier
+//! has outstanding work only a share of the active peers is allowed to serve backfill, so fresh
+//! blocks are imported, and can be served, as soon as possible.
+
+use block_sync::BlockRequest;
+use ethereum_types::H256;
+use fastmap::H256FastMap;
+use std::{
+    cmp,
+    time::{Duration, Instant},
+};
+use types::BlockNumber;
+
+/// Number of blocks below the highest known block that belong to the head tier.
+pub const HEAD_TIER_SIZE: BlockNumber = 128;
+/// While the head tier has pending work, at most `1 / BACKFILL_PEERS_DIVISOR` of the active peers
+/// (and at least one) may download backfill.
+const BACKFILL_PEERS_DIVISOR: usize = 4;
+/// Maximum number of head-tier blocks tracked for time-to-serve.
+const MAX_TRACKED_BLOCKS: usize = 1024;
+/// Tracked requests older than this are assumed to never be imported.
+const STALE_REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Download tier of a block request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadTier {
+    /// Blocks close to the network head.
+    Head,
+    /// Ancient blocks and blocks far behind the network head.
+    Backfill,
+}
+
+impl DownloadTier {
+    /// Tier of the new blocks download given the last imported and the highest known block.
+    pub fn classify(last_imported: BlockNumber, highest: Option<BlockNumber>) -> Self {
+        match highest {
+            Some(highest) if highest > last_imported.saturating_add(HEAD_TIER_SIZE) => {
+                DownloadTier::Backfill
+            }
+            _ => DownloadTier::Head,
+        }
+    }
+}
+
+/// Download scheduling statistics.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DownloadStats {
+    /// Number of head-tier requests sent.
+    pub head_requests: u64,
+    /// Number of backfill requests sent.
+    pub backfill_requests: u64,
+    /// Number of times a peer was kept off backfill in favour of the head tier.
+    pub backfill_deferred: u64,
+    /// Number of head-tier blocks imported after their body was requested.
+    pub head_served: u64,
+    /// Sum of the time-to-serve of all served head-tier blocks, in milliseconds.
+    pub head_time_to_serve_total_ms: u64,
+    /// Longest time-to-serve of a head-tier block, in milliseconds.
+    pub head_time_to_serve_max_ms: u64,
+    /// Time-to-serve of the last served head-tier block, in milliseconds.
+    pub head_time_to_serve_last_ms: u64,
+}
+
+/// Decides whether peers may serve backfill and measures the time between the first body
+/// request of a head-tier block and its import.
+#[derive(Default)]
+pub struct DownloadScheduler {
+    requested: H256FastMap<Instant>,
+    stats: DownloadStats,
+}
+
+impl DownloadScheduler {
+    /// Whether an idle peer may be given a backfill request.
+    pub fn allow_backfill(
+        &mut self,
+        head_pending: bool,
+        backfill_peers: usize,
+        active_peers: usize,
+    ) -> bool {
+        if !head_pending {
+            return true;
+        }
+        let limit = cmp::max(1, active_peers / BACKFILL_PEERS_DIVISOR);
+        if backfill_peers < limit {
+            true
+        } else {
+            self.stats.backfill_deferred += 1;
+            false
+        }
+    }
+
+    /// Record a request sent to a peer.
+    pub fn note_request(&mut self, tier: DownloadTier, request: &BlockRequest, now: Instant) {
+        match tier {
+            DownloadTier::Head => self.stats.head_requests += 1,
+            DownloadTier::Backfill => {
+                self.stats.backfill_requests += 1;
+                return;
+            }
+        }
+        let hashes = match *request {
+            BlockRequest::Bodies { ref hashes } => hashes,
+            _ => return,
+        };
+        if self.requested.len() + hashes.len() > MAX_TRACKED_BLOCKS {
+            self.requested
+                .retain(|_, requested| now.duration_since(*requested) < STALE_REQUEST_TIMEOUT);
+        }
+        for hash in hashes {
+            if self.requested.len() >= MAX_TRACKED_BLOCKS {
+                break;
+            }
+            self.requested.entry(*hash).or_insert(now);
+        }
+    }
+
+    /// Record imported blocks.
+    pub fn note_imported(&mut self, hashes: &[H256], now: Instant) {
+        for hash in hashes {
+            if let Some(requested) = self.requested.remove(hash) {
+                let elapsed = now.duration_since(requested).as_millis() as u64;
+                self.stats.head_served += 1;
+                self.stats.head_time_to_serve_total_ms += elapsed;
+                self.stats.head_time_to_serve_last_ms = elapsed;
+                self.stats.head_time_to_serve_max_ms =
+                    cmp::max(self.stats.head_time_to_serve_max_ms, elapsed);
+            }
+        }
+    }
+
+    /// Forget all pending requests.
+    pub fn clear(&mut self) {
+        self.requested.clear();
+    }
+
+    /// Scheduling statistics collected so far.
+    pub fn stats(&self) -> DownloadStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_download_tier() {
+        assert_eq!(DownloadTier::classify(100, None), DownloadTier::Head);
+        assert_eq!(DownloadTier::classify(100, Some(228)), DownloadTier::Head);
+        assert_eq!(
+            DownloadTier::classify(100, Some(229)),
+            DownloadTier::Backfill
+        );
+        assert_eq!(DownloadTier::classify(300, Some(200)), DownloadTier::Head);
+    }
+
+    #[test]
+    fn limits_backfill_while_head_is_pending() {
+        let mut scheduler = DownloadScheduler::default();
+        assert!(scheduler.allow_backfill(false, 10, 10));
+        assert!(scheduler.allow_backfill(true, 0, 0));
+        assert!(!scheduler.allow_backfill(true, 1, 2));
+        assert!(scheduler.allow_backfill(true, 1, 8));
+        assert!(!scheduler.allow_backfill(true, 2, 8));
+        assert_eq!(scheduler.stats().backfill_deferred, 2);
+    }
+
+    #[test]
+    fn measures_head_time_to_serve() {
+        let mut scheduler = DownloadScheduler::default();
+        let start = Instant::now();
+        let head = BlockRequest::Bodies {
+            hashes: vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)],
+        };
+        let backfill = BlockRequest::Bodies {
+            hashes: vec![H256::from_low_u64_be(3)],
+        };
+        scheduler.note_request(DownloadTier::Head, &head, start);
+        scheduler.note_request(DownloadTier::Backfill, &backfill, start);
+
+        scheduler.note_imported(
+            &[H256::from_low_u64_be(1), H256::from_low_u64_be(3)],
+            start + Duration::from_millis(40),
+        );
+        scheduler.note_imported(
+            &[H256::from_low_u64_be(2)],
+            start + Duration::from_millis(60),
+        );
+
+        let stats = scheduler.stats();
+        assert_eq!(stats.head_requests, 1);
+        assert_eq!(stats.backfill_requests, 1);
+        assert_eq!(stats.head_served, 2);
+        assert_eq!(stats.head_time_to_serve_total_ms, 100);
+        assert_eq!(stats.head_time_to_serve_max_ms, 60);
+        assert_eq!(stats.head_time_to_serve_last_ms, 60);
+    }
+}
@@ -32,6 +32,9 @@ extern crate ethereum_types;
 extern crate ethkey;
 extern crate ethstore;
 extern crate fastmap;
+extern crate fetch;
+extern crate futures;
+extern crate hyper;
 extern crate keccak_hash as hash;
 extern crate parity_bytes as bytes;
 extern crate parity_crypto as crypto;
@@ -43,21 +46,23 @@ extern crate primitive_types07;
 extern crate rand;
 extern crate rand_xorshift;
 extern crate rlp;
+extern crate rustc_hex;
 extern crate stats;
+extern crate time_utils;
 extern crate triehash_ethereum;
 
 #[cfg(test)]
 extern crate env_logger;
 #[cfg(test)]
 extern crate kvdb_memorydb;
-#[cfg(test)]
-extern crate rustc_hex;
 
 #[macro_use]
 extern crate enum_primitive;
 #[macro_use]
 extern crate macros;
 #[macro_use]
+extern crate serde_json;
+#[macro_use]
 extern crate log;
 #[macro_use]
 extern crate trace_time;
@@ -66,6 +71,9 @@ extern crate ethcore_miner;
 mod block_sync;
 mod blocks;
 mod chain;
+mod download_scheduler;
+mod relay_policy;
+mod rpc_bridge;
 mod snapshot;
 mod sync_io;
 mod transactions_stats;
@@ -79,3 +87,4 @@ pub use api::*;
 pub use chain::{SyncState, SyncStatus};
 pub use devp2p::validate_node_url;
 pub use network::{ConnectionDirection, ConnectionFilter, Error, ErrorKind, NonReservedPeerMode};
+pub use rpc_bridge::{RpcBridge, RpcBridgeConfig, RpcBridgeError};
@@ -0,0 +1,187 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Relay policy for blocks with future timestamps.
+//!
+//! Blocks within the import drift allowance may still carry a timestamp ahead of the local
+//! clock. Relaying them right away amplifies time-manipulation attempts, so when a bound is
+//! configured the best block is only announced to peers once its timestamp is no further ahead
+//! of the local clock than the bound. The release deadline is kept as an `Instant`, so
+//! adjustments of the system clock after a block is withheld do not shorten the wait.
+
+use ethereum_types::H256;
+use network::PeerId;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use time_utils::{Clock, SharedClock};
+
+/// Block relay statistics.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RelayStats {
+    /// Number of best blocks withheld from relaying.
+    pub withheld: u64,
+    /// Number of withheld blocks relayed once their timestamp was reached.
+    pub released: u64,
+    /// Number of announced blocks with a timestamp beyond the bound.
+    pub announced_beyond_bound: u64,
+    /// Number of connected peers which announced a block with a timestamp beyond the bound.
+    pub peers_beyond_bound: usize,
+}
+
+/// Withholds relaying of blocks whose timestamp is too far ahead of the local clock.
+#[derive(Default)]
+pub struct RelayPolicy {
+    clock: SharedClock,
+    max_future: Option<u64>,
+    withheld: Option<(H256, Instant)>,
+    withheld_total: u64,
+    released: u64,
+    announced_beyond_bound: u64,
+    withheld_by_peer: HashMap<PeerId, u64>,
+}
+
+impl RelayPolicy {
+    /// Create a policy relaying blocks at most `max_future` ahead of `clock`.
+    /// `None` relays all blocks immediately.
+    pub fn new(max_future: Option<Duration>, clock: SharedClock) -> Self {
+        RelayPolicy {
+            clock,
+            max_future: max_future.map(|bound| bound.as_secs()),
+            ..Default::default()
+        }
+    }
+
+    /// Current UNIX time in seconds.
+    pub fn unix_now(&self) -> u64 {
+        self.clock.unix_now().as_secs()
+    }
+
+    /// Whether a block with the given timestamp may be relayed at UNIX time `now`.
+    pub fn allows(&self, timestamp: u64, now: u64) -> bool {
+        self.max_future
+            .map_or(true, |bound| timestamp <= now.saturating_add(bound))
+    }
+
+    /// Record a block announced by a peer. Returns `false` if its timestamp is beyond the bound.
+    pub fn note_announced(&mut self, peer: PeerId, timestamp: u64, now: u64) -> bool {
+        if self.allows(timestamp, now) {
+            return true;
+        }
+        self.announced_beyond_bound += 1;
+        *self.withheld_by_peer.entry(peer).or_insert(0) += 1;
+        false
+    }
+
+    /// Withhold relaying of the best block with the given timestamp, observed at UNIX time
+    /// `unix_now` and `now`, until it falls within the bound.
+    pub fn withhold(&mut self, hash: H256, timestamp: u64, unix_now: u64, now: Instant) {
+        match self.withheld {
+            Some((withheld, _)) if withheld == hash => return,
+            _ => {}
+        }
+        let bound = self.max_future.unwrap_or(0);
+        let wait = timestamp.saturating_sub(unix_now.saturating_add(bound));
+        self.withheld_total += 1;
+        self.withheld = Some((hash, now + Duration::from_secs(wait)));
+    }
+
+    /// Forget the withheld block, e.g. because a newer block has been relayed.
+    pub fn clear_withheld(&mut self) {
+        self.withheld = None;
+    }
+
+    /// Take the withheld block if its release deadline has passed at `now`.
+    pub fn release(&mut self, now: Instant) -> Option<H256> {
+        match self.withheld {
+            Some((hash, deadline)) if deadline <= now => {
+                self.withheld = None;
+                self.released += 1;
+                Some(hash)
+            }
+            _ => None,
+        }
+    }
+
+    /// Forget the statistics of a disconnected peer.
+    pub fn remove_peer(&mut self, peer: PeerId) {
+        self.withheld_by_peer.remove(&peer);
+    }
+
+    /// Relay statistics collected so far.
+    pub fn stats(&self) -> RelayStats {
+        RelayStats {
+            withheld: self.withheld_total,
+            released: self.released,
+            announced_beyond_bound: self.announced_beyond_bound,
+            peers_beyond_bound: self.withheld_by_peer.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use time_utils::MockClock;
+
+    #[test]
+    fn relays_everything_without_bound() {
+        let mut policy = RelayPolicy::new(None, SharedClock::default());
+        assert!(policy.allows(u64::max_value(), 0));
+        assert!(policy.note_announced(1, u64::max_value(), 0));
+        assert_eq!(policy.stats().announced_beyond_bound, 0);
+    }
+
+    #[test]
+    fn withholds_future_blocks_until_time_is_reached() {
+        let clock = Arc::new(MockClock::with_unix_secs(100));
+        let mut policy = RelayPolicy::new(
+            Some(Duration::from_secs(2)),
+            SharedClock::new(clock.clone()),
+        );
+        assert_eq!(policy.unix_now(), 100);
+        let hash = H256::from_low_u64_be(1);
+        let start = Instant::now();
+        assert!(policy.allows(102, 100));
+        assert!(!policy.allows(103, 100));
+
+        assert!(!policy.note_announced(7, 110, 100));
+        assert!(!policy.note_announced(7, 111, 100));
+        policy.withhold(hash, 110, 100, start);
+        // Observing the same block again with a clock set back does not extend the wait.
+        policy.withhold(hash, 110, 90, start);
+
+        assert_eq!(policy.release(start + Duration::from_secs(5)), None);
+        assert_eq!(policy.release(start + Duration::from_secs(8)), Some(hash));
+        assert_eq!(policy.release(start + Duration::from_secs(8)), None);
+
+        let stats = policy.stats();
+        assert_eq!(stats.withheld, 1);
+        assert_eq!(stats.released, 1);
+        assert_eq!(stats.announced_beyond_bound, 2);
+        assert_eq!(stats.peers_beyond_bound, 1);
+
+        policy.remove_peer(7);
+        let stats = policy.stats();
+        assert_eq!(stats.announced_beyond_bound, 2);
+        assert_eq!(stats.peers_beyond_bound, 0);
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(policy.unix_now(), 110);
+    }
+}
@@ -0,0 +1,739 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cold-start synchronization from a trusted JSON-RPC provider.
+//!
+//! Pulls raw blocks, and receipts for ancient blocks, from an HTTP(S) endpoint in
+//! batches. Every block must extend the hash chain leading from the local best (or
+//! best ancient) block and match the roots committed to in its header before it is
+//! queued for import. Once the local chain is within `handover_distance` blocks of
+//! the provider's head, forward syncing is left to the devp2p peers.
+
+use std::{
+    cmp,
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+    },
+    thread,
+    time::Duration,
+};
+
+use bytes::Bytes;
+use ethcore::{
+    client::{BlockChainClient, BlockId},
+    error::{
+        BlockError, Error as EthcoreError, ErrorKind as EthcoreErrorKind, ImportErrorKind,
+        QueueErrorKind,
+    },
+    verification::queue::kind::blocks::Unverified,
+};
+use ethereum_types::H256;
+use fetch::{self, Abort, BodyReader, Fetch, Request, Url};
+use futures::Future;
+use hash::keccak;
+use hyper::header::{self, HeaderValue};
+use rlp::{self, Rlp, RlpStream};
+use rustc_hex::FromHex;
+use serde_json::{self, Value};
+use triehash_ethereum::ordered_trie_root;
+use types::{
+    header::{Header, HeaderDecodeParams},
+    receipt::TypedReceipt,
+    BlockNumber,
+};
+
+/// Timeout of a single batched request to the provider.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Delay before polling again when the import queues are full.
+const QUEUE_FULL_DELAY: Duration = Duration::from_millis(500);
+/// Delay before retrying after a transport error.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+/// Number of consecutive transport errors after which the bridge gives up.
+const MAX_TRANSPORT_FAILURES: usize = 10;
+/// Ancient block queue fullness above which no more ancient blocks are requested.
+const MAX_ANCIENT_QUEUE_FULLNESS: f32 = 0.8;
+
+/// RPC bridge configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcBridgeConfig {
+    /// Trusted provider endpoint.
+    pub url: String,
+    /// Number of blocks requested in a single batch.
+    pub batch_size: u64,
+    /// Distance from the provider's head at which forward sync is handed over to p2p.
+    pub handover_distance: u64,
+    /// Fill the gap of ancient blocks left by a snapshot restoration.
+    pub download_old_blocks: bool,
+    /// Number of first block where EIP-1559 rules begin.
+    pub eip1559_transition: BlockNumber,
+    /// Timestamp from which blocks carry EIP-4895 withdrawals.
+    pub eip4895_transition_timestamp: u64,
+}
+
+impl RpcBridgeConfig {
+    fn header_decode_params(&self) -> HeaderDecodeParams {
+        HeaderDecodeParams {
+            eip1559_transition: self.eip1559_transition,
+            eip4895_transition_timestamp: self.eip4895_transition_timestamp,
+        }
+    }
+}
+
+impl Default for RpcBridgeConfig {
+    fn default() -> Self {
+        RpcBridgeConfig {
+            url: String::new(),
+            batch_size: 32,
+            handover_distance: 64,
+            download_old_blocks: true,
+            eip1559_transition: BlockNumber::max_value(),
+            eip4895_transition_timestamp: u64::max_value(),
+        }
+    }
+}
+
+/// RPC bridge error.
+#[derive(Debug, PartialEq)]
+pub enum RpcBridgeError {
+    /// The provider could not be reached. Retried.
+    Transport(String),
+    /// The provider returned an error or a malformed response.
+    Rpc(String),
+    /// The provider returned data that does not match the local chain.
+    Invalid(String),
+    /// The client refused to import a block.
+    Import(String),
+}
+
+impl fmt::Display for RpcBridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RpcBridgeError::Transport(ref e) => write!(f, "Transport error: {}", e),
+            RpcBridgeError::Rpc(ref e) => write!(f, "RPC error: {}", e),
+            RpcBridgeError::Invalid(ref e) => write!(f, "Invalid data: {}", e),
+            RpcBridgeError::Import(ref e) => write!(f, "Import error: {}", e),
+        }
+    }
+}
+
+/// Handle to the RPC bridge worker. The worker is stopped when the handle is dropped.
+pub struct RpcBridge {
+    stop: Arc<AtomicBool>,
+}
+
+impl RpcBridge {
+    /// Start pulling blocks from the configured provider into `client`.
+    pub fn start(
+        config: RpcBridgeConfig,
+        client: &Arc<dyn BlockChainClient>,
+        fetch: fetch::Client,
+    ) -> Result<Self, String> {
+        let url = Url::parse(&config.url)
+            .map_err(|e| format!("Invalid RPC bridge URL {}: {}", config.url, e))?;
+        if config.batch_size == 0 {
+            return Err("RPC bridge batch size must be greater than zero".into());
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = Worker {
+            config,
+            url,
+            fetch,
+            client: Arc::downgrade(client),
+            stop: stop.clone(),
+            forward: None,
+            ancient: None,
+            handed_over: false,
+        };
+        thread::Builder::new()
+            .name("rpc-bridge".into())
+            .spawn(move || worker.run())
+            .map_err(|e| format!("Error starting RPC bridge: {}", e))?;
+
+        Ok(RpcBridge { stop })
+    }
+
+    /// Stop the worker after the batch in flight.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for RpcBridge {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+enum Step {
+    /// A batch was processed, carry on immediately.
+    Continue,
+    /// The import queues are full.
+    Wait,
+    /// Nothing left to do.
+    Done,
+}
+
+struct Worker {
+    config: RpcBridgeConfig,
+    url: Url,
+    fetch: fetch::Client,
+    client: Weak<dyn BlockChainClient>,
+    stop: Arc<AtomicBool>,
+    /// Last block queued for forward import.
+    forward: Option<(BlockNumber, H256)>,
+    /// Last block queued for ancient import.
+    ancient: Option<(BlockNumber, H256)>,
+    handed_over: bool,
+}
+
+impl Worker {
+    fn run(mut self) {
+        info!(target: "sync", "Syncing from trusted RPC provider {}", self.url);
+        let mut failures = 0;
+        while !self.stop.load(Ordering::SeqCst) {
+            let client = match self.client.upgrade() {
+                Some(client) => client,
+                None => return,
+            };
+            match self.step(&*client) {
+                Ok(Step::Continue) => failures = 0,
+                Ok(Step::Wait) => {
+                    failures = 0;
+                    thread::sleep(QUEUE_FULL_DELAY);
+                }
+                Ok(Step::Done) => {
+                    info!(target: "sync", "RPC bridge finished");
+                    return;
+                }
+                Err(RpcBridgeError::Transport(e)) => {
+                    failures += 1;
+                    if failures >= MAX_TRANSPORT_FAILURES {
+                        warn!(target: "sync", "RPC bridge giving up after {} failed requests: {}", failures, e);
+                        return;
+                    }
+                    debug!(target: "sync", "RPC bridge request failed, retrying: {}", e);
+                    thread::sleep(RETRY_DELAY);
+                }
+                Err(e) => {
+                    warn!(target: "sync", "RPC bridge stopped: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn step(&mut self, client: &dyn BlockChainClient) -> Result<Step, RpcBridgeError> {
+        if !self.handed_over {
+            if client.queue_info().is_full() {
+                return Ok(Step::Wait);
+            }
+            let head = self.provider_head()?;
+            match self.sync_forward(client, head)? {
+                Step::Done => {
+                    info!(target: "sync", "Local chain is within {} blocks of the RPC provider head #{}, handing over to p2p sync", self.config.handover_distance, head);
+                    self.handed_over = true;
+                }
+                step => return Ok(step),
+            }
+        }
+
+        if self.config.download_old_blocks {
+            if client.ancient_block_queue_fullness() > MAX_ANCIENT_QUEUE_FULLNESS {
+                return Ok(Step::Wait);
+            }
+            return self.sync_ancient(client);
+        }
+        Ok(Step::Done)
+    }
+
+    /// Queue the next batch of blocks on top of the best block.
+    fn sync_forward(
+        &mut self,
+        client: &dyn BlockChainClient,
+        head: BlockNumber,
+    ) -> Result<Step, RpcBridgeError> {
+        let chain = client.chain_info();
+        // p2p sync may have moved past us, or replaced what we queued.
+        let (number, mut parent) = match self.forward {
+            Some((number, hash)) if number > chain.best_block_number => (number, hash),
+            _ => (chain.best_block_number, chain.best_block_hash),
+        };
+        if number + self.config.handover_distance >= head {
+            return Ok(Step::Done);
+        }
+
+        let last = cmp::min(number + self.config.batch_size, head);
+        let blocks = self.raw_blocks(number + 1, last)?;
+        for (offset, bytes) in blocks.into_iter().enumerate() {
+            let block = verify_block(
+                bytes,
+                number + 1 + offset as u64,
+                &parent,
+                self.config.header_decode_params(),
+            )?;
+            let (block_number, hash) = (block.header.number(), block.header.hash());
+            match client.import_block(block) {
+                Ok(_)
+                | Err(EthcoreError(EthcoreErrorKind::Import(ImportErrorKind::AlreadyInChain), _))
+                | Err(EthcoreError(EthcoreErrorKind::Import(ImportErrorKind::AlreadyQueued), _)) => {
+                }
+                Err(EthcoreError(EthcoreErrorKind::Block(BlockError::UnknownParent(_)), _)) => {
+                    trace!(target: "sync", "RPC bridge block #{} has unknown parent, restarting from best block", block_number);
+                    self.forward = None;
+                    return Ok(Step::Continue);
+                }
+                Err(EthcoreError(EthcoreErrorKind::Queue(QueueErrorKind::Full(_)), _)) => {
+                    return Ok(Step::Wait);
+                }
+                Err(e) => return Err(RpcBridgeError::Import(format!("#{}: {}", block_number, e))),
+            }
+            self.forward = Some((block_number, hash));
+            parent = hash;
+        }
+        trace!(target: "sync", "RPC bridge queued blocks #{}..#{}", number + 1, last);
+        Ok(Step::Continue)
+    }
+
+    /// Queue the next batch of blocks missing below the first block of a restored snapshot.
+    fn sync_ancient(&mut self, client: &dyn BlockChainClient) -> Result<Step, RpcBridgeError> {
+        let chain = client.chain_info();
+        let (ancient_number, ancient_hash, first_number) = match (
+            chain.ancient_block_number,
+            chain.ancient_block_hash,
+            chain.first_block_number,
+        ) {
+            (Some(number), Some(hash), Some(first)) => (number, hash, first),
+            _ => return Ok(Step::Done),
+        };
+        let (number, mut parent) = match self.ancient {
+            Some((number, hash)) if number > ancient_number => (number, hash),
+            _ => (ancient_number, ancient_hash),
+        };
+        if number + 1 >= first_number {
+            return Ok(Step::Done);
+        }
+
+        let last = cmp::min(number + self.config.batch_size, first_number - 1);
+        let mut calls = Vec::new();
+        for n in number + 1..=last {
+            calls.push(("debug_getRawBlock", json!([quantity(n)])));
+            calls.push(("debug_getRawReceipts", json!([quantity(n)])));
+        }
+        let results = self.call(calls)?;
+        for (offset, pair) in results.chunks(2).enumerate() {
+            let block_number = number + 1 + offset as u64;
+            let block = verify_block(
+                decode_bytes(&pair[0])?,
+                block_number,
+                &parent,
+                self.config.header_decode_params(),
+            )?;
+            let receipts = match pair[1] {
+                Value::Array(ref receipts) => receipts
+                    .iter()
+                    .map(decode_bytes)
+                    .collect::<Result<Vec<_>, _>>()?,
+                _ => return Err(RpcBridgeError::Rpc("expected a list of receipts".into())),
+            };
+            let receipts = verify_receipts(&block.header, &receipts)?;
+            let hash = block.header.hash();
+            if block_number + 1 == first_number {
+                let first_parent = client
+                    .block_header(BlockId::Number(first_number))
+                    .map(|h| h.parent_hash());
+                if first_parent != Some(hash) {
+                    return Err(RpcBridgeError::Invalid(format!(
+                        "block #{} {} is not the parent of the first block",
+                        block_number, hash
+                    )));
+                }
+            }
+            match client.queue_ancient_block(block, receipts) {
+                Ok(_)
+                | Err(EthcoreError(EthcoreErrorKind::Import(ImportErrorKind::AlreadyInChain), _)) =>
+                    {}
+                Err(EthcoreError(EthcoreErrorKind::Block(BlockError::UnknownParent(_)), _)) => {
+                    self.ancient = None;
+                    return Ok(Step::Wait);
+                }
+                Err(EthcoreError(EthcoreErrorKind::Queue(QueueErrorKind::Full(_)), _)) => {
+                    return Ok(Step::Wait);
+                }
+                Err(e) => return Err(RpcBridgeError::Import(format!("#{}: {}", block_number, e))),
+            }
+            self.ancient = Some((block_number, hash));
+            parent = hash;
+        }
+        trace!(target: "sync", "RPC bridge queued ancient blocks #{}..#{}", number + 1, last);
+        Ok(Step::Continue)
+    }
+
+    fn provider_head(&self) -> Result<BlockNumber, RpcBridgeError> {
+        let result = self.call(vec![("eth_blockNumber", json!([]))])?;
+        decode_quantity(&result[0])
+    }
+
+    fn raw_blocks(&self, from: BlockNumber, to: BlockNumber) -> Result<Vec<Bytes>, RpcBridgeError> {
+        let calls = (from..=to)
+            .map(|n| ("debug_getRawBlock", json!([quantity(n)])))
+            .collect();
+        self.call(calls)?.iter().map(decode_bytes).collect()
+    }
+
+    /// Send the calls as a single batch and return their results in order.
+    fn call(&self, calls: Vec<(&str, Value)>) -> Result<Vec<Value>, RpcBridgeError> {
+        let count = calls.len();
+        let batch: Vec<Value> = calls
+            .into_iter()
+            .enumerate()
+            .map(|(id, (method, params))| {
+                json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+            })
+            .collect();
+        let request = Request::post(self.url.clone())
+            .with_header(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            )
+            .with_body(Value::Array(batch).to_string());
+
+        let response = self
+            .fetch
+            .fetch(request, Abort::default().with_max_duration(REQUEST_TIMEOUT))
+            .wait()
+            .map_err(|e| RpcBridgeError::Transport(e.to_string()))?;
+        if !response.is_success() {
+            return Err(RpcBridgeError::Transport(format!(
+                "HTTP status {}",
+                response.status()
+            )));
+        }
+        let response: Value = serde_json::from_reader(BodyReader::new(response))
+            .map_err(|e| RpcBridgeError::Rpc(e.to_string()))?;
+        batch_results(response, count)
+    }
+}
+
+/// Match the responses of a batch to their requests.
+fn batch_results(response: Value, count: usize) -> Result<Vec<Value>, RpcBridgeError> {
+    let responses = match response {
+        Value::Array(responses) => responses,
+        // Servers reply with a single error object to a batch they cannot parse.
+        other => vec![other],
+    };
+    let mut results = HashMap::with_capacity(count);
+    for mut response in responses {
+        if let Some(error) = response.get("error") {
+            return Err(RpcBridgeError::Rpc(error.to_string()));
+        }
+        let id = response
+            .get("id")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| RpcBridgeError::Rpc("response without id".into()))?;
+        let result = response
+            .get_mut("result")
+            .map(Value::take)
+            .ok_or_else(|| RpcBridgeError::Rpc("response without result".into()))?;
+        results.insert(id as usize, result);
+    }
+    (0..count)
+        .map(|id| {
+            results
+                .remove(&id)
+                .ok_or_else(|| RpcBridgeError::Rpc(format!("missing response {}", id)))
+        })
+        .collect()
+}
+
+fn quantity(n: u64) -> String {
+    format!("0x{:x}", n)
+}
+
+fn decode_quantity(value: &Value) -> Result<u64, RpcBridgeError> {
+    value
+        .as_str()
+        .filter(|s| s.starts_with("0x"))
+        .and_then(|s| u64::from_str_radix(&s[2..], 16).ok())
+        .ok_or_else(|| RpcBridgeError::Rpc(format!("invalid quantity {}", value)))
+}
+
+fn decode_bytes(value: &Value) -> Result<Bytes, RpcBridgeError> {
+    value
+        .as_str()
+        .filter(|s| s.starts_with("0x"))
+        .and_then(|s| s[2..].from_hex().ok())
+        .ok_or_else(|| RpcBridgeError::Rpc("invalid hex data".into()))
+}
+
+/// Check that `bytes` is block `number`, that it is a child of `parent`, and that its
+/// transactions, uncles and withdrawals match the header.
+fn verify_block<P: Into<HeaderDecodeParams>>(
+    bytes: Bytes,
+    number: BlockNumber,
+    parent: &H256,
+    params: P,
+) -> Result<Unverified, RpcBridgeError> {
+    let (transactions_root, uncles_hash) =
+        {
+            let rlp = Rlp::new(&bytes);
+            let transactions = rlp
+                .at(1)
+                .map_err(|e| RpcBridgeError::Invalid(format!("block #{}: {}", number, e)))?;
+            let mut items = Vec::new();
+            for tx in transactions.iter() {
+                if tx.is_list() {
+                    items.push(tx.as_raw());
+                } else {
+                    items.push(tx.data().map_err(|e| {
+                        RpcBridgeError::Invalid(format!("block #{}: {}", number, e))
+                    })?);
+                }
+            }
+            let uncles = rlp
+                .at(2)
+                .map_err(|e| RpcBridgeError::Invalid(format!("block #{}: {}", number, e)))?;
+            (ordered_trie_root(items), keccak(uncles.as_raw()))
+        };
+    let block = Unverified::from_rlp(bytes, params)
+        .map_err(|e| RpcBridgeError::Invalid(format!("block #{}: {}", number, e)))?;
+
+    let header = &block.header;
+    if header.number() != number {
+        return Err(RpcBridgeError::Invalid(format!(
+            "expected block #{}, got #{}",
+            number,
+            header.number()
+        )));
+    }
+    if header.parent_hash() != parent {
+        return Err(RpcBridgeError::Invalid(format!(
+            "block #{} {} does not extend {}",
+            number,
+            header.hash(),
+            parent
+        )));
+    }
+    if *header.transactions_root() != transactions_root {
+        return Err(RpcBridgeError::Invalid(format!(
+            "block #{} transactions do not match the header",
+            number
+        )));
+    }
+    if *header.uncles_hash() != uncles_hash {
+        return Err(RpcBridgeError::Invalid(format!(
+            "block #{} uncles do not match the header",
+            number
+        )));
+    }
+    let withdrawals_root = block
+        .withdrawals
+        .as_ref()
+        .map(|withdrawals| ordered_trie_root(withdrawals.iter().map(rlp::encode)));
+    if header.withdrawals_root() != withdrawals_root.as_ref() {
+        return Err(RpcBridgeError::Invalid(format!(
+            "block #{} withdrawals do not match the header",
+            number
+        )));
+    }
+    Ok(block)
+}
+
+/// Check the consensus-encoded `receipts` against the receipts root of `header` and
+/// return them as an RLP list ready for ancient import.
+fn verify_receipts(header: &Header, receipts: &[Bytes]) -> Result<Bytes, RpcBridgeError> {
+    if ordered_trie_root(receipts) != *header.receipts_root() {
+        return Err(RpcBridgeError::Invalid(format!(
+            "block #{} receipts do not match the header",
+            header.number()
+        )));
+    }
+    let mut stream = RlpStream::new_list(receipts.len());
+    for receipt in receipts {
+        if Rlp::new(receipt).is_list() {
+            stream.append_raw(receipt, 1);
+        } else {
+            stream.append(receipt);
+        }
+    }
+    let list = stream.out();
+    TypedReceipt::decode_rlp_list(&Rlp::new(&list)).map_err(|e| {
+        RpcBridgeError::Invalid(format!("block #{} receipts: {}", header.number(), e))
+    })?;
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethcore::client::{BlockChain, EachBlockWith, TestBlockChainClient};
+    use types::receipt::{LegacyReceipt, TransactionOutcome, TypedTxId};
+
+    fn is_invalid<T>(result: &Result<T, RpcBridgeError>) -> bool {
+        match *result {
+            Err(RpcBridgeError::Invalid(_)) => true,
+            _ => false,
+        }
+    }
+
+    fn raw_block(client: &TestBlockChainClient, number: BlockNumber) -> Bytes {
+        client
+            .block(BlockId::Number(number))
+            .expect("block exists")
+            .into_inner()
+    }
+
+    #[test]
+    fn verifies_chain_of_blocks() {
+        let client = TestBlockChainClient::new();
+        client.add_blocks(5, EachBlockWith::Transaction);
+        let mut parent = client.block_hash(BlockId::Number(0)).unwrap();
+        for number in 1..=5 {
+            let block = verify_block(
+                raw_block(&client, number),
+                number,
+                &parent,
+                BlockNumber::max_value(),
+            )
+            .unwrap();
+            parent = block.header.hash();
+        }
+    }
+
+    #[test]
+    fn rejects_block_not_extending_parent() {
+        let client = TestBlockChainClient::new();
+        client.add_blocks(2, EachBlockWith::Nothing);
+        let result = verify_block(
+            raw_block(&client, 2),
+            2,
+            &H256::from_low_u64_be(1),
+            BlockNumber::max_value(),
+        );
+        assert!(is_invalid(&result));
+    }
+
+    #[test]
+    fn rejects_unexpected_block_number() {
+        let client = TestBlockChainClient::new();
+        client.add_blocks(2, EachBlockWith::Nothing);
+        let parent = client.block_hash(BlockId::Number(1)).unwrap();
+        let result = verify_block(raw_block(&client, 2), 3, &parent, BlockNumber::max_value());
+        assert!(is_invalid(&result));
+    }
+
+    #[test]
+    fn rejects_transactions_not_matching_header() {
+        let client = TestBlockChainClient::new();
+        client.add_blocks(2, EachBlockWith::Transaction);
+        let parent = client.block_hash(BlockId::Number(1)).unwrap();
+        let bytes = raw_block(&client, 2);
+        // Keep the header, drop the transactions.
+        let rlp = Rlp::new(&bytes);
+        let mut stream = RlpStream::new_list(3);
+        stream.append_raw(rlp.at(0).unwrap().as_raw(), 1);
+        stream.begin_list(0);
+        stream.append_raw(rlp.at(2).unwrap().as_raw(), 1);
+
+        let result = verify_block(stream.out(), 2, &parent, BlockNumber::max_value());
+        assert!(is_invalid(&result));
+    }
+
+    #[test]
+    fn keeps_withdrawals_of_verified_block() {
+        use ethereum_types::Address;
+        use types::withdrawal::Withdrawal;
+
+        let params = HeaderDecodeParams {
+            eip1559_transition: BlockNumber::max_value(),
+            eip4895_transition_timestamp: 0,
+        };
+        let withdrawal = Withdrawal {
+            index: 0,
+            validator_index: 1,
+            address: Address::from_low_u64_be(2),
+            amount: 3,
+        };
+        let mut header = Header::default();
+        header.set_number(1);
+        header.set_withdrawals_root(Some(ordered_trie_root(vec![rlp::encode(&withdrawal)])));
+        let encode_block = |header: &Header| {
+            let mut stream = RlpStream::new_list(4);
+            stream.append(header);
+            stream.begin_list(0);
+            stream.begin_list(0);
+            stream.append_list(&[withdrawal.clone()]);
+            stream.out()
+        };
+
+        let block = verify_block(encode_block(&header), 1, &H256::zero(), params).unwrap();
+        assert_eq!(block.withdrawals, Some(vec![withdrawal.clone()]));
+
+        header.set_withdrawals_root(Some(H256::from_low_u64_be(1)));
+        let result = verify_block(encode_block(&header), 1, &H256::zero(), params);
+        assert!(is_invalid(&result));
+    }
+
+    #[test]
+    fn verifies_receipts_against_header() {
+        let receipts: Vec<Bytes> = vec![
+            TypedReceipt::new(
+                TypedTxId::Legacy,
+                LegacyReceipt::new(TransactionOutcome::StatusCode(1), 21000.into(), vec![]),
+            )
+            .encode(),
+            TypedReceipt::new(
+                TypedTxId::AccessList,
+                LegacyReceipt::new(TransactionOutcome::StatusCode(0), 42000.into(), vec![]),
+            )
+            .encode(),
+        ];
+        let mut header = Header::default();
+        header.set_receipts_root(ordered_trie_root(&receipts));
+
+        let list = verify_receipts(&header, &receipts).unwrap();
+        assert_eq!(
+            TypedReceipt::decode_rlp_list(&Rlp::new(&list))
+                .unwrap()
+                .len(),
+            2
+        );
+
+        header.set_receipts_root(H256::from_low_u64_be(1));
+        assert!(is_invalid(&verify_receipts(&header, &receipts)));
+    }
+
+    #[test]
+    fn orders_batch_results_by_id() {
+        let response = json!([
+            { "jsonrpc": "2.0", "id": 1, "result": "0x2" },
+            { "jsonrpc": "2.0", "id": 0, "result": "0x1" },
+        ]);
+        assert_eq!(
+            batch_results(response, 2).unwrap(),
+            vec![json!("0x1"), json!("0x2")]
+        );
+
+        let response = json!([{ "jsonrpc": "2.0", "id": 0, "result": "0x1" }]);
+        match batch_results(response, 2) {
+            Err(RpcBridgeError::Rpc(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -34,9 +34,9 @@
 use crate::bytes::Bytes;
 
 use crate::{
-    header::Header,
+    header::{Header, HeaderDecodeParams},
     transaction::{TypedTransaction, UnverifiedTransaction},
-    BlockNumber,
+    withdrawal::Withdrawal,
 };
 use rlp::{DecoderError, Rlp, RlpStream};
 
@@ -49,29 +49,41 @@ pub struct Block {
     pub transactions: Vec<UnverifiedTransaction>,
     /// The uncles of this block.
     pub uncles: Vec<Header>,
+    /// The EIP-4895 withdrawals of this block, present once they are activated.
+    pub withdrawals: Option<Vec<Withdrawal>>,
 }
 
 impl Block {
     /// Get the RLP-encoding of the block with the seal.
     pub fn rlp_bytes(&self) -> Bytes {
-        let mut block_rlp = RlpStream::new_list(3);
+        let mut block_rlp = RlpStream::new_list(3 + self.withdrawals.is_some() as usize);
         block_rlp.append(&self.header);
         TypedTransaction::rlp_append_list(&mut block_rlp, &self.transactions);
         block_rlp.append_list(&self.uncles);
+        if let Some(ref withdrawals) = self.withdrawals {
+            block_rlp.append_list(withdrawals);
+        }
         block_rlp.out()
     }
 
-    pub fn decode_rlp(rlp: &Rlp, eip1559_transition: BlockNumber) -> Result<Self, DecoderError> {
+    pub fn decode_rlp<P: Into<HeaderDecodeParams>>(
+        rlp: &Rlp,
+        params: P,
+    ) -> Result<Self, DecoderError> {
+        let params = params.into();
         if rlp.as_raw().len() != rlp.payload_info()?.total() {
             return Err(DecoderError::RlpIsTooBig);
         }
-        if rlp.item_count()? != 3 {
-            return Err(DecoderError::RlpIncorrectListLen);
-        }
+        let withdrawals = match rlp.item_count()? {
+            3 => None,
+            4 => Some(rlp.list_at(3)?),
+            _ => return Err(DecoderError::RlpIncorrectListLen),
+        };
         Ok(Block {
-            header: Header::decode_rlp(&rlp.at(0)?, eip1559_transition)?,
+            header: Header::decode_rlp(&rlp.at(0)?, params)?,
             transactions: TypedTransaction::decode_rlp_list(&rlp.at(1)?)?,
-            uncles: Header::decode_rlp_list(&rlp.at(2)?, eip1559_transition)?,
+            uncles: Header::decode_rlp_list(&rlp.at(2)?, params)?,
+            withdrawals,
         })
     }
 }
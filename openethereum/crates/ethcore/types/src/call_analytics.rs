@@ -16,6 +16,8 @@
 
 //! Call analytics related types
 
+use std::time::Duration;
+
 /// Options concerning what analytics we run on the call.
 #[derive(Eq, PartialEq, Default, Clone, Copy, Debug)]
 pub struct CallAnalytics {
@@ -25,4 +27,7 @@ pub struct CallAnalytics {
     pub vm_tracing: bool,
     /// Make a diff.
     pub state_diffing: bool,
+    /// Wall-clock execution limit requested for this call. It can only shorten the
+    /// limit configured on the client.
+    pub timeout: Option<Duration>,
 }
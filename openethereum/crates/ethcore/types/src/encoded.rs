@@ -26,7 +26,7 @@
 use crate::{
     block::Block as FullBlock,
     hash::keccak,
-    header::Header as FullHeader,
+    header::{Header as FullHeader, HeaderDecodeParams},
     transaction::UnverifiedTransaction,
     views::{self, BlockView, BodyView, HeaderView},
     BlockNumber,
@@ -49,8 +49,11 @@ impl Header {
     }
 
     /// Upgrade this encoded view to a fully owned `Header` object.
-    pub fn decode(&self, eip1559_transition: BlockNumber) -> Result<FullHeader, rlp::DecoderError> {
-        FullHeader::decode_rlp(&self.rlp(), eip1559_transition)
+    pub fn decode<P: Into<HeaderDecodeParams>>(
+        &self,
+        params: P,
+    ) -> Result<FullHeader, rlp::DecoderError> {
+        FullHeader::decode_rlp(&self.rlp(), params)
     }
 
     /// Get a borrowed header view onto the data.
@@ -152,6 +155,11 @@ impl Header {
     pub fn base_fee(&self) -> U256 {
         self.view().base_fee()
     }
+
+    /// Mix hash, see `Header::mix_hash`.
+    pub fn mix_hash(&self) -> Option<H256> {
+        self.view().mix_hash()
+    }
 }
 
 /// Owning block body view.
@@ -259,10 +267,14 @@ impl Block {
 
     /// Create a new owning block view by concatenating the encoded header and body
     pub fn new_from_header_and_body(header: &views::HeaderView, body: &views::BodyView) -> Self {
-        let mut stream = RlpStream::new_list(3);
+        let item_count = 3 + body.withdrawals_rlp().is_some() as usize;
+        let mut stream = RlpStream::new_list(item_count);
         stream.append_raw(header.rlp().as_raw(), 1);
         stream.append_raw(body.transactions_rlp().as_raw(), 1);
         stream.append_raw(body.uncles_rlp().as_raw(), 1);
+        if let Some(withdrawals) = body.withdrawals_rlp() {
+            stream.append_raw(withdrawals.as_raw(), 1);
+        }
         Block::new(stream.out())
     }
 
@@ -279,20 +291,21 @@ impl Block {
     }
 
     /// Decode to a full block.
-    pub fn decode(&self, eip1559_transition: BlockNumber) -> Result<FullBlock, rlp::DecoderError> {
-        FullBlock::decode_rlp(&self.rlp(), eip1559_transition)
+    pub fn decode<P: Into<HeaderDecodeParams>>(
+        &self,
+        params: P,
+    ) -> Result<FullBlock, rlp::DecoderError> {
+        FullBlock::decode_rlp(&self.rlp(), params)
     }
 
     /// Decode the header.
-    pub fn decode_header(&self, eip1559_transition: BlockNumber) -> FullHeader {
-        FullHeader::decode_rlp(&self.view().rlp().at(0).rlp, eip1559_transition).unwrap_or_else(
-            |e| {
-                panic!(
-                    "block header, view rlp is trusted and should be valid: {:?}",
-                    e
-                )
-            },
-        )
+    pub fn decode_header<P: Into<HeaderDecodeParams>>(&self, params: P) -> FullHeader {
+        FullHeader::decode_rlp(&self.view().rlp().at(0).rlp, params).unwrap_or_else(|e| {
+            panic!(
+                "block header, view rlp is trusted and should be valid: {:?}",
+                e
+            )
+        })
     }
 
     /// Clone the encoded header.
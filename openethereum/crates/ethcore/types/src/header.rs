@@ -34,6 +34,24 @@ enum Seal {
     Without,
 }
 
+/// Chain parameters needed to tell optional trailing header fields apart from the seal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderDecodeParams {
+    /// Block number from which headers carry `base_fee_per_gas`.
+    pub eip1559_transition: BlockNumber,
+    /// Timestamp from which headers carry `withdrawals_root`.
+    pub eip4895_transition_timestamp: u64,
+}
+
+impl From<BlockNumber> for HeaderDecodeParams {
+    fn from(eip1559_transition: BlockNumber) -> Self {
+        HeaderDecodeParams {
+            eip1559_transition,
+            eip4895_transition_timestamp: u64::max_value(),
+        }
+    }
+}
+
 /// Extended block header, wrapping `Header` with finalized and total difficulty information.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExtendedHeader {
@@ -53,7 +71,7 @@ pub struct ExtendedHeader {
 /// Doesn't do all that much on its own.
 ///
 /// Two versions of header exist. First one is before EIP1559. Second version is after EIP1559.
-/// EIP1559 version added field base_fee_per_gas.
+/// EIP1559 version added field base_fee_per_gas. EIP4895 further appended withdrawals_root.
 #[derive(Debug, Clone, Eq, MallocSizeOf)]
 pub struct Header {
     /// Parent hash.
@@ -90,6 +108,9 @@ pub struct Header {
     /// Base fee per gas. Introduced by EIP1559.
     base_fee_per_gas: Option<U256>,
 
+    /// Withdrawals root. Introduced by EIP4895.
+    withdrawals_root: Option<H256>,
+
     /// Memoized hash of that header and the seal.
     hash: Option<H256>,
 }
@@ -117,6 +138,7 @@ impl PartialEq for Header {
             && self.difficulty == c.difficulty
             && self.seal == c.seal
             && self.base_fee_per_gas == c.base_fee_per_gas
+            && self.withdrawals_root == c.withdrawals_root
     }
 }
 
@@ -142,6 +164,7 @@ impl Default for Header {
             seal: vec![],
             hash: None,
             base_fee_per_gas: None,
+            withdrawals_root: None,
         }
     }
 }
@@ -222,11 +245,24 @@ impl Header {
         &self.seal
     }
 
+    /// Mix hash carried in the first seal field, as in Ethash seals. After the merge it holds
+    /// the randomness returned by the `DIFFICULTY` opcode (EIP-4399).
+    pub fn mix_hash(&self) -> Option<H256> {
+        self.seal
+            .first()
+            .and_then(|field| Rlp::new(field).as_val().ok())
+    }
+
     /// Get the base fee field of the header.
     pub fn base_fee(&self) -> Option<U256> {
         self.base_fee_per_gas
     }
 
+    /// Get the withdrawals root field of the header.
+    pub fn withdrawals_root(&self) -> Option<&H256> {
+        self.withdrawals_root.as_ref()
+    }
+
     /// Get the seal field with RLP-decoded values as bytes.
     pub fn decode_seal<'a, T: ::std::iter::FromIterator<&'a [u8]>>(
         &'a self,
@@ -315,6 +351,11 @@ impl Header {
         change_field(&mut self.hash, &mut self.base_fee_per_gas, a);
     }
 
+    /// Set the withdrawals root field of the header.
+    pub fn set_withdrawals_root(&mut self, a: Option<H256>) {
+        change_field(&mut self.hash, &mut self.withdrawals_root, a);
+    }
+
     /// Get the hash of this header (keccak of the RLP with seal).
     pub fn hash(&self) -> H256 {
         self.hash.unwrap_or_else(|| keccak(self.rlp(Seal::With)))
@@ -339,11 +380,9 @@ impl Header {
 
     /// Place this header into an RLP stream `s`, optionally `with_seal`.
     fn stream_rlp(&self, s: &mut RlpStream, with_seal: Seal) {
-        let stream_length_without_seal = if self.base_fee_per_gas.is_some() {
-            14
-        } else {
-            13
-        };
+        let stream_length_without_seal = 13
+            + self.base_fee_per_gas.is_some() as usize
+            + self.withdrawals_root.is_some() as usize;
 
         if let Seal::With = with_seal {
             s.begin_list(stream_length_without_seal + self.seal.len());
@@ -371,8 +410,12 @@ impl Header {
             }
         }
 
-        if self.base_fee_per_gas.is_some() {
-            s.append(&self.base_fee_per_gas.unwrap());
+        if let Some(ref base_fee) = self.base_fee_per_gas {
+            s.append(base_fee);
+        }
+
+        if let Some(ref withdrawals_root) = self.withdrawals_root {
+            s.append(withdrawals_root);
         }
     }
 }
@@ -389,7 +432,11 @@ where
 }
 
 impl Header {
-    pub fn decode_rlp(r: &Rlp, eip1559_transition: BlockNumber) -> Result<Self, DecoderError> {
+    pub fn decode_rlp<P: Into<HeaderDecodeParams>>(
+        r: &Rlp,
+        params: P,
+    ) -> Result<Self, DecoderError> {
+        let params = params.into();
         let mut blockheader = Header {
             parent_hash: r.val_at(0)?,
             uncles_hash: r.val_at(1)?,
@@ -407,33 +454,37 @@ impl Header {
             seal: vec![],
             hash: keccak(r.as_raw()).into(),
             base_fee_per_gas: None,
+            withdrawals_root: None,
         };
 
-        if blockheader.number >= eip1559_transition {
-            for i in 13..r.item_count()? - 1 {
-                blockheader.seal.push(r.at(i)?.as_raw().to_vec())
-            }
-            blockheader.base_fee_per_gas = Some(r.val_at(r.item_count()? - 1)?);
-        } else {
-            for i in 13..r.item_count()? {
-                blockheader.seal.push(r.at(i)?.as_raw().to_vec())
-            }
+        let mut seal_end = r.item_count()?;
+        if blockheader.timestamp >= params.eip4895_transition_timestamp {
+            seal_end -= 1;
+            blockheader.withdrawals_root = Some(r.val_at(seal_end)?);
+        }
+        if blockheader.number >= params.eip1559_transition {
+            seal_end -= 1;
+            blockheader.base_fee_per_gas = Some(r.val_at(seal_end)?);
+        }
+        for i in 13..seal_end {
+            blockheader.seal.push(r.at(i)?.as_raw().to_vec())
         }
 
         Ok(blockheader)
     }
 
-    pub fn decode_rlp_list(
+    pub fn decode_rlp_list<P: Into<HeaderDecodeParams>>(
         rlp: &Rlp,
-        eip1559_transition: BlockNumber,
+        params: P,
     ) -> Result<Vec<Self>, DecoderError> {
+        let params = params.into();
         if !rlp.is_list() {
             // at least one byte needs to be present
             return Err(DecoderError::RlpIncorrectListLen);
         }
         let mut output = Vec::with_capacity(rlp.item_count()?);
         for h in rlp.iter() {
-            output.push(Self::decode_rlp(&h, eip1559_transition)?);
+            output.push(Self::decode_rlp(&h, params)?);
         }
         Ok(output)
     }
@@ -456,8 +507,8 @@ impl ExtendedHeader {
 mod tests {
     use crate::BlockNumber;
 
-    use super::Header;
-    use ethereum_types::U256;
+    use super::{Header, HeaderDecodeParams};
+    use ethereum_types::{H256, U256};
     use rlp::{self, Rlp};
     use rustc_hex::FromHex;
 
@@ -561,4 +612,28 @@ mod tests {
 
         assert_ne!(hash_legacy, hash_1559);
     }
+
+    #[test]
+    fn decode_and_encode_header_with_withdrawals_root() {
+        let mut header = Header::new();
+        header.set_number(10);
+        header.set_timestamp(1000);
+        header.set_seal(vec![rlp::encode(&H256::zero()), rlp::encode(&0u64)]);
+        header.set_base_fee(Some(U256::from(7)));
+        header.set_withdrawals_root(Some(H256::from_low_u64_be(0x4895)));
+        let encoded = rlp::encode(&header);
+
+        let params = HeaderDecodeParams {
+            eip1559_transition: 0,
+            eip4895_transition_timestamp: 1000,
+        };
+        let decoded = Header::decode_rlp(&Rlp::new(&encoded), params).unwrap();
+        assert_eq!(decoded, header);
+        assert_eq!(decoded.seal().len(), 2);
+        assert_eq!(
+            decoded.withdrawals_root(),
+            Some(&H256::from_low_u64_be(0x4895))
+        );
+        assert_eq!(decoded.hash(), header.hash());
+    }
 }
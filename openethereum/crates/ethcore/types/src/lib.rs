@@ -68,10 +68,13 @@ pub mod restoration_status;
 pub mod security_level;
 pub mod snapshot_manifest;
 pub mod state_diff;
+pub mod state_override;
+pub mod timestamp;
 pub mod trace_filter;
 pub mod transaction;
 pub mod tree_route;
 pub mod verification_queue_info;
+pub mod withdrawal;
 
 /// Type for block number.
 pub type BlockNumber = u64;
@@ -25,6 +25,10 @@
 pub struct PruningInfo {
     /// The first block which everything can be served after.
     pub earliest_chain: u64,
+    /// The first block whose body and receipts are still stored. `0` if history was never expired.
+    pub earliest_history: u64,
+    /// The first block whose transactions can be looked up by hash. `0` if the index was never pruned.
+    pub earliest_transaction_index: u64,
     /// The first block where state requests may be served.
     pub earliest_state: u64,
 }
@@ -0,0 +1,40 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Account overrides applied to the state before a virtual call.
+
+use std::collections::BTreeMap;
+
+use crate::bytes::Bytes;
+use ethereum_types::{Address, H256, U256};
+
+/// Fields of a single account replaced for the duration of a call.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccountOverride {
+    /// Fake balance.
+    pub balance: Option<U256>,
+    /// Fake nonce.
+    pub nonce: Option<U256>,
+    /// Fake code.
+    pub code: Option<Bytes>,
+    /// Storage replacing the whole storage of the account.
+    pub state: Option<BTreeMap<H256, H256>>,
+    /// Storage slots patched on top of the existing storage.
+    pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+/// Account overrides keyed by address.
+pub type StateOverride = BTreeMap<Address, AccountOverride>;
@@ -0,0 +1,216 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Typed block timestamps and step durations.
+//!
+//! Header timestamps count seconds since the UNIX epoch, while local time is usually carried
+//! around as a `Duration` or `SystemTime`. These wrappers keep the unit explicit where the
+//! two meet: AuRa step durations and the timestamp conditions of queued transactions.
+//! Engine hooks and headers keep raw `u64` values, as InstantSeal may store milliseconds.
+//!
+//! All arithmetic is checked or saturating.
+
+use std::{
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Timestamp in seconds since the UNIX epoch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockTimestamp(u64);
+
+impl BlockTimestamp {
+    /// Timestamp of the given number of seconds since the UNIX epoch.
+    pub const fn new(value: u64) -> Self {
+        BlockTimestamp(value)
+    }
+
+    /// Timestamp of the given time since the UNIX epoch, truncated to whole seconds.
+    pub fn from_unix_time(time: Duration) -> Self {
+        BlockTimestamp(time.as_secs())
+    }
+
+    /// Timestamp of the given system time, truncated to whole seconds. Times before the UNIX
+    /// epoch map to zero.
+    pub fn from_system_time(time: SystemTime) -> Self {
+        time.duration_since(UNIX_EPOCH)
+            .map(Self::from_unix_time)
+            .unwrap_or_default()
+    }
+
+    /// Number of seconds since the UNIX epoch.
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// Time since the UNIX epoch.
+    pub fn as_unix_time(self) -> Duration {
+        Duration::from_secs(self.0)
+    }
+
+    /// System time of this timestamp, `None` if it isn't representable.
+    pub fn to_system_time(self) -> Option<SystemTime> {
+        UNIX_EPOCH.checked_add(self.as_unix_time())
+    }
+
+    /// Add a duration, truncated to whole seconds, returning `None` on overflow.
+    pub fn checked_add(self, duration: Duration) -> Option<Self> {
+        self.0.checked_add(duration.as_secs()).map(BlockTimestamp)
+    }
+
+    /// Add a duration, truncated to whole seconds, saturating on overflow.
+    pub fn saturating_add(self, duration: Duration) -> Self {
+        BlockTimestamp(self.0.saturating_add(duration.as_secs()))
+    }
+
+    /// Subtract a duration, truncated to whole seconds, saturating at zero.
+    pub fn saturating_sub(self, duration: Duration) -> Self {
+        BlockTimestamp(self.0.saturating_sub(duration.as_secs()))
+    }
+
+    /// Time elapsed since an earlier timestamp, `None` if `earlier` is later than `self`.
+    pub fn duration_since(self, earlier: BlockTimestamp) -> Option<Duration> {
+        self.0.checked_sub(earlier.0).map(Duration::from_secs)
+    }
+}
+
+impl From<u64> for BlockTimestamp {
+    fn from(value: u64) -> Self {
+        BlockTimestamp(value)
+    }
+}
+
+impl From<BlockTimestamp> for u64 {
+    fn from(timestamp: BlockTimestamp) -> Self {
+        timestamp.0
+    }
+}
+
+impl fmt::Display for BlockTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Duration of a consensus step, in whole seconds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StepDuration(u64);
+
+impl StepDuration {
+    /// Step duration of the given number of seconds.
+    pub const fn from_secs(secs: u64) -> Self {
+        StepDuration(secs)
+    }
+
+    /// Number of seconds of a step.
+    pub const fn as_secs(self) -> u64 {
+        self.0
+    }
+
+    /// Length of a step.
+    pub fn as_duration(self) -> Duration {
+        Duration::from_secs(self.0)
+    }
+
+    /// Number of whole steps between `start` and `time`, `None` for a zero step duration or
+    /// if `time` is before `start`.
+    pub fn steps_between(self, start: BlockTimestamp, time: BlockTimestamp) -> Option<u64> {
+        time.0.checked_sub(start.0)?.checked_div(self.0)
+    }
+
+    /// Timestamp at which the given number of steps after `start` begins, `None` on overflow.
+    pub fn step_start(self, start: BlockTimestamp, steps: u64) -> Option<BlockTimestamp> {
+        steps
+            .checked_mul(self.0)?
+            .checked_add(start.0)
+            .map(BlockTimestamp)
+    }
+}
+
+impl From<u64> for StepDuration {
+    fn from(secs: u64) -> Self {
+        StepDuration(secs)
+    }
+}
+
+impl From<StepDuration> for Duration {
+    fn from(step: StepDuration) -> Self {
+        step.as_duration()
+    }
+}
+
+impl fmt::Display for StepDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}s", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockTimestamp, StepDuration};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn converts_block_timestamps() {
+        let ts = BlockTimestamp::from_unix_time(Duration::from_millis(1_500_999));
+        assert_eq!(ts.as_u64(), 1_500);
+        assert_eq!(
+            BlockTimestamp::from_system_time(UNIX_EPOCH + Duration::from_secs(42)),
+            BlockTimestamp::new(42)
+        );
+        assert_eq!(
+            ts.to_system_time(),
+            Some(UNIX_EPOCH + Duration::from_secs(1_500))
+        );
+        assert_eq!(u64::from(ts), 1_500);
+    }
+
+    #[test]
+    fn block_timestamp_arithmetic() {
+        let ts = BlockTimestamp::new(100);
+        assert_eq!(
+            ts.saturating_add(Duration::from_millis(2_900)),
+            BlockTimestamp::new(102)
+        );
+        assert_eq!(
+            ts.saturating_sub(Duration::from_secs(200)),
+            BlockTimestamp::new(0)
+        );
+        assert_eq!(
+            BlockTimestamp::new(u64::max_value()).checked_add(Duration::from_secs(1)),
+            None
+        );
+        assert_eq!(
+            ts.duration_since(BlockTimestamp::new(40)),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(ts.duration_since(BlockTimestamp::new(101)), None);
+    }
+
+    #[test]
+    fn counts_steps() {
+        let step = StepDuration::from_secs(5);
+        let start = BlockTimestamp::new(100);
+        assert_eq!(step.steps_between(start, BlockTimestamp::new(112)), Some(2));
+        assert_eq!(step.steps_between(start, BlockTimestamp::new(99)), None);
+        assert_eq!(
+            StepDuration::from_secs(0).steps_between(start, BlockTimestamp::new(112)),
+            None
+        );
+        assert_eq!(step.step_start(start, 3), Some(BlockTimestamp::new(115)));
+        assert_eq!(step.step_start(start, u64::max_value()), None);
+    }
+}
@@ -95,6 +95,12 @@ pub enum Error {
     TransactionTypeNotEnabled,
     /// Transaction sender is not an EOA (see EIP-3607)
     SenderIsNotEOA,
+    /// Transaction sender already has too many transactions or too much gas in the queue.
+    SenderLimitReached,
+    /// EIP-4337 bundle transaction was rejected by the alt-mempool validator.
+    InvalidUserOperations(String),
+    /// Alt-mempool lane for EIP-4337 bundle transactions is full.
+    AltMempoolFull,
 }
 
 impl From<crypto::publickey::Error> for Error {
@@ -157,6 +163,9 @@ impl fmt::Display for Error {
                 format!("Transaction type is not enabled for current block")
             }
             SenderIsNotEOA => "Transaction sender is not an EOA (see EIP-3607)".into(),
+            SenderLimitReached => "Transaction sender has reached its limit in the queue".into(),
+            InvalidUserOperations(ref err) => format!("Bundle transaction is invalid: {}.", err),
+            AltMempoolFull => "Alt-mempool lane is full".into(),
         };
 
         f.write_fmt(format_args!("Transaction error ({})", msg))
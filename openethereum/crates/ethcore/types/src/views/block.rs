@@ -22,7 +22,7 @@ use super::ViewRlp;
 use crate::{
     bytes::Bytes,
     hash::keccak,
-    header::Header,
+    header::{Header, HeaderDecodeParams},
     transaction::{LocalizedTransaction, TypedTransaction, UnverifiedTransaction},
     views::{HeaderView, TypedTransactionView},
 };
@@ -66,8 +66,8 @@ impl<'a> BlockView<'a> {
     }
 
     /// Create new Header object from header rlp.
-    pub fn header(&self, eip1559_transition: BlockNumber) -> Header {
-        Header::decode_rlp(&self.rlp.at(0).rlp, eip1559_transition).unwrap_or_else(|e| {
+    pub fn header<P: Into<HeaderDecodeParams>>(&self, params: P) -> Header {
+        Header::decode_rlp(&self.rlp.at(0).rlp, params).unwrap_or_else(|e| {
             panic!(
                 "block header, view rlp is trusted and should be valid: {:?}",
                 e
@@ -171,6 +171,15 @@ impl<'a> BlockView<'a> {
         self.rlp.at(2)
     }
 
+    /// Returns raw rlp for the EIP-4895 withdrawals of the given block, if it carries any.
+    pub fn withdrawals_rlp(&self) -> Option<ViewRlp<'a>> {
+        if self.rlp.item_count() > 3 {
+            Some(self.rlp.at(3))
+        } else {
+            None
+        }
+    }
+
     /// Return list of uncles of given block.
     pub fn uncles(&self, eip1559_transition: BlockNumber) -> Vec<Header> {
         Header::decode_rlp_list(&self.rlp.at(2).rlp, eip1559_transition).unwrap_or_else(|e| {
@@ -146,6 +146,15 @@ impl<'a> BodyView<'a> {
         self.rlp.at(1)
     }
 
+    /// Returns raw rlp for the EIP-4895 withdrawals of the given block, if it carries any.
+    pub fn withdrawals_rlp(&self) -> Option<ViewRlp<'a>> {
+        if self.rlp.item_count() > 2 {
+            Some(self.rlp.at(2))
+        } else {
+            None
+        }
+    }
+
     /// Return list of uncles of given block.
     pub fn uncles(&self, eip1559_transition: BlockNumber) -> Vec<Header> {
         Header::decode_rlp_list(&self.rlp.at(1).rlp, eip1559_transition).unwrap_or_else(|e| {
@@ -137,6 +137,14 @@ impl<'a> HeaderView<'a> {
         seal
     }
 
+    /// Returns the first seal field decoded as a hash, see `Header::mix_hash`.
+    pub fn mix_hash(&self) -> Option<H256> {
+        if self.rlp.item_count() <= 13 {
+            return None;
+        }
+        rlp::Rlp::new(self.rlp.at(13).as_raw()).as_val().ok()
+    }
+
     /// Returns block base fee. Should be called only for EIP1559 headers.
     /// If called for non EIP1559 header, returns garbage
     pub fn base_fee(&self) -> U256 {
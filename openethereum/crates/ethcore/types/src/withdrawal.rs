@@ -0,0 +1,65 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Validator withdrawals pushed from the beacon chain (EIP-4895).
+
+use ethereum_types::{Address, U256};
+use parity_util_mem::MallocSizeOf;
+
+/// Number of wei in one gwei, the unit withdrawal amounts are denominated in.
+const WEI_PER_GWEI: u64 = 1_000_000_000;
+
+/// A single withdrawal of ether from the consensus layer.
+#[derive(Debug, Clone, PartialEq, Eq, MallocSizeOf, RlpEncodable, RlpDecodable)]
+pub struct Withdrawal {
+    /// Monotonically increasing index of the withdrawal.
+    pub index: u64,
+    /// Index of the validator the withdrawal belongs to.
+    pub validator_index: u64,
+    /// Recipient of the withdrawn ether.
+    pub address: Address,
+    /// Withdrawn amount in gwei.
+    pub amount: u64,
+}
+
+impl Withdrawal {
+    /// Withdrawn amount in wei.
+    pub fn amount_wei(&self) -> U256 {
+        U256::from(self.amount) * U256::from(WEI_PER_GWEI)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Withdrawal;
+    use ethereum_types::{Address, U256};
+
+    #[test]
+    fn withdrawal_rlp_roundtrip() {
+        let withdrawal = Withdrawal {
+            index: 7,
+            validator_index: 42,
+            address: Address::from_low_u64_be(0x10),
+            amount: 32_000_000_000,
+        };
+        let encoded = rlp::encode(&withdrawal);
+        assert_eq!(rlp::decode::<Withdrawal>(&encoded).unwrap(), withdrawal);
+        assert_eq!(
+            withdrawal.amount_wei(),
+            U256::from(32) * U256::from(10).pow(18.into())
+        );
+    }
+}
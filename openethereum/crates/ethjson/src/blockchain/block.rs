@@ -19,8 +19,24 @@
 use crate::{
     blockchain::{header::Header, transaction::Transaction},
     bytes::Bytes,
+    hash::Address,
+    uint::Uint,
 };
 
+/// Blockchain test withdrawal deserializer.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Withdrawal {
+    /// Withdrawal index.
+    pub index: Uint,
+    /// Validator index.
+    pub validator_index: Uint,
+    /// Recipient address.
+    pub address: Address,
+    /// Amount in gwei.
+    pub amount: Uint,
+}
+
 /// Blockchain test block deserializer.
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct Block {
@@ -30,6 +46,7 @@ pub struct Block {
     pub transactions: Option<Vec<Transaction>>,
     #[serde(rename = "uncleHeaders")]
     pub uncles: Option<Vec<Header>>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
 }
 
 impl Block {
@@ -41,7 +58,8 @@ impl Block {
 
 #[cfg(test)]
 mod tests {
-    use crate::blockchain::block::Block;
+    use crate::{blockchain::block::Block, uint::Uint};
+    use ethereum_types::U256;
     use serde_json;
 
     #[test]
@@ -73,4 +91,22 @@ mod tests {
         let _deserialized: Block = serde_json::from_str(s).unwrap();
         // TODO: validate all fields
     }
+
+    #[test]
+    fn block_with_withdrawals_deserialization() {
+        let s = r#"{
+			"rlp" : "0x",
+			"withdrawals" : [{
+				"index" : "0x0",
+				"validatorIndex" : "0x2a",
+				"address" : "0x8888f1f195afa192cfee860698584c030f4c9db1",
+				"amount" : "0x3b9aca00"
+			}]
+		}"#;
+        let deserialized: Block = serde_json::from_str(s).unwrap();
+        let withdrawals = deserialized.withdrawals.unwrap();
+        assert_eq!(withdrawals.len(), 1);
+        assert_eq!(withdrawals[0].validator_index, Uint(U256::from(42)));
+        assert_eq!(withdrawals[0].amount, Uint(U256::from(1_000_000_000)));
+    }
 }
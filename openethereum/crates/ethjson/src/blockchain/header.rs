@@ -64,6 +64,8 @@ pub struct Header {
     pub uncles_hash: H256,
     /// Base fee
     pub base_fee_per_gas: Option<Uint>,
+    /// Withdrawals root
+    pub withdrawals_root: Option<H256>,
 }
 
 #[cfg(test)]
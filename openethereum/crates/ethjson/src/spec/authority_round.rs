@@ -106,6 +106,11 @@ pub struct AuthorityRoundParams {
     /// the specified contracts (can be more than one per block)
     #[serde(rename = "rewriteBytecode")]
     pub rewrite_bytecode_transitions: Option<BTreeMap<Uint, BTreeMap<Address, Bytes>>>,
+    /// Block from which header timestamps are validated against the median timestamp of the
+    /// recent ancestors instead of only the parent timestamp.
+    pub median_time_past_transition: Option<Uint>,
+    /// Number of ancestors the median timestamp is taken over. Defaults to 11.
+    pub median_time_past_window: Option<Uint>,
 }
 
 /// Authority engine deserialization.
@@ -154,7 +159,9 @@ mod tests {
 				"blockGasLimitContractTransitions": {
 					"10": "0x1000000000000000000000000000000000000001",
                     "20": "0x2000000000000000000000000000000000000002"
-                }
+                },
+				"medianTimePastTransition": 100,
+				"medianTimePastWindow": 5
 			}
 		}"#;
 
@@ -208,6 +215,14 @@ mod tests {
             deserialized.params.block_gas_limit_contract_transitions,
             Some(expected_bglc.to_vec().into_iter().collect())
         );
+        assert_eq!(
+            deserialized.params.median_time_past_transition,
+            Some(Uint(100.into()))
+        );
+        assert_eq!(
+            deserialized.params.median_time_past_window,
+            Some(Uint(5.into()))
+        );
     }
 
     #[test]
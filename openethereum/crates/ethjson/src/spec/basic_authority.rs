@@ -28,6 +28,12 @@ pub struct BasicAuthorityParams {
     pub duration_limit: Uint,
     /// Valid authorities
     pub validators: ValidatorSet,
+    /// Block from which the block author may differ from the signer.
+    pub fee_recipient_transition: Option<Uint>,
+    /// Block from which validators take turns to seal.
+    pub round_robin_transition: Option<Uint>,
+    /// Seconds a backup validator waits per position behind the scheduled one before sealing.
+    pub round_robin_grace_period: Option<Uint>,
 }
 
 /// Authority engine deserialization.
@@ -67,5 +73,8 @@ mod tests {
             H160::from_str("c6d9d2cd449a754c494264e1809c50e34d64562b").unwrap(),
         )]);
         assert_eq!(deserialized.params.validators, vs);
+        assert_eq!(deserialized.params.fee_recipient_transition, None);
+        assert_eq!(deserialized.params.round_robin_transition, None);
+        assert_eq!(deserialized.params.round_robin_grace_period, None);
     }
 }
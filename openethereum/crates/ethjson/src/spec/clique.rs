@@ -16,15 +16,24 @@
 
 //! Clique params deserialization.
 
+use crate::hash::H64;
 use std::num::NonZeroU64;
 
 /// Clique params deserialization.
 #[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct CliqueParams {
     /// period as defined in EIP
     pub period: Option<u64>,
     /// epoch length as defined in EIP
     pub epoch: Option<NonZeroU64>,
+    /// Percentage of the signers a proposal has to exceed to pass, 50 if not set.
+    pub vote_threshold: Option<u64>,
+    /// Nonce of a vote to add the beneficiary to the signers, `0xff..ff` if not set.
+    pub auth_nonce: Option<H64>,
+    /// Nonce of a vote to remove the beneficiary, also carried by blocks without a vote,
+    /// `0x00..00` if not set.
+    pub drop_nonce: Option<H64>,
 }
 
 /// Clique engine deserialization.
@@ -51,5 +60,31 @@ mod tests {
         let deserialized: Clique = serde_json::from_str(s).unwrap();
         assert_eq!(deserialized.params.period, Some(5u64));
         assert_eq!(deserialized.params.epoch, NonZeroU64::new(30000));
+        assert_eq!(deserialized.params.vote_threshold, None);
+        assert_eq!(deserialized.params.auth_nonce, None);
+    }
+
+    #[test]
+    fn clique_voting_deserialization() {
+        let s = r#"{
+			"params": {
+				"period": 5,
+				"epoch": 100,
+				"voteThreshold": 66,
+				"authNonce": "0x0000000000000001",
+				"dropNonce": "0x0000000000000002"
+			}
+		}"#;
+
+        let deserialized: Clique = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialized.params.vote_threshold, Some(66));
+        assert_eq!(
+            deserialized.params.auth_nonce,
+            Some(H64(ethereum_types::H64::from_low_u64_be(1)))
+        );
+        assert_eq!(
+            deserialized.params.drop_nonce,
+            Some(H64(ethereum_types::H64::from_low_u64_be(2)))
+        );
     }
 }
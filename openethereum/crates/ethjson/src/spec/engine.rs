@@ -16,7 +16,10 @@
 
 //! Engine deserialization.
 
-use super::{AuthorityRound, BasicAuthority, Clique, Ethash, InstantSeal, NullEngine};
+use super::{
+    AuthorityRound, BasicAuthority, Clique, Ethash, ExternalEngine, InstantSeal, NullEngine,
+    Tendermint,
+};
 
 /// Engine deserialization.
 #[derive(Debug, PartialEq, Deserialize)]
@@ -36,6 +39,10 @@ pub enum Engine {
     AuthorityRound(AuthorityRound),
     /// Clique engine.
     Clique(Clique),
+    /// Tendermint engine.
+    Tendermint(Tendermint),
+    /// Engine delegating consensus rules to an out-of-process plugin.
+    External(ExternalEngine),
 }
 
 #[cfg(test)]
@@ -146,5 +153,33 @@ mod tests {
             Engine::Clique(_) => {} // Clique is unit tested in its own file.
             _ => panic!(),
         };
+
+        let s = r#"{
+			"tendermint": {
+				"params": {
+					"validators": {
+						"list": ["0xc6d9d2cd449a754c494264e1809c50e34d64562b"]
+					}
+				}
+			}
+		}"#;
+        let deserialized: Engine = serde_json::from_str(s).unwrap();
+        match deserialized {
+            Engine::Tendermint(_) => {} // Tendermint is unit tested in its own file.
+            _ => panic!(),
+        };
+
+        let s = r#"{
+			"external": {
+				"params": {
+					"endpoint": "tcp://127.0.0.1:9545"
+				}
+			}
+		}"#;
+        let deserialized: Engine = serde_json::from_str(s).unwrap();
+        match deserialized {
+            Engine::External(_) => {} // External is unit tested in its own file.
+            _ => panic!(),
+        };
     }
 }
@@ -0,0 +1,65 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! External engine params deserialization.
+
+use crate::uint::Uint;
+
+/// External engine params deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalEngineParams {
+    /// Plugin endpoint: `tcp://host:port`, or the path of a Unix socket.
+    pub endpoint: String,
+    /// Number of seal fields in a header, defaults to 1.
+    pub seal_fields: Option<Uint>,
+    /// Milliseconds to wait for a reply of the plugin, defaults to 5000.
+    pub timeout: Option<Uint>,
+    /// Milliseconds to wait for a connection to the plugin, defaults to 1000.
+    pub connect_timeout: Option<Uint>,
+}
+
+/// External engine descriptor.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalEngine {
+    /// External engine parameters.
+    pub params: ExternalEngineParams,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{spec::external::ExternalEngine, uint::Uint};
+    use ethereum_types::U256;
+    use serde_json;
+
+    #[test]
+    fn external_engine_deserialization() {
+        let s = r#"{
+			"params": {
+				"endpoint": "/run/consensus.sock",
+				"sealFields": "0x2"
+			}
+		}"#;
+
+        let deserialized: ExternalEngine = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialized.params.endpoint, "/run/consensus.sock");
+        assert_eq!(deserialized.params.seal_fields, Some(Uint(U256::from(2))));
+        assert_eq!(deserialized.params.timeout, None);
+        assert_eq!(deserialized.params.connect_timeout, None);
+    }
+}
@@ -24,6 +24,8 @@ pub struct InstantSealParams {
     /// Whether to enable millisecond timestamp.
     #[serde(default)]
     pub millisecond_timestamp: bool,
+    /// Seal a block every given number of seconds instead of on every transaction.
+    pub period_seconds: Option<u64>,
 }
 
 /// Instant seal engine descriptor.
@@ -23,14 +23,17 @@ pub mod builtin;
 pub mod clique;
 pub mod engine;
 pub mod ethash;
+pub mod external;
 pub mod genesis;
 pub mod instant_seal;
 pub mod null_engine;
+pub mod overlay;
 pub mod params;
 pub mod seal;
 pub mod spec;
 pub mod state;
 pub mod step_duration;
+pub mod tendermint;
 pub mod validator_set;
 
 pub use self::{
@@ -41,13 +44,16 @@ pub use self::{
     clique::{Clique, CliqueParams},
     engine::Engine,
     ethash::{BlockReward, Ethash, EthashParams},
+    external::{ExternalEngine, ExternalEngineParams},
     genesis::Genesis,
     instant_seal::{InstantSeal, InstantSealParams},
     null_engine::{NullEngine, NullEngineParams},
-    params::Params,
+    overlay::SpecOverlay,
+    params::{BlockRewardEra, Params},
     seal::{AuthorityRoundSeal, Ethereum, Seal, TendermintSeal},
     spec::{ForkSpec, Spec},
     state::State,
     step_duration::StepDuration,
+    tendermint::{Tendermint, TendermintParams},
     validator_set::ValidatorSet,
 };
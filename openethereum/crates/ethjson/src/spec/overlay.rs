@@ -0,0 +1,146 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chain spec overlay deserialization.
+//!
+//! An overlay moves fork transitions of a chain spec at load time, so a fork on a private network
+//! can be scheduled without distributing a whole new spec file:
+//!
+//! ```ignore
+//! {
+//!     "name": "emergency-fork-1",
+//!     "params": {
+//!         "eip1559Transition": "0x1e8480",
+//!         "eip3198Transition": null
+//!     }
+//! }
+//! ```
+//!
+//! A `null` transition is deactivated.
+
+use crate::{spec::Params, uint::Uint};
+use serde_json::{self, Error, Value};
+use std::{collections::BTreeMap, io::Read};
+
+/// Suffix of the spec params an overlay may change.
+const TRANSITION_SUFFIX: &str = "Transition";
+
+/// Chain spec overlay.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpecOverlay {
+    /// Overlay name.
+    pub name: Option<String>,
+    /// Transition blocks by spec param name, `None` deactivates the transition.
+    pub params: BTreeMap<String, Option<Uint>>,
+}
+
+impl SpecOverlay {
+    /// Loads overlay from json.
+    pub fn load<R>(reader: R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        serde_json::from_reader(reader)
+    }
+
+    /// Apply the overlay to a raw chain spec. Only fork transitions may be changed, unknown
+    /// transition names are rejected when the resulting spec is deserialized.
+    pub fn apply(&self, spec: &mut Value) -> Result<(), String> {
+        let params = spec
+            .get_mut("params")
+            .and_then(Value::as_object_mut)
+            .ok_or_else(|| "Chain spec has no params".to_owned())?;
+        for (name, block) in &self.params {
+            if !name.ends_with(TRANSITION_SUFFIX) || name.len() == TRANSITION_SUFFIX.len() {
+                return Err(format!("Overlay param {} is not a fork transition", name));
+            }
+            let block: u64 = block.map_or(u64::max_value(), Into::into);
+            params.insert(name.clone(), Value::String(format!("{:#x}", block)));
+        }
+        Ok(())
+    }
+
+    /// Check that every param of the overlay is a fork transition known to the spec params,
+    /// without loading a spec.
+    pub fn validate(&self) -> Result<(), String> {
+        // the params every spec has to define
+        let mut spec = serde_json::json!({
+            "params": {
+                "maximumExtraDataSize": "0x20",
+                "minGasLimit": "0x1388",
+                "networkID": "0x1",
+                "gasLimitBoundDivisor": "0x400",
+            }
+        });
+        self.apply(&mut spec)?;
+        serde_json::from_value::<Params>(spec["params"].take())
+            .map(|_| ())
+            .map_err(|e| format!("Invalid overlay: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpecOverlay;
+    use serde_json::{self, Value};
+
+    #[test]
+    fn overlay_moves_transitions() {
+        let overlay: SpecOverlay = serde_json::from_str(
+            r#"{
+			"name": "fork",
+			"params": {
+				"eip1559Transition": "0x10",
+				"eip3198Transition": null
+			}
+		}"#,
+        )
+        .unwrap();
+        let mut spec: Value = serde_json::from_str(
+            r#"{ "params": { "eip1559Transition": "0x0", "networkID": "0x1" } }"#,
+        )
+        .unwrap();
+
+        overlay.apply(&mut spec).unwrap();
+        assert_eq!(spec["params"]["eip1559Transition"], "0x10");
+        assert_eq!(spec["params"]["eip3198Transition"], "0xffffffffffffffff");
+        assert_eq!(spec["params"]["networkID"], "0x1");
+    }
+
+    #[test]
+    fn overlay_rejects_other_params() {
+        let overlay: SpecOverlay =
+            serde_json::from_str(r#"{ "params": { "networkID": "0x2" } }"#).unwrap();
+        let mut spec: Value = serde_json::from_str(r#"{ "params": {} }"#).unwrap();
+        assert!(overlay.apply(&mut spec).is_err());
+
+        assert!(serde_json::from_str::<SpecOverlay>(r#"{ "params": {}, "engine": {} }"#).is_err());
+    }
+
+    #[test]
+    fn overlay_validates_transition_names() {
+        let valid: SpecOverlay = serde_json::from_str(
+            r#"{ "params": { "eip1559Transition": "0x10", "eip3198Transition": null } }"#,
+        )
+        .unwrap();
+        assert!(valid.validate().is_ok());
+
+        let unknown: SpecOverlay =
+            serde_json::from_str(r#"{ "params": { "eip9999Transition": "0x1" } }"#).unwrap();
+        assert!(unknown.validate().is_err());
+    }
+}
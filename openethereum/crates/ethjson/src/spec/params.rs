@@ -120,8 +120,22 @@ pub struct Params {
     /// See `CommonParams` docs.
     pub eip3541_transition: Option<Uint>,
     /// See `CommonParams` docs.
+    pub eip3074_transition: Option<Uint>,
+    /// See `CommonParams` docs.
     pub eip3607_transition: Option<Uint>,
     /// See `CommonParams` docs.
+    pub eip4399_transition: Option<Uint>,
+    /// See `CommonParams` docs.
+    pub eip3198_transition_timestamp: Option<Uint>,
+    /// See `CommonParams` docs.
+    pub eip3529_transition_timestamp: Option<Uint>,
+    /// See `CommonParams` docs.
+    pub eip3541_transition_timestamp: Option<Uint>,
+    /// See `CommonParams` docs.
+    pub eip3074_transition_timestamp: Option<Uint>,
+    /// See `CommonParams` docs.
+    pub eip4895_transition_timestamp: Option<Uint>,
+    /// See `CommonParams` docs.
     pub dust_protection_transition: Option<Uint>,
     /// See `CommonParams` docs.
     pub nonce_cap_increment: Option<Uint>,
@@ -170,11 +184,37 @@ pub struct Params {
     pub eip1559_fee_collector_transition: Option<Uint>,
     /// Block at which zero gas price transactions start being checked with Certifier contract.
     pub validate_service_transactions_transition: Option<Uint>,
+    /// Sender of the system transactions injected by the consensus layer.
+    pub system_transaction_sender: Option<Address>,
+    /// Contracts system transactions are allowed to call.
+    pub system_transaction_targets: Option<Vec<Address>>,
+    /// Maximum number of system transactions in a block.
+    pub max_system_transactions_per_block: Option<Uint>,
+    /// Engine-independent block reward eras.
+    pub block_reward_schedule: Option<Vec<BlockRewardEra>>,
+}
+
+/// One era of the block reward schedule.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockRewardEra {
+    /// First block of the era.
+    pub block: Uint,
+    /// Reward of the block author in wei.
+    pub reward: Uint,
+    /// Divisor of the reward paid to each uncle. Uncles are rewarded by depth as in Ethash if
+    /// omitted.
+    #[serde(default, deserialize_with = "uint::validate_optional_non_zero")]
+    pub uncle_reward_divisor: Option<Uint>,
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{spec::params::Params, uint::Uint};
+    use crate::{
+        spec::params::{BlockRewardEra, Params},
+        uint::Uint,
+    };
     use ethereum_types::U256;
     use serde_json;
 
@@ -190,7 +230,8 @@ mod tests {
 			"gasLimitBoundDivisor": "0x20",
 			"maxCodeSize": "0x1000",
 			"wasmActivationTransition": "0x1010",
-            "wasmDisableTransition": "0x2010"
+            "wasmDisableTransition": "0x2010",
+            "eip3541TransitionTimestamp": "0x6516eac0"
 		}"#;
 
         let deserialized: Params = serde_json::from_str(s).unwrap();
@@ -213,6 +254,42 @@ mod tests {
             deserialized.wasm_disable_transition,
             Some(Uint(U256::from(0x2010)))
         );
+        assert_eq!(
+            deserialized.eip3541_transition_timestamp,
+            Some(Uint(U256::from(0x6516eac0)))
+        );
+        assert_eq!(deserialized.eip3529_transition_timestamp, None);
+    }
+
+    #[test]
+    fn block_reward_schedule_deserialization() {
+        let s = r#"{
+			"maximumExtraDataSize": "0x20",
+			"networkID" : "0x1",
+			"minGasLimit": "0x1388",
+			"gasLimitBoundDivisor": "0x20",
+			"blockRewardSchedule": [
+				{ "block": "0x0", "reward": "0x4563918244f40000" },
+				{ "block": "0x1e8480", "reward": "0x3782dace9d900000", "uncleRewardDivisor": "0x20" }
+			]
+		}"#;
+
+        let deserialized: Params = serde_json::from_str(s).unwrap();
+        assert_eq!(
+            deserialized.block_reward_schedule,
+            Some(vec![
+                BlockRewardEra {
+                    block: Uint(U256::zero()),
+                    reward: Uint(U256::from(5_000_000_000_000_000_000u64)),
+                    uncle_reward_divisor: None,
+                },
+                BlockRewardEra {
+                    block: Uint(U256::from(2_000_000)),
+                    reward: Uint(U256::from(4_000_000_000_000_000_000u64)),
+                    uncle_reward_divisor: Some(Uint(U256::from(32))),
+                },
+            ])
+        );
     }
 
     #[test]
@@ -40,6 +40,11 @@ pub enum ForkSpec {
     Berlin,
     London,
     BerlinToLondonAt5,
+    /// London with DIFFICULTY returning PREVRANDAO (EIP-4399).
+    #[serde(alias = "Paris")]
+    Merge,
+    /// London with EIP-3074 AUTH and AUTHCALL.
+    EIP3074,
 }
 
 /// Spec deserialization.
@@ -75,9 +80,18 @@ impl Spec {
 
 #[cfg(test)]
 mod tests {
-    use crate::spec::spec::Spec;
+    use crate::spec::spec::{ForkSpec, Spec};
     use serde_json;
 
+    #[test]
+    fn fork_spec_deserialization() {
+        let merge: ForkSpec = serde_json::from_str(r#""Merge""#).unwrap();
+        let paris: ForkSpec = serde_json::from_str(r#""Paris""#).unwrap();
+        assert_eq!(merge, ForkSpec::Merge);
+        assert_eq!(paris, ForkSpec::Merge);
+        assert!(ForkSpec::Merge > ForkSpec::London);
+    }
+
     #[test]
     fn should_error_on_unknown_fields() {
         let s = r#"{
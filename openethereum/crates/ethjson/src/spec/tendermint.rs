@@ -0,0 +1,84 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tendermint params deserialization.
+
+use super::ValidatorSet;
+use crate::uint::Uint;
+
+/// Tendermint params deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct TendermintParams {
+    /// Valid validators.
+    pub validators: ValidatorSet,
+    /// Propose step timeout in milliseconds.
+    pub timeout_propose: Option<Uint>,
+    /// Prevote step timeout in milliseconds.
+    pub timeout_prevote: Option<Uint>,
+    /// Precommit step timeout in milliseconds.
+    pub timeout_precommit: Option<Uint>,
+    /// Commit step timeout in milliseconds.
+    pub timeout_commit: Option<Uint>,
+    /// Reward per block.
+    pub block_reward: Option<Uint>,
+}
+
+/// Tendermint engine deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tendermint {
+    /// Tendermint parameters.
+    pub params: TendermintParams,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        hash::Address,
+        spec::{tendermint::Tendermint, validator_set::ValidatorSet},
+        uint::Uint,
+    };
+    use ethereum_types::{H160, U256};
+    use serde_json;
+    use std::str::FromStr;
+
+    #[test]
+    fn tendermint_deserialization() {
+        let s = r#"{
+			"params": {
+				"validators": {
+					"list": ["0xc6d9d2cd449a754c494264e1809c50e34d64562b"]
+				},
+				"timeoutPropose": "0x0a",
+				"blockReward": 5
+			}
+		}"#;
+
+        let deserialized: Tendermint = serde_json::from_str(s).unwrap();
+        let vs = ValidatorSet::List(vec![Address(
+            H160::from_str("c6d9d2cd449a754c494264e1809c50e34d64562b").unwrap(),
+        )]);
+        assert_eq!(deserialized.params.validators, vs);
+        assert_eq!(
+            deserialized.params.timeout_propose,
+            Some(Uint(U256::from(0x0a)))
+        );
+        assert_eq!(deserialized.params.timeout_prevote, None);
+        assert_eq!(deserialized.params.block_reward, Some(Uint(U256::from(5))));
+    }
+}
@@ -32,6 +32,14 @@ pub enum ValidatorSet {
     Contract(Address),
     /// A map of starting blocks for each validator set.
     Multi(BTreeMap<Uint, ValidatorSet>),
+    /// A primary set with a fallback used while the primary one is empty or its contract has no
+    /// code.
+    Fallback {
+        /// Set used while available.
+        primary: Box<ValidatorSet>,
+        /// Set used when the primary one fails.
+        fallback: Box<ValidatorSet>,
+    },
 }
 
 #[cfg(test)]
@@ -55,10 +63,15 @@ mod tests {
 				"10": { "list": ["0xd6d9d2cd449a754c494264e1809c50e34d64562b"] },
 				"20": { "contract": "0xc6d9d2cd449a754c494264e1809c50e34d64562b" }
 			}
+		}, {
+			"fallback": {
+				"primary": { "safeContract": "0xc6d9d2cd449a754c494264e1809c50e34d64562b" },
+				"fallback": { "list": ["0xd6d9d2cd449a754c494264e1809c50e34d64562b"] }
+			}
 		}]"#;
 
         let deserialized: Vec<ValidatorSet> = serde_json::from_str(s).unwrap();
-        assert_eq!(deserialized.len(), 4);
+        assert_eq!(deserialized.len(), 5);
 
         assert_eq!(
             deserialized[0],
@@ -87,5 +100,16 @@ mod tests {
             }
             _ => assert!(false),
         }
+        assert_eq!(
+            deserialized[4],
+            ValidatorSet::Fallback {
+                primary: Box::new(ValidatorSet::SafeContract(Address(
+                    H160::from_str("c6d9d2cd449a754c494264e1809c50e34d64562b").unwrap()
+                ))),
+                fallback: Box::new(ValidatorSet::List(vec![Address(
+                    H160::from_str("d6d9d2cd449a754c494264e1809c50e34d64562b").unwrap()
+                )])),
+            }
+        );
     }
 }
@@ -15,7 +15,10 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Vm environment.
-use crate::{hash::Address, uint::Uint};
+use crate::{
+    hash::{Address, H256},
+    uint::Uint,
+};
 
 /// Vm environment.
 #[derive(Debug, PartialEq, Deserialize)]
@@ -38,6 +41,9 @@ pub struct Env {
     /// Block base fee.
     #[serde(rename = "currentBaseFee")]
     pub base_fee: Option<Uint>,
+    /// Block randomness (EIP-4399).
+    #[serde(rename = "currentRandom")]
+    pub random: Option<H256>,
 }
 
 #[cfg(test)]
@@ -57,4 +63,21 @@ mod tests {
         let _deserialized: Env = serde_json::from_str(s).unwrap();
         // TODO: validate all fields
     }
+
+    #[test]
+    fn env_with_random_deserialization() {
+        let s = r#"{
+			"currentCoinbase" : "2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+			"currentDifficulty" : "0x00",
+			"currentGasLimit" : "0x0f4240",
+			"currentNumber" : "0x01",
+			"currentTimestamp" : "0x03e8",
+			"currentRandom" : "0x0000000000000000000000000000000000000000000000000000000000020000"
+		}"#;
+        let deserialized: Env = serde_json::from_str(s).unwrap();
+        assert_eq!(
+            deserialized.random.map(|random| random.0.to_low_u64_be()),
+            Some(0x20000)
+        );
+    }
 }
@@ -255,6 +255,10 @@ impl HostInfo {
         &self.config.client_version
     }
 
+    pub(crate) fn node_certificate(&self) -> Option<&Vec<u8>> {
+        self.config.node_certificate.as_ref()
+    }
+
     pub(crate) fn secret(&self) -> &Secret {
         self.keys.secret()
     }
@@ -301,24 +305,10 @@ impl Host {
             Some(addr) => addr,
         };
 
-        let keys = if let Some(ref secret) = config.use_secret {
-            KeyPair::from_secret(secret.clone())?
-        } else {
-            config
-                .config_path
-                .clone()
-                .and_then(|ref p| load_key(Path::new(&p)))
-                .map_or_else(
-                    || {
-                        let key = Random.generate();
-                        if let Some(path) = config.config_path.clone() {
-                            save_key(Path::new(&path), key.secret());
-                        }
-                        key
-                    },
-                    |s| KeyPair::from_secret(s).expect("Error creating node secret key"),
-                )
-        };
+        let keys = load_node_key(
+            config.use_secret.clone(),
+            config.config_path.as_ref().map(String::as_str),
+        )?;
         let path = config.net_config_path.clone();
         // Setup the server socket
         let tcp_listener = TcpListener::bind(&listen_address)?;
@@ -870,7 +860,12 @@ impl Host {
                             }
 
                             if !self.filter.as_ref().map_or(true, |f| {
-                                f.connection_allowed(&self_id, &id, ConnectionDirection::Inbound)
+                                f.session_allowed(
+                                    &self_id,
+                                    &id,
+                                    s.info.certificate.as_ref().map(|c| &c[..]),
+                                    ConnectionDirection::Inbound,
+                                )
                             }) {
                                 trace!(target: "network", "Inbound connection not allowed for {:?}", id);
                                 s.disconnect(io, DisconnectReason::UnexpectedIdentity);
@@ -1439,6 +1434,27 @@ impl IoHandler<NetworkIoMessage> for Host {
     }
 }
 
+/// Returns the node key: `use_secret` if given, otherwise the key stored in `config_path`.
+/// A new key is generated and stored if there is none yet.
+pub fn load_node_key(
+    use_secret: Option<Secret>,
+    config_path: Option<&str>,
+) -> Result<KeyPair, Error> {
+    if let Some(secret) = use_secret {
+        return Ok(KeyPair::from_secret(secret)?);
+    }
+    match config_path.and_then(|path| load_key(Path::new(path))) {
+        Some(secret) => Ok(KeyPair::from_secret(secret)?),
+        None => {
+            let key = Random.generate();
+            if let Some(path) = config_path {
+                save_key(Path::new(path), key.secret());
+            }
+            Ok(key)
+        }
+    }
+}
+
 fn save_key(path: &Path, key: &Secret) {
     let mut path_buf = PathBuf::from(path);
     if let Err(e) = fs::create_dir_all(path_buf.as_path()) {
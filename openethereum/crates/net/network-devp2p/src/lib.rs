@@ -112,7 +112,7 @@ mod node_table;
 mod service;
 mod session;
 
-pub use host::NetworkContext;
+pub use host::{load_node_key, NetworkContext};
 pub use service::NetworkService;
 
 pub use connection::PAYLOAD_SOFT_LIMIT;
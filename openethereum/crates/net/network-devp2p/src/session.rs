@@ -136,6 +136,7 @@ impl Session {
                 originated,
                 remote_address: "Handshake".to_owned(),
                 local_address: local_addr,
+                certificate: None,
             },
             ping_time: Instant::now(),
             pong_time: None,
@@ -507,12 +508,17 @@ impl Session {
     {
         let mut rlp = RlpStream::new();
         rlp.append_raw(&[PACKET_HELLO as u8], 0);
-        rlp.begin_list(5)
+        let certificate = host.node_certificate();
+        rlp.begin_list(if certificate.is_some() { 6 } else { 5 })
             .append(&host.protocol_version)
             .append(&host.client_version())
             .append_list(&host.capabilities)
             .append(&host.local_endpoint.address.port())
             .append(host.id());
+        // Additional Hello fields are ignored by peers which don't know them.
+        if let Some(certificate) = certificate {
+            rlp.append(certificate);
+        }
         self.send(io, &rlp.drain())
     }
 
@@ -530,6 +536,7 @@ impl Session {
         let client_version = ClientVersion::from(client_version_string);
         let peer_caps: Vec<PeerCapabilityInfo> = rlp.list_at(2)?;
         let id = rlp.val_at::<NodeId>(4)?;
+        let certificate = rlp.val_at::<Vec<u8>>(5).ok();
 
         // Intersect with host capabilities
         // Leave only highset mutually supported capability version
@@ -581,6 +588,7 @@ impl Session {
         self.info.client_version = client_version;
         self.info.capabilities = caps;
         self.info.peer_capabilities = peer_caps;
+        self.info.certificate = certificate;
         if self.info.capabilities.is_empty() {
             trace!(target: "network", "No common capabilities with peer.");
             return Err(self.disconnect(io, DisconnectReason::UselessPeer));
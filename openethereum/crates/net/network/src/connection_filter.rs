@@ -33,4 +33,16 @@ pub trait ConnectionFilter: Send + Sync {
         connecting_id: &NodeId,
         direction: ConnectionDirection,
     ) -> bool;
+
+    /// Filter an established session, once the peer has presented its (optional) node certificate.
+    /// Defaults to `connection_allowed`.
+    fn session_allowed(
+        &self,
+        own_id: &NodeId,
+        connecting_id: &NodeId,
+        _certificate: Option<&[u8]>,
+        direction: ConnectionDirection,
+    ) -> bool {
+        self.connection_allowed(own_id, connecting_id, direction)
+    }
 }
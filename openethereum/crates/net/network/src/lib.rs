@@ -126,6 +126,8 @@ pub struct SessionInfo {
     pub remote_address: String,
     /// Local endpoint address of the session
     pub local_address: String,
+    /// Node certificate presented by the peer in the Hello packet
+    pub certificate: Option<Vec<u8>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -216,6 +218,8 @@ pub struct NetworkConfiguration {
     pub ip_filter: IpFilter,
     /// Client identifier
     pub client_version: String,
+    /// Node certificate presented to peers in the Hello packet
+    pub node_certificate: Option<Vec<u8>>,
 }
 
 impl Default for NetworkConfiguration {
@@ -245,6 +249,7 @@ impl NetworkConfiguration {
             reserved_nodes: Vec::new(),
             non_reserved_mode: NonReservedPeerMode::Accept,
             client_version: "Parity-network".into(),
+            node_certificate: None,
         }
     }
 
@@ -0,0 +1,311 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Certificate based node filter.
+//!
+//! A node certificate binds an enode public key to a network certificate authority (CA): it is
+//! `rlp([node_id, expires, signature])`, where `signature` is the CA signature of
+//! `keccak(rlp([node_id, expires]))` and `expires` is a UNIX timestamp (`0` never expires).
+//! Nodes present their certificate in the devp2p Hello packet, which makes it possible to
+//! permission peers of a consortium network without an on-chain contract.
+
+use std::{fmt, sync::Arc};
+
+use crypto::publickey::{self, recover, Public, Secret, Signature};
+use devp2p::NodeId;
+use hash::keccak;
+use network::{ConnectionDirection, ConnectionFilter};
+use rlp::{DecoderError, Rlp, RlpStream};
+use time_utils::{Clock, SharedClock};
+
+/// Error verifying a node certificate.
+#[derive(Debug, PartialEq)]
+pub enum CertificateError {
+    /// The certificate couldn't be decoded.
+    Decoder(DecoderError),
+    /// The signature is invalid.
+    InvalidSignature,
+    /// The certificate is signed by an unknown authority.
+    UnknownAuthority(Public),
+    /// The certificate was issued for another node.
+    NodeMismatch(NodeId),
+    /// The certificate expired at the given timestamp.
+    Expired(u64),
+}
+
+impl fmt::Display for CertificateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CertificateError::Decoder(ref e) => write!(f, "Invalid certificate encoding: {}", e),
+            CertificateError::InvalidSignature => write!(f, "Invalid certificate signature"),
+            CertificateError::UnknownAuthority(ref ca) => {
+                write!(f, "Certificate signed by unknown authority {:?}", ca)
+            }
+            CertificateError::NodeMismatch(ref id) => {
+                write!(f, "Certificate issued for another node {:?}", id)
+            }
+            CertificateError::Expired(expires) => {
+                write!(f, "Certificate expired at {}", expires)
+            }
+        }
+    }
+}
+
+impl From<DecoderError> for CertificateError {
+    fn from(err: DecoderError) -> Self {
+        CertificateError::Decoder(err)
+    }
+}
+
+/// Node certificate issued by a network certificate authority.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeCertificate {
+    /// Certified node public key.
+    pub node_id: NodeId,
+    /// Expiry UNIX timestamp, `0` if the certificate never expires.
+    pub expires: u64,
+    /// Certificate authority signature.
+    pub signature: Signature,
+}
+
+impl NodeCertificate {
+    /// Issue a certificate for the given node with the certificate authority key.
+    pub fn sign(
+        node_id: NodeId,
+        expires: u64,
+        authority: &Secret,
+    ) -> Result<Self, publickey::Error> {
+        let signature = publickey::sign(authority, &Self::message(&node_id, expires))?;
+        Ok(NodeCertificate {
+            node_id,
+            expires,
+            signature,
+        })
+    }
+
+    /// Decode a certificate from its RLP encoding.
+    pub fn decode(bytes: &[u8]) -> Result<Self, CertificateError> {
+        let rlp = Rlp::new(bytes);
+        let signature: Vec<u8> = rlp.val_at(2)?;
+        if signature.len() != 65 {
+            return Err(CertificateError::InvalidSignature);
+        }
+        Ok(NodeCertificate {
+            node_id: rlp.val_at(0)?,
+            expires: rlp.val_at(1)?,
+            signature: Signature::from_electrum(&signature),
+        })
+    }
+
+    /// RLP encoding of the certificate.
+    pub fn rlp_bytes(&self) -> Vec<u8> {
+        let mut s = RlpStream::new_list(3);
+        s.append(&self.node_id)
+            .append(&self.expires)
+            .append(&(&*self.signature as &[u8]));
+        s.out()
+    }
+
+    /// Public key of the authority which signed the certificate.
+    pub fn authority(&self) -> Result<Public, CertificateError> {
+        recover(&self.signature, &Self::message(&self.node_id, self.expires))
+            .map_err(|_| CertificateError::InvalidSignature)
+    }
+
+    /// Check that the certificate was issued for `node_id` by one of `authorities` and has not
+    /// expired at UNIX timestamp `now`.
+    pub fn verify(
+        &self,
+        node_id: &NodeId,
+        authorities: &[Public],
+        now: u64,
+    ) -> Result<(), CertificateError> {
+        if self.node_id != *node_id {
+            return Err(CertificateError::NodeMismatch(self.node_id));
+        }
+        if self.expires != 0 && self.expires <= now {
+            return Err(CertificateError::Expired(self.expires));
+        }
+        let authority = self.authority()?;
+        if !authorities.contains(&authority) {
+            return Err(CertificateError::UnknownAuthority(authority));
+        }
+        Ok(())
+    }
+
+    fn message(node_id: &NodeId, expires: u64) -> publickey::Message {
+        let mut s = RlpStream::new_list(2);
+        s.append(node_id).append(&expires);
+        keccak(s.out())
+    }
+}
+
+/// Connection filter admitting only peers presenting a valid certificate of one of the
+/// configured authorities. Peers passing the certificate check are further checked against
+/// the inner filter, if any.
+pub struct CertificateFilter {
+    authorities: Vec<Public>,
+    clock: SharedClock,
+    inner: Option<Arc<dyn ConnectionFilter>>,
+}
+
+impl CertificateFilter {
+    /// Create a new filter trusting the given certificate authorities. Certificate expiry is
+    /// checked against `clock`.
+    pub fn new(
+        authorities: Vec<Public>,
+        clock: SharedClock,
+        inner: Option<Arc<dyn ConnectionFilter>>,
+    ) -> Self {
+        CertificateFilter {
+            authorities,
+            clock,
+            inner,
+        }
+    }
+}
+
+impl ConnectionFilter for CertificateFilter {
+    fn connection_allowed(
+        &self,
+        own_id: &NodeId,
+        connecting_id: &NodeId,
+        direction: ConnectionDirection,
+    ) -> bool {
+        // The certificate is only known once the session is established.
+        self.inner.as_ref().map_or(true, |f| {
+            f.connection_allowed(own_id, connecting_id, direction)
+        })
+    }
+
+    fn session_allowed(
+        &self,
+        own_id: &NodeId,
+        connecting_id: &NodeId,
+        certificate: Option<&[u8]>,
+        direction: ConnectionDirection,
+    ) -> bool {
+        let verified = match certificate {
+            Some(certificate) => NodeCertificate::decode(certificate).and_then(|c| {
+                c.verify(
+                    connecting_id,
+                    &self.authorities,
+                    self.clock.unix_now().as_secs(),
+                )
+            }),
+            None => {
+                debug!(target: "network", "Peer {:?} presented no node certificate", connecting_id);
+                return false;
+            }
+        };
+        if let Err(e) = verified {
+            debug!(target: "network", "Rejecting node certificate of {:?}: {}", connecting_id, e);
+            return false;
+        }
+        self.inner.as_ref().map_or(true, |f| {
+            f.session_allowed(own_id, connecting_id, certificate, direction)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CertificateError, CertificateFilter, NodeCertificate};
+    use crypto::publickey::{Generator, Random};
+    use network::{ConnectionDirection, ConnectionFilter};
+    use std::{sync::Arc, time::Duration};
+    use time_utils::{MockClock, SharedClock};
+
+    #[test]
+    fn verifies_node_certificate() {
+        let ca = Random.generate();
+        let other_ca = Random.generate();
+        let node = Random.generate();
+        let other_node = Random.generate();
+
+        let certificate = NodeCertificate::sign(*node.public(), 1000, ca.secret()).unwrap();
+        let decoded = NodeCertificate::decode(&certificate.rlp_bytes()).unwrap();
+        assert_eq!(decoded, certificate);
+        assert_eq!(decoded.verify(node.public(), &[*ca.public()], 999), Ok(()));
+        assert_eq!(
+            decoded.verify(node.public(), &[*ca.public()], 1000),
+            Err(CertificateError::Expired(1000))
+        );
+        assert_eq!(
+            decoded.verify(other_node.public(), &[*ca.public()], 999),
+            Err(CertificateError::NodeMismatch(*node.public()))
+        );
+        assert_eq!(
+            decoded.verify(node.public(), &[*other_ca.public()], 999),
+            Err(CertificateError::UnknownAuthority(*ca.public()))
+        );
+    }
+
+    #[test]
+    fn filters_sessions_by_certificate() {
+        let ca = Random.generate();
+        let own = Random.generate();
+        let node = Random.generate();
+        let clock = Arc::new(MockClock::with_unix_secs(999));
+        let filter =
+            CertificateFilter::new(vec![*ca.public()], SharedClock::new(clock.clone()), None);
+        let certificate = NodeCertificate::sign(*node.public(), 0, ca.secret())
+            .unwrap()
+            .rlp_bytes();
+
+        assert!(filter.connection_allowed(
+            own.public(),
+            node.public(),
+            ConnectionDirection::Outbound
+        ));
+        assert!(filter.session_allowed(
+            own.public(),
+            node.public(),
+            Some(&certificate[..]),
+            ConnectionDirection::Inbound
+        ));
+        assert!(!filter.session_allowed(
+            own.public(),
+            node.public(),
+            None,
+            ConnectionDirection::Inbound
+        ));
+        assert!(!filter.session_allowed(
+            own.public(),
+            node.public(),
+            Some(&[0xc0u8][..]),
+            ConnectionDirection::Inbound
+        ));
+
+        // expiry follows the filter clock
+        let expiring = NodeCertificate::sign(*node.public(), 1000, ca.secret())
+            .unwrap()
+            .rlp_bytes();
+        assert!(filter.session_allowed(
+            own.public(),
+            node.public(),
+            Some(&expiring[..]),
+            ConnectionDirection::Inbound
+        ));
+        clock.advance(Duration::from_secs(1));
+        assert!(!filter.session_allowed(
+            own.public(),
+            node.public(),
+            Some(&expiring[..]),
+            ConnectionDirection::Inbound
+        ));
+    }
+}
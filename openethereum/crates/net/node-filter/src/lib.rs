@@ -14,15 +14,19 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Smart contract based node filter.
+//! Smart contract and certificate based node filters.
 
 extern crate ethabi;
 extern crate ethcore;
 extern crate ethcore_network as network;
 extern crate ethcore_network_devp2p as devp2p;
 extern crate ethereum_types;
+extern crate keccak_hash as hash;
 extern crate lru_cache;
+extern crate parity_crypto as crypto;
 extern crate parking_lot;
+extern crate rlp;
+extern crate time_utils;
 
 extern crate ethabi_derive;
 #[macro_use]
@@ -36,6 +40,10 @@ extern crate tempdir;
 #[macro_use]
 extern crate log;
 
+mod certificate;
+
+pub use certificate::{CertificateError, CertificateFilter, NodeCertificate};
+
 use std::sync::Weak;
 
 use devp2p::NodeId;
@@ -20,10 +20,16 @@
 
 use std::{io, net::SocketAddr};
 
+#[cfg(windows)]
+mod pipe;
+
 pub use jsonrpc_core::{MetaIoHandler, Metadata, Middleware};
 
 /// Type alias for ipc server
 pub type IpcServer = ipc::Server;
+
+/// Namespace of Windows named pipes.
+pub const PIPE_PREFIX: &str = r"\\.\pipe\";
 /// Type alias for http server
 pub type HttpServer = http::Server;
 /// Type alias for ws server
@@ -64,10 +70,11 @@ where
 
 /// Same as `start_http`, but takes an additional `middleware` parameter that is introduced as a
 /// hyper middleware.
-pub fn start_http_with_middleware<M, S, H, T, R>(
+pub fn start_http_with_middleware<M, S, H, T, R, A, B>(
     addr: &SocketAddr,
     cors_domains: http::DomainsValidation<http::AccessControlAllowOrigin>,
     allowed_hosts: http::DomainsValidation<http::Host>,
+    health_api: Option<(A, B)>,
     handler: H,
     extractor: T,
     middleware: R,
@@ -83,19 +90,56 @@ where
     H: Into<jsonrpc_core::MetaIoHandler<M, S>>,
     T: http::MetaExtractor<M>,
     R: http::RequestMiddleware,
+    A: Into<String>,
+    B: Into<String>,
 {
     Ok(http::ServerBuilder::with_meta_extractor(handler, extractor)
         .keep_alive(keep_alive)
         .threads(threads)
         .cors(cors_domains)
         .allowed_hosts(allowed_hosts)
+        .health_api(health_api)
         .cors_allow_headers(http::cors::AccessControlAllowHeaders::Any)
         .max_request_body_size(max_payload * 1024 * 1024)
         .request_middleware(middleware)
         .start_http(addr)?)
 }
 
-/// Start IPC server listening on given path.
+/// Returns the endpoint of an IPC server configured with `path`.
+///
+/// IPC is served over Unix domain sockets, except on Windows where named pipes are used.
+/// There, `path` has to be in the pipe namespace: a pipe derived from a filesystem path could
+/// collide with the one of another node. The server refuses to start on a pipe created by
+/// another process and limits access to the current user, see `start_ipc`.
+pub fn ipc_endpoint(path: &str) -> io::Result<String> {
+    if cfg!(windows) {
+        pipe_endpoint(path)
+    } else {
+        Ok(path.to_owned())
+    }
+}
+
+fn pipe_endpoint(path: &str) -> io::Result<String> {
+    let is_pipe = path
+        .get(..PIPE_PREFIX.len())
+        .map_or(false, |prefix| prefix.eq_ignore_ascii_case(PIPE_PREFIX));
+    if is_pipe && path.len() > PIPE_PREFIX.len() {
+        Ok(path.to_owned())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "IPC path {} is not a named pipe, use a path like {}jsonrpc.ipc",
+                path, PIPE_PREFIX
+            ),
+        ))
+    }
+}
+
+/// Start IPC server listening on the endpoint of given path, see `ipc_endpoint`.
+///
+/// On Windows, fails if the pipe exists already, and only the current user and LocalSystem may
+/// connect to it.
 pub fn start_ipc<M, S, H, T>(addr: &str, handler: H, extractor: T) -> io::Result<ipc::Server>
 where
     M: jsonrpc_core::Metadata,
@@ -105,7 +149,18 @@ where
     H: Into<jsonrpc_core::MetaIoHandler<M, S>>,
     T: ipc::MetaExtractor<M>,
 {
-    ipc::ServerBuilder::with_meta_extractor(handler, extractor).start(addr)
+    let endpoint = ipc_endpoint(addr)?;
+    #[cfg(windows)]
+    pipe::ensure_unused(&endpoint)?;
+
+    let server = ipc::ServerBuilder::with_meta_extractor(handler, extractor).start(&endpoint)?;
+
+    #[cfg(windows)]
+    if let Err(e) = pipe::restrict_access(&endpoint) {
+        server.close();
+        return Err(e);
+    }
+    Ok(server)
 }
 
 /// Start WS server and return `Server` handle.
@@ -139,3 +194,24 @@ where
         .session_stats(stats)
         .start(addr)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::pipe_endpoint;
+
+    #[test]
+    fn should_only_accept_pipe_paths() {
+        assert_eq!(
+            pipe_endpoint(r"\\.\pipe\jsonrpc.ipc").unwrap(),
+            r"\\.\pipe\jsonrpc.ipc"
+        );
+        assert_eq!(
+            pipe_endpoint(r"\\.\PIPE\node-1").unwrap(),
+            r"\\.\PIPE\node-1"
+        );
+        assert!(pipe_endpoint(r"C:\Users\me\AppData\OpenEthereum\jsonrpc.ipc").is_err());
+        assert!(pipe_endpoint("$HOME/.local/jsonrpc-1.ipc").is_err());
+        assert!(pipe_endpoint(r"\\.\pipe\").is_err());
+        assert!(pipe_endpoint("").is_err());
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Access control of the named pipes IPC is served over on Windows.
+//!
+//! The default security descriptor of a named pipe lets every user read from it, and whoever
+//! creates a pipe name first owns it. Before the server starts, the pipe name is claimed as the
+//! first instance to make sure no other process created it. Once the server listens, the pipe is
+//! limited to the current user and LocalSystem.
+
+use std::{ffi::OsStr, io, iter, mem, os::windows::ffi::OsStrExt, ptr, slice};
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FALSE, HLOCAL, LPVOID},
+        sddl::{ConvertSidToStringSidW, ConvertStringSecurityDescriptorToSecurityDescriptorW},
+        winerror::ERROR_SUCCESS,
+    },
+    um::{
+        accctrl::SE_FILE_OBJECT,
+        aclapi::SetNamedSecurityInfoW,
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        minwinbase::SECURITY_ATTRIBUTES,
+        namedpipeapi::CreateNamedPipeW,
+        processthreadsapi::{GetCurrentProcess, OpenProcessToken},
+        securitybaseapi::{GetSecurityDescriptorDacl, GetTokenInformation},
+        winbase::{
+            LocalFree, FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX,
+            PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES,
+        },
+        winnt::{
+            TokenUser, DACL_SECURITY_INFORMATION, HANDLE, PACL,
+            PROTECTED_DACL_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, TOKEN_QUERY, TOKEN_USER,
+        },
+    },
+};
+
+/// Revision of the security descriptor strings.
+const SDDL_REVISION_1: DWORD = 1;
+
+/// Security descriptor granting full access to the current user and LocalSystem only.
+struct SecurityDescriptor(PSECURITY_DESCRIPTOR);
+
+impl SecurityDescriptor {
+    fn current_user_only() -> io::Result<Self> {
+        let sddl = wide(&format!("D:P(A;;GA;;;{})(A;;GA;;;SY)", current_user_sid()?));
+        let mut descriptor = ptr::null_mut();
+        let converted = unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                sddl.as_ptr(),
+                SDDL_REVISION_1,
+                &mut descriptor,
+                ptr::null_mut(),
+            )
+        };
+        if converted == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(SecurityDescriptor(descriptor))
+    }
+
+    fn dacl(&self) -> io::Result<PACL> {
+        let mut present = FALSE;
+        let mut defaulted = FALSE;
+        let mut dacl = ptr::null_mut();
+        if unsafe { GetSecurityDescriptorDacl(self.0, &mut present, &mut dacl, &mut defaulted) }
+            == FALSE
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(dacl)
+    }
+}
+
+impl Drop for SecurityDescriptor {
+    fn drop(&mut self) {
+        unsafe { LocalFree(self.0 as HLOCAL) };
+    }
+}
+
+/// Fails if a pipe with the given name exists, e.g. one created by another process to intercept
+/// the requests of clients.
+pub fn ensure_unused(path: &str) -> io::Result<()> {
+    let descriptor = SecurityDescriptor::current_user_only()?;
+    let handle = create_instance(path, &descriptor, true).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("IPC pipe {} is already in use: {}", path, e),
+        )
+    })?;
+    unsafe { CloseHandle(handle) };
+    Ok(())
+}
+
+/// Limit access to the existing pipe with the given name to the current user and LocalSystem.
+pub fn restrict_access(path: &str) -> io::Result<()> {
+    let descriptor = SecurityDescriptor::current_user_only()?;
+    let mut name = wide(path);
+    let result = unsafe {
+        SetNamedSecurityInfoW(
+            name.as_mut_ptr(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            descriptor.dacl()?,
+            ptr::null_mut(),
+        )
+    };
+    if result != ERROR_SUCCESS {
+        let e = io::Error::from_raw_os_error(result as i32);
+        return Err(io::Error::new(
+            e.kind(),
+            format!("Unable to restrict access to IPC pipe {}: {}", path, e),
+        ));
+    }
+    Ok(())
+}
+
+fn create_instance(path: &str, descriptor: &SecurityDescriptor, first: bool) -> io::Result<HANDLE> {
+    let name = wide(path);
+    let mut attributes = SECURITY_ATTRIBUTES {
+        nLength: mem::size_of::<SECURITY_ATTRIBUTES>() as DWORD,
+        lpSecurityDescriptor: descriptor.0,
+        bInheritHandle: FALSE,
+    };
+    let first = if first {
+        FILE_FLAG_FIRST_PIPE_INSTANCE
+    } else {
+        0
+    };
+    let handle = unsafe {
+        CreateNamedPipeW(
+            name.as_ptr(),
+            PIPE_ACCESS_DUPLEX | first,
+            PIPE_TYPE_BYTE | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            0,
+            0,
+            0,
+            &mut attributes,
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(handle)
+}
+
+fn current_user_sid() -> io::Result<String> {
+    unsafe {
+        let mut token = ptr::null_mut();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        // u64 words keep the buffer aligned for `TOKEN_USER`.
+        let mut buffer = vec![0u64; 64];
+        let mut length = 0;
+        let queried = GetTokenInformation(
+            token,
+            TokenUser,
+            buffer.as_mut_ptr() as LPVOID,
+            (buffer.len() * mem::size_of::<u64>()) as DWORD,
+            &mut length,
+        );
+        let error = io::Error::last_os_error();
+        CloseHandle(token);
+        if queried == FALSE {
+            return Err(error);
+        }
+
+        let user = &*(buffer.as_ptr() as *const TOKEN_USER);
+        let mut sid = ptr::null_mut();
+        if ConvertSidToStringSidW(user.User.Sid, &mut sid) == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        let len = (0..).take_while(|&i| *sid.offset(i) != 0).count();
+        let string = String::from_utf16_lossy(slice::from_raw_parts(sid, len));
+        LocalFree(sid as HLOCAL);
+        Ok(string)
+    }
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    OsStr::new(s).encode_wide().chain(iter::once(0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create_instance, ensure_unused, restrict_access, wide, SecurityDescriptor};
+    use std::{mem, process, ptr};
+    use winapi::{
+        shared::{minwindef::FALSE, winerror::ERROR_SUCCESS},
+        um::{
+            accctrl::SE_FILE_OBJECT,
+            aclapi::GetNamedSecurityInfoW,
+            handleapi::CloseHandle,
+            securitybaseapi::GetAclInformation,
+            winbase::LocalFree,
+            winnt::{AclSizeInformation, ACL_SIZE_INFORMATION, DACL_SECURITY_INFORMATION},
+        },
+    };
+
+    fn pipe_path(name: &str) -> String {
+        format!(r"\\.\pipe\oe-rpc-servers-{}-{}", process::id(), name)
+    }
+
+    #[test]
+    fn should_refuse_pipe_in_use() {
+        let path = pipe_path("in-use");
+        let descriptor = SecurityDescriptor::current_user_only().unwrap();
+        let squatter = create_instance(&path, &descriptor, false).unwrap();
+
+        assert!(ensure_unused(&path).is_err());
+
+        unsafe { CloseHandle(squatter) };
+        assert!(ensure_unused(&path).is_ok());
+    }
+
+    #[test]
+    fn should_restrict_access_to_current_user() {
+        let path = pipe_path("restricted");
+        let descriptor = SecurityDescriptor::current_user_only().unwrap();
+        // Setting and reading the security descriptor each connect to an instance.
+        let first = create_instance(&path, &descriptor, true).unwrap();
+        let second = create_instance(&path, &descriptor, false).unwrap();
+
+        restrict_access(&path).unwrap();
+
+        let mut name = wide(&path);
+        let mut dacl = ptr::null_mut();
+        let mut security_descriptor = ptr::null_mut();
+        let result = unsafe {
+            GetNamedSecurityInfoW(
+                name.as_mut_ptr(),
+                SE_FILE_OBJECT,
+                DACL_SECURITY_INFORMATION,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut dacl,
+                ptr::null_mut(),
+                &mut security_descriptor,
+            )
+        };
+        assert_eq!(result, ERROR_SUCCESS);
+        let mut info: ACL_SIZE_INFORMATION = unsafe { mem::zeroed() };
+        let queried = unsafe {
+            GetAclInformation(
+                dacl,
+                &mut info as *mut _ as *mut _,
+                mem::size_of::<ACL_SIZE_INFORMATION>() as u32,
+                AclSizeInformation,
+            )
+        };
+        assert_ne!(queried, FALSE);
+        // The current user and LocalSystem.
+        assert_eq!(info.AceCount, 2);
+
+        unsafe {
+            LocalFree(security_descriptor);
+            CloseHandle(first);
+            CloseHandle(second);
+        }
+    }
+}
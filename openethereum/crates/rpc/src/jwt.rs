@@ -0,0 +1,321 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Engine API style JWT authentication of RPC requests.
+//!
+//! Requests carry an `Authorization: Bearer <token>` header with a HS256 token
+//! signed with a shared 32-byte secret and an `iat` claim close to the current time.
+
+use std::{fmt, fs, path::Path};
+
+use authcodes::{DefaultTimeProvider, TimeProvider};
+use base64;
+use crypto::hmac;
+use http::{self, hyper};
+use rustc_hex::FromHex;
+use serde_json;
+
+/// Maximal difference in seconds between the `iat` claim and the current time.
+pub const IAT_TOLERANCE: u64 = 60;
+
+/// Length of the shared secret in bytes.
+const SECRET_LENGTH: usize = 32;
+
+/// Reasons for rejecting a request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// No bearer token in the `Authorization` header.
+    MissingToken,
+    /// Token is not a valid JWT.
+    Malformed,
+    /// Token is not signed with HS256.
+    UnsupportedAlgorithm(String),
+    /// Signature does not match the secret.
+    InvalidSignature,
+    /// `iat` claim is too far from the current time.
+    Stale {
+        /// Issued at claim of the token
+        iat: u64,
+        /// Current time
+        now: u64,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::MissingToken => write!(f, "Missing bearer token"),
+            Error::Malformed => write!(f, "Malformed token"),
+            Error::UnsupportedAlgorithm(ref alg) => write!(f, "Unsupported algorithm {}", alg),
+            Error::InvalidSignature => write!(f, "Invalid token signature"),
+            Error::Stale { iat, now } => write!(
+                f,
+                "Token issued at {} is more than {}s away from {}",
+                iat, IAT_TOLERANCE, now
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    iat: u64,
+}
+
+/// Secret shared with the clients allowed to call the RPC.
+#[derive(Clone, PartialEq)]
+pub struct JwtSecret([u8; SECRET_LENGTH]);
+
+impl fmt::Debug for JwtSecret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "JwtSecret")
+    }
+}
+
+impl JwtSecret {
+    /// Parses hex encoded secret, optionally prefixed with `0x`.
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let hex = hex.trim();
+        let bytes: Vec<u8> = hex
+            .trim_start_matches("0x")
+            .from_hex()
+            .map_err(|e| format!("Invalid JWT secret: {}", e))?;
+        if bytes.len() != SECRET_LENGTH {
+            return Err(format!(
+                "Invalid JWT secret: expected {} bytes, got {}",
+                SECRET_LENGTH,
+                bytes.len()
+            ));
+        }
+
+        let mut secret = [0u8; SECRET_LENGTH];
+        secret.copy_from_slice(&bytes);
+        Ok(JwtSecret(secret))
+    }
+
+    /// Reads hex encoded secret from file.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read JWT secret from {}: {}", path.display(), e))?;
+        Self::from_hex(&content)
+    }
+
+    /// Validates `token` at `now` seconds since epoch.
+    pub fn validate(&self, token: &str, now: u64) -> Result<(), Error> {
+        let mut parts = token.split('.');
+        let (header, claims, signature) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(header), Some(claims), Some(signature)) if parts.next().is_none() => {
+                (header, claims, signature)
+            }
+            _ => return Err(Error::Malformed),
+        };
+
+        let decode = |part: &str| {
+            base64::decode_config(part, base64::URL_SAFE_NO_PAD).map_err(|_| Error::Malformed)
+        };
+        let header: Header =
+            serde_json::from_slice(&decode(header)?).map_err(|_| Error::Malformed)?;
+        if header.alg != "HS256" {
+            return Err(Error::UnsupportedAlgorithm(header.alg));
+        }
+
+        let signed = &token[..token.len() - signature.len() - 1];
+        let key = hmac::VerifyKey::sha256(&self.0);
+        if !hmac::verify(&key, signed.as_bytes(), &decode(signature)?) {
+            return Err(Error::InvalidSignature);
+        }
+
+        let claims: Claims =
+            serde_json::from_slice(&decode(claims)?).map_err(|_| Error::Malformed)?;
+        let distance = if claims.iat > now {
+            claims.iat - now
+        } else {
+            now - claims.iat
+        };
+        if distance > IAT_TOLERANCE {
+            return Err(Error::Stale {
+                iat: claims.iat,
+                now,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Validates the value of an `Authorization` header at `now` seconds since epoch.
+    pub fn validate_header(&self, authorization: Option<&[u8]>, now: u64) -> Result<(), Error> {
+        let token = authorization
+            .and_then(|value| ::std::str::from_utf8(value).ok())
+            .and_then(|value| {
+                let value = value.trim();
+                if value.len() > 7 && value[..7].eq_ignore_ascii_case("bearer ") {
+                    Some(value[7..].trim())
+                } else {
+                    None
+                }
+            })
+            .ok_or(Error::MissingToken)?;
+        self.validate(token, now)
+    }
+}
+
+/// HTTP request middleware rejecting requests without a valid JWT.
+pub struct JwtMiddleware<T: TimeProvider = DefaultTimeProvider> {
+    secret: JwtSecret,
+    now: T,
+}
+
+impl JwtMiddleware {
+    /// Creates new `JwtMiddleware` validating tokens against `secret`.
+    pub fn new(secret: JwtSecret) -> Self {
+        JwtMiddleware {
+            secret,
+            now: DefaultTimeProvider,
+        }
+    }
+}
+
+impl<T: TimeProvider + Send + Sync + 'static> http::RequestMiddleware for JwtMiddleware<T> {
+    fn on_request(&self, request: hyper::Request<hyper::Body>) -> http::RequestMiddlewareAction {
+        let authorization = request
+            .headers()
+            .get(hyper::header::AUTHORIZATION)
+            .map(|value| value.as_bytes());
+        match self.secret.validate_header(authorization, self.now.now()) {
+            Ok(()) => request.into(),
+            Err(e) => {
+                debug!(target: "rpc", "Rejected unauthenticated HTTP request: {}", e);
+                let mut response = hyper::Response::new(hyper::Body::from(e.to_string()));
+                *response.status_mut() = hyper::StatusCode::UNAUTHORIZED;
+                response.into()
+            }
+        }
+    }
+}
+
+/// HTTP request middleware serving the health endpoint without authentication.
+///
+/// `GET` requests for `path` or one of its `aliases` are directed to `path` and pass without a
+/// token. Any other request goes through `jwt` if set, including a `POST` to a health path, which
+/// the server would handle as a JSON-RPC call.
+pub struct HealthMiddleware {
+    path: &'static str,
+    aliases: Vec<&'static str>,
+    jwt: Option<JwtMiddleware>,
+}
+
+impl HealthMiddleware {
+    /// Creates new `HealthMiddleware` for the health endpoint served at `path`.
+    pub fn new(path: &'static str, aliases: Vec<&'static str>, jwt: Option<JwtMiddleware>) -> Self {
+        HealthMiddleware { path, aliases, jwt }
+    }
+}
+
+impl http::RequestMiddleware for HealthMiddleware {
+    fn on_request(
+        &self,
+        mut request: hyper::Request<hyper::Body>,
+    ) -> http::RequestMiddlewareAction {
+        let is_probe = *request.method() == hyper::Method::GET;
+        let path = request.uri().path();
+        if is_probe && (path == self.path || self.aliases.contains(&path)) {
+            *request.uri_mut() = hyper::Uri::from_static(self.path);
+            return request.into();
+        }
+        match self.jwt {
+            Some(ref jwt) => jwt.on_request(request),
+            None => request.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "0x7365637265747365637265747365637265747365637265747365637265747365";
+
+    fn token(secret: &JwtSecret, header: &str, claims: &str) -> String {
+        let encode = |part: &[u8]| base64::encode_config(part, base64::URL_SAFE_NO_PAD);
+        let signed = format!(
+            "{}.{}",
+            encode(header.as_bytes()),
+            encode(claims.as_bytes())
+        );
+        let signature = hmac::sign(&hmac::SigKey::sha256(&secret.0), signed.as_bytes());
+        format!("{}.{}", signed, encode(&signature))
+    }
+
+    #[test]
+    fn should_parse_secret() {
+        assert!(JwtSecret::from_hex(SECRET).is_ok());
+        assert!(JwtSecret::from_hex(&format!("{}\n", &SECRET[2..])).is_ok());
+        assert!(JwtSecret::from_hex("0x1234").is_err());
+        assert!(JwtSecret::from_hex("not hex").is_err());
+    }
+
+    #[test]
+    fn should_validate_tokens() {
+        let secret = JwtSecret::from_hex(SECRET).unwrap();
+        let other = JwtSecret([1u8; SECRET_LENGTH]);
+        let header = r#"{"alg":"HS256","typ":"JWT"}"#;
+        let valid = token(&secret, header, r#"{"iat":1000}"#);
+
+        assert_eq!(secret.validate(&valid, 1000), Ok(()));
+        assert_eq!(secret.validate(&valid, 1000 + IAT_TOLERANCE), Ok(()));
+        assert_eq!(
+            secret.validate(&valid, 1000 + IAT_TOLERANCE + 1),
+            Err(Error::Stale {
+                iat: 1000,
+                now: 1000 + IAT_TOLERANCE + 1
+            })
+        );
+        assert_eq!(other.validate(&valid, 1000), Err(Error::InvalidSignature));
+        assert_eq!(
+            secret.validate(
+                &token(&secret, r#"{"alg":"none"}"#, r#"{"iat":1000}"#),
+                1000
+            ),
+            Err(Error::UnsupportedAlgorithm("none".into()))
+        );
+        assert_eq!(
+            secret.validate(&token(&secret, header, r#"{}"#), 1000),
+            Err(Error::Malformed)
+        );
+        assert_eq!(secret.validate("abc", 1000), Err(Error::Malformed));
+    }
+
+    #[test]
+    fn should_validate_authorization_header() {
+        let secret = JwtSecret::from_hex(SECRET).unwrap();
+        let valid = token(&secret, r#"{"alg":"HS256"}"#, r#"{"iat":1000}"#);
+
+        assert_eq!(
+            secret.validate_header(Some(format!("Bearer {}", valid).as_bytes()), 1000),
+            Ok(())
+        );
+        assert_eq!(
+            secret.validate_header(Some(valid.as_bytes()), 1000),
+            Err(Error::MissingToken)
+        );
+        assert_eq!(secret.validate_header(None, 1000), Err(Error::MissingToken));
+    }
+}
@@ -41,6 +41,7 @@
 extern crate futures;
 
 extern crate ansi_term;
+extern crate base64;
 extern crate itertools;
 extern crate order_stat;
 extern crate parking_lot;
@@ -61,6 +62,7 @@ extern crate common_types as types;
 extern crate eip_712;
 extern crate ethash;
 extern crate ethcore;
+extern crate ethcore_db;
 extern crate ethcore_logger;
 extern crate ethcore_miner as miner;
 extern crate ethcore_network as network;
@@ -68,10 +70,12 @@ extern crate ethcore_sync as sync;
 extern crate ethereum_types;
 extern crate ethkey;
 extern crate ethstore;
+extern crate evm;
 extern crate fetch;
 extern crate keccak_hash as hash;
 extern crate parity_bytes as bytes;
 extern crate parity_crypto as crypto;
+extern crate parity_local_store as local_store;
 extern crate parity_runtime;
 extern crate parity_version as version;
 extern crate rlp;
@@ -117,6 +121,7 @@ extern crate rpc_common;
 pub extern crate jsonrpc_ws_server as ws;
 
 mod authcodes;
+mod jwt;
 pub mod v1;
 
 // #[cfg(test)]
@@ -132,11 +137,14 @@ pub use ipc::{
 pub use jsonrpc_pubsub::Session as PubSubSession;
 
 pub use authcodes::{AuthCodes, TimeProvider};
+pub use jwt::{Error as JwtError, HealthMiddleware, JwtMiddleware, JwtSecret};
 pub use v1::{
+    batch::{BatchDispatcher, BatchLimits},
     block_import::{is_major_importing, is_major_importing_or_waiting},
     dispatch,
     extractors::{RpcExtractor, WsDispatcher, WsExtractor, WsStats},
-    informant, signer, Metadata, NetworkSettings, Origin,
+    informant, recorder, signer, ChainHealth, ClockDrift, Metadata, NetworkSettings, Origin,
+    OverflowPolicy, RangeBudget, SubscriptionLimits, SubscriptionMetrics,
 };
 
 /// RPC HTTP Server instance
@@ -14,13 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use http::{self, hyper};
+use http;
+use jwt::{HealthMiddleware, JwtMiddleware, JwtSecret};
 use rpc_servers::{HttpServer, MetaIoHandler};
 
 use tests::{helpers::Server, http_client};
 use v1::{extractors, Metadata};
 
-fn serve(handler: Option<MetaIoHandler<Metadata>>) -> Server<HttpServer> {
+fn serve(
+    handler: Option<MetaIoHandler<Metadata>>,
+    jwt_secret: Option<JwtSecret>,
+) -> Server<HttpServer> {
     let address = "127.0.0.1:0".parse().unwrap();
     let handler = handler.unwrap_or_default();
 
@@ -29,12 +33,14 @@ fn serve(handler: Option<MetaIoHandler<Metadata>>) -> Server<HttpServer> {
             &address,
             http::DomainsValidation::Disabled,
             http::DomainsValidation::Disabled,
+            None::<(String, String)>,
             handler,
             extractors::RpcExtractor,
-            |request: hyper::Request<hyper::Body>| http::RequestMiddlewareAction::Proceed {
-                should_continue_on_invalid_cors: false,
-                request,
-            },
+            HealthMiddleware::new(
+                "/api/health",
+                vec!["/health"],
+                jwt_secret.map(JwtMiddleware::new),
+            ),
             1,
             5,
             false,
@@ -52,19 +58,118 @@ fn request(server: Server<HttpServer>, request: &str) -> http_client::Response {
 mod tests {
     use super::{request, Server};
     use jsonrpc_core::{MetaIoHandler, Value};
+    use jwt::JwtSecret;
     use v1::Metadata;
 
-    fn serve() -> (Server<::HttpServer>, ::std::net::SocketAddr) {
+    fn serve_with_secret(
+        jwt_secret: Option<JwtSecret>,
+    ) -> (Server<::HttpServer>, ::std::net::SocketAddr) {
         let mut io = MetaIoHandler::default();
         io.add_method_with_meta("hello", |_, meta: Metadata| {
             Ok(Value::String(format!("{}", meta.origin)))
         });
-        let server = super::serve(Some(io));
+        let server = super::serve(Some(io), jwt_secret);
         let address = server.server.address().to_owned();
 
         (server, address)
     }
 
+    fn serve() -> (Server<::HttpServer>, ::std::net::SocketAddr) {
+        serve_with_secret(None)
+    }
+
+    fn secret() -> JwtSecret {
+        JwtSecret::from_hex("0x7365637265747365637265747365637265747365637265747365637265747365")
+            .unwrap()
+    }
+
+    fn post(path: &str, jwt_secret: Option<JwtSecret>) -> ::tests::http_client::Response {
+        let (server, address) = serve_with_secret(jwt_secret);
+        let req = r#"{"method":"hello","params":[],"jsonrpc":"2.0","id":1}"#;
+        request(
+            server,
+            &format!(
+                "\
+				POST {} HTTP/1.1\r\n\
+				Host: {}\r\n\
+				Content-Type: application/json\r\n\
+				Content-Length: {}\r\n\
+				Connection: close\r\n\
+				\r\n\
+				{}
+			",
+                path,
+                address,
+                req.len(),
+                req
+            ),
+        )
+    }
+
+    #[test]
+    fn should_not_run_unauthenticated_calls_posted_to_health_paths() {
+        post("/health", None).assert_status("HTTP/1.1 200 OK");
+
+        post("/health", Some(secret())).assert_status("HTTP/1.1 401 Unauthorized");
+        post("/api/health", Some(secret())).assert_status("HTTP/1.1 401 Unauthorized");
+        post("/", Some(secret())).assert_status("HTTP/1.1 401 Unauthorized");
+    }
+
+    #[test]
+    fn should_probe_health_paths_without_authentication() {
+        for path in &["/health", "/api/health"] {
+            // given
+            let (server, address) = serve_with_secret(Some(secret()));
+
+            // when
+            let res = request(
+                server,
+                &format!(
+                    "\
+					GET {} HTTP/1.1\r\n\
+					Host: {}\r\n\
+					Connection: close\r\n\
+					\r\n\
+				",
+                    path, address
+                ),
+            );
+
+            // then
+            assert!(
+                res.status != "HTTP/1.1 401 Unauthorized",
+                "{}: {}",
+                path,
+                res.status
+            );
+        }
+    }
+
+    #[test]
+    fn should_authenticate_other_requests_to_health_paths() {
+        for path in &["/health", "/api/health"] {
+            // given
+            let (server, address) = serve_with_secret(Some(secret()));
+
+            // when
+            let res = request(
+                server,
+                &format!(
+                    "\
+					HEAD {} HTTP/1.1\r\n\
+					Host: {}\r\n\
+					Connection: close\r\n\
+					\r\n\
+				",
+                    path, address
+                ),
+            );
+
+            // then
+            res.assert_status("HTTP/1.1 401 Unauthorized");
+        }
+    }
+
     #[test]
     fn should_extract_rpc_origin() {
         // given
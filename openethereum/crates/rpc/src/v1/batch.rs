@@ -0,0 +1,303 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Limits and parallel execution of batch requests.
+//!
+//! Calls of a batch are spawned on the executor of a dedicated thread pool, at most
+//! `BatchLimits::parallelism` of them at a time, instead of being executed one after
+//! another by the server thread which received the batch. Handlers run synchronously,
+//! so the pool should not be shared with other work and its size bounds the number of
+//! batch calls executed at the same time by all batches.
+
+use std::{cmp, sync::Arc};
+
+use jsonrpc_core as core;
+use jsonrpc_core::futures::{
+    future::{self, Either},
+    stream,
+    sync::oneshot,
+    Future, Stream,
+};
+use parity_runtime::Executor;
+
+use v1::{helpers::errors, Metadata};
+
+/// Cost of calls executing transactions.
+const EXECUTION_CALL_COST: usize = 10;
+/// Cost of calls reading ranges of blocks or tracing.
+const HEAVY_CALL_COST: usize = 100;
+
+/// Limits of batch requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchLimits {
+    /// Maximum number of calls in a batch.
+    pub max_size: usize,
+    /// Maximum sum of the costs of the calls in a batch, see `call_cost`.
+    pub max_cost: usize,
+    /// Maximum number of calls of a single batch executed at the same time, and the
+    /// number of threads of the pool executing them.
+    pub parallelism: usize,
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        BatchLimits {
+            max_size: 1024,
+            max_cost: 10_000,
+            parallelism: 4,
+        }
+    }
+}
+
+/// Estimated cost of a call to `method`, relative to a simple lookup.
+pub fn call_cost(method: &str) -> usize {
+    match method {
+        "eth_getLogs" | "eth_getFilterLogs" => HEAVY_CALL_COST,
+        _ if method.starts_with("trace_") || method.starts_with("debug_") => HEAVY_CALL_COST,
+        "eth_call" | "eth_estimateGas" | "eth_createAccessList" | "parity_call" => {
+            EXECUTION_CALL_COST
+        }
+        _ => 1,
+    }
+}
+
+fn batch_cost(calls: &[core::Call]) -> usize {
+    calls
+        .iter()
+        .map(|call| match *call {
+            core::Call::MethodCall(ref call) => call_cost(&call.method),
+            core::Call::Notification(ref notification) => call_cost(&notification.method),
+            core::Call::Invalid { .. } => 1,
+        })
+        .sum()
+}
+
+/// Middleware enforcing `BatchLimits` and executing the calls of batches in parallel.
+/// All requests are handled by the wrapped handler.
+pub struct BatchDispatcher<M: core::Middleware<Metadata>> {
+    handler: Arc<core::MetaIoHandler<Metadata, M>>,
+    executor: Executor,
+    limits: BatchLimits,
+}
+
+impl<M: core::Middleware<Metadata>> BatchDispatcher<M> {
+    /// Create new `BatchDispatcher` spawning the calls of batches handled by `handler` on `executor`,
+    /// which should be dedicated to them.
+    pub fn new(
+        handler: core::MetaIoHandler<Metadata, M>,
+        executor: Executor,
+        limits: BatchLimits,
+    ) -> Self {
+        BatchDispatcher {
+            handler: Arc::new(handler),
+            executor,
+            limits,
+        }
+    }
+
+    fn rejected(error: core::Error) -> core::FutureResponse {
+        Box::new(future::ok(Some(core::Response::from(
+            error,
+            Some(core::Version::V2),
+        ))))
+    }
+}
+
+fn spawn_call<M: core::Middleware<Metadata>>(
+    handler: &Arc<core::MetaIoHandler<Metadata, M>>,
+    executor: &Executor,
+    call: core::Call,
+    meta: Metadata,
+) -> impl Future<Item = Option<core::Output>, Error = ()> {
+    let cancelled = match call {
+        core::Call::MethodCall(ref call) => Some((call.id.clone(), call.jsonrpc)),
+        _ => None,
+    };
+    let handler = handler.clone();
+    let (tx, rx) = oneshot::channel();
+    executor.spawn(future::lazy(move || {
+        handler.handle_call(call, meta).then(move |output| {
+            let _ = tx.send(output);
+            Ok(())
+        })
+    }));
+
+    rx.then(move |output| match output {
+        Ok(output) => output,
+        Err(_) => Ok(cancelled.map(|(id, jsonrpc)| {
+            core::Output::from(
+                Err(errors::internal("batch call was cancelled", "")),
+                id,
+                jsonrpc,
+            )
+        })),
+    })
+}
+
+impl<M: core::Middleware<Metadata>> core::Middleware<Metadata> for BatchDispatcher<M> {
+    type Future = Either<core::FutureRpcResult<M::Future, M::CallFuture>, core::FutureResponse>;
+    type CallFuture = core::middleware::NoopCallFuture;
+
+    fn on_request<F, X>(
+        &self,
+        request: core::Request,
+        meta: Metadata,
+        _process: F,
+    ) -> Either<Self::Future, X>
+    where
+        F: FnOnce(core::Request, Metadata) -> X,
+        X: core::futures::Future<Item = Option<core::Response>, Error = ()> + Send + 'static,
+    {
+        let calls = match request {
+            core::Request::Batch(calls) if calls.len() > 1 => calls,
+            request => {
+                return Either::A(Either::A(self.handler.handle_rpc_request(request, meta)));
+            }
+        };
+
+        if calls.len() > self.limits.max_size {
+            let error = errors::batch_too_large(calls.len(), self.limits.max_size);
+            return Either::A(Either::B(Self::rejected(error)));
+        }
+        let cost = batch_cost(&calls);
+        if cost > self.limits.max_cost {
+            let error = errors::batch_too_expensive(cost, self.limits.max_cost);
+            return Either::A(Either::B(Self::rejected(error)));
+        }
+
+        let handler = self.handler.clone();
+        let executor = self.executor.clone();
+        let response = stream::iter_ok::<_, ()>(calls)
+            .map(move |call| spawn_call(&handler, &executor, call, meta.clone()))
+            .buffered(cmp::max(1, self.limits.parallelism))
+            .collect()
+            .map(|outputs| {
+                let outputs = outputs.into_iter().filter_map(|o| o).collect::<Vec<_>>();
+                match outputs.is_empty() {
+                    true => None,
+                    false => Some(core::Response::Batch(outputs)),
+                }
+            });
+
+        Either::A(Either::B(Box::new(response)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::{MetaIoHandler, Params, Value};
+    use parity_runtime::Runtime;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn dispatcher(
+        runtime: &Runtime,
+        limits: BatchLimits,
+    ) -> MetaIoHandler<Metadata, BatchDispatcher<core::NoopMiddleware>> {
+        let mut handler = MetaIoHandler::default();
+        handler.add_method("echo", |params: Params| match params {
+            Params::Array(mut values) => Ok(values.pop().unwrap_or(Value::Null)),
+            _ => Ok(Value::Null),
+        });
+        let calls = Arc::new(AtomicUsize::new(0));
+        handler.add_method("eth_call", move |_: Params| {
+            Ok(Value::from(calls.fetch_add(1, Ordering::SeqCst)))
+        });
+        MetaIoHandler::with_middleware(BatchDispatcher::new(handler, runtime.executor(), limits))
+    }
+
+    #[test]
+    fn should_execute_batches_in_order() {
+        let runtime = Runtime::with_thread_count(4);
+        let io = dispatcher(&runtime, BatchLimits::default());
+
+        let request = r#"[
+			{"jsonrpc":"2.0","method":"echo","params":[1],"id":1},
+			{"jsonrpc":"2.0","method":"echo","params":[2],"id":2},
+			{"jsonrpc":"2.0","method":"echo","params":[3]},
+			{"jsonrpc":"2.0","method":"missing","params":[],"id":4}
+		]"#;
+        let response = r#"[{"jsonrpc":"2.0","result":1,"id":1},{"jsonrpc":"2.0","result":2,"id":2},{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":4}]"#;
+        assert_eq!(
+            io.handle_request_sync(request, Metadata::default()),
+            Some(response.to_owned())
+        );
+
+        let single = r#"{"jsonrpc":"2.0","method":"echo","params":[5],"id":5}"#;
+        assert_eq!(
+            io.handle_request_sync(single, Metadata::default()),
+            Some(r#"{"jsonrpc":"2.0","result":5,"id":5}"#.to_owned())
+        );
+    }
+
+    #[test]
+    fn should_reject_batches_over_limits() {
+        let runtime = Runtime::with_thread_count(1);
+        let io = dispatcher(
+            &runtime,
+            BatchLimits {
+                max_size: 3,
+                max_cost: 20,
+                parallelism: 1,
+            },
+        );
+        let call = |method: &str, id: usize| {
+            format!(
+                r#"{{"jsonrpc":"2.0","method":"{}","params":[],"id":{}}}"#,
+                method, id
+            )
+        };
+        let batch = |calls: Vec<String>| format!("[{}]", calls.join(","));
+
+        let too_large = batch((0..4).map(|id| call("echo", id)).collect());
+        let response = io
+            .handle_request_sync(&too_large, Metadata::default())
+            .unwrap();
+        assert!(
+            response.contains(r#""error":{"code":-32041"#),
+            "{}",
+            response
+        );
+        assert!(response.contains(r#""id":null"#), "{}", response);
+
+        let too_expensive = batch((0..3).map(|id| call("eth_call", id)).collect());
+        let response = io
+            .handle_request_sync(&too_expensive, Metadata::default())
+            .unwrap();
+        assert!(
+            response.contains(r#""error":{"code":-32041"#),
+            "{}",
+            response
+        );
+
+        let allowed = batch(vec![call("eth_call", 0), call("eth_call", 1)]);
+        assert_eq!(
+            io.handle_request_sync(&allowed, Metadata::default()),
+            Some(
+                r#"[{"jsonrpc":"2.0","result":0,"id":0},{"jsonrpc":"2.0","result":1,"id":1}]"#
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn should_estimate_call_costs() {
+        assert_eq!(call_cost("eth_blockNumber"), 1);
+        assert_eq!(call_cost("eth_call"), EXECUTION_CALL_COST);
+        assert_eq!(call_cost("eth_getLogs"), HEAVY_CALL_COST);
+        assert_eq!(call_cost("trace_replayTransaction"), HEAVY_CALL_COST);
+    }
+}
@@ -21,13 +21,14 @@ use std::{
     sync::Arc,
 };
 
-use authcodes;
+use authcodes::{self, DefaultTimeProvider, TimeProvider};
 use ethereum_types::H256;
 use http::hyper;
 use ipc;
 use jsonrpc_core as core;
 use jsonrpc_core::futures::future::Either;
 use jsonrpc_pubsub::Session;
+use jwt::JwtSecret;
 use ws;
 
 use v1::{informant::RpcStats, Metadata, Origin};
@@ -67,6 +68,7 @@ impl ipc::MetaExtractor<Metadata> for RpcExtractor {
 /// WebSockets server metadata extractor and request middleware.
 pub struct WsExtractor {
     authcodes_path: Option<PathBuf>,
+    jwt_secret: Option<JwtSecret>,
 }
 
 impl WsExtractor {
@@ -74,8 +76,15 @@ impl WsExtractor {
     pub fn new(path: Option<&Path>) -> Self {
         WsExtractor {
             authcodes_path: path.map(ToOwned::to_owned),
+            jwt_secret: None,
         }
     }
+
+    /// Requires a valid JWT in the `Authorization` header of the handshake.
+    pub fn with_jwt_secret(mut self, secret: Option<JwtSecret>) -> Self {
+        self.jwt_secret = secret;
+        self
+    }
 }
 
 impl ws::MetaExtractor<Metadata> for WsExtractor {
@@ -126,6 +135,16 @@ impl ws::RequestMiddleware for WsExtractor {
             return Some(response).into();
         }
 
+        if let Some(ref secret) = self.jwt_secret {
+            let authorization = req.header("authorization").map(|value| &value[..]);
+            if let Err(e) = secret.validate_header(authorization, DefaultTimeProvider.now()) {
+                debug!(target: "rpc", "Rejected unauthenticated WebSockets connection: {}", e);
+                let mut response = Response::new(401, "Unauthorized", vec![]);
+                add_security_headers(&mut response);
+                return Some(response).into();
+            }
+        }
+
         // If protocol is provided it needs to be valid.
         let protocols = req.protocols().ok().unwrap_or_else(Vec::new);
         if let Some(ref path) = self.authcodes_path {
@@ -21,9 +21,14 @@ use std::fmt;
 use ethcore::{
     client::{BlockChainClient, BlockId},
     error::{CallError, Error as EthcoreError, ErrorKind},
+    miner::BundleError,
 };
+use ethereum_types::H256;
 use jsonrpc_core::{Error, ErrorCode, Result as RpcResult, Value};
+use miner::pool::alt_mempool::ReservationError;
 use rlp::DecoderError;
+use serde::Serialize;
+use serde_json::{to_value, Map};
 use types::{blockchain_info::BlockChainInfo, transaction::Error as TransactionError};
 use v1::{impls::EthClientOptions, types::BlockNumber};
 use vm::Error as VMError;
@@ -41,6 +46,7 @@ mod codes {
     pub const EXECUTION_ERROR: i64 = -32015;
     pub const EXCEPTION_ERROR: i64 = -32016;
     pub const DATABASE_ERROR: i64 = -32017;
+    pub const EXECUTION_TIMEOUT: i64 = -32018;
     #[cfg(any(test, feature = "accounts"))]
     pub const ACCOUNT_LOCKED: i64 = -32020;
     #[cfg(any(test, feature = "accounts"))]
@@ -92,6 +98,50 @@ pub fn request_rejected() -> Error {
     }
 }
 
+pub fn subscription_overflow(max_queued: usize) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::REQUEST_REJECTED_LIMIT),
+        message: format!(
+            "Subscription dropped because more than {} notifications were waiting to be sent.",
+            max_queued
+        ),
+        data: None,
+    }
+}
+
+pub fn trace_too_large(size: usize, max_size: usize) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::REQUEST_REJECTED_LIMIT),
+        message: format!(
+            "Trace of {} operations rejected, at most {} operations are allowed.",
+            size, max_size
+        ),
+        data: None,
+    }
+}
+
+pub fn batch_too_large(size: usize, max_size: usize) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::REQUEST_REJECTED_LIMIT),
+        message: format!(
+            "Batch of {} calls rejected, at most {} calls are allowed.",
+            size, max_size
+        ),
+        data: None,
+    }
+}
+
+pub fn batch_too_expensive(cost: usize, max_cost: usize) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::REQUEST_REJECTED_LIMIT),
+        message: format!(
+            "Batch of estimated cost {} rejected, the maximal cost is {}.",
+            cost, max_cost
+        ),
+        data: None,
+    }
+}
+
 pub fn request_rejected_limit() -> Error {
     Error {
         code: ErrorCode::ServerError(codes::REQUEST_REJECTED_LIMIT),
@@ -227,6 +277,41 @@ pub fn unavailable_block(no_ancient_block: bool, by_hash: bool) -> Error {
     }
 }
 
+pub fn history_expired(earliest_history: u64) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+        message: "Requested block is older than the history kept by this node; its bodies and receipts have been expired.".into(),
+        data: Some(Value::String(format!("Earliest available block: {}", earliest_history))),
+    }
+}
+
+pub fn transaction_index_expired(earliest_transaction_index: u64) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+        message: "Transaction is in a block older than the transaction index kept by this node; indexing is disabled for that range.".into(),
+        data: Some(Value::String(format!("Earliest indexed block: {}", earliest_transaction_index))),
+    }
+}
+
+// a missed lookup by hash is only reported if the transaction is known to be no longer indexed,
+// pending and unknown transactions are still `null`.
+pub fn check_transaction_index<'a, T, C>(
+    client: &'a C,
+    hash: H256,
+) -> impl Fn(Option<T>) -> RpcResult<Option<T>> + 'a
+where
+    C: BlockChainClient,
+{
+    move |response| {
+        if response.is_none() && client.is_transaction_unindexed(&hash) {
+            return Err(transaction_index_expired(
+                client.pruning_info().earliest_transaction_index,
+            ));
+        }
+        Ok(response)
+    }
+}
+
 pub fn check_block_number_existence<'a, T, C>(
     client: &'a C,
     num: BlockNumber,
@@ -238,6 +323,10 @@ where
     move |response| {
         if response.is_none() {
             if let BlockNumber::Num(block_number) = num {
+                let earliest_history = client.pruning_info().earliest_history;
+                if block_number < earliest_history && !options.allow_missing_blocks {
+                    return Err(history_expired(earliest_history));
+                }
                 // tried to fetch block number and got nothing even though the block number is
                 // less than the latest block number
                 if block_number < client.chain_info().best_block_number
@@ -333,6 +422,22 @@ pub fn database<T: fmt::Debug>(error: T) -> Error {
     }
 }
 
+pub fn bundle(error: BundleError) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::REQUEST_REJECTED),
+        message: format!("Bundle rejected: {}", error),
+        data: None,
+    }
+}
+
+pub fn alt_mempool(error: ReservationError) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::REQUEST_REJECTED),
+        message: format!("Reservation rejected: {}", error),
+        data: None,
+    }
+}
+
 pub fn fetch<T: fmt::Debug>(error: T) -> Error {
     Error {
         code: ErrorCode::ServerError(codes::FETCH_ERROR),
@@ -409,6 +514,9 @@ pub fn transaction_message(error: &TransactionError) -> String {
         InvalidRlp(ref descr) => format!("Invalid RLP data: {}", descr),
         TransactionTypeNotEnabled => format!("Transaction type is not enabled for current block"),
         SenderIsNotEOA => "Transaction sender is not an EOA (see EIP-3607)".into(),
+        SenderLimitReached => "There are too many transactions from this sender in the queue. Wait for them to be mined.".into(),
+        InvalidUserOperations(ref descr) => format!("Invalid UserOperation bundle: {}", descr),
+        AltMempoolFull => "The UserOperation bundle lane is full or reserved by other bundlers. Try again later.".into(),
 	}
 }
 
@@ -455,6 +563,7 @@ pub fn call(error: CallError) -> Error {
         CallError::StateCorrupt => state_corrupt(),
         CallError::Exceptional(e) => exceptional(e),
         CallError::Execution(e) => execution(e),
+        CallError::Timeout => execution_timeout(),
         CallError::TransactionNotFound => internal(
             "{}, this should not be the case with eth_call, most likely a bug.",
             CallError::TransactionNotFound,
@@ -462,6 +571,14 @@ pub fn call(error: CallError) -> Error {
     }
 }
 
+pub fn execution_timeout() -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::EXECUTION_TIMEOUT),
+        message: "The execution was interrupted because it exceeded the call timeout.".into(),
+        data: None,
+    }
+}
+
 pub fn vm(error: &VMError, output: &[u8]) -> Error {
     use rustc_hex::ToHex;
 
@@ -501,6 +618,58 @@ pub fn filter_not_found() -> Error {
     }
 }
 
+pub fn range_budget_exceeded<T: Serialize>(resume_from: u64, partial_results: &[T]) -> Error {
+    let mut continuation = Map::new();
+    continuation.insert(
+        "fromBlock".into(),
+        Value::String(format!("0x{:x}", resume_from)),
+    );
+    budget_exceeded(resume_from, continuation, partial_results)
+}
+
+/// Like `range_budget_exceeded`, for a `trace_filter` page: the continuation also holds the
+/// number of traces still to skip and to return from `resume_from` on.
+pub fn trace_range_budget_exceeded<T: Serialize>(
+    resume_from: u64,
+    after: usize,
+    count: Option<usize>,
+    partial_results: &[T],
+) -> Error {
+    let mut continuation = Map::new();
+    continuation.insert(
+        "fromBlock".into(),
+        Value::String(format!("0x{:x}", resume_from)),
+    );
+    if after > 0 {
+        continuation.insert("after".into(), Value::from(after));
+    }
+    if let Some(count) = count {
+        continuation.insert("count".into(), Value::from(count));
+    }
+    budget_exceeded(resume_from, continuation, partial_results)
+}
+
+fn budget_exceeded<T: Serialize>(
+    resume_from: u64,
+    continuation: Map<String, Value>,
+    partial_results: &[T],
+) -> Error {
+    let mut data = Map::new();
+    data.insert(
+        "partialResults".into(),
+        to_value(partial_results).unwrap_or(Value::Null),
+    );
+    data.insert("continuation".into(), Value::Object(continuation));
+    Error {
+        code: ErrorCode::ServerError(codes::REQUEST_REJECTED_LIMIT),
+        message: format!(
+            "Query exceeds the request budget. Partial results are included, repeat the query from block 0x{:x} for the rest.",
+            resume_from
+        ),
+        data: Some(Value::Object(data)),
+    }
+}
+
 pub fn filter_block_not_found(id: BlockId) -> Error {
     Error {
 		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST), // Specified in EIP-234.
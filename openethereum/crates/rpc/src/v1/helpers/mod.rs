@@ -31,19 +31,24 @@ pub mod nonce;
 pub mod secretstore;
 
 mod network_settings;
+mod node_health;
 mod poll_filter;
 mod poll_manager;
+mod range_planner;
 mod requests;
 mod signature;
 mod subscribers;
 mod subscription_manager;
+mod subscription_queue;
 mod work;
 
 pub use self::{
     dispatch::{Dispatcher, FullDispatcher},
     network_settings::NetworkSettings,
+    node_health::{ChainHealth, ClockDrift},
     poll_filter::{limit_logs, PollFilter, SyncPollFilter},
     poll_manager::PollManager,
+    range_planner::{RangeBudget, RangeOutcome, RangePlanner},
     requests::{
         CallRequest, ConfirmationPayload, ConfirmationRequest, FilledTransactionRequest,
         TransactionRequest,
@@ -51,6 +56,7 @@ pub use self::{
     signature::verify_signature,
     subscribers::Subscribers,
     subscription_manager::GenericPollManager,
+    subscription_queue::{OverflowPolicy, QueuedSink, SubscriptionLimits, SubscriptionMetrics},
     work::submit_work_detail,
 };
 
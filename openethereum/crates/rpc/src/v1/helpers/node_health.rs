@@ -0,0 +1,155 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chain quality tracking for `parity_nodeHealth`.
+
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ethcore::client::{ChainNotify, NewBlocks, Reorg};
+use parking_lot::Mutex;
+
+/// How long a reorganization is reported as recent.
+pub const REORG_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Source of the local clock offset estimate.
+pub trait ClockDrift: Send + Sync {
+    /// Estimated offset of the local clock in milliseconds, if any has been made yet.
+    fn offset_millis(&self) -> Option<i64>;
+}
+
+#[derive(Default)]
+struct State {
+    last_import: Option<Instant>,
+    reorgs: VecDeque<(Instant, usize)>,
+}
+
+/// Tracks block imports and reorganizations of the canonical chain.
+pub struct ChainHealth {
+    state: Mutex<State>,
+    clock: Option<Arc<dyn ClockDrift>>,
+}
+
+impl ChainHealth {
+    /// Creates new `ChainHealth` reporting the clock offset estimated by `clock`.
+    pub fn new(clock: Option<Arc<dyn ClockDrift>>) -> Self {
+        ChainHealth {
+            state: Default::default(),
+            clock,
+        }
+    }
+
+    /// Time elapsed since the last block import, if any block was imported since startup.
+    pub fn since_last_import(&self, now: Instant) -> Option<Duration> {
+        self.state
+            .lock()
+            .last_import
+            .map(|last| now.saturating_duration_since(last))
+    }
+
+    /// Number of blocks retracted by the deepest reorganization within `REORG_WINDOW`.
+    pub fn reorg_depth(&self, now: Instant) -> usize {
+        self.state
+            .lock()
+            .reorgs
+            .iter()
+            .filter(|&&(at, _)| now.saturating_duration_since(at) < REORG_WINDOW)
+            .map(|&(_, depth)| depth)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Estimated offset of the local clock in milliseconds.
+    pub fn clock_drift_millis(&self) -> Option<i64> {
+        self.clock.as_ref().and_then(|clock| clock.offset_millis())
+    }
+
+    fn record_import(&self, now: Instant) {
+        self.state.lock().last_import = Some(now);
+    }
+
+    fn record_reorg(&self, now: Instant, depth: usize) {
+        let mut state = self.state.lock();
+        while state.reorgs.front().map_or(false, |&(at, _)| {
+            now.saturating_duration_since(at) >= REORG_WINDOW
+        }) {
+            state.reorgs.pop_front();
+        }
+        state.reorgs.push_back((now, depth));
+    }
+}
+
+impl ChainNotify for ChainHealth {
+    fn new_blocks(&self, new_blocks: NewBlocks) {
+        if !new_blocks.imported.is_empty() {
+            self.record_import(Instant::now());
+        }
+    }
+
+    fn chain_reorganized(&self, reorg: &Reorg) {
+        self.record_reorg(Instant::now(), reorg.retracted.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedClock(i64);
+
+    impl ClockDrift for FixedClock {
+        fn offset_millis(&self) -> Option<i64> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn should_track_imports_and_recent_reorgs() {
+        let health = ChainHealth::new(None);
+        let start = Instant::now();
+
+        assert_eq!(health.since_last_import(start), None);
+        assert_eq!(health.reorg_depth(start), 0);
+        assert_eq!(health.clock_drift_millis(), None);
+
+        health.record_import(start);
+        assert_eq!(
+            health.since_last_import(start + Duration::from_secs(12)),
+            Some(Duration::from_secs(12))
+        );
+
+        health.record_reorg(start, 3);
+        health.record_reorg(start + Duration::from_secs(60), 1);
+        assert_eq!(health.reorg_depth(start + Duration::from_secs(60)), 3);
+        assert_eq!(health.reorg_depth(start + REORG_WINDOW), 1);
+        assert_eq!(
+            health.reorg_depth(start + REORG_WINDOW + Duration::from_secs(60)),
+            0
+        );
+
+        health.record_reorg(start + REORG_WINDOW, 2);
+        assert_eq!(health.state.lock().reorgs.len(), 2);
+    }
+
+    #[test]
+    fn should_report_clock_drift() {
+        let health = ChainHealth::new(Some(Arc::new(FixedClock(-250))));
+        assert_eq!(health.clock_drift_millis(), Some(-250));
+    }
+}
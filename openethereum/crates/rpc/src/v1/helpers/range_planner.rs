@@ -0,0 +1,243 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Query planner for range-heavy RPCs.
+//!
+//! Queries over a block range (`eth_getLogs`, `trace_filter`) are split into bounded sub-ranges.
+//! Sub-ranges are sized from an index-based cost estimate, executed one after another while
+//! yielding the thread in between, and stop as soon as the per-request budget is spent. The
+//! results gathered so far are returned together with the block from which the query has to be
+//! resumed.
+
+use std::{
+    cmp,
+    collections::VecDeque,
+    thread,
+    time::{Duration, Instant},
+};
+
+use types::BlockNumber;
+
+/// Per-request budget of range queries. Queries are split into sub-ranges by default, but only
+/// limited in cost, results and time if the operator configures it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeBudget {
+    /// Maximum number of blocks in a single sub-range.
+    pub max_chunk_blocks: u64,
+    /// Maximum estimated cost of a single sub-range.
+    pub max_chunk_cost: u64,
+    /// Maximum estimated cost of the whole request.
+    pub max_cost: u64,
+    /// Maximum number of results of the whole request.
+    pub max_results: usize,
+    /// Maximum time spent executing the request.
+    pub max_duration: Duration,
+    /// Maximum number of blocks of a fee history request.
+    pub max_fee_history_blocks: u64,
+}
+
+impl Default for RangeBudget {
+    fn default() -> Self {
+        RangeBudget {
+            max_chunk_blocks: 1_000,
+            max_chunk_cost: 1_000,
+            max_cost: u64::max_value(),
+            max_results: usize::max_value(),
+            max_duration: Duration::from_secs(u64::max_value()),
+            max_fee_history_blocks: 1024,
+        }
+    }
+}
+
+/// Results of a planned range query.
+#[derive(Debug, PartialEq)]
+pub struct RangeOutcome<T> {
+    /// Results of all executed sub-ranges, in block order.
+    pub items: Vec<T>,
+    /// If the budget was exhausted, the first block that has not been queried.
+    pub resume_from: Option<BlockNumber>,
+}
+
+/// Splits block ranges into sub-ranges and executes them within a `RangeBudget`.
+pub struct RangePlanner<'a> {
+    budget: &'a RangeBudget,
+}
+
+impl<'a> RangePlanner<'a> {
+    /// Create a new planner enforcing the given budget.
+    pub fn new(budget: &'a RangeBudget) -> Self {
+        RangePlanner { budget }
+    }
+
+    /// Whether the range `from..=to` fits a single sub-range and can be executed directly.
+    pub fn is_single_chunk(&self, from: BlockNumber, to: BlockNumber) -> bool {
+        to.saturating_sub(from) < self.budget.max_chunk_blocks
+    }
+
+    /// Execute the query over `from..=to`.
+    ///
+    /// `estimate` returns the estimated cost of a sub-range, `run` executes it. Sub-ranges whose
+    /// estimate exceeds the chunk cost are split further, down to single blocks.
+    pub fn execute<T, E, C, R>(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+        mut estimate: C,
+        mut run: R,
+    ) -> Result<RangeOutcome<T>, E>
+    where
+        C: FnMut(BlockNumber, BlockNumber) -> u64,
+        R: FnMut(BlockNumber, BlockNumber) -> Result<Vec<T>, E>,
+    {
+        let started = Instant::now();
+        let chunk_blocks = cmp::max(1, self.budget.max_chunk_blocks);
+        let mut pending = VecDeque::new();
+        let mut start = from;
+        while start <= to {
+            let end = cmp::min(to, start.saturating_add(chunk_blocks - 1));
+            pending.push_back((start, end));
+            if end == BlockNumber::max_value() {
+                break;
+            }
+            start = end + 1;
+        }
+
+        let mut items = Vec::new();
+        let mut spent = 0u64;
+        while let Some((start, end)) = pending.pop_front() {
+            let cost = estimate(start, end);
+            if cost > self.budget.max_chunk_cost && start < end {
+                let mid = start + (end - start) / 2;
+                pending.push_front((mid + 1, end));
+                pending.push_front((start, mid));
+                continue;
+            }
+
+            let exhausted = spent.saturating_add(cost) > self.budget.max_cost
+                || items.len() >= self.budget.max_results
+                || started.elapsed() >= self.budget.max_duration;
+            // Always make progress, even if a single block is above the budget.
+            if exhausted && spent > 0 {
+                trace!(target: "rpc", "Range query budget exhausted at block #{}", start);
+                return Ok(RangeOutcome {
+                    items,
+                    resume_from: Some(start),
+                });
+            }
+
+            spent = spent.saturating_add(cost);
+            items.extend(run(start, end)?);
+            if !pending.is_empty() {
+                thread::yield_now();
+            }
+        }
+
+        Ok(RangeOutcome {
+            items,
+            resume_from: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RangeBudget, RangeOutcome, RangePlanner};
+    use std::{cell::RefCell, time::Duration};
+
+    fn budget() -> RangeBudget {
+        RangeBudget {
+            max_chunk_blocks: 10,
+            max_chunk_cost: 10,
+            max_cost: 100,
+            max_results: 1_000,
+            max_duration: Duration::from_secs(60),
+            max_fee_history_blocks: 1024,
+        }
+    }
+
+    #[test]
+    fn splits_range_into_chunks() {
+        let budget = budget();
+        let planner = RangePlanner::new(&budget);
+        let chunks = RefCell::new(Vec::new());
+        let outcome: Result<_, ()> = planner.execute(
+            5,
+            29,
+            |from, to| to - from + 1,
+            |from, to| {
+                chunks.borrow_mut().push((from, to));
+                Ok((from..=to).collect())
+            },
+        );
+
+        assert_eq!(
+            outcome,
+            Ok(RangeOutcome {
+                items: (5..=29).collect(),
+                resume_from: None,
+            })
+        );
+        assert_eq!(*chunks.borrow(), vec![(5, 14), (15, 24), (25, 29)]);
+        assert!(planner.is_single_chunk(5, 14));
+        assert!(!planner.is_single_chunk(5, 15));
+    }
+
+    #[test]
+    fn splits_expensive_chunks() {
+        let budget = budget();
+        let planner = RangePlanner::new(&budget);
+        let chunks = RefCell::new(Vec::new());
+        // Blocks 0..=4 are five times as expensive as the rest.
+        let cost = |from: u64, to: u64| (from..=to).map(|n| if n < 5 { 5 } else { 1 }).sum();
+        let outcome: Result<RangeOutcome<u64>, ()> = planner.execute(0, 9, cost, |from, to| {
+            chunks.borrow_mut().push((from, to));
+            Ok(Vec::new())
+        });
+
+        assert_eq!(outcome.unwrap().resume_from, None);
+        assert_eq!(*chunks.borrow(), vec![(0, 1), (2, 2), (3, 4), (5, 9)]);
+    }
+
+    #[test]
+    fn returns_partial_results_when_budget_is_exhausted() {
+        let budget = RangeBudget {
+            max_cost: 25,
+            ..budget()
+        };
+        let planner = RangePlanner::new(&budget);
+        let outcome: RangeOutcome<u64> = planner
+            .execute::<_, (), _, _>(
+                0,
+                99,
+                |from, to| to - from + 1,
+                |from, to| Ok((from..=to).collect()),
+            )
+            .unwrap();
+        assert_eq!(outcome.items.len(), 20);
+        assert_eq!(outcome.resume_from, Some(20));
+
+        let budget = RangeBudget {
+            max_results: 5,
+            ..budget()
+        };
+        let planner = RangePlanner::new(&budget);
+        let outcome: RangeOutcome<u64> = planner
+            .execute::<_, (), _, _>(0, 99, |_, _| 1, |from, to| Ok((from..=to).collect()))
+            .unwrap();
+        assert_eq!(outcome.items.len(), 10);
+        assert_eq!(outcome.resume_from, Some(10));
+    }
+}
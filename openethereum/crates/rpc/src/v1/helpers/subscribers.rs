@@ -104,6 +104,23 @@ impl<T> Subscribers<T> {
             _ => None,
         }
     }
+
+    /// Assigns id and adds a subscriber to the list, storing its sink as mapped by `f`.
+    pub fn push_with<S, F>(&mut self, sub: Subscriber<S>, f: F)
+    where
+        F: FnOnce(Sink<S>) -> T,
+    {
+        let id = self.next_id();
+        if let Ok(sink) = sub.assign_id(SubscriptionId::String(id.as_string())) {
+            debug!(target: "pubsub", "Adding subscription id={:?}", id);
+            self.subscriptions.insert(id, f(sink));
+        }
+    }
+
+    /// Removes all subscriptions for which `f` returns `false`.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.subscriptions.retain(|_, val| f(val));
+    }
 }
 
 impl<T> Subscribers<Sink<T>> {
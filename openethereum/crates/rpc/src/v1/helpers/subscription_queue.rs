@@ -0,0 +1,384 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Bounded per-subscription notification queues.
+//!
+//! Notifications of a subscription are sent one at a time; the next one is only handed to the
+//! transport once the previous one has been accepted. Notifications produced in the meantime
+//! wait in a queue of limited size, and a subscriber which does not keep up either loses its
+//! oldest notifications or is dropped.
+
+use std::{
+    collections::VecDeque,
+    mem,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use jsonrpc_core::{
+    futures::{
+        future::{self, Either, Loop},
+        Future,
+    },
+    Error,
+};
+use jsonrpc_pubsub::typed::Sink;
+use parity_runtime::Executor;
+use parking_lot::Mutex;
+use serde::Serialize;
+use stats::{PrometheusMetrics, PrometheusRegistry};
+use v1::helpers::errors;
+
+/// What to do with a subscription whose queue is full.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverflowPolicy {
+    /// Discard the oldest queued notification.
+    DropOldest,
+    /// Send an error notification and drop the subscription.
+    Disconnect,
+}
+
+impl FromStr for OverflowPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop-oldest" => Ok(OverflowPolicy::DropOldest),
+            "disconnect" => Ok(OverflowPolicy::Disconnect),
+            other => Err(format!(
+                "Invalid subscription overflow policy: {}. Expected drop-oldest or disconnect.",
+                other
+            )),
+        }
+    }
+}
+
+/// Limits of the notification queue of each subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriptionLimits {
+    /// Maximum number of notifications waiting to be sent.
+    pub max_queued: usize,
+    /// What to do once the queue is full.
+    pub policy: OverflowPolicy,
+}
+
+impl Default for SubscriptionLimits {
+    fn default() -> Self {
+        SubscriptionLimits {
+            max_queued: 10_000,
+            policy: OverflowPolicy::DropOldest,
+        }
+    }
+}
+
+/// Slow consumer statistics shared by all subscriptions.
+#[derive(Debug, Default)]
+pub struct SubscriptionMetrics {
+    queued: AtomicUsize,
+    overflows: AtomicUsize,
+    dropped: AtomicUsize,
+    disconnected: AtomicUsize,
+}
+
+impl SubscriptionMetrics {
+    /// Number of notifications currently waiting to be sent.
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Number of notifications discarded because of a full queue.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Number of subscriptions dropped because of a full queue.
+    pub fn disconnected(&self) -> usize {
+        self.disconnected.load(Ordering::Relaxed)
+    }
+}
+
+impl PrometheusMetrics for SubscriptionMetrics {
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        r.register_gauge(
+            "pubsub_queued",
+            "Notifications waiting to be sent to subscribers",
+            self.queued() as i64,
+        );
+        r.register_counter(
+            "pubsub_overflows",
+            "Notifications produced for a subscriber with a full queue",
+            self.overflows.load(Ordering::Relaxed) as i64,
+        );
+        r.register_counter(
+            "pubsub_dropped",
+            "Notifications discarded because the subscriber did not keep up",
+            self.dropped() as i64,
+        );
+        r.register_counter(
+            "pubsub_disconnected",
+            "Subscriptions dropped because the subscriber did not keep up",
+            self.disconnected() as i64,
+        );
+    }
+}
+
+struct Queue<T> {
+    items: VecDeque<Result<T, Error>>,
+    sending: bool,
+    closed: bool,
+    metrics: Arc<SubscriptionMetrics>,
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        self.metrics
+            .queued
+            .fetch_sub(self.items.len(), Ordering::Relaxed);
+    }
+}
+
+/// Outcome of pushing a notification to the queue.
+enum Pushed {
+    /// Nothing more to do.
+    Done,
+    /// Nothing is being sent yet, the queue has to be drained.
+    Drain,
+    /// The queue overflowed and the subscriber has to be told it was dropped.
+    Disconnect,
+}
+
+/// Subscription sink sending its notifications through a bounded queue.
+/// Clones share the queue.
+pub struct QueuedSink<T> {
+    sink: Sink<T>,
+    queue: Arc<Mutex<Queue<T>>>,
+    limits: SubscriptionLimits,
+    metrics: Arc<SubscriptionMetrics>,
+}
+
+impl<T> QueuedSink<T> {
+    /// Creates new `QueuedSink` wrapping `sink`.
+    pub fn new(
+        sink: Sink<T>,
+        limits: SubscriptionLimits,
+        metrics: Arc<SubscriptionMetrics>,
+    ) -> Self {
+        QueuedSink {
+            sink,
+            queue: Arc::new(Mutex::new(Queue {
+                items: VecDeque::new(),
+                sending: false,
+                closed: false,
+                metrics: metrics.clone(),
+            })),
+            limits,
+            metrics,
+        }
+    }
+
+    /// Returns `true` if the subscription was dropped for not keeping up.
+    pub fn is_closed(&self) -> bool {
+        self.queue.lock().closed
+    }
+}
+
+impl<T: Serialize + Send + 'static> QueuedSink<T> {
+    /// Queues a notification, sending it as soon as the previous ones have been sent.
+    pub fn notify(&self, executor: &Executor, item: T) {
+        self.notify_result(executor, Ok(item))
+    }
+
+    /// Queues a notification which may be an error, like the failure of a polled call.
+    pub fn notify_result(&self, executor: &Executor, result: Result<T, Error>) {
+        // the executor may run spawned futures in place, so the queue is unlocked first
+        match self.push(result) {
+            Pushed::Done => {}
+            Pushed::Drain => executor.spawn(Self::drain(
+                self.sink.clone(),
+                self.queue.clone(),
+                self.metrics.clone(),
+            )),
+            Pushed::Disconnect => executor.spawn(
+                self.sink
+                    .notify(Err(errors::subscription_overflow(self.limits.max_queued)))
+                    .map(|_| ())
+                    .map_err(|e| warn!(target: "rpc", "Unable to send notification: {}", e)),
+            ),
+        }
+    }
+
+    fn push(&self, item: Result<T, Error>) -> Pushed {
+        let mut queue = self.queue.lock();
+        if queue.closed {
+            return Pushed::Done;
+        }
+
+        if queue.items.len() >= self.limits.max_queued {
+            self.metrics.overflows.fetch_add(1, Ordering::Relaxed);
+            match self.limits.policy {
+                OverflowPolicy::DropOldest if queue.items.pop_front().is_some() => {
+                    self.metrics.queued.fetch_sub(1, Ordering::Relaxed);
+                    self.metrics.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowPolicy::DropOldest => {
+                    // nothing can wait in the queue, discard the notification itself
+                    self.metrics.dropped.fetch_add(1, Ordering::Relaxed);
+                    return Pushed::Done;
+                }
+                OverflowPolicy::Disconnect => {
+                    let discarded = queue.items.len();
+                    queue.items.clear();
+                    queue.closed = true;
+                    self.metrics.queued.fetch_sub(discarded, Ordering::Relaxed);
+                    self.metrics
+                        .dropped
+                        .fetch_add(discarded + 1, Ordering::Relaxed);
+                    self.metrics.disconnected.fetch_add(1, Ordering::Relaxed);
+                    debug!(target: "pubsub", "Dropping subscription with {} queued notifications", discarded);
+                    return Pushed::Disconnect;
+                }
+            }
+        }
+
+        queue.items.push_back(item);
+        self.metrics.queued.fetch_add(1, Ordering::Relaxed);
+        if mem::replace(&mut queue.sending, true) {
+            Pushed::Done
+        } else {
+            Pushed::Drain
+        }
+    }
+
+    fn drain(
+        sink: Sink<T>,
+        queue: Arc<Mutex<Queue<T>>>,
+        metrics: Arc<SubscriptionMetrics>,
+    ) -> impl Future<Item = (), Error = ()> + Send {
+        future::loop_fn((), move |()| {
+            let next = {
+                let mut queue = queue.lock();
+                let next = queue.items.pop_front();
+                match next {
+                    Some(_) => {
+                        metrics.queued.fetch_sub(1, Ordering::Relaxed);
+                    }
+                    None => queue.sending = false,
+                }
+                next
+            };
+
+            match next {
+                Some(item) => Either::A(sink.notify(item).then(|result| {
+                    if let Err(e) = result {
+                        warn!(target: "rpc", "Unable to send notification: {}", e);
+                    }
+                    Ok::<_, ()>(Loop::Continue(()))
+                })),
+                None => Either::B(future::ok(Loop::Break(()))),
+            }
+        })
+    }
+}
+
+impl<T> Clone for QueuedSink<T>
+where
+    Sink<T>: Clone,
+{
+    fn clone(&self) -> Self {
+        QueuedSink {
+            sink: self.sink.clone(),
+            queue: self.queue.clone(),
+            limits: self.limits.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+    use parity_runtime::Runtime;
+
+    fn sink(limits: SubscriptionLimits) -> (QueuedSink<u64>, Arc<SubscriptionMetrics>) {
+        let (subscriber, _id, _receiver) = Subscriber::<u64>::new_test("test_subscribe");
+        let sink = subscriber
+            .assign_id(SubscriptionId::Number(1))
+            .expect("test subscriber is not dropped; qed");
+        let metrics = Arc::new(SubscriptionMetrics::default());
+        (QueuedSink::new(sink, limits, metrics.clone()), metrics)
+    }
+
+    #[test]
+    fn should_parse_overflow_policy() {
+        assert_eq!("drop-oldest".parse(), Ok(OverflowPolicy::DropOldest));
+        assert_eq!("disconnect".parse(), Ok(OverflowPolicy::Disconnect));
+        assert!("block".parse::<OverflowPolicy>().is_err());
+    }
+
+    #[test]
+    fn should_drop_oldest_notifications() {
+        let runtime = Runtime::with_thread_count(1);
+        let executor = runtime.executor();
+        let (sink, metrics) = sink(SubscriptionLimits {
+            max_queued: 2,
+            policy: OverflowPolicy::DropOldest,
+        });
+
+        // pretend a notification is being sent to keep the rest queued
+        sink.queue.lock().sending = true;
+        for i in 0..5 {
+            sink.notify(&executor, i);
+        }
+
+        assert_eq!(
+            sink.queue.lock().items.iter().cloned().collect::<Vec<_>>(),
+            vec![Ok(3), Ok(4)]
+        );
+        assert_eq!(metrics.queued(), 2);
+        assert_eq!(metrics.dropped(), 3);
+        assert!(!sink.is_closed());
+
+        drop(sink);
+        assert_eq!(metrics.queued(), 0);
+    }
+
+    #[test]
+    fn should_disconnect_slow_subscriber() {
+        let runtime = Runtime::with_thread_count(1);
+        let executor = runtime.executor();
+        let (sink, metrics) = sink(SubscriptionLimits {
+            max_queued: 2,
+            policy: OverflowPolicy::Disconnect,
+        });
+
+        sink.queue.lock().sending = true;
+        for i in 0..3 {
+            sink.notify(&executor, i);
+        }
+
+        assert!(sink.is_closed());
+        assert_eq!(metrics.queued(), 0);
+        assert_eq!(metrics.dropped(), 3);
+        assert_eq!(metrics.disconnected(), 1);
+
+        sink.notify(&executor, 4);
+        assert_eq!(metrics.queued(), 0);
+    }
+}
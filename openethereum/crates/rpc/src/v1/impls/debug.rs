@@ -18,13 +18,18 @@
 
 use std::sync::Arc;
 
-use ethcore::client::BlockChainClient;
+use ethcore::client::{BlockChainClient, CallAnalytics, TransactionId};
+use ethereum_types::H256;
 use types::{header::Header, transaction::LocalizedTransaction};
 
 use jsonrpc_core::Result;
 use v1::{
+    helpers::errors,
     traits::Debug,
-    types::{Block, BlockTransactions, Bytes, RichBlock, Transaction},
+    types::{
+        Block, BlockTransactions, Bytes, GethTrace, GethTraceOptions, GethTracer, RichBlock,
+        StructLogTrace, Transaction, MAX_STRUCT_LOGS,
+    },
 };
 
 /// Debug rpc implementation.
@@ -49,7 +54,8 @@ impl<C: BlockChainClient + 'static> Debug for DebugClient<C> {
             .client
             .bad_blocks()
             .into_iter()
-            .map(|(block, reason)| {
+            .map(|bad_block| {
+                let (block, reason) = (bad_block.block, bad_block.reason);
                 let number = block.header.number();
                 let hash = block.header.hash();
                 let base_fee = block.header.base_fee();
@@ -103,6 +109,10 @@ impl<C: BlockChainClient + 'static> Debug for DebugClient<C> {
                     },
                     extra_info: vec![
                         ("reason".to_owned(), reason),
+                        (
+                            "reportedAt".to_owned(),
+                            format!("{:#x}", bad_block.reported_at),
+                        ),
                         ("rlp".to_owned(), serialize(&Bytes(block.bytes))),
                         ("hash".to_owned(), format!("{:#x}", hash)),
                     ]
@@ -112,6 +122,52 @@ impl<C: BlockChainClient + 'static> Debug for DebugClient<C> {
             })
             .collect())
     }
+
+    fn trace_transaction(
+        &self,
+        transaction_hash: H256,
+        options: Option<GethTraceOptions>,
+    ) -> Result<GethTrace> {
+        let options = options.unwrap_or_default();
+        let tracer = options
+            .tracer()
+            .map_err(|e| errors::invalid_params("tracer", e))?;
+        let timeout = options
+            .timeout()
+            .map_err(|e| errors::invalid_params("timeout", e))?;
+        if tracer == GethTracer::Prestate {
+            let state = self
+                .client
+                .replay_prestate(TransactionId::Hash(transaction_hash), Some(timeout))
+                .map_err(errors::call)?;
+            return Ok(GethTrace::from_prestate(state));
+        }
+
+        // the VM trace of the call tracer tells the gas used by reverted calls.
+        let analytics = CallAnalytics {
+            transaction_tracing: tracer == GethTracer::Call,
+            vm_tracing: true,
+            state_diffing: false,
+            timeout: Some(timeout),
+        };
+
+        let executed = self
+            .client
+            .replay(
+                TransactionId::Hash(transaction_hash),
+                analytics,
+                Default::default(),
+            )
+            .map_err(errors::call)?;
+        if tracer == GethTracer::StructLog {
+            let count = executed.vm_trace.as_ref().map_or(0, StructLogTrace::count);
+            if count > MAX_STRUCT_LOGS {
+                return Err(errors::trace_too_large(count, MAX_STRUCT_LOGS));
+            }
+        }
+        GethTrace::new(tracer, executed, &options)
+            .ok_or_else(|| errors::internal("Replay returned no trace", tracer))
+    }
 }
 
 fn serialize<T: ::serde::Serialize>(t: &T) -> String {
@@ -28,8 +28,8 @@ use parking_lot::Mutex;
 use ethash::{self, SeedHashCompute};
 use ethcore::{
     client::{
-        BlockChainClient, BlockId, Call, EngineInfo, ProvingBlockChainClient, StateClient,
-        StateInfo, StateOrBlock, TransactionId, UncleId,
+        BlockChainClient, BlockId, Call, CallAnalytics, EngineInfo, ProvingBlockChainClient,
+        StateClient, StateInfo, StateOrBlock, TransactionId, UncleId,
     },
     miner::{self, MinerService},
     snapshot::SnapshotService,
@@ -53,13 +53,13 @@ use v1::{
         block_import::is_major_importing,
         deprecated::{self, DeprecationNotice},
         dispatch::{default_gas_price, default_max_priority_fee_per_gas, FullDispatcher},
-        errors, fake_sign, limit_logs,
+        errors, fake_sign, limit_logs, RangeBudget, RangePlanner,
     },
     traits::Eth,
     types::{
-        block_number_to_id, Block, BlockNumber, BlockTransactions, Bytes, CallRequest, EthAccount,
-        EthFeeHistory, Filter, Index, Log, Receipt, RichBlock, StorageProof, SyncInfo, SyncStatus,
-        Transaction, Work,
+        block_number_to_id, into_state_override, Block, BlockNumber, BlockTransactions, Bytes,
+        CallRequest, EthAccount, EthFeeHistory, Filter, Index, Log, Receipt, RichBlock,
+        StateOverride, StorageProof, SyncInfo, SyncStatus, Transaction, Work,
     },
 };
 
@@ -77,6 +77,8 @@ pub struct EthClientOptions {
     pub allow_experimental_rpcs: bool,
     /// flag for ancient block sync
     pub no_ancient_blocks: bool,
+    /// Budget of range queries like `eth_getLogs`.
+    pub range_budget: RangeBudget,
 }
 
 impl EthClientOptions {
@@ -96,6 +98,7 @@ impl Default for EthClientOptions {
             allow_missing_blocks: false,
             allow_experimental_rpcs: false,
             no_ancient_blocks: false,
+            range_budget: RangeBudget::default(),
         }
     }
 }
@@ -188,6 +191,68 @@ where
         }
     }
 
+    /// Logs matching the filter. Queries over many canonical blocks are split into sub-ranges by
+    /// the range planner and fail with the partial results once the request budget is spent.
+    fn planned_logs(&self, filter: &EthcoreFilter) -> Result<Vec<Log>> {
+        if let Some(from) = self.client.block_number(filter.from_block.clone()) {
+            let earliest_history = self.client.pruning_info().earliest_history;
+            if from < earliest_history {
+                return Err(errors::history_expired(earliest_history));
+            }
+        }
+
+        let planner = RangePlanner::new(&self.options.range_budget);
+        let by_number = |id: &BlockId| match *id {
+            BlockId::Hash(_) => false,
+            _ => true,
+        };
+        let range = match (
+            by_number(&filter.from_block) && by_number(&filter.to_block),
+            self.client.block_number(filter.from_block.clone()),
+            self.client.block_number(filter.to_block.clone()),
+        ) {
+            (true, Some(from), Some(to)) if from <= to && !planner.is_single_chunk(from, to) => {
+                Some((from, to))
+            }
+            _ => None,
+        };
+
+        let (from, to) = match range {
+            Some(range) => range,
+            None => {
+                return self
+                    .client
+                    .logs(filter.clone())
+                    .map(|logs| logs.into_iter().map(From::from).collect())
+                    .map_err(errors::filter_block_not_found)
+            }
+        };
+
+        let outcome = planner.execute(
+            from,
+            to,
+            |from, to| self.client.logs_cost_estimate(filter, from, to),
+            |from, to| {
+                let mut chunk = filter.clone();
+                chunk.from_block = BlockId::Number(from);
+                chunk.to_block = BlockId::Number(to);
+                chunk.limit = None;
+                self.client
+                    .logs(chunk)
+                    .map_err(errors::filter_block_not_found)
+            },
+        )?;
+        let logs = outcome
+            .items
+            .into_iter()
+            .map(From::from)
+            .collect::<Vec<Log>>();
+        match outcome.resume_from {
+            Some(resume_from) => Err(errors::range_budget_exceeded(resume_from, &logs)),
+            None => Ok(logs),
+        }
+    }
+
     fn rich_block(&self, id: BlockNumberOrId, include_txs: bool) -> Result<Option<RichBlock>> {
         let client = &self.client;
 
@@ -258,7 +323,10 @@ where
         match (block, difficulty) {
             (Some(block), Some(total_difficulty)) => {
                 let view = block.header_view();
-                let eip1559_enabled = client.engine().schedule(view.number()).eip1559;
+                let eip1559_enabled = client
+                    .engine()
+                    .schedule(view.number(), view.timestamp())
+                    .eip1559;
                 let base_fee = if eip1559_enabled {
                     Some(view.base_fee())
                 } else {
@@ -721,8 +789,9 @@ where
             return Box::new(future::done(Ok(result)));
         }
 
-        if block_count > 1024.into() {
-            block_count = 1024.into();
+        let max_block_count = self.options.range_budget.max_fee_history_blocks;
+        if block_count > max_block_count.into() {
+            block_count = max_block_count.into();
         }
 
         let latest_block = self.client.chain_info().best_block_number;
@@ -782,9 +851,13 @@ where
             (gas_used.as_u64() as f64) / (h.gas_limit().as_u64() as f64)
         };
 
-        let get_block_transactions = |i| match self.client.block_body(BlockId::Number(i)) {
-            Some(body) => Some(body.transactions()),
-            None => None,
+        // Recent blocks are served by the miner's fee history, older ones by the client's cache.
+        let get_block_fees = |i| match self.miner.block_fees(i) {
+            Some(fees) => Ok(fees),
+            None => self
+                .client
+                .block_fees(BlockId::Number(i))
+                .ok_or_else(errors::state_pruned),
         };
 
         let reward_percentiles = reward_percentiles.unwrap_or_default();
@@ -794,60 +867,16 @@ where
             let is_last = i == last_block + 1;
 
             if i < pending_block {
-                match get_block_header(i) {
-                    Ok(h) => {
-                        let base_fee = h.base_fee();
-
-                        result.base_fee_per_gas.push(base_fee.unwrap_or_default());
+                match get_block_fees(i) {
+                    Ok(fees) => {
+                        result.base_fee_per_gas.push(fees.base_fee);
 
                         if !is_last {
-                            result.gas_used_ratio.push(calculate_gas_used_ratio(&h));
+                            result.gas_used_ratio.push(fees.gas_used_ratio());
 
                             if reward_percentiles.len() > 0 {
-                                let mut gas_and_reward: Vec<(U256, U256)> = vec![];
-                                if let Some(txs) = get_block_transactions(i) {
-                                    if let Some(receipt) = self.client.block_receipts(&h.hash()) {
-                                        if txs.len() == receipt.receipts.len() {
-                                            for i in 0..txs.len() {
-                                                let gas_used = if i == 0 {
-                                                    receipt.receipts[i].gas_used
-                                                } else {
-                                                    receipt.receipts[i].gas_used
-                                                        - receipt.receipts[i - 1].gas_used
-                                                };
-
-                                                gas_and_reward.push((
-                                                    gas_used,
-                                                    txs[i]
-                                                        .effective_gas_price(base_fee)
-                                                        .saturating_sub(
-                                                            base_fee.unwrap_or_default(),
-                                                        ),
-                                                ));
-                                            }
-                                        }
-                                    }
-                                }
-
-                                gas_and_reward.sort_by(|a, b| a.1.cmp(&b.1));
-
                                 reward_final.push(
-                                    reward_percentiles
-                                        .iter()
-                                        .map(|p| {
-                                            let target_gas = U256::from(
-                                                ((h.gas_used().as_u64() as f64) * p / 100.0) as u64,
-                                            );
-                                            let mut sum_gas = U256::default();
-                                            for pair in &gas_and_reward {
-                                                sum_gas += pair.0;
-                                                if target_gas <= sum_gas {
-                                                    return pair.1;
-                                                }
-                                            }
-                                            0.into()
-                                        })
-                                        .collect(),
+                                    reward_percentiles.iter().map(|p| fees.reward(*p)).collect(),
                                 );
                             }
                         }
@@ -1128,7 +1157,9 @@ where
                 .transaction(&hash)
                 .map(|t| Transaction::from_pending(t.pending().clone()))
         });
-        let result = Ok(tx).and_then(errors::check_block_gap(&*self.client, self.options));
+        let result = Ok(tx)
+            .and_then(errors::check_block_gap(&*self.client, self.options))
+            .and_then(errors::check_transaction_index(&*self.client, hash));
         Box::new(future::done(result))
     }
 
@@ -1179,7 +1210,8 @@ where
 
         let receipt = self.client.transaction_receipt(TransactionId::Hash(hash));
         let result = Ok(receipt.map(Into::into))
-            .and_then(errors::check_block_gap(&*self.client, self.options));
+            .and_then(errors::check_block_gap(&*self.client, self.options))
+            .and_then(errors::check_transaction_index(&*self.client, hash));
         Box::new(future::done(result))
     }
 
@@ -1249,9 +1281,9 @@ where
             Ok(value) => value,
             Err(err) => return Box::new(future::err(err)),
         };
-        let mut logs = match self.client.logs(filter.clone()) {
-            Ok(logs) => logs.into_iter().map(From::from).collect::<Vec<Log>>(),
-            Err(id) => return Box::new(future::err(errors::filter_block_not_found(id))),
+        let mut logs = match self.planned_logs(&filter) {
+            Ok(logs) => logs,
+            Err(err) => return Box::new(future::err(err)),
         };
 
         if include_pending {
@@ -1347,7 +1379,17 @@ where
         self.send_raw_transaction(raw)
     }
 
-    fn call(&self, request: CallRequest, num: Option<BlockNumber>) -> BoxFuture<Bytes> {
+    fn call(
+        &self,
+        request: CallRequest,
+        num: Option<BlockNumber>,
+        overrides: Option<StateOverride>,
+    ) -> BoxFuture<Bytes> {
+        let overrides = try_bf!(into_state_override(overrides.unwrap_or_default()));
+        let analytics = CallAnalytics {
+            timeout: request.timeout.map(|ms| Duration::from_millis(ms.as_u64())),
+            ..Default::default()
+        };
         let request = CallRequest::into(request);
         let signed = try_bf!(fake_sign::sign_call(request));
 
@@ -1378,7 +1420,7 @@ where
 
         let result = self
             .client
-            .call(&signed, Default::default(), &mut state, &header);
+            .call(&signed, analytics, &mut state, &header, &overrides);
 
         Box::new(future::done(
             result
@@ -1392,6 +1434,7 @@ where
     }
 
     fn estimate_gas(&self, request: CallRequest, num: Option<BlockNumber>) -> BoxFuture<U256> {
+        let timeout = request.timeout.map(|ms| Duration::from_millis(ms.as_u64()));
         let request = CallRequest::into(request);
         let signed = try_bf!(fake_sign::sign_call(request));
         let num = num.unwrap_or_default();
@@ -1420,7 +1463,7 @@ where
 
         Box::new(future::done(
             self.client
-                .estimate_gas(&signed, &state, &header)
+                .estimate_gas(&signed, &state, &header, timeout)
                 .map_err(errors::call),
         ))
     }
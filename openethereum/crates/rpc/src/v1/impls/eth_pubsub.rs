@@ -31,37 +31,57 @@ use jsonrpc_pubsub::{
 };
 
 use v1::{
-    helpers::{errors, limit_logs, Subscribers},
+    helpers::{
+        errors, limit_logs, QueuedSink, Subscribers, SubscriptionLimits, SubscriptionMetrics,
+    },
     metadata::Metadata,
     traits::EthPubSub,
-    types::{pubsub, Header, Log, RichHeader},
+    types::{pubsub, Header, LocalTransactionEvent, Log, Reorg, RichHeader, Transaction},
 };
 
 use ethcore::client::{
-    BlockChainClient, BlockId, ChainNotify, ChainRouteType, EngineInfo, NewBlocks,
+    self, BlockChainClient, BlockId, ChainNotify, ChainRouteType, EngineInfo, NewBlocks,
 };
 use ethereum_types::H256;
+use miner::pool::VerifiedTransaction;
 use parity_runtime::Executor;
 use parking_lot::RwLock;
 
 use types::{encoded, filter::Filter as EthFilter};
 
-type Client = Sink<pubsub::Result>;
+type Client = QueuedSink<pubsub::Result>;
 
 /// Eth PubSub implementation.
 pub struct EthPubSubClient<C> {
     handler: Arc<ChainNotificationHandler<C>>,
     heads_subscribers: Arc<RwLock<Subscribers<Client>>>,
     logs_subscribers: Arc<RwLock<Subscribers<(Client, EthFilter)>>>,
-    transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    transactions_subscribers: Arc<RwLock<Subscribers<(Client, pubsub::TransactionsParams)>>>,
+    reorgs_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    local_transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    limits: SubscriptionLimits,
+    metrics: Arc<SubscriptionMetrics>,
 }
 
 impl<C> EthPubSubClient<C> {
     /// Creates new `EthPubSubClient`.
     pub fn new(client: Arc<C>, executor: Executor) -> Self {
+        Self::with_limits(client, executor, Default::default(), Default::default())
+    }
+
+    /// Creates new `EthPubSubClient` queueing at most `limits.max_queued` notifications
+    /// per subscription.
+    pub fn with_limits(
+        client: Arc<C>,
+        executor: Executor,
+        limits: SubscriptionLimits,
+        metrics: Arc<SubscriptionMetrics>,
+    ) -> Self {
         let heads_subscribers = Arc::new(RwLock::new(Subscribers::default()));
         let logs_subscribers = Arc::new(RwLock::new(Subscribers::default()));
         let transactions_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let reorgs_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let local_transactions_subscribers = Arc::new(RwLock::new(Subscribers::default()));
 
         EthPubSubClient {
             handler: Arc::new(ChainNotificationHandler {
@@ -70,10 +90,16 @@ impl<C> EthPubSubClient<C> {
                 heads_subscribers: heads_subscribers.clone(),
                 logs_subscribers: logs_subscribers.clone(),
                 transactions_subscribers: transactions_subscribers.clone(),
+                reorgs_subscribers: reorgs_subscribers.clone(),
+                local_transactions_subscribers: local_transactions_subscribers.clone(),
             }),
             heads_subscribers,
             logs_subscribers,
             transactions_subscribers,
+            reorgs_subscribers,
+            local_transactions_subscribers,
+            limits,
+            metrics,
         }
     }
 
@@ -84,6 +110,8 @@ impl<C> EthPubSubClient<C> {
         *client.heads_subscribers.write() = Subscribers::default();
         *client.logs_subscribers.write() = Subscribers::default();
         *client.transactions_subscribers.write() = Subscribers::default();
+        *client.reorgs_subscribers.write() = Subscribers::default();
+        *client.local_transactions_subscribers.write() = Subscribers::default();
         client
     }
 
@@ -91,6 +119,20 @@ impl<C> EthPubSubClient<C> {
     pub fn handler(&self) -> Weak<ChainNotificationHandler<C>> {
         Arc::downgrade(&self.handler)
     }
+
+    fn queued(&self, sink: Sink<pubsub::Result>) -> Client {
+        QueuedSink::new(sink, self.limits.clone(), self.metrics.clone())
+    }
+}
+
+/// Removes subscriptions dropped for not keeping up with their notifications.
+fn prune_closed<T, F>(subscribers: &RwLock<Subscribers<T>>, sink: F)
+where
+    F: Fn(&T) -> &Client,
+{
+    if subscribers.read().values().any(|s| sink(s).is_closed()) {
+        subscribers.write().retain(|s| !sink(s).is_closed());
+    }
 }
 
 /// PubSub Notification handler.
@@ -99,7 +141,9 @@ pub struct ChainNotificationHandler<C> {
     executor: Executor,
     heads_subscribers: Arc<RwLock<Subscribers<Client>>>,
     logs_subscribers: Arc<RwLock<Subscribers<(Client, EthFilter)>>>,
-    transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    transactions_subscribers: Arc<RwLock<Subscribers<(Client, pubsub::TransactionsParams)>>>,
+    reorgs_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    local_transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
 }
 
 impl<C> ChainNotificationHandler<C>
@@ -107,12 +151,15 @@ where
     C: EngineInfo,
 {
     fn notify(executor: &Executor, subscriber: &Client, result: pubsub::Result) {
-        executor.spawn(
-            subscriber
-                .notify(Ok(result))
-                .map(|_| ())
-                .map_err(|e| warn!(target: "rpc", "Unable to send notification: {}", e)),
-        );
+        subscriber.notify(executor, result);
+    }
+
+    fn prune_closed(&self) {
+        prune_closed(&self.heads_subscribers, |s| s);
+        prune_closed(&self.logs_subscribers, |s| &s.0);
+        prune_closed(&self.transactions_subscribers, |s| &s.0);
+        prune_closed(&self.reorgs_subscribers, |s| s);
+        prune_closed(&self.local_transactions_subscribers, |s| s);
     }
 
     fn notify_heads(&self, headers: &[(encoded::Header, BTreeMap<String, String>)]) {
@@ -168,15 +215,28 @@ where
         }
     }
 
-    /// Notify all subscribers about new transaction hashes.
-    pub fn notify_new_transactions(&self, hashes: &[H256]) {
-        for subscriber in self.transactions_subscribers.read().values() {
-            for hash in hashes {
-                Self::notify(
-                    &self.executor,
-                    subscriber,
-                    pubsub::Result::TransactionHash(*hash),
-                );
+    /// Notify all subscribers about new transactions.
+    ///
+    /// Subscribers with a filter are only notified about matching transactions, either
+    /// with their hashes or with the full transactions if they asked for them.
+    pub fn notify_new_transactions(&self, transactions: &[Arc<VerifiedTransaction>]) {
+        self.prune_closed();
+        let subscribers = self.transactions_subscribers.read();
+        for tx in transactions {
+            let mut full = None;
+            for &(ref subscriber, ref params) in subscribers.values() {
+                if !params.filter.matches(tx) {
+                    continue;
+                }
+                let result = if params.include_transactions {
+                    let full = full.get_or_insert_with(|| {
+                        Box::new(Transaction::from_pending(tx.pending().clone()))
+                    });
+                    pubsub::Result::Transaction(full.clone())
+                } else {
+                    pubsub::Result::TransactionHash(tx.signed().hash())
+                };
+                Self::notify(&self.executor, subscriber, result);
             }
         }
     }
@@ -185,6 +245,7 @@ where
 impl<C: BlockChainClient + EngineInfo> ChainNotify for ChainNotificationHandler<C> {
     // t_nb 11.3 RPC. Notify subscriber header/logs about new block
     fn new_blocks(&self, new_blocks: NewBlocks) {
+        self.prune_closed();
         if self.heads_subscribers.read().is_empty() && self.logs_subscribers.read().is_empty() {
             return;
         }
@@ -234,6 +295,37 @@ impl<C: BlockChainClient + EngineInfo> ChainNotify for ChainNotificationHandler<
                 .collect()),
         });
     }
+
+    fn chain_reorganized(&self, reorg: &client::Reorg) {
+        self.prune_closed();
+        let subscribers = self.reorgs_subscribers.read();
+        if subscribers.is_empty() {
+            return;
+        }
+        let reorg: Reorg = reorg.clone().into();
+        for subscriber in subscribers.values() {
+            Self::notify(
+                &self.executor,
+                subscriber,
+                pubsub::Result::Reorg(Box::new(reorg.clone())),
+            );
+        }
+    }
+
+    fn local_transactions(&self, events: &[client::LocalTransactionEvent]) {
+        self.prune_closed();
+        let subscribers = self.local_transactions_subscribers.read();
+        for event in events {
+            let event: LocalTransactionEvent = event.clone().into();
+            for subscriber in subscribers.values() {
+                Self::notify(
+                    &self.executor,
+                    subscriber,
+                    pubsub::Result::LocalTransaction(Box::new(event.clone())),
+                );
+            }
+        }
+    }
 }
 
 impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
@@ -248,7 +340,9 @@ impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
     ) {
         let error = match (kind, params) {
             (pubsub::Kind::NewHeads, None) => {
-                self.heads_subscribers.write().push(subscriber);
+                self.heads_subscribers
+                    .write()
+                    .push_with(subscriber, |sink| self.queued(sink));
                 return;
             }
             (pubsub::Kind::NewHeads, _) => {
@@ -256,18 +350,47 @@ impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
             }
             (pubsub::Kind::Logs, Some(pubsub::Params::Logs(filter))) => match filter.try_into() {
                 Ok(filter) => {
-                    self.logs_subscribers.write().push(subscriber, filter);
+                    self.logs_subscribers
+                        .write()
+                        .push_with(subscriber, |sink| (self.queued(sink), filter));
                     return;
                 }
                 Err(err) => err,
             },
             (pubsub::Kind::Logs, _) => errors::invalid_params("logs", "Expected a filter object."),
             (pubsub::Kind::NewPendingTransactions, None) => {
-                self.transactions_subscribers.write().push(subscriber);
+                self.transactions_subscribers
+                    .write()
+                    .push_with(subscriber, |sink| (self.queued(sink), Default::default()));
+                return;
+            }
+            (pubsub::Kind::NewPendingTransactions, Some(pubsub::Params::Transactions(params))) => {
+                self.transactions_subscribers
+                    .write()
+                    .push_with(subscriber, |sink| (self.queued(sink), params));
+                return;
+            }
+            (pubsub::Kind::NewPendingTransactions, _) => errors::invalid_params(
+                "newPendingTransactions",
+                "Expected no parameters or a transaction filter.",
+            ),
+            (pubsub::Kind::Reorgs, None) => {
+                self.reorgs_subscribers
+                    .write()
+                    .push_with(subscriber, |sink| self.queued(sink));
+                return;
+            }
+            (pubsub::Kind::Reorgs, _) => {
+                errors::invalid_params("reorgs", "Expected no parameters.")
+            }
+            (pubsub::Kind::LocalTransactions, None) => {
+                self.local_transactions_subscribers
+                    .write()
+                    .push_with(subscriber, |sink| self.queued(sink));
                 return;
             }
-            (pubsub::Kind::NewPendingTransactions, _) => {
-                errors::invalid_params("newPendingTransactions", "Expected no parameters.")
+            (pubsub::Kind::LocalTransactions, _) => {
+                errors::invalid_params("localTransactions", "Expected no parameters.")
             }
             _ => errors::unimplemented(None),
         };
@@ -279,7 +402,13 @@ impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
         let res = self.heads_subscribers.write().remove(&id).is_some();
         let res2 = self.logs_subscribers.write().remove(&id).is_some();
         let res3 = self.transactions_subscribers.write().remove(&id).is_some();
+        let res4 = self.reorgs_subscribers.write().remove(&id).is_some();
+        let res5 = self
+            .local_transactions_subscribers
+            .write()
+            .remove(&id)
+            .is_some();
 
-        Ok(res || res2 || res3)
+        Ok(res || res2 || res3 || res4 || res5)
     }
 }
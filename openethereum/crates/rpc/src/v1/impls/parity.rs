@@ -15,7 +15,7 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Parity-specific rpc implementation.
-use std::{collections::BTreeMap, str::FromStr, sync::Arc};
+use std::{collections::BTreeMap, str::FromStr, sync::Arc, time::Instant};
 
 use crypto::{publickey::ecies, DEFAULT_MAC};
 use ethcore::{
@@ -38,13 +38,13 @@ use v1::{
         block_import::is_major_importing,
         errors,
         external_signer::{SignerService, SigningQueue},
-        fake_sign, verify_signature, NetworkSettings,
+        fake_sign, verify_signature, ChainHealth, NetworkSettings,
     },
     traits::Parity,
     types::{
-        block_number_to_id, BlockNumber, Bytes, CallRequest, ChainStatus, Header, Histogram,
-        LocalTransactionStatus, Peers, Receipt, RecoveredAccount, RichHeader, RpcSettings,
-        Transaction, TransactionStats,
+        block_number_to_id, BackupStatus, BlockNumber, Bytes, CallRequest, ChainStatus, Header,
+        Histogram, LocalTransactionStatus, NodeHealth, NonceState, Peers, Receipt,
+        RecoveredAccount, RichHeader, RpcSettings, Transaction, TransactionStats,
     },
 };
 use version::version_data;
@@ -64,6 +64,7 @@ where
     signer: Option<Arc<SignerService>>,
     ws_address: Option<Host>,
     snapshot: Option<Arc<dyn SnapshotService>>,
+    health: Option<Arc<ChainHealth>>,
 }
 
 impl<C, M> ParityClient<C, M>
@@ -92,8 +93,23 @@ where
             signer,
             ws_address,
             snapshot,
+            health: None,
         }
     }
+
+    /// Reports block imports and reorganizations tracked by `health` in `parity_nodeHealth`.
+    pub fn with_health(mut self, health: Arc<ChainHealth>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    fn is_syncing(&self) -> bool {
+        let is_warping = match self.snapshot.as_ref().map(|s| s.restoration_status()) {
+            Some(RestorationStatus::Ongoing { .. }) => true,
+            _ => false,
+        };
+        is_warping || is_major_importing(Some(self.sync.status().state), self.client.queue_info())
+    }
 }
 
 impl<C, M, S> Parity for ParityClient<C, M>
@@ -329,6 +345,13 @@ where
         Box::new(future::ok(self.miner.next_nonce(&*self.client, &address)))
     }
 
+    fn nonce_state(&self, address: H160) -> Result<NonceState> {
+        Ok(self
+            .miner
+            .sender_nonce_state(&*self.client, &address)
+            .into())
+    }
+
     fn mode(&self) -> Result<String> {
         Ok(self.client.mode().to_string())
     }
@@ -352,6 +375,10 @@ where
         Ok(ChainStatus { block_gap: gap })
     }
 
+    fn backup_status(&self) -> Result<BackupStatus> {
+        Ok(self.client.backup_status().into())
+    }
+
     fn node_kind(&self) -> Result<::v1::types::NodeKind> {
         use v1::types::{Availability, Capability, NodeKind};
 
@@ -462,7 +489,7 @@ where
         };
 
         self.client
-            .call_many(&requests, &mut state, &header)
+            .call_many(&requests, &mut state, &header, &Default::default())
             .map(|res| res.into_iter().map(|res| res.output.into()).collect())
             .map_err(errors::call)
     }
@@ -473,20 +500,31 @@ where
 
     fn status(&self) -> Result<()> {
         let has_peers = self.settings.is_dev_chain || self.sync.status().num_peers > 0;
-        let is_warping = match self.snapshot.as_ref().map(|s| s.restoration_status()) {
-            Some(RestorationStatus::Ongoing { .. }) => true,
-            _ => false,
-        };
-        let is_not_syncing = !is_warping
-            && !is_major_importing(Some(self.sync.status().state), self.client.queue_info());
-
-        if has_peers && is_not_syncing {
+        if has_peers && !self.is_syncing() {
             Ok(())
         } else {
             Err(errors::status_error(has_peers))
         }
     }
 
+    fn node_health(&self) -> Result<NodeHealth> {
+        let peers = self.sync.status().num_peers;
+        let syncing = self.is_syncing();
+        let now = Instant::now();
+        let health = self.health.as_ref();
+
+        Ok(NodeHealth {
+            healthy: (self.settings.is_dev_chain || peers > 0) && !syncing,
+            syncing,
+            peers,
+            seconds_since_last_import: health
+                .and_then(|h| h.since_last_import(now))
+                .map(|elapsed| elapsed.as_secs()),
+            reorg_depth: health.map_or(0, |h| h.reorg_depth(now)),
+            clock_drift_millis: health.and_then(|h| h.clock_drift_millis()),
+        })
+    }
+
     fn verify_signature(
         &self,
         is_prefixed: bool,
@@ -16,22 +16,24 @@
 
 /// Parity-specific rpc interface for operations altering the settings.
 use std::io;
-use std::{sync::Arc, time::Duration};
+use std::{path::Path, sync::Arc, time::Duration};
 
 use ethcore::{
-    client::{BlockChainClient, Mode},
+    client::{BlockChainClient, ChainInfo, Mode},
     miner::{self, MinerService},
 };
-use ethereum_types::{H160, H256, U256};
+use ethereum_types::{H160, H256, U256, U64};
 use fetch::{self, Fetch};
 use hash::keccak_buffer;
+use local_store::TransactionJournal;
 use sync::ManageNetwork;
+use types::transaction::{PendingTransaction, SignedTransaction, TypedTransaction};
 
 use jsonrpc_core::{futures::Future, BoxFuture, Result};
 use v1::{
     helpers::errors,
     traits::ParitySet,
-    types::{Bytes, Transaction},
+    types::{AltMempoolCapacity, AltMempoolReservation, Bytes, CacheSizes, Transaction},
 };
 
 #[cfg(any(test, feature = "accounts"))]
@@ -85,6 +87,7 @@ pub struct ParitySetClient<C, M, F = fetch::Client> {
     miner: Arc<M>,
     net: Arc<dyn ManageNetwork>,
     fetch: F,
+    journal: Option<Arc<dyn TransactionJournal>>,
 }
 
 impl<C, M, F> ParitySetClient<C, M, F>
@@ -98,13 +101,30 @@ where
             miner: miner.clone(),
             net: net.clone(),
             fetch,
+            journal: None,
         }
     }
+
+    /// Serves journaled local transactions from `journal`.
+    pub fn with_transaction_journal(mut self, journal: Arc<dyn TransactionJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    fn journaled(&self) -> Result<Vec<PendingTransaction>> {
+        self.journal
+            .as_ref()
+            .ok_or_else(|| {
+                errors::unsupported("Local transaction journal is not available.", None)
+            })?
+            .journaled_transactions()
+            .map_err(errors::database)
+    }
 }
 
 impl<C, M, F> ParitySet for ParitySetClient<C, M, F>
 where
-    C: BlockChainClient + 'static,
+    C: BlockChainClient + miner::BlockChainClient + 'static,
     M: MinerService + 'static,
     F: Fetch + 'static,
 {
@@ -162,6 +182,31 @@ where
         Ok(true)
     }
 
+    fn schedule_engine_signer_rotation(&self, secret: H256, handover_block: U64) -> Result<bool> {
+        let handover_block = handover_block.as_u64();
+        let best_block = self.client.chain_info().best_block_number;
+        if handover_block <= best_block {
+            return Err(errors::invalid_params(
+                "handover_block",
+                format!("must be above the best block #{}", best_block),
+            ));
+        }
+
+        let keypair = crypto::publickey::KeyPair::from_secret(secret.into())
+            .map_err(|e| errors::account("Invalid secret", e))?;
+        self.miner
+            .schedule_signer_rotation(
+                ethcore::engines::signer::from_keypair(keypair),
+                handover_block,
+            )
+            .map_err(|e| errors::unsupported(e, None))?;
+        Ok(true)
+    }
+
+    fn cancel_engine_signer_rotation(&self) -> Result<bool> {
+        Ok(self.miner.cancel_signer_rotation())
+    }
+
     fn add_reserved_peer(&self, peer: String) -> Result<bool> {
         match self.net.add_reserved_peer(peer) {
             Ok(()) => Ok(true),
@@ -216,6 +261,18 @@ where
             .map_err(|()| errors::cannot_restart())
     }
 
+    fn set_cache_sizes(&self, sizes: CacheSizes) -> Result<bool> {
+        self.client.set_cache_sizes(sizes.into());
+        Ok(true)
+    }
+
+    fn backup(&self, path: String) -> Result<bool> {
+        self.client
+            .backup(Path::new(&path))
+            .map_err(errors::database)?;
+        Ok(true)
+    }
+
     fn hash_content(&self, url: String) -> BoxFuture<H256> {
         let future = self
             .fetch
@@ -238,4 +295,70 @@ where
             .remove_transaction(&hash)
             .map(|t| Transaction::from_pending(t.pending().clone())))
     }
+
+    fn send_bundle(&self, raw: Vec<Bytes>, block_number: U64) -> Result<H256> {
+        let transactions = raw
+            .into_iter()
+            .map(|raw| {
+                TypedTransaction::decode(&raw.into_vec())
+                    .map_err(errors::rlp)
+                    .and_then(|tx| SignedTransaction::new(tx).map_err(errors::transaction))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.miner
+            .submit_bundle(
+                &*self.client,
+                miner::Bundle {
+                    transactions,
+                    block_number: block_number.as_u64(),
+                },
+            )
+            .map_err(errors::bundle)
+    }
+
+    fn journaled_transactions(&self) -> Result<Vec<Transaction>> {
+        Ok(self
+            .journaled()?
+            .into_iter()
+            .map(Transaction::from_pending)
+            .collect())
+    }
+
+    fn rebroadcast_journaled_transactions(&self) -> Result<Vec<H256>> {
+        let mut imported = Vec::new();
+        for pending in self.journaled()? {
+            let hash = pending.hash();
+            match self
+                .miner
+                .import_claimed_local_transaction(&*self.client, pending, true)
+            {
+                Ok(()) => imported.push(hash),
+                Err(e) => {
+                    trace!(target: "own_tx", "Journaled transaction {:?} not imported: {}", hash, e)
+                }
+            }
+        }
+        Ok(imported)
+    }
+
+    fn alt_mempool_capacity(&self) -> Result<AltMempoolCapacity> {
+        Ok(self.miner.alt_mempool_capacity().into())
+    }
+
+    fn reserve_alt_mempool(
+        &self,
+        bundler: H160,
+        slots: U64,
+        ttl: U64,
+    ) -> Result<AltMempoolReservation> {
+        self.miner
+            .reserve_alt_mempool(
+                bundler,
+                slots.as_u64() as usize,
+                Duration::from_secs(ttl.as_u64()),
+            )
+            .map(Into::into)
+            .map_err(errors::alt_mempool)
+    }
 }
@@ -21,24 +21,41 @@ use std::{sync::Arc, time::Duration};
 
 use jsonrpc_core::{
     self as core,
-    futures::{future, Future, Sink, Stream},
+    futures::{future, Stream},
     MetaIoHandler, Result,
 };
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use tokio_timer;
 
 use parity_runtime::Executor;
-use v1::{helpers::GenericPollManager, metadata::Metadata, traits::PubSub};
+use v1::{
+    helpers::{GenericPollManager, QueuedSink, SubscriptionLimits, SubscriptionMetrics},
+    metadata::Metadata,
+    traits::PubSub,
+};
 
 /// Parity PubSub implementation.
 pub struct PubSubClient<S: core::Middleware<Metadata>> {
     poll_manager: Arc<RwLock<GenericPollManager<S>>>,
     executor: Executor,
+    limits: SubscriptionLimits,
+    metrics: Arc<SubscriptionMetrics>,
 }
 
 impl<S: core::Middleware<Metadata>> PubSubClient<S> {
     /// Creates new `PubSubClient`.
     pub fn new(rpc: MetaIoHandler<Metadata, S>, executor: Executor) -> Self {
+        Self::with_limits(rpc, executor, Default::default(), Default::default())
+    }
+
+    /// Creates new `PubSubClient` queueing at most `limits.max_queued` notifications
+    /// per subscription.
+    pub fn with_limits(
+        rpc: MetaIoHandler<Metadata, S>,
+        executor: Executor,
+        limits: SubscriptionLimits,
+        metrics: Arc<SubscriptionMetrics>,
+    ) -> Self {
         let poll_manager = Arc::new(RwLock::new(GenericPollManager::new(rpc)));
         let pm2 = Arc::downgrade(&poll_manager);
 
@@ -63,6 +80,8 @@ impl<S: core::Middleware<Metadata>> PubSubClient<S> {
         PubSubClient {
             poll_manager,
             executor,
+            limits,
+            metrics,
         }
     }
 }
@@ -98,13 +117,20 @@ impl<S: core::Middleware<Metadata>> PubSub for PubSubClient<S> {
         let (id, receiver) = poll_manager.subscribe(meta, method, params);
         match subscriber.assign_id(id.clone()) {
             Ok(sink) => {
-                self.executor.spawn(
-                    receiver
-                        .forward(sink.sink_map_err(|e| {
-                            warn!("Cannot send notification: {:?}", e);
-                        }))
-                        .map(|_| ()),
-                );
+                let sink = QueuedSink::new(sink, self.limits.clone(), self.metrics.clone());
+                let executor = self.executor.clone();
+                let poll_manager = Arc::downgrade(&self.poll_manager);
+                self.executor.spawn(receiver.for_each(move |result| {
+                    sink.notify_result(&executor, result);
+                    if !sink.is_closed() {
+                        return Ok(());
+                    }
+                    // the subscriber did not keep up, stop polling for it
+                    if let Some(poll_manager) = poll_manager.upgrade() {
+                        poll_manager.write().unsubscribe(&id);
+                    }
+                    Err(())
+                }));
             }
             Err(_) => {
                 poll_manager.unsubscribe(&id);
@@ -16,7 +16,7 @@
 
 //! Traces api implementation.
 
-use std::sync::Arc;
+use std::{cmp, sync::Arc};
 
 use ethcore::client::{
     BlockChainClient, BlockId, Call, CallAnalytics, EngineInfo, StateClient, StateInfo, TraceId,
@@ -27,11 +27,12 @@ use types::transaction::{SignedTransaction, TypedTransaction};
 
 use jsonrpc_core::Result;
 use v1::{
-    helpers::{errors, fake_sign},
+    helpers::{errors, fake_sign, RangeBudget, RangePlanner},
     traits::Traces,
     types::{
-        block_number_to_id, BlockNumber, Bytes, CallRequest, Index, LocalizedTrace, TraceFilter,
-        TraceOptions, TraceResults, TraceResultsWithTransactionHash,
+        block_number_to_id, into_state_override, BlockNumber, Bytes, CallRequest, Index,
+        LocalizedTrace, ReplayOptions, TraceCall, TraceFilter, TraceOptions, TraceResults,
+        TraceResultsWithTransactionHash,
     },
 };
 
@@ -40,19 +41,27 @@ fn to_call_analytics(flags: TraceOptions) -> CallAnalytics {
         transaction_tracing: flags.contains(&("trace".to_owned())),
         vm_tracing: flags.contains(&("vmTrace".to_owned())),
         state_diffing: flags.contains(&("stateDiff".to_owned())),
+        timeout: None,
     }
 }
 
 /// Traces api implementation.
 pub struct TracesClient<C> {
     client: Arc<C>,
+    range_budget: RangeBudget,
 }
 
 impl<C> TracesClient<C> {
     /// Creates new Traces client.
     pub fn new(client: &Arc<C>) -> Self {
+        Self::with_range_budget(client, RangeBudget::default())
+    }
+
+    /// Creates new Traces client executing `trace_filter` within the given budget.
+    pub fn with_range_budget(client: &Arc<C>, range_budget: RangeBudget) -> Self {
         TracesClient {
             client: client.clone(),
+            range_budget,
         }
     }
 }
@@ -63,10 +72,83 @@ where
     C: BlockChainClient + StateClient<State = S> + Call<State = S> + EngineInfo + 'static,
 {
     fn filter(&self, filter: TraceFilter) -> Result<Option<Vec<LocalizedTrace>>> {
-        Ok(self
-            .client
-            .filter_traces(filter.into())
-            .map(|traces| traces.into_iter().map(LocalizedTrace::from).collect()))
+        let filter: ::ethcore::client::TraceFilter = filter.into();
+        let planner = RangePlanner::new(&self.range_budget);
+        let by_number = |id: &BlockId| match *id {
+            BlockId::Hash(_) => false,
+            _ => true,
+        };
+        let range = match (
+            by_number(&filter.range.start) && by_number(&filter.range.end),
+            self.client.block_number(filter.range.start.clone()),
+            self.client.block_number(filter.range.end.clone()),
+        ) {
+            (true, Some(from), Some(to)) if from <= to && !planner.is_single_chunk(from, to) => {
+                Some((from, to))
+            }
+            _ => None,
+        };
+
+        let (from, to) = match range {
+            Some(range) => range,
+            None => {
+                return Ok(self
+                    .client
+                    .filter_traces(filter)
+                    .map(|traces| traces.into_iter().map(LocalizedTrace::from).collect()))
+            }
+        };
+
+        // The offset of a paginated query spans the entire range, so sub-ranges are executed
+        // until the requested page is complete, each page within the budget.
+        let after = filter.after.unwrap_or(0);
+        let count = filter.count.unwrap_or(usize::max_value());
+        let page_end = after.saturating_add(count);
+        let page_budget = RangeBudget {
+            max_results: cmp::min(self.range_budget.max_results, page_end),
+            ..self.range_budget.clone()
+        };
+        let outcome = RangePlanner::new(&page_budget).execute(
+            from,
+            to,
+            |from, to| to - from + 1,
+            |from, to| {
+                let chunk = ::ethcore::client::TraceFilter {
+                    range: BlockId::Number(from)..BlockId::Number(to),
+                    from_address: filter.from_address.clone(),
+                    to_address: filter.to_address.clone(),
+                    after: None,
+                    count: None,
+                };
+                self.client.filter_traces(chunk).ok_or(())
+            },
+        );
+        // tracing is disabled
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(()) => return Ok(None),
+        };
+        let found = outcome.items.len();
+        let traces = outcome
+            .items
+            .into_iter()
+            .skip(after)
+            .take(count)
+            .map(LocalizedTrace::from)
+            .collect::<Vec<_>>();
+        match outcome.resume_from {
+            Some(resume_from) if found < page_end => {
+                let remaining_after = after.saturating_sub(found);
+                let remaining_count = filter.count.map(|count| count - traces.len());
+                Err(errors::trace_range_budget_exceeded(
+                    resume_from,
+                    remaining_after,
+                    remaining_count,
+                    &traces,
+                ))
+            }
+            _ => Ok(Some(traces)),
+        }
     }
 
     fn block_traces(&self, block_number: BlockNumber) -> Result<Option<Vec<LocalizedTrace>>> {
@@ -136,6 +218,7 @@ where
                 &header
                     .decode(self.client.engine().params().eip1559_transition)
                     .map_err(errors::decode)?,
+                &Default::default(),
             )
             .map(TraceResults::from)
             .map_err(errors::call)
@@ -143,17 +226,19 @@ where
 
     fn call_many(
         &self,
-        requests: Vec<(CallRequest, TraceOptions)>,
+        requests: Vec<TraceCall>,
         block: Option<BlockNumber>,
+        options: Option<ReplayOptions>,
     ) -> Result<Vec<TraceResults>> {
         let block = block.unwrap_or_default();
 
         let requests = requests
             .into_iter()
-            .map(|(request, flags)| {
-                let request = CallRequest::into(request);
+            .map(|call| {
+                let overrides = into_state_override(call.overrides.unwrap_or_default())?;
+                let request = CallRequest::into(call.request);
                 let signed = fake_sign::sign_call(request)?;
-                Ok((signed, to_call_analytics(flags)))
+                Ok((signed, to_call_analytics(call.options), overrides))
             })
             .collect::<Result<Vec<_>>>()?;
 
@@ -178,12 +263,13 @@ where
             .ok_or_else(errors::state_pruned)?;
 
         self.client
-            .call_many(
+            .call_many_with_overrides(
                 &requests,
                 &mut state,
                 &header
                     .decode(self.client.engine().params().eip1559_transition)
                     .map_err(errors::decode)?,
+                options.map(Into::into).unwrap_or_default(),
             )
             .map(|results| results.into_iter().map(TraceResults::from).collect())
             .map_err(errors::call)
@@ -229,6 +315,7 @@ where
                 &header
                     .decode(self.client.engine().params().eip1559_transition)
                     .map_err(errors::decode)?,
+                &Default::default(),
             )
             .map(TraceResults::from)
             .map_err(errors::call)
@@ -238,11 +325,13 @@ where
         &self,
         transaction_hash: H256,
         flags: TraceOptions,
+        options: Option<ReplayOptions>,
     ) -> Result<TraceResults> {
         self.client
             .replay(
                 TransactionId::Hash(transaction_hash),
                 to_call_analytics(flags),
+                options.unwrap_or_default().into(),
             )
             .map(TraceResults::from)
             .map_err(errors::call)
@@ -252,6 +341,7 @@ where
         &self,
         block_number: BlockNumber,
         flags: TraceOptions,
+        options: Option<ReplayOptions>,
     ) -> Result<Vec<TraceResultsWithTransactionHash>> {
         let id = match block_number {
             BlockNumber::Hash { hash, .. } => BlockId::Hash(hash),
@@ -268,7 +358,11 @@ where
         };
 
         self.client
-            .replay_block_transactions(id, to_call_analytics(flags))
+            .replay_block_transactions(
+                id,
+                to_call_analytics(flags),
+                options.unwrap_or_default().into(),
+            )
             .map(|results| results.map(TraceResultsWithTransactionHash::from).collect())
             .map_err(errors::call)
     }
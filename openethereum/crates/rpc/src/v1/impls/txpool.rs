@@ -0,0 +1,79 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Geth-compatible txpool rpc implementation.
+use std::sync::Arc;
+
+use ethcore::{client::Nonce, miner::MinerService};
+use jsonrpc_core::Result;
+use miner::pool::VerifiedTransaction;
+use types::transaction::Action;
+use v1::{
+    traits::TxPool,
+    types::{Transaction, TxPoolContent, TxPoolStatus},
+};
+
+/// Geth-compatible txpool rpc implementation.
+pub struct TxPoolClient<C, M> {
+    client: Arc<C>,
+    miner: Arc<M>,
+}
+
+impl<C, M> TxPoolClient<C, M> {
+    /// Creates new TxPoolClient.
+    pub fn new(client: &Arc<C>, miner: &Arc<M>) -> Self {
+        TxPoolClient {
+            client: client.clone(),
+            miner: miner.clone(),
+        }
+    }
+}
+
+/// Summarizes a transaction the way geth does in `txpool_inspect`.
+fn summary(tx: &VerifiedTransaction) -> String {
+    let tx = tx.signed().tx();
+    let to = match tx.action {
+        Action::Create => "contract creation".to_owned(),
+        Action::Call(ref to) => format!("{:#x}", to),
+    };
+    format!(
+        "{}: {} wei + {} gas × {} wei",
+        to, tx.value, tx.gas, tx.gas_price
+    )
+}
+
+impl<C, M> TxPool for TxPoolClient<C, M>
+where
+    C: Nonce + Sync + Send + 'static,
+    M: MinerService + 'static,
+{
+    fn content(&self) -> Result<TxPoolContent<Transaction>> {
+        let content = self.miner.queued_transactions_by_sender(&*self.client);
+        Ok(TxPoolContent::new(content, |tx| {
+            Transaction::from_signed(tx.signed().clone())
+        }))
+    }
+
+    fn inspect(&self) -> Result<TxPoolContent<String>> {
+        let content = self.miner.queued_transactions_by_sender(&*self.client);
+        Ok(TxPoolContent::new(content, summary))
+    }
+
+    fn status(&self) -> Result<TxPoolStatus> {
+        let content = self.miner.queued_transactions_by_sender(&*self.client);
+        Ok(TxPoolStatus::from(&content))
+    }
+}
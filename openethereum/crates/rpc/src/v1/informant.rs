@@ -31,6 +31,7 @@ use std::{
 };
 
 pub use self::parity_runtime::Executor;
+use v1::recorder::{is_read_only, BlockContext, RpcRecorder, TestVector};
 
 const RATE_SECONDS: usize = 10;
 const STATS_SAMPLES: usize = 60;
@@ -189,18 +190,39 @@ impl RpcStats {
 pub trait ActivityNotifier: Send + Sync + 'static {
     /// Activity on RPC interface
     fn active(&self);
+
+    /// Best block to record alongside RPC test vectors.
+    fn block_context(&self) -> Option<BlockContext> {
+        None
+    }
+
+    /// Block with the given number to replay RPC test vectors at.
+    fn block_context_at(&self, _number: u64) -> Option<BlockContext> {
+        None
+    }
 }
 
 /// Stats-counting RPC middleware
 pub struct Middleware<T: ActivityNotifier = ClientNotifier> {
     stats: Arc<RpcStats>,
     notifier: T,
+    recorder: Option<Arc<RpcRecorder>>,
 }
 
 impl<T: ActivityNotifier> Middleware<T> {
     /// Create new Middleware with stats counter and activity notifier.
     pub fn new(stats: Arc<RpcStats>, notifier: T) -> Self {
-        Middleware { stats, notifier }
+        Middleware {
+            stats,
+            notifier,
+            recorder: None,
+        }
+    }
+
+    /// Record all handled requests with the given recorder.
+    pub fn with_recorder(mut self, recorder: Option<Arc<RpcRecorder>>) -> Self {
+        self.recorder = recorder;
+        self
     }
 }
 
@@ -229,12 +251,44 @@ impl<M: core::Metadata, T: ActivityNotifier> core::Middleware<M> for Middleware<
         };
         let stats = self.stats.clone();
 
+        let _profile = if ethcore_db::read_profiler::is_enabled() {
+            let scope = match request {
+                core::Request::Single(core::Call::MethodCall(ref call)) => {
+                    format!("rpc:{}", call.method)
+                }
+                core::Request::Single(core::Call::Notification(ref notification)) => {
+                    format!("rpc:{}", notification.method)
+                }
+                _ => "rpc:batch".to_owned(),
+            };
+            Some(ethcore_db::read_profiler::scope(&scope))
+        } else {
+            None
+        };
+
+        let recording = self.recorder.as_ref().and_then(|recorder| {
+            if !is_read_only(&request) {
+                return None;
+            }
+            let request = ::serde_json::to_string(&request).ok()?;
+            Some((recorder.clone(), request, self.notifier.block_context()))
+        });
+
         let future = process(request, meta).map(move |res| {
             let time = start.elapsed().as_micros();
             if time > 10_000 {
                 debug!(target: "rpc", "[{:?}] Took {}ms", id, time / 1_000);
             }
             stats.add_roundtrip(time);
+            if let Some((recorder, request, block)) = recording {
+                recorder.record(&TestVector {
+                    block,
+                    request,
+                    response: res
+                        .as_ref()
+                        .and_then(|res| ::serde_json::to_string(res).ok()),
+                });
+            }
             res
         });
 
@@ -252,6 +306,33 @@ impl ActivityNotifier for ClientNotifier {
     fn active(&self) {
         self.client.keep_alive()
     }
+
+    fn block_context(&self) -> Option<BlockContext> {
+        use ethcore::client::BlockInfo;
+
+        let header = self.client.best_block_header();
+        Some(BlockContext {
+            number: header.number(),
+            hash: header.hash(),
+            timestamp: header.timestamp(),
+            author: *header.author(),
+            difficulty: *header.difficulty(),
+        })
+    }
+
+    fn block_context_at(&self, number: u64) -> Option<BlockContext> {
+        use ethcore::client::BlockInfo;
+        use types::ids::BlockId;
+
+        let header = self.client.block_header(BlockId::Number(number))?;
+        Some(BlockContext {
+            number: header.number(),
+            hash: header.hash(),
+            timestamp: header.timestamp(),
+            author: header.author(),
+            difficulty: header.difficulty(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -36,20 +36,26 @@ mod impls;
 mod tests;
 mod types;
 
+pub mod batch;
 pub mod extractors;
 pub mod informant;
 pub mod metadata;
+pub mod recorder;
 pub mod traits;
 
 pub use self::{
+    batch::{BatchDispatcher, BatchLimits},
     extractors::{RpcExtractor, WsDispatcher, WsExtractor, WsStats},
-    helpers::{block_import, dispatch, NetworkSettings},
+    helpers::{
+        block_import, dispatch, ChainHealth, ClockDrift, NetworkSettings, OverflowPolicy,
+        RangeBudget, SubscriptionLimits, SubscriptionMetrics,
+    },
     impls::*,
     metadata::Metadata,
     traits::{
         Debug, Eth, EthFilter, EthPubSub, EthSigning, Net, Parity, ParityAccounts,
         ParityAccountsInfo, ParitySet, ParitySetAccounts, ParitySigning, Personal, PubSub, Rpc,
-        SecretStore, Signer, Traces, Web3,
+        SecretStore, Signer, Traces, TxPool, Web3,
     },
     types::Origin,
 };
@@ -0,0 +1,469 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC test vector recorder and replay verifier.
+//!
+//! In recording mode every request handled by the RPC middleware is appended to a fixture file,
+//! one JSON test vector per line, together with its response and the best block at the time of
+//! the call. Replaying the fixture against a node which imported the same chain verifies that the
+//! responses are byte-identical, e.g. after an upgrade.
+//!
+//! Requests at the latest block are replayed at the recorded block instead, so they execute with
+//! the recorded timestamp, author and difficulty even if the node has imported further blocks.
+//! Test vectors whose recorded block the node does not know, or knows with a different header,
+//! are reported as mismatches without being replayed.
+//!
+//! Only read-only methods are recorded and replayed. Requests which could sign, send or
+//! otherwise change the state of the node, and their parameters, never end up in a fixture.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, Write},
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use ethereum_types::{Address, H256, U256};
+use jsonrpc_core::{Call, MetaIoHandler, Metadata, Middleware, Params, Request, Value};
+use parking_lot::Mutex;
+use serde_json;
+use v1::informant::ActivityNotifier;
+
+/// Prefixes of the methods which may be recorded and replayed.
+const READ_ONLY_METHODS: &[&str] = &[
+    "eth_blockNumber",
+    "eth_call",
+    "eth_chainId",
+    "eth_estimateGas",
+    "eth_feeHistory",
+    "eth_gasPrice",
+    "eth_get",
+    "eth_maxPriorityFeePerGas",
+    "eth_protocolVersion",
+    "eth_syncing",
+    "net_",
+    "trace_",
+    "web3_",
+];
+
+/// Methods with an optional block parameter, and its position.
+const BLOCK_PARAMS: &[(&str, usize)] = &[
+    ("eth_call", 1),
+    ("eth_estimateGas", 1),
+    ("eth_getBalance", 1),
+    ("eth_getCode", 1),
+    ("eth_getStorageAt", 2),
+    ("eth_getTransactionCount", 1),
+    ("trace_call", 2),
+];
+
+/// Whether the method only reads the chain state. Signing methods never are, whatever their
+/// namespace.
+pub fn is_read_only_method(method: &str) -> bool {
+    !method.to_lowercase().contains("sign")
+        && READ_ONLY_METHODS
+            .iter()
+            .any(|prefix| method.starts_with(prefix))
+}
+
+/// Whether all calls of the request are to read-only methods.
+pub fn is_read_only(request: &Request) -> bool {
+    fn call_is_read_only(call: &Call) -> bool {
+        match *call {
+            Call::MethodCall(ref call) => is_read_only_method(&call.method),
+            Call::Notification(ref notification) => is_read_only_method(&notification.method),
+            Call::Invalid { .. } => false,
+        }
+    }
+
+    match *request {
+        Request::Single(ref call) => call_is_read_only(call),
+        Request::Batch(ref calls) => calls.iter().all(call_is_read_only),
+    }
+}
+
+/// Best block at the time a request was handled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockContext {
+    /// Best block number.
+    pub number: u64,
+    /// Best block hash.
+    pub hash: H256,
+    /// Best block timestamp.
+    pub timestamp: u64,
+    /// Best block author.
+    pub author: Address,
+    /// Best block difficulty.
+    pub difficulty: U256,
+}
+
+/// Recorded request and response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestVector {
+    /// Best block when the request was handled.
+    pub block: Option<BlockContext>,
+    /// Serialized request.
+    pub request: String,
+    /// Serialized response, `None` for notifications.
+    pub response: Option<String>,
+}
+
+/// Appends handled requests to a fixture file.
+pub struct RpcRecorder {
+    file: Mutex<File>,
+    recorded: AtomicUsize,
+}
+
+impl RpcRecorder {
+    /// Create a recorder appending to the file at the given path.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(RpcRecorder {
+            file: Mutex::new(file),
+            recorded: AtomicUsize::new(0),
+        })
+    }
+
+    /// Append a test vector to the fixture.
+    pub fn record(&self, vector: &TestVector) {
+        let line = match serde_json::to_string(vector) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!(target: "rpc", "Unable to serialize RPC test vector: {}", e);
+                return;
+            }
+        };
+        let mut file = self.file.lock();
+        match writeln!(file, "{}", line) {
+            Ok(_) => {
+                self.recorded.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => warn!(target: "rpc", "Unable to write RPC test vector: {}", e),
+        }
+    }
+
+    /// Number of test vectors recorded so far.
+    pub fn recorded(&self) -> usize {
+        self.recorded.load(Ordering::Relaxed)
+    }
+}
+
+/// Replayed request whose response differs from the recorded one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayMismatch {
+    /// Fixture line of the test vector.
+    pub line: usize,
+    /// Best block when the request was recorded.
+    pub block: Option<BlockContext>,
+    /// The node's block at the recorded number. The request is not replayed if it differs from
+    /// the recorded one.
+    pub replayed_block: Option<BlockContext>,
+    /// Serialized request.
+    pub request: String,
+    /// Recorded response.
+    pub expected: Option<String>,
+    /// Replayed response, `None` if the request was not replayed.
+    pub actual: Option<String>,
+}
+
+/// Result of a fixture replay.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayReport {
+    /// Number of replayed test vectors.
+    pub replayed: usize,
+    /// Number of test vectors skipped because they are not read-only.
+    pub skipped: usize,
+    /// Test vectors with a different response.
+    pub mismatches: Vec<ReplayMismatch>,
+}
+
+impl ReplayReport {
+    /// Whether all responses matched.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Pin the block parameter of a call at the latest block to the given block.
+fn pin_block(call: &mut Call, number: u64) {
+    let call = match *call {
+        Call::MethodCall(ref mut call) => call,
+        _ => return,
+    };
+    let position = match BLOCK_PARAMS
+        .iter()
+        .find(|&&(method, _)| method == call.method)
+    {
+        Some(&(_, position)) => position,
+        None => return,
+    };
+    let params = match call.params {
+        Params::Array(ref mut params) => params,
+        _ => return,
+    };
+    let pinned = Value::String(format!("{:#x}", number));
+    if params.len() == position {
+        params.push(pinned);
+    } else if params.get(position) == Some(&Value::String("latest".into())) {
+        params[position] = pinned;
+    }
+}
+
+/// Replay the request at the recorded block.
+fn pin_request(request: &str, number: u64) -> Option<String> {
+    let mut request: Request = serde_json::from_str(request).ok()?;
+    match request {
+        Request::Single(ref mut call) => pin_block(call, number),
+        Request::Batch(ref mut calls) => calls.iter_mut().for_each(|call| pin_block(call, number)),
+    }
+    serde_json::to_string(&request).ok()
+}
+
+/// Replay all test vectors of a fixture against the handler and compare the responses. The
+/// notifier provides the node's blocks to check the recorded ones against.
+pub fn replay<R, T, S, A>(
+    fixture: R,
+    handler: &MetaIoHandler<T, S>,
+    meta: T,
+    notifier: &A,
+) -> Result<ReplayReport, String>
+where
+    R: BufRead,
+    T: Metadata,
+    S: Middleware<T>,
+    A: ActivityNotifier,
+{
+    let mut report = ReplayReport::default();
+    for (index, line) in fixture.lines().enumerate() {
+        let line = line.map_err(|e| format!("Unable to read RPC fixture: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let vector: TestVector = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid RPC test vector at line {}: {}", index + 1, e))?;
+        let read_only = serde_json::from_str::<Request>(&vector.request)
+            .map(|request| is_read_only(&request))
+            .unwrap_or(false);
+        if !read_only {
+            report.skipped += 1;
+            continue;
+        }
+        let replayed_block = vector.block.clone();
+        let request = match vector.block.clone() {
+            Some(block) => {
+                let replayed_block = notifier.block_context_at(block.number);
+                if replayed_block.as_ref() != Some(&block) {
+                    report.mismatches.push(ReplayMismatch {
+                        line: index + 1,
+                        block: Some(block),
+                        replayed_block,
+                        request: vector.request,
+                        expected: vector.response,
+                        actual: None,
+                    });
+                    continue;
+                }
+                pin_request(&vector.request, block.number).unwrap_or_else(|| vector.request.clone())
+            }
+            None => vector.request.clone(),
+        };
+        let actual = handler.handle_request_sync(&request, meta.clone());
+        report.replayed += 1;
+        if actual != vector.response {
+            report.mismatches.push(ReplayMismatch {
+                line: index + 1,
+                block: vector.block,
+                replayed_block,
+                request: vector.request,
+                expected: vector.response,
+                actual,
+            });
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_read_only_method, replay, BlockContext, RpcRecorder, TestVector};
+    use ethereum_types::{Address, H256};
+    use jsonrpc_core::{MetaIoHandler, Params, Value};
+    use std::{fs::File, io::BufReader};
+    use tempdir::TempDir;
+    use v1::informant::ActivityNotifier;
+
+    fn block(number: u64) -> BlockContext {
+        BlockContext {
+            number,
+            hash: H256::from_low_u64_be(number),
+            timestamp: 1_000 + number,
+            author: Address::from_low_u64_be(number),
+            difficulty: number.into(),
+        }
+    }
+
+    fn vector(request: &str, response: &str) -> TestVector {
+        TestVector {
+            block: Some(block(5)),
+            request: request.into(),
+            response: Some(response.into()),
+        }
+    }
+
+    /// Node which imported the blocks up to the given number.
+    struct TestNotifier(u64);
+
+    impl ActivityNotifier for TestNotifier {
+        fn active(&self) {}
+
+        fn block_context_at(&self, number: u64) -> Option<BlockContext> {
+            if number <= self.0 {
+                Some(block(number))
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn should_replay_recorded_vectors() {
+        let tempdir = TempDir::new("").unwrap();
+        let path = tempdir.path().join("fixture.jsonl");
+        let recorder = RpcRecorder::new(&path).unwrap();
+        let request = r#"{"jsonrpc":"2.0","method":"web3_hello","params":[],"id":1}"#;
+        recorder.record(&vector(
+            request,
+            r#"{"jsonrpc":"2.0","result":"world","id":1}"#,
+        ));
+        recorder.record(&vector(
+            request,
+            r#"{"jsonrpc":"2.0","result":"earth","id":1}"#,
+        ));
+        assert_eq!(recorder.recorded(), 2);
+
+        let mut io = MetaIoHandler::<()>::default();
+        io.add_method("web3_hello", |_: Params| Ok(Value::String("world".into())));
+
+        let report = replay(
+            BufReader::new(File::open(&path).unwrap()),
+            &io,
+            (),
+            &TestNotifier(5),
+        )
+        .unwrap();
+        assert_eq!(report.replayed, 2);
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].line, 2);
+        assert_eq!(
+            report.mismatches[0].actual,
+            Some(r#"{"jsonrpc":"2.0","result":"world","id":1}"#.into())
+        );
+    }
+
+    #[test]
+    fn should_only_handle_read_only_methods() {
+        assert!(is_read_only_method("eth_getBalance"));
+        assert!(is_read_only_method("eth_call"));
+        assert!(is_read_only_method("trace_block"));
+        assert!(!is_read_only_method("eth_sendRawTransaction"));
+        assert!(!is_read_only_method("eth_sign"));
+        assert!(!is_read_only_method("eth_signTypedData_v4"));
+        assert!(!is_read_only_method("personal_unlockAccount"));
+        assert!(!is_read_only_method("parity_setAuthor"));
+
+        let tempdir = TempDir::new("").unwrap();
+        let path = tempdir.path().join("fixture.jsonl");
+        let recorder = RpcRecorder::new(&path).unwrap();
+        recorder.record(&vector(
+            r#"{"jsonrpc":"2.0","method":"eth_sendRawTransaction","params":["0x00"],"id":1}"#,
+            r#"{"jsonrpc":"2.0","result":"0x00","id":1}"#,
+        ));
+
+        let mut io = MetaIoHandler::<()>::default();
+        io.add_method("eth_sendRawTransaction", |_: Params| {
+            panic!("must not be replayed")
+        });
+
+        let report = replay(
+            BufReader::new(File::open(&path).unwrap()),
+            &io,
+            (),
+            &TestNotifier(5),
+        )
+        .unwrap();
+        assert_eq!(report.replayed, 0);
+        assert_eq!(report.skipped, 1);
+    }
+
+    #[test]
+    fn should_replay_at_recorded_block() {
+        let tempdir = TempDir::new("").unwrap();
+        let path = tempdir.path().join("fixture.jsonl");
+        let recorder = RpcRecorder::new(&path).unwrap();
+        recorder.record(&vector(
+            r#"{"jsonrpc":"2.0","method":"eth_getBalance","params":["0x0000000000000000000000000000000000000001"],"id":1}"#,
+            r#"{"jsonrpc":"2.0","result":"0x5","id":1}"#,
+        ));
+        recorder.record(&vector(
+            r#"{"jsonrpc":"2.0","method":"eth_getBalance","params":["0x0000000000000000000000000000000000000001","latest"],"id":2}"#,
+            r#"{"jsonrpc":"2.0","result":"0x5","id":2}"#,
+        ));
+
+        let mut io = MetaIoHandler::<()>::default();
+        io.add_method("eth_getBalance", |params: Params| {
+            let (_, block): (Value, String) = params.parse()?;
+            Ok(Value::String(block))
+        });
+
+        // the node imported further blocks since the vectors were recorded
+        let report = replay(
+            BufReader::new(File::open(&path).unwrap()),
+            &io,
+            (),
+            &TestNotifier(10),
+        )
+        .unwrap();
+        assert_eq!(report.replayed, 2);
+        assert!(report.is_ok(), "{:?}", report.mismatches);
+    }
+
+    #[test]
+    fn should_not_replay_at_unknown_block() {
+        let tempdir = TempDir::new("").unwrap();
+        let path = tempdir.path().join("fixture.jsonl");
+        let recorder = RpcRecorder::new(&path).unwrap();
+        recorder.record(&vector(
+            r#"{"jsonrpc":"2.0","method":"web3_hello","params":[],"id":1}"#,
+            r#"{"jsonrpc":"2.0","result":"world","id":1}"#,
+        ));
+
+        let mut io = MetaIoHandler::<()>::default();
+        io.add_method("web3_hello", |_: Params| Ok(Value::String("world".into())));
+
+        let report = replay(
+            BufReader::new(File::open(&path).unwrap()),
+            &io,
+            (),
+            &TestNotifier(4),
+        )
+        .unwrap();
+        assert_eq!(report.replayed, 0);
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].replayed_block, None);
+        assert_eq!(report.mismatches[0].actual, None);
+    }
+}
@@ -147,6 +147,7 @@ impl EthTester {
                 allow_experimental_rpcs: true,
                 allow_missing_blocks: false,
                 no_ancient_blocks: false,
+                range_budget: Default::default(),
             },
         );
 
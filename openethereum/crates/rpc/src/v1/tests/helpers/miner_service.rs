@@ -19,23 +19,27 @@
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     sync::Arc,
+    time::Duration,
 };
 
 use bytes::Bytes;
 use ethcore::{
     block::SealedBlock,
     client::{
-        test_client::TestState, traits::ForceUpdateSealing, EngineInfo, Nonce, PrepareOpenBlock,
-        StateClient,
+        test_client::TestState, traits::ForceUpdateSealing, ChainInfo, EngineInfo, Nonce,
+        PrepareOpenBlock, StateClient,
     },
     engines::{signer::EngineSigner, EthEngine},
     error::Error,
-    miner::{self, AuthoringParams, MinerService, TransactionFilter},
+    miner::{
+        self, AuthoringParams, BlockFees, Bundle, BundleError, MinerService, TransactionFilter,
+    },
 };
 use ethereum_types::{Address, H256, U256};
 use miner::pool::{
-    local_transactions::Status as LocalTransactionStatus, verifier, QueueStatus,
-    VerifiedTransaction,
+    alt_mempool::{AltMempool, Capacity, Reservation, ReservationError},
+    local_transactions::Status as LocalTransactionStatus,
+    verifier, QueueStatus, SenderNonceState, SenderTransactions, VerifiedTransaction,
 };
 use parking_lot::{Mutex, RwLock};
 use types::{
@@ -63,6 +67,12 @@ pub struct TestMinerService {
     pub min_gas_price: RwLock<Option<U256>>,
     /// Signer (if any)
     pub signer: RwLock<Option<Box<dyn EngineSigner>>>,
+    /// Scheduled signer rotation (if any)
+    pub signer_rotation: RwLock<Option<(Box<dyn EngineSigner>, BlockNumber)>>,
+    /// Submitted bundles
+    pub bundles: Mutex<Vec<Bundle>>,
+    /// Alt-mempool lane, always empty
+    pub alt_mempool: Mutex<AltMempool>,
 
     authoring_params: RwLock<AuthoringParams>,
 }
@@ -78,10 +88,14 @@ impl Default for TestMinerService {
             min_gas_price: RwLock::new(Some(0.into())),
             authoring_params: RwLock::new(AuthoringParams {
                 author: Address::zero(),
+                fee_recipient: None,
                 gas_range_target: (12345.into(), 54321.into()),
                 extra_data: vec![1, 2, 3, 4],
             }),
             signer: RwLock::new(None),
+            signer_rotation: RwLock::new(None),
+            bundles: Default::default(),
+            alt_mempool: Default::default(),
         }
     }
 }
@@ -146,6 +160,30 @@ impl MinerService for TestMinerService {
         }
     }
 
+    fn schedule_signer_rotation(
+        &self,
+        signer: Box<dyn EngineSigner>,
+        handover_block: BlockNumber,
+    ) -> Result<(), &str> {
+        *self.signer_rotation.write() = Some((signer, handover_block));
+        Ok(())
+    }
+
+    fn cancel_signer_rotation(&self) -> bool {
+        self.signer_rotation.write().take().is_some()
+    }
+
+    fn signer_rotation(&self) -> Option<(Address, BlockNumber)> {
+        self.signer_rotation
+            .read()
+            .as_ref()
+            .map(|(signer, block)| (signer.address(), *block))
+    }
+
+    fn set_fee_recipient(&self, recipient: Option<Address>) {
+        self.authoring_params.write().fee_recipient = recipient;
+    }
+
     fn set_extra_data(&self, extra_data: Bytes) {
         self.authoring_params.write().extra_data = extra_data;
     }
@@ -177,6 +215,13 @@ impl MinerService for TestMinerService {
         transactions.iter().map(|_| Ok(())).collect()
     }
 
+    fn submit_bundle<C: ChainInfo>(&self, chain: &C, bundle: Bundle) -> Result<H256, BundleError> {
+        bundle.verify(chain.chain_info().best_block_number)?;
+        let hash = bundle.hash();
+        self.bundles.lock().push(bundle);
+        Ok(hash)
+    }
+
     /// Imports transactions to transaction queue.
     fn import_own_transaction<C: Nonce + Sync>(
         &self,
@@ -223,6 +268,15 @@ impl MinerService for TestMinerService {
         unimplemented!();
     }
 
+    fn prepare_proposal<C>(
+        &self,
+        _chain: &C,
+        _author: Address,
+        _transactions: Vec<SignedTransaction>,
+    ) -> Result<miner::ProposalPreview, Error> {
+        unimplemented!();
+    }
+
     fn work_package<C: PrepareOpenBlock>(
         &self,
         chain: &C,
@@ -323,6 +377,48 @@ impl MinerService for TestMinerService {
             .unwrap_or_default()
     }
 
+    fn sender_nonce_state<C: Nonce + Sync>(
+        &self,
+        chain: &C,
+        address: &Address,
+    ) -> SenderNonceState {
+        let mut transactions = self
+            .pending_transactions
+            .lock()
+            .iter()
+            .filter(|&(_, tx)| tx.sender() == *address)
+            .map(|(hash, tx)| (tx.tx().nonce, *hash))
+            .collect::<Vec<_>>();
+        transactions.sort();
+        SenderNonceState::new(chain.latest_nonce(address), transactions)
+    }
+
+    fn queued_transactions_by_sender<C: Nonce + Sync>(
+        &self,
+        chain: &C,
+    ) -> BTreeMap<Address, SenderTransactions> {
+        let mut by_sender = BTreeMap::new();
+        for tx in self.pending_transactions.lock().values() {
+            by_sender
+                .entry(tx.sender())
+                .or_insert_with(Vec::new)
+                .push(tx.clone());
+        }
+        by_sender
+            .into_iter()
+            .map(|(sender, mut transactions)| {
+                transactions.sort_by_key(|tx| tx.tx().nonce);
+                let transactions = transactions
+                    .into_iter()
+                    .map(|tx| Arc::new(VerifiedTransaction::from_pending_block_transaction(tx)));
+                (
+                    sender,
+                    SenderTransactions::new(chain.latest_nonce(&sender), transactions),
+                )
+            })
+            .collect()
+    }
+
     fn is_currently_sealing(&self) -> bool {
         false
     }
@@ -336,6 +432,10 @@ impl MinerService for TestMinerService {
                 no_early_reject: false,
                 block_base_fee: None,
                 allow_non_eoa_sender: false,
+                max_gas_per_sender: None,
+                replacement_bump: Default::default(),
+                prioritized_senders: Default::default(),
+                sender_premium: None,
             },
             status: txpool::LightStatus {
                 mem_usage: 1_000,
@@ -347,9 +447,25 @@ impl MinerService for TestMinerService {
                 max_per_sender: 16,
                 max_mem_usage: 5_000,
             },
+            expired_transactions: 0,
+            backwards_clock_jumps: 0,
+            churn: Default::default(),
         }
     }
 
+    fn alt_mempool_capacity(&self) -> Capacity {
+        self.alt_mempool.lock().capacity(0, 0.into())
+    }
+
+    fn reserve_alt_mempool(
+        &self,
+        bundler: Address,
+        slots: usize,
+        ttl: Duration,
+    ) -> Result<Reservation, ReservationError> {
+        self.alt_mempool.lock().reserve(bundler, slots, ttl, 0)
+    }
+
     /// Submit `seal` as a valid solution for the header of `pow_hash`.
     /// Will check the seal, but not actually insert the block into the chain.
     fn submit_seal(&self, _pow_hash: H256, _seal: Vec<Bytes>) -> Result<SealedBlock, Error> {
@@ -364,6 +480,10 @@ impl MinerService for TestMinerService {
         2_000_000_000u64.into()
     }
 
+    fn block_fees(&self, _number: BlockNumber) -> Option<BlockFees> {
+        None
+    }
+
     fn sensible_gas_limit(&self) -> U256 {
         0x5208.into()
     }
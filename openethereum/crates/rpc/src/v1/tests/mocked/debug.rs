@@ -16,13 +16,47 @@
 
 use std::sync::Arc;
 
-use ethcore::client::TestBlockChainClient;
+use ethcore::{
+    client::{Executed, TestBlockChainClient},
+    trace::trace::{Action, Call, CallResult, Res},
+    trace::FlatTrace,
+};
+use ethereum_types::Address;
+use vm::CallType;
 
 use jsonrpc_core::IoHandler;
 use v1::{Debug, DebugClient};
 
 fn io() -> IoHandler {
     let client = Arc::new(TestBlockChainClient::new());
+    client.set_execution_result(Ok(Executed {
+        exception: None,
+        gas: 20_000.into(),
+        gas_used: 10_000.into(),
+        refunded: 0.into(),
+        cumulative_gas_used: 10_000.into(),
+        logs: vec![],
+        contracts_created: vec![],
+        output: vec![1, 2, 3],
+        trace: vec![FlatTrace {
+            action: Action::Call(Call {
+                from: Address::from_low_u64_be(0xf),
+                to: Address::from_low_u64_be(0x10),
+                value: 0x1.into(),
+                gas: 0x100.into(),
+                input: vec![1, 2, 3],
+                call_type: CallType::Call,
+            }),
+            result: Res::Call(CallResult {
+                gas_used: 0x10.into(),
+                output: vec![4],
+            }),
+            subtraces: 0,
+            trace_address: vec![],
+        }],
+        vm_trace: None,
+        state_diff: None,
+    }));
 
     let mut io = IoHandler::new();
     io.extend_with(DebugClient::new(client).to_delegate());
@@ -32,6 +66,21 @@ fn io() -> IoHandler {
 #[test]
 fn rpc_debug_get_bad_blocks() {
     let request = r#"{"jsonrpc": "2.0", "method": "debug_getBadBlocks", "params": [], "id": 1}"#;
-    let response = "{\"jsonrpc\":\"2.0\",\"result\":[{\"author\":\"0x0000000000000000000000000000000000000000\",\"difficulty\":\"0x0\",\"extraData\":\"0x\",\"gasLimit\":\"0x0\",\"gasUsed\":\"0x0\",\"hash\":\"0x27bfb37e507ce90da141307204b1c6ba24194380613590ac50ca4b1d7198ff65\",\"logsBloom\":\"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\",\"miner\":\"0x0000000000000000000000000000000000000000\",\"number\":\"0x0\",\"parentHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"reason\":\"Invalid block\",\"receiptsRoot\":\"0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421\",\"rlp\":\"\\\"0x010203\\\"\",\"sealFields\":[],\"sha3Uncles\":\"0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347\",\"size\":\"0x3\",\"stateRoot\":\"0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421\",\"timestamp\":\"0x0\",\"totalDifficulty\":null,\"transactions\":[],\"transactionsRoot\":\"0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421\",\"uncles\":[]}],\"id\":1}";
+    let response = "{\"jsonrpc\":\"2.0\",\"result\":[{\"author\":\"0x0000000000000000000000000000000000000000\",\"difficulty\":\"0x0\",\"extraData\":\"0x\",\"gasLimit\":\"0x0\",\"gasUsed\":\"0x0\",\"hash\":\"0x27bfb37e507ce90da141307204b1c6ba24194380613590ac50ca4b1d7198ff65\",\"logsBloom\":\"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\",\"miner\":\"0x0000000000000000000000000000000000000000\",\"number\":\"0x0\",\"parentHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"reason\":\"Invalid block\",\"receiptsRoot\":\"0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421\",\"reportedAt\":\"0x0\",\"rlp\":\"\\\"0x010203\\\"\",\"sealFields\":[],\"sha3Uncles\":\"0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347\",\"size\":\"0x3\",\"stateRoot\":\"0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421\",\"timestamp\":\"0x0\",\"totalDifficulty\":null,\"transactions\":[],\"transactionsRoot\":\"0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421\",\"uncles\":[]}],\"id\":1}";
+    assert_eq!(io().handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_debug_trace_transaction() {
+    let request = r#"{"jsonrpc": "2.0", "method": "debug_traceTransaction", "params": ["0x0000000000000000000000000000000000000000000000000000000000000005", {"tracer": "callTracer"}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"type":"CALL","from":"0x000000000000000000000000000000000000000f","to":"0x0000000000000000000000000000000000000010","value":"0x1","gas":"0x100","gasUsed":"0x10","input":"0x010203","output":"0x04"},"id":1}"#;
+    assert_eq!(io().handle_request_sync(request), Some(response.to_owned()));
+
+    let request = r#"{"jsonrpc": "2.0", "method": "debug_traceTransaction", "params": ["0x0000000000000000000000000000000000000000000000000000000000000005", {"tracer": "4byteTracer"}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: tracer","data":"\"Unsupported tracer: 4byteTracer\""},"id":1}"#;
+    assert_eq!(io().handle_request_sync(request), Some(response.to_owned()));
+
+    let request = r#"{"jsonrpc": "2.0", "method": "debug_traceTransaction", "params": ["0x0000000000000000000000000000000000000000000000000000000000000005", {"tracer": "callTracer", "timeout": "1h"}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: timeout","data":"\"Invalid timeout: 1h\""},"id":1}"#;
     assert_eq!(io().handle_request_sync(request), Some(response.to_owned()));
 }
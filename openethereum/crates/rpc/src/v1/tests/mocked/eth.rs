@@ -338,6 +338,26 @@ fn rpc_eth_logs_error() {
     );
 }
 
+#[test]
+fn rpc_eth_expired_history() {
+    let tester = EthTester::default();
+    tester.client.set_earliest_history(10);
+
+    let request =
+        r#"{"jsonrpc": "2.0", "method": "eth_getLogs", "params": [{"fromBlock":"0x1"}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Requested block is older than the history kept by this node; its bodies and receipts have been expired.","data":"Earliest available block: 10"},"id":1}"#;
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_getBlockByNumber", "params": ["0x9", false], "id": 1}"#;
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+}
+
 #[test]
 fn rpc_logs_filter() {
     let tester = EthTester::default();
@@ -987,6 +1007,79 @@ fn rpc_eth_call_default_block() {
     );
 }
 
+#[test]
+fn rpc_eth_call_with_state_override() {
+    let tester = EthTester::default();
+    tester.client.set_execution_result(Ok(Executed {
+        exception: None,
+        gas: U256::zero(),
+        gas_used: U256::from(0xff30),
+        refunded: U256::from(0x5),
+        cumulative_gas_used: U256::zero(),
+        logs: vec![],
+        contracts_created: vec![],
+        output: vec![0x12, 0x34, 0xff],
+        trace: vec![],
+        vm_trace: None,
+        state_diff: None,
+    }));
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_call",
+		"params": [{
+			"from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"data": "0x"
+		},
+		"latest",
+		{
+			"0xd46e8dd67c5d32be8058bb8eb970870f07244567": {
+				"balance": "0x10",
+				"code": "0x6001"
+			}
+		}],
+		"id": 1
+	}"#;
+    let response = r#"{"jsonrpc":"2.0","result":"0x1234ff","id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+    let overrides = tester.client.call_overrides.read();
+    let account =
+        &overrides[&Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap()];
+    assert_eq!(account.balance, Some(U256::from(0x10)));
+    assert_eq!(account.code, Some(vec![0x60, 0x01]));
+    assert_eq!(account.nonce, None);
+}
+
+#[test]
+fn rpc_eth_call_rejects_conflicting_state_override() {
+    let tester = EthTester::default();
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_call",
+		"params": [{
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567"
+		},
+		"latest",
+		{
+			"0xd46e8dd67c5d32be8058bb8eb970870f07244567": {
+				"state": {},
+				"stateDiff": {}
+			}
+		}],
+		"id": 1
+	}"#;
+
+    let response = tester.io.handle_request_sync(request).unwrap();
+    assert!(response.contains(r#""code":-32602"#));
+    assert!(response.contains("stateOverride"));
+}
+
 #[test]
 fn rpc_eth_estimate_gas() {
     let tester = EthTester::default();
@@ -1253,6 +1346,36 @@ fn rpc_eth_transaction_receipt() {
     );
 }
 
+#[test]
+fn rpc_eth_transaction_receipt_not_indexed() {
+    let tester = EthTester::default();
+    tester.client.set_earliest_transaction_index(10);
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_getTransactionReceipt",
+		"params": ["0xb903239f8543d04b5dc1ba6579132b143087c68db1b2168786408fcbce568238"],
+		"id": 1
+	}"#;
+    // unknown transactions are still reported as missing.
+    let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+
+    tester.client.add_unindexed_transaction(
+        H256::from_str("b903239f8543d04b5dc1ba6579132b143087c68db1b2168786408fcbce568238").unwrap(),
+    );
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Transaction is in a block older than the transaction index kept by this node; indexing is disabled for that range.","data":"Earliest indexed block: 10"},"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+}
+
 #[test]
 fn rpc_eth_transaction_receipt_null() {
     let tester = EthTester::default();
@@ -27,13 +27,30 @@ use std::time::Duration;
 use v1::{EthPubSub, EthPubSubClient, Metadata};
 
 use ethcore::client::{
-    ChainNotify, ChainRoute, ChainRouteType, EachBlockWith, NewBlocks, TestBlockChainClient,
+    ChainNotify, ChainRoute, ChainRouteType, EachBlockWith, LocalTransactionEvent, NewBlocks,
+    Reorg, ReorgBlock, TestBlockChainClient,
 };
 use ethereum_types::{Address, H256};
+use miner::pool::VerifiedTransaction;
 use parity_runtime::Runtime;
+use serde_json;
+use types::transaction::{Action, Transaction, TypedTransaction};
 
 const DURATION_ZERO: Duration = Duration::from_millis(0);
 
+fn pending_transaction(nonce: u64) -> Arc<VerifiedTransaction> {
+    let tx = TypedTransaction::Legacy(Transaction {
+        action: Action::Create,
+        value: 0.into(),
+        data: vec![],
+        gas: 21_000.into(),
+        gas_price: 1.into(),
+        nonce: nonce.into(),
+    })
+    .fake_sign(Address::from_low_u64_be(1));
+    Arc::new(VerifiedTransaction::from_pending_block_transaction(tx))
+}
+
 #[test]
 fn should_subscribe_to_new_heads() {
     // given
@@ -231,7 +248,7 @@ fn should_subscribe_to_pending_transactions() {
 
     // Fail if params are provided
     let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["newPendingTransactions", {}], "id": 1}"#;
-    let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: newPendingTransactions","data":"\"Expected no parameters.\""},"id":1}"#;
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: newPendingTransactions","data":"\"Expected no parameters or a transaction filter.\""},"id":1}"#;
     assert_eq!(
         io.handle_request_sync(request, metadata.clone()),
         Some(response.to_owned())
@@ -246,14 +263,165 @@ fn should_subscribe_to_pending_transactions() {
     );
 
     // Send new transactions
-    handler.notify_new_transactions(&[H256::from_low_u64_be(5), H256::from_low_u64_be(7)]);
+    let (tx1, tx2) = (pending_transaction(5), pending_transaction(7));
+    handler.notify_new_transactions(&[tx1.clone(), tx2.clone()]);
 
     let (res, receiver) = receiver.into_future().wait().unwrap();
-    let response = r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"result":"0x0000000000000000000000000000000000000000000000000000000000000005","subscription":"0x43ca64edf03768e1"}}"#;
-    assert_eq!(res, Some(response.into()));
+    let response = format!(
+        r#"{{"jsonrpc":"2.0","method":"eth_subscription","params":{{"result":"{:?}","subscription":"0x43ca64edf03768e1"}}}}"#,
+        tx1.signed().hash()
+    );
+    assert_eq!(res, Some(response));
+
+    let (res, receiver) = receiver.into_future().wait().unwrap();
+    let response = format!(
+        r#"{{"jsonrpc":"2.0","method":"eth_subscription","params":{{"result":"{:?}","subscription":"0x43ca64edf03768e1"}}}}"#,
+        tx2.signed().hash()
+    );
+    assert_eq!(res, Some(response));
+
+    // And unsubscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_unsubscribe", "params": ["0x43ca64edf03768e1"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata),
+        Some(response.to_owned())
+    );
+
+    let (res, _receiver) = receiver.into_future().wait().unwrap();
+    assert_eq!(res, None);
+}
+
+#[test]
+fn should_notify_filtered_pending_transactions() {
+    // given
+    let el = Runtime::with_thread_count(1);
+    let client = TestBlockChainClient::new();
+
+    let pubsub = EthPubSubClient::new_test(Arc::new(client), el.executor());
+    let handler = pubsub.handler().upgrade().unwrap();
+    let pubsub = pubsub.to_delegate();
+
+    let mut io = MetaIoHandler::default();
+    io.extend_with(pubsub);
+
+    let mut metadata = Metadata::default();
+    let (sender, receiver) = futures::sync::mpsc::channel(8);
+    metadata.session = Some(Arc::new(Session::new(sender)));
+
+    // Subscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["newPendingTransactions", {"nonce": {"eq": "0x7"}}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":"0x43ca64edf03768e1","id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata.clone()),
+        Some(response.to_owned())
+    );
+
+    // Send new transactions, only the second one matches
+    let (tx1, tx2) = (pending_transaction(5), pending_transaction(7));
+    handler.notify_new_transactions(&[tx1, tx2.clone()]);
 
     let (res, receiver) = receiver.into_future().wait().unwrap();
-    let response = r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"result":"0x0000000000000000000000000000000000000000000000000000000000000007","subscription":"0x43ca64edf03768e1"}}"#;
+    let response = format!(
+        r#"{{"jsonrpc":"2.0","method":"eth_subscription","params":{{"result":"{:?}","subscription":"0x43ca64edf03768e1"}}}}"#,
+        tx2.signed().hash()
+    );
+    assert_eq!(res, Some(response));
+
+    // And unsubscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_unsubscribe", "params": ["0x43ca64edf03768e1"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata),
+        Some(response.to_owned())
+    );
+
+    let (res, _receiver) = receiver.into_future().wait().unwrap();
+    assert_eq!(res, None);
+}
+
+#[test]
+fn should_subscribe_to_full_pending_transactions() {
+    // given
+    let el = Runtime::with_thread_count(1);
+    let client = TestBlockChainClient::new();
+
+    let pubsub = EthPubSubClient::new_test(Arc::new(client), el.executor());
+    let handler = pubsub.handler().upgrade().unwrap();
+    let pubsub = pubsub.to_delegate();
+
+    let mut io = MetaIoHandler::default();
+    io.extend_with(pubsub);
+
+    let mut metadata = Metadata::default();
+    let (sender, receiver) = futures::sync::mpsc::channel(8);
+    metadata.session = Some(Arc::new(Session::new(sender)));
+
+    // Subscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["newPendingTransactions", {"includeTransactions": true}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":"0x43ca64edf03768e1","id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata.clone()),
+        Some(response.to_owned())
+    );
+
+    // Send new transaction
+    let tx = pending_transaction(5);
+    handler.notify_new_transactions(&[tx.clone()]);
+
+    let (res, _receiver) = receiver.into_future().wait().unwrap();
+    let res: serde_json::Value = serde_json::from_str(&res.unwrap()).unwrap();
+    let result = &res["params"]["result"];
+    assert_eq!(result["hash"], format!("{:?}", tx.signed().hash()));
+    assert_eq!(result["nonce"], "0x5");
+    assert_eq!(result["from"], format!("{:?}", Address::from_low_u64_be(1)));
+}
+
+#[test]
+fn should_subscribe_to_reorgs() {
+    // given
+    let el = Runtime::with_thread_count(1);
+    let client = TestBlockChainClient::new();
+
+    let pubsub = EthPubSubClient::new_test(Arc::new(client), el.executor());
+    let handler = pubsub.handler().upgrade().unwrap();
+    let pubsub = pubsub.to_delegate();
+
+    let mut io = MetaIoHandler::default();
+    io.extend_with(pubsub);
+
+    let mut metadata = Metadata::default();
+    let (sender, receiver) = futures::sync::mpsc::channel(8);
+    metadata.session = Some(Arc::new(Session::new(sender)));
+
+    // Subscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["reorgs"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":"0x43ca64edf03768e1","id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata.clone()),
+        Some(response.to_owned())
+    );
+
+    // Notify about a reorg
+    handler.chain_reorganized(&Reorg {
+        ancestor: ReorgBlock {
+            number: 1,
+            hash: H256::from_low_u64_be(1),
+        },
+        retracted: vec![ReorgBlock {
+            number: 2,
+            hash: H256::from_low_u64_be(2),
+        }],
+        enacted: vec![ReorgBlock {
+            number: 2,
+            hash: H256::from_low_u64_be(3),
+        }],
+        retracted_transactions: vec![H256::from_low_u64_be(4)],
+        enacted_transactions: vec![],
+    });
+
+    let (res, receiver) = receiver.into_future().wait().unwrap();
+    let response = r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"result":{"ancestor":{"number":"0x1","hash":"0x0000000000000000000000000000000000000000000000000000000000000001"},"retracted":[{"number":"0x2","hash":"0x0000000000000000000000000000000000000000000000000000000000000002"}],"enacted":[{"number":"0x2","hash":"0x0000000000000000000000000000000000000000000000000000000000000003"}],"retractedTransactions":["0x0000000000000000000000000000000000000000000000000000000000000004"],"enactedTransactions":[]},"subscription":"0x43ca64edf03768e1"}}"#;
     assert_eq!(res, Some(response.into()));
 
     // And unsubscribe
@@ -268,6 +436,42 @@ fn should_subscribe_to_pending_transactions() {
     assert_eq!(res, None);
 }
 
+#[test]
+fn should_subscribe_to_local_transactions() {
+    // given
+    let el = Runtime::with_thread_count(1);
+    let client = TestBlockChainClient::new();
+
+    let pubsub = EthPubSubClient::new_test(Arc::new(client), el.executor());
+    let handler = pubsub.handler().upgrade().unwrap();
+    let pubsub = pubsub.to_delegate();
+
+    let mut io = MetaIoHandler::default();
+    io.extend_with(pubsub);
+
+    let mut metadata = Metadata::default();
+    let (sender, receiver) = futures::sync::mpsc::channel(8);
+    metadata.session = Some(Arc::new(Session::new(sender)));
+
+    // Subscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["localTransactions"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":"0x43ca64edf03768e1","id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata.clone()),
+        Some(response.to_owned())
+    );
+
+    // Notify about a requeued transaction
+    handler.local_transactions(&[LocalTransactionEvent::Requeued {
+        hash: H256::from_low_u64_be(1),
+        block_hash: H256::from_low_u64_be(2),
+    }]);
+
+    let (res, _receiver) = receiver.into_future().wait().unwrap();
+    let response = r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"result":{"status":"requeued","hash":"0x0000000000000000000000000000000000000000000000000000000000000001","blockHash":"0x0000000000000000000000000000000000000000000000000000000000000002"},"subscription":"0x43ca64edf03768e1"}}"#;
+    assert_eq!(res, Some(response.into()));
+}
+
 #[test]
 fn should_return_unimplemented() {
     // given
@@ -38,4 +38,5 @@ mod signing;
 #[cfg(any(test, feature = "accounts"))]
 mod signing_unsafe;
 mod traces;
+mod txpool;
 mod web3;
@@ -29,7 +29,7 @@ use types::{
 use super::manage_network::TestManageNetwork;
 use jsonrpc_core::IoHandler;
 use v1::{
-    helpers::{external_signer::SignerService, NetworkSettings},
+    helpers::{external_signer::SignerService, ChainHealth, ClockDrift, NetworkSettings},
     metadata::Metadata,
     tests::helpers::{Config, TestMinerService, TestSyncProvider},
     Parity, ParityClient,
@@ -403,6 +403,35 @@ fn rpc_parity_ws_address() {
     assert_eq!(io2.handle_request_sync(request), Some(response2.to_owned()));
 }
 
+#[test]
+fn rpc_parity_nonce_state() {
+    use types::transaction::{Action, Transaction, TypedTransaction};
+    let deps = Dependencies::new();
+    let io = deps.default_client();
+    let sender = Address::from_low_u64_be(0x50);
+
+    for nonce in [0u64, 2, 3].iter() {
+        let tx = TypedTransaction::Legacy(Transaction {
+            value: 0.into(),
+            gas: 21_000.into(),
+            gas_price: 1.into(),
+            nonce: (*nonce).into(),
+            action: Action::Create,
+            data: vec![],
+        })
+        .fake_sign(sender);
+        deps.miner
+            .pending_transactions
+            .lock()
+            .insert(H256::from_low_u64_be(nonce + 0x60), tx);
+    }
+
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_nonceState", "params": ["0x0000000000000000000000000000000000000050"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"chainNonce":"0x0","gaps":[{"blocked":["0x0000000000000000000000000000000000000000000000000000000000000062","0x0000000000000000000000000000000000000000000000000000000000000063"],"end":"0x2","start":"0x1"}],"readyNonce":"0x1"},"id":1}"#;
+
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_next_nonce() {
     let deps = Dependencies::new();
@@ -493,6 +522,21 @@ fn rpc_parity_chain_status() {
     assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_backup_status() {
+    let deps = Dependencies::new();
+    let io = deps.default_client();
+
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_backupStatus", "params":[], "id": 1}"#;
+    let idle =
+        r#"{"jsonrpc":"2.0","result":{"state":"idle","path":null,"copied":0,"error":null},"id":1}"#;
+    assert_eq!(io.handle_request_sync(request), Some(idle.to_owned()));
+
+    deps.client.backups.write().push("/tmp/backup".into());
+    let done = r#"{"jsonrpc":"2.0","result":{"state":"done","path":"/tmp/backup","copied":0,"error":null},"id":1}"#;
+    assert_eq!(io.handle_request_sync(request), Some(done.to_owned()));
+}
+
 #[test]
 fn rpc_parity_node_kind() {
     let deps = Dependencies::new();
@@ -627,6 +671,51 @@ fn rpc_status_error_sync() {
     assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_node_health() {
+    let deps = Dependencies::new();
+    let io = deps.default_client();
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "parity_nodeHealth",
+		"params": [],
+		"id": 1
+	}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"clockDriftMillis":null,"healthy":true,"peers":120,"reorgDepth":0,"secondsSinceLastImport":null,"syncing":false},"id":1}"#;
+
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_node_health_syncing_with_clock_drift() {
+    struct Clock;
+    impl ClockDrift for Clock {
+        fn offset_millis(&self) -> Option<i64> {
+            Some(1500)
+        }
+    }
+
+    let deps = Dependencies::new();
+    deps.sync.status.write().state = ::sync::SyncState::Blocks;
+    let mut io = IoHandler::default();
+    io.extend_with(
+        deps.client(None)
+            .with_health(Arc::new(ChainHealth::new(Some(Arc::new(Clock)))))
+            .to_delegate(),
+    );
+
+    let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "parity_nodeHealth",
+		"params": [],
+		"id": 1
+	}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"clockDriftMillis":1500,"healthy":false,"peers":120,"reorgDepth":0,"secondsSinceLastImport":null,"syncing":true},"id":1}"#;
+
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_verify_signature() {
     let deps = Dependencies::new();
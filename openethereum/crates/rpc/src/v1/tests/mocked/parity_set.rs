@@ -161,6 +161,51 @@ fn rpc_parity_set_transactions_limit() {
     assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_set_cache_sizes() {
+    use ethcore::client::CacheSizes;
+
+    let miner = miner_service();
+    let client = client_service();
+    let network = network_service();
+
+    let mut io = IoHandler::new();
+    io.extend_with(parity_set_client(&client, &miner, &network).to_delegate());
+
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_setCacheSizes", "params":[{"state":64,"traces":8}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+    assert_eq!(
+        *client.cache_sizes.read(),
+        CacheSizes {
+            state: Some(64 * 1024 * 1024),
+            blockchain: None,
+            traces: Some(8 * 1024 * 1024),
+        }
+    );
+}
+
+#[test]
+fn rpc_parity_backup() {
+    let miner = miner_service();
+    let client = client_service();
+    let network = network_service();
+
+    let mut io = IoHandler::new();
+    io.extend_with(parity_set_client(&client, &miner, &network).to_delegate());
+
+    let request =
+        r#"{"jsonrpc": "2.0", "method": "parity_backup", "params":["/tmp/backup"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+    assert_eq!(
+        *client.backups.read(),
+        vec![::std::path::PathBuf::from("/tmp/backup")]
+    );
+}
+
 #[test]
 fn rpc_parity_set_hash_content() {
     let miner = miner_service();
@@ -208,6 +253,55 @@ fn rpc_parity_remove_transaction() {
     assert_eq!(io.handle_request_sync(&request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_send_bundle() {
+    use crypto::publickey::{Generator, Random};
+    use ethcore::miner::Bundle;
+    use rustc_hex::ToHex;
+    use types::transaction::{Action, Transaction, TypedTransaction};
+
+    let miner = miner_service();
+    let client = client_service();
+    let network = network_service();
+
+    let mut io = IoHandler::new();
+    io.extend_with(parity_set_client(&client, &miner, &network).to_delegate());
+
+    let keypair = Random.generate();
+    let signed = TypedTransaction::Legacy(Transaction {
+        nonce: 0.into(),
+        gas_price: 1.into(),
+        gas: 21_000.into(),
+        action: Action::Call(Address::from_low_u64_be(5)),
+        value: 1.into(),
+        data: vec![],
+    })
+    .sign(keypair.secret(), None);
+    let raw: String = signed.encode().to_hex();
+    let raw = format!("0x{}", raw);
+    let hash = Bundle {
+        transactions: vec![signed],
+        block_number: 1,
+    }
+    .hash();
+
+    let request = format!(
+        r#"{{"jsonrpc": "2.0", "method": "parity_sendBundle", "params":[["{}"], "0x1"], "id": 1}}"#,
+        raw
+    );
+    let response = format!(r#"{{"jsonrpc":"2.0","result":"0x{:x}","id":1}}"#, hash);
+    assert_eq!(io.handle_request_sync(&request), Some(response));
+    assert_eq!(miner.bundles.lock().len(), 1);
+
+    // the best block can't be targeted anymore
+    let request = format!(
+        r#"{{"jsonrpc": "2.0", "method": "parity_sendBundle", "params":[["{}"], "0x0"], "id": 1}}"#,
+        raw
+    );
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32040,"message":"Bundle rejected: Bundle targets block 0 but the best block is already 0"},"id":1}"#;
+    assert_eq!(io.handle_request_sync(&request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_set_engine_signer() {
     use accounts::AccountProvider;
@@ -241,3 +335,115 @@ fn rpc_parity_set_engine_signer() {
         .to_vec();
     assert_eq!(&format!("{}", signature.pretty()), "6f46069ded2154af6e806706e4f7f6fd310ac45f3c6dccb85f11c0059ee20a09245df0a0008bb84a10882b1298284bc93058e7bc5938ea728e77620061687a6401");
 }
+
+#[test]
+fn rpc_parity_schedule_engine_signer_rotation() {
+    let miner = miner_service();
+    let client = client_service();
+    let network = network_service();
+
+    let mut io = IoHandler::new();
+    io.extend_with(parity_set_client(&client, &miner, &network).to_delegate());
+
+    // handover block has to be in the future
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_scheduleEngineSignerRotation", "params":["0xc85ef7d79691fe79573b1a7064c19c1a9819ebdbd1faaab1a8ec92344438aaf4", "0x0"], "id": 1}"#;
+    assert!(io.handle_request_sync(request).unwrap().contains("error"));
+    assert_eq!(miner.signer_rotation(), None);
+
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_scheduleEngineSignerRotation", "params":["0xc85ef7d79691fe79573b1a7064c19c1a9819ebdbd1faaab1a8ec92344438aaf4", "0x5"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+    assert_eq!(
+        miner.signer_rotation(),
+        Some((
+            Address::from_str("cd2a3d9f938e13cd947ec05abc7fe734df8dd826").unwrap(),
+            5
+        ))
+    );
+
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_cancelEngineSignerRotation", "params":[], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+    assert_eq!(miner.signer_rotation(), None);
+}
+
+#[test]
+fn rpc_parity_rebroadcast_journaled_transactions() {
+    use local_store::{Error, TransactionJournal};
+    use types::transaction::{Action, PendingTransaction, Transaction, TypedTransaction};
+
+    struct TestJournal(Vec<PendingTransaction>);
+    impl TransactionJournal for TestJournal {
+        fn journaled_transactions(&self) -> Result<Vec<PendingTransaction>, Error> {
+            Ok(self.0.clone())
+        }
+    }
+
+    let miner = miner_service();
+    let client = client_service();
+    let network = network_service();
+
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_rebroadcastJournaledTransactions", "params":[], "id": 1}"#;
+
+    let mut io = IoHandler::new();
+    io.extend_with(parity_set_client(&client, &miner, &network).to_delegate());
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Local transaction journal is not available."},"id":1}"#;
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+    let signed = TypedTransaction::Legacy(Transaction {
+        nonce: 1.into(),
+        gas_price: 0x9184e72a000u64.into(),
+        gas: 0x76c0.into(),
+        action: Action::Call(Address::from_low_u64_be(5)),
+        value: 0x9184e72au64.into(),
+        data: vec![],
+    })
+    .fake_sign(Address::from_low_u64_be(2));
+    let journal = Arc::new(TestJournal(vec![PendingTransaction::new(
+        signed.clone(),
+        None,
+    )]));
+
+    let mut io = IoHandler::new();
+    io.extend_with(
+        parity_set_client(&client, &miner, &network)
+            .with_transaction_journal(journal)
+            .to_delegate(),
+    );
+    let response = format!(
+        r#"{{"jsonrpc":"2.0","result":["{:#x}"],"id":1}}"#,
+        signed.hash()
+    );
+    assert_eq!(io.handle_request_sync(request), Some(response));
+    assert_eq!(miner.imported_transactions.lock().clone(), vec![signed]);
+}
+
+#[test]
+fn rpc_parity_reserve_alt_mempool() {
+    use miner::pool::alt_mempool::{AltMempool, Options};
+
+    let miner = miner_service();
+    let client = client_service();
+    let network = network_service();
+
+    let mut io = IoHandler::new();
+    io.extend_with(parity_set_client(&client, &miner, &network).to_delegate());
+
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_reserveAltMempool", "params":["0x0000000000000000000000000000000000000001", "0x3", "0x3c"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32040,"message":"Reservation rejected: Alt-mempool lane is disabled"},"id":1}"#;
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+    *miner.alt_mempool.lock() = AltMempool::new(Options {
+        entry_points: vec![Address::from_low_u64_be(0x4337)].into_iter().collect(),
+        max_count: 4,
+        max_gas: None,
+    });
+    let response = io.handle_request_sync(request).unwrap();
+    assert!(response.contains(r#""bundler":"0x0000000000000000000000000000000000000001""#));
+    assert!(response.contains(r#""slots":"0x3""#));
+
+    let request =
+        r#"{"jsonrpc": "2.0", "method": "parity_altMempoolCapacity", "params":[], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"available":"0x1","count":"0x0","gas":"0x0","maxCount":"0x4","maxGas":null,"reserved":"0x3"},"id":1}"#;
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
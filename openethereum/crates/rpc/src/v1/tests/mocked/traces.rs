@@ -17,7 +17,7 @@
 use std::sync::Arc;
 
 use ethcore::{
-    client::TestBlockChainClient,
+    client::{ReplayOptions, TestBlockChainClient},
     executed::{CallError, Executed},
     trace::{
         trace::{Action, Call, Res},
@@ -29,7 +29,8 @@ use ethereum_types::{Address, H256};
 use vm::CallType;
 
 use jsonrpc_core::IoHandler;
-use v1::{tests::helpers::TestMinerService, Metadata, Traces, TracesClient};
+use serde_json::{self, Value};
+use v1::{helpers::RangeBudget, tests::helpers::TestMinerService, Metadata, Traces, TracesClient};
 
 struct Tester {
     client: Arc<TestBlockChainClient>,
@@ -81,6 +82,119 @@ fn io() -> Tester {
     }
 }
 
+fn trace_in_block(number: u64) -> LocalizedTrace {
+    LocalizedTrace {
+        action: Action::Call(Call {
+            from: Address::from_low_u64_be(0xf),
+            to: Address::from_low_u64_be(0x10),
+            value: 0x1.into(),
+            gas: 0x100.into(),
+            input: vec![],
+            call_type: CallType::Call,
+        }),
+        result: Res::None,
+        subtraces: 0,
+        trace_address: vec![0],
+        transaction_number: Some(0),
+        transaction_hash: Some(H256::from_low_u64_be(number)),
+        block_number: number,
+        block_hash: H256::from_low_u64_be(number),
+    }
+}
+
+/// Traces client over one trace in each of the blocks `0..=9`.
+fn io_with_budget(budget: RangeBudget) -> IoHandler<Metadata> {
+    let client = Arc::new(TestBlockChainClient::new());
+    *client.traces.write() = Some((0..10).map(trace_in_block).collect());
+    let mut io = IoHandler::default();
+    io.extend_with(TracesClient::with_range_budget(&client, budget).to_delegate());
+    io
+}
+
+fn small_chunks() -> RangeBudget {
+    RangeBudget {
+        max_chunk_blocks: 2,
+        max_chunk_cost: 2,
+        ..RangeBudget::default()
+    }
+}
+
+fn filter_response(io: &IoHandler<Metadata>, filter: &str) -> Value {
+    let request = format!(
+        r#"{{"jsonrpc":"2.0","method":"trace_filter","params": [{}],"id":1}}"#,
+        filter
+    );
+    serde_json::from_str(&io.handle_request_sync(&request).unwrap()).unwrap()
+}
+
+fn block_numbers(traces: &Value) -> Vec<u64> {
+    traces
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|trace| trace["blockNumber"].as_u64().unwrap())
+        .collect()
+}
+
+#[test]
+fn rpc_trace_filter_in_sub_ranges() {
+    let io = io_with_budget(small_chunks());
+    let response = filter_response(&io, r#"{"fromBlock":"0x0","toBlock":"0x9"}"#);
+    assert_eq!(
+        block_numbers(&response["result"]),
+        (0..10).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn rpc_trace_filter_pages_across_sub_ranges() {
+    let io = io_with_budget(small_chunks());
+    let response = filter_response(
+        &io,
+        r#"{"fromBlock":"0x0","toBlock":"0x9","after":3,"count":4}"#,
+    );
+    assert_eq!(block_numbers(&response["result"]), vec![3, 4, 5, 6]);
+}
+
+#[test]
+fn rpc_trace_filter_budgets_each_page() {
+    let io = io_with_budget(RangeBudget {
+        max_cost: 4,
+        ..small_chunks()
+    });
+
+    // the first page fits the budget
+    let response = filter_response(
+        &io,
+        r#"{"fromBlock":"0x0","toBlock":"0x9","after":0,"count":3}"#,
+    );
+    assert_eq!(block_numbers(&response["result"]), vec![0, 1, 2]);
+
+    // the second one is cut short after 4 blocks, and continues with the rest of the page
+    let response = filter_response(
+        &io,
+        r#"{"fromBlock":"0x0","toBlock":"0x9","after":3,"count":4}"#,
+    );
+    let data = &response["error"]["data"];
+    assert_eq!(block_numbers(&data["partialResults"]), vec![3]);
+    assert_eq!(data["continuation"]["fromBlock"], "0x4");
+    assert_eq!(data["continuation"]["count"], 3);
+    assert!(data["continuation"].get("after").is_none());
+
+    let response = filter_response(&io, r#"{"fromBlock":"0x4","toBlock":"0x9","count":3}"#);
+    assert_eq!(block_numbers(&response["result"]), vec![4, 5, 6]);
+}
+
+#[test]
+fn rpc_trace_filter_is_unlimited_by_default() {
+    let io = io_with_budget(RangeBudget::default());
+    let response = filter_response(&io, r#"{"fromBlock":"0x0","toBlock":"0x9","after":2}"#);
+    assert_eq!(
+        block_numbers(&response["result"]),
+        (2..10).collect::<Vec<_>>()
+    );
+}
+
 #[test]
 fn rpc_trace_filter() {
     let tester = io();
@@ -214,6 +328,31 @@ fn rpc_trace_multi_call() {
     );
 }
 
+#[test]
+fn rpc_trace_multi_call_with_overrides() {
+    let tester = io();
+
+    let request = r#"{"jsonrpc":"2.0","method":"trace_callMany","params":[[[{}, ["trace"]], [{}, ["trace"], {"0xd46e8dd67c5d32be8058bb8eb970870f07244567": {"balance": "0x10"}}]], "latest", {"timestamp": "0x64"}],"id":1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":[{"output":"0x010203","stateDiff":null,"trace":[],"vmTrace":null},{"output":"0x010203","stateDiff":null,"trace":[],"vmTrace":null}],"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+    let account: Address = "d46e8dd67c5d32be8058bb8eb970870f07244567".parse().unwrap();
+    assert_eq!(
+        tester.client.call_overrides.read()[&account].balance,
+        Some(0x10.into())
+    );
+    assert_eq!(
+        *tester.client.replay_options.read(),
+        ReplayOptions {
+            timestamp: Some(100),
+            ..Default::default()
+        }
+    );
+}
+
 #[test]
 fn rpc_trace_call_state_pruned() {
     let tester = io();
@@ -268,6 +407,27 @@ fn rpc_trace_replay_transaction() {
     );
 }
 
+#[test]
+fn rpc_trace_replay_transaction_with_overrides() {
+    let tester = io();
+
+    let request = r#"{"jsonrpc":"2.0","method":"trace_replayTransaction","params":["0x0000000000000000000000000000000000000000000000000000000000000005", ["trace"], {"timestamp": "0x64", "gasLimit": "0x5208"}],"id":1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"output":"0x010203","stateDiff":null,"trace":[],"vmTrace":null},"id":1}"#;
+
+    assert_eq!(
+        tester.io.handle_request_sync(request),
+        Some(response.to_owned())
+    );
+    assert_eq!(
+        *tester.client.replay_options.read(),
+        ReplayOptions {
+            timestamp: Some(100),
+            gas_limit: Some(21_000.into()),
+            ..Default::default()
+        }
+    );
+}
+
 #[test]
 fn rpc_trace_replay_transaction_state_pruned() {
     let tester = io();
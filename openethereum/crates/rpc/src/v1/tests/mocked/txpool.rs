@@ -0,0 +1,93 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use ethcore::client::TestBlockChainClient;
+use ethereum_types::{Address, U256};
+use types::transaction::{Action, Transaction, TypedTransaction};
+
+use jsonrpc_core::IoHandler;
+use serde_json;
+use v1::{tests::helpers::TestMinerService, TxPool, TxPoolClient};
+
+fn io() -> IoHandler {
+    let client = Arc::new(TestBlockChainClient::default());
+    let miner = Arc::new(TestMinerService::default());
+    let sender = Address::from_low_u64_be(2);
+    client.set_nonce(sender, U256::from(1));
+
+    for nonce in &[1u64, 2, 4] {
+        let signed = TypedTransaction::Legacy(Transaction {
+            nonce: (*nonce).into(),
+            gas_price: 0x9184e72a000u64.into(),
+            gas: 0x76c0.into(),
+            action: Action::Call(Address::from_low_u64_be(5)),
+            value: 0x9184e72au64.into(),
+            data: vec![],
+        })
+        .fake_sign(sender);
+        miner
+            .pending_transactions
+            .lock()
+            .insert(signed.hash(), signed);
+    }
+
+    let mut io = IoHandler::new();
+    io.extend_with(TxPoolClient::new(&client, &miner).to_delegate());
+    io
+}
+
+#[test]
+fn rpc_txpool_status() {
+    let io = io();
+
+    let request = r#"{"jsonrpc": "2.0", "method": "txpool_status", "params": [], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":{"pending":"0x2","queued":"0x1"},"id":1}"#;
+
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_txpool_inspect() {
+    let io = io();
+
+    let summary = "0x0000000000000000000000000000000000000005: 2441406250 wei + 30400 gas × 10000000000000 wei";
+    let request = r#"{"jsonrpc": "2.0", "method": "txpool_inspect", "params": [], "id": 1}"#;
+    let response = format!(
+        r#"{{"jsonrpc":"2.0","result":{{"pending":{{"0x0000000000000000000000000000000000000002":{{"1":"{0}","2":"{0}"}}}},"queued":{{"0x0000000000000000000000000000000000000002":{{"4":"{0}"}}}}}},"id":1}}"#,
+        summary
+    );
+
+    assert_eq!(io.handle_request_sync(request), Some(response));
+}
+
+#[test]
+fn rpc_txpool_content_groups_by_sender_and_nonce() {
+    let io = io();
+
+    let request = r#"{"jsonrpc": "2.0", "method": "txpool_content", "params": [], "id": 1}"#;
+    let response = io.handle_request_sync(request).unwrap();
+    let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+    let sender = &response["result"]["pending"]["0x0000000000000000000000000000000000000002"];
+
+    assert_eq!(sender["1"]["nonce"], "0x1");
+    assert_eq!(sender["2"]["nonce"], "0x2");
+    assert_eq!(
+        response["result"]["queued"]["0x0000000000000000000000000000000000000002"]["4"]["nonce"],
+        "0x4"
+    );
+}
@@ -16,10 +16,11 @@
 
 //! Debug RPC interface.
 
+use ethereum_types::H256;
 use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
 
-use v1::types::RichBlock;
+use v1::types::{GethTrace, GethTraceOptions, RichBlock};
 
 /// Debug RPC interface.
 #[rpc(server)]
@@ -27,4 +28,8 @@ pub trait Debug {
     /// Returns recently seen bad blocks.
     #[rpc(name = "debug_getBadBlocks")]
     fn bad_blocks(&self) -> Result<Vec<RichBlock>>;
+
+    /// Re-executes a transaction and renders it with one of the built-in Geth tracers.
+    #[rpc(name = "debug_traceTransaction")]
+    fn trace_transaction(&self, _: H256, _: Option<GethTraceOptions>) -> Result<GethTrace>;
 }
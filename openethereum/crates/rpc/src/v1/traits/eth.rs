@@ -21,7 +21,7 @@ use jsonrpc_derive::rpc;
 
 use v1::types::{
     BlockNumber, Bytes, CallRequest, EthAccount, EthFeeHistory, Filter, FilterChanges, Index, Log,
-    Receipt, RichBlock, SyncStatus, Transaction, Work,
+    Receipt, RichBlock, StateOverride, SyncStatus, Transaction, Work,
 };
 
 /// Eth rpc interface.
@@ -126,9 +126,15 @@ pub trait Eth {
     #[rpc(name = "eth_submitTransaction")]
     fn submit_transaction(&self, _: Bytes) -> Result<H256>;
 
-    /// Call contract, returning the output data.
+    /// Call contract, returning the output data. Accounts in the optional state override
+    /// map get the given balance, nonce, code or storage for the duration of the call.
     #[rpc(name = "eth_call")]
-    fn call(&self, _: CallRequest, _: Option<BlockNumber>) -> BoxFuture<Bytes>;
+    fn call(
+        &self,
+        _: CallRequest,
+        _: Option<BlockNumber>,
+        _: Option<StateOverride>,
+    ) -> BoxFuture<Bytes>;
 
     /// Estimate gas needed for execution of given contract.
     #[rpc(name = "eth_estimateGas")]
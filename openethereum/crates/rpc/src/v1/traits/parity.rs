@@ -24,8 +24,9 @@ use jsonrpc_derive::rpc;
 
 use ethcore::miner::TransactionFilter;
 use v1::types::{
-    BlockNumber, Bytes, CallRequest, ChainStatus, Histogram, LocalTransactionStatus, Peers,
-    Receipt, RecoveredAccount, RichHeader, RpcSettings, Transaction, TransactionStats,
+    BackupStatus, BlockNumber, Bytes, CallRequest, ChainStatus, Histogram, LocalTransactionStatus,
+    NodeHealth, NonceState, Peers, Receipt, RecoveredAccount, RichHeader, RpcSettings, Transaction,
+    TransactionStats,
 };
 
 /// Parity-specific rpc interface.
@@ -171,6 +172,11 @@ pub trait Parity {
     #[rpc(name = "parity_nextNonce")]
     fn next_nonce(&self, _: H160) -> BoxFuture<U256>;
 
+    /// Returns the chain nonce, the next ready nonce and the nonce gaps blocking
+    /// queued transactions of particular sender.
+    #[rpc(name = "parity_nonceState")]
+    fn nonce_state(&self, _: H160) -> Result<NonceState>;
+
     /// Get the mode. Returns one of: "active", "passive", "dark", "offline".
     #[rpc(name = "parity_mode")]
     fn mode(&self) -> Result<String>;
@@ -187,6 +193,10 @@ pub trait Parity {
     #[rpc(name = "parity_chainStatus")]
     fn chain_status(&self) -> Result<ChainStatus>;
 
+    /// Get the progress of the last database backup started with `parity_backup`.
+    #[rpc(name = "parity_backupStatus")]
+    fn backup_status(&self) -> Result<BackupStatus>;
+
     /// Get node kind info.
     #[rpc(name = "parity_nodeKind")]
     fn node_kind(&self) -> Result<::v1::types::NodeKind>;
@@ -221,6 +231,11 @@ pub trait Parity {
     #[rpc(name = "parity_nodeStatus")]
     fn status(&self) -> Result<()>;
 
+    /// Returns sync status, peer count, time since the last block import,
+    /// depth of recent reorganizations and clock drift estimate of the node.
+    #[rpc(name = "parity_nodeHealth")]
+    fn node_health(&self) -> Result<NodeHealth>;
+
     /// Extracts Address and public key from signature using the r, s and v params. Equivalent to Solidity erecover
     /// as well as checks the signature for chain replay protection
     #[rpc(name = "parity_verifySignature")]
@@ -16,11 +16,11 @@
 
 //! Parity-specific rpc interface for operations altering the settings.
 
-use ethereum_types::{H160, H256, U256};
+use ethereum_types::{H160, H256, U256, U64};
 use jsonrpc_core::{BoxFuture, Result};
 use jsonrpc_derive::rpc;
 
-use v1::types::{Bytes, Transaction};
+use v1::types::{AltMempoolCapacity, AltMempoolReservation, Bytes, CacheSizes, Transaction};
 
 /// Parity-specific rpc interface for operations altering the account-related settings.
 #[rpc(server)]
@@ -61,6 +61,15 @@ pub trait ParitySet {
     #[rpc(name = "parity_clearEngineSigner")]
     fn clear_engine_signer(&self) -> Result<bool>;
 
+    /// Schedules a switch of the engine signer to the given secret, taking effect
+    /// with the given handover block.
+    #[rpc(name = "parity_scheduleEngineSignerRotation")]
+    fn schedule_engine_signer_rotation(&self, _: H256, _: U64) -> Result<bool>;
+
+    /// Cancels a scheduled engine signer rotation.
+    #[rpc(name = "parity_cancelEngineSignerRotation")]
+    fn cancel_engine_signer_rotation(&self) -> Result<bool>;
+
     /// Sets the limits for transaction queue.
     #[rpc(name = "parity_setTransactionsLimit")]
     fn set_transactions_limit(&self, _: usize) -> Result<bool>;
@@ -105,6 +114,15 @@ pub trait ParitySet {
     #[rpc(name = "parity_setChain")]
     fn set_spec_name(&self, _: String) -> Result<bool>;
 
+    /// Change the state, blockchain and traces cache budgets (in MB) without restarting.
+    #[rpc(name = "parity_setCacheSizes")]
+    fn set_cache_sizes(&self, _: CacheSizes) -> Result<bool>;
+
+    /// Start writing a copy of the database into the given empty directory while the node keeps
+    /// running. Block import is paused until the backup is complete, see `parity_backupStatus`.
+    #[rpc(name = "parity_backup")]
+    fn backup(&self, _: String) -> Result<bool>;
+
     /// Hash a file content under given URL.
     #[rpc(name = "parity_hashContent")]
     fn hash_content(&self, _: String) -> BoxFuture<H256>;
@@ -117,4 +135,28 @@ pub trait ParitySet {
     /// Returns `true` when transaction was removed, `false` if it was not found.
     #[rpc(name = "parity_removeTransaction")]
     fn remove_transaction(&self, _: H256) -> Result<Option<Transaction>>;
+
+    /// Submits an ordered bundle of signed raw transactions which is included contiguously
+    /// at the top of the given block, or not at all. The block must be at most 25 blocks
+    /// ahead of the best block. Returns the hash of the bundle.
+    #[rpc(name = "parity_sendBundle")]
+    fn send_bundle(&self, _: Vec<Bytes>, _: U64) -> Result<H256>;
+
+    /// Returns locally submitted transactions from the on-disk journal, oldest first.
+    #[rpc(name = "parity_journaledTransactions")]
+    fn journaled_transactions(&self) -> Result<Vec<Transaction>>;
+
+    /// Imports journaled transactions missing from the transaction queue again,
+    /// so that they are broadcast to peers. Returns hashes of the imported transactions.
+    #[rpc(name = "parity_rebroadcastJournaledTransactions")]
+    fn rebroadcast_journaled_transactions(&self) -> Result<Vec<H256>>;
+
+    /// Returns the usage of the alt-mempool lane for EIP-4337 bundle transactions.
+    #[rpc(name = "parity_altMempoolCapacity")]
+    fn alt_mempool_capacity(&self) -> Result<AltMempoolCapacity>;
+
+    /// Reserves slots of the alt-mempool lane for bundle transactions sent by the given
+    /// bundler, for the given number of seconds. Replaces a previous reservation of the bundler.
+    #[rpc(name = "parity_reserveAltMempool")]
+    fn reserve_alt_mempool(&self, _: H160, _: U64, _: U64) -> Result<AltMempoolReservation>;
 }
@@ -20,8 +20,8 @@ use ethereum_types::H256;
 use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
 use v1::types::{
-    BlockNumber, Bytes, CallRequest, Index, LocalizedTrace, TraceFilter, TraceOptions,
-    TraceResults, TraceResultsWithTransactionHash,
+    BlockNumber, Bytes, CallRequest, Index, LocalizedTrace, ReplayOptions, TraceCall, TraceFilter,
+    TraceOptions, TraceResults, TraceResultsWithTransactionHash,
 };
 
 /// Traces specific rpc interface.
@@ -49,11 +49,14 @@ pub trait Traces {
         -> Result<TraceResults>;
 
     /// Executes all given calls and returns a number of possible traces for each of it.
+    /// Each call may carry state overrides applied on top of the changes made by the previous
+    /// calls, the optional block overrides replace fields of the block environment of all of them.
     #[rpc(name = "trace_callMany")]
     fn call_many(
         &self,
-        _: Vec<(CallRequest, TraceOptions)>,
+        _: Vec<TraceCall>,
         _: Option<BlockNumber>,
+        _: Option<ReplayOptions>,
     ) -> Result<Vec<TraceResults>>;
 
     /// Executes the given raw transaction and returns a number of possible traces for it.
@@ -66,14 +69,22 @@ pub trait Traces {
     ) -> Result<TraceResults>;
 
     /// Executes the transaction with the given hash and returns a number of possible traces for it.
+    /// The optional overrides replace fields of the block environment it is executed in.
     #[rpc(name = "trace_replayTransaction")]
-    fn replay_transaction(&self, _: H256, _: TraceOptions) -> Result<TraceResults>;
+    fn replay_transaction(
+        &self,
+        _: H256,
+        _: TraceOptions,
+        _: Option<ReplayOptions>,
+    ) -> Result<TraceResults>;
 
     /// Executes all the transactions at the given block and returns a number of possible traces for each transaction.
+    /// The optional overrides replace fields of the block environment they are executed in.
     #[rpc(name = "trace_replayBlockTransactions")]
     fn replay_block_transactions(
         &self,
         _: BlockNumber,
         _: TraceOptions,
+        _: Option<ReplayOptions>,
     ) -> Result<Vec<TraceResultsWithTransactionHash>>;
 }
@@ -0,0 +1,78 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Alt-mempool lane types.
+
+use std::time::Instant;
+
+use ethereum_types::{H160, U256, U64};
+use miner::pool::alt_mempool::{Capacity, Reservation};
+
+/// Usage of the lane for EIP-4337 bundle transactions.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AltMempoolCapacity {
+    /// Maximal number of bundle transactions.
+    pub max_count: U64,
+    /// Number of bundle transactions in the queue.
+    pub count: U64,
+    /// Number of slots reserved by bundlers.
+    pub reserved: U64,
+    /// Number of slots neither used nor reserved.
+    pub available: U64,
+    /// Maximal total gas of bundle transactions.
+    pub max_gas: Option<U256>,
+    /// Total gas of bundle transactions in the queue.
+    pub gas: U256,
+}
+
+impl From<Capacity> for AltMempoolCapacity {
+    fn from(capacity: Capacity) -> Self {
+        AltMempoolCapacity {
+            max_count: (capacity.max_count as u64).into(),
+            count: (capacity.count as u64).into(),
+            reserved: (capacity.reserved as u64).into(),
+            available: (capacity.available() as u64).into(),
+            max_gas: capacity.max_gas,
+            gas: capacity.gas,
+        }
+    }
+}
+
+/// Slots of the lane held for a bundler.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AltMempoolReservation {
+    /// Sender of the bundle transactions.
+    pub bundler: H160,
+    /// Number of reserved slots.
+    pub slots: U64,
+    /// Seconds until the remaining slots are released.
+    pub expires_in: U64,
+}
+
+impl From<Reservation> for AltMempoolReservation {
+    fn from(reservation: Reservation) -> Self {
+        let expires_in = reservation
+            .expires_at
+            .saturating_duration_since(Instant::now());
+        AltMempoolReservation {
+            bundler: reservation.bundler,
+            slots: (reservation.slots as u64).into(),
+            expires_in: expires_in.as_secs().into(),
+        }
+    }
+}
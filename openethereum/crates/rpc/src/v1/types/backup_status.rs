@@ -0,0 +1,63 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Database backup progress.
+
+use ethcore::client;
+
+/// Progress of the last database backup.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupStatus {
+    /// One of `idle`, `running`, `done` or `failed`.
+    pub state: String,
+    /// Directory the backup is written to.
+    pub path: Option<String>,
+    /// Number of database entries copied so far.
+    pub copied: u64,
+    /// Why the backup failed.
+    pub error: Option<String>,
+}
+
+impl From<client::BackupStatus> for BackupStatus {
+    fn from(s: client::BackupStatus) -> Self {
+        let path = |p: ::std::path::PathBuf| Some(p.display().to_string());
+        match s {
+            client::BackupStatus::Idle => BackupStatus {
+                state: "idle".into(),
+                ..Default::default()
+            },
+            client::BackupStatus::Running { path: p, copied } => BackupStatus {
+                state: "running".into(),
+                path: path(p),
+                copied,
+                error: None,
+            },
+            client::BackupStatus::Done { path: p, copied } => BackupStatus {
+                state: "done".into(),
+                path: path(p),
+                copied,
+                error: None,
+            },
+            client::BackupStatus::Failed { path: p, error } => BackupStatus {
+                state: "failed".into(),
+                path: path(p),
+                copied: 0,
+                error: Some(error),
+            },
+        }
+    }
+}
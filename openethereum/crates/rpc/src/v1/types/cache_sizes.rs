@@ -0,0 +1,44 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime cache budgets.
+
+use ethcore::client;
+
+const MB: usize = 1024 * 1024;
+
+/// Cache budgets in megabytes. Omitted sizes are left unchanged.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheSizes {
+    /// Size of the state cache.
+    pub state: Option<u32>,
+    /// Size of the blockchain cache.
+    pub blockchain: Option<u32>,
+    /// Size of the traces cache.
+    pub traces: Option<u32>,
+}
+
+impl From<CacheSizes> for client::CacheSizes {
+    fn from(s: CacheSizes) -> Self {
+        client::CacheSizes {
+            state: s.state.map(|mb| mb as usize * MB),
+            blockchain: s.blockchain.map(|mb| mb as usize * MB),
+            traces: s.traces.map(|mb| mb as usize * MB),
+        }
+    }
+}
@@ -52,6 +52,9 @@ pub struct CallRequest {
     /// Miner bribe
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_priority_fee_per_gas: Option<U256>,
+    /// Execution timeout in milliseconds, bounded by the node's `--jsonrpc-call-timeout`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<U64>,
 }
 
 impl Into<Request> for CallRequest {
@@ -107,6 +110,7 @@ mod tests {
                 nonce: Some(U256::from(4)),
                 access_list: None,
                 max_priority_fee_per_gas: None,
+                timeout: None,
             }
         );
     }
@@ -135,6 +139,7 @@ mod tests {
 			nonce: None,
 			access_list: None,
 			max_priority_fee_per_gas: None,
+			timeout: None,
 		});
     }
 
@@ -157,7 +162,17 @@ mod tests {
                 nonce: None,
                 access_list: None,
                 max_priority_fee_per_gas: None,
+                timeout: None,
             }
         );
     }
+
+    #[test]
+    fn call_request_deserialize_timeout() {
+        let s = r#"{"to":"0x0000000000000000000000000000000000000002","timeout":"0x1f4"}"#;
+        let deserialized: CallRequest = serde_json::from_str(s).unwrap();
+
+        assert_eq!(deserialized.to, Some(H160::from_low_u64_be(2)));
+        assert_eq!(deserialized.timeout, Some(500.into()));
+    }
 }
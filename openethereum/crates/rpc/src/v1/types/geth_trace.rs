@@ -0,0 +1,733 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Geth-compatible transaction traces.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use ethcore::{
+    client::Executed,
+    pod_state::PodState,
+    trace::{trace, FlatTrace, TraceError, VMOperation, VMTrace},
+};
+use ethereum_types::{BigEndianHash, H160, H256, U256};
+use evm::Instruction;
+use vm::CallType;
+
+use v1::types::Bytes;
+
+/// Most opcode level logs a single trace may render.
+pub const MAX_STRUCT_LOGS: usize = 100_000;
+
+/// Timeout of the replay if none is given, like in Geth.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Built-in tracer of `debug_traceTransaction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GethTracer {
+    /// Opcode level logs, used when no tracer is given.
+    StructLog,
+    /// Tree of calls, `callTracer`.
+    Call,
+    /// Accounts touched by the transaction, `prestateTracer`.
+    Prestate,
+}
+
+/// Options of `debug_traceTransaction`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GethTraceOptions {
+    /// Name of the built-in tracer.
+    pub tracer: Option<String>,
+    /// Leave out storage of struct logs.
+    #[serde(default)]
+    pub disable_storage: bool,
+    /// Leave out memory of struct logs.
+    #[serde(default)]
+    pub disable_memory: bool,
+    /// Leave out stack of struct logs.
+    #[serde(default)]
+    pub disable_stack: bool,
+    /// Timeout of the replay, like `500ms`, `5s` or `1m`.
+    pub timeout: Option<String>,
+}
+
+impl GethTraceOptions {
+    /// Returns the requested tracer.
+    pub fn tracer(&self) -> Result<GethTracer, String> {
+        match self.tracer.as_ref().map(String::as_str) {
+            None => Ok(GethTracer::StructLog),
+            Some("callTracer") => Ok(GethTracer::Call),
+            Some("prestateTracer") => Ok(GethTracer::Prestate),
+            Some(other) => Err(format!("Unsupported tracer: {}", other)),
+        }
+    }
+
+    /// Returns the requested timeout, 5 seconds by default.
+    pub fn timeout(&self) -> Result<Duration, String> {
+        let timeout = match self.timeout {
+            None => return Ok(DEFAULT_TIMEOUT),
+            Some(ref timeout) => timeout,
+        };
+        let invalid = || format!("Invalid timeout: {}", timeout);
+        let digits = timeout
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or_else(|| timeout.len());
+        let (value, unit) = timeout.split_at(digits);
+        let value: u64 = value.parse().map_err(|_| invalid())?;
+        let millis = match unit {
+            "ms" => 1,
+            "s" => 1_000,
+            "m" => 60_000,
+            _ => return Err(invalid()),
+        };
+        value
+            .checked_mul(millis)
+            .map(Duration::from_millis)
+            .ok_or_else(invalid)
+    }
+}
+
+/// Result of `debug_traceTransaction`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum GethTrace {
+    /// Opcode level logs.
+    StructLogs(StructLogTrace),
+    /// Tree of calls.
+    Call(CallFrame),
+    /// State of the touched accounts before the transaction.
+    Prestate(BTreeMap<H160, PrestateAccount>),
+}
+
+impl GethTrace {
+    /// Renders a transaction replayed with the analytics needed by `tracer`.
+    /// Returns `None` if the replay has no trace to render. The prestate is not
+    /// rendered from a replay, see `from_prestate`.
+    pub fn new(tracer: GethTracer, executed: Executed, options: &GethTraceOptions) -> Option<Self> {
+        match tracer {
+            GethTracer::StructLog => Some(GethTrace::StructLogs(StructLogTrace::new(
+                executed, options,
+            ))),
+            GethTracer::Call => CallFrame::from_traces(executed.trace, executed.vm_trace.as_ref())
+                .map(GethTrace::Call),
+            GethTracer::Prestate => None,
+        }
+    }
+
+    /// Renders the accounts accessed by a transaction, as they were before it.
+    pub fn from_prestate(state: PodState) -> Self {
+        GethTrace::Prestate(prestate(state))
+    }
+}
+
+/// Call made during the transaction, with the calls it made in turn.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    /// Type of the call, like `CALL` or `CREATE`.
+    #[serde(rename = "type")]
+    pub call_type: String,
+    /// Caller.
+    pub from: H160,
+    /// Callee, or address of the created contract.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<H160>,
+    /// Transferred value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+    /// Gas provided.
+    pub gas: U256,
+    /// Gas used.
+    pub gas_used: U256,
+    /// Input data or init code.
+    pub input: Bytes,
+    /// Output data or code of the created contract.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<Bytes>,
+    /// Reason of the failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Calls made by this one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallFrame>,
+}
+
+impl CallFrame {
+    /// Nests flat traces of a transaction into the tree of its calls. The VM trace
+    /// is needed to tell the gas used by reverted calls.
+    pub fn from_traces(traces: Vec<FlatTrace>, vm_trace: Option<&VMTrace>) -> Option<Self> {
+        let mut gas_left = vec![None; traces.len()];
+        if let (Some(vm_trace), false) = (vm_trace, traces.is_empty()) {
+            Self::gas_left(vm_trace, &traces, 0, &mut gas_left);
+        }
+
+        let mut frames: Vec<(usize, CallFrame)> = Vec::new();
+        for (t, gas_left) in traces.into_iter().zip(gas_left) {
+            let depth = t.trace_address.len();
+            if let Some(frame) = Self::from_trace(t, gas_left) {
+                Self::close(&mut frames, depth);
+                frames.push((depth, frame));
+            }
+        }
+        Self::close(&mut frames, 1);
+        frames.pop().map(|(_, frame)| frame)
+    }
+
+    // attach the frames at `depth` or deeper to their parents.
+    fn close(frames: &mut Vec<(usize, CallFrame)>, depth: usize) {
+        while frames.len() > 1 && frames.last().map_or(false, |&(d, _)| d >= depth) {
+            let (_, frame) = frames.pop().expect("frames are not empty; qed");
+            frames
+                .last_mut()
+                .expect("frames had a parent; qed")
+                .1
+                .calls
+                .push(frame);
+        }
+    }
+
+    // record the gas left when the call of `traces[index]` stopped, and recurse into
+    // the calls it made. Calls to builtin contracts have a VM frame but may have no
+    // flat trace, so a VM frame is only paired with the next flat trace if the latter
+    // calls the address found on the stack.
+    fn gas_left(
+        vm_trace: &VMTrace,
+        traces: &[FlatTrace],
+        index: usize,
+        gas_left: &mut [Option<U256>],
+    ) {
+        gas_left[index] = vm_trace
+            .operations
+            .last()
+            .and_then(|operation| operation.executed.as_ref())
+            .map(|executed| executed.gas_used);
+
+        let depth = traces[index].trace_address.len() + 1;
+        let mut next = index + 1;
+        let mut stack = Vec::new();
+        let mut subs = vm_trace.subs.iter().peekable();
+        for (step, operation) in vm_trace.operations.iter().enumerate() {
+            while let Some(sub) = subs.peek() {
+                if sub.parent_step != step {
+                    break;
+                }
+                let paired = traces.get(next).map_or(false, |t| {
+                    t.trace_address.len() == depth
+                        && Self::is_callee(t, operation.instruction, &stack)
+                });
+                if paired {
+                    Self::gas_left(sub, traces, next, gas_left);
+                    next += 1;
+                    while traces
+                        .get(next)
+                        .map_or(false, |t| t.trace_address.len() > depth)
+                    {
+                        next += 1;
+                    }
+                }
+                subs.next();
+            }
+
+            if !replay_stack(&mut stack, operation) {
+                break;
+            }
+        }
+    }
+
+    // whether `t` is the call made by `instruction` with `stack`.
+    fn is_callee(t: &FlatTrace, instruction: u8, stack: &[U256]) -> bool {
+        let creates =
+            instruction == Instruction::CREATE as u8 || instruction == Instruction::CREATE2 as u8;
+        match t.action {
+            trace::Action::Create(_) => creates,
+            trace::Action::Call(ref call) => {
+                // the address is below the gas, on top of the stack.
+                !creates
+                    && stack.len() >= 2
+                    && H160::from(H256::from_uint(&stack[stack.len() - 2])) == call.to
+            }
+            _ => false,
+        }
+    }
+
+    fn from_trace(t: FlatTrace, gas_left: Option<U256>) -> Option<Self> {
+        let (call_type, from, to, value, gas, input) = match t.action {
+            trace::Action::Call(call) => {
+                let (call_type, value) = match call.call_type {
+                    CallType::None | CallType::Call => ("CALL", Some(call.value)),
+                    CallType::CallCode => ("CALLCODE", Some(call.value)),
+                    CallType::DelegateCall => ("DELEGATECALL", None),
+                    CallType::StaticCall => ("STATICCALL", None),
+                };
+                (
+                    call_type,
+                    call.from,
+                    Some(call.to),
+                    value,
+                    call.gas,
+                    call.input,
+                )
+            }
+            trace::Action::Create(create) => (
+                "CREATE",
+                create.from,
+                None,
+                Some(create.value),
+                create.gas,
+                create.init,
+            ),
+            trace::Action::Suicide(suicide) => (
+                "SELFDESTRUCT",
+                suicide.address,
+                Some(suicide.refund_address),
+                Some(suicide.balance),
+                U256::zero(),
+                Vec::new(),
+            ),
+            trace::Action::Reward(_) => return None,
+        };
+
+        let mut frame = CallFrame {
+            call_type: call_type.into(),
+            from,
+            to,
+            value,
+            gas,
+            gas_used: U256::zero(),
+            input: input.into(),
+            output: None,
+            error: None,
+            calls: Vec::new(),
+        };
+        match t.result {
+            trace::Res::Call(result) => {
+                frame.gas_used = result.gas_used;
+                frame.output = Some(result.output.into());
+            }
+            trace::Res::Create(result) => {
+                frame.gas_used = result.gas_used;
+                frame.to = Some(result.address);
+                frame.output = Some(result.code.into());
+            }
+            trace::Res::FailedCall(error) | trace::Res::FailedCreate(error) => {
+                // a reverted call gives the gas it has left back to its caller,
+                // other failures consume all of it.
+                frame.gas_used = match (&error, gas_left) {
+                    (&TraceError::Reverted, Some(gas_left)) => frame.gas.saturating_sub(gas_left),
+                    _ => frame.gas,
+                };
+                frame.error = Some(error.to_string());
+            }
+            trace::Res::None => {}
+        }
+        Some(frame)
+    }
+}
+
+/// State of an account before the transaction.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct PrestateAccount {
+    /// Balance.
+    pub balance: U256,
+    /// Nonce.
+    pub nonce: u64,
+    /// Code, left out if empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    /// Storage slots read or written by the transaction.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<H256, H256>,
+}
+
+// renders the state before the transaction of the accounts it accessed.
+fn prestate(state: PodState) -> BTreeMap<H160, PrestateAccount> {
+    state
+        .drain()
+        .into_iter()
+        .map(|(address, account)| {
+            let prestate = PrestateAccount {
+                balance: account.balance,
+                // nonces are bounded by 2^64 - 1, see EIP-2681.
+                nonce: if account.nonce > U256::from(u64::max_value()) {
+                    u64::max_value()
+                } else {
+                    account.nonce.as_u64()
+                },
+                code: account.code.filter(|code| !code.is_empty()).map(Into::into),
+                storage: account.storage,
+            };
+            (address, prestate)
+        })
+        .collect()
+}
+
+// applies the effects of `operation` to the rebuilt `stack`. Returns `false` if the
+// operation was not executed.
+fn replay_stack(stack: &mut Vec<U256>, operation: &VMOperation) -> bool {
+    let executed = match operation.executed {
+        Some(ref executed) => executed,
+        None => return false,
+    };
+    let args = Instruction::from_u8(operation.instruction).map_or(0, |i| i.info().args);
+    let len = stack.len().saturating_sub(args);
+    stack.truncate(len);
+    stack.extend_from_slice(&executed.stack_push);
+    true
+}
+
+/// Opcode level logs of the transaction.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLogTrace {
+    /// Gas used by the transaction.
+    pub gas: U256,
+    /// Whether the transaction failed.
+    pub failed: bool,
+    /// Output of the transaction.
+    pub return_value: Bytes,
+    /// Executed operations.
+    pub struct_logs: Vec<StructLog>,
+}
+
+/// Executed operation, with the state of the VM before its execution.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    /// Program counter.
+    pub pc: usize,
+    /// Mnemonic of the operation.
+    pub op: String,
+    /// Remaining gas.
+    pub gas: U256,
+    /// Cost of the operation.
+    pub gas_cost: U256,
+    /// Depth of the call, starting from 1.
+    pub depth: usize,
+    /// Stack, from bottom to top.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Vec<U256>>,
+    /// Memory, in 32 byte words.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<Vec<H256>>,
+    /// Storage written by the call so far.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<H256, H256>>,
+}
+
+impl StructLogTrace {
+    /// Renders the VM trace of the transaction.
+    pub fn new(executed: Executed, options: &GethTraceOptions) -> Self {
+        let mut struct_logs = Vec::new();
+        if let Some(ref vm_trace) = executed.vm_trace {
+            Self::walk(vm_trace, 1, options, &mut struct_logs);
+        }
+
+        StructLogTrace {
+            gas: executed.gas_used,
+            failed: executed.exception.is_some(),
+            return_value: executed.output.into(),
+            struct_logs,
+        }
+    }
+
+    /// Counts the operations of a VM trace, including those of its subcalls.
+    pub fn count(vm_trace: &VMTrace) -> usize {
+        vm_trace.operations.len() + vm_trace.subs.iter().map(Self::count).sum::<usize>()
+    }
+
+    // the VM trace records only the effects of operations, so the stack,
+    // memory and storage of each frame are rebuilt while replaying them.
+    fn walk(
+        vm_trace: &VMTrace,
+        depth: usize,
+        options: &GethTraceOptions,
+        logs: &mut Vec<StructLog>,
+    ) {
+        let mut stack: Vec<U256> = Vec::new();
+        let mut memory: Vec<u8> = Vec::new();
+        let mut storage: BTreeMap<H256, H256> = BTreeMap::new();
+        let mut subs = vm_trace.subs.iter().peekable();
+
+        for (step, operation) in vm_trace.operations.iter().enumerate() {
+            let instruction = Instruction::from_u8(operation.instruction).map(|i| i.info());
+            let gas_left = operation
+                .executed
+                .as_ref()
+                .map_or_else(U256::zero, |executed| executed.gas_used);
+            logs.push(StructLog {
+                pc: operation.pc,
+                op: instruction.map_or_else(
+                    || format!("opcode {:#x} not defined", operation.instruction),
+                    |info| info.name.to_owned(),
+                ),
+                gas: gas_left.saturating_add(operation.gas_cost),
+                gas_cost: operation.gas_cost,
+                depth,
+                stack: if options.disable_stack {
+                    None
+                } else {
+                    Some(stack.clone())
+                },
+                memory: if options.disable_memory {
+                    None
+                } else {
+                    Some(memory.chunks(32).map(H256::from_slice).collect())
+                },
+                storage: if options.disable_storage {
+                    None
+                } else {
+                    Some(storage.clone())
+                },
+            });
+
+            while let Some(sub) = subs.peek() {
+                if sub.parent_step != step {
+                    break;
+                }
+                Self::walk(sub, depth + 1, options, logs);
+                subs.next();
+            }
+
+            if !replay_stack(&mut stack, operation) {
+                break;
+            }
+            let executed = operation
+                .executed
+                .as_ref()
+                .expect("replay_stack returned true for executed operations; qed");
+            if let Some(ref diff) = executed.mem_diff {
+                let end = diff.offset + diff.data.len();
+                if memory.len() < end {
+                    memory.resize((end + 31) / 32 * 32, 0);
+                }
+                memory[diff.offset..end].copy_from_slice(&diff.data);
+            }
+            if let Some(ref diff) = executed.store_diff {
+                storage.insert(
+                    H256::from_uint(&diff.location),
+                    H256::from_uint(&diff.value),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethcore::trace::{trace::*, TraceError};
+    use serde_json;
+
+    fn call_trace(trace_address: Vec<usize>, call_type: CallType, result: Res) -> FlatTrace {
+        FlatTrace {
+            action: Action::Call(Call {
+                from: H160::from_low_u64_be(trace_address.len() as u64),
+                to: H160::from_low_u64_be(trace_address.len() as u64 + 1),
+                value: 1.into(),
+                gas: 100.into(),
+                input: vec![],
+                call_type,
+            }),
+            result,
+            subtraces: 0,
+            trace_address,
+        }
+    }
+
+    #[test]
+    fn should_nest_call_frames() {
+        let ok = || {
+            Res::Call(CallResult {
+                gas_used: 10.into(),
+                output: vec![],
+            })
+        };
+        let frame = CallFrame::from_traces(
+            vec![
+                call_trace(vec![], CallType::Call, ok()),
+                call_trace(vec![0], CallType::StaticCall, ok()),
+                call_trace(vec![0, 0], CallType::DelegateCall, ok()),
+                call_trace(
+                    vec![1],
+                    CallType::Call,
+                    Res::FailedCall(TraceError::Reverted),
+                ),
+            ],
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(frame.calls.len(), 2);
+        assert_eq!(frame.calls[0].call_type, "STATICCALL");
+        assert_eq!(frame.calls[0].calls[0].call_type, "DELEGATECALL");
+        assert_eq!(frame.calls[1].error, Some("Reverted".into()));
+        let expected = r#"{"type":"STATICCALL","from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","gas":"0x64","gasUsed":"0xa","input":"0x","output":"0x","calls":[{"type":"DELEGATECALL","from":"0x0000000000000000000000000000000000000002","to":"0x0000000000000000000000000000000000000003","gas":"0x64","gasUsed":"0xa","input":"0x","output":"0x"}]}"#;
+        assert_eq!(serde_json::to_string(&frame.calls[0]).unwrap(), expected);
+    }
+
+    #[test]
+    fn should_tell_gas_used_by_reverted_calls() {
+        let op = |instruction, gas_left: u64, stack_push: Vec<U256>| VMOperation {
+            pc: 0,
+            instruction,
+            gas_cost: 3.into(),
+            executed: Some(VMExecutedOperation {
+                gas_used: gas_left.into(),
+                stack_push,
+                mem_diff: None,
+                store_diff: None,
+            }),
+        };
+        let sub = |parent_step, operations| VMTrace {
+            parent_step,
+            code: vec![],
+            operations,
+            subs: vec![],
+        };
+        // STATICCALL of the builtin at 0x9, which has no flat trace, then CALL of 0x2.
+        let vm_trace = VMTrace {
+            parent_step: 0,
+            code: vec![],
+            operations: vec![
+                op(0x60, 1000, vec![9.into()]),
+                op(0x60, 997, vec![50.into()]),
+                op(0xfa, 900, vec![1.into()]),
+                op(0x60, 897, vec![2.into()]),
+                op(0x60, 894, vec![100.into()]),
+                op(0xf1, 800, vec![0.into()]),
+            ],
+            subs: vec![sub(2, vec![]), sub(5, vec![op(0xfd, 60, vec![])])],
+        };
+        let frame = CallFrame::from_traces(
+            vec![
+                call_trace(
+                    vec![],
+                    CallType::Call,
+                    Res::Call(CallResult {
+                        gas_used: 10.into(),
+                        output: vec![],
+                    }),
+                ),
+                call_trace(
+                    vec![0],
+                    CallType::Call,
+                    Res::FailedCall(TraceError::Reverted),
+                ),
+            ],
+            Some(&vm_trace),
+        )
+        .unwrap();
+
+        assert_eq!(frame.calls[0].error, Some("Reverted".into()));
+        assert_eq!(frame.calls[0].gas_used, 40.into());
+    }
+
+    #[test]
+    fn should_render_prestate() {
+        use ethcore::pod_account::PodAccount;
+
+        let address = H160::from_low_u64_be(1);
+        let state = PodState::from(
+            vec![(
+                address,
+                PodAccount {
+                    balance: 10.into(),
+                    nonce: 2.into(),
+                    code: Some(vec![]),
+                    storage: vec![(H256::from_low_u64_be(1), H256::from_low_u64_be(3))]
+                        .into_iter()
+                        .collect(),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        );
+
+        let expected = r#"{"0x0000000000000000000000000000000000000001":{"balance":"0xa","nonce":2,"storage":{"0x0000000000000000000000000000000000000000000000000000000000000001":"0x0000000000000000000000000000000000000000000000000000000000000003"}}}"#;
+        assert_eq!(
+            serde_json::to_string(&GethTrace::from_prestate(state)).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn should_parse_timeout() {
+        let timeout = |t: &str| {
+            GethTraceOptions {
+                timeout: Some(t.into()),
+                ..Default::default()
+            }
+            .timeout()
+        };
+
+        assert_eq!(
+            GethTraceOptions::default().timeout(),
+            Ok(Duration::from_secs(5))
+        );
+        assert_eq!(timeout("300ms"), Ok(Duration::from_millis(300)));
+        assert_eq!(timeout("10s"), Ok(Duration::from_secs(10)));
+        assert_eq!(timeout("2m"), Ok(Duration::from_secs(120)));
+        assert!(timeout("10").is_err());
+        assert!(timeout("1h").is_err());
+        assert!(timeout("s").is_err());
+        assert!(timeout("-1s").is_err());
+    }
+
+    #[test]
+    fn should_rebuild_struct_log_stack() {
+        // PUSH1 0x01 PUSH1 0x02 ADD
+        let push = |pc, value: u64| VMOperation {
+            pc,
+            instruction: 0x60,
+            gas_cost: 3.into(),
+            executed: Some(VMExecutedOperation {
+                gas_used: 100.into(),
+                stack_push: vec![value.into()],
+                mem_diff: None,
+                store_diff: None,
+            }),
+        };
+        let vm_trace = VMTrace {
+            parent_step: 0,
+            code: vec![0x60, 0x01, 0x60, 0x02, 0x01],
+            operations: vec![
+                push(0, 1),
+                push(2, 2),
+                VMOperation {
+                    pc: 4,
+                    instruction: 0x01,
+                    gas_cost: 3.into(),
+                    executed: Some(VMExecutedOperation {
+                        gas_used: 94.into(),
+                        stack_push: vec![3.into()],
+                        mem_diff: None,
+                        store_diff: None,
+                    }),
+                },
+            ],
+            subs: vec![],
+        };
+        let mut logs = Vec::new();
+        StructLogTrace::walk(&vm_trace, 1, &Default::default(), &mut logs);
+
+        assert_eq!(logs.len(), 3);
+        assert_eq!(StructLogTrace::count(&vm_trace), 3);
+        assert_eq!(logs[2].op, "ADD");
+        assert_eq!(logs[2].gas, 97.into());
+        assert_eq!(logs[2].stack, Some(vec![1.into(), 2.into()]));
+    }
+}
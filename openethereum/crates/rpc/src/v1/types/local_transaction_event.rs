@@ -0,0 +1,65 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Local transaction lifecycle notification.
+
+use ethcore::client;
+use ethereum_types::H256;
+
+/// Change of a locally submitted transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum LocalTransactionEvent {
+    /// Transaction was included in a canonical block.
+    #[serde(rename_all = "camelCase")]
+    Mined {
+        /// Transaction hash.
+        hash: H256,
+        /// Hash of the block including the transaction.
+        block_hash: H256,
+    },
+    /// Transaction was removed from the pool without being mined.
+    Dropped {
+        /// Transaction hash.
+        hash: H256,
+        /// Reason of the removal.
+        reason: String,
+    },
+    /// Transaction went back to the pool because its block was retracted.
+    #[serde(rename_all = "camelCase")]
+    Requeued {
+        /// Transaction hash.
+        hash: H256,
+        /// Hash of the retracted block.
+        block_hash: H256,
+    },
+}
+
+impl From<client::LocalTransactionEvent> for LocalTransactionEvent {
+    fn from(e: client::LocalTransactionEvent) -> Self {
+        match e {
+            client::LocalTransactionEvent::Mined { hash, block_hash } => {
+                LocalTransactionEvent::Mined { hash, block_hash }
+            }
+            client::LocalTransactionEvent::Dropped { hash, reason } => {
+                LocalTransactionEvent::Dropped { hash, reason }
+            }
+            client::LocalTransactionEvent::Requeued { hash, block_hash } => {
+                LocalTransactionEvent::Requeued { hash, block_hash }
+            }
+        }
+    }
+}
@@ -20,8 +20,11 @@ pub use rpc_common::Bytes;
 
 pub use self::{
     account_info::{AccountInfo, EthAccount, ExtAccountInfo, RecoveredAccount, StorageProof},
+    alt_mempool::{AltMempoolCapacity, AltMempoolReservation},
+    backup_status::BackupStatus,
     block::{Block, BlockTransactions, Header, Rich, RichBlock, RichHeader},
     block_number::{block_number_to_id, BlockNumber},
+    cache_sizes::CacheSizes,
     call_request::CallRequest,
     confirmations::{
         ConfirmationPayload, ConfirmationRequest, ConfirmationResponse,
@@ -32,24 +35,33 @@ pub use self::{
     eip191::{EIP191Version, PresignedTransaction},
     fee_history::EthFeeHistory,
     filter::{Filter, FilterChanges},
+    geth_trace::{GethTrace, GethTraceOptions, GethTracer, StructLogTrace, MAX_STRUCT_LOGS},
     histogram::Histogram,
     index::Index,
+    local_transaction_event::LocalTransactionEvent,
     log::Log,
+    node_health::NodeHealth,
     node_kind::{Availability, Capability, NodeKind},
+    nonce_state::{NonceGap, NonceState},
     provenance::Origin,
     receipt::Receipt,
+    reorg::{Reorg, ReorgBlock},
+    replay_options::ReplayOptions,
     rpc_settings::RpcSettings,
     secretstore::EncryptedDocumentKey,
+    state_override::{into_state_override, AccountOverride, StateOverride},
     sync::{
         ChainStatus, EthProtocolInfo, PeerInfo, PeerNetworkInfo, PeerProtocolsInfo, Peers,
         SyncInfo, SyncStatus, TransactionStats,
     },
     trace::{LocalizedTrace, TraceResults, TraceResultsWithTransactionHash},
+    trace_call::TraceCall,
     trace_filter::TraceFilter,
     transaction::{LocalTransactionStatus, RichRawTransaction, Transaction},
     transaction_access_list::{AccessList, AccessListItem},
     transaction_condition::TransactionCondition,
     transaction_request::TransactionRequest,
+    txpool::{TxPoolContent, TxPoolStatus},
     work::Work,
 };
 
@@ -57,29 +69,41 @@ pub use self::{
 mod eth_types;
 
 mod account_info;
+mod alt_mempool;
+mod backup_status;
 mod block;
 mod block_number;
+mod cache_sizes;
 mod call_request;
 mod confirmations;
 mod derivation;
 mod eip191;
 mod fee_history;
 mod filter;
+mod geth_trace;
 mod histogram;
 mod index;
+mod local_transaction_event;
 mod log;
+mod node_health;
 mod node_kind;
+mod nonce_state;
 mod provenance;
 mod receipt;
+mod reorg;
+mod replay_options;
 mod rpc_settings;
 mod secretstore;
+mod state_override;
 mod sync;
 mod trace;
+mod trace_call;
 mod trace_filter;
 mod transaction;
 mod transaction_access_list;
 mod transaction_condition;
 mod transaction_request;
+mod txpool;
 mod work;
 
 pub mod pubsub;
@@ -0,0 +1,58 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node health report.
+
+/// Health and chain quality of the node, as returned by `parity_nodeHealth`.
+#[derive(Default, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeHealth {
+    /// Whether the node has peers (unless running a dev chain) and is not syncing.
+    pub healthy: bool,
+    /// Whether the node is syncing or restoring a snapshot.
+    pub syncing: bool,
+    /// Number of connected peers.
+    pub peers: usize,
+    /// Seconds since the last block import, `None` if nothing was imported since startup.
+    pub seconds_since_last_import: Option<u64>,
+    /// Number of blocks retracted by the deepest reorganization of the last hour.
+    pub reorg_depth: usize,
+    /// Estimated offset of the local clock in milliseconds, positive when it is behind.
+    pub clock_drift_millis: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeHealth;
+    use serde_json;
+
+    #[test]
+    fn node_health_serialization() {
+        let health = NodeHealth {
+            healthy: true,
+            syncing: false,
+            peers: 5,
+            seconds_since_last_import: Some(12),
+            reorg_depth: 2,
+            clock_drift_millis: None,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&health).unwrap(),
+            r#"{"healthy":true,"syncing":false,"peers":5,"secondsSinceLastImport":12,"reorgDepth":2,"clockDriftMillis":null}"#
+        );
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Nonce state of a sender's queued transactions.
+
+use ethereum_types::{H256, U256};
+use miner;
+
+/// Range of nonces missing from a sender's queued transactions.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NonceGap {
+    /// First missing nonce.
+    pub start: U256,
+    /// Nonce of the first transaction after the gap.
+    pub end: U256,
+    /// Transactions blocked by this gap, up to the next one.
+    pub blocked: Vec<H256>,
+}
+
+/// Nonce state of a sender.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NonceState {
+    /// Nonce in the latest state.
+    pub chain_nonce: U256,
+    /// Next nonce after the transactions which are ready in the pool.
+    pub ready_nonce: U256,
+    /// Gaps in the queued transactions.
+    pub gaps: Vec<NonceGap>,
+}
+
+impl From<miner::pool::SenderNonceState> for NonceState {
+    fn from(s: miner::pool::SenderNonceState) -> Self {
+        NonceState {
+            chain_nonce: s.chain_nonce,
+            ready_nonce: s.ready_nonce,
+            gaps: s
+                .gaps
+                .into_iter()
+                .map(|gap| NonceGap {
+                    start: gap.start,
+                    end: gap.end,
+                    blocked: gap.blocked,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn nonce_state_serialization() {
+        let state = NonceState {
+            chain_nonce: 1.into(),
+            ready_nonce: 2.into(),
+            gaps: vec![NonceGap {
+                start: 2.into(),
+                end: 4.into(),
+                blocked: vec![H256::from_low_u64_be(5)],
+            }],
+        };
+        assert_eq!(
+            serde_json::to_string(&state).unwrap(),
+            r#"{"chainNonce":"0x1","readyNonce":"0x2","gaps":[{"start":"0x2","end":"0x4","blocked":["0x0000000000000000000000000000000000000000000000000000000000000005"]}]}"#
+        );
+    }
+}
@@ -17,12 +17,13 @@
 //! Pub-Sub types.
 
 use ethereum_types::H256;
+use miner::pool::transaction_filter::TransactionFilter;
 use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
-use serde_json::{from_value, Value};
-use v1::types::{Filter, Log, RichHeader};
+use serde_json::{from_value, Map, Value};
+use v1::types::{Filter, LocalTransactionEvent, Log, Reorg, RichHeader, Transaction};
 
 /// Subscription result.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Result {
     /// New block header.
     Header(Box<RichHeader>),
@@ -30,6 +31,12 @@ pub enum Result {
     Log(Box<Log>),
     /// Transaction hash
     TransactionHash(H256),
+    /// Full transaction
+    Transaction(Box<Transaction>),
+    /// Chain reorganization
+    Reorg(Box<Reorg>),
+    /// Local transaction lifecycle change
+    LocalTransaction(Box<LocalTransactionEvent>),
 }
 
 impl Serialize for Result {
@@ -41,6 +48,9 @@ impl Serialize for Result {
             Result::Header(ref header) => header.serialize(serializer),
             Result::Log(ref log) => log.serialize(serializer),
             Result::TransactionHash(ref hash) => hash.serialize(serializer),
+            Result::Transaction(ref transaction) => transaction.serialize(serializer),
+            Result::Reorg(ref reorg) => reorg.serialize(serializer),
+            Result::LocalTransaction(ref event) => event.serialize(serializer),
         }
     }
 }
@@ -58,6 +68,10 @@ pub enum Kind {
     NewPendingTransactions,
     /// Node syncing status subscription.
     Syncing,
+    /// Chain reorganizations subscription.
+    Reorgs,
+    /// Local transactions lifecycle subscription.
+    LocalTransactions,
 }
 
 /// Subscription kind.
@@ -67,6 +81,38 @@ pub enum Params {
     None,
     /// Log parameters.
     Logs(Filter),
+    /// Pending transaction parameters.
+    Transactions(TransactionsParams),
+}
+
+/// Pending transactions subscription parameters.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct TransactionsParams {
+    /// Deliver full transactions instead of their hashes.
+    pub include_transactions: bool,
+    /// Only deliver transactions matching the filter.
+    pub filter: TransactionFilter,
+}
+
+impl<'a> Deserialize<'a> for TransactionsParams {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<TransactionsParams, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        // serde can't deny unknown fields next to a flattened struct, so the filter is
+        // deserialized from the remaining fields and rejects those it doesn't know.
+        let mut fields: Map<String, Value> = Deserialize::deserialize(deserializer)?;
+        let include_transactions = match fields.remove("includeTransactions") {
+            Some(include) => from_value(include).map_err(D::Error::custom)?,
+            None => false,
+        };
+        let filter = from_value(Value::Object(fields)).map_err(D::Error::custom)?;
+
+        Ok(TransactionsParams {
+            include_transactions,
+            filter,
+        })
+    }
 }
 
 impl Default for Params {
@@ -88,15 +134,19 @@ impl<'a> Deserialize<'a> for Params {
 
         from_value(v.clone())
             .map(Params::Logs)
+            .or_else(|e| from_value(v).map(Params::Transactions).map_err(|_| e))
             .map_err(|e| D::Error::custom(format!("Invalid Pub-Sub parameters: {}", e)))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Kind, Params, Result};
+    use super::{Kind, Params, Result, TransactionsParams};
+    use ethereum_types::H256;
     use serde_json;
-    use v1::types::{filter::VariadicValue, Filter, Header, RichHeader};
+    use v1::types::{
+        filter::VariadicValue, Filter, Header, LocalTransactionEvent, Reorg, ReorgBlock, RichHeader,
+    };
 
     #[test]
     fn should_deserialize_kind() {
@@ -116,6 +166,14 @@ mod tests {
             serde_json::from_str::<Kind>(r#""syncing""#).unwrap(),
             Kind::Syncing
         );
+        assert_eq!(
+            serde_json::from_str::<Kind>(r#""reorgs""#).unwrap(),
+            Kind::Reorgs
+        );
+        assert_eq!(
+            serde_json::from_str::<Kind>(r#""localTransactions""#).unwrap(),
+            Kind::LocalTransactions
+        );
     }
 
     #[test]
@@ -196,4 +254,78 @@ mod tests {
         let expected = r#"{"author":"0x0000000000000000000000000000000000000000","difficulty":"0x0","extraData":"0x","gasLimit":"0x0","gasUsed":"0x0","hash":"0x0000000000000000000000000000000000000000000000000000000000000000","logsBloom":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","miner":"0x0000000000000000000000000000000000000000","number":"0x0","parentHash":"0x0000000000000000000000000000000000000000000000000000000000000000","receiptsRoot":"0x0000000000000000000000000000000000000000000000000000000000000000","sealFields":["0x","0x"],"sha3Uncles":"0x0000000000000000000000000000000000000000000000000000000000000000","size":"0x45","stateRoot":"0x0000000000000000000000000000000000000000000000000000000000000000","timestamp":"0x0","transactionsRoot":"0x0000000000000000000000000000000000000000000000000000000000000000"}"#;
         assert_eq!(serde_json::to_string(&header).unwrap(), expected);
     }
+
+    #[test]
+    fn should_serialize_reorg() {
+        let reorg = Result::Reorg(Box::new(Reorg {
+            ancestor: ReorgBlock {
+                number: 1.into(),
+                hash: H256::from_low_u64_be(1),
+            },
+            retracted: vec![ReorgBlock {
+                number: 2.into(),
+                hash: H256::from_low_u64_be(2),
+            }],
+            enacted: vec![],
+            retracted_transactions: vec![H256::from_low_u64_be(3)],
+            enacted_transactions: vec![],
+        }));
+        let expected = r#"{"ancestor":{"number":"0x1","hash":"0x0000000000000000000000000000000000000000000000000000000000000001"},"retracted":[{"number":"0x2","hash":"0x0000000000000000000000000000000000000000000000000000000000000002"}],"enacted":[],"retractedTransactions":["0x0000000000000000000000000000000000000000000000000000000000000003"],"enactedTransactions":[]}"#;
+        assert_eq!(serde_json::to_string(&reorg).unwrap(), expected);
+    }
+
+    #[test]
+    fn should_serialize_local_transaction_event() {
+        let mined = Result::LocalTransaction(Box::new(LocalTransactionEvent::Mined {
+            hash: H256::from_low_u64_be(1),
+            block_hash: H256::from_low_u64_be(2),
+        }));
+        let expected = r#"{"status":"mined","hash":"0x0000000000000000000000000000000000000000000000000000000000000001","blockHash":"0x0000000000000000000000000000000000000000000000000000000000000002"}"#;
+        assert_eq!(serde_json::to_string(&mined).unwrap(), expected);
+
+        let dropped = Result::LocalTransaction(Box::new(LocalTransactionEvent::Dropped {
+            hash: H256::from_low_u64_be(1),
+            reason: "invalid".into(),
+        }));
+        let expected = r#"{"status":"dropped","hash":"0x0000000000000000000000000000000000000000000000000000000000000001","reason":"invalid"}"#;
+        assert_eq!(serde_json::to_string(&dropped).unwrap(), expected);
+    }
+
+    #[test]
+    fn should_deserialize_transaction_filter() {
+        let params = serde_json::from_str::<Params>(
+            r#"{"to":{"eq":"0x0000000000000000000000000000000000000020"},"selector":{"eq":"0xa9059cbb"}}"#,
+        )
+        .unwrap();
+        match params {
+            Params::Transactions(params) => assert!(!params.include_transactions),
+            other => panic!("Expected transaction filter, got {:?}", other),
+        }
+
+        let params = serde_json::from_str::<Params>(
+            r#"{"includeTransactions":true,"selector":{"eq":"0xa9059cbb"}}"#,
+        )
+        .unwrap();
+        match params {
+            Params::Transactions(params) => {
+                assert!(params.include_transactions);
+                assert_ne!(params.filter, Default::default());
+            }
+            other => panic!("Expected transaction filter, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_reject_unknown_transaction_filter_fields() {
+        assert!(serde_json::from_str::<Params>(r#"{"selecter":{"eq":"0xa9059cbb"}}"#).is_err());
+        assert!(serde_json::from_str::<TransactionsParams>(
+            r#"{"includeTransaction":true,"selector":{"eq":"0xa9059cbb"}}"#
+        )
+        .is_err());
+        assert!(
+            serde_json::from_str::<TransactionsParams>(r#"{"includeTransactions":true}"#)
+                .unwrap()
+                .include_transactions
+        );
+    }
 }
@@ -0,0 +1,67 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chain reorganization notification.
+
+use ethcore::client;
+use ethereum_types::{H256, U64};
+
+/// Block taking part in a reorganization.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReorgBlock {
+    /// Block number.
+    pub number: U64,
+    /// Block hash.
+    pub hash: H256,
+}
+
+impl From<client::ReorgBlock> for ReorgBlock {
+    fn from(b: client::ReorgBlock) -> Self {
+        ReorgBlock {
+            number: b.number.into(),
+            hash: b.hash,
+        }
+    }
+}
+
+/// Reorganization of the canonical chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reorg {
+    /// Common ancestor of the retracted and the enacted branch.
+    pub ancestor: ReorgBlock,
+    /// Blocks no longer canonical, from the old head down to the ancestor.
+    pub retracted: Vec<ReorgBlock>,
+    /// Blocks which became canonical, from the ancestor up to the new head.
+    pub enacted: Vec<ReorgBlock>,
+    /// Transactions included in the retracted blocks.
+    pub retracted_transactions: Vec<H256>,
+    /// Transactions included in the enacted blocks.
+    pub enacted_transactions: Vec<H256>,
+}
+
+impl From<client::Reorg> for Reorg {
+    fn from(r: client::Reorg) -> Self {
+        Reorg {
+            ancestor: r.ancestor.into(),
+            retracted: r.retracted.into_iter().map(Into::into).collect(),
+            enacted: r.enacted.into_iter().map(Into::into).collect(),
+            retracted_transactions: r.retracted_transactions,
+            enacted_transactions: r.enacted_transactions,
+        }
+    }
+}
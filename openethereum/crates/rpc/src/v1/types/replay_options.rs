@@ -0,0 +1,52 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Block environment overrides for replaying transactions.
+
+use ethcore::client;
+use ethereum_types::{H256, U256, U64};
+
+/// Block environment fields replaced when replaying transactions.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayOptions {
+    /// Block timestamp.
+    pub timestamp: Option<U64>,
+    /// Block number.
+    pub number: Option<U64>,
+    /// Block base fee.
+    pub base_fee: Option<U256>,
+    /// Block difficulty.
+    pub difficulty: Option<U256>,
+    /// Block randomness returned by `PREVRANDAO`.
+    pub random: Option<H256>,
+    /// Block gas limit.
+    pub gas_limit: Option<U256>,
+}
+
+impl From<ReplayOptions> for client::ReplayOptions {
+    fn from(o: ReplayOptions) -> Self {
+        client::ReplayOptions {
+            timestamp: o.timestamp.map(|t| t.as_u64()),
+            number: o.number.map(|n| n.as_u64()),
+            base_fee: o.base_fee,
+            difficulty: o.difficulty,
+            random: o.random,
+            gas_limit: o.gas_limit,
+        }
+    }
+}
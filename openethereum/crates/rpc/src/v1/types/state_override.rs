@@ -0,0 +1,116 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Account overrides for `eth_call`.
+
+use std::collections::BTreeMap;
+
+use ethereum_types::{H160, H256, U256};
+use jsonrpc_core::Error as RpcError;
+use types::state_override;
+use v1::{helpers::errors::invalid_params, types::Bytes};
+
+/// Fields of an account replaced for the duration of a call.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    /// Fake balance.
+    pub balance: Option<U256>,
+    /// Fake nonce.
+    pub nonce: Option<U256>,
+    /// Fake code.
+    pub code: Option<Bytes>,
+    /// Storage replacing the whole storage of the account.
+    pub state: Option<BTreeMap<H256, H256>>,
+    /// Storage slots patched on top of the existing storage.
+    pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+/// Account overrides keyed by address.
+pub type StateOverride = BTreeMap<H160, AccountOverride>;
+
+impl Into<state_override::AccountOverride> for AccountOverride {
+    fn into(self) -> state_override::AccountOverride {
+        state_override::AccountOverride {
+            balance: self.balance,
+            nonce: self.nonce,
+            code: self.code.map(Into::into),
+            state: self.state,
+            state_diff: self.state_diff,
+        }
+    }
+}
+
+/// Converts the overrides, rejecting accounts with both `state` and `stateDiff`.
+pub fn into_state_override(
+    overrides: StateOverride,
+) -> Result<state_override::StateOverride, RpcError> {
+    overrides
+        .into_iter()
+        .map(|(address, account)| {
+            if account.state.is_some() && account.state_diff.is_some() {
+                return Err(invalid_params(
+                    "stateOverride",
+                    format!("both state and stateDiff given for account {:?}", address),
+                ));
+            }
+            Ok((address, account.into()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountOverride, StateOverride};
+    use ethereum_types::{H160, H256, U256};
+    use serde_json;
+    use std::str::FromStr;
+
+    #[test]
+    fn state_override_deserialization() {
+        let s = r#"{
+			"0x0000000000000000000000000000000000000001": {
+				"balance": "0x10",
+				"nonce": "0x1",
+				"code": "0x6001",
+				"stateDiff": {
+					"0x0000000000000000000000000000000000000000000000000000000000000001": "0x0000000000000000000000000000000000000000000000000000000000000002"
+				}
+			}
+		}"#;
+        let deserialized: StateOverride = serde_json::from_str(s).unwrap();
+        let slot = H256::from_low_u64_be(1);
+
+        assert_eq!(deserialized.len(), 1);
+        assert_eq!(
+            deserialized[&H160::from_str("0000000000000000000000000000000000000001").unwrap()],
+            AccountOverride {
+                balance: Some(U256::from(0x10)),
+                nonce: Some(U256::from(1)),
+                code: Some(vec![0x60, 0x01].into()),
+                state: None,
+                state_diff: Some(vec![(slot, H256::from_low_u64_be(2))].into_iter().collect()),
+            }
+        );
+    }
+
+    #[test]
+    fn state_override_rejects_unknown_fields() {
+        let s = r#"{"0x0000000000000000000000000000000000000001": {"movePrecompileToAddress": "0x02"}}"#;
+        assert!(serde_json::from_str::<StateOverride>(s).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Calls traced by `trace_callMany`.
+
+use std::fmt;
+
+use serde::{
+    de::{Error, IgnoredAny, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use v1::types::{CallRequest, StateOverride, TraceOptions};
+
+/// Call given as `[request, traceOptions]` or `[request, traceOptions, stateOverride]`.
+#[derive(Debug, Default, PartialEq)]
+pub struct TraceCall {
+    /// Call to make.
+    pub request: CallRequest,
+    /// Traces to return.
+    pub options: TraceOptions,
+    /// Accounts replaced before the call, on top of the changes made by the previous calls.
+    pub overrides: Option<StateOverride>,
+}
+
+impl<'a> Deserialize<'a> for TraceCall {
+    fn deserialize<D>(deserializer: D) -> Result<TraceCall, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        deserializer.deserialize_seq(TraceCallVisitor)
+    }
+}
+
+struct TraceCallVisitor;
+
+impl<'a> Visitor<'a> for TraceCallVisitor {
+    type Value = TraceCall;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "2 or 3 elements: a call request, trace options and optional state overrides"
+        )
+    }
+
+    fn visit_seq<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
+    where
+        V: SeqAccess<'a>,
+    {
+        let request = visitor
+            .next_element()?
+            .ok_or_else(|| Error::invalid_length(0, &self))?;
+        let options = visitor
+            .next_element()?
+            .ok_or_else(|| Error::invalid_length(1, &self))?;
+        let overrides = visitor
+            .next_element::<Option<StateOverride>>()?
+            .and_then(|o| o);
+        let mut extra = 0;
+        while visitor.next_element::<IgnoredAny>()?.is_some() {
+            extra += 1;
+        }
+        if extra > 0 {
+            return Err(Error::invalid_length(3 + extra, &self));
+        }
+
+        Ok(TraceCall {
+            request,
+            options,
+            overrides,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TraceCall;
+    use serde_json;
+
+    #[test]
+    fn trace_call_deserialization() {
+        let plain: TraceCall = serde_json::from_str(r#"[{}, ["trace"]]"#).unwrap();
+        assert_eq!(plain.options, vec!["trace".to_owned()]);
+        assert_eq!(plain.overrides, None);
+
+        let overridden: TraceCall = serde_json::from_str(
+            r#"[{}, [], {"0x0000000000000000000000000000000000000005": {"balance": "0x1"}}]"#,
+        )
+        .unwrap();
+        assert_eq!(overridden.overrides.map(|o| o.len()), Some(1));
+
+        assert!(serde_json::from_str::<TraceCall>(r#"[{}]"#).is_err());
+        assert!(serde_json::from_str::<TraceCall>(r#"[{}, [], null, 1]"#).is_err());
+
+        let error = serde_json::from_str::<TraceCall>(r#"[{}, [], null, 1, 2]"#).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("invalid length 5, expected 2 or 3 elements"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Geth-compatible transaction pool types.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use ethereum_types::{H160, U64};
+use miner::pool::{SenderTransactions, VerifiedTransaction};
+
+/// Transactions of each sender, keyed by their decimal nonce.
+pub type TxPoolTransactions<T> = BTreeMap<H160, BTreeMap<String, T>>;
+
+/// Pending and queued transactions of the pool.
+#[derive(Debug, Serialize)]
+pub struct TxPoolContent<T> {
+    /// Transactions which can be included in the next block.
+    pub pending: TxPoolTransactions<T>,
+    /// Transactions waiting behind a nonce gap.
+    pub queued: TxPoolTransactions<T>,
+}
+
+impl<T> TxPoolContent<T> {
+    /// Converts the transactions of each sender with `convert`.
+    pub fn new<F>(content: BTreeMap<H160, SenderTransactions>, convert: F) -> Self
+    where
+        F: Fn(&VerifiedTransaction) -> T,
+    {
+        let by_nonce = |transactions: Vec<Arc<VerifiedTransaction>>| {
+            transactions
+                .iter()
+                .map(|tx| (tx.signed().tx().nonce.to_string(), convert(tx)))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let mut pending = BTreeMap::new();
+        let mut queued = BTreeMap::new();
+        for (sender, transactions) in content {
+            if !transactions.pending.is_empty() {
+                pending.insert(sender, by_nonce(transactions.pending));
+            }
+            if !transactions.queued.is_empty() {
+                queued.insert(sender, by_nonce(transactions.queued));
+            }
+        }
+
+        TxPoolContent { pending, queued }
+    }
+}
+
+/// Number of pending and queued transactions in the pool.
+#[derive(Debug, Serialize)]
+pub struct TxPoolStatus {
+    /// Number of transactions which can be included in the next block.
+    pub pending: U64,
+    /// Number of transactions waiting behind a nonce gap.
+    pub queued: U64,
+}
+
+impl<'a> From<&'a BTreeMap<H160, SenderTransactions>> for TxPoolStatus {
+    fn from(content: &'a BTreeMap<H160, SenderTransactions>) -> Self {
+        let (pending, queued) = content.values().fold((0, 0), |(pending, queued), txs| {
+            (pending + txs.pending.len(), queued + txs.queued.len())
+        });
+
+        TxPoolStatus {
+            pending: (pending as u64).into(),
+            queued: (queued as u64).into(),
+        }
+    }
+}
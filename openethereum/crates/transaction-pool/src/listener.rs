@@ -45,6 +45,9 @@ pub trait Listener<T> {
 
     /// The transaction has been culled from the pool.
     fn culled(&mut self, _tx: &Arc<T>) {}
+
+    /// The transaction has been removed from the pool for exceeding its maximal age.
+    fn expired(&mut self, _tx: &Arc<T>) {}
 }
 
 /// A no-op implementation of `Listener`.
@@ -86,4 +89,9 @@ where
         self.0.culled(tx);
         self.1.culled(tx);
     }
+
+    fn expired(&mut self, tx: &Arc<T>) {
+        self.0.expired(tx);
+        self.1.expired(tx);
+    }
 }
@@ -407,6 +407,18 @@ where
         }
     }
 
+    /// Removes single transaction from the pool for exceeding its maximal age.
+    /// The listener will get an `expired` notification.
+    pub fn expire(&mut self, hash: &T::Hash) -> Option<Arc<T>> {
+        if let Some(tx) = self.finalize_remove(hash) {
+            self.remove_from_set(tx.sender(), |set, scoring| set.remove(&tx, scoring));
+            self.listener.expired(&tx);
+            Some(tx)
+        } else {
+            None
+        }
+    }
+
     /// Removes all stalled transactions from given sender.
     fn remove_stalled<R: Ready<T>>(&mut self, sender: &T::Sender, ready: &mut R) -> usize {
         let removed_from_set = self.remove_from_set(sender, |transactions, scoring| {
@@ -857,6 +857,10 @@ mod listener {
         fn culled(&mut self, _tx: &SharedTransaction) {
             self.0.borrow_mut().push("culled".into());
         }
+
+        fn expired(&mut self, _tx: &SharedTransaction) {
+            self.0.borrow_mut().push("expired".into());
+        }
     }
 
     #[test]
@@ -926,6 +930,23 @@ mod listener {
         assert_eq!(txq.light_status().transaction_count, 0);
     }
 
+    #[test]
+    fn expire_transaction() {
+        let b = TransactionBuilder::default();
+        let listener = MyListener::default();
+        let results = listener.0.clone();
+        let mut txq = Pool::new(listener, DummyScoring::default(), Options::default());
+
+        // insert
+        let tx1 = import(&mut txq, b.tx().nonce(1).new()).unwrap();
+
+        // then
+        assert!(txq.expire(&tx1.hash()).is_some());
+        assert!(txq.expire(&tx1.hash()).is_none());
+        assert_eq!(*results.borrow(), &["added", "expired"]);
+        assert_eq!(txq.light_status().transaction_count, 0);
+    }
+
     #[test]
     fn clear_queue() {
         let b = TransactionBuilder::default();
@@ -26,6 +26,25 @@ use std::{
 extern crate log;
 pub extern crate prometheus;
 
+use prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+};
+
+/// Upper bounds, in seconds, of the buckets used by `latency_histogram`.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Creates a histogram of durations in seconds, meant to be kept across scrapes and added
+/// to each registry with `PrometheusRegistry::register_histogram`.
+pub fn latency_histogram(name: &str, help: &str) -> prometheus::Histogram {
+    prometheus::Histogram::with_opts(
+        prometheus::HistogramOpts::new(name, help).buckets(LATENCY_BUCKETS.to_vec()),
+    )
+    .expect("name and help must be non-empty; buckets are sorted; qed")
+}
+
 pub struct PrometheusRegistry {
     prefix: String,
     registry: prometheus::Registry,
@@ -67,6 +86,17 @@ impl PrometheusRegistry {
             .expect("prometheus identifiers must be are unique");
     }
 
+    /// Adds a long-lived prometheus histogram. Its name gets the registry prefix when collected.
+    pub fn register_histogram(&mut self, histogram: &prometheus::Histogram) {
+        let collector = Prefixed {
+            prefix: self.prefix.clone(),
+            inner: histogram.clone(),
+        };
+        self.registry
+            .register(Box::new(collector))
+            .expect("prometheus identifiers must be unique");
+    }
+
     /// Adds a new prometheus counter with the time spent in running the specified function
     pub fn register_optime<F: Fn() -> T, T>(&mut self, name: &str, f: &F) -> T {
         let start = Instant::now();
@@ -81,6 +111,27 @@ impl PrometheusRegistry {
     }
 }
 
+/// Collects an inner collector, prefixing the names of its metric families.
+struct Prefixed<C> {
+    prefix: String,
+    inner: C,
+}
+
+impl<C: Collector> Collector for Prefixed<C> {
+    fn desc(&self) -> Vec<&Desc> {
+        self.inner.desc()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let mut families = self.inner.collect();
+        for family in families.iter_mut() {
+            let name = format!("{}{}", self.prefix, family.get_name());
+            family.set_name(name);
+        }
+        families
+    }
+}
+
 /// Implements a prometheus metrics collector
 pub trait PrometheusMetrics {
     fn prometheus_metrics(&self, registry: &mut PrometheusRegistry);
@@ -287,4 +338,25 @@ mod tests {
     fn none_when_too_few_data() {
         assert!(Histogram::<usize>::create(&[], 1).is_none());
     }
+
+    #[test]
+    fn histogram_outlives_registry() {
+        let histogram = latency_histogram("import_test_seconds", "Test latency");
+        histogram.observe(0.002);
+
+        for observed in 1..3 {
+            let mut registry = PrometheusRegistry::new("oe_".into());
+            registry.register_histogram(&histogram);
+            let families = registry.registry().gather();
+            assert_eq!(families.len(), 1);
+            assert_eq!(families[0].get_name(), "oe_import_test_seconds");
+            assert_eq!(
+                families[0].get_metric()[0]
+                    .get_histogram()
+                    .get_sample_count(),
+                observed
+            );
+            histogram.observe(0.2);
+        }
+    }
 }
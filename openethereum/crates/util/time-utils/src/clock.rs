@@ -0,0 +1,192 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Wall-clock sources.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Source of wall-clock time used by timestamp dependent logic.
+pub trait Clock: Send + Sync {
+    /// Current wall-clock time.
+    fn now(&self) -> SystemTime;
+
+    /// Time elapsed since the UNIX epoch. Returns zero if the clock is set before the epoch.
+    fn unix_now(&self) -> Duration {
+        self.now().duration_since(UNIX_EPOCH).unwrap_or_default()
+    }
+}
+
+/// Clock backed by the operating system's wall-clock.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Manually driven clock, for tests.
+#[derive(Debug)]
+pub struct MockClock {
+    now: Mutex<SystemTime>,
+}
+
+impl MockClock {
+    /// Create a new clock set to the given time.
+    pub fn new(now: SystemTime) -> Self {
+        MockClock {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// Create a new clock set to the given number of seconds since the UNIX epoch.
+    pub fn with_unix_secs(secs: u64) -> Self {
+        Self::new(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    /// Set the current time.
+    pub fn set(&self, now: SystemTime) {
+        *self
+            .now
+            .lock()
+            .expect("mock clock lock is never poisoned; qed") = now;
+    }
+
+    /// Move the current time forward.
+    pub fn advance(&self, by: Duration) {
+        let mut now = self
+            .now
+            .lock()
+            .expect("mock clock lock is never poisoned; qed");
+        *now += by;
+    }
+
+    /// Move the current time backwards.
+    pub fn rewind(&self, by: Duration) {
+        let mut now = self
+            .now
+            .lock()
+            .expect("mock clock lock is never poisoned; qed");
+        *now -= by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        *self
+            .now
+            .lock()
+            .expect("mock clock lock is never poisoned; qed")
+    }
+}
+
+/// Shared handle to a replaceable clock.
+///
+/// All clones observe the same underlying clock, so a handle can be handed out
+/// before the actual clock is known (e.g. to engines created from the chain spec)
+/// and be pointed at the configured clock later on. Defaults to `SystemClock`.
+#[derive(Clone)]
+pub struct SharedClock {
+    inner: Arc<RwLock<Arc<dyn Clock>>>,
+}
+
+impl SharedClock {
+    /// Create a new handle using the given clock.
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        SharedClock {
+            inner: Arc::new(RwLock::new(clock)),
+        }
+    }
+
+    /// Replace the clock observed by this handle and all its clones.
+    pub fn set(&self, clock: Arc<dyn Clock>) {
+        *self
+            .inner
+            .write()
+            .expect("shared clock lock is never poisoned; qed") = clock;
+    }
+
+    /// The clock currently observed by this handle.
+    pub fn current(&self) -> Arc<dyn Clock> {
+        self.inner
+            .read()
+            .expect("shared clock lock is never poisoned; qed")
+            .clone()
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        SharedClock::new(Arc::new(SystemClock))
+    }
+}
+
+impl Clock for SharedClock {
+    fn now(&self) -> SystemTime {
+        self.inner
+            .read()
+            .expect("shared clock lock is never poisoned; qed")
+            .now()
+    }
+}
+
+impl fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedClock")
+            .field("now", &self.now())
+            .finish()
+    }
+}
+
+impl PartialEq for SharedClock {
+    /// Handles are equal when they observe the same underlying clock.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, MockClock, SharedClock};
+    use std::{sync::Arc, time::Duration};
+
+    #[test]
+    fn mock_clock_should_advance_and_rewind() {
+        let clock = MockClock::with_unix_secs(100);
+        assert_eq!(clock.unix_now(), Duration::from_secs(100));
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(clock.unix_now(), Duration::from_secs(105));
+
+        clock.rewind(Duration::from_secs(10));
+        assert_eq!(clock.unix_now(), Duration::from_secs(95));
+    }
+
+    #[test]
+    fn shared_clock_clones_should_observe_replacement() {
+        let shared = SharedClock::default();
+        let clone = shared.clone();
+        assert_eq!(shared, clone);
+
+        shared.set(Arc::new(MockClock::with_unix_secs(42)));
+        assert_eq!(clone.unix_now(), Duration::from_secs(42));
+    }
+}
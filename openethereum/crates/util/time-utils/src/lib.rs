@@ -14,8 +14,12 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
+mod clock;
+
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+pub use clock::{Clock, MockClock, SharedClock, SystemClock};
+
 /// Temporary trait for `checked operations` on SystemTime until these are available in the standard library
 pub trait CheckedSystemTime {
     /// Returns `Some<SystemTime>` when the result less or equal to `i32::max_value` to prevent `SystemTime` to panic because
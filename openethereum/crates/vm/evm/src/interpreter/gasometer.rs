@@ -257,7 +257,7 @@ impl<Gas: evm::CostType> Gasometer<Gas> {
                 let gas = overflowing!(data_gas.overflow_add(Gas::from(log_gas)));
                 Request::GasMem(gas, mem_needed(stack.peek(0), stack.peek(1))?)
             }
-            instructions::CALL | instructions::CALLCODE => {
+            instructions::CALL | instructions::CALLCODE | instructions::AUTHCALL => {
                 let mut gas = Gas::from(schedule.call_gas);
                 let mem = cmp::max(
                     mem_needed(stack.peek(5), stack.peek(6))?,
@@ -269,7 +269,7 @@ impl<Gas: evm::CostType> Gasometer<Gas> {
 
                 let is_value_transfer = !stack.peek(2).is_zero();
 
-                if instruction == instructions::CALL
+                if instruction != instructions::CALLCODE
                     && ((!schedule.no_empty && !ext.exists(&address)?)
                         || (schedule.no_empty
                             && is_value_transfer
@@ -299,6 +299,13 @@ impl<Gas: evm::CostType> Gasometer<Gas> {
 
                 Request::GasMemProvide(gas, mem, Some(requested))
             }
+            instructions::AUTH => {
+                let authority = u256_to_address(stack.peek(0));
+                let gas = overflowing!(Gas::from(schedule.auth_gas).overflow_add(
+                    accessed_addresses_gas(&authority, schedule.cold_account_access_cost)
+                ));
+                Request::GasMem(gas, mem_needed(stack.peek(1), stack.peek(2))?)
+            }
             instructions::CREATE => {
                 let start = stack.peek(1);
                 let len = stack.peek(2);
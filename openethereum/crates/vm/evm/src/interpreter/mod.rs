@@ -65,6 +65,9 @@ const TWO_POW_248: U256 = U256([0, 0, 0, 0x100000000000000]); //0x1 00000000 000
 /// https://eips.ethereum.org/EIPS/eip-2315.
 pub const MAX_SUB_STACK_SIZE: usize = 1023;
 
+/// Number of instructions executed between two checks of the execution deadline.
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
 fn to_biguint(x: U256) -> BigUint {
     let mut bytes = [0u8; 32];
     x.to_little_endian(&mut bytes);
@@ -201,7 +204,14 @@ pub struct Interpreter<Cost: CostType> {
 
 impl<Cost: 'static + CostType> vm::Exec for Interpreter<Cost> {
     fn exec(mut self: Box<Self>, ext: &mut dyn vm::Ext) -> vm::ExecTrapResult<GasLeft> {
+        let mut steps = 0usize;
         loop {
+            // Checked on entry as well, so frames resumed after a timed out sub-call stop too.
+            if steps % DEADLINE_CHECK_INTERVAL == 0 && ext.deadline_exceeded() {
+                return Ok(Err(vm::Error::ExecutionTimeout));
+            }
+            steps = steps.wrapping_add(1);
+
             let result = self.step(ext);
             match result {
                 InterpreterResult::Continue => {}
@@ -591,6 +601,7 @@ impl<Cost: CostType> Interpreter<Cost> {
             || (instruction == CHAINID && !schedule.have_chain_id)
             || (instruction == SELFBALANCE && !schedule.have_selfbalance)
             || (instruction == BASEFEE && !schedule.eip3198)
+            || ((instruction == AUTH || instruction == AUTHCALL) && !schedule.eip3074)
             || ((instruction == BEGINSUB || instruction == JUMPSUB || instruction == RETURNSUB)
                 && !schedule.have_subs)
         {
@@ -625,7 +636,9 @@ impl<Cost: CostType> Interpreter<Cost> {
                 Some((read(0), read(2)))
             }
             instructions::EXTCODECOPY => Some((read(1), read(3))),
-            instructions::CALL | instructions::CALLCODE => Some((read(5), read(6))),
+            instructions::CALL | instructions::CALLCODE | instructions::AUTHCALL => {
+                Some((read(5), read(6)))
+            }
             instructions::DELEGATECALL | instructions::STATICCALL => Some((read(4), read(5))),
             _ => None,
         };
@@ -755,10 +768,36 @@ impl<Cost: CostType> Interpreter<Cost> {
                     Err(trap) => Ok(InstructionResult::Trap(trap)),
                 };
             }
+            instructions::AUTH => {
+                let authority = u256_to_address(&self.stack.pop_back());
+                let offset = self.stack.pop_back();
+                let size = self.stack.pop_back();
+
+                ext.al_insert_address(authority);
+
+                // `y_parity || r || s || commit`, missing bytes are zero
+                let mut input = [0u8; 97];
+                {
+                    let data = self.mem.read_slice(offset, size);
+                    let len = cmp::min(data.len(), input.len());
+                    input[..len].copy_from_slice(&data[..len]);
+                }
+                let mut signature = [0u8; 65];
+                signature.copy_from_slice(&input[..65]);
+                let commit = H256::from_slice(&input[65..]);
+
+                let authorized = ext.auth(&authority, &signature, &commit)?;
+                self.stack.push(if authorized {
+                    U256::one()
+                } else {
+                    U256::zero()
+                });
+            }
             instructions::CALL
             | instructions::CALLCODE
             | instructions::DELEGATECALL
-            | instructions::STATICCALL => {
+            | instructions::STATICCALL
+            | instructions::AUTHCALL => {
                 assert!(
                     ext.schedule().call_value_transfer_gas > ext.schedule().call_stipend,
                     "overflow possible"
@@ -783,15 +822,28 @@ impl<Cost: CostType> Interpreter<Cost> {
                 let out_size = self.stack.pop_back();
 
                 // Add stipend (only CALL|CALLCODE when value > 0)
-                let call_gas = call_gas
-                    .overflow_add(value.map_or_else(
-                        || Cost::from(0),
-                        |val| match val.is_zero() {
-                            false => Cost::from(ext.schedule().call_stipend),
-                            true => Cost::from(0),
-                        },
-                    ))
-                    .0;
+                let call_gas = match instruction {
+                    instructions::AUTHCALL => call_gas,
+                    _ => {
+                        call_gas
+                            .overflow_add(value.map_or_else(
+                                || Cost::from(0),
+                                |val| match val.is_zero() {
+                                    false => Cost::from(ext.schedule().call_stipend),
+                                    true => Cost::from(0),
+                                },
+                            ))
+                            .0
+                    }
+                };
+
+                // AUTHCALL is sent from the authorized account
+                let authorized = match instruction {
+                    instructions::AUTHCALL => {
+                        Some(ext.authorized().ok_or(vm::Error::Unauthorized)?)
+                    }
+                    _ => None,
+                };
 
                 ext.al_insert_address(code_address);
 
@@ -832,6 +884,17 @@ impl<Cost: CostType> Interpreter<Cost> {
                         true,
                         CallType::StaticCall,
                     ),
+                    instructions::AUTHCALL => {
+                        if ext.is_static() && value.map_or(false, |v| !v.is_zero()) {
+                            return Err(vm::Error::MutableCallInStaticContext);
+                        }
+                        let authorized = authorized
+                            .as_ref()
+                            .expect("authorized set for AUTHCALL above; qed");
+                        let has_balance = ext.balance(authorized)?
+                            >= value.expect("value set for all but delegate call; qed");
+                        (authorized, &code_address, has_balance, CallType::Call)
+                    }
                     _ => panic!(format!(
                         "Unexpected instruction {:?} in CALL branch.",
                         instruction
@@ -1156,7 +1219,13 @@ impl<Cost: CostType> Interpreter<Cost> {
                 self.stack.push(U256::from(ext.env_info().number));
             }
             instructions::DIFFICULTY => {
-                self.stack.push(ext.env_info().difficulty.clone());
+                let env_info = ext.env_info();
+                match env_info.random {
+                    Some(ref random) if ext.schedule().eip4399 => {
+                        self.stack.push(U256::from_big_endian(random.as_bytes()))
+                    }
+                    _ => self.stack.push(env_info.difficulty.clone()),
+                }
             }
             instructions::GASLIMIT => {
                 self.stack.push(ext.env_info().gas_limit.clone());
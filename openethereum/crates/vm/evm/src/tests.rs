@@ -212,6 +212,40 @@ fn test_chain_id(factory: super::Factory) {
     );
 }
 
+evm_test! {test_prevrandao: test_prevrandao_int}
+fn test_prevrandao(factory: super::Factory) {
+    // 44       DIFFICULTY
+    // 60 00    PUSH 0
+    // 55       SSTORE
+    let code = hex!("44 60 00 55").to_vec();
+
+    let run = |eip4399: bool| {
+        let mut params = ActionParams::default();
+        params.gas = U256::from(100_000);
+        params.code = Some(Arc::new(code.clone()));
+        let mut ext = FakeExt::new_istanbul();
+        ext.info.difficulty = U256::from(0x0d);
+        ext.info.random = Some(H256::from_low_u64_be(0x42));
+        ext.schedule.eip4399 = eip4399;
+        {
+            let vm = factory.create(params, ext.schedule(), ext.depth());
+            test_finalize(vm.exec(&mut ext).ok().unwrap()).unwrap();
+        }
+        ext
+    };
+
+    assert_store(
+        &run(false),
+        0,
+        "000000000000000000000000000000000000000000000000000000000000000d",
+    );
+    assert_store(
+        &run(true),
+        0,
+        "0000000000000000000000000000000000000000000000000000000000000042",
+    );
+}
+
 evm_test! {test_extcodecopy: test_extcodecopy_int}
 fn test_extcodecopy(factory: super::Factory) {
     // 33 - sender
@@ -1284,6 +1318,98 @@ fn test_calls(factory: super::Factory) {
     assert_eq!(ext.calls.len(), 2);
 }
 
+evm_test! {test_auth_and_authcall: test_auth_and_authcall_int}
+fn test_auth_and_authcall(factory: super::Factory) {
+    // 60 00 60 00 73 <authority> f6 - AUTH(authority, 0, 0)
+    // 60 00 55 - store the result at 0
+    // 60 00 60 00 60 00 60 00 60 00 61 0998 61 ffff f7 - AUTHCALL 0x998 without value
+    let code = "600060007300000000000000000000000000000000000000aaf66000556000600060006000600061099861fffff7"
+        .from_hex()
+        .unwrap();
+
+    let address = Address::from_low_u64_be(0x155);
+    let authority = Address::from_low_u64_be(0xaa);
+    let code_address = Address::from_low_u64_be(0x998);
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    params.address = address.clone();
+    let mut ext = FakeExt::new_london(Address::zero(), address, &[]);
+    ext.schedule.eip3074 = true;
+    ext.auth_signer = Some(authority);
+
+    {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap()).unwrap();
+    }
+
+    assert_store(
+        &ext,
+        0,
+        "0000000000000000000000000000000000000000000000000000000000000001",
+    );
+    assert_eq!(ext.authorized, Some(authority));
+    assert_set_contains(
+        &ext.calls,
+        &FakeCall {
+            call_type: FakeCallType::Call,
+            create_scheme: None,
+            gas: U256::from(0xffff),
+            sender_address: Some(authority),
+            receive_address: Some(code_address.clone()),
+            value: Some(U256::zero()),
+            data: vec![],
+            code_address: Some(code_address),
+        },
+    );
+}
+
+evm_test! {test_authcall_without_auth: test_authcall_without_auth_int}
+fn test_authcall_without_auth(factory: super::Factory) {
+    let code = "6000600060006000600061099861fffff7".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new_london(Address::zero(), Address::zero(), &[]);
+    ext.schedule.eip3074 = true;
+
+    let current = {
+        let vm = factory.create(params.clone(), ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap())
+    };
+    assert_eq!(current, Result::Err(vm::Error::Unauthorized));
+
+    // not available before the transition
+    ext.schedule.eip3074 = false;
+    let current = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap())
+    };
+    assert_eq!(
+        current,
+        Result::Err(vm::Error::BadInstruction { instruction: 0xf7 })
+    );
+}
+
+evm_test! {test_execution_timeout: test_execution_timeout_int}
+fn test_execution_timeout(factory: super::Factory) {
+    // infinite loop: JUMPDEST PUSH1 0 JUMP
+    let code = "5b600056".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new();
+    ext.timed_out = true;
+
+    let current = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap())
+    };
+    assert_eq!(current, Result::Err(vm::Error::ExecutionTimeout));
+}
+
 evm_test! {test_create_in_staticcall: test_create_in_staticcall_int}
 fn test_create_in_staticcall(factory: super::Factory) {
     let code = "600060006064f000".from_hex().unwrap();
@@ -46,6 +46,8 @@ pub struct EnvInfo {
     pub gas_used: U256,
     /// Block base fee.
     pub base_fee: Option<U256>,
+    /// Block randomness (EIP-4399), taken from the mix hash.
+    pub random: Option<H256>,
 }
 
 impl Default for EnvInfo {
@@ -59,6 +61,7 @@ impl Default for EnvInfo {
             last_hashes: Arc::new(vec![]),
             gas_used: 0.into(),
             base_fee: None,
+            random: None,
         }
     }
 }
@@ -79,6 +82,7 @@ impl From<ethjson::vm::Env> for EnvInfo {
             ),
             gas_used: U256::default(),
             base_fee: e.base_fee.map(|i| i.into()),
+            random: e.random.map(Into::into),
         }
     }
 }
@@ -86,7 +90,7 @@ impl From<ethjson::vm::Env> for EnvInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ethereum_types::{Address, U256};
+    use ethereum_types::{Address, H256, U256};
     use ethjson;
     use std::str::FromStr;
 
@@ -101,6 +105,7 @@ mod tests {
             gas_limit: ethjson::uint::Uint(U256::from(40_000)),
             timestamp: ethjson::uint::Uint(U256::from(1_100)),
             base_fee: None,
+            random: Some(ethjson::hash::H256(H256::from_low_u64_be(0x42))),
         });
 
         assert_eq!(env_info.number, 1112339);
@@ -111,6 +116,7 @@ mod tests {
         assert_eq!(env_info.gas_limit, 40000.into());
         assert_eq!(env_info.difficulty, 50000.into());
         assert_eq!(env_info.gas_used, 0.into());
+        assert_eq!(env_info.random, Some(H256::from_low_u64_be(0x42)));
     }
 
     #[test]
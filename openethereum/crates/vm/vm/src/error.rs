@@ -88,6 +88,10 @@ pub enum Error {
     },
     /// When the code walks into a subroutine, that is not allowed
     InvalidSubEntry,
+    /// `AUTHCALL` without a successful `AUTH` in the same call frame
+    Unauthorized,
+    /// Execution has run past its wall-clock deadline
+    ExecutionTimeout,
     /// Built-in contract failed on given input
     BuiltIn(&'static str),
     /// When execution tries to modify the state in static context
@@ -144,6 +148,8 @@ impl fmt::Display for Error {
                 write!(f, "Out of subroutine stack {}/{}", wanted, limit)
             }
             InvalidSubEntry => write!(f, "Invalid subroutine entry"),
+            Unauthorized => write!(f, "AUTHCALL without authorization"),
+            ExecutionTimeout => write!(f, "Execution timed out"),
             BuiltIn(name) => write!(f, "Built-in failed: {}", name),
             Internal(ref msg) => write!(f, "Internal error: {}", msg),
             MutableCallInStaticContext => write!(f, "Mutable call in static context"),
@@ -162,6 +162,11 @@ pub trait Ext {
     /// Decrements sstore refunds counter.
     fn sub_sstore_refund(&mut self, value: usize);
 
+    /// Check whether the wall-clock deadline of the current execution has passed.
+    fn deadline_exceeded(&self) -> bool {
+        false
+    }
+
     /// Decide if any more operations should be traced. Passthrough for the VM trace.
     fn trace_next_instruction(&mut self, _pc: usize, _instruction: u8, _current_gas: U256) -> bool {
         false
@@ -202,4 +207,13 @@ pub trait Ext {
 
     /// Inserts an address into the list
     fn al_insert_address(&mut self, address: Address);
+
+    /// EIP-3074 `AUTH`: check that `signature` (`y_parity || r || s`) is a signature by
+    /// `authority` of `commit` for the executing contract and, if so, authorize `AUTHCALL`s on
+    /// behalf of `authority` for the rest of the current call frame. Any previous authorization
+    /// is cleared first.
+    fn auth(&mut self, authority: &Address, signature: &[u8; 65], commit: &H256) -> Result<bool>;
+
+    /// Address authorized by the last successful `AUTH` of the current call frame.
+    fn authorized(&self) -> Option<Address>;
 }
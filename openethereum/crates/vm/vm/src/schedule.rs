@@ -82,6 +82,8 @@ pub struct Schedule {
     pub create_gas: usize,
     /// Gas price for `*CALL*` opcodes
     pub call_gas: usize,
+    /// Gas price for `AUTH` opcode
+    pub auth_gas: usize,
     /// EIP-2929 COLD_SLOAD_COST
     pub cold_sload_cost: usize,
     /// EIP-2929 COLD_ACCOUNT_ACCESS_COST
@@ -169,10 +171,14 @@ pub struct Schedule {
     pub eip1559_gas_limit_bump: usize,
     /// Enable BASEFEE opcode
     pub eip3198: bool,
+    /// DIFFICULTY opcode returns the block randomness (PREVRANDAO) instead
+    pub eip4399: bool,
     /// Gas used in transaction divided by this number is the maximum refundable amount.
     pub max_refund_quotient: usize,
     // Enable EIP-3541 rule
     pub eip3541: bool,
+    /// Enable EIP-3074 AUTH and AUTHCALL opcodes
+    pub eip3074: bool,
 }
 
 /// Wasm cost table
@@ -286,6 +292,7 @@ impl Schedule {
             log_topic_gas: 375,
             create_gas: 32000,
             call_gas: 700,
+            auth_gas: 3100,
             cold_account_access_cost: 0,
             cold_sload_cost: 0,
             warm_storage_read_cost: 0,
@@ -324,8 +331,10 @@ impl Schedule {
             eip1559_elasticity_multiplier: 1,
             eip1559_gas_limit_bump: 1,
             eip3198: false,
+            eip4399: false,
             max_refund_quotient: MAX_REFUND_QUOTIENT,
             eip3541: false,
+            eip3074: false,
         }
     }
 
@@ -429,6 +438,7 @@ impl Schedule {
             log_topic_gas: 375,
             create_gas: 32000,
             call_gas: 40,
+            auth_gas: 3100,
             cold_account_access_cost: 0,
             cold_sload_cost: 0,
             warm_storage_read_cost: 0,
@@ -467,8 +477,10 @@ impl Schedule {
             eip1559_elasticity_multiplier: 1,
             eip1559_gas_limit_bump: 1,
             eip3198: false,
+            eip4399: false,
             max_refund_quotient: MAX_REFUND_QUOTIENT,
             eip3541: false,
+            eip3074: false,
         }
     }
 
@@ -78,6 +78,11 @@ pub struct FakeExt {
     pub tracing: bool,
     pub is_static: bool,
     pub access_list: AccessList,
+    /// Signer `auth` recovers from any signature.
+    pub auth_signer: Option<Address>,
+    pub authorized: Option<Address>,
+    /// Reported by `deadline_exceeded`.
+    pub timed_out: bool,
 
     chain_id: u64,
 }
@@ -315,6 +320,10 @@ impl Ext for FakeExt {
         self.sstore_clears -= value as i128;
     }
 
+    fn deadline_exceeded(&self) -> bool {
+        self.timed_out
+    }
+
     fn trace_next_instruction(&mut self, _pc: usize, _instruction: u8, _gas: U256) -> bool {
         self.tracing
     }
@@ -338,4 +347,13 @@ impl Ext for FakeExt {
     fn al_insert_address(&mut self, address: Address) {
         self.access_list.insert_address(address)
     }
+
+    fn auth(&mut self, authority: &Address, _signature: &[u8; 65], _commit: &H256) -> Result<bool> {
+        self.authorized = self.auth_signer.filter(|signer| signer == authority);
+        Ok(self.authorized.is_some())
+    }
+
+    fn authorized(&self) -> Option<Address> {
+        self.authorized
+    }
 }